@@ -0,0 +1,18 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Generates the gRPC service from `proto/chasm.proto` when the `grpc`
+//! feature is enabled. protoc comes from `protoc-bin-vendored`, so no system
+//! install is needed.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/chasm.proto");
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+        tonic_build::configure()
+            .build_client(false)
+            .compile_protos(&["proto/chasm.proto"], &["proto"])?;
+    }
+    Ok(())
+}
@@ -0,0 +1,208 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Action items found in harvested sessions
+//!
+//! `csm intelligence actions` reads sessions with [`ActionExtractor`] and
+//! keeps the TODOs, decisions and commitments it finds in `action_items`,
+//! where `csm actions` lists, closes and exports them. Running the pass again
+//! adds what is new and leaves the status of known items alone.
+//!
+//! [`ActionExtractor`]: crate::intelligence::ActionExtractor
+
+use crate::error::Result;
+use crate::intelligence::{ActionItem, ActionKind};
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+/// Status of an item nobody has dealt with yet
+pub const OPEN: &str = "open";
+
+/// Status of an item marked done
+pub const DONE: &str = "done";
+
+/// Create the action item table in the harvest database if it does not exist
+pub fn ensure_action_items_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS action_items (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            text TEXT NOT NULL,
+            role TEXT NOT NULL,
+            message_index INTEGER NOT NULL,
+            status TEXT NOT NULL DEFAULT 'open',
+            created_at INTEGER NOT NULL,
+            exported_to TEXT,
+            UNIQUE (session_id, kind, text),
+            FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_action_items_status ON action_items(status, kind);
+        "#,
+    )?;
+    Ok(())
+}
+
+/// An action item as stored, with the title of its session
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredActionItem {
+    pub id: i64,
+    pub session_id: String,
+    pub session_title: String,
+    pub kind: String,
+    pub text: String,
+    pub role: String,
+    pub status: String,
+    pub created_at: i64,
+    /// Where the item was exported, as `todoist:<task id>`, `jira:<issue key>`
+    /// or `linear:<issue key>`
+    pub exported_to: Option<String>,
+}
+
+/// Which stored items to list
+#[derive(Debug, Default)]
+pub struct ActionFilter<'a> {
+    pub kinds: Vec<ActionKind>,
+    /// A session ID or the start of one
+    pub session: Option<&'a str>,
+    /// Include items marked done
+    pub include_done: bool,
+    pub limit: Option<usize>,
+}
+
+/// Store the items found in a session, keeping those already known.
+/// Returns how many were new.
+pub fn save_action_items(
+    conn: &Connection,
+    session_id: &str,
+    items: &[ActionItem],
+) -> Result<usize> {
+    let now = Utc::now().timestamp_millis();
+    let mut stmt = conn.prepare_cached(
+        "INSERT OR IGNORE INTO action_items
+             (session_id, kind, text, role, message_index, status, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+    let mut added = 0;
+    for item in items {
+        added += stmt.execute(params![
+            session_id,
+            item.kind.as_str(),
+            item.text,
+            item.role,
+            item.message_index as i64,
+            OPEN,
+            now
+        ])?;
+    }
+    Ok(added)
+}
+
+/// Stored items matching `filter`, newest sessions first
+pub fn list_action_items(
+    conn: &Connection,
+    filter: &ActionFilter,
+) -> Result<Vec<StoredActionItem>> {
+    let mut sql = String::from(
+        "SELECT a.id, a.session_id, COALESCE(s.title, ''), a.kind, a.text, a.role, a.status,
+                a.created_at, a.exported_to
+         FROM action_items a LEFT JOIN sessions s ON s.id = a.session_id
+         WHERE (?1 IS NULL OR a.session_id = ?1 OR a.session_id LIKE ?1 || '%')",
+    );
+    if !filter.include_done {
+        sql.push_str(&format!(" AND a.status = '{}'", OPEN));
+    }
+    if !filter.kinds.is_empty() {
+        let kinds: Vec<String> = filter
+            .kinds
+            .iter()
+            .map(|kind| format!("'{}'", kind.as_str()))
+            .collect();
+        sql.push_str(&format!(" AND a.kind IN ({})", kinds.join(", ")));
+    }
+    sql.push_str(" ORDER BY s.updated_at DESC, a.session_id, a.message_index, a.id");
+    if let Some(limit) = filter.limit {
+        sql.push_str(&format!(" LIMIT {}", limit));
+    }
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map([filter.session], |row| {
+        Ok(StoredActionItem {
+            id: row.get(0)?,
+            session_id: row.get(1)?,
+            session_title: row.get(2)?,
+            kind: row.get(3)?,
+            text: row.get(4)?,
+            role: row.get(5)?,
+            status: row.get(6)?,
+            created_at: row.get(7)?,
+            exported_to: row.get(8)?,
+        })
+    })?;
+    Ok(rows.collect::<std::result::Result<Vec<_>, _>>()?)
+}
+
+/// Set an item's status. Returns whether the item exists.
+pub fn set_action_status(conn: &Connection, id: i64, status: &str) -> Result<bool> {
+    let changed = conn.execute(
+        "UPDATE action_items SET status = ?2 WHERE id = ?1",
+        params![id, status],
+    )?;
+    Ok(changed > 0)
+}
+
+/// Record where an item was exported, so it is not exported there again
+pub fn set_action_exported(conn: &Connection, id: i64, exported_to: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE action_items SET exported_to = ?2 WHERE id = ?1",
+        params![id, exported_to],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_keeps_status() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE sessions (id TEXT PRIMARY KEY, title TEXT, updated_at INTEGER);",
+        )
+        .unwrap();
+        conn.execute("INSERT INTO sessions VALUES ('s1', 'Cache', 1)", [])
+            .unwrap();
+        ensure_action_items_table(&conn).unwrap();
+
+        let item = |kind, text: &str| ActionItem {
+            kind,
+            text: text.to_string(),
+            role: "user".to_string(),
+            message_index: 0,
+        };
+        let items = [
+            item(ActionKind::Todo, "Add an index"),
+            item(ActionKind::Decision, "We decided to use SQLite"),
+        ];
+        assert_eq!(save_action_items(&conn, "s1", &items).unwrap(), 2);
+        let todo = &list_action_items(&conn, &ActionFilter::default()).unwrap()[0];
+        assert_eq!(todo.session_title, "Cache");
+        assert!(set_action_status(&conn, todo.id, DONE).unwrap());
+
+        // Found again by the next pass, but still done
+        assert_eq!(save_action_items(&conn, "s1", &items).unwrap(), 0);
+        let open = list_action_items(&conn, &ActionFilter::default()).unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].kind, "decision");
+        let filter = ActionFilter {
+            kinds: vec![ActionKind::Todo],
+            session: Some("s"),
+            include_done: true,
+            ..Default::default()
+        };
+        assert_eq!(list_action_items(&conn, &filter).unwrap()[0].status, DONE);
+    }
+}
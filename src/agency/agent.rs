@@ -8,6 +8,7 @@
 
 use crate::agency::models::{ModelConfig, ModelProvider};
 use crate::agency::tools::Tool;
+use crate::config::SandboxConfig;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -80,9 +81,19 @@ pub struct AgentConfig {
     /// Model configuration
     #[serde(default)]
     pub model: ModelConfig,
+    /// Models tried in turn when `model`'s provider fails
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_models: Vec<ModelConfig>,
     /// Registered tool names
     #[serde(default)]
     pub tools: Vec<String>,
+    /// MCP servers from `[agency.mcp_servers]` whose tools the agent mounts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mcp_servers: Vec<String>,
+    /// What the agent's file, shell, and HTTP tools may touch (default:
+    /// the built-in sandbox)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<SandboxConfig>,
     /// Sub-agent names (for hierarchical agents)
     #[serde(default)]
     pub sub_agents: Vec<String>,
@@ -110,7 +121,10 @@ impl Default for AgentConfig {
             instruction: String::new(),
             role: AgentRole::default(),
             model: ModelConfig::default(),
+            fallback_models: Vec::new(),
             tools: Vec::new(),
+            mcp_servers: Vec::new(),
+            sandbox: None,
             sub_agents: Vec::new(),
             output_key: None,
             max_iterations: None,
@@ -176,6 +190,16 @@ impl Agent {
         &self.config.model
     }
 
+    /// Models tried in turn when the agent's own fails
+    pub fn fallback_models(&self) -> &[ModelConfig] {
+        &self.config.fallback_models
+    }
+
+    /// The policy the agent's tools run under
+    pub fn sandbox(&self) -> SandboxConfig {
+        self.config.sandbox.clone().unwrap_or_default()
+    }
+
     /// Check if agent has tools
     pub fn has_tools(&self) -> bool {
         !self.registered_tools.is_empty()
@@ -246,15 +270,16 @@ impl AgentBuilder {
         self
     }
 
-    /// Set model by name (uses default provider based on model name)
+    /// Set model by name (uses default provider based on model name), or
+    /// as `provider/model` such as `ollama/qwen2.5`
     pub fn model(mut self, model: impl Into<String>) -> Self {
-        let model_name = model.into();
-        let provider = infer_provider(&model_name);
-        self.config.model = ModelConfig {
-            model: model_name,
-            provider,
-            ..Default::default()
-        };
+        self.config.model = parse_model(&model.into());
+        self
+    }
+
+    /// Add a model to fall back to, named like [`AgentBuilder::model`]
+    pub fn fallback_model(mut self, model: impl Into<String>) -> Self {
+        self.config.fallback_models.push(parse_model(&model.into()));
         self
     }
 
@@ -292,6 +317,18 @@ impl AgentBuilder {
         self
     }
 
+    /// Mount the tools of a configured MCP server
+    pub fn mcp_server(mut self, name: impl Into<String>) -> Self {
+        self.config.mcp_servers.push(name.into());
+        self
+    }
+
+    /// Confine the agent's tools to a sandbox policy
+    pub fn sandbox(mut self, sandbox: SandboxConfig) -> Self {
+        self.config.sandbox = Some(sandbox);
+        self
+    }
+
     /// Add a sub-agent
     pub fn sub_agent(mut self, agent: Agent) -> Self {
         let name = agent.config.name.clone();
@@ -339,6 +376,23 @@ impl AgentBuilder {
     }
 }
 
+/// The model named `provider/model`, or by a bare name whose provider is
+/// inferred
+pub fn parse_model(name: &str) -> ModelConfig {
+    let (provider, model) = match name
+        .split_once('/')
+        .and_then(|(prefix, model)| Some((ModelProvider::from_name(prefix)?, model)))
+    {
+        Some((provider, model)) => (provider, model.to_string()),
+        None => (infer_provider(name), name.to_string()),
+    };
+    ModelConfig {
+        model,
+        provider,
+        ..Default::default()
+    }
+}
+
 /// Infer provider from model name
 fn infer_provider(model: &str) -> ModelProvider {
     let model_lower = model.to_lowercase();
@@ -387,5 +441,13 @@ mod tests {
         assert_eq!(infer_provider("gpt-4o"), ModelProvider::OpenAI);
         assert_eq!(infer_provider("claude-3-opus"), ModelProvider::Anthropic);
         assert_eq!(infer_provider("llama-3.2-8b"), ModelProvider::Ollama);
+
+        let agent = AgentBuilder::new("local").model("ollama/qwen2.5").build();
+        assert_eq!(agent.config.model.provider, ModelProvider::Ollama);
+        assert_eq!(agent.config.model.model, "qwen2.5");
+        let agent = AgentBuilder::new("hub")
+            .model("meta-llama/Llama-3.1-8B")
+            .build();
+        assert_eq!(agent.config.model.model, "meta-llama/Llama-3.1-8B");
     }
 }
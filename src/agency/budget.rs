@@ -0,0 +1,453 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Token and Cost Budgets
+//!
+//! Every model call of a run is charged to the run's [`Budget`], which
+//! prices its tokens per model and stops the run with
+//! [`AgencyError::BudgetExceeded`] once the run, or all of the day's runs
+//! together, pass the limits under `[agency.budget]` in `config.toml`.
+//! What each run spent is kept in the `agent_usage` table of the harvest
+//! database, where the daily limits and `csm report spend` read it.
+
+use crate::agency::error::{AgencyError, AgencyResult};
+use crate::agency::models::{ModelConfig, TokenUsage};
+use crate::config::{BudgetConfig, ModelPrice};
+use chrono::{DateTime, NaiveTime, Utc};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Prices of well-known models in USD per million tokens, matched by prefix
+/// so dated versions share their family's price
+const PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("o1-mini", 1.10, 4.40),
+    ("o1", 15.00, 60.00),
+    ("o3-mini", 1.10, 4.40),
+    ("o4-mini", 1.10, 4.40),
+    ("claude-opus-4", 15.00, 75.00),
+    ("claude-sonnet-4", 3.00, 15.00),
+    ("claude-3-7-sonnet", 3.00, 15.00),
+    ("claude-3-5-sonnet", 3.00, 15.00),
+    ("claude-3-5-haiku", 0.80, 4.00),
+    ("gemini-2.5-pro", 1.25, 10.00),
+    ("gemini-2.5-flash", 0.30, 2.50),
+    ("gemini-2.0-flash", 0.10, 0.40),
+    ("gemini-1.5-pro", 1.25, 5.00),
+    ("gemini-1.5-flash", 0.075, 0.30),
+    ("deepseek-chat", 0.27, 1.10),
+    ("deepseek-reasoner", 0.55, 2.19),
+    ("mistral-large", 2.00, 6.00),
+    ("mistral-small", 0.20, 0.60),
+];
+
+/// The price of `model`: a configured one, else nothing for local
+/// providers, else the built-in price of the longest prefix that matches
+pub fn price(model: &ModelConfig, prices: &BTreeMap<String, ModelPrice>) -> Option<ModelPrice> {
+    if let Some(price) = prices.get(&model.model) {
+        return Some(*price);
+    }
+    if model.provider.is_local() {
+        return Some(ModelPrice::default());
+    }
+    builtin_price(&model.model)
+}
+
+/// The built-in price of the longest prefix of `model` that matches, with
+/// any `provider/` in front ignored
+pub fn builtin_price(model: &str) -> Option<ModelPrice> {
+    let name = model.rsplit('/').next().unwrap_or(model);
+    PRICES
+        .iter()
+        .filter(|(prefix, _, _)| name.starts_with(prefix))
+        .max_by_key(|(prefix, _, _)| prefix.len())
+        .map(|&(_, input, output)| ModelPrice { input, output })
+}
+
+/// What `usage` of `model` costs in USD, when its price is known
+pub fn cost(
+    model: &ModelConfig,
+    usage: &TokenUsage,
+    prices: &BTreeMap<String, ModelPrice>,
+) -> Option<f64> {
+    price(model, prices).map(|p| {
+        (f64::from(usage.prompt_tokens) * p.input + f64::from(usage.completion_tokens) * p.output)
+            / 1_000_000.0
+    })
+}
+
+/// Tokens and cost of one agent's calls to one model
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Spend {
+    pub agent: String,
+    pub model: String,
+    pub calls: u32,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Cost in USD, `None` when the model's price is unknown
+    pub cost_usd: Option<f64>,
+}
+
+impl Spend {
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+/// What a run may spend, and what it has spent so far
+#[derive(Debug)]
+pub struct Budget {
+    config: BudgetConfig,
+    /// Tokens and USD the day's earlier runs spent
+    day_tokens: u64,
+    day_cost: f64,
+    spent: Mutex<Vec<Spend>>,
+}
+
+impl Budget {
+    /// A budget with the limits of `config` and nothing spent today
+    pub fn new(config: BudgetConfig) -> Self {
+        Self {
+            config,
+            day_tokens: 0,
+            day_cost: 0.0,
+            spent: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// A budget with the limits of `config`, less what the runs recorded in
+    /// `conn` spent today
+    pub fn load(conn: &Connection, config: BudgetConfig) -> AgencyResult<Self> {
+        ensure_usage_table(conn)?;
+        let (day_tokens, day_cost): (i64, f64) = conn.query_row(
+            "SELECT COALESCE(SUM(prompt_tokens + completion_tokens), 0),
+                    COALESCE(SUM(cost_usd), 0.0)
+             FROM agent_usage WHERE created_at >= ?1",
+            [start_of_today().timestamp_millis()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        Ok(Self {
+            day_tokens: day_tokens.max(0) as u64,
+            day_cost,
+            ..Self::new(config)
+        })
+    }
+
+    /// Charge a call of `agent` to `model`, failing once a limit is passed
+    pub fn charge(&self, agent: &str, model: &ModelConfig, usage: &TokenUsage) -> AgencyResult<()> {
+        let cost = cost(model, usage, &self.config.prices);
+        let mut spent = self.spent.lock().unwrap_or_else(|e| e.into_inner());
+        let index = match spent
+            .iter()
+            .position(|s| s.agent == agent && s.model == model.model)
+        {
+            Some(index) => index,
+            None => {
+                spent.push(Spend {
+                    agent: agent.to_string(),
+                    model: model.model.clone(),
+                    cost_usd: Some(0.0),
+                    ..Default::default()
+                });
+                spent.len() - 1
+            }
+        };
+        let entry = &mut spent[index];
+        entry.calls += 1;
+        entry.prompt_tokens += u64::from(usage.prompt_tokens);
+        entry.completion_tokens += u64::from(usage.completion_tokens);
+        entry.cost_usd = entry.cost_usd.zip(cost).map(|(a, b)| a + b);
+
+        let (tokens, cost) = totals(&spent);
+        let limits = &self.config;
+        if let Some(max) = limits.max_run_tokens.filter(|max| tokens > *max) {
+            return Err(exceeded(format!(
+                "run used {} tokens, limit {}",
+                tokens, max
+            )));
+        }
+        if let Some(max) = limits
+            .max_day_tokens
+            .filter(|max| self.day_tokens + tokens > *max)
+        {
+            return Err(exceeded(format!(
+                "runs used {} tokens today, limit {}",
+                self.day_tokens + tokens,
+                max
+            )));
+        }
+        if let Some(max) = limits.max_run_usd.filter(|max| cost > *max) {
+            return Err(exceeded(format!(
+                "run cost ${:.4}, limit ${:.2}",
+                cost, max
+            )));
+        }
+        if let Some(max) = limits.max_day_usd.filter(|max| self.day_cost + cost > *max) {
+            return Err(exceeded(format!(
+                "runs cost ${:.4} today, limit ${:.2}",
+                self.day_cost + cost,
+                max
+            )));
+        }
+        Ok(())
+    }
+
+    /// What the run spent, per agent and model
+    pub fn spent(&self) -> Vec<Spend> {
+        self.spent.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Tokens the run used and what its priced calls cost in USD
+    pub fn total(&self) -> (u64, f64) {
+        totals(&self.spent())
+    }
+
+    /// Record what run `run_id` spent in `conn`
+    pub fn save(&self, conn: &Connection, run_id: &str) -> AgencyResult<()> {
+        for spend in self.spent() {
+            record_spend(conn, run_id, &spend)?;
+        }
+        Ok(())
+    }
+}
+
+/// Record `spend` under `run_id` in `conn`, for the daily limits and
+/// `csm report spend`
+pub fn record_spend(conn: &Connection, run_id: &str, spend: &Spend) -> AgencyResult<()> {
+    ensure_usage_table(conn)?;
+    conn.execute(
+        "INSERT INTO agent_usage
+         (run_id, agent, model, calls, prompt_tokens, completion_tokens, cost_usd, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            run_id,
+            spend.agent,
+            spend.model,
+            spend.calls,
+            spend.prompt_tokens as i64,
+            spend.completion_tokens as i64,
+            spend.cost_usd,
+            Utc::now().timestamp_millis(),
+        ],
+    )?;
+    Ok(())
+}
+
+fn totals(spent: &[Spend]) -> (u64, f64) {
+    spent.iter().fold((0, 0.0), |(tokens, cost), s| {
+        (tokens + s.total_tokens(), cost + s.cost_usd.unwrap_or(0.0))
+    })
+}
+
+fn exceeded(detail: String) -> AgencyError {
+    AgencyError::BudgetExceeded(format!(
+        "{}; raise the limits under [agency.budget] in config.toml",
+        detail
+    ))
+}
+
+/// Midnight today in the display timezone
+fn start_of_today() -> DateTime<Utc> {
+    let tz = crate::datetime::display_timezone();
+    tz.resolve(tz.date_of(Utc::now()).and_time(NaiveTime::MIN))
+        .unwrap_or_else(Utc::now)
+}
+
+pub fn ensure_usage_table(conn: &Connection) -> AgencyResult<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS agent_usage (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            run_id TEXT NOT NULL,
+            agent TEXT NOT NULL,
+            model TEXT NOT NULL,
+            calls INTEGER NOT NULL,
+            prompt_tokens INTEGER NOT NULL,
+            completion_tokens INTEGER NOT NULL,
+            cost_usd REAL,
+            created_at INTEGER NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_agent_usage_created ON agent_usage(created_at);
+        "#,
+    )?;
+    Ok(())
+}
+
+/// What agent runs spent on one day, or with one model
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SpendTotal {
+    pub name: String,
+    pub runs: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost_usd: f64,
+    /// Tokens spent on models without a known price
+    pub unpriced_tokens: u64,
+}
+
+/// Agent spend since a moment, by day and by model
+#[derive(Debug, Clone, Serialize)]
+pub struct SpendReport {
+    pub since: DateTime<Utc>,
+    pub total: SpendTotal,
+    pub days: Vec<SpendTotal>,
+    pub models: Vec<SpendTotal>,
+    pub agents: Vec<SpendTotal>,
+}
+
+impl SpendReport {
+    /// Sum the spend `conn` recorded since `since`
+    pub fn load(conn: &Connection, since: DateTime<Utc>) -> AgencyResult<Self> {
+        ensure_usage_table(conn)?;
+        let mut stmt = conn.prepare(
+            "SELECT run_id, agent, model, prompt_tokens, completion_tokens, cost_usd, created_at
+             FROM agent_usage WHERE created_at >= ?1 ORDER BY created_at",
+        )?;
+        let rows = stmt
+            .query_map([since.timestamp_millis()], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, i64>(4)?,
+                    row.get::<_, Option<f64>>(5)?,
+                    row.get::<_, i64>(6)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut total = SpendTotal {
+            name: "total".to_string(),
+            ..Default::default()
+        };
+        let mut days: BTreeMap<String, (SpendTotal, Vec<String>)> = BTreeMap::new();
+        let mut models: BTreeMap<String, (SpendTotal, Vec<String>)> = BTreeMap::new();
+        let mut agents: BTreeMap<String, (SpendTotal, Vec<String>)> = BTreeMap::new();
+        let mut runs: Vec<String> = Vec::new();
+        for (run_id, agent, model, prompt, completion, cost, created_at) in rows {
+            let day = crate::datetime::from_epoch(created_at)
+                .map(crate::datetime::format_date)
+                .unwrap_or_default();
+            for (totals, key) in [(&mut days, day), (&mut models, model), (&mut agents, agent)] {
+                let (spend, seen) = totals.entry(key.clone()).or_default();
+                spend.name = key;
+                add(spend, seen, &run_id, prompt, completion, cost);
+            }
+            add(&mut total, &mut runs, &run_id, prompt, completion, cost);
+        }
+
+        let sorted = |totals: BTreeMap<String, (SpendTotal, Vec<String>)>| {
+            let mut totals: Vec<SpendTotal> = totals.into_values().map(|(s, _)| s).collect();
+            totals.sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd));
+            totals
+        };
+        Ok(Self {
+            since,
+            total,
+            days: days.into_values().map(|(s, _)| s).collect(),
+            models: sorted(models),
+            agents: sorted(agents),
+        })
+    }
+}
+
+fn add(
+    total: &mut SpendTotal,
+    runs: &mut Vec<String>,
+    run_id: &str,
+    prompt: i64,
+    completion: i64,
+    cost: Option<f64>,
+) {
+    if !runs.iter().any(|r| r == run_id) {
+        runs.push(run_id.to_string());
+        total.runs += 1;
+    }
+    total.prompt_tokens += prompt.max(0) as u64;
+    total.completion_tokens += completion.max(0) as u64;
+    match cost {
+        Some(cost) => total.cost_usd += cost,
+        None => total.unpriced_tokens += (prompt + completion).max(0) as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agency::models::ModelProvider;
+
+    #[test]
+    fn test_budget() {
+        let model = |name: &str, provider| ModelConfig {
+            model: name.to_string(),
+            provider,
+            ..Default::default()
+        };
+        let house = model("house-model", ModelProvider::OpenAICompatible);
+        let mystery = model("mystery", ModelProvider::OpenAICompatible);
+        let prices = BTreeMap::from([(
+            house.model.clone(),
+            ModelPrice {
+                input: 1.0,
+                output: 2.0,
+            },
+        )]);
+        let openai = |name| price(&model(name, ModelProvider::OpenAI), &prices);
+        assert_eq!(openai("gpt-4o-mini-2024-07-18").unwrap().input, 0.15);
+        assert_eq!(openai("openai/gpt-4o").unwrap().input, 2.50);
+        let local = model("llama3", ModelProvider::Ollama);
+        assert_eq!(price(&local, &prices).unwrap().output, 0.0);
+        assert!(price(&mystery, &prices).is_none());
+
+        let conn = Connection::open_in_memory().unwrap();
+        let budget = Budget::load(
+            &conn,
+            BudgetConfig {
+                max_run_usd: Some(0.01),
+                prices,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        // 2000 prompt and 1000 completion tokens of house-model cost $0.004
+        let call = TokenUsage::new(2000, 1000);
+        budget.charge("writer", &house, &call).unwrap();
+        budget.charge("writer", &mystery, &call).unwrap();
+        budget.charge("writer", &house, &call).unwrap();
+        let err = budget.charge("writer", &house, &call).unwrap_err();
+        assert!(matches!(err, AgencyError::BudgetExceeded(_)));
+
+        // The call that passed the limit still counts
+        let spent = budget.spent();
+        assert_eq!((spent[0].calls, spent[0].prompt_tokens), (3, 6000));
+        assert_eq!(spent[1].cost_usd, None);
+        let (tokens, cost) = budget.total();
+        assert_eq!(tokens, 12000);
+        assert!((cost - 0.012).abs() < 1e-9);
+
+        // The day's spend counts against the next run
+        budget.save(&conn, "run-1").unwrap();
+        let next = Budget::load(
+            &conn,
+            BudgetConfig {
+                max_day_tokens: Some(12500),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(next.charge("coder", &mystery, &call).is_err());
+
+        let report = SpendReport::load(&conn, Utc::now() - chrono::Duration::days(1)).unwrap();
+        assert_eq!(report.total.runs, 1);
+        assert_eq!(report.total.unpriced_tokens, 3000);
+        assert_eq!(report.models[0].name, "house-model");
+        assert_eq!(report.days.len(), 1);
+    }
+}
@@ -0,0 +1,247 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Agent Definitions
+//!
+//! Agents declared in files, so they outlive the command that created them
+//! and can be kept in version control. Each `*.toml`, `*.yaml`, or `*.yml`
+//! file in the `agents` folder next to `config.toml` defines one agent,
+//! named by its `name` field or else by the file name. The library's
+//! `agents/*.json` items use the same fields.
+
+use crate::agency::error::{AgencyError, AgencyResult};
+use crate::agency::tools::{BuiltinTools, Tool};
+use crate::config::SandboxConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Extensions of agent files
+const EXTENSIONS: [&str; 3] = ["toml", "yaml", "yml"];
+
+/// What an agent file declares
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgentDefinition {
+    /// Name of the agent (default: the file name)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    #[serde(alias = "instructions", skip_serializing_if = "Option::is_none")]
+    pub instruction: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    /// A model name, or a full model configuration
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Models to fall back to when the agent's fails, as `provider/model`
+    /// (default: the `[agency.fallback]` chain)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fallback: Vec<String>,
+    /// Builtin tools the agent can call, by name
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+    /// How `agency run` runs the agent unless told otherwise
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orchestration: Option<String>,
+    /// Servers from `[agency.mcp_servers]` whose tools the agent can call
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mcp_servers: Vec<String>,
+    /// Whether the agent can search harvested sessions (default: yes)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_search: Option<bool>,
+    /// What the agent's tools may touch (default: `[agency.sandbox]`).
+    /// Settings it leaves out take the built-in defaults.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<SandboxConfig>,
+}
+
+impl AgentDefinition {
+    /// Parse a definition in the format `path`'s extension names
+    pub fn parse(text: &str, path: &Path) -> AgencyResult<Self> {
+        let invalid = |e: String| {
+            AgencyError::ConfigError(format!(
+                "Invalid agent definition in {}: {}",
+                path.display(),
+                e
+            ))
+        };
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(text).map_err(|e| invalid(e.to_string())),
+            _ => serde_yaml::from_str(text).map_err(|e| invalid(e.to_string())),
+        }
+    }
+
+    /// The builtin tools the definition names
+    pub fn builtin_tools(&self) -> AgencyResult<Vec<Tool>> {
+        let builtins = BuiltinTools::all();
+        self.tools
+            .iter()
+            .map(|name| {
+                builtins
+                    .iter()
+                    .find(|tool| tool.name == *name)
+                    .cloned()
+                    .ok_or_else(|| {
+                        let names: Vec<&str> = builtins.iter().map(|t| t.name.as_str()).collect();
+                        AgencyError::ToolNotFound(format!(
+                            "{} (builtin tools: {})",
+                            name,
+                            names.join(", ")
+                        ))
+                    })
+            })
+            .collect()
+    }
+
+    /// The model's name, when the definition gives one
+    pub fn model_name(&self) -> Option<&str> {
+        match &self.model {
+            Some(Value::String(model)) => Some(model),
+            Some(config) => config["model"].as_str(),
+            None => None,
+        }
+    }
+
+    /// Write the definition to `<dir>/<name>.toml`, refusing to replace an
+    /// agent file of that name unless `force` is set
+    pub fn save(&self, dir: &Path, name: &str, force: bool) -> AgencyResult<PathBuf> {
+        if !force {
+            if let Some(existing) = find(dir, name)? {
+                return Err(AgencyError::ConfigError(format!(
+                    "Agent '{}' is already defined in {}",
+                    name,
+                    existing.path.display()
+                )));
+            }
+        }
+        let text = toml::to_string_pretty(self)
+            .map_err(|e| AgencyError::SerializationError(e.to_string()))?;
+        let path = dir.join(format!("{}.toml", name));
+        std::fs::create_dir_all(dir)
+            .and_then(|_| std::fs::write(&path, text))
+            .map_err(|e| {
+                AgencyError::ConfigError(format!("Cannot write {}: {}", path.display(), e))
+            })?;
+        Ok(path)
+    }
+}
+
+/// An agent declared in a file
+#[derive(Debug, Clone)]
+pub struct AgentFile {
+    pub name: String,
+    pub path: PathBuf,
+    pub definition: AgentDefinition,
+}
+
+/// The `agents` folder next to `config.toml`
+pub fn agents_dir() -> AgencyResult<PathBuf> {
+    let config_path =
+        crate::config::Config::path().map_err(|e| AgencyError::ConfigError(e.to_string()))?;
+    let dir = config_path.parent().unwrap_or(Path::new("."));
+    Ok(dir.join("agents"))
+}
+
+/// The agents declared in `dir`, by name. A missing folder declares none.
+pub fn load_dir(dir: &Path) -> AgencyResult<Vec<AgentFile>> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| EXTENSIONS.contains(&e))
+        })
+        .collect();
+    paths.sort();
+
+    let mut agents: Vec<AgentFile> = Vec::new();
+    for path in paths {
+        let text = std::fs::read_to_string(&path).map_err(|e| {
+            AgencyError::ConfigError(format!("Cannot read {}: {}", path.display(), e))
+        })?;
+        let definition = AgentDefinition::parse(&text, &path)?;
+        let name = match &definition.name {
+            Some(name) => name.clone(),
+            None => path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        };
+        if let Some(other) = agents.iter().find(|a| a.name == name) {
+            return Err(AgencyError::ConfigError(format!(
+                "Agent '{}' is defined in both {} and {}",
+                name,
+                other.path.display(),
+                path.display()
+            )));
+        }
+        agents.push(AgentFile {
+            name,
+            path,
+            definition,
+        });
+    }
+    agents.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(agents)
+}
+
+/// The agent called `name` in `dir`, if one is declared there
+pub fn find(dir: &Path, name: &str) -> AgencyResult<Option<AgentFile>> {
+    Ok(load_dir(dir)?.into_iter().find(|agent| agent.name == name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agent_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("reviewer.yaml"),
+            "role: reviewer\ninstructions: Review carefully.\ntools: [read_file]\norchestration: sequential\nsandbox: { paths: [src], read_only: true }\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("helper.toml"),
+            "name = \"docs\"\nmodel = { provider = \"openai\", model = \"gpt-4o\" }\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "not an agent").unwrap();
+
+        let agents = load_dir(dir.path()).unwrap();
+        let names: Vec<&str> = agents.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["docs", "reviewer"]);
+        assert_eq!(agents[0].definition.model_name(), Some("gpt-4o"));
+        let reviewer = &agents[1].definition;
+        assert_eq!(reviewer.instruction.as_deref(), Some("Review carefully."));
+        assert_eq!(reviewer.orchestration.as_deref(), Some("sequential"));
+        assert_eq!(reviewer.builtin_tools().unwrap()[0].name, "read_file");
+        let sandbox = reviewer.sandbox.as_ref().unwrap();
+        assert!(sandbox.read_only && !sandbox.network);
+        assert_eq!(sandbox.paths, ["src"]);
+
+        let created = AgentDefinition {
+            role: Some("coder".into()),
+            model: Some(Value::String("gpt-4o".into())),
+            tools: vec!["no_such_tool".into()],
+            ..Default::default()
+        };
+        assert!(created.builtin_tools().is_err());
+        let path = created.save(dir.path(), "coder", false).unwrap();
+        assert!(path.ends_with("coder.toml"));
+        assert!(created.save(dir.path(), "coder", false).is_err());
+        assert!(created.save(dir.path(), "reviewer", false).is_err());
+        let coder = find(dir.path(), "coder").unwrap().unwrap();
+        assert_eq!(coder.definition.model_name(), Some("gpt-4o"));
+        assert_eq!(coder.definition.tools, ["no_such_tool"]);
+    }
+}
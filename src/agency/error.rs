@@ -39,6 +39,9 @@ pub enum AgencyError {
     #[error("Max iterations ({0}) exceeded")]
     MaxIterationsExceeded(u32),
 
+    #[error("Budget exceeded: {0}")]
+    BudgetExceeded(String),
+
     #[error("Cancelled by user")]
     Cancelled,
 
@@ -51,6 +54,14 @@ pub enum AgencyError {
     #[error("Network error: {0}")]
     NetworkError(String),
 
+    /// The provider answered with a status worth trying elsewhere
+    #[error("Provider unavailable: {0}")]
+    ProviderUnavailable(String),
+
+    /// A workspace policy allows none of the agent's models
+    #[error("Denied by workspace policy: {0}")]
+    PolicyDenied(String),
+
     #[error("Invalid state: {0}")]
     InvalidState(String),
 
@@ -3,22 +3,39 @@
 //! Agent Execution
 //!
 //! Handles the execution of individual agents with tool calling.
+//!
+//! When a model's provider is unreachable or answers 408, 429 or 5xx, the
+//! agent's fallback models are tried in turn, under the `[agency.fallback]`
+//! attempt limit and circuit breakers shared by every run in the process.
 
 #![allow(dead_code)]
 
 use crate::agency::agent::{Agent, AgentStatus};
+use crate::agency::budget::Budget;
 use crate::agency::error::{AgencyError, AgencyResult};
+use crate::agency::modality::ContentPart;
 use crate::agency::models::{
-    AgencyEvent, AgencyMessage, EventType, MessageRole, TokenUsage, ToolCall, ToolResult,
+    AgencyEvent, AgencyMessage, EventType, MessageRole, ModelConfig, ModelProvider, TokenUsage,
+    ToolCall, ToolResult,
 };
 use crate::agency::session::{generate_message_id, Session};
 use crate::agency::tools::ToolRegistry;
+use crate::config::{self, SandboxConfig};
+use crate::routing::{is_retryable_status, CircuitBreakers, FallbackPolicy, PolicyGuard};
 use chrono::Utc;
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 
+/// Metadata key of the `provider/model` that wrote an assistant message
+pub const SERVED_BY: &str = "served_by";
+
+/// Circuits of the providers agents call, shared by every run
+static BREAKERS: Lazy<Mutex<CircuitBreakers>> =
+    Lazy::new(|| Mutex::new(CircuitBreakers::default()));
+
 /// Execution context passed to tools
 #[derive(Debug, Clone)]
 pub struct ExecutionContext {
@@ -36,6 +53,14 @@ pub struct ExecutionContext {
     pub max_tool_calls: u32,
     /// Event sender for streaming
     pub event_sender: Option<mpsc::Sender<AgencyEvent>>,
+    /// Send replies as `MessageDelta` events while the model writes them
+    pub stream: bool,
+    /// Budget each model call is charged to
+    pub budget: Option<Arc<Budget>>,
+    /// Images sent with the next user message
+    pub attachments: Vec<ContentPart>,
+    /// Workspace policy limiting the providers models may come from
+    pub policy: Option<Arc<PolicyGuard>>,
 }
 
 impl ExecutionContext {
@@ -48,6 +73,10 @@ impl ExecutionContext {
             allow_tools: true,
             max_tool_calls: 10,
             event_sender: None,
+            stream: false,
+            budget: None,
+            attachments: Vec::new(),
+            policy: None,
         }
     }
 
@@ -121,6 +150,7 @@ impl Executor {
             id: generate_message_id(),
             role: MessageRole::User,
             content: user_message.to_string(),
+            attachments: std::mem::take(&mut ctx.attachments),
             tool_calls: vec![],
             tool_result: None,
             timestamp: Utc::now(),
@@ -150,14 +180,39 @@ impl Executor {
             ctx.emit(thinking_event).await;
 
             // Call the model with the current session context
-            let model_response = self.call_model(agent, session).await?;
+            let (model_response, served_by) = self
+                .call_with_fallback(agent, session, ctx, &mut events)
+                .await?;
+            let served = HashMap::from([(
+                SERVED_BY.to_string(),
+                serde_json::json!(format!("{}/{}", served_by.provider, served_by.model)),
+            )]);
 
             token_usage.add(&model_response.usage);
+            if let Some(budget) = &ctx.budget {
+                budget.charge(agent.name(), &served_by, &model_response.usage)?;
+            }
 
             // Check for tool calls
             if !model_response.tool_calls.is_empty() && ctx.allow_tools {
                 agent.set_status(AgentStatus::WaitingForTool);
 
+                // The model sees its own calls before their results
+                let call_msg = AgencyMessage {
+                    id: generate_message_id(),
+                    role: MessageRole::Assistant,
+                    content: model_response.content.clone(),
+                    attachments: Vec::new(),
+                    tool_calls: model_response.tool_calls.clone(),
+                    tool_result: None,
+                    timestamp: Utc::now(),
+                    tokens: Some(model_response.usage.completion_tokens),
+                    agent_name: Some(agent.name().to_string()),
+                    metadata: served,
+                };
+                session.add_message(call_msg.clone());
+                messages.push(call_msg);
+
                 for tool_call in &model_response.tool_calls {
                     tool_call_count += 1;
                     if tool_call_count > ctx.max_tool_calls {
@@ -180,7 +235,8 @@ impl Executor {
 
                     // Execute tool
                     agent.set_status(AgentStatus::Executing);
-                    let tool_result = self.execute_tool(tool_call).await;
+                    let mut tool_result = self.execute_tool(tool_call, &agent.sandbox()).await;
+                    tool_result.call_id = tool_call.id.clone();
 
                     // Emit tool result event
                     let result_event = AgencyEvent {
@@ -189,7 +245,8 @@ impl Executor {
                         data: serde_json::json!({
                             "tool": tool_call.name,
                             "success": tool_result.success,
-                            "content": tool_result.content
+                            "content": tool_result.content,
+                            "data": tool_result.data
                         }),
                         timestamp: Utc::now(),
                         session_id: Some(session.id.clone()),
@@ -202,6 +259,7 @@ impl Executor {
                         id: generate_message_id(),
                         role: MessageRole::Tool,
                         content: tool_result.content.clone(),
+                        attachments: Vec::new(),
                         tool_calls: vec![],
                         tool_result: Some(tool_result),
                         timestamp: Utc::now(),
@@ -225,12 +283,13 @@ impl Executor {
                 id: generate_message_id(),
                 role: MessageRole::Assistant,
                 content: model_response.content,
+                attachments: Vec::new(),
                 tool_calls: model_response.tool_calls,
                 tool_result: None,
                 timestamp: Utc::now(),
                 tokens: Some(model_response.usage.completion_tokens),
                 agent_name: Some(agent.name().to_string()),
-                metadata: HashMap::new(),
+                metadata: served,
             };
             session.add_message(assistant_msg.clone());
             messages.push(assistant_msg);
@@ -261,13 +320,141 @@ impl Executor {
         })
     }
 
-    /// Call the model using the appropriate provider
-    async fn call_model(&self, agent: &Agent, session: &Session) -> AgencyResult<ModelResponse> {
-        use crate::agency::models::ModelProvider;
+    /// Call the agent's model, then its fallback models while providers are
+    /// unreachable or answer with a status worth retrying. Gives the reply
+    /// and the model that wrote it.
+    async fn call_with_fallback(
+        &self,
+        agent: &Agent,
+        session: &Session,
+        ctx: &ExecutionContext,
+        events: &mut Vec<AgencyEvent>,
+    ) -> AgencyResult<(ModelResponse, ModelConfig)> {
+        let policy =
+            FallbackPolicy::from_config(&config::current().agency.fallback).unwrap_or_default();
+        let mut models = vec![agent.model()];
+        models.extend(agent.fallback_models());
+        if let Some(guard) = &ctx.policy {
+            let primary = models[0];
+            let mut denied = Vec::new();
+            models.retain(|model| {
+                match guard.check(&model.provider.to_string(), Some(&model.model)) {
+                    Ok(()) => true,
+                    Err(reason) => {
+                        denied.push((format!("{}/{}", model.provider, model.model), reason));
+                        false
+                    }
+                }
+            });
+            let Some(first) = models.first() else {
+                let reasons: Vec<String> = denied
+                    .iter()
+                    .map(|(target, reason)| format!("{}: {}", target, reason))
+                    .collect();
+                return Err(AgencyError::PolicyDenied(reasons.join("; ")));
+            };
+            if !std::ptr::eq(*first, primary) {
+                let event = AgencyEvent {
+                    event_type: EventType::ProviderFallback,
+                    agent_name: agent.name().to_string(),
+                    data: serde_json::json!({
+                        "from": format!("{}/{}", primary.provider, primary.model),
+                        "to": format!("{}/{}", first.provider, first.model),
+                        "error": denied[0].1,
+                        "denied": true,
+                    }),
+                    timestamp: Utc::now(),
+                    session_id: Some(session.id.clone()),
+                };
+                events.push(event.clone());
+                ctx.emit(event).await;
+            }
+        }
+        models.truncate(policy.max_attempts.max(1));
+        BREAKERS
+            .lock()
+            .unwrap()
+            .set_limits(policy.breaker_failures, policy.breaker_cooldown);
+
+        let last = models.len() - 1;
+        for (i, model) in models.iter().enumerate() {
+            let provider = model.provider.to_string();
+            // The last model is always tried, so a run never fails for open
+            // circuits alone
+            let result = if i < last && !BREAKERS.lock().unwrap().allow(&provider) {
+                Err(AgencyError::ProviderUnavailable(format!(
+                    "{} is failing; skipped for now",
+                    provider
+                )))
+            } else {
+                let result = self.call_model(agent, model, session, ctx).await;
+                let mut breakers = BREAKERS.lock().unwrap();
+                match &result {
+                    Err(AgencyError::NetworkError(_) | AgencyError::ProviderUnavailable(_)) => {
+                        breakers.record_failure(&provider)
+                    }
+                    _ => breakers.record_success(&provider),
+                }
+                result
+            };
+            match result {
+                Ok(response) => return Ok((response, (*model).clone())),
+                Err(e @ (AgencyError::NetworkError(_) | AgencyError::ProviderUnavailable(_)))
+                    if i < last =>
+                {
+                    let next = models[i + 1];
+                    let event = AgencyEvent {
+                        event_type: EventType::ProviderFallback,
+                        agent_name: agent.name().to_string(),
+                        data: serde_json::json!({
+                            "from": format!("{}/{}", model.provider, model.model),
+                            "to": format!("{}/{}", next.provider, next.model),
+                            "error": e.to_string(),
+                        }),
+                        timestamp: Utc::now(),
+                        session_id: Some(session.id.clone()),
+                    };
+                    events.push(event.clone());
+                    ctx.emit(event).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        unreachable!("the last model always gives a reply or an error")
+    }
 
-        let messages = session.to_api_messages();
+    /// Call one model using the appropriate provider
+    async fn call_model(
+        &self,
+        agent: &Agent,
+        model_config: &ModelConfig,
+        session: &Session,
+        ctx: &ExecutionContext,
+    ) -> AgencyResult<ModelResponse> {
+        let mut messages = session.to_api_messages();
+        if !agent.instruction().is_empty() {
+            messages.insert(
+                0,
+                serde_json::json!({ "role": "system", "content": agent.instruction() }),
+            );
+        }
         let tools = agent.tool_definitions();
-        let model_config = agent.model();
+
+        // Image parts are sent in OpenAI's format, which Anthropic reads
+        // once rewritten and Ollama's own API once moved to `images`
+        let has_images = messages.iter().any(|m| m["content"].is_array());
+        if has_images {
+            match model_config.provider {
+                ModelProvider::Anthropic => anthropic_images(&mut messages),
+                ModelProvider::Google | ModelProvider::Cohere | ModelProvider::Perplexity => {
+                    return Err(AgencyError::ModelError(format!(
+                        "{} models cannot be sent images from an agent; use an OpenAI, Anthropic or Ollama vision model",
+                        model_config.provider
+                    )));
+                }
+                _ => {}
+            }
+        }
 
         // Build request body
         let mut request_body = serde_json::json!({
@@ -284,6 +471,28 @@ impl Executor {
             request_body["tools"] = serde_json::json!(tools);
         }
 
+        // Ollama's own chat API takes sampling settings as options and
+        // tool arguments as objects, and streams unless told not to
+        let native_ollama = is_native_ollama(model_config);
+        if native_ollama {
+            request_body = ollama_request(request_body);
+        }
+
+        // Only OpenAI-compatible chat completions are read as a stream
+        let stream = ctx.stream
+            && ctx.event_sender.is_some()
+            && !native_ollama
+            && !matches!(
+                model_config.provider,
+                ModelProvider::Anthropic | ModelProvider::Google | ModelProvider::Cohere
+            );
+        if stream {
+            request_body["stream"] = serde_json::json!(true);
+            if model_config.provider == ModelProvider::OpenAI {
+                request_body["stream_options"] = serde_json::json!({ "include_usage": true });
+            }
+        }
+
         // Determine endpoint based on provider
         let endpoint = match model_config.provider {
             // Cloud Providers
@@ -305,10 +514,7 @@ impl Executor {
             ModelProvider::Azure => model_config.endpoint.clone().unwrap_or_default(),
 
             // Local Providers (OpenAI-compatible)
-            ModelProvider::Ollama => model_config
-                .endpoint
-                .clone()
-                .unwrap_or_else(|| "http://localhost:11434/api/chat".to_string()),
+            ModelProvider::Ollama => ollama_chat_url(model_config.endpoint.as_deref()),
             ModelProvider::LMStudio => model_config
                 .endpoint
                 .clone()
@@ -394,12 +600,42 @@ impl Executor {
         if !response.status().is_success() {
             let status = response.status();
             let body: String = response.text().await.unwrap_or_default();
+            if model_config.provider == ModelProvider::Ollama
+                && body.contains("does not support tools")
+            {
+                return Err(AgencyError::ModelError(format!(
+                    "{} cannot call tools; give the agent no tools or pick a model that can, such as llama3.1 or qwen2.5",
+                    model_config.model
+                )));
+            }
+            if is_retryable_status(status.as_u16()) {
+                return Err(AgencyError::ProviderUnavailable(format!(
+                    "{} answered {}: {}",
+                    model_config.provider, status, body
+                )));
+            }
             return Err(AgencyError::ModelError(format!(
                 "Model API error ({}): {}",
                 status, body
             )));
         }
 
+        if stream {
+            let mut response = response;
+            let mut reply = ReplyStream::default();
+            while let Some(chunk) = response
+                .chunk()
+                .await
+                // Part of the reply is out, so it is not retried elsewhere
+                .map_err(|e| AgencyError::ModelError(format!("Stream failed: {}", e)))?
+            {
+                for delta in reply.push(&chunk) {
+                    ctx.emit(Self::delta_event(agent, ctx, delta)).await;
+                }
+            }
+            return Ok(reply.finish());
+        }
+
         let response_body: serde_json::Value = response
             .json()
             .await
@@ -409,6 +645,12 @@ impl Executor {
         let (content, tool_calls, usage) =
             Self::parse_model_response(&response_body, &model_config.provider)?;
 
+        // Providers that cannot stream send their whole reply as one delta
+        if ctx.stream && !content.is_empty() {
+            let delta = serde_json::json!({ "content": content });
+            ctx.emit(Self::delta_event(agent, ctx, delta)).await;
+        }
+
         Ok(ModelResponse {
             content,
             tool_calls,
@@ -416,13 +658,21 @@ impl Executor {
         })
     }
 
+    fn delta_event(agent: &Agent, ctx: &ExecutionContext, data: serde_json::Value) -> AgencyEvent {
+        AgencyEvent {
+            event_type: EventType::MessageDelta,
+            agent_name: agent.name().to_string(),
+            data,
+            timestamp: Utc::now(),
+            session_id: Some(ctx.session_id.clone()),
+        }
+    }
+
     /// Parse model response based on provider format
     fn parse_model_response(
         response: &serde_json::Value,
         provider: &crate::agency::models::ModelProvider,
     ) -> AgencyResult<(String, Vec<ToolCall>, TokenUsage)> {
-        use crate::agency::models::ModelProvider;
-
         match provider {
             ModelProvider::Anthropic => {
                 // Anthropic format
@@ -480,6 +730,17 @@ impl Executor {
                 }
                 Ok((content, tool_calls, usage))
             }
+            ModelProvider::Ollama if response.get("message").is_some() => {
+                // Ollama's own chat format
+                let message = &response["message"];
+                let content = message["content"].as_str().unwrap_or("").to_string();
+                let tool_calls = Self::parse_tool_calls(&message["tool_calls"]);
+                let usage = TokenUsage::new(
+                    response["prompt_eval_count"].as_u64().unwrap_or(0) as u32,
+                    response["eval_count"].as_u64().unwrap_or(0) as u32,
+                );
+                Ok((content, tool_calls, usage))
+            }
             _ => {
                 // OpenAI-compatible format (OpenAI, Ollama, Azure, OpenAICompatible, Custom)
                 let choice = &response["choices"][0];
@@ -488,20 +749,7 @@ impl Executor {
                     .unwrap_or("")
                     .to_string();
 
-                let mut tool_calls = vec![];
-                if let Some(calls) = choice["message"]["tool_calls"].as_array() {
-                    for call in calls {
-                        tool_calls.push(ToolCall {
-                            id: call["id"].as_str().unwrap_or("").to_string(),
-                            name: call["function"]["name"].as_str().unwrap_or("").to_string(),
-                            arguments: serde_json::from_str(
-                                call["function"]["arguments"].as_str().unwrap_or("{}"),
-                            )
-                            .unwrap_or_default(),
-                            timestamp: Utc::now(),
-                        });
-                    }
-                }
+                let tool_calls = Self::parse_tool_calls(&choice["message"]["tool_calls"]);
 
                 let usage = TokenUsage::new(
                     response["usage"]["prompt_tokens"].as_u64().unwrap_or(0) as u32,
@@ -513,13 +761,36 @@ impl Executor {
         }
     }
 
-    /// Execute a tool
-    async fn execute_tool(&self, tool_call: &ToolCall) -> ToolResult {
+    /// Tool calls in OpenAI's shape. Local servers may leave out call IDs,
+    /// which are made up so results can answer them, and may send the
+    /// arguments as an object rather than a JSON string.
+    fn parse_tool_calls(calls: &serde_json::Value) -> Vec<ToolCall> {
+        calls
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|call| ToolCall {
+                id: call["id"]
+                    .as_str()
+                    .filter(|id| !id.is_empty())
+                    .map_or_else(|| uuid::Uuid::new_v4().to_string(), String::from),
+                name: call["function"]["name"].as_str().unwrap_or("").to_string(),
+                arguments: tool_arguments(&call["function"]["arguments"]),
+                timestamp: Utc::now(),
+            })
+            .collect()
+    }
+
+    /// Execute a tool within the calling agent's sandbox
+    async fn execute_tool(&self, tool_call: &ToolCall, sandbox: &SandboxConfig) -> ToolResult {
         let start = std::time::Instant::now();
 
         // Check if tool exists
         if let Some(executor) = self.tool_registry.get_executor(&tool_call.name) {
-            match executor.execute(tool_call.arguments.clone()).await {
+            match executor
+                .execute_sandboxed(tool_call.arguments.clone(), sandbox)
+                .await
+            {
                 Ok(result) => result,
                 Err(e) => ToolResult {
                     call_id: tool_call.id.clone(),
@@ -544,6 +815,118 @@ impl Executor {
     }
 }
 
+/// Arguments of a tool call, whether sent as an object or a JSON string
+fn tool_arguments(arguments: &serde_json::Value) -> serde_json::Value {
+    match arguments {
+        serde_json::Value::String(text) if !text.trim().is_empty() => {
+            serde_json::from_str(text).unwrap_or_default()
+        }
+        serde_json::Value::Object(_) => arguments.clone(),
+        _ => serde_json::json!({}),
+    }
+}
+
+/// Whether a model is reached through Ollama's own chat API rather than
+/// its OpenAI-compatible `/v1` one
+fn is_native_ollama(model_config: &ModelConfig) -> bool {
+    model_config.provider == ModelProvider::Ollama
+        && !model_config
+            .endpoint
+            .as_deref()
+            .is_some_and(|endpoint| endpoint.contains("/v1/"))
+}
+
+/// Ollama's chat endpoint: the model's endpoint, or else `OLLAMA_HOST`,
+/// with `/api/chat` added to a bare host
+fn ollama_chat_url(endpoint: Option<&str>) -> String {
+    let mut url = endpoint
+        .map(String::from)
+        .or_else(|| std::env::var("OLLAMA_HOST").ok().filter(|h| !h.is_empty()))
+        .unwrap_or_else(|| "http://localhost:11434".to_string());
+    // OLLAMA_HOST is often a bare host:port
+    if !url.contains("://") {
+        url = format!("http://{}", url);
+    }
+    let path = url.splitn(4, '/').nth(3).unwrap_or("");
+    if path.trim_matches('/').is_empty() {
+        url = format!("{}/api/chat", url.trim_end_matches('/'));
+    }
+    url
+}
+
+/// An OpenAI-style request body rewritten for Ollama's `/api/chat`
+fn ollama_request(mut body: serde_json::Value) -> serde_json::Value {
+    let mut options = serde_json::Map::new();
+    if let Some(request) = body.as_object_mut() {
+        if let Some(temperature) = request.remove("temperature") {
+            options.insert("temperature".to_string(), temperature);
+        }
+        if let Some(max_tokens) = request.remove("max_tokens") {
+            options.insert("num_predict".to_string(), max_tokens);
+        }
+    }
+    body["options"] = serde_json::Value::Object(options);
+    body["stream"] = serde_json::json!(false);
+
+    // Tool results name the tool that produced them
+    let mut names = HashMap::new();
+    for message in body["messages"].as_array_mut().into_iter().flatten() {
+        for call in message["tool_calls"].as_array_mut().into_iter().flatten() {
+            if let (Some(id), Some(name)) = (call["id"].as_str(), call["function"]["name"].as_str())
+            {
+                names.insert(id.to_string(), name.to_string());
+            }
+            let arguments = &mut call["function"]["arguments"];
+            *arguments = tool_arguments(arguments);
+        }
+        let name = message["tool_call_id"]
+            .as_str()
+            .and_then(|id| names.get(id))
+            .cloned();
+        if let Some(name) = name {
+            message["tool_name"] = serde_json::json!(name);
+        }
+
+        // Images go alongside the text as bare base64
+        if let Some(parts) = message["content"].as_array().cloned() {
+            let text = parts
+                .iter()
+                .filter_map(|part| part["text"].as_str())
+                .collect::<Vec<_>>()
+                .join("\n");
+            let images: Vec<_> = parts
+                .iter()
+                .filter_map(|part| part["image_url"]["url"].as_str())
+                .filter_map(|url| url.split_once(";base64,").map(|(_, data)| data))
+                .collect();
+            message["content"] = serde_json::json!(text);
+            message["images"] = serde_json::json!(images);
+        }
+    }
+    body
+}
+
+/// Image content parts rewritten as Anthropic's base64 image blocks
+fn anthropic_images(messages: &mut [serde_json::Value]) {
+    for message in messages {
+        for part in message["content"].as_array_mut().into_iter().flatten() {
+            let Some(url) = part["image_url"]["url"].as_str() else {
+                continue;
+            };
+            let source = match url
+                .strip_prefix("data:")
+                .and_then(|rest| rest.split_once(";base64,"))
+            {
+                Some((media_type, data)) => {
+                    serde_json::json!({ "type": "base64", "media_type": media_type, "data": data })
+                }
+                None => serde_json::json!({ "type": "url", "url": url }),
+            };
+            *part = serde_json::json!({ "type": "image", "source": source });
+        }
+    }
+}
+
 /// Response from model API
 struct ModelResponse {
     content: String,
@@ -551,6 +934,94 @@ struct ModelResponse {
     usage: TokenUsage,
 }
 
+/// Assembles a reply from the `data:` lines of an OpenAI-compatible stream
+#[derive(Default)]
+struct ReplyStream {
+    pending: Vec<u8>,
+    content: String,
+    /// Calls by index: id, name, and arguments so far
+    tool_calls: Vec<(String, String, String)>,
+    usage: TokenUsage,
+}
+
+impl ReplyStream {
+    /// Take in the next chunk and return the pieces it completes, as
+    /// `{"content"}` for text and `{"tool_call": {"index", "name",
+    /// "arguments"}}` for tool calls, which give the name once and then the
+    /// arguments a fragment at a time
+    fn push(&mut self, chunk: &[u8]) -> Vec<serde_json::Value> {
+        self.pending.extend_from_slice(chunk);
+        let mut deltas = Vec::new();
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:") else {
+                continue;
+            };
+            // The stream ends with `data: [DONE]`, which is not JSON
+            let Ok(event) = serde_json::from_str::<serde_json::Value>(data.trim()) else {
+                continue;
+            };
+
+            if let Some(usage) = event.get("usage").filter(|u| u.is_object()) {
+                self.usage = TokenUsage::new(
+                    usage["prompt_tokens"].as_u64().unwrap_or(0) as u32,
+                    usage["completion_tokens"].as_u64().unwrap_or(0) as u32,
+                );
+            }
+            let delta = &event["choices"][0]["delta"];
+            if let Some(text) = delta["content"].as_str().filter(|t| !t.is_empty()) {
+                self.content.push_str(text);
+                deltas.push(serde_json::json!({ "content": text }));
+            }
+            for call in delta["tool_calls"].as_array().into_iter().flatten() {
+                let index = call["index"].as_u64().unwrap_or(0) as usize;
+                if self.tool_calls.len() <= index {
+                    self.tool_calls.resize(index + 1, Default::default());
+                }
+                let entry = &mut self.tool_calls[index];
+                if let Some(id) = call["id"].as_str() {
+                    entry.0 = id.to_string();
+                }
+                let mut piece = serde_json::json!({ "index": index });
+                if let Some(name) = call["function"]["name"].as_str() {
+                    entry.1.push_str(name);
+                    piece["name"] = serde_json::json!(name);
+                }
+                if let Some(arguments) = call["function"]["arguments"].as_str() {
+                    entry.2.push_str(arguments);
+                    piece["arguments"] = serde_json::json!(arguments);
+                }
+                deltas.push(serde_json::json!({ "tool_call": piece }));
+            }
+        }
+        deltas
+    }
+
+    fn finish(self) -> ModelResponse {
+        let tool_calls = self
+            .tool_calls
+            .into_iter()
+            .filter(|(_, name, _)| !name.is_empty())
+            .map(|(id, name, arguments)| ToolCall {
+                id,
+                name,
+                arguments: if arguments.trim().is_empty() {
+                    serde_json::json!({})
+                } else {
+                    serde_json::from_str(&arguments).unwrap_or_default()
+                },
+                timestamp: Utc::now(),
+            })
+            .collect();
+        ModelResponse {
+            content: self.content,
+            tool_calls,
+            usage: self.usage,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -580,4 +1051,153 @@ mod tests {
         assert!(!result.response.is_empty());
         assert!(!result.messages.is_empty());
     }
+
+    #[test]
+    fn test_reply_stream() {
+        use serde_json::json;
+
+        let line = |event: serde_json::Value| format!("data: {}\n\n", event);
+        let call =
+            |call: serde_json::Value| json!({ "choices": [{ "delta": { "tool_calls": [call] } }] });
+        let text = line(json!({ "choices": [{ "delta": { "content": "Hel" } }] }))
+            + &line(json!({ "choices": [{ "delta": { "content": "lo" } }] }))
+            + &line(call(json!({
+                "index": 0, "id": "c1",
+                "function": { "name": "search", "arguments": "{\"q\":" }
+            })))
+            + &line(call(
+                json!({ "index": 0, "function": { "arguments": "\"rust\"}" } }),
+            ))
+            + &line(
+                json!({ "choices": [], "usage": { "prompt_tokens": 7, "completion_tokens": 3 } }),
+            )
+            + "data: [DONE]\n\n";
+
+        // Chunks may end anywhere, even inside a line
+        let (first, rest) = text.as_bytes().split_at(30);
+        let mut reply = ReplyStream::default();
+        let mut deltas = reply.push(first);
+        deltas.extend(reply.push(rest));
+
+        assert_eq!(
+            deltas,
+            [
+                json!({ "content": "Hel" }),
+                json!({ "content": "lo" }),
+                json!({ "tool_call": { "index": 0, "name": "search", "arguments": "{\"q\":" } }),
+                json!({ "tool_call": { "index": 0, "arguments": "\"rust\"}" } }),
+            ]
+        );
+        let response = reply.finish();
+        assert_eq!(response.content, "Hello");
+        assert_eq!(response.tool_calls[0].id, "c1");
+        assert_eq!(response.tool_calls[0].arguments, json!({ "q": "rust" }));
+        assert_eq!(response.usage.completion_tokens, 3);
+    }
+
+    #[test]
+    fn test_ollama_chat() {
+        use serde_json::json;
+
+        let body = ollama_request(json!({
+            "model": "qwen2.5",
+            "temperature": 0.2,
+            "max_tokens": 100,
+            "messages": [
+                { "role": "user", "content": "List src" },
+                { "role": "assistant", "content": "", "tool_calls": [{
+                    "id": "c1", "type": "function",
+                    "function": { "name": "list_directory", "arguments": "{\"path\":\"src\"}" }
+                }] },
+                { "role": "tool", "content": "main.rs", "tool_call_id": "c1" }
+            ]
+        }));
+        assert_eq!(
+            body["options"],
+            json!({ "temperature": 0.2, "num_predict": 100 })
+        );
+        assert_eq!(body["stream"], json!(false));
+        assert!(body.get("temperature").is_none());
+        let messages = &body["messages"];
+        assert_eq!(
+            messages[1]["tool_calls"][0]["function"]["arguments"],
+            json!({ "path": "src" })
+        );
+        assert_eq!(messages[2]["tool_name"], json!("list_directory"));
+
+        // Ollama sends arguments as objects and leaves out call IDs
+        let response = json!({
+            "message": { "role": "assistant", "content": "", "tool_calls": [{
+                "function": { "name": "read_file", "arguments": { "path": "src/main.rs" } }
+            }] },
+            "prompt_eval_count": 42,
+            "eval_count": 7,
+            "done": true
+        });
+        let (_, calls, usage) =
+            Executor::parse_model_response(&response, &ModelProvider::Ollama).unwrap();
+        assert_eq!(calls[0].name, "read_file");
+        assert_eq!(calls[0].arguments, json!({ "path": "src/main.rs" }));
+        assert!(!calls[0].id.is_empty());
+        assert_eq!((usage.prompt_tokens, usage.completion_tokens), (42, 7));
+
+        assert_eq!(
+            ollama_chat_url(Some("gpu-box:11434/")),
+            "http://gpu-box:11434/api/chat"
+        );
+        let openai = ModelConfig {
+            provider: ModelProvider::Ollama,
+            endpoint: Some("http://localhost:11434/v1/chat/completions".into()),
+            ..Default::default()
+        };
+        assert!(!is_native_ollama(&openai));
+    }
+
+    #[test]
+    fn test_image_messages() {
+        use crate::agency::modality::{ImageContent, ImageData};
+        use serde_json::json;
+
+        let mut session = Session::new("test", None);
+        session.add_message(AgencyMessage {
+            id: generate_message_id(),
+            role: MessageRole::User,
+            content: "What is wrong here?".to_string(),
+            attachments: vec![ContentPart::Image(ImageContent {
+                data: ImageData::Base64 {
+                    data: "iVBORw0K".to_string(),
+                    media_type: "image/png".to_string(),
+                },
+                format: None,
+                alt_text: Some("screenshot.png".to_string()),
+                regions: Vec::new(),
+            })],
+            tool_calls: vec![],
+            tool_result: None,
+            timestamp: Utc::now(),
+            tokens: None,
+            agent_name: None,
+            metadata: HashMap::new(),
+        });
+        let mut messages = session.to_api_messages();
+        assert_eq!(
+            messages[0]["content"],
+            json!([
+                { "type": "text", "text": "What is wrong here?" },
+                { "type": "image_url", "image_url": { "url": "data:image/png;base64,iVBORw0K" } }
+            ])
+        );
+
+        let body = ollama_request(json!({ "model": "llava", "messages": messages.clone() }));
+        assert_eq!(body["messages"][0]["content"], json!("What is wrong here?"));
+        assert_eq!(body["messages"][0]["images"], json!(["iVBORw0K"]));
+
+        anthropic_images(&mut messages);
+        assert_eq!(
+            messages[0]["content"][1],
+            json!({ "type": "image", "source": {
+                "type": "base64", "media_type": "image/png", "data": "iVBORw0K"
+            } })
+        );
+    }
 }
@@ -0,0 +1,364 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! MCP Client
+//!
+//! Mount tools from external MCP servers (filesystem, GitHub, databases) so
+//! agents can call them alongside the builtins. Servers are declared under
+//! `[agency.mcp_servers]` in the config file and started over stdio when an
+//! agent that names them runs. Each mounted tool is called
+//! `{server}__{tool}` so tools from different servers never collide.
+
+use crate::agency::error::{AgencyError, AgencyResult};
+use crate::agency::models::ToolResult;
+use crate::agency::tools::{Tool, ToolCategory, ToolExecutor, ToolRegistry};
+use crate::config::{self, McpServerConfig};
+use crate::mcp::types::Tool as RemoteTool;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+
+/// MCP protocol version this client speaks
+const PROTOCOL_VERSION: &str = "2025-03-26";
+
+/// How long to wait for a server to answer one request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Longest tool name model APIs accept
+const MAX_TOOL_NAME: usize = 64;
+
+/// A running MCP server, spoken to over its stdin and stdout
+pub struct McpClient {
+    server: String,
+    io: Mutex<ServerIo>,
+    // Killed when the last tool using it is dropped
+    _child: Child,
+}
+
+struct ServerIo {
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    next_id: u64,
+}
+
+impl McpClient {
+    /// Start the server `name` and complete the MCP handshake
+    pub async fn connect(name: &str, server: &McpServerConfig) -> AgencyResult<Self> {
+        let mut command = Command::new(&server.command);
+        command
+            .args(&server.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true);
+        for (key, value) in &server.env {
+            if let Some(value) = config::expand_env(value) {
+                command.env(key, value);
+            }
+        }
+
+        let mut child = command.spawn().map_err(|e| {
+            AgencyError::ExecutionFailed(format!(
+                "Failed to start MCP server '{}' ({}): {}",
+                name, server.command, e
+            ))
+        })?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+
+        let client = Self {
+            server: name.to_string(),
+            io: Mutex::new(ServerIo {
+                stdin,
+                stdout: BufReader::new(stdout).lines(),
+                next_id: 1,
+            }),
+            _child: child,
+        };
+
+        client
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": {
+                        "name": "chasm",
+                        "version": env!("CARGO_PKG_VERSION")
+                    }
+                }),
+            )
+            .await?;
+        client.notify("notifications/initialized").await?;
+
+        Ok(client)
+    }
+
+    /// Name of the server in the config file
+    pub fn server(&self) -> &str {
+        &self.server
+    }
+
+    /// Every tool the server offers
+    pub async fn list_tools(&self) -> AgencyResult<Vec<RemoteTool>> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let mut result = self.request("tools/list", params).await?;
+            let page: Vec<RemoteTool> = serde_json::from_value(result["tools"].take())?;
+            tools.extend(page);
+
+            match result["nextCursor"].as_str() {
+                Some(next) if !next.is_empty() => cursor = Some(next.to_string()),
+                _ => return Ok(tools),
+            }
+        }
+    }
+
+    /// Call the tool `name`, returning its text output and whether it failed
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> AgencyResult<(String, bool)> {
+        let arguments = if arguments.is_object() {
+            arguments
+        } else {
+            json!({})
+        };
+        let result = self
+            .request(
+                "tools/call",
+                json!({ "name": name, "arguments": arguments }),
+            )
+            .await?;
+
+        let text = result["content"]
+            .as_array()
+            .map(|content| {
+                content
+                    .iter()
+                    .map(|item| match item["type"].as_str() {
+                        Some("text") => item["text"].as_str().unwrap_or_default().to_string(),
+                        Some("resource") => item["resource"]["text"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_string(),
+                        Some(kind) => format!("[{} content]", kind),
+                        None => String::new(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .unwrap_or_default();
+        let is_error = result["isError"].as_bool().unwrap_or(false);
+
+        Ok((text, is_error))
+    }
+
+    /// Send a request and wait for its response. Notifications that arrive
+    /// first are skipped; requests from the server are answered.
+    async fn request(&self, method: &str, params: Value) -> AgencyResult<Value> {
+        let mut io = self.io.lock().await;
+        let id = io.next_id;
+        io.next_id += 1;
+
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        io.send(&message).await.map_err(|e| self.error(e))?;
+
+        loop {
+            let line = tokio::time::timeout(REQUEST_TIMEOUT, io.stdout.next_line())
+                .await
+                .map_err(|_| AgencyError::Timeout(REQUEST_TIMEOUT.as_secs()))?
+                .map_err(|e| self.error(e))?
+                .ok_or_else(|| self.error("server closed its output"))?;
+            let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+
+            if let Some(request) = message["method"].as_str() {
+                if !message["id"].is_null() {
+                    let reply = if request == "ping" {
+                        json!({ "jsonrpc": "2.0", "id": message["id"], "result": {} })
+                    } else {
+                        json!({
+                            "jsonrpc": "2.0",
+                            "id": message["id"],
+                            "error": { "code": -32601, "message": "Method not found" }
+                        })
+                    };
+                    io.send(&reply).await.map_err(|e| self.error(e))?;
+                }
+                continue;
+            }
+
+            if message["id"].as_u64() != Some(id) {
+                continue;
+            }
+            if let Some(error) = message.get("error") {
+                let reason = error["message"].as_str().unwrap_or("unknown error");
+                return Err(self.error(format!("{} failed: {}", method, reason)));
+            }
+            return Ok(message["result"].clone());
+        }
+    }
+
+    async fn notify(&self, method: &str) -> AgencyResult<()> {
+        let mut io = self.io.lock().await;
+        io.send(&json!({ "jsonrpc": "2.0", "method": method }))
+            .await
+            .map_err(|e| self.error(e))
+    }
+
+    fn error(&self, reason: impl std::fmt::Display) -> AgencyError {
+        AgencyError::ExecutionFailed(format!("MCP server '{}': {}", self.server, reason))
+    }
+}
+
+impl ServerIo {
+    async fn send(&mut self, message: &Value) -> std::io::Result<()> {
+        let mut line = message.to_string();
+        line.push('\n');
+        self.stdin.write_all(line.as_bytes()).await?;
+        self.stdin.flush().await
+    }
+}
+
+/// A tool on an MCP server, mounted into an agent's registry
+pub struct McpTool {
+    client: Arc<McpClient>,
+    remote_name: String,
+    tool: Tool,
+}
+
+impl McpTool {
+    /// Wrap the server's `remote` tool
+    pub fn new(client: Arc<McpClient>, remote: RemoteTool) -> Self {
+        let mut tool = Tool::new(
+            tool_name(client.server(), &remote.name),
+            remote.description.unwrap_or_default(),
+        );
+        tool.category = ToolCategory::Custom;
+        tool.metadata
+            .insert("input_schema".to_string(), remote.input_schema);
+        tool.metadata
+            .insert("mcp_server".to_string(), json!(client.server()));
+
+        Self {
+            client,
+            remote_name: remote.name,
+            tool,
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for McpTool {
+    fn definition(&self) -> &Tool {
+        &self.tool
+    }
+
+    async fn execute(&self, args: Value) -> AgencyResult<ToolResult> {
+        let start = Instant::now();
+        let (content, is_error) = self.client.call_tool(&self.remote_name, args).await?;
+
+        Ok(ToolResult {
+            call_id: String::new(),
+            name: self.tool.name.clone(),
+            success: !is_error,
+            content,
+            duration_ms: start.elapsed().as_millis() as u64,
+            data: None,
+        })
+    }
+}
+
+/// Start the configured servers `names` and register their tools, returning
+/// the definitions to give the agent
+pub async fn mount_servers(
+    names: &[String],
+    registry: &mut ToolRegistry,
+) -> AgencyResult<Vec<Tool>> {
+    let servers = &config::current().agency.mcp_servers;
+    let mut mounted = Vec::new();
+
+    for name in names {
+        let server = servers.get(name).ok_or_else(|| {
+            let known: Vec<&str> = servers.keys().map(String::as_str).collect();
+            AgencyError::ConfigError(format!(
+                "Unknown MCP server '{}'; [agency.mcp_servers] has {}",
+                name,
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            ))
+        })?;
+
+        let client = Arc::new(McpClient::connect(name, server).await?);
+        for remote in client.list_tools().await? {
+            if !is_allowed(server.tools.as_deref(), &remote.name) {
+                continue;
+            }
+            let tool = McpTool::new(Arc::clone(&client), remote);
+            mounted.push(tool.definition().clone());
+            registry.register_with_executor(tool);
+        }
+    }
+
+    Ok(mounted)
+}
+
+/// Whether `tool` matches one of the `patterns`; no patterns allow every tool
+fn is_allowed(patterns: Option<&[String]>, tool: &str) -> bool {
+    let Some(patterns) = patterns else {
+        return true;
+    };
+    patterns
+        .iter()
+        .any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => tool.starts_with(prefix),
+            None => tool == pattern,
+        })
+}
+
+/// `{server}__{tool}`, limited to the characters and length model APIs allow
+fn tool_name(server: &str, tool: &str) -> String {
+    format!("{}__{}", server, tool)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(MAX_TOOL_NAME)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_names_and_filters() {
+        assert_eq!(
+            tool_name("github", "search_issues"),
+            "github__search_issues"
+        );
+        assert_eq!(tool_name("fs", "read.file"), "fs__read_file");
+        assert_eq!(tool_name("db", &"x".repeat(100)).len(), MAX_TOOL_NAME);
+
+        let patterns = vec!["read_*".to_string(), "list_directory".to_string()];
+        assert!(is_allowed(Some(&patterns), "read_file"));
+        assert!(is_allowed(Some(&patterns), "list_directory"));
+        assert!(!is_allowed(Some(&patterns), "write_file"));
+        assert!(is_allowed(None, "write_file"));
+    }
+}
@@ -10,6 +10,9 @@
 //! - **Memory Types**: Short-term, long-term, episodic, and semantic memory
 //! - **Knowledge Base**: Structured document storage with chunking
 //! - **Context Window**: Smart context management for LLM prompts
+//! - **Persistence**: Memories, embeddings, and knowledge base documents kept in
+//!   SQLite, normally the harvest database ([`MemoryConfig::in_harvest_db`]) so
+//!   every agent run and the API server share them
 
 #![allow(dead_code)]
 //! - **Caching**: Frequently accessed information caching
@@ -309,8 +312,16 @@ pub struct VectorStore {
     config: VectorStoreConfig,
     entries: Vec<MemoryEntry>,
     db: Option<rusqlite::Connection>,
+    /// Which entries of `memory_entries` belong to this store
+    store: String,
 }
 
+/// Store name of agent memories in `memory_entries`
+const MEMORY_STORE: &str = "memory";
+
+/// Store name of knowledge base chunks in `memory_entries`
+const KNOWLEDGE_STORE: &str = "knowledge";
+
 impl VectorStore {
     /// Create a new in-memory vector store
     pub fn new(config: VectorStoreConfig) -> Self {
@@ -318,6 +329,7 @@ impl VectorStore {
             config,
             entries: Vec::new(),
             db: None,
+            store: MEMORY_STORE.to_string(),
         }
     }
 
@@ -326,7 +338,13 @@ impl VectorStore {
         config: VectorStoreConfig,
         db_path: impl AsRef<Path>,
     ) -> Result<Self, MemoryError> {
-        let db = rusqlite::Connection::open(db_path.as_ref())
+        Self::open(config, db_path.as_ref(), MEMORY_STORE)
+    }
+
+    /// Open the entries of `store` in the database at `db_path`. Several
+    /// stores share one `memory_entries` table.
+    fn open(config: VectorStoreConfig, db_path: &Path, store: &str) -> Result<Self, MemoryError> {
+        let db = rusqlite::Connection::open(db_path)
             .map_err(|e| MemoryError::Database(e.to_string()))?;
 
         // Initialize schema
@@ -346,22 +364,39 @@ impl VectorStore {
                 agent_id TEXT,
                 session_id TEXT,
                 metadata TEXT,
-                tags TEXT
+                tags TEXT,
+                store TEXT NOT NULL DEFAULT 'memory'
             );
-            
-            CREATE INDEX IF NOT EXISTS idx_memory_type ON memory_entries(memory_type);
-            CREATE INDEX IF NOT EXISTS idx_agent_id ON memory_entries(agent_id);
-            CREATE INDEX IF NOT EXISTS idx_session_id ON memory_entries(session_id);
-            CREATE INDEX IF NOT EXISTS idx_created_at ON memory_entries(created_at);
-            CREATE INDEX IF NOT EXISTS idx_importance ON memory_entries(importance DESC);
         "#,
         )
+        .and_then(|_| {
+            crate::commands::add_column_if_missing(
+                &db,
+                "memory_entries",
+                "store",
+                "TEXT NOT NULL DEFAULT 'memory'",
+            )
+        })
+        .and_then(|_| {
+            db.execute_batch(
+                r#"
+                CREATE INDEX IF NOT EXISTS idx_memory_entries_store ON memory_entries(store);
+                CREATE INDEX IF NOT EXISTS idx_memory_entries_type ON memory_entries(memory_type);
+                CREATE INDEX IF NOT EXISTS idx_memory_entries_agent ON memory_entries(agent_id);
+                CREATE INDEX IF NOT EXISTS idx_memory_entries_session ON memory_entries(session_id);
+                CREATE INDEX IF NOT EXISTS idx_memory_entries_created ON memory_entries(created_at);
+                CREATE INDEX IF NOT EXISTS idx_memory_entries_importance
+                    ON memory_entries(importance DESC);
+            "#,
+            )
+        })
         .map_err(|e| MemoryError::Database(e.to_string()))?;
 
         let mut store = Self {
             config,
             entries: Vec::new(),
             db: Some(db),
+            store: store.to_string(),
         };
 
         store.load_from_db()?;
@@ -377,12 +412,13 @@ impl VectorStore {
                         access_count, last_accessed, created_at, expires_at, 
                         agent_id, session_id, metadata, tags 
                  FROM memory_entries 
+                 WHERE store = ?1
                  ORDER BY importance DESC, created_at DESC",
                 )
                 .map_err(|e| MemoryError::Database(e.to_string()))?;
 
             let entries = stmt
-                .query_map([], |row| {
+                .query_map([&self.store], |row| {
                     let embedding_blob: Option<Vec<u8>> = row.get(2)?;
                     let embedding = embedding_blob.map(|blob| {
                         blob.chunks(4)
@@ -445,8 +481,8 @@ impl VectorStore {
                 "INSERT OR REPLACE INTO memory_entries 
                  (id, content, embedding, memory_type, source, importance, 
                   access_count, last_accessed, created_at, expires_at, 
-                  agent_id, session_id, metadata, tags)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                  agent_id, session_id, metadata, tags, store)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
                 rusqlite::params![
                     entry.id,
                     entry.content,
@@ -462,6 +498,7 @@ impl VectorStore {
                     entry.session_id,
                     serde_json::to_string(&entry.metadata).ok(),
                     serde_json::to_string(&entry.tags).ok(),
+                    self.store,
                 ],
             )
             .map_err(|e| MemoryError::Database(e.to_string()))?;
@@ -479,11 +516,28 @@ impl VectorStore {
 
     /// Search for similar entries
     pub fn search(&mut self, query_embedding: &Embedding, limit: usize) -> Vec<SearchResult> {
+        self.search_matching(query_embedding, limit, |_| true)
+    }
+
+    /// Search for similar entries among those `keep` accepts. Entries
+    /// embedded by a model of another dimension are skipped.
+    pub fn search_matching(
+        &mut self,
+        query_embedding: &Embedding,
+        limit: usize,
+        keep: impl Fn(&MemoryEntry) -> bool,
+    ) -> Vec<SearchResult> {
         let mut results: Vec<(usize, f32)> = self
             .entries
             .iter()
             .enumerate()
-            .filter(|(_, e)| !e.is_expired() && e.embedding.is_some())
+            .filter(|(_, e)| {
+                !e.is_expired()
+                    && e.embedding
+                        .as_ref()
+                        .is_some_and(|v| v.len() == query_embedding.len())
+                    && keep(e)
+            })
             .map(|(i, e)| {
                 let score = self
                     .config
@@ -497,7 +551,7 @@ impl VectorStore {
         results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
 
         // Take top results and update access counts
-        results
+        let results: Vec<SearchResult> = results
             .into_iter()
             .take(limit)
             .enumerate()
@@ -511,7 +565,22 @@ impl VectorStore {
                     rank,
                 }
             })
-            .collect()
+            .collect();
+
+        if let Some(ref db) = self.db {
+            for result in &results {
+                let _ = db.execute(
+                    "UPDATE memory_entries SET access_count = ?1, last_accessed = ?2 WHERE id = ?3",
+                    rusqlite::params![
+                        result.entry.access_count,
+                        result.entry.last_accessed.to_rfc3339(),
+                        result.entry.id,
+                    ],
+                );
+            }
+        }
+
+        results
     }
 
     /// Search by memory type
@@ -553,9 +622,27 @@ impl VectorStore {
         }
     }
 
+    /// Delete every entry tagged `tag`, returning how many there were
+    pub fn delete_tagged(&mut self, tag: &str) -> Result<usize, MemoryError> {
+        let ids: Vec<MemoryId> = self
+            .entries
+            .iter()
+            .filter(|e| e.tags.iter().any(|t| t == tag))
+            .map(|e| e.id.clone())
+            .collect();
+        self.forget(&ids)?;
+        Ok(ids.len())
+    }
+
     /// Prune old/low-importance entries
     fn prune(&mut self) -> Result<(), MemoryError> {
         // Remove expired entries
+        let mut removed: Vec<MemoryId> = self
+            .entries
+            .iter()
+            .filter(|e| e.is_expired())
+            .map(|e| e.id.clone())
+            .collect();
         self.entries.retain(|e| !e.is_expired());
 
         // If still over limit, remove lowest importance entries
@@ -565,9 +652,21 @@ impl VectorStore {
                     .partial_cmp(&a.importance)
                     .unwrap_or(std::cmp::Ordering::Equal)
             });
-            self.entries.truncate(self.config.max_entries);
+            removed.extend(self.entries.drain(self.config.max_entries..).map(|e| e.id));
         }
 
+        self.forget(&removed)
+    }
+
+    /// Remove entries from memory and the database
+    fn forget(&mut self, ids: &[MemoryId]) -> Result<(), MemoryError> {
+        self.entries.retain(|e| !ids.contains(&e.id));
+        if let Some(ref db) = self.db {
+            for id in ids {
+                db.execute("DELETE FROM memory_entries WHERE id = ?1", [id])
+                    .map_err(|e| MemoryError::Database(e.to_string()))?;
+            }
+        }
         Ok(())
     }
 
@@ -715,24 +814,63 @@ impl KnowledgeBase {
         }
     }
 
-    /// Create with persistence
+    /// Create with persistence. Documents go in `knowledge_documents` and
+    /// their chunks in `memory_entries`, apart from agent memories.
     pub fn with_persistence(
         vector_config: VectorStoreConfig,
         db_path: impl AsRef<Path>,
     ) -> Result<Self, MemoryError> {
-        Ok(Self {
-            vector_store: VectorStore::with_persistence(vector_config, db_path)?,
+        let vector_store = VectorStore::open(vector_config, db_path.as_ref(), KNOWLEDGE_STORE)?;
+        let mut kb = Self {
+            vector_store,
             documents: HashMap::new(),
             chunking_config: ChunkingConfig::default(),
-        })
+        };
+        kb.load_documents()?;
+        Ok(kb)
+    }
+
+    /// Load documents from the database
+    fn load_documents(&mut self) -> Result<(), MemoryError> {
+        let Some(ref db) = self.vector_store.db else {
+            return Ok(());
+        };
+        db.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS knowledge_documents (
+                id TEXT PRIMARY KEY,
+                document TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+        "#,
+        )
+        .map_err(|e| MemoryError::Database(e.to_string()))?;
+
+        let mut stmt = db
+            .prepare("SELECT document FROM knowledge_documents")
+            .map_err(|e| MemoryError::Database(e.to_string()))?;
+        let documents = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| MemoryError::Database(e.to_string()))?;
+        for json in documents.filter_map(|d| d.ok()) {
+            if let Ok(document) = serde_json::from_str::<Document>(&json) {
+                self.documents.insert(document.id.clone(), document);
+            }
+        }
+        Ok(())
     }
 
-    /// Add a document
+    /// Add a document, replacing any earlier version with the same ID.
+    /// Documents that come without chunks are chunked here; only chunks
+    /// with embeddings can be retrieved.
     pub fn add_document(&mut self, mut document: Document) -> Result<String, MemoryError> {
-        // Chunk the document
-        document.chunks = self.chunk_document(&document.content);
+        if document.chunks.is_empty() {
+            document.chunks = self.chunk_document(&document.content);
+        }
 
         let doc_id = document.id.clone();
+        self.vector_store
+            .delete_tagged(&format!("doc:{}", doc_id))?;
 
         // Add chunks to vector store
         for chunk in &document.chunks {
@@ -751,13 +889,28 @@ impl KnowledgeBase {
                 self.vector_store.add(entry)?;
             }
         }
+        // The vector store keeps the embeddings
+        for chunk in &mut document.chunks {
+            chunk.embedding = None;
+        }
+
+        if let Some(ref db) = self.vector_store.db {
+            let json = serde_json::to_string(&document)
+                .map_err(|e| MemoryError::InvalidInput(e.to_string()))?;
+            db.execute(
+                "INSERT OR REPLACE INTO knowledge_documents (id, document, updated_at)
+                 VALUES (?1, ?2, ?3)",
+                rusqlite::params![doc_id, json, document.updated_at.to_rfc3339()],
+            )
+            .map_err(|e| MemoryError::Database(e.to_string()))?;
+        }
 
         self.documents.insert(doc_id.clone(), document);
         Ok(doc_id)
     }
 
-    /// Chunk a document
-    fn chunk_document(&self, content: &str) -> Vec<DocumentChunk> {
+    /// Split text into chunks with this knowledge base's chunking strategy
+    pub fn chunk_document(&self, content: &str) -> Vec<DocumentChunk> {
         match self.chunking_config.strategy {
             ChunkingStrategy::Semantic => self.semantic_chunk(content),
             ChunkingStrategy::Paragraph => self.paragraph_chunk(content),
@@ -973,6 +1126,24 @@ impl KnowledgeBase {
         self.vector_store.search(query_embedding, limit)
     }
 
+    /// Retrieve relevant context for a query from the documents `keep` accepts
+    pub fn retrieve_from(
+        &mut self,
+        query_embedding: &Embedding,
+        limit: usize,
+        keep: impl Fn(&Document) -> bool,
+    ) -> Vec<SearchResult> {
+        let documents = &self.documents;
+        self.vector_store
+            .search_matching(query_embedding, limit, |entry| {
+                entry.tags.iter().any(|tag| {
+                    tag.strip_prefix("doc:")
+                        .and_then(|id| documents.get(id))
+                        .is_some_and(&keep)
+                })
+            })
+    }
+
     /// Get document by ID
     pub fn get_document(&self, id: &str) -> Option<&Document> {
         self.documents.get(id)
@@ -983,9 +1154,14 @@ impl KnowledgeBase {
         self.documents.values().collect()
     }
 
-    /// Delete document
-    pub fn delete_document(&mut self, id: &str) -> bool {
-        self.documents.remove(id).is_some()
+    /// Delete a document and its chunks
+    pub fn delete_document(&mut self, id: &str) -> Result<bool, MemoryError> {
+        self.vector_store.delete_tagged(&format!("doc:{}", id))?;
+        if let Some(ref db) = self.vector_store.db {
+            db.execute("DELETE FROM knowledge_documents WHERE id = ?1", [id])
+                .map_err(|e| MemoryError::Database(e.to_string()))?;
+        }
+        Ok(self.documents.remove(id).is_some())
     }
 }
 
@@ -1220,6 +1396,19 @@ pub struct MemoryConfig {
     pub summarize_threshold: usize,
 }
 
+impl MemoryConfig {
+    /// Keep memories and knowledge bases in the harvest database, where every
+    /// agent run and the API server share them
+    pub fn in_harvest_db() -> Result<Self, MemoryError> {
+        let path =
+            crate::commands::get_db_path(None).map_err(|e| MemoryError::Io(e.to_string()))?;
+        Ok(Self {
+            db_path: Some(path.to_string_lossy().into_owned()),
+            ..Self::default()
+        })
+    }
+}
+
 impl Default for MemoryConfig {
     fn default() -> Self {
         Self {
@@ -1252,8 +1441,7 @@ impl MemoryManager {
         };
 
         let knowledge_base = if let Some(ref path) = config.db_path {
-            let kb_path = format!("{}_kb", path);
-            KnowledgeBase::with_persistence(config.vector_store.clone(), kb_path)?
+            KnowledgeBase::with_persistence(config.vector_store.clone(), path)?
         } else {
             KnowledgeBase::new(config.vector_store.clone())
         };
@@ -1446,7 +1634,7 @@ fn truncate_to_tokens(text: &str, max_tokens: usize) -> String {
     if text.len() <= max_chars {
         text.to_string()
     } else {
-        format!("{}...", &text[..max_chars.min(text.len())])
+        format!("{}...", crate::text::prefix_chars(text, max_chars))
     }
 }
 
@@ -1555,6 +1743,51 @@ mod tests {
         assert!(store.get(&id).is_some());
     }
 
+    #[test]
+    fn test_persistence_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = MemoryConfig {
+            db_path: Some(dir.path().join("harvest.db").to_string_lossy().into_owned()),
+            ..MemoryConfig::default()
+        };
+
+        let mut manager = MemoryManager::new(config.clone()).unwrap();
+        manager
+            .remember_with_embedding(
+                "The user prefers tabs",
+                vec![1.0, 0.0, 0.0],
+                MemoryType::LongTerm,
+                MemorySource::UserInput,
+            )
+            .unwrap();
+        let now = Utc::now();
+        manager
+            .add_document(Document {
+                id: "style".to_string(),
+                title: "Style guide".to_string(),
+                content: "Use tabs.".to_string(),
+                doc_type: DocumentType::Markdown,
+                source: "STYLE.md".to_string(),
+                chunks: Vec::new(),
+                created_at: now,
+                updated_at: now,
+                metadata: HashMap::new(),
+            })
+            .unwrap();
+        drop(manager);
+
+        let mut manager = MemoryManager::new(config).unwrap();
+        let stats = manager.stats();
+        assert_eq!(stats.vector_store.total_entries, 1);
+        assert_eq!(stats.document_count, 1);
+        let recalled = manager.recall(&vec![1.0, 0.0, 0.0], 5);
+        assert_eq!(recalled[0].entry.content, "The user prefers tabs");
+        assert_eq!(recalled[0].entry.access_count, 1);
+
+        assert!(manager.knowledge_base.delete_document("style").unwrap());
+        assert_eq!(manager.stats().document_count, 0);
+    }
+
     #[test]
     fn test_context_window() {
         let mut ctx = ContextWindow::new(1000);
@@ -32,16 +32,22 @@
 //! ```
 
 pub mod agent;
+pub mod budget;
+pub mod definition;
 pub mod error;
 pub mod executor;
+pub mod mcp_client;
 pub mod memory;
 pub mod modality;
 pub mod models;
 pub mod orchestrator;
 pub mod proactive;
+pub mod rag;
 pub mod remote;
 pub mod runtime;
+pub mod sandbox;
 pub mod session;
+pub mod session_search;
 pub mod tools;
 
 // Autonomous agents
@@ -51,7 +57,7 @@ pub mod search_refinement;
 // Re-export main types
 pub use agent::{Agent, AgentBuilder, AgentConfig, AgentRole, AgentStatus};
 pub use error::AgencyError;
-pub use executor::{ExecutionContext, ExecutionResult, Executor};
+pub use executor::{ExecutionContext, ExecutionResult, Executor, SERVED_BY};
 pub use memory::{
     AgentCache, CacheEntry, ChunkingConfig, ChunkingStrategy, ContextSegment, ContextSegmentType,
     ContextWindow, Document, DocumentChunk, DocumentType, Embedding, EmbeddingModel,
@@ -66,7 +72,7 @@ pub use modality::{
     SensorType, SensorValues, VideoContent, VideoData, Waypoint,
 };
 pub use models::{AgencyEvent, AgencyMessage, EventType, ToolCall, ToolResult};
-pub use orchestrator::{OrchestrationType, Orchestrator, Pipeline, Swarm};
+pub use orchestrator::{OrchestrationType, Orchestrator, Pipeline, PipelineDefinition, Swarm};
 pub use proactive::{
     business_agent_config, household_agent_config, ActionRisk, ActionStatus, DetectedProblem,
     PermissionLevel, ProactiveAction, ProactiveAgentConfig, ProactiveMonitor, ProblemCategory,
@@ -77,7 +83,7 @@ pub use remote::{
     MonitorStats, NodeId, NodeStatus, RemoteAgentClient, RemoteEvent, RemoteMonitor,
     RemoteMonitorConfig, RemoteMonitorError, RemoteNode, RemoteTask, RemoteTaskBuilder,
     RemoteTaskId, RemoteTaskStatus, ResourceUsage, TaskArtifact, TaskLogEntry, TaskMetrics,
-    TaskPriority, TaskResult,
+    TaskPriority, TaskRequest, TaskResult,
 };
 pub use runtime::{Runtime, RuntimeConfig};
 pub use session::{Session, SessionManager, SessionState};
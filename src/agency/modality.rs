@@ -310,6 +310,27 @@ impl std::fmt::Display for ImageFormat {
     }
 }
 
+impl ImageFormat {
+    /// The format a file extension names
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_ascii_lowercase().as_str() {
+            "png" => Some(ImageFormat::Png),
+            "jpg" | "jpeg" => Some(ImageFormat::Jpeg),
+            "webp" => Some(ImageFormat::Webp),
+            "gif" => Some(ImageFormat::Gif),
+            "bmp" => Some(ImageFormat::Bmp),
+            "tif" | "tiff" => Some(ImageFormat::Tiff),
+            "heic" => Some(ImageFormat::Heic),
+            _ => None,
+        }
+    }
+
+    /// MIME type, e.g. `image/png`
+    pub fn media_type(&self) -> String {
+        format!("image/{}", self)
+    }
+}
+
 // =============================================================================
 // Multimodal Content Types
 // =============================================================================
@@ -330,6 +351,42 @@ pub struct ImageContent {
     pub regions: Vec<BoundingBoxRegion>,
 }
 
+impl ImageContent {
+    /// Read an image file, base64 encoding it and naming it after the file
+    pub fn from_file(path: &std::path::Path) -> std::io::Result<Self> {
+        use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+
+        let format = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(ImageFormat::from_extension)
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("{} does not have an image extension", path.display()),
+                )
+            })?;
+        let bytes = std::fs::read(path)?;
+        Ok(Self {
+            data: ImageData::Base64 {
+                data: BASE64.encode(bytes),
+                media_type: format.media_type(),
+            },
+            format: Some(format),
+            alt_text: path.file_name().map(|name| name.to_string_lossy().into_owned()),
+            regions: Vec::new(),
+        })
+    }
+
+    /// The image as a URL: its own, or a `data:` URL for inline data
+    pub fn url(&self) -> String {
+        match &self.data {
+            ImageData::Base64 { data, media_type } => format!("data:{};base64,{}", media_type, data),
+            ImageData::Url { url, .. } => url.clone(),
+        }
+    }
+}
+
 /// Image data - either base64 encoded or URL reference
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
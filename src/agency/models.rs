@@ -10,6 +10,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::modality::ContentPart;
+
 /// Message in an Agency conversation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgencyMessage {
@@ -19,6 +21,9 @@ pub struct AgencyMessage {
     pub role: MessageRole,
     /// Message content
     pub content: String,
+    /// Images and other parts sent along with the text
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<ContentPart>,
     /// Optional tool calls in this message
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub tool_calls: Vec<ToolCall>,
@@ -150,6 +155,8 @@ pub enum EventType {
     SwarmFailed,
     /// Agent handoff to another agent
     Handoff,
+    /// A model's provider failed and the next fallback model is tried
+    ProviderFallback,
     /// Error occurred
     Error,
 }
@@ -176,6 +183,7 @@ impl std::fmt::Display for EventType {
             EventType::SwarmCompleted => write!(f, "swarm_completed"),
             EventType::SwarmFailed => write!(f, "swarm_failed"),
             EventType::Handoff => write!(f, "handoff"),
+            EventType::ProviderFallback => write!(f, "provider_fallback"),
             EventType::Error => write!(f, "error"),
         }
     }
@@ -341,6 +349,38 @@ impl ModelProvider {
         )
     }
 
+    /// The provider a name such as `ollama` or `lmstudio` refers to, as
+    /// written by `Display`
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_lowercase().as_str() {
+            "google" => ModelProvider::Google,
+            "openai" => ModelProvider::OpenAI,
+            "anthropic" => ModelProvider::Anthropic,
+            "azure" => ModelProvider::Azure,
+            "groq" => ModelProvider::Groq,
+            "together" => ModelProvider::Together,
+            "fireworks" => ModelProvider::Fireworks,
+            "deepseek" => ModelProvider::DeepSeek,
+            "mistral" => ModelProvider::Mistral,
+            "cohere" => ModelProvider::Cohere,
+            "perplexity" => ModelProvider::Perplexity,
+            "ollama" => ModelProvider::Ollama,
+            "lmstudio" => ModelProvider::LMStudio,
+            "jan" => ModelProvider::Jan,
+            "gpt4all" => ModelProvider::GPT4All,
+            "localai" => ModelProvider::LocalAI,
+            "llamafile" => ModelProvider::Llamafile,
+            "textgenwebui" => ModelProvider::TextGenWebUI,
+            "vllm" => ModelProvider::VLLM,
+            "koboldcpp" => ModelProvider::KoboldCpp,
+            "tabbyml" => ModelProvider::TabbyML,
+            "exo" => ModelProvider::Exo,
+            "openai_compatible" => ModelProvider::OpenAICompatible,
+            "custom" => ModelProvider::Custom,
+            _ => return None,
+        })
+    }
+
     /// Check if this provider uses OpenAI-compatible API
     pub fn is_openai_compatible(&self) -> bool {
         matches!(
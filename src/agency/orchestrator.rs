@@ -117,6 +117,130 @@ impl Swarm {
     }
 }
 
+/// A pipeline or swarm as written in a YAML file, naming library or default
+/// agents:
+///
+/// ```yaml
+/// name: research-report
+/// mode: sequential        # sequential, parallel, or loop
+/// agents:
+///   - researcher
+///   - name: writer
+///     model: gpt-4o
+/// coordinator: coordinator  # swarms only
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct PipelineDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// How a pipeline runs its agents; swarms ignore it
+    #[serde(default = "default_mode")]
+    pub mode: OrchestrationType,
+    /// The pipeline's agents, or a swarm's workers
+    #[serde(default)]
+    pub agents: Vec<AgentRef>,
+    /// The agent that leads a swarm
+    pub coordinator: Option<AgentRef>,
+    /// What a swarm works toward
+    pub goal: Option<String>,
+    /// Most rounds of a loop
+    #[serde(default = "default_max_iterations")]
+    pub max_iterations: u32,
+    /// Prompt to use when none is given
+    pub input: Option<String>,
+}
+
+/// An agent in a definition: its name, or its name and a model to use
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum AgentRef {
+    Name(String),
+    Agent { name: String, model: Option<String> },
+}
+
+impl AgentRef {
+    pub fn name(&self) -> &str {
+        match self {
+            AgentRef::Name(name) | AgentRef::Agent { name, .. } => name,
+        }
+    }
+
+    pub fn model(&self) -> Option<&str> {
+        match self {
+            AgentRef::Name(_) => None,
+            AgentRef::Agent { model, .. } => model.as_deref(),
+        }
+    }
+}
+
+fn default_mode() -> OrchestrationType {
+    OrchestrationType::Sequential
+}
+
+fn default_max_iterations() -> u32 {
+    5
+}
+
+impl PipelineDefinition {
+    /// Parse a definition from YAML
+    pub fn from_yaml(text: &str) -> AgencyResult<Self> {
+        serde_yaml::from_str(text).map_err(|e| AgencyError::ConfigError(e.to_string()))
+    }
+
+    /// The agents to load, in the order `pipeline` and `swarm` take them;
+    /// a swarm's coordinator comes first
+    pub fn members(&self, swarm: bool) -> Vec<&AgentRef> {
+        let coordinator = self.coordinator.iter().filter(|_| swarm);
+        coordinator.chain(&self.agents).collect()
+    }
+
+    /// Build the pipeline from its loaded `agents`
+    pub fn pipeline(&self, agents: Vec<Agent>) -> AgencyResult<Pipeline> {
+        if agents.is_empty() {
+            return Err(self.invalid("names no agents"));
+        }
+        Ok(match self.mode {
+            OrchestrationType::Sequential => Pipeline::sequential(&self.name, agents),
+            OrchestrationType::Parallel => Pipeline::parallel(&self.name, agents),
+            OrchestrationType::Loop => {
+                if agents.len() > 1 {
+                    return Err(self.invalid("loops one agent, but names several"));
+                }
+                let agent = agents.into_iter().next().unwrap();
+                Pipeline::loop_agent(&self.name, agent, self.max_iterations)
+            }
+            OrchestrationType::Hierarchical => {
+                return Err(self.invalid("is hierarchical; run it as a swarm"))
+            }
+        })
+    }
+
+    /// Build the swarm from its loaded coordinator and workers
+    pub fn swarm(&self, agents: Vec<Agent>) -> AgencyResult<Swarm> {
+        if self.coordinator.is_none() {
+            return Err(self.invalid("names no coordinator"));
+        }
+        let mut agents = agents.into_iter();
+        let coordinator = agents
+            .next()
+            .ok_or_else(|| self.invalid("names no coordinator"))?;
+        let workers: Vec<Agent> = agents.collect();
+        if workers.is_empty() {
+            return Err(self.invalid("names no worker agents"));
+        }
+        let swarm = Swarm::new(&self.name, &self.description, coordinator, workers);
+        Ok(match &self.goal {
+            Some(goal) => swarm.with_goal(goal),
+            None => swarm,
+        })
+    }
+
+    fn invalid(&self, problem: &str) -> AgencyError {
+        AgencyError::ConfigError(format!("Pipeline '{}' {}", self.name, problem))
+    }
+}
+
 /// Orchestrator handles multi-agent execution
 pub struct Orchestrator {
     executor: Arc<Executor>,
@@ -204,11 +328,15 @@ impl Orchestrator {
             let executor = self.executor.clone();
             let input = input.to_string();
             let user_id = ctx.user_id.clone();
+            let attachments = ctx.attachments.clone();
+            let policy = ctx.policy.clone();
 
             handles.push(tokio::spawn(async move {
                 let mut session = Session::new(agent.name(), user_id.clone());
                 let mut ctx = ExecutionContext::new(&session);
                 ctx.user_id = user_id;
+                ctx.attachments = attachments;
+                ctx.policy = policy;
 
                 executor
                     .execute(agent.as_ref(), &mut session, &input, &mut ctx)
@@ -490,4 +618,34 @@ mod tests {
         assert!(!result.response.is_empty());
         assert_eq!(result.agent_results.len(), 2);
     }
+
+    #[test]
+    fn test_pipeline_definition() {
+        let definition = PipelineDefinition::from_yaml(
+            "name: review\nmode: loop\nmax_iterations: 3\nagents:\n  - name: reviewer\n    model: gpt-4o\n",
+        )
+        .unwrap();
+        assert_eq!(definition.members(false)[0].model(), Some("gpt-4o"));
+        let pipeline = definition
+            .pipeline(vec![create_test_agent("reviewer")])
+            .unwrap();
+        assert_eq!(pipeline.orchestration, OrchestrationType::Loop);
+        assert_eq!(pipeline.max_iterations, 3);
+        assert!(definition
+            .pipeline(vec![create_test_agent("a"), create_test_agent("b")])
+            .is_err());
+
+        let definition = PipelineDefinition::from_yaml(
+            "name: team\ncoordinator: coordinator\nagents: [coder, tester]\n",
+        )
+        .unwrap();
+        let names: Vec<&str> = definition.members(true).iter().map(|a| a.name()).collect();
+        assert_eq!(names, ["coordinator", "coder", "tester"]);
+        assert_eq!(definition.members(false).len(), 2);
+        let agents = names.iter().map(|n| create_test_agent(n)).collect();
+        let swarm = definition.swarm(agents).unwrap();
+        assert_eq!(swarm.coordinator.name(), "coordinator");
+        assert_eq!(swarm.workers.len(), 2);
+        assert!(definition.pipeline(Vec::new()).is_err());
+    }
 }
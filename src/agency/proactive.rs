@@ -18,6 +18,7 @@
 
 #![allow(dead_code)]
 
+use crate::notifications::{Notification, NotificationLevel};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -347,6 +348,31 @@ pub struct ProactiveAction {
     pub created_at: DateTime<Utc>,
 }
 
+impl ProactiveAction {
+    /// Notification asking the user to review a pending action
+    /// (`agency.approval_requested`, deliver with `notifications::dispatch`)
+    pub fn approval_notification(&self) -> Notification {
+        let level = match self.risk_level {
+            ActionRisk::High => NotificationLevel::Warning,
+            _ => NotificationLevel::Info,
+        };
+        let risk = serde_json::to_value(self.risk_level)
+            .ok()
+            .and_then(|v| v.as_str().map(String::from))
+            .unwrap_or_default();
+        Notification::new(
+            "agency.approval_requested",
+            format!("Approval needed: {}", self.action_type),
+            self.description.clone(),
+        )
+        .with_level(level)
+        .with_field("action_id", self.id.clone())
+        .with_field("agent_id", self.agent_id.clone())
+        .with_field("reasoning", self.reasoning.clone())
+        .with_field("risk", risk)
+    }
+}
+
 // =============================================================================
 // Proactive Agent Configuration
 // =============================================================================
@@ -600,6 +626,36 @@ mod tests {
         assert!(!PermissionLevel::HighAutonomy.can_auto_approve("email_send")); // Always requires approval
     }
 
+    #[test]
+    fn test_approval_notification() {
+        let action = ProactiveAction {
+            id: "act-1".to_string(),
+            agent_id: "household".to_string(),
+            problem_id: None,
+            action_type: "bill_pay".to_string(),
+            description: "Pay the electricity bill".to_string(),
+            reasoning: "Due in 2 days".to_string(),
+            estimated_impact: None,
+            risk_level: ActionRisk::High,
+            status: ActionStatus::Pending,
+            auto_approved: false,
+            approved_at: None,
+            approved_by: None,
+            executed_at: None,
+            result: None,
+            error: None,
+            parameters: HashMap::new(),
+            created_at: Utc::now(),
+        };
+        let n = action.approval_notification();
+        assert_eq!(n.event, "agency.approval_requested");
+        assert_eq!(n.level, NotificationLevel::Warning);
+        assert_eq!(
+            n.render("{{title}} ({{risk}}): {{message}}"),
+            "Approval needed: bill_pay (high): Pay the electricity bill"
+        );
+    }
+
     #[test]
     fn test_household_config() {
         let config = household_agent_config();
@@ -0,0 +1,358 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Retrieval over Harvested Sessions
+//!
+//! Indexes the sessions `csm harvest` collected into the knowledge base, so
+//! `csm ask` can answer questions from past chats. Each session becomes the
+//! document `session:<id>`, chunked message by message so every retrieved
+//! chunk can be cited as one message of one session. Chunks are embedded by
+//! the same `/embeddings` endpoint semantic search uses, and a session is
+//! embedded again only when it changes or the embedding model does.
+
+use crate::agency::memory::{
+    Document, DocumentType, KnowledgeBase, MemorySource, VectorStoreConfig,
+};
+use crate::commands::message_content;
+use crate::search::Embedder;
+use crate::text::prefix_chars;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::Connection;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Prefix of the IDs of session documents
+const SESSION_PREFIX: &str = "session:";
+
+/// Texts sent to the embeddings endpoint per request
+const EMBED_BATCH: usize = 32;
+
+/// Characters of a chunk sent to the embeddings endpoint
+const EMBED_CHARS: usize = 8000;
+
+/// Characters of a chunk quoted to the model
+const SOURCE_CHARS: usize = 2000;
+
+/// A message of a harvested session that answers a question
+#[derive(Debug, Clone, Serialize)]
+pub struct Source {
+    pub session_id: String,
+    pub title: String,
+    pub provider: String,
+    /// Index of the message within its session
+    pub message_index: i64,
+    pub role: String,
+    /// The retrieved part of the message
+    pub content: String,
+    pub score: f32,
+}
+
+/// What an index update did
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct IndexStats {
+    /// Sessions embedded because they were new or changed
+    pub indexed: usize,
+    /// Sessions already up to date
+    pub unchanged: usize,
+    /// Sessions dropped because they are no longer harvested
+    pub removed: usize,
+    /// Chunks embedded
+    pub chunks: usize,
+}
+
+/// A harvested session as the index sees it
+struct SessionRow {
+    id: String,
+    title: String,
+    provider: String,
+    updated_at: i64,
+}
+
+/// The knowledge base of one harvest database's sessions
+pub struct SessionIndex {
+    conn: Connection,
+    kb: KnowledgeBase,
+}
+
+impl SessionIndex {
+    /// Open the index kept in the harvest database at `db_path`
+    pub fn open(db_path: &Path) -> Result<Self> {
+        let conn = Connection::open(db_path)?;
+        let config = VectorStoreConfig {
+            // Every message of every session may be a chunk
+            max_entries: usize::MAX,
+            ..Default::default()
+        };
+        let kb = KnowledgeBase::with_persistence(config, db_path)
+            .context("Failed to open the knowledge base")?;
+        Ok(Self { conn, kb })
+    }
+
+    /// Embed new and changed sessions and drop deleted ones, calling
+    /// `on_session` with each session title before it is embedded
+    pub fn update(
+        &mut self,
+        embedder: &dyn Embedder,
+        on_session: &mut dyn FnMut(&str),
+    ) -> Result<IndexStats> {
+        let mut stats = IndexStats::default();
+        let sessions = self.sessions()?;
+
+        let harvested: HashSet<String> = sessions.iter().map(|s| document_id(&s.id)).collect();
+        let gone: Vec<String> = self
+            .kb
+            .list_documents()
+            .into_iter()
+            .filter(|d| d.id.starts_with(SESSION_PREFIX) && !harvested.contains(&d.id))
+            .map(|d| d.id.clone())
+            .collect();
+        for id in gone {
+            self.kb.delete_document(&id)?;
+            stats.removed += 1;
+        }
+
+        for session in &sessions {
+            let current = self
+                .kb
+                .get_document(&document_id(&session.id))
+                .is_some_and(|d| {
+                    d.metadata.get("updated_at") == Some(&json!(session.updated_at))
+                        && d.metadata.get("model") == Some(&json!(embedder.model()))
+                });
+            if current {
+                stats.unchanged += 1;
+                continue;
+            }
+            on_session(&session.title);
+            stats.chunks += self.index_session(session, embedder)?;
+            stats.indexed += 1;
+        }
+        Ok(stats)
+    }
+
+    fn sessions(&self) -> Result<Vec<SessionRow>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, title, provider, updated_at FROM sessions ORDER BY updated_at")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(SessionRow {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                provider: row.get(2)?,
+                updated_at: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Chunk, embed, and store one session, returning its number of chunks
+    fn index_session(&mut self, session: &SessionRow, embedder: &dyn Embedder) -> Result<usize> {
+        let mut stmt = self.conn.prepare(
+            "SELECT message_index, role, content_raw, id FROM messages_v2
+             WHERE session_id = ?1 ORDER BY message_index, id",
+        )?;
+        let messages = stmt
+            .query_map([&session.id], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    message_content(&self.conn, row.get(3)?, row.get(2)?)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        // Chunks never span messages, so each one cites a single message
+        let mut content = String::new();
+        let mut chunks = Vec::new();
+        let mut cited = Vec::new();
+        for (index, role, text) in &messages {
+            for mut chunk in self.kb.chunk_document(text) {
+                chunk.index = chunks.len() as u32;
+                chunk.start_pos += content.len();
+                chunk.end_pos += content.len();
+                chunks.push(chunk);
+                cited.push(json!({ "index": index, "role": role }));
+            }
+            content.push_str(text);
+            content.push_str("\n\n");
+        }
+
+        for batch in chunks.chunks_mut(EMBED_BATCH) {
+            let texts: Vec<String> = batch
+                .iter()
+                .map(|chunk| {
+                    let text = format!("{}\n{}", session.title, chunk.content);
+                    prefix_chars(&text, EMBED_CHARS).to_string()
+                })
+                .collect();
+            let vectors = embedder.embed(&texts)?;
+            for (chunk, vector) in batch.iter_mut().zip(vectors) {
+                chunk.embedding = Some(vector);
+            }
+        }
+
+        let now = Utc::now();
+        let count = chunks.len();
+        let metadata = HashMap::from([
+            ("provider".to_string(), json!(session.provider)),
+            ("updated_at".to_string(), json!(session.updated_at)),
+            ("model".to_string(), json!(embedder.model())),
+            ("messages".to_string(), Value::Array(cited)),
+        ]);
+        self.kb.add_document(Document {
+            id: document_id(&session.id),
+            title: session.title.clone(),
+            content,
+            doc_type: DocumentType::Text,
+            source: document_id(&session.id),
+            chunks,
+            created_at: now,
+            updated_at: now,
+            metadata,
+        })?;
+        Ok(count)
+    }
+
+    /// The `limit` messages most similar to `question`
+    pub fn retrieve(
+        &mut self,
+        question: &str,
+        embedder: &dyn Embedder,
+        limit: usize,
+    ) -> Result<Vec<Source>> {
+        let query = embedder
+            .embed(&[question.to_string()])?
+            .pop()
+            .context("No embedding returned for the question")?;
+        let model = json!(embedder.model());
+        let results = self.kb.retrieve_from(&query, limit, |d| {
+            d.id.starts_with(SESSION_PREFIX) && d.metadata.get("model") == Some(&model)
+        });
+
+        let mut sources = Vec::new();
+        for result in results {
+            let MemorySource::Document { path, chunk_index } = &result.entry.source else {
+                continue;
+            };
+            let Some(document) = self.kb.get_document(path) else {
+                continue;
+            };
+            let message = &document.metadata["messages"][*chunk_index as usize];
+            sources.push(Source {
+                session_id: path[SESSION_PREFIX.len()..].to_string(),
+                title: document.title.clone(),
+                provider: document.metadata["provider"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                message_index: message["index"].as_i64().unwrap_or_default(),
+                role: message["role"].as_str().unwrap_or_default().to_string(),
+                content: result.entry.content,
+                score: result.score,
+            });
+        }
+        Ok(sources)
+    }
+}
+
+fn document_id(session_id: &str) -> String {
+    format!("{}{}", SESSION_PREFIX, session_id)
+}
+
+/// The prompt asking a model to answer `question` from `sources`, citing
+/// them by number
+pub fn answer_prompt(question: &str, sources: &[Source]) -> String {
+    let mut prompt = String::from(
+        "Answer the question using these excerpts from my past chat sessions. \
+         Cite the excerpts you rely on by number, like [2]. If they do not \
+         answer the question, say so instead of guessing.\n\n",
+    );
+    for (i, source) in sources.iter().enumerate() {
+        prompt.push_str(&format!(
+            "[{}] \"{}\" ({}, session {}, message {}, {})\n{}\n\n",
+            i + 1,
+            source.title,
+            source.provider,
+            source.session_id,
+            source.message_index,
+            source.role,
+            prefix_chars(&source.content, SOURCE_CHARS)
+        ));
+    }
+    prompt.push_str(&format!("Question: {}", question));
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Embeds text as counts of a few words
+    struct WordCounts;
+
+    impl Embedder for WordCounts {
+        fn model(&self) -> &str {
+            "word-counts"
+        }
+
+        fn embed(&self, input: &[String]) -> Result<Vec<Vec<f32>>> {
+            Ok(input
+                .iter()
+                .map(|text| {
+                    let text = text.to_lowercase();
+                    ["tokio", "runtime", "sqlite", "index", "chat"]
+                        .iter()
+                        .map(|word| text.matches(word).count() as f32)
+                        .collect()
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_index_and_retrieve() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("harvest.db");
+        crate::commands::harvest_init(Some(db.to_str().unwrap()), false).unwrap();
+        let conn = Connection::open(&db).unwrap();
+        conn.execute_batch(
+            "INSERT INTO sessions
+             (id, provider, title, message_count, created_at, updated_at, harvested_at, session_json)
+             VALUES ('s1', 'copilot', 'Chat', 2, 0, 1, 0, '{}'),
+                    ('s2', 'cursor', 'Chat', 1, 0, 1, 0, '{}');
+             INSERT INTO messages_v2 (session_id, message_index, role, content_raw) VALUES
+                 ('s1', 0, 'user', 'Why does the tokio runtime panic?'),
+                 ('s1', 1, 'assistant', 'Use spawn_blocking.'),
+                 ('s2', 0, 'user', 'Which sqlite index helps here?');",
+        )
+        .unwrap();
+
+        let mut index = SessionIndex::open(&db).unwrap();
+        let stats = index.update(&WordCounts, &mut |_| {}).unwrap();
+        assert_eq!((stats.indexed, stats.chunks), (2, 3));
+
+        let sources = index.retrieve("sqlite index", &WordCounts, 1).unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].session_id, "s2");
+        assert_eq!(sources[0].content, "Which sqlite index helps here?");
+
+        let sources = index.retrieve("tokio runtime", &WordCounts, 1).unwrap();
+        assert_eq!(
+            (sources[0].session_id.as_str(), sources[0].message_index),
+            ("s1", 0)
+        );
+        let prompt = answer_prompt("Why the panic?", &sources);
+        assert!(prompt.contains("[1] \"Chat\" (copilot, session s1, message 0, user)"));
+
+        // Unchanged sessions are skipped and deleted ones dropped
+        conn.execute("DELETE FROM sessions WHERE id = 's2'", [])
+            .unwrap();
+        let mut index = SessionIndex::open(&db).unwrap();
+        let stats = index.update(&WordCounts, &mut |_| {}).unwrap();
+        assert_eq!((stats.indexed, stats.unchanged, stats.removed), (0, 1, 1));
+        let sources = index.retrieve("sqlite index", &WordCounts, 5).unwrap();
+        assert!(sources.iter().all(|s| s.session_id == "s1"));
+    }
+}
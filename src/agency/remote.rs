@@ -57,6 +57,45 @@ pub struct RemoteNode {
     pub metadata: HashMap<String, serde_json::Value>,
 }
 
+impl RemoteNode {
+    /// A node that has just come online
+    pub fn new(id: impl Into<String>, address: impl Into<String>) -> Self {
+        let id = id.into();
+        Self {
+            name: id.clone(),
+            id,
+            address: address.into(),
+            status: NodeStatus::Online,
+            tags: Vec::new(),
+            hardware: None,
+            active_agents: 0,
+            running_tasks: 0,
+            last_heartbeat: Utc::now(),
+            registered_at: Utc::now(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// This machine, named by its host name and reachable at `address`
+    pub fn local(address: impl Into<String>) -> Self {
+        let mut system = sysinfo::System::new();
+        system.refresh_memory();
+        system.refresh_cpu();
+        let host = sysinfo::System::host_name().unwrap_or_else(generate_node_id);
+        Self {
+            hardware: Some(HardwareInfo {
+                cpu_cores: system.cpus().len() as u32,
+                ram_total: system.total_memory(),
+                ram_available: system.available_memory(),
+                gpus: Vec::new(),
+                os: sysinfo::System::long_os_version().unwrap_or_default(),
+                arch: std::env::consts::ARCH.to_string(),
+            }),
+            ..Self::new(host, address)
+        }
+    }
+}
+
 /// Node status
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -186,6 +225,18 @@ pub enum TaskPriority {
     Critical = 3,
 }
 
+/// An agent run submitted to a remote server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRequest {
+    /// Agent to run, by name
+    pub agent: String,
+    /// Prompt or task for the agent
+    pub prompt: String,
+    /// Model to use instead of the agent's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
 /// Task result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskResult {
@@ -363,6 +414,53 @@ pub enum RemoteEvent {
     AgentUnregistered { node_id: NodeId, agent_id: String },
 }
 
+impl RemoteEvent {
+    /// The task the event is about, if any
+    pub fn task_id(&self) -> Option<&str> {
+        match self {
+            RemoteEvent::TaskCreated(task) => Some(&task.id),
+            RemoteEvent::TaskStarted { task_id, .. }
+            | RemoteEvent::TaskProgress { task_id, .. }
+            | RemoteEvent::TaskStepCompleted { task_id, .. }
+            | RemoteEvent::TaskCompleted { task_id, .. }
+            | RemoteEvent::TaskFailed { task_id, .. }
+            | RemoteEvent::TaskCancelled { task_id, .. }
+            | RemoteEvent::TaskLog { task_id, .. } => Some(task_id),
+            _ => None,
+        }
+    }
+
+    /// Whether the event ends its task
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            RemoteEvent::TaskCompleted { .. }
+                | RemoteEvent::TaskFailed { .. }
+                | RemoteEvent::TaskCancelled { .. }
+        )
+    }
+
+    /// The event that ended `task`, if it has ended
+    pub fn finished(task: &RemoteTask) -> Option<Self> {
+        let task_id = task.id.clone();
+        match task.status {
+            RemoteTaskStatus::Completed => Some(RemoteEvent::TaskCompleted {
+                task_id,
+                result: task.result.clone()?,
+            }),
+            RemoteTaskStatus::Failed | RemoteTaskStatus::TimedOut => Some(RemoteEvent::TaskFailed {
+                task_id,
+                error: task.error.clone().unwrap_or_default(),
+            }),
+            RemoteTaskStatus::Cancelled => Some(RemoteEvent::TaskCancelled {
+                task_id,
+                reason: None,
+            }),
+            _ => None,
+        }
+    }
+}
+
 // =============================================================================
 // Remote Monitor
 // =============================================================================
@@ -861,7 +959,8 @@ pub struct MonitorStats {
 // Remote Agent Client
 // =============================================================================
 
-/// Client for remote agents to report progress
+/// Client for a remote csm API server: submits agent runs and follows them,
+/// and lets agents running elsewhere report progress
 pub struct RemoteAgentClient {
     /// Node ID this client represents
     pub node_id: NodeId,
@@ -870,7 +969,6 @@ pub struct RemoteAgentClient {
     /// Authentication token
     auth_token: Option<String>,
     /// HTTP client
-    #[allow(dead_code)]
     client: reqwest::Client,
 }
 
@@ -879,7 +977,7 @@ impl RemoteAgentClient {
     pub fn new(node_id: impl Into<String>, server_url: impl Into<String>) -> Self {
         Self {
             node_id: node_id.into(),
-            server_url: server_url.into(),
+            server_url: server_url.into().trim_end_matches('/').to_string(),
             auth_token: None,
             client: reqwest::Client::new(),
         }
@@ -891,30 +989,60 @@ impl RemoteAgentClient {
         self
     }
 
-    /// Send heartbeat
-    pub async fn heartbeat(&self) -> Result<(), RemoteMonitorError> {
-        let url = format!(
-            "{}/api/v1/nodes/{}/heartbeat",
-            self.server_url, self.node_id
-        );
-
-        let mut request = self.client.post(&url);
-        if let Some(ref token) = self.auth_token {
-            request = request.header("Authorization", format!("Bearer {}", token));
-        }
-
+    /// Send `request` with the token, failing on an error status
+    async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, RemoteMonitorError> {
+        let request = match self.auth_token {
+            Some(ref token) => request.bearer_auth(token),
+            None => request,
+        };
         let response = request
             .send()
             .await
             .map_err(|e| RemoteMonitorError::Network(e.to_string()))?;
 
-        if !response.status().is_success() {
-            return Err(RemoteMonitorError::ApiError(
-                response.status().as_u16(),
-                response.text().await.unwrap_or_default(),
-            ));
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            // The API wraps its errors in an envelope
+            let message = serde_json::from_str::<serde_json::Value>(&body)
+                .ok()
+                .and_then(|v| v["error"].as_str().map(String::from))
+                .unwrap_or(body);
+            return Err(match status.as_u16() {
+                401 | 403 => RemoteMonitorError::AuthError(message),
+                code => RemoteMonitorError::ApiError(code, message),
+            });
         }
 
+        Ok(response)
+    }
+
+    /// The payload of an API response
+    async fn data<T: serde::de::DeserializeOwned>(
+        response: reqwest::Response,
+    ) -> Result<T, RemoteMonitorError> {
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| RemoteMonitorError::Network(e.to_string()))?;
+        serde_json::from_value(body["data"].clone())
+            .map_err(|e| RemoteMonitorError::InvalidState(e.to_string()))
+    }
+
+    fn task_url(&self, task_id: &str, rest: &str) -> String {
+        format!("{}/api/v1/tasks/{}{}", self.server_url, task_id, rest)
+    }
+
+    /// Send heartbeat
+    pub async fn heartbeat(&self) -> Result<(), RemoteMonitorError> {
+        let url = format!(
+            "{}/api/v1/nodes/{}/heartbeat",
+            self.server_url, self.node_id
+        );
+        self.send(self.client.post(&url)).await?;
         Ok(())
     }
 
@@ -925,30 +1053,12 @@ impl RemoteAgentClient {
         progress: f32,
         message: Option<&str>,
     ) -> Result<(), RemoteMonitorError> {
-        let url = format!("{}/api/v1/tasks/{}/progress", self.server_url, task_id);
-
         let body = serde_json::json!({
             "progress": progress,
             "message": message,
         });
-
-        let mut request = self.client.post(&url).json(&body);
-        if let Some(ref token) = self.auth_token {
-            request = request.header("Authorization", format!("Bearer {}", token));
-        }
-
-        let response = request
-            .send()
-            .await
-            .map_err(|e| RemoteMonitorError::Network(e.to_string()))?;
-
-        if !response.status().is_success() {
-            return Err(RemoteMonitorError::ApiError(
-                response.status().as_u16(),
-                response.text().await.unwrap_or_default(),
-            ));
-        }
-
+        let url = self.task_url(task_id, "/progress");
+        self.send(self.client.post(&url).json(&body)).await?;
         Ok(())
     }
 
@@ -960,31 +1070,13 @@ impl RemoteAgentClient {
         total: u32,
         description: Option<&str>,
     ) -> Result<(), RemoteMonitorError> {
-        let url = format!("{}/api/v1/tasks/{}/step", self.server_url, task_id);
-
         let body = serde_json::json!({
             "step": step,
             "total": total,
             "description": description,
         });
-
-        let mut request = self.client.post(&url).json(&body);
-        if let Some(ref token) = self.auth_token {
-            request = request.header("Authorization", format!("Bearer {}", token));
-        }
-
-        let response = request
-            .send()
-            .await
-            .map_err(|e| RemoteMonitorError::Network(e.to_string()))?;
-
-        if !response.status().is_success() {
-            return Err(RemoteMonitorError::ApiError(
-                response.status().as_u16(),
-                response.text().await.unwrap_or_default(),
-            ));
-        }
-
+        let url = self.task_url(task_id, "/step");
+        self.send(self.client.post(&url).json(&body)).await?;
         Ok(())
     }
 
@@ -994,25 +1086,8 @@ impl RemoteAgentClient {
         task_id: &str,
         result: TaskResult,
     ) -> Result<(), RemoteMonitorError> {
-        let url = format!("{}/api/v1/tasks/{}/complete", self.server_url, task_id);
-
-        let mut request = self.client.post(&url).json(&result);
-        if let Some(ref token) = self.auth_token {
-            request = request.header("Authorization", format!("Bearer {}", token));
-        }
-
-        let response = request
-            .send()
-            .await
-            .map_err(|e| RemoteMonitorError::Network(e.to_string()))?;
-
-        if !response.status().is_success() {
-            return Err(RemoteMonitorError::ApiError(
-                response.status().as_u16(),
-                response.text().await.unwrap_or_default(),
-            ));
-        }
-
+        let url = self.task_url(task_id, "/complete");
+        self.send(self.client.post(&url).json(&result)).await?;
         Ok(())
     }
 
@@ -1022,29 +1097,11 @@ impl RemoteAgentClient {
         task_id: &str,
         error: &str,
     ) -> Result<(), RemoteMonitorError> {
-        let url = format!("{}/api/v1/tasks/{}/fail", self.server_url, task_id);
-
         let body = serde_json::json!({
             "error": error,
         });
-
-        let mut request = self.client.post(&url).json(&body);
-        if let Some(ref token) = self.auth_token {
-            request = request.header("Authorization", format!("Bearer {}", token));
-        }
-
-        let response = request
-            .send()
-            .await
-            .map_err(|e| RemoteMonitorError::Network(e.to_string()))?;
-
-        if !response.status().is_success() {
-            return Err(RemoteMonitorError::ApiError(
-                response.status().as_u16(),
-                response.text().await.unwrap_or_default(),
-            ));
-        }
-
+        let url = self.task_url(task_id, "/fail");
+        self.send(self.client.post(&url).json(&body)).await?;
         Ok(())
     }
 
@@ -1055,33 +1112,94 @@ impl RemoteAgentClient {
         level: LogLevel,
         message: &str,
     ) -> Result<(), RemoteMonitorError> {
-        let url = format!("{}/api/v1/tasks/{}/log", self.server_url, task_id);
-
         let body = serde_json::json!({
             "level": level,
             "message": message,
             "timestamp": Utc::now(),
         });
+        let url = self.task_url(task_id, "/log");
+        self.send(self.client.post(&url).json(&body)).await?;
+        Ok(())
+    }
 
-        let mut request = self.client.post(&url).json(&body);
-        if let Some(ref token) = self.auth_token {
-            request = request.header("Authorization", format!("Bearer {}", token));
-        }
+    /// Start an agent run on the server; it runs in the background
+    pub async fn submit_task(&self, request: &TaskRequest) -> Result<RemoteTask, RemoteMonitorError> {
+        let url = format!("{}/api/v1/tasks", self.server_url);
+        let response = self.send(self.client.post(&url).json(request)).await?;
+        Self::data(response).await
+    }
 
-        let response = request
-            .send()
-            .await
-            .map_err(|e| RemoteMonitorError::Network(e.to_string()))?;
+    /// Get a task, with its recent logs and its result once finished
+    pub async fn get_task(&self, task_id: &str) -> Result<RemoteTask, RemoteMonitorError> {
+        let response = self.send(self.client.get(self.task_url(task_id, ""))).await?;
+        Self::data(response).await
+    }
 
-        if !response.status().is_success() {
-            return Err(RemoteMonitorError::ApiError(
-                response.status().as_u16(),
-                response.text().await.unwrap_or_default(),
-            ));
-        }
+    /// List the server's tasks, newest first
+    pub async fn list_tasks(&self) -> Result<Vec<RemoteTask>, RemoteMonitorError> {
+        let url = format!("{}/api/v1/tasks", self.server_url);
+        let response = self.send(self.client.get(&url)).await?;
+        Self::data(response).await
+    }
 
+    /// Stop a running task
+    pub async fn cancel_task(&self, task_id: &str) -> Result<(), RemoteMonitorError> {
+        let url = self.task_url(task_id, "/cancel");
+        self.send(self.client.post(&url)).await?;
         Ok(())
     }
+
+    /// Follow a task, calling `on_event` with the logs it already has and
+    /// then each event as it happens, until the task finishes
+    pub async fn stream_events(
+        &self,
+        task_id: &str,
+        mut on_event: impl FnMut(RemoteEvent),
+    ) -> Result<(), RemoteMonitorError> {
+        let url = self.task_url(task_id, "/events");
+        let mut response = self.send(self.client.get(&url)).await?;
+
+        let mut buffer = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| RemoteMonitorError::Network(e.to_string()))?
+        {
+            buffer.extend_from_slice(&chunk);
+            while let Some(end) = buffer.windows(2).position(|w| w == b"\n\n") {
+                let frame: Vec<u8> = buffer.drain(..end + 2).collect();
+                if let Some(event) = parse_event(&String::from_utf8_lossy(&frame)) {
+                    on_event(event);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Download a file a task produced
+    pub async fn download_artifact(
+        &self,
+        task_id: &str,
+        name: &str,
+    ) -> Result<Vec<u8>, RemoteMonitorError> {
+        let url = self.task_url(task_id, &format!("/artifacts/{}", urlencoding::encode(name)));
+        let response = self.send(self.client.get(&url)).await?;
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| RemoteMonitorError::Network(e.to_string()))?;
+        Ok(bytes.to_vec())
+    }
+}
+
+/// The event in one server-sent event frame
+fn parse_event(frame: &str) -> Option<RemoteEvent> {
+    let data: Vec<&str> = frame
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(str::trim_start)
+        .collect();
+    serde_json::from_str(&data.join("\n")).ok()
 }
 
 // =============================================================================
@@ -1371,4 +1489,21 @@ mod tests {
         assert_eq!(task.total_steps, Some(5));
         assert_eq!(task.status, RemoteTaskStatus::Queued);
     }
+
+    #[test]
+    fn test_parse_event() {
+        let frame = "data: {\"type\":\"TaskFailed\",\"data\":{\"task_id\":\"t1\",\"error\":\"Out of memory\"}}\n\n";
+        let event = parse_event(frame).unwrap();
+        assert_eq!(event.task_id(), Some("t1"));
+        assert!(event.is_final());
+        assert!(parse_event(": keep-alive\n\n").is_none());
+
+        let mut task = RemoteTaskBuilder::new("t2", "node-1", "agent-1").build();
+        assert!(RemoteEvent::finished(&task).is_none());
+        task.status = RemoteTaskStatus::Cancelled;
+        assert!(matches!(
+            RemoteEvent::finished(&task),
+            Some(RemoteEvent::TaskCancelled { .. })
+        ));
+    }
 }
@@ -7,12 +7,15 @@
 #![allow(dead_code)]
 
 use crate::agency::agent::Agent;
+use crate::agency::budget::Budget;
 use crate::agency::error::{AgencyError, AgencyResult};
 use crate::agency::executor::{ExecutionContext, ExecutionResult, Executor};
+use crate::agency::modality::{ContentPart, ImageContent};
 use crate::agency::models::AgencyEvent;
 use crate::agency::orchestrator::{Orchestrator, OrchestratorResult, Pipeline, Swarm};
 use crate::agency::session::{Session, SessionManager};
 use crate::agency::tools::ToolRegistry;
+use crate::routing::PolicyGuard;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -100,6 +103,14 @@ impl Runtime {
         })
     }
 
+    /// Run tools from `registry` instead of the builtins
+    pub fn with_tool_registry(mut self, registry: ToolRegistry) -> Self {
+        self.tool_registry = Arc::new(registry);
+        self.executor = Arc::new(Executor::new(self.tool_registry.clone()));
+        self.orchestrator = Orchestrator::new(self.executor.clone());
+        self
+    }
+
     /// Register an agent
     pub fn register_agent(&mut self, agent: Agent) {
         self.agents
@@ -157,6 +168,10 @@ impl Runtime {
         ctx.allow_tools = options.allow_tools;
         ctx.max_tool_calls = options.max_tool_calls.unwrap_or(self.config.max_tool_calls);
         ctx.event_sender = options.event_sender;
+        ctx.stream = options.stream.unwrap_or(self.config.streaming);
+        ctx.budget = options.budget;
+        ctx.attachments = options.attachments;
+        ctx.policy = workspace_policy(options.policy)?;
 
         // Execute
         let result = self
@@ -199,6 +214,10 @@ impl Runtime {
         ctx.user_id = options.user_id;
         ctx.allow_tools = options.allow_tools;
         ctx.event_sender = options.event_sender;
+        ctx.stream = options.stream.unwrap_or(self.config.streaming);
+        ctx.budget = options.budget;
+        ctx.attachments = options.attachments;
+        ctx.policy = workspace_policy(options.policy)?;
 
         self.orchestrator
             .run_pipeline(pipeline, input, &mut ctx)
@@ -219,6 +238,10 @@ impl Runtime {
         ctx.user_id = options.user_id;
         ctx.allow_tools = options.allow_tools;
         ctx.event_sender = options.event_sender;
+        ctx.stream = options.stream.unwrap_or(self.config.streaming);
+        ctx.budget = options.budget;
+        ctx.attachments = options.attachments;
+        ctx.policy = workspace_policy(options.policy)?;
 
         self.orchestrator.run_swarm(swarm, input, &mut ctx).await
     }
@@ -252,6 +275,17 @@ impl Runtime {
     }
 }
 
+/// The policy a run was given, or else the one covering the current
+/// directory
+fn workspace_policy(policy: Option<Arc<PolicyGuard>>) -> AgencyResult<Option<Arc<PolicyGuard>>> {
+    match policy {
+        Some(policy) => Ok(Some(policy)),
+        None => Ok(PolicyGuard::here("agency")
+            .map_err(|e| AgencyError::ConfigError(e.to_string()))?
+            .map(Arc::new)),
+    }
+}
+
 /// Options for running an agent
 #[derive(Debug, Clone, Default)]
 pub struct RunOptions {
@@ -265,6 +299,16 @@ pub struct RunOptions {
     pub max_tool_calls: Option<u32>,
     /// Event sender for streaming
     pub event_sender: Option<mpsc::Sender<AgencyEvent>>,
+    /// Send replies as `MessageDelta` events as they are written (default:
+    /// the runtime's `streaming` setting)
+    pub stream: Option<bool>,
+    /// Budget every model call of the run is charged to
+    pub budget: Option<Arc<Budget>>,
+    /// Images sent with the message, for vision models
+    pub attachments: Vec<ContentPart>,
+    /// Workspace policy the run's models must follow (default: the policy
+    /// covering the current directory, if any)
+    pub policy: Option<Arc<PolicyGuard>>,
 }
 
 impl RunOptions {
@@ -289,6 +333,26 @@ impl RunOptions {
         self.allow_tools = allow;
         self
     }
+
+    pub fn with_streaming(mut self, stream: bool) -> Self {
+        self.stream = Some(stream);
+        self
+    }
+
+    pub fn with_budget(mut self, budget: Arc<Budget>) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    pub fn with_policy(mut self, policy: Arc<PolicyGuard>) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    pub fn with_image(mut self, image: ImageContent) -> Self {
+        self.attachments.push(ContentPart::Image(image));
+        self
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,714 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Tool Sandbox
+//!
+//! Runs the builtin file, shell, and HTTP tools under the calling agent's
+//! [`SandboxConfig`], so agents can be given tools without the run of the
+//! machine. Paths outside the sandbox's folders are refused, as are denied
+//! commands and, unless the sandbox allows it, the network. Commands run in
+//! the first sandbox folder with a time limit and an optional memory limit.
+//! On Linux with bubblewrap (`bwrap`) they see only the sandbox's folders,
+//! read-only if the sandbox is, and the system's program folders; without
+//! it, commands are refused unless the sandbox sets `allow_unconfined`, and
+//! always when it is read-only. Command output and file contents are cut to
+//! `max_output_bytes`.
+//!
+//! The command denylist is a guard against mistakes, not a security
+//! boundary: it matches the programs a command line starts, or for Python
+//! and JavaScript any mention of them, which a determined script can
+//! disguise.
+
+use crate::agency::error::AgencyResult;
+use crate::agency::models::ToolResult;
+use crate::agency::tools::{BuiltinTools, Tool, ToolExecutor};
+use crate::config::{self, SandboxConfig};
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+/// Most entries `list_directory` returns
+const MAX_ENTRIES: usize = 1000;
+
+/// Words that run the command after them
+const WRAPPERS: [&str; 6] = ["env", "exec", "command", "nohup", "nice", "xargs"];
+
+/// Folders a confined command sees read-only besides the sandbox's, so
+/// programs and their libraries still load
+#[cfg(unix)]
+const SYSTEM_DIRS: [&str; 9] = [
+    "/usr", "/bin", "/sbin", "/lib", "/lib32", "/lib64", "/etc", "/opt", "/nix",
+];
+
+/// A builtin tool that runs inside the calling agent's sandbox
+pub struct SandboxedTool {
+    tool: Tool,
+}
+
+impl SandboxedTool {
+    /// Executors for the builtin tools the sandbox governs
+    pub fn all() -> Vec<Self> {
+        [
+            BuiltinTools::read_file(),
+            BuiltinTools::write_file(),
+            BuiltinTools::list_directory(),
+            BuiltinTools::code_execution(),
+            BuiltinTools::http_request(),
+        ]
+        .into_iter()
+        .map(|tool| Self { tool })
+        .collect()
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for SandboxedTool {
+    fn definition(&self) -> &Tool {
+        &self.tool
+    }
+
+    /// Outside an agent run, the `[agency.sandbox]` policy applies
+    async fn execute(&self, args: Value) -> AgencyResult<ToolResult> {
+        let sandbox = config::current().agency.sandbox.clone();
+        self.execute_sandboxed(args, &sandbox).await
+    }
+
+    async fn execute_sandboxed(
+        &self,
+        args: Value,
+        sandbox: &SandboxConfig,
+    ) -> AgencyResult<ToolResult> {
+        let start = Instant::now();
+        let outcome = match self.tool.name.as_str() {
+            "read_file" => read_file(&args, sandbox),
+            "write_file" => write_file(&args, sandbox),
+            "list_directory" => list_directory(&args, sandbox),
+            "code_execution" => run_code(&args, sandbox).await,
+            "http_request" => http_request(&args, sandbox).await,
+            name => Err(anyhow::anyhow!("{} is not a sandboxed tool", name)),
+        };
+
+        let (success, content, data) = match outcome {
+            Ok((content, data)) => (true, content, Some(data)),
+            Err(e) => (false, format!("{:#}", e), None),
+        };
+        Ok(ToolResult {
+            call_id: String::new(),
+            name: self.tool.name.clone(),
+            success,
+            content,
+            duration_ms: start.elapsed().as_millis() as u64,
+            data,
+        })
+    }
+}
+
+/// `path` with `~` expanded, made absolute from `base`
+fn expand(path: &str, base: &Path) -> PathBuf {
+    let path = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => dirs::home_dir()
+            .unwrap_or_default()
+            .join(rest.trim_start_matches(['/', '\\'])),
+        _ => PathBuf::from(path),
+    };
+    if path.is_absolute() {
+        path
+    } else {
+        base.join(path)
+    }
+}
+
+/// The sandbox's folders that exist, with links resolved. Relative ones
+/// start at the working directory.
+fn roots(sandbox: &SandboxConfig) -> Vec<PathBuf> {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    sandbox
+        .paths
+        .iter()
+        .filter_map(|path| expand(path, &cwd).canonicalize().ok())
+        .collect()
+}
+
+/// `path` made absolute with links resolved, if it lies inside the sandbox.
+/// Relative paths start at the first sandbox folder, where commands run,
+/// and a path that does not exist yet is resolved through its nearest
+/// existing parent.
+fn confine(path: &str, sandbox: &SandboxConfig) -> anyhow::Result<PathBuf> {
+    let roots = roots(sandbox);
+    let base = roots.first().context("The sandbox has no folders")?;
+    let requested = expand(path, base);
+    let mut existing = requested.as_path();
+    let mut missing = Vec::new();
+    let base = loop {
+        if let Ok(base) = existing.canonicalize() {
+            break base;
+        }
+        // `file_name` is None for a trailing `..`, which could climb out
+        let name = existing
+            .file_name()
+            .with_context(|| format!("Cannot resolve {}", path))?;
+        missing.push(name);
+        existing = existing
+            .parent()
+            .with_context(|| format!("Cannot resolve {}", path))?;
+    };
+    let resolved = missing.iter().rev().fold(base, |dir, name| dir.join(name));
+
+    if roots.iter().any(|root| resolved.starts_with(root)) {
+        Ok(resolved)
+    } else {
+        bail!(
+            "{} is outside the sandbox (allowed: {})",
+            path,
+            sandbox.paths.join(", ")
+        )
+    }
+}
+
+/// The first `max` bytes of `bytes` as text, and whether any were cut
+fn cut(bytes: &[u8], max: usize) -> (String, bool) {
+    let end = bytes.len().min(max);
+    (
+        String::from_utf8_lossy(&bytes[..end]).into_owned(),
+        end < bytes.len(),
+    )
+}
+
+fn path_arg<'a>(args: &'a Value, default: Option<&'a str>) -> anyhow::Result<&'a str> {
+    args["path"]
+        .as_str()
+        .filter(|p| !p.is_empty())
+        .or(default)
+        .context("Pass a path")
+}
+
+fn read_file(args: &Value, sandbox: &SandboxConfig) -> anyhow::Result<(String, Value)> {
+    let path = confine(path_arg(args, None)?, sandbox)?;
+    let bytes = std::fs::read(&path).with_context(|| format!("Cannot read {}", path.display()))?;
+    let (mut text, truncated) = cut(&bytes, sandbox.max_output_bytes);
+    if truncated {
+        text.push_str("\n\n[File cut short]");
+    }
+    let data = json!({ "path": path, "bytes": bytes.len(), "truncated": truncated });
+    Ok((text, data))
+}
+
+fn write_file(args: &Value, sandbox: &SandboxConfig) -> anyhow::Result<(String, Value)> {
+    if sandbox.read_only {
+        bail!("The sandbox is read-only");
+    }
+    let path = confine(path_arg(args, None)?, sandbox)?;
+    let content = args["content"]
+        .as_str()
+        .context("Pass the content to write")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let written = if args["append"].as_bool().unwrap_or(false) {
+        use std::io::Write;
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+    } else {
+        std::fs::write(&path, content)
+    };
+    written.with_context(|| format!("Cannot write {}", path.display()))?;
+    Ok((
+        format!("Wrote {} bytes to {}", content.len(), path.display()),
+        json!({ "path": path, "bytes": content.len() }),
+    ))
+}
+
+fn list_directory(args: &Value, sandbox: &SandboxConfig) -> anyhow::Result<(String, Value)> {
+    let dir = confine(path_arg(args, Some("."))?, sandbox)?;
+    if !dir.is_dir() {
+        bail!("{} is not a directory", dir.display());
+    }
+    let recursive = args["recursive"].as_bool().unwrap_or(false);
+    let hidden = args["include_hidden"].as_bool().unwrap_or(false);
+
+    let entries: Vec<walkdir::DirEntry> = walkdir::WalkDir::new(&dir)
+        .min_depth(1)
+        .max_depth(if recursive { usize::MAX } else { 1 })
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| hidden || !entry.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(Result::ok)
+        .take(MAX_ENTRIES + 1)
+        .collect();
+
+    let mut lines = Vec::new();
+    let mut listed = Vec::new();
+    for entry in entries.iter().take(MAX_ENTRIES) {
+        let name = entry
+            .path()
+            .strip_prefix(&dir)
+            .unwrap_or(entry.path())
+            .to_string_lossy()
+            .into_owned();
+        let is_dir = entry.file_type().is_dir();
+        lines.push(if is_dir {
+            format!("{}/", name)
+        } else {
+            name.clone()
+        });
+        listed.push(json!({ "name": name, "type": if is_dir { "directory" } else { "file" } }));
+    }
+    if entries.len() > MAX_ENTRIES {
+        lines.push(format!("[Only the first {} entries]", MAX_ENTRIES));
+    }
+    Ok((lines.join("\n"), json!({ "path": dir, "entries": listed })))
+}
+
+/// The programs each part of a shell command line starts
+fn programs(code: &str) -> Vec<&str> {
+    let mut programs = Vec::new();
+    for part in code.split([';', '|', '&', '\n', '(', ')', '`', '{', '}']) {
+        let words = part
+            .split_whitespace()
+            .map(|word| word.trim_matches(['"', '\'']))
+            .filter(|word| !word.contains('='));
+        for word in words {
+            let program = word.rsplit(['/', '\\']).next().unwrap_or(word);
+            programs.push(program);
+            if !WRAPPERS.contains(&program) {
+                break;
+            }
+        }
+    }
+    programs
+}
+
+/// The words of code in a language other than shell, any of which may name
+/// a program it starts
+fn words(code: &str) -> Vec<&str> {
+    code.split(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '-')))
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Whether `phrase` appears in `text` as whole words, so `rm -rf /` is
+/// found in `rm -rf /*` but not in `rm -rf /tmp/build`
+fn contains_phrase(text: &str, phrase: &str) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '/');
+    text.match_indices(phrase).any(|(i, _)| {
+        let before = text[..i].chars().next_back();
+        let after = text[i + phrase.len()..].chars().next();
+        !before.is_some_and(is_word) && !after.is_some_and(is_word)
+    })
+}
+
+/// Refuse code that starts a denied program or contains a denied phrase.
+/// Only shell code can be read for the programs it starts, so code in other
+/// languages is refused for naming a denied program anywhere.
+fn check_command(code: &str, shell: bool, sandbox: &SandboxConfig) -> anyhow::Result<()> {
+    let spaced = code.split_whitespace().collect::<Vec<_>>().join(" ");
+    let programs = if shell { programs(code) } else { words(code) };
+    for denied in &sandbox.denied_commands {
+        let denied = denied.trim();
+        if denied.contains(' ') {
+            if contains_phrase(&spaced, denied) {
+                bail!(
+                    "The sandbox does not allow commands containing '{}'",
+                    denied
+                );
+            }
+        } else if programs.contains(&denied) {
+            bail!("The sandbox does not allow running {}", denied);
+        }
+    }
+    Ok(())
+}
+
+/// The bubblewrap program on the PATH, which confines commands to the
+/// sandbox's folders
+fn bwrap() -> Option<PathBuf> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .map(|dir| dir.join("bwrap"))
+        .find(|path| path.is_file())
+}
+
+/// Refuse to run commands that cannot be confined to the sandbox's folders,
+/// unless the sandbox allows it and is not read-only
+fn check_unconfined(sandbox: &SandboxConfig) -> anyhow::Result<()> {
+    if sandbox.read_only {
+        bail!("A read-only sandbox cannot run commands here; they need bwrap to keep them from writing");
+    }
+    if !sandbox.allow_unconfined {
+        bail!("Commands cannot be confined to the sandbox's folders here (it needs bwrap); set allow_unconfined = true in the sandbox to run them anyway");
+    }
+    Ok(())
+}
+
+/// A command running `program flag code` in the sandbox's folders, the
+/// first of which is `roots[0]`, under its memory and network limits
+#[cfg(unix)]
+fn sandboxed_command(
+    program: &str,
+    flag: &str,
+    code: &str,
+    roots: &[PathBuf],
+    sandbox: &SandboxConfig,
+) -> anyhow::Result<Command> {
+    // `ulimit` caps the memory of the program `exec` then becomes
+    let mut script = String::new();
+    if let Some(mb) = sandbox.max_memory_mb {
+        script.push_str(&format!("ulimit -v {} && ", mb * 1024));
+    }
+    script.push_str("exec \"$@\"");
+    let shell = ["sh", "-c", script.as_str(), "sh", program, flag, code];
+
+    if let Some(bwrap) = bwrap() {
+        let mut command = Command::new(bwrap);
+        command.args([
+            "--die-with-parent",
+            "--unshare-user-try",
+            "--unshare-ipc",
+            "--unshare-pid",
+            "--unshare-uts",
+        ]);
+        if !sandbox.network {
+            command.arg("--unshare-net");
+        }
+        for dir in SYSTEM_DIRS {
+            command.args(["--ro-bind-try", dir, dir]);
+        }
+        command.args(["--proc", "/proc", "--dev", "/dev", "--tmpfs", "/tmp"]);
+        // Bound after the tmpfs, so folders under /tmp stay visible
+        let bind = if sandbox.read_only {
+            "--ro-bind"
+        } else {
+            "--bind"
+        };
+        for root in roots {
+            command.arg(bind).arg(root).arg(root);
+        }
+        command.arg("--chdir").arg(&roots[0]).arg("--").args(shell);
+        return Ok(command);
+    }
+
+    check_unconfined(sandbox)?;
+    let mut command = if sandbox.network {
+        Command::new("sh")
+    } else if cfg!(target_os = "linux") {
+        let mut command = Command::new("unshare");
+        command.args(["--net", "--map-root-user", "--", "sh"]);
+        command
+    } else {
+        bail!("Commands cannot be kept off the network on this platform; set network = true in the sandbox to run them");
+    };
+    command.args(&shell[1..]);
+    Ok(command)
+}
+
+#[cfg(not(unix))]
+fn sandboxed_command(
+    program: &str,
+    flag: &str,
+    code: &str,
+    _roots: &[PathBuf],
+    sandbox: &SandboxConfig,
+) -> anyhow::Result<Command> {
+    check_unconfined(sandbox)?;
+    if !sandbox.network {
+        bail!("Commands cannot be kept off the network on this platform; set network = true in the sandbox to run them");
+    }
+    let mut command = Command::new(program);
+    command.args([flag, code]);
+    Ok(command)
+}
+
+async fn run_code(args: &Value, sandbox: &SandboxConfig) -> anyhow::Result<(String, Value)> {
+    let code = args["code"]
+        .as_str()
+        .filter(|code| !code.trim().is_empty())
+        .context("Pass the code to run")?;
+    let language = args["language"].as_str().unwrap_or("shell").to_lowercase();
+    let (program, flag) = match language.as_str() {
+        "shell" | "sh" | "bash" => {
+            if cfg!(windows) {
+                ("cmd", "/C")
+            } else {
+                ("sh", "-c")
+            }
+        }
+        "python" | "py" => (if cfg!(windows) { "python" } else { "python3" }, "-c"),
+        "javascript" | "js" | "node" => ("node", "-e"),
+        other => bail!(
+            "Unsupported language {}; use shell, python, or javascript",
+            other
+        ),
+    };
+    check_command(code, matches!(program, "sh" | "cmd"), sandbox)?;
+    let roots = roots(sandbox);
+    let dir = roots
+        .first()
+        .context("The sandbox has no folder to run commands in")?;
+    let limit = args["timeout"]
+        .as_u64()
+        .map_or(sandbox.timeout_secs, |t| t.min(sandbox.timeout_secs));
+
+    let mut command = sandboxed_command(program, flag, code, &roots, sandbox)?;
+    command
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    let output = match tokio::time::timeout(Duration::from_secs(limit), command.output()).await {
+        Ok(output) => output.with_context(|| {
+            if sandbox.network || bwrap().is_some() {
+                format!("Cannot run {}", program)
+            } else {
+                format!(
+                    "Cannot run {} without network access here (it needs unshare); \
+                     set network = true in the sandbox to allow it",
+                    program
+                )
+            }
+        })?,
+        Err(_) => bail!("Stopped at the sandbox's {}s time limit", limit),
+    };
+
+    let mut bytes = output.stdout;
+    if !output.stderr.is_empty() {
+        if !bytes.is_empty() {
+            bytes.push(b'\n');
+        }
+        bytes.extend_from_slice(&output.stderr);
+    }
+    let (mut text, truncated) = cut(&bytes, sandbox.max_output_bytes);
+    if truncated {
+        text.push_str("\n\n[Output cut short]");
+    }
+    if !output.status.success() {
+        bail!("Exited with {}\n{}", output.status, text);
+    }
+    let data = json!({ "exit_code": output.status.code(), "truncated": truncated });
+    Ok((text, data))
+}
+
+async fn http_request(args: &Value, sandbox: &SandboxConfig) -> anyhow::Result<(String, Value)> {
+    if !sandbox.network {
+        bail!("The sandbox does not allow network access");
+    }
+    let url = reqwest::Url::parse(args["url"].as_str().context("Pass a url")?)?;
+    if !matches!(url.scheme(), "http" | "https") {
+        bail!("Only http and https URLs can be requested");
+    }
+    let method: reqwest::Method = args["method"]
+        .as_str()
+        .unwrap_or("GET")
+        .to_uppercase()
+        .parse()?;
+    let headers = match &args["headers"] {
+        Value::String(text) if !text.trim().is_empty() => {
+            serde_json::from_str(text).context("headers must be a JSON object")?
+        }
+        other => other.clone(),
+    };
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(sandbox.timeout_secs))
+        .build()?;
+    let mut request = client.request(method, url.clone());
+    for (name, value) in headers.as_object().into_iter().flatten() {
+        let value = value
+            .as_str()
+            .map_or_else(|| value.to_string(), String::from);
+        request = request.header(name, value);
+    }
+    if let Some(body) = args["body"].as_str() {
+        request = request.body(body.to_string());
+    }
+
+    let mut response = request.send().await?;
+    let status = response.status();
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() > sandbox.max_output_bytes {
+            break;
+        }
+    }
+    let (mut text, truncated) = cut(&bytes, sandbox.max_output_bytes);
+    if truncated {
+        text.push_str("\n\n[Response cut short]");
+    }
+    if !status.is_success() {
+        bail!("HTTP {}\n{}", status, text);
+    }
+    Ok((
+        text,
+        json!({ "url": url.as_str(), "status": status.as_u16() }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn call(name: &str, args: Value, sandbox: &SandboxConfig) -> ToolResult {
+        let tool = SandboxedTool::all()
+            .into_iter()
+            .find(|t| t.tool.name == name)
+            .unwrap();
+        tool.execute_sandboxed(args, sandbox).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sandbox() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("work");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::write(dir.path().join("secret.txt"), "hidden").unwrap();
+        let mut sandbox = SandboxConfig {
+            paths: vec![root.to_string_lossy().into_owned()],
+            max_output_bytes: 8,
+            ..Default::default()
+        };
+        // Relative paths start at the first sandbox folder
+        let inside = "notes/today.md";
+        let written = call(
+            "write_file",
+            json!({ "path": inside, "content": "0123456789" }),
+            &sandbox,
+        )
+        .await;
+        assert!(written.success, "{}", written.content);
+        let read = call("read_file", json!({ "path": root.join(inside) }), &sandbox).await;
+        assert!(read.content.starts_with("01234567\n"));
+        let listed = call("list_directory", json!({ "recursive": true }), &sandbox).await;
+        assert_eq!(listed.content, "notes/\nnotes/today.md");
+
+        // Paths outside the sandbox are refused, however they are spelled
+        let secret = dir.path().join("secret.txt");
+        let climb = root.join("../secret.txt");
+        let missing_climb = root.join("new/../../secret.txt");
+        for path in [&secret, &climb, &missing_climb] {
+            let read = call("read_file", json!({ "path": path }), &sandbox).await;
+            assert!(!read.success, "{} was readable", path.display());
+        }
+
+        sandbox.read_only = true;
+        let written = call(
+            "write_file",
+            json!({ "path": inside, "content": "x" }),
+            &sandbox,
+        )
+        .await;
+        assert_eq!(written.content, "The sandbox is read-only");
+
+        for code in [
+            "sudo ls",
+            "ls && env FOO=1 /usr/bin/sudo id",
+            "echo $(dd if=x)",
+            "rm  -rf  /",
+        ] {
+            assert!(
+                check_command(code, true, &sandbox).is_err(),
+                "{} was allowed",
+                code
+            );
+        }
+        for code in ["git add . && echo sudo", "rm -rf /tmp/build"] {
+            assert!(
+                check_command(code, true, &sandbox).is_ok(),
+                "{} was refused",
+                code
+            );
+        }
+        // Other languages are refused for naming a denied program at all
+        for (code, language) in [
+            ("import os; os.system(\"sudo id\")", "python"),
+            (
+                "import subprocess; subprocess.run(['/usr/bin/sudo', 'id'])",
+                "python",
+            ),
+            ("require('child_process').execSync('sudo id')", "javascript"),
+            (
+                "require('fs').writeFileSync('x', ''); eval('rm -rf /')",
+                "js",
+            ),
+        ] {
+            let ran = call(
+                "code_execution",
+                json!({ "code": code, "language": language }),
+                &sandbox,
+            )
+            .await;
+            assert!(
+                ran.content.starts_with("The sandbox does not allow"),
+                "{}",
+                code
+            );
+        }
+        assert!(check_command("print(len(sys.argv))", false, &sandbox).is_ok());
+
+        // A read-only sandbox's commands cannot write, confined or not
+        let ran = call(
+            "code_execution",
+            json!({ "code": "echo x > made.txt", "language": "shell" }),
+            &sandbox,
+        )
+        .await;
+        assert!(!ran.success, "{}", ran.content);
+        assert!(!root.join("made.txt").exists());
+        sandbox.read_only = false;
+
+        // Commands cannot read outside the sandbox's folders: they are
+        // confined, or refused where they cannot be
+        let ran = call(
+            "code_execution",
+            json!({ "code": format!("cat {}", secret.display()), "language": "sh" }),
+            &sandbox,
+        )
+        .await;
+        assert!(
+            !ran.success && !ran.content.contains("hidden"),
+            "{}",
+            ran.content
+        );
+        if bwrap().is_none() {
+            assert!(ran.content.contains("allow_unconfined"), "{}", ran.content);
+        }
+
+        let fetched = call(
+            "http_request",
+            json!({ "url": "http://127.0.0.1:9/" }),
+            &sandbox,
+        )
+        .await;
+        assert_eq!(fetched.content, "The sandbox does not allow network access");
+
+        // Commands run in the first folder, within the time limit
+        #[cfg(unix)]
+        {
+            sandbox.network = true;
+            sandbox.allow_unconfined = true;
+            sandbox.timeout_secs = 1;
+            let ran = call(
+                "code_execution",
+                json!({ "code": "pwd", "language": "shell" }),
+                &sandbox,
+            )
+            .await;
+            assert!(ran.success, "{}", ran.content);
+            let slow = call(
+                "code_execution",
+                json!({ "code": "sleep 5", "language": "shell" }),
+                &sandbox,
+            )
+            .await;
+            assert_eq!(slow.content, "Stopped at the sandbox's 1s time limit");
+        }
+    }
+}
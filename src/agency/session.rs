@@ -7,6 +7,7 @@
 #![allow(dead_code)]
 
 use crate::agency::error::{AgencyError, AgencyResult};
+use crate::agency::modality::ContentPart;
 use crate::agency::models::{AgencyMessage, MessageRole, TokenUsage};
 use chrono::{DateTime, Utc};
 use rusqlite::{params, Connection, OptionalExtension};
@@ -122,10 +123,48 @@ impl Session {
         self.messages
             .iter()
             .map(|m| {
-                serde_json::json!({
+                let mut message = serde_json::json!({
                     "role": m.role.to_string(),
                     "content": m.content
-                })
+                });
+                // Images go as OpenAI content parts after the text
+                let images: Vec<_> = m
+                    .attachments
+                    .iter()
+                    .filter_map(|part| match part {
+                        ContentPart::Image(image) => Some(serde_json::json!({
+                            "type": "image_url",
+                            "image_url": { "url": image.url() }
+                        })),
+                        _ => None,
+                    })
+                    .collect();
+                if !images.is_empty() {
+                    let mut parts = vec![serde_json::json!({ "type": "text", "text": m.content })];
+                    parts.extend(images);
+                    message["content"] = serde_json::json!(parts);
+                }
+                // Tool results answer the assistant's calls by ID
+                if !m.tool_calls.is_empty() {
+                    message["tool_calls"] = m
+                        .tool_calls
+                        .iter()
+                        .map(|call| {
+                            serde_json::json!({
+                                "id": call.id,
+                                "type": "function",
+                                "function": {
+                                    "name": call.name,
+                                    "arguments": call.arguments.to_string()
+                                }
+                            })
+                        })
+                        .collect();
+                }
+                if let Some(result) = &m.tool_result {
+                    message["tool_call_id"] = serde_json::json!(result.call_id);
+                }
+                message
             })
             .collect()
     }
@@ -431,6 +470,7 @@ mod tests {
             id: "msg1".to_string(),
             role: MessageRole::User,
             content: "Hello".to_string(),
+            attachments: Vec::new(),
             tool_calls: vec![],
             tool_result: None,
             timestamp: Utc::now(),
@@ -0,0 +1,201 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Session Search Tool
+//!
+//! Runs the `session_search` builtin against the harvest database, so agents
+//! can search the chats `csm harvest` collected, by text or by meaning, and
+//! read any of them in full while they reason.
+
+use crate::agency::error::{AgencyError, AgencyResult};
+use crate::agency::models::ToolResult;
+use crate::agency::tools::{BuiltinTools, Tool, ToolExecutor};
+use crate::commands::{export_session_json, render_export};
+use crate::search::{search, Embedder, HttpEmbedder, SearchFilters, SearchMode, SearchOptions};
+use crate::text::prefix_chars;
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Sessions returned when the model does not ask for a number
+const DEFAULT_LIMIT: usize = 5;
+
+/// Most sessions one search returns
+const MAX_LIMIT: usize = 20;
+
+/// Characters of a fetched session given to the model
+const MAX_TRANSCRIPT_CHARS: usize = 20_000;
+
+/// The `session_search` tool over one harvest database
+pub struct SessionSearch {
+    tool: Tool,
+    db_path: PathBuf,
+}
+
+impl SessionSearch {
+    /// Search the harvest database at `db_path`
+    pub fn new(db_path: PathBuf) -> Self {
+        Self {
+            tool: BuiltinTools::session_search(),
+            db_path,
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for SessionSearch {
+    fn definition(&self) -> &Tool {
+        &self.tool
+    }
+
+    async fn execute(&self, args: Value) -> AgencyResult<ToolResult> {
+        let start = Instant::now();
+        let db_path = self.db_path.clone();
+        // SQLite and the embeddings client both block
+        let outcome = tokio::task::spawn_blocking(move || run(&db_path, &args))
+            .await
+            .map_err(|e| AgencyError::ExecutionFailed(e.to_string()))?;
+
+        let (success, content, data) = match outcome {
+            Ok(data) => {
+                let content = match data["transcript"].as_str() {
+                    Some(transcript) => transcript.to_string(),
+                    None => serde_json::to_string_pretty(&data).unwrap_or_default(),
+                };
+                (true, content, Some(data))
+            }
+            Err(e) => (false, format!("{:#}", e), None),
+        };
+        Ok(ToolResult {
+            call_id: String::new(),
+            name: self.tool.name.clone(),
+            success,
+            content,
+            duration_ms: start.elapsed().as_millis() as u64,
+            data,
+        })
+    }
+}
+
+/// Fetch the session `args` names, or else search for its `query`
+fn run(db_path: &Path, args: &Value) -> anyhow::Result<Value> {
+    if !db_path.exists() {
+        bail!(
+            "No harvest database at {}; run csm harvest run first",
+            db_path.display()
+        );
+    }
+    let conn = Connection::open(db_path)?;
+    if let Some(id) = args["session_id"].as_str().filter(|id| !id.is_empty()) {
+        return fetch(&conn, id);
+    }
+
+    let query = args["query"]
+        .as_str()
+        .filter(|q| !q.trim().is_empty())
+        .context("Pass a query to search, or a session_id to read")?;
+    let mode: SearchMode = args["mode"].as_str().unwrap_or("text").parse()?;
+    let filters = SearchFilters {
+        providers: args["provider"]
+            .as_str()
+            .map(|p| vec![p.to_string()])
+            .unwrap_or_default(),
+        workspace: args["workspace"].as_str().map(String::from),
+        ..Default::default()
+    }
+    .with_dates(args["after"].as_str(), args["before"].as_str())?;
+    let limit = args["limit"]
+        .as_u64()
+        .map_or(DEFAULT_LIMIT, |n| n as usize)
+        .clamp(1, MAX_LIMIT);
+
+    let embedder = match mode {
+        SearchMode::Text => None,
+        SearchMode::Semantic => HttpEmbedder::from_env(),
+    };
+    let options = SearchOptions {
+        query: query.to_string(),
+        mode,
+        filters,
+        limit,
+        ..Default::default()
+    };
+    let results = search(
+        &conn,
+        &options,
+        embedder.as_ref().map(|e| e as &dyn Embedder),
+    )?;
+    Ok(json!({
+        "query": query,
+        "mode": mode,
+        "total": results.total,
+        "sessions": results.hits,
+    }))
+}
+
+/// The transcript of session `id`, cut short if it is very long
+fn fetch(conn: &Connection, id: &str) -> anyhow::Result<Value> {
+    let sessions = export_session_json(conn, None, Some(&[id.to_string()]))?;
+    if sessions.is_empty() {
+        bail!("No harvested session with ID {}", id);
+    }
+    let transcript = render_export(&sessions, "md")?;
+    let kept = prefix_chars(&transcript, MAX_TRANSCRIPT_CHARS);
+    let truncated = kept.len() < transcript.len();
+    let mut text = kept.to_string();
+    if truncated {
+        text.push_str("\n\n[Transcript cut short]");
+    }
+    Ok(json!({
+        "session_id": id,
+        "transcript": text,
+        "truncated": truncated,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_search_and_fetch() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("harvest.db");
+        crate::commands::harvest_init(Some(db.to_str().unwrap()), false).unwrap();
+        let conn = Connection::open(&db).unwrap();
+        let session = json!({
+            "version": 3, "sessionId": "s1", "creationDate": 0, "lastMessageDate": 0,
+            "customTitle": "Tokio runtime questions",
+            "requests": [{
+                "message": { "text": "Why does block_on panic inside a runtime?" },
+                "response": { "value": [{ "value": "Use spawn_blocking instead." }] }
+            }]
+        });
+        conn.execute(
+            "INSERT INTO sessions
+             (id, provider, title, message_count, created_at, updated_at, harvested_at, session_json)
+             VALUES ('s1', 'copilot', 'Tokio runtime questions', 2, 0, 0, 0, ?1)",
+            [session.to_string()],
+        )
+        .unwrap();
+        let tool = SessionSearch::new(db);
+
+        let found = tool
+            .execute(json!({ "query": "tokio", "limit": 50 }))
+            .await
+            .unwrap();
+        assert!(found.success, "{}", found.content);
+        let data = found.data.unwrap();
+        assert_eq!(data["total"], 1);
+        assert_eq!(data["sessions"][0]["id"], "s1");
+
+        let read = tool.execute(json!({ "session_id": "s1" })).await.unwrap();
+        assert!(read.content.contains("Use spawn_blocking instead."));
+
+        let missing = tool.execute(json!({ "session_id": "s2" })).await.unwrap();
+        assert!(!missing.success);
+        assert!(!tool.execute(json!({})).await.unwrap().success);
+    }
+}
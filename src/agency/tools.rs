@@ -8,6 +8,9 @@
 
 use crate::agency::error::AgencyResult;
 use crate::agency::models::ToolResult;
+use crate::agency::sandbox::SandboxedTool;
+use crate::agency::session_search::SessionSearch;
+use crate::config::SandboxConfig;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -73,6 +76,23 @@ impl Tool {
 
     /// Convert to function definition for model API
     pub fn to_function_definition(&self) -> Value {
+        serde_json::json!({
+            "type": "function",
+            "function": {
+                "name": self.name,
+                "description": self.description,
+                "parameters": self.parameters_schema()
+            }
+        })
+    }
+
+    /// JSON schema of the parameters. Tools mounted from MCP servers keep
+    /// their server's schema in the `input_schema` metadata.
+    fn parameters_schema(&self) -> Value {
+        if let Some(schema) = self.metadata.get("input_schema") {
+            return schema.clone();
+        }
+
         let mut properties = serde_json::Map::new();
         let mut required = Vec::new();
 
@@ -99,16 +119,9 @@ impl Tool {
         }
 
         serde_json::json!({
-            "type": "function",
-            "function": {
-                "name": self.name,
-                "description": self.description,
-                "parameters": {
-                    "type": "object",
-                    "properties": properties,
-                    "required": required
-                }
-            }
+            "type": "object",
+            "properties": properties,
+            "required": required
         })
     }
 }
@@ -229,6 +242,18 @@ pub trait ToolExecutor: Send + Sync {
 
     /// Execute the tool with the given arguments
     async fn execute(&self, args: Value) -> AgencyResult<ToolResult>;
+
+    /// Execute the tool for an agent confined by `sandbox`. Tools that
+    /// touch files, run commands, or use the network honor it; the rest
+    /// ignore it.
+    async fn execute_sandboxed(
+        &self,
+        args: Value,
+        sandbox: &SandboxConfig,
+    ) -> AgencyResult<ToolResult> {
+        let _ = sandbox;
+        self.execute(args).await
+    }
 }
 
 /// Type alias for tool execution function
@@ -297,6 +322,12 @@ impl ToolRegistry {
         for tool in BuiltinTools::all() {
             self.register(tool);
         }
+        for executor in SandboxedTool::all() {
+            self.register_with_executor(executor);
+        }
+        if let Ok(db_path) = crate::commands::get_db_path(None) {
+            self.register_with_executor(SessionSearch::new(db_path));
+        }
     }
 }
 
@@ -314,6 +345,7 @@ impl BuiltinTools {
             Self::list_directory(),
             Self::http_request(),
             Self::calculator(),
+            Self::session_search(),
         ]
     }
 
@@ -396,6 +428,22 @@ impl BuiltinTools {
             .category(ToolCategory::Data)
             .build()
     }
+
+    /// Chat history search tool
+    pub fn session_search() -> Tool {
+        ToolBuilder::new("session_search")
+            .description("Search the user's past chat sessions from every AI assistant they use, or read one in full. Pass a query to find sessions, then a session_id from the results to read its conversation.")
+            .string_param("query", "What to look for in past sessions", false)
+            .string_param("session_id", "ID of a session to read instead of searching", false)
+            .string_param("mode", "text for words in the chats, semantic for similar meaning (default: text)", false)
+            .string_param("provider", "Only sessions from this provider, such as copilot or cursor", false)
+            .string_param("workspace", "Only sessions from this workspace, by ID or name", false)
+            .string_param("after", "Only sessions updated on or after this date (YYYY-MM-DD)", false)
+            .string_param("before", "Only sessions updated before this date (YYYY-MM-DD)", false)
+            .number_param("limit", "Maximum number of sessions (default: 5)", false)
+            .category(ToolCategory::Search)
+            .build()
+    }
 }
 
 #[cfg(test)]
@@ -432,6 +480,7 @@ mod tests {
         let registry = ToolRegistry::with_builtins();
         assert!(registry.get("web_search").is_some());
         assert!(registry.get("code_execution").is_some());
+        assert!(registry.get("session_search").is_some());
         assert!(registry.get("nonexistent").is_none());
     }
 }
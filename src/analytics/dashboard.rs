@@ -9,6 +9,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
+use super::quality::{QualityReport, SessionScore};
+
 // ============================================================================
 // Dashboard Types
 // ============================================================================
@@ -34,6 +36,8 @@ pub struct TeamDashboard {
     pub session_analytics: SessionAnalytics,
     /// Collaboration metrics
     pub collaboration: CollaborationMetrics,
+    /// Quality and sentiment by provider, project, model, and month
+    pub quality: QualityReport,
 }
 
 /// Analytics time period
@@ -347,6 +351,9 @@ impl AnalyticsEngine {
         // Calculate collaboration metrics
         let collaboration = self.calculate_collaboration_metrics(&period_sessions, member_data);
 
+        // Calculate quality and sentiment trends
+        let quality = self.calculate_quality_trends(&period_sessions, start_date);
+
         let dashboard = TeamDashboard {
             team_id,
             generated_at: Utc::now(),
@@ -357,6 +364,7 @@ impl AnalyticsEngine {
             provider_breakdown,
             session_analytics,
             collaboration,
+            quality,
         };
 
         // Cache dashboard
@@ -655,6 +663,26 @@ impl AnalyticsEngine {
         }
     }
 
+    fn calculate_quality_trends(
+        &self,
+        sessions: &[&SessionAnalyticsData],
+        start_date: DateTime<Utc>,
+    ) -> QualityReport {
+        let scores: Vec<SessionScore> = sessions
+            .iter()
+            .map(|s| SessionScore {
+                session_id: s.session_id.clone(),
+                provider: s.provider.clone(),
+                project: s.project.clone(),
+                model: s.model.clone(),
+                updated_at: s.created_at,
+                quality: s.quality_score,
+                sentiment: s.sentiment,
+            })
+            .collect();
+        QualityReport::from_scores(start_date, &scores)
+    }
+
     /// Clear cache
     pub fn clear_cache(&mut self) {
         self.cache.clear();
@@ -678,12 +706,15 @@ pub struct SessionAnalyticsData {
     pub session_id: String,
     pub owner_id: Uuid,
     pub provider: String,
+    pub project: Option<String>,
     pub model: Option<String>,
     pub message_count: u32,
     pub token_count: u32,
     pub created_at: DateTime<Utc>,
     pub tags: Vec<String>,
     pub quality_score: u8,
+    /// -1 to 1, how the user's messages read
+    pub sentiment: f32,
     pub is_shared: bool,
     pub comment_count: u32,
 }
@@ -717,12 +748,14 @@ mod tests {
             session_id: "session-1".to_string(),
             owner_id,
             provider: "copilot".to_string(),
+            project: Some("api".to_string()),
             model: Some("gpt-4".to_string()),
             message_count: 10,
             token_count: 500,
             created_at: Utc::now(),
             tags: vec!["rust".to_string()],
             quality_score: 85,
+            sentiment: -0.5,
             is_shared: false,
             comment_count: 0,
         }];
@@ -737,5 +770,7 @@ mod tests {
 
         assert_eq!(dashboard.overview.total_sessions, 1);
         assert_eq!(dashboard.overview.total_messages, 10);
+        assert_eq!(dashboard.quality.models[0].name, "gpt-4");
+        assert_eq!(dashboard.quality.providers[0].negative_sessions, 1);
     }
 }
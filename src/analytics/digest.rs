@@ -0,0 +1,503 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Activity digest
+//!
+//! A [`Digest`] sums up a week (or any period) of the harvest database: new
+//! sessions and messages per provider, the topics worked on most, and the
+//! longest sessions. `csm report digest` prints it or sends it as an HTML
+//! email, and the `send_digest` automation action sends it on a schedule.
+//! Topics come from `session_topics` (see `csm intelligence topics`);
+//! sessions not filed there yet are read with the `[topics]` taxonomy.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::datetime::{format, format_date, from_epoch};
+use crate::error::Result;
+use crate::intelligence::TopicExtractor;
+use crate::providers::GenericSession;
+use crate::storage::parse_session_json;
+
+/// New sessions and messages of one provider
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProviderActivity {
+    pub provider: String,
+    /// Sessions started in the period
+    pub new_sessions: u64,
+    /// Messages written in the period, in new or older sessions
+    pub messages: u64,
+}
+
+/// A topic and how many sessions of the period are filed under it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TopicActivity {
+    pub topic: String,
+    pub sessions: u64,
+}
+
+/// One of the longest sessions of the period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NotableSession {
+    pub session_id: String,
+    pub title: String,
+    pub provider: String,
+    pub workspace: Option<String>,
+    pub messages: u64,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What happened in the harvest database between `since` and `until`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Digest {
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub new_sessions: u64,
+    /// New sessions in the period of the same length before this one
+    pub previous_new_sessions: u64,
+    /// Sessions updated in the period
+    pub active_sessions: u64,
+    pub messages: u64,
+    /// Most new sessions first
+    pub providers: Vec<ProviderActivity>,
+    /// Most sessions first
+    pub topics: Vec<TopicActivity>,
+    /// Most messages first
+    pub long_sessions: Vec<NotableSession>,
+}
+
+impl Digest {
+    /// The activity between `since` and `until`, with at most `limit` topics
+    /// and long sessions
+    pub fn load(
+        conn: &Connection,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Self> {
+        let (from, to) = (since.timestamp_millis(), until.timestamp_millis());
+        let previous = from - (to - from);
+
+        let mut providers: BTreeMap<String, ProviderActivity> = BTreeMap::new();
+        let mut stmt = conn.prepare(
+            "SELECT provider, COUNT(*) FROM sessions
+             WHERE created_at >= ?1 AND created_at < ?2 GROUP BY provider",
+        )?;
+        let rows = stmt.query_map(params![from, to], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        for row in rows {
+            let (provider, count) = row?;
+            let activity = providers.entry(provider.clone()).or_default();
+            activity.provider = provider;
+            activity.new_sessions = count as u64;
+        }
+        let mut stmt = conn.prepare(
+            "SELECT s.provider, COUNT(*) FROM messages_v2 m JOIN sessions s ON s.id = m.session_id
+             WHERE COALESCE(m.timestamp, s.updated_at) >= ?1
+               AND COALESCE(m.timestamp, s.updated_at) < ?2
+             GROUP BY s.provider",
+        )?;
+        let rows = stmt.query_map(params![from, to], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        for row in rows {
+            let (provider, count) = row?;
+            let activity = providers.entry(provider.clone()).or_default();
+            activity.provider = provider;
+            activity.messages = count as u64;
+        }
+        let mut providers: Vec<ProviderActivity> = providers.into_values().collect();
+        providers.sort_by(|a, b| {
+            b.new_sessions
+                .cmp(&a.new_sessions)
+                .then(b.messages.cmp(&a.messages))
+        });
+
+        let previous_new_sessions: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM sessions WHERE created_at >= ?1 AND created_at < ?2",
+            params![previous, from],
+            |row| row.get(0),
+        )?;
+        let active: Vec<String> = {
+            let mut stmt =
+                conn.prepare("SELECT id FROM sessions WHERE updated_at >= ?1 AND updated_at < ?2")?;
+            let rows = stmt.query_map(params![from, to], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+
+        let mut stmt = conn.prepare(
+            "SELECT id, title, provider, COALESCE(workspace_name, workspace_id), message_count,
+                    updated_at
+             FROM sessions
+             WHERE updated_at >= ?1 AND updated_at < ?2 AND message_count > 0
+             ORDER BY message_count DESC, updated_at DESC LIMIT ?3",
+        )?;
+        let rows = stmt.query_map(params![from, to, limit as i64], |row| {
+            Ok(NotableSession {
+                session_id: row.get(0)?,
+                title: row.get(1)?,
+                provider: row.get(2)?,
+                workspace: row.get(3)?,
+                messages: row.get::<_, i64>(4)? as u64,
+                updated_at: from_epoch(row.get(5)?).unwrap_or_default(),
+            })
+        })?;
+        let long_sessions = rows.collect::<rusqlite::Result<_>>()?;
+
+        Ok(Self {
+            since,
+            until,
+            new_sessions: providers.iter().map(|p| p.new_sessions).sum(),
+            previous_new_sessions: previous_new_sessions as u64,
+            active_sessions: active.len() as u64,
+            messages: providers.iter().map(|p| p.messages).sum(),
+            providers,
+            topics: session_topics(conn, &active, limit)?,
+            long_sessions,
+        })
+    }
+
+    /// Whether nothing happened in the period
+    pub fn is_empty(&self) -> bool {
+        self.active_sessions == 0 && self.messages == 0
+    }
+
+    /// `Chasm digest: 12 new sessions, Oct 10 - Oct 16`
+    pub fn subject(&self) -> String {
+        format!(
+            "Chasm digest: {} new session{}, {}",
+            self.new_sessions,
+            if self.new_sessions == 1 { "" } else { "s" },
+            self.period()
+        )
+    }
+
+    fn period(&self) -> String {
+        // `until` itself is not part of the period
+        let last = self.until - chrono::Duration::milliseconds(1);
+        format!(
+            "{} - {}",
+            format(self.since, "%b %-d"),
+            format(last, "%b %-d")
+        )
+    }
+
+    /// How the new sessions compare with the period before: `+4 on the
+    /// period before`
+    fn change(&self) -> String {
+        let change = self.new_sessions as i64 - self.previous_new_sessions as i64;
+        match change {
+            0 => "as many as the period before".to_string(),
+            _ => format!("{:+} on the period before", change),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\n\n", self.subject());
+        text.push_str(&format!(
+            "{} new sessions ({}), {} active, {} messages\n",
+            self.new_sessions,
+            self.change(),
+            self.active_sessions,
+            self.messages
+        ));
+        if !self.providers.is_empty() {
+            text.push_str("\nBy provider\n");
+            for p in &self.providers {
+                text.push_str(&format!(
+                    "  {}: {} new sessions, {} messages\n",
+                    p.provider, p.new_sessions, p.messages
+                ));
+            }
+        }
+        if !self.topics.is_empty() {
+            text.push_str("\nTop topics\n");
+            for t in &self.topics {
+                text.push_str(&format!("  {} ({} sessions)\n", t.topic, t.sessions));
+            }
+        }
+        if !self.long_sessions.is_empty() {
+            text.push_str("\nLongest sessions\n");
+            for s in &self.long_sessions {
+                text.push_str(&format!(
+                    "  {} - {} messages, {} {}\n",
+                    s.title,
+                    s.messages,
+                    s.provider,
+                    format_date(s.updated_at)
+                ));
+            }
+        }
+        text
+    }
+
+    /// The digest as an HTML email, with inline styles since mail clients
+    /// drop style sheets
+    pub fn to_html(&self) -> String {
+        const CELL: &str = "padding:4px 12px 4px 0;border-bottom:1px solid #e5e7eb;";
+        const HEAD: &str = "padding:4px 12px 4px 0;text-align:left;color:#6b7280;font-weight:600;";
+        let table = |headers: &[&str], rows: Vec<Vec<String>>| {
+            let mut html = String::from("<table style=\"border-collapse:collapse;\"><tr>");
+            for header in headers {
+                html.push_str(&format!("<th style=\"{}\">{}</th>", HEAD, header));
+            }
+            html.push_str("</tr>");
+            for row in rows {
+                html.push_str("<tr>");
+                for cell in row {
+                    html.push_str(&format!("<td style=\"{}\">{}</td>", CELL, cell));
+                }
+                html.push_str("</tr>");
+            }
+            html.push_str("</table>");
+            html
+        };
+
+        let mut html = format!(
+            "<!DOCTYPE html><html><body style=\"font-family:-apple-system,Segoe UI,sans-serif;\
+             color:#111827;\"><h2>Chasm digest</h2><p style=\"color:#6b7280;\">{}</p>",
+            escape(&self.period())
+        );
+        html.push_str(&format!(
+            "<p><b style=\"font-size:20px;\">{}</b> new sessions ({}), \
+             <b>{}</b> active, <b>{}</b> messages</p>",
+            self.new_sessions,
+            escape(&self.change()),
+            self.active_sessions,
+            self.messages
+        ));
+        if self.is_empty() {
+            html.push_str("<p>No chat activity in this period.</p>");
+        }
+        if !self.providers.is_empty() {
+            html.push_str("<h3>By provider</h3>");
+            html.push_str(&table(
+                &["Provider", "New sessions", "Messages"],
+                self.providers
+                    .iter()
+                    .map(|p| {
+                        vec![
+                            escape(&p.provider),
+                            p.new_sessions.to_string(),
+                            p.messages.to_string(),
+                        ]
+                    })
+                    .collect(),
+            ));
+        }
+        if !self.topics.is_empty() {
+            html.push_str("<h3>Top topics</h3>");
+            html.push_str(&table(
+                &["Topic", "Sessions"],
+                self.topics
+                    .iter()
+                    .map(|t| vec![escape(&t.topic), t.sessions.to_string()])
+                    .collect(),
+            ));
+        }
+        if !self.long_sessions.is_empty() {
+            html.push_str("<h3>Longest sessions</h3>");
+            html.push_str(&table(
+                &["Session", "Provider", "Workspace", "Messages", "Updated"],
+                self.long_sessions
+                    .iter()
+                    .map(|s| {
+                        vec![
+                            format!(
+                                "{}<br><span style=\"color:#9ca3af;font-size:12px;\">{}</span>",
+                                escape(&s.title),
+                                escape(&s.session_id)
+                            ),
+                            escape(&s.provider),
+                            escape(s.workspace.as_deref().unwrap_or("")),
+                            s.messages.to_string(),
+                            format_date(s.updated_at),
+                        ]
+                    })
+                    .collect(),
+            ));
+        }
+        html.push_str(
+            "<p style=\"color:#9ca3af;font-size:12px;\">Sent by csm report digest</p>\
+             </body></html>",
+        );
+        html
+    }
+}
+
+/// The most common topics of the sessions, from `session_topics` or, for
+/// sessions not filed there, the `[topics]` taxonomy
+fn session_topics(
+    conn: &Connection,
+    sessions: &[String],
+    limit: usize,
+) -> Result<Vec<TopicActivity>> {
+    crate::topics::ensure_topics_table(conn)?;
+    let wanted: HashSet<&str> = sessions.iter().map(String::as_str).collect();
+    let mut stored: HashMap<String, Vec<String>> = HashMap::new();
+    let mut stmt = conn.prepare("SELECT session_id, topic FROM session_topics")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+    for row in rows {
+        let (session_id, topic) = row?;
+        if wanted.contains(session_id.as_str()) {
+            stored.entry(session_id).or_default().push(topic);
+        }
+    }
+
+    let extractor = TopicExtractor::from_config(&crate::config::current().topics).ok();
+    let mut counts: HashMap<String, u64> = HashMap::new();
+    for id in sessions {
+        let topics = match (stored.remove(id), &extractor) {
+            (Some(topics), _) => topics,
+            (None, Some(extractor)) => {
+                let json: String = conn.query_row(
+                    "SELECT session_json FROM sessions WHERE id = ?1",
+                    [id],
+                    |row| row.get(0),
+                )?;
+                let Ok(session) = parse_session_json(&json) else {
+                    continue;
+                };
+                let text: Vec<String> = GenericSession::from(session)
+                    .messages
+                    .into_iter()
+                    .map(|message| message.content)
+                    .collect();
+                extractor
+                    .extract_text(&text.join("\n"))
+                    .into_iter()
+                    .map(|topic| topic.name)
+                    .collect()
+            }
+            (None, None) => continue,
+        };
+        for topic in topics {
+            *counts.entry(topic).or_default() += 1;
+        }
+    }
+
+    let mut topics: Vec<TopicActivity> = counts
+        .into_iter()
+        .map(|(topic, sessions)| TopicActivity { topic, sessions })
+        .collect();
+    topics.sort_by(|a, b| b.sessions.cmp(&a.sessions).then(a.topic.cmp(&b.topic)));
+    topics.truncate(limit);
+    Ok(topics)
+}
+
+/// Text as HTML shows it literally
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_digest() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE sessions (id TEXT PRIMARY KEY, provider TEXT NOT NULL,
+                 workspace_id TEXT, workspace_name TEXT, title TEXT NOT NULL,
+                 message_count INTEGER DEFAULT 0, created_at INTEGER NOT NULL,
+                 updated_at INTEGER NOT NULL, session_json TEXT NOT NULL);
+             CREATE TABLE messages_v2 (id INTEGER PRIMARY KEY, session_id TEXT NOT NULL,
+                 role TEXT NOT NULL, timestamp INTEGER);",
+        )
+        .unwrap();
+        let until = Utc::now();
+        let since = until - Duration::days(7);
+        let day = |days: i64| (until - Duration::days(days)).timestamp_millis();
+        let sessions = [
+            ("s1", "copilot", "Fix the <parser>", 12, day(2), day(1)),
+            ("s2", "copilot", "Add caching", 4, day(3), day(3)),
+            ("s3", "cursor", "Old question", 30, day(20), day(5)),
+            ("s4", "cursor", "Last week", 2, day(10), day(9)),
+        ];
+        for (id, provider, title, messages, created, updated) in sessions {
+            conn.execute(
+                "INSERT INTO sessions (id, provider, workspace_name, title, message_count,
+                     created_at, updated_at, session_json)
+                 VALUES (?1, ?2, 'chasm', ?3, ?4, ?5, ?6, '{}')",
+                params![id, provider, title, messages, created, updated],
+            )
+            .unwrap();
+        }
+        for (session, at) in [
+            ("s1", day(1)),
+            ("s1", day(2)),
+            ("s3", day(5)),
+            ("s4", day(9)),
+        ] {
+            conn.execute(
+                "INSERT INTO messages_v2 (session_id, role, timestamp) VALUES (?1, 'user', ?2)",
+                params![session, at],
+            )
+            .unwrap();
+        }
+        crate::topics::ensure_topics_table(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO session_topics (session_id, topic, confidence) VALUES
+                 ('s1', 'lang/rust', 0.5), ('s2', 'lang/rust', 0.5), ('s3', 'testing', 0.5),
+                 ('s4', 'testing', 0.5);",
+        )
+        .unwrap();
+
+        let digest = Digest::load(&conn, since, until, 2).unwrap();
+        assert_eq!(digest.new_sessions, 2);
+        assert_eq!(digest.previous_new_sessions, 1);
+        assert_eq!(digest.active_sessions, 3);
+        assert_eq!(digest.messages, 3);
+        assert_eq!(
+            digest.providers,
+            [
+                ProviderActivity {
+                    provider: "copilot".to_string(),
+                    new_sessions: 2,
+                    messages: 2,
+                },
+                ProviderActivity {
+                    provider: "cursor".to_string(),
+                    new_sessions: 0,
+                    messages: 1,
+                },
+            ]
+        );
+        assert_eq!(
+            digest.topics,
+            [
+                TopicActivity {
+                    topic: "lang/rust".to_string(),
+                    sessions: 2,
+                },
+                TopicActivity {
+                    topic: "testing".to_string(),
+                    sessions: 1,
+                },
+            ]
+        );
+        let long: Vec<&str> = digest
+            .long_sessions
+            .iter()
+            .map(|s| s.session_id.as_str())
+            .collect();
+        assert_eq!(long, ["s3", "s1"]);
+
+        assert!(digest
+            .subject()
+            .starts_with("Chasm digest: 2 new sessions, "));
+        let html = digest.to_html();
+        assert!(html.contains("Fix the &lt;parser&gt;"));
+        assert!(html.contains("+1 on the period before"));
+        assert!(digest.to_text().contains("  lang/rust (2 sessions)\n"));
+    }
+}
@@ -5,7 +5,11 @@
 //! Provides team and session analytics with reporting capabilities.
 
 pub mod dashboard;
+pub mod digest;
+pub mod quality;
 pub mod reports;
 
 pub use dashboard::*;
+pub use digest::*;
+pub use quality::*;
 pub use reports::*;
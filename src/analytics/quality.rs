@@ -0,0 +1,288 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Quality and sentiment trends
+//!
+//! Scores harvested sessions with [`QualityScorer`] and [`SentimentAnalyzer`]
+//! and averages the scores per provider, project, model, and month, so the
+//! tools that leave you frustrated stand out.
+
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::datetime::{self, from_epoch};
+use crate::error::Result;
+use crate::intelligence::{Exchange, QualityScorer, SentimentAnalyzer, NEGATIVE_SENTIMENT};
+
+/// The scores of one session
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionScore {
+    pub session_id: String,
+    pub provider: String,
+    /// Workspace name, or ID when it has none
+    pub project: Option<String>,
+    /// Model that answered most of the session
+    pub model: Option<String>,
+    pub updated_at: DateTime<Utc>,
+    /// 0-100, from [`QualityScorer`]
+    pub quality: u8,
+    /// -1 to 1, from [`SentimentAnalyzer`] on the user's messages
+    pub sentiment: f32,
+}
+
+/// Average scores of the sessions sharing a provider, project, model, or month
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QualityTrend {
+    pub name: String,
+    pub sessions: u64,
+    pub avg_quality: f64,
+    pub avg_sentiment: f64,
+    /// Sessions whose user messages read as negative
+    pub negative_sessions: u64,
+}
+
+impl QualityTrend {
+    /// Share (0-1) of sessions that read as negative
+    pub fn negative_share(&self) -> f64 {
+        if self.sessions == 0 {
+            0.0
+        } else {
+            self.negative_sessions as f64 / self.sessions as f64
+        }
+    }
+
+    fn add(&mut self, quality: u8, sentiment: f32) {
+        // Running means, so no sums need keeping
+        self.sessions += 1;
+        let n = self.sessions as f64;
+        self.avg_quality += (quality as f64 - self.avg_quality) / n;
+        self.avg_sentiment += (sentiment as f64 - self.avg_sentiment) / n;
+        if sentiment < NEGATIVE_SENTIMENT {
+            self.negative_sessions += 1;
+        }
+    }
+}
+
+/// Quality and sentiment of sessions since a moment, overall and by
+/// provider, project, model, and month
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityReport {
+    pub since: DateTime<Utc>,
+    pub total: QualityTrend,
+    /// Most frustrating first
+    pub providers: Vec<QualityTrend>,
+    /// Most frustrating first
+    pub projects: Vec<QualityTrend>,
+    /// Most frustrating first
+    pub models: Vec<QualityTrend>,
+    /// `YYYY-MM`, oldest first
+    pub months: Vec<QualityTrend>,
+}
+
+impl QualityReport {
+    /// Score the harvested sessions updated since `since`, optionally from
+    /// one provider only
+    pub fn load(conn: &Connection, since: DateTime<Utc>, provider: Option<&str>) -> Result<Self> {
+        let scores = score_sessions(conn, since, provider)?;
+        Ok(Self::from_scores(since, &scores))
+    }
+
+    pub fn from_scores(since: DateTime<Utc>, scores: &[SessionScore]) -> Self {
+        let mut total = QualityTrend {
+            name: "total".to_string(),
+            ..Default::default()
+        };
+        let mut providers: BTreeMap<String, QualityTrend> = BTreeMap::new();
+        let mut projects: BTreeMap<String, QualityTrend> = BTreeMap::new();
+        let mut models: BTreeMap<String, QualityTrend> = BTreeMap::new();
+        let mut months: BTreeMap<String, QualityTrend> = BTreeMap::new();
+        for score in scores {
+            let keys = [
+                (&mut providers, Some(score.provider.clone())),
+                (&mut projects, score.project.clone()),
+                (&mut models, score.model.clone()),
+                (&mut months, Some(datetime::format(score.updated_at, "%Y-%m"))),
+            ];
+            for (trends, key) in keys {
+                let Some(key) = key else { continue };
+                let trend = trends.entry(key.clone()).or_default();
+                trend.name = key;
+                trend.add(score.quality, score.sentiment);
+            }
+            total.add(score.quality, score.sentiment);
+        }
+
+        let frustrating_first = |trends: BTreeMap<String, QualityTrend>| {
+            let mut trends: Vec<QualityTrend> = trends.into_values().collect();
+            trends.sort_by(|a, b| {
+                a.avg_sentiment
+                    .total_cmp(&b.avg_sentiment)
+                    .then(b.sessions.cmp(&a.sessions))
+            });
+            trends
+        };
+        Self {
+            since,
+            total,
+            providers: frustrating_first(providers),
+            projects: frustrating_first(projects),
+            models: frustrating_first(models),
+            months: months.into_values().collect(),
+        }
+    }
+}
+
+/// Score every harvested session updated since `since` from its messages
+pub fn score_sessions(
+    conn: &Connection,
+    since: DateTime<Utc>,
+    provider: Option<&str>,
+) -> Result<Vec<SessionScore>> {
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.provider, COALESCE(s.workspace_name, s.workspace_id), s.updated_at,
+                m.role, m.content_raw, m.is_canceled, m.model_id
+         FROM sessions s LEFT JOIN messages_v2 m ON m.session_id = s.id
+         WHERE s.updated_at >= ?1 AND (?2 IS NULL OR LOWER(s.provider) = LOWER(?2))
+         ORDER BY s.id, m.message_index, m.id",
+    )?;
+    let rows = stmt.query_map(rusqlite::params![since.timestamp_millis(), provider], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<String>>(2)?,
+            row.get::<_, i64>(3)?,
+            row.get::<_, Option<String>>(4)?,
+            row.get::<_, Option<String>>(5)?,
+            row.get::<_, Option<bool>>(6)?,
+            row.get::<_, Option<String>>(7)?,
+        ))
+    })?;
+
+    let mut sessions: Vec<(SessionScore, Vec<Message>)> = Vec::new();
+    for row in rows {
+        let (id, provider, project, updated_at, role, content, canceled, model) = row?;
+        if sessions.last().map(|(s, _)| &s.session_id) != Some(&id) {
+            let score = SessionScore {
+                session_id: id,
+                provider,
+                project,
+                model: None,
+                updated_at: from_epoch(updated_at).unwrap_or_default(),
+                quality: 0,
+                sentiment: 0.0,
+            };
+            sessions.push((score, Vec::new()));
+        }
+        if let (Some(role), Some(content)) = (role, content) {
+            let messages = &mut sessions.last_mut().unwrap().1;
+            messages.push(Message {
+                role,
+                content,
+                canceled: canceled.unwrap_or(false),
+                model,
+            });
+        }
+    }
+
+    let scorer = QualityScorer::new();
+    let sentiment = SentimentAnalyzer::new();
+    Ok(sessions
+        .into_iter()
+        .map(|(mut score, messages)| {
+            let exchanges: Vec<Exchange> = messages
+                .iter()
+                .enumerate()
+                .filter(|(_, m)| m.role == "user")
+                .map(|(i, m)| {
+                    let answer = messages.get(i + 1).filter(|next| next.role == "assistant");
+                    Exchange {
+                        prompt: &m.content,
+                        answered: answer.is_some(),
+                        canceled: answer.is_some_and(|a| a.canceled),
+                    }
+                })
+                .collect();
+            let prompts: Vec<&str> = exchanges.iter().map(|e| e.prompt).collect();
+            score.quality = scorer.score_exchanges(&exchanges).overall;
+            score.sentiment = sentiment.analyze_text(&prompts.join("\n")).score;
+            score.model = main_model(&messages);
+            score
+        })
+        .collect())
+}
+
+/// A row of `messages_v2`
+struct Message {
+    role: String,
+    content: String,
+    canceled: bool,
+    model: Option<String>,
+}
+
+/// The model named on most of a session's messages
+fn main_model(messages: &[Message]) -> Option<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for model in messages.iter().filter_map(|m| m.model.as_deref()) {
+        *counts.entry(model).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(a.0)))
+        .map(|(model, _)| model.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quality_report() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE sessions (
+                id TEXT PRIMARY KEY, provider TEXT NOT NULL, workspace_id TEXT,
+                workspace_name TEXT, updated_at INTEGER NOT NULL
+            );
+            CREATE TABLE messages_v2 (
+                id INTEGER PRIMARY KEY, session_id TEXT NOT NULL, message_index INTEGER NOT NULL,
+                role TEXT NOT NULL, content_raw TEXT NOT NULL, is_canceled INTEGER DEFAULT 0,
+                model_id TEXT
+            );
+            INSERT INTO sessions VALUES
+                ('a', 'copilot', 'w1', 'api', 1717200000000),
+                ('b', 'cursor', 'w2', NULL, 1719800000000),
+                ('c', 'cursor', 'w2', NULL, 1719900000000);
+            INSERT INTO messages_v2 (session_id, message_index, role, content_raw, is_canceled, model_id) VALUES
+                ('a', 0, 'user', 'Write a test for the parser, thanks', 0, NULL),
+                ('a', 1, 'assistant', 'Here it is', 0, 'gpt-4o'),
+                ('b', 0, 'user', 'This is still broken and the fix is wrong', 0, NULL),
+                ('b', 1, 'assistant', 'Sorry', 1, 'claude-sonnet'),
+                ('c', 0, 'user', 'Fix the bug', 0, NULL);
+            "#,
+        )
+        .unwrap();
+
+        let since = DateTime::<Utc>::UNIX_EPOCH;
+        let report = QualityReport::load(&conn, since, None).unwrap();
+        assert_eq!(report.total.sessions, 3);
+        assert_eq!(report.total.negative_sessions, 2);
+        // The provider whose sessions read as frustrated comes first
+        let providers: Vec<(&str, u64)> = report
+            .providers
+            .iter()
+            .map(|t| (t.name.as_str(), t.sessions))
+            .collect();
+        assert_eq!(providers, [("cursor", 2), ("copilot", 1)]);
+        assert_eq!(report.providers[0].negative_share(), 1.0);
+        assert_eq!(report.projects[1].name, "api");
+        assert_eq!(report.models[0].name, "claude-sonnet");
+        assert!(report.months.windows(2).all(|w| w[0].name < w[1].name));
+
+        let scores = score_sessions(&conn, since, Some("Copilot")).unwrap();
+        assert_eq!(scores.len(), 1);
+        assert!(scores[0].quality > 60);
+        assert!(scores[0].sentiment > 0.0);
+    }
+}
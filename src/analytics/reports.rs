@@ -10,6 +10,7 @@ use std::io::Write;
 use uuid::Uuid;
 
 use super::dashboard::{TeamDashboard, AnalyticsPeriod, MemberStats, ProviderStats};
+use super::quality::{QualityReport, QualityTrend};
 
 // ============================================================================
 // Report Types
@@ -39,6 +40,8 @@ pub enum ReportType {
     SessionSummary,
     /// Collaboration report
     Collaboration,
+    /// Quality and sentiment trends report
+    QualityTrends,
 }
 
 /// Report request
@@ -263,6 +266,23 @@ impl ReportGenerator {
                     dashboard.collaboration.active_collaborations
                 ));
             }
+            ReportType::QualityTrends => {
+                csv.push_str("Quality Trends Report\n");
+                for (heading, trends) in quality_sections(&dashboard.quality) {
+                    csv.push_str(&format!("\n{}\n", heading));
+                    csv.push_str("Name,Sessions,Avg Quality,Avg Sentiment,Negative\n");
+                    for t in trends {
+                        csv.push_str(&format!(
+                            "{},{},{:.1},{:.2},{:.1}%\n",
+                            t.name,
+                            t.sessions,
+                            t.avg_quality,
+                            t.avg_sentiment,
+                            t.negative_share() * 100.0
+                        ));
+                    }
+                }
+            }
         }
 
         csv
@@ -286,6 +306,9 @@ impl ReportGenerator {
             ReportType::Collaboration => {
                 serde_json::to_string_pretty(&dashboard.collaboration).unwrap_or_default()
             }
+            ReportType::QualityTrends => {
+                serde_json::to_string_pretty(&dashboard.quality).unwrap_or_default()
+            }
         }
     }
 
@@ -429,6 +452,34 @@ impl ReportGenerator {
                 ));
                 html.push_str("</div>\n");
             }
+            ReportType::QualityTrends => {
+                html.push_str("<h2>Overview</h2>\n<div>\n");
+                html.push_str(&self.metric_card(
+                    "Avg Quality",
+                    &format!("{:.0}", dashboard.quality.total.avg_quality),
+                    0.0,
+                ));
+                html.push_str(&self.metric_card(
+                    "Avg Sentiment",
+                    &format!("{:+.2}", dashboard.quality.total.avg_sentiment),
+                    0.0,
+                ));
+                html.push_str("</div>\n");
+
+                for (heading, trends) in quality_sections(&dashboard.quality) {
+                    html.push_str(&format!("<h2>{}</h2>\n", heading));
+                    html.push_str(
+                        "<table>\n<tr><th>Name</th><th>Sessions</th><th>Quality</th><th>Sentiment</th><th>Negative</th></tr>\n"
+                    );
+                    for t in trends {
+                        html.push_str(&format!(
+                            "<tr><td>{}</td><td>{}</td><td>{:.0}</td><td>{:+.2}</td><td>{:.0}%</td></tr>\n",
+                            t.name, t.sessions, t.avg_quality, t.avg_sentiment, t.negative_share() * 100.0
+                        ));
+                    }
+                    html.push_str("</table>\n");
+                }
+            }
         }
 
         html.push_str("<div class=\"footer\">Generated by Chasm Analytics</div>\n");
@@ -474,6 +525,7 @@ impl ReportGenerator {
             ReportType::ProviderUsage => "Provider Usage Report".to_string(),
             ReportType::SessionSummary => "Session Summary Report".to_string(),
             ReportType::Collaboration => "Collaboration Report".to_string(),
+            ReportType::QualityTrends => "Quality Trends Report".to_string(),
         }
     }
 
@@ -485,6 +537,7 @@ impl ReportGenerator {
             ReportType::ProviderUsage => "provider-usage",
             ReportType::SessionSummary => "session-summary",
             ReportType::Collaboration => "collaboration",
+            ReportType::QualityTrends => "quality-trends",
         };
 
         let ext = match request.format {
@@ -499,6 +552,16 @@ impl ReportGenerator {
     }
 }
 
+/// The breakdowns of a quality report, with their headings
+fn quality_sections(quality: &QualityReport) -> [(&'static str, &[QualityTrend]); 4] {
+    [
+        ("By Provider", &quality.providers),
+        ("By Project", &quality.projects),
+        ("By Model", &quality.models),
+        ("By Month", &quality.months),
+    ]
+}
+
 impl Default for ReportGenerator {
     fn default() -> Self {
         Self::new()
@@ -577,6 +640,7 @@ mod tests {
                 active_collaborations: 5,
                 top_collaborators: vec![],
             },
+            quality: QualityReport::from_scores(Utc::now(), &[]),
         }
     }
 
@@ -0,0 +1,443 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Access control for the API server
+//!
+//! Every route except login, token refresh, health, and the REST and GraphQL
+//! docs needs an API key or a JWT access token from `/auth/login`. Anonymous
+//! clients can be given read-only or full access with `csm api serve --anonymous`.
+//!
+//! Each request also gets a [`Scope`] saying whose sessions it can see.
+//! Signed-in users, and API keys issued to a user, see only the sessions
+//! they wrote through the API. API keys issued without a user are admin keys
+//! and see everything, as does every client when authentication is disabled.
+//! Anonymous read-only clients see sessions nobody owns, such as harvested ones.
+//! What a request may do with what it sees depends on its role; see `rbac`.
+
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::{header, Method};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, FromRequest, HttpMessage, HttpRequest, HttpResponse};
+use std::future::{ready, Ready};
+
+use super::auth::{validate_token, AuthenticatedUser};
+use super::keys::{verify_key, ApiKey, KEY_PREFIX};
+use super::rbac::{self, user_role};
+use super::state::AppState;
+use chasm::teams::Role;
+
+/// Routes that never need credentials
+const PUBLIC_PATHS: &[&str] = &[
+    "/api/health",
+    "/api/docs",
+    "/api/openapi.json",
+    "/api/openapi.yaml",
+    "/api/graphql/playground",
+    "/api/graphql/sdl",
+    "/auth/login",
+    "/auth/refresh",
+];
+
+/// Routes whose browser clients cannot set headers (WebSocket and
+/// EventSource), which may pass the credential as `?access_token=`
+const QUERY_TOKEN_PATHS: &[&str] = &["/ws", "/recording/ws", "/sync/subscribe"];
+
+/// Routes that carry every user's data or server-wide settings, which only
+/// admins may use
+const ADMIN_PATHS: &[&str] = &[
+    "/sync",
+    "/ws",
+    "/recording",
+    "/api/mcp",
+    "/api/settings",
+    "/api/webhooks",
+    "/api/audit",
+    "/api/admin",
+];
+
+/// What clients without credentials may do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnonymousAccess {
+    /// Every request needs credentials
+    #[default]
+    Deny,
+    /// GET requests are allowed; writes and WebSockets need credentials
+    ReadOnly,
+    /// Authentication is disabled
+    Allow,
+}
+
+impl AnonymousAccess {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Deny => "deny",
+            Self::ReadOnly => "read-only",
+            Self::Allow => "allow",
+        }
+    }
+}
+
+impl std::str::FromStr for AnonymousAccess {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "deny" => Ok(Self::Deny),
+            "read-only" | "readonly" => Ok(Self::ReadOnly),
+            "allow" => Ok(Self::Allow),
+            _ => anyhow::bail!(
+                "Unknown anonymous access '{}' (expected deny, read-only, or allow)",
+                s
+            ),
+        }
+    }
+}
+
+/// Whose sessions a request can see
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scope {
+    /// Every session: admin keys, and all clients when authentication is off
+    All,
+    /// Sessions owned by this user, or by nobody for `None`
+    Owner(Option<String>),
+}
+
+impl Scope {
+    pub fn is_admin(&self) -> bool {
+        *self == Scope::All
+    }
+
+    /// The owner recorded on sessions written with this scope
+    pub fn owner(&self) -> Option<&str> {
+        match self {
+            Scope::All => None,
+            Scope::Owner(owner) => owner.as_deref(),
+        }
+    }
+
+    /// Whether a session with this owner is visible
+    pub fn can_access(&self, owner: Option<&str>) -> bool {
+        match self {
+            Scope::All => true,
+            Scope::Owner(own) => own.as_deref() == owner,
+        }
+    }
+
+    /// SQL condition limiting rows to this scope by their `column`
+    pub fn condition(&self, column: &str) -> String {
+        match self {
+            Scope::All => "1".to_string(),
+            Scope::Owner(None) => format!("{} IS NULL", column),
+            // User IDs come from our own tables or signed tokens, but are
+            // quoted anyway
+            Scope::Owner(Some(id)) => format!("{} = '{}'", column, id.replace('\'', "''")),
+        }
+    }
+
+    /// SQL condition limiting sessions to those this scope can read: its
+    /// own, and for a signed-in user, those shared with one of their teams.
+    /// `table` is the name or alias of the `sessions` table in the query.
+    pub fn readable(&self, table: &str) -> String {
+        match self {
+            Scope::Owner(Some(id)) => format!(
+                "({t}.user_id = '{id}' OR {t}.team_id IN \
+                 (SELECT team_id FROM team_members WHERE user_id = '{id}'))",
+                t = table,
+                id = id.replace('\'', "''")
+            ),
+            _ => self.condition(&format!("{}.user_id", table)),
+        }
+    }
+}
+
+impl FromRequest for Scope {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let scope = req.extensions().get::<Scope>().cloned();
+        ready(Ok(scope.unwrap_or(Scope::Owner(None))))
+    }
+}
+
+fn admin_only(path: &str) -> bool {
+    ADMIN_PATHS.iter().any(|prefix| {
+        path.strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    })
+}
+
+fn key_scope(key: &ApiKey) -> Scope {
+    match &key.user_id {
+        Some(user_id) => Scope::Owner(Some(user_id.clone())),
+        None => Scope::All,
+    }
+}
+
+fn anonymous_scope(anonymous: AnonymousAccess) -> Scope {
+    match anonymous {
+        AnonymousAccess::Allow => Scope::All,
+        _ => Scope::Owner(None),
+    }
+}
+
+/// The scope of an authenticated request, or of an anonymous one
+fn request_scope(req: &ServiceRequest, anonymous: AnonymousAccess) -> Scope {
+    let extensions = req.extensions();
+    if let Some(key) = extensions.get::<ApiKey>() {
+        return key_scope(key);
+    }
+    if let Some(user) = extensions.get::<AuthenticatedUser>() {
+        return Scope::Owner(Some(user.user_id.clone()));
+    }
+    anonymous_scope(anonymous)
+}
+
+/// The scope of a read-only call made outside actix, such as over gRPC,
+/// from the credential it carried
+#[cfg(feature = "grpc")]
+pub(super) fn credential_scope(
+    conn: &rusqlite::Connection,
+    credential: Option<&str>,
+    anonymous: AnonymousAccess,
+) -> Result<Scope, &'static str> {
+    let Some(credential) = credential else {
+        return match anonymous {
+            AnonymousAccess::Deny => {
+                Err("Authentication required; send an API key or access token")
+            }
+            _ => Ok(anonymous_scope(anonymous)),
+        };
+    };
+    if credential.starts_with(KEY_PREFIX) {
+        return match verify_key(conn, credential) {
+            Ok(Some(key)) => Ok(key_scope(&key)),
+            _ => Err("Invalid or expired credentials"),
+        };
+    }
+    validate_token(credential)
+        .map(|user| Scope::Owner(Some(user.user_id)))
+        .ok_or("Invalid or expired credentials")
+}
+
+/// Server-wide access settings, registered as app data
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AccessPolicy {
+    pub anonymous: AnonymousAccess,
+}
+
+/// Whether an anonymous request is allowed without credentials
+fn allows_anonymous(anonymous: AnonymousAccess, method: &Method, websocket: bool) -> bool {
+    match anonymous {
+        AnonymousAccess::Allow => true,
+        // WebSocket clients can send commands, so they are not read-only
+        AnonymousAccess::ReadOnly => {
+            (method == Method::GET || method == Method::HEAD) && !websocket
+        }
+        AnonymousAccess::Deny => false,
+    }
+}
+
+/// The credential sent with a request, if any
+fn credential(req: &ServiceRequest) -> Option<String> {
+    let headers = req.headers();
+    if let Some(key) = headers.get("X-API-Key").and_then(|v| v.to_str().ok()) {
+        return Some(key.trim().to_string());
+    }
+    if let Some(token) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+    {
+        return Some(token.trim().to_string());
+    }
+    if QUERY_TOKEN_PATHS.contains(&req.path()) {
+        return web::Query::<std::collections::HashMap<String, String>>::from_query(
+            req.query_string(),
+        )
+        .ok()
+        .and_then(|q| q.get("access_token").cloned());
+    }
+    None
+}
+
+/// Check a credential, recording the API key or JWT user for the `/auth`
+/// handlers and the rate limiter, and its role
+fn authenticate(req: &ServiceRequest, credential: &str) -> bool {
+    let state = req.app_data::<web::Data<AppState>>();
+    if credential.starts_with(KEY_PREFIX) {
+        let Some(state) = state else {
+            return false;
+        };
+        let db = state.db.lock().unwrap();
+        return match verify_key(&db.conn, credential) {
+            Ok(Some(key)) => {
+                let mut extensions = req.extensions_mut();
+                extensions.insert(key.role);
+                extensions.insert(key);
+                true
+            }
+            _ => false,
+        };
+    }
+    match validate_token(credential) {
+        Some(user) => {
+            // Looked up on each request so role changes apply to issued tokens
+            let role = state
+                .and_then(|state| user_role(&state.db.lock().unwrap().conn, &user.user_id).ok())
+                .unwrap_or(Role::Member);
+            let mut extensions = req.extensions_mut();
+            extensions.insert(role);
+            extensions.insert(user);
+            true
+        }
+        None => false,
+    }
+}
+
+/// The request line for the access log, with the value of any
+/// `access_token` in the query replaced so tokens stay out of the logs
+pub fn logged_request_line(req: &ServiceRequest) -> String {
+    let mut target = req.path().to_string();
+    let query = req.query_string();
+    if !query.is_empty() {
+        let redacted: Vec<&str> = query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some(("access_token", _)) => "access_token=[redacted]",
+                _ => pair,
+            })
+            .collect();
+        target.push('?');
+        target.push_str(&redacted.join("&"));
+    }
+    format!("{} {} {:?}", req.method(), target, req.version())
+}
+
+fn unauthorized(message: &str) -> HttpResponse {
+    HttpResponse::Unauthorized()
+        .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+        .json(serde_json::json!({
+            "success": false,
+            "error": message,
+        }))
+}
+
+/// Middleware that rejects requests without valid credentials
+pub async fn require_auth(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let policy = req
+        .app_data::<web::Data<AccessPolicy>>()
+        .map(|p| *p.get_ref())
+        .unwrap_or_default();
+
+    if req.method() == Method::OPTIONS || PUBLIC_PATHS.contains(&req.path()) {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+
+    let websocket = req.headers().contains_key(header::UPGRADE);
+    match credential(&req) {
+        Some(credential) => {
+            if !authenticate(&req, &credential) {
+                let response = unauthorized("Invalid or expired credentials");
+                return Ok(req.into_response(response));
+            }
+        }
+        None => {
+            if !allows_anonymous(policy.anonymous, req.method(), websocket) {
+                let message = match policy.anonymous {
+                    AnonymousAccess::ReadOnly => {
+                        "Anonymous access is read-only; send an API key or access token"
+                    }
+                    _ => "Authentication required; send an API key or access token",
+                };
+                return Ok(req.into_response(unauthorized(message)));
+            }
+        }
+    }
+
+    let scope = request_scope(&req, policy.anonymous);
+    if !scope.is_admin() && admin_only(req.path()) {
+        let response = HttpResponse::Forbidden().json(serde_json::json!({
+            "success": false,
+            "error": "This endpoint spans every user's data and needs an admin API key",
+        }));
+        return Ok(req.into_response(response));
+    }
+
+    let role = req
+        .extensions()
+        .get::<Role>()
+        .copied()
+        .unwrap_or_else(|| rbac::anonymous_role(policy.anonymous));
+    let permission = rbac::required_permission(req.method(), req.path(), websocket, &scope);
+    if let Some(permission) = permission.filter(|p| !role.has_permission(*p)) {
+        let response = HttpResponse::Forbidden().json(serde_json::json!({
+            "success": false,
+            "error": format!(
+                "The {} role does not have permission to {}",
+                role,
+                permission.description().to_lowercase()
+            ),
+        }));
+        return Ok(req.into_response(response));
+    }
+    req.extensions_mut().insert(scope);
+
+    Ok(next.call(req).await?.map_into_boxed_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logged_request_line_hides_tokens() {
+        let req = actix_web::test::TestRequest::get()
+            .uri("/ws?room=a&access_token=csm_secret&x=1")
+            .to_srv_request();
+        assert_eq!(
+            logged_request_line(&req),
+            "GET /ws?room=a&access_token=[redacted]&x=1 HTTP/1.1"
+        );
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/sessions")
+            .to_srv_request();
+        assert_eq!(logged_request_line(&req), "GET /api/sessions HTTP/1.1");
+    }
+
+    #[test]
+    fn test_anonymous_access() {
+        use AnonymousAccess::*;
+
+        assert!(!allows_anonymous(Deny, &Method::GET, false));
+        assert!(allows_anonymous(ReadOnly, &Method::GET, false));
+        assert!(!allows_anonymous(ReadOnly, &Method::POST, false));
+        assert!(!allows_anonymous(ReadOnly, &Method::GET, true));
+        assert!(allows_anonymous(Allow, &Method::DELETE, false));
+
+        assert_eq!("read-only".parse::<AnonymousAccess>().unwrap(), ReadOnly);
+        assert!("open".parse::<AnonymousAccess>().is_err());
+    }
+
+    #[test]
+    fn test_scope() {
+        let user = Scope::Owner(Some("o'brien".to_string()));
+        assert!(user.can_access(Some("o'brien")));
+        assert!(!user.can_access(None));
+        assert_eq!(user.condition("user_id"), "user_id = 'o''brien'");
+        assert_eq!(
+            Scope::Owner(None).condition("s.user_id"),
+            "s.user_id IS NULL"
+        );
+        assert!(Scope::All.can_access(Some("anyone")));
+        assert_eq!(Scope::All.owner(), None);
+
+        assert!(admin_only("/sync/delta"));
+        assert!(admin_only("/ws"));
+        assert!(!admin_only("/wsx"));
+        assert!(!admin_only("/api/sessions"));
+    }
+}
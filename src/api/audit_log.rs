@@ -0,0 +1,653 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Audit log of mutating API requests
+//!
+//! Every POST, PUT, PATCH, and DELETE the server answers is recorded in the
+//! `audit_log` table: who sent it, what it touched, when, the status it got,
+//! and for sessions, agents, swarms, accounts, webhooks, and SWE projects a
+//! short summary of the record before and after. Admins read the log at
+//! `GET /api/audit` or with `csm audit list`.
+
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::{header, Method, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage, HttpResponse};
+use anyhow::{Context, Result};
+use colored::*;
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, OptionalExtension, ToSql};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Instant;
+use utoipa::IntoParams;
+
+use super::auth::AuthenticatedUser;
+use super::docs::ApiEnvelope;
+use super::keys::ApiKey;
+use super::pagination::{self, page_json, parse_cursor};
+use super::state::AppState;
+use crate::datetime::DateFilter;
+
+/// POST routes that only read, and are not recorded
+const READ_ONLY_PATHS: &[&str] = &["/api/graphql"];
+
+/// A kind of record whose state is summarized in the log
+struct Resource {
+    kind: &'static str,
+    /// Collection path; the record ID is the next path segment
+    path: &'static str,
+    /// Query returning the summary of the record with ID `?1` as JSON
+    summary: &'static str,
+}
+
+const RESOURCES: &[Resource] = &[
+    Resource {
+        kind: "session",
+        path: "/api/sessions",
+        summary: "SELECT json_object('title', title, 'provider', provider,
+                         'messages', message_count, 'updated_at', updated_at, 'owner', user_id)
+                  FROM sessions WHERE id = ?1",
+    },
+    Resource {
+        kind: "agent",
+        path: "/api/agents",
+        summary: "SELECT json_object('name', name, 'model', model, 'provider', provider,
+                         'active', is_active, 'updated_at', updated_at)
+                  FROM agents WHERE id = ?1",
+    },
+    Resource {
+        kind: "swarm",
+        path: "/api/swarms",
+        summary: "SELECT json_object('name', name, 'orchestration', orchestration,
+                         'status', status, 'updated_at', updated_at)
+                  FROM swarms WHERE id = ?1",
+    },
+    Resource {
+        kind: "account",
+        path: "/api/settings/accounts",
+        summary: "SELECT json_object('provider', provider, 'name', name, 'default', is_default)
+                  FROM provider_accounts WHERE id = ?1",
+    },
+    Resource {
+        kind: "webhook",
+        path: "/api/webhooks",
+        summary: "SELECT json_object('name', name, 'url', url, 'events', json(events),
+                         'enabled', enabled)
+                  FROM webhooks WHERE id = ?1",
+    },
+    Resource {
+        kind: "project",
+        path: "/api/swe/projects",
+        summary: "SELECT json_object('name', name, 'path', path, 'updated_at', updated_at)
+                  FROM swe_projects WHERE id = ?1",
+    },
+];
+
+/// One recorded request
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    /// Milliseconds since the epoch
+    pub timestamp: i64,
+    /// `api_key`, `user`, or `anonymous`
+    pub actor_type: String,
+    /// API key ID or user ID
+    pub actor_id: Option<String>,
+    /// API key name or user email
+    pub actor_name: Option<String>,
+    /// User the request acted for; `None` for admin keys and anonymous clients
+    pub user_id: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    pub ip: Option<String>,
+    pub resource_type: Option<String>,
+    pub resource_id: Option<String>,
+    /// Summary of the record before the request, if it existed
+    pub before: Option<serde_json::Value>,
+    /// Summary of the record after the request, if it still exists
+    pub after: Option<serde_json::Value>,
+    pub duration_ms: u64,
+}
+
+/// Create the audit log table if it does not exist
+pub fn init_audit_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp INTEGER NOT NULL,
+            actor_type TEXT NOT NULL,
+            actor_id TEXT,
+            actor_name TEXT,
+            user_id TEXT,
+            method TEXT NOT NULL,
+            path TEXT NOT NULL,
+            status INTEGER NOT NULL,
+            ip TEXT,
+            resource_type TEXT,
+            resource_id TEXT,
+            before TEXT,
+            after TEXT,
+            duration_ms INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE INDEX IF NOT EXISTS idx_audit_log_timestamp ON audit_log(timestamp);
+        CREATE INDEX IF NOT EXISTS idx_audit_log_resource ON audit_log(resource_type, resource_id);",
+    )
+}
+
+fn insert(conn: &Connection, entry: &AuditEntry) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO audit_log (timestamp, actor_type, actor_id, actor_name, user_id, method,
+                                path, status, ip, resource_type, resource_id, before, after,
+                                duration_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        params![
+            entry.timestamp,
+            entry.actor_type,
+            entry.actor_id,
+            entry.actor_name,
+            entry.user_id,
+            entry.method,
+            entry.path,
+            entry.status,
+            entry.ip,
+            entry.resource_type,
+            entry.resource_id,
+            entry.before.as_ref().map(|v| v.to_string()),
+            entry.after.as_ref().map(|v| v.to_string()),
+            entry.duration_ms as i64,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Which entries to list
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    /// API key ID or name, or user ID or email
+    pub actor: Option<String>,
+    pub method: Option<String>,
+    pub resource_type: Option<String>,
+    pub resource_id: Option<String>,
+    /// Milliseconds since the epoch, inclusive
+    pub after: Option<i64>,
+    /// Milliseconds since the epoch, exclusive
+    pub before: Option<i64>,
+}
+
+impl AuditFilter {
+    /// Set the time range from `--after` / `--before` style values
+    pub fn with_dates(mut self, after: Option<&str>, before: Option<&str>) -> Result<Self> {
+        let range = DateFilter::parse(after, before)?;
+        self.after = range.after.map(|dt| dt.timestamp_millis());
+        self.before = range.before.map(|dt| dt.timestamp_millis());
+        Ok(self)
+    }
+
+    fn sql(&self, params: &mut Vec<(&'static str, Value)>) -> String {
+        let mut conditions = vec!["1"];
+        if let Some(actor) = &self.actor {
+            params.push((":actor", actor.clone().into()));
+            conditions.push("(actor_id = :actor OR actor_name = :actor OR user_id = :actor)");
+        }
+        if let Some(method) = &self.method {
+            params.push((":method", method.to_uppercase().into()));
+            conditions.push("method = :method");
+        }
+        if let Some(resource_type) = &self.resource_type {
+            params.push((":resource_type", resource_type.clone().into()));
+            conditions.push("resource_type = :resource_type");
+        }
+        if let Some(resource_id) = &self.resource_id {
+            params.push((":resource_id", resource_id.clone().into()));
+            conditions.push("resource_id = :resource_id");
+        }
+        if let Some(after) = self.after {
+            params.push((":after", after.into()));
+            conditions.push("timestamp >= :after");
+        }
+        if let Some(before) = self.before {
+            params.push((":before", before.into()));
+            conditions.push("timestamp < :before");
+        }
+        conditions.join(" AND ")
+    }
+}
+
+fn named<'a>(params: &'a [(&'static str, Value)]) -> Vec<(&'static str, &'a dyn ToSql)> {
+    params
+        .iter()
+        .map(|(name, value)| (*name, value as &dyn ToSql))
+        .collect()
+}
+
+fn row_to_entry(row: &rusqlite::Row) -> rusqlite::Result<AuditEntry> {
+    let json = |text: Option<String>| text.and_then(|t| serde_json::from_str(&t).ok());
+    Ok(AuditEntry {
+        id: row.get(0)?,
+        timestamp: row.get(1)?,
+        actor_type: row.get(2)?,
+        actor_id: row.get(3)?,
+        actor_name: row.get(4)?,
+        user_id: row.get(5)?,
+        method: row.get(6)?,
+        path: row.get(7)?,
+        status: row.get(8)?,
+        ip: row.get(9)?,
+        resource_type: row.get(10)?,
+        resource_id: row.get(11)?,
+        before: json(row.get(12)?),
+        after: json(row.get(13)?),
+        duration_ms: row.get::<_, i64>(14)? as u64,
+    })
+}
+
+/// Entries matching `filter`, newest first, with IDs below `below` if given
+pub fn list(
+    conn: &Connection,
+    filter: &AuditFilter,
+    below: Option<i64>,
+    limit: usize,
+) -> rusqlite::Result<Vec<AuditEntry>> {
+    let mut params = Vec::new();
+    let mut conditions = filter.sql(&mut params);
+    if let Some(below) = below {
+        params.push((":below", below.into()));
+        conditions.push_str(" AND id < :below");
+    }
+    params.push((":limit", (limit as i64).into()));
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, timestamp, actor_type, actor_id, actor_name, user_id, method, path, status,
+                ip, resource_type, resource_id, before, after, duration_ms
+         FROM audit_log WHERE {} ORDER BY id DESC LIMIT :limit",
+        conditions
+    ))?;
+    let entries = stmt
+        .query_map(named(&params).as_slice(), row_to_entry)?
+        .collect();
+    entries
+}
+
+/// Number of entries matching `filter`
+pub fn count(conn: &Connection, filter: &AuditFilter) -> rusqlite::Result<i64> {
+    let mut params = Vec::new();
+    let conditions = filter.sql(&mut params);
+    conn.query_row(
+        &format!("SELECT COUNT(*) FROM audit_log WHERE {}", conditions),
+        named(&params).as_slice(),
+        |row| row.get(0),
+    )
+}
+
+// ============================================================================
+// Recording
+// ============================================================================
+
+/// The resource a path refers to, with the record ID if the path names one.
+/// Only the record itself and single-segment actions on it, such as
+/// `/api/sessions/{id}/restore`, count; deeper paths belong to other records.
+fn target(path: &str) -> Option<(&'static Resource, Option<&str>)> {
+    RESOURCES.iter().find_map(|resource| {
+        let rest = path.strip_prefix(resource.path)?;
+        if rest.is_empty() {
+            return Some((resource, None));
+        }
+        let mut segments = rest.strip_prefix('/')?.split('/');
+        let id = segments.next().filter(|id| !id.is_empty())?;
+        (segments.count() <= 1).then_some((resource, Some(id)))
+    })
+}
+
+/// Summary of a record, or `None` if it does not exist
+fn snapshot(conn: &Connection, resource: &Resource, id: &str) -> Option<serde_json::Value> {
+    conn.query_row(resource.summary, [id], |row| row.get::<_, String>(0))
+        .optional()
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+}
+
+/// Who sent a request: `(actor_type, actor_id, actor_name, user_id)`
+type Actor = (&'static str, Option<String>, Option<String>, Option<String>);
+
+fn actor(req: &ServiceRequest) -> Actor {
+    let extensions = req.extensions();
+    if let Some(key) = extensions.get::<ApiKey>() {
+        return (
+            "api_key",
+            Some(key.id.clone()),
+            Some(key.name.clone()),
+            key.user_id.clone(),
+        );
+    }
+    if let Some(user) = extensions.get::<AuthenticatedUser>() {
+        return (
+            "user",
+            Some(user.user_id.clone()),
+            Some(user.email.clone()),
+            Some(user.user_id.clone()),
+        );
+    }
+    ("anonymous", None, None, None)
+}
+
+/// The `id` of a record created by a JSON response, buffering the body
+async fn created_id(
+    res: ServiceResponse<BoxBody>,
+) -> Result<(ServiceResponse<BoxBody>, Option<String>), Error> {
+    let json = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if !json {
+        return Ok((res, None));
+    }
+    let (req, res) = res.into_parts();
+    let (res, body) = res.into_parts();
+    let bytes = actix_web::body::to_bytes(body)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let id = serde_json::from_slice::<serde_json::Value>(&bytes)
+        .ok()
+        .and_then(|v| {
+            v["data"]["id"]
+                .as_str()
+                .or(v["id"].as_str())
+                .map(String::from)
+        });
+    let res = res.set_body(bytes).map_into_boxed_body();
+    Ok((ServiceResponse::new(req, res), id))
+}
+
+/// Middleware that records mutating requests once they are answered. It runs
+/// inside `access::require_auth`, so rejected credentials are not recorded.
+pub async fn record(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let mutating = matches!(
+        *req.method(),
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    );
+    let state = req.app_data::<web::Data<AppState>>().cloned();
+    let (Some(state), true) = (state, mutating && !READ_ONLY_PATHS.contains(&req.path())) else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+
+    let (actor_type, actor_id, actor_name, user_id) = actor(&req);
+    let method = req.method().to_string();
+    let path = req.path().to_string();
+    let ip = req.peer_addr().map(|addr| addr.ip().to_string());
+    let target = target(&path).map(|(resource, id)| (resource, id.map(String::from)));
+    let before = match &target {
+        Some((resource, Some(id))) => snapshot(&state.db.lock().unwrap().conn, resource, id),
+        _ => None,
+    };
+
+    let started = Instant::now();
+    let res = next.call(req).await?.map_into_boxed_body();
+    let status = res.status();
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    let (res, target) = match target {
+        Some((resource, None)) if method == "POST" && status.is_success() => {
+            let (res, id) = created_id(res).await?;
+            (res, Some((resource, id)))
+        }
+        target => (res, target),
+    };
+
+    let db = state.db.lock().unwrap();
+    let after = match &target {
+        Some((resource, Some(id))) => snapshot(&db.conn, resource, id),
+        _ => None,
+    };
+    // IDs that matched no record, like `/api/sessions/bulk`, are not recorded
+    let resource_id = target
+        .as_ref()
+        .and_then(|(_, id)| id.clone())
+        .filter(|_| before.is_some() || after.is_some());
+    let entry = AuditEntry {
+        id: 0,
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        actor_type: actor_type.to_string(),
+        actor_id,
+        actor_name,
+        user_id,
+        method,
+        path,
+        status: status.as_u16(),
+        ip,
+        resource_type: target.map(|(resource, _)| resource.kind.to_string()),
+        resource_id,
+        before,
+        after,
+        duration_ms,
+    };
+    if let Err(e) = insert(&db.conn, &entry) {
+        eprintln!("[WARN] Failed to write audit log entry: {}", e);
+    }
+    Ok(res)
+}
+
+// ============================================================================
+// Handler
+// ============================================================================
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    /// API key ID or name, or user ID or email
+    pub actor: Option<String>,
+    /// HTTP method
+    pub method: Option<String>,
+    /// session, agent, swarm, account, webhook, or project
+    pub resource_type: Option<String>,
+    pub resource_id: Option<String>,
+    /// Entries at or after this date
+    pub after: Option<String>,
+    /// Entries before this date
+    pub before: Option<String>,
+    /// Cursor from the previous page
+    pub cursor: Option<String>,
+    #[serde(alias = "perPage")]
+    pub per_page: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+fn failure(status: StatusCode, message: &str) -> HttpResponse {
+    HttpResponse::build(status).json(serde_json::json!({
+        "success": false,
+        "error": message,
+    }))
+}
+
+/// List audit log entries, newest first
+#[utoipa::path(
+    get,
+    path = "/api/audit",
+    tag = "audit",
+    params(AuditQuery),
+    responses(
+        (status = 200, description = "One page of audit log entries", body = ApiEnvelope),
+        (status = 400, description = "Invalid date or cursor"),
+        (status = 403, description = "Not an admin")
+    )
+)]
+pub async fn list_audit_log(
+    state: web::Data<AppState>,
+    query: web::Query<AuditQuery>,
+) -> HttpResponse {
+    let query = query.into_inner();
+    let filter = AuditFilter {
+        actor: query.actor,
+        method: query.method,
+        resource_type: query.resource_type,
+        resource_id: query.resource_id,
+        ..Default::default()
+    };
+    let filter = match filter.with_dates(query.after.as_deref(), query.before.as_deref()) {
+        Ok(filter) => filter,
+        Err(e) => return failure(StatusCode::BAD_REQUEST, &e.to_string()),
+    };
+    let below = match parse_cursor::<i64>(query.cursor.as_deref()) {
+        Ok(below) => below,
+        Err(message) => return failure(StatusCode::BAD_REQUEST, message),
+    };
+    let per_page = pagination::per_page(query.per_page, query.limit, 50);
+
+    let db = state.db.lock().unwrap();
+    let result = list(&db.conn, &filter, below, per_page + 1)
+        .and_then(|entries| Ok((entries, count(&db.conn, &filter)?)));
+    match result {
+        Ok((mut entries, total)) => {
+            let next = pagination::finish_page(&mut entries, per_page, |entry| {
+                pagination::encode_cursor(&entry.id)
+            });
+            let items = entries
+                .into_iter()
+                .filter_map(|entry| serde_json::to_value(entry).ok())
+                .collect();
+            HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "data": page_json(items, total, per_page, next),
+            }))
+        }
+        Err(e) => failure(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    }
+}
+
+// =============================================================================
+// CLI
+// =============================================================================
+
+fn format_time(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(timestamp)
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+/// `csm audit list`
+pub fn audit_list(database: &Path, filter: &AuditFilter, limit: usize, json: bool) -> Result<()> {
+    let conn = Connection::open(database)
+        .with_context(|| format!("Failed to open {}", database.display()))?;
+    init_audit_table(&conn)?;
+    let entries = list(&conn, filter, None, limit)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+    if entries.is_empty() {
+        println!("{} No audit log entries.", "[*]".blue());
+        return Ok(());
+    }
+
+    for entry in &entries {
+        let status = if entry.status < 400 {
+            entry.status.to_string().green()
+        } else {
+            entry.status.to_string().red()
+        };
+        let actor = match (&entry.actor_name, &entry.actor_id) {
+            (Some(name), _) => format!("{} {}", entry.actor_type.replace('_', " "), name),
+            (None, Some(id)) => format!("{} {}", entry.actor_type.replace('_', " "), id),
+            (None, None) => entry.actor_type.clone(),
+        };
+        println!(
+            "   {}  {} {}  {}  {}",
+            format_time(entry.timestamp).dimmed(),
+            entry.method.bold(),
+            entry.path,
+            status,
+            actor.cyan()
+        );
+        if let (Some(kind), Some(id)) = (&entry.resource_type, &entry.resource_id) {
+            let summary = |v: &Option<serde_json::Value>| {
+                v.as_ref()
+                    .map(|v| v.to_string())
+                    .unwrap_or_else(|| "(none)".to_string())
+            };
+            println!(
+                "      {} {} {}: {} -> {}",
+                "`".dimmed(),
+                kind,
+                id,
+                summary(&entry.before).dimmed(),
+                summary(&entry.after)
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_and_list() {
+        assert_eq!(
+            target("/api/sessions").map(|(r, id)| (r.kind, id)),
+            Some(("session", None))
+        );
+        assert_eq!(
+            target("/api/sessions/abc/restore").map(|(r, id)| (r.kind, id)),
+            Some(("session", Some("abc")))
+        );
+        assert_eq!(
+            target("/api/settings/accounts/a1").map(|(r, id)| (r.kind, id)),
+            Some(("account", Some("a1")))
+        );
+        assert!(target("/api/swe/projects/p1/memory/m1").is_none());
+        assert!(target("/api/settings").is_none());
+
+        let conn = Connection::open_in_memory().unwrap();
+        init_audit_table(&conn).unwrap();
+        for (i, method) in ["POST", "DELETE", "PUT"].iter().enumerate() {
+            let entry = AuditEntry {
+                id: 0,
+                timestamp: 1_000 * i as i64,
+                actor_type: "api_key".to_string(),
+                actor_id: Some("k1".to_string()),
+                actor_name: Some("ci".to_string()),
+                user_id: None,
+                method: method.to_string(),
+                path: "/api/sessions/s1".to_string(),
+                status: 200,
+                ip: None,
+                resource_type: Some("session".to_string()),
+                resource_id: Some("s1".to_string()),
+                before: Some(serde_json::json!({ "title": "Old" })),
+                after: None,
+                duration_ms: 3,
+            };
+            insert(&conn, &entry).unwrap();
+        }
+
+        let all = list(&conn, &AuditFilter::default(), None, 10).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].method, "PUT");
+        assert_eq!(all[0].before.as_ref().unwrap()["title"], "Old");
+        assert_eq!(
+            list(&conn, &AuditFilter::default(), Some(all[0].id), 10)
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let filter = AuditFilter {
+            actor: Some("ci".to_string()),
+            method: Some("delete".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(count(&conn, &filter).unwrap(), 1);
+        let filter = AuditFilter {
+            after: Some(1_000),
+            ..Default::default()
+        };
+        assert_eq!(count(&conn, &filter).unwrap(), 2);
+    }
+}
@@ -12,18 +12,20 @@ use rusqlite::OptionalExtension;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::future::{ready, Ready};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 // =============================================================================
 // Configuration
 // =============================================================================
 
-/// JWT secret key - MUST be set via the `JWT_SECRET` environment variable.
-/// Panics at startup if not configured.
-fn jwt_secret() -> Vec<u8> {
+/// JWT secret key, set via the `JWT_SECRET` environment variable. Without it
+/// tokens are neither issued nor accepted, and only API keys authenticate.
+pub fn jwt_secret() -> Option<Vec<u8>> {
     std::env::var("JWT_SECRET")
-        .expect("JWT_SECRET environment variable must be set")
-        .into_bytes()
+        .ok()
+        .filter(|s| !s.is_empty())
+        .map(String::into_bytes)
 }
 
 const JWT_EXPIRY_HOURS: i64 = 24;
@@ -257,20 +259,20 @@ impl FromRequest for AuthenticatedUser {
 // Request/Response Types
 // =============================================================================
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct RegisterRequest {
     pub email: String,
     pub password: String,
     pub display_name: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct LoginRequest {
     pub email: String,
     pub password: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct RefreshTokenRequest {
     pub refresh_token: String,
 }
@@ -283,19 +285,19 @@ pub struct AuthResponse {
     pub expires_in: i64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateProfileRequest {
     pub display_name: Option<String>,
     pub avatar_url: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct ChangePasswordRequest {
     pub current_password: String,
     pub new_password: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpgradeSubscriptionRequest {
     pub tier: String,
     /// Payment token from payment provider (Stripe, etc.)
@@ -329,7 +331,7 @@ pub fn generate_access_token(user: &User) -> Option<String> {
         token_type: "access".to_string(),
     };
 
-    let secret = jwt_secret();
+    let secret = jwt_secret()?;
     encode(
         &Header::default(),
         &claims,
@@ -352,7 +354,7 @@ pub fn generate_refresh_token(user: &User) -> Option<String> {
         token_type: "refresh".to_string(),
     };
 
-    let secret = jwt_secret();
+    let secret = jwt_secret()?;
     encode(
         &Header::default(),
         &claims,
@@ -365,12 +367,17 @@ pub fn generate_refresh_token(user: &User) -> Option<String> {
 pub fn validate_token(token: &str) -> Option<AuthenticatedUser> {
     let validation = Validation::new(Algorithm::HS256);
 
-    let secret = jwt_secret();
+    let secret = jwt_secret()?;
     let token_data =
         decode::<Claims>(token, &DecodingKey::from_secret(&secret), &validation).ok()?;
 
     let claims = token_data.claims;
 
+    // Refresh tokens are only accepted by /auth/refresh
+    if claims.token_type != "access" {
+        return None;
+    }
+
     // Check if token is expired
     if claims.exp < Utc::now().timestamp() {
         return None;
@@ -387,7 +394,7 @@ pub fn validate_token(token: &str) -> Option<AuthenticatedUser> {
 pub fn validate_refresh_token(token: &str) -> Option<AuthenticatedUser> {
     let validation = Validation::new(Algorithm::HS256);
 
-    let secret = jwt_secret();
+    let secret = jwt_secret()?;
     let token_data =
         decode::<Claims>(token, &DecodingKey::from_secret(&secret), &validation).ok()?;
 
@@ -434,6 +441,13 @@ pub fn init_auth_tables(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
         )",
         [],
     )?;
+    // Role checked on each request; see `rbac`
+    crate::commands::add_column_if_missing(
+        conn,
+        "users",
+        "role",
+        "TEXT NOT NULL DEFAULT 'member'",
+    )?;
 
     conn.execute(
         "CREATE TABLE IF NOT EXISTS refresh_tokens (
@@ -486,6 +500,17 @@ pub fn init_auth_tables(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
 // =============================================================================
 
 /// Register a new user
+#[utoipa::path(
+    post,
+    path = "/auth/register",
+    tag = "auth",
+    request_body = RegisterRequest,
+    responses(
+        (status = 200, description = "Registered user and tokens", body = Object),
+        (status = 400, description = "Invalid email or password"),
+        (status = 409, description = "Email already registered")
+    )
+)]
 pub async fn register(
     app_state: web::Data<crate::api::state::AppState>,
     body: web::Json<RegisterRequest>,
@@ -617,6 +642,17 @@ pub async fn register(
 }
 
 /// Login with email and password
+#[utoipa::path(
+    post,
+    path = "/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "User and tokens", body = Object),
+        (status = 401, description = "Invalid credentials")
+    ),
+    security(())
+)]
 pub async fn login(
     app_state: web::Data<crate::api::state::AppState>,
     body: web::Json<LoginRequest>,
@@ -756,6 +792,17 @@ pub async fn login(
 }
 
 /// Refresh access token using refresh token
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    tag = "auth",
+    request_body = RefreshTokenRequest,
+    responses(
+        (status = 200, description = "New tokens", body = Object),
+        (status = 401, description = "Invalid or expired refresh token")
+    ),
+    security(())
+)]
 pub async fn refresh_token(
     app_state: web::Data<crate::api::state::AppState>,
     body: web::Json<RefreshTokenRequest>,
@@ -863,6 +910,13 @@ pub async fn refresh_token(
 }
 
 /// Get current user profile
+#[utoipa::path(
+    get,
+    path = "/auth/profile",
+    tag = "auth",
+    responses((status = 200, description = "User profile", body = Object)),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_profile(
     app_state: web::Data<crate::api::state::AppState>,
     auth_user: AuthenticatedUser,
@@ -946,6 +1000,14 @@ pub async fn get_profile(
 }
 
 /// Update user profile
+#[utoipa::path(
+    put,
+    path = "/auth/profile",
+    tag = "auth",
+    request_body = UpdateProfileRequest,
+    responses((status = 200, description = "Updated profile", body = Object)),
+    security(("bearer_auth" = []))
+)]
 pub async fn update_profile(
     app_state: web::Data<crate::api::state::AppState>,
     auth_user: AuthenticatedUser,
@@ -1005,6 +1067,17 @@ pub async fn update_profile(
 }
 
 /// Change password
+#[utoipa::path(
+    put,
+    path = "/auth/password",
+    tag = "auth",
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 200, description = "Password changed", body = Object),
+        (status = 401, description = "Current password is wrong")
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn change_password(
     app_state: web::Data<crate::api::state::AppState>,
     auth_user: AuthenticatedUser,
@@ -1074,6 +1147,13 @@ pub async fn change_password(
 }
 
 /// Get subscription details
+#[utoipa::path(
+    get,
+    path = "/auth/subscription",
+    tag = "auth",
+    responses((status = 200, description = "Subscription tier and features", body = Object)),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_subscription(
     app_state: web::Data<crate::api::state::AppState>,
     auth_user: AuthenticatedUser,
@@ -1116,6 +1196,14 @@ pub async fn get_subscription(
 }
 
 /// Upgrade subscription (simulated - in production would integrate with payment provider)
+#[utoipa::path(
+    post,
+    path = "/auth/subscription/upgrade",
+    tag = "auth",
+    request_body = UpgradeSubscriptionRequest,
+    responses((status = 200, description = "Upgraded subscription", body = Object)),
+    security(("bearer_auth" = []))
+)]
 pub async fn upgrade_subscription(
     app_state: web::Data<crate::api::state::AppState>,
     auth_user: AuthenticatedUser,
@@ -1176,6 +1264,13 @@ pub async fn upgrade_subscription(
 }
 
 /// Logout (invalidate session)
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    tag = "auth",
+    responses((status = 200, description = "Logged out", body = Object)),
+    security(("bearer_auth" = []))
+)]
 pub async fn logout(_auth_user: AuthenticatedUser) -> HttpResponse {
     // In a production system, you would:
     // 1. Add the token to a blacklist
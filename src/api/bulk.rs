@@ -0,0 +1,561 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Bulk session operations
+//!
+//! `POST /api/sessions/bulk` tags, untags, deletes, exports, or merges many
+//! sessions in one request. The work runs in the background: the response is
+//! a job whose progress can be polled at `/api/sessions/bulk/{id}`, and an
+//! export is downloaded from `/api/sessions/bulk/{id}/export` once the job
+//! completes. Sessions the caller cannot see are reported as failures, not
+//! touched.
+//!
+//! Jobs live in memory and are forgotten an hour after they finish.
+
+use actix_web::http::{header, StatusCode};
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::access::Scope;
+use super::docs::ApiEnvelope;
+use super::handlers_simple::{
+    load_scoped_session, new_api_session, owns_session, session_summary, store_session,
+    StoredSession,
+};
+use super::state::AppState;
+use super::sync::{self, SharedSyncState, SyncOperation};
+use crate::commands::delete_harvested_session;
+use crate::reviews;
+
+/// Most sessions one job can cover
+const MAX_SESSIONS: usize = 5000;
+
+/// Milliseconds a finished job is kept for polling
+const JOB_KEEP_MS: i64 = 60 * 60 * 1000;
+
+/// What a bulk job does to each session
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BulkOperation {
+    /// Add `tags` to each session
+    Tag,
+    /// Remove `tags` from each session
+    Untag,
+    /// Delete each session, restorably unless `permanent` is set
+    Delete,
+    /// Collect the sessions into one download
+    Export,
+    /// Combine the sessions into a new one; the originals are kept
+    Merge,
+}
+
+/// Export file layout
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// A JSON array of sessions
+    #[default]
+    Json,
+    /// One session per line
+    Jsonl,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct BulkRequest {
+    pub operation: BulkOperation,
+    /// Sessions to operate on
+    pub ids: Vec<String>,
+    /// Tags to add or remove, for `tag` and `untag`
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Delete outright instead of keeping the sessions for restore
+    #[serde(default)]
+    pub permanent: bool,
+    /// Export layout, `json` by default
+    #[serde(default)]
+    pub format: ExportFormat,
+    /// Title of the merged session
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// A session a job could not process
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkFailure {
+    pub id: String,
+    pub error: String,
+}
+
+/// A bulk job and its progress
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkJob {
+    pub id: String,
+    pub operation: BulkOperation,
+    pub status: JobStatus,
+    pub total: usize,
+    pub processed: usize,
+    pub succeeded: usize,
+    pub failures: Vec<BulkFailure>,
+    /// Merged session, export location, or the reason the job failed
+    pub result: Option<serde_json::Value>,
+    pub created_at: i64,
+    pub finished_at: Option<i64>,
+    #[serde(skip)]
+    scope: Scope,
+    #[serde(skip)]
+    export: Option<(ExportFormat, String)>,
+}
+
+/// Bulk jobs started since the server came up, registered as app data
+#[derive(Default)]
+pub struct BulkJobs {
+    jobs: Mutex<HashMap<String, BulkJob>>,
+}
+
+impl BulkJobs {
+    fn insert(&self, job: BulkJob) {
+        let now = Utc::now().timestamp_millis();
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.retain(|_, job| job.finished_at.is_none_or(|at| now - at < JOB_KEEP_MS));
+        jobs.insert(job.id.clone(), job);
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut BulkJob)) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(id) {
+            f(job);
+        }
+    }
+
+    /// A job the caller started, or any job for admins
+    fn get(&self, id: &str, scope: &Scope) -> Option<BulkJob> {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.get(id)
+            .filter(|job| scope.is_admin() || job.scope == *scope)
+            .cloned()
+    }
+
+    fn record(&self, id: &str, session_id: &str, outcome: Result<(), String>) {
+        self.update(id, |job| {
+            job.processed += 1;
+            match outcome {
+                Ok(()) => job.succeeded += 1,
+                Err(error) => job.failures.push(BulkFailure {
+                    id: session_id.to_string(),
+                    error,
+                }),
+            }
+        });
+    }
+
+    fn finish(&self, id: &str, result: Result<Option<serde_json::Value>, String>) {
+        self.update(id, |job| {
+            job.finished_at = Some(Utc::now().timestamp_millis());
+            match result {
+                Ok(result) => {
+                    job.status = JobStatus::Completed;
+                    job.result = result;
+                }
+                Err(error) => {
+                    job.status = JobStatus::Failed;
+                    job.result = Some(serde_json::json!({ "error": error }));
+                }
+            }
+        });
+    }
+}
+
+fn success(status: StatusCode, data: impl Serialize) -> HttpResponse {
+    HttpResponse::build(status).json(serde_json::json!({
+        "success": true,
+        "data": data,
+    }))
+}
+
+fn failure(status: StatusCode, message: &str) -> HttpResponse {
+    HttpResponse::build(status).json(serde_json::json!({
+        "success": false,
+        "error": message,
+    }))
+}
+
+fn validate(request: &BulkRequest) -> Result<(), String> {
+    if request.ids.is_empty() {
+        return Err("Select at least one session".to_string());
+    }
+    if request.ids.len() > MAX_SESSIONS {
+        return Err(format!("A job can cover at most {} sessions", MAX_SESSIONS));
+    }
+    match request.operation {
+        BulkOperation::Tag | BulkOperation::Untag
+            if request.tags.iter().all(|t| t.trim().is_empty()) =>
+        {
+            Err("tags must name at least one tag".to_string())
+        }
+        BulkOperation::Merge if request.ids.len() < 2 => {
+            Err("Select at least two sessions to merge".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Combine sessions into a new one owned by `owner`, with every request in
+/// time order. Provider and workspace are kept when all sessions share them.
+fn merge_sessions(
+    sources: Vec<StoredSession>,
+    title: Option<String>,
+    owner: Option<String>,
+) -> StoredSession {
+    let same = |f: fn(&StoredSession) -> Option<&str>| {
+        let first = sources.first().and_then(f);
+        sources
+            .iter()
+            .all(|s| f(s) == first)
+            .then(|| first.map(str::to_string))
+            .flatten()
+    };
+    let provider = same(|s| Some(s.provider.as_str())).unwrap_or_else(|| "merged".to_string());
+    let workspace_id = same(|s| s.workspace_id.as_deref());
+    let workspace_name = workspace_id
+        .as_ref()
+        .and_then(|_| same(|s| s.workspace_name.as_deref()));
+
+    let mut session = new_api_session(&Uuid::new_v4().to_string());
+    session.custom_title = title.filter(|t| !t.is_empty()).or_else(|| {
+        Some(format!(
+            "Merged: {}",
+            sources
+                .iter()
+                .map(|s| s.session.title())
+                .collect::<Vec<_>>()
+                .join(" + ")
+        ))
+    });
+    for source in sources {
+        session.requests.extend(source.session.requests);
+    }
+    session.requests.sort_by_key(|r| r.timestamp.unwrap_or(0));
+    let (first, last) = session.timestamp_range().unwrap_or_else(|| {
+        let now = Utc::now().timestamp_millis();
+        (now, now)
+    });
+    session.creation_date = first;
+    session.last_message_date = last;
+
+    StoredSession {
+        provider,
+        workspace_id,
+        workspace_name,
+        owner,
+        session,
+    }
+}
+
+/// Add or remove user tags on a session the caller can see
+fn retag(
+    state: &AppState,
+    sync_state: &SharedSyncState,
+    session_id: &str,
+    request: &BulkRequest,
+    scope: &Scope,
+) -> anyhow::Result<()> {
+    let db = state.db.lock().unwrap();
+    let stored = load_scoped_session(&db.conn, session_id, scope)?
+        .ok_or_else(|| anyhow::anyhow!("Session not found"))?;
+    let mut tags = reviews::user_tags(&db.conn, session_id)?;
+    let changed: Vec<&str> = request.tags.iter().map(|t| t.trim()).collect();
+    if request.operation == BulkOperation::Tag {
+        tags.extend(changed.iter().map(|t| t.to_string()));
+    } else {
+        tags.retain(|t| !changed.contains(&t.as_str()));
+    }
+    let tags = reviews::set_user_tags(&db.conn, session_id, &tags)?;
+    drop(db);
+
+    sync::publish_session_change(
+        sync_state,
+        SyncOperation::Update,
+        session_id,
+        Some(session_summary(&stored, tags)),
+    );
+    Ok(())
+}
+
+fn delete(
+    state: &AppState,
+    sync_state: &SharedSyncState,
+    session_id: &str,
+    permanent: bool,
+    scope: &Scope,
+) -> anyhow::Result<()> {
+    let db = state.db.lock().unwrap();
+    if !owns_session(&db.conn, "sessions", session_id, scope)? {
+        anyhow::bail!("Session not found");
+    }
+    let tx = db.conn.unchecked_transaction()?;
+    delete_harvested_session(&tx, session_id, !permanent)?;
+    tx.commit()?;
+    drop(db);
+
+    sync::publish_session_change(sync_state, SyncOperation::Delete, session_id, None);
+    Ok(())
+}
+
+fn load(state: &AppState, session_id: &str, scope: &Scope) -> anyhow::Result<StoredSession> {
+    let db = state.db.lock().unwrap();
+    load_scoped_session(&db.conn, session_id, scope)?
+        .ok_or_else(|| anyhow::anyhow!("Session not found"))
+}
+
+/// Work through a job's sessions, one database lock per session so other
+/// requests are not held up
+fn run_job(
+    state: &AppState,
+    sync_state: &SharedSyncState,
+    jobs: &BulkJobs,
+    job_id: &str,
+    request: BulkRequest,
+    scope: Scope,
+) -> Result<Option<serde_json::Value>, String> {
+    let mut loaded = Vec::new();
+    for session_id in &request.ids {
+        let outcome = match request.operation {
+            BulkOperation::Tag | BulkOperation::Untag => {
+                retag(state, sync_state, session_id, &request, &scope)
+            }
+            BulkOperation::Delete => {
+                delete(state, sync_state, session_id, request.permanent, &scope)
+            }
+            BulkOperation::Export | BulkOperation::Merge => {
+                load(state, session_id, &scope).map(|stored| loaded.push(stored))
+            }
+        };
+        jobs.record(job_id, session_id, outcome.map_err(|e| e.to_string()));
+    }
+
+    match request.operation {
+        BulkOperation::Export => {
+            let sessions = loaded
+                .iter()
+                .map(|stored| serde_json::to_value(&stored.session))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?;
+            let body = match request.format {
+                ExportFormat::Json => serde_json::to_string(&sessions),
+                ExportFormat::Jsonl => sessions
+                    .iter()
+                    .map(serde_json::to_string)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(|lines| lines.join("\n")),
+            }
+            .map_err(|e| e.to_string())?;
+            jobs.update(job_id, |job| job.export = Some((request.format, body)));
+            Ok(Some(serde_json::json!({
+                "sessions": sessions.len(),
+                "download": format!("/api/sessions/bulk/{}/export", job_id),
+            })))
+        }
+        BulkOperation::Merge => {
+            if loaded.len() < 2 {
+                return Err("Fewer than two of the sessions could be loaded".to_string());
+            }
+            let merged = merge_sessions(loaded, request.title, scope.owner().map(str::to_string));
+            let session_id = merged.session.session_id.clone().unwrap_or_default();
+            let db = state.db.lock().unwrap();
+            let tags = store_session(&db.conn, &merged, None).map_err(|e| e.to_string())?;
+            drop(db);
+            let summary = session_summary(&merged, tags);
+            sync::publish_session_change(
+                sync_state,
+                SyncOperation::Create,
+                &session_id,
+                Some(summary.clone()),
+            );
+            Ok(Some(summary))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Start a bulk operation on many sessions
+#[utoipa::path(
+    post,
+    path = "/api/sessions/bulk",
+    tag = "sessions",
+    request_body = BulkRequest,
+    responses(
+        (status = 202, description = "Job started; poll it for progress", body = ApiEnvelope),
+        (status = 400, description = "No sessions, too many, or missing tags")
+    )
+)]
+pub async fn start_bulk_job(
+    state: web::Data<AppState>,
+    sync_state: web::Data<SharedSyncState>,
+    jobs: web::Data<BulkJobs>,
+    body: web::Json<BulkRequest>,
+    scope: Scope,
+) -> HttpResponse {
+    let mut request = body.into_inner();
+    if let Err(message) = validate(&request) {
+        return failure(StatusCode::BAD_REQUEST, &message);
+    }
+    let mut seen = std::collections::HashSet::new();
+    request.ids.retain(|id| seen.insert(id.clone()));
+
+    let job = BulkJob {
+        id: Uuid::new_v4().to_string(),
+        operation: request.operation,
+        status: JobStatus::Running,
+        total: request.ids.len(),
+        processed: 0,
+        succeeded: 0,
+        failures: Vec::new(),
+        result: None,
+        created_at: Utc::now().timestamp_millis(),
+        finished_at: None,
+        scope: scope.clone(),
+        export: None,
+    };
+    let job_id = job.id.clone();
+    jobs.insert(job.clone());
+
+    let sync_state = sync_state.get_ref().clone();
+    let worker_jobs = jobs.clone();
+    tokio::task::spawn_blocking(move || {
+        let result = run_job(&state, &sync_state, &worker_jobs, &job_id, request, scope);
+        worker_jobs.finish(&job_id, result);
+    });
+    success(StatusCode::ACCEPTED, job)
+}
+
+/// Get a bulk job's progress
+#[utoipa::path(
+    get,
+    path = "/api/sessions/bulk/{id}",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Job ID")),
+    responses(
+        (status = 200, description = "Job progress", body = ApiEnvelope),
+        (status = 404, description = "Job not found")
+    )
+)]
+pub async fn get_bulk_job(
+    jobs: web::Data<BulkJobs>,
+    path: web::Path<String>,
+    scope: Scope,
+) -> HttpResponse {
+    match jobs.get(&path, &scope) {
+        Some(job) => success(StatusCode::OK, job),
+        None => failure(StatusCode::NOT_FOUND, "Job not found"),
+    }
+}
+
+/// Download the sessions collected by a finished export job
+#[utoipa::path(
+    get,
+    path = "/api/sessions/bulk/{id}/export",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Job ID")),
+    responses(
+        (status = 200, description = "Exported sessions as JSON or JSON Lines"),
+        (status = 404, description = "Job not found"),
+        (status = 409, description = "Not an export job, or still running")
+    )
+)]
+pub async fn download_bulk_export(
+    jobs: web::Data<BulkJobs>,
+    path: web::Path<String>,
+    scope: Scope,
+) -> HttpResponse {
+    let Some(job) = jobs.get(&path, &scope) else {
+        return failure(StatusCode::NOT_FOUND, "Job not found");
+    };
+    let Some((format, body)) = job.export else {
+        return failure(
+            StatusCode::CONFLICT,
+            "The job is still running or is not an export",
+        );
+    };
+    let (content_type, extension) = match format {
+        ExportFormat::Json => ("application/json", "json"),
+        ExportFormat::Jsonl => ("application/x-ndjson", "jsonl"),
+    };
+    HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"sessions-{}.{}\"", job.id, extension),
+        ))
+        .body(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::handlers_simple::{append_message, NewMessage};
+    use super::*;
+
+    fn stored(provider: &str, workspace: &str, timestamps: &[i64]) -> StoredSession {
+        let mut session = new_api_session(&Uuid::new_v4().to_string());
+        for &timestamp in timestamps {
+            let message = NewMessage {
+                role: "user".to_string(),
+                content: format!("at {}", timestamp),
+                model: None,
+                timestamp: Some(timestamp),
+            };
+            append_message(&mut session, &message).unwrap();
+        }
+        StoredSession {
+            provider: provider.to_string(),
+            workspace_id: Some(workspace.to_string()),
+            workspace_name: None,
+            owner: None,
+            session,
+        }
+    }
+
+    #[test]
+    fn test_merge_sessions() {
+        let merged = merge_sessions(
+            vec![
+                stored("copilot", "ws", &[10, 30]),
+                stored("cursor", "ws", &[20]),
+            ],
+            None,
+            Some("u1".to_string()),
+        );
+        let order: Vec<i64> = merged
+            .session
+            .requests
+            .iter()
+            .filter_map(|r| r.timestamp)
+            .collect();
+        assert_eq!(order, vec![10, 20, 30]);
+        assert_eq!(merged.provider, "merged");
+        assert_eq!(merged.workspace_id.as_deref(), Some("ws"));
+        assert_eq!(merged.owner.as_deref(), Some("u1"));
+        assert_eq!(
+            (
+                merged.session.creation_date,
+                merged.session.last_message_date
+            ),
+            (10, 30)
+        );
+        assert_eq!(merged.session.title(), "Merged: at 10 + at 20");
+
+        let request: BulkRequest =
+            serde_json::from_str(r#"{"operation": "merge", "ids": ["a"]}"#).unwrap();
+        assert!(validate(&request).is_err());
+    }
+}
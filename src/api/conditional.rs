@@ -0,0 +1,119 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Conditional requests for session and snapshot reads
+//!
+//! Full sessions and sync snapshots are large, and mobile clients fetch them
+//! again whether or not anything changed. Successful `GET`s of those
+//! endpoints carry a weak `ETag` computed from the response body (or set by
+//! the handler, when the body holds something that changes on every request,
+//! such as a timestamp). A client that sends the tag back in `If-None-Match`
+//! gets `304 Not Modified` with no body. Tags are weak because the same
+//! content may be sent gzip- or brotli-compressed.
+
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::http::{Method, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpResponse};
+use sha2::{Digest, Sha256};
+
+/// Paths whose responses get an `ETag`
+const CONDITIONAL_PREFIXES: &[&str] = &["/api/sessions", "/sync/snapshot"];
+
+/// Weak entity tag for a response body
+pub fn weak_etag(body: &[u8]) -> String {
+    format!("W/\"{:x}\"", Sha256::digest(body))
+}
+
+/// Whether an `If-None-Match` value matches `etag`, using the weak comparison
+/// that RFC 9110 requires for this header
+fn matches(if_none_match: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(etag);
+    if_none_match.trim() == "*" || if_none_match.split(',').any(|tag| opaque(tag) == etag)
+}
+
+fn is_conditional(req: &ServiceRequest) -> bool {
+    req.method() == Method::GET
+        && CONDITIONAL_PREFIXES
+            .iter()
+            .any(|prefix| req.path().starts_with(prefix))
+}
+
+/// Middleware that tags session and snapshot responses and answers
+/// `If-None-Match` with `304 Not Modified`. Streamed bodies pass through
+/// untagged.
+pub async fn etags(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    if !is_conditional(&req) {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+    let if_none_match = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
+
+    let res = next.call(req).await?.map_into_boxed_body();
+    if res.status() != StatusCode::OK {
+        return Ok(res);
+    }
+    let (req, res) = res.into_parts();
+    let (mut res, body) = res.into_parts();
+    let body = match body.try_into_bytes() {
+        Ok(body) => body,
+        Err(body) => return Ok(ServiceResponse::new(req, res.set_body(body))),
+    };
+
+    let etag = match res.headers().get(header::ETAG) {
+        Some(etag) => etag.clone(),
+        None => {
+            let etag = HeaderValue::from_str(&weak_etag(&body))?;
+            res.headers_mut().insert(header::ETAG, etag.clone());
+            etag
+        }
+    };
+    if !res.headers().contains_key(header::CACHE_CONTROL) {
+        res.headers_mut().insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("private, no-cache"),
+        );
+    }
+
+    let not_modified = match (if_none_match, etag.to_str()) {
+        (Some(if_none_match), Ok(etag)) => matches(&if_none_match, etag),
+        _ => false,
+    };
+    if not_modified {
+        let mut not_modified = HttpResponse::build(StatusCode::NOT_MODIFIED).finish();
+        for name in [header::ETAG, header::CACHE_CONTROL] {
+            if let Some(value) = res.headers().get(&name) {
+                not_modified.headers_mut().insert(name, value.clone());
+            }
+        }
+        return Ok(ServiceResponse::new(req, not_modified));
+    }
+    Ok(ServiceResponse::new(req, res.set_body(BoxBody::new(body))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_none_match() {
+        let etag = weak_etag(b"{\"success\":true}");
+        assert_eq!(etag, weak_etag(b"{\"success\":true}"));
+        assert_ne!(etag, weak_etag(b"{\"success\":false}"));
+        assert!(etag.starts_with("W/\""));
+
+        assert!(matches(&etag, &etag));
+        assert!(matches(etag.trim_start_matches("W/"), &etag));
+        assert!(matches(&format!("\"other\", {}", etag), &etag));
+        assert!(matches("*", &etag));
+        assert!(!matches("\"other\"", &etag));
+    }
+}
@@ -0,0 +1,272 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Running the API server in the background
+//!
+//! `csm api serve --daemon` starts a detached copy of the server that writes
+//! its output to a log file and its process id to `api.pid` beside the
+//! database. `--log-file` rotates the log once it passes a size limit,
+//! keeping the last few files, so a long-running server cannot fill the disk.
+
+use anyhow::{bail, Context, Result};
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// Rotated log files kept next to the current one
+const LOG_KEEP: usize = 5;
+
+/// How often the log file size is checked
+const LOG_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+fn data_dir(database_path: &Path) -> &Path {
+    database_path.parent().unwrap_or_else(|| Path::new("."))
+}
+
+/// Log file used by `--daemon` when `--log-file` is not given
+pub fn default_log_path(database_path: &Path) -> PathBuf {
+    data_dir(database_path).join("logs").join("api.log")
+}
+
+/// Process id file written by `--daemon`
+pub fn pid_path(database_path: &Path) -> PathBuf {
+    data_dir(database_path).join("api.pid")
+}
+
+/// `api.log.3` for generation 3 of `api.log`
+fn rotated_path(path: &Path, generation: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", generation));
+    PathBuf::from(name)
+}
+
+/// Shift `api.log` to `api.log.1`, `api.log.1` to `api.log.2`, and so on,
+/// dropping the oldest
+fn rotate(path: &Path, keep: usize) -> io::Result<()> {
+    let oldest = rotated_path(path, keep);
+    if oldest.exists() {
+        fs::remove_file(&oldest)?;
+    }
+    for generation in (1..keep).rev() {
+        let from = rotated_path(path, generation);
+        if from.exists() {
+            fs::rename(&from, rotated_path(path, generation + 1))?;
+        }
+    }
+    if path.exists() {
+        fs::rename(path, rotated_path(path, 1))?;
+    }
+    Ok(())
+}
+
+fn open_log(path: &Path) -> io::Result<File> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn log_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// Point this process's stdout and stderr at `file`
+#[cfg(unix)]
+fn redirect_std_handles(file: File) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    for target in [libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+        // SAFETY: both descriptors are valid; dup2 replaces the target atomically
+        if unsafe { libc::dup2(file.as_raw_fd(), target) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Point this process's stdout and stderr at `file`
+#[cfg(windows)]
+fn redirect_std_handles(file: File) -> io::Result<()> {
+    use std::os::windows::io::IntoRawHandle;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::System::Console::{SetStdHandle, STD_ERROR_HANDLE, STD_OUTPUT_HANDLE};
+
+    // The standard handles keep using the file, so it is never closed
+    let handle = HANDLE(file.into_raw_handle());
+    for target in [STD_OUTPUT_HANDLE, STD_ERROR_HANDLE] {
+        // SAFETY: the handle stays open for the life of the process
+        unsafe { SetStdHandle(target, handle) }.map_err(io::Error::other)?;
+    }
+    Ok(())
+}
+
+/// Send stdout and stderr to `path`, rotating it when it grows past
+/// `max_bytes`
+pub fn redirect_output(path: &Path, max_bytes: u64) -> Result<()> {
+    if log_size(path) > max_bytes {
+        rotate(path, LOG_KEEP)?;
+    }
+    let file =
+        open_log(path).with_context(|| format!("Failed to open log file {}", path.display()))?;
+    redirect_std_handles(file)?;
+
+    let path = path.to_path_buf();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(LOG_CHECK_INTERVAL);
+        if log_size(&path) <= max_bytes {
+            continue;
+        }
+        let reopened = rotate(&path, LOG_KEEP)
+            .and_then(|_| open_log(&path))
+            .and_then(redirect_std_handles);
+        if let Err(e) = reopened {
+            eprintln!("[WARN] Failed to rotate {}: {}", path.display(), e);
+        }
+    });
+    Ok(())
+}
+
+/// The process id recorded in `path`, if that process is still running
+fn running_pid(path: &Path) -> Option<u32> {
+    let pid: u32 = fs::read_to_string(path).ok()?.trim().parse().ok()?;
+    let mut system = sysinfo::System::new();
+    system
+        .refresh_process(sysinfo::Pid::from_u32(pid))
+        .then_some(pid)
+}
+
+/// Remove the pid file if it still names this process
+pub fn release_pid_file(database_path: &Path) {
+    let path = pid_path(database_path);
+    let ours = fs::read_to_string(&path)
+        .map(|pid| pid.trim() == std::process::id().to_string())
+        .unwrap_or(false);
+    if ours {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Start a detached copy of this program with `args`, writing its output to
+/// `log_path` and its process id to the pid file beside the database
+pub fn spawn_daemon(args: Vec<OsString>, database_path: &Path, log_path: &Path) -> Result<()> {
+    let pid_file = pid_path(database_path);
+    if let Some(pid) = running_pid(&pid_file) {
+        bail!(
+            "The API server is already running in the background (pid {}, see {})",
+            pid,
+            pid_file.display()
+        );
+    }
+
+    let log = open_log(log_path)
+        .with_context(|| format!("Failed to open log file {}", log_path.display()))?;
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // SAFETY: setsid is async-signal-safe; the child leaves the terminal's
+        // session so closing the terminal does not stop it
+        unsafe {
+            command.pre_exec(|| {
+                if libc::setsid() < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    }
+
+    let mut child = command
+        .spawn()
+        .context("Failed to start the background server")?;
+    let pid = child.id();
+    if let Some(dir) = pid_file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&pid_file, pid.to_string())?;
+
+    // Most startup errors, such as a port in use, show up within a moment
+    std::thread::sleep(Duration::from_secs(1));
+    if let Some(status) = child.try_wait()? {
+        let _ = fs::remove_file(&pid_file);
+        bail!(
+            "The background server exited ({}); see {}",
+            status,
+            log_path.display()
+        );
+    }
+
+    println!("[+] API server running in the background (pid {})", pid);
+    println!("   Log: {}", log_path.display());
+    println!("   Pid file: {}", pid_file.display());
+    if cfg!(windows) {
+        println!("   Stop it with: taskkill /PID {} /F", pid);
+    } else {
+        println!("   Stop it with: kill {}", pid);
+        println!("   Reload its configuration with: kill -HUP {}", pid);
+    }
+    Ok(())
+}
+
+/// Resolves on Ctrl+C, or on SIGTERM on Unix
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_keeps_newest() {
+        let dir = std::env::temp_dir().join(format!("csm-log-{}", uuid::Uuid::new_v4()));
+        let log = dir.join("logs").join("api.log");
+
+        for n in 0..4 {
+            fs::create_dir_all(log.parent().unwrap()).unwrap();
+            fs::write(&log, format!("run {}", n)).unwrap();
+            rotate(&log, 2).unwrap();
+        }
+
+        assert!(!log.exists());
+        assert_eq!(fs::read_to_string(rotated_path(&log, 1)).unwrap(), "run 3");
+        assert_eq!(fs::read_to_string(rotated_path(&log, 2)).unwrap(), "run 2");
+        assert!(!rotated_path(&log, 3).exists());
+        assert_eq!(default_log_path(&dir.join("csm.db")), log);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -2,27 +2,209 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //! OpenAPI documentation module
 //!
-//! Serves the OpenAPI specification and Swagger UI.
+//! The specification is generated from the `#[utoipa::path]` annotations on
+//! the handlers, so adding a route means annotating its handler and listing
+//! it in [`ApiDoc`]. Served at `/api/openapi.json` with a Swagger UI at
+//! `/api/docs`.
 
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{HttpResponse, Responder};
+use utoipa::openapi::security::{
+    ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityRequirement, SecurityScheme,
+};
+use utoipa::{Modify, OpenApi, ToSchema};
 
-/// OpenAPI specification as YAML
-const OPENAPI_YAML: &str = include_str!("../../openapi.yaml");
+use super::{
+    audit_log, auth, bulk, gateway, handlers_simple, handlers_swe, health, rate_limit, recording,
+    reload, remote, sync, teams, webhooks,
+};
 
-/// Get OpenAPI specification (YAML)
-pub async fn openapi_yaml() -> impl Responder {
-    HttpResponse::Ok()
-        .content_type("application/yaml")
-        .body(OPENAPI_YAML)
+/// Envelope returned by the `/api` handlers
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct ApiEnvelope {
+    success: bool,
+    /// Response payload, present when `success` is true
+    data: Option<serde_json::Value>,
+    /// Error message, present when `success` is false
+    error: Option<String>,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Chasm API"),
+    paths(
+        handlers_simple::health_check,
+        handlers_simple::list_workspaces,
+        handlers_simple::get_workspace,
+        handlers_simple::list_sessions,
+        handlers_simple::search_sessions,
+        handlers_simple::get_session,
+        handlers_simple::list_session_messages,
+        handlers_simple::create_session,
+        handlers_simple::append_session_message,
+        handlers_simple::update_session,
+        handlers_simple::delete_session,
+        handlers_simple::restore_session,
+        handlers_simple::diff_session,
+        bulk::start_bulk_job,
+        bulk::get_bulk_job,
+        bulk::download_bulk_export,
+        handlers_simple::list_providers,
+        handlers_simple::get_stats,
+        handlers_simple::list_agents,
+        handlers_simple::create_agent,
+        handlers_simple::get_agent,
+        handlers_simple::update_agent,
+        handlers_simple::delete_agent,
+        handlers_simple::run_agent,
+        remote::submit_task,
+        remote::list_tasks,
+        remote::get_task,
+        remote::task_events,
+        remote::cancel_task,
+        remote::download_artifact,
+        remote::report_progress,
+        remote::report_step,
+        remote::report_log,
+        remote::report_completed,
+        remote::report_failed,
+        remote::node_heartbeat,
+        handlers_simple::list_swarms,
+        handlers_simple::create_swarm,
+        handlers_simple::get_swarm,
+        handlers_simple::delete_swarm,
+        handlers_simple::get_settings,
+        handlers_simple::update_settings,
+        handlers_simple::list_accounts,
+        handlers_simple::create_account,
+        handlers_simple::delete_account,
+        handlers_simple::get_system_info,
+        health::get_system_health,
+        health::get_provider_health,
+        gateway::routing_status,
+        rate_limit::rate_limit_metrics,
+        handlers_simple::list_mcp_tools,
+        handlers_simple::call_mcp_tool,
+        handlers_simple::call_mcp_tools_batch,
+        handlers_simple::get_csm_system_prompt,
+        handlers_swe::list_projects,
+        handlers_swe::create_project,
+        handlers_swe::get_project,
+        handlers_swe::delete_project,
+        handlers_swe::open_project,
+        handlers_swe::get_context,
+        handlers_swe::execute_tool,
+        handlers_swe::list_memory,
+        handlers_swe::create_memory,
+        handlers_swe::get_memory,
+        handlers_swe::update_memory,
+        handlers_swe::delete_memory,
+        handlers_swe::list_rules,
+        handlers_swe::create_rule,
+        handlers_swe::update_rule,
+        handlers_swe::delete_rule,
+        sync::get_sync_version,
+        sync::get_sync_delta,
+        sync::post_sync_event,
+        sync::post_sync_batch,
+        sync::get_sync_snapshot,
+        sync::sync_sse,
+        recording::record_events,
+        recording::store_snapshot,
+        recording::list_sessions,
+        recording::get_session,
+        recording::get_recovery,
+        recording::recording_status,
+        auth::register,
+        auth::login,
+        auth::refresh_token,
+        auth::logout,
+        auth::get_profile,
+        auth::update_profile,
+        auth::change_password,
+        auth::get_subscription,
+        auth::upgrade_subscription,
+        gateway::chat_completions,
+        gateway::list_models,
+        webhooks::list_webhooks,
+        webhooks::create_webhook,
+        webhooks::get_webhook,
+        webhooks::update_webhook,
+        webhooks::delete_webhook,
+        webhooks::test_webhook,
+        webhooks::list_deliveries,
+        audit_log::list_audit_log,
+        reload::reload_config,
+        teams::list_teams,
+        teams::create_team,
+        teams::get_team,
+        teams::delete_team,
+        teams::set_member,
+        teams::remove_member,
+        teams::get_team_activity,
+        teams::share_session,
+    ),
+    modifiers(&Credentials),
+    tags(
+        (name = "workspaces", description = "Workspaces with harvested sessions"),
+        (name = "sessions", description = "Harvested chat sessions"),
+        (name = "providers", description = "Supported chat providers"),
+        (name = "agents", description = "Agent definitions"),
+        (name = "swarms", description = "Multi-agent swarms"),
+        (name = "remote", description = "Agent runs in the background, followed from other machines"),
+        (name = "settings", description = "Settings and provider accounts"),
+        (name = "system", description = "Health and server information"),
+        (name = "mcp", description = "MCP tool calls"),
+        (name = "swe", description = "SWE mode projects, memory, and rules"),
+        (name = "sync", description = "Incremental sync for web and mobile clients"),
+        (name = "recording", description = "Real-time session recording"),
+        (name = "auth", description = "Accounts and tokens"),
+        (name = "gateway", description = "OpenAI-compatible chat completions"),
+        (name = "webhooks", description = "Signed callbacks on server events"),
+        (name = "audit", description = "Record of mutating requests"),
+        (name = "teams", description = "Team workspaces and shared sessions"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Registers the credentials checked by `access::require_auth`: a JWT access
+/// token or an API key, required on every route unless it opts out
+struct Credentials;
+
+impl Modify for Credentials {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-API-Key"))),
+        );
+        openapi.security = Some(vec![
+            SecurityRequirement::new("bearer_auth", Vec::<String>::new()),
+            SecurityRequirement::new("api_key", Vec::<String>::new()),
+        ]);
+    }
 }
 
 /// Get OpenAPI specification (JSON)
 pub async fn openapi_json() -> impl Responder {
-    // Parse YAML and convert to JSON
-    match serde_yaml_to_json(OPENAPI_YAML) {
-        Ok(json) => HttpResponse::Ok()
-            .content_type("application/json")
-            .body(json),
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+/// Get OpenAPI specification (YAML)
+pub async fn openapi_yaml() -> impl Responder {
+    match serde_yaml::to_string(&ApiDoc::openapi()) {
+        Ok(yaml) => HttpResponse::Ok()
+            .content_type("application/yaml")
+            .body(yaml),
         Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
     }
 }
@@ -31,26 +213,7 @@ pub async fn openapi_json() -> impl Responder {
 pub async fn swagger_ui() -> impl Responder {
     HttpResponse::Ok()
         .content_type("text/html")
-        .body(SWAGGER_UI_HTML)
-}
-
-/// Convert YAML to JSON string
-fn serde_yaml_to_json(yaml: &str) -> Result<String, String> {
-    // Simple YAML to JSON conversion using serde_json
-    let value: serde_json::Value =
-        serde_yaml::from_str(yaml).map_err(|e| format!("YAML parse error: {}", e))?;
-    serde_json::to_string_pretty(&value).map_err(|e| format!("JSON serialize error: {}", e))
-}
-
-/// Configure documentation routes
-pub fn configure_docs_routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(
-        web::scope("/docs")
-            .route("", web::get().to(swagger_ui))
-            .route("/", web::get().to(swagger_ui))
-            .route("/openapi.yaml", web::get().to(openapi_yaml))
-            .route("/openapi.json", web::get().to(openapi_json)),
-    );
+        .body(SWAGGER_UI_HTML.replace("{version}", env!("CARGO_PKG_VERSION")))
 }
 
 /// Embedded Swagger UI HTML
@@ -112,7 +275,7 @@ const SWAGGER_UI_HTML: &str = r#"<!DOCTYPE html>
 <body>
     <div class="custom-header">
         <h1>🔗 Chasm API</h1>
-        <span class="version">v1.3.0</span>
+        <span class="version">v{version}</span>
         <a href="https://github.com/nervosys/chasm" target="_blank">GitHub →</a>
     </div>
     <div id="swagger-ui"></div>
@@ -120,7 +283,7 @@ const SWAGGER_UI_HTML: &str = r#"<!DOCTYPE html>
     <script>
         window.onload = function() {
             SwaggerUIBundle({
-                url: "/docs/openapi.yaml",
+                url: "/api/openapi.json",
                 dom_id: '#swagger-ui',
                 deepLinking: true,
                 presets: [
@@ -142,3 +305,34 @@ const SWAGGER_UI_HTML: &str = r#"<!DOCTYPE html>
 </body>
 </html>
 "#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utoipa::openapi::path::ParameterIn;
+
+    #[test]
+    fn test_path_parameters_are_declared() {
+        let spec = ApiDoc::openapi();
+        assert!(spec.paths.paths.contains_key("/api/sessions/{id}"));
+        assert!(spec.paths.paths.contains_key("/sync/delta"));
+
+        for (path, item) in &spec.paths.paths {
+            let placeholders: Vec<&str> = path
+                .split('/')
+                .filter_map(|s| s.strip_prefix('{')?.strip_suffix('}'))
+                .collect();
+            let operations = [&item.get, &item.post, &item.put, &item.delete];
+            for operation in operations.into_iter().flatten() {
+                let declared: Vec<&str> = operation
+                    .parameters
+                    .iter()
+                    .flatten()
+                    .filter(|p| p.parameter_in == ParameterIn::Path)
+                    .map(|p| p.name.as_str())
+                    .collect();
+                assert_eq!(declared, placeholders, "parameters of {}", path);
+            }
+        }
+    }
+}
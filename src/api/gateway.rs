@@ -0,0 +1,1366 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! OpenAI-compatible gateway
+//!
+//! `/v1/chat/completions` and `/v1/models` let any OpenAI client use csm as
+//! its endpoint. The request's `model` picks the upstream: `auto` lets the
+//! [`ModelRouter`] choose among configured providers, `provider/model` (for
+//! example `ollama/llama3.2`) goes to that provider, and a model the router
+//! knows goes to its provider. Every completion, streamed or not, is recorded
+//! in the harvest database, so csm works as a logging proxy, and its tokens
+//! and cost go to `agent_usage` under the `gateway` agent, where
+//! `csm report spend` totals them.
+//!
+//! Configured providers are probed every `probe_interval_secs`, and failed
+//! requests count against them too. `auto` avoids providers that are down or
+//! slow; `GET /api/system/routing` (and `csm routing status`) shows what the
+//! router currently thinks of each.
+//!
+//! A request whose provider is unreachable or answers 408, 429 or 5xx goes
+//! on along the `[api.routing.fallback]` chain. The provider that served it
+//! is in the `X-Chasm-Provider` header, the ones that failed first in
+//! `X-Chasm-Fallback`, and each fallback is logged.
+//!
+//! A request naming its workspace (a name or a directory) in
+//! `X-Chasm-Workspace` only goes to providers the `[policies]` covering it
+//! allow: `auto` routes among those, and a disallowed provider is skipped
+//! like a failed one. Each decision is recorded for `csm routing audit`.
+
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::access::Scope;
+use super::docs::ApiEnvelope;
+use super::handlers_simple::{
+    append_message, load_stored_session, new_api_session, store_session, NewMessage, StoredSession,
+};
+use super::state::AppState;
+use super::sync::{self, SharedSyncState, SyncOperation};
+use crate::agency::budget::{self, Spend};
+use crate::config::{self, ApiConfig, ApiProviderConfig, ApiRoutingConfig, ModelPrice};
+use crate::routing::{
+    describe_attempts, estimate_tokens, is_retryable_status, record_decision, Attempt,
+    BreakerState, CircuitBreakers, FallbackPolicy, FallbackTarget, HealthPolicy, HealthTracker,
+    ModelRouter, Policies, PolicyDecision, ProviderHealth, RoutingConfig, RoutingRequest,
+    RoutingStrategy, WorkspacePolicy,
+};
+
+/// Header naming the session a conversation is recorded in
+const SESSION_HEADER: &str = "X-Chasm-Session";
+
+/// Header listing the providers that failed before one served a request
+const FALLBACK_HEADER: &str = "X-Chasm-Fallback";
+
+/// Header naming the workspace, or directory, a request is made for
+const WORKSPACE_HEADER: &str = "X-Chasm-Workspace";
+
+/// Seconds between provider probes unless `[api.routing]` says otherwise
+const DEFAULT_PROBE_INTERVAL_SECS: u64 = 30;
+
+/// A provider that serves the OpenAI chat completions API
+struct UpstreamSpec {
+    /// Provider name, as used by the router and in `provider/model`
+    provider: &'static str,
+    /// Variable that overrides the endpoint
+    endpoint_var: &'static str,
+    default_endpoint: &'static str,
+    /// Path from the endpoint to the OpenAI-compatible API
+    api_path: &'static str,
+    /// Variable holding the API key, for cloud providers
+    key_var: Option<&'static str>,
+}
+
+const UPSTREAMS: &[UpstreamSpec] = &[
+    UpstreamSpec {
+        provider: "openai",
+        endpoint_var: "OPENAI_BASE_URL",
+        default_endpoint: "https://api.openai.com/v1",
+        api_path: "",
+        key_var: Some("OPENAI_API_KEY"),
+    },
+    UpstreamSpec {
+        provider: "anthropic",
+        endpoint_var: "ANTHROPIC_BASE_URL",
+        default_endpoint: "https://api.anthropic.com/v1",
+        api_path: "",
+        key_var: Some("ANTHROPIC_API_KEY"),
+    },
+    UpstreamSpec {
+        provider: "google",
+        endpoint_var: "GEMINI_BASE_URL",
+        default_endpoint: "https://generativelanguage.googleapis.com/v1beta/openai",
+        api_path: "",
+        key_var: Some("GOOGLE_API_KEY"),
+    },
+    UpstreamSpec {
+        provider: "ollama",
+        endpoint_var: "OLLAMA_HOST",
+        default_endpoint: "http://localhost:11434",
+        api_path: "/v1",
+        key_var: None,
+    },
+    UpstreamSpec {
+        provider: "vllm",
+        endpoint_var: "VLLM_ENDPOINT",
+        default_endpoint: "http://localhost:8000/v1",
+        api_path: "",
+        key_var: None,
+    },
+];
+
+/// Where requests for a provider are sent
+#[derive(Debug, Clone, PartialEq)]
+pub struct Upstream {
+    pub provider: String,
+    pub base_url: String,
+    pub api_key: Option<String>,
+    /// Whether the router may pick this provider: cloud providers need an API
+    /// key and local ones an endpoint variable
+    pub configured: bool,
+}
+
+impl Upstream {
+    /// Endpoint and key from the provider's variables, then from
+    /// `[api.providers.<name>]` in the config file
+    fn configure(spec: &UpstreamSpec, settings: Option<&ApiProviderConfig>) -> Self {
+        let settings = settings.cloned().unwrap_or_default();
+        let endpoint = std::env::var(spec.endpoint_var).ok().or(settings.endpoint);
+        let api_key = spec.key_var.and_then(|var| {
+            std::env::var(var)
+                .ok()
+                .or_else(|| settings.api_key.as_deref().and_then(config::expand_env))
+        });
+        let configured = settings.enabled != Some(false)
+            && match spec.key_var {
+                Some(_) => api_key.is_some(),
+                None => endpoint.is_some(),
+            };
+        let mut base_url = endpoint.unwrap_or_else(|| spec.default_endpoint.to_string());
+        // OLLAMA_HOST is often a bare host:port
+        if !base_url.contains("://") {
+            base_url = format!("http://{}", base_url);
+        }
+        let base_url = format!("{}{}", base_url.trim_end_matches('/'), spec.api_path);
+        Self {
+            provider: spec.provider.to_string(),
+            base_url,
+            api_key,
+            configured,
+        }
+    }
+}
+
+/// Where one request goes
+#[derive(Debug, Clone, PartialEq)]
+struct Target {
+    upstream: Upstream,
+    model: String,
+}
+
+/// Why a request's model could not be served
+#[derive(Debug, PartialEq)]
+enum ResolveError {
+    UnknownModel(String),
+    /// The model's provider has no API key or endpoint set
+    Unconfigured(String),
+}
+
+/// Upstreams and the router that picks between them, replaced together on
+/// reload
+struct Routes {
+    upstreams: Vec<Upstream>,
+    router: ModelRouter,
+    policy: RoutingConfig,
+    /// Time between provider probes; `None` when probing is off
+    probe_interval: Option<Duration>,
+    fallback: FallbackPolicy,
+}
+
+impl Routes {
+    fn new(
+        upstreams: Vec<Upstream>,
+        policy: RoutingConfig,
+        prices: &BTreeMap<String, ModelPrice>,
+    ) -> Self {
+        let mut router = ModelRouter::new();
+        for (model, price) in prices {
+            router.set_price(model, price.input, price.output);
+        }
+        let providers: Vec<String> = router.models().iter().map(|m| m.provider.clone()).collect();
+        for provider in providers {
+            let configured = upstreams
+                .iter()
+                .any(|u| u.provider == provider && u.configured);
+            router.set_provider_available(&provider, configured);
+        }
+        Self {
+            upstreams,
+            router,
+            policy,
+            probe_interval: Some(Duration::from_secs(DEFAULT_PROBE_INTERVAL_SECS)),
+            fallback: FallbackPolicy::default(),
+        }
+    }
+
+    fn upstream(&self, provider: &str) -> Option<&Upstream> {
+        self.upstreams.iter().find(|u| u.provider == provider)
+    }
+
+    /// The provider and model the router picks for a conversation
+    fn route(&mut self, conversation: &Conversation, config: RoutingConfig) -> (String, String) {
+        let prompt = conversation.prompt().unwrap_or_default().to_string();
+        let context: Vec<String> = conversation
+            .messages
+            .iter()
+            .map(|(_, text)| text.clone())
+            .collect();
+        let decision = self.router.route(&RoutingRequest {
+            id: uuid::Uuid::new_v4(),
+            estimated_tokens: context.iter().map(|t| estimate_tokens(t)).sum(),
+            max_output_tokens: conversation.max_output_tokens,
+            content: prompt,
+            context,
+            config,
+            timestamp: chrono::Utc::now(),
+        });
+        (decision.provider, decision.model_id)
+    }
+
+    /// The model of `provider` the router would pick for a conversation, or
+    /// the first it knows when the router will not pick one
+    fn provider_model(&mut self, provider: &str, conversation: &Conversation) -> Option<String> {
+        let mut config = self.policy.clone();
+        config.constraints.allowed_providers = Some(vec![provider.to_string()]);
+        config.constraints.blocked_providers.clear();
+        let (picked, model) = self.route(conversation, config);
+        if picked == provider {
+            return Some(model);
+        }
+        self.router
+            .models()
+            .iter()
+            .find(|m| m.provider == provider)
+            .map(|m| m.model_id.clone())
+    }
+}
+
+/// The fallback chain set in `[api.routing.fallback]`
+fn fallback_policy(settings: &ApiRoutingConfig) -> anyhow::Result<FallbackPolicy> {
+    let policy = FallbackPolicy::from_config(&settings.fallback)?;
+    if let Some(unknown) = policy
+        .chain
+        .iter()
+        .find(|t| !UPSTREAMS.iter().any(|spec| spec.provider == t.provider))
+    {
+        anyhow::bail!(
+            "Unknown provider '{}' in [api.routing.fallback] chain",
+            unknown.provider
+        );
+    }
+    Ok(policy)
+}
+
+/// The routing policy set in `[api.routing]`
+fn routing_policy(settings: &ApiRoutingConfig) -> anyhow::Result<RoutingConfig> {
+    let mut policy = RoutingConfig::default();
+    if let Some(strategy) = &settings.strategy {
+        policy.strategy = serde_json::from_value(Value::String(strategy.clone()))
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "Unknown routing strategy '{}'; use best_quality, lowest_cost, fastest_response, or balanced",
+                    strategy
+                )
+            })?;
+    }
+    policy.constraints.allowed_providers = settings.allowed_providers.clone();
+    policy.constraints.blocked_providers = settings.blocked_providers.clone().unwrap_or_default();
+    policy.constraints.max_cost = settings.max_cost;
+    policy.constraints.max_latency_ms = settings.max_latency_ms;
+    if let Some(min_quality) = settings.min_quality {
+        if !(0.0..=1.0).contains(&min_quality) {
+            anyhow::bail!("min_quality must be between 0 and 1, not {}", min_quality);
+        }
+        policy.min_quality = min_quality;
+    }
+    if settings.slow_ms == Some(0) {
+        anyhow::bail!("slow_ms must be above 0");
+    }
+    Ok(policy)
+}
+
+/// What the router thinks of one provider, for `GET /api/system/routing`
+#[derive(Debug, Serialize)]
+pub struct ProviderStatus {
+    #[serde(flatten)]
+    pub health: ProviderHealth,
+    /// 0 (down) to 1 (fast)
+    pub score: f64,
+    /// Whether `auto` may route to it at all
+    pub configured: bool,
+    pub models: usize,
+    /// Whether fallbacks skip it after repeated failures
+    pub circuit: BreakerState,
+}
+
+/// The routing policy and provider health in effect
+#[derive(Debug, Serialize)]
+pub struct RoutingStatus {
+    pub strategy: RoutingStrategy,
+    pub min_quality: f64,
+    /// `None` when probing is off
+    pub probe_interval_secs: Option<u64>,
+    pub policy: HealthPolicy,
+    /// `provider` or `provider/model`, tried in order when a request fails
+    pub fallback: Vec<String>,
+    /// Most providers one request is sent to
+    pub max_attempts: usize,
+    pub providers: Vec<ProviderStatus>,
+}
+
+/// Gateway state, registered as app data
+pub struct Gateway {
+    routes: Mutex<Routes>,
+    /// Kept across reloads, so a reload does not forget an outage
+    health: Mutex<HealthTracker>,
+    /// Also kept across reloads
+    breakers: Mutex<CircuitBreakers>,
+    policies: Mutex<Policies>,
+    client: reqwest::Client,
+}
+
+impl Gateway {
+    pub fn new(upstreams: Vec<Upstream>) -> Self {
+        let client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        Self {
+            routes: Mutex::new(Routes::new(
+                upstreams,
+                RoutingConfig::default(),
+                &BTreeMap::new(),
+            )),
+            health: Mutex::new(HealthTracker::default()),
+            breakers: Mutex::new(CircuitBreakers::default()),
+            policies: Mutex::new(Policies::default()),
+            client,
+        }
+    }
+
+    /// Replace the upstreams and routing policy. Requests already sent
+    /// upstream finish where they are.
+    pub fn reload(&self, settings: &ApiConfig) -> anyhow::Result<()> {
+        if let Some(unknown) = settings
+            .providers
+            .keys()
+            .find(|name| !UPSTREAMS.iter().any(|spec| spec.provider == name.as_str()))
+        {
+            anyhow::bail!(
+                "Unknown provider '{}' in [api.providers]; use {}",
+                unknown,
+                UPSTREAMS
+                    .iter()
+                    .map(|spec| spec.provider)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        let policy = routing_policy(&settings.routing)?;
+        let fallback = fallback_policy(&settings.routing)?;
+        let upstreams = UPSTREAMS
+            .iter()
+            .map(|spec| Upstream::configure(spec, settings.providers.get(spec.provider)))
+            .collect();
+        let mut routes = Routes::new(upstreams, policy, &settings.routing.prices);
+        routes.probe_interval = match settings.routing.probe_interval_secs {
+            Some(0) => None,
+            secs => Some(Duration::from_secs(secs.unwrap_or(DEFAULT_PROBE_INTERVAL_SECS))),
+        };
+        self.breakers
+            .lock()
+            .unwrap()
+            .set_limits(fallback.breaker_failures, fallback.breaker_cooldown);
+        routes.fallback = fallback;
+        let mut health = self.health.lock().unwrap();
+        let policy = HealthPolicy {
+            slow_ms: settings
+                .routing
+                .slow_ms
+                .unwrap_or(HealthPolicy::default().slow_ms),
+            ..*health.policy()
+        };
+        health.set_policy(policy);
+        health.apply(&mut routes.router);
+        *self.routes.lock().unwrap() = routes;
+        Ok(())
+    }
+
+    /// Replace the workspace policies requests are checked against
+    pub fn set_policies(&self, policies: Policies) {
+        *self.policies.lock().unwrap() = policies;
+    }
+
+    /// The policy covering a workspace name or directory
+    fn policy_for(&self, workspace: &str) -> Option<WorkspacePolicy> {
+        self.policies.lock().unwrap().resolve(workspace).cloned()
+    }
+
+    /// Time between provider probes; `None` when probing is off
+    pub fn probe_interval(&self) -> Option<Duration> {
+        self.routes.lock().unwrap().probe_interval
+    }
+
+    /// Count a probe of, or a request to, `provider`: its latency on
+    /// success, or why it failed
+    pub fn record_health(&self, provider: &str, result: Result<u64, String>) {
+        let mut health = self.health.lock().unwrap();
+        match result {
+            Ok(latency_ms) => health.record_success(provider, latency_ms),
+            Err(error) => health.record_failure(provider, &error),
+        }
+        health.apply(&mut self.routes.lock().unwrap().router);
+    }
+
+    /// The routing policy and what the router thinks of each provider
+    pub fn routing_status(&self) -> RoutingStatus {
+        let health = self.health.lock().unwrap();
+        let routes = self.routes.lock().unwrap();
+        let breakers = self.breakers.lock().unwrap();
+        let policy = *health.policy();
+        let providers = routes
+            .upstreams
+            .iter()
+            .map(|upstream| {
+                let health = health
+                    .get(&upstream.provider)
+                    .cloned()
+                    .unwrap_or_else(|| ProviderHealth::new(&upstream.provider));
+                ProviderStatus {
+                    score: health.score(&policy),
+                    configured: upstream.configured,
+                    models: routes
+                        .router
+                        .models()
+                        .iter()
+                        .filter(|m| m.provider == upstream.provider)
+                        .count(),
+                    circuit: breakers.state(&upstream.provider),
+                    health,
+                }
+            })
+            .collect();
+        RoutingStatus {
+            strategy: routes.policy.strategy,
+            min_quality: routes.policy.min_quality,
+            probe_interval_secs: routes.probe_interval.map(|d| d.as_secs()),
+            policy,
+            fallback: routes.fallback.chain.iter().map(|t| t.to_string()).collect(),
+            max_attempts: routes.fallback.max_attempts,
+            providers,
+        }
+    }
+
+    /// Upstreams the router can choose from
+    pub fn configured_upstreams(&self) -> Vec<Upstream> {
+        self.routes
+            .lock()
+            .unwrap()
+            .upstreams
+            .iter()
+            .filter(|u| u.configured)
+            .cloned()
+            .collect()
+    }
+
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Providers the router can choose from
+    pub fn configured_providers(&self) -> Vec<String> {
+        self.configured_upstreams()
+            .into_iter()
+            .map(|u| u.provider)
+            .collect()
+    }
+
+    /// Pick the upstream and model for a request's `model` field; `auto`
+    /// only picks providers `policy` allows
+    fn resolve(
+        &self,
+        model: &str,
+        conversation: &Conversation,
+        policy: Option<&WorkspacePolicy>,
+    ) -> Result<Target, ResolveError> {
+        let mut guard = self.routes.lock().unwrap();
+        let routes = &mut *guard;
+        if let Some((provider, name)) = model.split_once('/') {
+            if let Some(upstream) = routes.upstream(provider) {
+                return Ok(Target {
+                    upstream: upstream.clone(),
+                    model: name.to_string(),
+                });
+            }
+        }
+
+        let (provider, model) = if model.is_empty() || model == "auto" {
+            let mut config = routes.policy.clone();
+            if let Some(policy) = policy {
+                config.constraints.blocked_providers.extend(
+                    routes
+                        .upstreams
+                        .iter()
+                        .filter(|u| !policy.allows(&u.provider))
+                        .map(|u| u.provider.clone()),
+                );
+            }
+            routes.route(conversation, config)
+        } else {
+            match routes.router.models().iter().find(|m| m.model_id == model) {
+                Some(known) => (known.provider.clone(), known.model_id.clone()),
+                None => return Err(ResolveError::UnknownModel(model.to_string())),
+            }
+        };
+
+        match routes.upstream(&provider) {
+            Some(upstream) if upstream.configured => Ok(Target {
+                upstream: upstream.clone(),
+                model,
+            }),
+            _ => Err(ResolveError::Unconfigured(model)),
+        }
+    }
+
+    /// `primary`, then the configured providers of the fallback chain, at
+    /// most `max_attempts` in all, leaving out the providers `policy` does
+    /// not allow. Gives the candidates and the ones left out.
+    fn candidates(
+        &self,
+        primary: Target,
+        conversation: &Conversation,
+        policy: Option<&WorkspacePolicy>,
+    ) -> (Vec<Target>, Vec<Attempt>) {
+        let mut guard = self.routes.lock().unwrap();
+        let routes = &mut *guard;
+        let plan = routes.fallback.plan(&FallbackTarget {
+            provider: primary.upstream.provider.clone(),
+            model: Some(primary.model.clone()),
+        });
+        let max_attempts = routes.fallback.max_attempts;
+        let primary_model = primary.model.clone();
+        let mut candidates = Vec::new();
+        let mut denied = Vec::new();
+        let mut consider = |target: Target| {
+            match policy.map_or(Ok(()), |p| p.decide(&target.upstream.provider)) {
+                Ok(()) => candidates.push(target),
+                Err(reason) => denied.push(Attempt {
+                    target: format!("{}/{}", target.upstream.provider, target.model),
+                    error: reason,
+                }),
+            }
+        };
+        consider(primary);
+        for step in plan {
+            let Some(upstream) = routes
+                .upstream(&step.provider)
+                .filter(|u| u.configured)
+                .cloned()
+            else {
+                continue;
+            };
+            let model = step
+                .model
+                .or_else(|| routes.provider_model(&step.provider, conversation))
+                .unwrap_or_else(|| primary_model.clone());
+            consider(Target { upstream, model });
+        }
+        candidates.truncate(max_attempts);
+        (candidates, denied)
+    }
+
+    /// Send a chat request to each candidate in turn while providers are
+    /// unreachable or answer with a status worth retrying. Gives the last
+    /// answer or error, the target it came from, and the attempts that
+    /// failed before it, after those in `attempts`.
+    async fn send(
+        &self,
+        body: &mut Value,
+        candidates: Vec<Target>,
+        mut attempts: Vec<Attempt>,
+    ) -> (
+        Result<reqwest::Response, reqwest::Error>,
+        Target,
+        Vec<Attempt>,
+    ) {
+        let last = candidates.len() - 1;
+        for (i, target) in candidates.into_iter().enumerate() {
+            let provider = target.upstream.provider.clone();
+            let name = format!("{}/{}", provider, target.model);
+            // The last candidate is always tried, so a request is never
+            // refused for open circuits alone
+            if i < last && !self.breakers.lock().unwrap().allow(&provider) {
+                attempts.push(Attempt {
+                    target: name,
+                    error: "circuit open".to_string(),
+                });
+                continue;
+            }
+
+            body["model"] = Value::String(target.model.clone());
+            let url = format!("{}/chat/completions", target.upstream.base_url);
+            let mut request = self.client.post(&url).json(&*body);
+            if let Some(key) = &target.upstream.api_key {
+                request = request.bearer_auth(key);
+            }
+            match request.send().await {
+                Ok(response) => {
+                    let status = response.status();
+                    if !is_retryable_status(status.as_u16()) {
+                        self.breakers.lock().unwrap().record_success(&provider);
+                        return (Ok(response), target, attempts);
+                    }
+                    self.breakers.lock().unwrap().record_failure(&provider);
+                    // Answers take as long as they are, so only failures say
+                    // anything about the provider
+                    if status.is_server_error() {
+                        self.record_health(&provider, Err(format!("Answered {}", status)));
+                    }
+                    if i == last {
+                        return (Ok(response), target, attempts);
+                    }
+                    attempts.push(Attempt {
+                        target: name,
+                        error: format!("answered {}", status),
+                    });
+                }
+                Err(e) => {
+                    self.breakers.lock().unwrap().record_failure(&provider);
+                    self.record_health(&provider, Err(e.to_string()));
+                    if i == last {
+                        return (Err(e), target, attempts);
+                    }
+                    attempts.push(Attempt {
+                        target: name,
+                        error: format!("unreachable: {}", e),
+                    });
+                }
+            }
+        }
+        unreachable!("the last candidate always gives an answer or an error")
+    }
+
+    /// Cost in USD of a completion; local providers are free, and models
+    /// without a known price give `None`
+    fn cost(&self, target: &Target, prompt_tokens: u64, completion_tokens: u64) -> Option<f64> {
+        let local = UPSTREAMS
+            .iter()
+            .any(|spec| spec.provider == target.upstream.provider && spec.key_var.is_none());
+        if local {
+            return Some(0.0);
+        }
+        self.routes
+            .lock()
+            .unwrap()
+            .router
+            .cost(&target.model, prompt_tokens, completion_tokens)
+    }
+}
+
+/// The text of a chat request, and the session it is recorded in
+struct Conversation {
+    session_id: String,
+    /// Role and text of each message
+    messages: Vec<(String, String)>,
+    /// The request's `max_tokens` or `max_completion_tokens`
+    max_output_tokens: Option<usize>,
+    /// Whose sessions the caller can record into
+    scope: Scope,
+    /// Workspace name or directory from `X-Chasm-Workspace`
+    workspace: Option<String>,
+}
+
+impl Conversation {
+    fn from_request(req: &HttpRequest, body: &Value, scope: Scope) -> Option<Self> {
+        let messages: Vec<(String, String)> = body
+            .get("messages")?
+            .as_array()?
+            .iter()
+            .map(|m| {
+                let role = m.get("role").and_then(Value::as_str).unwrap_or_default();
+                let text = m.get("content").map(content_text).unwrap_or_default();
+                (role.to_string(), text)
+            })
+            .collect();
+
+        let session_id = match req
+            .headers()
+            .get(SESSION_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            Some(id) if !id.trim().is_empty() => id.trim().to_string(),
+            _ => derive_session_id(scope.owner(), &messages),
+        };
+        let max_output_tokens = ["max_completion_tokens", "max_tokens"]
+            .iter()
+            .find_map(|key| body.get(*key).and_then(Value::as_u64))
+            .map(|max| max as usize);
+        let workspace = req
+            .headers()
+            .get(WORKSPACE_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|w| !w.is_empty())
+            .map(str::to_string);
+        Some(Self {
+            session_id,
+            messages,
+            max_output_tokens,
+            scope,
+            workspace,
+        })
+    }
+
+    /// Whether the session exists and belongs to someone the caller cannot see
+    fn belongs_elsewhere(&self, state: &AppState) -> anyhow::Result<bool> {
+        let db = state.db.lock().unwrap();
+        Ok(load_stored_session(&db.conn, &self.session_id)?
+            .is_some_and(|stored| !self.scope.can_access(stored.owner.as_deref())))
+    }
+
+    /// The latest user message
+    fn prompt(&self) -> Option<&str> {
+        self.messages
+            .iter()
+            .rev()
+            .find(|(role, _)| role == "user")
+            .map(|(_, text)| text.as_str())
+    }
+}
+
+/// Clients resend the whole conversation on every turn, so a conversation is
+/// identified by how it starts: the system prompt and first user message.
+/// The owner is included so two users starting alike get separate sessions.
+fn derive_session_id(owner: Option<&str>, messages: &[(String, String)]) -> String {
+    let mut hasher = Sha256::new();
+    if let Some(owner) = owner {
+        hasher.update(owner.as_bytes());
+        hasher.update([0]);
+    }
+    for (role, text) in messages {
+        hasher.update(role.as_bytes());
+        hasher.update([0]);
+        hasher.update(text.as_bytes());
+        hasher.update([0]);
+        if role == "user" {
+            break;
+        }
+    }
+    let digest = hasher.finalize();
+    let hex: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("gateway-{}", hex)
+}
+
+/// Message content as text; content may be a string or a list of parts
+fn content_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|p| p.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// Prompt and completion tokens from a response's `usage`
+fn usage_tokens(response: &Value) -> Option<(u64, u64)> {
+    let usage = response.get("usage")?;
+    Some((
+        usage.get("prompt_tokens")?.as_u64()?,
+        usage.get("completion_tokens")?.as_u64()?,
+    ))
+}
+
+/// Assembles the reply text from streamed `data:` lines
+#[derive(Default)]
+struct StreamCollector {
+    pending: String,
+    reply: String,
+    /// Sent in the last chunk when the client asks with `stream_options`
+    usage: Option<(u64, u64)>,
+}
+
+impl StreamCollector {
+    fn push(&mut self, chunk: &[u8]) {
+        self.pending.push_str(&String::from_utf8_lossy(chunk));
+        while let Some(end) = self.pending.find('\n') {
+            let line: String = self.pending.drain(..=end).collect();
+            let Some(data) = line.trim().strip_prefix("data:") else {
+                continue;
+            };
+            if let Ok(event) = serde_json::from_str::<Value>(data.trim()) {
+                if let Some(text) = event
+                    .pointer("/choices/0/delta/content")
+                    .and_then(Value::as_str)
+                {
+                    self.reply.push_str(text);
+                }
+                if let Some(usage) = usage_tokens(&event) {
+                    self.usage = Some(usage);
+                }
+            }
+        }
+    }
+}
+
+/// Store the conversation and its reply as a harvested session
+fn record(
+    state: &AppState,
+    sync_state: &SharedSyncState,
+    conversation: &Conversation,
+    reply: &str,
+    target: &Target,
+) -> anyhow::Result<()> {
+    let db = state.db.lock().unwrap();
+    let existing = load_stored_session(&db.conn, &conversation.session_id)?;
+    if let Some(existing) = &existing {
+        if !conversation.scope.can_access(existing.owner.as_deref()) {
+            anyhow::bail!(
+                "session {} belongs to another user",
+                conversation.session_id
+            );
+        }
+    }
+    let operation = if existing.is_some() {
+        SyncOperation::Update
+    } else {
+        SyncOperation::Create
+    };
+    let mut stored = existing.unwrap_or_else(|| StoredSession {
+        provider: target.upstream.provider.clone(),
+        workspace_id: None,
+        workspace_name: None,
+        owner: conversation.scope.owner().map(str::to_string),
+        session: new_api_session(&conversation.session_id),
+    });
+
+    // The request carries the whole conversation, so rebuild it
+    stored.session.requests.clear();
+    let messages = conversation
+        .messages
+        .iter()
+        .map(|(role, text)| (role.as_str(), text.as_str()))
+        .chain(std::iter::once(("assistant", reply)));
+    for (role, text) in messages {
+        if !matches!(role, "user" | "assistant") || text.is_empty() {
+            continue;
+        }
+        let message = NewMessage {
+            role: role.to_string(),
+            content: text.to_string(),
+            model: (role == "assistant").then(|| target.model.clone()),
+            timestamp: None,
+        };
+        let _ = append_message(&mut stored.session, &message);
+    }
+
+    store_session(&db.conn, &stored, None)?;
+    sync::publish_session_change(sync_state, operation, &conversation.session_id, None);
+    Ok(())
+}
+
+fn record_or_warn(
+    state: &AppState,
+    sync_state: &SharedSyncState,
+    conversation: &Conversation,
+    reply: &str,
+    target: &Target,
+) {
+    if let Err(e) = record(state, sync_state, conversation, reply, target) {
+        eprintln!("[WARN] Failed to record gateway conversation: {}", e);
+    }
+}
+
+/// Record a completion's tokens and cost in `agent_usage`. Without the
+/// provider's `usage`, the tokens are estimated from the text.
+fn record_cost_or_warn(
+    state: &AppState,
+    gateway: &Gateway,
+    conversation: &Conversation,
+    reply: &str,
+    usage: Option<(u64, u64)>,
+    target: &Target,
+) {
+    let (prompt_tokens, completion_tokens) = usage.unwrap_or_else(|| {
+        let prompt: usize = conversation
+            .messages
+            .iter()
+            .map(|(_, text)| estimate_tokens(text))
+            .sum();
+        (prompt as u64, estimate_tokens(reply) as u64)
+    });
+    let spend = Spend {
+        agent: "gateway".to_string(),
+        model: target.model.clone(),
+        calls: 1,
+        prompt_tokens,
+        completion_tokens,
+        cost_usd: gateway.cost(target, prompt_tokens, completion_tokens),
+    };
+    let db = state.db.lock().unwrap();
+    if let Err(e) = budget::record_spend(&db.conn, &conversation.session_id, &spend) {
+        eprintln!("[WARN] Failed to record gateway cost: {}", e);
+    }
+}
+
+/// Record which providers a policy allowed or refused for one request. They
+/// go to the harvest database, next to the decisions of agents and `csm run`.
+fn record_policy_or_warn(
+    conversation: &Conversation,
+    policy: &WorkspacePolicy,
+    targets: &[String],
+) {
+    let workspace = conversation.workspace.as_deref().unwrap_or_default();
+    let recorded = crate::commands::get_db_path(None)
+        .and_then(|path| Ok(rusqlite::Connection::open(path)?))
+        .and_then(|conn| {
+            for target in targets {
+                let (provider, model) = target
+                    .split_once('/')
+                    .map_or((target.as_str(), None), |(p, m)| (p, Some(m)));
+                let decision = PolicyDecision::new("gateway", workspace, policy, provider, model);
+                record_decision(&conn, &decision)?;
+            }
+            Ok(())
+        });
+    if let Err(e) = recorded {
+        eprintln!("[WARN] Failed to record policy decision: {}", e);
+    }
+}
+
+/// Error body in the OpenAI format, which OpenAI clients know how to show
+fn openai_error(status: actix_web::http::StatusCode, kind: &str, message: &str) -> HttpResponse {
+    HttpResponse::build(status).json(serde_json::json!({
+        "error": { "message": message, "type": kind }
+    }))
+}
+
+/// Create a chat completion through the configured providers
+#[utoipa::path(
+    post,
+    path = "/v1/chat/completions",
+    tag = "gateway",
+    request_body(content = Object, description = "OpenAI chat completion request; `model` may be `auto` or `provider/model`"),
+    responses(
+        (status = 200, description = "OpenAI chat completion, or a `text/event-stream` when `stream` is true", body = Object),
+        (status = 400, description = "Invalid request"),
+        (status = 403, description = "The workspace's policy allows none of the providers that could serve the model"),
+        (status = 404, description = "Unknown model"),
+        (status = 502, description = "The provider could not be reached"),
+        (status = 503, description = "No configured provider can serve the model")
+    )
+)]
+pub async fn chat_completions(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    sync_state: web::Data<SharedSyncState>,
+    gateway: web::Data<Gateway>,
+    body: web::Json<Value>,
+    scope: Scope,
+) -> HttpResponse {
+    use actix_web::http::StatusCode;
+
+    let mut body = body.into_inner();
+    let Some(conversation) = Conversation::from_request(&req, &body, scope) else {
+        return openai_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_request_error",
+            "messages must be an array",
+        );
+    };
+    if conversation.belongs_elsewhere(&state).unwrap_or(false) {
+        return openai_error(
+            StatusCode::NOT_FOUND,
+            "invalid_request_error",
+            &format!("Session {} not found", conversation.session_id),
+        );
+    }
+    let model = body
+        .get("model")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let policy = conversation
+        .workspace
+        .as_deref()
+        .and_then(|workspace| gateway.policy_for(workspace));
+    let target = match gateway.resolve(&model, &conversation, policy.as_ref()) {
+        Ok(target) => target,
+        Err(ResolveError::UnknownModel(model)) => {
+            return openai_error(
+                StatusCode::NOT_FOUND,
+                "invalid_request_error",
+                &format!(
+                    "Unknown model '{}'; use auto, a model from /v1/models, or provider/model",
+                    model
+                ),
+            )
+        }
+        Err(ResolveError::Unconfigured(model)) => {
+            return openai_error(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "gateway_error",
+                &format!(
+                    "No configured provider serves {}; set its API key or endpoint variable, or use provider/model",
+                    model
+                ),
+            )
+        }
+    };
+    let stream = body.get("stream").and_then(Value::as_bool).unwrap_or(false);
+
+    let (candidates, denied) = gateway.candidates(target, &conversation, policy.as_ref());
+    let mut policy_targets: Vec<String> = denied.iter().map(|a| a.target.clone()).collect();
+    if candidates.is_empty() {
+        if let Some(policy) = &policy {
+            record_policy_or_warn(&conversation, policy, &policy_targets);
+        }
+        return openai_error(
+            StatusCode::FORBIDDEN,
+            "policy_error",
+            &format!("Denied by workspace policy: {}", describe_attempts(&denied)),
+        );
+    }
+    let (response, target, attempts) = gateway.send(&mut body, candidates, denied).await;
+    if let Some(policy) = &policy {
+        policy_targets.push(format!("{}/{}", target.upstream.provider, target.model));
+        record_policy_or_warn(&conversation, policy, &policy_targets);
+    }
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => {
+            let mut message = format!("{} is unreachable: {}", target.upstream.provider, e);
+            if !attempts.is_empty() {
+                message.push_str(&format!(" (after {})", describe_attempts(&attempts)));
+            }
+            return openai_error(StatusCode::BAD_GATEWAY, "gateway_error", &message);
+        }
+    };
+    if !attempts.is_empty() {
+        eprintln!(
+            "[WARN] Gateway request served by {}/{} after {}",
+            target.upstream.provider,
+            target.model,
+            describe_attempts(&attempts)
+        );
+    }
+
+    let status =
+        StatusCode::from_u16(response.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/json")
+        .to_string();
+    let mut builder = HttpResponse::build(status);
+    builder
+        .insert_header((header::CONTENT_TYPE, content_type))
+        .insert_header(("X-Chasm-Provider", target.upstream.provider.clone()))
+        .insert_header(("X-Chasm-Model", target.model.clone()))
+        .insert_header((SESSION_HEADER, conversation.session_id.clone()));
+    if !attempts.is_empty() {
+        let failed: Vec<&str> = attempts.iter().map(|a| a.target.as_str()).collect();
+        builder.insert_header((FALLBACK_HEADER, failed.join(", ")));
+    }
+
+    // Provider errors are passed through and not recorded
+    if !status.is_success() || !stream {
+        let bytes = match response.bytes().await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                return openai_error(StatusCode::BAD_GATEWAY, "gateway_error", &e.to_string())
+            }
+        };
+        if status.is_success() {
+            let completion = serde_json::from_slice::<Value>(&bytes).ok();
+            let reply = completion
+                .as_ref()
+                .and_then(|v| v.pointer("/choices/0/message/content").map(content_text))
+                .unwrap_or_default();
+            record_or_warn(&state, &sync_state, &conversation, &reply, &target);
+            let usage = completion.as_ref().and_then(usage_tokens);
+            record_cost_or_warn(&state, &gateway, &conversation, &reply, usage, &target);
+        }
+        return builder.body(bytes);
+    }
+
+    // Pass chunks on as they arrive rather than through the compressor
+    builder.insert_header(header::ContentEncoding::Identity);
+    let mut response = response;
+    let body = async_stream::stream! {
+        let mut collector = StreamCollector::default();
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => {
+                    collector.push(&chunk);
+                    yield Ok::<_, std::io::Error>(chunk);
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    eprintln!("[WARN] Gateway stream from {} failed: {}", target.upstream.provider, e);
+                    break;
+                }
+            }
+        }
+        record_or_warn(&state, &sync_state, &conversation, &collector.reply, &target);
+        record_cost_or_warn(&state, &gateway, &conversation, &collector.reply, collector.usage, &target);
+    };
+    builder.streaming(body)
+}
+
+/// List the models `auto` routes between, plus `auto` itself
+#[utoipa::path(
+    get,
+    path = "/v1/models",
+    tag = "gateway",
+    responses((status = 200, description = "OpenAI model list", body = Object))
+)]
+pub async fn list_models(gateway: web::Data<Gateway>) -> HttpResponse {
+    let routes = gateway.routes.lock().unwrap();
+    let router = &routes.router;
+    let mut data = vec![serde_json::json!({
+        "id": "auto",
+        "object": "model",
+        "created": 0,
+        "owned_by": "chasm",
+    })];
+    data.extend(router.models().iter().filter(|m| m.available).map(|m| {
+        serde_json::json!({
+            "id": m.model_id,
+            "object": "model",
+            "created": 0,
+            "owned_by": m.provider,
+        })
+    }));
+    HttpResponse::Ok().json(serde_json::json!({ "object": "list", "data": data }))
+}
+
+/// Provider health, latency, and scores as the router currently sees them
+#[utoipa::path(
+    get,
+    path = "/api/system/routing",
+    tag = "system",
+    responses((status = 200, description = "Routing policy and the health of each provider", body = ApiEnvelope))
+)]
+pub async fn routing_status(gateway: web::Data<Gateway>) -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "data": gateway.routing_status(),
+    }))
+}
+
+/// Configure the gateway routes
+pub fn configure_gateway_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/v1")
+            .route("/chat/completions", web::post().to(chat_completions))
+            .route("/models", web::get().to(list_models)),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upstream(provider: &str, configured: bool) -> Upstream {
+        Upstream {
+            provider: provider.to_string(),
+            base_url: format!("http://{}.test/v1", provider),
+            api_key: None,
+            configured,
+        }
+    }
+
+    #[test]
+    fn test_resolve_and_collect() {
+        let gateway = Gateway::new(vec![upstream("ollama", true), upstream("openai", false)]);
+        let conversation = Conversation {
+            session_id: String::new(),
+            messages: vec![("user".into(), "Write a Python function".into())],
+            max_output_tokens: None,
+            scope: Scope::All,
+            workspace: None,
+        };
+
+        let explicit = gateway.resolve("ollama/llama3.2", &conversation, None).unwrap();
+        assert_eq!(explicit.model, "llama3.2");
+
+        // Only configured providers are routed to
+        let routed = gateway.resolve("auto", &conversation, None).unwrap();
+        assert_eq!(routed.upstream.provider, "ollama");
+        assert_eq!(
+            gateway.resolve("gpt-4o", &conversation, None),
+            Err(ResolveError::Unconfigured("gpt-4o".into()))
+        );
+        assert_eq!(
+            gateway.resolve("vllm/x", &conversation, None),
+            Err(ResolveError::UnknownModel("vllm/x".into()))
+        );
+
+        let mut collector = StreamCollector::default();
+        collector.push(b"data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\ndata: {\"choi");
+        collector.push(b"ces\":[{\"delta\":{\"content\":\"lo\"}}]}\n\ndata: [DONE]\n\n");
+        assert_eq!(collector.reply, "Hello");
+        collector.push(b"data: {\"choices\":[],\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":2}}\n");
+        assert_eq!(collector.usage, Some((9, 2)));
+
+        // Local providers are free; cloud models are priced from the table
+        let local = gateway.resolve("ollama/llama3.2", &conversation, None).unwrap();
+        assert_eq!(gateway.cost(&local, 1000, 1000), Some(0.0));
+        let cloud = Target {
+            upstream: upstream("openai", true),
+            model: "gpt-4o-mini".into(),
+        };
+        assert_eq!(gateway.cost(&cloud, 1_000_000, 0), Some(0.15));
+
+        let first = [
+            ("system".to_string(), "be brief".to_string()),
+            ("user".into(), "hi".into()),
+        ];
+        let mut longer = first.to_vec();
+        longer.push(("assistant".into(), "hello".into()));
+        assert_eq!(
+            derive_session_id(None, &first),
+            derive_session_id(None, &longer)
+        );
+        assert_ne!(
+            derive_session_id(Some("u1"), &first),
+            derive_session_id(None, &first)
+        );
+    }
+
+    #[test]
+    fn test_down_provider_is_avoided() {
+        let gateway = Gateway::new(vec![upstream("ollama", true), upstream("openai", true)]);
+        let conversation = Conversation {
+            session_id: String::new(),
+            messages: vec![("user".into(), "Write a Python function".into())],
+            max_output_tokens: None,
+            scope: Scope::All,
+            workspace: None,
+        };
+        let first = gateway.resolve("auto", &conversation, None).unwrap();
+        let provider = first.upstream.provider.clone();
+
+        gateway.record_health(&provider, Err("connection refused".into()));
+        gateway.record_health(&provider, Err("connection refused".into()));
+        let routed = gateway.resolve("auto", &conversation, None).unwrap();
+        assert_ne!(routed.upstream.provider, provider);
+
+        let status = gateway.routing_status();
+        let down = status
+            .providers
+            .iter()
+            .find(|p| p.health.provider == provider)
+            .unwrap();
+        assert_eq!(down.health.state, crate::routing::ProviderState::Down);
+        assert_eq!(down.score, 0.0);
+        assert_eq!(
+            down.health.last_error.as_deref(),
+            Some("connection refused")
+        );
+    }
+
+    #[test]
+    fn test_fallback_candidates() {
+        let gateway = Gateway::new(vec![
+            upstream("vllm", true),
+            upstream("ollama", true),
+            upstream("google", false),
+            upstream("openai", true),
+        ]);
+        gateway.routes.lock().unwrap().fallback = FallbackPolicy::from_config(
+            &config::FallbackConfig {
+                chain: vec!["google".into(), "ollama/llama3.2".into(), "openai".into()],
+                max_attempts: Some(3),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let conversation = Conversation {
+            session_id: String::new(),
+            messages: vec![("user".into(), "Write a Python function".into())],
+            max_output_tokens: None,
+            scope: Scope::All,
+            workspace: None,
+        };
+
+        // Unconfigured steps are left out, and the budget counts the primary
+        let primary = gateway.resolve("vllm/qwen", &conversation, None).unwrap();
+        let (candidates, denied) = gateway.candidates(primary, &conversation, None);
+        assert!(denied.is_empty());
+        let names: Vec<String> = candidates
+            .iter()
+            .map(|t| format!("{}/{}", t.upstream.provider, t.model))
+            .collect();
+        assert_eq!(names[..2], ["vllm/qwen", "ollama/llama3.2"]);
+        // A bare provider gets a model the router knows for it
+        assert_eq!(candidates[2].upstream.provider, "openai");
+        assert!(gateway
+            .routes
+            .lock()
+            .unwrap()
+            .router
+            .models()
+            .iter()
+            .any(|m| m.provider == "openai" && m.model_id == candidates[2].model));
+
+        // A local-only workspace never reaches OpenAI, even by `auto`
+        let mut settings = BTreeMap::new();
+        settings.insert(
+            "client-a".to_string(),
+            config::PolicyConfig {
+                workspaces: vec!["client-a".into()],
+                local_only: Some(true),
+                ..Default::default()
+            },
+        );
+        gateway.set_policies(Policies::from_config(&settings).unwrap());
+        let policy = gateway.policy_for("client-a").unwrap();
+        let primary = gateway.resolve("openai/gpt-4o", &conversation, None).unwrap();
+        let (candidates, denied) = gateway.candidates(primary, &conversation, Some(&policy));
+        let names: Vec<String> = candidates
+            .iter()
+            .map(|t| format!("{}/{}", t.upstream.provider, t.model))
+            .collect();
+        assert_eq!(names, ["ollama/llama3.2"]);
+        assert_eq!(denied[0].target, "openai/gpt-4o");
+        let routed = gateway
+            .resolve("auto", &conversation, Some(&policy))
+            .unwrap();
+        assert_ne!(routed.upstream.provider, "openai");
+        assert!(gateway.policy_for("client-b").is_none());
+
+        let mut breakers = gateway.breakers.lock().unwrap();
+        for _ in 0..3 {
+            breakers.record_failure("vllm");
+        }
+        drop(breakers);
+        let status = gateway.routing_status();
+        let vllm = status
+            .providers
+            .iter()
+            .find(|p| p.health.provider == "vllm")
+            .unwrap();
+        assert_eq!(vllm.circuit, BreakerState::Open);
+        assert_eq!(status.fallback, ["google", "ollama/llama3.2", "openai"]);
+    }
+}
@@ -2,27 +2,69 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //! GraphQL API module
 //!
-//! Provides a GraphQL endpoint alongside REST for flexible querying.
-
-use actix_web::{web, HttpResponse, Responder};
+//! A read-only GraphQL endpoint over the harvest database, served alongside
+//! REST at `/api/graphql`. Workspaces, sessions, messages, and tool
+//! invocations nest inside each other, so a dashboard can fetch a workspace's
+//! sessions and their messages in one request instead of one REST call per
+//! level. Lists page with the same opaque cursors as the REST endpoints.
+//! Queries see the same sessions as the REST endpoints for the caller's
+//! [`Scope`].
+
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use async_graphql::{
-    Context, EmptySubscription, FieldResult, InputObject, Object, Schema, SimpleObject, ID,
+    BatchRequest, ComplexObject, Context, EmptyMutation, EmptySubscription, Json, Object,
+    OutputType, Result, Schema, SimpleObject, ID,
 };
-use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
-use chrono::{DateTime, Utc};
-use std::sync::Arc;
+use rusqlite::{params, Connection, OptionalExtension};
 
+use super::access::Scope;
+use super::handlers_simple::{extract_messages_from_session, get_workspace_info, workspaces_sql};
+use super::pagination::{self, Cursor};
 use super::state::AppState;
 
 /// GraphQL schema type
-pub type ChasmSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+pub type ChasmSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Deepest query accepted, enough for workspace > session > message > tool
+const MAX_DEPTH: usize = 10;
+
+/// Most expensive query accepted, counting one per field
+const MAX_COMPLEXITY: usize = 2000;
 
 // ============================================================================
 // GraphQL Types
 // ============================================================================
 
-/// Workspace type for GraphQL
+/// One page of a list, with the cursor for the next page
+#[derive(SimpleObject)]
+#[graphql(concrete(name = "WorkspacePage", params(Workspace)))]
+#[graphql(concrete(name = "SessionPage", params(Session)))]
+#[graphql(concrete(name = "MessagePage", params(Message)))]
+pub struct Page<T: OutputType> {
+    /// Items on this page
+    pub items: Vec<T>,
+    /// Total number of items across all pages
+    pub total: i64,
+    /// Whether another page follows
+    pub has_more: bool,
+    /// Pass as `after` to fetch the next page
+    pub next_cursor: Option<String>,
+}
+
+impl<T: OutputType> Page<T> {
+    fn new(items: Vec<T>, total: i64, next_cursor: Option<String>) -> Self {
+        Self {
+            items,
+            total,
+            has_more: next_cursor.is_some(),
+            next_cursor,
+        }
+    }
+}
+
+/// Workspace with harvested sessions
 #[derive(SimpleObject, Clone)]
+#[graphql(complex)]
 pub struct Workspace {
     /// Unique identifier
     pub id: ID,
@@ -30,175 +72,186 @@ pub struct Workspace {
     pub name: String,
     /// Workspace path
     pub path: String,
-    /// Provider (e.g., "vscode", "cursor")
+    /// Provider (e.g., "copilot", "cursor")
     pub provider: String,
     /// Number of sessions
-    pub session_count: i32,
-    /// Last harvested timestamp
-    pub last_harvested: Option<DateTime<Utc>>,
+    pub session_count: i64,
     /// Creation timestamp
-    pub created_at: DateTime<Utc>,
+    pub created_at: Option<i64>,
+    /// Timestamp of the most recently updated session
+    pub updated_at: i64,
+}
+
+#[ComplexObject]
+impl Workspace {
+    /// Sessions in this workspace, most recently updated first
+    async fn sessions(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<Page<Session>> {
+        let filter = SessionFilter {
+            workspace_id: Some(self.id.clone()),
+            ..Default::default()
+        };
+        let state = ctx.data::<web::Data<AppState>>()?;
+        let db = state.db.lock().unwrap();
+        query_sessions(&db.conn, &scope(ctx), &filter, first, after.as_deref())
+    }
 }
 
-/// Session type for GraphQL
+/// Harvested chat session
 #[derive(SimpleObject, Clone)]
+#[graphql(complex)]
 pub struct Session {
     /// Unique identifier
     pub id: ID,
     /// Session title
     pub title: String,
-    /// Workspace ID
-    pub workspace_id: Option<ID>,
     /// Provider (e.g., "copilot", "cursor")
     pub provider: String,
-    /// Model used
-    pub model: Option<String>,
+    /// Workspace ID
+    pub workspace_id: Option<ID>,
     /// Number of messages
-    pub message_count: i32,
-    /// Total tokens
-    pub token_count: i32,
-    /// Whether archived
-    pub archived: bool,
-    /// Tags
-    pub tags: Vec<String>,
+    pub message_count: i64,
     /// Creation timestamp
-    pub created_at: DateTime<Utc>,
+    pub created_at: i64,
     /// Last updated timestamp
-    pub updated_at: DateTime<Utc>,
+    pub updated_at: i64,
+}
+
+#[ComplexObject]
+impl Session {
+    /// Workspace the session belongs to
+    async fn workspace(&self, ctx: &Context<'_>) -> Result<Option<Workspace>> {
+        let Some(id) = &self.workspace_id else {
+            return Ok(None);
+        };
+        let state = ctx.data::<web::Data<AppState>>()?;
+        let db = state.db.lock().unwrap();
+        Ok(load_workspace(&db.conn, &scope(ctx), id)?)
+    }
+
+    /// Messages in conversation order
+    async fn messages(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<Page<Message>> {
+        let per_page = page_size(first, pagination::DEFAULT_PER_PAGE);
+        // Messages keep their order, so the cursor is the position of the next one
+        let start = pagination::parse_cursor::<usize>(after.as_deref())?.unwrap_or(0);
+
+        let messages = load_messages(ctx, &self.id)?;
+        let total = messages.len();
+        let end = (start + per_page).min(total);
+        let page = messages.into_iter().skip(start).take(per_page).collect();
+        let next = (end < total).then(|| pagination::encode_cursor(&end));
+        Ok(Page::new(page, total as i64, next))
+    }
+
+    /// Tool invocations across all messages, in order
+    async fn tool_invocations(
+        &self,
+        ctx: &Context<'_>,
+        tool_name: Option<String>,
+    ) -> Result<Vec<ToolInvocation>> {
+        Ok(load_messages(ctx, &self.id)?
+            .into_iter()
+            .flat_map(|m| m.tool_invocations)
+            .filter(|t| tool_name.as_ref().is_none_or(|name| &t.tool_name == name))
+            .collect())
+    }
 }
 
-/// Message type for GraphQL
+/// Message in a session
 #[derive(SimpleObject, Clone)]
 pub struct Message {
-    /// Unique identifier
-    pub id: ID,
-    /// Session ID
-    pub session_id: ID,
-    /// Message role (user, assistant, system)
+    /// Position in the session
+    pub index: i64,
+    /// Message role (user, assistant)
     pub role: String,
-    /// Message content
+    /// Message content as markdown
     pub content: String,
     /// Model used
-    pub model: Option<String>,
-    /// Token count
-    pub token_count: i32,
+    pub model_id: Option<String>,
     /// Creation timestamp
-    pub created_at: DateTime<Utc>,
-}
-
-/// Provider type for GraphQL
-#[derive(SimpleObject, Clone)]
-pub struct Provider {
-    /// Unique identifier
-    pub id: ID,
-    /// Provider name
-    pub name: String,
-    /// Provider type
-    pub provider_type: String,
-    /// Whether enabled
-    pub enabled: bool,
-    /// Base URL
-    pub base_url: Option<String>,
+    pub created_at: Option<i64>,
+    /// Whether the response was canceled
+    pub is_canceled: bool,
+    /// Tools the assistant invoked in this message
+    pub tool_invocations: Vec<ToolInvocation>,
 }
 
-/// Agent type for GraphQL
+/// Tool invoked by an assistant message
 #[derive(SimpleObject, Clone)]
-pub struct Agent {
-    /// Unique identifier
-    pub id: ID,
-    /// Agent name
-    pub name: String,
-    /// Description
-    pub description: Option<String>,
-    /// System prompt
-    pub system_prompt: Option<String>,
-    /// Provider ID
-    pub provider_id: Option<ID>,
-    /// Model
-    pub model: String,
-    /// Temperature
-    pub temperature: f64,
-    /// Creation timestamp
-    pub created_at: DateTime<Utc>,
+pub struct ToolInvocation {
+    /// Position of the message that invoked the tool
+    pub message_index: i64,
+    /// Tool identifier
+    pub tool_name: String,
+    /// Provider's ID for the call
+    pub tool_call_id: Option<String>,
+    /// Whether the call finished
+    pub is_complete: bool,
+    /// Whether the user confirmed the call, if it needed confirmation
+    pub is_confirmed: Option<bool>,
+    /// Message shown while the tool ran
+    pub invocation_message: Option<String>,
+    /// Tool-specific input and output
+    pub data: Option<Json<serde_json::Value>>,
+    /// Files the tool changed
+    pub file_changes: Json<serde_json::Value>,
 }
 
-/// Statistics overview
+/// Aggregate statistics for dashboards
 #[derive(SimpleObject, Clone)]
-pub struct StatsOverview {
+pub struct Analytics {
     /// Total sessions
-    pub total_sessions: i32,
+    pub total_sessions: i64,
     /// Total messages
-    pub total_messages: i32,
-    /// Total tokens
-    pub total_tokens: i64,
-    /// Active providers
-    pub active_providers: i32,
+    pub total_messages: i64,
     /// Total workspaces
-    pub workspaces: i32,
-    /// Sessions today
-    pub sessions_today: i32,
-    /// Messages today
-    pub messages_today: i32,
+    pub total_workspaces: i64,
+    /// Total tool invocations recorded by harvest
+    pub total_tool_invocations: i64,
+    /// Sessions per provider, largest first
+    pub sessions_by_provider: Vec<ProviderCount>,
+    /// Most used tools, largest first
+    pub tool_usage: Vec<ToolCount>,
 }
 
-/// Search result
+/// Number of sessions for a provider
 #[derive(SimpleObject, Clone)]
-pub struct SearchResult {
-    /// Sessions matching query
-    pub sessions: Vec<Session>,
-    /// Messages matching query
-    pub messages: Vec<Message>,
-    /// Total count
-    pub total: i32,
-}
-
-// ============================================================================
-// Input Types
-// ============================================================================
-
-/// Input for creating a session
-#[derive(InputObject)]
-pub struct CreateSessionInput {
-    pub title: String,
-    pub workspace_id: Option<ID>,
+pub struct ProviderCount {
     pub provider: String,
+    pub count: i64,
 }
 
-/// Input for updating a session
-#[derive(InputObject)]
-pub struct UpdateSessionInput {
-    pub title: Option<String>,
-    pub tags: Option<Vec<String>>,
-    pub archived: Option<bool>,
+/// Number of invocations of a tool
+#[derive(SimpleObject, Clone)]
+pub struct ToolCount {
+    pub tool_name: String,
+    pub count: i64,
 }
 
-/// Input for creating an agent
-#[derive(InputObject)]
-pub struct CreateAgentInput {
-    pub name: String,
-    pub description: Option<String>,
-    pub system_prompt: Option<String>,
-    pub provider_id: Option<ID>,
-    pub model: String,
-    pub temperature: Option<f64>,
-}
+// ============================================================================
+// Input Types
+// ============================================================================
 
 /// Filter for sessions
-#[derive(InputObject, Default)]
+#[derive(async_graphql::InputObject, Default)]
 pub struct SessionFilter {
+    /// Only sessions in this workspace
     pub workspace_id: Option<ID>,
+    /// Only sessions from this provider
     pub provider: Option<String>,
-    pub archived: Option<bool>,
+    /// Only sessions whose title or messages contain this text
     pub search: Option<String>,
 }
 
-/// Pagination input
-#[derive(InputObject, Default)]
-pub struct Pagination {
-    pub limit: Option<i32>,
-    pub offset: Option<i32>,
-}
-
 // ============================================================================
 // Query Root
 // ============================================================================
@@ -207,283 +260,396 @@ pub struct QueryRoot;
 
 #[Object]
 impl QueryRoot {
-    /// Get all workspaces
+    /// Workspaces, most recently active first
     async fn workspaces(
         &self,
         ctx: &Context<'_>,
-        pagination: Option<Pagination>,
-    ) -> FieldResult<Vec<Workspace>> {
-        let _state = ctx.data::<Arc<AppState>>()?;
-        let pagination = pagination.unwrap_or_default();
-        let _limit = pagination.limit.unwrap_or(20);
-        let _offset = pagination.offset.unwrap_or(0);
-
-        // TODO: Implement actual database query
-        Ok(vec![])
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<Page<Workspace>> {
+        let per_page = page_size(first, pagination::DEFAULT_PER_PAGE);
+        let cursor = pagination::parse_cursor::<Cursor>(after.as_deref())?;
+        let state = ctx.data::<web::Data<AppState>>()?;
+        let db = state.db.lock().unwrap();
+
+        let workspaces_sql = workspaces_sql(&db.conn, &scope(ctx))?;
+        let total: i64 = db.conn.query_row(
+            &format!("SELECT COUNT(*) FROM ({})", workspaces_sql),
+            [],
+            |row| row.get(0),
+        )?;
+        let mut stmt = db.conn.prepare(&format!(
+            "SELECT * FROM ({})
+             WHERE ?1 IS NULL OR updated_at < ?1 OR (updated_at = ?1 AND id < ?2)
+             ORDER BY updated_at DESC, id DESC
+             LIMIT ?3",
+            workspaces_sql
+        ))?;
+        let mut rows = stmt
+            .query_map(
+                params![
+                    cursor.as_ref().map(|c| c.updated_at),
+                    cursor.as_ref().map(|c| c.id.as_str()),
+                    (per_page + 1) as i64,
+                ],
+                row_to_workspace,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let next = pagination::finish_page(&mut rows, per_page, |w| {
+            pagination::encode_cursor(&Cursor {
+                updated_at: w.updated_at,
+                id: w.id.to_string(),
+            })
+        });
+        Ok(Page::new(rows, total, next))
     }
 
     /// Get a workspace by ID
-    async fn workspace(&self, ctx: &Context<'_>, id: ID) -> FieldResult<Option<Workspace>> {
-        let _state = ctx.data::<Arc<AppState>>()?;
-        let _id = id.to_string();
-
-        // TODO: Implement actual database query
-        Ok(None)
+    async fn workspace(&self, ctx: &Context<'_>, id: ID) -> Result<Option<Workspace>> {
+        let state = ctx.data::<web::Data<AppState>>()?;
+        let db = state.db.lock().unwrap();
+        Ok(load_workspace(&db.conn, &scope(ctx), &id)?)
     }
 
-    /// Get all sessions with optional filtering
+    /// Sessions, most recently updated first
     async fn sessions(
         &self,
         ctx: &Context<'_>,
         filter: Option<SessionFilter>,
-        pagination: Option<Pagination>,
-    ) -> FieldResult<Vec<Session>> {
-        let _state = ctx.data::<Arc<AppState>>()?;
-        let _filter = filter.unwrap_or_default();
-        let pagination = pagination.unwrap_or_default();
-        let _limit = pagination.limit.unwrap_or(20);
-        let _offset = pagination.offset.unwrap_or(0);
-
-        // TODO: Implement actual database query
-        Ok(vec![])
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<Page<Session>> {
+        let state = ctx.data::<web::Data<AppState>>()?;
+        let db = state.db.lock().unwrap();
+        query_sessions(
+            &db.conn,
+            &scope(ctx),
+            &filter.unwrap_or_default(),
+            first,
+            after.as_deref(),
+        )
     }
 
     /// Get a session by ID
-    async fn session(&self, ctx: &Context<'_>, id: ID) -> FieldResult<Option<Session>> {
-        let _state = ctx.data::<Arc<AppState>>()?;
-        let _id = id.to_string();
-
-        // TODO: Implement actual database query
-        Ok(None)
+    async fn session(&self, ctx: &Context<'_>, id: ID) -> Result<Option<Session>> {
+        let state = ctx.data::<web::Data<AppState>>()?;
+        let db = state.db.lock().unwrap();
+        Ok(db
+            .conn
+            .query_row(
+                &format!(
+                    "{} WHERE id = ?1 AND {}",
+                    SESSION_COLUMNS,
+                    scope(ctx).readable("sessions")
+                ),
+                [id.as_str()],
+                row_to_session,
+            )
+            .optional()?)
     }
 
-    /// Get messages for a session
-    async fn messages(
+    /// Aggregate statistics
+    async fn analytics(
         &self,
         ctx: &Context<'_>,
-        session_id: ID,
-        pagination: Option<Pagination>,
-    ) -> FieldResult<Vec<Message>> {
-        let _state = ctx.data::<Arc<AppState>>()?;
-        let _session_id = session_id.to_string();
-        let pagination = pagination.unwrap_or_default();
-        let _limit = pagination.limit.unwrap_or(100);
-        let _offset = pagination.offset.unwrap_or(0);
-
-        // TODO: Implement actual database query
-        Ok(vec![])
-    }
-
-    /// Get all providers
-    async fn providers(&self, ctx: &Context<'_>) -> FieldResult<Vec<Provider>> {
-        let _state = ctx.data::<Arc<AppState>>()?;
-
-        // Return common providers
-        Ok(vec![
-            Provider {
-                id: "copilot".into(),
-                name: "GitHub Copilot".to_string(),
-                provider_type: "copilot".to_string(),
-                enabled: true,
-                base_url: None,
+        #[graphql(default = 10)] top_tools: i32,
+    ) -> Result<Analytics> {
+        let state = ctx.data::<web::Data<AppState>>()?;
+        let db = state.db.lock().unwrap();
+        let conn = &db.conn;
+        let owned = scope(ctx).condition("user_id");
+        let owned_ids = format!("session_id IN (SELECT id FROM sessions WHERE {})", owned);
+
+        let count = |sql: String| conn.query_row(&sql, [], |row| row.get::<_, i64>(0));
+        let has_tools = table_exists(conn, "tool_invocations");
+        let total_messages = if table_exists(conn, "messages_v2") {
+            count(format!(
+                "SELECT COUNT(*) FROM messages_v2 WHERE {}",
+                owned_ids
+            ))?
+        } else {
+            count(format!(
+                "SELECT COALESCE(SUM(message_count), 0) FROM sessions WHERE {}",
+                owned
+            ))?
+        };
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT provider, COUNT(*) FROM sessions WHERE {}
+             GROUP BY provider ORDER BY COUNT(*) DESC",
+            owned
+        ))?;
+        let sessions_by_provider = stmt
+            .query_map([], |row| {
+                Ok(ProviderCount {
+                    provider: row.get(0)?,
+                    count: row.get(1)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+
+        let tool_usage = if has_tools {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT tool_name, COUNT(*) FROM tool_invocations WHERE {}
+                 GROUP BY tool_name ORDER BY COUNT(*) DESC, tool_name LIMIT ?1",
+                owned_ids
+            ))?;
+            let tools = stmt
+                .query_map([top_tools.max(0)], |row| {
+                    Ok(ToolCount {
+                        tool_name: row.get(0)?,
+                        count: row.get(1)?,
+                    })
+                })?
+                .collect::<Result<_, _>>()?;
+            tools
+        } else {
+            Vec::new()
+        };
+
+        Ok(Analytics {
+            total_sessions: count(format!("SELECT COUNT(*) FROM sessions WHERE {}", owned))?,
+            total_messages,
+            total_workspaces: count(format!(
+                "SELECT COUNT(DISTINCT workspace_id) FROM sessions WHERE {}",
+                owned
+            ))?,
+            total_tool_invocations: if has_tools {
+                count(format!(
+                    "SELECT COUNT(*) FROM tool_invocations WHERE {}",
+                    owned_ids
+                ))?
+            } else {
+                0
             },
-            Provider {
-                id: "cursor".into(),
-                name: "Cursor".to_string(),
-                provider_type: "cursor".to_string(),
-                enabled: true,
-                base_url: None,
-            },
-            Provider {
-                id: "chatgpt".into(),
-                name: "ChatGPT".to_string(),
-                provider_type: "chatgpt".to_string(),
-                enabled: true,
-                base_url: Some("https://chatgpt.com".to_string()),
-            },
-        ])
-    }
-
-    /// Get all agents
-    async fn agents(&self, ctx: &Context<'_>) -> FieldResult<Vec<Agent>> {
-        let _state = ctx.data::<Arc<AppState>>()?;
-
-        // TODO: Implement actual database query
-        Ok(vec![])
-    }
-
-    /// Get an agent by ID
-    async fn agent(&self, ctx: &Context<'_>, id: ID) -> FieldResult<Option<Agent>> {
-        let _state = ctx.data::<Arc<AppState>>()?;
-        let _id = id.to_string();
-
-        // TODO: Implement actual database query
-        Ok(None)
-    }
-
-    /// Get statistics overview
-    async fn stats(&self, ctx: &Context<'_>) -> FieldResult<StatsOverview> {
-        let _state = ctx.data::<Arc<AppState>>()?;
-
-        // TODO: Implement actual database query
-        Ok(StatsOverview {
-            total_sessions: 0,
-            total_messages: 0,
-            total_tokens: 0,
-            active_providers: 3,
-            workspaces: 0,
-            sessions_today: 0,
-            messages_today: 0,
-        })
-    }
-
-    /// Search across sessions and messages
-    async fn search(
-        &self,
-        ctx: &Context<'_>,
-        query: String,
-        limit: Option<i32>,
-    ) -> FieldResult<SearchResult> {
-        let _state = ctx.data::<Arc<AppState>>()?;
-        let _limit = limit.unwrap_or(20);
-        let _query = query;
-
-        // TODO: Implement actual search
-        Ok(SearchResult {
-            sessions: vec![],
-            messages: vec![],
-            total: 0,
+            sessions_by_provider,
+            tool_usage,
         })
     }
 }
 
 // ============================================================================
-// Mutation Root
+// Queries
 // ============================================================================
 
-pub struct MutationRoot;
-
-#[Object]
-impl MutationRoot {
-    /// Create a new session
-    async fn create_session(
-        &self,
-        ctx: &Context<'_>,
-        input: CreateSessionInput,
-    ) -> FieldResult<Session> {
-        let _state = ctx.data::<Arc<AppState>>()?;
-
-        // TODO: Implement actual creation
-        Ok(Session {
-            id: uuid::Uuid::new_v4().to_string().into(),
-            title: input.title,
-            workspace_id: input.workspace_id,
-            provider: input.provider,
-            model: None,
-            message_count: 0,
-            token_count: 0,
-            archived: false,
-            tags: vec![],
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        })
-    }
+const SESSION_COLUMNS: &str = "SELECT id, title, provider, workspace_id, message_count,
+                                      created_at, updated_at
+                               FROM sessions";
 
-    /// Update a session
-    async fn update_session(
-        &self,
-        ctx: &Context<'_>,
-        id: ID,
-        input: UpdateSessionInput,
-    ) -> FieldResult<Session> {
-        let _state = ctx.data::<Arc<AppState>>()?;
-        let _id = id.to_string();
-
-        // TODO: Implement actual update
-        Ok(Session {
-            id,
-            title: input.title.unwrap_or_default(),
-            workspace_id: None,
-            provider: "unknown".to_string(),
-            model: None,
-            message_count: 0,
-            token_count: 0,
-            archived: input.archived.unwrap_or(false),
-            tags: input.tags.unwrap_or_default(),
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        })
-    }
+/// Page size for a `first` argument
+fn page_size(first: Option<i32>, default: usize) -> usize {
+    pagination::per_page(first.map(|n| n.max(0) as usize), None, default)
+}
 
-    /// Delete a session
-    async fn delete_session(&self, ctx: &Context<'_>, id: ID) -> FieldResult<bool> {
-        let _state = ctx.data::<Arc<AppState>>()?;
-        let _id = id.to_string();
+/// Whose sessions the request can see, set by the HTTP handlers
+fn scope(ctx: &Context<'_>) -> Scope {
+    ctx.data_opt::<Scope>()
+        .cloned()
+        .unwrap_or(Scope::Owner(None))
+}
 
-        // TODO: Implement actual deletion
-        Ok(true)
-    }
+fn table_exists(conn: &Connection, name: &str) -> bool {
+    conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type='table' AND name=?1",
+        [name],
+        |row| row.get(0),
+    )
+    .unwrap_or(false)
+}
 
-    /// Archive a session
-    async fn archive_session(&self, ctx: &Context<'_>, id: ID) -> FieldResult<Session> {
-        let _state = ctx.data::<Arc<AppState>>()?;
-
-        // TODO: Implement actual archiving
-        Ok(Session {
-            id,
-            title: "Archived".to_string(),
-            workspace_id: None,
-            provider: "unknown".to_string(),
-            model: None,
-            message_count: 0,
-            token_count: 0,
-            archived: true,
-            tags: vec![],
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        })
-    }
+fn row_to_workspace(row: &rusqlite::Row) -> rusqlite::Result<Workspace> {
+    let id: String = row.get(0)?;
+    let name: Option<String> = row.get(1)?;
+    let path: Option<String> = row.get(2)?;
+    let (name, path) = match name {
+        Some(name) => (name, path.unwrap_or_default()),
+        None => get_workspace_info(&id),
+    };
+    Ok(Workspace {
+        id: id.into(),
+        name,
+        path,
+        provider: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+        session_count: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
 
-    /// Create an agent
-    async fn create_agent(&self, ctx: &Context<'_>, input: CreateAgentInput) -> FieldResult<Agent> {
-        let _state = ctx.data::<Arc<AppState>>()?;
-
-        Ok(Agent {
-            id: uuid::Uuid::new_v4().to_string().into(),
-            name: input.name,
-            description: input.description,
-            system_prompt: input.system_prompt,
-            provider_id: input.provider_id,
-            model: input.model,
-            temperature: input.temperature.unwrap_or(0.7),
-            created_at: Utc::now(),
-        })
-    }
+fn row_to_session(row: &rusqlite::Row) -> rusqlite::Result<Session> {
+    Ok(Session {
+        id: row.get::<_, String>(0)?.into(),
+        title: row.get(1)?,
+        provider: row.get(2)?,
+        workspace_id: row.get::<_, Option<String>>(3)?.map(ID::from),
+        message_count: row.get::<_, Option<i64>>(4)?.unwrap_or(0),
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
 
-    /// Delete an agent
-    async fn delete_agent(&self, ctx: &Context<'_>, id: ID) -> FieldResult<bool> {
-        let _state = ctx.data::<Arc<AppState>>()?;
-        let _id = id.to_string();
+fn load_workspace(
+    conn: &Connection,
+    scope: &Scope,
+    id: &str,
+) -> rusqlite::Result<Option<Workspace>> {
+    conn.query_row(
+        &format!(
+            "SELECT * FROM ({}) WHERE id = ?1",
+            workspaces_sql(conn, scope)?
+        ),
+        [id],
+        row_to_workspace,
+    )
+    .optional()
+}
 
-        // TODO: Implement actual deletion
-        Ok(true)
-    }
+fn query_sessions(
+    conn: &Connection,
+    scope: &Scope,
+    filter: &SessionFilter,
+    first: Option<i32>,
+    after: Option<&str>,
+) -> Result<Page<Session>> {
+    let per_page = page_size(first, pagination::DEFAULT_PER_PAGE);
+    let cursor = pagination::parse_cursor::<Cursor>(after)?;
+    let filters = format!(
+        "(?1 IS NULL OR workspace_id = ?1)
+         AND (?2 IS NULL OR provider = ?2)
+         AND (?3 IS NULL OR title LIKE ?3 OR EXISTS (
+             SELECT 1 FROM messages m WHERE m.session_id = sessions.id
+             AND m.content LIKE ?3))
+         AND {}",
+        scope.readable("sessions")
+    );
+    let workspace_id = filter.workspace_id.as_deref();
+    let provider = filter.provider.as_deref();
+    let search = filter.search.as_ref().map(|q| format!("%{}%", q));
+
+    let total: i64 = conn.query_row(
+        &format!("SELECT COUNT(*) FROM sessions WHERE {}", filters),
+        params![workspace_id, provider, search],
+        |row| row.get(0),
+    )?;
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE {}
+           AND (?4 IS NULL OR updated_at < ?4 OR (updated_at = ?4 AND id < ?5))
+         ORDER BY updated_at DESC, id DESC
+         LIMIT ?6",
+        SESSION_COLUMNS, filters
+    ))?;
+    let mut rows = stmt
+        .query_map(
+            params![
+                workspace_id,
+                provider,
+                search,
+                cursor.as_ref().map(|c| c.updated_at),
+                cursor.as_ref().map(|c| c.id.as_str()),
+                (per_page + 1) as i64,
+            ],
+            row_to_session,
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let next = pagination::finish_page(&mut rows, per_page, |s| {
+        pagination::encode_cursor(&Cursor {
+            updated_at: s.updated_at,
+            id: s.id.to_string(),
+        })
+    });
+    Ok(Page::new(rows, total, next))
+}
 
-    /// Trigger a harvest
-    async fn harvest(&self, ctx: &Context<'_>, providers: Option<Vec<String>>) -> FieldResult<i32> {
-        let _state = ctx.data::<Arc<AppState>>()?;
-        let _providers = providers;
+/// A session's messages, parsed the same way as the REST endpoints
+fn load_messages(ctx: &Context<'_>, session_id: &str) -> Result<Vec<Message>> {
+    let state = ctx.data::<web::Data<AppState>>()?;
+    let db = state.db.lock().unwrap();
+    let session_json: Option<String> = db
+        .conn
+        .query_row(
+            &format!(
+                "SELECT session_json FROM sessions WHERE id = ?1 AND {}",
+                scope(ctx).readable("sessions")
+            ),
+            [session_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    let parsed = session_json
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or(serde_json::Value::Null);
+    Ok(extract_messages_from_session(&parsed)
+        .iter()
+        .map(message_from_json)
+        .collect())
+}
 
-        // TODO: Implement actual harvest
-        Ok(0)
+fn message_from_json(message: &serde_json::Value) -> Message {
+    let str_field = |key: &str| message.get(key).and_then(|v| v.as_str()).map(String::from);
+    let index = message.get("index").and_then(|v| v.as_i64()).unwrap_or(0);
+    let tool_invocations = message
+        .get("tool_invocations")
+        .and_then(|v| v.as_array())
+        .map(|tools| {
+            tools
+                .iter()
+                .map(|tool| tool_from_json(index, tool))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Message {
+        index,
+        role: str_field("role").unwrap_or_default(),
+        content: str_field("content").unwrap_or_default(),
+        model_id: str_field("model_id"),
+        created_at: message.get("created_at").and_then(|v| v.as_i64()),
+        is_canceled: message
+            .get("is_canceled")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        tool_invocations,
     }
+}
 
-    /// Trigger sync
-    async fn sync(&self, ctx: &Context<'_>) -> FieldResult<bool> {
-        let _state = ctx.data::<Arc<AppState>>()?;
-
-        // TODO: Implement actual sync
-        Ok(true)
+fn tool_from_json(message_index: i64, tool: &serde_json::Value) -> ToolInvocation {
+    // Invocation messages are either plain strings or markdown objects
+    let invocation_message = tool.get("invocation_message").and_then(|m| {
+        m.as_str()
+            .or_else(|| m.get("value").and_then(|v| v.as_str()))
+            .map(String::from)
+    });
+
+    ToolInvocation {
+        message_index,
+        tool_name: tool
+            .get("tool_name")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        tool_call_id: tool
+            .get("tool_call_id")
+            .and_then(|v| v.as_str())
+            .map(String::from),
+        is_complete: tool
+            .get("is_complete")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        is_confirmed: tool.get("is_confirmed").and_then(|v| v.as_bool()),
+        invocation_message,
+        data: tool
+            .get("tool_specific_data")
+            .filter(|v| !v.is_null())
+            .cloned()
+            .map(Json),
+        file_changes: Json(
+            tool.get("file_changes")
+                .cloned()
+                .unwrap_or(serde_json::json!([])),
+        ),
     }
 }
 
@@ -491,22 +657,39 @@ impl MutationRoot {
 // HTTP Handlers
 // ============================================================================
 
-/// GraphQL endpoint handler
-pub async fn graphql_handler(
+/// Execute a GraphQL query or batch of queries sent as JSON
+pub async fn graphql_post(
     schema: web::Data<ChasmSchema>,
-    req: GraphQLRequest,
-) -> GraphQLResponse {
-    schema.execute(req.into_inner()).await.into()
+    request: web::Json<BatchRequest>,
+    scope: Scope,
+) -> impl Responder {
+    let request = request.into_inner().data(scope);
+    HttpResponse::Ok().json(schema.execute_batch(request).await)
+}
+
+/// Execute a GraphQL query sent in the query string
+pub async fn graphql_get(
+    schema: web::Data<ChasmSchema>,
+    req: HttpRequest,
+    scope: Scope,
+) -> impl Responder {
+    match async_graphql::http::parse_query_string(req.query_string()) {
+        Ok(request) => HttpResponse::Ok().json(schema.execute(request.data(scope)).await),
+        Err(e) => HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": e.to_string(),
+        })),
+    }
 }
 
 /// GraphQL Playground UI
 pub async fn graphql_playground() -> impl Responder {
     HttpResponse::Ok()
         .content_type("text/html")
-        .body(GRAPHQL_PLAYGROUND_HTML)
+        .body(GRAPHQL_PLAYGROUND_HTML.replace("{version}", env!("CARGO_PKG_VERSION")))
 }
 
-/// GraphQL introspection endpoint
+/// GraphQL schema in SDL form
 pub async fn graphql_sdl(schema: web::Data<ChasmSchema>) -> impl Responder {
     HttpResponse::Ok()
         .content_type("text/plain")
@@ -514,24 +697,14 @@ pub async fn graphql_sdl(schema: web::Data<ChasmSchema>) -> impl Responder {
 }
 
 /// Create the GraphQL schema
-pub fn create_schema(state: Arc<AppState>) -> ChasmSchema {
-    Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+pub fn create_schema(state: web::Data<AppState>) -> ChasmSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
         .data(state)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
         .finish()
 }
 
-/// Configure GraphQL routes
-pub fn configure_graphql_routes(cfg: &mut web::ServiceConfig, schema: ChasmSchema) {
-    cfg.app_data(web::Data::new(schema))
-        .service(
-            web::resource("/graphql")
-                .route(web::get().to(graphql_handler))
-                .route(web::post().to(graphql_handler)),
-        )
-        .route("/graphql/playground", web::get().to(graphql_playground))
-        .route("/graphql/sdl", web::get().to(graphql_sdl));
-}
-
 /// Embedded GraphQL Playground HTML
 const GRAPHQL_PLAYGROUND_HTML: &str = r#"<!DOCTYPE html>
 <html lang="en">
@@ -585,8 +758,8 @@ const GRAPHQL_PLAYGROUND_HTML: &str = r#"<!DOCTYPE html>
 <body>
     <div class="custom-header">
         <h1>◈ Chasm GraphQL</h1>
-        <span class="badge">v1.3.0</span>
-        <a href="/docs">REST API →</a>
+        <span class="badge">v{version}</span>
+        <a href="/api/docs">REST API →</a>
     </div>
     <div id="graphiql"></div>
     <script crossorigin src="https://cdn.jsdelivr.net/npm/react@18/umd/react.production.min.js"></script>
@@ -594,7 +767,7 @@ const GRAPHQL_PLAYGROUND_HTML: &str = r#"<!DOCTYPE html>
     <script crossorigin src="https://cdn.jsdelivr.net/npm/graphiql@3/graphiql.min.js"></script>
     <script>
         const fetcher = GraphiQL.createFetcher({
-            url: '/graphql',
+            url: '/api/graphql',
         });
 
         const root = ReactDOM.createRoot(document.getElementById('graphiql'));
@@ -602,9 +775,98 @@ const GRAPHQL_PLAYGROUND_HTML: &str = r#"<!DOCTYPE html>
             React.createElement(GraphiQL, {
                 fetcher,
                 defaultEditorToolsVisibility: true,
+                defaultHeaders: '{"X-API-Key": ""}',
             })
         );
     </script>
 </body>
 </html>
 "#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::ChatDatabase;
+
+    #[tokio::test]
+    async fn test_nested_query() {
+        let db = ChatDatabase::open_in_memory().unwrap();
+        db.conn
+            .execute_batch(
+                "DROP TABLE IF EXISTS sessions;
+                 CREATE TABLE sessions (
+                     id TEXT PRIMARY KEY, provider TEXT NOT NULL, workspace_id TEXT,
+                     title TEXT NOT NULL, message_count INTEGER DEFAULT 0,
+                     created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL,
+                     session_json TEXT NOT NULL, user_id TEXT, team_id TEXT);",
+            )
+            .unwrap();
+        let session_json = serde_json::json!({
+            "requests": [{
+                "message": {"text": "fix the build"},
+                "response": [
+                    {"value": "Done."},
+                    {"kind": "toolInvocationSerialized", "toolId": "run_in_terminal",
+                     "isComplete": true}
+                ]
+            }]
+        });
+        for (id, updated_at) in [("a", 1), ("b", 2)] {
+            db.conn
+                .execute(
+                    "INSERT INTO sessions VALUES (?1, 'copilot', 'ws1', ?1, 2, 0, ?2, ?3, NULL, NULL)",
+                    params![id, updated_at, session_json.to_string()],
+                )
+                .unwrap();
+        }
+
+        super::super::teams::init_team_tables(&db.conn).unwrap();
+
+        let state = web::Data::new(AppState::new(db, "test.db".into()));
+        let schema = create_schema(state.clone());
+        let response = schema
+            .execute(
+                "{ workspaces { total items { id sessions(first: 1) {
+                     hasMore items { id messages { items { role toolInvocations { toolName } } } }
+                 } } } }",
+            )
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+        let data = response.data.into_json().unwrap();
+        let workspace = &data["workspaces"]["items"][0];
+        assert_eq!(data["workspaces"]["total"], 1);
+        assert_eq!(workspace["id"], "ws1");
+        assert_eq!(workspace["sessions"]["hasMore"], true);
+        let session = &workspace["sessions"]["items"][0];
+        assert_eq!(session["id"], "b");
+        let messages = &session["messages"]["items"];
+        assert_eq!(messages[0]["role"], "user");
+        assert_eq!(
+            messages[1]["toolInvocations"][0]["toolName"],
+            "run_in_terminal"
+        );
+
+        let user = Scope::Owner(Some("u1".to_string()));
+        let request = async_graphql::Request::new("{ sessions { total } }").data(user);
+        let data = schema.execute(request).await.data.into_json().unwrap();
+        assert_eq!(data["sessions"]["total"], 0);
+
+        // Sessions shared with a team are visible to its members
+        {
+            let db = state.db.lock().unwrap();
+            db.conn
+                .execute_batch(
+                    "INSERT INTO teams (id, name, owner_id, created_at, updated_at)
+                         VALUES ('t1', 'Platform', 'u2', 0, 0);
+                     INSERT INTO team_members VALUES ('t1', 'u1', 'viewer', 0);
+                     UPDATE sessions SET team_id = 't1' WHERE id = 'a';",
+                )
+                .unwrap();
+        }
+        let user = Scope::Owner(Some("u1".to_string()));
+        let request = async_graphql::Request::new("{ sessions { total } }").data(user);
+        let data = schema.execute(request).await.data.into_json().unwrap();
+        assert_eq!(data["sessions"]["total"], 1);
+    }
+}
@@ -0,0 +1,442 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! gRPC interface
+//!
+//! Serves the `chasm.v1.Sessions` service from `proto/chasm.proto` on its
+//! own port (`csm api serve --grpc-port`), for integrations such as data
+//! pipelines that ingest whole chat corpora and would rather stream than
+//! page through JSON. Listing and search go through the same search engine
+//! as `/api/sessions/search`; `ExportSessions` streams each session with its
+//! messages. Credentials and scoping are the same as for the REST API.
+
+#![allow(
+    clippy::result_large_err,
+    reason = "tonic service methods must return `Status`, and the helpers share their error type"
+)]
+
+use actix_web::web;
+use anyhow::Result;
+use rusqlite::{Connection, OptionalExtension};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use tokio::sync::mpsc;
+use tonic::transport::server::TcpIncoming;
+use tonic::{Request, Response, Status};
+
+use super::access::{credential_scope, AnonymousAccess, Scope};
+use super::pagination::{decode_cursor, encode_cursor};
+use super::state::AppState;
+use crate::commands::message_content;
+use crate::reviews;
+use crate::search::{self, SearchFilters, SearchHit, SearchMode, SearchOptions};
+
+mod proto {
+    tonic::include_proto!("chasm.v1");
+}
+
+use proto::sessions_server::{Sessions, SessionsServer};
+
+/// Page size when a request does not give one
+const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Largest page a request can ask for
+const MAX_PAGE_SIZE: usize = 1000;
+
+/// Sessions looked up at a time while exporting by filter
+const EXPORT_BATCH: usize = 500;
+
+/// Exported sessions buffered ahead of a slow client
+const EXPORT_BUFFER: usize = 16;
+
+type ExportStream =
+    Pin<Box<dyn futures_util::Stream<Item = Result<proto::SessionExport, Status>> + Send>>;
+
+struct SessionService {
+    state: web::Data<AppState>,
+    anonymous: AnonymousAccess,
+}
+
+fn internal(e: impl std::fmt::Display) -> Status {
+    Status::internal(e.to_string())
+}
+
+impl SessionService {
+    /// Scope of a call from its `x-api-key` or `authorization` metadata
+    fn scope<T>(&self, request: &Request<T>) -> Result<Scope, Status> {
+        let metadata = request.metadata();
+        let credential = metadata
+            .get("x-api-key")
+            .and_then(|v| v.to_str().ok())
+            .or_else(|| {
+                metadata
+                    .get("authorization")
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.strip_prefix("Bearer "))
+            })
+            .map(str::trim);
+        let db = self.state.db.lock().unwrap();
+        credential_scope(&db.conn, credential, self.anonymous).map_err(Status::unauthenticated)
+    }
+
+    /// Run a search on a blocking thread with a connection of its own
+    async fn search(&self, options: SearchOptions) -> Result<(Vec<proto::SearchHit>, i64), Status> {
+        let db_path = self.state.db_path.clone();
+        tokio::task::spawn_blocking(move || {
+            let embedder = match options.mode {
+                SearchMode::Semantic => Some(search::HttpEmbedder::from_env().ok_or_else(|| {
+                    Status::failed_precondition(
+                        "Semantic search needs an embeddings endpoint: set CSM_EMBEDDING_URL, OPENAI_API_KEY, or OLLAMA_HOST",
+                    )
+                })?),
+                SearchMode::Text => None,
+            };
+            let conn = Connection::open(&db_path).map_err(internal)?;
+            let embedder = embedder.as_ref().map(|e| e as &dyn search::Embedder);
+            let results = search::search(&conn, &options, embedder).map_err(internal)?;
+            let hits = results
+                .hits
+                .into_iter()
+                .map(|hit| proto::SearchHit {
+                    score: hit.score,
+                    snippet: hit.snippet.clone().unwrap_or_default(),
+                    session: Some(hit_session(&conn, hit)),
+                })
+                .collect();
+            Ok((hits, results.total))
+        })
+        .await
+        .map_err(internal)?
+    }
+}
+
+/// Search filters from a request, limited to what `scope` can see
+fn filters(filters: Option<proto::Filters>, scope: &Scope) -> Result<SearchFilters, Status> {
+    let filters = filters.unwrap_or_default();
+    fn non_empty(value: &str) -> Option<&str> {
+        Some(value).filter(|v| !v.is_empty())
+    }
+    SearchFilters {
+        providers: filters.providers,
+        workspace: non_empty(&filters.workspace).map(String::from),
+        tags: filters.tags,
+        condition: Some(scope.readable("s")),
+        ..Default::default()
+    }
+    .with_dates(non_empty(&filters.after), non_empty(&filters.before))
+    .map_err(|e| Status::invalid_argument(e.to_string()))
+}
+
+/// Page size and offset from a request
+fn page(page_size: u32, page_token: &str) -> Result<(usize, usize), Status> {
+    let size = match page_size as usize {
+        0 => DEFAULT_PAGE_SIZE,
+        size => size.min(MAX_PAGE_SIZE),
+    };
+    let offset = if page_token.is_empty() {
+        0
+    } else {
+        decode_cursor::<usize>(page_token)
+            .ok_or_else(|| Status::invalid_argument("Invalid page_token"))?
+    };
+    Ok((size, offset))
+}
+
+/// Drop the extra hit fetched to tell whether there is another page
+fn finish_page<T>(items: &mut Vec<T>, size: usize, offset: usize) -> String {
+    if items.len() <= size {
+        return String::new();
+    }
+    items.truncate(size);
+    encode_cursor(&(offset + size))
+}
+
+fn hit_session(conn: &Connection, hit: SearchHit) -> proto::Session {
+    proto::Session {
+        tags: reviews::session_tags(conn, &hit.id).unwrap_or_default(),
+        id: hit.id,
+        title: hit.title,
+        provider: hit.provider,
+        workspace_id: hit.workspace_id.unwrap_or_default(),
+        workspace_name: hit.workspace_name.unwrap_or_default(),
+        message_count: hit.message_count,
+        updated_at: hit.updated_at,
+    }
+}
+
+/// A session by ID, if `scope` can see it
+fn load_session(conn: &Connection, id: &str, scope: &Scope) -> Result<Option<proto::Session>> {
+    let session = conn
+        .query_row(
+            &format!(
+                "SELECT id, title, provider, workspace_id, workspace_name, message_count, updated_at
+                 FROM sessions WHERE id = ?1 AND {}",
+                scope.readable("sessions")
+            ),
+            [id],
+            |row| {
+                Ok(proto::Session {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    provider: row.get(2)?,
+                    workspace_id: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                    workspace_name: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                    message_count: row.get(5)?,
+                    updated_at: row.get(6)?,
+                    tags: Vec::new(),
+                })
+            },
+        )
+        .optional()?;
+    Ok(match session {
+        Some(mut session) => {
+            session.tags = reviews::session_tags(conn, id)?;
+            Some(session)
+        }
+        None => None,
+    })
+}
+
+fn messages(conn: &Connection, session_id: &str) -> rusqlite::Result<Vec<proto::Message>> {
+    let mut stmt = conn.prepare(
+        "SELECT message_index, role, content_raw, model_id, timestamp, id
+         FROM messages_v2 WHERE session_id = ?1 ORDER BY message_index, id",
+    )?;
+    let messages = stmt
+        .query_map([session_id], |row| {
+            Ok(proto::Message {
+                index: row.get(0)?,
+                role: row.get(1)?,
+                content: message_content(conn, row.get(5)?, row.get(2)?)?,
+                model: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                timestamp: row.get::<_, Option<i64>>(4)?.unwrap_or_default(),
+            })
+        })?
+        .collect();
+    messages
+}
+
+/// Send each session with its messages until the client goes away
+fn export(
+    conn: &Connection,
+    ids: Vec<String>,
+    filters: SearchFilters,
+    scope: &Scope,
+    tx: &mpsc::Sender<Result<proto::SessionExport, Status>>,
+) -> Result<(), Status> {
+    let send = |session: proto::Session| -> Result<bool, Status> {
+        let messages = messages(conn, &session.id).map_err(internal)?;
+        let item = proto::SessionExport {
+            session: Some(session),
+            messages,
+        };
+        Ok(tx.blocking_send(Ok(item)).is_ok())
+    };
+
+    if !ids.is_empty() {
+        for id in ids {
+            let session = load_session(conn, &id, scope)
+                .map_err(internal)?
+                .ok_or_else(|| Status::not_found(format!("Session not found: {}", id)))?;
+            if !send(session)? {
+                break;
+            }
+        }
+        return Ok(());
+    }
+
+    let mut offset = 0;
+    loop {
+        let options = SearchOptions {
+            filters: filters.clone(),
+            offset,
+            limit: EXPORT_BATCH,
+            ..Default::default()
+        };
+        let hits = search::search(conn, &options, None).map_err(internal)?.hits;
+        let done = hits.len() < EXPORT_BATCH;
+        for hit in hits {
+            if !send(hit_session(conn, hit))? {
+                return Ok(());
+            }
+        }
+        if done {
+            return Ok(());
+        }
+        offset += EXPORT_BATCH;
+    }
+}
+
+#[tonic::async_trait]
+impl Sessions for SessionService {
+    type ExportSessionsStream = ExportStream;
+
+    async fn list_sessions(
+        &self,
+        request: Request<proto::ListSessionsRequest>,
+    ) -> Result<Response<proto::ListSessionsResponse>, Status> {
+        let scope = self.scope(&request)?;
+        let request = request.into_inner();
+        let (size, offset) = page(request.page_size, &request.page_token)?;
+        let options = SearchOptions {
+            filters: filters(request.filters, &scope)?,
+            offset,
+            limit: size + 1,
+            ..Default::default()
+        };
+        let (mut hits, total) = self.search(options).await?;
+        let next_page_token = finish_page(&mut hits, size, offset);
+        Ok(Response::new(proto::ListSessionsResponse {
+            sessions: hits.into_iter().filter_map(|hit| hit.session).collect(),
+            next_page_token,
+            total,
+        }))
+    }
+
+    async fn search_sessions(
+        &self,
+        request: Request<proto::SearchSessionsRequest>,
+    ) -> Result<Response<proto::SearchSessionsResponse>, Status> {
+        let scope = self.scope(&request)?;
+        let request = request.into_inner();
+        let (size, offset) = page(request.page_size, &request.page_token)?;
+        let options = SearchOptions {
+            query: request.query,
+            mode: if request.semantic {
+                SearchMode::Semantic
+            } else {
+                SearchMode::Text
+            },
+            filters: filters(request.filters, &scope)?,
+            offset,
+            limit: size + 1,
+            facets: false,
+        };
+        let (mut hits, total) = self.search(options).await?;
+        let next_page_token = finish_page(&mut hits, size, offset);
+        Ok(Response::new(proto::SearchSessionsResponse {
+            hits,
+            next_page_token,
+            total,
+        }))
+    }
+
+    async fn export_sessions(
+        &self,
+        request: Request<proto::ExportSessionsRequest>,
+    ) -> Result<Response<Self::ExportSessionsStream>, Status> {
+        let scope = self.scope(&request)?;
+        let request = request.into_inner();
+        let filters = filters(request.filters, &scope)?;
+        let db_path = self.state.db_path.clone();
+
+        let (tx, mut rx) = mpsc::channel(EXPORT_BUFFER);
+        tokio::task::spawn_blocking(move || {
+            let result = Connection::open(&db_path)
+                .map_err(internal)
+                .and_then(|conn| export(&conn, request.ids, filters, &scope, &tx));
+            if let Err(status) = result {
+                let _ = tx.blocking_send(Err(status));
+            }
+        });
+        let stream = async_stream::stream! {
+            while let Some(item) = rx.recv().await {
+                yield item;
+            }
+        };
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+/// Bind the gRPC port, failing now rather than in the background
+pub fn bind(addr: SocketAddr) -> Result<TcpIncoming> {
+    TcpIncoming::new(addr, true, None)
+        .map_err(|e| anyhow::anyhow!("Failed to bind gRPC port {}: {}", addr, e))
+}
+
+/// Serve the gRPC interface on a bound port
+pub async fn serve(
+    state: web::Data<AppState>,
+    anonymous: AnonymousAccess,
+    incoming: TcpIncoming,
+) -> Result<()> {
+    tonic::transport::Server::builder()
+        .add_service(SessionsServer::new(SessionService { state, anonymous }))
+        .serve_with_incoming(incoming)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::ChatDatabase;
+
+    #[tokio::test]
+    async fn test_list_and_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("csm.db");
+        Connection::open(&db_path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE sessions (
+                     id TEXT PRIMARY KEY, provider TEXT NOT NULL, workspace_id TEXT,
+                     workspace_name TEXT, title TEXT NOT NULL, message_count INTEGER,
+                     updated_at INTEGER NOT NULL, user_id TEXT
+                 );
+                 CREATE TABLE messages_v2 (
+                     id INTEGER PRIMARY KEY, session_id TEXT NOT NULL,
+                     message_index INTEGER NOT NULL, role TEXT NOT NULL,
+                     content_raw TEXT NOT NULL, model_id TEXT, timestamp INTEGER
+                 );
+                 INSERT INTO sessions VALUES
+                     ('a', 'copilot', NULL, NULL, 'First', 2, 10, NULL),
+                     ('b', 'cursor', NULL, NULL, 'Second', 0, 20, NULL),
+                     ('c', 'cursor', NULL, NULL, 'Private', 0, 30, 'u1');
+                 INSERT INTO messages_v2 (session_id, message_index, role, content_raw) VALUES
+                     ('a', 0, 'user', 'hello'), ('a', 0, 'assistant', 'hi');",
+            )
+            .unwrap();
+        let db = ChatDatabase::open(&db_path).unwrap();
+        let service = SessionService {
+            state: web::Data::new(AppState::new(db, db_path)),
+            anonymous: AnonymousAccess::ReadOnly,
+        };
+
+        let page = service
+            .list_sessions(Request::new(proto::ListSessionsRequest {
+                page_size: 1,
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.sessions[0].id, "b");
+        assert!(!page.next_page_token.is_empty());
+
+        let mut stream = service
+            .export_sessions(Request::new(proto::ExportSessionsRequest {
+                ids: vec!["a".to_string()],
+                filters: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        use futures_util::StreamExt;
+        let export = stream.next().await.unwrap().unwrap();
+        assert_eq!(export.session.unwrap().title, "First");
+        let roles: Vec<_> = export.messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["user", "assistant"]);
+        assert!(stream.next().await.is_none());
+
+        let denied = SessionService {
+            anonymous: AnonymousAccess::Deny,
+            ..service
+        };
+        let status = denied
+            .list_sessions(Request::new(Default::default()))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    }
+}
@@ -9,8 +9,23 @@
 use actix_web::{web, HttpResponse, Responder};
 use rusqlite::{params, OptionalExtension};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
+use super::access::Scope;
+use super::docs::ApiEnvelope;
+use super::pagination::{self, Cursor};
 use super::state::AppState;
+use super::sync::{self, SharedSyncState, SyncOperation};
+use super::teams;
+use crate::commands::{
+    delete_harvested_session, ensure_deleted_sessions_table, insert_or_update_session,
+    restore_harvested_session,
+};
+use crate::models::{ChatMessage, ChatRequest, ChatSession};
+use crate::reviews;
+use crate::search;
+use crate::session_diff;
+use chasm::teams::EventType;
 
 /// Check if a string is an empty code block marker (just ``` with no content)
 fn is_empty_code_block(s: &str) -> bool {
@@ -62,23 +77,95 @@ impl<T: Serialize> ApiResponse<T> {
             error: Some(message.to_string()),
         })
     }
+
+    fn created(data: T) -> HttpResponse {
+        HttpResponse::Created().json(Self {
+            success: true,
+            data: Some(data),
+            error: None,
+        })
+    }
+
+    fn not_found(message: &str) -> HttpResponse {
+        HttpResponse::NotFound().json(ApiResponse::<()> {
+            success: false,
+            data: None,
+            error: Some(message.to_string()),
+        })
+    }
+
+    fn bad_request(message: &str) -> HttpResponse {
+        HttpResponse::BadRequest().json(ApiResponse::<()> {
+            success: false,
+            data: None,
+            error: Some(message.to_string()),
+        })
+    }
 }
 
 // =============================================================================
 // Query Parameters
 // =============================================================================
 
-#[derive(Debug, Deserialize)]
+/// Cursor pagination shared by the list endpoints
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageQuery {
+    /// `nextCursor` from the previous page
+    pub cursor: Option<String>,
+    /// Items per page (at most 200)
+    pub per_page: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct SessionQuery {
     pub workspace_id: Option<String>,
     pub provider: Option<String>,
+    /// Alias for `per_page`
     pub limit: Option<usize>,
+    /// `nextCursor` from the previous page
+    pub cursor: Option<String>,
+    /// Items per page (at most 200)
+    pub per_page: Option<usize>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct SearchQuery {
+    /// Words to match; may be empty to list sessions by the filters alone
+    #[serde(default)]
     pub q: String,
+    /// `text` (default) or `semantic`
+    pub mode: Option<String>,
+    /// Comma-separated providers
+    pub provider: Option<String>,
+    /// Workspace ID or name
+    pub workspace: Option<String>,
+    /// Sessions updated on or after this date (YYYY-MM-DD or RFC 3339)
+    pub after: Option<String>,
+    /// Sessions updated on or before this date (YYYY-MM-DD or RFC 3339)
+    pub before: Option<String>,
+    /// Comma-separated tags a session must all have
+    pub tags: Option<String>,
+    /// Language most of a session is written in: ISO 639-3 code or name
+    pub lang: Option<String>,
+    /// Count matches by provider, workspace, tag, language, and month
+    pub facets: Option<bool>,
+    /// Alias for `per_page`
     pub limit: Option<usize>,
+    /// `nextCursor` from the previous page
+    pub cursor: Option<String>,
+    /// Items per page (at most 200)
+    pub per_page: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DiffQuery {
+    /// Checkpoint number, commit of the harvest database, or another session
+    /// ID; prefix with `checkpoint:`, `commit:`, or `session:` when ambiguous
+    pub against: String,
 }
 
 // =============================================================================
@@ -125,7 +212,7 @@ fn lookup_workspace_path(workspace_hash: &str) -> Option<String> {
 }
 
 /// Get workspace info (name and path) from hash
-fn get_workspace_info(workspace_hash: &str) -> (String, String) {
+pub(super) fn get_workspace_info(workspace_hash: &str) -> (String, String) {
     if let Some(path) = lookup_workspace_path(workspace_hash) {
         let name = derive_workspace_name(&path);
         (name, path)
@@ -139,6 +226,14 @@ fn get_workspace_info(workspace_hash: &str) -> (String, String) {
 // Health Check
 // =============================================================================
 
+/// Health check
+#[utoipa::path(
+    get,
+    path = "/api/health",
+    tag = "system",
+    responses((status = 200, description = "Server is running", body = Object)),
+    security(())
+)]
 pub async fn health_check() -> impl Responder {
     HttpResponse::Ok().json(serde_json::json!({
         "status": "ok",
@@ -150,105 +245,166 @@ pub async fn health_check() -> impl Responder {
 // Workspace Handlers (using harvest schema)
 // =============================================================================
 
-pub async fn list_workspaces(state: web::Data<AppState>) -> impl Responder {
-    let db = state.db.lock().unwrap();
+/// Query listing workspaces as `(id, name, path, provider, session_count,
+/// created_at, updated_at)`, for use as a subquery. Without a populated
+/// workspaces table, workspaces are derived from sessions and `name` and
+/// `path` are NULL. Sessions outside `scope` are not counted, and workspaces
+/// without any of the caller's sessions are left out unless they are an admin.
+pub(super) fn workspaces_sql(
+    conn: &rusqlite::Connection,
+    scope: &Scope,
+) -> rusqlite::Result<String> {
+    let has_workspaces: bool =
+        conn.query_row("SELECT COUNT(*) > 0 FROM workspaces", [], |row| row.get(0))?;
 
-    // First try to get workspaces from the workspaces table
-    let result: Result<Vec<serde_json::Value>, _> = (|| {
-        let mut stmt = db.conn.prepare(
+    Ok(if has_workspaces {
+        format!(
             "SELECT w.id, w.name, w.path, w.provider, COUNT(s.id) as session_count,
-                    w.created_at, COALESCE(MAX(s.updated_at), w.updated_at) as updated_at
+                    w.created_at, COALESCE(MAX(s.updated_at), w.updated_at, 0) as updated_at
              FROM workspaces w
-             LEFT JOIN sessions s ON w.id = s.workspace_id
+             LEFT JOIN sessions s ON w.id = s.workspace_id AND {}
              GROUP BY w.id
-             ORDER BY updated_at DESC",
-        )?;
+             {}",
+            scope.readable("s"),
+            if scope.is_admin() {
+                ""
+            } else {
+                "HAVING COUNT(s.id) > 0"
+            }
+        )
+    } else {
+        format!(
+            "SELECT workspace_id as id, NULL as name, NULL as path, provider,
+                    COUNT(*) as session_count, MIN(created_at) as created_at,
+                    MAX(updated_at) as updated_at
+             FROM sessions
+             WHERE workspace_id IS NOT NULL AND workspace_id != '' AND {}
+             GROUP BY workspace_id",
+            scope.readable("sessions")
+        )
+    })
+}
 
-        let workspaces: Vec<serde_json::Value> = stmt
-            .query_map([], |row| {
-                let id: String = row.get(0)?;
-                let name: String = row.get(1)?;
-                let path: Option<String> = row.get(2)?;
-                let provider: String = row.get(3)?;
-                let count: i64 = row.get(4)?;
-                let created_at: Option<i64> = row.get(5).ok();
-                let updated_at: Option<i64> = row.get(6).ok();
-                Ok(serde_json::json!({
-                    "id": id,
-                    "name": name,
-                    "path": path.unwrap_or_default(),
-                    "provider": provider,
-                    "sessionCount": count,
-                    "createdAt": created_at.unwrap_or(0),
-                    "updatedAt": updated_at.unwrap_or(0),
-                }))
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
+/// List workspaces that have harvested sessions
+#[utoipa::path(
+    get,
+    path = "/api/workspaces",
+    tag = "workspaces",
+    params(PageQuery),
+    responses(
+        (status = 200, description = "A page of workspaces, most recently active first", body = ApiEnvelope),
+        (status = 400, description = "Invalid cursor")
+    )
+)]
+pub async fn list_workspaces(
+    state: web::Data<AppState>,
+    query: web::Query<PageQuery>,
+    scope: Scope,
+) -> impl Responder {
+    let db = state.db.lock().unwrap();
+    let per_page = pagination::per_page(query.per_page, None, pagination::DEFAULT_PER_PAGE);
+    let cursor = match pagination::parse_cursor::<Cursor>(query.cursor.as_deref()) {
+        Ok(cursor) => cursor,
+        Err(e) => return ApiResponse::<()>::bad_request(e),
+    };
 
-        // If workspaces table is empty, derive workspaces from sessions
-        if workspaces.is_empty() {
-            let mut stmt = db.conn.prepare(
-                "SELECT workspace_id, provider, COUNT(*) as session_count,
-                        MIN(created_at) as created_at, MAX(updated_at) as updated_at
-                 FROM sessions
-                 WHERE workspace_id IS NOT NULL AND workspace_id != ''
-                 GROUP BY workspace_id
-                 ORDER BY MAX(updated_at) DESC",
-            )?;
+    let result: Result<(Vec<serde_json::Value>, i64, Option<String>), _> = (|| {
+        let workspaces_sql = workspaces_sql(&db.conn, &scope)?;
 
-            let derived: Vec<serde_json::Value> = stmt
-                .query_map([], |row| {
+        let total: i64 = db.conn.query_row(
+            &format!("SELECT COUNT(*) FROM ({})", workspaces_sql),
+            [],
+            |row| row.get(0),
+        )?;
+
+        let mut stmt = db.conn.prepare(&format!(
+            "SELECT * FROM ({})
+             WHERE ?1 IS NULL OR updated_at < ?1 OR (updated_at = ?1 AND id < ?2)
+             ORDER BY updated_at DESC, id DESC
+             LIMIT ?3",
+            workspaces_sql
+        ))?;
+
+        let mut rows: Vec<(Cursor, serde_json::Value)> = stmt
+            .query_map(
+                params![
+                    cursor.as_ref().map(|c| c.updated_at),
+                    cursor.as_ref().map(|c| c.id.as_str()),
+                    (per_page + 1) as i64,
+                ],
+                |row| {
                     let id: String = row.get(0)?;
-                    let provider: String = row.get(1)?;
-                    let count: i64 = row.get(2)?;
-                    let created_at: Option<i64> = row.get(3)?;
-                    let updated_at: Option<i64> = row.get(4)?;
-                    let (name, path) = get_workspace_info(&id);
-                    Ok(serde_json::json!({
+                    let name: Option<String> = row.get(1)?;
+                    let path: Option<String> = row.get(2)?;
+                    let provider: String = row.get(3)?;
+                    let count: i64 = row.get(4)?;
+                    let created_at: Option<i64> = row.get(5)?;
+                    let updated_at: i64 = row.get(6)?;
+                    let (name, path) = match name {
+                        Some(name) => (name, path.unwrap_or_default()),
+                        None => get_workspace_info(&id),
+                    };
+                    let json = serde_json::json!({
                         "id": id,
                         "name": name,
                         "path": path,
                         "provider": provider,
                         "sessionCount": count,
                         "createdAt": created_at.unwrap_or(0),
-                        "updatedAt": updated_at.unwrap_or(0),
-                    }))
-                })?
-                .collect::<Result<Vec<_>, _>>()?;
-
-            return Ok(derived);
-        }
+                        "updatedAt": updated_at,
+                    });
+                    Ok((Cursor { updated_at, id }, json))
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
 
-        Ok::<_, rusqlite::Error>(workspaces)
+        let next =
+            pagination::finish_page(&mut rows, per_page, |(c, _)| pagination::encode_cursor(c));
+        Ok::<_, rusqlite::Error>((rows.into_iter().map(|(_, w)| w).collect(), total, next))
     })();
 
     match result {
-        Ok(workspaces) => {
-            let total = workspaces.len();
-            ApiResponse::success(serde_json::json!({
-                "items": workspaces,
-                "total": total,
-                "limit": total,
-                "offset": 0,
-                "hasMore": false
-            }))
+        Ok((workspaces, total, next)) => {
+            ApiResponse::success(pagination::page_json(workspaces, total, per_page, next))
         }
         Err(e) => ApiResponse::<()>::error(&e.to_string()),
     }
 }
 
-pub async fn get_workspace(state: web::Data<AppState>, path: web::Path<String>) -> impl Responder {
+/// Get a workspace
+#[utoipa::path(
+    get,
+    path = "/api/workspaces/{id}",
+    tag = "workspaces",
+    params(("id" = String, Path, description = "Workspace ID")),
+    responses(
+        (status = 200, description = "Workspace details", body = ApiEnvelope),
+        (status = 404, description = "Workspace not found")
+    )
+)]
+pub async fn get_workspace(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    scope: Scope,
+) -> impl Responder {
     let db = state.db.lock().unwrap();
     let workspace_id = path.into_inner();
 
     let result: Result<Option<serde_json::Value>, _> = (|| {
-        let mut stmt = db.conn.prepare(
+        let mut stmt = db.conn.prepare(&format!(
             "SELECT w.id, w.name, w.path, w.provider, COUNT(s.id) as session_count
              FROM workspaces w
-             LEFT JOIN sessions s ON w.id = s.workspace_id
+             LEFT JOIN sessions s ON w.id = s.workspace_id AND {}
              WHERE w.id = ?1
-             GROUP BY w.id",
-        )?;
+             GROUP BY w.id
+             {}",
+            scope.readable("s"),
+            if scope.is_admin() {
+                ""
+            } else {
+                "HAVING COUNT(s.id) > 0"
+            }
+        ))?;
 
         let workspace = stmt
             .query_row([&workspace_id], |row| {
@@ -284,86 +440,126 @@ pub async fn get_workspace(state: web::Data<AppState>, path: web::Path<String>)
 // Session Handlers (using harvest schema)
 // =============================================================================
 
+/// List sessions
+#[utoipa::path(
+    get,
+    path = "/api/sessions",
+    tag = "sessions",
+    params(SessionQuery),
+    responses(
+        (status = 200, description = "A page of session summaries, most recent first", body = ApiEnvelope),
+        (status = 400, description = "Invalid cursor")
+    )
+)]
 pub async fn list_sessions(
     state: web::Data<AppState>,
     query: web::Query<SessionQuery>,
+    scope: Scope,
 ) -> impl Responder {
     let db = state.db.lock().unwrap();
-    let limit = query.limit.unwrap_or(100) as i64;
-
-    let result: Result<Vec<serde_json::Value>, _> = (|| {
-        let mut sql = String::from(
-            "SELECT id, provider, workspace_id, title, message_count, 
-                    created_at, updated_at
-             FROM sessions WHERE 1=1",
+    let per_page = pagination::per_page(query.per_page, query.limit, pagination::DEFAULT_PER_PAGE);
+    let cursor = match pagination::parse_cursor::<Cursor>(query.cursor.as_deref()) {
+        Ok(cursor) => cursor,
+        Err(e) => return ApiResponse::<()>::bad_request(e),
+    };
+
+    let result: Result<(Vec<serde_json::Value>, i64, Option<String>), _> = (|| {
+        let filters = format!(
+            "(?1 IS NULL OR workspace_id = ?1) AND (?2 IS NULL OR provider = ?2) AND {}",
+            scope.readable("sessions")
         );
+        let workspace_id = query.workspace_id.as_deref();
+        let provider = query.provider.as_deref();
 
-        if query.workspace_id.is_some() {
-            sql.push_str(" AND workspace_id = ?1");
-        }
-        if query.provider.is_some() {
-            sql.push_str(" AND provider = ?2");
-        }
-        sql.push_str(" ORDER BY updated_at DESC LIMIT ?3");
-
-        let mut stmt = db.conn.prepare(&sql)?;
+        let total: i64 = db.conn.query_row(
+            &format!("SELECT COUNT(*) FROM sessions WHERE {}", filters),
+            params![workspace_id, provider],
+            |row| row.get(0),
+        )?;
 
-        let sessions: Vec<serde_json::Value> = stmt
+        let mut stmt = db.conn.prepare(&format!(
+            "SELECT id, provider, workspace_id, title, message_count,
+                    created_at, updated_at
+             FROM sessions
+             WHERE {}
+               AND (?3 IS NULL OR updated_at < ?3 OR (updated_at = ?3 AND id < ?4))
+             ORDER BY updated_at DESC, id DESC
+             LIMIT ?5",
+            filters
+        ))?;
+
+        let mut rows: Vec<(Cursor, serde_json::Value)> = stmt
             .query_map(
                 params![
-                    query.workspace_id.as_deref().unwrap_or(""),
-                    query.provider.as_deref().unwrap_or(""),
-                    limit,
+                    workspace_id,
+                    provider,
+                    cursor.as_ref().map(|c| c.updated_at),
+                    cursor.as_ref().map(|c| c.id.as_str()),
+                    (per_page + 1) as i64,
                 ],
                 |row| {
+                    let id: String = row.get(0)?;
+                    let updated_at: i64 = row.get(6)?;
                     let workspace_id: Option<String> = row.get(2)?;
                     let workspace_name = workspace_id.as_ref().map(|id| {
                         let (name, _path) = get_workspace_info(id);
                         name
                     });
-                    Ok(serde_json::json!({
-                        "id": row.get::<_, String>(0)?,
+                    let json = serde_json::json!({
+                        "id": id,
                         "provider": row.get::<_, String>(1)?,
                         "workspaceId": workspace_id,
                         "workspaceName": workspace_name,
                         "title": row.get::<_, String>(3)?,
                         "messageCount": row.get::<_, i64>(4)?,
                         "createdAt": row.get::<_, i64>(5)?,
-                        "updatedAt": row.get::<_, i64>(6)?,
-                    }))
+                        "updatedAt": updated_at,
+                    });
+                    Ok((Cursor { updated_at, id }, json))
                 },
             )?
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok::<_, rusqlite::Error>(sessions)
+        let next =
+            pagination::finish_page(&mut rows, per_page, |(c, _)| pagination::encode_cursor(c));
+        Ok::<_, rusqlite::Error>((rows.into_iter().map(|(_, s)| s).collect(), total, next))
     })();
 
     match result {
-        Ok(sessions) => {
-            let total = sessions.len();
-            ApiResponse::success(serde_json::json!({
-                "items": sessions,
-                "total": total,
-                "limit": limit,
-                "offset": 0,
-                "hasMore": false
-            }))
+        Ok((sessions, total, next)) => {
+            ApiResponse::success(pagination::page_json(sessions, total, per_page, next))
         }
         Err(e) => ApiResponse::<()>::error(&e.to_string()),
     }
 }
 
-pub async fn get_session(state: web::Data<AppState>, path: web::Path<String>) -> impl Responder {
+/// Get a session with its messages
+#[utoipa::path(
+    get,
+    path = "/api/sessions/{id}",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Session ID")),
+    responses(
+        (status = 200, description = "Session with its messages", body = ApiEnvelope),
+        (status = 404, description = "Session not found")
+    )
+)]
+pub async fn get_session(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    scope: Scope,
+) -> impl Responder {
     let db = state.db.lock().unwrap();
     let session_id = path.into_inner();
 
     let result: Result<Option<serde_json::Value>, _> = (|| {
         // Get session info
-        let mut stmt = db.conn.prepare(
+        let mut stmt = db.conn.prepare(&format!(
             "SELECT id, provider, workspace_id, title, message_count,
                     created_at, updated_at, session_json
-             FROM sessions WHERE id = ?1",
-        )?;
+             FROM sessions WHERE id = ?1 AND {}",
+            scope.readable("sessions")
+        ))?;
 
         let session = stmt
             .query_row([&session_id], |row| {
@@ -423,8 +619,123 @@ pub async fn get_session(state: web::Data<AppState>, path: web::Path<String>) ->
     }
 }
 
+/// List a session's messages a page at a time
+#[utoipa::path(
+    get,
+    path = "/api/sessions/{id}/messages",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Session ID"), PageQuery),
+    responses(
+        (status = 200, description = "A page of messages, oldest first", body = ApiEnvelope),
+        (status = 400, description = "Invalid cursor"),
+        (status = 404, description = "Session not found")
+    )
+)]
+pub async fn list_session_messages(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<PageQuery>,
+    scope: Scope,
+) -> impl Responder {
+    let db = state.db.lock().unwrap();
+    let session_id = path.into_inner();
+    let per_page = pagination::per_page(query.per_page, None, pagination::DEFAULT_PER_PAGE);
+    // Messages keep their order, so the cursor is the position of the next one
+    let start = match pagination::parse_cursor::<usize>(query.cursor.as_deref()) {
+        Ok(start) => start,
+        Err(e) => return ApiResponse::<()>::bad_request(e),
+    };
+    let start = start.unwrap_or(0);
+
+    let session_json: Result<Option<String>, _> = db
+        .conn
+        .query_row(
+            &format!(
+                "SELECT session_json FROM sessions WHERE id = ?1 AND {}",
+                scope.readable("sessions")
+            ),
+            [&session_id],
+            |row| row.get(0),
+        )
+        .optional();
+
+    match session_json {
+        Ok(Some(session_json)) => {
+            let parsed: serde_json::Value =
+                serde_json::from_str(&session_json).unwrap_or(serde_json::json!({}));
+            let messages = extract_messages_from_session(&parsed);
+            let total = messages.len();
+            let end = (start + per_page).min(total);
+            let page: Vec<serde_json::Value> =
+                messages.into_iter().skip(start).take(per_page).collect();
+            let next = (end < total).then(|| pagination::encode_cursor(&end));
+            ApiResponse::success(pagination::page_json(page, total as i64, per_page, next))
+        }
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "Session not found"
+        })),
+        Err(e) => ApiResponse::<()>::error(&e.to_string()),
+    }
+}
+
+/// Turns added, removed, and edited in a session since a checkpoint, a
+/// commit of the harvest database, or relative to another session
+#[utoipa::path(
+    get,
+    path = "/api/sessions/{id}/diff",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Session ID"), DiffQuery),
+    responses(
+        (status = 200, description = "Changed turns with counts", body = ApiEnvelope),
+        (status = 400, description = "Invalid checkpoint number"),
+        (status = 404, description = "Session, checkpoint, or commit not found")
+    )
+)]
+pub async fn diff_session(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<DiffQuery>,
+    scope: Scope,
+) -> impl Responder {
+    let session_id = path.into_inner();
+    let against = {
+        let db = state.db.lock().unwrap();
+        let against = match session_diff::Against::resolve(&db.conn, &session_id, &query.against) {
+            Ok(against) => against,
+            Err(e) => return ApiResponse::<()>::bad_request(&e.to_string()),
+        };
+        let mut visible = owns_session(&db.conn, "sessions", &session_id, &scope);
+        if let (Ok(true), session_diff::Against::Session(other)) = (&visible, &against) {
+            visible = owns_session(&db.conn, "sessions", other, &scope);
+        }
+        match visible {
+            Ok(true) => against,
+            Ok(false) => return ApiResponse::<()>::not_found("Session not found"),
+            Err(e) => return ApiResponse::<()>::error(&e.to_string()),
+        }
+    };
+
+    // Comparing against a commit runs git, so diff on a blocking thread with
+    // a connection of its own
+    let db_path = state.db_path.clone();
+    let result = web::block(move || {
+        let conn = rusqlite::Connection::open(&db_path)?;
+        session_diff::diff_session(&conn, &db_path, &session_id, &against)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(diff)) => ApiResponse::success(diff),
+        Ok(Err(e)) => ApiResponse::<()>::not_found(&format!("{:#}", e)),
+        Err(e) => ApiResponse::<()>::error(&e.to_string()),
+    }
+}
+
 /// Extract messages from session_json.requests array with full markdown and tool invocations
-fn extract_messages_from_session(session_json: &serde_json::Value) -> Vec<serde_json::Value> {
+pub(super) fn extract_messages_from_session(
+    session_json: &serde_json::Value,
+) -> Vec<serde_json::Value> {
     let mut messages = Vec::new();
 
     if let Some(requests) = session_json.get("requests").and_then(|r| r.as_array()) {
@@ -674,49 +985,649 @@ fn extract_file_changes_from_tool(
     file_changes
 }
 
+/// Search sessions by text or meaning, with filters and facet counts
+#[utoipa::path(
+    get,
+    path = "/api/sessions/search",
+    tag = "sessions",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "A page of matching sessions, best first", body = ApiEnvelope),
+        (status = 400, description = "Invalid cursor, date, language, or mode, or semantic search is not configured")
+    )
+)]
 pub async fn search_sessions(
     state: web::Data<AppState>,
     query: web::Query<SearchQuery>,
+    scope: Scope,
+) -> impl Responder {
+    let query = query.into_inner();
+    let per_page = pagination::per_page(query.per_page, query.limit, 20);
+    let offset = match pagination::parse_cursor::<usize>(query.cursor.as_deref()) {
+        Ok(offset) => offset.unwrap_or(0),
+        Err(e) => return ApiResponse::<()>::bad_request(e),
+    };
+    let list = |value: &Option<String>| -> Vec<String> {
+        value
+            .iter()
+            .flat_map(|v| v.split(','))
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect()
+    };
+
+    let filters = search::SearchFilters {
+        providers: list(&query.provider),
+        workspace: query.workspace.clone().filter(|w| !w.is_empty()),
+        tags: list(&query.tags),
+        condition: Some(scope.readable("s")),
+        ..Default::default()
+    }
+    .with_dates(query.after.as_deref(), query.before.as_deref())
+    .and_then(|filters| filters.with_language(query.lang.as_deref()));
+    let mode = query
+        .mode
+        .as_deref()
+        .map(str::parse::<search::SearchMode>)
+        .transpose();
+    let (filters, mode) = match (filters, mode) {
+        (Ok(filters), Ok(mode)) => (filters, mode.unwrap_or_default()),
+        (Err(e), _) | (_, Err(e)) => return ApiResponse::<()>::bad_request(&e.to_string()),
+    };
+    let options = search::SearchOptions {
+        query: query.q.clone(),
+        mode,
+        filters,
+        offset,
+        // One extra to tell whether there is another page
+        limit: per_page + 1,
+        facets: query.facets.unwrap_or(false),
+    };
+
+    // Semantic mode can wait on an embeddings endpoint, so search on a
+    // blocking thread with a connection of its own
+    let db_path = state.db_path.clone();
+    let result = web::block(move || {
+        let embedder = match options.mode {
+            search::SearchMode::Semantic => match search::HttpEmbedder::from_env() {
+                Some(embedder) => Some(embedder),
+                None => return Ok(Err(
+                    "Semantic search needs an embeddings endpoint: set CSM_EMBEDDING_URL, OPENAI_API_KEY, or OLLAMA_HOST",
+                )),
+            },
+            search::SearchMode::Text => None,
+        };
+        let conn = rusqlite::Connection::open(&db_path)?;
+        let embedder = embedder.as_ref().map(|e| e as &dyn search::Embedder);
+        search::search(&conn, &options, embedder).map(Ok)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(Ok(mut results))) => {
+            let next = pagination::finish_page(&mut results.hits, per_page, |_| {
+                pagination::encode_cursor(&(offset + per_page))
+            });
+            let items = results
+                .hits
+                .iter()
+                .filter_map(|hit| serde_json::to_value(hit).ok())
+                .collect();
+            let mut page = pagination::page_json(items, results.total, per_page, next);
+            page["query"] = serde_json::json!(query.q);
+            page["mode"] = serde_json::json!(mode);
+            if let Some(facets) = results.facets {
+                page["facets"] = serde_json::json!(facets);
+            }
+            ApiResponse::success(page)
+        }
+        Ok(Ok(Err(message))) => ApiResponse::<()>::bad_request(message),
+        Ok(Err(e)) => ApiResponse::<()>::error(&e.to_string()),
+        Err(e) => ApiResponse::<()>::error(&e.to_string()),
+    }
+}
+
+// =============================================================================
+// Session Write Handlers
+// =============================================================================
+
+/// A message to add to a session
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct NewMessage {
+    /// `user` or `assistant`
+    pub role: String,
+    pub content: String,
+    pub model: Option<String>,
+    /// Milliseconds since the epoch; defaults to now
+    pub timestamp: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateSessionRequest {
+    /// Session ID; generated if omitted
+    pub id: Option<String>,
+    /// Title; derived from the first message if omitted
+    pub title: Option<String>,
+    /// Provider name; defaults to `api`
+    pub provider: Option<String>,
+    pub workspace_id: Option<String>,
+    pub workspace_name: Option<String>,
+    #[serde(default)]
+    pub messages: Vec<NewMessage>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateSessionRequest {
+    /// New title; an empty string goes back to the derived title
+    pub title: Option<String>,
+    /// Replaces the session's user tags
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteSessionQuery {
+    /// Delete outright instead of keeping the session for restore
+    pub permanent: Option<bool>,
+}
+
+/// A harvested session with the columns a rewrite has to keep
+pub(super) struct StoredSession {
+    pub(super) provider: String,
+    pub(super) workspace_id: Option<String>,
+    pub(super) workspace_name: Option<String>,
+    /// User the session belongs to, `None` for harvested and admin sessions
+    pub(super) owner: Option<String>,
+    pub(super) session: ChatSession,
+}
+
+pub(super) fn load_stored_session(
+    conn: &rusqlite::Connection,
+    session_id: &str,
+) -> anyhow::Result<Option<StoredSession>> {
+    type Row = (
+        String,
+        Option<String>,
+        Option<String>,
+        Option<String>,
+        String,
+    );
+    let row: Option<Row> = conn
+        .query_row(
+            "SELECT provider, workspace_id, workspace_name, user_id, session_json
+             FROM sessions WHERE id = ?1",
+            [session_id],
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            },
+        )
+        .optional()?;
+    let Some((provider, workspace_id, workspace_name, owner, json)) = row else {
+        return Ok(None);
+    };
+
+    let mut session = crate::storage::parse_session_json(&json)?;
+    // Harvested sessions may only have their ID in the row
+    session.session_id = Some(session_id.to_string());
+    Ok(Some(StoredSession {
+        provider,
+        workspace_id,
+        workspace_name,
+        owner,
+        session,
+    }))
+}
+
+/// Load a session the caller can see; others are reported as missing
+pub(super) fn load_scoped_session(
+    conn: &rusqlite::Connection,
+    session_id: &str,
+    scope: &Scope,
+) -> anyhow::Result<Option<StoredSession>> {
+    Ok(load_stored_session(conn, session_id)?
+        .filter(|stored| scope.can_access(stored.owner.as_deref())))
+}
+
+/// Whether the caller can see the session with this ID in `table`, which is
+/// `sessions` or `deleted_sessions`
+pub(super) fn owns_session(
+    conn: &rusqlite::Connection,
+    table: &str,
+    session_id: &str,
+    scope: &Scope,
+) -> rusqlite::Result<bool> {
+    let owner: Option<Option<String>> = conn
+        .query_row(
+            &format!("SELECT user_id FROM {} WHERE id = ?1", table),
+            [session_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(owner.is_some_and(|owner| scope.can_access(owner.as_deref())))
+}
+
+/// Write a session through the harvest path and return its tags
+pub(super) fn store_session(
+    conn: &rusqlite::Connection,
+    stored: &StoredSession,
+    tags: Option<&[String]>,
+) -> anyhow::Result<Vec<String>> {
+    let session_id = stored.session.session_id.as_deref().unwrap_or_default();
+    let tx = conn.unchecked_transaction()?;
+    insert_or_update_session(
+        &tx,
+        &stored.session,
+        &stored.provider,
+        stored.workspace_id.as_deref(),
+        stored.workspace_name.as_deref(),
+    )?;
+    tx.execute(
+        "UPDATE sessions SET user_id = ?1 WHERE id = ?2",
+        params![stored.owner, session_id],
+    )?;
+    let tags = match tags {
+        Some(tags) => reviews::set_user_tags(&tx, session_id, tags)?,
+        None => reviews::session_tags(&tx, session_id)?,
+    };
+    tx.commit()?;
+    Ok(tags)
+}
+
+pub(super) fn session_summary(stored: &StoredSession, tags: Vec<String>) -> serde_json::Value {
+    let session = &stored.session;
+    serde_json::json!({
+        "id": session.session_id,
+        "provider": stored.provider,
+        "workspaceId": stored.workspace_id,
+        "workspaceName": stored.workspace_name,
+        "title": session.title(),
+        "messageCount": session.request_count(),
+        "createdAt": session.creation_date,
+        "updatedAt": session.last_message_date,
+        "tags": tags,
+    })
+}
+
+/// An empty session written through the API
+pub(super) fn new_api_session(session_id: &str) -> ChatSession {
+    ChatSession {
+        version: 3,
+        session_id: Some(session_id.to_string()),
+        creation_date: 0,
+        last_message_date: 0,
+        is_imported: true,
+        initial_location: "api".to_string(),
+        custom_title: None,
+        requester_username: None,
+        requester_avatar_icon_uri: None,
+        responder_username: None,
+        responder_avatar_icon_uri: None,
+        requests: Vec::new(),
+    }
+}
+
+fn new_request(timestamp: i64, model: Option<String>) -> ChatRequest {
+    ChatRequest {
+        timestamp: Some(timestamp),
+        message: None,
+        response: None,
+        variable_data: None,
+        request_id: Some(uuid::Uuid::new_v4().to_string()),
+        response_id: None,
+        model_id: model,
+        agent: None,
+        result: None,
+        followups: None,
+        is_canceled: Some(false),
+        content_references: None,
+        code_citations: None,
+        response_markdown_info: None,
+        source_session: None,
+    }
+}
+
+/// Add a message the way chat providers store them: a user message starts a
+/// request, and an assistant message answers the last unanswered one.
+/// Returns the message's index in `/messages`.
+pub(super) fn append_message(
+    session: &mut ChatSession,
+    message: &NewMessage,
+) -> Result<usize, &'static str> {
+    if message.content.is_empty() {
+        return Err("Message content is empty");
+    }
+    let timestamp = message
+        .timestamp
+        .unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+    let model = message.model.clone();
+
+    let index = match message.role.as_str() {
+        "user" => {
+            let mut request = new_request(timestamp, model);
+            request.message = Some(ChatMessage {
+                text: Some(message.content.clone()),
+                parts: None,
+            });
+            session.requests.push(request);
+            (session.requests.len() - 1) * 2
+        }
+        "assistant" => {
+            let unanswered = session
+                .requests
+                .last()
+                .is_some_and(|r| r.message.is_some() && r.response.is_none());
+            if !unanswered {
+                session.requests.push(new_request(timestamp, model.clone()));
+            }
+            let request = session.requests.last_mut().unwrap();
+            request.response = Some(serde_json::json!([{ "value": message.content }]));
+            request.response_id = Some(uuid::Uuid::new_v4().to_string());
+            if model.is_some() {
+                request.model_id = model;
+            }
+            (session.requests.len() - 1) * 2 + 1
+        }
+        _ => return Err("Message role must be user or assistant"),
+    };
+
+    session.creation_date = if session.creation_date == 0 {
+        timestamp
+    } else {
+        session.creation_date.min(timestamp)
+    };
+    session.last_message_date = session.last_message_date.max(timestamp);
+    Ok(index)
+}
+
+/// Create a session, optionally with messages and tags
+#[utoipa::path(
+    post,
+    path = "/api/sessions",
+    tag = "sessions",
+    request_body = CreateSessionRequest,
+    responses(
+        (status = 201, description = "Created session", body = ApiEnvelope),
+        (status = 400, description = "Invalid message"),
+        (status = 409, description = "A session with this ID exists")
+    )
+)]
+pub async fn create_session(
+    state: web::Data<AppState>,
+    sync_state: web::Data<SharedSyncState>,
+    body: web::Json<CreateSessionRequest>,
+    scope: Scope,
 ) -> impl Responder {
+    let body = body.into_inner();
+    let session_id = body.id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let mut stored = StoredSession {
+        provider: body.provider.unwrap_or_else(|| "api".to_string()),
+        workspace_id: body.workspace_id,
+        workspace_name: body.workspace_name,
+        owner: scope.owner().map(str::to_string),
+        session: new_api_session(&session_id),
+    };
+    stored.session.custom_title = body.title.filter(|t| !t.is_empty());
+    for message in &body.messages {
+        if let Err(e) = append_message(&mut stored.session, message) {
+            return ApiResponse::<()>::bad_request(e);
+        }
+    }
+    if stored.session.requests.is_empty() {
+        let now = chrono::Utc::now().timestamp_millis();
+        stored.session.creation_date = now;
+        stored.session.last_message_date = now;
+    }
+
     let db = state.db.lock().unwrap();
-    let limit = query.limit.unwrap_or(20) as i64;
-    let search_term = format!("%{}%", query.q);
+    let exists = db
+        .conn
+        .query_row(
+            "SELECT 1 FROM sessions WHERE id = ?1",
+            [&session_id],
+            |_| Ok(()),
+        )
+        .optional();
+    match exists {
+        Ok(Some(())) => {
+            return HttpResponse::Conflict().json(ApiResponse::<()> {
+                success: false,
+                data: None,
+                error: Some(format!("Session {} already exists", session_id)),
+            })
+        }
+        Ok(None) => {}
+        Err(e) => return ApiResponse::<()>::error(&e.to_string()),
+    }
 
-    let result: Result<Vec<serde_json::Value>, _> = (|| {
-        let mut stmt = db.conn.prepare(
-            "SELECT DISTINCT s.id, s.title, s.provider, s.workspace_id, s.message_count, s.updated_at
-             FROM sessions s
-             LEFT JOIN messages m ON s.id = m.session_id
-             WHERE s.title LIKE ?1 OR m.content LIKE ?1
-             ORDER BY s.updated_at DESC
-             LIMIT ?2"
-        )?;
+    match store_session(&db.conn, &stored, Some(&body.tags)) {
+        Ok(tags) => {
+            let summary = session_summary(&stored, tags);
+            sync::publish_session_change(
+                &sync_state,
+                SyncOperation::Create,
+                &session_id,
+                Some(summary.clone()),
+            );
+            ApiResponse::created(summary)
+        }
+        Err(e) => ApiResponse::<()>::error(&format!("Failed to create session: {}", e)),
+    }
+}
 
-        let results: Vec<serde_json::Value> = stmt
-            .query_map(params![search_term, limit], |row| {
-                let workspace_id: Option<String> = row.get(3)?;
-                let workspace_name = workspace_id.as_ref().map(|id| derive_workspace_name(id));
-                Ok(serde_json::json!({
-                    "id": row.get::<_, String>(0)?,
-                    "title": row.get::<_, String>(1)?,
-                    "provider": row.get::<_, String>(2)?,
-                    "workspace_id": workspace_id,
-                    "workspace_name": workspace_name,
-                    "message_count": row.get::<_, i64>(4)?,
-                    "updated_at": row.get::<_, i64>(5)?,
-                }))
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
+/// Append a message to a session
+#[utoipa::path(
+    post,
+    path = "/api/sessions/{id}/messages",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Session ID")),
+    request_body = NewMessage,
+    responses(
+        (status = 201, description = "Message added", body = ApiEnvelope),
+        (status = 400, description = "Invalid message"),
+        (status = 404, description = "Session not found")
+    )
+)]
+pub async fn append_session_message(
+    state: web::Data<AppState>,
+    sync_state: web::Data<SharedSyncState>,
+    path: web::Path<String>,
+    body: web::Json<NewMessage>,
+    scope: Scope,
+) -> impl Responder {
+    let session_id = path.into_inner();
+    let db = state.db.lock().unwrap();
+
+    let mut stored = match load_scoped_session(&db.conn, &session_id, &scope) {
+        Ok(Some(stored)) => stored,
+        Ok(None) => return ApiResponse::<()>::not_found("Session not found"),
+        Err(e) => return ApiResponse::<()>::error(&e.to_string()),
+    };
+    let index = match append_message(&mut stored.session, &body) {
+        Ok(index) => index,
+        Err(e) => return ApiResponse::<()>::bad_request(e),
+    };
+
+    match store_session(&db.conn, &stored, None) {
+        Ok(tags) => {
+            let summary = session_summary(&stored, tags);
+            teams::record_session_activity(
+                &db.conn,
+                &scope,
+                &session_id,
+                EventType::SessionUpdated,
+            );
+            sync::publish_session_change(
+                &sync_state,
+                SyncOperation::Update,
+                &session_id,
+                Some(summary.clone()),
+            );
+            ApiResponse::created(serde_json::json!({
+                "index": index,
+                "role": body.role,
+                "session": summary,
+            }))
+        }
+        Err(e) => ApiResponse::<()>::error(&format!("Failed to add message: {}", e)),
+    }
+}
+
+/// Change a session's title or tags
+#[utoipa::path(
+    put,
+    path = "/api/sessions/{id}",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Session ID")),
+    request_body = UpdateSessionRequest,
+    responses(
+        (status = 200, description = "Updated session", body = ApiEnvelope),
+        (status = 404, description = "Session not found")
+    )
+)]
+pub async fn update_session(
+    state: web::Data<AppState>,
+    sync_state: web::Data<SharedSyncState>,
+    path: web::Path<String>,
+    body: web::Json<UpdateSessionRequest>,
+    scope: Scope,
+) -> impl Responder {
+    let session_id = path.into_inner();
+    let body = body.into_inner();
+    let db = state.db.lock().unwrap();
+
+    let mut stored = match load_scoped_session(&db.conn, &session_id, &scope) {
+        Ok(Some(stored)) => stored,
+        Ok(None) => return ApiResponse::<()>::not_found("Session not found"),
+        Err(e) => return ApiResponse::<()>::error(&e.to_string()),
+    };
+    if let Some(title) = body.title {
+        stored.session.custom_title = Some(title).filter(|t| !t.is_empty());
+    }
+
+    match store_session(&db.conn, &stored, body.tags.as_deref()) {
+        Ok(tags) => {
+            let summary = session_summary(&stored, tags);
+            teams::record_session_activity(
+                &db.conn,
+                &scope,
+                &session_id,
+                EventType::SessionUpdated,
+            );
+            sync::publish_session_change(
+                &sync_state,
+                SyncOperation::Update,
+                &session_id,
+                Some(summary.clone()),
+            );
+            ApiResponse::success(summary)
+        }
+        Err(e) => ApiResponse::<()>::error(&format!("Failed to update session: {}", e)),
+    }
+}
 
-        Ok::<_, rusqlite::Error>(results)
+/// Delete a session. It can be restored unless `permanent` is set.
+#[utoipa::path(
+    delete,
+    path = "/api/sessions/{id}",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Session ID"), DeleteSessionQuery),
+    responses(
+        (status = 200, description = "Session deleted", body = ApiEnvelope),
+        (status = 404, description = "Session not found")
+    )
+)]
+pub async fn delete_session(
+    state: web::Data<AppState>,
+    sync_state: web::Data<SharedSyncState>,
+    path: web::Path<String>,
+    query: web::Query<DeleteSessionQuery>,
+    scope: Scope,
+) -> impl Responder {
+    let session_id = path.into_inner();
+    let permanent = query.permanent.unwrap_or(false);
+    let db = state.db.lock().unwrap();
+
+    let result = (|| -> anyhow::Result<bool> {
+        if !owns_session(&db.conn, "sessions", &session_id, &scope)? {
+            return Ok(false);
+        }
+        let tx = db.conn.unchecked_transaction()?;
+        teams::record_session_activity(&tx, &scope, &session_id, EventType::SessionDeleted);
+        let deleted = delete_harvested_session(&tx, &session_id, !permanent)?;
+        tx.commit()?;
+        Ok(deleted)
     })();
 
     match result {
-        Ok(results) => ApiResponse::success(serde_json::json!({
-            "results": results,
-            "query": query.q,
-        })),
-        Err(e) => ApiResponse::<()>::error(&e.to_string()),
+        Ok(false) => ApiResponse::<()>::not_found("Session not found"),
+        Ok(true) => {
+            sync::publish_session_change(&sync_state, SyncOperation::Delete, &session_id, None);
+            ApiResponse::success(serde_json::json!({
+                "id": session_id,
+                "deleted": true,
+                "permanent": permanent,
+            }))
+        }
+        Err(e) => ApiResponse::<()>::error(&format!("Failed to delete session: {}", e)),
+    }
+}
+
+/// Restore a session deleted without `permanent`
+#[utoipa::path(
+    post,
+    path = "/api/sessions/{id}/restore",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Session ID")),
+    responses(
+        (status = 200, description = "Restored session", body = ApiEnvelope),
+        (status = 404, description = "No deleted session with this ID")
+    )
+)]
+pub async fn restore_session(
+    state: web::Data<AppState>,
+    sync_state: web::Data<SharedSyncState>,
+    path: web::Path<String>,
+    scope: Scope,
+) -> impl Responder {
+    let session_id = path.into_inner();
+    let db = state.db.lock().unwrap();
+
+    let result = (|| -> anyhow::Result<Option<serde_json::Value>> {
+        ensure_deleted_sessions_table(&db.conn)?;
+        if !owns_session(&db.conn, "deleted_sessions", &session_id, &scope)? {
+            return Ok(None);
+        }
+        let tx = db.conn.unchecked_transaction()?;
+        if !restore_harvested_session(&tx, &session_id)? {
+            return Ok(None);
+        }
+        tx.commit()?;
+        let stored = load_stored_session(&db.conn, &session_id)?;
+        let tags = reviews::session_tags(&db.conn, &session_id)?;
+        Ok(stored.map(|stored| session_summary(&stored, tags)))
+    })();
+
+    match result {
+        Ok(Some(summary)) => {
+            sync::publish_session_change(
+                &sync_state,
+                SyncOperation::Create,
+                &session_id,
+                Some(summary.clone()),
+            );
+            ApiResponse::success(summary)
+        }
+        Ok(None) => ApiResponse::<()>::not_found("No deleted session with this ID"),
+        Err(e) => ApiResponse::<()>::error(&format!("Failed to restore session: {}", e)),
     }
 }
 
@@ -821,14 +1732,25 @@ fn get_file_changes(
 // Stats Handler (using harvest schema)
 // =============================================================================
 
-pub async fn get_stats(state: web::Data<AppState>) -> impl Responder {
+/// Get database statistics
+#[utoipa::path(
+    get,
+    path = "/api/stats",
+    tag = "system",
+    responses((status = 200, description = "Database statistics", body = ApiEnvelope))
+)]
+pub async fn get_stats(state: web::Data<AppState>, scope: Scope) -> impl Responder {
     let db = state.db.lock().unwrap();
+    let owned = scope.condition("user_id");
+    let owned_ids = format!("session_id IN (SELECT id FROM sessions WHERE {})", owned);
 
     let result: Result<serde_json::Value, _> =
         (|| {
-            let total_sessions: i64 =
-                db.conn
-                    .query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get(0))?;
+            let total_sessions: i64 = db.conn.query_row(
+                &format!("SELECT COUNT(*) FROM sessions WHERE {}", owned),
+                [],
+                |row| row.get(0),
+            )?;
 
             // Check if enhanced tables exist and query them safely
             let messages_v2_exists: bool = db.conn.query_row(
@@ -851,13 +1773,20 @@ pub async fn get_stats(state: web::Data<AppState>) -> impl Responder {
 
             let total_messages: i64 = if messages_v2_exists {
                 db.conn
-                    .query_row("SELECT COUNT(*) FROM messages_v2", [], |row| row.get(0))
+                    .query_row(
+                        &format!("SELECT COUNT(*) FROM messages_v2 WHERE {}", owned_ids),
+                        [],
+                        |row| row.get(0),
+                    )
                     .unwrap_or(0)
             } else {
                 // Fallback: estimate from session message_count
                 db.conn
                     .query_row(
-                        "SELECT COALESCE(SUM(message_count), 0) FROM sessions",
+                        &format!(
+                            "SELECT COALESCE(SUM(message_count), 0) FROM sessions WHERE {}",
+                            owned
+                        ),
                         [],
                         |row| row.get(0),
                     )
@@ -866,9 +1795,11 @@ pub async fn get_stats(state: web::Data<AppState>) -> impl Responder {
 
             let total_tool_invocations: i64 = if tool_invocations_exists {
                 db.conn
-                    .query_row("SELECT COUNT(*) FROM tool_invocations", [], |row| {
-                        row.get(0)
-                    })
+                    .query_row(
+                        &format!("SELECT COUNT(*) FROM tool_invocations WHERE {}", owned_ids),
+                        [],
+                        |row| row.get(0),
+                    )
                     .unwrap_or(0)
             } else {
                 0
@@ -876,15 +1807,21 @@ pub async fn get_stats(state: web::Data<AppState>) -> impl Responder {
 
             let total_file_changes: i64 = if file_changes_exists {
                 db.conn
-                    .query_row("SELECT COUNT(*) FROM file_changes", [], |row| row.get(0))
+                    .query_row(
+                        &format!("SELECT COUNT(*) FROM file_changes WHERE {}", owned_ids),
+                        [],
+                        |row| row.get(0),
+                    )
                     .unwrap_or(0)
             } else {
                 0
             };
 
-            let mut stmt = db.conn.prepare(
-                "SELECT provider, COUNT(*) FROM sessions GROUP BY provider ORDER BY COUNT(*) DESC",
-            )?;
+            let mut stmt = db.conn.prepare(&format!(
+                "SELECT provider, COUNT(*) FROM sessions WHERE {}
+                 GROUP BY provider ORDER BY COUNT(*) DESC",
+                owned
+            ))?;
 
             let by_provider: Vec<(String, i64)> = stmt
                 .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
@@ -892,7 +1829,10 @@ pub async fn get_stats(state: web::Data<AppState>) -> impl Responder {
 
             // Count unique workspaces
             let total_workspaces: i64 = db.conn.query_row(
-                "SELECT COUNT(DISTINCT workspace_id) FROM sessions",
+                &format!(
+                    "SELECT COUNT(DISTINCT workspace_id) FROM sessions WHERE {}",
+                    owned
+                ),
                 [],
                 |row| row.get(0),
             )?;
@@ -990,6 +1930,13 @@ impl ProviderInfo {
     }
 }
 
+/// List supported providers
+#[utoipa::path(
+    get,
+    path = "/api/providers",
+    tag = "providers",
+    responses((status = 200, description = "Supported providers", body = ApiEnvelope))
+)]
 pub async fn list_providers() -> impl Responder {
     let providers = vec![
         // ===========================================
@@ -1536,6 +2483,12 @@ pub async fn list_providers() -> impl Responder {
 // =============================================================================
 
 /// List available MCP tools
+#[utoipa::path(
+    get,
+    path = "/api/mcp/tools",
+    tag = "mcp",
+    responses((status = 200, description = "MCP tools in MCP and OpenAI function formats", body = ApiEnvelope))
+)]
 pub async fn list_mcp_tools() -> impl Responder {
     use crate::mcp::tools::list_tools;
 
@@ -1563,12 +2516,20 @@ pub async fn list_mcp_tools() -> impl Responder {
 }
 
 /// Execute an MCP tool call
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct ToolCallRequest {
     pub name: String,
     pub arguments: std::collections::HashMap<String, serde_json::Value>,
 }
 
+/// Call an MCP tool
+#[utoipa::path(
+    post,
+    path = "/api/mcp/call",
+    tag = "mcp",
+    request_body = ToolCallRequest,
+    responses((status = 200, description = "Tool result", body = ApiEnvelope))
+)]
 pub async fn call_mcp_tool(body: web::Json<ToolCallRequest>) -> impl Responder {
     use crate::mcp::tools::call_tool;
 
@@ -1582,11 +2543,19 @@ pub async fn call_mcp_tool(body: web::Json<ToolCallRequest>) -> impl Responder {
 }
 
 /// Execute multiple MCP tool calls
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct BatchToolCallRequest {
     pub calls: Vec<ToolCallRequest>,
 }
 
+/// Call several MCP tools
+#[utoipa::path(
+    post,
+    path = "/api/mcp/batch",
+    tag = "mcp",
+    request_body = BatchToolCallRequest,
+    responses((status = 200, description = "Tool results in request order", body = ApiEnvelope))
+)]
 pub async fn call_mcp_tools_batch(body: web::Json<BatchToolCallRequest>) -> impl Responder {
     use crate::mcp::tools::call_tool;
 
@@ -1607,6 +2576,12 @@ pub async fn call_mcp_tools_batch(body: web::Json<BatchToolCallRequest>) -> impl
 }
 
 /// Get system prompt with CSM tools context
+#[utoipa::path(
+    get,
+    path = "/api/mcp/system-prompt",
+    tag = "mcp",
+    responses((status = 200, description = "System prompt describing the available tools", body = ApiEnvelope))
+)]
 pub async fn get_csm_system_prompt() -> impl Responder {
     use crate::mcp::tools::list_tools;
 
@@ -1671,7 +2646,7 @@ pub struct Agent {
     pub metadata: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateAgentRequest {
     pub name: String,
     pub description: Option<String>,
@@ -1686,7 +2661,7 @@ pub struct CreateAgentRequest {
     pub metadata: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateAgentRequest {
     pub name: Option<String>,
     pub description: Option<String>,
@@ -1727,6 +2702,12 @@ fn init_agents_table(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
 }
 
 /// List all agents
+#[utoipa::path(
+    get,
+    path = "/api/agents",
+    tag = "agents",
+    responses((status = 200, description = "Agents", body = ApiEnvelope))
+)]
 pub async fn list_agents(state: web::Data<AppState>) -> impl Responder {
     let db = state.db.lock().unwrap();
 
@@ -1780,6 +2761,16 @@ pub async fn list_agents(state: web::Data<AppState>) -> impl Responder {
 }
 
 /// Get a single agent
+#[utoipa::path(
+    get,
+    path = "/api/agents/{id}",
+    tag = "agents",
+    params(("id" = String, Path, description = "Agent ID")),
+    responses(
+        (status = 200, description = "Agent", body = ApiEnvelope),
+        (status = 404, description = "Agent not found")
+    )
+)]
 pub async fn get_agent(state: web::Data<AppState>, path: web::Path<String>) -> impl Responder {
     let id = path.into_inner();
     let db = state.db.lock().unwrap();
@@ -1835,6 +2826,13 @@ pub async fn get_agent(state: web::Data<AppState>, path: web::Path<String>) -> i
 }
 
 /// Create a new agent
+#[utoipa::path(
+    post,
+    path = "/api/agents",
+    tag = "agents",
+    request_body = CreateAgentRequest,
+    responses((status = 200, description = "Created agent", body = ApiEnvelope))
+)]
 pub async fn create_agent(
     state: web::Data<AppState>,
     body: web::Json<CreateAgentRequest>,
@@ -1901,6 +2899,17 @@ pub async fn create_agent(
 }
 
 /// Update an agent
+#[utoipa::path(
+    put,
+    path = "/api/agents/{id}",
+    tag = "agents",
+    params(("id" = String, Path, description = "Agent ID")),
+    request_body = UpdateAgentRequest,
+    responses(
+        (status = 200, description = "Updated agent", body = ApiEnvelope),
+        (status = 404, description = "Agent not found")
+    )
+)]
 pub async fn update_agent(
     state: web::Data<AppState>,
     path: web::Path<String>,
@@ -1970,6 +2979,16 @@ pub async fn update_agent(
 }
 
 /// Delete an agent
+#[utoipa::path(
+    delete,
+    path = "/api/agents/{id}",
+    tag = "agents",
+    params(("id" = String, Path, description = "Agent ID")),
+    responses(
+        (status = 200, description = "Agent deleted", body = ApiEnvelope),
+        (status = 404, description = "Agent not found")
+    )
+)]
 pub async fn delete_agent(state: web::Data<AppState>, path: web::Path<String>) -> impl Responder {
     let id = path.into_inner();
     let db = state.db.lock().unwrap();
@@ -1989,17 +3008,96 @@ pub async fn delete_agent(state: web::Data<AppState>, path: web::Path<String>) -
     }
 }
 
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RunAgentRequest {
+    pub prompt: String,
+    /// Model to use instead of the agent's own
+    pub model: Option<String>,
+}
+
+/// Run an agent, streaming its events as Server-Sent Events
+///
+/// Agents are the ones `csm agency run` knows: the built-in roles and those
+/// defined in the library. Each event is sent as it happens, named by its
+/// type, with `message_delta` events carrying the reply as the model writes
+/// it. The stream ends with a `result` or an `error` event.
+#[utoipa::path(
+    post,
+    path = "/api/agents/{name}/run",
+    tag = "agents",
+    params(("name" = String, Path, description = "Agent name")),
+    request_body = RunAgentRequest,
+    responses(
+        (status = 200, description = "Stream of agent events", body = String, content_type = "text/event-stream"),
+        (status = 404, description = "Agent not found")
+    )
+)]
+pub async fn run_agent(path: web::Path<String>, body: web::Json<RunAgentRequest>) -> HttpResponse {
+    use crate::commands::{load_agent, run_agent_with_events};
+
+    let name = path.into_inner();
+    let RunAgentRequest { prompt, model } = body.into_inner();
+    let agent = match load_agent(&name, model.as_deref()) {
+        Ok(agent) => agent,
+        Err(e) => return ApiResponse::<()>::not_found(&e.to_string()),
+    };
+
+    // The run blocks on its own runtime, so it gets a thread and hands its
+    // events over; a closed stream cancels it
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        let events = tx.clone();
+        let result = run_agent_with_events(
+            agent,
+            &prompt,
+            &mut |event| {
+                let _ = events.send((event.event_type.to_string(), serde_json::json!(event)));
+            },
+            &|| events.is_closed(),
+        );
+        let last = match result {
+            Ok(result) => (
+                "result".to_string(),
+                serde_json::json!({
+                    "agent": name,
+                    "response": result.response,
+                    "success": result.success,
+                    "error": result.error,
+                    "token_usage": result.token_usage,
+                    "duration_ms": result.duration_ms,
+                }),
+            ),
+            Err(e) => (
+                "error".to_string(),
+                serde_json::json!({ "error": e.to_string() }),
+            ),
+        };
+        let _ = tx.send(last);
+    });
+
+    let stream = async_stream::stream! {
+        while let Some((event, data)) = rx.recv().await {
+            yield Ok::<_, std::io::Error>(web::Bytes::from(format!("event: {}\ndata: {}\n\n", event, data)));
+        }
+    };
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .insert_header(actix_web::http::header::ContentEncoding::Identity)
+        .streaming(stream)
+}
+
 // =============================================================================
 // Swarm Endpoints
 // =============================================================================
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct SwarmAgent {
     pub agent_id: String,
     pub role: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateSwarmRequest {
     pub name: String,
     pub description: Option<String>,
@@ -2027,6 +3125,12 @@ fn init_swarms_table(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
 }
 
 /// List all swarms
+#[utoipa::path(
+    get,
+    path = "/api/swarms",
+    tag = "swarms",
+    responses((status = 200, description = "Swarms", body = ApiEnvelope))
+)]
 pub async fn list_swarms(state: web::Data<AppState>) -> impl Responder {
     let db = state.db.lock().unwrap();
 
@@ -2070,6 +3174,16 @@ pub async fn list_swarms(state: web::Data<AppState>) -> impl Responder {
 }
 
 /// Get a single swarm
+#[utoipa::path(
+    get,
+    path = "/api/swarms/{id}",
+    tag = "swarms",
+    params(("id" = String, Path, description = "Swarm ID")),
+    responses(
+        (status = 200, description = "Swarm", body = ApiEnvelope),
+        (status = 404, description = "Swarm not found")
+    )
+)]
 pub async fn get_swarm(state: web::Data<AppState>, path: web::Path<String>) -> impl Responder {
     let id = path.into_inner();
     let db = state.db.lock().unwrap();
@@ -2116,6 +3230,13 @@ pub async fn get_swarm(state: web::Data<AppState>, path: web::Path<String>) -> i
 }
 
 /// Create a new swarm
+#[utoipa::path(
+    post,
+    path = "/api/swarms",
+    tag = "swarms",
+    request_body = CreateSwarmRequest,
+    responses((status = 200, description = "Created swarm", body = ApiEnvelope))
+)]
 pub async fn create_swarm(
     state: web::Data<AppState>,
     body: web::Json<CreateSwarmRequest>,
@@ -2166,6 +3287,16 @@ pub async fn create_swarm(
 }
 
 /// Delete a swarm
+#[utoipa::path(
+    delete,
+    path = "/api/swarms/{id}",
+    tag = "swarms",
+    params(("id" = String, Path, description = "Swarm ID")),
+    responses(
+        (status = 200, description = "Swarm deleted", body = ApiEnvelope),
+        (status = 404, description = "Swarm not found")
+    )
+)]
 pub async fn delete_swarm(state: web::Data<AppState>, path: web::Path<String>) -> impl Responder {
     let id = path.into_inner();
     let db = state.db.lock().unwrap();
@@ -2220,6 +3351,12 @@ fn init_settings_table(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
 }
 
 /// Get all settings
+#[utoipa::path(
+    get,
+    path = "/api/settings",
+    tag = "settings",
+    responses((status = 200, description = "Settings as key/value pairs", body = ApiEnvelope))
+)]
 pub async fn get_settings(state: web::Data<AppState>) -> HttpResponse {
     let db = state.db.lock().unwrap();
 
@@ -2254,6 +3391,13 @@ pub async fn get_settings(state: web::Data<AppState>) -> HttpResponse {
 }
 
 /// Update settings
+#[utoipa::path(
+    put,
+    path = "/api/settings",
+    tag = "settings",
+    request_body = Object,
+    responses((status = 200, description = "Settings updated", body = ApiEnvelope))
+)]
 pub async fn update_settings(
     state: web::Data<AppState>,
     body: web::Json<serde_json::Value>,
@@ -2303,13 +3447,19 @@ fn init_accounts_table(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
     Ok(())
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateAccountRequest {
     pub provider: String,
     pub credentials: serde_json::Value,
 }
 
 /// List provider accounts
+#[utoipa::path(
+    get,
+    path = "/api/settings/accounts",
+    tag = "settings",
+    responses((status = 200, description = "Provider accounts", body = ApiEnvelope))
+)]
 pub async fn list_accounts(state: web::Data<AppState>) -> impl Responder {
     let db = state.db.lock().unwrap();
 
@@ -2346,6 +3496,13 @@ pub async fn list_accounts(state: web::Data<AppState>) -> impl Responder {
 }
 
 /// Create a provider account
+#[utoipa::path(
+    post,
+    path = "/api/settings/accounts",
+    tag = "settings",
+    request_body = CreateAccountRequest,
+    responses((status = 200, description = "Created account", body = ApiEnvelope))
+)]
 pub async fn create_account(
     state: web::Data<AppState>,
     body: web::Json<CreateAccountRequest>,
@@ -2384,6 +3541,16 @@ pub async fn create_account(
 }
 
 /// Delete a provider account
+#[utoipa::path(
+    delete,
+    path = "/api/settings/accounts/{id}",
+    tag = "settings",
+    params(("id" = String, Path, description = "Account ID")),
+    responses(
+        (status = 200, description = "Account deleted", body = ApiEnvelope),
+        (status = 404, description = "Account not found")
+    )
+)]
 pub async fn delete_account(state: web::Data<AppState>, path: web::Path<String>) -> impl Responder {
     let id = path.into_inner();
     let db = state.db.lock().unwrap();
@@ -2407,9 +3574,15 @@ pub async fn delete_account(state: web::Data<AppState>, path: web::Path<String>)
 // System Endpoints
 // =============================================================================
 
-static START_TIME: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+pub(super) static START_TIME: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
 
 /// Get system information
+#[utoipa::path(
+    get,
+    path = "/api/system/info",
+    tag = "system",
+    responses((status = 200, description = "Version, platform, and database information", body = ApiEnvelope))
+)]
 pub async fn get_system_info(state: web::Data<AppState>) -> impl Responder {
     let start = START_TIME.get_or_init(std::time::Instant::now);
     let uptime = start.elapsed().as_secs();
@@ -2441,47 +3614,44 @@ pub async fn get_system_info(state: web::Data<AppState>) -> impl Responder {
     }))
 }
 
-/// Get system health
-pub async fn get_system_health(state: web::Data<AppState>) -> impl Responder {
-    let start = START_TIME.get_or_init(std::time::Instant::now);
-    let uptime = start.elapsed().as_secs();
-
-    // Try a simple DB query to verify connection
-    let db = state.db.lock().unwrap();
-    let db_ok = db.conn.execute("SELECT 1", []).is_ok();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    ApiResponse::success(serde_json::json!({
-        "status": if db_ok { "healthy" } else { "degraded" },
-        "version": env!("CARGO_PKG_VERSION"),
-        "uptime": uptime,
-        "checks": {
-            "database": if db_ok { "ok" } else { "error" },
-            "api": "ok"
+    fn message(role: &str, content: &str) -> NewMessage {
+        NewMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+            model: None,
+            timestamp: Some(1_700_000_000_000),
         }
-    }))
-}
+    }
 
-/// Get provider health status  
-pub async fn get_provider_health() -> impl Responder {
-    // In a real implementation, this would check actual provider connectivity
-    ApiResponse::success(serde_json::json!([
-        {
-            "provider": "copilot",
-            "status": "connected",
-            "latency": 45,
-            "lastCheck": std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as i64
-        },
-        {
-            "provider": "ollama",
-            "status": "disconnected",
-            "latency": null,
-            "lastCheck": std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as i64
-        }
-    ]))
+    #[test]
+    fn test_append_message() {
+        let mut session: ChatSession = serde_json::from_str("{}").unwrap();
+
+        assert_eq!(append_message(&mut session, &message("user", "hi")), Ok(0));
+        assert_eq!(
+            append_message(&mut session, &message("assistant", "hello")),
+            Ok(1)
+        );
+        // An assistant message with nothing to answer gets its own request
+        assert_eq!(
+            append_message(&mut session, &message("assistant", "also")),
+            Ok(3)
+        );
+        assert!(append_message(&mut session, &message("system", "x")).is_err());
+        assert!(append_message(&mut session, &message("user", "")).is_err());
+
+        let json = serde_json::to_value(&session).unwrap();
+        let messages = extract_messages_from_session(&json);
+        let roles: Vec<&str> = messages
+            .iter()
+            .map(|m| m["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, ["user", "assistant", "assistant"]);
+        assert_eq!(messages[1]["content"], "hello");
+        assert_eq!(session.creation_date, 1_700_000_000_000);
+    }
 }
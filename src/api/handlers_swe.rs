@@ -12,7 +12,9 @@ use rusqlite::{params, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::process::Command;
+use utoipa::{IntoParams, ToSchema};
 
+use super::docs::ApiEnvelope;
 use super::state::AppState;
 
 // =============================================================================
@@ -138,7 +140,7 @@ pub struct SweSession {
 // Request Types
 // =============================================================================
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateProjectRequest {
     pub path: String,
@@ -146,7 +148,7 @@ pub struct CreateProjectRequest {
     pub description: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateMemoryRequest {
     pub key: String,
@@ -156,7 +158,7 @@ pub struct CreateMemoryRequest {
     pub expires_at: Option<i64>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateMemoryRequest {
     pub value: Option<String>,
@@ -165,7 +167,7 @@ pub struct UpdateMemoryRequest {
     pub expires_at: Option<i64>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateRuleRequest {
     pub rule: String,
@@ -176,7 +178,7 @@ pub struct CreateRuleRequest {
     pub conditions: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateRuleRequest {
     pub rule: Option<String>,
@@ -188,19 +190,21 @@ pub struct UpdateRuleRequest {
     pub conditions: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ExecuteToolRequest {
     pub tool: String,
     pub input: serde_json::Value,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ProjectQuery {
     pub limit: Option<usize>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct MemoryQuery {
     pub category: Option<String>,
     pub importance: Option<String>,
@@ -208,7 +212,8 @@ pub struct MemoryQuery {
     pub limit: Option<usize>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct RuleQuery {
     pub category: Option<String>,
     pub enabled: Option<bool>,
@@ -351,6 +356,13 @@ pub fn init_swe_tables(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
 // =============================================================================
 
 /// List all SWE projects
+#[utoipa::path(
+    get,
+    path = "/api/swe/projects",
+    tag = "swe",
+    params(ProjectQuery),
+    responses((status = 200, description = "Projects", body = ApiEnvelope))
+)]
 pub async fn list_projects(
     state: web::Data<AppState>,
     query: web::Query<ProjectQuery>,
@@ -405,6 +417,16 @@ pub async fn list_projects(
 }
 
 /// Get a single project by ID
+#[utoipa::path(
+    get,
+    path = "/api/swe/projects/{id}",
+    tag = "swe",
+    params(("id" = String, Path, description = "Project ID")),
+    responses(
+        (status = 200, description = "Project", body = ApiEnvelope),
+        (status = 404, description = "Project not found")
+    )
+)]
 pub async fn get_project(state: web::Data<AppState>, path: web::Path<String>) -> impl Responder {
     let project_id = path.into_inner();
     let db = state.db.lock().unwrap();
@@ -448,6 +470,16 @@ pub async fn get_project(state: web::Data<AppState>, path: web::Path<String>) ->
 }
 
 /// Create a new SWE project
+#[utoipa::path(
+    post,
+    path = "/api/swe/projects",
+    tag = "swe",
+    request_body = CreateProjectRequest,
+    responses(
+        (status = 200, description = "Created project", body = ApiEnvelope),
+        (status = 400, description = "Invalid project path")
+    )
+)]
 pub async fn create_project(
     state: web::Data<AppState>,
     body: web::Json<CreateProjectRequest>,
@@ -529,6 +561,13 @@ pub async fn create_project(
 }
 
 /// Delete a project
+#[utoipa::path(
+    delete,
+    path = "/api/swe/projects/{id}",
+    tag = "swe",
+    params(("id" = String, Path, description = "Project ID")),
+    responses((status = 200, description = "Project deleted", body = ApiEnvelope))
+)]
 pub async fn delete_project(state: web::Data<AppState>, path: web::Path<String>) -> impl Responder {
     let project_id = path.into_inner();
     let db = state.db.lock().unwrap();
@@ -544,6 +583,13 @@ pub async fn delete_project(state: web::Data<AppState>, path: web::Path<String>)
 }
 
 /// Open a project (update last_opened timestamp)
+#[utoipa::path(
+    post,
+    path = "/api/swe/projects/{id}/open",
+    tag = "swe",
+    params(("id" = String, Path, description = "Project ID")),
+    responses((status = 200, description = "Project opened", body = ApiEnvelope))
+)]
 pub async fn open_project(state: web::Data<AppState>, path: web::Path<String>) -> impl Responder {
     let project_id = path.into_inner();
     let db = state.db.lock().unwrap();
@@ -568,6 +614,16 @@ pub async fn open_project(state: web::Data<AppState>, path: web::Path<String>) -
 // =============================================================================
 
 /// List memory entries for a project
+#[utoipa::path(
+    get,
+    path = "/api/swe/projects/{project_id}/memory",
+    tag = "swe",
+    params(
+        ("project_id" = String, Path, description = "Project ID"),
+        MemoryQuery
+    ),
+    responses((status = 200, description = "Memory entries", body = ApiEnvelope))
+)]
 pub async fn list_memory(
     state: web::Data<AppState>,
     path: web::Path<String>,
@@ -638,6 +694,19 @@ pub async fn list_memory(
 }
 
 /// Get a single memory entry
+#[utoipa::path(
+    get,
+    path = "/api/swe/projects/{project_id}/memory/{id}",
+    tag = "swe",
+    params(
+        ("project_id" = String, Path, description = "Project ID"),
+        ("id" = String, Path, description = "Memory entry ID")
+    ),
+    responses(
+        (status = 200, description = "Memory entry", body = ApiEnvelope),
+        (status = 404, description = "Memory entry not found")
+    )
+)]
 pub async fn get_memory(
     state: web::Data<AppState>,
     path: web::Path<(String, String)>,
@@ -692,6 +761,14 @@ pub async fn get_memory(
 }
 
 /// Create a memory entry
+#[utoipa::path(
+    post,
+    path = "/api/swe/projects/{project_id}/memory",
+    tag = "swe",
+    params(("project_id" = String, Path, description = "Project ID")),
+    request_body = CreateMemoryRequest,
+    responses((status = 200, description = "Created memory entry", body = ApiEnvelope))
+)]
 pub async fn create_memory(
     state: web::Data<AppState>,
     path: web::Path<String>,
@@ -759,6 +836,17 @@ pub async fn create_memory(
 }
 
 /// Update a memory entry
+#[utoipa::path(
+    put,
+    path = "/api/swe/projects/{project_id}/memory/{id}",
+    tag = "swe",
+    params(
+        ("project_id" = String, Path, description = "Project ID"),
+        ("id" = String, Path, description = "Memory entry ID")
+    ),
+    request_body = UpdateMemoryRequest,
+    responses((status = 200, description = "Updated memory entry", body = ApiEnvelope))
+)]
 pub async fn update_memory(
     state: web::Data<AppState>,
     path: web::Path<(String, String)>,
@@ -811,6 +899,16 @@ pub async fn update_memory(
 }
 
 /// Delete a memory entry
+#[utoipa::path(
+    delete,
+    path = "/api/swe/projects/{project_id}/memory/{id}",
+    tag = "swe",
+    params(
+        ("project_id" = String, Path, description = "Project ID"),
+        ("id" = String, Path, description = "Memory entry ID")
+    ),
+    responses((status = 200, description = "Memory entry deleted", body = ApiEnvelope))
+)]
 pub async fn delete_memory(
     state: web::Data<AppState>,
     path: web::Path<(String, String)>,
@@ -833,6 +931,16 @@ pub async fn delete_memory(
 // =============================================================================
 
 /// List rules for a project
+#[utoipa::path(
+    get,
+    path = "/api/swe/projects/{project_id}/rules",
+    tag = "swe",
+    params(
+        ("project_id" = String, Path, description = "Project ID"),
+        RuleQuery
+    ),
+    responses((status = 200, description = "Rules", body = ApiEnvelope))
+)]
 pub async fn list_rules(
     state: web::Data<AppState>,
     path: web::Path<String>,
@@ -892,6 +1000,14 @@ pub async fn list_rules(
 }
 
 /// Create a rule
+#[utoipa::path(
+    post,
+    path = "/api/swe/projects/{project_id}/rules",
+    tag = "swe",
+    params(("project_id" = String, Path, description = "Project ID")),
+    request_body = CreateRuleRequest,
+    responses((status = 200, description = "Created rule", body = ApiEnvelope))
+)]
 pub async fn create_rule(
     state: web::Data<AppState>,
     path: web::Path<String>,
@@ -951,6 +1067,17 @@ pub async fn create_rule(
 }
 
 /// Update a rule
+#[utoipa::path(
+    put,
+    path = "/api/swe/projects/{project_id}/rules/{id}",
+    tag = "swe",
+    params(
+        ("project_id" = String, Path, description = "Project ID"),
+        ("id" = String, Path, description = "Rule ID")
+    ),
+    request_body = UpdateRuleRequest,
+    responses((status = 200, description = "Updated rule", body = ApiEnvelope))
+)]
 pub async fn update_rule(
     state: web::Data<AppState>,
     path: web::Path<(String, String)>,
@@ -1014,6 +1141,16 @@ pub async fn update_rule(
 }
 
 /// Delete a rule
+#[utoipa::path(
+    delete,
+    path = "/api/swe/projects/{project_id}/rules/{id}",
+    tag = "swe",
+    params(
+        ("project_id" = String, Path, description = "Project ID"),
+        ("id" = String, Path, description = "Rule ID")
+    ),
+    responses((status = 200, description = "Rule deleted", body = ApiEnvelope))
+)]
 pub async fn delete_rule(
     state: web::Data<AppState>,
     path: web::Path<(String, String)>,
@@ -1037,6 +1174,16 @@ pub async fn delete_rule(
 
 /// Get the context to inject into model prompts
 /// This returns all enabled rules and relevant memory for the project
+#[utoipa::path(
+    get,
+    path = "/api/swe/projects/{id}/context",
+    tag = "swe",
+    params(("id" = String, Path, description = "Project ID")),
+    responses(
+        (status = 200, description = "Enabled rules and relevant memory", body = ApiEnvelope),
+        (status = 404, description = "Project not found")
+    )
+)]
 pub async fn get_context(state: web::Data<AppState>, path: web::Path<String>) -> impl Responder {
     let project_id = path.into_inner();
     let db = state.db.lock().unwrap();
@@ -1160,6 +1307,14 @@ pub async fn get_context(state: web::Data<AppState>, path: web::Path<String>) ->
 // =============================================================================
 
 /// Execute a tool (file operations, terminal commands, etc.)
+#[utoipa::path(
+    post,
+    path = "/api/swe/projects/{id}/execute",
+    tag = "swe",
+    params(("id" = String, Path, description = "Project ID")),
+    request_body = ExecuteToolRequest,
+    responses((status = 200, description = "Tool output", body = ApiEnvelope))
+)]
 pub async fn execute_tool(
     state: web::Data<AppState>,
     path: web::Path<String>,
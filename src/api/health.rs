@@ -0,0 +1,454 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Deep health checks
+//!
+//! `GET /api/system/health` exercises each dependency instead of reporting a
+//! fixed "ok": it reads and writes the database, queries the full-text index,
+//! measures free disk space beside the database, asks each configured gateway
+//! provider for its models, and looks for background workers that have
+//! stopped. Each check is `ok`, `degraded`, or `failed`; any failure makes the
+//! whole report `unhealthy` and the response a 503, so load balancers can take
+//! the server out of rotation. `GET /api/health` stays a cheap liveness probe.
+//!
+//! Every provider probe also feeds the gateway's router, and
+//! [`spawn_provider_probes`] repeats them in the background so `auto` routing
+//! learns about outages before a request runs into one.
+
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::docs::ApiEnvelope;
+use super::gateway::{Gateway, Upstream};
+use super::handlers_simple::START_TIME;
+use super::state::AppState;
+
+/// Checks slower than this are reported as degraded
+const SLOW_CHECK: Duration = Duration::from_secs(1);
+
+/// How long a provider has to answer
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often to look whether a reload turned probing back on
+const IDLE_PROBE_RECHECK: Duration = Duration::from_secs(60);
+
+/// Free space below which the disk check fails
+const DISK_FAILED_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Free space below which the disk check is degraded
+const DISK_DEGRADED_BYTES: u64 = 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Degraded,
+    Failed,
+}
+
+/// The result of one check
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub status: CheckStatus,
+    /// What is wrong, or what was measured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub latency_ms: u64,
+}
+
+impl Check {
+    fn ok(message: Option<String>, started: Instant) -> Self {
+        Self::new(CheckStatus::Ok, message, started)
+    }
+
+    fn degraded(message: String, started: Instant) -> Self {
+        Self::new(CheckStatus::Degraded, Some(message), started)
+    }
+
+    fn failed(message: String, started: Instant) -> Self {
+        Self::new(CheckStatus::Failed, Some(message), started)
+    }
+
+    fn new(status: CheckStatus, message: Option<String>, started: Instant) -> Self {
+        let elapsed = started.elapsed();
+        let status = if status == CheckStatus::Ok && elapsed > SLOW_CHECK {
+            CheckStatus::Degraded
+        } else {
+            status
+        };
+        Self {
+            status,
+            message,
+            latency_ms: elapsed.as_millis() as u64,
+        }
+    }
+}
+
+/// A background task that should run for the life of the server
+pub enum Worker {
+    Task(tokio::task::JoinHandle<()>),
+    Thread(std::thread::JoinHandle<()>),
+}
+
+impl Worker {
+    fn is_finished(&self) -> bool {
+        match self {
+            Worker::Task(handle) => handle.is_finished(),
+            Worker::Thread(handle) => handle.is_finished(),
+        }
+    }
+}
+
+impl From<tokio::task::JoinHandle<()>> for Worker {
+    fn from(handle: tokio::task::JoinHandle<()>) -> Self {
+        Worker::Task(handle)
+    }
+}
+
+impl From<std::thread::JoinHandle<()>> for Worker {
+    fn from(handle: std::thread::JoinHandle<()>) -> Self {
+        Worker::Thread(handle)
+    }
+}
+
+/// Background workers the health check watches, registered as app data
+#[derive(Default)]
+pub struct Workers {
+    workers: Mutex<Vec<(&'static str, Worker)>>,
+}
+
+impl Workers {
+    /// Watch a worker; several may share a name
+    pub fn track(&self, name: &'static str, worker: impl Into<Worker>) {
+        self.workers.lock().unwrap().push((name, worker.into()));
+    }
+
+    fn check(&self) -> BTreeMap<String, Check> {
+        let started = Instant::now();
+        let mut checks = BTreeMap::new();
+        for (name, worker) in self.workers.lock().unwrap().iter() {
+            let check = if worker.is_finished() {
+                Check::degraded("Stopped".to_string(), started)
+            } else {
+                Check::ok(None, started)
+            };
+            let entry = checks
+                .entry(format!("worker.{}", name))
+                .or_insert(check.clone());
+            if check.status > entry.status {
+                *entry = check;
+            }
+        }
+        checks
+    }
+}
+
+/// Read and write the database, rolling the write back
+fn check_database(conn: &Connection) -> Check {
+    let started = Instant::now();
+    let result = conn
+        .query_row("SELECT COUNT(*) FROM sessions", [], |row| {
+            row.get::<_, i64>(0)
+        })
+        .and_then(|sessions| {
+            let tx = conn.unchecked_transaction()?;
+            tx.execute_batch(
+                "CREATE TABLE health_probe (checked_at INTEGER);
+                 INSERT INTO health_probe VALUES (strftime('%s', 'now'));",
+            )?;
+            tx.rollback()?;
+            Ok(sessions)
+        });
+    match result {
+        Ok(sessions) => Check::ok(Some(format!("{} sessions", sessions)), started),
+        Err(e) => Check::failed(e.to_string(), started),
+    }
+}
+
+/// Query the full-text index search uses
+fn check_search_index(conn: &Connection) -> Check {
+    let started = Instant::now();
+    let exists = conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE name = 'messages_fts'",
+            [],
+            |_| Ok(()),
+        )
+        .optional();
+    match exists {
+        Ok(Some(())) => match conn.query_row(
+            "SELECT COUNT(*) FROM messages_fts WHERE messages_fts MATCH 'health'",
+            [],
+            |row| row.get::<_, i64>(0),
+        ) {
+            Ok(_) => Check::ok(None, started),
+            Err(e) => Check::failed(e.to_string(), started),
+        },
+        Ok(None) => Check::degraded(
+            "messages_fts is missing; text search scans messages instead (run 'csm harvest rebuild')"
+                .to_string(),
+            started,
+        ),
+        Err(e) => Check::failed(e.to_string(), started),
+    }
+}
+
+/// Free space on the disk holding the database
+fn check_disk(db_path: &Path) -> Check {
+    let started = Instant::now();
+    let dir = db_path
+        .parent()
+        .and_then(|dir| dir.canonicalize().ok())
+        .unwrap_or_else(|| db_path.to_path_buf());
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    let Some(disk) = disks
+        .list()
+        .iter()
+        .filter(|disk| dir.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+    else {
+        return Check::degraded(format!("No disk found for {}", dir.display()), started);
+    };
+
+    let gb = |bytes: u64| bytes as f64 / (1024.0 * 1024.0 * 1024.0);
+    let message = format!(
+        "{:.1} GB free of {:.1} GB",
+        gb(disk.available_space()),
+        gb(disk.total_space())
+    );
+    if disk.available_space() < DISK_FAILED_BYTES {
+        Check::failed(message, started)
+    } else if disk.available_space() < DISK_DEGRADED_BYTES
+        || disk.available_space() < disk.total_space() / 20
+    {
+        Check::degraded(message, started)
+    } else {
+        Check::ok(Some(message), started)
+    }
+}
+
+/// Ask a provider for its models; any answer but a server error counts as
+/// reachable
+async fn check_provider(client: &reqwest::Client, upstream: &Upstream) -> Check {
+    let started = Instant::now();
+    let mut request = client
+        .get(format!("{}/models", upstream.base_url))
+        .timeout(PROVIDER_TIMEOUT);
+    if let Some(key) = &upstream.api_key {
+        request = request.bearer_auth(key);
+    }
+    match request.send().await {
+        Ok(response) if response.status().is_server_error() => {
+            Check::degraded(format!("Answered {}", response.status()), started)
+        }
+        Ok(response)
+            if matches!(
+                response.status(),
+                reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN
+            ) =>
+        {
+            Check::degraded(
+                format!("Rejected the API key ({})", response.status()),
+                started,
+            )
+        }
+        Ok(_) => Check::ok(None, started),
+        Err(e) => Check::degraded(format!("Unreachable: {}", e), started),
+    }
+}
+
+/// Probe every configured gateway provider at once, and tell the router how
+/// each did
+pub async fn check_providers(gateway: &Gateway) -> BTreeMap<String, Check> {
+    let upstreams = gateway.configured_upstreams();
+    let checks = futures_util::future::join_all(
+        upstreams
+            .iter()
+            .map(|upstream| check_provider(gateway.client(), upstream)),
+    )
+    .await;
+    let checks: BTreeMap<String, Check> = upstreams
+        .into_iter()
+        .map(|upstream| upstream.provider)
+        .zip(checks)
+        .collect();
+    for (provider, check) in &checks {
+        // A slow answer is degraded without a message; the router judges
+        // latency itself
+        let result = match &check.message {
+            Some(message) if check.status != CheckStatus::Ok => Err(message.clone()),
+            _ => Ok(check.latency_ms),
+        };
+        gateway.record_health(provider, result);
+    }
+    checks
+}
+
+/// Probe the gateway's providers every `probe_interval_secs`, for as long as
+/// the server runs
+pub fn spawn_provider_probes(gateway: web::Data<Gateway>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            // Read each round, so a reload can change or stop probing
+            match gateway.probe_interval() {
+                Some(interval) => {
+                    check_providers(&gateway).await;
+                    tokio::time::sleep(interval).await;
+                }
+                None => tokio::time::sleep(IDLE_PROBE_RECHECK).await,
+            }
+        }
+    })
+}
+
+/// Worst status of all checks, as reported for the whole server
+fn overall(checks: &BTreeMap<String, Check>) -> &'static str {
+    match checks.values().map(|check| check.status).max() {
+        Some(CheckStatus::Failed) => "unhealthy",
+        Some(CheckStatus::Degraded) => "degraded",
+        _ => "healthy",
+    }
+}
+
+/// Check the database, search index, disk, providers, and background workers
+#[utoipa::path(
+    get,
+    path = "/api/system/health",
+    tag = "system",
+    responses(
+        (status = 200, description = "Healthy or degraded, with each check's status", body = ApiEnvelope),
+        (status = 503, description = "A check failed")
+    )
+)]
+pub async fn get_system_health(
+    state: web::Data<AppState>,
+    gateway: web::Data<Gateway>,
+    workers: web::Data<Workers>,
+) -> HttpResponse {
+    let uptime = START_TIME.get_or_init(Instant::now).elapsed().as_secs();
+
+    // The database checks get a connection of their own so a slow write
+    // probe does not hold up other requests
+    let db_path = state.db_path.clone();
+    let local = web::block(move || {
+        let started = Instant::now();
+        let mut checks = BTreeMap::new();
+        match Connection::open(&db_path) {
+            Ok(conn) => {
+                let _ = conn.busy_timeout(Duration::from_secs(2));
+                checks.insert("database".to_string(), check_database(&conn));
+                checks.insert("search_index".to_string(), check_search_index(&conn));
+            }
+            Err(e) => {
+                checks.insert(
+                    "database".to_string(),
+                    Check::failed(e.to_string(), started),
+                );
+            }
+        }
+        checks.insert("disk".to_string(), check_disk(&db_path));
+        checks
+    });
+    let (local, providers) = futures_util::join!(local, check_providers(&gateway));
+
+    let mut checks = match local {
+        Ok(checks) => checks,
+        Err(e) => BTreeMap::from([(
+            "database".to_string(),
+            Check::failed(e.to_string(), Instant::now()),
+        )]),
+    };
+    checks.extend(
+        providers
+            .into_iter()
+            .map(|(provider, check)| (format!("provider.{}", provider), check)),
+    );
+    checks.extend(workers.check());
+
+    let status = overall(&checks);
+    let code = if status == "unhealthy" {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    HttpResponse::build(code).json(serde_json::json!({
+        "success": true,
+        "data": {
+            "status": status,
+            "version": env!("CARGO_PKG_VERSION"),
+            "uptime": uptime,
+            "checks": checks,
+        },
+    }))
+}
+
+/// Reachability of each configured gateway provider
+#[utoipa::path(
+    get,
+    path = "/api/system/providers/health",
+    tag = "system",
+    responses((status = 200, description = "Reachability of each provider", body = ApiEnvelope))
+)]
+pub async fn get_provider_health(gateway: web::Data<Gateway>) -> HttpResponse {
+    let checked_at = chrono::Utc::now().timestamp_millis();
+    let providers: Vec<_> = check_providers(&gateway)
+        .await
+        .into_iter()
+        .map(|(provider, check)| {
+            serde_json::json!({
+                "provider": provider,
+                "status": if check.status == CheckStatus::Ok { "connected" } else { "disconnected" },
+                "latency": check.latency_ms,
+                "message": check.message,
+                "lastCheck": checked_at,
+            })
+        })
+        .collect();
+    HttpResponse::Ok().json(serde_json::json!({ "success": true, "data": providers }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_database_checks_and_workers() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE sessions (id TEXT PRIMARY KEY);")
+            .unwrap();
+        assert_eq!(check_database(&conn).status, CheckStatus::Ok);
+        // The write probe leaves nothing behind
+        assert!(conn
+            .query_row("SELECT 1 FROM health_probe", [], |_| Ok(()))
+            .is_err());
+        assert_eq!(check_search_index(&conn).status, CheckStatus::Degraded);
+
+        conn.execute_batch("CREATE VIRTUAL TABLE messages_fts USING fts5(content_raw);")
+            .unwrap();
+        assert_eq!(check_search_index(&conn).status, CheckStatus::Ok);
+
+        let workers = Workers::default();
+        let (stop, stopped) = std::sync::mpsc::channel::<()>();
+        workers.track(
+            "watcher",
+            std::thread::spawn(move || {
+                let _ = stopped.recv();
+            }),
+        );
+        let finished = std::thread::spawn(|| {});
+        while !finished.is_finished() {
+            std::thread::yield_now();
+        }
+        workers.track("bridge", finished);
+        let checks = workers.check();
+        assert_eq!(checks["worker.watcher"].status, CheckStatus::Ok);
+        assert_eq!(checks["worker.bridge"].status, CheckStatus::Degraded);
+        assert_eq!(overall(&checks), "degraded");
+        drop(stop);
+    }
+}
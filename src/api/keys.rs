@@ -0,0 +1,347 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! API keys for the API server
+//!
+//! Keys are issued with `csm api keys create` and sent in the `X-API-Key`
+//! header or as an `Authorization: Bearer` token. Only a SHA-256 hash of each
+//! key is stored, so a lost key has to be revoked and replaced. A key issued
+//! to a user with `--user` sees only that user's sessions; other keys are
+//! admin keys. Each key also has a role (`--role`) limiting what it may do,
+//! by default `member` for user keys and `admin` for admin keys.
+
+use anyhow::{Context, Result};
+use colored::*;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+use crate::commands::add_column_if_missing;
+use chasm::teams::Role;
+
+/// Prefix that distinguishes API keys from JWTs
+pub const KEY_PREFIX: &str = "csm_";
+
+/// Characters of the key shown in listings
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+/// An issued API key (never the key itself)
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    pub prefix: String,
+    pub created_at: i64,
+    pub last_used_at: Option<i64>,
+    pub revoked_at: Option<i64>,
+    /// User the key acts for; keys without one are admin keys
+    pub user_id: Option<String>,
+    pub role: Role,
+}
+
+/// Role of a key created without `--role`
+pub fn default_key_role(user_id: Option<&str>) -> Role {
+    match user_id {
+        Some(_) => Role::Member,
+        None => Role::Admin,
+    }
+}
+
+/// Create the API key table if it does not exist
+pub fn init_api_key_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS api_keys (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            key_hash TEXT NOT NULL UNIQUE,
+            prefix TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            last_used_at INTEGER,
+            revoked_at INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_api_keys_hash ON api_keys(key_hash);",
+    )?;
+    add_column_if_missing(conn, "api_keys", "user_id", "TEXT")?;
+    add_column_if_missing(conn, "api_keys", "role", "TEXT")
+}
+
+fn hash_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+/// Issue a new key, returning its record and the key itself
+pub fn create_key(
+    conn: &Connection,
+    name: &str,
+    user_id: Option<&str>,
+    role: Role,
+) -> rusqlite::Result<(ApiKey, String)> {
+    let secret: [u8; 32] = rand::random();
+    let key = format!(
+        "{}{}",
+        KEY_PREFIX,
+        secret
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+    );
+    let record = ApiKey {
+        id: uuid::Uuid::new_v4().simple().to_string()[..12].to_string(),
+        name: name.to_string(),
+        prefix: key[..DISPLAY_PREFIX_LEN].to_string(),
+        created_at: chrono::Utc::now().timestamp(),
+        last_used_at: None,
+        revoked_at: None,
+        user_id: user_id.map(String::from),
+        role,
+    };
+    conn.execute(
+        "INSERT INTO api_keys (id, name, key_hash, prefix, created_at, user_id, role)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            record.id,
+            record.name,
+            hash_key(&key),
+            record.prefix,
+            record.created_at,
+            record.user_id,
+            record.role.as_str()
+        ],
+    )?;
+    Ok((record, key))
+}
+
+fn row_to_key(row: &rusqlite::Row) -> rusqlite::Result<ApiKey> {
+    let user_id: Option<String> = row.get(6)?;
+    // Keys from before roles existed get the default for their kind
+    let role = row
+        .get::<_, Option<String>>(7)?
+        .and_then(|role| role.parse().ok())
+        .unwrap_or_else(|| default_key_role(user_id.as_deref()));
+    Ok(ApiKey {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        prefix: row.get(2)?,
+        created_at: row.get(3)?,
+        last_used_at: row.get(4)?,
+        revoked_at: row.get(5)?,
+        user_id,
+        role,
+    })
+}
+
+/// All keys, newest first
+pub fn list_keys(conn: &Connection) -> rusqlite::Result<Vec<ApiKey>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, prefix, created_at, last_used_at, revoked_at, user_id, role
+         FROM api_keys ORDER BY created_at DESC",
+    )?;
+    let keys = stmt.query_map([], row_to_key)?.collect();
+    keys
+}
+
+/// Number of keys that have not been revoked
+pub fn active_key_count(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM api_keys WHERE revoked_at IS NULL",
+        [],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|n| n as usize)
+}
+
+/// Revoke a key by ID or display prefix, returning how many were revoked
+pub fn revoke_key(conn: &Connection, id_or_prefix: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE api_keys SET revoked_at = ?2
+         WHERE (id = ?1 OR prefix = ?1) AND revoked_at IS NULL",
+        params![id_or_prefix, chrono::Utc::now().timestamp()],
+    )
+}
+
+/// Look up an active key and record that it was used
+pub fn verify_key(conn: &Connection, key: &str) -> rusqlite::Result<Option<ApiKey>> {
+    let record = conn
+        .query_row(
+            "SELECT id, name, prefix, created_at, last_used_at, revoked_at, user_id, role
+             FROM api_keys WHERE key_hash = ?1 AND revoked_at IS NULL",
+            params![hash_key(key)],
+            row_to_key,
+        )
+        .optional()?;
+    if let Some(record) = &record {
+        conn.execute(
+            "UPDATE api_keys SET last_used_at = ?2 WHERE id = ?1",
+            params![record.id, chrono::Utc::now().timestamp()],
+        )?;
+    }
+    Ok(record)
+}
+
+// =============================================================================
+// CLI
+// =============================================================================
+
+fn open(database: &Path) -> Result<Connection> {
+    if let Some(parent) = database.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let conn = Connection::open(database)
+        .with_context(|| format!("Failed to open {}", database.display()))?;
+    init_api_key_table(&conn)?;
+    Ok(conn)
+}
+
+fn format_time(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// The ID of the user with this email or ID
+pub(super) fn find_user(conn: &Connection, user: &str) -> Result<String> {
+    super::auth::init_auth_tables(conn)?;
+    conn.query_row(
+        "SELECT id FROM users WHERE email = ?1 OR id = ?1",
+        params![user],
+        |row| row.get(0),
+    )
+    .optional()?
+    .with_context(|| format!("No user with email or ID '{}'", user))
+}
+
+/// `csm api keys create`
+pub fn keys_create(
+    database: &Path,
+    name: &str,
+    user: Option<&str>,
+    role: Option<&str>,
+) -> Result<()> {
+    let conn = open(database)?;
+    let user_id = user.map(|user| find_user(&conn, user)).transpose()?;
+    let role = match role {
+        Some(role) => role.parse()?,
+        None => default_key_role(user_id.as_deref()),
+    };
+    let (record, key) = create_key(&conn, name, user_id.as_deref(), role)?;
+
+    println!(
+        "{} Created API key '{}' ({})",
+        "[OK]".green().bold(),
+        record.name,
+        record.id
+    );
+    match user {
+        Some(user) => println!("   Sees only sessions owned by {}", user),
+        None => println!("   Admin key: sees every user's sessions"),
+    }
+    println!("   Role: {}", record.role);
+    println!();
+    println!("   {}", key.bold());
+    println!();
+    println!(
+        "{} Store this key now; it cannot be shown again.",
+        "[!]".yellow()
+    );
+    println!("    Send it as 'X-API-Key: <key>' or 'Authorization: Bearer <key>'.");
+    Ok(())
+}
+
+/// `csm api keys list`
+pub fn keys_list(database: &Path, json: bool) -> Result<()> {
+    let conn = open(database)?;
+    let keys = list_keys(&conn)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&keys)?);
+        return Ok(());
+    }
+    if keys.is_empty() {
+        println!(
+            "{} No API keys. Create one with 'csm api keys create <name>'.",
+            "[*]".blue()
+        );
+        return Ok(());
+    }
+
+    for key in &keys {
+        let status = match key.revoked_at {
+            Some(at) => format!("revoked {}", format_time(at)).red().to_string(),
+            None => "active".green().to_string(),
+        };
+        let owner = match &key.user_id {
+            Some(user_id) => format!("user {}", user_id),
+            None => "admin".to_string(),
+        };
+        println!(
+            "   {}  {}  {}...  {}  {}  {}",
+            key.id.cyan(),
+            key.name.bold(),
+            key.prefix.dimmed(),
+            owner.dimmed(),
+            key.role,
+            status
+        );
+        let last_used = key
+            .last_used_at
+            .map(format_time)
+            .unwrap_or_else(|| "never".to_string());
+        println!(
+            "      {} created {}, last used {}",
+            "`".dimmed(),
+            format_time(key.created_at),
+            last_used
+        );
+    }
+    Ok(())
+}
+
+/// `csm api keys revoke`
+pub fn keys_revoke(database: &Path, id_or_prefix: &str) -> Result<()> {
+    let conn = open(database)?;
+    match revoke_key(&conn, id_or_prefix)? {
+        0 => anyhow::bail!("No active API key matches '{}'", id_or_prefix),
+        n => println!(
+            "{} Revoked {} API key{}",
+            "[OK]".green().bold(),
+            n,
+            if n == 1 { "" } else { "s" }
+        ),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_lifecycle() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_api_key_table(&conn).unwrap();
+
+        let (record, key) = create_key(&conn, "ci", None, Role::Admin).unwrap();
+        assert!(key.starts_with(KEY_PREFIX));
+        assert!(key.starts_with(&record.prefix));
+        assert_eq!(active_key_count(&conn).unwrap(), 1);
+
+        let verified = verify_key(&conn, &key).unwrap().unwrap();
+        assert_eq!(verified.id, record.id);
+        assert!(verify_key(&conn, "csm_wrong").unwrap().is_none());
+
+        assert_eq!(revoke_key(&conn, &record.prefix).unwrap(), 1);
+        assert!(verify_key(&conn, &key).unwrap().is_none());
+        assert_eq!(active_key_count(&conn).unwrap(), 0);
+        assert_eq!(revoke_key(&conn, &record.id).unwrap(), 0);
+
+        let (_, key) = create_key(&conn, "alice's laptop", Some("user-1"), Role::Viewer).unwrap();
+        let verified = verify_key(&conn, &key).unwrap().unwrap();
+        assert_eq!(verified.user_id.as_deref(), Some("user-1"));
+        assert_eq!(verified.role, Role::Viewer);
+
+        // Keys from before roles existed get the default for their kind
+        conn.execute("UPDATE api_keys SET role = NULL", []).unwrap();
+        let verified = verify_key(&conn, &key).unwrap().unwrap();
+        assert_eq!(verified.role, Role::Member);
+    }
+}
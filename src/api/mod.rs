@@ -5,25 +5,44 @@
 //! Provides a REST API for the web frontend and mobile app to interact with CSM.
 //! Uses Actix-web for the HTTP server.
 
+mod access;
 #[cfg(feature = "enterprise")]
 mod audit;
+pub mod audit_log;
 mod auth;
+mod bulk;
 pub mod caching;
+mod conditional;
+pub mod daemon;
 mod docs;
+mod gateway;
 mod graphql;
+#[cfg(feature = "grpc")]
+mod grpc;
 mod handlers_simple;
 mod handlers_swe;
+mod health;
+pub mod keys;
+mod pagination;
+mod rate_limit;
+pub mod rbac;
 #[cfg(feature = "enterprise")]
 mod retention;
 pub mod sdk;
+pub mod service;
 #[cfg(feature = "enterprise")]
 mod sso;
 mod recording;
+mod reload;
+mod remote;
 mod state;
 mod sync;
+mod teams;
+mod tls;
 mod webhooks;
 mod websocket;
 
+pub use access::AnonymousAccess;
 pub use recording::{
     configure_recording_routes, create_recording_state,
 };
@@ -32,18 +51,22 @@ pub use audit::{
     configure_audit_routes, AuditAction, AuditCategory, AuditEvent, AuditEventBuilder, AuditService,
 };
 pub use auth::configure_auth_routes;
+pub use gateway::configure_gateway_routes;
+pub use rate_limit::RateLimitConfig;
 #[cfg(feature = "enterprise")]
 pub use retention::{configure_retention_routes, RetentionPolicy, RetentionService};
 #[cfg(feature = "enterprise")]
 pub use sso::{configure_sso_routes, SamlIdpConfig, SsoService};
 pub use state::AppState;
-pub use sync::{configure_sync_routes, create_sync_state};
-pub use websocket::{configure_websocket_routes, WebSocketState};
+pub use sync::{configure_sync_routes, create_sync_state, spawn_session_watcher};
+pub use tls::TlsSource;
+pub use webhooks::configure_webhook_routes;
+pub use websocket::{configure_websocket_routes, spawn_sync_bridge, WebSocketState};
 
 use actix_cors::Cors;
 use actix_web::{middleware, web, App, HttpServer};
 use anyhow::Result;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::database::ChatDatabase;
 
@@ -54,6 +77,14 @@ pub struct ServerConfig {
     pub port: u16,
     pub database_path: String,
     pub cors_origins: Vec<String>,
+    pub anonymous: AnonymousAccess,
+    pub rate_limit: RateLimitConfig,
+    /// Serve HTTPS instead of HTTP
+    pub tls: Option<TlsSource>,
+    /// Seconds to let in-flight requests finish on shutdown
+    pub shutdown_timeout: u64,
+    /// Also serve the gRPC interface on this port
+    pub grpc_port: Option<u16>,
 }
 
 impl Default for ServerConfig {
@@ -74,6 +105,11 @@ impl Default for ServerConfig {
                 "http://localhost:19006".to_string(), // Expo web alt
                 "http://127.0.0.1:19006".to_string(), // Expo web alt
             ],
+            anonymous: AnonymousAccess::default(),
+            rate_limit: RateLimitConfig::default(),
+            tls: None,
+            shutdown_timeout: 30,
+            grpc_port: None,
         }
     }
 }
@@ -88,13 +124,61 @@ fn configure_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api")
             .route("/health", web::get().to(health_check))
+            .route("/openapi.json", web::get().to(docs::openapi_json))
+            .route("/openapi.yaml", web::get().to(docs::openapi_yaml))
+            .route("/docs", web::get().to(docs::swagger_ui))
+            .route("/graphql", web::post().to(graphql::graphql_post))
+            .route("/graphql", web::get().to(graphql::graphql_get))
+            .route(
+                "/graphql/playground",
+                web::get().to(graphql::graphql_playground),
+            )
+            .route("/graphql/sdl", web::get().to(graphql::graphql_sdl))
             .route("/workspaces", web::get().to(list_workspaces))
             .route("/workspaces/{id}", web::get().to(get_workspace))
             .route("/sessions", web::get().to(list_sessions))
+            .route("/sessions", web::post().to(create_session))
+            .route("/sessions/bulk", web::post().to(bulk::start_bulk_job))
+            .route("/sessions/bulk/{id}", web::get().to(bulk::get_bulk_job))
+            .route(
+                "/sessions/bulk/{id}/export",
+                web::get().to(bulk::download_bulk_export),
+            )
             .route("/sessions/search", web::get().to(search_sessions))
             .route("/sessions/{id}", web::get().to(get_session))
+            .route("/sessions/{id}", web::put().to(update_session))
+            .route("/sessions/{id}", web::delete().to(delete_session))
+            .route(
+                "/sessions/{id}/messages",
+                web::get().to(list_session_messages),
+            )
+            .route(
+                "/sessions/{id}/messages",
+                web::post().to(append_session_message),
+            )
+            .route("/sessions/{id}/restore", web::post().to(restore_session))
+            .route("/sessions/{id}/diff", web::get().to(diff_session))
+            .route("/sessions/{id}/team", web::put().to(teams::share_session))
+            // Team routes
+            .route("/teams", web::get().to(teams::list_teams))
+            .route("/teams", web::post().to(teams::create_team))
+            .route("/teams/{id}", web::get().to(teams::get_team))
+            .route("/teams/{id}", web::delete().to(teams::delete_team))
+            .route(
+                "/teams/{id}/members/{user}",
+                web::put().to(teams::set_member),
+            )
+            .route(
+                "/teams/{id}/members/{user}",
+                web::delete().to(teams::remove_member),
+            )
+            .route(
+                "/teams/{id}/activity",
+                web::get().to(teams::get_team_activity),
+            )
             .route("/providers", web::get().to(list_providers))
             .route("/stats", web::get().to(get_stats))
+            .route("/audit", web::get().to(audit_log::list_audit_log))
             .route("/stats/overview", web::get().to(get_stats))
             // Agent routes
             .route("/agents", web::get().to(list_agents))
@@ -102,6 +186,32 @@ fn configure_routes(cfg: &mut web::ServiceConfig) {
             .route("/agents/{id}", web::get().to(get_agent))
             .route("/agents/{id}", web::put().to(update_agent))
             .route("/agents/{id}", web::delete().to(delete_agent))
+            .route("/agents/{name}/run", web::post().to(run_agent))
+            // Remote agent tasks
+            .route("/v1/tasks", web::get().to(remote::list_tasks))
+            .route("/v1/tasks", web::post().to(remote::submit_task))
+            .route("/v1/tasks/{id}", web::get().to(remote::get_task))
+            .route("/v1/tasks/{id}/events", web::get().to(remote::task_events))
+            .route("/v1/tasks/{id}/cancel", web::post().to(remote::cancel_task))
+            .route(
+                "/v1/tasks/{id}/artifacts/{name}",
+                web::get().to(remote::download_artifact),
+            )
+            .route(
+                "/v1/tasks/{id}/progress",
+                web::post().to(remote::report_progress),
+            )
+            .route("/v1/tasks/{id}/step", web::post().to(remote::report_step))
+            .route("/v1/tasks/{id}/log", web::post().to(remote::report_log))
+            .route(
+                "/v1/tasks/{id}/complete",
+                web::post().to(remote::report_completed),
+            )
+            .route("/v1/tasks/{id}/fail", web::post().to(remote::report_failed))
+            .route(
+                "/v1/nodes/{id}/heartbeat",
+                web::post().to(remote::node_heartbeat),
+            )
             // Swarm routes
             .route("/swarms", web::get().to(list_swarms))
             .route("/swarms", web::post().to(create_swarm))
@@ -115,11 +225,17 @@ fn configure_routes(cfg: &mut web::ServiceConfig) {
             .route("/settings/accounts/{id}", web::delete().to(delete_account))
             // System routes
             .route("/system/info", web::get().to(get_system_info))
-            .route("/system/health", web::get().to(get_system_health))
+            .route("/admin/reload", web::post().to(reload::reload_config))
+            .route("/system/health", web::get().to(health::get_system_health))
+            .route(
+                "/system/rate-limits",
+                web::get().to(rate_limit::rate_limit_metrics),
+            )
             .route(
                 "/system/providers/health",
-                web::get().to(get_provider_health),
+                web::get().to(health::get_provider_health),
             )
+            .route("/system/routing", web::get().to(gateway::routing_status))
             // MCP routes
             .route("/mcp/tools", web::get().to(list_mcp_tools))
             .route("/mcp/call", web::post().to(call_mcp_tool))
@@ -194,6 +310,14 @@ fn configure_routes(cfg: &mut web::ServiceConfig) {
 
 /// Start the API server
 pub async fn start_server(config: ServerConfig) -> Result<()> {
+    start_server_until(config, daemon::shutdown_signal()).await
+}
+
+/// Start the API server and stop it gracefully once `shutdown` resolves
+pub async fn start_server_until(
+    config: ServerConfig,
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+) -> Result<()> {
     // Ensure database directory exists
     let db_path = PathBuf::from(&config.database_path);
     if let Some(parent) = db_path.parent() {
@@ -202,6 +326,7 @@ pub async fn start_server(config: ServerConfig) -> Result<()> {
 
     // Open database
     let db = ChatDatabase::open(&db_path)?;
+    crate::commands::ensure_session_owner_column(&db.conn)?;
 
     // Initialize SWE tables
     {
@@ -212,27 +337,145 @@ pub async fn start_server(config: ServerConfig) -> Result<()> {
     }
 
     // Initialize Auth tables
-    {
+    let api_keys = {
         let conn = rusqlite::Connection::open(&db_path)?;
         if let Err(e) = auth::init_auth_tables(&conn) {
             eprintln!("[WARN] Failed to initialize Auth tables: {}", e);
         }
-    }
+        keys::init_api_key_table(&conn)?;
+        webhooks::init_webhook_tables(&conn)?;
+        audit_log::init_audit_table(&conn)?;
+        teams::init_team_tables(&conn)?;
+        keys::active_key_count(&conn)?
+    };
 
     let state = web::Data::new(AppState::new(db, db_path));
+    let graphql_schema = web::Data::new(graphql::create_schema(state.clone()));
+    let workers = web::Data::new(health::Workers::default());
     let sync_state = web::Data::new(create_sync_state());
+    match spawn_session_watcher(sync_state.get_ref().clone()) {
+        Ok(watcher) => workers.track("session_watcher", watcher),
+        Err(e) => eprintln!("[WARN] Session file watcher disabled: {}", e),
+    }
     let ws_state = web::Data::new(WebSocketState::new());
+    if let Some(bridge) = spawn_sync_bridge(sync_state.get_ref().clone(), ws_state.clone()) {
+        workers.track("sync_bridge", bridge);
+    }
+    let webhooks = web::Data::new(webhooks::Webhooks::new(state.clone()));
+    let dispatcher =
+        webhooks::spawn_dispatcher(webhooks.clone(), sync_state.get_ref().clone(), &ws_state);
+    for task in dispatcher {
+        workers.track("webhooks", task);
+    }
+    let bulk_jobs = web::Data::new(bulk::BulkJobs::default());
+    let remote_tasks = web::Data::new(
+        remote::RemoteTasks::new(&format!("{}:{}", config.host, config.port)).await,
+    );
     let recording_state = web::Data::new(create_recording_state());
-    let cors_origins = config.cors_origins.clone();
+    let access_policy = web::Data::new(access::AccessPolicy {
+        anonymous: config.anonymous,
+    });
+    let rate_limiter = web::Data::new(rate_limit::RateLimiter::new(config.rate_limit));
+    let gateway = web::Data::new(gateway::Gateway::new(Vec::new()));
+    let live_settings = web::Data::new(reload::LiveSettings::new(config.cors_origins.clone()));
+    reload::apply(&live_settings, &gateway, crate::config::current())?;
+    #[cfg(unix)]
+    if let Some(listener) = reload::spawn_hangup_listener(live_settings.clone(), gateway.clone()) {
+        workers.track("config_reload", listener);
+    }
+    workers.track(
+        "provider_probes",
+        health::spawn_provider_probes(gateway.clone()),
+    );
+    let max_body_bytes = config.rate_limit.max_body_bytes;
+    let tls = config
+        .tls
+        .as_ref()
+        .map(|source| tls::load(source, Path::new(&config.database_path), &config.host))
+        .transpose()?;
+    let scheme = if tls.is_some() { "https" } else { "http" };
+
+    #[cfg(not(feature = "grpc"))]
+    if config.grpc_port.is_some() {
+        anyhow::bail!("This build has no gRPC support; rebuild chasm with --features grpc");
+    }
+    #[cfg(feature = "grpc")]
+    let grpc_incoming = match config.grpc_port {
+        Some(_) if tls.is_some() => anyhow::bail!(
+            "gRPC is served without TLS; leave out --grpc-port or terminate TLS for it in a proxy"
+        ),
+        Some(port) => {
+            let addr = std::net::ToSocketAddrs::to_socket_addrs(&(config.host.as_str(), port))?
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("Cannot resolve {}", config.host))?;
+            Some(grpc::bind(addr)?)
+        }
+        None => None,
+    };
+    #[cfg(feature = "grpc")]
+    let grpc_state = state.clone();
 
     println!("[*] CSM API Server starting...");
-    println!("   Address: http://{}:{}", config.host, config.port);
+    println!("   Address: {}://{}:{}", scheme, config.host, config.port);
+    if let Some(port) = config.grpc_port {
+        println!(
+            "   gRPC: {}:{} (chasm.v1.Sessions, see proto/chasm.proto)",
+            config.host, port
+        );
+    }
     println!("   Database: {}", config.database_path);
+    match &tls {
+        Some(tls) => {
+            println!("   TLS certificate: {}", tls.cert_path.display());
+            println!("   SHA-256 fingerprint: {}", tls.fingerprint);
+            if tls.generated {
+                println!("   [*] Created a self-signed certificate; clients must trust it or pin its fingerprint");
+            }
+        }
+        None if !matches!(config.host.as_str(), "127.0.0.1" | "localhost" | "::1") => {
+            println!("   [!] Serving plain HTTP: credentials and chat content are not encrypted");
+            println!("       Use --tls-cert/--tls-key or --tls-self-signed to serve HTTPS");
+        }
+        None => {}
+    }
+    println!();
+    println!("[*] Authentication:");
+    println!("   API keys: {} active", api_keys);
+    println!(
+        "   JWT tokens: {}",
+        if auth::jwt_secret().is_some() {
+            "enabled"
+        } else {
+            "disabled (set JWT_SECRET to enable /auth/login)"
+        }
+    );
+    println!("   Anonymous access: {}", config.anonymous.as_str());
+    if config.anonymous != AnonymousAccess::Allow && api_keys == 0 {
+        println!("   [!] No API keys exist; create one with 'csm api keys create <name>'");
+    }
+    if config.rate_limit.enabled() {
+        println!(
+            "   Rate limit: {} requests/minute per client (burst {})",
+            config.rate_limit.requests_per_minute, config.rate_limit.burst
+        );
+    } else {
+        println!("   Rate limit: disabled");
+    }
+    println!("   Max request body: {} MB", max_body_bytes / (1024 * 1024));
+    println!();
+    println!("[*] API documentation:");
+    println!("   GET /api/docs           - Interactive API reference");
+    println!("   GET /api/openapi.json   - OpenAPI specification");
+    println!("   POST /api/graphql       - GraphQL queries");
+    println!("   GET /api/graphql/playground - GraphQL playground");
     println!();
     println!("[*] Mobile app endpoints:");
     println!("   GET /api/workspaces     - List workspaces");
     println!("   GET /api/sessions       - List sessions");
     println!("   GET /api/sessions/:id   - Get session details");
+    println!("   POST /api/sessions      - Create session");
+    println!("   POST /api/sessions/:id/messages - Append message");
+    println!("   POST /api/sessions/bulk - Tag, delete, export, or merge many sessions");
     println!("   GET /api/stats          - Database statistics");
     println!();
     println!("[*] SWE Mode endpoints:");
@@ -251,47 +494,140 @@ pub async fn start_server(config: ServerConfig) -> Result<()> {
     println!("   GET /sync/delta?from=N  - Get changes since version N");
     println!("   POST /sync/event        - Push a sync event");
     println!("   GET /sync/snapshot      - Get full data snapshot");
-    println!("   GET /sync/subscribe     - SSE stream for real-time updates");
-    println!("   GET /ws                 - WebSocket for bidirectional updates");
+    println!("   GET /sync/subscribe     - SSE stream for real-time updates (includes session file changes)");
+    println!();
+    println!("[*] WebSocket endpoint:");
+    println!("   GET /ws                 - Channels: sessions, session:<id>, harvest, agent:<id>");
+    println!("                             Commands: sync_request, harvest_start, agent_command");
+    println!();
+    println!("[*] OpenAI-compatible gateway:");
+    println!("   POST /v1/chat/completions - Routed, recorded chat completions");
+    println!("   GET /v1/models          - Models available to model \"auto\"");
+    let providers = gateway.configured_providers();
+    if providers.is_empty() {
+        println!("   [!] No providers configured; set OPENAI_API_KEY, OLLAMA_HOST, ... or use provider/model");
+    } else {
+        println!("   Routing between: {}", providers.join(", "));
+    }
+    println!("   GET /api/system/routing - Provider health and latency the router sees");
+    println!();
+    println!("[*] Remote agent tasks:");
+    println!("   POST /api/v1/tasks      - Run an agent in the background");
+    println!("   GET /api/v1/tasks/{{id}}/events - Follow a task's log");
+    println!("   GET /api/v1/tasks/{{id}}/artifacts/{{name}} - Download a file it wrote");
+    println!();
+    println!("[*] Audit log:");
+    println!("   GET /api/audit          - Mutating requests with before/after summaries");
+    println!();
+    println!("[*] Configuration:");
+    println!("   POST /api/admin/reload  - Reload CORS origins, providers, and routing from the config file");
+    if cfg!(unix) {
+        println!("   (also on SIGHUP: kill -HUP {})", std::process::id());
+    }
+    println!();
+    println!("[*] Webhooks:");
+    println!("   GET/POST /api/webhooks  - List or register webhooks");
+    println!("   GET /api/webhooks/{{id}}/deliveries - Delivery log");
     println!();
     println!("Press Ctrl+C to stop the server...");
     println!();
 
     eprintln!("[DEBUG] Creating HttpServer...");
     let server = HttpServer::new(move || {
-        let origins = cors_origins.clone();
+        let origins = live_settings.clone();
         let cors = Cors::default()
             .allowed_origin_fn(move |origin, _req_head| {
-                let origin_str = origin.to_str().unwrap_or("");
-                origins.iter().any(|allowed| allowed == origin_str)
-                    || origin_str.starts_with("http://localhost:")
-                    || origin_str.starts_with("http://127.0.0.1:")
-                    || origin_str.starts_with("exp://")
+                origins.allows_origin(origin.to_str().unwrap_or(""))
             })
             .allowed_methods(vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"])
-            .allowed_headers(vec!["Content-Type", "Authorization", "Accept"])
+            .allowed_headers(vec![
+                "Content-Type",
+                "Authorization",
+                "X-API-Key",
+                "Accept",
+                "If-None-Match",
+            ])
+            .expose_headers(vec!["ETag"])
             .supports_credentials()
             .max_age(3600);
 
         App::new()
             .app_data(state.clone())
+            .app_data(graphql_schema.clone())
             .app_data(sync_state.clone())
             .app_data(ws_state.clone())
             .app_data(recording_state.clone())
+            .app_data(access_policy.clone())
+            .app_data(rate_limiter.clone())
+            .app_data(gateway.clone())
+            .app_data(live_settings.clone())
+            .app_data(workers.clone())
+            .app_data(webhooks.clone())
+            .app_data(bulk_jobs.clone())
+            .app_data(remote_tasks.clone())
+            .app_data(web::JsonConfig::default().limit(max_body_bytes))
+            .app_data(web::PayloadConfig::new(max_body_bytes))
+            .wrap(middleware::from_fn(conditional::etags))
+            .wrap(middleware::from_fn(audit_log::record))
+            .wrap(middleware::from_fn(rate_limit::limit_credential))
+            .wrap(middleware::from_fn(access::require_auth))
+            .wrap(middleware::from_fn(rate_limit::limit))
             .wrap(cors)
-            .wrap(middleware::Logger::default())
+            .wrap(middleware::Compress::default())
+            // The default format, with `%r` replaced by a request line
+            // that leaves out query-string access tokens
+            .wrap(
+                middleware::Logger::new(
+                    r#"%a "%{request}xi" %s %b "%{Referer}i" "%{User-Agent}i" %T"#,
+                )
+                .custom_request_replace("request", access::logged_request_line),
+            )
+            .configure(configure_webhook_routes)
             .configure(configure_routes)
             .configure(configure_sync_routes)
             .configure(configure_auth_routes)
             .configure(configure_recording_routes)
+            .configure(configure_gateway_routes)
             .configure(|cfg| configure_websocket_routes(cfg, ws_state.clone()))
     });
 
     eprintln!("[DEBUG] Binding to {}:{}...", config.host, config.port);
-    let server = server.bind((config.host.as_str(), config.port))?;
+    let server = match tls {
+        Some(tls) => server.bind_rustls_0_23((config.host.as_str(), config.port), tls.config)?,
+        None => server.bind((config.host.as_str(), config.port))?,
+    };
 
     eprintln!("[DEBUG] Starting server...");
-    server.run().await?;
+    let server = server
+        .disable_signals()
+        .shutdown_timeout(config.shutdown_timeout)
+        .run();
+    let handle = server.handle();
+    let shutdown_timeout = config.shutdown_timeout;
+    tokio::spawn(async move {
+        shutdown.await;
+        println!(
+            "[*] Shutting down; waiting up to {}s for open requests...",
+            shutdown_timeout
+        );
+        handle.stop(true).await;
+    });
+    #[cfg(feature = "grpc")]
+    let grpc_task = grpc_incoming.map(|incoming| {
+        let anonymous = config.anonymous;
+        tokio::spawn(async move {
+            if let Err(e) = grpc::serve(grpc_state, anonymous, incoming).await {
+                eprintln!("[WARN] gRPC server stopped: {}", e);
+            }
+        })
+    });
+    let result = server.await;
+    #[cfg(feature = "grpc")]
+    if let Some(task) = grpc_task {
+        task.abort();
+    }
+    daemon::release_pid_file(Path::new(&config.database_path));
+    result?;
 
     eprintln!("[DEBUG] Server stopped.");
     Ok(())
@@ -0,0 +1,115 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Cursor pagination for list endpoints
+//!
+//! Lists are ordered newest first by `updated_at`, then `id`. A cursor is the
+//! position of the last item on a page, encoded so clients treat it as opaque;
+//! the next page starts strictly after it, so rows added while a client pages
+//! through do not shift or repeat items the way offsets would.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Page size when the client does not ask for one
+pub const DEFAULT_PER_PAGE: usize = 50;
+
+/// Largest page a client can ask for
+pub const MAX_PER_PAGE: usize = 200;
+
+/// Position of the last item on a page ordered by `updated_at` then `id`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cursor {
+    #[serde(rename = "u")]
+    pub updated_at: i64,
+    #[serde(rename = "i")]
+    pub id: String,
+}
+
+/// Encode a cursor value as an opaque string
+pub fn encode_cursor<T: Serialize>(value: &T) -> String {
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(value).unwrap_or_default())
+}
+
+/// Decode a cursor produced by [`encode_cursor`]
+pub fn decode_cursor<T: DeserializeOwned>(cursor: &str) -> Option<T> {
+    let bytes = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// Decode an optional cursor parameter, failing if one was sent but is invalid
+pub fn parse_cursor<T: DeserializeOwned>(cursor: Option<&str>) -> Result<Option<T>, &'static str> {
+    cursor
+        .map(|c| decode_cursor(c).ok_or("Invalid cursor"))
+        .transpose()
+}
+
+/// Page size for a request, accepting the older `limit` parameter
+pub fn per_page(per_page: Option<usize>, limit: Option<usize>, default: usize) -> usize {
+    per_page.or(limit).unwrap_or(default).clamp(1, MAX_PER_PAGE)
+}
+
+/// Split one extra fetched row off a page, returning the cursor for the next
+/// page if there is one
+pub fn finish_page<T>(
+    rows: &mut Vec<T>,
+    per_page: usize,
+    cursor_of: impl Fn(&T) -> String,
+) -> Option<String> {
+    if rows.len() <= per_page {
+        return None;
+    }
+    rows.truncate(per_page);
+    rows.last().map(cursor_of)
+}
+
+/// Response body for one page of a list
+pub fn page_json(
+    items: Vec<serde_json::Value>,
+    total: i64,
+    per_page: usize,
+    next_cursor: Option<String>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "items": items,
+        "total": total,
+        "perPage": per_page,
+        "limit": per_page,
+        "hasMore": next_cursor.is_some(),
+        "nextCursor": next_cursor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = Cursor {
+            updated_at: 1_700_000_000_000,
+            id: "a/b+c".to_string(),
+        };
+        let encoded = encode_cursor(&cursor);
+        assert!(!encoded.contains('/') && !encoded.contains('+'));
+        assert_eq!(decode_cursor::<Cursor>(&encoded), Some(cursor));
+        assert_eq!(decode_cursor::<Cursor>("not a cursor"), None);
+        assert_eq!(decode_cursor::<usize>(&encode_cursor(&40usize)), Some(40));
+    }
+
+    #[test]
+    fn test_finish_page() {
+        let mut rows = vec![5, 4, 3];
+        assert_eq!(
+            finish_page(&mut rows, 2, |n| n.to_string()),
+            Some("4".into())
+        );
+        assert_eq!(rows, vec![5, 4]);
+
+        let mut rows = vec![5, 4];
+        assert_eq!(finish_page(&mut rows, 2, |n| n.to_string()), None);
+        assert_eq!(per_page(None, Some(1000), DEFAULT_PER_PAGE), MAX_PER_PAGE);
+        assert_eq!(per_page(Some(0), None, DEFAULT_PER_PAGE), 1);
+    }
+}
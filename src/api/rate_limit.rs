@@ -0,0 +1,384 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Rate limiting and request size limits for the API server
+//!
+//! Each client gets a token bucket. Every request is limited per peer IP
+//! address before its credentials are checked, so guessing API keys is
+//! throttled too, and authenticated requests are then also limited per API
+//! key or JWT user. Search and GraphQL requests
+//! cost more than plain reads because they scan the database. Clients over
+//! their limit get `429 Too Many Requests` with a `Retry-After` header, and
+//! bodies over the size limit get `413 Payload Too Large`.
+
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage, HttpResponse, Responder};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::auth::AuthenticatedUser;
+use super::keys::ApiKey;
+
+/// Routes that scan the database, and what a request to them costs
+const EXPENSIVE_PATHS: &[(&str, u32)] = &[
+    ("/api/sessions/search", 5),
+    ("/api/graphql", 5),
+    ("/sync/snapshot", 5),
+];
+
+/// Tracked clients before idle buckets are dropped
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Clients listed in the metrics as most limited
+const TOP_LIMITED_CLIENTS: usize = 10;
+
+/// Rate and size limits, set with `csm api serve` flags
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitConfig {
+    /// Sustained requests per minute per client; 0 disables rate limiting
+    pub requests_per_minute: u32,
+    /// Requests a client can make at once before the rate applies
+    pub burst: u32,
+    /// Largest request body accepted, in bytes
+    pub max_body_bytes: usize,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: 600,
+            burst: 120,
+            max_body_bytes: 16 * 1024 * 1024,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn enabled(&self) -> bool {
+        self.requests_per_minute > 0
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    limited: u64,
+}
+
+/// Token buckets and counters, registered as app data
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<String, Bucket>>,
+    allowed: AtomicU64,
+    limited: AtomicU64,
+    oversized: AtomicU64,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+            allowed: AtomicU64::new(0),
+            limited: AtomicU64::new(0),
+            oversized: AtomicU64::new(0),
+        }
+    }
+
+    fn refill_per_sec(&self) -> f64 {
+        f64::from(self.config.requests_per_minute) / 60.0
+    }
+
+    fn capacity(&self) -> f64 {
+        f64::from(self.config.burst.max(1))
+    }
+
+    /// Take `cost` tokens from a client's bucket, returning the tokens left,
+    /// or how long to wait if there are not enough
+    fn check(&self, client: &str, cost: u32, now: Instant) -> Result<u32, Duration> {
+        let capacity = self.capacity();
+        let rate = self.refill_per_sec();
+        // A request can never cost more than a full bucket
+        let cost = f64::from(cost).min(capacity);
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_CLIENTS && !buckets.contains_key(client) {
+            // Buckets that have refilled are the same as new ones
+            buckets.retain(|_, b| {
+                b.tokens + now.duration_since(b.updated).as_secs_f64() * rate < capacity
+            });
+        }
+        let bucket = buckets.entry(client.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+            limited: 0,
+        });
+
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= cost {
+            bucket.tokens -= cost;
+            self.allowed.fetch_add(1, Ordering::Relaxed);
+            Ok(bucket.tokens as u32)
+        } else {
+            bucket.limited += 1;
+            self.limited.fetch_add(1, Ordering::Relaxed);
+            Err(Duration::from_secs_f64((cost - bucket.tokens) / rate))
+        }
+    }
+}
+
+/// The bucket a request counts against before authentication
+fn peer_key(req: &ServiceRequest) -> String {
+    // The peer address, not X-Forwarded-For, which any client can set
+    match req.peer_addr() {
+        Some(addr) => format!("ip:{}", addr.ip()),
+        None => "ip:unknown".to_string(),
+    }
+}
+
+/// The bucket an authenticated request also counts against
+fn credential_key(req: &ServiceRequest) -> Option<String> {
+    let extensions = req.extensions();
+    if let Some(key) = extensions.get::<ApiKey>() {
+        return Some(format!("key:{}", key.id));
+    }
+    extensions
+        .get::<AuthenticatedUser>()
+        .map(|user| format!("user:{}", user.user_id))
+}
+
+fn request_cost(path: &str) -> u32 {
+    EXPENSIVE_PATHS
+        .iter()
+        .find(|(p, _)| *p == path)
+        .map_or(1, |(_, cost)| *cost)
+}
+
+fn content_length(req: &ServiceRequest) -> Option<usize> {
+    req.headers()
+        .get(header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Middleware that enforces the size limit and the per-IP rate limit.
+/// Registered outside `access::require_auth`, so requests with bad
+/// credentials are counted before they are checked.
+pub async fn limit(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let Some(limiter) = req.app_data::<web::Data<RateLimiter>>().cloned() else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    let config = limiter.config;
+
+    if content_length(&req).is_some_and(|len| len > config.max_body_bytes) {
+        limiter.oversized.fetch_add(1, Ordering::Relaxed);
+        let response = HttpResponse::PayloadTooLarge().json(serde_json::json!({
+            "success": false,
+            "error": format!(
+                "Request body exceeds the {} MB limit",
+                config.max_body_bytes / (1024 * 1024)
+            ),
+        }));
+        return Ok(req.into_response(response));
+    }
+
+    if !config.enabled() {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+    charge(&limiter, peer_key(&req), req, next).await
+}
+
+/// Middleware that limits authenticated requests per credential. Registered
+/// inside `access::require_auth`, which records the credential.
+pub async fn limit_credential(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let limiter = req.app_data::<web::Data<RateLimiter>>().cloned();
+    let client = credential_key(&req);
+    match (limiter, client) {
+        (Some(limiter), Some(client)) if limiter.config.enabled() => {
+            charge(&limiter, client, req, next).await
+        }
+        _ => Ok(next.call(req).await?.map_into_boxed_body()),
+    }
+}
+
+/// Take a request's cost from `client`'s bucket and pass it on with the
+/// bucket's remaining tokens in the headers, or answer 429
+async fn charge(
+    limiter: &RateLimiter,
+    client: String,
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let config = limiter.config;
+    let remaining = match limiter.check(&client, request_cost(req.path()), Instant::now()) {
+        Ok(remaining) => remaining,
+        Err(wait) => {
+            let retry_after = (wait.as_secs_f64().ceil() as u64).max(1);
+            let response = HttpResponse::TooManyRequests()
+                .insert_header((header::RETRY_AFTER, retry_after.to_string()))
+                .insert_header(("X-RateLimit-Limit", config.requests_per_minute.to_string()))
+                .insert_header(("X-RateLimit-Remaining", "0"))
+                .json(serde_json::json!({
+                    "success": false,
+                    "error": format!("Rate limit exceeded; retry in {}s", retry_after),
+                }));
+            return Ok(req.into_response(response));
+        }
+    };
+
+    let mut response = next.call(req).await?.map_into_boxed_body();
+    let headers = response.headers_mut();
+    let remaining_header = header::HeaderName::from_static("x-ratelimit-remaining");
+    // The tighter of the IP and credential buckets is the one reported
+    let reported = headers
+        .get(&remaining_header)
+        .and_then(|v| v.to_str().ok()?.parse::<u32>().ok())
+        .map_or(remaining, |inner| inner.min(remaining));
+    headers.insert(
+        header::HeaderName::from_static("x-ratelimit-limit"),
+        HeaderValue::from(config.requests_per_minute),
+    );
+    headers.insert(remaining_header, HeaderValue::from(reported));
+    Ok(response)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LimitedClient {
+    client: String,
+    limited: u64,
+}
+
+/// Rate limit settings and counters
+#[utoipa::path(
+    get,
+    path = "/api/system/rate-limits",
+    tag = "system",
+    responses((status = 200, description = "Rate limit settings and counters", body = Object))
+)]
+pub async fn rate_limit_metrics(limiter: web::Data<RateLimiter>) -> impl Responder {
+    let (tracked, mut top) = {
+        let buckets = limiter.buckets.lock().unwrap();
+        let top: Vec<LimitedClient> = buckets
+            .iter()
+            .filter(|(_, b)| b.limited > 0)
+            .map(|(client, b)| LimitedClient {
+                client: client.clone(),
+                limited: b.limited,
+            })
+            .collect();
+        (buckets.len(), top)
+    };
+    top.sort_by_key(|c| std::cmp::Reverse(c.limited));
+    top.truncate(TOP_LIMITED_CLIENTS);
+
+    let config = limiter.config;
+    HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "data": {
+            "enabled": config.enabled(),
+            "requestsPerMinute": config.requests_per_minute,
+            "burst": config.burst,
+            "maxBodyBytes": config.max_body_bytes,
+            "allowed": limiter.allowed.load(Ordering::Relaxed),
+            "limited": limiter.limited.load(Ordering::Relaxed),
+            "oversized": limiter.oversized.load(Ordering::Relaxed),
+            "trackedClients": tracked,
+            "topLimitedClients": top,
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_minute: 60,
+            burst: 3,
+            ..Default::default()
+        });
+        let start = Instant::now();
+
+        assert_eq!(limiter.check("ip:a", 1, start), Ok(2));
+        assert_eq!(limiter.check("ip:a", 1, start), Ok(1));
+        assert_eq!(limiter.check("ip:a", 1, start), Ok(0));
+        let wait = limiter.check("ip:a", 1, start).unwrap_err();
+        assert_eq!(wait.as_secs(), 1);
+
+        // Other clients have their own bucket
+        assert_eq!(limiter.check("ip:b", 1, start), Ok(2));
+
+        // One token refills per second; expensive requests cap at the burst
+        let later = start + Duration::from_secs(2);
+        assert_eq!(limiter.check("ip:a", 1, later), Ok(1));
+        assert!(limiter.check("ip:a", 5, later).is_err());
+        assert_eq!(
+            limiter.check("ip:b", 5, later + Duration::from_secs(5)),
+            Ok(0)
+        );
+
+        assert_eq!(limiter.limited.load(Ordering::Relaxed), 2);
+        assert_eq!(request_cost("/api/graphql"), 5);
+        assert_eq!(request_cost("/api/sessions"), 1);
+    }
+
+    #[test]
+    fn test_bad_keys_are_limited() {
+        use actix_web::{middleware, test, App};
+
+        actix_web::rt::System::new().block_on(async {
+            let limiter = web::Data::new(RateLimiter::new(RateLimitConfig {
+                requests_per_minute: 60,
+                burst: 3,
+                ..Default::default()
+            }));
+            let app = test::init_service(
+                App::new()
+                    .app_data(limiter.clone())
+                    .wrap(middleware::from_fn(limit_credential))
+                    .wrap(middleware::from_fn(super::super::access::require_auth))
+                    .wrap(middleware::from_fn(limit))
+                    .route("/api/sessions", web::get().to(HttpResponse::Ok)),
+            )
+            .await;
+
+            let mut statuses = Vec::new();
+            for attempt in 0..5 {
+                let req = test::TestRequest::get()
+                    .uri("/api/sessions")
+                    .peer_addr("10.0.0.1:4000".parse().unwrap())
+                    .insert_header(("X-API-Key", format!("csm_guess{}", attempt)))
+                    .to_request();
+                statuses.push(test::call_service(&app, req).await.status().as_u16());
+            }
+            assert_eq!(statuses, [401, 401, 401, 429, 429]);
+
+            // Other addresses have their own bucket
+            let req = test::TestRequest::get()
+                .uri("/api/sessions")
+                .peer_addr("10.0.0.2:4000".parse().unwrap())
+                .insert_header(("X-API-Key", "csm_guess"))
+                .to_request();
+            assert_eq!(test::call_service(&app, req).await.status(), 401);
+        });
+    }
+}
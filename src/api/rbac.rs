@@ -0,0 +1,268 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Role checks for API routes
+//!
+//! A request's [`Scope`] decides whose sessions it sees; its [`Role`] from
+//! `teams::rbac` decides what it may do with them. API keys get a role when
+//! they are created (`csm api keys create --role`), signed-in users with
+//! `csm api users set-role`, and anonymous clients are viewers, or owners
+//! when authentication is disabled.
+//!
+//! Each route needs one [`Permission`]. Reads need `view_sessions`; writing
+//! sessions needs the matching create, edit, or delete permission, with the
+//! "all sessions" variant for admin-scoped credentials; agents, tools, and
+//! the gateway need `manage_agents`; changing teams and sharing sessions
+//! need `share_sessions`; settings, reloads, webhooks, and the audit log
+//! need the admin permissions. A request whose role lacks the
+//! permission gets `403 Forbidden`.
+
+use actix_web::http::Method;
+use anyhow::{Context, Result};
+use colored::*;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+use super::access::{AnonymousAccess, Scope};
+use super::keys::find_user;
+use chasm::teams::{Permission, Role};
+
+/// Role of clients without credentials
+pub fn anonymous_role(anonymous: AnonymousAccess) -> Role {
+    match anonymous {
+        AnonymousAccess::Allow => Role::Owner,
+        _ => Role::Viewer,
+    }
+}
+
+/// Role of a signed-in user
+pub fn user_role(conn: &Connection, user_id: &str) -> rusqlite::Result<Role> {
+    let role: Option<String> = conn
+        .query_row("SELECT role FROM users WHERE id = ?1", [user_id], |row| {
+            row.get(0)
+        })
+        .optional()?;
+    Ok(role
+        .and_then(|role| role.parse().ok())
+        .unwrap_or(Role::Member))
+}
+
+fn under(path: &str, prefix: &str) -> bool {
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// The permission a request needs, or `None` for routes any credential may
+/// use, such as managing one's own account. WebSocket clients can send
+/// commands, so upgrades count as writes.
+pub fn required_permission(
+    method: &Method,
+    path: &str,
+    websocket: bool,
+    scope: &Scope,
+) -> Option<Permission> {
+    use Permission::*;
+
+    let read = (method == Method::GET || method == Method::HEAD) && !websocket;
+    if under(path, "/auth") {
+        return None;
+    }
+    // Rate limit counters name clients, so they are as sensitive as the audit log
+    if under(path, "/api/audit") || under(path, "/api/system/rate-limits") {
+        return Some(ViewAuditLog);
+    }
+    if under(path, "/api/webhooks") {
+        return Some(ManageWebhooks);
+    }
+    if under(path, "/api/settings") || under(path, "/api/admin") {
+        return Some(if read { ViewTeam } else { EditTeamSettings });
+    }
+    // Team roles decide the rest, in the team handlers
+    if under(path, "/api/teams") || (under(path, "/api/sessions") && path.ends_with("/team")) {
+        return Some(if read { ViewTeam } else { ShareSessions });
+    }
+    // GraphQL has no mutations, so its POSTs are reads
+    if read || path == "/api/graphql" {
+        return Some(ViewSessions);
+    }
+    if [
+        "/v1",
+        "/api/v1",
+        "/api/mcp",
+        "/api/agents",
+        "/api/swarms",
+        "/api/swe",
+    ]
+        .iter()
+        .any(|prefix| under(path, prefix))
+    {
+        return Some(ManageAgents);
+    }
+
+    let all = scope.is_admin();
+    if under(path, "/api/sessions") {
+        let edit =
+            method == Method::PUT || path == "/api/sessions/bulk" || path.ends_with("/restore");
+        if edit {
+            return Some(if all {
+                EditAllSessions
+            } else {
+                EditOwnSessions
+            });
+        }
+        if method == Method::DELETE {
+            return Some(if all {
+                DeleteAllSessions
+            } else {
+                DeleteOwnSessions
+            });
+        }
+    }
+    // Everything else records new content: sessions, messages, recordings,
+    // and sync events
+    Some(CreateSession)
+}
+
+// =============================================================================
+// CLI
+// =============================================================================
+
+/// `csm api users set-role`
+pub fn users_set_role(database: &Path, user: &str, role: &str) -> Result<()> {
+    let role: Role = role.parse()?;
+    let conn = Connection::open(database)
+        .with_context(|| format!("Failed to open {}", database.display()))?;
+    let user_id = find_user(&conn, user)?;
+    conn.execute(
+        "UPDATE users SET role = ?2 WHERE id = ?1",
+        params![user_id, role.as_str()],
+    )?;
+    println!(
+        "{} {} is now {} (from their next request)",
+        "[OK]".green().bold(),
+        user,
+        role
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Permission::*;
+
+    #[test]
+    fn test_permission_matrix() {
+        // Method, path, whether the credential is admin-scoped, the
+        // permission needed, and which of viewer, member, and admin have it
+        let matrix = [
+            ("GET", "/api/sessions/s1", false, ViewSessions, "vma"),
+            ("POST", "/api/graphql", false, ViewSessions, "vma"),
+            ("POST", "/api/sessions", false, CreateSession, "-ma"),
+            (
+                "POST",
+                "/api/sessions/s1/messages",
+                false,
+                CreateSession,
+                "-ma",
+            ),
+            ("POST", "/recording/events", true, CreateSession, "-ma"),
+            ("PUT", "/api/sessions/s1", false, EditOwnSessions, "-ma"),
+            ("POST", "/api/sessions/bulk", false, EditOwnSessions, "-ma"),
+            (
+                "POST",
+                "/api/sessions/s1/restore",
+                false,
+                EditOwnSessions,
+                "-ma",
+            ),
+            ("PUT", "/api/sessions/s1", true, EditAllSessions, "--a"),
+            (
+                "DELETE",
+                "/api/sessions/s1",
+                false,
+                DeleteOwnSessions,
+                "-ma",
+            ),
+            ("DELETE", "/api/sessions/s1", true, DeleteAllSessions, "--a"),
+            ("POST", "/api/mcp/call", false, ManageAgents, "-ma"),
+            (
+                "POST",
+                "/api/swe/projects/p1/execute",
+                false,
+                ManageAgents,
+                "-ma",
+            ),
+            ("POST", "/api/agents", false, ManageAgents, "-ma"),
+            ("POST", "/v1/chat/completions", false, ManageAgents, "-ma"),
+            ("POST", "/api/v1/tasks", false, ManageAgents, "-ma"),
+            ("GET", "/api/v1/tasks/t1/events", false, ViewSessions, "vma"),
+            ("GET", "/api/settings", true, ViewTeam, "vma"),
+            ("PUT", "/api/settings", true, EditTeamSettings, "--a"),
+            ("POST", "/api/admin/reload", true, EditTeamSettings, "--a"),
+            ("GET", "/api/webhooks", true, ManageWebhooks, "--a"),
+            ("GET", "/api/audit", true, ViewAuditLog, "--a"),
+            ("GET", "/api/system/rate-limits", true, ViewAuditLog, "--a"),
+            ("POST", "/api/teams", false, ShareSessions, "-ma"),
+            ("PUT", "/api/sessions/s1/team", false, ShareSessions, "-ma"),
+            ("GET", "/api/teams/t1/activity", false, ViewTeam, "vma"),
+        ];
+        for (method, path, admin, permission, roles) in matrix {
+            let method = Method::from_bytes(method.as_bytes()).unwrap();
+            let scope = if admin {
+                Scope::All
+            } else {
+                Scope::Owner(Some("u1".to_string()))
+            };
+            let request = format!("{} {}", method, path);
+            assert_eq!(
+                required_permission(&method, path, false, &scope),
+                Some(permission),
+                "{}",
+                request
+            );
+            for (role, flag) in [Role::Viewer, Role::Member, Role::Admin]
+                .into_iter()
+                .zip(roles.chars())
+            {
+                let allowed = flag != '-';
+                assert_eq!(
+                    role.has_permission(permission),
+                    allowed,
+                    "{} as {}",
+                    request,
+                    role
+                );
+            }
+        }
+
+        let own = Scope::Owner(Some("u1".to_string()));
+        assert_eq!(
+            required_permission(&Method::PUT, "/auth/profile", false, &own),
+            None
+        );
+        assert_eq!(
+            required_permission(&Method::GET, "/ws", true, &Scope::All),
+            Some(CreateSession)
+        );
+        assert_eq!(anonymous_role(AnonymousAccess::ReadOnly), Role::Viewer);
+        assert_eq!(anonymous_role(AnonymousAccess::Allow), Role::Owner);
+    }
+
+    #[test]
+    fn test_user_role() {
+        let conn = Connection::open_in_memory().unwrap();
+        super::super::auth::init_auth_tables(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO users (id, email, display_name, password_hash, password_salt,
+                                created_at, updated_at)
+             VALUES ('u1', 'a@example.com', 'A', '', '', 0, 0)",
+            [],
+        )
+        .unwrap();
+        assert_eq!(user_role(&conn, "u1").unwrap(), Role::Member);
+        conn.execute("UPDATE users SET role = 'viewer' WHERE id = 'u1'", [])
+            .unwrap();
+        assert_eq!(user_role(&conn, "u1").unwrap(), Role::Viewer);
+        assert_eq!(user_role(&conn, "missing").unwrap(), Role::Member);
+    }
+}
@@ -477,7 +477,14 @@ pub struct RecordEventResponse {
     pub responses: Vec<RecordingResponse>,
 }
 
-/// POST /api/recording/events - Process recording events
+/// Process recording events
+#[utoipa::path(
+    post,
+    path = "/recording/events",
+    tag = "recording",
+    request_body = Object,
+    responses((status = 200, description = "Per-event responses", body = Object))
+)]
 pub async fn record_events(
     state: web::Data<Arc<RecordingState>>,
     body: web::Json<RecordEventRequest>,
@@ -498,7 +505,14 @@ pub async fn record_events(
     })
 }
 
-/// POST /api/recording/snapshot - Store full session snapshot
+/// Store a full session snapshot
+#[utoipa::path(
+    post,
+    path = "/recording/snapshot",
+    tag = "recording",
+    request_body = Object,
+    responses((status = 200, description = "Snapshot stored", body = Object))
+)]
 pub async fn store_snapshot(
     state: web::Data<Arc<RecordingState>>,
     body: web::Json<RecordingEvent>,
@@ -515,7 +529,13 @@ pub async fn store_snapshot(
     }
 }
 
-/// GET /api/recording/sessions - List active recording sessions
+/// List active recording sessions
+#[utoipa::path(
+    get,
+    path = "/recording/sessions",
+    tag = "recording",
+    responses((status = 200, description = "Active recording sessions", body = Object))
+)]
 pub async fn list_sessions(state: web::Data<Arc<RecordingState>>) -> impl Responder {
     let sessions: Vec<_> = state
         .active_sessions
@@ -542,7 +562,17 @@ pub async fn list_sessions(state: web::Data<Arc<RecordingState>>) -> impl Respon
     }))
 }
 
-/// GET /api/recording/session/{id} - Get specific session data
+/// Get a recorded session
+#[utoipa::path(
+    get,
+    path = "/recording/session/{id}",
+    tag = "recording",
+    params(("id" = String, Path, description = "Session ID")),
+    responses(
+        (status = 200, description = "Recorded session", body = Object),
+        (status = 404, description = "Session not found")
+    )
+)]
 pub async fn get_session(
     state: web::Data<Arc<RecordingState>>,
     path: web::Path<String>,
@@ -569,7 +599,17 @@ pub async fn get_session(
     }
 }
 
-/// GET /api/recording/session/{id}/recovery - Get recovery data for session
+/// Get recovery data for a session
+#[utoipa::path(
+    get,
+    path = "/recording/session/{id}/recovery",
+    tag = "recording",
+    params(("id" = String, Path, description = "Session ID")),
+    responses(
+        (status = 200, description = "Recovery data", body = Object),
+        (status = 404, description = "Session not found")
+    )
+)]
 pub async fn get_recovery(
     state: web::Data<Arc<RecordingState>>,
     path: web::Path<String>,
@@ -592,7 +632,13 @@ pub async fn get_recovery(
     }
 }
 
-/// GET /api/recording/status - Recording service status
+/// Recording service status
+#[utoipa::path(
+    get,
+    path = "/recording/status",
+    tag = "recording",
+    responses((status = 200, description = "Recording service status", body = Object))
+)]
 pub async fn recording_status(
     state: web::Data<Arc<RecordingState>>,
     _req: HttpRequest,
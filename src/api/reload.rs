@@ -0,0 +1,222 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Reloading server settings while it runs
+//!
+//! CORS origins, gateway upstreams, and the gateway's routing policy come from
+//! the `[api]` section of the config file, and workspace policies from
+//! `[policies]`. On SIGHUP, or on
+//! `POST /api/admin/reload`, the server reads the file again and swaps them in
+//! place, so open SSE and WebSocket clients stay connected. A file that fails
+//! to parse, names an unknown provider or strategy, or has a policy covering
+//! nothing is rejected, and the
+//! running settings are kept.
+
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse};
+use anyhow::Result;
+use serde::Serialize;
+use std::sync::RwLock;
+
+use super::docs::ApiEnvelope;
+use super::gateway::Gateway;
+use crate::config::Config;
+use crate::routing::Policies;
+
+/// Settings the request pipeline reads on every request, registered as app
+/// data
+pub struct LiveSettings {
+    /// Origins from the server's own configuration, always allowed
+    base_origins: Vec<String>,
+    /// Origins from `[api] cors_origins`
+    cors_origins: RwLock<Vec<String>>,
+}
+
+impl LiveSettings {
+    pub fn new(base_origins: Vec<String>) -> Self {
+        Self {
+            base_origins,
+            cors_origins: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Whether a browser at `origin` may call the API
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        origin.starts_with("http://localhost:")
+            || origin.starts_with("http://127.0.0.1:")
+            || origin.starts_with("exp://")
+            || self.base_origins.iter().any(|allowed| allowed == origin)
+            || self
+                .cors_origins
+                .read()
+                .unwrap()
+                .iter()
+                .any(|allowed| allowed == origin)
+    }
+}
+
+/// What a reload put in place
+#[derive(Debug, Serialize)]
+pub struct Reloaded {
+    /// Origins allowed besides localhost
+    pub cors_origins: Vec<String>,
+    /// Providers the gateway routes to
+    pub providers: Vec<String>,
+    pub routing_strategy: String,
+    /// Workspace policies requests are checked against
+    pub policies: Vec<String>,
+}
+
+/// Apply the `[api]` and `[policies]` sections, changing nothing if either
+/// is invalid
+pub fn apply(settings: &LiveSettings, gateway: &Gateway, config: &Config) -> Result<Reloaded> {
+    let api = &config.api;
+    let policies = Policies::from_config(&config.policies)?;
+    gateway.reload(api)?;
+    gateway.set_policies(policies);
+    let cors_origins = api.cors_origins.clone().unwrap_or_default();
+    *settings.cors_origins.write().unwrap() = cors_origins.clone();
+    Ok(Reloaded {
+        cors_origins,
+        providers: gateway.configured_providers(),
+        routing_strategy: api
+            .routing
+            .strategy
+            .clone()
+            .unwrap_or_else(|| "balanced".to_string()),
+        policies: config.policies.keys().cloned().collect(),
+    })
+}
+
+/// Read the config file again and apply it
+pub fn reload(settings: &LiveSettings, gateway: &Gateway) -> Result<Reloaded> {
+    let config = Config::load()?;
+    apply(settings, gateway, &config)
+}
+
+fn log_reload(result: &Result<Reloaded>) {
+    match result {
+        Ok(reloaded) => println!(
+            "[*] Configuration reloaded: {} extra CORS origins, providers [{}], routing {}, policies [{}]",
+            reloaded.cors_origins.len(),
+            reloaded.providers.join(", "),
+            reloaded.routing_strategy,
+            reloaded.policies.join(", ")
+        ),
+        Err(e) => eprintln!("[WARN] Configuration not reloaded: {:#}", e),
+    }
+}
+
+/// Reload the configuration each time the process gets SIGHUP
+#[cfg(unix)]
+pub fn spawn_hangup_listener(
+    settings: web::Data<LiveSettings>,
+    gateway: web::Data<Gateway>,
+) -> Option<tokio::task::JoinHandle<()>> {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            eprintln!("[WARN] SIGHUP reload disabled: {}", e);
+            return None;
+        }
+    };
+    Some(tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            log_reload(&reload(&settings, &gateway));
+        }
+    }))
+}
+
+/// Reload CORS origins, gateway providers, routing, and workspace policies
+/// from the config file
+#[utoipa::path(
+    post,
+    path = "/api/admin/reload",
+    tag = "system",
+    responses(
+        (status = 200, description = "The settings now in effect", body = ApiEnvelope),
+        (status = 400, description = "The config file is invalid; nothing was changed"),
+        (status = 403, description = "Not an admin")
+    )
+)]
+pub async fn reload_config(
+    settings: web::Data<LiveSettings>,
+    gateway: web::Data<Gateway>,
+) -> HttpResponse {
+    let result = reload(&settings, &gateway);
+    log_reload(&result);
+    match result {
+        Ok(reloaded) => HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "data": reloaded,
+        })),
+        Err(e) => HttpResponse::build(StatusCode::BAD_REQUEST).json(serde_json::json!({
+            "success": false,
+            "error": format!("{:#}", e),
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ApiConfig, ApiProviderConfig, ApiRoutingConfig, PolicyConfig};
+
+    fn with_api(api: ApiConfig) -> Config {
+        Config {
+            api,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_apply_keeps_settings_on_error() {
+        let settings = LiveSettings::new(vec!["https://base.test".to_string()]);
+        let gateway = Gateway::new(Vec::new());
+        let mut api = ApiConfig {
+            cors_origins: Some(vec!["https://chat.test".to_string()]),
+            ..Default::default()
+        };
+        api.providers.insert(
+            "ollama".to_string(),
+            ApiProviderConfig {
+                endpoint: Some("http://gpu.test:11434".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let mut config = with_api(api);
+        let reloaded = apply(&settings, &gateway, &config).unwrap();
+        assert!(reloaded.providers.contains(&"ollama".to_string()));
+        assert!(settings.allows_origin("https://chat.test"));
+        assert!(settings.allows_origin("https://base.test"));
+        assert!(settings.allows_origin("http://localhost:5173"));
+        assert!(!settings.allows_origin("https://other.test"));
+
+        let invalid = ApiConfig {
+            routing: ApiRoutingConfig {
+                strategy: Some("cheapest".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(apply(&settings, &gateway, &with_api(invalid)).is_err());
+        let invalid = ApiConfig {
+            routing: ApiRoutingConfig {
+                min_quality: Some(1.5),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(apply(&settings, &gateway, &with_api(invalid)).is_err());
+        // A policy covering nothing is rejected too
+        config
+            .policies
+            .insert("client-a".to_string(), PolicyConfig::default());
+        assert!(apply(&settings, &gateway, &config).is_err());
+        assert!(settings.allows_origin("https://chat.test"));
+        assert!(gateway
+            .configured_providers()
+            .contains(&"ollama".to_string()));
+    }
+}
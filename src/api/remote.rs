@@ -0,0 +1,816 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Remote agent tasks
+//!
+//! `POST /api/v1/tasks` runs an agent on this server in the background, so
+//! heavy work can go to a machine with the GPUs while `csm agency remote`
+//! watches from another. The run's events fill the task's log, followed live
+//! at `/api/v1/tasks/{id}/events`, and files the agent writes become
+//! artifacts downloadable from `/api/v1/tasks/{id}/artifacts/{name}`.
+//! Agents running elsewhere report their own tasks through the progress,
+//! step, log, complete, and fail endpoints.
+//!
+//! A task belongs to whoever submitted it: clients scoped to a user see and
+//! act on only their own tasks, as with sessions.
+//!
+//! Tasks live in memory and are lost when the server stops.
+
+use actix_web::http::{header, StatusCode};
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::RecvError;
+use utoipa::ToSchema;
+
+use super::access::Scope;
+use super::docs::ApiEnvelope;
+use crate::agency::{
+    generate_task_id, AgencyEvent, ArtifactType, EventType, ExecutionResult, LogLevel, RemoteEvent,
+    RemoteMonitor, RemoteMonitorConfig, RemoteMonitorError, RemoteNode, RemoteTask,
+    RemoteTaskBuilder, RemoteTaskStatus, TaskArtifact, TaskLogEntry, TaskMetrics, TaskResult,
+};
+use crate::commands::{load_agent, run_agent_with_events};
+
+/// The tasks this server runs or has been told about
+pub struct RemoteTasks {
+    monitor: RemoteMonitor,
+    /// This server, as the node its own runs are on
+    node_id: String,
+    /// Cancel flags of the runs in progress
+    running: Mutex<HashMap<String, Arc<AtomicBool>>>,
+    /// The user who submitted each task, or None for admins and anonymous
+    /// clients
+    owners: Mutex<HashMap<String, Option<String>>>,
+}
+
+impl RemoteTasks {
+    /// Tasks for the server listening at `address`
+    pub async fn new(address: &str) -> Self {
+        let monitor = RemoteMonitor::new(RemoteMonitorConfig::default());
+        let node = RemoteNode::local(address);
+        let node_id = node.id.clone();
+        let _ = monitor.register_node(node).await;
+        Self {
+            monitor,
+            node_id,
+            running: Mutex::new(HashMap::new()),
+            owners: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether `scope` can see the task with this ID
+    fn can_access(&self, scope: &Scope, task_id: &str) -> bool {
+        let owners = self.owners.lock().unwrap();
+        scope.can_access(owners.get(task_id).and_then(Option::as_deref))
+    }
+
+    /// The task with this ID, if `scope` can see it
+    async fn task_for(&self, scope: &Scope, task_id: &str) -> Option<RemoteTask> {
+        if !self.can_access(scope, task_id) {
+            return None;
+        }
+        self.monitor.get_task(task_id).await
+    }
+
+    /// Add an event of a run to its task's log, noting files it wrote
+    async fn record(&self, task_id: &str, event: &AgencyEvent, artifacts: &mut Vec<TaskArtifact>) {
+        if let Some(entry) = log_entry(event) {
+            let _ = self.monitor.add_task_log(task_id, entry).await;
+        }
+        if let Some(mut artifact) = written_file(event) {
+            if artifacts.iter().any(|a| a.location == artifact.location) {
+                return;
+            }
+            if artifacts.iter().any(|a| a.name == artifact.name) {
+                artifact.name = format!("{}-{}", artifacts.len() + 1, artifact.name);
+            }
+            artifacts.push(artifact);
+        }
+    }
+
+    /// Close a task with the outcome of its run, unless it was cancelled
+    async fn finish(
+        &self,
+        task_id: &str,
+        result: anyhow::Result<ExecutionResult>,
+        artifacts: Vec<TaskArtifact>,
+    ) {
+        self.running.lock().unwrap().remove(task_id);
+        let cancelled = self
+            .monitor
+            .get_task(task_id)
+            .await
+            .is_none_or(|task| task.status == RemoteTaskStatus::Cancelled);
+        if cancelled {
+            return;
+        }
+
+        let _ = match result {
+            Ok(result) if result.success => {
+                let result = TaskResult {
+                    success: true,
+                    output: Some(serde_json::json!({
+                        "response": result.response,
+                        "token_usage": result.token_usage,
+                    })),
+                    artifacts: artifacts.into_iter().filter_map(fingerprint).collect(),
+                    metrics: TaskMetrics {
+                        duration_ms: result.duration_ms,
+                        tokens_used: Some(result.token_usage.total_tokens as u64),
+                        ..Default::default()
+                    },
+                };
+                self.monitor.complete_task(task_id, result).await
+            }
+            Ok(result) => {
+                let error = result.error.unwrap_or_else(|| "The agent failed".to_string());
+                self.monitor.fail_task(task_id, error).await
+            }
+            Err(e) => self.monitor.fail_task(task_id, format!("{:#}", e)).await,
+        };
+    }
+}
+
+/// What the run thread hands over to the task
+enum RunUpdate {
+    Event(AgencyEvent),
+    Finished(anyhow::Result<ExecutionResult>),
+}
+
+/// A task log line for an event, if it is worth one; streamed text is left
+/// to the final response
+fn log_entry(event: &AgencyEvent) -> Option<TaskLogEntry> {
+    let data = &event.data;
+    let tool = data["tool"].as_str().unwrap_or_default();
+    let (level, message) = match event.event_type {
+        EventType::MessageDelta => return None,
+        EventType::AgentStarted => (LogLevel::Info, format!("{} started", event.agent_name)),
+        EventType::AgentCompleted => (LogLevel::Info, format!("{} completed", event.agent_name)),
+        EventType::AgentThinking => (LogLevel::Debug, format!("{} is thinking", event.agent_name)),
+        EventType::ToolCallStarted => (
+            LogLevel::Info,
+            format!("[>] {} {}", tool, data["arguments"]),
+        ),
+        EventType::ToolCallCompleted => {
+            let first = data["content"]
+                .as_str()
+                .and_then(|content| content.lines().next())
+                .unwrap_or_default();
+            if data["success"].as_bool() == Some(true) {
+                (LogLevel::Info, format!("[+] {}: {}", tool, first))
+            } else {
+                (LogLevel::Warn, format!("[x] {}: {}", tool, first))
+            }
+        }
+        EventType::AgentFailed | EventType::ToolCallFailed => (
+            LogLevel::Error,
+            data["error"]
+                .as_str()
+                .map(String::from)
+                .unwrap_or_else(|| data.to_string()),
+        ),
+        other => (LogLevel::Debug, other.to_string()),
+    };
+    Some(TaskLogEntry {
+        timestamp: event.timestamp,
+        level,
+        message,
+        data: None,
+    })
+}
+
+/// The file a successful `write_file` call wrote
+fn written_file(event: &AgencyEvent) -> Option<TaskArtifact> {
+    let data = &event.data;
+    if event.event_type != EventType::ToolCallCompleted
+        || data["tool"] != "write_file"
+        || data["success"] != true
+    {
+        return None;
+    }
+    let location = data["data"]["path"].as_str()?;
+    let name = std::path::Path::new(location).file_name()?.to_str()?;
+    Some(TaskArtifact {
+        name: name.to_string(),
+        artifact_type: ArtifactType::File,
+        location: location.to_string(),
+        size: None,
+        checksum: None,
+    })
+}
+
+/// `artifact` with the size and checksum of the file as the run left it,
+/// or None when the run removed it again
+fn fingerprint(mut artifact: TaskArtifact) -> Option<TaskArtifact> {
+    let bytes = std::fs::read(&artifact.location).ok()?;
+    artifact.size = Some(bytes.len() as u64);
+    artifact.checksum = Some(format!("{:x}", Sha256::digest(&bytes)));
+    Some(artifact)
+}
+
+fn success(status: StatusCode, data: impl Serialize) -> HttpResponse {
+    HttpResponse::build(status).json(serde_json::json!({
+        "success": true,
+        "data": data,
+    }))
+}
+
+fn failure(status: StatusCode, message: &str) -> HttpResponse {
+    HttpResponse::build(status).json(serde_json::json!({
+        "success": false,
+        "error": message,
+    }))
+}
+
+fn monitor_failure(error: RemoteMonitorError) -> HttpResponse {
+    let status = match error {
+        RemoteMonitorError::NodeNotFound(_) | RemoteMonitorError::TaskNotFound(_) => {
+            StatusCode::NOT_FOUND
+        }
+        _ => StatusCode::BAD_REQUEST,
+    };
+    failure(status, &error.to_string())
+}
+
+/// Run an agent in the background
+#[utoipa::path(
+    post,
+    path = "/api/v1/tasks",
+    tag = "remote",
+    request_body = RunTaskRequest,
+    responses(
+        (status = 202, description = "The task, running", body = ApiEnvelope),
+        (status = 400, description = "No prompt"),
+        (status = 404, description = "Agent not found")
+    )
+)]
+pub async fn submit_task(
+    tasks: web::Data<RemoteTasks>,
+    scope: Scope,
+    body: web::Json<RunTaskRequest>,
+) -> HttpResponse {
+    let RunTaskRequest {
+        agent,
+        prompt,
+        model,
+    } = body.into_inner();
+    if prompt.trim().is_empty() {
+        return failure(StatusCode::BAD_REQUEST, "Pass a prompt");
+    }
+    let agent = match load_agent(&agent, model.as_deref()) {
+        Ok(agent) => agent,
+        Err(e) => return failure(StatusCode::NOT_FOUND, &e.to_string()),
+    };
+
+    let title = prompt.lines().next().unwrap_or_default();
+    let task = RemoteTaskBuilder::new(generate_task_id(), &tasks.node_id, agent.name())
+        .agent_name(agent.name())
+        .title(title.chars().take(80).collect::<String>())
+        .description(prompt.clone())
+        .metadata("model", &agent.model().model)
+        .build();
+    let task_id = task.id.clone();
+    tasks
+        .owners
+        .lock()
+        .unwrap()
+        .insert(task_id.clone(), scope.owner().map(str::to_string));
+    if let Err(e) = tasks.monitor.create_task(task).await {
+        tasks.owners.lock().unwrap().remove(&task_id);
+        return monitor_failure(e);
+    }
+    let _ = tasks
+        .monitor
+        .update_task_status(&task_id, RemoteTaskStatus::Running)
+        .await;
+    let cancelled = Arc::new(AtomicBool::new(false));
+    tasks
+        .running
+        .lock()
+        .unwrap()
+        .insert(task_id.clone(), cancelled.clone());
+
+    // The run blocks on its own runtime, so it gets a thread and hands its
+    // events over
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        let events = tx.clone();
+        let result = run_agent_with_events(
+            agent,
+            &prompt,
+            &mut |event| {
+                let _ = events.send(RunUpdate::Event(event.clone()));
+            },
+            &|| cancelled.load(Ordering::Relaxed),
+        );
+        let _ = tx.send(RunUpdate::Finished(result));
+    });
+    let worker = tasks.clone();
+    let id = task_id.clone();
+    actix_web::rt::spawn(async move {
+        let mut artifacts = Vec::new();
+        while let Some(update) = rx.recv().await {
+            match update {
+                RunUpdate::Event(event) => worker.record(&id, &event, &mut artifacts).await,
+                RunUpdate::Finished(result) => {
+                    worker.finish(&id, result, std::mem::take(&mut artifacts)).await
+                }
+            }
+        }
+    });
+
+    match tasks.monitor.get_task(&task_id).await {
+        Some(task) => success(StatusCode::ACCEPTED, task),
+        None => failure(StatusCode::NOT_FOUND, "Task not found"),
+    }
+}
+
+/// List the tasks the caller can see, newest first, without their logs
+#[utoipa::path(
+    get,
+    path = "/api/v1/tasks",
+    tag = "remote",
+    responses((status = 200, description = "Tasks", body = ApiEnvelope))
+)]
+pub async fn list_tasks(tasks: web::Data<RemoteTasks>, scope: Scope) -> HttpResponse {
+    let mut list = tasks.monitor.list_tasks().await;
+    list.retain(|task| tasks.can_access(&scope, &task.id));
+    list.sort_by_key(|task| std::cmp::Reverse(task.started_at));
+    for task in &mut list {
+        task.logs.clear();
+    }
+    success(StatusCode::OK, list)
+}
+
+/// Get a task with its recent logs
+#[utoipa::path(
+    get,
+    path = "/api/v1/tasks/{id}",
+    tag = "remote",
+    params(("id" = String, Path, description = "Task ID")),
+    responses(
+        (status = 200, description = "The task", body = ApiEnvelope),
+        (status = 404, description = "Task not found")
+    )
+)]
+pub async fn get_task(
+    tasks: web::Data<RemoteTasks>,
+    scope: Scope,
+    path: web::Path<String>,
+) -> HttpResponse {
+    match tasks.task_for(&scope, &path).await {
+        Some(task) => success(StatusCode::OK, task),
+        None => failure(StatusCode::NOT_FOUND, "Task not found"),
+    }
+}
+
+/// Follow a task: its logs so far, then its events until it finishes
+#[utoipa::path(
+    get,
+    path = "/api/v1/tasks/{id}/events",
+    tag = "remote",
+    params(("id" = String, Path, description = "Task ID")),
+    responses(
+        (status = 200, description = "Stream of task events", body = String, content_type = "text/event-stream"),
+        (status = 404, description = "Task not found")
+    )
+)]
+pub async fn task_events(
+    tasks: web::Data<RemoteTasks>,
+    scope: Scope,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let task_id = path.into_inner();
+    // Subscribe first so nothing falls between the logs and the live events
+    let mut events = tasks.monitor.subscribe();
+    let Some(task) = tasks.task_for(&scope, &task_id).await else {
+        return failure(StatusCode::NOT_FOUND, "Task not found");
+    };
+
+    let frame = |event: &RemoteEvent| {
+        Ok::<_, std::io::Error>(web::Bytes::from(format!(
+            "data: {}\n\n",
+            serde_json::to_string(event).unwrap_or_default()
+        )))
+    };
+    let stream = async_stream::stream! {
+        let seen = task.logs.last().map(|entry| entry.timestamp);
+        for entry in task.logs.iter().cloned() {
+            yield frame(&RemoteEvent::TaskLog { task_id: task_id.clone(), entry });
+        }
+        if let Some(last) = RemoteEvent::finished(&task) {
+            yield frame(&last);
+            return;
+        }
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            if event.task_id() != Some(task_id.as_str()) {
+                continue;
+            }
+            if let RemoteEvent::TaskLog { entry, .. } = &event {
+                if seen.is_some_and(|seen| entry.timestamp <= seen) {
+                    continue;
+                }
+            }
+            yield frame(&event);
+            if event.is_final() {
+                break;
+            }
+        }
+    };
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .insert_header(header::ContentEncoding::Identity)
+        .streaming(stream)
+}
+
+/// Stop a task
+#[utoipa::path(
+    post,
+    path = "/api/v1/tasks/{id}/cancel",
+    tag = "remote",
+    params(("id" = String, Path, description = "Task ID")),
+    responses(
+        (status = 200, description = "Task cancelled", body = ApiEnvelope),
+        (status = 404, description = "Task not found"),
+        (status = 409, description = "Task already finished")
+    )
+)]
+pub async fn cancel_task(
+    tasks: web::Data<RemoteTasks>,
+    scope: Scope,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let task_id = path.into_inner();
+    let Some(task) = tasks.task_for(&scope, &task_id).await else {
+        return failure(StatusCode::NOT_FOUND, "Task not found");
+    };
+    if task.completed_at.is_some() {
+        return failure(StatusCode::CONFLICT, "The task already finished");
+    }
+    if let Some(flag) = tasks.running.lock().unwrap().get(&task_id) {
+        flag.store(true, Ordering::Relaxed);
+    }
+    match tasks
+        .monitor
+        .cancel_task(&task_id, Some("Cancelled by request".to_string()))
+        .await
+    {
+        Ok(()) => success(StatusCode::OK, serde_json::json!({ "id": task_id })),
+        Err(e) => monitor_failure(e),
+    }
+}
+
+/// Download a file a task produced
+#[utoipa::path(
+    get,
+    path = "/api/v1/tasks/{id}/artifacts/{name}",
+    tag = "remote",
+    params(
+        ("id" = String, Path, description = "Task ID"),
+        ("name" = String, Path, description = "Artifact name")
+    ),
+    responses(
+        (status = 200, description = "The file", content_type = "application/octet-stream"),
+        (status = 404, description = "Task or artifact not found")
+    )
+)]
+pub async fn download_artifact(
+    tasks: web::Data<RemoteTasks>,
+    scope: Scope,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (task_id, name) = path.into_inner();
+    let Some(task) = tasks.task_for(&scope, &task_id).await else {
+        return failure(StatusCode::NOT_FOUND, "Task not found");
+    };
+    // Only files the task lists are served, never a path from the request
+    let Some(artifact) = task
+        .result
+        .iter()
+        .flat_map(|result| &result.artifacts)
+        .find(|artifact| artifact.name == name)
+    else {
+        return failure(StatusCode::NOT_FOUND, "The task has no such artifact");
+    };
+    match std::fs::read(&artifact.location) {
+        Ok(bytes) => HttpResponse::Ok()
+            .content_type("application/octet-stream")
+            .insert_header((
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", artifact.name),
+            ))
+            .body(bytes),
+        Err(_) => failure(StatusCode::NOT_FOUND, "The artifact no longer exists"),
+    }
+}
+
+/// Body of `POST /api/v1/tasks`, as [`crate::agency::TaskRequest`] sends it
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RunTaskRequest {
+    /// Agent to run, by name
+    pub agent: String,
+    /// Prompt or task for the agent
+    pub prompt: String,
+    /// Model to use instead of the agent's
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ProgressReport {
+    /// Progress from 0 to 1
+    pub progress: f32,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct StepReport {
+    pub step: u32,
+    pub total: u32,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct FailureReport {
+    pub error: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LogReport {
+    /// trace, debug, info, warn, or error
+    #[schema(value_type = String)]
+    pub level: LogLevel,
+    pub message: String,
+}
+
+/// 404 for a task `scope` cannot see, as if it did not exist
+fn hidden(tasks: &RemoteTasks, scope: &Scope, task_id: &str) -> Option<HttpResponse> {
+    (!tasks.can_access(scope, task_id))
+        .then(|| monitor_failure(RemoteMonitorError::TaskNotFound(task_id.to_string())))
+}
+
+fn reported(result: Result<(), RemoteMonitorError>) -> HttpResponse {
+    match result {
+        Ok(()) => success(StatusCode::OK, serde_json::json!({})),
+        Err(e) => monitor_failure(e),
+    }
+}
+
+/// Report a task's progress
+#[utoipa::path(
+    post,
+    path = "/api/v1/tasks/{id}/progress",
+    tag = "remote",
+    params(("id" = String, Path, description = "Task ID")),
+    request_body = ProgressReport,
+    responses(
+        (status = 200, description = "Recorded", body = ApiEnvelope),
+        (status = 404, description = "Task not found")
+    )
+)]
+pub async fn report_progress(
+    tasks: web::Data<RemoteTasks>,
+    scope: Scope,
+    path: web::Path<String>,
+    body: web::Json<ProgressReport>,
+) -> HttpResponse {
+    if let Some(response) = hidden(&tasks, &scope, &path) {
+        return response;
+    }
+    let ProgressReport { progress, message } = body.into_inner();
+    reported(
+        tasks
+            .monitor
+            .update_task_progress(&path, progress, message)
+            .await,
+    )
+}
+
+/// Report a finished step of a task
+#[utoipa::path(
+    post,
+    path = "/api/v1/tasks/{id}/step",
+    tag = "remote",
+    params(("id" = String, Path, description = "Task ID")),
+    request_body = StepReport,
+    responses(
+        (status = 200, description = "Recorded", body = ApiEnvelope),
+        (status = 400, description = "No steps"),
+        (status = 404, description = "Task not found")
+    )
+)]
+pub async fn report_step(
+    tasks: web::Data<RemoteTasks>,
+    scope: Scope,
+    path: web::Path<String>,
+    body: web::Json<StepReport>,
+) -> HttpResponse {
+    if let Some(response) = hidden(&tasks, &scope, &path) {
+        return response;
+    }
+    let StepReport {
+        step,
+        total,
+        description,
+    } = body.into_inner();
+    if total == 0 {
+        return failure(StatusCode::BAD_REQUEST, "A task needs at least one step");
+    }
+    reported(
+        tasks
+            .monitor
+            .update_task_step(&path, step, total, description)
+            .await,
+    )
+}
+
+/// Add a line to a task's log
+#[utoipa::path(
+    post,
+    path = "/api/v1/tasks/{id}/log",
+    tag = "remote",
+    params(("id" = String, Path, description = "Task ID")),
+    request_body = LogReport,
+    responses(
+        (status = 200, description = "Recorded", body = ApiEnvelope),
+        (status = 404, description = "Task not found")
+    )
+)]
+pub async fn report_log(
+    tasks: web::Data<RemoteTasks>,
+    scope: Scope,
+    path: web::Path<String>,
+    body: web::Json<LogReport>,
+) -> HttpResponse {
+    if let Some(response) = hidden(&tasks, &scope, &path) {
+        return response;
+    }
+    let LogReport { level, message } = body.into_inner();
+    let entry = TaskLogEntry {
+        timestamp: Utc::now(),
+        level,
+        message,
+        data: None,
+    };
+    reported(tasks.monitor.add_task_log(&path, entry).await)
+}
+
+/// Report that a task finished
+#[utoipa::path(
+    post,
+    path = "/api/v1/tasks/{id}/complete",
+    tag = "remote",
+    params(("id" = String, Path, description = "Task ID")),
+    request_body(content = serde_json::Value, description = "Task result"),
+    responses(
+        (status = 200, description = "Recorded", body = ApiEnvelope),
+        (status = 404, description = "Task not found")
+    )
+)]
+pub async fn report_completed(
+    tasks: web::Data<RemoteTasks>,
+    scope: Scope,
+    path: web::Path<String>,
+    body: web::Json<TaskResult>,
+) -> HttpResponse {
+    if let Some(response) = hidden(&tasks, &scope, &path) {
+        return response;
+    }
+    reported(tasks.monitor.complete_task(&path, body.into_inner()).await)
+}
+
+/// Report that a task failed
+#[utoipa::path(
+    post,
+    path = "/api/v1/tasks/{id}/fail",
+    tag = "remote",
+    params(("id" = String, Path, description = "Task ID")),
+    request_body = FailureReport,
+    responses(
+        (status = 200, description = "Recorded", body = ApiEnvelope),
+        (status = 404, description = "Task not found")
+    )
+)]
+pub async fn report_failed(
+    tasks: web::Data<RemoteTasks>,
+    scope: Scope,
+    path: web::Path<String>,
+    body: web::Json<FailureReport>,
+) -> HttpResponse {
+    if let Some(response) = hidden(&tasks, &scope, &path) {
+        return response;
+    }
+    reported(tasks.monitor.fail_task(&path, body.into_inner().error).await)
+}
+
+/// Report that a node is alive, registering it the first time
+#[utoipa::path(
+    post,
+    path = "/api/v1/nodes/{id}/heartbeat",
+    tag = "remote",
+    params(("id" = String, Path, description = "Node ID")),
+    responses((status = 200, description = "Recorded", body = ApiEnvelope))
+)]
+pub async fn node_heartbeat(
+    tasks: web::Data<RemoteTasks>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let node_id = path.into_inner();
+    match tasks.monitor.heartbeat(&node_id).await {
+        Err(RemoteMonitorError::NodeNotFound(_)) => {
+            let address = req
+                .peer_addr()
+                .map(|addr| addr.to_string())
+                .unwrap_or_default();
+            reported(
+                tasks
+                    .monitor
+                    .register_node(RemoteNode::new(node_id, address))
+                    .await,
+            )
+        }
+        result => reported(result),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(event_type: EventType, data: serde_json::Value) -> AgencyEvent {
+        AgencyEvent {
+            event_type,
+            agent_name: "coder".to_string(),
+            data,
+            timestamp: Utc::now(),
+            session_id: None,
+        }
+    }
+
+    #[test]
+    fn test_run_events() {
+        let call = event(
+            EventType::ToolCallStarted,
+            json!({ "tool": "write_file", "arguments": { "path": "out.md" } }),
+        );
+        let entry = log_entry(&call).unwrap();
+        assert_eq!(entry.message, r#"[>] write_file {"path":"out.md"}"#);
+        assert!(log_entry(&event(EventType::MessageDelta, json!({ "content": "Hi" }))).is_none());
+
+        let wrote = event(
+            EventType::ToolCallCompleted,
+            json!({
+                "tool": "write_file",
+                "success": true,
+                "content": "Wrote 2 bytes to /work/out.md",
+                "data": { "path": "/work/out.md", "bytes": 2 }
+            }),
+        );
+        let artifact = written_file(&wrote).unwrap();
+        assert_eq!(artifact.name, "out.md");
+        assert_eq!(artifact.location, "/work/out.md");
+
+        let failed = event(
+            EventType::ToolCallCompleted,
+            json!({ "tool": "write_file", "success": false, "content": "The sandbox is read-only" }),
+        );
+        assert!(written_file(&failed).is_none());
+        assert_eq!(log_entry(&failed).unwrap().level, LogLevel::Warn);
+    }
+    #[tokio::test]
+    async fn test_tasks_scoped_to_submitter() {
+        let tasks = RemoteTasks::new("127.0.0.1:8787").await;
+        for (id, owner) in [("alice-task", Some("alice")), ("admin-task", None)] {
+            let task = RemoteTaskBuilder::new(id.to_string(), &tasks.node_id, "coder").build();
+            tasks.monitor.create_task(task).await.unwrap();
+            tasks
+                .owners
+                .lock()
+                .unwrap()
+                .insert(id.to_string(), owner.map(str::to_string));
+        }
+
+        let alice = Scope::Owner(Some("alice".to_string()));
+        let bob = Scope::Owner(Some("bob".to_string()));
+        assert!(tasks.task_for(&alice, "alice-task").await.is_some());
+        assert!(tasks.task_for(&alice, "admin-task").await.is_none());
+        assert!(tasks.task_for(&bob, "alice-task").await.is_none());
+        assert!(hidden(&tasks, &bob, "alice-task").is_some());
+        assert!(tasks.task_for(&Scope::All, "alice-task").await.is_some());
+        assert!(tasks
+            .task_for(&Scope::Owner(None), "admin-task")
+            .await
+            .is_some());
+    }
+}
@@ -0,0 +1,429 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Installing the API server as a system service
+//!
+//! `csm api install-service` registers `csm api serve` to start at boot and
+//! restart on failure: a systemd unit on Linux, a launchd job on macOS, and a
+//! service on Windows. User-level units are the default on Linux and macOS;
+//! `--system` installs a system-wide one, which needs root.
+
+use anyhow::{bail, Context, Result};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// What to install
+#[derive(Debug, Clone)]
+pub struct ServiceSpec {
+    pub name: String,
+    /// The `csm` executable to run
+    pub exe: PathBuf,
+    /// Arguments after `api serve`
+    pub serve_args: Vec<String>,
+    /// Install for all users instead of the current one
+    pub system: bool,
+}
+
+impl ServiceSpec {
+    fn command_line(&self) -> Vec<String> {
+        let mut args = vec![
+            self.exe.to_string_lossy().to_string(),
+            "api".to_string(),
+            "serve".to_string(),
+        ];
+        args.extend(self.serve_args.iter().cloned());
+        args
+    }
+}
+
+/// Quote an argument for a systemd `ExecStart=` line
+fn systemd_quote(arg: &str) -> String {
+    // systemd expands % specifiers and $ variables even inside quotes
+    let escaped = arg.replace('%', "%%").replace('$', "$$");
+    if !escaped.is_empty()
+        && !escaped
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | ';'))
+    {
+        return escaped;
+    }
+    format!("\"{}\"", escaped.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// The systemd unit for `spec`
+pub fn systemd_unit(spec: &ServiceSpec) -> String {
+    let exec = spec
+        .command_line()
+        .iter()
+        .map(|arg| systemd_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let wanted_by = if spec.system {
+        "multi-user.target"
+    } else {
+        "default.target"
+    };
+    format!(
+        "[Unit]
+Description=Chasm API server
+After=network-online.target
+Wants=network-online.target
+
+[Service]
+Type=simple
+ExecStart={exec}
+Restart=on-failure
+RestartSec=5
+KillSignal=SIGTERM
+TimeoutStopSec=45
+
+[Install]
+WantedBy={wanted_by}
+"
+    )
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The launchd job for `spec`
+pub fn launchd_plist(spec: &ServiceSpec) -> String {
+    let arguments: String = spec
+        .command_line()
+        .iter()
+        .map(|arg| format!("        <string>{}</string>\n", xml_escape(arg)))
+        .collect();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>ExitTimeOut</key>
+    <integer>45</integer>
+</dict>
+</plist>
+"#,
+        label = xml_escape(&spec.name),
+    )
+}
+
+/// Where the unit or job file for a service lives
+fn definition_path(name: &str, system: bool) -> Result<PathBuf> {
+    if cfg!(target_os = "macos") {
+        if system {
+            return Ok(PathBuf::from("/Library/LaunchDaemons").join(format!("{}.plist", name)));
+        }
+        let home = dirs::home_dir().context("Could not find the home directory")?;
+        return Ok(home
+            .join("Library/LaunchAgents")
+            .join(format!("{}.plist", name)));
+    }
+    if system {
+        return Ok(PathBuf::from("/etc/systemd/system").join(format!("{}.service", name)));
+    }
+    let config = dirs::config_dir().context("Could not find the config directory")?;
+    Ok(config
+        .join("systemd/user")
+        .join(format!("{}.service", name)))
+}
+
+fn run(program: &str, args: &[&str]) -> Result<()> {
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !status.success() {
+        bail!("'{} {}' failed ({})", program, args.join(" "), status);
+    }
+    Ok(())
+}
+
+fn systemctl(system: bool, args: &[&str]) -> Result<()> {
+    let mut full = Vec::with_capacity(args.len() + 1);
+    if !system {
+        full.push("--user");
+    }
+    full.extend_from_slice(args);
+    run("systemctl", &full)
+}
+
+/// Install and start the service. With `dry_run`, print what would be
+/// installed instead.
+pub fn install(spec: &ServiceSpec, dry_run: bool) -> Result<()> {
+    if cfg!(windows) {
+        if dry_run {
+            println!("Service: {}", spec.name);
+            println!("Command: {}", spec.command_line().join(" "));
+            return Ok(());
+        }
+        #[cfg(windows)]
+        windows_service_impl::install(spec)?;
+        println!("[+] Installed and started Windows service '{}'", spec.name);
+        println!(
+            "   Remove it with: csm api uninstall-service --name {}",
+            spec.name
+        );
+        return Ok(());
+    }
+    if !cfg!(any(target_os = "linux", target_os = "macos")) {
+        bail!("Service installation is supported on Linux, macOS, and Windows");
+    }
+
+    let macos = cfg!(target_os = "macos");
+    let definition = if macos {
+        launchd_plist(spec)
+    } else {
+        systemd_unit(spec)
+    };
+    let path = definition_path(&spec.name, spec.system)?;
+    if dry_run {
+        println!("# {}", path.display());
+        print!("{}", definition);
+        return Ok(());
+    }
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, definition)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("[+] Wrote {}", path.display());
+
+    let path_str = path.to_string_lossy();
+    if macos {
+        run("launchctl", &["load", "-w", &path_str])?;
+        println!("[+] Loaded launchd job '{}'", spec.name);
+    } else {
+        let unit = format!("{}.service", spec.name);
+        systemctl(spec.system, &["daemon-reload"])?;
+        systemctl(spec.system, &["enable", "--now", &unit])?;
+        println!("[+] Enabled and started {}", unit);
+        let scope = if spec.system { "" } else { "--user " };
+        println!("   Status: systemctl {}status {}", scope, unit);
+        println!("   Logs:   journalctl {}-u {}", scope, unit);
+        if !spec.system {
+            println!("   [*] Run 'loginctl enable-linger' to keep it running after you log out");
+        }
+    }
+    println!(
+        "   Remove it with: csm api uninstall-service --name {}",
+        spec.name
+    );
+    Ok(())
+}
+
+/// Stop the service and remove its definition
+pub fn uninstall(name: &str, system: bool) -> Result<()> {
+    if cfg!(windows) {
+        #[cfg(windows)]
+        windows_service_impl::uninstall(name)?;
+        println!("[+] Removed Windows service '{}'", name);
+        return Ok(());
+    }
+
+    let path = definition_path(name, system)?;
+    if !path.exists() {
+        bail!("No service named '{}' at {}", name, path.display());
+    }
+    let path_str = path.to_string_lossy();
+    if cfg!(target_os = "macos") {
+        if let Err(e) = run("launchctl", &["unload", "-w", &path_str]) {
+            eprintln!("[WARN] {}", e);
+        }
+        std::fs::remove_file(&path)?;
+    } else {
+        let unit = format!("{}.service", name);
+        if let Err(e) = systemctl(system, &["disable", "--now", &unit]) {
+            eprintln!("[WARN] {}", e);
+        }
+        std::fs::remove_file(&path)?;
+        systemctl(system, &["daemon-reload"])?;
+    }
+    println!("[+] Removed {}", path.display());
+    Ok(())
+}
+
+/// Run the server under the Windows service manager, which starts the
+/// service with `csm api serve --service-name <name>`
+#[cfg(windows)]
+pub fn run_windows_service(name: &str, config: super::ServerConfig) -> Result<()> {
+    windows_service_impl::run(name, config)
+}
+
+#[cfg(windows)]
+mod windows_service_impl {
+    use super::ServiceSpec;
+    use crate::api::ServerConfig;
+    use anyhow::Result;
+    use std::ffi::OsString;
+    use std::sync::{Mutex, OnceLock};
+    use std::time::Duration;
+    use windows_service::service::{
+        ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode,
+        ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+    };
+    use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+    use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+    use windows_service::{define_windows_service, service_dispatcher};
+
+    /// The service manager calls `service_main` without arguments we control
+    static SERVICE: OnceLock<(String, ServerConfig)> = OnceLock::new();
+
+    define_windows_service!(ffi_service_main, service_main);
+
+    pub fn install(spec: &ServiceSpec) -> Result<()> {
+        let manager = ServiceManager::local_computer(
+            None::<&str>,
+            ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+        )?;
+        let mut launch_arguments: Vec<OsString> = vec!["api".into(), "serve".into()];
+        launch_arguments.extend(spec.serve_args.iter().map(OsString::from));
+        launch_arguments.push("--service-name".into());
+        launch_arguments.push(OsString::from(&spec.name));
+
+        let info = ServiceInfo {
+            name: OsString::from(&spec.name),
+            display_name: OsString::from("Chasm API server"),
+            service_type: ServiceType::OWN_PROCESS,
+            start_type: ServiceStartType::AutoStart,
+            error_control: ServiceErrorControl::Normal,
+            executable_path: spec.exe.clone(),
+            launch_arguments,
+            dependencies: vec![],
+            account_name: None,
+            account_password: None,
+        };
+        let service =
+            manager.create_service(&info, ServiceAccess::CHANGE_CONFIG | ServiceAccess::START)?;
+        service.set_description("Serves the chasm REST, GraphQL, and sync APIs")?;
+        service.start::<OsString>(&[])?;
+        Ok(())
+    }
+
+    pub fn uninstall(name: &str) -> Result<()> {
+        let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)?;
+        let service = manager.open_service(
+            name,
+            ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE,
+        )?;
+        if service.query_status()?.current_state != ServiceState::Stopped {
+            service.stop()?;
+        }
+        service.delete()?;
+        Ok(())
+    }
+
+    pub fn run(name: &str, config: ServerConfig) -> Result<()> {
+        let _ = SERVICE.set((name.to_string(), config));
+        service_dispatcher::start(name, ffi_service_main)?;
+        Ok(())
+    }
+
+    fn status(state: ServiceState, exit_code: u32) -> ServiceStatus {
+        ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state: state,
+            controls_accepted: if state == ServiceState::Running {
+                ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN
+            } else {
+                ServiceControlAccept::empty()
+            },
+            exit_code: ServiceExitCode::Win32(exit_code),
+            checkpoint: 0,
+            wait_hint: Duration::from_secs(45),
+            process_id: None,
+        }
+    }
+
+    fn service_main(_arguments: Vec<OsString>) {
+        if let Err(e) = run_service() {
+            eprintln!("[!] Service failed: {:#}", e);
+        }
+    }
+
+    fn run_service() -> Result<()> {
+        let (name, config) = SERVICE
+            .get()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Service configuration missing"))?;
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let stop_tx = Mutex::new(Some(stop_tx));
+
+        let handler = move |control| match control {
+            ServiceControl::Stop | ServiceControl::Shutdown => {
+                if let Some(tx) = stop_tx.lock().unwrap().take() {
+                    let _ = tx.send(());
+                }
+                ServiceControlHandlerResult::NoError
+            }
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            _ => ServiceControlHandlerResult::NotImplemented,
+        };
+        let status_handle = service_control_handler::register(&name, handler)?;
+        status_handle.set_service_status(status(ServiceState::Running, 0))?;
+
+        let result = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .map_err(anyhow::Error::from)
+            .and_then(|rt| {
+                rt.block_on(crate::api::start_server_until(config, async {
+                    let _ = stop_rx.await;
+                }))
+            });
+        if let Err(e) = &result {
+            eprintln!("[!] Server error: {:#}", e);
+        }
+
+        let exit_code = if result.is_ok() { 0 } else { 1 };
+        status_handle.set_service_status(status(ServiceState::Stopped, exit_code))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_definitions() {
+        let spec = ServiceSpec {
+            name: "chasm-api".to_string(),
+            exe: PathBuf::from("/opt/chasm/bin/chasm"),
+            serve_args: vec![
+                "--database".to_string(),
+                "/home/me/My Data/csm.db".to_string(),
+                "--port".to_string(),
+                "9000".to_string(),
+            ],
+            system: false,
+        };
+
+        let unit = systemd_unit(&spec);
+        assert!(unit.contains(
+            "ExecStart=/opt/chasm/bin/chasm api serve --database \"/home/me/My Data/csm.db\" --port 9000"
+        ));
+        assert!(unit.contains("WantedBy=default.target"));
+        assert_eq!(systemd_quote("50%"), "50%%");
+
+        let plist = launchd_plist(&spec);
+        assert!(plist.contains("<string>/home/me/My Data/csm.db</string>"));
+        assert!(plist.contains("<key>Label</key>\n    <string>chasm-api</string>"));
+        assert_eq!(xml_escape("a&b<c>"), "a&amp;b&lt;c&gt;");
+    }
+}
@@ -10,7 +10,7 @@ use crate::database::ChatDatabase;
 /// Shared application state
 pub struct AppState {
     pub db: Mutex<ChatDatabase>,
-    #[allow(dead_code)] // Reserved for future use (e.g., reopening database)
+    /// Path of the database, for work that needs a connection of its own
     pub db_path: PathBuf,
 }
 
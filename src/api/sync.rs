@@ -8,10 +8,15 @@
 //! Uses Server-Sent Events (SSE) for real-time push updates instead of
 //! WebSockets for better compatibility with various deployment scenarios.
 
+use super::conditional::weak_etag;
+use super::docs::ApiEnvelope;
+use crate::storage::watcher::{SessionEventKind, SessionWatcher};
+use actix_web::http::header::{self, ContentEncoding};
 use actix_web::{web, HttpResponse};
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, RwLock};
 use tokio::sync::broadcast;
+use utoipa::IntoParams;
 
 // =============================================================================
 // Sync Message Types
@@ -183,17 +188,79 @@ pub fn create_sync_state() -> SharedSyncState {
     Arc::new(RwLock::new(SyncState::new()))
 }
 
+/// Publish a change to a harvested session made through the REST API
+pub fn publish_session_change(
+    sync_state: &SharedSyncState,
+    operation: SyncOperation,
+    session_id: &str,
+    data: Option<serde_json::Value>,
+) {
+    if let Ok(mut state) = sync_state.write() {
+        state.add_event(SyncEvent {
+            id: uuid::Uuid::new_v4().to_string(),
+            entity_type: SyncEntityType::Session,
+            operation,
+            entity_id: session_id.to_string(),
+            data,
+            timestamp: 0,
+            client_id: "api".to_string(),
+            version: 0,
+        });
+    }
+}
+
+/// Publish session file changes from VS Code's workspaceStorage to SSE subscribers
+pub fn spawn_session_watcher(
+    sync_state: SharedSyncState,
+) -> crate::error::Result<std::thread::JoinHandle<()>> {
+    let watcher = SessionWatcher::new()?;
+
+    let handle = std::thread::Builder::new()
+        .name("csm-sync-watcher".to_string())
+        .spawn(move || {
+            for event in watcher {
+                let operation = match event.kind {
+                    SessionEventKind::Created => SyncOperation::Create,
+                    SessionEventKind::Modified => SyncOperation::Update,
+                    SessionEventKind::Deleted => SyncOperation::Delete,
+                };
+                let sync_event = SyncEvent {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    entity_type: SyncEntityType::Session,
+                    operation,
+                    entity_id: event.session_id.clone(),
+                    data: serde_json::to_value(&event).ok(),
+                    timestamp: 0,
+                    client_id: "watcher".to_string(),
+                    version: 0,
+                };
+                if let Ok(mut state) = sync_state.write() {
+                    state.add_event(sync_event);
+                }
+            }
+        })?;
+
+    Ok(handle)
+}
+
 // =============================================================================
 // HTTP/REST Sync Endpoints
 // =============================================================================
 
 /// Query parameters for delta request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct DeltaQuery {
     pub from: Option<u64>,
 }
 
 /// Get current sync version
+#[utoipa::path(
+    get,
+    path = "/sync/version",
+    tag = "sync",
+    responses((status = 200, description = "Current sync version and event count", body = ApiEnvelope))
+)]
 pub async fn get_sync_version(sync_state: web::Data<SharedSyncState>) -> HttpResponse {
     let state = sync_state.read().unwrap();
     HttpResponse::Ok().json(serde_json::json!({
@@ -206,6 +273,13 @@ pub async fn get_sync_version(sync_state: web::Data<SharedSyncState>) -> HttpRes
 }
 
 /// Get sync delta since a version
+#[utoipa::path(
+    get,
+    path = "/sync/delta",
+    tag = "sync",
+    params(DeltaQuery),
+    responses((status = 200, description = "Events since the requested version", body = ApiEnvelope))
+)]
 pub async fn get_sync_delta(
     sync_state: web::Data<SharedSyncState>,
     query: web::Query<DeltaQuery>,
@@ -222,6 +296,13 @@ pub async fn get_sync_delta(
 }
 
 /// Post a sync event
+#[utoipa::path(
+    post,
+    path = "/sync/event",
+    tag = "sync",
+    request_body = Object,
+    responses((status = 200, description = "Version assigned to the event", body = ApiEnvelope))
+)]
 pub async fn post_sync_event(
     sync_state: web::Data<SharedSyncState>,
     body: web::Json<SyncEvent>,
@@ -244,6 +325,13 @@ pub struct BatchSyncRequest {
 }
 
 /// Post multiple sync events
+#[utoipa::path(
+    post,
+    path = "/sync/batch",
+    tag = "sync",
+    request_body = Object,
+    responses((status = 200, description = "Version of the last event", body = ApiEnvelope))
+)]
 pub async fn post_sync_batch(
     sync_state: web::Data<SharedSyncState>,
     body: web::Json<BatchSyncRequest>,
@@ -264,6 +352,12 @@ pub async fn post_sync_batch(
 }
 
 /// Get full snapshot
+#[utoipa::path(
+    get,
+    path = "/sync/snapshot",
+    tag = "sync",
+    responses((status = 200, description = "Workspaces, sessions, and the current version", body = ApiEnvelope))
+)]
 pub async fn get_sync_snapshot(
     sync_state: web::Data<SharedSyncState>,
     app_state: web::Data<crate::api::state::AppState>,
@@ -297,20 +391,25 @@ pub async fn get_sync_snapshot(
     // (clients should call /api/providers for the full list)
     let providers: Vec<serde_json::Value> = Vec::new();
 
-    let snapshot = SyncSnapshot {
+    let mut snapshot = SyncSnapshot {
         workspaces,
         sessions,
         agents,
         swarms,
         providers,
-        timestamp: chrono::Utc::now().timestamp_millis(),
+        timestamp: 0,
         version: sync.version,
     };
+    // Tag the content without the timestamp, which changes on every request
+    let etag = weak_etag(&serde_json::to_vec(&snapshot).unwrap_or_default());
+    snapshot.timestamp = chrono::Utc::now().timestamp_millis();
 
-    HttpResponse::Ok().json(serde_json::json!({
-        "success": true,
-        "data": snapshot,
-    }))
+    HttpResponse::Ok()
+        .insert_header((header::ETAG, etag))
+        .json(serde_json::json!({
+            "success": true,
+            "data": snapshot,
+        }))
 }
 
 /// Query agents directly from database
@@ -423,6 +522,12 @@ fn query_swarms_from_db(
 }
 
 /// Subscribe to sync events via Server-Sent Events (SSE)
+#[utoipa::path(
+    get,
+    path = "/sync/subscribe",
+    tag = "sync",
+    responses((status = 200, description = "Stream of sync events", body = String, content_type = "text/event-stream"))
+)]
 pub async fn sync_sse(sync_state: web::Data<SharedSyncState>) -> HttpResponse {
     let state = sync_state.read().unwrap();
     let mut rx = state.subscribe();
@@ -455,10 +560,12 @@ pub async fn sync_sse(sync_state: web::Data<SharedSyncState>) -> HttpResponse {
         }
     };
 
+    // Compressing would hold events back until the encoder's buffer fills
     HttpResponse::Ok()
         .content_type("text/event-stream")
         .insert_header(("Cache-Control", "no-cache"))
         .insert_header(("Connection", "keep-alive"))
+        .insert_header(ContentEncoding::Identity)
         .streaming(stream)
 }
 
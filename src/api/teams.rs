@@ -0,0 +1,924 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Team workspaces
+//!
+//! Teams let signed-in users share sessions. The owner of a session assigns
+//! it to one of their teams with `PUT /api/sessions/{id}/team`, and from then
+//! on the team's members see it in their session lists, searches, and
+//! workspace counts, over REST, GraphQL, and gRPC alike. Members can read a
+//! shared session; only its owner can change or delete it.
+//!
+//! Each member has a team role from `teams::rbac`, separate from the role of
+//! their credential: team owners and admins add members and change their
+//! roles, members and above share sessions, and everyone on the team can
+//! read its activity feed, which records membership changes, sharing, and
+//! edits to shared sessions. Admin-scoped keys act as the owner of every
+//! team.
+
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use super::access::Scope;
+use super::docs::ApiEnvelope;
+use super::handlers_simple::owns_session;
+use super::keys::find_user;
+use super::state::AppState;
+use crate::commands::ensure_deleted_sessions_table;
+use chasm::teams::{
+    member_event, session_event, team_event, ActivityEvent, EventType, MemberStatus, Permission,
+    Role, TeamMember, TeamSettings, TeamWorkspace,
+};
+
+/// Most activity events returned at once
+const MAX_ACTIVITY: usize = 500;
+
+// ============================================================================
+// Storage
+// ============================================================================
+
+/// Create the team, membership, and activity tables if they do not exist
+pub fn init_team_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS teams (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT,
+            owner_id TEXT NOT NULL,
+            settings TEXT NOT NULL DEFAULT '{}',
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS team_members (
+            team_id TEXT NOT NULL REFERENCES teams(id) ON DELETE CASCADE,
+            user_id TEXT NOT NULL,
+            role TEXT NOT NULL,
+            joined_at INTEGER NOT NULL,
+            PRIMARY KEY (team_id, user_id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_team_members_user ON team_members(user_id);
+        CREATE TABLE IF NOT EXISTS team_activity (
+            id TEXT PRIMARY KEY,
+            team_id TEXT NOT NULL REFERENCES teams(id) ON DELETE CASCADE,
+            event TEXT NOT NULL,
+            timestamp INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_team_activity_team
+            ON team_activity(team_id, timestamp);",
+    )
+}
+
+/// IDs in our tables are UUID strings; anything else maps to the nil UUID
+fn uuid(id: &str) -> Uuid {
+    Uuid::parse_str(id).unwrap_or_default()
+}
+
+fn time(secs: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(secs, 0).unwrap_or_default()
+}
+
+fn members(conn: &Connection, team_id: &str) -> rusqlite::Result<Vec<TeamMember>> {
+    let mut stmt = conn.prepare(
+        "SELECT m.user_id, m.role, m.joined_at, u.display_name, u.email, u.avatar_url
+         FROM team_members m LEFT JOIN users u ON u.id = m.user_id
+         WHERE m.team_id = ?1
+         ORDER BY m.joined_at, m.user_id",
+    )?;
+    let members = stmt
+        .query_map([team_id], |row| {
+            let role: String = row.get(1)?;
+            Ok(TeamMember {
+                user_id: uuid(&row.get::<_, String>(0)?),
+                role: role.parse().unwrap_or(Role::Member),
+                joined_at: time(row.get(2)?),
+                display_name: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                email: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                avatar_url: row.get(5)?,
+                custom_permissions: None,
+                last_active: None,
+                status: MemberStatus::Active,
+            })
+        })?
+        .collect();
+    members
+}
+
+/// A team with its members and the IDs of the sessions shared with it
+fn load_team(conn: &Connection, team_id: &str) -> rusqlite::Result<Option<TeamWorkspace>> {
+    let team = conn
+        .query_row(
+            "SELECT name, description, owner_id, settings, created_at, updated_at
+             FROM teams WHERE id = ?1",
+            [team_id],
+            |row| {
+                let owner_id: String = row.get(2)?;
+                let settings: String = row.get(3)?;
+                Ok(TeamWorkspace {
+                    id: uuid(team_id),
+                    name: row.get(0)?,
+                    description: row.get(1)?,
+                    avatar_url: None,
+                    owner_id: uuid(&owner_id),
+                    settings: serde_json::from_str(&settings).unwrap_or_default(),
+                    members: Vec::new(),
+                    shared_sessions: Vec::new(),
+                    created_at: time(row.get(4)?),
+                    updated_at: time(row.get(5)?),
+                })
+            },
+        )
+        .optional()?;
+    let Some(mut team) = team else {
+        return Ok(None);
+    };
+    team.members = members(conn, team_id)?;
+    let mut stmt =
+        conn.prepare("SELECT id FROM sessions WHERE team_id = ?1 ORDER BY updated_at DESC")?;
+    team.shared_sessions = stmt
+        .query_map([team_id], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(Some(team))
+}
+
+/// Teams the caller can see: all of them for admin keys, otherwise those the
+/// caller belongs to
+fn list(conn: &Connection, scope: &Scope) -> rusqlite::Result<Vec<TeamWorkspace>> {
+    let mut stmt = conn.prepare(
+        "SELECT id FROM teams
+         WHERE ?1 OR id IN (SELECT team_id FROM team_members WHERE user_id = ?2)
+         ORDER BY name, id",
+    )?;
+    let ids = stmt
+        .query_map(params![scope.is_admin(), scope.owner()], |row| {
+            row.get::<_, String>(0)
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let mut teams = Vec::new();
+    for id in ids {
+        teams.extend(load_team(conn, &id)?);
+    }
+    Ok(teams)
+}
+
+fn member_role(conn: &Connection, team_id: &str, user_id: &str) -> rusqlite::Result<Option<Role>> {
+    let role: Option<String> = conn
+        .query_row(
+            "SELECT role FROM team_members WHERE team_id = ?1 AND user_id = ?2",
+            [team_id, user_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(role.map(|role| role.parse().unwrap_or(Role::Member)))
+}
+
+/// The caller's role in a team, or `None` if they are not on it
+fn caller_role(conn: &Connection, team_id: &str, scope: &Scope) -> rusqlite::Result<Option<Role>> {
+    if scope.is_admin() {
+        return Ok(Some(Role::Owner));
+    }
+    match scope.owner() {
+        Some(user_id) => member_role(conn, team_id, user_id),
+        None => Ok(None),
+    }
+}
+
+/// Who an activity event is attributed to
+fn actor(conn: &Connection, scope: &Scope) -> (Uuid, String) {
+    let Some(user_id) = scope.owner() else {
+        let name = if scope.is_admin() {
+            "admin"
+        } else {
+            "anonymous"
+        };
+        return (Uuid::nil(), name.to_string());
+    };
+    let name: Option<String> = conn
+        .query_row(
+            "SELECT display_name FROM users WHERE id = ?1",
+            [user_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .ok()
+        .flatten();
+    (uuid(user_id), name.unwrap_or_else(|| user_id.to_string()))
+}
+
+/// Add an event to a team's feed. The team's ID is passed as stored, since
+/// `event.team_id` only holds it if it is a UUID.
+fn record(conn: &Connection, team_id: &str, event: &ActivityEvent) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO team_activity (id, team_id, event, timestamp) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            event.id.to_string(),
+            team_id,
+            serde_json::to_string(event).unwrap_or_default(),
+            event.timestamp.timestamp_millis(),
+        ],
+    )?;
+    Ok(())
+}
+
+fn record_or_warn(conn: &Connection, team_id: &str, event: &ActivityEvent) {
+    if let Err(e) = record(conn, team_id, event) {
+        eprintln!("[WARN] Failed to record team activity: {}", e);
+    }
+}
+
+fn session_activity(
+    conn: &Connection,
+    scope: &Scope,
+    team_id: &str,
+    session_id: &str,
+    event_type: EventType,
+) -> rusqlite::Result<()> {
+    let (title, provider): (String, Option<String>) = conn.query_row(
+        "SELECT title, provider FROM sessions WHERE id = ?1",
+        [session_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let (actor_id, actor_name) = actor(conn, scope);
+    record(
+        conn,
+        team_id,
+        &session_event(
+            uuid(team_id),
+            actor_id,
+            actor_name,
+            event_type,
+            session_id.to_string(),
+            title,
+            provider,
+        ),
+    )
+}
+
+/// Record a change to a session in the activity feed of the team it is
+/// shared with, if any. Call before deleting the session.
+pub(super) fn record_session_activity(
+    conn: &Connection,
+    scope: &Scope,
+    session_id: &str,
+    event_type: EventType,
+) {
+    let team_id: Option<String> = conn
+        .query_row(
+            "SELECT team_id FROM sessions WHERE id = ?1",
+            [session_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .ok()
+        .flatten()
+        .flatten();
+    if let Some(team_id) = team_id {
+        if let Err(e) = session_activity(conn, scope, &team_id, session_id, event_type) {
+            eprintln!("[WARN] Failed to record team activity: {}", e);
+        }
+    }
+}
+
+fn activity(
+    conn: &Connection,
+    team_id: &str,
+    limit: usize,
+) -> rusqlite::Result<Vec<ActivityEvent>> {
+    let mut stmt = conn.prepare(
+        "SELECT event FROM team_activity WHERE team_id = ?1
+         ORDER BY timestamp DESC, rowid DESC LIMIT ?2",
+    )?;
+    let events = stmt
+        .query_map(params![team_id, limit as i64], |row| {
+            row.get::<_, String>(0)
+        })?
+        .filter_map(|event| match event {
+            Ok(event) => serde_json::from_str(&event).ok().map(Ok),
+            Err(e) => Some(Err(e)),
+        })
+        .collect();
+    events
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// A team to create
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateTeamRequest {
+    pub name: String,
+    pub description: Option<String>,
+    /// Email or ID of the user who owns the team. Only admin keys, which do
+    /// not belong to a user, may name one; other callers own the teams they
+    /// create.
+    pub owner: Option<String>,
+}
+
+/// A member's team role
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetMemberRequest {
+    /// `admin`, `member`, `viewer`, or `guest`; new members get the team's
+    /// default role when omitted
+    pub role: Option<String>,
+}
+
+/// The team to share a session with
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ShareSessionRequest {
+    /// Team ID, or null to stop sharing the session
+    pub team_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ActivityQuery {
+    /// Most recent events to return (default 50, at most 500)
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct SharedSession {
+    id: String,
+    team_id: Option<String>,
+}
+
+fn success(status: StatusCode, data: impl Serialize) -> HttpResponse {
+    HttpResponse::build(status).json(serde_json::json!({
+        "success": true,
+        "data": data,
+    }))
+}
+
+fn failure(status: StatusCode, message: &str) -> HttpResponse {
+    HttpResponse::build(status).json(serde_json::json!({
+        "success": false,
+        "error": message,
+    }))
+}
+
+fn internal(e: impl std::fmt::Display) -> HttpResponse {
+    failure(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string())
+}
+
+/// Check the caller's team role: `404` if they are not on the team (or it
+/// does not exist), `403` if their role lacks `permission`
+fn authorize(
+    conn: &Connection,
+    team_id: &str,
+    scope: &Scope,
+    permission: Permission,
+) -> Result<Role, HttpResponse> {
+    let exists = conn
+        .query_row("SELECT 1 FROM teams WHERE id = ?1", [team_id], |_| Ok(()))
+        .optional()
+        .map_err(internal)?
+        .is_some();
+    let role = caller_role(conn, team_id, scope).map_err(internal)?;
+    match role {
+        Some(role) if exists => {
+            if role.has_permission(permission) {
+                Ok(role)
+            } else {
+                Err(failure(
+                    StatusCode::FORBIDDEN,
+                    &format!(
+                        "The team {} role does not have permission to {}",
+                        role,
+                        permission.description().to_lowercase()
+                    ),
+                ))
+            }
+        }
+        _ => Err(failure(StatusCode::NOT_FOUND, "Team not found")),
+    }
+}
+
+fn team_response(conn: &Connection, team_id: &str, status: StatusCode) -> HttpResponse {
+    match load_team(conn, team_id) {
+        Ok(Some(team)) => success(status, team),
+        Ok(None) => failure(StatusCode::NOT_FOUND, "Team not found"),
+        Err(e) => internal(e),
+    }
+}
+
+/// List the caller's teams
+#[utoipa::path(
+    get,
+    path = "/api/teams",
+    tag = "teams",
+    responses((status = 200, description = "Teams with their members and shared sessions", body = ApiEnvelope))
+)]
+pub async fn list_teams(state: web::Data<AppState>, scope: Scope) -> HttpResponse {
+    let db = state.db.lock().unwrap();
+    match list(&db.conn, &scope) {
+        Ok(teams) => success(StatusCode::OK, teams),
+        Err(e) => internal(e),
+    }
+}
+
+/// Create a team, owned by the caller
+#[utoipa::path(
+    post,
+    path = "/api/teams",
+    tag = "teams",
+    request_body = CreateTeamRequest,
+    responses(
+        (status = 201, description = "Created team", body = ApiEnvelope),
+        (status = 400, description = "No name, or no owner for an admin key"),
+        (status = 404, description = "The named owner does not exist")
+    )
+)]
+pub async fn create_team(
+    state: web::Data<AppState>,
+    body: web::Json<CreateTeamRequest>,
+    scope: Scope,
+) -> HttpResponse {
+    let body = body.into_inner();
+    if body.name.trim().is_empty() {
+        return failure(StatusCode::BAD_REQUEST, "Give the team a name");
+    }
+    let db = state.db.lock().unwrap();
+    let owner_id = match (scope.owner(), body.owner) {
+        (Some(user_id), _) => user_id.to_string(),
+        (None, Some(owner)) if scope.is_admin() => match find_user(&db.conn, &owner) {
+            Ok(user_id) => user_id,
+            Err(e) => return failure(StatusCode::NOT_FOUND, &e.to_string()),
+        },
+        _ => {
+            return failure(
+                StatusCode::BAD_REQUEST,
+                "Name the user who owns the team in `owner`",
+            )
+        }
+    };
+
+    let team_id = Uuid::new_v4().to_string();
+    let now = Utc::now().timestamp();
+    let result = (|| -> rusqlite::Result<()> {
+        let tx = db.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO teams (id, name, description, owner_id, settings, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+            params![
+                team_id,
+                body.name.trim(),
+                body.description,
+                owner_id,
+                serde_json::to_string(&TeamSettings::default()).unwrap_or_default(),
+                now,
+            ],
+        )?;
+        tx.execute(
+            "INSERT INTO team_members (team_id, user_id, role, joined_at) VALUES (?1, ?2, ?3, ?4)",
+            params![team_id, owner_id, Role::Owner.as_str(), now],
+        )?;
+        let (actor_id, actor_name) = actor(&tx, &scope);
+        record(
+            &tx,
+            &team_id,
+            &team_event(
+                uuid(&team_id),
+                actor_id,
+                actor_name,
+                EventType::TeamCreated,
+                body.name.trim().to_string(),
+                None,
+            ),
+        )?;
+        tx.commit()
+    })();
+    match result {
+        Ok(()) => team_response(&db.conn, &team_id, StatusCode::CREATED),
+        Err(e) => internal(e),
+    }
+}
+
+/// Get a team the caller belongs to
+#[utoipa::path(
+    get,
+    path = "/api/teams/{id}",
+    tag = "teams",
+    params(("id" = String, Path, description = "Team ID")),
+    responses(
+        (status = 200, description = "Team with its members and shared sessions", body = ApiEnvelope),
+        (status = 404, description = "No such team, or the caller is not on it")
+    )
+)]
+pub async fn get_team(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    scope: Scope,
+) -> HttpResponse {
+    let db = state.db.lock().unwrap();
+    if let Err(response) = authorize(&db.conn, &path, &scope, Permission::ViewTeam) {
+        return response;
+    }
+    team_response(&db.conn, &path, StatusCode::OK)
+}
+
+/// Delete a team. Its shared sessions go back to being private to their
+/// owners.
+#[utoipa::path(
+    delete,
+    path = "/api/teams/{id}",
+    tag = "teams",
+    params(("id" = String, Path, description = "Team ID")),
+    responses(
+        (status = 200, description = "Team deleted", body = ApiEnvelope),
+        (status = 403, description = "Only the team's owner can delete it"),
+        (status = 404, description = "No such team, or the caller is not on it")
+    )
+)]
+pub async fn delete_team(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    scope: Scope,
+) -> HttpResponse {
+    let team_id = path.into_inner();
+    let db = state.db.lock().unwrap();
+    if let Err(response) = authorize(&db.conn, &team_id, &scope, Permission::DeleteTeam) {
+        return response;
+    }
+    let result = (|| -> anyhow::Result<()> {
+        ensure_deleted_sessions_table(&db.conn)?;
+        let tx = db.conn.unchecked_transaction()?;
+        for table in ["sessions", "deleted_sessions"] {
+            tx.execute(
+                &format!("UPDATE {} SET team_id = NULL WHERE team_id = ?1", table),
+                [&team_id],
+            )?;
+        }
+        for table in ["team_activity", "team_members"] {
+            tx.execute(
+                &format!("DELETE FROM {} WHERE team_id = ?1", table),
+                [&team_id],
+            )?;
+        }
+        tx.execute("DELETE FROM teams WHERE id = ?1", [&team_id])?;
+        Ok(tx.commit()?)
+    })();
+    match result {
+        Ok(()) => success(
+            StatusCode::OK,
+            serde_json::json!({ "id": team_id, "deleted": true }),
+        ),
+        Err(e) => internal(e),
+    }
+}
+
+/// Add a member to a team or change their role
+#[utoipa::path(
+    put,
+    path = "/api/teams/{id}/members/{user}",
+    tag = "teams",
+    params(
+        ("id" = String, Path, description = "Team ID"),
+        ("user" = String, Path, description = "Email or ID of the user")
+    ),
+    request_body = SetMemberRequest,
+    responses(
+        (status = 200, description = "Team with its members", body = ApiEnvelope),
+        (status = 400, description = "Unknown role, or a change to the team's owner"),
+        (status = 403, description = "The caller's team role cannot manage members"),
+        (status = 404, description = "No such team or user")
+    )
+)]
+pub async fn set_member(
+    state: web::Data<AppState>,
+    path: web::Path<(String, String)>,
+    body: web::Json<SetMemberRequest>,
+    scope: Scope,
+) -> HttpResponse {
+    let (team_id, user) = path.into_inner();
+    let db = state.db.lock().unwrap();
+    let conn = &db.conn;
+    if let Err(response) = authorize(conn, &team_id, &scope, Permission::ViewTeam) {
+        return response;
+    }
+    let user_id = match find_user(conn, &user) {
+        Ok(user_id) => user_id,
+        Err(e) => return failure(StatusCode::NOT_FOUND, &e.to_string()),
+    };
+    let team = match load_team(conn, &team_id) {
+        Ok(Some(team)) => team,
+        Ok(None) => return failure(StatusCode::NOT_FOUND, "Team not found"),
+        Err(e) => return internal(e),
+    };
+    let previous = match member_role(conn, &team_id, &user_id) {
+        Ok(previous) => previous,
+        Err(e) => return internal(e),
+    };
+    let permission = if previous.is_some() {
+        Permission::EditMemberRoles
+    } else {
+        Permission::InviteMembers
+    };
+    if let Err(response) = authorize(conn, &team_id, &scope, permission) {
+        return response;
+    }
+    let role = match body.role.as_deref().map(str::parse::<Role>) {
+        Some(Ok(role)) => role,
+        Some(Err(e)) => return failure(StatusCode::BAD_REQUEST, &e.to_string()),
+        None => previous.unwrap_or(team.settings.default_member_role),
+    };
+    if role == Role::Owner || previous == Some(Role::Owner) {
+        return failure(
+            StatusCode::BAD_REQUEST,
+            "A team has one owner, whose role cannot be changed",
+        );
+    }
+
+    let result = conn.execute(
+        "INSERT INTO team_members (team_id, user_id, role, joined_at) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(team_id, user_id) DO UPDATE SET role = excluded.role",
+        params![team_id, user_id, role.as_str(), Utc::now().timestamp()],
+    );
+    if let Err(e) = result {
+        return internal(e);
+    }
+    if previous != Some(role) {
+        let member = team.members.iter().find(|m| m.user_id == uuid(&user_id));
+        let (name, email) = match member {
+            Some(member) => (member.display_name.clone(), member.email.clone()),
+            None => conn
+                .query_row(
+                    "SELECT display_name, email FROM users WHERE id = ?1",
+                    [&user_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .unwrap_or_default(),
+        };
+        let (actor_id, actor_name) = actor(conn, &scope);
+        let event_type = if previous.is_some() {
+            EventType::RoleChanged
+        } else {
+            EventType::MemberJoined
+        };
+        record_or_warn(
+            conn,
+            &team_id,
+            &member_event(
+                team.id,
+                actor_id,
+                actor_name,
+                event_type,
+                uuid(&user_id),
+                name,
+                Some(email),
+                Some(role.to_string()),
+                previous.map(|role| role.to_string()),
+            ),
+        );
+    }
+    team_response(conn, &team_id, StatusCode::OK)
+}
+
+/// Remove a member from a team. Members can always remove themselves.
+#[utoipa::path(
+    delete,
+    path = "/api/teams/{id}/members/{user}",
+    tag = "teams",
+    params(
+        ("id" = String, Path, description = "Team ID"),
+        ("user" = String, Path, description = "Email or ID of the user")
+    ),
+    responses(
+        (status = 200, description = "Team with its members", body = ApiEnvelope),
+        (status = 400, description = "The team's owner cannot leave it"),
+        (status = 403, description = "The caller's team role cannot remove members"),
+        (status = 404, description = "No such team or member")
+    )
+)]
+pub async fn remove_member(
+    state: web::Data<AppState>,
+    path: web::Path<(String, String)>,
+    scope: Scope,
+) -> HttpResponse {
+    let (team_id, user) = path.into_inner();
+    let db = state.db.lock().unwrap();
+    let conn = &db.conn;
+    if let Err(response) = authorize(conn, &team_id, &scope, Permission::ViewTeam) {
+        return response;
+    }
+    let member = match find_user(conn, &user) {
+        Ok(user_id) => member_role(conn, &team_id, &user_id).map(|role| role.map(|r| (user_id, r))),
+        Err(_) => Ok(None),
+    };
+    let (user_id, role) = match member {
+        Ok(Some(member)) => member,
+        Ok(None) => return failure(StatusCode::NOT_FOUND, "No such member"),
+        Err(e) => return internal(e),
+    };
+    let leaving = scope.owner() == Some(user_id.as_str());
+    if !leaving {
+        if let Err(response) = authorize(conn, &team_id, &scope, Permission::RemoveMembers) {
+            return response;
+        }
+    }
+    if role == Role::Owner {
+        return failure(
+            StatusCode::BAD_REQUEST,
+            "The team's owner cannot leave it; delete the team instead",
+        );
+    }
+
+    let removed = conn.execute(
+        "DELETE FROM team_members WHERE team_id = ?1 AND user_id = ?2",
+        [&team_id, &user_id],
+    );
+    if let Err(e) = removed {
+        return internal(e);
+    }
+    let (name, email): (String, String) = conn
+        .query_row(
+            "SELECT display_name, email FROM users WHERE id = ?1",
+            [&user_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap_or_default();
+    let (actor_id, actor_name) = actor(conn, &scope);
+    let event_type = if leaving {
+        EventType::MemberLeft
+    } else {
+        EventType::MemberRemoved
+    };
+    record_or_warn(
+        conn,
+        &team_id,
+        &member_event(
+            uuid(&team_id),
+            actor_id,
+            actor_name,
+            event_type,
+            uuid(&user_id),
+            name,
+            Some(email),
+            None,
+            Some(role.to_string()),
+        ),
+    );
+    team_response(conn, &team_id, StatusCode::OK)
+}
+
+/// A team's recent activity, newest first
+#[utoipa::path(
+    get,
+    path = "/api/teams/{id}/activity",
+    tag = "teams",
+    params(("id" = String, Path, description = "Team ID"), ActivityQuery),
+    responses(
+        (status = 200, description = "Activity events", body = ApiEnvelope),
+        (status = 404, description = "No such team, or the caller is not on it")
+    )
+)]
+pub async fn get_team_activity(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<ActivityQuery>,
+    scope: Scope,
+) -> HttpResponse {
+    let db = state.db.lock().unwrap();
+    if let Err(response) = authorize(&db.conn, &path, &scope, Permission::ViewActivityFeed) {
+        return response;
+    }
+    let limit = query.limit.unwrap_or(50).clamp(1, MAX_ACTIVITY);
+    match activity(&db.conn, &path, limit) {
+        Ok(events) => success(StatusCode::OK, events),
+        Err(e) => internal(e),
+    }
+}
+
+/// Share a session with a team, or stop sharing it
+#[utoipa::path(
+    put,
+    path = "/api/sessions/{id}/team",
+    tag = "teams",
+    params(("id" = String, Path, description = "Session ID")),
+    request_body = ShareSessionRequest,
+    responses(
+        (status = 200, description = "The session's team", body = ApiEnvelope),
+        (status = 403, description = "The caller's team role cannot share sessions"),
+        (status = 404, description = "No such session owned by the caller, or no such team")
+    )
+)]
+pub async fn share_session(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    body: web::Json<ShareSessionRequest>,
+    scope: Scope,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let team_id = body.into_inner().team_id.filter(|id| !id.is_empty());
+    let db = state.db.lock().unwrap();
+    let conn = &db.conn;
+    match owns_session(conn, "sessions", &session_id, &scope) {
+        Ok(true) => {}
+        Ok(false) => return failure(StatusCode::NOT_FOUND, "Session not found"),
+        Err(e) => return internal(e),
+    }
+    if let Some(team_id) = &team_id {
+        if let Err(response) = authorize(conn, team_id, &scope, Permission::ShareSessions) {
+            return response;
+        }
+    }
+    let previous: Option<String> = match conn.query_row(
+        "SELECT team_id FROM sessions WHERE id = ?1",
+        [&session_id],
+        |row| row.get(0),
+    ) {
+        Ok(previous) => previous,
+        Err(e) => return internal(e),
+    };
+    if previous == team_id {
+        return success(
+            StatusCode::OK,
+            SharedSession {
+                id: session_id,
+                team_id,
+            },
+        );
+    }
+
+    // The old team sees the session leave as an update to it
+    if previous.is_some() {
+        record_session_activity(conn, &scope, &session_id, EventType::SessionUpdated);
+    }
+    if let Err(e) = conn.execute(
+        "UPDATE sessions SET team_id = ?2 WHERE id = ?1",
+        params![session_id, team_id],
+    ) {
+        return internal(e);
+    }
+    if team_id.is_some() {
+        record_session_activity(conn, &scope, &session_id, EventType::SessionShared);
+    }
+    success(
+        StatusCode::OK,
+        SharedSession {
+            id: session_id,
+            team_id,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_sessions_are_readable_by_members() {
+        let conn = Connection::open_in_memory().unwrap();
+        super::super::auth::init_auth_tables(&conn).unwrap();
+        init_team_tables(&conn).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE sessions (id TEXT PRIMARY KEY, title TEXT NOT NULL, provider TEXT,
+                                    updated_at INTEGER, user_id TEXT, team_id TEXT);
+             INSERT INTO teams (id, name, owner_id, created_at, updated_at)
+                 VALUES ('t1', 'Platform', 'u1', 0, 0);
+             INSERT INTO team_members VALUES ('t1', 'u1', 'owner', 0), ('t1', 'u2', 'viewer', 1);
+             INSERT INTO sessions VALUES ('s1', 'Shared', 'copilot', 0, 'u1', 't1'),
+                                         ('s2', 'Private', 'copilot', 0, 'u1', NULL);",
+        )
+        .unwrap();
+
+        let readable = |user: &str| -> Vec<String> {
+            let scope = Scope::Owner(Some(user.to_string()));
+            let mut stmt = conn
+                .prepare(&format!(
+                    "SELECT id FROM sessions WHERE {} ORDER BY id",
+                    scope.readable("sessions")
+                ))
+                .unwrap();
+            let ids = stmt.query_map([], |row| row.get(0)).unwrap();
+            ids.map(Result::unwrap).collect()
+        };
+        assert_eq!(readable("u1"), ["s1", "s2"]);
+        assert_eq!(readable("u2"), ["s1"]);
+        assert!(readable("u3").is_empty());
+
+        let viewer = Scope::Owner(Some("u2".to_string()));
+        assert_eq!(
+            caller_role(&conn, "t1", &viewer).unwrap(),
+            Some(Role::Viewer)
+        );
+        assert!(authorize(&conn, "t1", &viewer, Permission::ShareSessions).is_err());
+        assert!(authorize(&conn, "t1", &viewer, Permission::ViewActivityFeed).is_ok());
+        let outsider = Scope::Owner(Some("u3".to_string()));
+        assert!(authorize(&conn, "t1", &outsider, Permission::ViewTeam).is_err());
+
+        let team = load_team(&conn, "t1").unwrap().unwrap();
+        assert_eq!(team.members.len(), 2);
+        assert_eq!(team.shared_sessions, ["s1"]);
+
+        let owner = Scope::Owner(Some("u1".to_string()));
+        record_session_activity(&conn, &owner, "s1", EventType::SessionUpdated);
+        record_session_activity(&conn, &owner, "s2", EventType::SessionUpdated);
+        let events = activity(&conn, "t1", 10).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, EventType::SessionUpdated);
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! TLS for the API server
+//!
+//! `csm api serve --tls-cert <pem> --tls-key <pem>` serves HTTPS with an
+//! existing certificate. `--tls-self-signed` creates a certificate next to the
+//! database on first use and reuses it afterwards, so clients only need to
+//! trust it once.
+
+use anyhow::{Context, Result};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Where the server's certificate comes from
+#[derive(Debug, Clone)]
+pub enum TlsSource {
+    /// PEM certificate chain and private key files
+    Files { cert: PathBuf, key: PathBuf },
+    /// A generated certificate stored beside the database
+    SelfSigned,
+}
+
+/// A loaded certificate, ready to serve
+pub struct TlsSetup {
+    pub config: rustls::ServerConfig,
+    pub cert_path: PathBuf,
+    /// SHA-256 fingerprint of the leaf certificate, for pinning
+    pub fingerprint: String,
+    /// Whether the self-signed certificate was created on this start
+    pub generated: bool,
+}
+
+/// Certificate and key paths used by `--tls-self-signed`
+pub fn self_signed_paths(database_path: &Path) -> (PathBuf, PathBuf) {
+    let dir = database_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("tls");
+    (dir.join("cert.pem"), dir.join("key.pem"))
+}
+
+/// Names the self-signed certificate is valid for
+fn subject_names(host: &str) -> Vec<String> {
+    let mut names = vec![
+        "localhost".to_string(),
+        "127.0.0.1".to_string(),
+        "::1".to_string(),
+    ];
+    // Wildcard binds say nothing about the name clients will use
+    if !matches!(host, "0.0.0.0" | "::" | "") && !names.iter().any(|n| n == host) {
+        names.push(host.to_string());
+    }
+    names
+}
+
+/// Write a new self-signed certificate and key
+fn generate_self_signed(cert_path: &Path, key_path: &Path, host: &str) -> Result<()> {
+    let certified = rcgen::generate_simple_self_signed(subject_names(host))
+        .context("Failed to generate a self-signed certificate")?;
+
+    if let Some(dir) = cert_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(cert_path, certified.cert.pem())?;
+    std::fs::write(key_path, certified.key_pair.serialize_pem())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(key_path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+fn fingerprint(cert: &CertificateDer) -> String {
+    Sha256::digest(cert.as_ref())
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Load the certificate for `source`, creating a self-signed one if needed
+pub fn load(source: &TlsSource, database_path: &Path, host: &str) -> Result<TlsSetup> {
+    let mut generated = false;
+    let (cert_path, key_path) = match source {
+        TlsSource::Files { cert, key } => (cert.clone(), key.clone()),
+        TlsSource::SelfSigned => {
+            let (cert, key) = self_signed_paths(database_path);
+            if !cert.exists() || !key.exists() {
+                generate_self_signed(&cert, &key, host)?;
+                generated = true;
+            }
+            (cert, key)
+        }
+    };
+
+    let certs = CertificateDer::pem_file_iter(&cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("Failed to read certificate {}", cert_path.display()))?;
+    let leaf = certs
+        .first()
+        .with_context(|| format!("No certificate found in {}", cert_path.display()))?;
+    let fingerprint = fingerprint(leaf);
+    let key = PrivateKeyDer::from_pem_file(&key_path)
+        .with_context(|| format!("Failed to read private key {}", key_path.display()))?;
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = rustls::ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .with_context(|| {
+            format!(
+                "{} does not match the certificate in {}",
+                key_path.display(),
+                cert_path.display()
+            )
+        })?;
+
+    Ok(TlsSetup {
+        config,
+        cert_path,
+        fingerprint,
+        generated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_signed_is_reused() {
+        let dir = std::env::temp_dir().join(format!("csm-tls-{}", uuid::Uuid::new_v4()));
+        let database = dir.join("csm.db");
+
+        let first = load(&TlsSource::SelfSigned, &database, "192.168.1.20").unwrap();
+        assert!(first.generated);
+        assert_eq!(first.cert_path, dir.join("tls").join("cert.pem"));
+
+        let second = load(&TlsSource::SelfSigned, &database, "192.168.1.20").unwrap();
+        assert!(!second.generated);
+        assert_eq!(first.fingerprint, second.fingerprint);
+
+        let (cert, _) = self_signed_paths(&database);
+        let missing_key = TlsSource::Files {
+            cert,
+            key: dir.join("missing.pem"),
+        };
+        assert!(load(&missing_key, &database, "localhost").is_err());
+        assert_eq!(subject_names("0.0.0.0").len(), 3);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
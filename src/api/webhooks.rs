@@ -1,338 +1,570 @@
 // Copyright (c) 2024-2026 Nervosys LLC
 // SPDX-License-Identifier: AGPL-3.0-only
-//! Webhook integration module
+//! Outgoing webhooks
 //!
-//! Provides webhook dispatch for session and system events.
+//! Webhooks registered at `/api/webhooks` receive a POST when something
+//! happens on the server: a session is created, updated, or deleted through
+//! the API, a harvest started over WebSocket finishes, or an agent reports
+//! that it finished. Failed deliveries are retried with exponential backoff,
+//! and every attempt is kept in a delivery log.
+//!
+//! Each request carries `X-Chasm-Event`, `X-Chasm-Delivery`, and
+//! `X-Chasm-Timestamp` headers, and `X-Chasm-Signature: sha256=<hex>`, an
+//! HMAC-SHA256 of `<timestamp>.<body>` keyed with the webhook's secret.
+//! Receivers should recompute it and reject stale timestamps.
 
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse};
 use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
-/// Webhook event types
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
-#[serde(rename_all = "snake_case")]
+use super::docs::ApiEnvelope;
+use super::state::AppState;
+use super::sync::{ServerMessage, SharedSyncState, SyncEntityType, SyncOperation};
+use super::websocket::{WebSocketState, WsServerMessage, HARVEST_CHANNEL};
+
+/// Wait before the first retry, doubled for each one after
+const RETRY_BASE: Duration = Duration::from_secs(2);
+
+/// Longest wait between attempts
+const RETRY_MAX: Duration = Duration::from_secs(300);
+
+/// Most retries a webhook can ask for
+const MAX_RETRIES: u8 = 10;
+
+/// Delivery log entries kept per webhook
+const LOG_KEEP: i64 = 500;
+
+/// How long a receiver has to answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Events a webhook can subscribe to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
 pub enum WebhookEvent {
-    /// Session created
+    #[serde(rename = "session.created")]
     SessionCreated,
-    /// Session updated
+    #[serde(rename = "session.updated")]
     SessionUpdated,
-    /// Session deleted
+    #[serde(rename = "session.deleted")]
     SessionDeleted,
-    /// Session archived
-    SessionArchived,
-    /// Session merged
-    SessionMerged,
-    /// Session exported
-    SessionExported,
-    /// Workspace created
-    WorkspaceCreated,
-    /// Workspace deleted
-    WorkspaceDeleted,
-    /// Harvest completed
+    #[serde(rename = "harvest.completed")]
     HarvestCompleted,
-    /// Sync completed
-    SyncCompleted,
-    /// Provider health changed
-    ProviderHealthChanged,
+    #[serde(rename = "harvest.failed")]
+    HarvestFailed,
+    #[serde(rename = "agent.finished")]
+    AgentFinished,
+    /// Sent by `POST /api/webhooks/{id}/test` whatever the subscription
+    #[serde(rename = "ping")]
+    Ping,
 }
 
-impl std::fmt::Display for WebhookEvent {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
         match self {
-            WebhookEvent::SessionCreated => write!(f, "session.created"),
-            WebhookEvent::SessionUpdated => write!(f, "session.updated"),
-            WebhookEvent::SessionDeleted => write!(f, "session.deleted"),
-            WebhookEvent::SessionArchived => write!(f, "session.archived"),
-            WebhookEvent::SessionMerged => write!(f, "session.merged"),
-            WebhookEvent::SessionExported => write!(f, "session.exported"),
-            WebhookEvent::WorkspaceCreated => write!(f, "workspace.created"),
-            WebhookEvent::WorkspaceDeleted => write!(f, "workspace.deleted"),
-            WebhookEvent::HarvestCompleted => write!(f, "harvest.completed"),
-            WebhookEvent::SyncCompleted => write!(f, "sync.completed"),
-            WebhookEvent::ProviderHealthChanged => write!(f, "provider.health_changed"),
+            WebhookEvent::SessionCreated => "session.created",
+            WebhookEvent::SessionUpdated => "session.updated",
+            WebhookEvent::SessionDeleted => "session.deleted",
+            WebhookEvent::HarvestCompleted => "harvest.completed",
+            WebhookEvent::HarvestFailed => "harvest.failed",
+            WebhookEvent::AgentFinished => "agent.finished",
+            WebhookEvent::Ping => "ping",
         }
     }
 }
 
-/// Webhook configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl std::fmt::Display for WebhookEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A registered webhook (never its secret)
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct WebhookConfig {
-    /// Unique ID
     pub id: String,
-    /// Display name
     pub name: String,
-    /// Target URL
     pub url: String,
-    /// Secret for HMAC signature
-    #[serde(skip_serializing)]
-    pub secret: Option<String>,
-    /// Events to subscribe to
     pub events: Vec<WebhookEvent>,
-    /// Whether webhook is enabled
     pub enabled: bool,
-    /// Custom headers
-    #[serde(default)]
+    /// Extra headers sent with every delivery
     pub headers: HashMap<String, String>,
-    /// Retry configuration
-    #[serde(default)]
+    /// Retries after a failed first attempt
     pub retry_count: u8,
-    /// Creation timestamp
-    pub created_at: DateTime<Utc>,
-    /// Last triggered timestamp
-    pub last_triggered: Option<DateTime<Utc>>,
-    /// Failure count
-    #[serde(default)]
+    pub created_at: i64,
+    pub last_delivery_at: Option<i64>,
+    /// Deliveries that failed for good since the last success
     pub failure_count: u32,
+    #[serde(skip)]
+    secret: String,
 }
 
-/// Webhook payload
+/// Body of every delivery
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebhookPayload {
-    /// Event ID
+    /// Event ID, the same for every attempt
     pub id: String,
-    /// Event type
-    pub event: String,
-    /// Timestamp
+    pub event: WebhookEvent,
     pub timestamp: DateTime<Utc>,
-    /// Event data
     pub data: serde_json::Value,
-    /// Webhook ID
     pub webhook_id: String,
 }
 
-/// Webhook delivery result
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// One delivery attempt, as kept in the log
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct WebhookDelivery {
-    /// Delivery ID
     pub id: String,
-    /// Webhook ID
     pub webhook_id: String,
-    /// Event type
+    /// ID of the event, shared by its retries
+    pub event_id: String,
     pub event: String,
-    /// Response status code
+    pub attempt: u32,
     pub status_code: Option<u16>,
-    /// Whether delivery succeeded
     pub success: bool,
-    /// Error message if failed
     pub error: Option<String>,
-    /// Delivery timestamp
-    pub delivered_at: DateTime<Utc>,
-    /// Response time in ms
     pub response_time_ms: u64,
+    pub delivered_at: i64,
+}
+
+// ============================================================================
+// Registry
+// ============================================================================
+
+/// Create the webhook and delivery log tables if they do not exist
+pub fn init_webhook_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS webhooks (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            url TEXT NOT NULL,
+            secret TEXT NOT NULL,
+            events TEXT NOT NULL,
+            headers TEXT NOT NULL DEFAULT '{}',
+            retry_count INTEGER NOT NULL DEFAULT 3,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL,
+            last_delivery_at INTEGER,
+            failure_count INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE TABLE IF NOT EXISTS webhook_deliveries (
+            id TEXT PRIMARY KEY,
+            webhook_id TEXT NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+            event_id TEXT NOT NULL,
+            event TEXT NOT NULL,
+            attempt INTEGER NOT NULL,
+            status_code INTEGER,
+            success INTEGER NOT NULL,
+            error TEXT,
+            response_time_ms INTEGER NOT NULL,
+            delivered_at INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook
+            ON webhook_deliveries(webhook_id);",
+    )
+}
+
+const WEBHOOK_COLUMNS: &str = "SELECT id, name, url, secret, events, headers, retry_count,
+                                      enabled, created_at, last_delivery_at, failure_count
+                               FROM webhooks";
+
+fn row_to_webhook(row: &rusqlite::Row) -> rusqlite::Result<WebhookConfig> {
+    let events: String = row.get(4)?;
+    let headers: String = row.get(5)?;
+    Ok(WebhookConfig {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        url: row.get(2)?,
+        secret: row.get(3)?,
+        events: serde_json::from_str(&events).unwrap_or_default(),
+        headers: serde_json::from_str(&headers).unwrap_or_default(),
+        retry_count: row.get(6)?,
+        enabled: row.get(7)?,
+        created_at: row.get(8)?,
+        last_delivery_at: row.get(9)?,
+        failure_count: row.get(10)?,
+    })
+}
+
+fn list(conn: &Connection) -> rusqlite::Result<Vec<WebhookConfig>> {
+    let mut stmt = conn.prepare(&format!("{} ORDER BY created_at, id", WEBHOOK_COLUMNS))?;
+    let webhooks = stmt.query_map([], row_to_webhook)?.collect();
+    webhooks
+}
+
+fn get(conn: &Connection, id: &str) -> rusqlite::Result<Option<WebhookConfig>> {
+    conn.query_row(
+        &format!("{} WHERE id = ?1", WEBHOOK_COLUMNS),
+        [id],
+        row_to_webhook,
+    )
+    .optional()
+}
+
+fn save(conn: &Connection, webhook: &WebhookConfig) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO webhooks (id, name, url, secret, events, headers, retry_count,
+                               enabled, created_at, last_delivery_at, failure_count)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+         ON CONFLICT(id) DO UPDATE SET
+             name = excluded.name, url = excluded.url, secret = excluded.secret,
+             events = excluded.events, headers = excluded.headers,
+             retry_count = excluded.retry_count, enabled = excluded.enabled",
+        params![
+            webhook.id,
+            webhook.name,
+            webhook.url,
+            webhook.secret,
+            serde_json::to_string(&webhook.events).unwrap_or_default(),
+            serde_json::to_string(&webhook.headers).unwrap_or_default(),
+            webhook.retry_count,
+            webhook.enabled,
+            webhook.created_at,
+            webhook.last_delivery_at,
+            webhook.failure_count,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Enabled webhooks subscribed to `event`
+fn subscribers(conn: &Connection, event: WebhookEvent) -> rusqlite::Result<Vec<WebhookConfig>> {
+    Ok(list(conn)?
+        .into_iter()
+        .filter(|w| w.enabled && w.events.contains(&event))
+        .collect())
+}
+
+/// Add an attempt to the log, dropping the oldest entries past [`LOG_KEEP`]
+fn log_delivery(conn: &Connection, delivery: &WebhookDelivery) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO webhook_deliveries (id, webhook_id, event_id, event, attempt, status_code,
+                                         success, error, response_time_ms, delivered_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            delivery.id,
+            delivery.webhook_id,
+            delivery.event_id,
+            delivery.event,
+            delivery.attempt,
+            delivery.status_code,
+            delivery.success,
+            delivery.error,
+            delivery.response_time_ms as i64,
+            delivery.delivered_at,
+        ],
+    )?;
+    conn.execute(
+        "DELETE FROM webhook_deliveries WHERE webhook_id = ?1 AND rowid NOT IN (
+             SELECT rowid FROM webhook_deliveries WHERE webhook_id = ?1
+             ORDER BY rowid DESC LIMIT ?2)",
+        params![delivery.webhook_id, LOG_KEEP],
+    )?;
+    Ok(())
+}
+
+fn deliveries(
+    conn: &Connection,
+    webhook_id: &str,
+    limit: usize,
+) -> rusqlite::Result<Vec<WebhookDelivery>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, webhook_id, event_id, event, attempt, status_code, success, error,
+                response_time_ms, delivered_at
+         FROM webhook_deliveries WHERE webhook_id = ?1
+         ORDER BY rowid DESC LIMIT ?2",
+    )?;
+    let deliveries = stmt
+        .query_map(params![webhook_id, limit as i64], |row| {
+            Ok(WebhookDelivery {
+                id: row.get(0)?,
+                webhook_id: row.get(1)?,
+                event_id: row.get(2)?,
+                event: row.get(3)?,
+                attempt: row.get(4)?,
+                status_code: row.get(5)?,
+                success: row.get(6)?,
+                error: row.get(7)?,
+                response_time_ms: row.get::<_, i64>(8)? as u64,
+                delivered_at: row.get(9)?,
+            })
+        })?
+        .collect();
+    deliveries
+}
+
+// ============================================================================
+// Delivery
+// ============================================================================
+
+/// HMAC-SHA256 of `<timestamp>.<body>`, hex encoded
+fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Wait after failed attempt `attempt` (1-based)
+fn backoff(attempt: u32) -> Duration {
+    RETRY_BASE
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(RETRY_MAX)
+}
+
+/// Network errors, rate limits, and server errors may clear up; other
+/// rejections will not
+fn retryable(delivery: &WebhookDelivery) -> bool {
+    !delivery.success && delivery.status_code.is_none_or(|s| s == 429 || s >= 500)
 }
 
-/// Webhook state (in-memory for now)
-pub struct WebhookState {
-    /// Registered webhooks
-    webhooks: RwLock<HashMap<String, WebhookConfig>>,
-    /// Recent deliveries (circular buffer)
-    deliveries: RwLock<Vec<WebhookDelivery>>,
-    /// HTTP client
+fn new_secret() -> String {
+    let bytes: [u8; 24] = rand::random();
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("whsec_{}", hex)
+}
+
+/// Sends events to the registered webhooks
+#[derive(Clone)]
+pub struct Webhooks {
+    state: web::Data<AppState>,
     client: reqwest::Client,
 }
 
-impl WebhookState {
-    /// Create new webhook state
-    pub fn new() -> Self {
+impl Webhooks {
+    pub fn new(state: web::Data<AppState>) -> Self {
         Self {
-            webhooks: RwLock::new(HashMap::new()),
-            deliveries: RwLock::new(Vec::new()),
+            state,
             client: reqwest::Client::builder()
-                .timeout(std::time::Duration::from_secs(30))
+                .timeout(REQUEST_TIMEOUT)
                 .build()
                 .unwrap_or_default(),
         }
     }
 
-    /// Register a webhook
-    pub fn register(&self, config: WebhookConfig) {
-        let mut webhooks = self.webhooks.write().unwrap();
-        webhooks.insert(config.id.clone(), config);
-    }
-
-    /// Remove a webhook
-    pub fn remove(&self, id: &str) -> bool {
-        let mut webhooks = self.webhooks.write().unwrap();
-        webhooks.remove(id).is_some()
-    }
-
-    /// Get a webhook by ID
-    pub fn get(&self, id: &str) -> Option<WebhookConfig> {
-        let webhooks = self.webhooks.read().unwrap();
-        webhooks.get(id).cloned()
-    }
-
-    /// List all webhooks
-    pub fn list(&self) -> Vec<WebhookConfig> {
-        let webhooks = self.webhooks.read().unwrap();
-        webhooks.values().cloned().collect()
-    }
-
-    /// Get webhooks subscribed to an event
-    pub fn get_for_event(&self, event: &WebhookEvent) -> Vec<WebhookConfig> {
-        let webhooks = self.webhooks.read().unwrap();
-        webhooks
-            .values()
-            .filter(|w| w.enabled && w.events.contains(event))
-            .cloned()
-            .collect()
-    }
-
-    /// Dispatch an event to all subscribed webhooks
-    pub async fn dispatch(&self, event: WebhookEvent, data: serde_json::Value) {
-        let webhooks = self.get_for_event(&event);
-
+    /// Deliver `event` to every enabled webhook subscribed to it, in the
+    /// background
+    pub fn emit(&self, event: WebhookEvent, data: serde_json::Value) {
+        let webhooks = {
+            let db = self.state.db.lock().unwrap();
+            subscribers(&db.conn, event)
+        };
+        let webhooks = match webhooks {
+            Ok(webhooks) => webhooks,
+            Err(e) => {
+                eprintln!("[WARN] Failed to load webhooks for {}: {}", event, e);
+                return;
+            }
+        };
+        let event_id = Uuid::new_v4().to_string();
         for webhook in webhooks {
             let payload = WebhookPayload {
-                id: Uuid::new_v4().to_string(),
-                event: event.to_string(),
+                id: event_id.clone(),
+                event,
                 timestamp: Utc::now(),
                 data: data.clone(),
                 webhook_id: webhook.id.clone(),
             };
-
-            let delivery = self.deliver(&webhook, &payload).await;
-
-            // Store delivery result
-            let mut deliveries = self.deliveries.write().unwrap();
-            deliveries.push(delivery);
-
-            // Keep only last 1000 deliveries
-            if deliveries.len() > 1000 {
-                let drain_count = deliveries.len() - 1000;
-                deliveries.drain(0..drain_count);
-            }
+            let this = self.clone();
+            tokio::spawn(async move { this.deliver(&webhook, &payload).await });
         }
     }
 
-    /// Deliver a webhook
-    async fn deliver(&self, webhook: &WebhookConfig, payload: &WebhookPayload) -> WebhookDelivery {
-        let start = std::time::Instant::now();
-        let delivery_id = Uuid::new_v4().to_string();
-
-        let mut request = self
-            .client
-            .post(&webhook.url)
-            .header("Content-Type", "application/json")
-            .header("X-Chasm-Webhook-Id", &webhook.id)
-            .header("X-Chasm-Event", &payload.event)
-            .header("X-Chasm-Delivery-Id", &delivery_id);
-
-        // Add signature if secret is configured
-        if let Some(ref secret) = webhook.secret {
-            if let Ok(body) = serde_json::to_string(payload) {
-                let signature = compute_signature(secret, &body);
-                request = request.header("X-Chasm-Signature", format!("sha256={}", signature));
+    /// Send a payload, retrying with backoff, and record the outcome
+    async fn deliver(&self, webhook: &WebhookConfig, payload: &WebhookPayload) {
+        let Ok(body) = serde_json::to_string(payload) else {
+            return;
+        };
+        let attempts = u32::from(webhook.retry_count.min(MAX_RETRIES)) + 1;
+        let mut delivered = false;
+        for attempt in 1..=attempts {
+            let delivery = self.attempt(webhook, payload, &body, attempt).await;
+            delivered = delivery.success;
+            if !retryable(&delivery) || attempt == attempts {
+                break;
             }
+            tokio::time::sleep(backoff(attempt)).await;
         }
 
-        // Add custom headers
-        for (key, value) in &webhook.headers {
-            request = request.header(key, value);
+        let db = self.state.db.lock().unwrap();
+        let updated = db.conn.execute(
+            "UPDATE webhooks SET last_delivery_at = ?1,
+                 failure_count = CASE WHEN ?2 THEN 0 ELSE failure_count + 1 END
+             WHERE id = ?3",
+            params![Utc::now().timestamp(), delivered, webhook.id],
+        );
+        if let Err(e) = updated {
+            eprintln!("[WARN] Failed to update webhook {}: {}", webhook.id, e);
         }
+    }
 
-        let result = request.json(payload).send().await;
-
-        let elapsed = start.elapsed();
-
-        match result {
+    /// Make one attempt and log it
+    async fn attempt(
+        &self,
+        webhook: &WebhookConfig,
+        payload: &WebhookPayload,
+        body: &str,
+        attempt: u32,
+    ) -> WebhookDelivery {
+        let timestamp = Utc::now().timestamp();
+        let mut request = self.client.post(&webhook.url);
+        for (name, value) in &webhook.headers {
+            request = request.header(name, value);
+        }
+        let request = request
+            .header("Content-Type", "application/json")
+            .header("X-Chasm-Event", payload.event.as_str())
+            .header("X-Chasm-Delivery", &payload.id)
+            .header("X-Chasm-Timestamp", timestamp.to_string())
+            .header(
+                "X-Chasm-Signature",
+                format!("sha256={}", sign(&webhook.secret, timestamp, body)),
+            )
+            .body(body.to_string());
+
+        let start = Instant::now();
+        let (status_code, error) = match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                (Some(response.status().as_u16()), None)
+            }
             Ok(response) => {
                 let status = response.status().as_u16();
-                WebhookDelivery {
-                    id: delivery_id,
-                    webhook_id: webhook.id.clone(),
-                    event: payload.event.clone(),
-                    status_code: Some(status),
-                    success: response.status().is_success(),
-                    error: if response.status().is_success() {
-                        None
-                    } else {
-                        Some(format!("HTTP {}", status))
-                    },
-                    delivered_at: Utc::now(),
-                    response_time_ms: elapsed.as_millis() as u64,
-                }
+                (Some(status), Some(format!("HTTP {}", status)))
             }
-            Err(e) => WebhookDelivery {
-                id: delivery_id,
-                webhook_id: webhook.id.clone(),
-                event: payload.event.clone(),
-                status_code: None,
-                success: false,
-                error: Some(e.to_string()),
-                delivered_at: Utc::now(),
-                response_time_ms: elapsed.as_millis() as u64,
-            },
-        }
-    }
-
-    /// Get recent deliveries
-    pub fn get_deliveries(&self, limit: usize) -> Vec<WebhookDelivery> {
-        let deliveries = self.deliveries.read().unwrap();
-        deliveries.iter().rev().take(limit).cloned().collect()
-    }
-
-    /// Get deliveries for a specific webhook
-    pub fn get_webhook_deliveries(&self, webhook_id: &str, limit: usize) -> Vec<WebhookDelivery> {
-        let deliveries = self.deliveries.read().unwrap();
-        deliveries
-            .iter()
-            .rev()
-            .filter(|d| d.webhook_id == webhook_id)
-            .take(limit)
-            .cloned()
-            .collect()
-    }
-}
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let delivery = WebhookDelivery {
+            id: Uuid::new_v4().to_string(),
+            webhook_id: webhook.id.clone(),
+            event_id: payload.id.clone(),
+            event: payload.event.to_string(),
+            attempt,
+            status_code,
+            success: error.is_none(),
+            error,
+            response_time_ms: start.elapsed().as_millis() as u64,
+            delivered_at: Utc::now().timestamp(),
+        };
 
-impl Default for WebhookState {
-    fn default() -> Self {
-        Self::new()
+        let db = self.state.db.lock().unwrap();
+        if let Err(e) = log_delivery(&db.conn, &delivery) {
+            eprintln!("[WARN] Failed to log webhook delivery: {}", e);
+        }
+        delivery
     }
 }
 
-/// Compute HMAC-SHA256 signature
-fn compute_signature(secret: &str, body: &str) -> String {
-    use hmac::{Hmac, Mac};
-    use sha2::Sha256;
-
-    type HmacSha256 = Hmac<Sha256>;
-
-    let mut mac =
-        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
-    mac.update(body.as_bytes());
-
-    let result = mac.finalize();
-    hex::encode(result.into_bytes())
+/// Emit the webhook events `map` finds in a broadcast channel
+fn forward<T: Clone + Send + 'static>(
+    webhooks: web::Data<Webhooks>,
+    mut rx: broadcast::Receiver<T>,
+    map: impl Fn(T) -> Option<(WebhookEvent, serde_json::Value)> + Send + 'static,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(message) => {
+                    if let Some((event, data)) = map(message) {
+                        webhooks.emit(event, data);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    })
 }
 
-/// Hex encoding helper
-mod hex {
-    pub fn encode(bytes: impl AsRef<[u8]>) -> String {
-        bytes
-            .as_ref()
-            .iter()
-            .map(|b| format!("{:02x}", b))
-            .collect()
+/// Turn server events into webhook deliveries: session changes made through
+/// the server (not ones the file watcher sees), harvest results, and agents
+/// reporting `finished`. Returns the forwarding tasks.
+pub fn spawn_dispatcher(
+    webhooks: web::Data<Webhooks>,
+    sync_state: SharedSyncState,
+    ws_state: &WebSocketState,
+) -> Vec<tokio::task::JoinHandle<()>> {
+    let mut tasks = Vec::new();
+    if let Ok(rx) = sync_state.read().map(|sync| sync.subscribe()) {
+        tasks.push(forward(webhooks.clone(), rx, |message| {
+            let ServerMessage::SyncEvent { event } = message else {
+                return None;
+            };
+            if !matches!(event.entity_type, SyncEntityType::Session) || event.client_id == "watcher"
+            {
+                return None;
+            }
+            let kind = match event.operation {
+                SyncOperation::Create => WebhookEvent::SessionCreated,
+                SyncOperation::Delete => WebhookEvent::SessionDeleted,
+                SyncOperation::Update | SyncOperation::Sync => WebhookEvent::SessionUpdated,
+            };
+            Some((
+                kind,
+                serde_json::json!({ "session_id": event.entity_id, "session": event.data }),
+            ))
+        }));
     }
+
+    let harvest = ws_state.get_channel_sender(HARVEST_CHANNEL).subscribe();
+    tasks.push(forward(webhooks.clone(), harvest, |message| match message {
+        WsServerMessage::HarvestComplete { job_id, stats } => Some((
+            WebhookEvent::HarvestCompleted,
+            serde_json::json!({ "job_id": job_id, "stats": stats }),
+        )),
+        WsServerMessage::HarvestFailed { job_id, error } => Some((
+            WebhookEvent::HarvestFailed,
+            serde_json::json!({ "job_id": job_id, "error": error }),
+        )),
+        _ => None,
+    }));
+
+    tasks.push(forward(
+        webhooks,
+        ws_state.agent_events.subscribe(),
+        |message| match message {
+            WsServerMessage::AgentEvent {
+                agent_id,
+                event,
+                data,
+            } if event == "finished" => Some((
+                WebhookEvent::AgentFinished,
+                serde_json::json!({ "agent_id": agent_id, "result": data }),
+            )),
+            _ => None,
+        },
+    ));
+    tasks
 }
 
 // ============================================================================
 // API Handlers
 // ============================================================================
 
-/// Request to create a webhook
-#[derive(Debug, Deserialize)]
+/// A webhook to register
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateWebhookRequest {
     pub name: String,
+    /// `http` or `https` URL that receives the POSTs
     pub url: String,
+    /// Signing secret; one is generated when omitted
     pub secret: Option<String>,
     pub events: Vec<WebhookEvent>,
     #[serde(default)]
     pub headers: HashMap<String, String>,
+    /// Retries after a failed first attempt, at most 10
     #[serde(default = "default_retry_count")]
     pub retry_count: u8,
 }
@@ -341,8 +573,8 @@ fn default_retry_count() -> u8 {
     3
 }
 
-/// Request to update a webhook
-#[derive(Debug, Deserialize)]
+/// Changes to a webhook; omitted fields are kept
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateWebhookRequest {
     pub name: Option<String>,
     pub url: Option<String>,
@@ -353,192 +585,353 @@ pub struct UpdateWebhookRequest {
     pub retry_count: Option<u8>,
 }
 
-/// Test webhook request
-#[derive(Debug, Deserialize)]
-pub struct TestWebhookRequest {
-    pub event: Option<WebhookEvent>,
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeliveryQuery {
+    /// Most recent attempts to return (default 50)
+    pub limit: Option<usize>,
+}
+
+fn success(status: StatusCode, data: impl Serialize) -> HttpResponse {
+    HttpResponse::build(status).json(serde_json::json!({
+        "success": true,
+        "data": data,
+    }))
+}
+
+fn failure(status: StatusCode, message: &str) -> HttpResponse {
+    HttpResponse::build(status).json(serde_json::json!({
+        "success": false,
+        "error": message,
+    }))
+}
+
+fn validate(url: &str, events: &[WebhookEvent], retry_count: u8) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid url: {}", e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err("url must be http or https".to_string());
+    }
+    if events.is_empty() {
+        return Err("Subscribe to at least one event".to_string());
+    }
+    if retry_count > MAX_RETRIES {
+        return Err(format!("retry_count can be at most {}", MAX_RETRIES));
+    }
+    Ok(())
 }
 
 /// List webhooks
-pub async fn list_webhooks(state: web::Data<Arc<WebhookState>>) -> impl Responder {
-    let webhooks = state.list();
-    HttpResponse::Ok().json(webhooks)
+#[utoipa::path(
+    get,
+    path = "/api/webhooks",
+    tag = "webhooks",
+    responses((status = 200, description = "Registered webhooks", body = ApiEnvelope))
+)]
+pub async fn list_webhooks(state: web::Data<AppState>) -> HttpResponse {
+    let db = state.db.lock().unwrap();
+    match list(&db.conn) {
+        Ok(webhooks) => success(StatusCode::OK, webhooks),
+        Err(e) => failure(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    }
 }
 
-/// Create webhook
+/// Register a webhook. The response includes its signing secret, which is
+/// not shown again.
+#[utoipa::path(
+    post,
+    path = "/api/webhooks",
+    tag = "webhooks",
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, description = "Registered webhook with its secret", body = ApiEnvelope),
+        (status = 400, description = "Invalid URL, events, or retry count")
+    )
+)]
 pub async fn create_webhook(
-    state: web::Data<Arc<WebhookState>>,
+    state: web::Data<AppState>,
     body: web::Json<CreateWebhookRequest>,
-) -> impl Responder {
-    let config = WebhookConfig {
+) -> HttpResponse {
+    let body = body.into_inner();
+    if let Err(message) = validate(&body.url, &body.events, body.retry_count) {
+        return failure(StatusCode::BAD_REQUEST, &message);
+    }
+    let webhook = WebhookConfig {
         id: Uuid::new_v4().to_string(),
-        name: body.name.clone(),
-        url: body.url.clone(),
-        secret: body.secret.clone(),
-        events: body.events.clone(),
+        name: body.name,
+        url: body.url,
+        events: body.events,
         enabled: true,
-        headers: body.headers.clone(),
+        headers: body.headers,
         retry_count: body.retry_count,
-        created_at: Utc::now(),
-        last_triggered: None,
+        created_at: Utc::now().timestamp(),
+        last_delivery_at: None,
         failure_count: 0,
+        secret: body
+            .secret
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(new_secret),
     };
 
-    state.register(config.clone());
-    HttpResponse::Created().json(config)
+    let db = state.db.lock().unwrap();
+    match save(&db.conn, &webhook) {
+        Ok(()) => {
+            let mut data = serde_json::to_value(&webhook).unwrap_or_default();
+            data["secret"] = serde_json::Value::String(webhook.secret.clone());
+            success(StatusCode::CREATED, data)
+        }
+        Err(e) => failure(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    }
 }
 
-/// Get webhook by ID
-pub async fn get_webhook(
-    state: web::Data<Arc<WebhookState>>,
-    path: web::Path<String>,
-) -> impl Responder {
-    let id = path.into_inner();
-    match state.get(&id) {
-        Some(webhook) => HttpResponse::Ok().json(webhook),
-        None => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "not_found",
-            "message": "Webhook not found"
-        })),
+/// Get a webhook
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/{id}",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Webhook ID")),
+    responses(
+        (status = 200, description = "Webhook", body = ApiEnvelope),
+        (status = 404, description = "Webhook not found")
+    )
+)]
+pub async fn get_webhook(state: web::Data<AppState>, path: web::Path<String>) -> HttpResponse {
+    let db = state.db.lock().unwrap();
+    match get(&db.conn, &path) {
+        Ok(Some(webhook)) => success(StatusCode::OK, webhook),
+        Ok(None) => failure(StatusCode::NOT_FOUND, "Webhook not found"),
+        Err(e) => failure(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
     }
 }
 
-/// Update webhook
+/// Change a webhook
+#[utoipa::path(
+    put,
+    path = "/api/webhooks/{id}",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Webhook ID")),
+    request_body = UpdateWebhookRequest,
+    responses(
+        (status = 200, description = "Updated webhook", body = ApiEnvelope),
+        (status = 400, description = "Invalid URL, events, or retry count"),
+        (status = 404, description = "Webhook not found")
+    )
+)]
 pub async fn update_webhook(
-    state: web::Data<Arc<WebhookState>>,
+    state: web::Data<AppState>,
     path: web::Path<String>,
     body: web::Json<UpdateWebhookRequest>,
-) -> impl Responder {
-    let id = path.into_inner();
+) -> HttpResponse {
+    let body = body.into_inner();
+    let db = state.db.lock().unwrap();
+    let mut webhook = match get(&db.conn, &path) {
+        Ok(Some(webhook)) => webhook,
+        Ok(None) => return failure(StatusCode::NOT_FOUND, "Webhook not found"),
+        Err(e) => return failure(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    };
 
-    if let Some(mut webhook) = state.get(&id) {
-        if let Some(name) = &body.name {
-            webhook.name = name.clone();
-        }
-        if let Some(url) = &body.url {
-            webhook.url = url.clone();
-        }
-        if let Some(secret) = &body.secret {
-            webhook.secret = Some(secret.clone());
-        }
-        if let Some(events) = &body.events {
-            webhook.events = events.clone();
-        }
-        if let Some(enabled) = body.enabled {
-            webhook.enabled = enabled;
-        }
-        if let Some(headers) = &body.headers {
-            webhook.headers = headers.clone();
-        }
-        if let Some(retry_count) = body.retry_count {
-            webhook.retry_count = retry_count;
-        }
+    if let Some(name) = body.name {
+        webhook.name = name;
+    }
+    if let Some(url) = body.url {
+        webhook.url = url;
+    }
+    if let Some(secret) = body.secret.filter(|s| !s.is_empty()) {
+        webhook.secret = secret;
+    }
+    if let Some(events) = body.events {
+        webhook.events = events;
+    }
+    if let Some(enabled) = body.enabled {
+        webhook.enabled = enabled;
+    }
+    if let Some(headers) = body.headers {
+        webhook.headers = headers;
+    }
+    if let Some(retry_count) = body.retry_count {
+        webhook.retry_count = retry_count;
+    }
+    if let Err(message) = validate(&webhook.url, &webhook.events, webhook.retry_count) {
+        return failure(StatusCode::BAD_REQUEST, &message);
+    }
 
-        state.register(webhook.clone());
-        HttpResponse::Ok().json(webhook)
-    } else {
-        HttpResponse::NotFound().json(serde_json::json!({
-            "error": "not_found",
-            "message": "Webhook not found"
-        }))
+    match save(&db.conn, &webhook) {
+        Ok(()) => success(StatusCode::OK, webhook),
+        Err(e) => failure(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
     }
 }
 
-/// Delete webhook
-pub async fn delete_webhook(
-    state: web::Data<Arc<WebhookState>>,
-    path: web::Path<String>,
-) -> impl Responder {
-    let id = path.into_inner();
-    if state.remove(&id) {
-        HttpResponse::NoContent().finish()
-    } else {
-        HttpResponse::NotFound().json(serde_json::json!({
-            "error": "not_found",
-            "message": "Webhook not found"
-        }))
+/// Delete a webhook and its delivery log
+#[utoipa::path(
+    delete,
+    path = "/api/webhooks/{id}",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Webhook ID")),
+    responses(
+        (status = 200, description = "Webhook deleted", body = ApiEnvelope),
+        (status = 404, description = "Webhook not found")
+    )
+)]
+pub async fn delete_webhook(state: web::Data<AppState>, path: web::Path<String>) -> HttpResponse {
+    let db = state.db.lock().unwrap();
+    match db
+        .conn
+        .execute("DELETE FROM webhooks WHERE id = ?1", [path.as_str()])
+    {
+        Ok(0) => failure(StatusCode::NOT_FOUND, "Webhook not found"),
+        Ok(_) => success(
+            StatusCode::OK,
+            serde_json::json!({ "id": path.as_str(), "deleted": true }),
+        ),
+        Err(e) => failure(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
     }
 }
 
-/// Test webhook
+/// Send a `ping` event once and report how the receiver answered
+#[utoipa::path(
+    post,
+    path = "/api/webhooks/{id}/test",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Webhook ID")),
+    responses(
+        (status = 200, description = "The delivery attempt", body = ApiEnvelope),
+        (status = 404, description = "Webhook not found")
+    )
+)]
 pub async fn test_webhook(
-    state: web::Data<Arc<WebhookState>>,
+    state: web::Data<AppState>,
+    webhooks: web::Data<Webhooks>,
     path: web::Path<String>,
-    body: web::Json<TestWebhookRequest>,
-) -> impl Responder {
-    let id = path.into_inner();
-
-    if let Some(webhook) = state.get(&id) {
-        let event = body.event.clone().unwrap_or(WebhookEvent::SessionCreated);
-        let test_data = serde_json::json!({
-            "test": true,
-            "message": "This is a test webhook delivery from Chasm"
-        });
-
-        let payload = WebhookPayload {
-            id: Uuid::new_v4().to_string(),
-            event: event.to_string(),
-            timestamp: Utc::now(),
-            data: test_data,
-            webhook_id: webhook.id.clone(),
-        };
+) -> HttpResponse {
+    let webhook = {
+        let db = state.db.lock().unwrap();
+        get(&db.conn, &path)
+    };
+    let webhook = match webhook {
+        Ok(Some(webhook)) => webhook,
+        Ok(None) => return failure(StatusCode::NOT_FOUND, "Webhook not found"),
+        Err(e) => return failure(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    };
 
-        let delivery = state.deliver(&webhook, &payload).await;
-        HttpResponse::Ok().json(delivery)
-    } else {
-        HttpResponse::NotFound().json(serde_json::json!({
-            "error": "not_found",
-            "message": "Webhook not found"
-        }))
-    }
+    let payload = WebhookPayload {
+        id: Uuid::new_v4().to_string(),
+        event: WebhookEvent::Ping,
+        timestamp: Utc::now(),
+        data: serde_json::json!({ "message": "Test delivery from Chasm" }),
+        webhook_id: webhook.id.clone(),
+    };
+    let body = serde_json::to_string(&payload).unwrap_or_default();
+    let delivery = webhooks.attempt(&webhook, &payload, &body, 1).await;
+    success(StatusCode::OK, delivery)
 }
 
-/// Get webhook deliveries
-pub async fn get_deliveries(
-    state: web::Data<Arc<WebhookState>>,
+/// Recent delivery attempts, newest first
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/{id}/deliveries",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Webhook ID"), DeliveryQuery),
+    responses(
+        (status = 200, description = "Delivery log", body = ApiEnvelope),
+        (status = 404, description = "Webhook not found")
+    )
+)]
+pub async fn list_deliveries(
+    state: web::Data<AppState>,
     path: web::Path<String>,
-    query: web::Query<HashMap<String, String>>,
-) -> impl Responder {
-    let id = path.into_inner();
-    let limit: usize = query
-        .get("limit")
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(50);
-
-    let deliveries = state.get_webhook_deliveries(&id, limit);
-    HttpResponse::Ok().json(deliveries)
-}
-
-/// Get all recent deliveries
-pub async fn get_all_deliveries(
-    state: web::Data<Arc<WebhookState>>,
-    query: web::Query<HashMap<String, String>>,
-) -> impl Responder {
-    let limit: usize = query
-        .get("limit")
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(50);
-
-    let deliveries = state.get_deliveries(limit);
-    HttpResponse::Ok().json(deliveries)
-}
-
-/// Configure webhook routes
-pub fn configure_webhook_routes(cfg: &mut web::ServiceConfig, state: web::Data<Arc<WebhookState>>) {
-    cfg.app_data(state).service(
-        web::scope("/webhooks")
+    query: web::Query<DeliveryQuery>,
+) -> HttpResponse {
+    let db = state.db.lock().unwrap();
+    let limit = query.limit.unwrap_or(50).clamp(1, LOG_KEEP as usize);
+    let result = get(&db.conn, &path).and_then(|webhook| {
+        webhook
+            .map(|w| deliveries(&db.conn, &w.id, limit))
+            .transpose()
+    });
+    match result {
+        Ok(Some(deliveries)) => success(StatusCode::OK, deliveries),
+        Ok(None) => failure(StatusCode::NOT_FOUND, "Webhook not found"),
+        Err(e) => failure(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    }
+}
+
+/// Configure webhook routes. Registered before the `/api` scope, which
+/// would otherwise claim these paths.
+pub fn configure_webhook_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api/webhooks")
             .route("", web::get().to(list_webhooks))
             .route("", web::post().to(create_webhook))
-            .route("/deliveries", web::get().to(get_all_deliveries))
             .route("/{id}", web::get().to(get_webhook))
             .route("/{id}", web::put().to(update_webhook))
             .route("/{id}", web::delete().to(delete_webhook))
             .route("/{id}/test", web::post().to(test_webhook))
-            .route("/{id}/deliveries", web::get().to(get_deliveries)),
+            .route("/{id}/deliveries", web::get().to(list_deliveries)),
     );
 }
 
-/// Create webhook state
-pub fn create_webhook_state() -> Arc<WebhookState> {
-    Arc::new(WebhookState::new())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_and_signing() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON").unwrap();
+        init_webhook_tables(&conn).unwrap();
+
+        let events: Vec<WebhookEvent> =
+            serde_json::from_str(r#"["session.created", "agent.finished"]"#).unwrap();
+        let webhook = WebhookConfig {
+            id: "w1".to_string(),
+            name: "ci".to_string(),
+            url: "https://example.com/hook".to_string(),
+            events,
+            enabled: true,
+            headers: HashMap::new(),
+            retry_count: 3,
+            created_at: 0,
+            last_delivery_at: None,
+            failure_count: 0,
+            secret: "s3cret".to_string(),
+        };
+        save(&conn, &webhook).unwrap();
+        assert_eq!(
+            subscribers(&conn, WebhookEvent::AgentFinished)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(subscribers(&conn, WebhookEvent::HarvestCompleted)
+            .unwrap()
+            .is_empty());
+        assert!(validate("ftp://example.com", &webhook.events, 3).is_err());
+
+        let delivery = WebhookDelivery {
+            id: "d1".to_string(),
+            webhook_id: "w1".to_string(),
+            event_id: "e1".to_string(),
+            event: "session.created".to_string(),
+            attempt: 1,
+            status_code: Some(503),
+            success: false,
+            error: Some("HTTP 503".to_string()),
+            response_time_ms: 12,
+            delivered_at: 0,
+        };
+        log_delivery(&conn, &delivery).unwrap();
+        assert!(retryable(&delivery));
+        assert_eq!(
+            deliveries(&conn, "w1", 10).unwrap()[0].status_code,
+            Some(503)
+        );
+        conn.execute("DELETE FROM webhooks WHERE id = 'w1'", [])
+            .unwrap();
+        assert!(deliveries(&conn, "w1", 10).unwrap().is_empty());
+
+        assert_eq!(backoff(1), RETRY_BASE);
+        assert_eq!(backoff(3), RETRY_BASE * 4);
+        assert_eq!(backoff(30), RETRY_MAX);
+        assert_eq!(sign("key", 1, "{}").len(), 64);
+        assert_ne!(sign("key", 1, "{}"), sign("key", 2, "{}"));
+    }
 }
@@ -8,14 +8,31 @@
 //!
 //! Note: For simpler use cases, the SSE-based sync (in sync.rs) may be preferred
 //! as it has better HTTP/2 compatibility and doesn't require connection upgrades.
+//!
+//! Clients subscribe to channels and receive only what they asked for:
+//!
+//! - `sessions` - every session created, updated, or deleted on disk
+//! - `session:<id>` - changes and streamed tokens for one session
+//! - `harvest` - progress of harvests started by any client
+//! - `agent:<id>` - live output of one agent
+//!
+//! Every message is JSON with a `type` tag; see [`WsClientMessage`] and
+//! [`WsServerMessage`].
 
+use super::state::AppState;
+use super::sync::{SharedSyncState, SyncDelta, SyncEntityType};
+use crate::commands::{harvest_run_with_progress, HarvestProgress, HarvestStats};
 use actix_web::{web, Error, HttpRequest, HttpResponse};
 use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::RwLock;
 use std::time::{Duration, Instant};
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
 use uuid::Uuid;
 
 // =============================================================================
@@ -25,6 +42,11 @@ use uuid::Uuid;
 const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
 const CLIENT_TIMEOUT: Duration = Duration::from_secs(60);
 
+/// Channel for all session changes
+pub const SESSIONS_CHANNEL: &str = "sessions";
+/// Channel for harvest progress
+pub const HARVEST_CHANNEL: &str = "harvest";
+
 // =============================================================================
 // WebSocket Message Types
 // =============================================================================
@@ -55,6 +77,16 @@ pub enum WsClientMessage {
     /// Request sync delta from version
     SyncRequest { from_version: u64 },
 
+    /// Start a harvest into the server's database; progress goes to `harvest`
+    HarvestStart {
+        /// Only harvest these providers
+        #[serde(default)]
+        providers: Option<Vec<String>>,
+        /// Skip sessions unchanged since the last harvest
+        #[serde(default)]
+        incremental: bool,
+    },
+
     /// Ping message for keepalive
     Ping { timestamp: i64 },
 }
@@ -90,6 +122,9 @@ pub enum WsServerMessage {
         data: Option<serde_json::Value>,
     },
 
+    /// Agent command handed to the running agent
+    AgentCommandAccepted { agent_id: String, command: String },
+
     /// Sync event for real-time updates
     SyncEvent {
         entity_type: String,
@@ -98,6 +133,20 @@ pub enum WsServerMessage {
         data: Option<serde_json::Value>,
         version: u64,
     },
+    /// Changes since the version in a `sync_request`
+    SyncDelta { delta: SyncDelta },
+
+    /// Harvest accepted; progress follows on the `harvest` channel
+    HarvestStarted { job_id: String },
+    /// A provider or workspace finished harvesting
+    HarvestProgress {
+        job_id: String,
+        progress: HarvestProgress,
+    },
+    /// Harvest finished
+    HarvestComplete { job_id: String, stats: HarvestStats },
+    /// Harvest stopped with an error
+    HarvestFailed { job_id: String, error: String },
 
     /// Pong response to ping
     Pong { timestamp: i64 },
@@ -116,6 +165,15 @@ pub struct ClientInfo {
     pub subscriptions: Vec<String>,
 }
 
+/// A command for a running agent, sent by a WebSocket client
+#[derive(Debug, Clone)]
+pub struct AgentCommandRequest {
+    pub client_id: String,
+    pub agent_id: String,
+    pub command: String,
+    pub params: Option<serde_json::Value>,
+}
+
 /// Global WebSocket state shared across connections
 pub struct WebSocketState {
     /// Broadcast channel for server-wide messages
@@ -126,16 +184,27 @@ pub struct WebSocketState {
     pub clients: RwLock<HashMap<String, ClientInfo>>,
     /// Current sync version
     pub version: std::sync::atomic::AtomicU64,
+    /// Commands from clients, for agent runners to subscribe to
+    pub agent_commands: broadcast::Sender<AgentCommandRequest>,
+    /// Every agent event, whichever channel it went to, for webhooks
+    pub agent_events: broadcast::Sender<WsServerMessage>,
+    /// Whether a harvest started over WebSocket is running
+    harvest_running: AtomicBool,
 }
 
 impl WebSocketState {
     pub fn new() -> Self {
         let (broadcast_tx, _) = broadcast::channel(1024);
+        let (agent_commands, _) = broadcast::channel(64);
+        let (agent_events, _) = broadcast::channel(256);
         Self {
             broadcast_tx,
             channel_senders: RwLock::new(HashMap::new()),
             clients: RwLock::new(HashMap::new()),
             version: std::sync::atomic::AtomicU64::new(1),
+            agent_commands,
+            agent_events,
+            harvest_running: AtomicBool::new(false),
         }
     }
 
@@ -216,32 +285,107 @@ impl Default for WebSocketState {
 // WebSocket Handler
 // =============================================================================
 
+/// Whether clients may subscribe to `channel`
+fn is_known_channel(channel: &str) -> bool {
+    channel == SESSIONS_CHANNEL
+        || channel == HARVEST_CHANNEL
+        || channel
+            .strip_prefix("session:")
+            .or_else(|| channel.strip_prefix("agent:"))
+            .is_some_and(|id| !id.is_empty())
+}
+
+/// One client connection and the channels it listens to
+struct Connection {
+    client_id: String,
+    /// Messages queued for this client
+    outbox: mpsc::UnboundedSender<WsServerMessage>,
+    /// Task forwarding each subscribed channel into the outbox
+    subscriptions: HashMap<String, JoinHandle<()>>,
+}
+
+impl Connection {
+    fn subscribe(&mut self, state: &WebSocketState, channel: &str) {
+        if self.subscriptions.contains_key(channel) {
+            return;
+        }
+        let mut rx = state.get_channel_sender(channel).subscribe();
+        let outbox = self.outbox.clone();
+        let task = actix_web::rt::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(msg) => {
+                        if outbox.send(msg).is_err() {
+                            break;
+                        }
+                    }
+                    // Slow client; it can catch up with a sync_request
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+        self.subscriptions.insert(channel.to_string(), task);
+
+        if let Ok(mut clients) = state.clients.write() {
+            if let Some(client) = clients.get_mut(&self.client_id) {
+                if !client.subscriptions.iter().any(|c| c == channel) {
+                    client.subscriptions.push(channel.to_string());
+                }
+            }
+        }
+    }
+
+    fn unsubscribe(&mut self, state: &WebSocketState, channel: &str) {
+        if let Some(task) = self.subscriptions.remove(channel) {
+            task.abort();
+        }
+        if let Ok(mut clients) = state.clients.write() {
+            if let Some(client) = clients.get_mut(&self.client_id) {
+                client.subscriptions.retain(|c| c != channel);
+            }
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        for task in self.subscriptions.values() {
+            task.abort();
+        }
+    }
+}
+
+fn error_message(code: &str, message: impl Into<String>) -> WsServerMessage {
+    WsServerMessage::Error {
+        code: code.to_string(),
+        message: message.into(),
+    }
+}
+
 /// Handle incoming WebSocket message
 fn handle_client_message(
-    client_id: &str,
+    conn: &mut Connection,
     msg: WsClientMessage,
-    state: &WebSocketState,
+    state: &web::Data<WebSocketState>,
+    sync_state: Option<&SharedSyncState>,
+    db_path: Option<&Path>,
 ) -> Option<WsServerMessage> {
+    let client_id = conn.client_id.clone();
     match msg {
         WsClientMessage::Subscribe { channel } => {
-            // Update client subscriptions
-            if let Ok(mut clients) = state.clients.write() {
-                if let Some(client) = clients.get_mut(client_id) {
-                    if !client.subscriptions.contains(&channel) {
-                        client.subscriptions.push(channel.clone());
-                    }
-                }
+            if !is_known_channel(&channel) {
+                return Some(error_message(
+                    "unknown_channel",
+                    format!("Unknown channel: {}", channel),
+                ));
             }
+            conn.subscribe(state, &channel);
             Some(WsServerMessage::Subscribed { channel })
         }
 
         WsClientMessage::Unsubscribe { channel } => {
-            // Update client subscriptions
-            if let Ok(mut clients) = state.clients.write() {
-                if let Some(client) = clients.get_mut(client_id) {
-                    client.subscriptions.retain(|c| c != &channel);
-                }
-            }
+            conn.unsubscribe(state, &channel);
             Some(WsServerMessage::Unsubscribed { channel })
         }
 
@@ -287,34 +431,119 @@ fn handle_client_message(
             command,
             params,
         } => {
-            log::info!(
-                "Client {} sent agent command {} to {}: {:?}",
+            let request = AgentCommandRequest {
                 client_id,
-                command,
-                agent_id,
-                params
-            );
-            // TODO: Implement agent commands
-            None
+                agent_id: agent_id.clone(),
+                command: command.clone(),
+                params,
+            };
+            // Output comes back on the agent's channel
+            conn.subscribe(state, &format!("agent:{}", agent_id));
+            match state.agent_commands.send(request) {
+                Ok(_) => Some(WsServerMessage::AgentCommandAccepted { agent_id, command }),
+                Err(_) => Some(error_message(
+                    "agent_not_running",
+                    format!("No running agent accepts commands for {}", agent_id),
+                )),
+            }
         }
 
         WsClientMessage::SyncRequest { from_version } => {
-            log::info!(
-                "Client {} requested sync from version {}",
-                client_id,
-                from_version
+            let delta = sync_state
+                .and_then(|sync| sync.read().ok())
+                .map(|sync| sync.get_delta(from_version));
+            match delta {
+                Some(delta) => Some(WsServerMessage::SyncDelta { delta }),
+                None => Some(error_message("sync_unavailable", "Sync is not enabled")),
+            }
+        }
+
+        WsClientMessage::HarvestStart {
+            providers,
+            incremental,
+        } => {
+            let Some(db_path) = db_path else {
+                return Some(error_message(
+                    "harvest_unavailable",
+                    "Harvesting is not enabled",
+                ));
+            };
+            if state.harvest_running.swap(true, Ordering::SeqCst) {
+                return Some(error_message(
+                    "harvest_running",
+                    "A harvest is already running",
+                ));
+            }
+            // Subscribe before starting so no progress is missed
+            conn.subscribe(state, HARVEST_CHANNEL);
+            let job_id = Uuid::new_v4().to_string();
+            spawn_harvest(
+                state.clone(),
+                job_id.clone(),
+                db_path.to_path_buf(),
+                providers,
+                incremental,
             );
-            // TODO: Implement sync delta response
-            None
+            Some(WsServerMessage::HarvestStarted { job_id })
         }
     }
 }
 
+/// Run a harvest into `db_path` on a blocking thread, publishing progress to
+/// `harvest`
+fn spawn_harvest(
+    state: web::Data<WebSocketState>,
+    job_id: String,
+    db_path: PathBuf,
+    providers: Option<Vec<String>>,
+    incremental: bool,
+) {
+    tokio::task::spawn_blocking(move || {
+        let db_path = db_path.to_string_lossy();
+        let result = harvest_run_with_progress(
+            Some(&db_path),
+            providers.as_deref(),
+            None,
+            incremental,
+            false,
+            None,
+            &mut |progress| {
+                state.broadcast_to_channel(
+                    HARVEST_CHANNEL,
+                    WsServerMessage::HarvestProgress {
+                        job_id: job_id.clone(),
+                        progress: progress.clone(),
+                    },
+                );
+                ControlFlow::Continue(())
+            },
+        );
+        let msg = match result {
+            Ok(stats) => WsServerMessage::HarvestComplete {
+                job_id: job_id.clone(),
+                stats,
+            },
+            Err(e) => WsServerMessage::HarvestFailed {
+                job_id: job_id.clone(),
+                error: format!("{:#}", e),
+            },
+        };
+        state.harvest_running.store(false, Ordering::SeqCst);
+        state.broadcast_to_channel(HARVEST_CHANNEL, msg);
+    });
+}
+
+fn send_json(msg: &WsServerMessage) -> Option<String> {
+    serde_json::to_string(msg).ok()
+}
+
 /// WebSocket endpoint handler using actix-ws
 pub async fn ws_handler(
     req: HttpRequest,
     body: web::Payload,
     state: web::Data<WebSocketState>,
+    sync_state: Option<web::Data<SharedSyncState>>,
+    app_state: Option<web::Data<AppState>>,
 ) -> Result<HttpResponse, Error> {
     // Perform WebSocket handshake
     let (response, mut session, mut msg_stream) = actix_ws::handle(&req, body)?;
@@ -330,7 +559,7 @@ pub async fn ws_handler(
         client_id: client_id.clone(),
         version: state.current_version(),
     };
-    if let Ok(json) = serde_json::to_string(&connected_msg) {
+    if let Some(json) = send_json(&connected_msg) {
         let _ = session.text(json).await;
     }
 
@@ -344,6 +573,12 @@ pub async fn ws_handler(
     actix_web::rt::spawn(async move {
         let mut heartbeat_interval = tokio::time::interval(HEARTBEAT_INTERVAL);
         let mut last_heartbeat = Instant::now();
+        let (outbox, mut outbox_rx) = mpsc::unbounded_channel();
+        let mut conn = Connection {
+            client_id: client_id_clone.clone(),
+            outbox,
+            subscriptions: HashMap::new(),
+        };
 
         loop {
             tokio::select! {
@@ -352,24 +587,21 @@ pub async fn ws_handler(
                     match msg_result {
                         Ok(actix_ws::Message::Text(text)) => {
                             last_heartbeat = Instant::now();
-                            if let Ok(client_msg) = serde_json::from_str::<WsClientMessage>(&text) {
-                                if let Some(response) = handle_client_message(
-                                    &client_id_clone,
+                            let response = match serde_json::from_str::<WsClientMessage>(&text) {
+                                Ok(client_msg) => handle_client_message(
+                                    &mut conn,
                                     client_msg,
                                     &state_clone,
-                                ) {
-                                    if let Ok(json) = serde_json::to_string(&response) {
-                                        let _ = session.text(json).await;
-                                    }
-                                }
-                            } else {
-                                let error_msg = WsServerMessage::Error {
-                                    code: "invalid_message".to_string(),
-                                    message: "Failed to parse message".to_string(),
-                                };
-                                if let Ok(json) = serde_json::to_string(&error_msg) {
-                                    let _ = session.text(json).await;
-                                }
+                                    sync_state.as_ref().map(|s| s.get_ref()),
+                                    app_state.as_ref().map(|s| s.db_path.as_path()),
+                                ),
+                                Err(e) => Some(error_message(
+                                    "invalid_message",
+                                    format!("Failed to parse message: {}", e),
+                                )),
+                            };
+                            if let Some(json) = response.as_ref().and_then(send_json) {
+                                let _ = session.text(json).await;
                             }
                         }
                         Ok(actix_ws::Message::Ping(data)) => {
@@ -389,7 +621,14 @@ pub async fn ws_handler(
 
                 // Handle broadcast messages
                 Ok(msg) = broadcast_rx.recv() => {
-                    if let Ok(json) = serde_json::to_string(&msg) {
+                    if let Some(json) = send_json(&msg) {
+                        let _ = session.text(json).await;
+                    }
+                }
+
+                // Handle messages from subscribed channels
+                Some(msg) = outbox_rx.recv() => {
+                    if let Some(json) = send_json(&msg) {
                         let _ = session.text(json).await;
                     }
                 }
@@ -406,6 +645,7 @@ pub async fn ws_handler(
         }
 
         // Cleanup
+        drop(conn);
         state_clone.unregister_client(&client_id_clone);
         let _ = session.close(None).await;
         log::info!("WebSocket client {} disconnected", client_id_clone);
@@ -414,6 +654,47 @@ pub async fn ws_handler(
     Ok(response)
 }
 
+/// Forward sync events (including session file changes from the watcher) to
+/// WebSocket channels: session events go to `sessions` and `session:<id>`,
+/// everything else to all clients
+pub fn spawn_sync_bridge(
+    sync_state: SharedSyncState,
+    state: web::Data<WebSocketState>,
+) -> Option<tokio::task::JoinHandle<()>> {
+    let Ok(mut rx) = sync_state.read().map(|sync| sync.subscribe()) else {
+        return None;
+    };
+    Some(tokio::spawn(async move {
+        loop {
+            let event = match rx.recv().await {
+                Ok(super::sync::ServerMessage::SyncEvent { event }) => event,
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let is_session = matches!(event.entity_type, SyncEntityType::Session);
+            let msg = WsServerMessage::SyncEvent {
+                entity_type: serde_json::to_value(&event.entity_type)
+                    .ok()
+                    .and_then(|v| v.as_str().map(str::to_string))
+                    .unwrap_or_default(),
+                entity_id: event.entity_id.clone(),
+                operation: serde_json::to_value(&event.operation)
+                    .ok()
+                    .and_then(|v| v.as_str().map(str::to_string))
+                    .unwrap_or_default(),
+                data: event.data,
+                version: event.version,
+            };
+            if is_session {
+                state.broadcast_to_channel(&format!("session:{}", event.entity_id), msg.clone());
+                state.broadcast_to_channel(SESSIONS_CHANNEL, msg);
+            } else {
+                state.broadcast(msg);
+            }
+        }
+    }))
+}
+
 /// Configure WebSocket routes
 pub fn configure_websocket_routes(cfg: &mut web::ServiceConfig, state: web::Data<WebSocketState>) {
     cfg.app_data(state).route("/ws", web::get().to(ws_handler));
@@ -472,5 +753,43 @@ pub fn broadcast_agent_event(
         event: event.to_string(),
         data,
     };
+    let _ = state.agent_events.send(msg.clone());
     state.broadcast_to_channel(&format!("agent:{}", agent_id), msg);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_channels() {
+        assert!(is_known_channel("sessions"));
+        assert!(is_known_channel("harvest"));
+        assert!(is_known_channel("session:abc"));
+        assert!(is_known_channel("agent:reviewer"));
+        assert!(!is_known_channel("session:"));
+        assert!(!is_known_channel("everything"));
+    }
+
+    #[test]
+    fn test_harvest_start_defaults() {
+        // Clients cannot choose where the server harvests to
+        let msg: WsClientMessage =
+            serde_json::from_str(r#"{"type":"harvest_start","path":"/tmp/x.db"}"#).unwrap();
+        match msg {
+            WsClientMessage::HarvestStart {
+                providers,
+                incremental,
+            } => {
+                assert!(providers.is_none() && !incremental);
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        let json = serde_json::to_value(WsServerMessage::HarvestStarted {
+            job_id: "j1".to_string(),
+        })
+        .unwrap();
+        assert_eq!(json["type"], "harvest_started");
+    }
+}
@@ -0,0 +1,685 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Actions on harvested sessions, shell commands, and HTTP requests
+//!
+//! Session actions work on the harvest database. Archive and delete move
+//! sessions to the trash (`deleted_sessions`), from which
+//! `POST /api/sessions/{id}/restore` brings them back. Each action returns
+//! the JSON kept as its `ActionResult` data.
+
+use super::{SessionFilter, SyncDirection};
+use crate::analytics::Digest;
+use crate::cloud_sync::{CloudSyncService, LocalSyncService};
+use crate::commands::{
+    delete_harvested_session, export_session_json, find_harvested, harvest_run_with_progress,
+    insert_or_update_session, item_issue, render_export, session_issue,
+};
+use crate::integrations::issues::{self, NewIssue};
+use crate::intelligence::ActionKind;
+use crate::text::prefix_chars;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{Duration, Utc};
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+
+/// Most characters of command output or response body kept in a result
+const MAX_OUTPUT_CHARS: usize = 64 * 1024;
+
+/// How long an HTTP action waits for an answer
+const HTTP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// IDs of the harvested sessions a filter selects, oldest first. Tags match
+/// when a session has any of them; `query` is matched against titles and IDs.
+pub fn select_sessions(conn: &Connection, filter: &SessionFilter) -> Result<Vec<String>> {
+    crate::reviews::ensure_tags_table(conn)?;
+    let mut query = String::from("SELECT id FROM sessions WHERE 1=1");
+    let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    if let Some(provider) = &filter.provider {
+        query.push_str(" AND LOWER(provider) LIKE ?");
+        params.push(Box::new(format!("%{}%", provider.to_lowercase())));
+    }
+    if let Some(days) = filter.older_than_days {
+        query.push_str(" AND updated_at < ?");
+        params.push(Box::new(
+            (Utc::now() - Duration::days(days as i64)).timestamp_millis(),
+        ));
+    }
+    if let Some(days) = filter.newer_than_days {
+        query.push_str(" AND updated_at >= ?");
+        params.push(Box::new(
+            (Utc::now() - Duration::days(days as i64)).timestamp_millis(),
+        ));
+    }
+    if let Some(tags) = filter.tags.as_ref().filter(|t| !t.is_empty()) {
+        let placeholders = vec!["?"; tags.len()].join(",");
+        query.push_str(&format!(
+            " AND id IN (SELECT session_id FROM session_tags WHERE tag IN ({}))",
+            placeholders
+        ));
+        for tag in tags {
+            params.push(Box::new(tag.clone()));
+        }
+    }
+    if let Some(text) = &filter.query {
+        query.push_str(" AND (LOWER(title) LIKE ? OR LOWER(id) LIKE ?)");
+        let pattern = format!("%{}%", text.to_lowercase());
+        params.push(Box::new(pattern.clone()));
+        params.push(Box::new(pattern));
+    }
+    query.push_str(" ORDER BY updated_at, id");
+
+    let mut stmt = conn.prepare(&query)?;
+    let params: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+    let ids = stmt.query_map(params.as_slice(), |row| row.get(0))?;
+    Ok(ids.collect::<std::result::Result<Vec<String>, _>>()?)
+}
+
+fn open(db_path: &Path) -> Result<Connection> {
+    if !db_path.exists() {
+        bail!("Harvest database not found: {}", db_path.display());
+    }
+    Connection::open(db_path).with_context(|| format!("Failed to open {}", db_path.display()))
+}
+
+/// Archive and delete act on every session an empty filter selects, which
+/// is never what a workflow means
+fn require_filter(filter: &SessionFilter, action: &str) -> Result<()> {
+    let empty = filter.provider.is_none()
+        && filter.older_than_days.is_none()
+        && filter.newer_than_days.is_none()
+        && filter.tags.as_ref().is_none_or(|t| t.is_empty())
+        && filter.query.is_none();
+    if empty {
+        bail!(
+            "Refusing to {} every session; give the filter a condition",
+            action
+        );
+    }
+    Ok(())
+}
+
+/// An output path: `~` is the home directory, and relative paths go under
+/// the exports root
+fn output_path(path: &str) -> PathBuf {
+    let path = crate::config::expand_home(Path::new(path));
+    crate::config::resolve_export_path(&path.to_string_lossy())
+}
+
+/// Write the selected sessions to `output` (relative paths go under the
+/// exports root) as `json`, `jsonl`, or `md`
+pub fn export_sessions(
+    db_path: &Path,
+    filter: &SessionFilter,
+    format: &str,
+    output: &str,
+) -> Result<serde_json::Value> {
+    let conn = open(db_path)?;
+    let ids = select_sessions(&conn, filter)?;
+    let output = output_path(output);
+    if ids.is_empty() {
+        return Ok(serde_json::json!({ "exported": 0 }));
+    }
+    let content = render_export(&export_session_json(&conn, None, Some(&ids))?, format)?;
+    write_file(&output, &content)?;
+    Ok(serde_json::json!({
+        "exported": ids.len(),
+        "output": output,
+    }))
+}
+
+/// Write the selected sessions to a JSON Lines file in `destination`, then
+/// move them to the trash
+pub fn archive_sessions(
+    db_path: &Path,
+    filter: &SessionFilter,
+    destination: &str,
+) -> Result<serde_json::Value> {
+    require_filter(filter, "archive")?;
+    let mut conn = open(db_path)?;
+    let ids = select_sessions(&conn, filter)?;
+    if ids.is_empty() {
+        return Ok(serde_json::json!({ "archived": 0 }));
+    }
+    let file = output_path(destination).join(format!(
+        "sessions-{}.jsonl",
+        Utc::now().format("%Y%m%d-%H%M%S")
+    ));
+    let content = render_export(&export_session_json(&conn, None, Some(&ids))?, "jsonl")?;
+    write_file(&file, &content)?;
+
+    let tx = conn.transaction()?;
+    for id in &ids {
+        delete_harvested_session(&tx, id, true)?;
+    }
+    tx.commit()?;
+    Ok(serde_json::json!({
+        "archived": ids.len(),
+        "file": file,
+    }))
+}
+
+/// Move the selected sessions to the trash
+pub fn delete_sessions(db_path: &Path, filter: &SessionFilter) -> Result<serde_json::Value> {
+    require_filter(filter, "delete")?;
+    let mut conn = open(db_path)?;
+    let ids = select_sessions(&conn, filter)?;
+    let tx = conn.transaction()?;
+    let mut deleted = 0;
+    for id in &ids {
+        if delete_harvested_session(&tx, id, true)? {
+            deleted += 1;
+        }
+    }
+    tx.commit()?;
+    Ok(serde_json::json!({ "deleted": deleted }))
+}
+
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Harvest new and changed sessions into the database, from one provider
+/// or from every available one. With a `commit` message, a harvest that
+/// changed the database commits it to git.
+pub fn harvest(
+    db_path: &Path,
+    provider: Option<&str>,
+    commit: Option<&str>,
+) -> Result<serde_json::Value> {
+    let providers = provider.map(|p| vec![p.to_string()]);
+    let path = db_path.to_string_lossy();
+    let stats = harvest_run_with_progress(
+        Some(path.as_ref()),
+        providers.as_deref(),
+        None,
+        true,
+        commit.is_some(),
+        commit,
+        &mut |_| ControlFlow::Continue(()),
+    )?;
+    Ok(serde_json::json!({
+        "sessions_found": stats.sessions_found,
+        "sessions_added": stats.sessions_added,
+        "sessions_updated": stats.sessions_updated,
+        "errors": stats.errors,
+    }))
+}
+
+/// A session as written to a sync directory
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncedSession {
+    provider: String,
+    workspace_id: Option<String>,
+    workspace_name: Option<String>,
+    updated_at: i64,
+    session: serde_json::Value,
+}
+
+/// The directory a sync provider names: `local` for the default sync
+/// folder, or a path such as a network drive
+fn sync_directory(provider: &str) -> Result<PathBuf> {
+    match provider.trim().to_lowercase().as_str() {
+        "local" => Ok(dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("csm")
+            .join("sync")),
+        "s3" | "azure" | "azureblob" | "gcs" | "dropbox" | "onedrive" | "googledrive" => {
+            Err(anyhow!(
+                "Sync with {} is not supported yet; use local or a directory",
+                provider
+            ))
+        }
+        _ => {
+            let path = crate::config::expand_home(Path::new(provider.trim()));
+            if !path.is_absolute() {
+                bail!(
+                    "Unknown sync provider '{}': use local or an absolute directory",
+                    provider
+                );
+            }
+            Ok(path)
+        }
+    }
+}
+
+/// Copy sessions between the harvest database and a sync directory:
+/// `push` writes sessions that changed, `pull` imports sessions that are
+/// newer there, and `both` does both
+pub async fn sync_sessions(
+    db_path: &Path,
+    provider: &str,
+    direction: SyncDirection,
+) -> Result<serde_json::Value> {
+    let dir = sync_directory(provider)?;
+    let service = LocalSyncService::new(dir.clone());
+    if !service.test_connection().await? {
+        bail!("Cannot use sync directory {}", dir.display());
+    }
+
+    let mut downloaded = 0;
+    if matches!(direction, SyncDirection::Pull | SyncDirection::Both) {
+        let mut incoming = Vec::new();
+        for remote in service.list_remote_sessions().await? {
+            let data = service.download_session(&remote.session_id).await?;
+            match serde_json::from_slice::<SyncedSession>(&data) {
+                Ok(synced) => incoming.push((remote.session_id, synced)),
+                Err(e) => log::warn!("Skipping synced session {}: {}", remote.session_id, e),
+            }
+        }
+        let db_path = db_path.to_path_buf();
+        downloaded = tokio::task::spawn_blocking(move || -> Result<usize> {
+            let mut conn = open(&db_path)?;
+            let tx = conn.transaction()?;
+            let mut imported = 0;
+            for (id, synced) in incoming {
+                let local: Option<i64> = tx
+                    .query_row(
+                        "SELECT updated_at FROM sessions WHERE id = ?",
+                        [&id],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                if local.is_some_and(|at| at >= synced.updated_at) {
+                    continue;
+                }
+                let mut session: crate::models::ChatSession =
+                    serde_json::from_value(synced.session)?;
+                session.session_id = Some(id);
+                insert_or_update_session(
+                    &tx,
+                    &session,
+                    &synced.provider,
+                    synced.workspace_id.as_deref(),
+                    synced.workspace_name.as_deref(),
+                )?;
+                imported += 1;
+            }
+            tx.commit()?;
+            Ok(imported)
+        })
+        .await??;
+    }
+
+    let mut uploaded = 0;
+    if matches!(direction, SyncDirection::Push | SyncDirection::Both) {
+        let db_path = db_path.to_path_buf();
+        let outgoing = tokio::task::spawn_blocking(move || -> Result<Vec<(String, Vec<u8>)>> {
+            let conn = open(&db_path)?;
+            let mut stmt = conn.prepare(
+                "SELECT id, provider, workspace_id, workspace_name, updated_at, session_json
+                 FROM sessions",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    SyncedSession {
+                        provider: row.get(1)?,
+                        workspace_id: row.get(2)?,
+                        workspace_name: row.get(3)?,
+                        updated_at: row.get(4)?,
+                        session: serde_json::from_str(&row.get::<_, String>(5)?)
+                            .unwrap_or_default(),
+                    },
+                ))
+            })?;
+            let mut outgoing = Vec::new();
+            for row in rows {
+                let (id, synced) = row?;
+                outgoing.push((id, serde_json::to_vec_pretty(&synced)?));
+            }
+            Ok(outgoing)
+        })
+        .await??;
+        for (id, data) in outgoing {
+            let unchanged = service
+                .download_session(&id)
+                .await
+                .is_ok_and(|existing| existing == data);
+            if !unchanged {
+                service.upload_session(&id, &data).await?;
+                uploaded += 1;
+            }
+        }
+    }
+
+    Ok(serde_json::json!({
+        "directory": dir,
+        "uploaded": uploaded,
+        "downloaded": downloaded,
+    }))
+}
+
+/// File an issue in Jira or Linear for each selected session not filed
+/// there yet, or with `action_items`, for each of their open TODOs and
+/// commitments not filed yet
+pub async fn create_issues(
+    db_path: &Path,
+    filter: &SessionFilter,
+    tracker: Option<&str>,
+    project: Option<&str>,
+    action_items: bool,
+) -> Result<serde_json::Value> {
+    require_filter(filter, "file issues for")?;
+    let tracker = issues::tracker(tracker, project)?;
+    let (name, markup) = (tracker.name(), tracker.markup());
+
+    let (db, filter) = (db_path.to_path_buf(), filter.clone());
+    let pending = tokio::task::spawn_blocking(move || -> Result<Vec<(IssueFor, NewIssue)>> {
+        let conn = open(&db)?;
+        issues::ensure_session_issues_table(&conn)?;
+        crate::actions::ensure_action_items_table(&conn)?;
+        let mut pending = Vec::new();
+        for id in select_sessions(&conn, &filter)? {
+            if action_items {
+                let items = crate::actions::list_action_items(
+                    &conn,
+                    &crate::actions::ActionFilter {
+                        kinds: vec![ActionKind::Todo, ActionKind::Commitment],
+                        session: Some(&id),
+                        ..Default::default()
+                    },
+                )?;
+                let prefix = format!("{}:", name);
+                for item in items.iter().filter(|item| {
+                    item.session_id == id
+                        && !item
+                            .exported_to
+                            .as_deref()
+                            .is_some_and(|to| to.starts_with(&prefix))
+                }) {
+                    pending.push((IssueFor::Item(item.id), item_issue(markup, item)));
+                }
+            } else if issues::list_session_issues(&conn, Some(&id), Some(name))?
+                .iter()
+                .all(|stored| stored.session_id != id)
+            {
+                let (id, provider, chat) = find_harvested(&conn, &id)?;
+                let issue = session_issue(markup, &id, &provider, &chat, None);
+                pending.push((IssueFor::Session(id), issue));
+            }
+        }
+        Ok(pending)
+    })
+    .await??;
+
+    let mut filed = Vec::new();
+    for (target, issue) in pending {
+        let created = tracker
+            .create_issue(&issue)
+            .await
+            .map_err(|e| anyhow!("Could not file \"{}\": {}", issue.title, e))?;
+        filed.push((target, created));
+    }
+
+    let db = db_path.to_path_buf();
+    tokio::task::spawn_blocking(move || -> Result<serde_json::Value> {
+        let conn = open(&db)?;
+        for (target, created) in &filed {
+            match target {
+                IssueFor::Session(id) => issues::save_session_issue(&conn, id, created)?,
+                IssueFor::Item(id) => {
+                    crate::actions::set_action_exported(&conn, *id, &created.reference())?
+                }
+            }
+        }
+        let created: Vec<&issues::CreatedIssue> = filed.iter().map(|(_, issue)| issue).collect();
+        Ok(serde_json::json!({
+            "created": created.len(),
+            "issues": created,
+        }))
+    })
+    .await?
+}
+
+/// What an issue is filed for
+enum IssueFor {
+    Session(String),
+    /// An action item, by ID
+    Item(i64),
+}
+
+/// Email a digest of the last `days` days to `to`, or to the recipients
+/// under `[integrations.smtp]`
+pub fn send_digest(db_path: &Path, to: &[String], days: u32) -> Result<serde_json::Value> {
+    let conn = open(db_path)?;
+    let until = Utc::now();
+    let digest = Digest::load(&conn, until - Duration::days(days as i64), until, 5)?;
+    let recipients = crate::commands::send_digest(&digest, to)?;
+    Ok(serde_json::json!({
+        "subject": digest.subject(),
+        "to": recipients,
+        "new_sessions": digest.new_sessions,
+        "messages": digest.messages,
+    }))
+}
+
+/// Send an HTTP request. Answers other than 2xx fail the action.
+pub async fn http_request(
+    method: &str,
+    url: &str,
+    headers: &HashMap<String, String>,
+    body: Option<String>,
+) -> Result<serde_json::Value> {
+    let method = reqwest::Method::from_bytes(method.trim().to_uppercase().as_bytes())
+        .map_err(|_| anyhow!("Invalid HTTP method '{}'", method))?;
+    let client = reqwest::Client::builder().timeout(HTTP_TIMEOUT).build()?;
+    let mut request = client.request(method.clone(), url);
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    if let Some(body) = body {
+        request = request.body(body);
+    }
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("{} {} failed", method, url))?;
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+    let text = prefix_chars(&text, MAX_OUTPUT_CHARS);
+    if !status.is_success() {
+        bail!("{} {} returned {}: {}", method, url, status, text.trim());
+    }
+    let body = serde_json::from_str(text).unwrap_or_else(|_| serde_json::json!(text));
+    Ok(serde_json::json!({
+        "status": status.as_u16(),
+        "body": body,
+    }))
+}
+
+/// Quote `value` as one argument for the shell `shell` runs commands with.
+/// cmd cannot quote `"`, `%`, `!` or line breaks, so values with them are
+/// refused there.
+pub fn shell_quote(value: &str) -> Result<String> {
+    if cfg!(windows) {
+        if value.contains(['"', '%', '!', '\r', '\n']) {
+            bail!(
+                "'{}' cannot be passed safely to cmd; use the action's env instead",
+                value
+            );
+        }
+        Ok(format!("\"{}\"", value))
+    } else {
+        Ok(format!("'{}'", value.replace('\'', r"'\''")))
+    }
+}
+
+/// Run a command through the system shell, capturing its output. A
+/// non-zero exit fails the action.
+pub async fn shell(
+    command: &str,
+    cwd: Option<&str>,
+    env: &HashMap<String, String>,
+) -> Result<serde_json::Value> {
+    let (program, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut cmd = tokio::process::Command::new(program);
+    cmd.args([flag, command])
+        .envs(env)
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true);
+    if let Some(dir) = cwd {
+        cmd.current_dir(crate::config::expand_home(Path::new(dir)));
+    }
+    let output = cmd
+        .output()
+        .await
+        .with_context(|| format!("Failed to run '{}'", command))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let (stdout, stderr) = (
+        prefix_chars(&stdout, MAX_OUTPUT_CHARS),
+        prefix_chars(&stderr, MAX_OUTPUT_CHARS),
+    );
+    if !output.status.success() {
+        let code = output
+            .status
+            .code()
+            .map_or("a signal".to_string(), |c| format!("exit code {}", c));
+        bail!("'{}' failed with {}: {}", command, code, stderr.trim());
+    }
+    Ok(serde_json::json!({
+        "exit_code": output.status.code(),
+        "stdout": stdout,
+        "stderr": stderr,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(id: &str, title: &str) -> crate::models::ChatSession {
+        let now = Utc::now().timestamp_millis();
+        serde_json::from_value(serde_json::json!({
+            "sessionId": id,
+            "customTitle": title,
+            "creationDate": now,
+            "lastMessageDate": now,
+            "requests": [{ "message": { "text": title } }]
+        }))
+        .unwrap()
+    }
+
+    fn harvest_db(dir: &Path) -> PathBuf {
+        let path = dir.join("harvest.db");
+        crate::commands::harvest_init(Some(path.to_str().unwrap()), false).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_session_actions() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = harvest_db(dir.path());
+        let conn = Connection::open(&db).unwrap();
+        for (id, provider, title) in [
+            ("old-copilot", "copilot", "Rust lifetimes"),
+            ("new-copilot", "copilot", "Release notes"),
+            ("cursor", "cursor", "Rust macros"),
+        ] {
+            insert_or_update_session(&conn, &session(id, title), provider, None, None).unwrap();
+        }
+        let long_ago = (Utc::now() - Duration::days(90)).timestamp_millis();
+        conn.execute(
+            "UPDATE sessions SET updated_at = ?1 WHERE id = 'old-copilot'",
+            [long_ago],
+        )
+        .unwrap();
+        crate::reviews::set_user_tags(&conn, "cursor", &["keep".to_string()]).unwrap();
+
+        let filter =
+            |value: serde_json::Value| -> SessionFilter { serde_json::from_value(value).unwrap() };
+        let select = |f: SessionFilter| select_sessions(&conn, &f).unwrap();
+        assert_eq!(
+            select(filter(serde_json::json!({ "older_than_days": 30 }))),
+            ["old-copilot"]
+        );
+        assert_eq!(
+            select(filter(serde_json::json!({ "tags": ["keep"] }))),
+            ["cursor"]
+        );
+        assert_eq!(
+            select(filter(
+                serde_json::json!({ "provider": "copilot", "query": "rust" })
+            )),
+            ["old-copilot"]
+        );
+
+        let out = dir.path().join("rust.json");
+        let rust = filter(serde_json::json!({ "query": "rust" }));
+        let result = export_sessions(&db, &rust, "json", out.to_str().unwrap()).unwrap();
+        assert_eq!(result["exported"], 2);
+        let exported: Vec<serde_json::Value> =
+            serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+        assert_eq!(exported.len(), 2);
+
+        // Sync to a directory and back into an empty database
+        let sync_dir = dir.path().join("sync");
+        let provider = sync_dir.to_str().unwrap();
+        let pushed = sync_sessions(&db, provider, SyncDirection::Push)
+            .await
+            .unwrap();
+        assert_eq!(pushed["uploaded"], 3);
+        let again = sync_sessions(&db, provider, SyncDirection::Push)
+            .await
+            .unwrap();
+        assert_eq!(again["uploaded"], 0);
+        let other = tempfile::tempdir().unwrap();
+        let other_db = harvest_db(other.path());
+        let pulled = sync_sessions(&other_db, provider, SyncDirection::Pull)
+            .await
+            .unwrap();
+        assert_eq!(pulled["downloaded"], 3);
+        assert!(sync_sessions(&db, "s3", SyncDirection::Push).await.is_err());
+
+        // Archive and delete move sessions to the trash
+        let archive = dir.path().join("archive");
+        let old = filter(serde_json::json!({ "older_than_days": 30 }));
+        let result = archive_sessions(&db, &old, archive.to_str().unwrap()).unwrap();
+        assert_eq!(result["archived"], 1);
+        let file = PathBuf::from(result["file"].as_str().unwrap());
+        assert_eq!(std::fs::read_to_string(file).unwrap().lines().count(), 1);
+        let result =
+            delete_sessions(&db, &filter(serde_json::json!({ "provider": "cursor" }))).unwrap();
+        assert_eq!(result["deleted"], 1);
+        assert!(delete_sessions(&db, &filter(serde_json::json!({}))).is_err());
+        let trashed: i64 = conn
+            .query_row("SELECT COUNT(*) FROM deleted_sessions", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(trashed, 2);
+        assert_eq!(select(filter(serde_json::json!({}))), ["new-copilot"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_shell_action() {
+        let env = HashMap::from([("GREETING".to_string(), "hello".to_string())]);
+        let result = shell("echo $GREETING; echo oops >&2", None, &env)
+            .await
+            .unwrap();
+        assert_eq!(result["exit_code"], 0);
+        assert_eq!(result["stdout"], "hello\n");
+        assert_eq!(result["stderr"], "oops\n");
+        let err = shell("echo broken >&2; exit 3", None, &env)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("exit code 3: broken"), "{}", err);
+
+        let value = "$(echo pwned)'; echo \"x\" `id`";
+        let command = format!("printf %s {}", shell_quote(value).unwrap());
+        let result = shell(&command, None, &env).await.unwrap();
+        assert_eq!(result["stdout"], value);
+    }
+}
@@ -0,0 +1,312 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Cron schedules
+//!
+//! Standard five-field expressions (`minute hour day-of-month month
+//! day-of-week`) with lists, ranges, steps, month and weekday names, and the
+//! `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly` shorthands. As in
+//! Vixie cron, when both day fields are restricted a day matching either one
+//! counts.
+
+use super::zoneinfo::ZoneInfo;
+use crate::datetime::DisplayTimezone;
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Timelike, Utc};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// How far ahead to look for a matching time before giving up, for
+/// expressions such as `0 0 30 2 *` that never match
+const SEARCH_YEARS: i64 = 5;
+
+/// A parsed cron expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    /// Bit `n` set when the field matches `n`
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    /// Sunday is 0
+    weekdays: u8,
+    /// Whether the day-of-month field is `*`
+    any_day: bool,
+    /// Whether the day-of-week field is `*`
+    any_weekday: bool,
+}
+
+impl FromStr for CronSchedule {
+    type Err = anyhow::Error;
+
+    fn from_str(expr: &str) -> Result<Self> {
+        Self::parse(expr)
+    }
+}
+
+impl CronSchedule {
+    /// Parse a five-field expression or an `@` shorthand
+    pub fn parse(expr: &str) -> Result<Self> {
+        let expr = expr.trim();
+        let expanded = match expr.to_ascii_lowercase().as_str() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other if other.starts_with('@') => {
+                bail!("Unsupported cron shorthand '{}'", expr)
+            }
+            _ => expr,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        if fields.len() != 5 {
+            bail!(
+                "Invalid cron expression '{}': expected 5 fields (minute hour day month weekday), found {}",
+                expr,
+                fields.len()
+            );
+        }
+        let field = |i: usize, name: &str, min: u32, max: u32, names: &[&str]| {
+            parse_field(fields[i], min, max, names)
+                .map_err(|e| anyhow!("Invalid cron expression '{}': {} {}", expr, name, e))
+        };
+        let minutes = field(0, "minute", 0, 59, &[])?;
+        let hours = field(1, "hour", 0, 23, &[])?;
+        let days = field(2, "day of month", 1, 31, &[])?;
+        let months = field(3, "month", 1, 12, &MONTHS)?;
+        // 7 is Sunday too
+        let weekdays = field(4, "day of week", 0, 7, &WEEKDAYS)?;
+        Ok(Self {
+            minutes,
+            hours: hours as u32,
+            days: days as u32,
+            months: months as u16,
+            weekdays: ((weekdays | (weekdays >> 7)) & 0x7f) as u8,
+            any_day: fields[2].starts_with('*'),
+            any_weekday: fields[4].starts_with('*'),
+        })
+    }
+
+    /// Whether the schedule fires on `date`
+    pub fn matches_date(&self, date: NaiveDate) -> bool {
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        if self.any_day || self.any_weekday {
+            day && weekday
+        } else {
+            day || weekday
+        }
+    }
+
+    /// The first time after `after` the schedule fires, reading its fields
+    /// as wall-clock time in `tz`
+    pub fn next_after(&self, after: DateTime<Utc>, tz: &ScheduleTimezone) -> Option<DateTime<Utc>> {
+        let start = tz.local(after).with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = start + Duration::days(366 * SEARCH_YEARS);
+        let mut t = start;
+        while t < limit {
+            let date = t.date();
+            if self.months & (1 << date.month()) == 0 {
+                let (year, month) = match date.month() {
+                    12 => (date.year() + 1, 1),
+                    m => (date.year(), m + 1),
+                };
+                t = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.matches_date(date) {
+                t = date.succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if self.hours & (1 << t.hour()) == 0 {
+                t = midnight(date)? + Duration::hours(t.hour() as i64 + 1);
+            } else if self.minutes & (1 << t.minute()) == 0 {
+                t += Duration::minutes(1);
+            } else {
+                match tz.resolve(t) {
+                    Some(at) if at > after => return Some(at),
+                    _ => t += Duration::minutes(1),
+                }
+            }
+        }
+        None
+    }
+}
+
+fn midnight(date: NaiveDate) -> Option<NaiveDateTime> {
+    date.and_hms_opt(0, 0, 0)
+}
+
+/// Bits of the values a comma-separated field matches
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64> {
+    let value = |s: &str| -> Result<u32> {
+        let lower = s.to_ascii_lowercase();
+        let n = match names.iter().position(|n| *n == lower) {
+            // Names count from the field's minimum: jan is 1, sun is 0
+            Some(i) => i as u32 + min,
+            None => s.parse().map_err(|_| anyhow!("'{}' is not a number", s))?,
+        };
+        if n < min || n > max {
+            bail!("{} is outside {}-{}", n, min, max);
+        }
+        Ok(n)
+    };
+    let mut bits = 0u64;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| anyhow!("step '{}' must be a positive number", step))?;
+                (range, Some(step))
+            }
+            None => (item, None),
+        };
+        let (from, to) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((from, to)) => (value(from)?, value(to)?),
+                // `5/15` runs from 5 to the end of the range
+                None if step.is_some() => (value(range)?, max),
+                None => {
+                    let n = value(range)?;
+                    (n, n)
+                }
+            },
+        };
+        if from > to {
+            bail!("range {} goes backwards", range);
+        }
+        for n in (from..=to).step_by(step.unwrap_or(1) as usize) {
+            bits |= 1 << n;
+        }
+    }
+    Ok(bits)
+}
+
+/// The timezone a schedule's times are read in
+#[derive(Debug, Clone)]
+pub enum ScheduleTimezone {
+    /// `local`, `utc` or a fixed offset
+    Display(DisplayTimezone),
+    /// A named zone such as `Europe/Berlin`
+    Zone(Arc<ZoneInfo>),
+}
+
+impl ScheduleTimezone {
+    /// The timezone a schedule names, or the display timezone if it names none
+    pub fn parse(name: Option<&str>) -> Result<Self> {
+        let Some(name) = name.map(str::trim).filter(|n| !n.is_empty()) else {
+            return Ok(Self::Display(crate::datetime::display_timezone()));
+        };
+        if let Ok(tz) = name.parse::<DisplayTimezone>() {
+            return Ok(Self::Display(tz));
+        }
+        static ZONES: OnceLock<Mutex<HashMap<String, Arc<ZoneInfo>>>> = OnceLock::new();
+        let mut zones = ZONES
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(zone) = zones.get(name) {
+            return Ok(Self::Zone(zone.clone()));
+        }
+        let zone = Arc::new(ZoneInfo::load(name).map_err(|e| {
+            anyhow!(
+                "{}: use local, utc, an offset like +05:30, or a name like Europe/Berlin",
+                e
+            )
+        })?);
+        zones.insert(name.to_string(), zone.clone());
+        Ok(Self::Zone(zone))
+    }
+
+    /// Wall-clock time at an instant
+    pub fn local(&self, at: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Self::Display(tz) => tz.convert(at).naive_local(),
+            Self::Zone(zone) => zone.local(at),
+        }
+    }
+
+    /// The instant a wall-clock time stands for. Times skipped when clocks
+    /// go forward resolve to the hour after.
+    pub fn resolve(&self, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Display(tz) => tz.resolve(local),
+            Self::Zone(zone) => zone.resolve(local),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        let tz = ScheduleTimezone::Display(DisplayTimezone::Utc);
+        let next = |expr: &str, after| expr.parse::<CronSchedule>().unwrap().next_after(after, &tz);
+        // Saturday 17 October 2026
+        let after = utc(2026, 10, 17, 10, 7);
+        assert_eq!(next("*/15 * * * *", after), Some(utc(2026, 10, 17, 10, 15)));
+        assert_eq!(
+            next("0 9 * * mon-fri", after),
+            Some(utc(2026, 10, 19, 9, 0))
+        );
+        assert_eq!(next("30 8 1 */3 *", after), Some(utc(2027, 1, 1, 8, 30)));
+        assert_eq!(next("@monthly", after), Some(utc(2026, 11, 1, 0, 0)));
+        assert_eq!(next("0 0 * * 7", after), Some(utc(2026, 10, 18, 0, 0)));
+        // Either day field matches: the 20th or the next Sunday
+        assert_eq!(next("0 12 20 * sun", after), Some(utc(2026, 10, 18, 12, 0)));
+        assert_eq!(next("0 12 20 * *", after), Some(utc(2026, 10, 20, 12, 0)));
+        assert_eq!(next("0 0 29 2 *", after), Some(utc(2028, 2, 29, 0, 0)));
+        assert_eq!(next("0 0 30 2 *", after), None);
+        // Strictly after, even on an exact match
+        assert_eq!(next("7 10 * * *", after), Some(utc(2026, 10, 18, 10, 7)));
+
+        for bad in [
+            "* * * *",
+            "60 * * * *",
+            "* * * foo *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "@reboot",
+        ] {
+            assert!(bad.parse::<CronSchedule>().is_err(), "{}", bad);
+        }
+
+        // 02:30 is skipped when New York moves its clocks forward
+        let york = ScheduleTimezone::Zone(Arc::new(
+            ZoneInfo::from_rule("America/New_York", "EST5EDT,M3.2.0,M11.1.0").unwrap(),
+        ));
+        let daily = CronSchedule::parse("30 2 * * *").unwrap();
+        let after = utc(2026, 3, 7, 12, 0);
+        assert_eq!(daily.next_after(after, &york), Some(utc(2026, 3, 8, 7, 30)));
+        let after = utc(2026, 3, 8, 7, 30);
+        assert_eq!(daily.next_after(after, &york), Some(utc(2026, 3, 9, 6, 30)));
+        let nine = CronSchedule::parse("0 9 * * *").unwrap();
+        assert_eq!(
+            nine.next_after(utc(2026, 7, 1, 0, 0), &york),
+            Some(utc(2026, 7, 1, 13, 0))
+        );
+        assert_eq!(
+            nine.next_after(utc(2026, 12, 1, 0, 0), &york),
+            Some(utc(2026, 12, 1, 14, 0))
+        );
+
+        assert!(matches!(
+            ScheduleTimezone::parse(Some("+05:30")).unwrap(),
+            ScheduleTimezone::Display(DisplayTimezone::Fixed(_))
+        ));
+        assert!(ScheduleTimezone::parse(Some("No/Such_Zone")).is_err());
+    }
+}
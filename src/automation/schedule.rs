@@ -0,0 +1,108 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Next-run times kept across restarts
+//!
+//! The scheduler writes when each workflow is next due to a JSON file, so a
+//! run that came due while it was stopped still happens (once) when it
+//! starts again. A workflow whose triggers changed since is scheduled afresh.
+
+use super::{Trigger, Workflow};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// When one workflow is next due
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledRun {
+    pub next_run: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<DateTime<Utc>>,
+    /// The timed triggers `next_run` was worked out from
+    pub triggers: String,
+}
+
+/// Next-run times by workflow ID
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleState {
+    #[serde(default)]
+    pub workflows: BTreeMap<String, ScheduledRun>,
+}
+
+impl ScheduleState {
+    /// Read the state file; a missing file is an empty schedule
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text)
+                .with_context(|| format!("Invalid schedule state {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    /// Write the state file, replacing it in one step
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, path).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// What a workflow's next run depends on
+    pub fn fingerprint(workflow: &Workflow) -> String {
+        let timed: Vec<&Trigger> = workflow
+            .triggers
+            .iter()
+            .filter(|t| {
+                matches!(
+                    t,
+                    Trigger::Interval { .. } | Trigger::TimeOfDay { .. } | Trigger::Schedule { .. }
+                )
+            })
+            .collect();
+        serde_json::to_string(&timed).unwrap_or_default()
+    }
+
+    /// The saved next run, if the workflow's triggers have not changed since
+    pub fn saved_run(&self, workflow: &Workflow) -> Option<DateTime<Utc>> {
+        self.workflows
+            .get(&workflow.id)
+            .filter(|run| run.triggers == Self::fingerprint(workflow))
+            .map(|run| run.next_run)
+    }
+
+    /// When a workflow is next due: the saved time, or the first time its
+    /// triggers fire after `now`
+    pub fn next_run(&self, workflow: &Workflow, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.saved_run(workflow).or_else(|| workflow.next_fire(now))
+    }
+
+    /// Set when a workflow is next due, or drop it when it never is
+    pub fn set(
+        &mut self,
+        workflow: &Workflow,
+        next_run: Option<DateTime<Utc>>,
+        last_run: Option<DateTime<Utc>>,
+    ) {
+        match next_run {
+            Some(next_run) => {
+                let last_run =
+                    last_run.or_else(|| self.workflows.get(&workflow.id).and_then(|r| r.last_run));
+                self.workflows.insert(
+                    workflow.id.clone(),
+                    ScheduledRun {
+                        next_run,
+                        last_run,
+                        triggers: Self::fingerprint(workflow),
+                    },
+                );
+            }
+            None => {
+                self.workflows.remove(&workflow.id);
+            }
+        }
+    }
+}
@@ -0,0 +1,288 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Built-in workflow templates
+//!
+//! A template is a workflow definition with `{{name}}` placeholders for its
+//! parameters. Placeholders that are not parameters, such as `{{today}}`, are
+//! left for the engine to fill in when the workflow runs.
+
+use super::Workflow;
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::HashMap;
+
+/// A value a template asks for
+#[derive(Debug, Clone, Copy)]
+pub struct TemplateParam {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Used when no value is given; parameters without one are required
+    pub default: Option<&'static str>,
+    /// Whether the value is a whole number rather than text
+    pub number: bool,
+}
+
+/// A workflow that ships with csm
+#[derive(Debug, Clone, Copy)]
+pub struct WorkflowTemplate {
+    pub id: &'static str,
+    pub description: &'static str,
+    pub params: &'static [TemplateParam],
+    /// Workflow JSON with `{{param}}` placeholders
+    workflow: &'static str,
+}
+
+const CRON: &str = "When to run, as a cron expression";
+
+pub const TEMPLATES: &[WorkflowTemplate] = &[
+    WorkflowTemplate {
+        id: "nightly-harvest",
+        description: "Harvest new sessions every night and commit the database to git",
+        params: &[TemplateParam {
+            name: "cron",
+            description: CRON,
+            default: Some("0 2 * * *"),
+            number: false,
+        }],
+        workflow: r#"{
+            "name": "Nightly harvest",
+            "description": "Harvest new sessions and commit the database to git (run `csm harvest git init` once first)",
+            "triggers": [{ "type": "schedule", "cron": "{{cron}}" }],
+            "actions": [{ "type": "harvest", "commit": "Nightly harvest {{today}}" }]
+        }"#,
+    },
+    WorkflowTemplate {
+        id: "weekly-obsidian-export",
+        description: "Export the week's sessions to an Obsidian vault as Markdown",
+        params: &[
+            TemplateParam {
+                name: "vault",
+                description: "Obsidian vault directory",
+                default: None,
+                number: false,
+            },
+            TemplateParam {
+                name: "folder",
+                description: "Folder in the vault for the exports",
+                default: Some("Chat Sessions"),
+                number: false,
+            },
+            TemplateParam {
+                name: "cron",
+                description: CRON,
+                default: Some("0 17 * * fri"),
+                number: false,
+            },
+        ],
+        workflow: r#"{
+            "name": "Weekly Obsidian export",
+            "description": "Export sessions updated in the last week to the vault as Markdown",
+            "triggers": [{ "type": "schedule", "cron": "{{cron}}" }],
+            "actions": [{
+                "type": "export",
+                "filter": { "newer_than_days": 7 },
+                "format": "md",
+                "output": "{{vault}}/{{folder}}/{{today}}.md"
+            }]
+        }"#,
+    },
+    WorkflowTemplate {
+        id: "weekly-digest",
+        description: "Email a digest of the week's sessions, providers, and topics",
+        params: &[
+            TemplateParam {
+                name: "to",
+                description: "Address to send the digest to",
+                default: None,
+                number: false,
+            },
+            TemplateParam {
+                name: "cron",
+                description: CRON,
+                default: Some("0 8 * * mon"),
+                number: false,
+            },
+        ],
+        workflow: r#"{
+            "name": "Weekly digest",
+            "description": "Email the week's new sessions, top topics, and longest sessions (needs [integrations.smtp])",
+            "triggers": [{ "type": "schedule", "cron": "{{cron}}" }],
+            "actions": [{ "type": "send_digest", "to": ["{{to}}"], "days": 7 }]
+        }"#,
+    },
+    WorkflowTemplate {
+        id: "stale-session-archival",
+        description: "Archive sessions nobody has touched in a while, then move them to the trash",
+        params: &[
+            TemplateParam {
+                name: "days",
+                description: "Archive sessions not updated for this many days",
+                default: Some("90"),
+                number: true,
+            },
+            TemplateParam {
+                name: "destination",
+                description: "Directory for the archive files",
+                default: Some("~/chasm-archive"),
+                number: false,
+            },
+            TemplateParam {
+                name: "cron",
+                description: CRON,
+                default: Some("0 3 * * sun"),
+                number: false,
+            },
+        ],
+        workflow: r#"{
+            "name": "Stale session archival",
+            "description": "Archive sessions not updated for {{days}} days to JSON Lines, then move them to the trash",
+            "triggers": [{ "type": "schedule", "cron": "{{cron}}" }],
+            "actions": [{
+                "type": "archive",
+                "filter": { "older_than_days": {{days}} },
+                "destination": "{{destination}}"
+            }]
+        }"#,
+    },
+    WorkflowTemplate {
+        id: "secret-leak-alert",
+        description: "Alert a notification channel when a session looks like it contains an API key or private key",
+        params: &[TemplateParam {
+            name: "channel",
+            description: "Notification channel under [notifications.channels]",
+            default: None,
+            number: false,
+        }],
+        workflow: r#"{
+            "name": "Secret leak alert",
+            "description": "Scan session files as they change for API keys and private keys (needs grep)",
+            "triggers": [{
+                "type": "file_change",
+                "pattern": "**/chatSessions/*.json",
+                "events": ["created", "modified"]
+            }],
+            "actions": [
+                {
+                    "type": "shell",
+                    "command": "grep -cE -e 'AKIA[0-9A-Z]{16}|gh[pousr]_[A-Za-z0-9]{36}|sk-[A-Za-z0-9_-]{32,}|xox[abpr]-[A-Za-z0-9-]{10,}|-----BEGIN [A-Z ]*PRIVATE KEY-----' \"$SESSION_FILE\" || true",
+                    "env": { "SESSION_FILE": "{{event.path}}" }
+                },
+                {
+                    "type": "if",
+                    "condition": { "type": "matches", "value": "{{shell_output}}", "pattern": "^[1-9]" },
+                    "then": [{
+                        "type": "notify",
+                        "channel": { "type": "named", "name": "{{channel}}" },
+                        "title": "Possible secret in a chat session",
+                        "message": "{{event.path}} looks like it contains an API key or private key"
+                    }]
+                }
+            ]
+        }"#,
+    },
+];
+
+/// The built-in template with this ID
+pub fn find(id: &str) -> Option<&'static WorkflowTemplate> {
+    TEMPLATES.iter().find(|t| t.id == id)
+}
+
+impl WorkflowTemplate {
+    /// The workflow definition, its parameters filled in from `values` or
+    /// their defaults. It has no ID, so it takes the library file's name.
+    pub fn instantiate(&self, values: &HashMap<String, String>) -> Result<serde_json::Value> {
+        if let Some(unknown) = values
+            .keys()
+            .find(|name| !self.params.iter().any(|p| p.name == name.as_str()))
+        {
+            let names: Vec<&str> = self.params.iter().map(|p| p.name).collect();
+            bail!(
+                "Template '{}' has no parameter '{}' (it takes {})",
+                self.id,
+                unknown,
+                names.join(", ")
+            );
+        }
+
+        let mut text = self.workflow.to_string();
+        for param in self.params {
+            let value = values
+                .get(param.name)
+                .map(String::as_str)
+                .or(param.default)
+                .filter(|v| !v.trim().is_empty())
+                .ok_or_else(|| anyhow!("Template '{}' needs a {}", self.id, param.name))?;
+            let value = if param.number {
+                value
+                    .trim()
+                    .parse::<u32>()
+                    .map_err(|_| anyhow!("{} must be a whole number, not '{}'", param.name, value))?
+                    .to_string()
+            } else {
+                // Escaped for a JSON string, without the quotes
+                let quoted = serde_json::to_string(value)?;
+                quoted[1..quoted.len() - 1].to_string()
+            };
+            text = text.replace(&format!("{{{{{}}}}}", param.name), &value);
+        }
+
+        let definition: serde_json::Value = serde_json::from_str(&text)
+            .with_context(|| format!("Template '{}' is not valid JSON", self.id))?;
+        let mut workflow: Workflow = serde_json::from_value(definition.clone())
+            .with_context(|| format!("Template '{}' is not a valid workflow", self.id))?;
+        workflow.id = self.id.to_string();
+        workflow.validate()?;
+        Ok(definition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automation::{Action, Trigger};
+
+    #[test]
+    fn test_templates_instantiate() {
+        for template in TEMPLATES {
+            let values: HashMap<String, String> = template
+                .params
+                .iter()
+                .filter(|p| p.default.is_none())
+                .map(|p| (p.name.to_string(), "x".to_string()))
+                .collect();
+            let definition = template.instantiate(&values).unwrap();
+            assert!(definition.get("id").is_none());
+        }
+
+        let archival = find("stale-session-archival").unwrap();
+        let values = HashMap::from([
+            ("days".to_string(), "30".to_string()),
+            ("destination".to_string(), r#"C:\archive "old""#.to_string()),
+        ]);
+        let workflow: Workflow =
+            serde_json::from_value(archival.instantiate(&values).unwrap()).unwrap();
+        match &workflow.actions[0] {
+            Action::Archive {
+                filter,
+                destination,
+            } => {
+                assert_eq!(filter.older_than_days, Some(30));
+                assert_eq!(destination, r#"C:\archive "old""#);
+            }
+            other => panic!("unexpected action {:?}", other),
+        }
+        assert!(matches!(
+            &workflow.triggers[0],
+            Trigger::Schedule { cron, .. } if cron == "0 3 * * sun"
+        ));
+
+        let days = HashMap::from([("days".to_string(), "soon".to_string())]);
+        assert!(archival.instantiate(&days).is_err());
+        let cron = HashMap::from([("cron".to_string(), "every day".to_string())]);
+        assert!(archival.instantiate(&cron).is_err());
+        let unknown = HashMap::from([("vault".to_string(), "~/notes".to_string())]);
+        assert!(archival.instantiate(&unknown).is_err());
+        // Required parameters have no default
+        let export = find("weekly-obsidian-export").unwrap();
+        assert!(export.instantiate(&HashMap::new()).is_err());
+    }
+}
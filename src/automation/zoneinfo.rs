@@ -0,0 +1,522 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Named timezones from the system's tz database
+//!
+//! Schedules may name a timezone such as `Europe/Berlin`, so their times
+//! follow its daylight saving changes. Zones are read from TZif files under
+//! `$TZDIR` or `/usr/share/zoneinfo`; times past the last transition in the
+//! file follow the POSIX TZ rule at its end.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Utc, Weekday};
+use std::path::PathBuf;
+
+/// Directory of the tz database unless `TZDIR` says otherwise
+const DEFAULT_TZDIR: &str = "/usr/share/zoneinfo";
+
+/// A named timezone: its UTC offsets over time
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZoneInfo {
+    pub name: String,
+    /// UTC instants (seconds) where the offset changes, and the offset
+    /// (seconds east of UTC) from then on
+    transitions: Vec<(i64, i32)>,
+    /// Offset before the first transition
+    initial: i32,
+    /// Offsets after the last transition
+    rule: Option<PosixTz>,
+}
+
+impl ZoneInfo {
+    /// Read a zone such as `America/New_York` from the tz database
+    pub fn load(name: &str) -> Result<Self> {
+        let valid = !name.is_empty()
+            && !name.starts_with('/')
+            && name
+                .split('/')
+                .all(|part| !part.is_empty() && part != "." && part != "..");
+        if !valid {
+            bail!("Invalid timezone name '{}'", name);
+        }
+        let dir = std::env::var_os("TZDIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_TZDIR));
+        let path = dir.join(name);
+        let data = std::fs::read(&path)
+            .with_context(|| format!("Unknown timezone '{}' (no {})", name, path.display()))?;
+        Self::parse(name, &data)
+    }
+
+    /// Parse the contents of a TZif file
+    pub fn parse(name: &str, data: &[u8]) -> Result<Self> {
+        let invalid = || anyhow!("Timezone file for '{}' is not a TZif file", name);
+        let header = Header::read(data).ok_or_else(invalid)?;
+        // Version 2 and later repeat the data with 64-bit times, then add
+        // the POSIX rule
+        let (header, body, time_size) = if header.version >= b'2' {
+            let rest = data.get(header.len(4)..).ok_or_else(invalid)?;
+            let header = Header::read(rest).ok_or_else(invalid)?;
+            (header, &rest[44..], 8)
+        } else {
+            (header, &data[44..], 4)
+        };
+
+        let mut cursor = Cursor { data: body, pos: 0 };
+        let times: Vec<i64> = (0..header.timecnt)
+            .map(|_| cursor.int(time_size))
+            .collect::<Option<_>>()
+            .ok_or_else(invalid)?;
+        let indices: Vec<usize> = (0..header.timecnt)
+            .map(|_| cursor.bytes(1).map(|b| b[0] as usize))
+            .collect::<Option<_>>()
+            .ok_or_else(invalid)?;
+        let types: Vec<(i32, bool)> = (0..header.typecnt)
+            .map(|_| {
+                let offset = cursor.int(4)? as i32;
+                let dst = cursor.bytes(2)?[0] != 0;
+                Some((offset, dst))
+            })
+            .collect::<Option<_>>()
+            .ok_or_else(invalid)?;
+        if types.is_empty() || indices.iter().any(|&i| i >= types.len()) {
+            return Err(invalid());
+        }
+        cursor
+            .bytes(
+                header.charcnt
+                    + header.leapcnt * (time_size + 4)
+                    + header.isstdcnt
+                    + header.isutcnt,
+            )
+            .ok_or_else(invalid)?;
+
+        let rule = if time_size == 8 {
+            let footer = &body[cursor.pos..];
+            std::str::from_utf8(footer)
+                .ok()
+                .and_then(|f| f.strip_prefix('\n'))
+                .and_then(|f| f.split('\n').next())
+                .filter(|f| !f.is_empty())
+                .map(|f| {
+                    PosixTz::parse(f)
+                        .ok_or_else(|| anyhow!("Unsupported rule '{}' for timezone '{}'", f, name))
+                })
+                .transpose()?
+        } else {
+            None
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            transitions: times
+                .into_iter()
+                .zip(indices)
+                .map(|(at, i)| (at, types[i].0))
+                .collect(),
+            initial: types[0].0,
+            rule,
+        })
+    }
+
+    /// A zone that follows a POSIX TZ rule such as `EST5EDT,M3.2.0,M11.1.0`
+    pub fn from_rule(name: &str, rule: &str) -> Option<Self> {
+        let rule = PosixTz::parse(rule)?;
+        Some(Self {
+            name: name.to_string(),
+            transitions: Vec::new(),
+            initial: rule.std_offset,
+            rule: Some(rule),
+        })
+    }
+
+    /// Seconds east of UTC at an instant
+    pub fn offset_at(&self, at: DateTime<Utc>) -> i32 {
+        let t = at.timestamp();
+        let after = self.transitions.partition_point(|(when, _)| *when <= t);
+        match (after, &self.rule) {
+            (n, Some(rule)) if n == self.transitions.len() => rule.offset_at(t),
+            (0, _) => self.initial,
+            (n, _) => self.transitions[n - 1].1,
+        }
+    }
+
+    /// Wall-clock time in this zone
+    pub fn local(&self, at: DateTime<Utc>) -> NaiveDateTime {
+        at.naive_utc() + Duration::seconds(self.offset_at(at) as i64)
+    }
+
+    /// The instant a wall-clock time stands for: the earlier one when
+    /// clocks go back, and an hour (or however far they jump) later when
+    /// they skip it
+    pub fn resolve(&self, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+        let as_utc = local.and_utc();
+        let day = Duration::days(1);
+        let mut offsets = vec![
+            self.offset_at(as_utc - day),
+            self.offset_at(as_utc),
+            self.offset_at(as_utc + day),
+        ];
+        offsets.sort_unstable();
+        offsets.dedup();
+        let at = |offset: i32| as_utc - Duration::seconds(offset as i64);
+        offsets
+            .iter()
+            .map(|&offset| at(offset))
+            .filter(|t| self.local(*t) == local)
+            .min()
+            // Skipped by a jump forward: read it with the offset before
+            .or_else(|| Some(at(self.offset_at(as_utc - day))))
+    }
+}
+
+struct Header {
+    version: u8,
+    isutcnt: usize,
+    isstdcnt: usize,
+    leapcnt: usize,
+    timecnt: usize,
+    typecnt: usize,
+    charcnt: usize,
+}
+
+impl Header {
+    fn read(data: &[u8]) -> Option<Self> {
+        if data.len() < 44 || &data[..4] != b"TZif" {
+            return None;
+        }
+        let count = |i: usize| {
+            let at = 20 + i * 4;
+            u32::from_be_bytes(data[at..at + 4].try_into().unwrap()) as usize
+        };
+        Some(Self {
+            version: data[4],
+            isutcnt: count(0),
+            isstdcnt: count(1),
+            leapcnt: count(2),
+            timecnt: count(3),
+            typecnt: count(4),
+            charcnt: count(5),
+        })
+    }
+
+    /// Length of the header and its data with `time_size`-byte times
+    fn len(&self, time_size: usize) -> usize {
+        44 + self.timecnt * (time_size + 1)
+            + self.typecnt * 6
+            + self.charcnt
+            + self.leapcnt * (time_size + 4)
+            + self.isstdcnt
+            + self.isutcnt
+    }
+}
+
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn bytes(&mut self, n: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos + n)?;
+        self.pos += n;
+        Some(bytes)
+    }
+
+    /// A big-endian signed integer of 4 or 8 bytes
+    fn int(&mut self, size: usize) -> Option<i64> {
+        let bytes = self.bytes(size)?;
+        Some(match size {
+            4 => i32::from_be_bytes(bytes.try_into().ok()?) as i64,
+            _ => i64::from_be_bytes(bytes.try_into().ok()?),
+        })
+    }
+}
+
+/// The day of the year a daylight saving change falls on
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RuleDay {
+    /// `Mm.w.d`: weekday `d` (0 = Sunday) of week `w` (5 = last) of month `m`
+    Month { month: u32, week: u32, weekday: u32 },
+    /// `Jn`: day 1 to 365, not counting February 29
+    Julian(u32),
+    /// `n`: day 0 to 365
+    Ordinal(u32),
+}
+
+impl RuleDay {
+    fn date(&self, year: i32) -> Option<NaiveDate> {
+        match *self {
+            RuleDay::Month {
+                month,
+                week,
+                weekday,
+            } => {
+                let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+                let weekday = Weekday::try_from(((weekday + 6) % 7) as u8).ok()?;
+                let lead = (7 + weekday.num_days_from_monday() as i64
+                    - first.weekday().num_days_from_monday() as i64)
+                    % 7;
+                let mut date = first + Duration::days(lead + 7 * (week as i64 - 1));
+                while date.month() != month {
+                    date -= Duration::days(7);
+                }
+                Some(date)
+            }
+            RuleDay::Julian(day) => {
+                let leap = NaiveDate::from_ymd_opt(year, 2, 29).is_some();
+                let ordinal = if leap && day >= 60 { day + 1 } else { day };
+                NaiveDate::from_yo_opt(year, ordinal)
+            }
+            RuleDay::Ordinal(day) => NaiveDate::from_yo_opt(year, day + 1),
+        }
+    }
+}
+
+/// A POSIX TZ rule, as at the end of TZif files
+#[derive(Debug, Clone, PartialEq)]
+struct PosixTz {
+    /// Seconds east of UTC outside daylight saving time
+    std_offset: i32,
+    dst: Option<DstRule>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct DstRule {
+    offset: i32,
+    start: RuleDay,
+    /// Seconds after midnight, in standard time
+    start_time: i32,
+    end: RuleDay,
+    /// Seconds after midnight, in daylight saving time
+    end_time: i32,
+}
+
+impl PosixTz {
+    fn parse(rule: &str) -> Option<Self> {
+        let mut rest = rule;
+        skip_name(&mut rest)?;
+        // POSIX offsets count west of UTC
+        let std_offset = -parse_time(&mut rest)?;
+        if rest.is_empty() {
+            return Some(Self {
+                std_offset,
+                dst: None,
+            });
+        }
+        skip_name(&mut rest)?;
+        let offset = if rest.is_empty() || rest.starts_with(',') {
+            std_offset + 3600
+        } else {
+            -parse_time(&mut rest)?
+        };
+        // US rules when a zone names daylight saving time but not when
+        let (start, start_time, end, end_time) = if rest.is_empty() {
+            (
+                RuleDay::Month {
+                    month: 3,
+                    week: 2,
+                    weekday: 0,
+                },
+                7200,
+                RuleDay::Month {
+                    month: 11,
+                    week: 1,
+                    weekday: 0,
+                },
+                7200,
+            )
+        } else {
+            rest = rest.strip_prefix(',')?;
+            let (start, start_time) = parse_change(&mut rest)?;
+            rest = rest.strip_prefix(',')?;
+            let (end, end_time) = parse_change(&mut rest)?;
+            if !rest.is_empty() {
+                return None;
+            }
+            (start, start_time, end, end_time)
+        };
+        Some(Self {
+            std_offset,
+            dst: Some(DstRule {
+                offset,
+                start,
+                start_time,
+                end,
+                end_time,
+            }),
+        })
+    }
+
+    fn offset_at(&self, t: i64) -> i32 {
+        let Some(dst) = &self.dst else {
+            return self.std_offset;
+        };
+        let Some(year) = DateTime::from_timestamp(t + self.std_offset as i64, 0).map(|d| d.year())
+        else {
+            return self.std_offset;
+        };
+        let change = |day: RuleDay, time: i32, offset: i32| {
+            day.date(year).map(|date| {
+                date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp() + time as i64
+                    - offset as i64
+            })
+        };
+        let (Some(start), Some(end)) = (
+            change(dst.start, dst.start_time, self.std_offset),
+            change(dst.end, dst.end_time, dst.offset),
+        ) else {
+            return self.std_offset;
+        };
+        // Southern zones start daylight saving late in the year
+        let in_dst = if start < end {
+            start <= t && t < end
+        } else {
+            t >= start || t < end
+        };
+        if in_dst {
+            dst.offset
+        } else {
+            self.std_offset
+        }
+    }
+}
+
+/// Skip a zone abbreviation: letters, or anything between `<` and `>`
+fn skip_name(rest: &mut &str) -> Option<()> {
+    let len = if let Some(quoted) = rest.strip_prefix('<') {
+        quoted.find('>')? + 2
+    } else {
+        rest.find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len())
+    };
+    if len < 3 {
+        return None;
+    }
+    *rest = &rest[len..];
+    Some(())
+}
+
+/// `[+-]hh[:mm[:ss]]` in seconds
+fn parse_time(rest: &mut &str) -> Option<i32> {
+    let (sign, body) = match rest.as_bytes().first()? {
+        b'-' => (-1, &rest[1..]),
+        b'+' => (1, &rest[1..]),
+        _ => (1, *rest),
+    };
+    let len = body
+        .find(|c: char| !c.is_ascii_digit() && c != ':')
+        .unwrap_or(body.len());
+    let mut seconds = 0;
+    for (i, part) in body[..len].split(':').enumerate() {
+        if i > 2 || part.is_empty() {
+            return None;
+        }
+        seconds += part.parse::<i32>().ok()? * [3600, 60, 1][i];
+    }
+    *rest = &body[len..];
+    Some(sign * seconds)
+}
+
+/// `Mm.w.d`, `Jn` or `n`, then an optional `/time` (default 02:00)
+fn parse_change(rest: &mut &str) -> Option<(RuleDay, i32)> {
+    let len = rest.find([',', '/']).unwrap_or(rest.len());
+    let spec = &rest[..len];
+    let day = if let Some(month) = spec.strip_prefix('M') {
+        let mut parts = month.split('.').map(|p| p.parse::<u32>().ok());
+        let (month, week, weekday) = (parts.next()??, parts.next()??, parts.next()??);
+        if !(1..=12).contains(&month) || !(1..=5).contains(&week) || weekday > 6 {
+            return None;
+        }
+        RuleDay::Month {
+            month,
+            week,
+            weekday,
+        }
+    } else if let Some(day) = spec.strip_prefix('J') {
+        RuleDay::Julian(day.parse().ok().filter(|d| (1..=365).contains(d))?)
+    } else {
+        RuleDay::Ordinal(spec.parse().ok().filter(|d| *d <= 365)?)
+    };
+    *rest = &rest[len..];
+    let time = match rest.strip_prefix('/') {
+        Some(time) => {
+            *rest = time;
+            parse_time(rest)?
+        }
+        None => 7200,
+    };
+    Some((day, time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_rules_and_tzif() {
+        // New York: EDT from 2026-03-08 02:00 EST to 2026-11-01 02:00 EDT
+        let york = ZoneInfo::from_rule("America/New_York", "EST5EDT,M3.2.0,M11.1.0").unwrap();
+        assert_eq!(york.offset_at(utc(2026, 3, 8, 6, 59)), -5 * 3600);
+        assert_eq!(york.offset_at(utc(2026, 3, 8, 7, 0)), -4 * 3600);
+        assert_eq!(york.offset_at(utc(2026, 11, 1, 5, 59)), -4 * 3600);
+        assert_eq!(york.offset_at(utc(2026, 11, 1, 6, 0)), -5 * 3600);
+        let local = |h, min| {
+            NaiveDate::from_ymd_opt(2026, 3, 8)
+                .unwrap()
+                .and_hms_opt(h, min, 0)
+                .unwrap()
+        };
+        assert_eq!(york.resolve(local(1, 30)), Some(utc(2026, 3, 8, 6, 30)));
+        // 02:30 does not exist that day; it is read as 03:30
+        assert_eq!(york.resolve(local(2, 30)), Some(utc(2026, 3, 8, 7, 30)));
+        // 01:30 happens twice on 1 November; the first one counts
+        let twice = NaiveDate::from_ymd_opt(2026, 11, 1)
+            .unwrap()
+            .and_hms_opt(1, 30, 0)
+            .unwrap();
+        assert_eq!(york.resolve(twice), Some(utc(2026, 11, 1, 5, 30)));
+
+        // Sydney is on daylight saving time in January
+        let sydney =
+            ZoneInfo::from_rule("Australia/Sydney", "AEST-10AEDT,M10.1.0,M4.1.0/3").unwrap();
+        assert_eq!(sydney.offset_at(utc(2026, 1, 15, 0, 0)), 11 * 3600);
+        assert_eq!(sydney.offset_at(utc(2026, 7, 15, 0, 0)), 10 * 3600);
+        let kolkata = ZoneInfo::from_rule("Asia/Kolkata", "IST-5:30").unwrap();
+        assert_eq!(kolkata.offset_at(utc(2026, 7, 15, 0, 0)), 19800);
+        assert!(ZoneInfo::from_rule("x", "EST5EDT,M13.1.0,M11.1.0").is_none());
+
+        // A version 2 file: one transition, then the rule
+        let mut v2 = Vec::new();
+        let header = |out: &mut Vec<u8>, timecnt: u32, typecnt: u32, charcnt: u32| {
+            out.extend_from_slice(b"TZif2");
+            out.extend_from_slice(&[0; 15]);
+            for count in [0, 0, 0, timecnt, typecnt, charcnt] {
+                out.extend_from_slice(&u32::to_be_bytes(count));
+            }
+        };
+        header(&mut v2, 0, 1, 4);
+        v2.extend_from_slice(&(-18000i32).to_be_bytes());
+        v2.extend_from_slice(&[0, 0]);
+        v2.extend_from_slice(b"EST\0");
+        header(&mut v2, 1, 2, 8);
+        v2.extend_from_slice(&utc(2000, 1, 1, 0, 0).timestamp().to_be_bytes());
+        v2.push(1);
+        for (offset, dst, abbr) in [(-17762i32, 0u8, 0u8), (-18000, 0, 4)] {
+            v2.extend_from_slice(&offset.to_be_bytes());
+            v2.extend_from_slice(&[dst, abbr]);
+        }
+        v2.extend_from_slice(b"LMT\0EST\0");
+        v2.extend_from_slice(b"\nEST5EDT,M3.2.0,M11.1.0\n");
+        let zone = ZoneInfo::parse("Test/Zone", &v2).unwrap();
+        assert_eq!(zone.offset_at(utc(1990, 1, 1, 0, 0)), -17762);
+        assert_eq!(zone.offset_at(utc(2026, 7, 1, 0, 0)), -4 * 3600);
+        assert_eq!(zone.offset_at(utc(2026, 12, 1, 0, 0)), -5 * 3600);
+        assert!(ZoneInfo::parse("Bad", b"not a tz file").is_err());
+        assert!(ZoneInfo::load("../etc/passwd").is_err());
+    }
+}
@@ -0,0 +1,610 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Encrypted backup archives
+//!
+//! An archive is a snapshot of the harvest database and every workspace's
+//! session files. The layout is:
+//!
+//! ```text
+//! "CSMBAK01" | u32 header length | header JSON | nonce || AES-256-GCM(gzip(payload))
+//! ```
+//!
+//! The header is readable without the passphrase and carries the key
+//! derivation salt and a SHA-256 of the encrypted payload, so corruption can
+//! be detected before decrypting. The payload is a length-prefixed manifest
+//! followed by the file contents in manifest order; each manifest entry has
+//! its own SHA-256, checked on open.
+
+use super::{backup_file, BackupKind};
+use crate::config::{self, StorageRoot};
+use crate::encryption::EncryptionManager;
+use crate::error::{CsmError, Result};
+use crate::storage::is_session_file_extension;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// First bytes of every archive
+pub const ARCHIVE_MAGIC: &[u8; 8] = b"CSMBAK01";
+
+/// File extension for archives
+pub const ARCHIVE_EXTENSION: &str = "csmbak";
+
+/// Environment variable holding the archive passphrase
+pub const PASSPHRASE_ENV: &str = "CSM_BACKUP_PASSPHRASE";
+
+const FORMAT_VERSION: u32 = 1;
+const SALT_SIZE: usize = 32;
+
+/// What an archive entry contains
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    HarvestDatabase,
+    /// A workspace's `workspace.json`
+    WorkspaceMetadata,
+    SessionFile,
+}
+
+/// A file in an archive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveEntry {
+    pub kind: EntryKind,
+    /// Path inside the archive (`harvest/<file>` or `workspaces/<hash>/...`)
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Readable part of an archive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveHeader {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub created_with: String,
+    pub cipher: String,
+    pub kdf: String,
+    /// Base64 salt for the passphrase key derivation
+    pub salt: String,
+    /// SHA-256 of the encrypted payload
+    pub payload_sha256: String,
+}
+
+/// Encrypted list of archive contents
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveManifest {
+    /// Where the harvest database was read from
+    pub harvest_db: Option<PathBuf>,
+    /// Where workspace session files were read from
+    pub workspace_storage: Option<PathBuf>,
+    pub entries: Vec<ArchiveEntry>,
+}
+
+impl ArchiveManifest {
+    pub fn count(&self, kind: EntryKind) -> usize {
+        self.entries.iter().filter(|e| e.kind == kind).count()
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.entries.iter().map(|e| e.size).sum()
+    }
+}
+
+/// Locations a backup reads from and restores to
+#[derive(Debug, Clone, Default)]
+pub struct BackupSources {
+    pub harvest_db: Option<PathBuf>,
+    pub workspace_storage: Option<PathBuf>,
+}
+
+impl BackupSources {
+    /// The configured harvest database (or `chat_sessions.db` in the current
+    /// directory) and the VS Code workspace storage
+    pub fn detect() -> Self {
+        let harvest_db = config::storage_root(StorageRoot::HarvestDb).or_else(|| {
+            std::env::current_dir()
+                .ok()
+                .map(|dir| dir.join("chat_sessions.db"))
+        });
+        Self {
+            harvest_db: harvest_db.filter(|p| p.is_file()),
+            workspace_storage: crate::workspace::get_workspace_storage_path()
+                .ok()
+                .filter(|p| p.is_dir()),
+        }
+    }
+}
+
+/// A decrypted archive: its manifest and file contents
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    pub manifest: ArchiveManifest,
+    contents: Vec<Vec<u8>>,
+}
+
+impl Snapshot {
+    /// Read the harvest database and workspace session files
+    pub fn capture(sources: &BackupSources) -> Result<Self> {
+        let mut snapshot = Self::default();
+
+        if let Some(db) = &sources.harvest_db {
+            let name = db
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| "chat_sessions.db".to_string());
+            snapshot.add(
+                EntryKind::HarvestDatabase,
+                format!("harvest/{}", name),
+                snapshot_database(db)?,
+            );
+            snapshot.manifest.harvest_db = Some(db.clone());
+        }
+
+        if let Some(storage) = &sources.workspace_storage {
+            let mut workspaces: Vec<_> = fs::read_dir(storage)?
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.join("chatSessions").is_dir())
+                .collect();
+            workspaces.sort();
+
+            for dir in workspaces {
+                let hash = dir.file_name().unwrap_or_default().to_string_lossy();
+                let metadata = dir.join("workspace.json");
+                if metadata.is_file() {
+                    snapshot.add(
+                        EntryKind::WorkspaceMetadata,
+                        format!("workspaces/{}/workspace.json", hash),
+                        fs::read(&metadata)?,
+                    );
+                }
+
+                let mut sessions: Vec<PathBuf> = fs::read_dir(dir.join("chatSessions"))?
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.path())
+                    .filter(|p| p.is_file() && p.extension().is_some_and(is_session_file_extension))
+                    .collect();
+                sessions.sort();
+                for session in sessions {
+                    let name = session.file_name().unwrap_or_default().to_string_lossy();
+                    snapshot.add(
+                        EntryKind::SessionFile,
+                        format!("workspaces/{}/chatSessions/{}", hash, name),
+                        fs::read(&session)?,
+                    );
+                }
+            }
+            snapshot.manifest.workspace_storage = Some(storage.clone());
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Add a file
+    pub fn add(&mut self, kind: EntryKind, path: String, data: Vec<u8>) {
+        self.manifest.entries.push(ArchiveEntry {
+            kind,
+            path,
+            size: data.len() as u64,
+            sha256: hex::encode(Sha256::digest(&data)),
+        });
+        self.contents.push(data);
+    }
+
+    /// Entries with their contents
+    pub fn files(&self) -> impl Iterator<Item = (&ArchiveEntry, &[u8])> {
+        self.manifest
+            .entries
+            .iter()
+            .zip(self.contents.iter().map(Vec::as_slice))
+    }
+
+    pub fn file(&self, path: &str) -> Option<&[u8]> {
+        self.files().find(|(e, _)| e.path == path).map(|(_, d)| d)
+    }
+
+    /// Compress and encrypt into archive bytes
+    pub fn seal(&self, passphrase: &str) -> Result<Vec<u8>> {
+        let manifest = serde_json::to_vec(&self.manifest)?;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&(manifest.len() as u32).to_le_bytes())?;
+        encoder.write_all(&manifest)?;
+        for data in &self.contents {
+            encoder.write_all(data)?;
+        }
+        let payload = encoder.finish()?;
+
+        let salt: [u8; SALT_SIZE] = rand::random();
+        let sealed = cipher(passphrase, &salt)?
+            .seal(&payload)
+            .map_err(|e| CsmError::BackupError(e.to_string()))?;
+
+        let header = ArchiveHeader {
+            version: FORMAT_VERSION,
+            created_at: Utc::now(),
+            created_with: env!("CARGO_PKG_VERSION").to_string(),
+            cipher: "AES-256-GCM".to_string(),
+            kdf: "PBKDF2-HMAC-SHA256".to_string(),
+            salt: BASE64.encode(salt),
+            payload_sha256: hex::encode(Sha256::digest(&sealed)),
+        };
+        let header = serde_json::to_vec(&header)?;
+
+        let mut archive = Vec::with_capacity(12 + header.len() + sealed.len());
+        archive.extend_from_slice(ARCHIVE_MAGIC);
+        archive.extend_from_slice(&(header.len() as u32).to_le_bytes());
+        archive.extend_from_slice(&header);
+        archive.extend_from_slice(&sealed);
+        Ok(archive)
+    }
+
+    /// Decrypt archive bytes, checking the payload and every file checksum
+    pub fn open(archive: &[u8], passphrase: &str) -> Result<Self> {
+        let (header, sealed) = check_integrity(archive)?;
+        let salt = BASE64
+            .decode(&header.salt)
+            .map_err(|e| CsmError::BackupError(format!("invalid salt: {}", e)))?;
+        let payload = cipher(passphrase, &salt)?.open(sealed).map_err(|_| {
+            CsmError::BackupError("wrong passphrase: the archive could not be decrypted".into())
+        })?;
+
+        let mut decoded = Vec::new();
+        GzDecoder::new(payload.as_slice()).read_to_end(&mut decoded)?;
+        let (len, rest) = split_len(&decoded)?;
+        if rest.len() < len {
+            return Err(truncated());
+        }
+        let manifest: ArchiveManifest = serde_json::from_slice(&rest[..len])?;
+
+        let mut data = &rest[len..];
+        let mut contents = Vec::with_capacity(manifest.entries.len());
+        for entry in &manifest.entries {
+            let size = entry.size as usize;
+            if data.len() < size {
+                return Err(truncated());
+            }
+            let (file, tail) = data.split_at(size);
+            if hex::encode(Sha256::digest(file)) != entry.sha256 {
+                return Err(CsmError::BackupError(format!(
+                    "checksum mismatch for {}",
+                    entry.path
+                )));
+            }
+            contents.push(file.to_vec());
+            data = tail;
+        }
+
+        Ok(Self { manifest, contents })
+    }
+}
+
+/// Read an archive header without decrypting
+pub fn read_header(archive: &[u8]) -> Result<ArchiveHeader> {
+    Ok(parse(archive)?.0)
+}
+
+/// Check the magic, header, and payload digest. Needs no passphrase.
+pub fn check_integrity(archive: &[u8]) -> Result<(ArchiveHeader, &[u8])> {
+    let (header, sealed) = parse(archive)?;
+    if hex::encode(Sha256::digest(sealed)) != header.payload_sha256 {
+        return Err(CsmError::BackupError(
+            "payload checksum mismatch: the archive is corrupted or truncated".into(),
+        ));
+    }
+    Ok((header, sealed))
+}
+
+fn parse(archive: &[u8]) -> Result<(ArchiveHeader, &[u8])> {
+    let body = archive
+        .strip_prefix(ARCHIVE_MAGIC.as_slice())
+        .ok_or_else(|| CsmError::BackupError("not a csm backup archive".into()))?;
+    let (len, rest) = split_len(body)?;
+    if rest.len() < len {
+        return Err(truncated());
+    }
+    let header: ArchiveHeader = serde_json::from_slice(&rest[..len])?;
+    if header.version != FORMAT_VERSION {
+        return Err(CsmError::BackupError(format!(
+            "unsupported archive version {}",
+            header.version
+        )));
+    }
+    Ok((header, &rest[len..]))
+}
+
+fn split_len(bytes: &[u8]) -> Result<(usize, &[u8])> {
+    if bytes.len() < 4 {
+        return Err(truncated());
+    }
+    let (len, rest) = bytes.split_at(4);
+    let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+    Ok((len, rest))
+}
+
+fn truncated() -> CsmError {
+    CsmError::BackupError("archive is truncated".into())
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<EncryptionManager> {
+    if passphrase.is_empty() {
+        return Err(CsmError::BackupError("passphrase must not be empty".into()));
+    }
+    EncryptionManager::new(passphrase, salt).map_err(|e| CsmError::BackupError(e.to_string()))
+}
+
+/// Copy a SQLite database consistently, even while it is being written
+fn snapshot_database(path: &Path) -> Result<Vec<u8>> {
+    let tmp = std::env::temp_dir().join(format!("csm-backup-{}.db", uuid::Uuid::new_v4().simple()));
+    let conn =
+        rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    conn.execute("VACUUM INTO ?1", [tmp.to_string_lossy().as_ref()])?;
+    drop(conn);
+    let data = fs::read(&tmp);
+    let _ = fs::remove_file(&tmp);
+    Ok(data?)
+}
+
+/// The passphrase from `backups.passphrase` or `$CSM_BACKUP_PASSPHRASE`
+pub fn passphrase() -> Result<String> {
+    let configured = config::current().backups.passphrase.as_deref();
+    let value = match configured {
+        Some(value) => config::expand_env(value),
+        None => std::env::var(PASSPHRASE_ENV).ok().filter(|v| !v.is_empty()),
+    };
+    value.ok_or_else(|| {
+        CsmError::BackupError(match configured {
+            Some(value) if value.starts_with('$') => {
+                format!("backups.passphrase reads {}, which is not set", value)
+            }
+            _ => format!(
+                "no passphrase: set ${} or backups.passphrase in config",
+                PASSPHRASE_ENV
+            ),
+        })
+    })
+}
+
+/// Name for a new archive
+pub fn archive_name(created_at: DateTime<Utc>) -> String {
+    format!(
+        "chasm-backup-{}.{}",
+        created_at.format("%Y%m%d-%H%M%S"),
+        ARCHIVE_EXTENSION
+    )
+}
+
+/// Where to restore files
+#[derive(Debug, Clone)]
+pub enum RestoreTarget {
+    /// Back to the harvest database and workspace storage
+    Original(BackupSources),
+    /// Into a directory, keeping the archive layout
+    Directory(PathBuf),
+}
+
+/// Outcome of a restore
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    pub restored: Vec<PathBuf>,
+    /// Existing files with different contents, left alone without `force`
+    pub skipped: Vec<PathBuf>,
+    /// Existing files that already match the archive
+    pub unchanged: usize,
+}
+
+/// Write a snapshot's files. Existing files that differ are skipped unless
+/// `force`, in which case they are backed up first.
+pub fn restore(
+    snapshot: &Snapshot,
+    target: &RestoreTarget,
+    force: bool,
+    dry_run: bool,
+) -> Result<RestoreReport> {
+    let mut report = RestoreReport::default();
+    for (entry, data) in snapshot.files() {
+        let dest = destination_path(entry, &snapshot.manifest, target)?;
+
+        if dest.exists() {
+            if fs::read(&dest).is_ok_and(|current| current == data) {
+                report.unchanged += 1;
+                continue;
+            }
+            if !force {
+                report.skipped.push(dest);
+                continue;
+            }
+            if !dry_run {
+                let kind = match entry.kind {
+                    EntryKind::HarvestDatabase => BackupKind::HarvestDatabase,
+                    _ => BackupKind::SessionFile,
+                };
+                backup_file(kind, &dest)?;
+            }
+        }
+
+        if !dry_run {
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            crate::storage::write_file_atomic(&dest, data)?;
+        }
+        report.restored.push(dest);
+    }
+    Ok(report)
+}
+
+fn destination_path(
+    entry: &ArchiveEntry,
+    manifest: &ArchiveManifest,
+    target: &RestoreTarget,
+) -> Result<PathBuf> {
+    let relative = Path::new(&entry.path);
+    if !relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+    {
+        return Err(CsmError::BackupError(format!(
+            "refusing unsafe archive path {}",
+            entry.path
+        )));
+    }
+
+    let sources = match target {
+        RestoreTarget::Directory(dir) => return Ok(dir.join(relative)),
+        RestoreTarget::Original(sources) => sources,
+    };
+    let missing = |what: &str| {
+        CsmError::BackupError(format!(
+            "no {} to restore {} into; use a directory target instead",
+            what, entry.path
+        ))
+    };
+    match entry.kind {
+        EntryKind::HarvestDatabase => sources
+            .harvest_db
+            .clone()
+            .or_else(|| manifest.harvest_db.clone())
+            .ok_or_else(|| missing("harvest database")),
+        EntryKind::WorkspaceMetadata | EntryKind::SessionFile => {
+            let storage = sources
+                .workspace_storage
+                .as_ref()
+                .ok_or_else(|| missing("workspace storage"))?;
+            let rest = relative
+                .strip_prefix("workspaces")
+                .map_err(|_| missing("workspace"))?;
+            Ok(storage.join(rest))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample() -> Snapshot {
+        let mut snapshot = Snapshot::default();
+        snapshot.add(
+            EntryKind::SessionFile,
+            "workspaces/abc/chatSessions/s1.json".into(),
+            b"{\"requests\":[]}".to_vec(),
+        );
+        snapshot.add(
+            EntryKind::WorkspaceMetadata,
+            "workspaces/abc/workspace.json".into(),
+            b"{}".to_vec(),
+        );
+        snapshot
+    }
+
+    #[test]
+    fn test_seal_and_open_roundtrip() {
+        let archive = sample().seal("hunter2").unwrap();
+        assert!(archive.starts_with(ARCHIVE_MAGIC));
+        assert_eq!(read_header(&archive).unwrap().cipher, "AES-256-GCM");
+
+        let opened = Snapshot::open(&archive, "hunter2").unwrap();
+        assert_eq!(opened.manifest.entries.len(), 2);
+        assert_eq!(
+            opened.file("workspaces/abc/chatSessions/s1.json").unwrap(),
+            b"{\"requests\":[]}"
+        );
+        // File contents are not readable in the archive
+        let needle = b"requests";
+        assert!(!archive.windows(needle.len()).any(|w| w == needle));
+    }
+
+    #[test]
+    fn test_open_rejects_wrong_passphrase_and_tampering() {
+        let mut archive = sample().seal("hunter2").unwrap();
+        let err = Snapshot::open(&archive, "wrong").unwrap_err();
+        assert!(err.to_string().contains("wrong passphrase"));
+
+        let last = archive.len() - 1;
+        archive[last] ^= 0xff;
+        assert!(check_integrity(&archive).is_err());
+        assert!(Snapshot::open(&archive, "hunter2").is_err());
+        assert!(read_header(b"not an archive").is_err());
+    }
+
+    #[test]
+    fn test_capture_reads_database_and_sessions() {
+        let dir = TempDir::new().unwrap();
+        let db = dir.path().join("harvest.db");
+        let conn = rusqlite::Connection::open(&db).unwrap();
+        conn.execute_batch("CREATE TABLE t (x); INSERT INTO t VALUES (42);")
+            .unwrap();
+        drop(conn);
+
+        let storage = dir.path().join("workspaceStorage");
+        let sessions = storage.join("abc").join("chatSessions");
+        fs::create_dir_all(&sessions).unwrap();
+        fs::write(sessions.join("s1.json"), "{}").unwrap();
+        fs::write(sessions.join("notes.txt"), "skip").unwrap();
+        fs::create_dir_all(storage.join("no-sessions")).unwrap();
+
+        let snapshot = Snapshot::capture(&BackupSources {
+            harvest_db: Some(db),
+            workspace_storage: Some(storage),
+        })
+        .unwrap();
+        let manifest = &snapshot.manifest;
+        assert_eq!(manifest.count(EntryKind::HarvestDatabase), 1);
+        assert_eq!(manifest.count(EntryKind::SessionFile), 1);
+        assert_eq!(manifest.count(EntryKind::WorkspaceMetadata), 0);
+
+        let copy = dir.path().join("copy.db");
+        fs::write(&copy, snapshot.file("harvest/harvest.db").unwrap()).unwrap();
+        let value: i64 = rusqlite::Connection::open(&copy)
+            .unwrap()
+            .query_row("SELECT x FROM t", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(value, 42);
+    }
+
+    #[test]
+    fn test_restore_skips_changed_files_without_force() {
+        let dir = TempDir::new().unwrap();
+        let storage = dir.path().join("workspaceStorage");
+        let target = RestoreTarget::Original(BackupSources {
+            harvest_db: None,
+            workspace_storage: Some(storage.clone()),
+        });
+        let snapshot = sample();
+
+        let report = restore(&snapshot, &target, false, true).unwrap();
+        assert_eq!(report.restored.len(), 2);
+        assert!(!storage.exists(), "dry run must not write");
+
+        let report = restore(&snapshot, &target, false, false).unwrap();
+        assert_eq!(report.restored.len(), 2);
+        let session = storage.join("abc/chatSessions/s1.json");
+        assert_eq!(fs::read_to_string(&session).unwrap(), "{\"requests\":[]}");
+
+        fs::write(&session, "changed").unwrap();
+        let report = restore(&snapshot, &target, false, false).unwrap();
+        assert_eq!(report.unchanged, 1);
+        assert_eq!(report.skipped, vec![session.clone()]);
+        assert_eq!(fs::read_to_string(&session).unwrap(), "changed");
+    }
+
+    #[test]
+    fn test_restore_rejects_unsafe_paths() {
+        let dir = TempDir::new().unwrap();
+        let mut snapshot = Snapshot::default();
+        snapshot.add(EntryKind::SessionFile, "../escape.json".into(), vec![]);
+        let target = RestoreTarget::Directory(dir.path().to_path_buf());
+        assert!(restore(&snapshot, &target, false, false).is_err());
+    }
+}
@@ -0,0 +1,517 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Backup catalog and retention
+//!
+//! Destructive commands (merges, restores, repairs, recovery) copy what they
+//! are about to change before touching it. Each copy is recorded in a catalog
+//! (`backups.json` next to `config.toml`) with the command that made it and
+//! the path it protects, so `csm backup list` can show every backup and
+//! `csm backup prune` can delete old ones by count and age per source.
+//!
+//! `csm backup run` is separate: it writes an encrypted archive of the
+//! harvest database and all workspace session files to a local directory,
+//! S3, or WebDAV (see [`archive`] and [`destination`]).
+
+pub mod archive;
+pub mod destination;
+
+use crate::config::{self, Config, StorageRoot};
+use crate::error::{CsmError, Result};
+use archive::{ArchiveManifest, BackupSources, Snapshot};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Catalog file name, stored in the config directory
+pub const CATALOG_FILE: &str = "backups.json";
+
+/// What a backup contains
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupKind {
+    /// A workspace `chatSessions` directory
+    WorkspaceSessions,
+    /// The harvest database
+    HarvestDatabase,
+    /// A single session file
+    SessionFile,
+    /// A project's `.vscode/chat-sessions` directory
+    ProjectSessions,
+}
+
+impl std::fmt::Display for BackupKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::WorkspaceSessions => "workspace",
+            Self::HarvestDatabase => "harvest-db",
+            Self::SessionFile => "session",
+            Self::ProjectSessions => "project",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A recorded backup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupEntry {
+    pub id: String,
+    pub kind: BackupKind,
+    /// Command that created the backup (e.g. `merge workspace`)
+    pub command: String,
+    /// Path that was backed up
+    pub source: PathBuf,
+    /// Backup file or directory
+    pub path: PathBuf,
+    pub created_at: DateTime<Utc>,
+    pub size_bytes: u64,
+}
+
+impl BackupEntry {
+    pub fn exists(&self) -> bool {
+        self.path.exists()
+    }
+}
+
+/// How many backups to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Newest backups kept per source
+    pub keep: usize,
+    /// Backups older than this are removed regardless of `keep`
+    pub max_age: Option<Duration>,
+}
+
+impl RetentionPolicy {
+    /// Policy from the `[backups]` config section
+    pub fn from_config() -> Self {
+        let backups = &config::current().backups;
+        Self {
+            keep: backups.keep(),
+            max_age: backups.max_age_days.map(|d| Duration::days(d as i64)),
+        }
+    }
+}
+
+/// Outcome of a prune
+#[derive(Debug, Default)]
+pub struct PruneReport {
+    /// Entries whose backups were deleted (or would be, on a dry run)
+    pub removed: Vec<BackupEntry>,
+    /// Catalog entries dropped because the backup was already gone
+    pub missing: Vec<BackupEntry>,
+    pub freed_bytes: u64,
+}
+
+/// The backup catalog
+pub struct BackupManager {
+    catalog_path: PathBuf,
+    entries: Vec<BackupEntry>,
+}
+
+impl BackupManager {
+    /// Open the catalog in the config directory
+    pub fn open() -> Result<Self> {
+        let config_path = Config::path()?;
+        let dir = config_path.parent().unwrap_or(Path::new("."));
+        Self::open_at(&dir.join(CATALOG_FILE))
+    }
+
+    /// Open a catalog file, starting empty if it does not exist
+    pub fn open_at(catalog_path: &Path) -> Result<Self> {
+        let entries = if catalog_path.exists() {
+            serde_json::from_str(&fs::read_to_string(catalog_path)?)?
+        } else {
+            Vec::new()
+        };
+        Ok(Self {
+            catalog_path: catalog_path.to_path_buf(),
+            entries,
+        })
+    }
+
+    pub fn catalog_path(&self) -> &Path {
+        &self.catalog_path
+    }
+
+    /// Entries, oldest first
+    pub fn entries(&self) -> &[BackupEntry] {
+        &self.entries
+    }
+
+    pub fn find(&self, id: &str) -> Option<&BackupEntry> {
+        self.entries.iter().find(|e| e.id.starts_with(id))
+    }
+
+    /// Record a backup that has already been written
+    pub fn record(
+        &mut self,
+        kind: BackupKind,
+        command: &str,
+        source: &Path,
+        path: &Path,
+    ) -> Result<BackupEntry> {
+        let entry = BackupEntry {
+            id: uuid::Uuid::new_v4().simple().to_string()[..12].to_string(),
+            kind,
+            command: command.to_string(),
+            source: source.to_path_buf(),
+            path: path.to_path_buf(),
+            created_at: Utc::now(),
+            size_bytes: path_size(path),
+        };
+        self.entries.push(entry.clone());
+        self.save()?;
+        Ok(entry)
+    }
+
+    /// Backups the policy would remove, newest kept first within each source
+    pub fn expired(&self, policy: &RetentionPolicy, now: DateTime<Utc>) -> Vec<&BackupEntry> {
+        let mut by_source: HashMap<&Path, Vec<&BackupEntry>> = HashMap::new();
+        for entry in self.entries.iter().filter(|e| e.exists()) {
+            by_source.entry(&entry.source).or_default().push(entry);
+        }
+
+        let mut expired = Vec::new();
+        for mut entries in by_source.into_values() {
+            entries.sort_by_key(|e| std::cmp::Reverse(e.created_at));
+            for (i, entry) in entries.into_iter().enumerate() {
+                let too_old = policy
+                    .max_age
+                    .is_some_and(|age| now.signed_duration_since(entry.created_at) > age);
+                if i >= policy.keep || too_old {
+                    expired.push(entry);
+                }
+            }
+        }
+        expired.sort_by_key(|e| e.created_at);
+        expired
+    }
+
+    /// Delete expired backups and drop entries whose backups no longer exist
+    pub fn prune(&mut self, policy: &RetentionPolicy, dry_run: bool) -> Result<PruneReport> {
+        let mut report = PruneReport {
+            removed: self
+                .expired(policy, Utc::now())
+                .into_iter()
+                .cloned()
+                .collect(),
+            missing: self
+                .entries
+                .iter()
+                .filter(|e| !e.exists())
+                .cloned()
+                .collect(),
+            freed_bytes: 0,
+        };
+        report.freed_bytes = report.removed.iter().map(|e| e.size_bytes).sum();
+        if dry_run {
+            return Ok(report);
+        }
+
+        for entry in &report.removed {
+            delete_backup(&entry.path)?;
+        }
+        let dropped: Vec<&str> = report
+            .removed
+            .iter()
+            .chain(&report.missing)
+            .map(|e| e.id.as_str())
+            .collect();
+        self.entries.retain(|e| !dropped.contains(&e.id.as_str()));
+        self.save()?;
+        Ok(report)
+    }
+
+    /// Add backups made before the catalog existed (`chatSessions-backup-*`
+    /// directories under `workspace_storage` and the backup root). Returns how many were added.
+    pub fn adopt_untracked(&mut self, workspace_storage: &Path) -> Result<usize> {
+        let mut roots = vec![workspace_storage.to_path_buf()];
+        roots.extend(config::storage_root(StorageRoot::Backups));
+
+        let mut added = 0;
+        for root in roots.iter().filter(|r| r.is_dir()) {
+            for dir in walkdir::WalkDir::new(root)
+                .min_depth(2)
+                .max_depth(2)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_dir())
+            {
+                let name = dir.file_name().to_string_lossy();
+                let Some(ts) = name.strip_prefix("chatSessions-backup-") else {
+                    continue;
+                };
+                let path = dir.path();
+                if self.entries.iter().any(|e| e.path == path) {
+                    continue;
+                }
+                let Some(workspace) = path.parent() else {
+                    continue;
+                };
+                // Backups under the backup root sit in `<root>/<hash>/`
+                let source = if roots[1..].iter().any(|r| r == root) {
+                    workspace_storage.join(workspace.file_name().unwrap_or_default())
+                } else {
+                    workspace.to_path_buf()
+                };
+                self.entries.push(BackupEntry {
+                    id: uuid::Uuid::new_v4().simple().to_string()[..12].to_string(),
+                    kind: BackupKind::WorkspaceSessions,
+                    command: "unknown".to_string(),
+                    source: source.join("chatSessions"),
+                    path: path.to_path_buf(),
+                    created_at: ts
+                        .parse()
+                        .ok()
+                        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+                        .unwrap_or_else(Utc::now),
+                    size_bytes: path_size(path),
+                });
+                added += 1;
+            }
+        }
+
+        if added > 0 {
+            self.entries.sort_by_key(|e| e.created_at);
+            self.save()?;
+        }
+        Ok(added)
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(parent) = self.catalog_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = self.catalog_path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(&self.entries)?)?;
+        fs::rename(&tmp, &self.catalog_path)?;
+        Ok(())
+    }
+}
+
+/// A written archive
+#[derive(Debug, Clone)]
+pub struct ArchiveReport {
+    pub name: String,
+    /// Where it was written
+    pub destination: String,
+    pub manifest: ArchiveManifest,
+    /// Encrypted archive size
+    pub size: u64,
+}
+
+/// Snapshot `sources` into an encrypted archive and write it to `destination`
+/// (or `backups.destination`)
+pub fn create_archive(destination: Option<&str>, sources: &BackupSources) -> Result<ArchiveReport> {
+    let passphrase = archive::passphrase()?;
+    let dest = destination::open_or_configured(destination)?;
+
+    let snapshot = Snapshot::capture(sources)?;
+    if snapshot.manifest.entries.is_empty() {
+        return Err(CsmError::BackupError(
+            "nothing to back up: no harvest database or workspace sessions found".into(),
+        ));
+    }
+    let data = snapshot.seal(&passphrase)?;
+    let name = archive::archive_name(Utc::now());
+    dest.put(&name, &data)?;
+
+    Ok(ArchiveReport {
+        name,
+        destination: dest.describe(),
+        manifest: snapshot.manifest,
+        size: data.len() as u64,
+    })
+}
+
+/// Copy a file to a timestamped `.backup` beside it (or under the backup root) and record it
+pub fn backup_file(kind: BackupKind, source: &Path) -> Result<PathBuf> {
+    let name = source
+        .file_name()
+        .ok_or_else(|| CsmError::InvalidSessionFormat(source.display().to_string()))?
+        .to_string_lossy();
+    let dir = match config::storage_root(StorageRoot::Backups) {
+        Some(root) => root,
+        None => source.parent().unwrap_or(Path::new(".")).to_path_buf(),
+    };
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.{}.backup", name, Utc::now().timestamp()));
+    fs::copy(source, &path)?;
+    record(kind, source, &path);
+    Ok(path)
+}
+
+/// Record a backup in the default catalog.
+/// The backup itself already exists, so failures only log a warning.
+pub fn record(kind: BackupKind, source: &Path, path: &Path) {
+    let result = BackupManager::open()
+        .and_then(|mut manager| manager.record(kind, &current_command(), source, path));
+    if let Err(e) = result {
+        log::warn!("Could not record backup {}: {}", path.display(), e);
+    }
+}
+
+/// Subcommand words of this invocation (e.g. `merge workspace`), without flags or arguments
+fn current_command() -> String {
+    let words: Vec<String> = std::env::args()
+        .skip(1)
+        .take_while(|a| !a.starts_with('-'))
+        .take(2)
+        .collect();
+    if words.is_empty() {
+        "unknown".to_string()
+    } else {
+        words.join(" ")
+    }
+}
+
+/// Total size of a file or directory
+fn path_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// Delete a backup, refusing paths that do not look like one
+fn delete_backup(path: &Path) -> Result<()> {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if !name.contains("backup") {
+        return Err(CsmError::InvalidSessionFormat(format!(
+            "refusing to delete {}: not a backup path",
+            path.display()
+        )));
+    }
+    if path.is_dir() {
+        fs::remove_dir_all(path)?;
+    } else if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn backup_at(
+        manager: &mut BackupManager,
+        dir: &Path,
+        source: &str,
+        days_ago: i64,
+    ) -> BackupEntry {
+        let path = dir.join(format!("{}-backup-{}", source, days_ago));
+        fs::create_dir_all(&path).unwrap();
+        fs::write(path.join("s.json"), "{}").unwrap();
+        let mut entry = manager
+            .record(
+                BackupKind::WorkspaceSessions,
+                "test",
+                &dir.join(source),
+                &path,
+            )
+            .unwrap();
+        entry.created_at = Utc::now() - Duration::days(days_ago);
+        manager.entries.last_mut().unwrap().created_at = entry.created_at;
+        entry
+    }
+
+    #[test]
+    fn test_record_persists_catalog() {
+        let dir = TempDir::new().unwrap();
+        let catalog = dir.path().join(CATALOG_FILE);
+        let mut manager = BackupManager::open_at(&catalog).unwrap();
+        let entry = backup_at(&mut manager, dir.path(), "ws1", 0);
+        assert_eq!(entry.size_bytes, 2);
+
+        let reopened = BackupManager::open_at(&catalog).unwrap();
+        assert_eq!(reopened.entries().len(), 1);
+        assert!(reopened.find(&entry.id[..6]).is_some());
+    }
+
+    #[test]
+    fn test_retention_keeps_newest_per_source() {
+        let dir = TempDir::new().unwrap();
+        let mut manager = BackupManager::open_at(&dir.path().join(CATALOG_FILE)).unwrap();
+        let oldest = backup_at(&mut manager, dir.path(), "ws1", 3);
+        backup_at(&mut manager, dir.path(), "ws1", 2);
+        backup_at(&mut manager, dir.path(), "ws1", 1);
+        backup_at(&mut manager, dir.path(), "ws2", 30);
+
+        let policy = RetentionPolicy {
+            keep: 2,
+            max_age: None,
+        };
+        let expired = manager.expired(&policy, Utc::now());
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, oldest.id);
+
+        let policy = RetentionPolicy {
+            keep: 2,
+            max_age: Some(Duration::days(7)),
+        };
+        assert_eq!(manager.expired(&policy, Utc::now()).len(), 2);
+    }
+
+    #[test]
+    fn test_prune_deletes_and_drops_missing() {
+        let dir = TempDir::new().unwrap();
+        let mut manager = BackupManager::open_at(&dir.path().join(CATALOG_FILE)).unwrap();
+        let old = backup_at(&mut manager, dir.path(), "ws1", 2);
+        let new = backup_at(&mut manager, dir.path(), "ws1", 1);
+        let gone = backup_at(&mut manager, dir.path(), "ws2", 1);
+        fs::remove_dir_all(&gone.path).unwrap();
+
+        let policy = RetentionPolicy {
+            keep: 1,
+            max_age: None,
+        };
+        let report = manager.prune(&policy, true).unwrap();
+        assert_eq!(report.removed.len(), 1);
+        assert!(old.path.exists(), "dry run must not delete");
+
+        let report = manager.prune(&policy, false).unwrap();
+        assert_eq!(report.removed[0].id, old.id);
+        assert_eq!(report.missing[0].id, gone.id);
+        assert!(!old.path.exists());
+        assert!(new.path.exists());
+        assert_eq!(manager.entries().len(), 1);
+    }
+
+    #[test]
+    fn test_adopt_untracked_backups() {
+        let dir = TempDir::new().unwrap();
+        let storage = dir.path().join("workspaceStorage");
+        let backup = storage
+            .join("abc123")
+            .join("chatSessions-backup-1700000000");
+        fs::create_dir_all(&backup).unwrap();
+
+        let mut manager = BackupManager::open_at(&dir.path().join(CATALOG_FILE)).unwrap();
+        assert_eq!(manager.adopt_untracked(&storage).unwrap(), 1);
+        assert_eq!(manager.adopt_untracked(&storage).unwrap(), 0);
+
+        let entry = &manager.entries()[0];
+        assert_eq!(entry.source, storage.join("abc123").join("chatSessions"));
+        assert_eq!(entry.created_at.timestamp(), 1_700_000_000);
+    }
+
+    #[test]
+    fn test_delete_refuses_non_backup_paths() {
+        let dir = TempDir::new().unwrap();
+        let important = dir.path().join("chatSessions");
+        fs::create_dir_all(&important).unwrap();
+        assert!(delete_backup(&important).is_err());
+        assert!(important.exists());
+    }
+}
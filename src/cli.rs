@@ -13,6 +13,10 @@ use clap::{Parser, Subcommand};
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// Re-run provider discovery instead of using the cached result
+    #[arg(long, global = true)]
+    pub refresh_providers: bool,
 }
 
 #[derive(Subcommand)]
@@ -82,6 +86,15 @@ pub enum Commands {
         command: Option<ImportCommands>,
     },
 
+    // ============================================================================
+    // Extract Commands
+    // ============================================================================
+    /// Pull the code out of a session into files
+    Extract {
+        #[command(subcommand)]
+        command: ExtractCommands,
+    },
+
     // ============================================================================
     // Move Commands
     // ============================================================================
@@ -156,6 +169,12 @@ pub enum Commands {
         command: HarvestCommands,
     },
 
+    /// Compare harvested sessions with checkpoints, git history, or each other
+    Diff {
+        #[command(subcommand)]
+        command: DiffCommands,
+    },
+
     // ============================================================================
     // Recover Commands
     // ============================================================================
@@ -176,6 +195,19 @@ pub enum Commands {
         command: ApiCommands,
     },
 
+    /// Review the API server's audit log of mutating requests
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommands,
+    },
+
+    /// Inspect how the API server's gateway routes requests, and workspace
+    /// policies
+    Routing {
+        #[command(subcommand)]
+        command: RoutingCommands,
+    },
+
     // ============================================================================
     // Agency Commands
     // ============================================================================
@@ -185,6 +217,61 @@ pub enum Commands {
         command: AgencyCommands,
     },
 
+    /// Continue a harvested session with another provider or model
+    Continue {
+        /// Harvested session ID (or the start of one)
+        session_id: String,
+
+        /// Next message in the conversation
+        prompt: String,
+
+        /// Provider and model to continue with (e.g., ollama:llama3, anthropic:claude-sonnet-4-20250514, gpt-4o)
+        #[arg(long, value_name = "PROVIDER:MODEL")]
+        with: String,
+
+        /// Agent to continue with
+        #[arg(short, long, default_value = "assistant")]
+        agent: String,
+
+        /// Context window of the model in tokens (default: its known size, or 8192)
+        #[arg(long)]
+        context_window: Option<usize>,
+
+        /// Do not store the continued conversation as a session
+        #[arg(long)]
+        no_save: bool,
+
+        /// Enable verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Answer a question from your harvested sessions, citing the messages used
+    Ask {
+        /// The question
+        question: String,
+
+        /// Agent that writes the answer
+        #[arg(short, long, default_value = "assistant")]
+        agent: String,
+
+        /// Model to use (default: the agent's)
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Messages to retrieve as sources
+        #[arg(short, long, default_value = "8")]
+        limit: usize,
+
+        /// Answer from the index as it is, without embedding new sessions first
+        #[arg(long)]
+        no_index: bool,
+
+        /// Enable verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
     // ============================================================================
     // Telemetry Commands
     // ============================================================================
@@ -194,6 +281,152 @@ pub enum Commands {
         command: Option<TelemetryCommands>,
     },
 
+    // ============================================================================
+    // Backup Commands
+    // ============================================================================
+    /// Manage backups: list and prune safety copies, write and restore encrypted archives
+    Backup {
+        #[command(subcommand)]
+        command: BackupCommands,
+    },
+
+    // ============================================================================
+    // Pack Commands
+    // ============================================================================
+    /// Share workflows, agents, prompt templates, and saved searches as signed packs
+    Pack {
+        #[command(subcommand)]
+        command: PackCommands,
+    },
+
+    // ============================================================================
+    // Automation Commands
+    // ============================================================================
+    /// Run library workflows: agent runs, backups, and notifications on a schedule
+    #[command(visible_alias = "workflow")]
+    Automation {
+        #[command(subcommand)]
+        command: AutomationCommands,
+    },
+
+    // ============================================================================
+    // Plugin Commands
+    // ============================================================================
+    /// Install, enable, and configure plugins
+    #[command(visible_alias = "plugins")]
+    Plugin {
+        #[command(subcommand)]
+        command: PluginCommands,
+    },
+
+    // ============================================================================
+    // Config Commands
+    // ============================================================================
+    /// Show or edit csm configuration (storage roots, profiles, defaults)
+    Config {
+        #[command(subcommand)]
+        command: Option<ConfigCommands>,
+    },
+
+    // ============================================================================
+    // Notification Commands
+    // ============================================================================
+    /// Configure and test notification channels (desktop, Slack, Discord, email, webhook)
+    Notify {
+        #[command(subcommand)]
+        command: NotifyCommands,
+    },
+
+    // ============================================================================
+    // Project Commands
+    // ============================================================================
+    /// Group workspaces (renamed folders, worktrees, monorepo parts) into projects
+    Project {
+        #[command(subcommand)]
+        command: ProjectCommands,
+    },
+
+    // ============================================================================
+    // Report Commands
+    // ============================================================================
+    /// Summaries built from harvested sessions
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
+    },
+
+    // ============================================================================
+    // Intelligence Commands
+    // ============================================================================
+    /// Tidy harvested sessions: find duplicates, title untitled sessions, find action items
+    Intelligence {
+        #[command(subcommand)]
+        command: IntelligenceCommands,
+    },
+
+    // ============================================================================
+    // Action Item Commands
+    // ============================================================================
+    /// TODOs, decisions and commitments found by 'csm intelligence actions'
+    Actions {
+        #[command(subcommand)]
+        command: ActionsCommands,
+    },
+
+    // ============================================================================
+    // GitHub Commands
+    // ============================================================================
+    /// Link sessions to GitHub repositories, pull requests and issues
+    Github {
+        #[command(subcommand)]
+        command: GithubCommands,
+    },
+
+    // ============================================================================
+    // Slack Commands
+    // ============================================================================
+    /// Import Slack threads as sessions and post session summaries to Slack
+    Slack {
+        #[command(subcommand)]
+        command: SlackCommands,
+    },
+
+    // ============================================================================
+    // Obsidian Commands
+    // ============================================================================
+    /// Keep an Obsidian vault folder in step with harvested sessions
+    Obsidian {
+        #[command(subcommand)]
+        command: ObsidianCommands,
+    },
+
+    // ============================================================================
+    // Issue Commands
+    // ============================================================================
+    /// File sessions as issues in Jira or Linear
+    Issue {
+        #[command(subcommand)]
+        command: IssueCommands,
+    },
+
+    // ============================================================================
+    // Knowledge Graph Commands
+    // ============================================================================
+    /// Files, crates, APIs, people and errors from sessions, and how they are linked
+    Graph {
+        #[command(subcommand)]
+        command: GraphCommands,
+    },
+
+    // ============================================================================
+    // MCP Commands
+    // ============================================================================
+    /// Connect MCP clients such as Claude Desktop, VS Code, and Cursor to csm-mcp
+    Mcp {
+        #[command(subcommand)]
+        command: McpCommands,
+    },
+
     // ============================================================================
     // Easter Egg
     // ============================================================================
@@ -293,15 +526,15 @@ pub enum FindCommands {
         #[arg(long, short = 'c')]
         content: bool,
 
-        /// Filter sessions modified after this date (YYYY-MM-DD)
+        /// Filter sessions modified on or after this date (YYYY-MM-DD, local time unless Z/+HH:MM is given)
         #[arg(long)]
         after: Option<String>,
 
-        /// Filter sessions modified before this date (YYYY-MM-DD)
+        /// Filter sessions modified on or before this date (YYYY-MM-DD, local time unless Z/+HH:MM is given)
         #[arg(long)]
         before: Option<String>,
 
-        /// Filter by internal message timestamp date (YYYY-MM-DD)
+        /// Filter by internal message timestamp date (YYYY-MM-DD, local time)
         #[arg(long)]
         date: Option<String>,
 
@@ -309,6 +542,10 @@ pub enum FindCommands {
         #[arg(long, short = 'a')]
         all: bool,
 
+        /// Only search workspaces in this project (see `csm project`)
+        #[arg(long, conflicts_with_all = ["workspace", "all"])]
+        project: Option<String>,
+
         /// Filter by provider (vscode, cursor, claudecode, opencode, openclaw, antigravity)
         #[arg(long, short = 'p')]
         provider: Option<String>,
@@ -624,6 +861,29 @@ pub enum MergeCommands {
         #[arg(long)]
         no_backup: bool,
     },
+
+    /// Merge sessions from every workspace in a project (see `csm project`)
+    #[command(visible_alias = "proj")]
+    Project {
+        /// Project name
+        project: String,
+
+        /// Title for the merged session
+        #[arg(short, long)]
+        title: Option<String>,
+
+        /// Target project path to save the merged session (default: current directory)
+        #[arg(long)]
+        target_path: Option<String>,
+
+        /// Skip VS Code running check
+        #[arg(long)]
+        force: bool,
+
+        /// Don't create backup of current sessions
+        #[arg(long)]
+        no_backup: bool,
+    },
 }
 
 // ============================================================================
@@ -717,6 +977,43 @@ pub enum ImportCommands {
     },
 }
 
+// ============================================================================
+// Extract Subcommands
+// ============================================================================
+
+#[derive(Subcommand)]
+pub enum ExtractCommands {
+    /// Write a session's code blocks to files, keeping the last version of each
+    Code {
+        /// Harvested session ID (or a prefix of it), or a session file
+        session: String,
+
+        /// Directory to write the files to
+        #[arg(short, long, default_value = ".")]
+        out: String,
+
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Only extract blocks in this language (e.g. rust, py)
+        #[arg(long)]
+        lang: Option<String>,
+
+        /// List the files without writing them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Overwrite files that already exist
+        #[arg(long)]
+        force: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
 // ============================================================================
 // Move Subcommands
 // ============================================================================
@@ -1079,9 +1376,13 @@ pub enum DetectCommands {
         /// Project path (default: current directory)
         path: Option<String>,
 
-        /// Show detailed information
+        /// Show every provider checked, with paths and all read errors
         #[arg(long)]
         verbose: bool,
+
+        /// Output the report as JSON
+        #[arg(long)]
+        json: bool,
     },
 
     /// Find all workspace hashes for a project path (including orphaned workspaces with sessions)
@@ -1191,7 +1492,7 @@ pub enum HarvestCommands {
         #[arg(long, default_value = "5")]
         timeout: u64,
 
-        /// Show verbose debug output for browser scanning
+        /// Show every provider checked, all read errors, and browser scanning details
         #[arg(long, short)]
         verbose: bool,
     },
@@ -1223,6 +1524,17 @@ pub enum HarvestCommands {
         message: Option<String>,
     },
 
+    /// Watch VS Code workspaces and harvest sessions as they change
+    Watch {
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Milliseconds a file must be quiet before it is harvested
+        #[arg(long, default_value = "500")]
+        debounce: u64,
+    },
+
     /// Show harvest database status
     Status {
         /// Path to the harvest database
@@ -1247,6 +1559,10 @@ pub enum HarvestCommands {
         /// Search sessions by title or ID
         #[arg(long)]
         search: Option<String>,
+
+        /// Only sessions filed under this topic or its subtopics (see 'csm intelligence topics')
+        #[arg(long)]
+        topic: Option<String>,
     },
 
     /// Export sessions from the harvest database
@@ -1357,9 +1673,42 @@ pub enum HarvestCommands {
         #[arg(long)]
         path: Option<String>,
 
-        /// Filter by provider
+        /// Filter by provider (repeatable)
         #[arg(long)]
-        provider: Option<String>,
+        provider: Vec<String>,
+
+        /// Filter by workspace ID or name
+        #[arg(long)]
+        workspace: Option<String>,
+
+        /// Sessions updated on or after this date (YYYY-MM-DD, local time unless Z/+HH:MM is given)
+        #[arg(long)]
+        after: Option<String>,
+
+        /// Sessions updated on or before this date (YYYY-MM-DD, local time unless Z/+HH:MM is given)
+        #[arg(long)]
+        before: Option<String>,
+
+        /// Only sessions with this tag (repeatable)
+        #[arg(long)]
+        tag: Vec<String>,
+
+        /// Only sessions filed under this topic or its subtopics (repeatable)
+        #[arg(long)]
+        topic: Vec<String>,
+
+        /// Only sessions mostly written in this language (ISO 639-3 code such as spa, or a name)
+        #[arg(long)]
+        lang: Option<String>,
+
+        /// Rank by embedding similarity instead of matching words
+        /// (needs CSM_EMBEDDING_URL, OPENAI_API_KEY, or OLLAMA_HOST)
+        #[arg(long)]
+        semantic: bool,
+
+        /// Count matches by provider, workspace, tag, language, and month
+        #[arg(long)]
+        facets: bool,
 
         /// Maximum results to show
         #[arg(long, default_value = "20")]
@@ -1426,6 +1775,28 @@ pub enum HarvestGitCommands {
     },
 }
 
+#[derive(Subcommand)]
+pub enum DiffCommands {
+    /// Show the turns added, removed, and edited in SESSION since AGAINST
+    Session {
+        /// Session ID
+        session: String,
+
+        /// Checkpoint number, commit of the harvest database, or another
+        /// session ID; prefix with checkpoint:, commit:, or session: when
+        /// ambiguous
+        against: String,
+
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
 // ============================================================================
 // Recover Subcommands
 // ============================================================================
@@ -1583,6 +1954,29 @@ pub enum RecoverCommands {
         #[arg(long)]
         json: bool,
     },
+
+    /// Find orphaned and deleted-workspace sessions and register or export them
+    Wizard {
+        /// Only include sessions from or suggested for this project path
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Register every suggested session without prompting
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Export all found sessions to this directory instead of registering them
+        #[arg(long)]
+        export: Option<String>,
+
+        /// Show what would be recovered without making changes
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Register even if VS Code is running
+        #[arg(long)]
+        force: bool,
+    },
 }
 
 // ============================================================================
@@ -1604,33 +1998,268 @@ pub enum ApiCommands {
         /// Path to the database file
         #[arg(long)]
         database: Option<String>,
-    },
-}
 
-// ============================================================================
-// Agency (Agent Development Kit) Subcommands
-// ============================================================================
+        /// Access for requests without credentials: deny, read-only (GET only), or allow
+        #[arg(long, default_value = "deny")]
+        anonymous: String,
 
-#[derive(Subcommand)]
-pub enum AgencyCommands {
-    /// List available agents and their roles
-    List {
-        /// Show detailed information
-        #[arg(short, long)]
-        verbose: bool,
-    },
+        /// Requests per minute allowed per API key, user, or anonymous IP (0 disables)
+        #[arg(long, default_value = "600")]
+        rate_limit: u32,
 
-    /// Show agent information
-    Info {
-        /// Agent name or ID
-        name: String,
-    },
+        /// Requests a client can send at once before the rate limit applies
+        #[arg(long, default_value = "120")]
+        rate_burst: u32,
 
-    /// List supported orchestration modes
-    Modes,
+        /// Largest request body accepted, in megabytes
+        #[arg(long, default_value = "16")]
+        max_body_mb: usize,
 
-    /// Run an agent with a prompt
-    Run {
+        /// PEM certificate chain to serve HTTPS with (requires --tls-key)
+        #[arg(long, requires = "tls_key", conflicts_with = "tls_self_signed")]
+        tls_cert: Option<String>,
+
+        /// PEM private key for --tls-cert
+        #[arg(long, requires = "tls_cert")]
+        tls_key: Option<String>,
+
+        /// Serve HTTPS with a self-signed certificate, created beside the database on first use
+        #[arg(long)]
+        tls_self_signed: bool,
+
+        /// Run in the background, logging to --log-file (default: logs/api.log beside the database)
+        #[arg(long)]
+        daemon: bool,
+
+        /// Write server output to this file, rotating it when it grows past --log-max-mb
+        #[arg(long)]
+        log_file: Option<String>,
+
+        /// Size at which the log file is rotated, in megabytes (5 old logs are kept)
+        #[arg(long, default_value = "10")]
+        log_max_mb: u64,
+
+        /// Seconds to let open requests finish after Ctrl+C or SIGTERM
+        #[arg(long, default_value = "30")]
+        shutdown_timeout: u64,
+
+        /// Also serve the gRPC interface on this port (builds with the `grpc` feature)
+        #[arg(long)]
+        grpc_port: Option<u16>,
+
+        /// Set by the Windows service manager when running as a service
+        #[arg(long, hide = true)]
+        service_name: Option<String>,
+    },
+
+    /// Install the API server as a service that starts at boot (systemd, launchd, or Windows)
+    InstallService {
+        /// Service name
+        #[arg(long, default_value = "chasm-api")]
+        name: String,
+
+        /// Install a system-wide service instead of a user service (requires root)
+        #[arg(long)]
+        system: bool,
+
+        /// Print the service definition instead of installing it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Options for 'api serve', after -- (e.g. -- --port 9000 --tls-self-signed)
+        #[arg(last = true)]
+        serve_args: Vec<String>,
+    },
+
+    /// Stop and remove a service installed with install-service
+    UninstallService {
+        /// Service name
+        #[arg(long, default_value = "chasm-api")]
+        name: String,
+
+        /// Remove a system-wide service
+        #[arg(long)]
+        system: bool,
+    },
+
+    /// Manage API keys for the API server
+    Keys {
+        #[command(subcommand)]
+        command: ApiKeyCommands,
+    },
+
+    /// Manage API server users
+    Users {
+        #[command(subcommand)]
+        command: ApiUserCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ApiKeyCommands {
+    /// Create an API key (shown once)
+    Create {
+        /// Name describing who uses the key
+        name: String,
+
+        /// Email or ID of the user the key acts for; without it the key is an admin key
+        #[arg(long)]
+        user: Option<String>,
+
+        /// What the key may do: viewer, member (editor), admin, or owner
+        /// [default: member with --user, admin without]
+        #[arg(long)]
+        role: Option<String>,
+
+        /// Path to the database file
+        #[arg(long)]
+        database: Option<String>,
+    },
+
+    /// List API keys
+    List {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Path to the database file
+        #[arg(long)]
+        database: Option<String>,
+    },
+
+    /// Revoke an API key
+    Revoke {
+        /// Key ID or the key prefix shown by 'list'
+        key: String,
+
+        /// Path to the database file
+        #[arg(long)]
+        database: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ApiUserCommands {
+    /// Set what a user may do: viewer, member (editor), admin, or owner
+    SetRole {
+        /// Email or ID of the user
+        user: String,
+
+        /// Role to give them
+        role: String,
+
+        /// Path to the database file
+        #[arg(long)]
+        database: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RoutingCommands {
+    /// Show the health, latency, and score of each gateway provider
+    Status {
+        /// Server URL (default: http://localhost:8787)
+        #[arg(long, default_value = "http://localhost:8787")]
+        server: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show the workspace policy covering a directory or workspace name
+    Policy {
+        /// Directory or workspace name (default: the current directory)
+        workspace: Option<String>,
+    },
+
+    /// List the providers workspace policies allowed or refused, newest first
+    Audit {
+        /// Only decisions of this policy or workspace
+        #[arg(long)]
+        workspace: Option<String>,
+
+        /// Only refused providers
+        #[arg(long)]
+        denied: bool,
+
+        /// Maximum entries to show
+        #[arg(short = 'n', long, default_value = "50")]
+        limit: usize,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Path to the database file
+        #[arg(long)]
+        database: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AuditCommands {
+    /// List recorded requests, newest first
+    List {
+        /// Only requests by this API key (ID or name) or user (ID or email)
+        #[arg(long)]
+        actor: Option<String>,
+
+        /// Only requests with this HTTP method
+        #[arg(long)]
+        method: Option<String>,
+
+        /// Only requests touching this resource type (session, agent, swarm,
+        /// account, webhook, project), optionally narrowed to one ID as TYPE:ID
+        #[arg(long)]
+        resource: Option<String>,
+
+        /// Only requests at or after this date
+        #[arg(long)]
+        after: Option<String>,
+
+        /// Only requests before this date
+        #[arg(long)]
+        before: Option<String>,
+
+        /// Maximum entries to show
+        #[arg(short = 'n', long, default_value = "50")]
+        limit: usize,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Path to the database file
+        #[arg(long)]
+        database: Option<String>,
+    },
+}
+
+// ============================================================================
+// Agency (Agent Development Kit) Subcommands
+// ============================================================================
+
+#[derive(Subcommand)]
+pub enum AgencyCommands {
+    /// List available agents and their roles
+    List {
+        /// Show detailed information
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Show agent information
+    Info {
+        /// Agent name or ID
+        name: String,
+    },
+
+    /// List supported orchestration modes
+    Modes,
+
+    /// Run an agent with a prompt
+    Run {
         /// Agent name to run
         #[arg(short, long, default_value = "assistant")]
         agent: String,
@@ -1642,16 +2271,105 @@ pub enum AgencyCommands {
         #[arg(short, long)]
         model: Option<String>,
 
-        /// Orchestration mode (single, sequential, parallel, swarm)
-        #[arg(long, default_value = "single")]
-        orchestration: String,
+        /// Orchestration mode (single, sequential, parallel, swarm; default: the agent's, else single)
+        #[arg(long)]
+        orchestration: Option<String>,
+
+        /// Image to show a vision model with the prompt (repeatable)
+        #[arg(long = "image", value_name = "FILE")]
+        images: Vec<String>,
+
+        /// Text file to add to the prompt (repeatable)
+        #[arg(long = "file", value_name = "FILE")]
+        files: Vec<String>,
+
+        /// Enable verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Continue a harvested session, from any provider, with an agent
+    Resume {
+        /// Harvested session ID (or the start of one)
+        session_id: String,
+
+        /// Next message in the conversation
+        prompt: String,
+
+        /// Agent to continue with
+        #[arg(short, long, default_value = "assistant")]
+        agent: String,
+
+        /// Model to use (e.g., gpt-4o, ollama/qwen2.5)
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Do not store the continued conversation as a session
+        #[arg(long)]
+        no_save: bool,
+
+        /// Enable verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Run agents on another machine: a csm API server or an SSH host
+    Remote {
+        /// URL of the csm API server running the agents
+        #[arg(long, global = true, env = "CSM_REMOTE_SERVER")]
+        server: Option<String>,
+
+        /// API key for the server
+        #[arg(long, global = true, env = "CSM_API_KEY", hide_env_values = true)]
+        token: Option<String>,
+
+        #[command(subcommand)]
+        command: AgencyRemoteCommands,
+    },
+
+    /// Run a pipeline of agents (sequential, parallel, or loop) from a YAML file
+    Pipeline {
+        /// Path to the pipeline definition
+        file: String,
+
+        /// Prompt or task (default: the definition's input)
+        prompt: Option<String>,
+
+        /// Model for agents the definition gives none
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Do not store the agents' transcripts as sessions
+        #[arg(long)]
+        no_save: bool,
+
+        /// Enable verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Run a coordinator and its worker agents from a YAML file
+    Swarm {
+        /// Path to the swarm definition
+        file: String,
+
+        /// Prompt or task (default: the definition's input)
+        prompt: Option<String>,
+
+        /// Model for agents the definition gives none
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Do not store the agents' transcripts as sessions
+        #[arg(long)]
+        no_save: bool,
 
         /// Enable verbose output
         #[arg(short, long)]
         verbose: bool,
     },
 
-    /// Create a new agent configuration
+    /// Create an agent, saved as a TOML file in the agents folder
     Create {
         /// Agent name
         name: String,
@@ -1667,6 +2385,18 @@ pub enum AgencyCommands {
         /// Model to use
         #[arg(short, long)]
         model: Option<String>,
+
+        /// Builtin tool the agent can call (repeatable)
+        #[arg(short, long = "tool")]
+        tools: Vec<String>,
+
+        /// Orchestration mode `agency run` uses for the agent
+        #[arg(long)]
+        orchestration: Option<String>,
+
+        /// Replace an existing agent file of the same name
+        #[arg(long)]
+        force: bool,
     },
 
     /// List available tools
@@ -1676,6 +2406,69 @@ pub enum AgencyCommands {
     Templates,
 }
 
+#[derive(Subcommand)]
+pub enum AgencyRemoteCommands {
+    /// Start an agent run on the server and follow it
+    Submit {
+        /// Prompt or task for the agent
+        prompt: String,
+
+        /// Agent to run
+        #[arg(short, long, default_value = "assistant")]
+        agent: String,
+
+        /// Model to use (e.g., gpt-4o, ollama/qwen2.5)
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Run over SSH on this host (user@host) instead of on a server
+        #[arg(long, conflicts_with = "server")]
+        ssh: Option<String>,
+
+        /// Print the task ID and return without following the run
+        #[arg(short, long, conflicts_with = "ssh")]
+        detach: bool,
+
+        /// Download the files the agent wrote into this folder once it finishes
+        #[arg(long, conflicts_with_all = ["ssh", "detach"])]
+        fetch: Option<String>,
+    },
+
+    /// List the server's tasks
+    List,
+
+    /// Show a task's status and result
+    Status {
+        /// Task ID
+        task_id: String,
+    },
+
+    /// Follow a task's log until it finishes
+    Logs {
+        /// Task ID
+        task_id: String,
+    },
+
+    /// Download the files a task produced
+    Fetch {
+        /// Task ID
+        task_id: String,
+
+        /// Artifact to download (default: all)
+        name: Option<String>,
+
+        /// Folder to save into
+        #[arg(short, long, default_value = ".")]
+        out: String,
+    },
+
+    /// Stop a running task
+    Cancel {
+        /// Task ID
+        task_id: String,
+    },
+}
+
 // ============================================================================
 // Telemetry Subcommands
 // ============================================================================
@@ -1752,11 +2545,11 @@ pub enum TelemetryCommands {
         #[arg(short, long, default_value = "table")]
         format: String,
 
-        /// Show records after this date (YYYY-MM-DD)
+        /// Show records on or after this date (YYYY-MM-DD, local time unless Z/+HH:MM is given)
         #[arg(long)]
         after: Option<String>,
 
-        /// Show records before this date (YYYY-MM-DD)
+        /// Show records on or before this date (YYYY-MM-DD, local time unless Z/+HH:MM is given)
         #[arg(long)]
         before: Option<String>,
     },
@@ -1824,10 +2617,1157 @@ pub enum TelemetryCommands {
     Test,
 }
 
-/// Parse key=value pairs for telemetry record command
-fn parse_key_value(s: &str) -> std::result::Result<(String, String), String> {
-    let pos = s
-        .find('=')
-        .ok_or_else(|| format!("invalid key=value pair: no '=' found in '{s}'"))?;
-    Ok((s[..pos].to_string(), s[pos + 1..].to_string()))
+// ============================================================================
+// Backup Subcommands
+// ============================================================================
+
+#[derive(Subcommand)]
+pub enum BackupCommands {
+    /// List recorded backups, newest first
+    List {
+        /// Only show backups whose source path contains this text
+        #[arg(long)]
+        source: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Delete backups outside the retention policy ([backups] in config)
+    Prune {
+        /// Newest backups to keep per source (overrides backups.keep)
+        #[arg(long)]
+        keep: Option<usize>,
+
+        /// Also delete backups older than this many days (overrides backups.max_age_days)
+        #[arg(long)]
+        older_than: Option<u64>,
+
+        /// Show what would be deleted without deleting
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Write an encrypted archive of the harvest database and session files
+    Run {
+        /// Directory, s3://bucket/prefix, or webdav+https://host/path (default: backups.destination)
+        #[arg(long, short = 'd')]
+        destination: Option<String>,
+
+        /// Leave out the harvest database
+        #[arg(long)]
+        no_harvest: bool,
+
+        /// Leave out workspace session files
+        #[arg(long)]
+        no_sessions: bool,
+    },
+
+    /// List encrypted archives at a destination
+    Archives {
+        /// Directory, s3://bucket/prefix, or webdav+https://host/path (default: backups.destination)
+        #[arg(long, short = 'd')]
+        destination: Option<String>,
+    },
+
+    /// Check an archive's checksums and decrypt it to verify every file
+    Verify {
+        /// Archive file, or an archive name at the destination
+        archive: String,
+
+        /// Destination to read named archives from (default: backups.destination)
+        #[arg(long, short = 'd')]
+        destination: Option<String>,
+    },
+
+    /// Restore an encrypted archive
+    Restore {
+        /// Archive file, or an archive name at the destination
+        #[arg(required_unless_present = "interactive")]
+        archive: Option<String>,
+
+        /// Destination to read named archives from (default: backups.destination)
+        #[arg(long, short = 'd')]
+        destination: Option<String>,
+
+        /// Extract into this directory instead of the original locations
+        #[arg(long)]
+        into: Option<String>,
+
+        /// Overwrite files that differ from the archive (after backing them up)
+        #[arg(long)]
+        force: bool,
+
+        /// Show what would be restored without writing
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Browse backups, archives, git snapshots, and checkpoints and pick
+        /// a point in time and scope to restore
+        #[arg(long, short = 'i', conflicts_with_all = ["archive", "into", "force"])]
+        interactive: bool,
+    },
+}
+
+// ============================================================================
+// Pack Subcommands
+// ============================================================================
+
+#[derive(Subcommand)]
+pub enum PackCommands {
+    /// Build automation packs (workflows, agents, prompt templates, saved searches)
+    Automation {
+        #[command(subcommand)]
+        command: AutomationPackCommands,
+    },
+
+    /// Import a pack into the local library
+    Import {
+        /// Pack file
+        file: String,
+
+        /// Replace library items that already exist
+        #[arg(long)]
+        force: bool,
+
+        /// Import packs that are unsigned or cannot be verified without CSM_PACK_KEY
+        #[arg(long)]
+        allow_unsigned: bool,
+
+        /// Show what would be imported without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Show a pack's manifest, contents, and signature status
+    Inspect {
+        /// Pack file
+        file: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AutomationCommands {
+    /// List library workflows with their triggers and next run
+    List,
+
+    /// Run a library workflow now
+    Run {
+        /// Workflow name
+        name: String,
+
+        /// Output the run record as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Run enabled workflows on their schedules and on session file changes until Ctrl+C
+    Start,
+
+    /// List built-in workflow templates and their parameters
+    Templates,
+
+    /// Add a workflow to the library from a built-in template, asking for its parameters
+    Init {
+        /// Template to start from (see `automation templates`)
+        #[arg(long)]
+        template: String,
+
+        /// Workflow name (defaults to the template's)
+        name: Option<String>,
+
+        /// Template parameter as NAME=VALUE (repeatable); others are asked for
+        #[arg(long = "set", value_name = "NAME=VALUE")]
+        set: Vec<String>,
+
+        /// Use defaults for parameters not given with --set instead of asking
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Replace a library workflow with the same name
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Show past runs kept in the harvest database
+    Runs {
+        /// Workflow ID (all workflows when omitted)
+        workflow: Option<String>,
+
+        /// Show one run's action results (a run ID or its first characters)
+        #[arg(long)]
+        run: Option<String>,
+
+        /// Maximum number of runs to show
+        #[arg(long, default_value = "20")]
+        limit: usize,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PluginCommands {
+    /// List installed plugins with their state, permissions, and actions
+    List {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Install a plugin from a directory, a plugin.json, the URL of one, or the registry
+    Install {
+        /// Plugin directory, plugin.json path, URL, or registry plugin ID
+        source: String,
+
+        /// Grant the shell and sensitive permissions without asking
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Replace an installed plugin with the same ID, keeping its data and configuration
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Search the plugin registry
+    Search {
+        /// Words to search for
+        #[arg(default_value = "")]
+        query: String,
+
+        /// Only plugins in this category (provider, export, analysis, ui, automation, storage, auth, other)
+        #[arg(long)]
+        category: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Update plugins installed from the registry, showing what changed
+    Update {
+        /// Plugin ID (default: every plugin installed from the registry)
+        id: Option<String>,
+
+        /// Grant new shell and sensitive permissions without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Enable a plugin, granting the permissions it asks for
+    Enable {
+        /// Plugin ID
+        id: String,
+
+        /// Grant the shell and sensitive permissions without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Disable a plugin without removing it
+    Disable {
+        /// Plugin ID
+        id: String,
+    },
+
+    /// Remove a plugin and its data
+    Remove {
+        /// Plugin ID
+        id: String,
+    },
+
+    /// Run a plugin from its source directory, reloading it whenever a file in it changes
+    Dev {
+        /// Plugin directory
+        path: String,
+
+        /// Grant the shell and sensitive permissions without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Show or change a plugin's configuration
+    Config {
+        /// Plugin ID
+        id: String,
+
+        /// Setting as KEY=VALUE (repeatable); VALUE is parsed as JSON when it can be
+        #[arg(long = "set", value_name = "KEY=VALUE")]
+        set: Vec<String>,
+
+        /// Setting to remove (repeatable)
+        #[arg(long, value_name = "KEY")]
+        unset: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AutomationPackCommands {
+    /// List library items available for export
+    List,
+
+    /// Export library items to a pack (everything when no items are selected).
+    /// Signed with CSM_PACK_KEY when set
+    Export {
+        /// Output file (e.g., team.csmpack)
+        file: String,
+
+        /// Workflows to include (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        workflow: Vec<String>,
+
+        /// Agents to include (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        agent: Vec<String>,
+
+        /// Prompt templates to include (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        prompt: Vec<String>,
+
+        /// Saved searches to include (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        search: Vec<String>,
+
+        /// Pack name (defaults to the file name)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Pack description
+        #[arg(long)]
+        description: Option<String>,
+    },
+}
+
+// ============================================================================
+// Config Subcommands
+// ============================================================================
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Show resolved storage roots and where each value comes from
+    Show,
+
+    /// Print the config file path
+    Path,
+
+    /// Get a config value by dotted key (e.g., storage.harvest_db)
+    Get {
+        /// Dotted key
+        key: String,
+
+        /// Read from a named profile instead of the top level
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    /// Set a config value by dotted key
+    Set {
+        /// Dotted key (e.g., storage.backup_dir, defaults.harvest_providers)
+        key: String,
+
+        /// Value (TOML syntax for lists, e.g. '["copilot","cursor"]')
+        value: String,
+
+        /// Write to a named profile instead of the top level
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    /// Remove a config value by dotted key
+    Unset {
+        /// Dotted key
+        key: String,
+
+        /// Remove from a named profile instead of the top level
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    /// Select the active profile
+    Use {
+        /// Profile name (omit to clear the active profile)
+        name: Option<String>,
+    },
+
+    /// List configured profiles
+    Profiles,
+}
+
+// ============================================================================
+// Notify Subcommands
+// ============================================================================
+
+#[derive(Subcommand)]
+pub enum NotifyCommands {
+    /// List configured channels and available providers
+    List,
+
+    /// Send a test notification to a channel
+    Test {
+        /// Channel name, or a provider name when one channel uses it (e.g., slack)
+        channel: String,
+
+        /// Message to send
+        #[arg(long, short)]
+        message: Option<String>,
+    },
+
+    /// Add or replace a channel
+    Add {
+        /// Channel name
+        name: String,
+
+        /// Provider (system, slack, discord, email, webhook)
+        provider: String,
+
+        /// Webhook URL (slack, discord, webhook); use $VAR to read it from the environment
+        #[arg(long)]
+        url: Option<String>,
+
+        /// Recipient address (email)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Event patterns to deliver (comma-separated, e.g. "harvest.*,agency.*")
+        #[arg(long, value_delimiter = ',')]
+        events: Option<Vec<String>>,
+
+        /// Message template (e.g. "[{{level}}] {{title}}: {{message}}")
+        #[arg(long)]
+        template: Option<String>,
+    },
+
+    /// Remove a channel
+    Remove {
+        /// Channel name
+        name: String,
+    },
+}
+
+/// Parse key=value pairs for telemetry record command
+fn parse_key_value(s: &str) -> std::result::Result<(String, String), String> {
+    let pos = s
+        .find('=')
+        .ok_or_else(|| format!("invalid key=value pair: no '=' found in '{s}'"))?;
+    Ok((s[..pos].to_string(), s[pos + 1..].to_string()))
+}
+
+// ============================================================================
+// Project Subcommands
+// ============================================================================
+
+#[derive(Subcommand)]
+pub enum ProjectCommands {
+    /// Create a project
+    Create {
+        /// Project name
+        name: String,
+
+        /// Short description
+        #[arg(long, short = 'd')]
+        description: Option<String>,
+
+        /// Workspaces to add (hash, hash prefix, or project folder)
+        #[arg(long = "workspace", short = 'w')]
+        workspaces: Vec<String>,
+    },
+
+    /// Add workspaces to a project
+    #[command(name = "add-workspace")]
+    AddWorkspace {
+        /// Project name
+        project: String,
+
+        /// Workspaces to add (hash, hash prefix, or project folder)
+        #[arg(required = true, num_args = 1..)]
+        workspaces: Vec<String>,
+    },
+
+    /// Remove a workspace from a project
+    #[command(name = "remove-workspace")]
+    RemoveWorkspace {
+        /// Project name
+        project: String,
+
+        /// Workspace hash, hash prefix, or project folder
+        workspace: String,
+    },
+
+    /// List projects
+    #[command(visible_alias = "ls")]
+    List {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show a project's workspaces
+    Show {
+        /// Project name
+        name: String,
+    },
+
+    /// Show session and message totals for a project
+    Stats {
+        /// Project name
+        name: String,
+    },
+
+    /// Suggest projects from workspaces that share a git remote
+    Suggest {
+        /// Create the suggested projects
+        #[arg(long)]
+        create: bool,
+    },
+
+    /// Delete a project (workspaces and sessions are kept)
+    #[command(visible_alias = "rm")]
+    Delete {
+        /// Project name
+        name: String,
+    },
+}
+
+// ============================================================================
+// Report Subcommands
+// ============================================================================
+
+#[derive(Subcommand)]
+pub enum ReportCommands {
+    /// Summarize code-review sessions: what was reviewed, feedback themes, most discussed files
+    Reviews {
+        /// Only sessions updated since this age (12h, 30d, 2w) or date
+        #[arg(long, default_value = "30d")]
+        since: String,
+
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Maximum number of sessions, themes, and files to show
+        #[arg(long, default_value = "10")]
+        limit: usize,
+
+        /// Re-run review detection on sessions in the window before reporting
+        #[arg(long)]
+        rescan: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Summarize the tokens and cost of agent runs by day, model, and agent
+    Spend {
+        /// Only runs since this age (12h, 30d, 2w) or date
+        #[arg(long, default_value = "30d")]
+        since: String,
+
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Compare session quality and sentiment by provider, project, model, and month
+    Quality {
+        /// Only sessions updated since this age (12h, 30d, 2w) or date
+        #[arg(long, default_value = "90d")]
+        since: String,
+
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Only sessions from this provider
+        #[arg(long)]
+        provider: Option<String>,
+
+        /// Maximum number of rows per breakdown
+        #[arg(long, default_value = "10")]
+        limit: usize,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Sum up new sessions per provider, top topics, and the longest sessions, and email it
+    Digest {
+        /// The period: since this age (12h, 30d, 2w) or date
+        #[arg(long, default_value = "7d")]
+        since: String,
+
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Maximum number of topics and long sessions
+        #[arg(long, default_value = "5")]
+        limit: usize,
+
+        /// Email it through the server under [integrations.smtp]
+        #[arg(long)]
+        send: bool,
+
+        /// Recipient, instead of `to` under [integrations.smtp] (repeatable)
+        #[arg(long, requires = "send")]
+        to: Vec<String>,
+
+        /// Also write the HTML email to this file
+        #[arg(long)]
+        html: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+// ============================================================================
+// Intelligence Subcommands
+// ============================================================================
+
+#[derive(Subcommand)]
+pub enum IntelligenceCommands {
+    /// Find near-duplicate sessions (questions asked again, copied chats) and suggest which to merge or delete
+    Dupes {
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Similarity (0-1) a pair needs to be reported [default: 0.6, or 0.9 with --semantic]
+        #[arg(long)]
+        threshold: Option<f32>,
+
+        /// Compare embeddings from the semantic search endpoint instead of shared words
+        #[arg(long)]
+        semantic: bool,
+
+        /// Only compare sessions from this provider
+        #[arg(long)]
+        provider: Option<String>,
+
+        /// Maximum number of pairs to show
+        #[arg(long, default_value = "20")]
+        limit: usize,
+
+        /// Delete the sessions suggested for deletion (they can be restored)
+        #[arg(long)]
+        delete: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Title sessions that are "Untitled" or named after a cut-off first message
+    Retitle {
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Show the new titles without saving them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Retitle every session without a title of its own
+        #[arg(long)]
+        all: bool,
+
+        /// Have this model write the titles instead of taking them from the first message
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Only retitle sessions from this provider
+        #[arg(long)]
+        provider: Option<String>,
+
+        /// Retitle at most this many sessions, most recent first
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// Also write the titles into VS Code's session files
+        #[arg(long)]
+        write_back: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// File sessions under the topics defined in [topics] of the config file
+    Topics {
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Only read sessions from this provider
+        #[arg(long)]
+        provider: Option<String>,
+
+        /// Only read this session (ID or a prefix of it)
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Only show the topics already stored, without reading sessions
+        #[arg(long)]
+        list: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Find TODOs, decisions and commitments in sessions and store them as action items
+    Actions {
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Only read sessions from this provider
+        #[arg(long)]
+        provider: Option<String>,
+
+        /// Only read this session (ID or a prefix of it)
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+// ============================================================================
+// Action Item Subcommands
+// ============================================================================
+
+#[derive(Subcommand)]
+pub enum ActionsCommands {
+    /// List open action items
+    #[command(visible_alias = "ls")]
+    List {
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Only these kinds: todo, decision, commitment (comma-separated)
+        #[arg(long)]
+        kind: Option<String>,
+
+        /// Only items from this session (ID or a prefix of it)
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Include items marked done
+        #[arg(long)]
+        all: bool,
+
+        /// Maximum number of items to show
+        #[arg(long, default_value = "50")]
+        limit: usize,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Mark action items done
+    Done {
+        /// IDs of the items, as shown by 'csm actions list'
+        #[arg(required = true)]
+        ids: Vec<i64>,
+
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Open the items again instead
+        #[arg(long)]
+        reopen: bool,
+    },
+
+    /// Export open action items to a task manager or a file
+    Export {
+        /// Where to: todoist (needs TODOIST_API_TOKEN), jira, linear, markdown, json
+        #[arg(long, default_value = "markdown")]
+        to: String,
+
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Only these kinds (comma-separated) [default: todo,commitment]
+        #[arg(long)]
+        kind: Option<String>,
+
+        /// Only items from this session (ID or a prefix of it)
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Todoist project, Jira project key or Linear team key to add them to
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Write to this file instead of stdout (markdown, json)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Show what would be exported without creating tasks or files
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+// ============================================================================
+// GitHub Subcommands
+// ============================================================================
+
+#[derive(Subcommand)]
+pub enum GithubCommands {
+    /// Link a session to the repository, branch and commit it worked on
+    Link {
+        /// Session ID (or a prefix of it)
+        session: String,
+
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Git checkout to read the repository, branch and commit from
+        /// [default: the session's project folder, or the current directory]
+        #[arg(long)]
+        dir: Option<String>,
+
+        /// Repository as owner/repo, instead of the checkout's
+        #[arg(long)]
+        repo: Option<String>,
+
+        /// Branch, instead of the checkout's
+        #[arg(long)]
+        branch: Option<String>,
+
+        /// Commit, instead of the checkout's
+        #[arg(long)]
+        commit: Option<String>,
+
+        /// Pull request the session was for
+        #[arg(long)]
+        pr: Option<u64>,
+
+        /// Issue the session was for
+        #[arg(long, conflicts_with = "pr")]
+        issue: Option<u64>,
+
+        /// Remove the session's link instead
+        #[arg(long)]
+        remove: bool,
+    },
+
+    /// List sessions linked to GitHub
+    #[command(visible_alias = "ls")]
+    Links {
+        /// Only sessions linked to this issue or pull request (owner/repo#12 or a URL)
+        reference: Option<String>,
+
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Only sessions linked to this repository (owner/repo)
+        #[arg(long)]
+        repo: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Post a summary of a session as a comment on its pull request or issue
+    Comment {
+        /// Session ID (or a prefix of it)
+        session: String,
+
+        /// Issue or pull request to comment on, instead of the linked one
+        /// (owner/repo#12, #12 or a URL)
+        #[arg(long)]
+        on: Option<String>,
+
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Print the comment without posting it
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Import an issue or pull request discussion as a session
+    Import {
+        /// Issue or pull request: owner/repo#12, a github.com URL, or #12 in --repo
+        reference: String,
+
+        /// Repository for a bare number [default: the current checkout's]
+        #[arg(long)]
+        repo: Option<String>,
+
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+    },
+}
+
+// ============================================================================
+// Slack Subcommands
+// ============================================================================
+
+#[derive(Subcommand)]
+pub enum SlackCommands {
+    /// List the recent threads of a channel
+    Threads {
+        /// Channel name or ID
+        channel: String,
+
+        /// Recent messages to look for threads in
+        #[arg(long, default_value = "100")]
+        limit: u32,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Import threads as sessions
+    Import {
+        /// Threads, as message links or CHANNEL:TS from 'csm slack threads'
+        #[arg(required = true)]
+        threads: Vec<String>,
+
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+    },
+
+    /// Post a summary of a session to a channel
+    Post {
+        /// Session ID (or a prefix of it)
+        session: String,
+
+        /// Channel name or ID [default: channel under [integrations.slack]]
+        #[arg(long)]
+        channel: Option<String>,
+
+        /// Reply in this thread instead (message link or CHANNEL:TS)
+        #[arg(long, conflicts_with = "channel")]
+        thread: Option<String>,
+
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Print the summary without posting it
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+// ============================================================================
+// Obsidian Subcommands
+// ============================================================================
+
+#[derive(Subcommand)]
+pub enum ObsidianCommands {
+    /// Write a note per harvested session and an index note per workspace
+    Sync {
+        /// Vault directory [default: vault under [integrations.obsidian]]
+        vault: Option<String>,
+
+        /// Folder in the vault for the notes [default: "Chat Sessions"]
+        #[arg(long)]
+        folder: Option<String>,
+
+        /// Keep watching VS Code workspaces, harvesting and syncing sessions as they change
+        #[arg(long)]
+        watch: bool,
+
+        /// Milliseconds a file must be quiet before it is harvested (with --watch)
+        #[arg(long, default_value = "500")]
+        debounce: u64,
+
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+    },
+}
+
+// ============================================================================
+// Issue Subcommands
+// ============================================================================
+
+#[derive(Subcommand)]
+pub enum IssueCommands {
+    /// File an issue for a session, with a summary of it and a link back
+    Create {
+        /// Session ID (or a prefix of it)
+        session: String,
+
+        /// Tracker: jira or linear [default: issue_tracker under [integrations]]
+        #[arg(long)]
+        tracker: Option<String>,
+
+        /// Jira project or Linear team key instead of the configured one
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Issue title [default: the session's title]
+        #[arg(long)]
+        title: Option<String>,
+
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Print the issue without filing it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// File another issue for a session that already has one
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// List the issues filed for sessions
+    #[command(visible_alias = "ls")]
+    List {
+        /// Only issues of this session (ID or a prefix of it)
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Only issues in this tracker: jira or linear
+        #[arg(long)]
+        tracker: Option<String>,
+
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+// ============================================================================
+// Knowledge Graph Subcommands
+// ============================================================================
+
+#[derive(Subcommand)]
+pub enum GraphCommands {
+    /// Read harvested sessions into the graph, replacing what they added before
+    Build {
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Only read sessions from this provider
+        #[arg(long)]
+        provider: Option<String>,
+
+        /// Only read this session (ID or a prefix of it)
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show a node (file, crate, API, person, error or session) and its links
+    Query {
+        /// Name of the node, or part of it
+        name: String,
+
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Only nodes of this kind: session, file, crate, api, person, error
+        #[arg(long)]
+        kind: Option<String>,
+
+        /// Maximum number of links to show
+        #[arg(long, default_value = "25")]
+        limit: usize,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Write the graph for Gephi, yEd, Graphviz and similar tools
+    Export {
+        /// File format: graphml, dot, json
+        #[arg(long, default_value = "graphml")]
+        format: String,
+
+        /// Path to the harvest database
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Only nodes of these kinds (comma-separated)
+        #[arg(long)]
+        kind: Option<String>,
+
+        /// Leave out links seen fewer times than this
+        #[arg(long, default_value = "1")]
+        min_weight: i64,
+
+        /// Leave out session nodes, keeping only entities
+        #[arg(long)]
+        no_sessions: bool,
+    },
+}
+
+// ============================================================================
+// MCP Subcommands
+// ============================================================================
+
+#[derive(Subcommand)]
+pub enum McpCommands {
+    /// Add csm-mcp to an MCP client's configuration and check that it starts
+    Install {
+        /// Client to configure
+        #[arg(long, value_parser = ["claude-desktop", "vscode", "cursor"])]
+        client: String,
+
+        /// Configure the current project (.vscode/mcp.json or .cursor/mcp.json)
+        /// instead of the user's settings
+        #[arg(long)]
+        project: bool,
+
+        /// Server name in the client's configuration
+        #[arg(long, default_value = "csm")]
+        name: String,
+
+        /// Let the client's agents use tools that change data
+        #[arg(long)]
+        allow_write: bool,
+
+        /// Configuration file to edit instead of the client's default
+        #[arg(long)]
+        config: Option<String>,
+
+        /// Skip starting csm-mcp to check that it answers
+        #[arg(long)]
+        no_verify: bool,
+    },
 }
@@ -0,0 +1,268 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Action item commands
+
+use anyhow::{bail, Context, Result};
+use colored::*;
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+use tabled::{settings::Style as TableStyle, Table, Tabled};
+
+use super::harvest::{get_db_path, open_harvest_db};
+use super::issues::export_issues;
+use crate::actions::{
+    ensure_action_items_table, list_action_items, set_action_exported, set_action_status,
+    ActionFilter, StoredActionItem, DONE, OPEN,
+};
+use crate::integrations::productivity::{
+    Task, TaskPriority, TaskStatus, TasksProvider, TodoistTasks,
+};
+use crate::intelligence::ActionKind;
+use crate::text::truncate;
+
+#[derive(Tabled)]
+struct ActionRow {
+    #[tabled(rename = "ID")]
+    id: i64,
+    #[tabled(rename = "Kind")]
+    kind: String,
+    #[tabled(rename = "Item")]
+    text: String,
+    #[tabled(rename = "Session")]
+    session: String,
+    #[tabled(rename = "Status")]
+    status: String,
+}
+
+fn open_db(db_path: Option<&str>) -> Result<Connection> {
+    let conn = open_harvest_db(db_path)?;
+    ensure_action_items_table(&conn)?;
+    Ok(conn)
+}
+
+fn parse_kinds(kind: Option<&str>) -> Result<Vec<ActionKind>> {
+    let Some(kind) = kind else {
+        return Ok(Vec::new());
+    };
+    kind.split(',')
+        .map(|kind| {
+            ActionKind::parse(kind.trim()).with_context(|| {
+                format!(
+                    "Unknown kind '{}'; use todo, decision or commitment",
+                    kind.trim()
+                )
+            })
+        })
+        .collect()
+}
+
+/// List the action items found in harvested sessions
+pub fn actions_list(
+    db_path: Option<&str>,
+    kind: Option<&str>,
+    session: Option<&str>,
+    all: bool,
+    limit: usize,
+    json: bool,
+) -> Result<()> {
+    let conn = open_db(db_path)?;
+    let filter = ActionFilter {
+        kinds: parse_kinds(kind)?,
+        session,
+        include_done: all,
+        limit: Some(limit),
+    };
+    let items = list_action_items(&conn, &filter)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&items)?);
+        return Ok(());
+    }
+    println!("\n{} Action Items", "[*]".blue().bold());
+    println!("{}", "=".repeat(60));
+    if items.is_empty() {
+        println!(
+            "\n{} No action items; find them with 'csm intelligence actions'",
+            "[!]".yellow()
+        );
+        return Ok(());
+    }
+    let rows: Vec<ActionRow> = items
+        .iter()
+        .map(|item| ActionRow {
+            id: item.id,
+            kind: item.kind.clone(),
+            text: truncate(&item.text, 70),
+            session: truncate(&item.session_title, 30),
+            status: item.status.clone(),
+        })
+        .collect();
+    println!("{}", Table::new(rows).with(TableStyle::ascii_rounded()));
+    println!("{}", "[i] Close one with 'csm actions done <id>'".dimmed());
+    Ok(())
+}
+
+/// Mark action items done, or open again with `reopen`
+pub fn actions_done(db_path: Option<&str>, ids: &[i64], reopen: bool) -> Result<()> {
+    let conn = open_db(db_path)?;
+    let status = if reopen { OPEN } else { DONE };
+    for id in ids {
+        if set_action_status(&conn, *id, status)? {
+            println!("{} Marked #{} {}", "[+]".green(), id, status);
+        } else {
+            eprintln!("{} No action item #{}", "[!]".yellow(), id);
+        }
+    }
+    Ok(())
+}
+
+/// Export open action items to a task manager, an issue tracker or a file:
+/// `todoist`, `jira`, `linear`, `markdown` or `json`
+pub fn actions_export(
+    db_path: Option<&str>,
+    to: &str,
+    kind: Option<&str>,
+    session: Option<&str>,
+    project: Option<&str>,
+    output: Option<&str>,
+    dry_run: bool,
+) -> Result<()> {
+    let conn = open_db(db_path)?;
+    let mut kinds = parse_kinds(kind)?;
+    if kinds.is_empty() {
+        // Decisions are a record, not something to do
+        kinds = vec![ActionKind::Todo, ActionKind::Commitment];
+    }
+    let filter = ActionFilter {
+        kinds,
+        session,
+        ..Default::default()
+    };
+    let items = list_action_items(&conn, &filter)?;
+
+    let to = to.to_lowercase();
+    let text = match to.as_str() {
+        "todoist" => return export_todoist(&conn, &items, project, dry_run),
+        "jira" | "linear" => return export_issues(&conn, &items, &to, project, dry_run),
+        "markdown" | "md" => checklist(&items),
+        "json" => {
+            let tasks: Vec<Task> = items.iter().map(|item| task(item, project)).collect();
+            serde_json::to_string_pretty(&tasks)?
+        }
+        other => bail!(
+            "Unknown export target '{}'; use todoist, jira, linear, markdown or json",
+            other
+        ),
+    };
+    match output {
+        Some(path) if !dry_run => {
+            std::fs::write(path, text).with_context(|| format!("Cannot write {}", path))?;
+            println!(
+                "{} Exported {} action item(s) to {}",
+                "[+]".green(),
+                items.len(),
+                path
+            );
+        }
+        _ => println!("{}", text),
+    }
+    Ok(())
+}
+
+fn export_todoist(
+    conn: &Connection,
+    items: &[StoredActionItem],
+    project: Option<&str>,
+    dry_run: bool,
+) -> Result<()> {
+    let pending: Vec<&StoredActionItem> = items
+        .iter()
+        .filter(|item| {
+            !item
+                .exported_to
+                .as_deref()
+                .is_some_and(|to| to.starts_with("todoist:"))
+        })
+        .collect();
+    if pending.is_empty() {
+        println!("{} Nothing new to export", "[i]".blue());
+        return Ok(());
+    }
+    if dry_run {
+        for item in &pending {
+            println!("  {} {}", "Would create".dimmed(), item.text);
+        }
+        println!("\n{} Dry run: no tasks were created", "[i]".blue());
+        return Ok(());
+    }
+
+    let Some(todoist) = TodoistTasks::from_env() else {
+        bail!("Set TODOIST_API_TOKEN to a Todoist API token (Settings > Integrations > Developer)");
+    };
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let mut created = 0;
+    for item in pending {
+        let result = rt.block_on(todoist.create_task(&task(item, project)));
+        if !result.success {
+            bail!(
+                "Could not create a task for #{}: {}",
+                item.id,
+                result.error.unwrap_or_default()
+            );
+        }
+        let task_id = result
+            .data
+            .as_ref()
+            .and_then(|task| task["id"].as_str())
+            .unwrap_or_default();
+        set_action_exported(conn, item.id, &format!("todoist:{}", task_id))?;
+        created += 1;
+    }
+    println!("{} Created {} task(s) in Todoist", "[+]".green(), created);
+    Ok(())
+}
+
+/// An action item as a task for a task manager
+fn task(item: &StoredActionItem, project: Option<&str>) -> Task {
+    Task {
+        id: item.id.to_string(),
+        title: item.text.clone(),
+        description: Some(format!(
+            "From the chat session \"{}\" ({})",
+            item.session_title, item.session_id
+        )),
+        due_date: None,
+        priority: TaskPriority::None,
+        status: if item.status == DONE {
+            TaskStatus::Completed
+        } else {
+            TaskStatus::Pending
+        },
+        project: project.map(String::from),
+        labels: vec!["csm".to_string(), item.kind.clone()],
+        subtasks: Vec::new(),
+        created_at: chrono::DateTime::from_timestamp_millis(item.created_at)
+            .map(|time| time.to_rfc3339())
+            .unwrap_or_default(),
+        completed_at: None,
+    }
+}
+
+/// The items as a Markdown checklist, one section per session
+fn checklist(items: &[StoredActionItem]) -> String {
+    let mut sessions: BTreeMap<&str, Vec<&StoredActionItem>> = BTreeMap::new();
+    for item in items {
+        sessions.entry(&item.session_title).or_default().push(item);
+    }
+    let mut text = String::from("# Action items\n");
+    for (title, items) in sessions {
+        text.push_str(&format!("\n## {}\n\n", title));
+        for item in items {
+            let done = if item.status == DONE { 'x' } else { ' ' };
+            text.push_str(&format!("- [{}] {}\n", done, item.text));
+        }
+    }
+    text
+}
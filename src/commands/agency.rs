@@ -2,9 +2,34 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //! Agency (Agent Development Kit) command implementations
 
-use crate::agency::{AgentRole, OrchestrationType};
-use anyhow::Result;
+use crate::agency::budget::{self, Budget, Spend};
+use crate::agency::definition::{self, AgentDefinition};
+use crate::agency::mcp_client::mount_servers;
+use crate::agency::models::{AgencyMessage, EventType, MessageRole, ModelConfig, ModelProvider};
+use crate::agency::rag::{answer_prompt, SessionIndex};
+use crate::agency::runtime::RunOptions;
+use crate::agency::tools::ToolRegistry;
+use crate::agency::{
+    AgencyEvent, Agent, AgentBuilder, AgentRole, BuiltinTools, ContentPart, ExecutionResult,
+    ImageContent, OrchestrationType, PipelineDefinition, Runtime, SERVED_BY,
+};
+use crate::commands::{get_db_path, insert_or_update_session};
+use crate::config::{self, SandboxConfig};
+use crate::models::ChatSession;
+use crate::pack::{validate_item_name, Library, PackItemKind};
+use crate::providers::{GenericMessage, GenericSession};
+use crate::routing::{
+    self, ConversationContext, FallbackPolicy, MessageRole as ContinuationRole, NormalizedMessage,
+};
+use crate::search::HttpEmbedder;
+use crate::text::truncate;
+use anyhow::{bail, Context, Result};
 use colored::Colorize;
+use serde_json::Value;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
 
 /// List available agents and roles
 pub fn list_agents(verbose: bool) -> Result<()> {
@@ -53,6 +78,37 @@ pub fn list_agents(verbose: bool) -> Result<()> {
         }
     }
 
+    let agents_dir = definition::agents_dir()?;
+    let agents = definition::load_dir(&agents_dir)?;
+    println!();
+    println!("{}", "[*] Your Agents:".bold());
+    println!();
+    if agents.is_empty() {
+        println!(
+            "  {}",
+            format!(
+                "None yet; create one with csm agency create, or add a TOML or YAML file to {}",
+                agents_dir.display()
+            )
+            .dimmed()
+        );
+    }
+    for agent in &agents {
+        let model = agent.definition.model_name().unwrap_or(DEFAULT_MODEL);
+        print!(
+            "  {} {}",
+            agent.name.green(),
+            format!("({})", model).dimmed()
+        );
+        if !agent.definition.description.is_empty() {
+            print!(" - {}", agent.definition.description);
+        }
+        println!();
+        if verbose {
+            println!("      {}", agent.path.display().to_string().dimmed());
+        }
+    }
+
     if verbose {
         println!();
         println!("{}", "[*] Default Agents:".bold());
@@ -87,6 +143,52 @@ pub fn show_agent_info(name: &str) -> Result<()> {
     println!("{}", format!("Agent: {}", name).bold());
     println!();
 
+    if let Some(agent) = definition::find(&definition::agents_dir()?, name)? {
+        let definition = agent.definition;
+        println!(
+            "  {} {}",
+            "Role:".dimmed(),
+            definition.role.as_deref().unwrap_or("custom").green()
+        );
+        if !definition.description.is_empty() {
+            println!("  {} {}", "Description:".dimmed(), definition.description);
+        }
+        println!(
+            "  {} {}",
+            "Model:".dimmed(),
+            definition.model_name().unwrap_or(DEFAULT_MODEL)
+        );
+        if let Some(temperature) = definition.temperature {
+            println!("  {} {}", "Temperature:".dimmed(), temperature);
+        }
+        if let Some(orchestration) = &definition.orchestration {
+            println!("  {} {}", "Orchestration:".dimmed(), orchestration);
+        }
+        if !definition.tools.is_empty() {
+            println!("  {} {}", "Tools:".dimmed(), definition.tools.join(", "));
+        }
+        if !definition.mcp_servers.is_empty() {
+            println!(
+                "  {} {}",
+                "MCP servers:".dimmed(),
+                definition.mcp_servers.join(", ")
+            );
+        }
+        let sandbox = match &definition.sandbox {
+            Some(sandbox) => describe_sandbox(sandbox),
+            None => format!(
+                "{} (default)",
+                describe_sandbox(&config::current().agency.sandbox)
+            ),
+        };
+        println!("  {} {}", "Sandbox:".dimmed(), sandbox);
+        if let Some(instruction) = &definition.instruction {
+            println!("  {} {}", "Instruction:".dimmed(), instruction);
+        }
+        println!("  {} {}", "File:".dimmed(), agent.path.display());
+        return Ok(());
+    }
+
     // Default agent configurations
     match name.to_lowercase().as_str() {
         "assistant" => {
@@ -152,6 +254,25 @@ pub fn show_agent_info(name: &str) -> Result<()> {
     Ok(())
 }
 
+/// A sandbox policy in one line
+fn describe_sandbox(sandbox: &SandboxConfig) -> String {
+    let mut parts = vec![sandbox.paths.join(", ")];
+    if sandbox.read_only {
+        parts.push("read-only".to_string());
+    } else if sandbox.allow_unconfined {
+        parts.push("unconfined commands".to_string());
+    }
+    parts.push(format!(
+        "network {}",
+        if sandbox.network { "on" } else { "off" }
+    ));
+    parts.push(format!("{}s limit", sandbox.timeout_secs));
+    if let Some(mb) = sandbox.max_memory_mb {
+        parts.push(format!("{} MB", mb));
+    }
+    parts.join("; ")
+}
+
 /// List orchestration modes
 pub fn list_modes() -> Result<()> {
     println!("{}", "[*] Orchestration Modes:".bold());
@@ -194,14 +315,31 @@ pub fn list_modes() -> Result<()> {
     Ok(())
 }
 
-/// Run an agent with a prompt
+/// Run an agent with a prompt, in the agent's own orchestration mode unless
+/// `orchestration` names one
 pub fn run_agent(
     agent_name: &str,
     prompt: &str,
     model: Option<&str>,
-    orchestration: &str,
+    orchestration: Option<&str>,
+    images: &[String],
+    files: &[String],
     verbose: bool,
 ) -> Result<()> {
+    let prompt = &with_files(prompt, files)?;
+    let orchestration = match orchestration {
+        Some(mode) => mode.to_string(),
+        None => find_definition(agent_name)?
+            .and_then(|definition| definition.orchestration)
+            .unwrap_or_else(|| "single".to_string()),
+    };
+    let orchestration = orchestration.as_str();
+    if orchestration.eq_ignore_ascii_case("single") {
+        return run_single(agent_name, prompt, model, images, verbose);
+    }
+    if !images.is_empty() {
+        bail!("Images can only be sent in single orchestration");
+    }
     let model_name = model.unwrap_or("gemini-2.0-flash");
 
     println!("{}", "[*] Starting agent execution...".bold());
@@ -254,10 +392,6 @@ pub fn run_agent(
     // Show what would happen
     println!("{}", "[*] Execution Plan:".bold());
     match orchestration.to_lowercase().as_str() {
-        "single" => {
-            println!("  1. {} agent receives prompt", agent_name);
-            println!("  2. Agent processes and responds");
-        }
         "sequential" => {
             println!("  1. First agent processes prompt");
             println!("  2. Result passed to next agent");
@@ -284,14 +418,616 @@ pub fn run_agent(
     Ok(())
 }
 
-/// Create a new agent configuration
-pub fn create_agent(
-    name: &str,
-    role: &str,
-    instruction: Option<&str>,
+/// `prompt` followed by the contents of each of `files`
+fn with_files(prompt: &str, files: &[String]) -> Result<String> {
+    let mut prompt = prompt.to_string();
+    for file in files {
+        let text = std::fs::read_to_string(file)
+            .with_context(|| format!("Cannot read {} as text", file))?;
+        prompt.push_str(&format!("\n\n{}:\n```\n{}\n```", file, text.trim_end()));
+    }
+    Ok(prompt)
+}
+
+/// Run one agent, printing its reply while the model writes it
+fn run_single(
+    agent_name: &str,
+    prompt: &str,
+    model: Option<&str>,
+    images: &[String],
+    verbose: bool,
+) -> Result<()> {
+    let agent = load_agent(agent_name, model)?;
+    let attachments = images
+        .iter()
+        .map(|path| {
+            ImageContent::from_file(std::path::Path::new(path))
+                .map(ContentPart::Image)
+                .with_context(|| format!("Cannot attach {}", path))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    println!("{}", "[*] Starting agent execution...".bold());
+    println!();
+    println!("  {} {}", "Agent:".dimmed(), agent_name.green());
+    println!("  {} {}", "Model:".dimmed(), agent.model().model.yellow());
+    println!();
+
+    let mut printer = RunPrinter {
+        verbose,
+        ..Default::default()
+    };
+    let model = agent.model().clone();
+    let result = run_with_history(
+        agent,
+        Vec::new(),
+        prompt,
+        attachments,
+        &mut |event| printer.print(event),
+        &|| false,
+    );
+    printer.end_line();
+    let result = result?;
+
+    let usage = &result.token_usage;
+    let cost = budget::cost(&model, usage, &config::current().agency.budget.prices);
+    println!();
+    println!(
+        "{}",
+        completed_line(result.duration_ms, u64::from(usage.total_tokens), cost).dimmed()
+    );
+    if verbose {
+        print_spend(&[Spend {
+            agent: agent_name.to_string(),
+            model: model.model,
+            prompt_tokens: u64::from(usage.prompt_tokens),
+            completion_tokens: u64::from(usage.completion_tokens),
+            cost_usd: cost,
+            ..Default::default()
+        }]);
+    }
+    Ok(())
+}
+
+/// Continue the harvested session `session_id`, from any provider, with
+/// `prompt`. The agent sees the session as its conversation so far, and the
+/// whole conversation is saved as a new `agency` session whose earlier turns
+/// point back to the original, unless `save` is false.
+pub fn resume_session(
+    session_id: &str,
+    prompt: &str,
+    agent_name: &str,
+    model: Option<&str>,
+    save: bool,
+    verbose: bool,
+) -> Result<()> {
+    let db_path = get_db_path(None)?;
+    if !db_path.exists() {
+        bail!(
+            "No harvest database at {}; run csm harvest run first",
+            db_path.display()
+        );
+    }
+    let conn = rusqlite::Connection::open(&db_path)?;
+    let (id, provider, original) = find_harvested(&conn, session_id)?;
+    let title = original.title();
+    let generic = GenericSession::from(original.clone());
+    let history: Vec<AgencyMessage> = generic
+        .messages
+        .iter()
+        .filter_map(|message| agency_message(message, agent_name))
+        .collect();
+    if history.is_empty() {
+        bail!("Session {} has no messages to continue", id);
+    }
+
+    let agent = load_agent(agent_name, model)?;
+    println!("{}", "[*] Resuming session...".bold());
+    println!();
+    println!(
+        "  {} {} ({}, {} messages)",
+        "Session:".dimmed(),
+        title.green(),
+        provider,
+        history.len()
+    );
+    println!("  {} {}", "Agent:".dimmed(), agent_name.green());
+    println!("  {} {}", "Model:".dimmed(), agent.model().model.yellow());
+    println!();
+
+    let mut printer = RunPrinter {
+        verbose,
+        ..Default::default()
+    };
+    let model_config = agent.model().clone();
+    let result = run_with_history(
+        agent,
+        history,
+        prompt,
+        Vec::new(),
+        &mut |event| printer.print(event),
+        &|| false,
+    );
+    printer.end_line();
+    let result = result?;
+
+    let usage = &result.token_usage;
+    let cost = budget::cost(
+        &model_config,
+        usage,
+        &config::current().agency.budget.prices,
+    );
+    println!();
+    println!(
+        "{}",
+        completed_line(result.duration_ms, u64::from(usage.total_tokens), cost).dimmed()
+    );
+
+    if save {
+        let continued = continuation(&id, &provider, &original, &result, &model_config.model);
+        let new_id = continued.session_id.clone().unwrap_or_default();
+        insert_or_update_session(&conn, &continued, "agency", None, None)?;
+        println!(
+            "{}",
+            format!("[+] Saved as session {} (continues {})", new_id, id).dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// Continue the harvested session `session_id` with another provider's
+/// model, `target` being `provider:model` (e.g. `ollama:llama3`) or a model
+/// name. The oldest messages are folded into a summary until the rest fit the
+/// model's context window (`context_window`, or the router's profile of the
+/// model). The stitched conversation is saved as a new session, unless `save`
+/// is false, each turn naming the model, and for earlier turns the session,
+/// it came from.
+#[allow(clippy::too_many_arguments)]
+pub fn continue_session(
+    session_id: &str,
+    prompt: &str,
+    target: &str,
+    agent_name: &str,
+    context_window: Option<usize>,
+    save: bool,
+    verbose: bool,
+) -> Result<()> {
+    let db_path = get_db_path(None)?;
+    if !db_path.exists() {
+        bail!(
+            "No harvest database at {}; run csm harvest run first",
+            db_path.display()
+        );
+    }
+    let conn = rusqlite::Connection::open(&db_path)?;
+    let (id, provider, original) = find_harvested(&conn, session_id)?;
+
+    // provider:model, as ollama model names have colons of their own
+    let target = match target.split_once(':') {
+        Some((prefix, model)) if ModelProvider::from_name(prefix).is_some() => {
+            format!("{}/{}", prefix, model)
+        }
+        _ => target.to_string(),
+    };
+    let agent = load_agent(agent_name, Some(&target))?;
+    let model_config = agent.model().clone();
+    let to_provider = model_config.provider.to_string();
+
+    let mut context = ConversationContext::new(&original.title(), None);
+    for message in GenericSession::from(original.clone()).messages {
+        let role = match message.role.as_str() {
+            "user" => ContinuationRole::User,
+            "assistant" => ContinuationRole::Assistant,
+            _ => continue,
+        };
+        if message.content.trim().is_empty() {
+            continue;
+        }
+        let mut normalized = NormalizedMessage::new(
+            role,
+            &message.content,
+            &provider,
+            message.model.as_deref(),
+        );
+        if let Some(timestamp) = message
+            .timestamp
+            .and_then(chrono::DateTime::from_timestamp_millis)
+        {
+            normalized.timestamp = timestamp;
+        }
+        context.messages.push(normalized);
+    }
+    if context.messages.is_empty() {
+        bail!("Session {} has no messages to continue", id);
+    }
+    let total = context.messages.len();
+
+    // A quarter of the window is left for the reply
+    let window = context_window.unwrap_or_else(|| routing::context_window(&model_config.model));
+    let budget = (window - window / 4).saturating_sub(routing::estimate_tokens(prompt));
+    let folded = context.compress(budget);
+    context.switch_to(&to_provider, Some(&model_config.model), None);
+    let history: Vec<AgencyMessage> = context
+        .carried_messages()
+        .into_iter()
+        .filter_map(|message| {
+            let message = GenericMessage {
+                role: match message.role {
+                    ContinuationRole::Assistant => "assistant",
+                    _ => "user",
+                }
+                .to_string(),
+                content: message.content,
+                timestamp: Some(message.timestamp.timestamp_millis()),
+                model: message.source_model,
+            };
+            agency_message(&message, agent_name)
+        })
+        .collect();
+
+    println!("{}", "[*] Continuing session...".bold());
+    println!();
+    println!(
+        "  {} {} ({}, {} messages)",
+        "Session:".dimmed(),
+        context.title.green(),
+        provider,
+        total
+    );
+    println!(
+        "  {} {}/{}",
+        "With:".dimmed(),
+        to_provider,
+        model_config.model.yellow()
+    );
+    if folded > 0 {
+        println!(
+            "  {} {} oldest messages summarized to fit {} tokens",
+            "Context:".dimmed(),
+            folded,
+            window
+        );
+    }
+    println!();
+
+    let mut printer = RunPrinter {
+        verbose,
+        ..Default::default()
+    };
+    let result = run_with_history(
+        agent,
+        history,
+        prompt,
+        Vec::new(),
+        &mut |event| printer.print(event),
+        &|| false,
+    );
+    printer.end_line();
+    let result = result?;
+
+    let usage = &result.token_usage;
+    let cost = budget::cost(
+        &model_config,
+        usage,
+        &config::current().agency.budget.prices,
+    );
+    println!();
+    println!(
+        "{}",
+        completed_line(result.duration_ms, u64::from(usage.total_tokens), cost).dimmed()
+    );
+
+    if save {
+        let model = format!("{}/{}", to_provider, model_config.model);
+        let continued = continuation(&id, &provider, &original, &result, &model);
+        let new_id = continued.session_id.clone().unwrap_or_default();
+        insert_or_update_session(&conn, &continued, "agency", None, None)?;
+        println!(
+            "{}",
+            format!("[+] Saved as session {} (continues {} with {})", new_id, id, model).dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// The harvested session `id`, or else the only one whose ID starts with
+/// it, with its full ID and provider
+pub(crate) fn find_harvested(conn: &rusqlite::Connection, id: &str) -> Result<(String, String, ChatSession)> {
+    let mut stmt = conn.prepare(
+        "SELECT id, provider, session_json FROM sessions
+         WHERE id = ?1 OR id LIKE ?1 || '%'
+         ORDER BY id = ?1 DESC LIMIT 2",
+    )?;
+    let rows = stmt
+        .query_map([id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let (found, provider, json) = match rows.as_slice() {
+        [] => bail!("No harvested session with ID {}", id),
+        [only] => only.clone(),
+        [exact, _] if exact.0 == id => exact.clone(),
+        _ => bail!(
+            "More than one session ID starts with {}; give more of it",
+            id
+        ),
+    };
+    let session = serde_json::from_str(&json)
+        .with_context(|| format!("Session {} could not be read", found))?;
+    Ok((found, provider, session))
+}
+
+/// A harvested user or assistant message as a turn of the agent's
+/// conversation
+fn agency_message(message: &GenericMessage, agent_name: &str) -> Option<AgencyMessage> {
+    let role = match message.role.as_str() {
+        "user" => MessageRole::User,
+        "assistant" => MessageRole::Assistant,
+        _ => return None,
+    };
+    if message.content.trim().is_empty() {
+        return None;
+    }
+    Some(AgencyMessage {
+        id: crate::agency::session::generate_message_id(),
+        role,
+        content: message.content.clone(),
+        attachments: Vec::new(),
+        tool_calls: Vec::new(),
+        tool_result: None,
+        timestamp: message
+            .timestamp
+            .and_then(chrono::DateTime::from_timestamp_millis)
+            .unwrap_or_else(chrono::Utc::now),
+        tokens: None,
+        agent_name: Some(agent_name.to_string()),
+        metadata: Default::default(),
+    })
+}
+
+/// The original session followed by the agent's turn, as a new session.
+/// Requests copied from the original name it as their source.
+fn continuation(
+    id: &str,
+    provider: &str,
+    original: &ChatSession,
+    result: &ExecutionResult,
+    model: &str,
+) -> ChatSession {
+    let mut messages = GenericSession::from(original.clone()).messages;
+    let copied = ChatSession::from(GenericSession {
+        id: id.to_string(),
+        title: None,
+        messages: messages.clone(),
+        created_at: None,
+        updated_at: None,
+        provider: None,
+        model: None,
+    })
+    .requests
+    .len();
+    messages.extend(transcript(result).into_iter().map(|mut message| {
+        if message.role == "assistant" && message.model.is_none() {
+            message.model = Some(model.to_string());
+        }
+        message
+    }));
+    let mut session: ChatSession = GenericSession {
+        id: format!("agency-{}", uuid::Uuid::new_v4()),
+        title: Some(format!("{} (continued)", original.title())),
+        created_at: messages.first().and_then(|m| m.timestamp),
+        updated_at: messages.last().and_then(|m| m.timestamp),
+        messages,
+        provider: Some("agency".to_string()),
+        model: Some(model.to_string()),
+    }
+    .into();
+    let source = format!("{}:{}", provider, id);
+    for request in session.requests.iter_mut().take(copied) {
+        request.source_session = Some(source.clone());
+    }
+    session
+}
+
+/// Answer `question` from the harvested sessions most like it, citing them.
+/// New and changed sessions are embedded first when `index` is set.
+pub fn ask(
+    question: &str,
+    agent_name: &str,
     model: Option<&str>,
+    limit: usize,
+    index: bool,
+    verbose: bool,
 ) -> Result<()> {
-    let role_enum = match role.to_lowercase().as_str() {
+    let db_path = get_db_path(None)?;
+    if !db_path.exists() {
+        bail!(
+            "No harvest database at {}; run csm harvest run first",
+            db_path.display()
+        );
+    }
+    let embedder = HttpEmbedder::from_env().context(
+        "Asking needs an embeddings endpoint: set CSM_EMBEDDING_URL, OPENAI_API_KEY, or OLLAMA_HOST",
+    )?;
+    let mut sessions = SessionIndex::open(&db_path)?;
+
+    if index {
+        let stats = sessions.update(&embedder, &mut |title| {
+            if verbose {
+                println!("  {} {}", "Indexing".dimmed(), title);
+            }
+        })?;
+        if stats.indexed > 0 || stats.removed > 0 {
+            println!(
+                "{} Indexed {} sessions ({} chunks), dropped {}, {} unchanged",
+                "[*]".blue(),
+                stats.indexed,
+                stats.chunks,
+                stats.removed,
+                stats.unchanged
+            );
+        }
+    }
+
+    let sources = sessions.retrieve(question, &embedder, limit.max(1))?;
+    if sources.is_empty() {
+        bail!("No indexed sessions to answer from; run csm harvest run, then ask again");
+    }
+
+    let agent = load_agent(agent_name, model)?;
+    if verbose {
+        println!(
+            "  {} {} ({})",
+            "Agent:".dimmed(),
+            agent_name.green(),
+            agent.model().model.yellow()
+        );
+    }
+    println!();
+    let mut printer = RunPrinter {
+        verbose,
+        ..Default::default()
+    };
+    run_agent_with_events(
+        agent,
+        &answer_prompt(question, &sources),
+        &mut |event| printer.print(event),
+        &|| false,
+    )?;
+    printer.end_line();
+
+    println!();
+    println!("{}", "[*] Sources:".bold());
+    for (i, source) in sources.iter().enumerate() {
+        println!(
+            "  [{}] {} {}",
+            i + 1,
+            source.title.green(),
+            format!(
+                "({}, session {}, message {}, {})",
+                source.provider, source.session_id, source.message_index, source.role
+            )
+            .dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// Prints the events of a run as they arrive
+#[derive(Default)]
+struct RunPrinter {
+    verbose: bool,
+    /// Whether text has been printed since the last newline
+    mid_line: bool,
+    /// Tool calls already shown while the model wrote them
+    streamed_calls: usize,
+    /// Name each agent as it starts
+    show_agents: bool,
+    /// Whether any reply text has been printed
+    streamed: bool,
+}
+
+impl RunPrinter {
+    fn print(&mut self, event: &AgencyEvent) {
+        let data = &event.data;
+        match event.event_type {
+            EventType::MessageDelta => {
+                if let Some(text) = data["content"].as_str() {
+                    print!("{}", text);
+                    self.mid_line = !text.ends_with('\n');
+                    self.streamed = true;
+                }
+                // Tool calls show up as the model writes their arguments
+                let call = &data["tool_call"];
+                if let Some(name) = call["name"].as_str() {
+                    self.end_line();
+                    print!("{} {} ", "[>]".cyan(), name.bold());
+                    self.streamed_calls += 1;
+                    self.mid_line = true;
+                }
+                if let Some(arguments) = call["arguments"].as_str() {
+                    print!("{}", arguments.dimmed());
+                    self.mid_line = true;
+                }
+                let _ = std::io::stdout().flush();
+            }
+            EventType::ToolCallStarted => {
+                if self.streamed_calls > 0 {
+                    self.streamed_calls -= 1;
+                } else {
+                    self.end_line();
+                    println!(
+                        "{} {} {}",
+                        "[>]".cyan(),
+                        data["tool"].as_str().unwrap_or_default().bold(),
+                        data["arguments"].to_string().dimmed()
+                    );
+                }
+                self.end_line();
+            }
+            EventType::ToolCallCompleted => {
+                self.end_line();
+                let mark = if data["success"].as_bool() == Some(true) {
+                    "[+]".green()
+                } else {
+                    "[x]".red()
+                };
+                let content = data["content"].as_str().unwrap_or_default();
+                if self.verbose {
+                    println!("  {} {}", mark, content);
+                } else {
+                    let first = content.lines().next().unwrap_or_default();
+                    println!("  {} {}", mark, truncate(first, 100).dimmed());
+                }
+            }
+            EventType::AgentStarted if self.show_agents => {
+                self.end_line();
+                println!();
+                println!("{} {}", "[*]".cyan(), event.agent_name.bold());
+            }
+            EventType::ProviderFallback if data["denied"] == true => {
+                self.end_line();
+                println!(
+                    "{} {} is not allowed here, using {}: {}",
+                    "[!]".yellow(),
+                    data["from"].as_str().unwrap_or_default(),
+                    data["to"].as_str().unwrap_or_default().bold(),
+                    truncate(data["error"].as_str().unwrap_or_default(), 100).dimmed()
+                );
+            }
+            EventType::ProviderFallback => {
+                self.end_line();
+                println!(
+                    "{} {} failed, continuing with {}: {}",
+                    "[!]".yellow(),
+                    data["from"].as_str().unwrap_or_default(),
+                    data["to"].as_str().unwrap_or_default().bold(),
+                    truncate(data["error"].as_str().unwrap_or_default(), 100).dimmed()
+                );
+            }
+            EventType::AgentThinking if self.verbose => {
+                self.end_line();
+                println!("{}", "[...] Thinking".dimmed());
+            }
+            _ => {}
+        }
+    }
+
+    fn end_line(&mut self) {
+        if self.mid_line {
+            println!();
+            self.mid_line = false;
+        }
+    }
+}
+
+/// The role named `role`, or `Custom` for names it does not know
+pub(crate) fn parse_role(role: &str) -> AgentRole {
+    match role.to_lowercase().as_str() {
         "coordinator" => AgentRole::Coordinator,
         "researcher" => AgentRole::Researcher,
         "coder" => AgentRole::Coder,
@@ -304,9 +1040,12 @@ pub fn create_agent(
         "business" => AgentRole::Business,
         "tester" => AgentRole::Tester,
         _ => AgentRole::Custom,
-    };
+    }
+}
 
-    let default_instruction = match role_enum {
+/// The instruction an agent of `role` gets when it does not set one
+pub(crate) fn role_instruction(role: AgentRole) -> &'static str {
+    match role {
         AgentRole::Coordinator => "You are a coordinator agent that manages and delegates tasks.",
         AgentRole::Researcher => "You are a research specialist that gathers and analyzes information.",
         AgentRole::Coder => "You are a coding specialist that writes and modifies code.",
@@ -319,20 +1058,53 @@ pub fn create_agent(
         AgentRole::Business => "You are a proactive Business Agent that monitors and solves work problems with user permission. Optimize calendars, triage emails, prepare for meetings, track deadlines, and coordinate projects.",
         AgentRole::Tester => "You are a testing specialist that creates and runs tests.",
         AgentRole::Custom => "You are a helpful AI assistant.",
-    };
+    }
+}
 
-    let instruction = instruction.unwrap_or(default_instruction);
-    let model = model.unwrap_or("gemini-2.0-flash");
+/// Create an agent and save it as a file in the agents folder
+pub fn create_agent(
+    name: &str,
+    role: &str,
+    instruction: Option<&str>,
+    model: Option<&str>,
+    tools: &[String],
+    orchestration: Option<&str>,
+    force: bool,
+) -> Result<()> {
+    validate_item_name(name)?;
+    let role_enum = parse_role(role);
+    let definition = AgentDefinition {
+        role: Some(role.to_lowercase()),
+        instruction: instruction.map(String::from),
+        model: model.map(|m| Value::String(m.to_string())),
+        tools: tools.to_vec(),
+        orchestration: orchestration.map(String::from),
+        ..Default::default()
+    };
+    definition.builtin_tools()?;
+    let path = definition.save(&definition::agents_dir()?, name, force)?;
 
-    println!("{}", "[+] Agent Configuration Created:".bold().green());
+    println!("{}", "[+] Agent Created:".bold().green());
     println!();
     println!("  {} {}", "Name:".dimmed(), name.cyan().bold());
     println!("  {} {:?}", "Role:".dimmed(), role_enum);
-    println!("  {} {}", "Model:".dimmed(), model.yellow());
-    println!("  {} {}", "Instruction:".dimmed(), instruction);
+    println!(
+        "  {} {}",
+        "Model:".dimmed(),
+        model.unwrap_or(DEFAULT_MODEL).yellow()
+    );
+    println!(
+        "  {} {}",
+        "Instruction:".dimmed(),
+        instruction.unwrap_or(role_instruction(role_enum))
+    );
+    if !tools.is_empty() {
+        println!("  {} {}", "Tools:".dimmed(), tools.join(", "));
+    }
+    println!("  {} {}", "File:".dimmed(), path.display());
     println!();
     println!("{}", "[*] To use this agent:".dimmed());
-    println!("   csm Agency run --agent {} \"Your prompt here\"", name);
+    println!("   csm agency run --agent {} \"Your prompt here\"", name);
 
     Ok(())
 }
@@ -400,3 +1172,544 @@ pub fn list_templates() -> Result<()> {
 
     Ok(())
 }
+
+/// Agents every install has: name, role, and temperature
+const DEFAULT_AGENTS: &[(&str, AgentRole, f32)] = &[
+    ("assistant", AgentRole::Assistant, 0.7),
+    ("researcher", AgentRole::Researcher, 0.5),
+    ("coder", AgentRole::Coder, 0.3),
+    ("reviewer", AgentRole::Reviewer, 0.2),
+];
+
+const DEFAULT_MODEL: &str = "gemini-2.0-flash";
+
+/// The definition of the agent called `name` from the agent files, else
+/// the library, else the default agents
+fn find_definition(name: &str) -> Result<Option<AgentDefinition>> {
+    if let Some(file) = definition::find(&definition::agents_dir()?, name)? {
+        return Ok(Some(file.definition));
+    }
+    if let Some(item) = Library::open()?.get(PackItemKind::Agent, name)? {
+        let definition = serde_json::from_value::<AgentDefinition>(item.data)
+            .with_context(|| format!("Invalid agent definition for '{}'", name))?;
+        return Ok(Some(definition));
+    }
+    Ok(DEFAULT_AGENTS
+        .iter()
+        .find(|(n, _, _)| *n == name)
+        .map(|(role, _, temperature)| AgentDefinition {
+            role: Some(role.to_string()),
+            temperature: Some(*temperature),
+            ..Default::default()
+        }))
+}
+
+/// Load the agent called `name` from the agent files, the library, or the
+/// default agents. `model` replaces the agent's model.
+pub(crate) fn load_agent(name: &str, model: Option<&str>) -> Result<Agent> {
+    let Some(definition) = find_definition(name)? else {
+        let agents_dir = definition::agents_dir()?;
+        let mut names: Vec<String> = definition::load_dir(&agents_dir)?
+            .into_iter()
+            .map(|agent| agent.name)
+            .collect();
+        names.extend(
+            Library::open()?
+                .items(PackItemKind::Agent)?
+                .into_iter()
+                .map(|item| item.name),
+        );
+        names.extend(DEFAULT_AGENTS.iter().map(|(n, _, _)| n.to_string()));
+        bail!(
+            "No agent named '{}'; available agents: {}. Define one in {}",
+            name,
+            names.join(", "),
+            agents_dir.join(format!("{}.toml", name)).display()
+        );
+    };
+    let tools = definition.builtin_tools()?;
+
+    let role = definition
+        .role
+        .as_deref()
+        .map(parse_role)
+        .unwrap_or_default();
+    let instruction = definition
+        .instruction
+        .unwrap_or_else(|| role_instruction(role).to_string());
+    let mut builder = AgentBuilder::new(name)
+        .description(definition.description)
+        .role(role)
+        .instruction(instruction);
+    builder = match (model, definition.model) {
+        (Some(model), _) => builder.model(model),
+        (None, Some(Value::String(model))) => builder.model(model),
+        (None, Some(config)) => builder.model_config(
+            serde_json::from_value::<ModelConfig>(config)
+                .with_context(|| format!("Invalid model for agent '{}'", name))?,
+        ),
+        (None, None) => builder.model(DEFAULT_MODEL),
+    };
+    if let Some(temperature) = definition.temperature {
+        builder = builder.temperature(temperature);
+    }
+    builder = builder.sandbox(
+        definition
+            .sandbox
+            .unwrap_or_else(|| config::current().agency.sandbox.clone()),
+    );
+    for server in definition.mcp_servers {
+        builder = builder.mcp_server(server);
+    }
+    let search = definition.session_search.unwrap_or(true)
+        && !tools.iter().any(|tool| tool.name == "session_search");
+    for tool in tools {
+        builder = builder.tool(tool);
+    }
+    if search {
+        builder = builder.tool(BuiltinTools::session_search());
+    }
+
+    let mut agent = builder.build();
+    resolve_api_key(&mut agent.config.model);
+    agent.config.fallback_models = fallback_models(&agent.config.model, definition.fallback)?;
+    Ok(agent)
+}
+
+/// Keys come from the definition, a `$VAR` it names, or the provider's
+/// usual environment variable
+fn resolve_api_key(model: &mut ModelConfig) {
+    model.api_key = match model.api_key.take() {
+        Some(key) if key.starts_with('$') => config::expand_env(&key),
+        Some(key) => Some(key),
+        None => api_key_var(model.provider)
+            .and_then(|var| std::env::var(var).ok())
+            .filter(|key| !key.is_empty()),
+    };
+}
+
+/// The models an agent falls back to: those its definition names, or the
+/// `[agency.fallback]` chain. A bare provider keeps the agent's model name;
+/// sampling settings are the agent's.
+fn fallback_models(primary: &ModelConfig, chain: Vec<String>) -> Result<Vec<ModelConfig>> {
+    let settings = &config::current().agency.fallback;
+    let chain = if chain.is_empty() {
+        settings.chain.clone()
+    } else {
+        chain
+    };
+    let policy = FallbackPolicy::from_config(&config::FallbackConfig {
+        chain,
+        ..settings.clone()
+    })?;
+    let mut models = Vec::new();
+    for step in policy.chain {
+        let Some(provider) = ModelProvider::from_name(&step.provider) else {
+            bail!(
+                "Unknown provider '{}' in fallback chain; use a provider such as ollama or openai",
+                step.provider
+            );
+        };
+        let mut model = ModelConfig {
+            provider,
+            model: step.model.unwrap_or_else(|| primary.model.clone()),
+            temperature: primary.temperature,
+            max_tokens: primary.max_tokens,
+            ..Default::default()
+        };
+        if model.provider == primary.provider && model.model == primary.model {
+            continue;
+        }
+        resolve_api_key(&mut model);
+        models.push(model);
+    }
+    Ok(models)
+}
+
+/// The environment variable holding a provider's API key
+fn api_key_var(provider: ModelProvider) -> Option<&'static str> {
+    Some(match provider {
+        ModelProvider::Google => "GOOGLE_API_KEY",
+        ModelProvider::OpenAI => "OPENAI_API_KEY",
+        ModelProvider::Anthropic => "ANTHROPIC_API_KEY",
+        ModelProvider::Groq => "GROQ_API_KEY",
+        ModelProvider::Together => "TOGETHER_API_KEY",
+        ModelProvider::Fireworks => "FIREWORKS_API_KEY",
+        ModelProvider::DeepSeek => "DEEPSEEK_API_KEY",
+        ModelProvider::Mistral => "MISTRAL_API_KEY",
+        ModelProvider::Cohere => "COHERE_API_KEY",
+        ModelProvider::Perplexity => "PERPLEXITY_API_KEY",
+        _ => return None,
+    })
+}
+
+/// Run `agent` on `prompt`, calling `on_event` as it works. The run stops
+/// with an error once `cancelled` returns true.
+pub(crate) fn run_agent_with_events(
+    agent: Agent,
+    prompt: &str,
+    on_event: &mut dyn FnMut(&AgencyEvent),
+    cancelled: &dyn Fn() -> bool,
+) -> Result<ExecutionResult> {
+    run_with_history(agent, Vec::new(), prompt, Vec::new(), on_event, cancelled)
+}
+
+/// Run `agent` on `prompt`, with `attachments` alongside it, as the next
+/// turn of a conversation that so far holds `history`
+fn run_with_history(
+    agent: Agent,
+    history: Vec<AgencyMessage>,
+    prompt: &str,
+    attachments: Vec<ContentPart>,
+    on_event: &mut dyn FnMut(&AgencyEvent),
+    cancelled: &dyn Fn() -> bool,
+) -> Result<ExecutionResult> {
+    let name = agent.name().to_string();
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    rt.block_on(async {
+        let mut agents = [agent];
+        let registry = mount_tools(&mut agents).await?;
+        let [agent] = agents;
+
+        // Sessions are not kept between runs
+        let mut runtime = Runtime::in_memory()?.with_tool_registry(registry);
+        runtime.register_agent(agent);
+        let (tx, events) = mpsc::channel(100);
+        let budget = open_budget()?;
+        let mut session = runtime.sessions().create(&name, None)?;
+        for message in history {
+            session.add_message(message);
+        }
+        runtime.sessions().save(&session)?;
+        let options = RunOptions {
+            event_sender: Some(tx),
+            budget: Some(budget.clone()),
+            attachments,
+            ..RunOptions::new()
+        }
+        .with_session(session.id);
+        let run = runtime.run(&name, prompt, Some(options));
+        let result = drive(run, events, on_event, cancelled).await;
+        save_spend(&budget);
+        result
+    })
+}
+
+/// A budget for a new run: the `[agency.budget]` limits, less what the day's
+/// runs already spent
+fn open_budget() -> Result<Arc<Budget>> {
+    let limits = config::current().agency.budget.clone();
+    let db_path = get_db_path(None)?;
+    let budget = if db_path.exists() {
+        Budget::load(&rusqlite::Connection::open(&db_path)?, limits)?
+    } else {
+        Budget::new(limits)
+    };
+    Ok(Arc::new(budget))
+}
+
+/// Record what a run spent in the harvest database, where the day's later
+/// runs and `csm report spend` find it
+fn save_spend(budget: &Budget) {
+    if budget.spent().is_empty() {
+        return;
+    }
+    let saved = get_db_path(None).and_then(|path| {
+        let conn = rusqlite::Connection::open(path)?;
+        budget.save(&conn, &uuid::Uuid::new_v4().to_string())?;
+        Ok(())
+    });
+    if let Err(e) = saved {
+        eprintln!("{} Token usage not recorded: {:#}", "[!]".yellow(), e);
+    }
+}
+
+/// The line closing a run: its time, tokens, and cost when known
+fn completed_line(duration_ms: u64, tokens: u64, cost: Option<f64>) -> String {
+    let mut line = format!(
+        "[+] Completed in {:.1}s, {} tokens",
+        duration_ms as f64 / 1000.0,
+        tokens
+    );
+    if let Some(cost) = cost {
+        line.push_str(&format!(", ${:.4}", cost));
+    }
+    line
+}
+
+/// Tokens and cost per agent and model
+fn print_spend(spent: &[Spend]) {
+    for spend in spent {
+        let cost = match spend.cost_usd {
+            Some(cost) => format!("${:.4}", cost),
+            None => "price unknown".to_string(),
+        };
+        println!(
+            "  {} {} {}",
+            format!("{} ({}):", spend.agent, spend.model).dimmed(),
+            format!(
+                "{} prompt + {} completion tokens,",
+                spend.prompt_tokens, spend.completion_tokens
+            )
+            .dimmed(),
+            cost.dimmed()
+        );
+    }
+}
+
+/// A registry with the builtins and the tools of the MCP servers `agents`
+/// use, each of which gets the tools of its own servers
+async fn mount_tools(agents: &mut [Agent]) -> Result<ToolRegistry> {
+    let mut servers: Vec<String> = Vec::new();
+    for agent in agents.iter() {
+        for server in &agent.config.mcp_servers {
+            if !servers.contains(server) {
+                servers.push(server.clone());
+            }
+        }
+    }
+
+    let mut registry = ToolRegistry::with_builtins();
+    let tools = mount_servers(&servers, &mut registry).await?;
+    for agent in agents {
+        let own = tools.iter().filter(|tool| {
+            tool.metadata
+                .get("mcp_server")
+                .and_then(Value::as_str)
+                .is_some_and(|server| agent.config.mcp_servers.iter().any(|s| s == server))
+        });
+        agent.registered_tools.extend(own.cloned().map(Arc::new));
+    }
+    Ok(registry)
+}
+
+/// Wait for `run`, passing on its `events`, until it ends or is cancelled
+async fn drive<T>(
+    run: impl std::future::Future<Output = crate::agency::error::AgencyResult<T>>,
+    mut events: mpsc::Receiver<AgencyEvent>,
+    on_event: &mut dyn FnMut(&AgencyEvent),
+    cancelled: &dyn Fn() -> bool,
+) -> Result<T> {
+    tokio::pin!(run);
+    let mut poll = tokio::time::interval(Duration::from_millis(250));
+
+    let result = loop {
+        tokio::select! {
+            result = &mut run => break result,
+            Some(event) = events.recv() => on_event(&event),
+            _ = poll.tick() => {
+                if cancelled() {
+                    bail!("Agent run cancelled");
+                }
+            }
+        }
+    };
+    while let Ok(event) = events.try_recv() {
+        on_event(&event);
+    }
+    Ok(result?)
+}
+
+/// Run the pipeline defined in the YAML file `path`, or the swarm when
+/// `swarm` is set, and store each agent's transcript as a harvested session
+/// unless `save` is false
+pub fn run_pipeline_file(
+    path: &str,
+    prompt: Option<&str>,
+    model: Option<&str>,
+    swarm: bool,
+    save: bool,
+    verbose: bool,
+) -> Result<()> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("Cannot read {}", path))?;
+    let definition = PipelineDefinition::from_yaml(&text)
+        .with_context(|| format!("Invalid pipeline definition in {}", path))?;
+    let Some(prompt) = prompt.or(definition.input.as_deref()) else {
+        bail!("Give a prompt, or set input in {}", path);
+    };
+    let agents = definition
+        .members(swarm)
+        .into_iter()
+        .map(|member| load_agent(member.name(), member.model().or(model)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mode = if swarm {
+        "swarm".to_string()
+    } else {
+        serde_json::to_value(definition.mode)?
+            .as_str()
+            .unwrap_or_default()
+            .to_string()
+    };
+    println!("{}", "[*] Starting pipeline execution...".bold());
+    println!();
+    println!("  {} {}", "Pipeline:".dimmed(), definition.name.green());
+    println!("  {} {}", "Mode:".dimmed(), mode.cyan());
+    let names: Vec<&str> = agents.iter().map(|a| a.name()).collect();
+    println!("  {} {}", "Agents:".dimmed(), names.join(", "));
+
+    let mut printer = RunPrinter {
+        verbose,
+        show_agents: true,
+        ..Default::default()
+    };
+    let budget = open_budget()?;
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let result = rt.block_on(async {
+        let mut agents = agents;
+        let registry = mount_tools(&mut agents).await?;
+        let runtime = Runtime::in_memory()?.with_tool_registry(registry);
+        let (tx, events) = mpsc::channel(100);
+        let options = RunOptions {
+            event_sender: Some(tx),
+            budget: Some(budget.clone()),
+            ..RunOptions::new()
+        };
+        let on_event = &mut |event: &AgencyEvent| printer.print(event);
+        let result = if swarm {
+            let swarm = definition.swarm(agents)?;
+            let run = runtime.run_swarm(&swarm, prompt, Some(options));
+            drive(run, events, on_event, &|| false).await
+        } else {
+            let pipeline = definition.pipeline(agents)?;
+            let run = runtime.run_pipeline(&pipeline, prompt, Some(options));
+            drive(run, events, on_event, &|| false).await
+        };
+        save_spend(&budget);
+        result
+    });
+    printer.end_line();
+    let result = result?;
+
+    // Parallel agents run without streaming, so show what they found
+    if !printer.streamed {
+        println!("{}", result.response);
+    }
+    println!();
+    let spent = budget.spent();
+    let (tokens, cost) = budget.total();
+    let priced = spent.iter().all(|s| s.cost_usd.is_some());
+    println!(
+        "{}",
+        completed_line(result.duration_ms, tokens, Some(cost).filter(|_| priced)).dimmed()
+    );
+    if verbose {
+        print_spend(&spent);
+    }
+
+    if save {
+        match save_transcripts(&definition.name, &result.agent_results) {
+            Ok(ids) => println!(
+                "{}",
+                format!("[+] Saved {} agent transcripts as sessions", ids.len()).dimmed()
+            ),
+            Err(e) => println!("{} Transcripts not saved: {:#}", "[!]".yellow(), e),
+        }
+    }
+    Ok(())
+}
+
+/// Store each agent's part of a pipeline run as a session in the harvest
+/// database, returning the session IDs
+pub(crate) fn save_transcripts(pipeline: &str, results: &[ExecutionResult]) -> Result<Vec<String>> {
+    let db_path = get_db_path(None)?;
+    if !db_path.exists() {
+        bail!(
+            "no harvest database at {}; run csm harvest init",
+            db_path.display()
+        );
+    }
+    let conn = rusqlite::Connection::open(&db_path)?;
+
+    // An agent may run more than once, as a swarm's coordinator does
+    let mut transcripts: Vec<(String, Vec<GenericMessage>)> = Vec::new();
+    for result in results {
+        let Some(agent) = result.messages.iter().find_map(|m| m.agent_name.clone()) else {
+            continue;
+        };
+        let index = match transcripts.iter().position(|(name, _)| *name == agent) {
+            Some(index) => index,
+            None => {
+                transcripts.push((agent, Vec::new()));
+                transcripts.len() - 1
+            }
+        };
+        transcripts[index].1.extend(transcript(result));
+    }
+
+    let mut ids = Vec::new();
+    for (agent, messages) in transcripts {
+        let id = format!("agency-{}", uuid::Uuid::new_v4());
+        let session: ChatSession = GenericSession {
+            id: id.clone(),
+            title: Some(format!("{}: {}", pipeline, agent)),
+            created_at: messages.first().and_then(|m| m.timestamp),
+            updated_at: messages.last().and_then(|m| m.timestamp),
+            messages,
+            provider: Some("agency".to_string()),
+            model: None,
+        }
+        .into();
+        insert_or_update_session(&conn, &session, "agency", None, None)?;
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+/// An agent's messages as user and assistant turns, with its tool calls
+/// noted in its replies
+fn transcript(result: &ExecutionResult) -> Vec<GenericMessage> {
+    let mut messages: Vec<GenericMessage> = Vec::new();
+    for message in &result.messages {
+        let timestamp = Some(message.timestamp.timestamp_millis());
+        let mut text = message.content.clone();
+        for part in &message.attachments {
+            if let ContentPart::Image(image) = part {
+                text.push_str(&format!(
+                    "\n[image] {}",
+                    image.alt_text.as_deref().unwrap_or("image")
+                ));
+            }
+        }
+        for call in &message.tool_calls {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(&format!("[tool] {} {}", call.name, call.arguments));
+        }
+        let role = match message.role {
+            MessageRole::User => "user",
+            MessageRole::Assistant => "assistant",
+            _ => continue,
+        };
+        if text.is_empty() {
+            continue;
+        }
+        // Which provider wrote it, in case a fallback model did
+        let model = message
+            .metadata
+            .get(SERVED_BY)
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        // The model's steps toward one answer make a single reply
+        match messages.last_mut() {
+            Some(last) if role == "assistant" && last.role == "assistant" => {
+                last.content.push_str("\n\n");
+                last.content.push_str(&text);
+                last.timestamp = timestamp;
+                last.model = model.or(last.model.take());
+            }
+            _ => messages.push(GenericMessage {
+                role: role.to_string(),
+                content: text,
+                timestamp,
+                model,
+            }),
+        }
+    }
+    messages
+}
@@ -0,0 +1,535 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Automation workflow commands
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use colored::*;
+
+use crate::automation::history::{self, RunRetention};
+use crate::automation::schedule::ScheduleState;
+use crate::automation::templates::{self, TEMPLATES};
+use crate::automation::{self, AutomationEngine, RunStatus, Trigger, Workflow, WorkflowRun};
+use crate::datetime::format_datetime_short;
+use crate::pack::{Library, PackItem, PackItemKind};
+use crate::storage::watcher::{PathWatcher, SessionWatcher, DEFAULT_DEBOUNCE};
+
+/// Runs kept in the engine's history
+const MAX_HISTORY: usize = 100;
+
+/// The workflows in `library/workflows/`, named by file when they have no ID
+fn library_workflows() -> Result<Vec<Workflow>> {
+    let library = Library::open()?;
+    library
+        .items(PackItemKind::Workflow)?
+        .into_iter()
+        .map(|item| {
+            let mut workflow: Workflow = serde_json::from_value(item.data)
+                .with_context(|| format!("Invalid workflow '{}'", item.name))?;
+            if workflow.id.is_empty() {
+                workflow.id = item.name.clone();
+            }
+            if workflow.name.is_empty() {
+                workflow.name = workflow.id.clone();
+            }
+            Ok(workflow)
+        })
+        .collect()
+}
+
+fn describe_trigger(trigger: &Trigger) -> String {
+    match trigger {
+        Trigger::Interval { seconds } => format!("every {}s", seconds),
+        Trigger::TimeOfDay { time, days } => {
+            let mut text = format!("daily at {}", time.format("%H:%M"));
+            if let Some(days) = days {
+                let days: Vec<String> = days.iter().map(|d| d.to_string()).collect();
+                text.push_str(&format!(" ({})", days.join(", ")));
+            }
+            text
+        }
+        Trigger::FileChange { pattern, .. } => format!("on change to {}", pattern),
+        Trigger::Schedule { cron, timezone } => match timezone {
+            Some(tz) => format!("cron {} ({})", cron, tz),
+            None => format!("cron {}", cron),
+        },
+        Trigger::Event { event_type, .. } => format!("on event {}", event_type),
+        Trigger::Webhook { path, .. } => format!("webhook {}", path),
+        Trigger::Manual => "manual".to_string(),
+    }
+}
+
+/// Where `automation start` keeps next-run times: `schedule.json` in the library
+fn schedule_file() -> Result<std::path::PathBuf> {
+    Ok(Library::open()?.root().join("schedule.json"))
+}
+
+/// Next-run times saved by `automation start`, if any can be read
+fn saved_schedule() -> ScheduleState {
+    schedule_file()
+        .and_then(|path| ScheduleState::load(&path))
+        .unwrap_or_default()
+}
+
+/// List library workflows with their triggers and next scheduled run
+pub fn automation_list() -> Result<()> {
+    let workflows = library_workflows()?;
+    if workflows.is_empty() {
+        println!(
+            "{} No workflows. Add JSON files under {} in the library",
+            "[!]".yellow(),
+            "workflows/".cyan()
+        );
+        return Ok(());
+    }
+
+    let schedule = saved_schedule();
+    let now = Utc::now();
+    println!("{} Workflows:", "[*]".blue().bold());
+    for workflow in &workflows {
+        let status = if workflow.enabled {
+            "enabled".green()
+        } else {
+            "disabled".dimmed()
+        };
+        println!("  {} {}", workflow.id.cyan(), status);
+        if let Some(description) = &workflow.description {
+            println!("    {}", description.dimmed());
+        }
+        let triggers: Vec<String> = workflow.triggers.iter().map(describe_trigger).collect();
+        println!("    {} {}", "Triggers:".dimmed(), triggers.join(", "));
+        if let Some(next) = schedule
+            .next_run(workflow, now)
+            .filter(|_| workflow.enabled)
+        {
+            println!(
+                "    {} {}",
+                "Next run:".dimmed(),
+                format_datetime_short(next)
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The type of each of a workflow's actions, to label their results with
+fn action_names(workflow: &Workflow) -> Vec<String> {
+    workflow
+        .actions
+        .iter()
+        .map(|a| {
+            let action = serde_json::to_value(a).unwrap_or_default();
+            action["type"].as_str().unwrap_or("action").to_string()
+        })
+        .collect()
+}
+
+fn print_results(run: &WorkflowRun, actions: &[String]) {
+    for result in &run.results {
+        let action = actions
+            .get(result.action_index)
+            .map_or("action", String::as_str);
+        let took = format!("{:.1}s", result.duration_ms as f64 / 1000.0);
+        match &result.error {
+            None => println!("{} {} {}", "[+]".green(), action, took.dimmed()),
+            Some(e) => println!("{} {} failed: {}", "[x]".red(), action, e),
+        }
+        if let Some(data) = &result.data {
+            if let Some(response) = data["response"].as_str() {
+                println!("{}", response);
+            }
+            if let Some(session) = data["session_id"].as_str() {
+                println!("    {} {}", "Saved as session".dimmed(), session.cyan());
+            }
+            // Steps of a parallel block
+            for step in data["steps"].as_array().into_iter().flatten() {
+                let id = step["id"].as_str().unwrap_or("step");
+                let took = format!(
+                    "{:.1}s",
+                    step["duration_ms"].as_f64().unwrap_or(0.0) / 1000.0
+                );
+                match (step["status"].as_str(), step["error"].as_str()) {
+                    (Some("skipped"), _) => println!("    {} {} skipped", "[*]".dimmed(), id),
+                    (_, Some(e)) => println!("    {} {} failed: {}", "[!]".yellow(), id, e),
+                    _ => println!("    {} {} {}", "[+]".green(), id, took.dimmed()),
+                }
+            }
+        }
+    }
+}
+
+/// Run a library workflow now and report what each action did
+pub fn automation_run(name: &str, json: bool) -> Result<()> {
+    let workflow = library_workflows()?
+        .into_iter()
+        .find(|w| w.id == name)
+        .with_context(|| format!("No workflow named '{}' in the library", name))?;
+    let actions = action_names(&workflow);
+    let history_db = crate::commands::get_db_path(None)?;
+
+    let rt = tokio::runtime::Runtime::new()?;
+    let run = rt.block_on(async {
+        let engine = AutomationEngine::new(MAX_HISTORY)
+            .with_history_db(history_db, RunRetention::from_config());
+        engine.register(workflow).await?;
+        let run_id = engine.trigger(name, None).await?;
+        engine
+            .get_run(&run_id)
+            .await
+            .context("The run was not recorded")
+    })?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&run)?);
+    } else {
+        print_results(&run, &actions);
+    }
+
+    if run.status == RunStatus::Failed {
+        anyhow::bail!(
+            "Workflow '{}' failed: {}",
+            name,
+            run.error.unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+/// Run enabled library workflows on their schedules and on session file
+/// changes until interrupted
+pub fn automation_start() -> Result<()> {
+    let workflows: Vec<Workflow> = library_workflows()?
+        .into_iter()
+        .filter(|w| w.enabled)
+        .collect();
+    if workflows.is_empty() {
+        anyhow::bail!("No enabled workflows in the library");
+    }
+    let watch_sessions = workflows
+        .iter()
+        .flat_map(|w| &w.triggers)
+        .any(|t| matches!(t, Trigger::FileChange { .. }) && t.watched_path().is_none());
+    let (roots, missing): (Vec<_>, Vec<_>) = automation::watched_paths(&workflows)
+        .into_iter()
+        .partition(|(root, _)| root.exists());
+
+    let schedule_file = schedule_file()?;
+    let schedule = ScheduleState::load(&schedule_file).unwrap_or_else(|e| {
+        println!("{} {}; starting afresh", "[!]".yellow(), e);
+        ScheduleState::default()
+    });
+    let now = Utc::now();
+
+    println!(
+        "{} Running {} workflows",
+        "[*]".blue().bold(),
+        workflows.len()
+    );
+    for workflow in &workflows {
+        match schedule.next_run(workflow, now) {
+            Some(next) if next <= now => println!(
+                "  {} {} {}",
+                workflow.id.cyan(),
+                "missed run at".dimmed(),
+                format_datetime_short(next)
+            ),
+            Some(next) => println!(
+                "  {} {} {}",
+                workflow.id.cyan(),
+                "next run".dimmed(),
+                format_datetime_short(next)
+            ),
+            None => println!("  {}", workflow.id.cyan()),
+        }
+    }
+    for (root, _) in &missing {
+        println!(
+            "{} {} does not exist; changes there are not watched",
+            "[!]".yellow(),
+            root.display()
+        );
+    }
+    println!("{}", "Press Ctrl+C to stop".dimmed());
+
+    let history_db = crate::commands::get_db_path(None)?;
+    let rt = tokio::runtime::Runtime::new()?;
+    rt.block_on(async {
+        let engine = AutomationEngine::new(MAX_HISTORY)
+            .with_schedule_file(schedule_file)
+            .with_history_db(history_db, RunRetention::from_config());
+        // File triggers outside the session store, to filter watched paths by
+        let path_triggers: Vec<Trigger> = workflows
+            .iter()
+            .flat_map(|w| &w.triggers)
+            .filter(|t| t.watched_path().is_some())
+            .cloned()
+            .collect();
+        for workflow in workflows {
+            engine.register(workflow).await?;
+        }
+        let sessions = async {
+            if watch_sessions {
+                engine.run_file_triggers(SessionWatcher::new()?).await;
+            } else {
+                std::future::pending::<()>().await;
+            }
+            Ok::<_, anyhow::Error>(())
+        };
+        let paths = async {
+            if roots.is_empty() {
+                std::future::pending::<()>().await;
+            } else {
+                let watcher = PathWatcher::watch(
+                    &roots,
+                    move |path| path_triggers.iter().any(|t| t.matches_path(path)),
+                    DEFAULT_DEBOUNCE,
+                )?;
+                engine.run_path_triggers(watcher).await;
+            }
+            Ok::<_, anyhow::Error>(())
+        };
+        // Report each run as it finishes
+        let report = async {
+            let mut seen = std::collections::HashSet::new();
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                let runs = engine.get_runs(None, MAX_HISTORY).await;
+                for run in runs.iter().rev() {
+                    let Some(ended) = run.ended_at else { continue };
+                    if !seen.insert(run.id.clone()) {
+                        continue;
+                    }
+                    let when = format_datetime_short(ended);
+                    match &run.error {
+                        None => println!(
+                            "{} {} ran on {} at {}",
+                            "[+]".green(),
+                            run.workflow_id.cyan(),
+                            run.trigger,
+                            when
+                        ),
+                        Some(e) => println!(
+                            "{} {} failed at {}: {}",
+                            "[x]".red(),
+                            run.workflow_id.cyan(),
+                            when,
+                            e
+                        ),
+                    }
+                }
+            }
+        };
+        tokio::select! {
+            _ = engine.run_scheduled_triggers() => {}
+            _ = report => {}
+            result = sessions => result?,
+            result = paths => result?,
+            _ = tokio::signal::ctrl_c() => println!("\n{} Stopped", "[*]".blue()),
+        }
+        Ok(())
+    })
+}
+
+/// Show runs kept in the harvest database, or one run's action results
+pub fn automation_runs(
+    workflow: Option<&str>,
+    run_id: Option<&str>,
+    limit: usize,
+    json: bool,
+) -> Result<()> {
+    let conn = history::open_history(&crate::commands::get_db_path(None)?)?;
+
+    if let Some(run_id) = run_id {
+        let run = history::load_run(&conn, run_id)?
+            .with_context(|| format!("No workflow run '{}'", run_id))?;
+        if json {
+            println!("{}", serde_json::to_string_pretty(&run)?);
+            return Ok(());
+        }
+        let actions = history::load_workflow(&conn, &run.workflow_id)?
+            .map(|w| action_names(&w))
+            .unwrap_or_default();
+        println!(
+            "{} {} run {} ({}, {})",
+            "[*]".blue().bold(),
+            run.workflow_id.cyan(),
+            run.id,
+            run.trigger,
+            format_datetime_short(run.started_at)
+        );
+        print_results(&run, &actions);
+        if let Some(e) = &run.error {
+            println!("{} {}", "[x]".red(), e);
+        }
+        return Ok(());
+    }
+
+    let runs = history::load_runs(&conn, workflow, Some(limit))?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&runs)?);
+        return Ok(());
+    }
+    if runs.is_empty() {
+        match workflow {
+            Some(id) => println!("{} No runs of '{}' recorded", "[!]".yellow(), id),
+            None => println!("{} No workflow runs recorded", "[!]".yellow()),
+        }
+        return Ok(());
+    }
+
+    let width = runs.iter().map(|r| r.workflow_id.len()).max().unwrap_or(0);
+    println!("{} Workflow runs:", "[*]".blue().bold());
+    for run in &runs {
+        let status = match run.status {
+            RunStatus::Completed => "completed".green(),
+            RunStatus::Failed => "failed".red(),
+            RunStatus::Running => "running".yellow(),
+            RunStatus::Cancelled => "cancelled".dimmed(),
+        };
+        let took = run
+            .ended_at
+            .map(|end| {
+                format!(
+                    "{:.1}s",
+                    (end - run.started_at).num_milliseconds() as f64 / 1000.0
+                )
+            })
+            .unwrap_or_default();
+        println!(
+            "  {} {} {:<width$} {:<9} {} {}",
+            format_datetime_short(run.started_at),
+            run.id.chars().take(8).collect::<String>().dimmed(),
+            run.workflow_id.cyan(),
+            status,
+            run.trigger,
+            took.dimmed()
+        );
+        if let Some(e) = &run.error {
+            println!("    {}", e.red());
+        }
+    }
+    Ok(())
+}
+
+/// List the built-in workflow templates
+pub fn automation_templates() -> Result<()> {
+    println!("{} Workflow templates:", "[*]".blue().bold());
+    for template in TEMPLATES {
+        println!("  {}", template.id.cyan());
+        println!("    {}", template.description.dimmed());
+        for param in template.params {
+            let default = param
+                .default
+                .map_or_else(|| "required".to_string(), |d| format!("default {}", d));
+            println!(
+                "    {} {} ({})",
+                param.name.green(),
+                param.description,
+                default.dimmed()
+            );
+        }
+    }
+    println!(
+        "\n{} csm automation init --template <name> [--set name=value]",
+        "Use:".dimmed()
+    );
+    Ok(())
+}
+
+/// Add a workflow from a built-in template to the library, asking on the
+/// terminal for parameters not given with `--set`
+pub fn automation_init(
+    template: &str,
+    name: Option<&str>,
+    set: &[String],
+    yes: bool,
+    force: bool,
+) -> Result<()> {
+    use std::io::{IsTerminal, Write};
+
+    let template = templates::find(template).with_context(|| {
+        let ids: Vec<&str> = TEMPLATES.iter().map(|t| t.id).collect();
+        format!(
+            "No workflow template '{}' (available: {})",
+            template,
+            ids.join(", ")
+        )
+    })?;
+    let name = name.unwrap_or(template.id);
+    let library = Library::open()?;
+    if library.contains(PackItemKind::Workflow, name) && !force {
+        anyhow::bail!(
+            "Workflow '{}' already exists in the library; use --force to replace it",
+            name
+        );
+    }
+
+    let mut values = std::collections::HashMap::new();
+    for pair in set {
+        let (key, value) = pair
+            .split_once('=')
+            .with_context(|| format!("Expected NAME=VALUE, got '{}'", pair))?;
+        values.insert(key.trim().to_string(), value.to_string());
+    }
+
+    if !yes && std::io::stdin().is_terminal() {
+        for param in template.params {
+            if values.contains_key(param.name) {
+                continue;
+            }
+            match param.default {
+                Some(default) => print!("{} [{}]: ", param.description, default),
+                None => print!("{}: ", param.description),
+            }
+            std::io::stdout().flush()?;
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input)?;
+            let input = input.trim();
+            if !input.is_empty() {
+                values.insert(param.name.to_string(), input.to_string());
+            }
+        }
+    }
+
+    if let Some(param) = template
+        .params
+        .iter()
+        .find(|p| p.default.is_none() && !values.contains_key(p.name))
+    {
+        anyhow::bail!(
+            "Template '{}' needs a value for {}: --set {}=<{}>",
+            template.id,
+            param.name,
+            param.name,
+            param.description.to_lowercase()
+        );
+    }
+    let data = template.instantiate(&values)?;
+    let workflow: Workflow = serde_json::from_value(data.clone())?;
+    let path = library.save(&PackItem {
+        kind: PackItemKind::Workflow,
+        name: name.to_string(),
+        data,
+    })?;
+
+    println!(
+        "{} Added workflow {} from {}",
+        "[+]".green(),
+        name.cyan(),
+        template.id
+    );
+    println!("    {}", path.display().to_string().dimmed());
+    if let Some(next) = workflow.next_fire(Utc::now()) {
+        println!(
+            "    {} {}",
+            "First run:".dimmed(),
+            format_datetime_short(next)
+        );
+    }
+    println!(
+        "{} Run {} to put it on its schedule",
+        "[*]".blue(),
+        "csm automation start".cyan()
+    );
+    Ok(())
+}
@@ -0,0 +1,369 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Backup catalog commands
+
+use anyhow::Result;
+use chrono::Duration;
+use colored::*;
+use tabled::{settings::Style as TableStyle, Table, Tabled};
+
+use super::workspace_cmds::format_file_size;
+use crate::backup::archive::{self, BackupSources, EntryKind, RestoreTarget, Snapshot};
+use crate::backup::destination;
+use crate::backup::{create_archive, BackupEntry, BackupManager, RetentionPolicy};
+use crate::datetime::format_datetime_short;
+use crate::text::truncate_start;
+use crate::workspace::get_workspace_storage_path;
+
+#[derive(Tabled)]
+struct BackupRow {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Created")]
+    created: String,
+    #[tabled(rename = "Kind")]
+    kind: String,
+    #[tabled(rename = "Command")]
+    command: String,
+    #[tabled(rename = "Size")]
+    size: String,
+    #[tabled(rename = "Source")]
+    source: String,
+}
+
+impl From<&BackupEntry> for BackupRow {
+    fn from(entry: &BackupEntry) -> Self {
+        Self {
+            id: entry.id.clone(),
+            created: format_datetime_short(entry.created_at),
+            kind: entry.kind.to_string(),
+            command: entry.command.clone(),
+            size: if entry.exists() {
+                format_file_size(entry.size_bytes)
+            } else {
+                "missing".to_string()
+            },
+            source: truncate_start(&entry.source.display().to_string(), 60),
+        }
+    }
+}
+
+/// Open the catalog, adding backups made before it existed
+fn open_catalog() -> Result<BackupManager> {
+    let mut manager = BackupManager::open()?;
+    if let Ok(storage) = get_workspace_storage_path() {
+        let adopted = manager.adopt_untracked(&storage)?;
+        if adopted > 0 {
+            println!(
+                "{} Added {} existing backup(s) to the catalog",
+                "[+]".green(),
+                adopted
+            );
+        }
+    }
+    Ok(manager)
+}
+
+/// List recorded backups, newest first
+pub fn backup_list(source: Option<&str>, json: bool) -> Result<()> {
+    let manager = open_catalog()?;
+    let mut entries: Vec<&BackupEntry> = manager
+        .entries()
+        .iter()
+        .filter(|e| source.is_none_or(|s| e.source.to_string_lossy().contains(s)))
+        .collect();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.created_at));
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!("No backups recorded.");
+        return Ok(());
+    }
+
+    let rows: Vec<BackupRow> = entries.iter().map(|e| BackupRow::from(*e)).collect();
+    let table = Table::new(&rows)
+        .with(TableStyle::ascii_rounded())
+        .to_string();
+    println!("{}", table);
+
+    let total: u64 = entries
+        .iter()
+        .filter(|e| e.exists())
+        .map(|e| e.size_bytes)
+        .sum();
+    println!(
+        "\nTotal backups: {} ({})",
+        entries.len(),
+        format_file_size(total)
+    );
+    println!(
+        "Catalog: {}",
+        manager.catalog_path().display().to_string().dimmed()
+    );
+    Ok(())
+}
+
+/// Delete backups outside the retention policy
+pub fn backup_prune(keep: Option<usize>, older_than: Option<u64>, dry_run: bool) -> Result<()> {
+    let mut manager = open_catalog()?;
+
+    let mut policy = RetentionPolicy::from_config();
+    if let Some(keep) = keep {
+        policy.keep = keep;
+    }
+    if let Some(days) = older_than {
+        policy.max_age = Some(Duration::days(days as i64));
+    }
+
+    let report = manager.prune(&policy, dry_run)?;
+
+    println!(
+        "{} Retention: keep {} per source{}",
+        "[*]".blue(),
+        policy.keep,
+        policy
+            .max_age
+            .map(|age| format!(", at most {} days old", age.num_days()))
+            .unwrap_or_default()
+    );
+
+    if report.removed.is_empty() && report.missing.is_empty() {
+        println!("{} Nothing to prune", "[OK]".green().bold());
+        return Ok(());
+    }
+
+    for entry in &report.removed {
+        println!(
+            "   {} {} {} {}",
+            if dry_run { "[would remove]" } else { "[-]" }.red(),
+            format_datetime_short(entry.created_at),
+            entry.kind,
+            entry.path.display()
+        );
+    }
+
+    if dry_run {
+        println!(
+            "\n{} Would free {} from {} backup(s). Run without --dry-run to delete.",
+            "[!]".yellow(),
+            format_file_size(report.freed_bytes),
+            report.removed.len()
+        );
+    } else {
+        println!(
+            "\n{} Removed {} backup(s), freed {}",
+            "[OK]".green().bold(),
+            report.removed.len(),
+            format_file_size(report.freed_bytes)
+        );
+        if !report.missing.is_empty() {
+            println!(
+                "   Dropped {} catalog entr{} for backups already deleted",
+                report.missing.len(),
+                if report.missing.len() == 1 {
+                    "y"
+                } else {
+                    "ies"
+                }
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Write an encrypted archive of the harvest database and session files
+pub fn backup_run(destination: Option<&str>, no_harvest: bool, no_sessions: bool) -> Result<()> {
+    let mut sources = BackupSources::detect();
+    if no_harvest {
+        sources.harvest_db = None;
+    }
+    if no_sessions {
+        sources.workspace_storage = None;
+    }
+
+    println!("{} Creating encrypted backup...", "[*]".blue());
+    let report = create_archive(destination, &sources)?;
+    let manifest = &report.manifest;
+
+    if let Some(db) = &manifest.harvest_db {
+        println!("   {} harvest database {}", "[+]".green(), db.display());
+    }
+    if let Some(storage) = &manifest.workspace_storage {
+        println!(
+            "   {} {} session file(s) from {} workspace(s) in {}",
+            "[+]".green(),
+            manifest.count(EntryKind::SessionFile),
+            manifest.count(EntryKind::WorkspaceMetadata),
+            storage.display()
+        );
+    }
+    println!(
+        "{} Wrote {} ({}, {} before compression) to {}",
+        "[OK]".green().bold(),
+        report.name.cyan(),
+        format_file_size(report.size),
+        format_file_size(manifest.total_size()),
+        report.destination
+    );
+    Ok(())
+}
+
+#[derive(Tabled)]
+struct ArchiveRow {
+    #[tabled(rename = "Archive")]
+    name: String,
+    #[tabled(rename = "Modified")]
+    modified: String,
+    #[tabled(rename = "Size")]
+    size: String,
+}
+
+/// List archives at a destination, newest first
+pub fn backup_archives(destination: Option<&str>) -> Result<()> {
+    let dest = destination::open_or_configured(destination)?;
+    let mut archives = dest.list()?;
+    archives.sort_by(|a, b| b.name.cmp(&a.name));
+
+    if archives.is_empty() {
+        println!("No archives in {}", dest.describe());
+        return Ok(());
+    }
+
+    let rows: Vec<ArchiveRow> = archives
+        .iter()
+        .map(|a| ArchiveRow {
+            name: a.name.clone(),
+            modified: a
+                .modified
+                .map(format_datetime_short)
+                .unwrap_or_else(|| "-".to_string()),
+            size: format_file_size(a.size),
+        })
+        .collect();
+    println!("{}", Table::new(&rows).with(TableStyle::ascii_rounded()));
+    println!(
+        "\nTotal archives: {} in {}",
+        archives.len(),
+        dest.describe()
+    );
+    Ok(())
+}
+
+/// Read an archive from a local path, or by name from the destination
+fn read_archive(archive: &str, destination: Option<&str>) -> Result<(Vec<u8>, String)> {
+    let path = std::path::Path::new(archive);
+    if path.is_file() {
+        return Ok((std::fs::read(path)?, path.display().to_string()));
+    }
+    let dest = destination::open_or_configured(destination)?;
+    let data = dest.get(archive)?;
+    Ok((
+        data,
+        format!("{}/{}", dest.describe().trim_end_matches('/'), archive),
+    ))
+}
+
+/// Check an archive's integrity and, with the passphrase, every file in it
+pub fn backup_verify(archive: &str, destination: Option<&str>) -> Result<()> {
+    let (data, location) = read_archive(archive, destination)?;
+    let (header, _) = archive::check_integrity(&data)?;
+    println!(
+        "{} {} ({}, created {} with csm {})",
+        "[+]".green(),
+        location,
+        format_file_size(data.len() as u64),
+        format_datetime_short(header.created_at),
+        header.created_with
+    );
+    println!("   {} payload checksum ok", "[+]".green());
+
+    let passphrase = match archive::passphrase() {
+        Ok(passphrase) => passphrase,
+        Err(e) => {
+            println!("{} Contents not checked. {}", "[!]".yellow(), e);
+            return Ok(());
+        }
+    };
+    let snapshot = Snapshot::open(&data, &passphrase)?;
+    let manifest = &snapshot.manifest;
+    println!(
+        "   {} decrypted; {} file(s) match their checksums",
+        "[+]".green(),
+        manifest.entries.len()
+    );
+    println!(
+        "{} Archive is intact: {} harvest database, {} session file(s), {} total",
+        "[OK]".green().bold(),
+        manifest.count(EntryKind::HarvestDatabase),
+        manifest.count(EntryKind::SessionFile),
+        format_file_size(manifest.total_size())
+    );
+    Ok(())
+}
+
+/// Restore an archive to its original locations or into a directory
+pub fn backup_restore(
+    archive: &str,
+    destination: Option<&str>,
+    into: Option<&str>,
+    force: bool,
+    dry_run: bool,
+) -> Result<()> {
+    let (data, location) = read_archive(archive, destination)?;
+    let snapshot = Snapshot::open(&data, &archive::passphrase()?)?;
+
+    let target = match into {
+        Some(dir) => RestoreTarget::Directory(dir.into()),
+        None => RestoreTarget::Original(BackupSources {
+            harvest_db: crate::config::storage_root(crate::config::StorageRoot::HarvestDb),
+            ..BackupSources::detect()
+        }),
+    };
+    if into.is_none() && !dry_run && crate::storage::is_vscode_running() {
+        println!(
+            "{} VS Code is running; it may not pick up restored sessions until restarted",
+            "[!]".yellow()
+        );
+    }
+
+    println!("{} Restoring from {}", "[*]".blue(), location);
+    let report = archive::restore(&snapshot, &target, force, dry_run)?;
+
+    for path in &report.restored {
+        println!(
+            "   {} {}",
+            if dry_run { "[would restore]" } else { "[+]" }.green(),
+            path.display()
+        );
+    }
+    for path in &report.skipped {
+        println!(
+            "   {} {} (differs from archive, skipped)",
+            "[=]".dimmed(),
+            path.display()
+        );
+    }
+
+    println!(
+        "\n{} {}{} restored, {} unchanged, {} skipped",
+        "[OK]".green().bold(),
+        if dry_run { "Dry run: " } else { "" },
+        report.restored.len(),
+        report.unchanged,
+        report.skipped.len()
+    );
+    if !report.skipped.is_empty() {
+        println!("   Use --force to overwrite changed files (they are backed up first)");
+    }
+    if into.is_none() && !dry_run && !report.restored.is_empty() {
+        println!(
+            "   Run {} in a project to add restored sessions to VS Code's index",
+            "csm register all".cyan()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,237 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Config command implementations
+
+use anyhow::Result;
+use colored::*;
+use std::path::PathBuf;
+
+use crate::config::{Config, StorageRoot, ValueSource, PROFILE_ENV};
+use crate::datetime::display_timezone;
+use crate::workspace::{get_global_storage_path, get_vscode_user_path, get_workspace_storage_path};
+
+/// Built-in default for a storage root, if it has one
+fn default_root(root: StorageRoot) -> Option<PathBuf> {
+    match root {
+        StorageRoot::VsCodeUser => get_vscode_user_path().ok(),
+        StorageRoot::WorkspaceStorage => get_workspace_storage_path().ok(),
+        StorageRoot::GlobalStorage => get_global_storage_path().ok(),
+        StorageRoot::HarvestDb => std::env::current_dir()
+            .ok()
+            .map(|d| d.join("chat_sessions.db")),
+        StorageRoot::Backups | StorageRoot::Exports => None,
+    }
+}
+
+/// Show resolved configuration
+pub fn config_show() -> Result<()> {
+    let path = Config::path()?;
+    let config = Config::load()?;
+
+    println!("{}", "[CONFIG]".cyan().bold());
+    println!(
+        "File:    {} {}",
+        path.display(),
+        if path.exists() {
+            "".normal()
+        } else {
+            "(not created)".dimmed()
+        }
+    );
+    println!(
+        "Profile: {}",
+        config
+            .active_profile()
+            .map(|p| p.green().to_string())
+            .unwrap_or_else(|| "(none)".dimmed().to_string())
+    );
+
+    println!();
+    println!("{}", "Storage roots".bold());
+    for root in StorageRoot::ALL {
+        let (value, source) = match config.resolve(root) {
+            Some((path, source)) => (path.display().to_string(), source),
+            None => match default_root(root) {
+                Some(path) => (path.display().to_string(), ValueSource::Default),
+                None => ("(alongside source)".to_string(), ValueSource::Default),
+            },
+        };
+        let source = match source {
+            ValueSource::Default => source.to_string().dimmed(),
+            _ => source.to_string().yellow(),
+        };
+        println!("  {:<18} {} [{}]", root.key(), value, source);
+    }
+
+    let defaults = config.defaults();
+    println!();
+    println!("{}", "Defaults".bold());
+    println!(
+        "  {:<18} {}",
+        "harvest_providers",
+        defaults
+            .harvest_providers
+            .map(|p| p.join(", "))
+            .unwrap_or_else(|| "(all)".dimmed().to_string())
+    );
+    println!(
+        "  {:<18} {}",
+        "harvest_exclude",
+        defaults
+            .harvest_exclude
+            .map(|p| p.join(", "))
+            .unwrap_or_else(|| "(none)".dimmed().to_string())
+    );
+
+    let limits = config.limits();
+    println!();
+    println!("{}", "Limits".bold());
+    println!(
+        "  {:<18} {} MB",
+        "max_session_mb",
+        limits.max_session_bytes() / (1024 * 1024)
+    );
+    println!(
+        "  {:<18} {} KB",
+        "message_chunk_kb",
+        limits.message_chunk_bytes() / 1024
+    );
+
+    println!();
+    println!("{}", "Display".bold());
+    println!("  {:<18} {}", "timezone", display_timezone());
+
+    println!();
+    println!("{}", "Backups".bold());
+    println!("  {:<18} {} per source", "keep", config.backups.keep());
+    println!(
+        "  {:<18} {}",
+        "max_age_days",
+        config
+            .backups
+            .max_age_days
+            .map(|d| d.to_string())
+            .unwrap_or_else(|| "(no limit)".dimmed().to_string())
+    );
+
+    println!();
+    println!("{}", "Automation".bold());
+    println!(
+        "  {:<18} {} per workflow",
+        "keep_runs",
+        config.automation.keep_runs()
+    );
+    println!(
+        "  {:<18} {}",
+        "max_run_age_days",
+        config
+            .automation
+            .max_run_age_days
+            .map(|d| d.to_string())
+            .unwrap_or_else(|| "(no limit)".dimmed().to_string())
+    );
+
+    Ok(())
+}
+
+/// Print the config file path
+pub fn config_path() -> Result<()> {
+    println!("{}", Config::path()?.display());
+    Ok(())
+}
+
+/// Print a single config value
+pub fn config_get(key: &str, profile: Option<&str>) -> Result<()> {
+    let config = Config::load()?;
+    match config.get_key(key, profile)? {
+        Some(toml::Value::String(s)) => println!("{}", s),
+        Some(value) => println!("{}", value),
+        None => anyhow::bail!("'{}' is not set", key),
+    }
+    Ok(())
+}
+
+/// Set a config value and save
+pub fn config_set(key: &str, value: &str, profile: Option<&str>) -> Result<()> {
+    let mut config = Config::load()?;
+    config.set_key(key, value, profile)?;
+    let path = config.save()?;
+
+    println!(
+        "{} Set {}{} = {}",
+        "[OK]".green().bold(),
+        profile.map(|p| format!("[{}] ", p)).unwrap_or_default(),
+        key.cyan(),
+        value
+    );
+    println!("   Saved to {}", path.display().to_string().dimmed());
+    Ok(())
+}
+
+/// Remove a config value and save
+pub fn config_unset(key: &str, profile: Option<&str>) -> Result<()> {
+    let mut config = Config::load()?;
+    config.unset_key(key, profile)?;
+    config.save()?;
+
+    println!(
+        "{} Unset {}{}",
+        "[OK]".green().bold(),
+        profile.map(|p| format!("[{}] ", p)).unwrap_or_default(),
+        key.cyan()
+    );
+    Ok(())
+}
+
+/// Select the active profile
+pub fn config_use(name: Option<&str>) -> Result<()> {
+    let mut config = Config::load()?;
+
+    if let Some(name) = name {
+        if !config.profiles.contains_key(name) {
+            println!(
+                "{} Profile '{}' has no settings yet; use {} to add some",
+                "[!]".yellow(),
+                name,
+                format!("csm config set <key> <value> --profile {}", name).cyan()
+            );
+        }
+    }
+
+    config.profile = name.map(String::from);
+    config.save()?;
+
+    match name {
+        Some(name) => println!("{} Active profile: {}", "[OK]".green().bold(), name.green()),
+        None => println!("{} Active profile cleared", "[OK]".green().bold()),
+    }
+
+    if std::env::var(PROFILE_ENV).is_ok_and(|p| !p.is_empty()) {
+        println!(
+            "{} ${} is set and takes precedence",
+            "[!]".yellow(),
+            PROFILE_ENV
+        );
+    }
+    Ok(())
+}
+
+/// List configured profiles
+pub fn config_profiles() -> Result<()> {
+    let config = Config::load()?;
+    let active = config.active_profile();
+
+    if config.profiles.is_empty() {
+        println!("No profiles configured.");
+        return Ok(());
+    }
+
+    for name in config.profiles.keys() {
+        if active.as_deref() == Some(name.as_str()) {
+            println!("* {}", name.green().bold());
+        } else {
+            println!("  {}", name);
+        }
+    }
+    Ok(())
+}
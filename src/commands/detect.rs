@@ -4,9 +4,14 @@
 
 use anyhow::Result;
 use colored::*;
+use serde::Serialize;
 
+use super::providers::provider_registry;
 use crate::models::Workspace;
-use crate::providers::{ProviderRegistry, ProviderType};
+use crate::providers::diagnostics::{diagnose, print_diagnostic, ProviderDiagnostic};
+use crate::providers::ProviderType;
+use crate::storage::copy_file_atomic;
+use crate::text::truncate;
 use crate::workspace::{
     discover_workspaces, find_workspace_by_path, get_chat_sessions_from_workspace,
 };
@@ -94,7 +99,7 @@ pub fn detect_providers(with_sessions: bool) -> Result<()> {
     println!("\n{} Detecting Providers", "[D]".blue().bold());
     println!("{}", "=".repeat(60));
 
-    let registry = ProviderRegistry::new();
+    let registry = provider_registry();
     let mut found_count = 0;
     let mut with_sessions_count = 0;
 
@@ -178,7 +183,7 @@ pub fn detect_session(session_id: &str, path: Option<&str>) -> Result<()> {
     println!("{}", "=".repeat(60));
     println!("{} Session: {}", "[*]".blue(), session_id.cyan());
 
-    let registry = ProviderRegistry::new();
+    let registry = provider_registry();
     let session_lower = session_id.to_lowercase();
     let mut found = false;
 
@@ -288,14 +293,75 @@ pub fn detect_session(session_id: &str, path: Option<&str>) -> Result<()> {
     Ok(())
 }
 
+/// Workspace section of the `detect all --json` report
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WorkspaceReport {
+    id: String,
+    directory: String,
+    name: Option<String>,
+    sessions: Option<usize>,
+}
+
+/// Machine-readable `detect all` report
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DetectReport {
+    path: String,
+    workspace: Option<WorkspaceReport>,
+    providers: Vec<ProviderDiagnostic>,
+    total_sessions: usize,
+}
+
 /// Detect everything (workspace, providers, sessions) for a path
-pub fn detect_all(path: Option<&str>, verbose: bool) -> Result<()> {
+pub fn detect_all(path: Option<&str>, verbose: bool, json: bool) -> Result<()> {
     let project_path = path.map(|p| p.to_string()).unwrap_or_else(|| {
         std::env::current_dir()
             .map(|p| p.to_string_lossy().to_string())
             .unwrap_or_else(|_| ".".to_string())
     });
 
+    let workspace_info = find_workspace_by_path(&project_path)?;
+    let registry = provider_registry();
+    let provider_types = vec![
+        ProviderType::Copilot,
+        ProviderType::Cursor,
+        ProviderType::Ollama,
+        ProviderType::Vllm,
+        ProviderType::Foundry,
+        ProviderType::LmStudio,
+        ProviderType::LocalAI,
+        ProviderType::TextGenWebUI,
+        ProviderType::Jan,
+        ProviderType::Gpt4All,
+        ProviderType::Llamafile,
+    ];
+    let diagnostics: Vec<ProviderDiagnostic> = provider_types
+        .into_iter()
+        .map(|pt| diagnose(&registry, pt))
+        .collect();
+    let total_sessions: usize = diagnostics.iter().map(|d| d.sessions).sum();
+
+    if json {
+        let report = DetectReport {
+            path: project_path,
+            workspace: workspace_info
+                .as_ref()
+                .map(|(ws_id, ws_dir, ws_name)| WorkspaceReport {
+                    id: ws_id.clone(),
+                    directory: ws_dir.display().to_string(),
+                    name: ws_name.clone(),
+                    sessions: get_chat_sessions_from_workspace(ws_dir)
+                        .ok()
+                        .map(|s| s.len()),
+                }),
+            providers: diagnostics,
+            total_sessions,
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
     println!("\n{} Auto-Detection Report", "[D]".blue().bold());
     println!("{}", "=".repeat(70));
     println!("{} Path: {}", "[*]".blue(), project_path.cyan());
@@ -303,7 +369,6 @@ pub fn detect_all(path: Option<&str>, verbose: bool) -> Result<()> {
 
     // 1. Workspace Detection
     println!("{} Workspace", "---".dimmed());
-    let workspace_info = find_workspace_by_path(&project_path)?;
 
     match &workspace_info {
         Some((ws_id, ws_dir, ws_name)) => {
@@ -318,23 +383,28 @@ pub fn detect_all(path: Option<&str>, verbose: bool) -> Result<()> {
             }
 
             // Get sessions from workspace
-            if let Ok(sessions) = get_chat_sessions_from_workspace(ws_dir) {
-                println!("   {} Sessions: {}", "[*]".blue(), sessions.len());
-
-                if verbose && !sessions.is_empty() {
-                    println!("\n   {} Recent Sessions:", "[*]".blue());
-                    for (i, swp) in sessions.iter().take(5).enumerate() {
-                        println!(
-                            "      {}. {} ({} messages)",
-                            i + 1,
-                            truncate(&swp.session.title(), 40),
-                            swp.session.request_count()
-                        );
-                    }
-                    if sessions.len() > 5 {
-                        println!("      ... and {} more", sessions.len() - 5);
+            match get_chat_sessions_from_workspace(ws_dir) {
+                Ok(sessions) => {
+                    println!("   {} Sessions: {}", "[*]".blue(), sessions.len());
+
+                    if verbose && !sessions.is_empty() {
+                        println!("\n   {} Recent Sessions:", "[*]".blue());
+                        for (i, swp) in sessions.iter().take(5).enumerate() {
+                            println!(
+                                "      {}. {} ({} messages)",
+                                i + 1,
+                                truncate(&swp.session.title(), 40),
+                                swp.session.request_count()
+                            );
+                        }
+                        if sessions.len() > 5 {
+                            println!("      ... and {} more", sessions.len() - 5);
+                        }
                     }
                 }
+                Err(e) => {
+                    println!("   {} Sessions: could not be read ({})", "[!]".yellow(), e);
+                }
             }
         }
         None => {
@@ -350,50 +420,20 @@ pub fn detect_all(path: Option<&str>, verbose: bool) -> Result<()> {
     // 2. Provider Detection
     println!("{} Available Providers", "---".dimmed());
 
-    let registry = ProviderRegistry::new();
-    let provider_types = vec![
-        ProviderType::Copilot,
-        ProviderType::Cursor,
-        ProviderType::Ollama,
-        ProviderType::Vllm,
-        ProviderType::Foundry,
-        ProviderType::LmStudio,
-        ProviderType::LocalAI,
-        ProviderType::TextGenWebUI,
-        ProviderType::Jan,
-        ProviderType::Gpt4All,
-        ProviderType::Llamafile,
-    ];
-
-    let mut total_sessions = 0;
-    let mut provider_summary: Vec<(String, usize)> = Vec::new();
-
-    for provider_type in provider_types {
-        if let Some(provider) = registry.get_provider(provider_type) {
-            if provider.is_available() {
-                let session_count = provider.list_sessions().map(|s| s.len()).unwrap_or(0);
-
-                if session_count > 0 || verbose {
-                    let status = if session_count > 0 {
-                        format!("{} sessions", session_count.to_string().cyan())
-                    } else {
-                        "ready".dimmed().to_string()
-                    };
-                    println!("   {} {}: {}", "[+]".green(), provider.name(), status);
-
-                    total_sessions += session_count;
-                    if session_count > 0 {
-                        provider_summary.push((provider.name().to_string(), session_count));
-                    }
-                }
-            }
+    let mut shown = 0;
+    for diagnostic in &diagnostics {
+        if diagnostic.sessions > 0 || diagnostic.status.is_problem() || verbose {
+            print_diagnostic(diagnostic, verbose);
+            shown += 1;
         }
     }
+    let providers_with_sessions = diagnostics.iter().filter(|d| d.sessions > 0).count();
+    let problems = diagnostics.iter().filter(|d| d.status.is_problem()).count();
 
-    if provider_summary.is_empty() && !verbose {
+    if shown == 0 {
         println!("   {} No providers with sessions found", "[i]".yellow());
         println!(
-            "   {} Use --verbose to see all available providers",
+            "   {} Use --verbose to see every provider and where it was looked for",
             "[i]".dimmed()
         );
     }
@@ -411,13 +451,20 @@ pub fn detect_all(path: Option<&str>, verbose: bool) -> Result<()> {
     println!(
         "   {} Total providers with sessions: {}",
         "[*]".blue(),
-        provider_summary.len()
+        providers_with_sessions
     );
     println!(
         "   {} Total sessions across providers: {}",
         "[*]".blue(),
         total_sessions
     );
+    if problems > 0 {
+        println!(
+            "   {} Providers that could not be fully read: {}",
+            "[!]".yellow(),
+            problems.to_string().yellow()
+        );
+    }
 
     // 4. Recommendations
     if workspace_info.is_none() || total_sessions == 0 {
@@ -431,7 +478,7 @@ pub fn detect_all(path: Option<&str>, verbose: bool) -> Result<()> {
             );
         }
 
-        if total_sessions == 0 {
+        if total_sessions == 0 && problems == 0 {
             println!(
                 "   {} Start a chat session in your IDE to create history",
                 "[->]".cyan()
@@ -442,15 +489,6 @@ pub fn detect_all(path: Option<&str>, verbose: bool) -> Result<()> {
     Ok(())
 }
 
-/// Helper function to truncate strings
-fn truncate(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
-        s.to_string()
-    } else {
-        format!("{}...", &s[..max_len - 3])
-    }
-}
-
 /// Detect all workspace hashes for a project path (including orphaned workspaces)
 /// This helps find sessions that exist on disk but are in old/orphaned workspace folders
 pub fn detect_orphaned(path: Option<&str>, recover: bool) -> Result<()> {
@@ -650,7 +688,7 @@ pub fn detect_orphaned(path: Option<&str>, recover: bool) -> Result<()> {
                             let filename = src.file_name().unwrap();
                             let dest = active_chat_sessions.join(filename);
                             if !dest.exists() {
-                                std::fs::copy(&src, &dest)?;
+                                copy_file_atomic(&src, &dest)?;
                                 recovered += 1;
                                 println!(
                                     "   {} Copied: {} (from {}...)",
@@ -0,0 +1,86 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Diff commands
+
+use anyhow::{Context, Result};
+use colored::*;
+use rusqlite::Connection;
+
+use super::harvest::existing_db_path;
+use crate::session_diff::{self, Against, ChangeKind};
+use crate::text::truncate;
+
+/// Width of the content shown for each changed turn
+const PREVIEW_WIDTH: usize = 100;
+
+/// Show how a harvested session differs from a checkpoint, a commit of the
+/// harvest database, or another session
+pub fn diff_session(
+    db_path: Option<&str>,
+    session_id: &str,
+    against: &str,
+    json: bool,
+) -> Result<()> {
+    let db_path = existing_db_path(db_path)?;
+    let conn = Connection::open(&db_path)
+        .with_context(|| format!("Failed to open {}", db_path.display()))?;
+    let against = Against::resolve(&conn, session_id, against)?;
+    let diff = session_diff::diff_session(&conn, &db_path, session_id, &against)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+        return Ok(());
+    }
+
+    println!(
+        "\n{} {} against {}",
+        "[*]".blue().bold(),
+        diff.session_id.bold(),
+        diff.against
+    );
+    println!("{}", "=".repeat(60));
+
+    if diff.changes.is_empty() {
+        println!(
+            "\n{} No differences ({} turns unchanged)",
+            "[+]".green(),
+            diff.unchanged
+        );
+        return Ok(());
+    }
+
+    let preview = |content: &Option<String>| {
+        truncate(
+            &content.as_deref().unwrap_or_default().replace('\n', " "),
+            PREVIEW_WIDTH,
+        )
+    };
+    for change in &diff.changes {
+        let (index, label) = match change.kind {
+            ChangeKind::Added => (change.new_index, "added".green()),
+            ChangeKind::Removed => (change.old_index, "removed".red()),
+            ChangeKind::Edited => (change.new_index, "edited".yellow()),
+        };
+        println!(
+            "\n  #{} {} {}",
+            index.unwrap_or_default(),
+            change.role.cyan(),
+            label
+        );
+        if change.kind != ChangeKind::Added {
+            println!("    {} {}", "-".red(), preview(&change.old));
+        }
+        if change.kind != ChangeKind::Removed {
+            println!("    {} {}", "+".green(), preview(&change.new));
+        }
+    }
+
+    println!(
+        "\n{} {} added, {} removed, {} edited, {} unchanged",
+        "[i]".blue(),
+        diff.added,
+        diff.removed,
+        diff.edited,
+        diff.unchanged
+    );
+    Ok(())
+}
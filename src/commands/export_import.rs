@@ -6,7 +6,9 @@ use anyhow::{Context, Result};
 use colored::*;
 use std::path::Path;
 
+use crate::config;
 use crate::models::Workspace;
+use crate::storage::{check_session_file_size, copy_file_atomic};
 use crate::workspace::{get_workspace_by_hash, get_workspace_by_path};
 
 /// Export chat sessions from a workspace
@@ -25,8 +27,8 @@ pub fn export_sessions(destination: &str, hash: Option<&str>, path: Option<&str>
     }
 
     // Create destination directory
-    let dest_path = Path::new(destination);
-    std::fs::create_dir_all(dest_path)?;
+    let dest_path = config::resolve_export_path(destination);
+    std::fs::create_dir_all(&dest_path)?;
 
     // Copy all session files
     let mut exported_count = 0;
@@ -45,7 +47,7 @@ pub fn export_sessions(destination: &str, hash: Option<&str>, path: Option<&str>
         "{} Exported {} chat session(s) to {}",
         "[OK]".green(),
         exported_count,
-        destination
+        dest_path.display()
     );
 
     Ok(())
@@ -75,8 +77,10 @@ pub fn import_sessions(
     std::fs::create_dir_all(&workspace.chat_sessions_path)?;
 
     // Import all JSON files
+    let max_bytes = config::current().limits().max_session_bytes();
     let mut imported_count = 0;
     let mut skipped_count = 0;
+    let mut rejected_count = 0;
 
     for entry in std::fs::read_dir(src_path)? {
         let entry = entry?;
@@ -85,10 +89,13 @@ pub fn import_sessions(
         if src_file.extension().map(|e| e == "json").unwrap_or(false) {
             let dest_file = workspace.chat_sessions_path.join(entry.file_name());
 
-            if dest_file.exists() && !force {
+            if let Err(e) = check_session_file_size(&src_file, max_bytes) {
+                println!("{} {}", "[!]".yellow(), e);
+                rejected_count += 1;
+            } else if dest_file.exists() && !force {
                 skipped_count += 1;
             } else {
-                std::fs::copy(&src_file, &dest_file)?;
+                copy_file_atomic(&src_file, &dest_file)?;
                 imported_count += 1;
             }
         }
@@ -106,6 +113,13 @@ pub fn import_sessions(
             skipped_count
         );
     }
+    if rejected_count > 0 {
+        println!(
+            "{} Rejected {} oversized session(s).",
+            "[!]".yellow(),
+            rejected_count
+        );
+    }
 
     Ok(())
 }
@@ -212,8 +226,8 @@ pub fn export_specific_sessions(
 ) -> Result<()> {
     use crate::workspace::{discover_workspaces, get_chat_sessions_from_workspace, normalize_path};
 
-    let dest_path = Path::new(destination);
-    std::fs::create_dir_all(dest_path)?;
+    let dest_path = config::resolve_export_path(destination);
+    std::fs::create_dir_all(&dest_path)?;
 
     let workspaces = discover_workspaces()?;
 
@@ -287,7 +301,7 @@ pub fn export_specific_sessions(
         "\n{} Exported {} session(s) to {}",
         "[OK]".green().bold(),
         exported_count,
-        destination
+        dest_path.display()
     );
 
     Ok(())
@@ -309,8 +323,10 @@ pub fn import_specific_sessions(
 
     std::fs::create_dir_all(&target_ws.chat_sessions_path)?;
 
+    let max_bytes = config::current().limits().max_session_bytes();
     let mut imported_count = 0;
     let mut skipped_count = 0;
+    let mut rejected_count = 0;
 
     for file_path in session_files {
         let src_path = Path::new(file_path);
@@ -327,11 +343,14 @@ pub fn import_specific_sessions(
 
         let dest_file = target_ws.chat_sessions_path.join(&filename);
 
-        if dest_file.exists() && !force {
+        if let Err(e) = check_session_file_size(src_path, max_bytes) {
+            println!("   {} {}", "[!]".yellow(), e);
+            rejected_count += 1;
+        } else if dest_file.exists() && !force {
             println!("   {} Skipping (exists): {}", "[!]".yellow(), filename);
             skipped_count += 1;
         } else {
-            std::fs::copy(src_path, &dest_file)?;
+            copy_file_atomic(src_path, &dest_file)?;
             imported_count += 1;
             println!("   {} Imported: {}", "[OK]".green(), filename);
         }
@@ -349,6 +368,13 @@ pub fn import_specific_sessions(
             skipped_count
         );
     }
+    if rejected_count > 0 {
+        println!(
+            "{} Rejected {} oversized session(s).",
+            "[!]".yellow(),
+            rejected_count
+        );
+    }
 
     Ok(())
 }
@@ -0,0 +1,714 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Extract commands
+//!
+//! `csm extract code` writes the code blocks of a session to files, so what
+//! a long pairing session produced can be used without copying it out of
+//! the chat by hand. A file the assistant rewrote several times is written
+//! once, in its last version.
+
+use anyhow::{bail, Context, Result};
+use colored::*;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use tabled::{settings::Style as TableStyle, Table, Tabled};
+
+use super::agency::find_harvested;
+use super::harvest::open_harvest_db;
+use crate::providers::GenericSession;
+use crate::storage::parse_session_file;
+
+/// How alike (0-1) two unnamed blocks must be for the later one to count as
+/// a new version of the earlier
+const ITERATION_SIMILARITY: f32 = 0.5;
+
+/// Fence languages, their file extension first and then the other names
+/// they go by
+const LANGUAGES: &[&[&str]] = &[
+    &["rs", "rust"],
+    &["py", "python", "python3", "py3"],
+    &["js", "javascript", "node", "mjs", "cjs"],
+    &["jsx"],
+    &["ts", "typescript"],
+    &["tsx"],
+    &["go", "golang"],
+    &["java"],
+    &["kt", "kotlin"],
+    &["swift"],
+    &["c"],
+    &["h"],
+    &["cpp", "c++", "cxx", "cc", "hpp"],
+    &["cs", "csharp", "c#"],
+    &["rb", "ruby"],
+    &["php"],
+    &["sh", "bash", "shell", "zsh"],
+    &["ps1", "powershell", "pwsh"],
+    &["bat", "batch", "cmd"],
+    &["sql"],
+    &["html", "htm"],
+    &["css"],
+    &["scss", "sass"],
+    &["json", "jsonc"],
+    &["yaml", "yml"],
+    &["toml"],
+    &["xml"],
+    &["md", "markdown"],
+    &["dockerfile", "docker"],
+    &["lua"],
+    &["r"],
+    &["dart"],
+    &["scala"],
+    &["hs", "haskell"],
+    &["ex", "elixir", "exs"],
+    &["zig"],
+    &["vue"],
+    &["svelte"],
+    &["proto", "protobuf"],
+    &["graphql", "gql"],
+    &["tf", "terraform", "hcl"],
+    &["ini", "cfg", "conf"],
+    &["diff", "patch"],
+    &["txt", "text", "plaintext", "console", "output"],
+];
+
+/// Other extensions a name in the chat can have and still be a file
+const FILE_EXTENSIONS: &[&str] = &["lock", "env", "csv", "svg", "gitignore", "mod", "sum"];
+
+/// Files known by name rather than extension
+const BARE_FILENAMES: &[&str] = &["Dockerfile", "Makefile", "Justfile", "Procfile", "Gemfile"];
+
+/// A fenced code block from a session
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeBlock {
+    /// Language from the fence, or guessed from the code
+    pub language: Option<String>,
+    /// File the block belongs to, from the fence, a `filepath:` comment or
+    /// the sentence before it
+    pub filename: Option<String>,
+    pub code: String,
+}
+
+/// A file built from the blocks of a session
+#[derive(Debug, Serialize)]
+pub struct ExtractedFile {
+    pub path: String,
+    pub language: String,
+    pub lines: usize,
+    /// How many different versions of the file the session has; the last
+    /// one is written
+    pub versions: usize,
+    #[serde(skip)]
+    code: String,
+    /// Every version so far, to tell a new one from a repeat
+    #[serde(skip)]
+    seen: Vec<String>,
+    /// Whether the name came from the session rather than a `snippet-N` stand-in
+    #[serde(skip)]
+    named: bool,
+}
+
+#[derive(Tabled)]
+struct ExtractedRow {
+    #[tabled(rename = "File")]
+    path: String,
+    #[tabled(rename = "Language")]
+    language: String,
+    #[tabled(rename = "Lines")]
+    lines: usize,
+    #[tabled(rename = "Versions")]
+    versions: usize,
+}
+
+/// Write the code blocks of `session`, a harvested session ID or a session
+/// file, into `out`
+pub fn extract_code(
+    session: &str,
+    out: &str,
+    db_path: Option<&str>,
+    lang: Option<&str>,
+    dry_run: bool,
+    force: bool,
+    json: bool,
+) -> Result<()> {
+    let (title, session) = load_session(session, db_path)?;
+    let lang = lang.map(|lang| extension_for(lang).unwrap_or(lang).to_string());
+
+    let blocks: Vec<CodeBlock> = session
+        .messages
+        .iter()
+        .filter(|message| message.role == "assistant")
+        .flat_map(|message| code_blocks(&message.content))
+        .filter(|block| {
+            lang.as_deref()
+                .is_none_or(|lang| block_extension(block) == lang)
+        })
+        .collect();
+    let files = collect_files(&blocks);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&files)?);
+    } else {
+        println!("\n{} Code from {}", "[*]".blue().bold(), title.bold());
+        println!("{}", "=".repeat(60));
+        if files.is_empty() {
+            println!("\n{} The session has no code blocks", "[!]".yellow());
+            return Ok(());
+        }
+        let rows: Vec<ExtractedRow> = files
+            .iter()
+            .map(|file| ExtractedRow {
+                path: file.path.clone(),
+                language: file.language.clone(),
+                lines: file.lines,
+                versions: file.versions,
+            })
+            .collect();
+        println!("{}", Table::new(rows).with(TableStyle::ascii_rounded()));
+    }
+
+    if dry_run {
+        if !json {
+            println!("\n{} Dry run: no files were written", "[i]".blue());
+        }
+        return Ok(());
+    }
+
+    let out = Path::new(out);
+    let mut written = 0;
+    for file in &files {
+        let path = out.join(&file.path);
+        if path.exists() && !force {
+            eprintln!(
+                "{} {} exists, skipped (use --force to overwrite)",
+                "[!]".yellow(),
+                path.display()
+            );
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, &file.code)
+            .with_context(|| format!("Cannot write {}", path.display()))?;
+        written += 1;
+    }
+    if !json {
+        println!(
+            "\n{} Wrote {} file(s) to {}",
+            "[+]".green(),
+            written,
+            out.display()
+        );
+    }
+    Ok(())
+}
+
+/// The title and messages of a session file, or else of a harvested session
+fn load_session(session: &str, db_path: Option<&str>) -> Result<(String, GenericSession)> {
+    let file = Path::new(session);
+    if file.is_file() {
+        let session = parse_session_file(file)?;
+        return Ok((session.title(), GenericSession::from(session)));
+    }
+
+    let conn = open_harvest_db(db_path)?;
+    let (_, _, session) = find_harvested(&conn, session)?;
+    Ok((session.title(), GenericSession::from(session)))
+}
+
+/// The fenced code blocks in a message, including one left open at the end
+/// of a cut-off response
+pub fn code_blocks(text: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    let mut prose: Vec<&str> = Vec::new();
+    // fence character, fence length, indentation, info string, code lines
+    let mut open: Option<(char, usize, usize, &str, Vec<&str>)> = None;
+
+    for line in text.lines() {
+        let indent = line.len() - line.trim_start().len();
+        let trimmed = line.trim_start();
+        match &mut open {
+            None => match fence(trimmed) {
+                Some((ch, len)) => open = Some((ch, len, indent, trimmed[len..].trim(), Vec::new())),
+                None => prose.push(line),
+            },
+            Some((ch, len, _, _, _))
+                if fence(trimmed).is_some_and(|(c, l)| c == *ch && l >= *len)
+                    && trimmed.trim_end().chars().all(|c| c == *ch) =>
+            {
+                let (_, _, _, info, lines) = open.take().unwrap();
+                blocks.extend(block(info, &lines, &prose));
+                prose.clear();
+            }
+            Some((_, _, fence_indent, _, lines)) => {
+                // Code in a list item is indented along with its fence
+                let strip = indent.min(*fence_indent);
+                lines.push(&line[strip..]);
+            }
+        }
+    }
+    if let Some((_, _, _, info, lines)) = open {
+        blocks.extend(block(info, &lines, &prose));
+    }
+    blocks
+}
+
+/// The character and length of a code fence opening `line`
+fn fence(line: &str) -> Option<(char, usize)> {
+    let ch = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = line.chars().take_while(|c| *c == ch).count();
+    (len >= 3).then_some((ch, len))
+}
+
+fn block(info: &str, lines: &[&str], prose: &[&str]) -> Option<CodeBlock> {
+    let (mut language, mut filename) = parse_info(info);
+    let mut lines = lines;
+    if let Some((name, explicit)) = lines.first().and_then(|line| comment_filename(line)) {
+        filename = filename.or(Some(name));
+        // A `filepath:` comment is for the reader, not part of the file
+        if explicit {
+            lines = &lines[1..];
+        }
+    }
+    let code = lines.join("\n");
+    if code.trim().is_empty() {
+        return None;
+    }
+
+    if language.is_none() {
+        language = filename
+            .as_deref()
+            .and_then(extension_of)
+            .or_else(|| detect_language(&code))
+            .map(String::from);
+    }
+    if filename.is_none() {
+        let extension = language.as_deref().and_then(extension_for);
+        filename = prose_filename(prose, extension);
+    }
+    Some(CodeBlock {
+        language,
+        filename,
+        code: code.trim_end().to_string() + "\n",
+    })
+}
+
+/// The language and file name in a fence's info string, as in ```` ```rust ````,
+/// ```` ```rust:src/main.rs ````, ```` ```src/main.rs ```` or
+/// ```` ```python title="app.py" ````
+fn parse_info(info: &str) -> (Option<String>, Option<String>) {
+    let mut language = None;
+    let mut filename = None;
+    for (i, token) in info.split_whitespace().enumerate() {
+        if let Some((key, value)) = token.split_once('=') {
+            if matches!(key, "title" | "file" | "filename" | "path") {
+                filename = Some(value.trim_matches(['"', '\'']).to_string());
+            }
+        } else if i == 0 {
+            match token.split_once(':') {
+                Some((lang, name)) if !lang.is_empty() && looks_like_filename(name) => {
+                    language = Some(lang.to_lowercase());
+                    filename = Some(name.to_string());
+                }
+                _ if looks_like_filename(token) && extension_for(token).is_none() => {
+                    filename = Some(token.to_string())
+                }
+                _ => language = Some(token.trim_start_matches('{').to_lowercase()),
+            }
+        }
+    }
+    (language.filter(|l| !l.is_empty()), filename)
+}
+
+/// The file named by a comment such as `// filepath: src/main.rs` or
+/// `# app.py`, and whether it was marked as a path
+fn comment_filename(line: &str) -> Option<(String, bool)> {
+    let line = line.trim();
+    if line.starts_with("#!") {
+        return None;
+    }
+    let body = ["<!--", "//", "/*", "--", "#", ";"]
+        .iter()
+        .find_map(|leader| line.strip_prefix(leader))?;
+    let body = body
+        .trim()
+        .trim_end_matches("-->")
+        .trim_end_matches("*/")
+        .trim();
+
+    let lower = body.to_lowercase();
+    for label in ["filepath:", "file path:", "filename:", "file:", "path:"] {
+        if lower.starts_with(label) {
+            let name = body[label.len()..].trim();
+            return (!name.is_empty() && !name.contains(char::is_whitespace))
+                .then(|| (name.to_string(), true));
+        }
+    }
+    looks_like_filename(body).then(|| (body.to_string(), false))
+}
+
+/// The file named in the last lines of prose before a block, as in
+/// "Update `src/lib.rs`:", preferring one with the block's extension
+fn prose_filename(prose: &[&str], extension: Option<&str>) -> Option<String> {
+    let lines = prose.iter().rev().filter(|line| !line.trim().is_empty()).take(2);
+    for line in lines {
+        let candidates: Vec<&str> = line
+            .split(|c: char| c.is_whitespace() || c == '`' || c == '*')
+            .map(|word| word.trim_matches(|c: char| ",:;()[]\"'".contains(c)))
+            .map(|word| word.strip_suffix('.').unwrap_or(word))
+            .filter(|word| looks_like_filename(word))
+            .collect();
+        let matching = candidates
+            .iter()
+            .rev()
+            .find(|name| extension.is_some() && extension_of(name) == extension);
+        if let Some(name) = matching.or(candidates.last()) {
+            return Some(name.to_string());
+        }
+    }
+    None
+}
+
+/// Whether `name` reads as a file name or path rather than a word, version
+/// or method call
+fn looks_like_filename(name: &str) -> bool {
+    if name.is_empty() || name.len() > 200 || name.contains("://") {
+        return false;
+    }
+    if name.contains(char::is_whitespace) || name.contains(['<', '>', '|', '?', '*']) {
+        return false;
+    }
+    let base = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    if BARE_FILENAMES.contains(&base) {
+        return true;
+    }
+    let Some((stem, extension)) = base.rsplit_once('.') else {
+        return false;
+    };
+    (!stem.is_empty() || base.starts_with('.'))
+        && (LANGUAGES.iter().any(|names| names[0] == extension)
+            || FILE_EXTENSIONS.contains(&extension))
+}
+
+/// The extension for a fence language, or `None` for one we don't know
+fn extension_for(language: &str) -> Option<&'static str> {
+    let language = language.to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|names| names.contains(&language.as_str()))
+        .map(|names| names[0])
+}
+
+/// The known extension of a file name
+fn extension_of(name: &str) -> Option<&'static str> {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    if base == "Dockerfile" {
+        return Some("dockerfile");
+    }
+    let (_, extension) = base.rsplit_once('.')?;
+    LANGUAGES
+        .iter()
+        .map(|names| names[0])
+        .find(|known| *known == extension)
+}
+
+fn block_extension(block: &CodeBlock) -> &'static str {
+    block
+        .filename
+        .as_deref()
+        .and_then(extension_of)
+        .or_else(|| block.language.as_deref().and_then(extension_for))
+        .unwrap_or("txt")
+}
+
+/// The language of an unlabeled block, from tell-tale lines
+pub fn detect_language(code: &str) -> Option<&'static str> {
+    let trimmed = code.trim_start();
+    let first = trimmed.lines().next().unwrap_or_default();
+    if let Some(interpreter) = first.strip_prefix("#!") {
+        return [("python", "py"), ("node", "js"), ("sh", "sh")]
+            .iter()
+            .find(|(name, _)| interpreter.contains(name))
+            .map(|(_, extension)| *extension);
+    }
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+    {
+        return Some("json");
+    }
+    if trimmed.starts_with("<?php") {
+        return Some("php");
+    }
+    if trimmed.starts_with("<?xml") {
+        return Some("xml");
+    }
+    let lower_first = first.to_lowercase();
+    if lower_first.starts_with("<!doctype html") || lower_first.starts_with("<html") {
+        return Some("html");
+    }
+    if first.starts_with("FROM ") {
+        return Some("dockerfile");
+    }
+
+    let has_line = |prefixes: &[&str]| {
+        code.lines()
+            .map(str::trim_start)
+            .any(|line| prefixes.iter().any(|prefix| line.starts_with(prefix)))
+    };
+    if has_line(&["fn ", "pub fn ", "use std::", "impl ", "#[derive"]) {
+        return Some("rs");
+    }
+    if has_line(&["package "]) && has_line(&["func "]) {
+        return Some("go");
+    }
+    if has_line(&["#include"]) {
+        let cpp = code.contains("std::") || code.contains("cout") || has_line(&["class "]);
+        return Some(if cpp { "cpp" } else { "c" });
+    }
+    if has_line(&["using System", "namespace "]) && code.contains(';') {
+        return Some("cs");
+    }
+    if has_line(&["public class ", "public static void ", "import java."]) {
+        return Some("java");
+    }
+    if has_line(&["def ", "async def "])
+        || code.lines().any(|line| line.starts_with("from ") && line.contains(" import "))
+        || has_line(&["if __name__ =="])
+    {
+        return Some("py");
+    }
+    if has_line(&["interface ", "export interface ", "type "]) && code.contains(": ") {
+        return Some("ts");
+    }
+    if has_line(&["const ", "let ", "function ", "export ", "import ", "console.log"]) {
+        return Some("js");
+    }
+    if has_line(&["SELECT ", "INSERT INTO ", "CREATE TABLE ", "UPDATE ", "DELETE FROM "]) {
+        return Some("sql");
+    }
+    if has_line(&[
+        "$ ", "cargo ", "npm ", "git ", "pip ", "cd ", "sudo ", "apt ", "brew ", "export ",
+    ]) {
+        return Some("sh");
+    }
+    if code.lines().any(|line| {
+        let line = line.trim();
+        line.starts_with('[') && line.ends_with(']') && !line.contains(',')
+    }) && code.contains(" = ")
+    {
+        return Some("toml");
+    }
+    None
+}
+
+/// A name for an unnamed block that is obviously a well-known file
+fn conventional_name(extension: &str, code: &str) -> Option<&'static str> {
+    match extension {
+        "toml" if code.contains("[package]") || code.contains("[workspace]") => Some("Cargo.toml"),
+        "toml" if code.contains("[project]") || code.contains("[tool.") => Some("pyproject.toml"),
+        "json" if code.contains("\"compilerOptions\"") => Some("tsconfig.json"),
+        "json" if code.contains("\"dependencies\"") || code.contains("\"scripts\"") => {
+            Some("package.json")
+        }
+        "dockerfile" => Some("Dockerfile"),
+        _ => None,
+    }
+}
+
+/// One file per named block, and per unnamed block that is not a version of
+/// an earlier one; later versions replace earlier ones
+fn collect_files(blocks: &[CodeBlock]) -> Vec<ExtractedFile> {
+    let names: Vec<Option<String>> = blocks.iter().map(|b| b.filename.clone()).collect();
+    let paths = relative_paths(&names);
+    let mut files: Vec<ExtractedFile> = Vec::new();
+
+    for (block, path) in blocks.iter().zip(paths) {
+        let extension = block_extension(block);
+        let path = path.or_else(|| conventional_name(extension, &block.code).map(String::from));
+        let existing = match &path {
+            Some(path) => files.iter().position(|f| f.named && &f.path == path).or_else(|| {
+                // An unnamed block the session later gives a name to
+                similar_file(&files, extension, &block.code, false)
+            }),
+            None => similar_file(&files, extension, &block.code, true),
+        };
+
+        if let Some(index) = existing {
+            let file = &mut files[index];
+            if let Some(path) = path.filter(|_| !file.named) {
+                file.path = path;
+                file.named = true;
+            }
+            if !file.seen.iter().any(|code| code.trim() == block.code.trim()) {
+                file.seen.push(block.code.clone());
+                file.versions += 1;
+            }
+            file.lines = block.code.lines().count();
+            file.code = block.code.clone();
+            continue;
+        }
+        let named = path.is_some();
+        files.push(ExtractedFile {
+            path: path.unwrap_or_default(),
+            language: block
+                .language
+                .clone()
+                .unwrap_or_else(|| extension.to_string()),
+            lines: block.code.lines().count(),
+            versions: 1,
+            code: block.code.clone(),
+            seen: vec![block.code.clone()],
+            named,
+        });
+        if !named {
+            let index = files.len();
+            files.last_mut().unwrap().path = format!("snippet-{}.{}", index, extension);
+        }
+    }
+    files
+}
+
+/// The earlier file, unnamed or (with `any`) named, that `code` is most
+/// likely a new version of
+fn similar_file(files: &[ExtractedFile], extension: &str, code: &str, any: bool) -> Option<usize> {
+    files
+        .iter()
+        .enumerate()
+        .filter(|(_, file)| (any || !file.named) && file_extension(file) == extension)
+        .map(|(i, file)| (i, line_similarity(&file.code, code)))
+        .filter(|(_, score)| *score >= ITERATION_SIMILARITY)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+}
+
+fn file_extension(file: &ExtractedFile) -> &str {
+    extension_of(&file.path)
+        .or_else(|| file.path.rsplit_once('.').map(|(_, extension)| extension))
+        .unwrap_or("txt")
+}
+
+/// Jaccard similarity of the non-blank lines of two blocks
+fn line_similarity(a: &str, b: &str) -> f32 {
+    let lines = |code: &str| -> HashSet<String> {
+        code.lines()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect()
+    };
+    let (a, b) = (lines(a), lines(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f32 / a.union(&b).count() as f32
+}
+
+/// The names of blocks as safe paths under the output directory: absolute
+/// paths lose the directory they all share, and paths leaving it are dropped
+fn relative_paths(names: &[Option<String>]) -> Vec<Option<String>> {
+    let parts = |name: &str| -> Vec<String> {
+        name.split(['/', '\\'])
+            .filter(|part| !part.is_empty() && *part != ".")
+            .map(String::from)
+            .collect()
+    };
+    let is_absolute = |name: &str| {
+        name.starts_with(['/', '\\']) || name.chars().nth(1) == Some(':') || name.starts_with('~')
+    };
+
+    // The directory all absolute paths share
+    let mut root: Option<Vec<String>> = None;
+    for name in names.iter().flatten().filter(|name| is_absolute(name)) {
+        let mut dir = parts(name);
+        dir.pop();
+        root = Some(match root {
+            None => dir,
+            Some(root) => root
+                .into_iter()
+                .zip(dir)
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect(),
+        });
+    }
+
+    names
+        .iter()
+        .map(|name| {
+            let name = name.as_deref()?;
+            let mut parts = parts(name);
+            if is_absolute(name) {
+                parts.drain(..root.as_ref().map_or(0, Vec::len));
+            }
+            let path: PathBuf = parts.iter().collect();
+            let safe = !parts.is_empty()
+                && path.components().all(|c| matches!(c, Component::Normal(_)));
+            safe.then(|| parts.join("/"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_code_blocks() {
+        let message = "Create `src/main.rs`:\n\n```rust\nfn main() {\n    println!(\"hi\");\n}\n```\n\n\
+            1. Then run:\n   ```\n   cargo run\n   ```\n\n\
+            ```python\n# filepath: /home/me/app/tools/gen.py\nprint(1)\n```\n\
+            ```toml\n[package]\nname = \"demo\"\n";
+        let blocks = code_blocks(message);
+        assert_eq!(blocks.len(), 4);
+        assert_eq!(blocks[0].filename.as_deref(), Some("src/main.rs"));
+        assert_eq!(blocks[0].language.as_deref(), Some("rust"));
+        assert_eq!(blocks[1].language.as_deref(), Some("sh"));
+        assert_eq!(blocks[1].code, "cargo run\n");
+        assert_eq!(blocks[2].filename.as_deref(), Some("/home/me/app/tools/gen.py"));
+        assert_eq!(blocks[2].code, "print(1)\n");
+        // Left open by a cut-off response
+        assert_eq!(blocks[3].language.as_deref(), Some("toml"));
+
+        let names: Vec<String> = collect_files(&blocks).into_iter().map(|f| f.path).collect();
+        assert_eq!(names, ["src/main.rs", "snippet-2.sh", "gen.py", "Cargo.toml"]);
+    }
+
+    #[test]
+    fn test_collect_iterations() {
+        let first = "```\nfn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\nfn main() {\n    println!(\"{}\", add(1, 2));\n}\n```";
+        let second = "Now with a check:\n```rust\nfn add(a: i32, b: i32) -> i32 {\n    a.checked_add(b).unwrap()\n}\n\nfn main() {\n    println!(\"{}\", add(1, 2));\n}\n```";
+        let third = "The final `src/main.rs`:\n```rust\nfn add(a: i32, b: i32) -> i32 {\n    a.checked_add(b).unwrap()\n}\n\nfn main() {\n    println!(\"{}\", add(1, 2));\n}\n```";
+        let blocks: Vec<CodeBlock> = [first, second, third, first]
+            .iter()
+            .flat_map(|message| code_blocks(message))
+            .collect();
+        let files = collect_files(&blocks);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "src/main.rs");
+        assert_eq!(files[0].versions, 2);
+        assert!(files[0].code.contains("a + b"));
+    }
+
+    #[test]
+    fn test_relative_paths() {
+        let names = [
+            Some("C:\\work\\app\\src\\lib.rs".to_string()),
+            Some("C:\\work\\app\\Cargo.toml".to_string()),
+            Some("../../etc/passwd".to_string()),
+            None,
+        ];
+        assert_eq!(
+            relative_paths(&names),
+            [Some("src/lib.rs".into()), Some("Cargo.toml".into()), None, None]
+        );
+    }
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(detect_language("#!/usr/bin/env python3\nprint(1)"), Some("py"));
+        assert_eq!(detect_language("{\"a\": 1}"), Some("json"));
+        assert_eq!(detect_language("package main\n\nfunc main() {}"), Some("go"));
+        assert_eq!(detect_language("SELECT * FROM t;"), Some("sql"));
+        assert_eq!(detect_language("hello there"), None);
+    }
+}
@@ -7,6 +7,7 @@ use colored::*;
 use std::path::Path;
 use std::process::Command;
 
+use crate::backup::{self, BackupKind};
 use crate::workspace::get_workspace_by_path;
 
 /// Configure git settings for chat sessions
@@ -629,6 +630,7 @@ pub fn git_restore(project_path: &str, commit: &str, with_files: bool, backup: b
         if let Err(e) = std::fs::rename(&chat_sessions_path, &backup_path) {
             println!("{} Failed to create backup: {}", "[!]".yellow(), e);
         } else {
+            backup::record(BackupKind::ProjectSessions, &chat_sessions_path, &backup_path);
             println!("{} Created backup: {}", "[OK]".green(), backup_name);
         }
     }
@@ -0,0 +1,329 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! GitHub commands
+
+use anyhow::{anyhow, bail, Context, Result};
+use colored::*;
+use rusqlite::Connection;
+use std::path::PathBuf;
+use tabled::{settings::Style as TableStyle, Table, Tabled};
+
+use super::agency::find_harvested;
+use super::harvest::{insert_or_update_session, open_harvest_db};
+use super::summary::SessionSummary;
+use crate::integrations::github::{
+    ensure_github_links_table, get_link, github_repo, list_links, parse_reference, remove_link,
+    save_link, GitHubClient, SessionLink, PROVIDER,
+};
+use crate::text::truncate;
+
+#[derive(Tabled)]
+struct LinkRow {
+    #[tabled(rename = "Session")]
+    session: String,
+    #[tabled(rename = "Title")]
+    title: String,
+    #[tabled(rename = "Linked to")]
+    target: String,
+    #[tabled(rename = "Branch")]
+    branch: String,
+    #[tabled(rename = "Commit")]
+    commit: String,
+}
+
+fn open_db(db_path: Option<&str>) -> Result<Connection> {
+    let conn = open_harvest_db(db_path)?;
+    ensure_github_links_table(&conn)?;
+    Ok(conn)
+}
+
+fn runtime() -> Result<tokio::runtime::Runtime> {
+    Ok(tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?)
+}
+
+/// `owner/repo` from `--repo`, which may also be a remote URL
+fn repo_arg(repo: &str) -> Result<String> {
+    github_repo(repo)
+        .or_else(|| (repo.split('/').count() == 2).then(|| repo.to_string()))
+        .with_context(|| format!("Not a GitHub repository: {}; give it as owner/repo", repo))
+}
+
+fn short_commit(commit: &str) -> &str {
+    &commit[..commit.len().min(7)]
+}
+
+/// The project folder of a harvested session's workspace, if it is known
+fn session_folder(conn: &Connection, session_id: &str) -> Option<PathBuf> {
+    let workspace: String = conn
+        .query_row(
+            "SELECT workspace_id FROM sessions WHERE id = ?1",
+            [session_id],
+            |row| row.get(0),
+        )
+        .ok()?;
+    let folder = crate::workspace::get_workspace_by_hash(&workspace)
+        .ok()??
+        .project_path?;
+    Some(PathBuf::from(folder)).filter(|path| path.is_dir())
+}
+
+/// Link a harvested session to the repository, branch and commit it worked
+/// on, found from a git checkout, and to a pull request or issue
+#[allow(clippy::too_many_arguments)]
+pub fn github_link(
+    db_path: Option<&str>,
+    session: &str,
+    dir: Option<&str>,
+    repo: Option<&str>,
+    branch: Option<&str>,
+    commit: Option<&str>,
+    pr: Option<u64>,
+    issue: Option<u64>,
+    remove: bool,
+) -> Result<()> {
+    let conn = open_db(db_path)?;
+    let (session_id, _, _) = find_harvested(&conn, session)?;
+    if remove {
+        if remove_link(&conn, &session_id)? {
+            println!("{} Unlinked session {}", "[+]".green(), session_id);
+        } else {
+            println!("{} Session {} has no link", "[i]".blue(), session_id);
+        }
+        return Ok(());
+    }
+
+    let dir = match dir {
+        Some(dir) => PathBuf::from(dir),
+        None => session_folder(&conn, &session_id).unwrap_or(std::env::current_dir()?),
+    };
+    let detected = SessionLink::detect(&dir);
+    let previous = get_link(&conn, &session_id)?;
+    let mut link = match repo.map(repo_arg).transpose()? {
+        // The checkout's branch and commit are of no use for another repository
+        Some(repo) => match detected {
+            Some(detected) if detected.repo.eq_ignore_ascii_case(&repo) => detected,
+            _ => SessionLink {
+                repo,
+                ..Default::default()
+            },
+        },
+        None => detected.with_context(|| {
+            format!(
+                "No GitHub repository is checked out in {}; give one with --repo owner/repo",
+                dir.display()
+            )
+        })?,
+    };
+    if let Some(branch) = branch {
+        link.branch = Some(branch.to_string());
+    }
+    if let Some(commit) = commit {
+        link.commit = Some(commit.to_string());
+    }
+    link.pull_request = pr;
+    link.issue = issue;
+    // Linking again keeps the pull request or issue unless given another
+    if let Some(previous) = previous.filter(|p| p.repo == link.repo) {
+        if pr.is_none() && issue.is_none() {
+            link.pull_request = previous.pull_request;
+            link.issue = previous.issue;
+        }
+    }
+    save_link(&conn, &session_id, &link)?;
+
+    let mut at = Vec::new();
+    if let Some(branch) = &link.branch {
+        at.push(format!("branch {}", branch));
+    }
+    if let Some(commit) = &link.commit {
+        at.push(format!("commit {}", short_commit(commit)));
+    }
+    println!(
+        "{} Linked session {} to {}{}",
+        "[+]".green(),
+        session_id,
+        link.reference().unwrap_or_else(|| link.repo.clone()),
+        if at.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", at.join(", "))
+        }
+    );
+    Ok(())
+}
+
+/// List sessions linked to GitHub, optionally only those linked to a
+/// repository or to one of its pull requests or issues
+pub fn github_links(
+    db_path: Option<&str>,
+    repo: Option<&str>,
+    reference: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    let conn = open_db(db_path)?;
+    let repo = repo.map(repo_arg).transpose()?;
+    let (repo, number) = match reference {
+        Some(reference) => {
+            let (repo, number) = parse_reference(reference, repo.as_deref())
+                .with_context(|| format!("Not an issue or pull request: {}", reference))?;
+            (Some(repo), Some(number))
+        }
+        None => (repo, None),
+    };
+    let links = list_links(&conn, repo.as_deref(), number)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&links)?);
+        return Ok(());
+    }
+    println!("\n{} GitHub Links", "[*]".blue().bold());
+    println!("{}", "=".repeat(60));
+    if links.is_empty() {
+        println!(
+            "\n{} No linked sessions; link one with 'csm github link <session>'",
+            "[!]".yellow()
+        );
+        return Ok(());
+    }
+    let rows: Vec<LinkRow> = links
+        .iter()
+        .map(|stored| LinkRow {
+            session: truncate(&stored.session_id, 12),
+            title: truncate(&stored.session_title, 40),
+            target: stored
+                .link
+                .reference()
+                .unwrap_or_else(|| stored.link.repo.clone()),
+            branch: stored.link.branch.clone().unwrap_or_default(),
+            commit: stored
+                .link
+                .commit
+                .as_deref()
+                .map(short_commit)
+                .unwrap_or_default()
+                .to_string(),
+        })
+        .collect();
+    println!("{}", Table::new(rows).with(TableStyle::ascii_rounded()));
+    Ok(())
+}
+
+/// Post a summary of a harvested session as a comment on its pull request
+/// or issue, or on `reference`
+pub fn github_comment(
+    db_path: Option<&str>,
+    session: &str,
+    reference: Option<&str>,
+    dry_run: bool,
+) -> Result<()> {
+    let conn = open_db(db_path)?;
+    let (session_id, provider, chat) = find_harvested(&conn, session)?;
+    let link = get_link(&conn, &session_id)?;
+    let (repo, number) = match reference {
+        Some(reference) => parse_reference(reference, link.as_ref().map(|l| l.repo.as_str()))
+            .with_context(|| format!("Not an issue or pull request: {}", reference))?,
+        None => link
+            .as_ref()
+            .and_then(|l| Some((l.repo.clone(), l.pull_request.or(l.issue)?)))
+            .with_context(|| {
+                format!(
+                    "Session {} is not linked to a pull request or issue; link it with \
+                     'csm github link {} --pr <number>' or name one",
+                    session_id, session_id
+                )
+            })?,
+    };
+    let mut summary = SessionSummary::new(&session_id, &provider, &chat);
+    if let Some(link) = &link {
+        match (&link.branch, &link.commit) {
+            (Some(branch), Some(commit)) => {
+                summary
+                    .about
+                    .push_str(&format!(", on `{}` at {}", branch, short_commit(commit)))
+            }
+            (Some(branch), None) => summary.about.push_str(&format!(", on `{}`", branch)),
+            (None, Some(commit)) => summary
+                .about
+                .push_str(&format!(", at {}", short_commit(commit))),
+            (None, None) => {}
+        }
+    }
+    let body = summary.to_markdown();
+    if dry_run {
+        print!("{}", body);
+        println!(
+            "\n{} Dry run: nothing was posted to {}#{}",
+            "[i]".blue(),
+            repo,
+            number
+        );
+        return Ok(());
+    }
+
+    let client = GitHubClient::from_env();
+    if !client.has_token() {
+        bail!(
+            "Set GITHUB_TOKEN to a GitHub token that can comment on {}",
+            repo
+        );
+    }
+    let result = runtime()?.block_on(client.post_comment(&repo, number, &body));
+    if !result.success {
+        bail!(
+            "Could not comment on {}#{}: {}",
+            repo,
+            number,
+            result.error.unwrap_or_default()
+        );
+    }
+    let url = result
+        .data
+        .as_ref()
+        .and_then(|comment| comment["html_url"].as_str())
+        .unwrap_or_default()
+        .to_string();
+    println!(
+        "{} Commented on {}#{} {}",
+        "[+]".green(),
+        repo,
+        number,
+        url.dimmed()
+    );
+    Ok(())
+}
+
+/// Import an issue or pull request discussion as a harvested session
+pub fn github_import(db_path: Option<&str>, reference: &str, repo: Option<&str>) -> Result<()> {
+    let conn = open_db(db_path)?;
+    let default_repo = match repo {
+        Some(repo) => Some(repo_arg(repo)?),
+        None => SessionLink::detect(&std::env::current_dir()?).map(|link| link.repo),
+    };
+    let (repo, number) =
+        parse_reference(reference, default_repo.as_deref()).with_context(|| {
+            format!(
+                "Not an issue or pull request: {}; give it as owner/repo#12 or a github.com URL",
+                reference
+            )
+        })?;
+
+    let client = GitHubClient::from_env();
+    let (chat, link) = runtime()?
+        .block_on(client.thread_session(&repo, number))
+        .map_err(|e| anyhow!("Could not read {}#{}: {}", repo, number, e))?;
+    let session_id = chat.session_id.clone().unwrap_or_default();
+    let updated = insert_or_update_session(&conn, &chat, PROVIDER, None, Some(&repo))?;
+    save_link(&conn, &session_id, &link)?;
+    println!(
+        "{} {} {}#{} as session {} ({} posts)",
+        "[+]".green(),
+        if updated { "Updated" } else { "Imported" },
+        repo,
+        number,
+        session_id,
+        chat.requests.len()
+    );
+    Ok(())
+}
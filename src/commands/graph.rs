@@ -0,0 +1,268 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Knowledge graph commands
+
+use anyhow::{bail, Context, Result};
+use colored::*;
+use rusqlite::Connection;
+use serde::Serialize;
+use tabled::{settings::Style as TableStyle, Table, Tabled};
+
+use super::harvest::open_harvest_db;
+use crate::graph::{
+    ensure_graph_tables, find_nodes, index_session, load_graph, neighbors, prune_nodes, relation,
+    EntityKind, ExportFormat, SessionGraph,
+};
+use crate::providers::GenericSession;
+use crate::storage::parse_session_json;
+use crate::text::truncate;
+
+#[derive(Tabled)]
+struct NeighborRow {
+    #[tabled(rename = "Kind")]
+    kind: String,
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Relation")]
+    relation: String,
+    #[tabled(rename = "Weight")]
+    weight: i64,
+}
+
+fn open_db(db_path: Option<&str>) -> Result<Connection> {
+    let conn = open_harvest_db(db_path)?;
+    ensure_graph_tables(&conn)?;
+    Ok(conn)
+}
+
+fn parse_kinds(kind: Option<&str>) -> Result<Vec<EntityKind>> {
+    let Some(kind) = kind else {
+        return Ok(Vec::new());
+    };
+    kind.split(',')
+        .map(|kind| {
+            EntityKind::parse(kind.trim()).with_context(|| {
+                format!(
+                    "Unknown kind '{}'; use session, file, crate, api, person or error",
+                    kind.trim()
+                )
+            })
+        })
+        .collect()
+}
+
+/// What a pass of `csm graph build` did
+#[derive(Debug, Default, Serialize)]
+pub struct GraphBuild {
+    pub sessions: usize,
+    pub edges: usize,
+    pub nodes: i64,
+    /// Nodes no session mentions any more
+    pub pruned: usize,
+}
+
+/// Read harvested sessions for entities and the links between them, and
+/// store them as the knowledge graph
+pub fn graph_build(
+    db_path: Option<&str>,
+    provider: Option<&str>,
+    session: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    let mut conn = open_db(db_path)?;
+    let sessions = {
+        let mut stmt = conn.prepare(
+            "SELECT id, COALESCE(title, ''), session_json FROM sessions
+             WHERE (?1 IS NULL OR provider = ?1)
+               AND (?2 IS NULL OR id = ?2 OR id LIKE ?2 || '%')",
+        )?;
+        let rows = stmt.query_map([provider, session], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()?
+    };
+    if let (Some(session), true) = (session, sessions.is_empty()) {
+        bail!("No harvested session with ID {}", session);
+    }
+
+    let mut build = GraphBuild::default();
+    let tx = conn.transaction()?;
+    for (id, title, session_json) in &sessions {
+        let Ok(session) = parse_session_json(session_json) else {
+            continue;
+        };
+        let mut graph = SessionGraph::new();
+        if let Some(requester) = &session.requester_username {
+            graph.add_participant(requester);
+        }
+        let title = if title.is_empty() {
+            session.title()
+        } else {
+            title.clone()
+        };
+        for message in GenericSession::from(session).messages {
+            graph.add_message(&message.content);
+        }
+        build.edges += index_session(&tx, id, &title, &graph)?;
+        build.sessions += 1;
+    }
+    build.pruned = prune_nodes(&tx)?;
+    build.nodes = tx.query_row("SELECT COUNT(*) FROM graph_nodes", [], |row| row.get(0))?;
+    tx.commit()?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&build)?);
+        return Ok(());
+    }
+    println!(
+        "{} Read {} session(s) into {} link(s)",
+        "[+]".green(),
+        build.sessions,
+        build.edges
+    );
+    println!("    {} node(s) in the graph", build.nodes);
+    println!(
+        "{}",
+        "[i] Explore it with 'csm graph query <name>'".dimmed()
+    );
+    Ok(())
+}
+
+/// Show a node of the graph and what it is linked to
+pub fn graph_query(
+    db_path: Option<&str>,
+    name: &str,
+    kind: Option<&str>,
+    limit: usize,
+    json: bool,
+) -> Result<()> {
+    let conn = open_db(db_path)?;
+    let kind = match kind {
+        Some(kind) => Some(parse_kinds(Some(kind))?[0]),
+        None => None,
+    };
+    let nodes = find_nodes(&conn, name, kind)?;
+    let Some(node) = nodes.first() else {
+        bail!(
+            "Nothing named '{}' in the graph; build it with 'csm graph build'",
+            name
+        );
+    };
+    if nodes.len() > 1 {
+        if json {
+            println!("{}", serde_json::to_string_pretty(&nodes)?);
+            return Ok(());
+        }
+        println!(
+            "\n{} {} nodes match '{}':",
+            "[!]".yellow(),
+            nodes.len(),
+            name
+        );
+        for node in &nodes {
+            println!("  {:<8} {}", node.kind, truncate(node.display_name(), 70));
+        }
+        println!(
+            "{}",
+            "[i] Use the full name, or narrow with --kind".dimmed()
+        );
+        return Ok(());
+    }
+
+    let linked = neighbors(&conn, node.id, limit)?;
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "node": node,
+                "neighbors": linked,
+            }))?
+        );
+        return Ok(());
+    }
+    println!(
+        "\n{} {} {}",
+        "[*]".blue().bold(),
+        node.kind,
+        node.display_name().bold()
+    );
+    println!("{}", "=".repeat(60));
+    if node.kind != EntityKind::Session.as_str() {
+        let sessions = linked
+            .iter()
+            .filter(|n| n.node.kind == EntityKind::Session.as_str())
+            .filter(|n| {
+                [relation::MENTIONS, relation::PARTICIPATED_IN].contains(&n.relation.as_str())
+            })
+            .count();
+        println!("Mentioned in {} session(s)", sessions);
+    }
+    if linked.is_empty() {
+        println!("\n{} No links", "[!]".yellow());
+        return Ok(());
+    }
+    let rows: Vec<NeighborRow> = linked
+        .iter()
+        .map(|n| NeighborRow {
+            kind: n.node.kind.clone(),
+            name: truncate(n.node.display_name(), 60),
+            relation: n.relation.clone(),
+            weight: n.weight,
+        })
+        .collect();
+    println!("{}", Table::new(rows).with(TableStyle::ascii_rounded()));
+    Ok(())
+}
+
+/// Write the graph as GraphML, DOT or JSON
+pub fn graph_export(
+    db_path: Option<&str>,
+    format: &str,
+    output: Option<&str>,
+    kind: Option<&str>,
+    min_weight: i64,
+    no_sessions: bool,
+) -> Result<()> {
+    let conn = open_db(db_path)?;
+    let Some(format) = ExportFormat::parse(format) else {
+        bail!("Unknown format '{}'; use graphml, dot or json", format);
+    };
+    let mut kinds = parse_kinds(kind)?;
+    if no_sessions {
+        if kinds.is_empty() {
+            kinds = vec![
+                EntityKind::File,
+                EntityKind::Crate,
+                EntityKind::Api,
+                EntityKind::Person,
+                EntityKind::Error,
+            ];
+        } else {
+            kinds.retain(|kind| *kind != EntityKind::Session);
+        }
+    }
+    let graph = load_graph(&conn, &kinds, min_weight)?;
+    if graph.nodes.is_empty() {
+        bail!("The graph is empty; build it with 'csm graph build'");
+    }
+
+    let text = format.render(&graph);
+    match output {
+        Some(path) => {
+            std::fs::write(path, text).with_context(|| format!("Cannot write {}", path))?;
+            println!(
+                "{} Exported {} node(s) and {} edge(s) to {}",
+                "[+]".green(),
+                graph.nodes.len(),
+                graph.edges.len(),
+                path
+            );
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
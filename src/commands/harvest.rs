@@ -9,19 +9,33 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use colored::*;
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::ops::ControlFlow;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::Duration;
 
+use super::providers::provider_registry;
+use crate::backup::{backup_file, BackupKind};
 use crate::browser::{get_installed_browsers, scan_browser_auth, BrowserType};
+use crate::config::{self, StorageRoot};
+use crate::datetime::{format_date, format_datetime, format_datetime_short};
 use crate::database::{ChatDatabase, ShareLinkParser};
+use crate::language;
 use crate::models::ChatSession;
+use crate::notifications::{self, Notification, NotificationLevel};
+use crate::providers::diagnostics::{self, ProviderStatus};
 use crate::providers::{ProviderRegistry, ProviderType};
-use crate::storage::parse_session_json;
-use crate::workspace::{discover_workspaces, get_chat_sessions_from_workspace};
+use crate::reviews;
+use crate::search::{self, HttpEmbedder, SearchMode, SearchOptions};
+use crate::storage::{parse_session_file, parse_session_json};
+use crate::storage::watcher::{SessionEvent, SessionEventKind, SessionWatcher};
+use crate::text::{prefix_chars, snippet, truncate};
+use crate::workspace::{
+    discover_workspaces, get_workspace_storage_path, scan_chat_sessions_from_workspace,
+};
 
 /// Check if a string is an empty code block marker (just ``` with no content)
 fn is_empty_code_block(s: &str) -> bool {
@@ -335,7 +349,7 @@ impl Default for HarvestConfig {
 }
 
 /// Statistics from a harvest operation
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct HarvestStats {
     pub providers_scanned: usize,
     pub workspaces_scanned: usize,
@@ -346,6 +360,33 @@ pub struct HarvestStats {
     pub errors: Vec<String>,
 }
 
+/// Progress reported after each provider or workspace is harvested
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarvestProgress {
+    /// Provider or workspace just finished
+    pub source: String,
+    pub sources_done: usize,
+    pub sources_total: usize,
+    pub sessions_found: usize,
+    pub sessions_added: usize,
+    pub sessions_updated: usize,
+    pub errors: usize,
+}
+
+impl HarvestProgress {
+    fn new(source: impl Into<String>, done: usize, total: usize, stats: &HarvestStats) -> Self {
+        Self {
+            source: source.into(),
+            sources_done: done,
+            sources_total: total,
+            sessions_found: stats.sessions_found,
+            sessions_added: stats.sessions_added,
+            sessions_updated: stats.sessions_updated,
+            errors: stats.errors.len(),
+        }
+    }
+}
+
 /// A harvested session record
 #[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -418,7 +459,7 @@ pub fn harvest_scan(
     println!("\n{} Scanning for Providers", "[H]".magenta().bold());
     println!("{}", "=".repeat(60));
 
-    let registry = ProviderRegistry::new();
+    let registry = provider_registry();
     let mut total_sessions = 0;
     let mut available_providers = Vec::new();
 
@@ -445,53 +486,38 @@ pub fn harvest_scan(
         ProviderType::Gemini,
     ];
 
+    let mut problem_count = 0;
     for pt in &provider_types {
-        if let Some(provider) = registry.get_provider(*pt) {
-            let available = provider.is_available();
-            let session_count = if available {
-                provider.list_sessions().map(|s| s.len()).unwrap_or(0)
-            } else {
-                0
-            };
-
-            if available {
-                available_providers.push((*pt, session_count));
-                total_sessions += session_count;
+        // VS Code sessions are covered by the workspace scan below
+        if *pt == ProviderType::Copilot {
+            continue;
+        }
 
-                let status = if session_count > 0 {
-                    format!(
-                        "{} {} sessions",
-                        "+".green(),
-                        session_count.to_string().cyan()
-                    )
-                } else {
-                    format!("{} no sessions", "+".green())
-                };
+        let diagnostic = diagnostics::diagnose(&registry, *pt);
+        if diagnostic.status.is_problem() {
+            problem_count += 1;
+        }
+        let found = matches!(
+            diagnostic.status,
+            ProviderStatus::Ready | ProviderStatus::Partial
+        );
+        if !found && !diagnostic.status.is_problem() && !verbose {
+            continue;
+        }
 
-                println!(
-                    "   {} {}: {}",
-                    "[+]".green(),
-                    provider.name().bold(),
-                    status
-                );
+        diagnostics::print_diagnostic(&diagnostic, verbose);
+        if found {
+            available_providers.push((*pt, diagnostic.sessions));
+            total_sessions += diagnostic.sessions;
+        }
 
-                if show_sessions && session_count > 0 {
-                    if let Ok(sessions) = provider.list_sessions() {
-                        for session in sessions.iter().take(3) {
-                            println!("      {} {}", "`".dimmed(), session.title().dimmed());
-                        }
-                        if sessions.len() > 3 {
-                            println!("      {} ... and {} more", "`".dimmed(), sessions.len() - 3);
-                        }
-                    }
+        if show_sessions && diagnostic.sessions > 0 {
+            if let Some(Ok(sessions)) = registry.get_provider(*pt).map(|p| p.list_sessions()) {
+                for session in sessions.iter().take(3) {
+                    println!("      {} {}", "`".dimmed(), session.title().dimmed());
                 }
-
-                if let Some(path) = provider.sessions_path() {
-                    println!(
-                        "      {} {}",
-                        "`".dimmed(),
-                        path.display().to_string().dimmed()
-                    );
+                if sessions.len() > 3 {
+                    println!("      {} ... and {} more", "`".dimmed(), sessions.len() - 3);
                 }
             }
         }
@@ -544,7 +570,12 @@ pub fn harvest_scan(
             total_sessions += ws_sessions;
         }
         Err(e) => {
+            problem_count += 1;
             println!("   {} Failed to scan workspaces: {}", "[!]".yellow(), e);
+            let diagnostic = diagnostics::diagnose(&registry, ProviderType::Copilot);
+            if let Some(suggestion) = diagnostic.suggestion {
+                println!("      {} {}", "[->]".cyan(), suggestion);
+            }
         }
     }
 
@@ -603,6 +634,12 @@ pub fn harvest_scan(
         "   {} total sessions to harvest",
         total_sessions.to_string().cyan()
     );
+    if problem_count > 0 {
+        println!(
+            "   {} provider(s) could not be fully read, see above",
+            problem_count.to_string().yellow()
+        );
+    }
 
     Ok(())
 }
@@ -616,6 +653,29 @@ pub fn harvest_run(
     auto_commit: bool,
     message: Option<&str>,
 ) -> Result<()> {
+    harvest_run_with_progress(
+        path,
+        providers,
+        exclude,
+        incremental,
+        auto_commit,
+        message,
+        &mut |_| ControlFlow::Continue(()),
+    )?;
+    Ok(())
+}
+
+/// Run the harvest, calling `on_progress` after each provider and workspace.
+/// Returning `ControlFlow::Break` from it stops the harvest with an error.
+pub fn harvest_run_with_progress(
+    path: Option<&str>,
+    providers: Option<&[String]>,
+    exclude: Option<&[String]>,
+    incremental: bool,
+    auto_commit: bool,
+    message: Option<&str>,
+    on_progress: &mut dyn FnMut(&HarvestProgress) -> ControlFlow<()>,
+) -> Result<HarvestStats> {
     let db_path = get_db_path(path)?;
 
     println!("\n{} Running Harvest", "[H]".magenta().bold());
@@ -628,28 +688,35 @@ pub fn harvest_run(
     }
 
     let conn = Connection::open(&db_path)?;
+    ensure_fts_tables(&conn)?;
+    ensure_message_chunks_table(&conn)?;
+    reviews::ensure_tags_table(&conn)?;
     let mut stats = HarvestStats::default();
 
     // Get last harvest time for incremental updates
-    let last_harvest: Option<i64> = if incremental {
-        conn.query_row("SELECT MAX(harvested_at) FROM sessions", [], |row| {
+    let started_at = Utc::now().timestamp_millis();
+    let previous_harvest: Option<i64> = conn
+        .query_row("SELECT MAX(harvested_at) FROM sessions", [], |row| {
             row.get(0)
         })
         .ok()
-    } else {
-        None
-    };
+        .flatten();
+    let last_harvest = previous_harvest.filter(|_| incremental);
 
     if let Some(ts) = last_harvest {
         let dt = DateTime::from_timestamp_millis(ts)
-            .map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string())
+            .map(format_datetime)
             .unwrap_or_default();
         println!("{} Incremental harvest since: {}", "[*]".blue(), dt);
     }
 
-    let registry = ProviderRegistry::new();
-    let include_providers = providers.map(|p| p.to_vec());
-    let exclude_providers = exclude.map(|p| p.to_vec()).unwrap_or_default();
+    let registry = ProviderRegistry::cached(&db_path);
+    let defaults = config::current().defaults();
+    let include_providers = providers.map(|p| p.to_vec()).or(defaults.harvest_providers);
+    let exclude_providers = exclude
+        .map(|p| p.to_vec())
+        .or(defaults.harvest_exclude)
+        .unwrap_or_default();
 
     // Harvest from LLM providers
     println!("\n{} Harvesting from providers...", "[*]".blue());
@@ -668,30 +735,55 @@ pub fn harvest_run(
         ProviderType::Llamafile,
     ];
 
-    for pt in &provider_types {
-        let provider_name = pt.display_name().to_lowercase();
+    let selected: Vec<ProviderType> = provider_types
+        .into_iter()
+        .filter(|pt| {
+            let provider_name = pt.display_name().to_lowercase();
 
-        // Check include/exclude filters
-        if let Some(ref include) = include_providers {
-            if !include
+            // Check include/exclude filters
+            if let Some(ref include) = include_providers {
+                if !include
+                    .iter()
+                    .any(|p| provider_name.contains(&p.to_lowercase()))
+                {
+                    return false;
+                }
+            }
+            !exclude_providers
                 .iter()
                 .any(|p| provider_name.contains(&p.to_lowercase()))
-            {
-                continue;
-            }
-        }
-        if exclude_providers
-            .iter()
-            .any(|p| provider_name.contains(&p.to_lowercase()))
-        {
-            continue;
-        }
+        })
+        .filter(|pt| {
+            registry
+                .get_provider(*pt)
+                .is_some_and(|provider| provider.is_available())
+        })
+        .collect();
 
-        if let Some(provider) = registry.get_provider(*pt) {
-            if !provider.is_available() {
-                continue;
-            }
+    let harvest_workspaces = include_providers.is_none()
+        || include_providers
+            .as_ref()
+            .map(|p| {
+                p.iter()
+                    .any(|x| x == "copilot" || x == "vscode" || x == "workspace")
+            })
+            .unwrap_or(false);
+    let workspaces = if harvest_workspaces {
+        discover_workspaces().ok().map(|workspaces| {
+            workspaces
+                .into_iter()
+                .filter(|ws| ws.chat_session_count > 0)
+                .collect::<Vec<_>>()
+        })
+    } else {
+        None
+    };
+
+    let sources_total = selected.len() + workspaces.as_ref().map_or(0, |w| w.len());
+    let mut sources_done = 0;
 
+    for pt in &selected {
+        if let Some(provider) = registry.get_provider(*pt) {
             stats.providers_scanned += 1;
 
             match provider.list_sessions() {
@@ -741,71 +833,79 @@ pub fn harvest_run(
                     stats.errors.push(format!("{}: {}", provider.name(), e));
                 }
             }
+
+            sources_done += 1;
+            let progress =
+                HarvestProgress::new(provider.name(), sources_done, sources_total, &stats);
+            if on_progress(&progress).is_break() {
+                return Err(cancel_harvest(&conn, previous_harvest, started_at, &progress));
+            }
         }
     }
 
     // Harvest from VS Code workspaces
-    if include_providers.is_none()
-        || include_providers
-            .as_ref()
-            .map(|p| {
-                p.iter()
-                    .any(|x| x == "copilot" || x == "vscode" || x == "workspace")
-            })
-            .unwrap_or(false)
-    {
+    if let Some(workspaces) = &workspaces {
         println!("\n{} Harvesting from VS Code workspaces...", "[*]".blue());
 
-        if let Ok(workspaces) = discover_workspaces() {
-            for ws in &workspaces {
-                if ws.chat_session_count == 0 {
-                    continue;
-                }
+        for ws in workspaces {
+            stats.workspaces_scanned += 1;
 
-                stats.workspaces_scanned += 1;
+            if let Ok((sessions, failures)) =
+                scan_chat_sessions_from_workspace(&ws.workspace_path)
+            {
+                for (path, e) in failures {
+                    stats
+                        .errors
+                        .push(format!("{}: {}", path.display(), e));
+                }
 
-                if let Ok(sessions) = get_chat_sessions_from_workspace(&ws.workspace_path) {
-                    for swp in sessions {
-                        stats.sessions_found += 1;
+                for swp in sessions {
+                    stats.sessions_found += 1;
 
-                        // Check if session should be skipped (incremental)
-                        if let Some(last) = last_harvest {
-                            if swp.session.last_message_date <= last {
-                                stats.sessions_skipped += 1;
-                                continue;
-                            }
+                    // Check if session should be skipped (incremental)
+                    if let Some(last) = last_harvest {
+                        if swp.session.last_message_date <= last {
+                            stats.sessions_skipped += 1;
+                            continue;
                         }
+                    }
 
-                        let ws_name = ws.project_path.clone();
-
-                        match insert_or_update_session(
-                            &conn,
-                            &swp.session,
-                            "GitHub Copilot",
-                            Some(&ws.hash),
-                            ws_name.as_deref(),
-                        ) {
-                            Ok(updated) => {
-                                if updated {
-                                    stats.sessions_updated += 1;
-                                } else {
-                                    stats.sessions_added += 1;
-                                }
-                            }
-                            Err(e) => {
-                                stats.errors.push(format!("{}: {}", swp.session.title(), e));
+                    let ws_name = ws.project_path.clone();
+
+                    match insert_or_update_session(
+                        &conn,
+                        &swp.session,
+                        "GitHub Copilot",
+                        Some(&ws.hash),
+                        ws_name.as_deref(),
+                    ) {
+                        Ok(updated) => {
+                            if updated {
+                                stats.sessions_updated += 1;
+                            } else {
+                                stats.sessions_added += 1;
                             }
                         }
+                        Err(e) => {
+                            stats.errors.push(format!("{}: {}", swp.session.title(), e));
+                        }
                     }
                 }
             }
 
-            println!(
-                "   {} Workspaces: {} scanned",
-                "[+]".green(),
-                stats.workspaces_scanned.to_string().cyan()
-            );
+            sources_done += 1;
+            let source = ws.project_path.clone().unwrap_or_else(|| ws.hash.clone());
+            let progress = HarvestProgress::new(source, sources_done, sources_total, &stats);
+            if on_progress(&progress).is_break() {
+                return Err(cancel_harvest(&conn, previous_harvest, started_at, &progress));
+            }
         }
+
+        println!(
+            "   {} Workspaces: {} scanned",
+            "[+]".green(),
+            stats.workspaces_scanned.to_string().cyan()
+        );
     }
 
     // Harvest from web-based cloud providers (ChatGPT, Claude, etc.)
@@ -867,9 +967,139 @@ pub fn harvest_run(
 
     println!("\nDatabase: {}", db_path.display());
 
+    Ok(stats)
+}
+
+/// Watch VS Code workspaces and harvest sessions as they are written
+pub fn harvest_watch(path: Option<&str>, debounce_ms: u64) -> Result<()> {
+    let db_path = get_db_path(path)?;
+
+    if !db_path.exists() {
+        println!("{} Database not found, creating...", "[*]".blue());
+        create_harvest_database(&db_path)?;
+    }
+
+    let mut harvester = WatchHarvester::open(&db_path)?;
+    let storage_path = get_workspace_storage_path()?;
+    let watcher = SessionWatcher::watch(&storage_path, Duration::from_millis(debounce_ms))?;
+
+    println!("\n{} Watching for session changes", "[H]".magenta().bold());
+    println!("{}", "=".repeat(60));
+    println!("   Storage:  {}", storage_path.display());
+    println!("   Database: {}", db_path.display());
+    println!("   Press Ctrl+C to stop\n");
+
+    for event in watcher {
+        let time = crate::datetime::format(Utc::now(), "%H:%M:%S");
+
+        if event.kind == SessionEventKind::Deleted {
+            println!(
+                "{} {} Deleted on disk (kept in database): {}",
+                time.to_string().dimmed(),
+                "[-]".yellow(),
+                event.session_id
+            );
+            send_watch_notification(
+                Notification::new(
+                    "harvest.session_deleted",
+                    "Session deleted",
+                    format!("{} was removed on disk", event.session_id),
+                )
+                .with_level(NotificationLevel::Warning)
+                .with_field("session_id", event.session_id.clone()),
+            );
+            continue;
+        }
+
+        match harvester.harvest(&event) {
+            Ok((session, ws_name, updated)) => {
+                println!(
+                    "{} {} {}: {}",
+                    time.to_string().dimmed(),
+                    "[+]".green(),
+                    if updated { "Updated" } else { "Added" },
+                    session.title()
+                );
+                let (event_name, title) = if updated {
+                    ("harvest.session_updated", "Session updated")
+                } else {
+                    ("harvest.session_added", "Session harvested")
+                };
+                send_watch_notification(
+                    Notification::new(event_name, title, session.title())
+                        .with_level(NotificationLevel::Success)
+                        .with_field("session_id", event.session_id.clone())
+                        .with_field("workspace", ws_name.unwrap_or_default()),
+                );
+            }
+            Err(e) => println!("{} {} {}", time.to_string().dimmed(), "[!]".red(), e),
+        }
+    }
+
     Ok(())
 }
 
+/// Harvests the sessions a [`SessionWatcher`] reports written
+pub(crate) struct WatchHarvester {
+    conn: Connection,
+    /// Workspace hash -> project path, refreshed when an unknown hash appears
+    project_paths: std::collections::HashMap<String, Option<String>>,
+}
+
+impl WatchHarvester {
+    pub fn open(db_path: &Path) -> Result<Self> {
+        let conn = Connection::open(db_path)?;
+        ensure_fts_tables(&conn)?;
+        ensure_message_chunks_table(&conn)?;
+        reviews::ensure_tags_table(&conn)?;
+        Ok(Self {
+            conn,
+            project_paths: std::collections::HashMap::new(),
+        })
+    }
+
+    pub fn conn(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Harvest the session of a created or modified event; returns it with
+    /// its workspace's project path and whether it was harvested before
+    pub fn harvest(&mut self, event: &SessionEvent) -> Result<(ChatSession, Option<String>, bool)> {
+        let session = parse_session_file(&event.path)
+            .map_err(|e| anyhow::anyhow!("{}: {}", event.path.display(), e))?;
+
+        let ws_name = match event.workspace_hash.as_deref() {
+            Some(hash) => {
+                if !self.project_paths.contains_key(hash) {
+                    if let Ok(workspaces) = discover_workspaces() {
+                        self.project_paths
+                            .extend(workspaces.into_iter().map(|ws| (ws.hash, ws.project_path)));
+                    }
+                }
+                self.project_paths.get(hash).cloned().flatten()
+            }
+            None => None,
+        };
+
+        let updated = insert_or_update_session(
+            &self.conn,
+            &session,
+            "GitHub Copilot",
+            event.workspace_hash.as_deref(),
+            ws_name.as_deref(),
+        )
+        .map_err(|e| anyhow::anyhow!("{}: {}", session.title(), e))?;
+        Ok((session, ws_name, updated))
+    }
+}
+
+/// Deliver a watch event to subscribed channels; failures are reported but never stop watching
+fn send_watch_notification(notification: Notification) {
+    for (channel, e) in notifications::dispatch(&notification) {
+        println!("   {} Notification to {} failed: {}", "[!]".yellow(), channel, e);
+    }
+}
+
 /// Show harvest database status
 pub fn harvest_status(path: Option<&str>) -> Result<()> {
     let db_path = get_db_path(path)?;
@@ -954,17 +1184,17 @@ pub fn harvest_status(path: Option<&str>) -> Result<()> {
 
     if let Some(ts) = last_harvest {
         let dt = DateTime::from_timestamp_millis(ts)
-            .map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string())
+            .map(format_datetime)
             .unwrap_or_else(|| "Unknown".to_string());
         println!("{} Last Harvest: {}", "[*]".blue(), dt);
     }
 
     if let (Some(old), Some(new)) = (oldest, newest) {
         let old_dt = DateTime::from_timestamp_millis(old)
-            .map(|d| d.format("%Y-%m-%d").to_string())
+            .map(format_date)
             .unwrap_or_default();
         let new_dt = DateTime::from_timestamp_millis(new)
-            .map(|d| d.format("%Y-%m-%d").to_string())
+            .map(format_date)
             .unwrap_or_default();
         println!("{} Date Range: {} to {}", "[*]".blue(), old_dt, new_dt);
     }
@@ -1029,6 +1259,7 @@ pub fn harvest_list(
     provider: Option<&str>,
     limit: usize,
     search: Option<&str>,
+    topic: Option<&str>,
 ) -> Result<()> {
     let db_path = get_db_path(path)?;
 
@@ -1042,47 +1273,10 @@ pub fn harvest_list(
     }
 
     let conn = Connection::open(&db_path)?;
-
-    let mut query = String::from(
-        "SELECT id, provider, title, message_count, created_at, updated_at, workspace_name 
-         FROM sessions WHERE 1=1",
-    );
-    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-
-    if let Some(p) = provider {
-        query.push_str(" AND LOWER(provider) LIKE ?");
-        params_vec.push(Box::new(format!("%{}%", p.to_lowercase())));
+    if topic.is_some() {
+        crate::topics::ensure_topics_table(&conn)?;
     }
-
-    if let Some(s) = search {
-        query.push_str(" AND (LOWER(title) LIKE ? OR LOWER(id) LIKE ?)");
-        let pattern = format!("%{}%", s.to_lowercase());
-        params_vec.push(Box::new(pattern.clone()));
-        params_vec.push(Box::new(pattern));
-    }
-
-    query.push_str(" ORDER BY updated_at DESC LIMIT ?");
-    params_vec.push(Box::new(limit as i64));
-
-    let mut stmt = conn.prepare(&query)?;
-
-    // Build params slice
-    let params_slice: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|b| b.as_ref()).collect();
-
-    let sessions: Vec<HarvestQueryResult> = stmt
-        .query_map(params_slice.as_slice(), |row| {
-            Ok((
-                row.get(0)?,
-                row.get(1)?,
-                row.get(2)?,
-                row.get(3)?,
-                row.get(4)?,
-                row.get(5)?,
-                row.get(6)?,
-            ))
-        })?
-        .filter_map(|r| r.ok())
-        .collect();
+    let sessions = list_harvested_sessions(&conn, provider, limit, search, topic)?;
 
     println!("\n{} Harvested Sessions", "[H]".magenta().bold());
     println!("{}", "=".repeat(60));
@@ -1094,11 +1288,11 @@ pub fn harvest_list(
 
     for (id, prov, title, msg_count, _created, updated, ws_name) in &sessions {
         let date = DateTime::from_timestamp_millis(*updated)
-            .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+            .map(format_datetime_short)
             .unwrap_or_default();
 
         println!("\n{} {}", "[S]".cyan(), title.bold());
-        println!("   ID: {}", &id[..16.min(id.len())].dimmed());
+        println!("   ID: {}", prefix_chars(id, 16).dimmed());
         println!(
             "   Provider: {} | Messages: {} | Updated: {}",
             prov.cyan(),
@@ -1119,6 +1313,105 @@ pub fn harvest_list(
     Ok(())
 }
 
+/// Harvested sessions, most recently updated first, whose provider matches
+/// `provider`, whose title or ID contains `search`, and that are filed under
+/// `topic` or one of its subtopics
+pub(crate) fn list_harvested_sessions(
+    conn: &Connection,
+    provider: Option<&str>,
+    limit: usize,
+    search: Option<&str>,
+    topic: Option<&str>,
+) -> Result<Vec<HarvestQueryResult>> {
+    page_harvested_sessions(conn, provider, search, topic, 0, limit)
+}
+
+/// Like [`list_harvested_sessions`], skipping the first `offset` sessions
+pub(crate) fn page_harvested_sessions(
+    conn: &Connection,
+    provider: Option<&str>,
+    search: Option<&str>,
+    topic: Option<&str>,
+    offset: usize,
+    limit: usize,
+) -> Result<Vec<HarvestQueryResult>> {
+    let (filter, mut params_vec) = harvest_filter(provider, search, topic);
+    let query = format!(
+        "SELECT id, provider, title, message_count, created_at, updated_at, workspace_name 
+         FROM sessions WHERE 1=1{} ORDER BY updated_at DESC, id LIMIT ? OFFSET ?",
+        filter
+    );
+    params_vec.push(Box::new(limit as i64));
+    params_vec.push(Box::new(offset as i64));
+
+    let mut stmt = conn.prepare(&query)?;
+
+    // Build params slice
+    let params_slice: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|b| b.as_ref()).collect();
+
+    let sessions = stmt
+        .query_map(params_slice.as_slice(), |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+                row.get(6)?,
+            ))
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(sessions)
+}
+
+/// How many sessions [`list_harvested_sessions`] could return
+pub(crate) fn count_harvested_sessions(
+    conn: &Connection,
+    provider: Option<&str>,
+    search: Option<&str>,
+    topic: Option<&str>,
+) -> Result<usize> {
+    let (filter, params_vec) = harvest_filter(provider, search, topic);
+    let query = format!("SELECT COUNT(*) FROM sessions WHERE 1=1{}", filter);
+    let params_slice: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|b| b.as_ref()).collect();
+    let count: i64 = conn.query_row(&query, params_slice.as_slice(), |row| row.get(0))?;
+    Ok(count as usize)
+}
+
+/// SQL conditions and parameters selecting sessions by provider, search and
+/// topic
+fn harvest_filter(
+    provider: Option<&str>,
+    search: Option<&str>,
+    topic: Option<&str>,
+) -> (String, Vec<Box<dyn rusqlite::ToSql>>) {
+    let mut filter = String::new();
+    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+    if let Some(p) = provider {
+        filter.push_str(" AND LOWER(provider) LIKE ?");
+        params_vec.push(Box::new(format!("%{}%", p.to_lowercase())));
+    }
+
+    if let Some(s) = search {
+        filter.push_str(" AND (LOWER(title) LIKE ? OR LOWER(id) LIKE ?)");
+        let pattern = format!("%{}%", s.to_lowercase());
+        params_vec.push(Box::new(pattern.clone()));
+        params_vec.push(Box::new(pattern));
+    }
+
+    if let Some(t) = topic {
+        filter.push_str(&format!(" AND {}", crate::topics::topic_condition("sessions.id", "?")));
+        // The condition names the topic twice
+        params_vec.push(Box::new(t.to_lowercase()));
+        params_vec.push(Box::new(t.to_lowercase()));
+    }
+
+    (filter, params_vec)
+}
+
 /// Export sessions from the harvest database
 pub fn harvest_export(
     path: Option<&str>,
@@ -1128,7 +1421,7 @@ pub fn harvest_export(
     session_ids: Option<&[String]>,
 ) -> Result<()> {
     let db_path = get_db_path(path)?;
-    let output_path = PathBuf::from(output);
+    let output_path = config::resolve_export_path(output);
 
     if !db_path.exists() {
         anyhow::bail!("Database not found: {}", db_path.display());
@@ -1139,6 +1432,38 @@ pub fn harvest_export(
     println!("\n{} Exporting Sessions", "[H]".magenta().bold());
     println!("{}", "=".repeat(60));
 
+    let sessions = export_session_json(&conn, provider, session_ids)?;
+    if sessions.is_empty() {
+        println!("{} No sessions to export", "[i]".dimmed());
+        return Ok(());
+    }
+    let content = render_export(&sessions, format)?;
+
+    // Create output directory if needed
+    if let Some(parent) = output_path.parent() {
+        if !parent.exists() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    fs::write(&output_path, content)?;
+
+    println!(
+        "{} Exported {} sessions to {}",
+        "[+]".green(),
+        sessions.len().to_string().cyan(),
+        output_path.display()
+    );
+
+    Ok(())
+}
+
+/// `session_json` of the harvested sessions matching `provider` and
+/// `session_ids`
+pub(crate) fn export_session_json(
+    conn: &Connection,
+    provider: Option<&str>,
+    session_ids: Option<&[String]>,
+) -> Result<Vec<String>> {
     // Build query
     let mut query = String::from("SELECT session_json FROM sessions WHERE 1=1");
     let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
@@ -1159,23 +1484,15 @@ pub fn harvest_export(
     let mut stmt = conn.prepare(&query)?;
     let params_slice: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|b| b.as_ref()).collect();
 
-    let sessions: Vec<String> = stmt
+    let sessions = stmt
         .query_map(params_slice.as_slice(), |row| row.get(0))?
         .filter_map(|r| r.ok())
         .collect();
+    Ok(sessions)
+}
 
-    if sessions.is_empty() {
-        println!("{} No sessions to export", "[i]".dimmed());
-        return Ok(());
-    }
-
-    // Create output directory if needed
-    if let Some(parent) = output_path.parent() {
-        if !parent.exists() {
-            fs::create_dir_all(parent)?;
-        }
-    }
-
+/// Render sessions' `session_json` as a `json`, `jsonl`, or `md` export
+pub(crate) fn render_export(sessions: &[String], format: &str) -> Result<String> {
     match format.to_lowercase().as_str() {
         "json" => {
             // Export as JSON array
@@ -1183,28 +1500,23 @@ pub fn harvest_export(
                 .iter()
                 .filter_map(|s| serde_json::from_str(s).ok())
                 .collect();
-            let json_output = serde_json::to_string_pretty(&parsed)?;
-            fs::write(&output_path, json_output)?;
+            Ok(serde_json::to_string_pretty(&parsed)?)
         }
         "jsonl" => {
             // Export as JSON Lines
-            let content: String = sessions
+            Ok(sessions
                 .iter()
                 .filter_map(|s| serde_json::from_str::<serde_json::Value>(s).ok())
                 .map(|v| serde_json::to_string(&v).unwrap_or_default())
                 .collect::<Vec<_>>()
-                .join("\n");
-            fs::write(&output_path, content)?;
+                .join("\n"))
         }
         "md" | "markdown" => {
             // Export as Markdown
             let mut md_content = String::from("# Chat Sessions Export\n\n");
-            md_content.push_str(&format!(
-                "Exported: {}\n\n",
-                Utc::now().format("%Y-%m-%d %H:%M:%S")
-            ));
+            md_content.push_str(&format!("Exported: {}\n\n", format_datetime(Utc::now())));
 
-            for session_json in &sessions {
+            for session_json in sessions {
                 if let Ok(session) = parse_session_json(session_json) {
                     md_content.push_str(&format!("## {}\n\n", session.title()));
                     md_content.push_str(&format!("Messages: {}\n\n", session.request_count()));
@@ -1239,21 +1551,12 @@ pub fn harvest_export(
                     md_content.push_str("---\n\n");
                 }
             }
-            fs::write(&output_path, md_content)?;
+            Ok(md_content)
         }
         _ => {
             anyhow::bail!("Unknown format: {}. Supported: json, jsonl, md", format);
         }
     }
-
-    println!(
-        "{} Exported {} sessions to {}",
-        "[+]".green(),
-        sessions.len().to_string().cyan(),
-        output_path.display()
-    );
-
-    Ok(())
 }
 
 /// Git operations for harvest database
@@ -1356,9 +1659,8 @@ pub fn harvest_git_restore(path: Option<&str>, commit: &str) -> Result<()> {
     println!("{}", "=".repeat(60));
 
     // Create backup first
-    let backup_path = db_path.with_extension("db.backup");
     if db_path.exists() {
-        fs::copy(&db_path, &backup_path)?;
+        let backup_path = backup_file(BackupKind::HarvestDatabase, &db_path)?;
         println!(
             "{} Created backup: {}",
             "[+]".green(),
@@ -1391,20 +1693,60 @@ pub fn harvest_git_restore(path: Option<&str>, commit: &str) -> Result<()> {
 // Helper Functions
 // ============================================================================
 
-fn get_db_path(path: Option<&str>) -> Result<PathBuf> {
+/// Stop a harvest part way. Sessions stored so far stay, but their harvest
+/// time goes back to the previous harvest's, so the next incremental harvest
+/// still visits the sources this one skipped.
+fn cancel_harvest(
+    conn: &Connection,
+    previous_harvest: Option<i64>,
+    started_at: i64,
+    progress: &HarvestProgress,
+) -> anyhow::Error {
+    if let Err(e) = conn.execute(
+        "UPDATE sessions SET harvested_at = ?1 WHERE harvested_at >= ?2",
+        params![previous_harvest.unwrap_or(0), started_at],
+    ) {
+        return e.into();
+    }
+    anyhow::anyhow!(
+        "Harvest cancelled after {} of {} sources",
+        progress.sources_done,
+        progress.sources_total
+    )
+}
+
+pub(crate) fn get_db_path(path: Option<&str>) -> Result<PathBuf> {
     if let Some(p) = path {
         return Ok(PathBuf::from(p));
     }
 
-    // Check environment variable
-    if let Ok(p) = std::env::var("CSM_HARVEST_DB") {
-        return Ok(PathBuf::from(p));
+    // Check CSM_HARVEST_DB, then the active profile and config file
+    if let Some(p) = config::storage_root(StorageRoot::HarvestDb) {
+        return Ok(p);
     }
 
     // Default to current directory
     Ok(std::env::current_dir()?.join("chat_sessions.db"))
 }
 
+/// [`get_db_path`] for commands that read a harvest database that must exist
+pub(crate) fn existing_db_path(path: Option<&str>) -> Result<PathBuf> {
+    let db_path = get_db_path(path)?;
+    if !db_path.exists() {
+        anyhow::bail!(
+            "Harvest database not found: {}\nRun 'csm harvest run' first.",
+            db_path.display()
+        );
+    }
+    Ok(db_path)
+}
+
+/// Open the harvest database at `path`, or the configured one, which must exist
+pub(crate) fn open_harvest_db(path: Option<&str>) -> Result<Connection> {
+    let db_path = existing_db_path(path)?;
+    Connection::open(&db_path).with_context(|| format!("Failed to open {}", db_path.display()))
+}
+
 fn create_harvest_database(path: &Path) -> Result<()> {
     let conn = Connection::open(path)?;
 
@@ -1569,32 +1911,294 @@ fn create_harvest_database(path: &Path) -> Result<()> {
         INSERT OR REPLACE INTO harvest_metadata (key, value) 
         VALUES ('version', '2.1'),
                ('created_at', datetime('now'));
-               
+        "#,
+    )?;
+
+    ensure_fts_tables(&conn)?;
+    ensure_message_chunks_table(&conn)?;
+    reviews::ensure_tags_table(&conn)?;
+    ensure_session_owner_column(&conn)?;
+
+    Ok(())
+}
+
+/// Add a column to an existing table unless it is already there
+pub(crate) fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> rusqlite::Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+        params![table, column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))?;
+    }
+    Ok(())
+}
+
+/// Add the columns recording which API user owns a session and which team
+/// it is shared with. Harvested sessions have no owner; sessions written
+/// through the API server by a signed-in user are visible only to that user
+/// and the members of its team.
+pub(crate) fn ensure_session_owner_column(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "sessions", "user_id", "TEXT")?;
+    add_column_if_missing(conn, "sessions", "team_id", "TEXT")?;
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_sessions_user ON sessions(user_id);
+         CREATE INDEX IF NOT EXISTS idx_sessions_team ON sessions(team_id);",
+    )?;
+    Ok(())
+}
+
+/// Create the full-text indexes of messages_v2 if they do not exist: messages_fts
+/// with the tokenizer set in `[search]`, and messages_trigram for languages
+/// written without spaces. A changed tokenizer takes effect on `csm harvest rebuild`.
+fn ensure_fts_tables(conn: &Connection) -> Result<()> {
+    language::ensure_language_columns(conn)?;
+    conn.execute_batch(&format!(
+        r#"
         -- Full-text search for messages (standalone FTS table)
         CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
-            content_raw
+            content_raw,
+            tokenize = '{}'
         );
-        
+
         -- Triggers to keep FTS index in sync with messages_v2
         CREATE TRIGGER IF NOT EXISTS messages_v2_ai AFTER INSERT ON messages_v2 BEGIN
             INSERT INTO messages_fts(rowid, content_raw) VALUES (new.id, new.content_raw);
         END;
-        
-        CREATE TRIGGER IF NOT EXISTS messages_v2_ad AFTER DELETE ON messages_v2 BEGIN
-            INSERT INTO messages_fts(messages_fts, rowid, content_raw) VALUES ('delete', old.id, old.content_raw);
+        "#,
+        config::current().search.tokenizer()
+    ))?;
+    ensure_fts_triggers(conn)?;
+    ensure_trigram_index(conn)
+}
+
+/// Create the delete/update triggers that keep messages_fts in sync.
+/// messages_fts is a regular FTS5 table, so rows are removed with DELETE; the
+/// `'delete'` command only works on external-content tables and failed every
+/// re-harvest of a session. Databases created with those triggers get them replaced.
+fn ensure_fts_triggers(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        DROP TRIGGER IF EXISTS messages_v2_ad;
+        DROP TRIGGER IF EXISTS messages_v2_au;
+
+        CREATE TRIGGER messages_v2_ad AFTER DELETE ON messages_v2 BEGIN
+            DELETE FROM messages_fts WHERE rowid = old.id;
         END;
-        
-        CREATE TRIGGER IF NOT EXISTS messages_v2_au AFTER UPDATE ON messages_v2 BEGIN
-            INSERT INTO messages_fts(messages_fts, rowid, content_raw) VALUES ('delete', old.id, old.content_raw);
+
+        CREATE TRIGGER messages_v2_au AFTER UPDATE ON messages_v2 BEGIN
+            DELETE FROM messages_fts WHERE rowid = old.id;
             INSERT INTO messages_fts(rowid, content_raw) VALUES (new.id, new.content_raw);
         END;
         "#,
     )?;
+    Ok(())
+}
+
+/// Keep messages in languages written without spaces in messages_trigram, or
+/// drop it when `[search] trigram = false`
+fn ensure_trigram_index(conn: &Connection) -> Result<()> {
+    if !config::current().search.trigram() {
+        conn.execute_batch(
+            r#"
+            DROP TRIGGER IF EXISTS messages_v2_trigram_ai;
+            DROP TRIGGER IF EXISTS messages_v2_trigram_ad;
+            DROP TRIGGER IF EXISTS messages_v2_trigram_au;
+            DROP TABLE IF EXISTS messages_trigram;
+            "#,
+        )?;
+        return Ok(());
+    }
+    let languages = language::unspaced_sql_list();
+    conn.execute_batch(&format!(
+        r#"
+        CREATE VIRTUAL TABLE IF NOT EXISTS messages_trigram USING fts5(
+            content_raw,
+            tokenize = 'trigram'
+        );
+
+        DROP TRIGGER IF EXISTS messages_v2_trigram_ai;
+        DROP TRIGGER IF EXISTS messages_v2_trigram_ad;
+        DROP TRIGGER IF EXISTS messages_v2_trigram_au;
+
+        CREATE TRIGGER messages_v2_trigram_ai AFTER INSERT ON messages_v2
+        WHEN new.language IN ({0}) BEGIN
+            INSERT INTO messages_trigram(rowid, content_raw) VALUES (new.id, new.content_raw);
+        END;
+
+        CREATE TRIGGER messages_v2_trigram_ad AFTER DELETE ON messages_v2 BEGIN
+            DELETE FROM messages_trigram WHERE rowid = old.id;
+        END;
+
+        CREATE TRIGGER messages_v2_trigram_au AFTER UPDATE ON messages_v2 BEGIN
+            DELETE FROM messages_trigram WHERE rowid = old.id;
+            INSERT INTO messages_trigram(rowid, content_raw)
+            SELECT new.id, new.content_raw WHERE new.language IN ({0});
+        END;
+        "#,
+        languages
+    ))?;
+    Ok(())
+}
+
+/// Create the overflow table for oversized messages. `content_raw` keeps the
+/// first chunk (so FTS and previews stay bounded) and the rest is stored here;
+/// [`message_content`] puts a message back together.
+fn ensure_message_chunks_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS message_chunks (
+            message_id INTEGER NOT NULL,
+            chunk_index INTEGER NOT NULL,
+            content TEXT NOT NULL,
+            PRIMARY KEY (message_id, chunk_index),
+            FOREIGN KEY (message_id) REFERENCES messages_v2(id) ON DELETE CASCADE
+        );
+
+        -- Foreign keys are not enforced, so chunks go with their message here
+        CREATE TRIGGER IF NOT EXISTS message_chunks_ad AFTER DELETE ON messages_v2 BEGIN
+            DELETE FROM message_chunks WHERE message_id = old.id;
+        END;
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Split message content into chunks of at most `max_bytes`, on char boundaries
+fn split_message_chunks(content: &str, max_bytes: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = content;
+    while rest.len() > max_bytes {
+        let mut end = max_bytes;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        if end == 0 {
+            // A single char wider than the chunk size
+            end = rest
+                .chars()
+                .next()
+                .map(char::len_utf8)
+                .unwrap_or(rest.len());
+        }
+        let (head, tail) = rest.split_at(end);
+        chunks.push(head);
+        rest = tail;
+    }
+    chunks.push(rest);
+    chunks
+}
+
+/// Insert a messages_v2 row, moving content past `limits.message_chunk_kb`
+/// into message_chunks. Returns the new message id.
+#[allow(clippy::too_many_arguments)]
+fn insert_message_v2(
+    conn: &Connection,
+    session_id: &str,
+    message_index: i64,
+    request_id: Option<&str>,
+    response_id: Option<&str>,
+    role: &str,
+    content: &str,
+    model_id: Option<&str>,
+    timestamp: Option<i64>,
+    is_canceled: bool,
+    metadata_json: Option<String>,
+) -> Result<i64> {
+    let chunk_bytes = config::current().limits().message_chunk_bytes();
+    let chunks = split_message_chunks(content, chunk_bytes);
+
+    conn.execute(
+        r#"
+        INSERT OR REPLACE INTO messages_v2 
+        (session_id, message_index, request_id, response_id, role, 
+         content_raw, content_markdown, model_id, timestamp, is_canceled, metadata_json,
+         language)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+        params![
+            session_id,
+            message_index,
+            request_id,
+            response_id,
+            role,
+            chunks[0],
+            chunks[0],
+            model_id,
+            timestamp,
+            is_canceled as i64,
+            metadata_json,
+            language::detect_language(chunks[0]),
+        ],
+    )?;
+    let message_id = conn.last_insert_rowid();
+
+    for (idx, chunk) in chunks.iter().enumerate().skip(1) {
+        conn.execute(
+            "INSERT OR REPLACE INTO message_chunks (message_id, chunk_index, content) VALUES (?, ?, ?)",
+            params![message_id, idx as i64, chunk],
+        )?;
+    }
+
+    Ok(message_id)
+}
+
+/// A harvested message's full content: `head`, its `content_raw`, followed by
+/// its overflow chunks. Databases without message_chunks have no overflow.
+pub(crate) fn message_content(
+    conn: &Connection,
+    message_id: i64,
+    mut head: String,
+) -> rusqlite::Result<String> {
+    let mut stmt = match conn.prepare_cached(
+        "SELECT content FROM message_chunks WHERE message_id = ?1 ORDER BY chunk_index",
+    ) {
+        Ok(stmt) => stmt,
+        Err(e) => {
+            let exists: bool = conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'message_chunks')",
+                [],
+                |row| row.get(0),
+            )?;
+            return if exists { Err(e) } else { Ok(head) };
+        }
+    };
+    let mut rows = stmt.query([message_id])?;
+    while let Some(row) = rows.next()? {
+        head.push_str(&row.get::<_, String>(0)?);
+    }
+    Ok(head)
+}
+
+/// Set a session's language to the one most of its message text is in
+fn update_session_language(conn: &Connection, session_id: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE sessions SET language = (
+             SELECT language FROM messages_v2
+             WHERE session_id = ?1 AND language IS NOT NULL
+             GROUP BY language ORDER BY SUM(LENGTH(content_raw)) DESC LIMIT 1
+         ) WHERE id = ?1",
+        [session_id],
+    )?;
+    Ok(())
+}
 
+/// Delete a session's messages_v2 rows; their overflow chunks go with them
+fn delete_session_messages(conn: &Connection, session_id: &str) -> Result<()> {
+    conn.execute("DELETE FROM messages_v2 WHERE session_id = ?", [session_id])?;
     Ok(())
 }
 
-fn insert_or_update_session(
+pub(crate) fn insert_or_update_session(
     conn: &Connection,
     session: &ChatSession,
     provider: &str,
@@ -1608,6 +2212,8 @@ fn insert_or_update_session(
 
     let now = Utc::now().timestamp_millis();
     let session_json = serde_json::to_string(session)?;
+    language::ensure_language_columns(conn)?;
+    ensure_message_chunks_table(conn)?;
 
     // Check if session exists
     let existing: Option<i64> = conn
@@ -1620,12 +2226,25 @@ fn insert_or_update_session(
 
     let updated = existing.is_some();
 
+    // An upsert: REPLACE deletes the old row, which cascades to the
+    // session's tags and checkpoints
     conn.execute(
         r#"
-        INSERT OR REPLACE INTO sessions 
+        INSERT INTO sessions 
         (id, provider, provider_type, workspace_id, workspace_name, title, 
          message_count, created_at, updated_at, harvested_at, session_json)
         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(id) DO UPDATE SET
+            provider = excluded.provider,
+            provider_type = excluded.provider_type,
+            workspace_id = excluded.workspace_id,
+            workspace_name = excluded.workspace_name,
+            title = excluded.title,
+            message_count = excluded.message_count,
+            created_at = excluded.created_at,
+            updated_at = excluded.updated_at,
+            harvested_at = excluded.harvested_at,
+            session_json = excluded.session_json
         "#,
         params![
             session_id,
@@ -1645,9 +2264,191 @@ fn insert_or_update_session(
     // Populate enhanced message tables
     populate_enhanced_messages(conn, &session_id, session)?;
 
+    // Tag code-review sessions
+    reviews::sync_review_tag(conn, &session_id, session)?;
+
     Ok(updated)
 }
 
+/// Copy sessions from another harvest database (a backup or an older
+/// revision) and rebuild their messages. Returns the number copied.
+pub(crate) fn copy_sessions_from(
+    conn: &Connection,
+    source: &Path,
+    ids: &[String],
+) -> Result<usize> {
+    ensure_fts_tables(conn)?;
+    ensure_message_chunks_table(conn)?;
+    reviews::ensure_tags_table(conn)?;
+    conn.execute(
+        "ATTACH DATABASE ?1 AS source",
+        [source.to_string_lossy().as_ref()],
+    )?;
+    let copied = copy_attached_sessions(conn, ids);
+    let detached = conn.execute("DETACH DATABASE source", []);
+    let copied = copied?;
+    detached?;
+    Ok(copied)
+}
+
+fn copy_attached_sessions(conn: &Connection, ids: &[String]) -> Result<usize> {
+    const COLUMNS: &str = "id, provider, provider_type, workspace_id, workspace_name, title, \
+         message_count, created_at, updated_at, harvested_at, session_json";
+
+    let mut copied = 0;
+    for id in ids {
+        let rows = conn.execute(
+            &format!(
+                "INSERT OR REPLACE INTO main.sessions ({0}) SELECT {0} FROM source.sessions WHERE id = ?1",
+                COLUMNS
+            ),
+            [id],
+        )?;
+        if rows == 0 {
+            continue;
+        }
+        let json: String = conn.query_row(
+            "SELECT session_json FROM main.sessions WHERE id = ?1",
+            [id],
+            |row| row.get(0),
+        )?;
+        let session = parse_session_json(&json)?;
+        populate_enhanced_messages(conn, id, &session)?;
+        reviews::sync_review_tag(conn, id, &session)?;
+        copied += 1;
+    }
+    Ok(copied)
+}
+
+/// Create the table that holds soft-deleted sessions until they are restored
+pub(crate) fn ensure_deleted_sessions_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS deleted_sessions (
+            id TEXT PRIMARY KEY,
+            provider TEXT NOT NULL,
+            workspace_id TEXT,
+            workspace_name TEXT,
+            title TEXT NOT NULL,
+            session_json TEXT NOT NULL,
+            user_tags TEXT NOT NULL DEFAULT '[]',
+            deleted_at INTEGER NOT NULL
+        );
+        "#,
+    )?;
+    add_column_if_missing(conn, "deleted_sessions", "user_id", "TEXT")?;
+    add_column_if_missing(conn, "deleted_sessions", "team_id", "TEXT")?;
+    Ok(())
+}
+
+/// Remove a session and its messages. A soft delete keeps the session in
+/// `deleted_sessions` so [`restore_harvested_session`] can bring it back; a
+/// permanent delete also drops it from there. Returns whether anything was
+/// deleted.
+pub(crate) fn delete_harvested_session(
+    conn: &Connection,
+    session_id: &str,
+    soft: bool,
+) -> Result<bool> {
+    ensure_deleted_sessions_table(conn)?;
+    ensure_session_owner_column(conn)?;
+    reviews::ensure_tags_table(conn)?;
+
+    if soft {
+        // Tags go with the sessions row, so user tags are kept with the copy
+        conn.execute(
+            "INSERT OR REPLACE INTO deleted_sessions
+             (id, provider, workspace_id, workspace_name, title, session_json, user_tags,
+              deleted_at, user_id, team_id)
+             SELECT id, provider, workspace_id, workspace_name, title, session_json,
+                    (SELECT json_group_array(tag) FROM session_tags
+                     WHERE session_id = ?1 AND source = ?2),
+                    ?3, user_id, team_id
+             FROM sessions WHERE id = ?1",
+            params![
+                session_id,
+                reviews::USER_SOURCE,
+                Utc::now().timestamp_millis()
+            ],
+        )?;
+    } else {
+        conn.execute(
+            "DELETE FROM session_tags WHERE session_id = ?",
+            [session_id],
+        )?;
+    }
+
+    delete_session_messages(conn, session_id)?;
+    for table in ["tool_invocations", "file_changes", "messages"] {
+        conn.execute(
+            &format!("DELETE FROM {} WHERE session_id = ?", table),
+            [session_id],
+        )?;
+    }
+    let mut deleted = conn.execute("DELETE FROM sessions WHERE id = ?", [session_id])?;
+    if !soft {
+        deleted += conn.execute("DELETE FROM deleted_sessions WHERE id = ?", [session_id])?;
+    }
+    Ok(deleted > 0)
+}
+
+/// Move a soft-deleted session back into `sessions` and rebuild its messages.
+/// Returns false if there is no deleted session with that id.
+pub(crate) fn restore_harvested_session(conn: &Connection, session_id: &str) -> Result<bool> {
+    ensure_deleted_sessions_table(conn)?;
+    ensure_session_owner_column(conn)?;
+    type DeletedRow = (
+        String,
+        Option<String>,
+        Option<String>,
+        String,
+        String,
+        Option<String>,
+        Option<String>,
+    );
+    let row: Option<DeletedRow> = conn
+        .query_row(
+            "SELECT provider, workspace_id, workspace_name, session_json, user_tags, user_id,
+                    team_id
+             FROM deleted_sessions WHERE id = ?",
+            [session_id],
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                    row.get(6)?,
+                ))
+            },
+        )
+        .optional()?;
+    let Some((provider, workspace_id, workspace_name, json, user_tags, user_id, team_id)) = row
+    else {
+        return Ok(false);
+    };
+
+    let mut session = parse_session_json(&json)?;
+    session.session_id = Some(session_id.to_string());
+    insert_or_update_session(
+        conn,
+        &session,
+        &provider,
+        workspace_id.as_deref(),
+        workspace_name.as_deref(),
+    )?;
+    conn.execute(
+        "UPDATE sessions SET user_id = ?2, team_id = ?3 WHERE id = ?1",
+        params![session_id, user_id, team_id],
+    )?;
+    let user_tags: Vec<String> = serde_json::from_str(&user_tags).unwrap_or_default();
+    reviews::set_user_tags(conn, session_id, &user_tags)?;
+    conn.execute("DELETE FROM deleted_sessions WHERE id = ?", [session_id])?;
+    Ok(true)
+}
+
 /// Populate the enhanced messages_v2, tool_invocations, and file_changes tables
 fn populate_enhanced_messages(
     conn: &Connection,
@@ -1655,7 +2456,7 @@ fn populate_enhanced_messages(
     session: &ChatSession,
 ) -> Result<()> {
     // Delete existing messages for this session to avoid duplicates
-    conn.execute("DELETE FROM messages_v2 WHERE session_id = ?", [session_id])?;
+    delete_session_messages(conn, session_id)?;
     conn.execute(
         "DELETE FROM tool_invocations WHERE session_id = ?",
         [session_id],
@@ -1678,26 +2479,21 @@ fn populate_enhanced_messages(
             if !content.is_empty() {
                 let metadata = serde_json::json!({
                     "variable_data": request.variable_data,
+                    "source_session": request.source_session,
                 });
 
-                conn.execute(
-                    r#"
-                    INSERT OR REPLACE INTO messages_v2 
-                    (session_id, message_index, request_id, response_id, role, 
-                     content_raw, content_markdown, model_id, timestamp, is_canceled, metadata_json)
-                    VALUES (?, ?, ?, ?, 'user', ?, ?, ?, ?, 0, ?)
-                    "#,
-                    params![
-                        session_id,
-                        (idx * 2) as i64,
-                        request_id,
-                        response_id,
-                        &content,
-                        &content, // content_markdown same as raw for user messages
-                        model_id,
-                        timestamp,
-                        serde_json::to_string(&metadata).ok(),
-                    ],
+                insert_message_v2(
+                    conn,
+                    session_id,
+                    (idx * 2) as i64,
+                    request_id,
+                    response_id,
+                    "user",
+                    &content,
+                    model_id,
+                    timestamp,
+                    false,
+                    serde_json::to_string(&metadata).ok(),
                 )?;
             }
         }
@@ -1711,32 +2507,23 @@ fn populate_enhanced_messages(
                     "content_references": request.content_references,
                     "code_citations": request.code_citations,
                     "response_markdown_info": request.response_markdown_info,
+                    "source_session": request.source_session,
                 });
 
-                conn.execute(
-                    r#"
-                    INSERT OR REPLACE INTO messages_v2 
-                    (session_id, message_index, request_id, response_id, role, 
-                     content_raw, content_markdown, model_id, timestamp, is_canceled, metadata_json)
-                    VALUES (?, ?, ?, ?, 'assistant', ?, ?, ?, ?, ?, ?)
-                    "#,
-                    params![
-                        session_id,
-                        (idx * 2 + 1) as i64,
-                        request_id,
-                        response_id,
-                        &content,
-                        &content,
-                        model_id,
-                        timestamp,
-                        is_canceled as i64,
-                        serde_json::to_string(&metadata).ok(),
-                    ],
+                let message_id = insert_message_v2(
+                    conn,
+                    session_id,
+                    (idx * 2 + 1) as i64,
+                    request_id,
+                    response_id,
+                    "assistant",
+                    &content,
+                    model_id,
+                    timestamp,
+                    is_canceled,
+                    serde_json::to_string(&metadata).ok(),
                 )?;
 
-                // Get the message_id we just inserted
-                let message_id: i64 = conn.last_insert_rowid();
-
                 // Insert tool invocations and file changes
                 for (inv_idx, invocation) in tool_invocations.iter().enumerate() {
                     insert_tool_invocation(
@@ -1747,7 +2534,7 @@ fn populate_enhanced_messages(
         }
     }
 
-    Ok(())
+    update_session_language(conn, session_id)
 }
 
 /// Extract response content and tool invocations from the response JSON
@@ -1757,7 +2544,12 @@ fn extract_response_content_and_tools(
     let mut text_parts = Vec::new();
     let mut tool_invocations = Vec::new();
 
-    if let Some(items) = response.as_array() {
+    // VS Code stores the parts as an array; imported and agency sessions
+    // wrap them in {"value": [...]}
+    let items = response
+        .as_array()
+        .or_else(|| response.get("value").and_then(|v| v.as_array()));
+    if let Some(items) = items {
         for item in items {
             let kind = item.get("kind").and_then(|k| k.as_str()).unwrap_or("");
 
@@ -2324,6 +3116,8 @@ fn insert_cloud_conversation_to_harvest_db(
     )?;
 
     // Also populate messages_v2 table for detailed message storage
+    language::ensure_language_columns(conn)?;
+    ensure_message_chunks_table(conn)?;
     populate_cloud_messages(conn, &conv.id, conv)?;
 
     Ok(())
@@ -2336,7 +3130,7 @@ fn populate_cloud_messages(
     conv: &crate::providers::cloud::common::CloudConversation,
 ) -> Result<()> {
     // Delete existing messages for this session to avoid duplicates
-    conn.execute("DELETE FROM messages_v2 WHERE session_id = ?", [session_id])?;
+    delete_session_messages(conn, session_id)?;
 
     for (idx, message) in conv.messages.iter().enumerate() {
         let timestamp = message.timestamp.map(|dt| dt.timestamp_millis());
@@ -2347,27 +3141,22 @@ fn populate_cloud_messages(
             other => other,
         };
 
-        conn.execute(
-            r#"
-            INSERT INTO messages_v2 
-            (session_id, message_index, request_id, response_id, role, 
-             content_raw, content_markdown, model_id, timestamp, is_canceled, metadata_json)
-            VALUES (?, ?, ?, NULL, ?, ?, ?, ?, ?, 0, NULL)
-            "#,
-            params![
-                session_id,
-                idx as i64,
-                message.id,
-                role,
-                &message.content,
-                &message.content,
-                message.model.as_deref(),
-                timestamp,
-            ],
+        insert_message_v2(
+            conn,
+            session_id,
+            idx as i64,
+            message.id.as_deref(),
+            None,
+            role,
+            &message.content,
+            message.model.as_deref(),
+            timestamp,
+            false,
+            None,
         )?;
     }
 
-    Ok(())
+    update_session_language(conn, session_id)
 }
 
 fn update_harvest_metadata(conn: &Connection) -> Result<()> {
@@ -2574,7 +3363,7 @@ pub fn harvest_share(
         println!(
             "{} Registered share link (ID: {})",
             "[+]".green(),
-            &link_id[..8]
+            prefix_chars(&link_id, 8)
         );
     }
 
@@ -2703,23 +3492,19 @@ pub fn harvest_shares(
 
         println!(
             "{} [{}] {} - {}",
-            format!("#{}", &id[..8]).dimmed(),
+            format!("#{}", prefix_chars(&id, 8)).dimmed(),
             status_colored,
             provider.bold(),
             title.as_deref().unwrap_or("(untitled)")
         );
 
         // Truncate URL for display
-        let display_url = if url.len() > 60 {
-            format!("{}...", &url[..57])
-        } else {
-            url.clone()
-        };
+        let display_url = truncate(&url, 60);
         println!("   {} {}", "URL:".dimmed(), display_url.dimmed());
 
         // Format timestamp
         let timestamp = chrono::DateTime::from_timestamp(created_at, 0)
-            .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+            .map(format_datetime_short)
             .unwrap_or_else(|| created_at.to_string());
         println!("   {} {}", "Added:".dimmed(), timestamp.dimmed());
         println!();
@@ -2748,15 +3533,15 @@ pub fn harvest_checkpoint(
     let conn = db.connection();
 
     // Find the session
-    let session: Option<(i64, String, i64)> = conn
+    let session: Option<(String, i64)> = conn
         .query_row(
-            "SELECT id, session_id, message_count FROM sessions WHERE session_id = ? OR id = ?",
-            params![session_id, session_id.parse::<i64>().unwrap_or(-1)],
-            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            "SELECT id, message_count FROM sessions WHERE id = ?",
+            [session_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
         )
         .ok();
 
-    let (internal_id, actual_session_id, msg_count) = match session {
+    let (actual_session_id, msg_count) = match session {
         Some(s) => s,
         None => {
             println!("{} Session not found: {}", "[!]".red(), session_id);
@@ -2768,21 +3553,22 @@ pub fn harvest_checkpoint(
     // Get current checkpoint number
     let checkpoint_num: i64 = conn.query_row(
         "SELECT COALESCE(MAX(checkpoint_number), 0) + 1 FROM checkpoints WHERE session_id = ?",
-        [internal_id],
+        [&actual_session_id],
         |row| row.get(0),
     )?;
 
-    // Calculate content hash (simple hash of message count + session_id for now)
-    let content_hash = format!(
-        "{:x}",
-        md5_hash(&format!("{}:{}", actual_session_id, msg_count))
-    );
-
     // Get message snapshot
-    let messages: Vec<String> = {
-        let mut stmt = conn
-            .prepare("SELECT content FROM messages WHERE session_id = ? ORDER BY message_index")?;
-        let rows = stmt.query_map([internal_id], |row| row.get::<_, String>(0))?;
+    let messages: Vec<serde_json::Value> = {
+        let mut stmt = conn.prepare(
+            "SELECT id, role, content_raw FROM messages_v2 WHERE session_id = ?
+             ORDER BY message_index, id",
+        )?;
+        let rows = stmt.query_map([&actual_session_id], |row| {
+            Ok(serde_json::json!({
+                "role": row.get::<_, String>(1)?,
+                "content": message_content(conn, row.get(0)?, row.get(2)?)?,
+            }))
+        })?;
         rows.filter_map(|r| r.ok()).collect()
     };
 
@@ -2791,6 +3577,9 @@ pub fn harvest_checkpoint(
         "messages": messages,
     });
 
+    // Calculate content hash of the snapshot
+    let content_hash = format!("{:032x}", md5_hash(&snapshot.to_string()));
+
     let default_message = format!("Checkpoint {}", checkpoint_num);
     let message_text = message.unwrap_or(&default_message);
 
@@ -2799,7 +3588,7 @@ pub fn harvest_checkpoint(
         "INSERT INTO checkpoints (session_id, checkpoint_number, message, message_count, content_hash, snapshot)
          VALUES (?, ?, ?, ?, ?, ?)",
         params![
-            internal_id,
+            actual_session_id,
             checkpoint_num,
             message_text,
             msg_count,
@@ -2842,21 +3631,20 @@ pub fn harvest_checkpoints(db_path: Option<&str>, session_id: &str) -> Result<()
     let conn = db.connection();
 
     // Find the session
-    let session: Option<(i64, String, String)> = conn
+    let session: Option<(String, String)> = conn
         .query_row(
-            "SELECT id, session_id, name FROM sessions WHERE session_id = ? OR id = ?",
-            params![session_id, session_id.parse::<i64>().unwrap_or(-1)],
+            "SELECT id, title FROM sessions WHERE id = ?",
+            [session_id],
             |row| {
                 Ok((
                     row.get(0)?,
-                    row.get(1)?,
-                    row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                    row.get::<_, Option<String>>(1)?.unwrap_or_default(),
                 ))
             },
         )
         .ok();
 
-    let (internal_id, actual_session_id, name) = match session {
+    let (actual_session_id, name) = match session {
         Some(s) => s,
         None => {
             println!("{} Session not found: {}", "[!]".red(), session_id);
@@ -2886,7 +3674,7 @@ pub fn harvest_checkpoints(db_path: Option<&str>, session_id: &str) -> Result<()
     )?;
 
     let checkpoints: Vec<_> = stmt
-        .query_map([internal_id], |row| {
+        .query_map([&actual_session_id], |row| {
             Ok((
                 row.get::<_, i64>(0)?,
                 row.get::<_, String>(1)?,
@@ -2961,16 +3749,16 @@ pub fn harvest_restore_checkpoint(
     };
 
     // Find the checkpoint
-    let checkpoint: Option<(String, i64)> = conn
+    let checkpoint: Option<i64> = conn
         .query_row(
-            "SELECT snapshot, message_count FROM checkpoints 
+            "SELECT message_count FROM checkpoints 
          WHERE session_id = ? AND checkpoint_number = ?",
             params![internal_id, checkpoint_number],
-            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+            |row| row.get(0),
         )
         .ok();
 
-    let (snapshot_json, original_msg_count) = match checkpoint {
+    let original_msg_count = match checkpoint {
         Some(c) => c,
         None => {
             println!(
@@ -2987,14 +3775,6 @@ pub fn harvest_restore_checkpoint(
         }
     };
 
-    // Parse snapshot
-    let snapshot: serde_json::Value =
-        serde_json::from_str(&snapshot_json).context("Failed to parse checkpoint snapshot")?;
-
-    let messages = snapshot["messages"]
-        .as_array()
-        .context("Invalid snapshot format")?;
-
     println!("{}", "=".repeat(60).cyan());
     println!("{}", " Restore Checkpoint ".bold().yellow());
     println!("{}", "=".repeat(60).cyan());
@@ -3012,31 +3792,14 @@ pub fn harvest_restore_checkpoint(
     );
     println!();
 
-    // Delete current messages
-    let deleted = conn.execute("DELETE FROM messages WHERE session_id = ?", [internal_id])?;
+    let (deleted, restored) =
+        restore_checkpoint(conn, &internal_id.to_string(), checkpoint_number)?;
 
     println!("{} Removed {} current messages", "[-]".red(), deleted);
-
-    // Restore messages from snapshot
-    for (idx, msg) in messages.iter().enumerate() {
-        if let Some(content) = msg.as_str() {
-            conn.execute(
-                "INSERT INTO messages (session_id, message_index, role, content) VALUES (?, ?, 'unknown', ?)",
-                params![internal_id, idx as i64, content],
-            )?;
-        }
-    }
-
-    // Update session message count
-    conn.execute(
-        "UPDATE sessions SET message_count = ?, updated_at = datetime('now') WHERE id = ?",
-        params![original_msg_count, internal_id],
-    )?;
-
     println!(
         "{} Restored {} messages from checkpoint",
         "[+]".green(),
-        messages.len()
+        restored
     );
     println!();
     println!(
@@ -3048,10 +3811,85 @@ pub fn harvest_restore_checkpoint(
     Ok(())
 }
 
+/// Reset a session's messages to the ones saved in its checkpoint `number`,
+/// in one transaction. Returns the number of messages removed and restored.
+pub(crate) fn restore_checkpoint(
+    conn: &Connection,
+    session_id: &str,
+    number: i64,
+) -> Result<(usize, usize)> {
+    let (snapshot, message_count): (Option<String>, i64) = conn
+        .query_row(
+            "SELECT snapshot, message_count FROM checkpoints
+             WHERE session_id = ?1 AND checkpoint_number = ?2",
+            params![session_id, number],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?
+        .with_context(|| format!("Checkpoint #{} not found", number))?;
+    let snapshot: serde_json::Value =
+        serde_json::from_str(snapshot.as_deref().context("Checkpoint has no snapshot")?)
+            .context("Failed to parse checkpoint snapshot")?;
+    let messages: Vec<&str> = snapshot["messages"]
+        .as_array()
+        .context("Invalid snapshot format")?
+        .iter()
+        .filter_map(|m| m.as_str())
+        .collect();
+
+    let tx = conn.unchecked_transaction()?;
+    let deleted = tx.execute("DELETE FROM messages WHERE session_id = ?1", [session_id])?;
+    for (index, content) in messages.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO messages (session_id, message_index, role, content)
+             VALUES (?1, ?2, 'unknown', ?3)",
+            params![session_id, index as i64, content],
+        )?;
+    }
+    tx.execute(
+        "UPDATE sessions SET message_count = ?1 WHERE id = ?2",
+        params![message_count, session_id],
+    )?;
+    tx.commit()?;
+    Ok((deleted, messages.len()))
+}
+
 // ============================================================================
 // Search Commands
 // ============================================================================
 
+/// Record the language of messages and sessions that have none. Returns the
+/// number of messages whose language was found.
+fn detect_missing_languages(conn: &Connection) -> Result<usize> {
+    let messages = {
+        let mut stmt =
+            conn.prepare("SELECT id, content_raw FROM messages_v2 WHERE language IS NULL")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()?
+    };
+    let mut detected = 0;
+    for (id, content) in &messages {
+        if let Some(code) = language::detect_language(content) {
+            conn.execute(
+                "UPDATE messages_v2 SET language = ?1 WHERE id = ?2",
+                params![code, id],
+            )?;
+            detected += 1;
+        }
+    }
+    let sessions = {
+        let mut stmt = conn.prepare("SELECT id FROM sessions WHERE language IS NULL")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()?
+    };
+    for id in &sessions {
+        update_session_language(conn, id)?;
+    }
+    Ok(detected)
+}
+
 /// Rebuild the FTS index from messages_v2 table
 pub fn harvest_rebuild_fts(db_path: Option<&str>) -> Result<()> {
     let db_path = get_db_path(db_path)?;
@@ -3067,39 +3905,35 @@ pub fn harvest_rebuild_fts(db_path: Option<&str>) -> Result<()> {
     println!("{}", "=".repeat(70).cyan());
     println!();
 
-    // Drop existing FTS table and triggers
+    // Drop existing FTS tables and triggers
     println!("{} Dropping old FTS index...", "[*]".blue());
     conn.execute_batch(
         r#"
         DROP TRIGGER IF EXISTS messages_v2_ai;
         DROP TRIGGER IF EXISTS messages_v2_ad;
         DROP TRIGGER IF EXISTS messages_v2_au;
+        DROP TRIGGER IF EXISTS messages_v2_trigram_ai;
+        DROP TRIGGER IF EXISTS messages_v2_trigram_ad;
+        DROP TRIGGER IF EXISTS messages_v2_trigram_au;
         DROP TABLE IF EXISTS messages_fts;
+        DROP TABLE IF EXISTS messages_trigram;
         "#,
     )?;
 
-    // Create new FTS table with triggers
-    println!("{} Creating new FTS index...", "[*]".blue());
-    conn.execute_batch(
-        r#"
-        CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
-            content_raw
+    // Detect the language of messages harvested before it was recorded
+    language::ensure_language_columns(&conn)?;
+    let detected = detect_missing_languages(&conn)?;
+    if detected > 0 {
+        println!(
+            "{} Detected the language of {} messages",
+            "[*]".blue(),
+            detected
         );
-        
-        CREATE TRIGGER IF NOT EXISTS messages_v2_ai AFTER INSERT ON messages_v2 BEGIN
-            INSERT INTO messages_fts(rowid, content_raw) VALUES (new.id, new.content_raw);
-        END;
-        
-        CREATE TRIGGER IF NOT EXISTS messages_v2_ad AFTER DELETE ON messages_v2 BEGIN
-            INSERT INTO messages_fts(messages_fts, rowid, content_raw) VALUES ('delete', old.id, old.content_raw);
-        END;
-        
-        CREATE TRIGGER IF NOT EXISTS messages_v2_au AFTER UPDATE ON messages_v2 BEGIN
-            INSERT INTO messages_fts(messages_fts, rowid, content_raw) VALUES ('delete', old.id, old.content_raw);
-            INSERT INTO messages_fts(rowid, content_raw) VALUES (new.id, new.content_raw);
-        END;
-        "#,
-    )?;
+    }
+
+    // Create new FTS tables with triggers
+    println!("{} Creating new FTS index...", "[*]".blue());
+    ensure_fts_tables(&conn)?;
 
     // Count total messages
     let total_messages: i64 =
@@ -3112,6 +3946,17 @@ pub fn harvest_rebuild_fts(db_path: Option<&str>) -> Result<()> {
         "INSERT INTO messages_fts(rowid, content_raw) SELECT id, content_raw FROM messages_v2",
         [],
     )?;
+    if config::current().search.trigram() {
+        let languages = language::unspaced_sql_list();
+        conn.execute(
+            &format!(
+                "INSERT INTO messages_trigram(rowid, content_raw)
+                 SELECT id, content_raw FROM messages_v2 WHERE language IN ({})",
+                languages
+            ),
+            [],
+        )?;
+    }
 
     // Verify the index
     let indexed: i64 = conn.query_row("SELECT COUNT(*) FROM messages_fts", [], |row| row.get(0))?;
@@ -3123,13 +3968,8 @@ pub fn harvest_rebuild_fts(db_path: Option<&str>) -> Result<()> {
     Ok(())
 }
 
-/// Full-text search across all sessions
-pub fn harvest_search(
-    db_path: Option<&str>,
-    query: &str,
-    provider_filter: Option<&str>,
-    limit: usize,
-) -> Result<()> {
+/// Search sessions with the engine behind `/api/sessions/search`
+pub fn harvest_search(db_path: Option<&str>, options: &SearchOptions) -> Result<()> {
     let db_path = get_db_path(db_path)?;
 
     if !db_path.exists() {
@@ -3140,166 +3980,83 @@ pub fn harvest_search(
     let conn = db.connection();
 
     println!("{}", "=".repeat(70).cyan());
-    println!("{} Search: {}", "[?]".bold(), query.bold());
+    println!("{} Search: {}", "[?]".bold(), options.query.bold());
     println!("{}", "=".repeat(70).cyan());
     println!();
 
-    // Results: (session_id, provider, title, content)
-    let results: Vec<(String, String, String, String)> = {
-        // Check if FTS table exists
-        let fts_exists: bool = conn
-            .query_row(
-                "SELECT 1 FROM sqlite_master WHERE type='table' AND name='messages_fts'",
-                [],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
-
-        if fts_exists {
-            // Use FTS search - query the FTS table and join with messages/sessions
-            let sql = format!(
-                "SELECT s.id, s.provider, s.title, m.content_raw
-                 FROM messages_fts fts
-                 JOIN messages_v2 m ON m.id = fts.rowid
-                 JOIN sessions s ON m.session_id = s.id
-                 WHERE messages_fts MATCH ?
-                 {}
-                 LIMIT {}",
-                if provider_filter.is_some() {
-                    "AND s.provider = ?"
-                } else {
-                    ""
-                },
-                limit
-            );
-
-            let mut stmt = conn.prepare(&sql)?;
-
-            if let Some(provider) = provider_filter {
-                stmt.query_map(params![query, provider], |row| {
-                    Ok((
-                        row.get::<_, String>(0)?,
-                        row.get::<_, String>(1)?,
-                        row.get::<_, Option<String>>(2)?.unwrap_or_default(),
-                        row.get::<_, String>(3)?,
-                    ))
-                })?
-                .collect::<Result<Vec<_>, _>>()?
-            } else {
-                stmt.query_map([query], |row| {
-                    Ok((
-                        row.get::<_, String>(0)?,
-                        row.get::<_, String>(1)?,
-                        row.get::<_, Option<String>>(2)?.unwrap_or_default(),
-                        row.get::<_, String>(3)?,
-                    ))
-                })?
-                .collect::<Result<Vec<_>, _>>()?
-            }
-        } else {
-            // Fall back to LIKE search
-            let search_pattern = format!("%{}%", query);
-            let sql = format!(
-                "SELECT s.id, s.provider, s.title, m.content_raw
-                 FROM messages_v2 m
-                 JOIN sessions s ON m.session_id = s.id
-                 WHERE m.content_raw LIKE ?
-                 {}
-                 LIMIT {}",
-                if provider_filter.is_some() {
-                    "AND s.provider = ?"
-                } else {
-                    ""
-                },
-                limit
-            );
-
-            let mut stmt = conn.prepare(&sql)?;
-
-            if let Some(provider) = provider_filter {
-                stmt.query_map(params![search_pattern, provider], |row| {
-                    Ok((
-                        row.get::<_, String>(0)?,
-                        row.get::<_, String>(1)?,
-                        row.get::<_, Option<String>>(2)?.unwrap_or_default(),
-                        row.get::<_, String>(3)?,
-                    ))
-                })?
-                .collect::<Result<Vec<_>, _>>()?
-            } else {
-                stmt.query_map([search_pattern], |row| {
-                    Ok((
-                        row.get::<_, String>(0)?,
-                        row.get::<_, String>(1)?,
-                        row.get::<_, Option<String>>(2)?.unwrap_or_default(),
-                        row.get::<_, String>(3)?,
-                    ))
-                })?
-                .collect::<Result<Vec<_>, _>>()?
-            }
-        }
+    let embedder = match options.mode {
+        SearchMode::Semantic => HttpEmbedder::from_env(),
+        SearchMode::Text => None,
     };
+    let results = search::search(
+        conn,
+        options,
+        embedder.as_ref().map(|e| e as &dyn search::Embedder),
+    )?;
 
-    if results.is_empty() {
-        println!("{} No results found for '{}'", "[i]".blue(), query);
+    if results.hits.is_empty() {
+        println!("{} No results found for '{}'", "[i]".blue(), options.query);
         return Ok(());
     }
 
-    println!("{} Found {} result(s):", "[i]".blue(), results.len());
+    println!(
+        "{} Showing {} of {} matching session(s):",
+        "[i]".blue(),
+        results.hits.len(),
+        results.total
+    );
     println!();
 
-    for (session_id, provider, title, content) in results {
-        // Highlight the search term in content
-        let display_name = if title.is_empty() {
-            session_id.clone()
+    for hit in &results.hits {
+        let display_name = if hit.title.is_empty() {
+            hit.id.clone()
         } else {
-            format!("{} ({})", title, &session_id[..8.min(session_id.len())])
+            format!("{} ({})", hit.title, prefix_chars(&hit.id, 8))
         };
 
         println!(
-            "{} {} [{}]",
+            "{} {} [{}] {}",
             "*".cyan(),
             display_name.bold(),
-            provider.dimmed()
+            hit.provider.dimmed(),
+            format!("{:.2}", hit.score).dimmed()
         );
-
-        // Show snippet around the match
-        let snippet = create_search_snippet(&content, query, 100);
-        println!("   {}", snippet.dimmed());
+        if let Some(excerpt) = &hit.snippet {
+            println!("   {}", excerpt.dimmed());
+        }
         println!();
     }
 
-    Ok(())
-}
-
-/// Create a search result snippet with the query highlighted
-fn create_search_snippet(content: &str, query: &str, max_len: usize) -> String {
-    let content_lower = content.to_lowercase();
-    let query_lower = query.to_lowercase();
-
-    if let Some(pos) = content_lower.find(&query_lower) {
-        let start = pos.saturating_sub(max_len / 2);
-        let end = (pos + query.len() + max_len / 2).min(content.len());
-
-        let mut snippet = String::new();
-        if start > 0 {
-            snippet.push_str("...");
-        }
-        snippet.push_str(&content[start..end]);
-        if end < content.len() {
-            snippet.push_str("...");
-        }
-
-        // Replace newlines with spaces for display
-        snippet.replace('\n', " ").replace('\r', "")
-    } else {
-        // Fallback: just show first max_len chars
-        if content.len() > max_len {
-            format!("{}...", &content[..max_len])
-        } else {
-            content.to_string()
-        }
+    if let Some(facets) = &results.facets {
+        let show = |name: &str, counts: &[search::FacetCount]| {
+            if counts.is_empty() {
+                return;
+            }
+            let counts: Vec<String> = counts
+                .iter()
+                .map(|c| format!("{} ({})", c.value, c.count))
+                .collect();
+            println!("   {:<11} {}", format!("{}:", name).bold(), counts.join(", "));
+        };
+        println!("{} Matches by:", "[i]".blue());
+        show("Provider", &facets.providers);
+        show("Workspace", &facets.workspaces);
+        show("Tag", &facets.tags);
+        let languages: Vec<search::FacetCount> = facets
+            .languages
+            .iter()
+            .map(|c| search::FacetCount {
+                value: language::language_name(&c.value)
+                    .unwrap_or(&c.value)
+                    .to_string(),
+                count: c.count,
+            })
+            .collect();
+        show("Language", &languages);
+        show("Month", &facets.months);
     }
+
+    Ok(())
 }
 
 /// Simple MD5 hash for content checksums
@@ -3312,3 +4069,149 @@ fn md5_hash(data: &str) -> u128 {
     }
     hash
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database(dir: &Path, name: &str) -> Connection {
+        let path = dir.join(name);
+        harvest_init(Some(path.to_str().unwrap()), false).unwrap();
+        Connection::open(&path).unwrap()
+    }
+
+    fn session(id: &str, text: &str) -> ChatSession {
+        serde_json::from_value(serde_json::json!({
+            "sessionId": id,
+            "creationDate": 1,
+            "lastMessageDate": 1,
+            "requests": [{ "message": { "text": text } }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_large_messages_round_trip_through_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = database(dir.path(), "harvest.db");
+
+        // Over two chunks, with multi-byte chars straddling the boundaries
+        let chunk_bytes = config::current().limits().message_chunk_bytes();
+        let text = format!("{} zebra", "log line é日本\n".repeat(chunk_bytes / 8));
+        assert!(text.len() > 2 * chunk_bytes);
+        let big = session("big", &text);
+        insert_or_update_session(&conn, &big, "copilot", None, None).unwrap();
+
+        let (id, head): (i64, String) = conn
+            .query_row(
+                "SELECT id, content_raw FROM messages_v2 WHERE session_id = 'big' AND role = 'user'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert!(head.len() <= chunk_bytes);
+        let chunks = |conn: &Connection| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM message_chunks", [], |row| row.get(0))
+                .unwrap()
+        };
+        assert!(chunks(&conn) >= 2);
+        assert_eq!(message_content(&conn, id, head).unwrap(), text);
+
+        // Re-harvesting replaces the chunks instead of leaving the old ones
+        let before = chunks(&conn);
+        insert_or_update_session(&conn, &big, "copilot", None, None).unwrap();
+        assert_eq!(chunks(&conn), before);
+
+        // Readers of databases without the table get the head back
+        let bare = Connection::open_in_memory().unwrap();
+        assert_eq!(message_content(&bare, 1, "head".into()).unwrap(), "head");
+    }
+
+    #[test]
+    fn test_copy_sessions_from() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = database(dir.path(), "backup.db");
+        for (id, text) in [("a", "from the backup"), ("b", "not restored")] {
+            insert_or_update_session(&source, &session(id, text), "copilot", None, None).unwrap();
+        }
+        drop(source);
+
+        let conn = database(dir.path(), "harvest.db");
+        insert_or_update_session(&conn, &session("a", "edited since"), "copilot", None, None)
+            .unwrap();
+        let ids = ["a".to_string(), "missing".to_string()];
+        let copied = copy_sessions_from(&conn, &dir.path().join("backup.db"), &ids).unwrap();
+        assert_eq!(copied, 1);
+
+        let count = |sql: &str| -> i64 { conn.query_row(sql, [], |row| row.get(0)).unwrap() };
+        assert_eq!(count("SELECT COUNT(*) FROM sessions"), 1);
+        assert_eq!(
+            count("SELECT COUNT(*) FROM messages_v2 WHERE session_id = 'a' AND content_raw = 'from the backup'"),
+            1
+        );
+        assert_eq!(
+            count("SELECT COUNT(*) FROM messages_fts WHERE messages_fts MATCH 'edited'"),
+            0
+        );
+        assert_eq!(
+            count("SELECT COUNT(*) FROM messages_fts WHERE messages_fts MATCH 'backup'"),
+            1
+        );
+        // The source is detached again
+        assert_eq!(count("SELECT COUNT(*) FROM pragma_database_list"), 1);
+    }
+
+    #[test]
+    fn test_fts_triggers_remove_deleted_messages() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = database(dir.path(), "harvest.db");
+        // Databases from before used the 'delete' command, which a regular
+        // FTS5 table refuses
+        conn.execute_batch(
+            "DROP TRIGGER messages_v2_ad;
+             CREATE TRIGGER messages_v2_ad AFTER DELETE ON messages_v2 BEGIN
+                 INSERT INTO messages_fts(messages_fts, rowid, content_raw)
+                 VALUES ('delete', old.id, old.content_raw);
+             END;",
+        )
+        .unwrap();
+        ensure_fts_tables(&conn).unwrap();
+
+        insert_or_update_session(&conn, &session("a", "first draft"), "copilot", None, None)
+            .unwrap();
+        delete_session_messages(&conn, "a").unwrap();
+        let found: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM messages_fts WHERE messages_fts MATCH 'draft'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(found, 0);
+    }
+
+    #[test]
+    fn test_restore_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = database(dir.path(), "harvest.db");
+        insert_or_update_session(&conn, &session("a", "hello"), "copilot", None, None).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO messages (session_id, message_index, role, content)
+               VALUES ('a', 0, 'user', 'one'), ('a', 1, 'user', 'two'), ('a', 2, 'user', 'three');
+               INSERT INTO checkpoints (session_id, checkpoint_number, message_count, content_hash, snapshot)
+               VALUES ('a', 1, 1, '', '{"messages": ["one"]}');"#,
+        )
+        .unwrap();
+
+        assert_eq!(restore_checkpoint(&conn, "a", 1).unwrap(), (3, 1));
+        let messages: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM messages WHERE session_id = 'a'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(messages, 1);
+        assert!(restore_checkpoint(&conn, "a", 2).is_err());
+    }
+}
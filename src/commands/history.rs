@@ -8,11 +8,13 @@ use colored::*;
 use std::path::Path;
 use uuid::Uuid;
 
+use crate::datetime::{format_date, format_datetime_short};
 use crate::models::{ChatRequest, ChatSession};
 use crate::storage::{
-    add_session_to_index, backup_workspace_sessions, get_workspace_storage_db, is_vscode_running,
-    register_all_sessions_from_directory,
+    backup_workspace_sessions, copy_file_atomic, get_workspace_storage_db, is_vscode_running,
+    register_all_sessions_from_directory, write_and_register_session, write_session_file,
 };
+use crate::text::truncate;
 use crate::workspace::{
     discover_workspaces, find_all_workspaces_for_project, find_workspace_by_path,
     get_chat_sessions_from_workspace, normalize_path,
@@ -79,7 +81,7 @@ pub fn history_show(project_path: Option<&str>) -> Result<()> {
         };
 
         let mod_date: DateTime<Utc> = (*last_mod).into();
-        let mod_str = mod_date.format("%Y-%m-%d %H:%M").to_string();
+        let mod_str = format_datetime_short(mod_date);
 
         let sessions = get_chat_sessions_from_workspace(ws_dir)?;
 
@@ -224,7 +226,7 @@ pub fn history_fetch(project_path: Option<&str>, force: bool, no_register: bool)
                 );
                 skipped_count += 1;
             } else {
-                std::fs::copy(&session_with_path.path, &dest_file)?;
+                copy_file_atomic(&session_with_path.path, &dest_file)?;
                 let title = session_with_path.session.title();
                 println!(
                     "   {} Fetched: {} ({}...)",
@@ -424,8 +426,14 @@ pub fn history_merge(
     std::fs::create_dir_all(&chat_sessions_dir)?;
     let merged_file = chat_sessions_dir.join(format!("{}.json", merged_session_id));
 
-    let json = serde_json::to_string_pretty(&merged_session)?;
-    std::fs::write(&merged_file, json)?;
+    // Write the file and index entry together so a failed index update is rolled back
+    let vscode_running = is_vscode_running() && !force;
+    if vscode_running {
+        write_session_file(&merged_file, &merged_session)?;
+    } else {
+        let db_path = get_workspace_storage_db(&current_ws_id)?;
+        write_and_register_session(&db_path, &merged_file, &merged_session)?;
+    }
 
     println!(
         "   {} File: {}",
@@ -436,22 +444,12 @@ pub fn history_merge(
     // Register in VS Code index
     println!("\n{} Registering in VS Code index...", "[#]".blue());
 
-    if is_vscode_running() && !force {
+    if vscode_running {
         println!(
             "{} VS Code is running. Close it and run again, or use --force",
             "[!]".yellow()
         );
     } else {
-        let db_path = get_workspace_storage_db(&current_ws_id)?;
-        add_session_to_index(
-            &db_path,
-            &merged_session_id,
-            &merged_title,
-            last_time,
-            false,
-            "panel",
-            false,
-        )?;
         println!("   {} Registered in index", "[OK]".green());
     }
 
@@ -795,8 +793,14 @@ fn merge_sessions_internal(
     std::fs::create_dir_all(&chat_sessions_dir)?;
     let merged_file = chat_sessions_dir.join(format!("{}.json", merged_session_id));
 
-    let json = serde_json::to_string_pretty(&merged_session)?;
-    std::fs::write(&merged_file, json)?;
+    // Write the file and index entry together so a failed index update is rolled back
+    let vscode_running = is_vscode_running() && !force;
+    if vscode_running {
+        write_session_file(&merged_file, &merged_session)?;
+    } else {
+        let db_path = get_workspace_storage_db(target_ws_id)?;
+        write_and_register_session(&db_path, &merged_file, &merged_session)?;
+    }
 
     println!(
         "   {} File: {}",
@@ -807,22 +811,12 @@ fn merge_sessions_internal(
     // Register in VS Code index
     println!("\n{} Registering in VS Code index...", "[#]".blue());
 
-    if is_vscode_running() && !force {
+    if vscode_running {
         println!(
             "{} VS Code is running. Close it and run again, or use --force",
             "[!]".yellow()
         );
     } else {
-        let db_path = get_workspace_storage_db(target_ws_id)?;
-        add_session_to_index(
-            &db_path,
-            &merged_session_id,
-            &merged_title,
-            last_time,
-            false,
-            "panel",
-            false,
-        )?;
         println!("   {} Registered in index", "[OK]".green());
     }
 
@@ -856,19 +850,10 @@ fn timestamp_to_date(timestamp: i64) -> String {
     };
 
     DateTime::from_timestamp(secs, 0)
-        .map(|dt| dt.format("%Y-%m-%d").to_string())
+        .map(format_date)
         .unwrap_or_else(|| "unknown".to_string())
 }
 
-/// Truncate string to max length
-fn truncate(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
-        s.to_string()
-    } else {
-        format!("{}...", &s[..max_len - 3])
-    }
-}
-
 /// Fetch sessions from workspaces matching a name pattern
 pub fn fetch_by_workspace(
     workspace_name: &str,
@@ -967,7 +952,7 @@ pub fn fetch_by_workspace(
                 continue;
             }
 
-            fs::copy(src_file, &dest_file)?;
+            copy_file_atomic(src_file, &dest_file)?;
             fetched_count += 1;
             println!(
                 "   {} Fetched: {}",
@@ -1092,7 +1077,7 @@ pub fn fetch_sessions(
                     continue;
                 }
 
-                fs::copy(src_file, &dest_file)?;
+                copy_file_atomic(src_file, &dest_file)?;
                 fetched_count += 1;
                 found_ids.push(session_id);
                 println!(
@@ -1250,6 +1235,100 @@ pub fn merge_by_workspace_names(
     )
 }
 
+/// Merge chat sessions from every workspace in a project
+pub fn merge_by_project(
+    project_name: &str,
+    title: Option<&str>,
+    target_path: Option<&str>,
+    force: bool,
+    no_backup: bool,
+) -> Result<()> {
+    let project = crate::projects::ProjectStore::open()?.require(project_name)?;
+
+    println!(
+        "\n{} Merging Sessions from Project: {}",
+        "[M]".blue().bold(),
+        project.name.cyan()
+    );
+    println!("{}", "=".repeat(70));
+
+    let workspaces: Vec<_> = discover_workspaces()?
+        .into_iter()
+        .filter(|ws| project.contains(&ws.hash))
+        .collect();
+    if workspaces.is_empty() {
+        println!(
+            "\n{} None of the project's {} workspace(s) exist in workspace storage",
+            "[X]".red(),
+            project.workspaces.len()
+        );
+        return Ok(());
+    }
+
+    // Default to the current directory, then the project's first workspace
+    let target_path = target_path.map(|p| p.to_string()).unwrap_or_else(|| {
+        std::env::current_dir()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| ".".to_string())
+    });
+    let target_ws = match find_workspace_by_path(&target_path)? {
+        Some(ws) => ws,
+        None => workspaces
+            .iter()
+            .find(|ws| ws.project_path.is_some())
+            .map(|ws| {
+                (
+                    ws.hash.clone(),
+                    ws.workspace_path.clone(),
+                    ws.project_path.clone(),
+                )
+            })
+            .context("Target workspace not found. Make sure the project is opened in VS Code")?,
+    };
+    let (target_ws_id, target_ws_dir, _) = target_ws;
+
+    println!(
+        "\n{} Target workspace: {}...",
+        "[>]".blue(),
+        &target_ws_id[..16.min(target_ws_id.len())]
+    );
+
+    println!("\n{} Collecting sessions...", "[D]".blue());
+    let mut all_sessions = Vec::new();
+    for ws in &workspaces {
+        let sessions = get_chat_sessions_from_workspace(&ws.workspace_path)?;
+        if !sessions.is_empty() {
+            println!(
+                "   {} {}... ({} sessions) -> {}",
+                "[d]".blue(),
+                &ws.hash[..16.min(ws.hash.len())],
+                sessions.len(),
+                ws.project_path.as_deref().unwrap_or("(unknown)")
+            );
+            all_sessions.extend(sessions);
+        }
+    }
+
+    if all_sessions.is_empty() {
+        println!(
+            "\n{} No chat sessions found in the project's workspaces",
+            "[X]".red()
+        );
+        return Ok(());
+    }
+
+    let auto_title = format!("Merged: {}", project.name);
+    merge_sessions_internal(
+        all_sessions,
+        Some(title.unwrap_or(&auto_title)),
+        &target_ws_id,
+        &target_ws_dir,
+        force,
+        no_backup,
+        &format!("project {}", project.name),
+    )
+}
+
 /// Merge chat sessions from an LLM provider
 pub fn merge_from_provider(
     provider_name: &str,
@@ -1259,7 +1338,8 @@ pub fn merge_from_provider(
     force: bool,
     no_backup: bool,
 ) -> Result<()> {
-    use crate::providers::{ProviderRegistry, ProviderType};
+    use super::providers::provider_registry;
+    use crate::providers::ProviderType;
 
     println!(
         "\n{} Merging Sessions from Provider: {}",
@@ -1291,7 +1371,7 @@ pub fn merge_from_provider(
     };
 
     // Get provider sessions
-    let registry = ProviderRegistry::new();
+    let registry = provider_registry();
     let provider = registry
         .get_provider(provider_type)
         .context(format!("Provider '{}' not available", provider_name))?;
@@ -1419,8 +1499,9 @@ pub fn merge_cross_provider(
     force: bool,
     no_backup: bool,
 ) -> Result<()> {
+    use super::providers::provider_registry;
     use crate::models::ChatSession;
-    use crate::providers::{ProviderRegistry, ProviderType};
+    use crate::providers::ProviderType;
 
     println!("\n{} Cross-Provider Merge", "[M]".blue().bold());
     println!("{}", "=".repeat(70));
@@ -1434,7 +1515,7 @@ pub fn merge_cross_provider(
         println!("{} Workspace filter: {}", "[*]".blue(), ws.cyan());
     }
 
-    let registry = ProviderRegistry::new();
+    let registry = provider_registry();
     let mut all_sessions: Vec<(String, ChatSession)> = Vec::new(); // (provider_name, session)
 
     // Parse and collect sessions from each provider
@@ -1599,8 +1680,9 @@ pub fn merge_all_providers(
     force: bool,
     no_backup: bool,
 ) -> Result<()> {
+    use super::providers::provider_registry;
     use crate::models::ChatSession;
-    use crate::providers::{ProviderRegistry, ProviderType};
+    use crate::providers::ProviderType;
 
     println!("\n{} Merge All Providers", "[M]".blue().bold());
     println!("{}", "=".repeat(70));
@@ -1609,7 +1691,7 @@ pub fn merge_all_providers(
         println!("{} Workspace filter: {}", "[*]".blue(), ws.cyan());
     }
 
-    let registry = ProviderRegistry::new();
+    let registry = provider_registry();
     let mut all_sessions: Vec<(String, ChatSession)> = Vec::new();
     let mut providers_found = 0;
 
@@ -0,0 +1,745 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Intelligence commands
+
+use anyhow::{bail, Context, Result};
+use colored::*;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use tabled::{settings::Style as TableStyle, Table, Tabled};
+
+use super::agency::{load_agent, run_agent_with_events};
+use super::harvest::{
+    delete_harvested_session, existing_db_path, message_content, open_harvest_db,
+};
+use crate::actions::{ensure_action_items_table, save_action_items};
+use crate::intelligence::{
+    ActionExtractor, ActionKind, DuplicateAction, SimilarityDetector, TitleGenerator,
+    TopicExtractor,
+};
+use crate::providers::GenericSession;
+use crate::search::{self, Embedder, HttpEmbedder, SearchHit};
+use crate::storage::{is_vscode_running, parse_session_json, set_session_title};
+use crate::text::{prefix_chars, truncate};
+use crate::topics::{ensure_topics_table, save_session_topics, topic_counts, TopicCount};
+
+/// Default similarity for sessions compared by the words they share
+const WORD_THRESHOLD: f32 = 0.6;
+
+/// Default similarity for sessions compared by their embeddings
+const EMBEDDING_THRESHOLD: f32 = 0.9;
+
+/// A pair of near-duplicate sessions and what to do about them
+#[derive(Debug, Serialize)]
+pub struct Duplicate {
+    pub score: f32,
+    pub action: DuplicateAction,
+    /// The session to delete, or to merge into `keep`
+    pub session: SearchHit,
+    /// The longer of the two, or the newer when they are the same length
+    pub keep: SearchHit,
+}
+
+#[derive(Tabled)]
+struct DuplicateRow {
+    #[tabled(rename = "Similarity")]
+    score: String,
+    #[tabled(rename = "Suggestion")]
+    action: String,
+    #[tabled(rename = "Session")]
+    session: String,
+    #[tabled(rename = "Keep")]
+    keep: String,
+}
+
+fn describe(hit: &SearchHit) -> String {
+    format!(
+        "{} ({}, {} messages) {}",
+        truncate(&hit.title, 32),
+        hit.provider,
+        hit.message_count,
+        &hit.id[..hit.id.len().min(8)]
+    )
+}
+
+/// Find near-duplicate harvested sessions and suggest which to merge or
+/// delete, deleting the copies when `delete` is set
+pub fn intelligence_dupes(
+    db_path: Option<&str>,
+    threshold: Option<f32>,
+    semantic: bool,
+    provider: Option<&str>,
+    limit: usize,
+    delete: bool,
+    json: bool,
+) -> Result<()> {
+    let mut conn = open_harvest_db(db_path)?;
+    let embedder = if semantic {
+        Some(HttpEmbedder::from_env().context(
+            "--semantic needs an embeddings endpoint: set CSM_EMBEDDING_URL, OPENAI_API_KEY, or OLLAMA_HOST",
+        )?)
+    } else {
+        None
+    };
+    let threshold = threshold.unwrap_or(if semantic {
+        EMBEDDING_THRESHOLD
+    } else {
+        WORD_THRESHOLD
+    });
+
+    let duplicates = find_duplicates(
+        &conn,
+        embedder.as_ref().map(|e| e as &dyn Embedder),
+        provider,
+        threshold,
+    )?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&duplicates)?);
+    } else {
+        print_duplicates(&duplicates, limit);
+    }
+
+    if delete {
+        let tx = conn.transaction()?;
+        let mut deleted = 0;
+        for id in copies(&duplicates) {
+            if delete_harvested_session(&tx, id, true)? {
+                deleted += 1;
+            }
+        }
+        tx.commit()?;
+        if !json {
+            println!(
+                "\n{} Deleted {} copied session(s); restore one with POST /api/sessions/{{id}}/restore",
+                "[+]".green(),
+                deleted
+            );
+        }
+    }
+    Ok(())
+}
+
+fn print_duplicates(duplicates: &[Duplicate], limit: usize) {
+    println!("\n{} Near-duplicate Sessions", "[*]".blue().bold());
+    println!("{}", "=".repeat(60));
+    if duplicates.is_empty() {
+        println!("\n{} No near-duplicate sessions found", "[!]".yellow());
+        return;
+    }
+
+    let rows: Vec<DuplicateRow> = duplicates
+        .iter()
+        .take(limit)
+        .map(|d| DuplicateRow {
+            score: format!("{:.0}%", d.score * 100.0),
+            action: d.action.to_string(),
+            session: describe(&d.session),
+            keep: describe(&d.keep),
+        })
+        .collect();
+    println!("{}", Table::new(&rows).with(TableStyle::ascii_rounded()));
+    if duplicates.len() > limit {
+        println!("   ... and {} more", duplicates.len() - limit);
+    }
+
+    let copies = copies(duplicates).len();
+    if copies > 0 {
+        println!(
+            "\n   {} of these are copies; {} removes them",
+            copies,
+            "--delete".cyan()
+        );
+    }
+}
+
+/// The sessions to delete: each copy once, and never a session that is
+/// kept in place of another
+fn copies(duplicates: &[Duplicate]) -> Vec<&str> {
+    let mut deleted = HashSet::new();
+    let mut kept = HashSet::new();
+    for duplicate in duplicates {
+        if duplicate.action != DuplicateAction::Delete
+            || deleted.contains(duplicate.keep.id.as_str())
+            || kept.contains(duplicate.session.id.as_str())
+        {
+            continue;
+        }
+        kept.insert(duplicate.keep.id.as_str());
+        deleted.insert(duplicate.session.id.as_str());
+    }
+    duplicates
+        .iter()
+        .map(|d| d.session.id.as_str())
+        .filter(|id| deleted.remove(id))
+        .collect()
+}
+
+/// Pairs of harvested sessions at least `threshold` alike, most alike
+/// first. Sessions are compared by their embeddings when given an
+/// `embedder`, and otherwise by the words they share.
+pub fn find_duplicates(
+    conn: &Connection,
+    embedder: Option<&dyn Embedder>,
+    provider: Option<&str>,
+    threshold: f32,
+) -> Result<Vec<Duplicate>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, title, provider, workspace_id, workspace_name, message_count, updated_at,
+                session_json
+         FROM sessions WHERE ?1 IS NULL OR provider = ?1",
+    )?;
+    let mut hits = Vec::new();
+    let mut sessions = Vec::new();
+    let mut rows = stmt.query([provider])?;
+    while let Some(row) = rows.next()? {
+        let hit = SearchHit {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            provider: row.get(2)?,
+            workspace_id: row.get(3)?,
+            workspace_name: row.get(4)?,
+            message_count: row.get::<_, Option<i64>>(5)?.unwrap_or(0),
+            updated_at: row.get(6)?,
+            score: 0.0,
+            snippet: None,
+            message_index: None,
+        };
+        if embedder.is_none() {
+            let text = session_text(conn, &hit.id, &row.get::<_, String>(7)?)?;
+            sessions.push((hit.id.clone(), text));
+        }
+        hits.push(hit);
+    }
+
+    let pairs: Vec<(usize, usize, f32)> = match embedder {
+        Some(embedder) => {
+            let vectors = search::session_embeddings(conn, &hits, embedder)?;
+            let mut pairs = Vec::new();
+            for i in 0..hits.len() {
+                for j in i + 1..hits.len() {
+                    let (Some(a), Some(b)) = (vectors.get(&hits[i].id), vectors.get(&hits[j].id))
+                    else {
+                        continue;
+                    };
+                    let score = search::cosine(a, b) as f32;
+                    if score >= threshold {
+                        pairs.push((i, j, score));
+                    }
+                }
+            }
+            pairs
+        }
+        None => {
+            let index: HashMap<&str, usize> = hits
+                .iter()
+                .enumerate()
+                .map(|(i, hit)| (hit.id.as_str(), i))
+                .collect();
+            SimilarityDetector::new()
+                .find_duplicates(&sessions, threshold)
+                .into_iter()
+                .map(|pair| {
+                    (
+                        index[pair.session_a_id.as_str()],
+                        index[pair.session_b_id.as_str()],
+                        pair.score,
+                    )
+                })
+                .collect()
+        }
+    };
+
+    let mut duplicates: Vec<Duplicate> = pairs
+        .into_iter()
+        .map(|(i, j, score)| {
+            let (a, b) = (&hits[i], &hits[j]);
+            let a_first = (a.message_count, a.updated_at) >= (b.message_count, b.updated_at);
+            let (keep, session) = if a_first { (a, b) } else { (b, a) };
+            Duplicate {
+                score,
+                action: DuplicateAction::for_score(score),
+                session: session.clone(),
+                keep: keep.clone(),
+            }
+        })
+        .collect();
+    duplicates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(duplicates)
+}
+
+/// Everything said in a session: its messages, or for sessions harvested
+/// without them, what its JSON holds
+fn session_text(conn: &Connection, session_id: &str, session_json: &str) -> Result<String> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, content_raw FROM messages_v2 WHERE session_id = ?1 ORDER BY message_index, id",
+    )?;
+    let messages = stmt
+        .query_map([session_id], |row| {
+            message_content(conn, row.get(0)?, row.get(1)?)
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    if !messages.is_empty() {
+        return Ok(messages.join("\n"));
+    }
+    Ok(parse_session_json(session_json)
+        .map(|session| session.collect_all_text())
+        .unwrap_or_default())
+}
+
+/// Characters of a conversation a model reads to title it
+const TITLE_EXCERPT_CHARS: usize = 3000;
+
+/// A new title for a harvested session
+#[derive(Debug, Serialize)]
+pub struct Retitle {
+    pub id: String,
+    pub provider: String,
+    #[serde(skip)]
+    workspace_id: Option<String>,
+    pub old_title: String,
+    pub new_title: String,
+}
+
+#[derive(Tabled)]
+struct RetitleRow {
+    #[tabled(rename = "Session")]
+    id: String,
+    #[tabled(rename = "Provider")]
+    provider: String,
+    #[tabled(rename = "Old title")]
+    old_title: String,
+    #[tabled(rename = "New title")]
+    new_title: String,
+}
+
+/// Title harvested sessions that are "Untitled" or named after a cut-off
+/// first message, from that message or by asking `model`. Titles go into the
+/// database, and with `write_back` into VS Code's own session files.
+#[allow(clippy::too_many_arguments)]
+pub fn intelligence_retitle(
+    db_path: Option<&str>,
+    dry_run: bool,
+    all: bool,
+    model: Option<&str>,
+    provider: Option<&str>,
+    limit: Option<usize>,
+    write_back: bool,
+    json: bool,
+) -> Result<()> {
+    let db_path = existing_db_path(db_path)?;
+    let mut conn = Connection::open(&db_path)
+        .with_context(|| format!("Failed to open {}", db_path.display()))?;
+
+    let titles = TitleGenerator::new();
+    let mut retitles = Vec::new();
+    let mut sessions = Vec::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT id, provider, workspace_id, title, session_json FROM sessions
+             WHERE ?1 IS NULL OR provider = ?1 ORDER BY updated_at DESC",
+        )?;
+        let mut rows = stmt.query([provider])?;
+        while let Some(row) = rows.next()? {
+            let session_json: String = row.get(4)?;
+            let Ok(session) = parse_session_json(&session_json) else {
+                continue;
+            };
+            let untitled = if all {
+                session.custom_title.as_deref().is_none_or(|t| t.trim().is_empty())
+            } else {
+                TitleGenerator::needs_title(&session)
+            };
+            if !untitled {
+                continue;
+            }
+            if limit.is_some_and(|limit| sessions.len() >= limit) {
+                break;
+            }
+            sessions.push((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, String>(3)?,
+                session,
+                session_json,
+            ));
+        }
+    }
+
+    for (id, provider, workspace_id, old_title, session, session_json) in &sessions {
+        let new_title = match model {
+            Some(model) => {
+                if !json {
+                    println!("  {} {}", "Titling".dimmed(), truncate(old_title, 60));
+                }
+                let text = session_text(&conn, id, session_json)?;
+                model_title(model, &text, &titles)?
+            }
+            None => titles.generate(session),
+        };
+        let Some(new_title) = new_title.filter(|title| title != old_title) else {
+            continue;
+        };
+        retitles.push(Retitle {
+            id: id.clone(),
+            provider: provider.clone(),
+            workspace_id: workspace_id.clone(),
+            old_title: old_title.clone(),
+            new_title,
+        });
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&retitles)?);
+    } else {
+        print_retitles(&retitles);
+    }
+    if dry_run || retitles.is_empty() {
+        if dry_run && !json {
+            println!("\n{} Dry run: no titles were changed", "[i]".blue());
+        }
+        return Ok(());
+    }
+
+    let tx = conn.transaction()?;
+    for retitle in &retitles {
+        let session_json: String = tx.query_row(
+            "SELECT session_json FROM sessions WHERE id = ?1",
+            [&retitle.id],
+            |row| row.get(0),
+        )?;
+        let mut session = parse_session_json(&session_json)?;
+        session.custom_title = Some(retitle.new_title.clone());
+        tx.execute(
+            "UPDATE sessions SET title = ?2, session_json = ?3 WHERE id = ?1",
+            rusqlite::params![retitle.id, retitle.new_title, serde_json::to_string(&session)?],
+        )?;
+    }
+    tx.commit()?;
+    if !json {
+        println!(
+            "\n{} Retitled {} session(s) in {}",
+            "[+]".green(),
+            retitles.len(),
+            db_path.display()
+        );
+    }
+
+    if write_back {
+        if is_vscode_running() && !json {
+            println!(
+                "{} VS Code is running; it may not show the new titles until it restarts",
+                "[!]".yellow()
+            );
+        }
+        let mut written = 0;
+        for retitle in &retitles {
+            let Some(workspace_id) = &retitle.workspace_id else {
+                continue;
+            };
+            if set_session_title(workspace_id, &retitle.id, &retitle.new_title)? {
+                written += 1;
+            }
+        }
+        if !json {
+            println!(
+                "{} Wrote {} title(s) back to VS Code session files",
+                "[+]".green(),
+                written
+            );
+        }
+    }
+    Ok(())
+}
+
+fn print_retitles(retitles: &[Retitle]) {
+    println!("\n{} Session Titles", "[*]".blue().bold());
+    println!("{}", "=".repeat(60));
+    if retitles.is_empty() {
+        println!("\n{} No sessions need a title", "[!]".yellow());
+        return;
+    }
+    let rows: Vec<RetitleRow> = retitles
+        .iter()
+        .map(|r| RetitleRow {
+            id: r.id[..r.id.len().min(8)].to_string(),
+            provider: r.provider.clone(),
+            old_title: truncate(&r.old_title.replace('\n', " "), 40),
+            new_title: r.new_title.clone(),
+        })
+        .collect();
+    println!("{}", Table::new(&rows).with(TableStyle::ascii_rounded()));
+}
+
+/// A title written by `model` from the start of the conversation
+fn model_title(model: &str, text: &str, titles: &TitleGenerator) -> Result<Option<String>> {
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    let prompt = format!(
+        "Write a title of at most eight words for this conversation. Reply with the title alone.\n\n{}",
+        prefix_chars(text, TITLE_EXCERPT_CHARS)
+    );
+    let agent = load_agent("assistant", Some(model))?;
+    let result = run_agent_with_events(agent, &prompt, &mut |_| {}, &|| false)?;
+    Ok(titles.tidy(&result.response))
+}
+
+/// What a pass of `csm intelligence actions` found
+#[derive(Debug, Default, Serialize)]
+pub struct ActionScan {
+    pub sessions: usize,
+    pub items: usize,
+    /// Items not found by an earlier pass
+    pub new: usize,
+    pub todos: usize,
+    pub decisions: usize,
+    pub commitments: usize,
+}
+
+/// Find TODOs, decisions and commitments in harvested sessions and store
+/// them in `action_items`
+pub fn intelligence_actions(
+    db_path: Option<&str>,
+    provider: Option<&str>,
+    session: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    let mut conn = open_harvest_db(db_path)?;
+    ensure_action_items_table(&conn)?;
+
+    let sessions = {
+        let mut stmt = conn.prepare(
+            "SELECT id, session_json FROM sessions
+             WHERE (?1 IS NULL OR provider = ?1)
+               AND (?2 IS NULL OR id = ?2 OR id LIKE ?2 || '%')",
+        )?;
+        let rows = stmt.query_map([provider, session], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()?
+    };
+    if let (Some(session), true) = (session, sessions.is_empty()) {
+        bail!("No harvested session with ID {}", session);
+    }
+
+    let extractor = ActionExtractor::new();
+    let mut scan = ActionScan::default();
+    let tx = conn.transaction()?;
+    for (id, session_json) in &sessions {
+        let Ok(session) = parse_session_json(session_json) else {
+            continue;
+        };
+        let session = GenericSession::from(session);
+        let items = extractor.extract(
+            session
+                .messages
+                .iter()
+                .map(|message| (message.role.as_str(), message.content.as_str())),
+        );
+        scan.sessions += 1;
+        scan.items += items.len();
+        for item in &items {
+            match item.kind {
+                ActionKind::Todo => scan.todos += 1,
+                ActionKind::Decision => scan.decisions += 1,
+                ActionKind::Commitment => scan.commitments += 1,
+            }
+        }
+        scan.new += save_action_items(&tx, id, &items)?;
+    }
+    tx.commit()?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&scan)?);
+        return Ok(());
+    }
+    println!(
+        "{} Read {} session(s): {} TODO(s), {} decision(s), {} commitment(s)",
+        "[+]".green(),
+        scan.sessions,
+        scan.todos,
+        scan.decisions,
+        scan.commitments
+    );
+    println!("    {} new since the last pass", scan.new);
+    println!("{}", "[i] List them with 'csm actions list'".dimmed());
+    Ok(())
+}
+
+#[derive(Tabled)]
+struct TopicRow {
+    #[tabled(rename = "Topic")]
+    topic: String,
+    #[tabled(rename = "Sessions")]
+    sessions: i64,
+}
+
+/// What a pass of `csm intelligence topics` found
+#[derive(Debug, Default, Serialize)]
+pub struct TopicScan {
+    pub sessions: usize,
+    /// Sessions filed under at least one topic
+    pub tagged: usize,
+    pub topics: Vec<TopicCount>,
+}
+
+/// File harvested sessions under the topics of the `[topics]` taxonomy and
+/// store them in `session_topics`. With `list`, only show what is stored.
+pub fn intelligence_topics(
+    db_path: Option<&str>,
+    provider: Option<&str>,
+    session: Option<&str>,
+    list: bool,
+    json: bool,
+) -> Result<()> {
+    let mut conn = open_harvest_db(db_path)?;
+    ensure_topics_table(&conn)?;
+
+    let mut scan = TopicScan::default();
+    if !list {
+        let extractor = TopicExtractor::from_config(&crate::config::current().topics)
+            .context("Invalid [topics] configuration")?;
+        let sessions = {
+            let mut stmt = conn.prepare(
+                "SELECT id, session_json FROM sessions
+                 WHERE (?1 IS NULL OR provider = ?1)
+                   AND (?2 IS NULL OR id = ?2 OR id LIKE ?2 || '%')",
+            )?;
+            let rows = stmt.query_map([provider, session], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        if let (Some(session), true) = (session, sessions.is_empty()) {
+            bail!("No harvested session with ID {}", session);
+        }
+
+        let tx = conn.transaction()?;
+        for (id, session_json) in &sessions {
+            let Ok(session) = parse_session_json(session_json) else {
+                continue;
+            };
+            let text: Vec<String> = GenericSession::from(session)
+                .messages
+                .into_iter()
+                .map(|message| message.content)
+                .collect();
+            let topics = extractor.extract_text(&text.join("\n"));
+            scan.sessions += 1;
+            if !topics.is_empty() {
+                scan.tagged += 1;
+            }
+            save_session_topics(&tx, id, &topics)?;
+        }
+        tx.commit()?;
+    }
+    scan.topics = topic_counts(&conn)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&scan)?);
+        return Ok(());
+    }
+    if !list {
+        println!(
+            "{} Read {} session(s), {} filed under a topic",
+            "[+]".green(),
+            scan.sessions,
+            scan.tagged
+        );
+    }
+    if scan.topics.is_empty() {
+        println!(
+            "{} No topics yet; define them under [topics.taxonomy] in the config file",
+            "[!]".yellow()
+        );
+        return Ok(());
+    }
+    let rows: Vec<TopicRow> = scan
+        .topics
+        .iter()
+        .map(|count| {
+            let depth = count.topic.matches('/').count();
+            let name = count.topic.rsplit('/').next().unwrap_or(&count.topic);
+            TopicRow {
+                topic: format!("{}{}", "  ".repeat(depth), name),
+                sessions: count.sessions,
+            }
+        })
+        .collect();
+    println!("{}", Table::new(rows).with(TableStyle::ascii_rounded()));
+    println!(
+        "{}",
+        "[i] Filter with 'csm harvest list --topic <name>' or 'csm harvest search --topic <name>'"
+            .dimmed()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::insert_or_update_session;
+    use crate::models::ChatSession;
+
+    fn session(id: &str, turns: &[&str]) -> ChatSession {
+        let requests: Vec<_> = turns
+            .iter()
+            .map(|text| {
+                serde_json::json!({
+                    "message": { "text": text },
+                    "response": [{ "value": format!("Answer to: {}", text) }]
+                })
+            })
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "sessionId": id,
+            "creationDate": 1,
+            "lastMessageDate": 1,
+            "requests": requests
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("harvest.db");
+        super::super::harvest_init(Some(path.to_str().unwrap()), false).unwrap();
+        let conn = Connection::open(&path).unwrap();
+
+        let question = "how do I read a file line by line in rust";
+        let follow_up = "and skip the empty lines";
+        let sessions = [
+            ("copilot-1", "copilot", session("copilot-1", &[question])),
+            (
+                "cursor-1",
+                "cursor",
+                session("cursor-1", &[question, follow_up]),
+            ),
+            ("copilot-2", "copilot", session("copilot-2", &[question])),
+            (
+                "other",
+                "copilot",
+                session("other", &["write a haiku about the sea"]),
+            ),
+        ];
+        for (_, provider, session) in &sessions {
+            insert_or_update_session(&conn, session, provider, None, None).unwrap();
+        }
+
+        let duplicates = find_duplicates(&conn, None, None, 0.5).unwrap();
+        assert_eq!(duplicates.len(), 3);
+        // The two identical sessions are copies
+        assert_eq!(duplicates[0].action, DuplicateAction::Delete);
+        // The longer session is kept over a re-ask
+        let merge = &duplicates[1];
+        assert_eq!(merge.action, DuplicateAction::Merge);
+        assert_eq!(merge.keep.id, "cursor-1");
+        assert_eq!(copies(&duplicates).len(), 1);
+
+        let copilot = find_duplicates(&conn, None, Some("copilot"), 0.5).unwrap();
+        assert_eq!(copilot.len(), 1);
+    }
+}
@@ -0,0 +1,244 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Issue commands
+
+use anyhow::{anyhow, bail, Context, Result};
+use colored::*;
+use rusqlite::Connection;
+use tabled::{settings::Style as TableStyle, Table, Tabled};
+
+use super::agency::find_harvested;
+use super::harvest::open_harvest_db;
+use super::summary::{jira_escape, SessionSummary};
+use crate::actions::StoredActionItem;
+use crate::integrations::issues::{
+    ensure_session_issues_table, issue_title, list_session_issues, save_session_issue, tracker,
+    Markup, NewIssue,
+};
+use crate::models::ChatSession;
+use crate::text::truncate;
+
+#[derive(Tabled)]
+struct IssueRow {
+    #[tabled(rename = "Issue")]
+    key: String,
+    #[tabled(rename = "Session")]
+    session: String,
+    #[tabled(rename = "Title")]
+    title: String,
+    #[tabled(rename = "URL")]
+    url: String,
+}
+
+fn open_db(db_path: Option<&str>) -> Result<Connection> {
+    let conn = open_harvest_db(db_path)?;
+    ensure_session_issues_table(&conn)?;
+    Ok(conn)
+}
+
+fn runtime() -> Result<tokio::runtime::Runtime> {
+    Ok(tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?)
+}
+
+/// Where issues link to a session: `session_url` under `[integrations]`
+/// with its ID, or else its `csm://` resource
+pub(crate) fn session_link(session_id: &str) -> String {
+    match &crate::config::current().integrations.session_url {
+        Some(url) => url.replace("{id}", session_id),
+        None => format!("csm://session/{}", session_id),
+    }
+}
+
+/// An issue for a session: its title, and a summary of what was asked,
+/// decided and left to do
+pub(crate) fn session_issue(
+    markup: Markup,
+    session_id: &str,
+    provider: &str,
+    chat: &ChatSession,
+    title: Option<&str>,
+) -> NewIssue {
+    let mut summary = SessionSummary::new(session_id, provider, chat);
+    summary.link = Some(session_link(session_id));
+    NewIssue {
+        title: issue_title(title.unwrap_or(&summary.title)),
+        description: match markup {
+            Markup::Markdown => summary.to_markdown(),
+            Markup::Jira => summary.to_jira(),
+        },
+        labels: vec!["csm".to_string()],
+    }
+}
+
+/// An issue for an action item, linking to the session it was found in
+pub(crate) fn item_issue(markup: Markup, item: &StoredActionItem) -> NewIssue {
+    let link = session_link(&item.session_id);
+    let description = match markup {
+        Markup::Markdown => format!(
+            "{}\n\nFrom the chat session \"{}\" ([`{}`]({}))\n",
+            item.text, item.session_title, item.session_id, link
+        ),
+        Markup::Jira => format!(
+            "{}\n\nFrom the chat session \"{}\" ([{}|{}])",
+            jira_escape(&item.text),
+            jira_escape(&item.session_title),
+            item.session_id,
+            link
+        ),
+    };
+    NewIssue {
+        title: issue_title(&item.text),
+        description,
+        labels: vec!["csm".to_string(), item.kind.clone()],
+    }
+}
+
+fn tracker_name(name: &str) -> &str {
+    match name {
+        "jira" => "Jira",
+        "linear" => "Linear",
+        other => other,
+    }
+}
+
+/// File an issue for a harvested session in Jira or Linear
+pub fn issue_create(
+    db_path: Option<&str>,
+    session: &str,
+    tracker_arg: Option<&str>,
+    project: Option<&str>,
+    title: Option<&str>,
+    dry_run: bool,
+    force: bool,
+) -> Result<()> {
+    let conn = open_db(db_path)?;
+    let (session_id, provider, chat) = find_harvested(&conn, session)?;
+    let tracker = tracker(tracker_arg, project)?;
+    let filed: Vec<_> = list_session_issues(&conn, Some(&session_id), Some(tracker.name()))?
+        .into_iter()
+        .filter(|stored| stored.session_id == session_id)
+        .collect();
+    if let Some(stored) = filed.first().filter(|_| !force) {
+        println!(
+            "{} Session {} was filed as {} {}; pass --force to file another issue",
+            "[i]".blue(),
+            session_id,
+            stored.issue.key,
+            stored.issue.url.dimmed()
+        );
+        return Ok(());
+    }
+
+    let issue = session_issue(tracker.markup(), &session_id, &provider, &chat, title);
+    if dry_run {
+        println!("{}\n\n{}", issue.title.bold(), issue.description);
+        println!(
+            "\n{} Dry run: nothing was filed in {}",
+            "[i]".blue(),
+            tracker_name(tracker.name())
+        );
+        return Ok(());
+    }
+    let created = runtime()?
+        .block_on(tracker.create_issue(&issue))
+        .map_err(|e| anyhow!(e))?;
+    save_session_issue(&conn, &session_id, &created)?;
+    println!(
+        "{} Filed {} for session {} {}",
+        "[+]".green(),
+        created.key,
+        session_id,
+        created.url.dimmed()
+    );
+    Ok(())
+}
+
+/// List the issues filed for sessions
+pub fn issue_list(
+    db_path: Option<&str>,
+    session: Option<&str>,
+    tracker: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    let conn = open_db(db_path)?;
+    let tracker = tracker.map(str::to_lowercase);
+    let issues = list_session_issues(&conn, session, tracker.as_deref())?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&issues)?);
+        return Ok(());
+    }
+    println!("\n{} Session Issues", "[*]".blue().bold());
+    println!("{}", "=".repeat(60));
+    if issues.is_empty() {
+        println!(
+            "\n{} No issues filed; file one with 'csm issue create <session>'",
+            "[!]".yellow()
+        );
+        return Ok(());
+    }
+    let rows: Vec<IssueRow> = issues
+        .iter()
+        .map(|stored| IssueRow {
+            key: stored.issue.key.clone(),
+            session: truncate(&stored.session_id, 12),
+            title: truncate(&stored.session_title, 40),
+            url: stored.issue.url.clone(),
+        })
+        .collect();
+    println!("{}", Table::new(rows).with(TableStyle::ascii_rounded()));
+    Ok(())
+}
+
+/// File an issue for each action item not filed in the tracker yet,
+/// recording it as the item's `exported_to`
+pub(crate) fn export_issues(
+    conn: &Connection,
+    items: &[StoredActionItem],
+    tracker_arg: &str,
+    project: Option<&str>,
+    dry_run: bool,
+) -> Result<()> {
+    let prefix = format!("{}:", tracker_arg);
+    let pending: Vec<&StoredActionItem> = items
+        .iter()
+        .filter(|item| {
+            !item
+                .exported_to
+                .as_deref()
+                .is_some_and(|to| to.starts_with(&prefix))
+        })
+        .collect();
+    if pending.is_empty() {
+        println!("{} Nothing new to export", "[i]".blue());
+        return Ok(());
+    }
+    if dry_run {
+        for item in &pending {
+            println!("  {} {}", "Would file".dimmed(), item.text);
+        }
+        println!("\n{} Dry run: no issues were filed", "[i]".blue());
+        return Ok(());
+    }
+
+    let tracker = tracker(Some(tracker_arg), project)?;
+    let rt = runtime()?;
+    let mut created = 0;
+    for item in pending {
+        let issue = rt
+            .block_on(tracker.create_issue(&item_issue(tracker.markup(), item)))
+            .map_err(|e| anyhow!("Could not file an issue for #{}: {}", item.id, e))?;
+        crate::actions::set_action_exported(conn, item.id, &issue.reference())?;
+        println!("  {} {} {}", issue.key, item.text, issue.url.dimmed());
+        created += 1;
+    }
+    println!(
+        "{} Filed {} issue(s) in {}",
+        "[+]".green(),
+        created,
+        tracker_name(tracker.name())
+    );
+    Ok(())
+}
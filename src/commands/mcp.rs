@@ -0,0 +1,257 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! MCP client configuration commands
+
+use anyhow::{bail, Context, Result};
+use colored::*;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::workspace::get_vscode_user_path;
+
+/// How long csm-mcp gets to answer the connectivity check
+const VERIFY_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The client's configuration file
+fn client_config_path(client: &str, project: bool) -> Result<PathBuf> {
+    let cwd = std::env::current_dir()?;
+    let home = || dirs::home_dir().context("Could not find the home directory");
+    Ok(match (client, project) {
+        ("claude-desktop", false) => dirs::config_dir()
+            .context("Could not find the configuration directory")?
+            .join("Claude")
+            .join("claude_desktop_config.json"),
+        ("claude-desktop", true) => {
+            bail!("Claude Desktop has no project configuration; drop --project")
+        }
+        ("vscode", false) => get_vscode_user_path()?.join("mcp.json"),
+        ("vscode", true) => cwd.join(".vscode").join("mcp.json"),
+        ("cursor", false) => home()?.join(".cursor").join("mcp.json"),
+        ("cursor", true) => cwd.join(".cursor").join("mcp.json"),
+        _ => bail!(
+            "Unknown client '{}' (expected claude-desktop, vscode, or cursor)",
+            client
+        ),
+    })
+}
+
+/// Key holding the server map; VS Code calls it `servers`
+fn servers_key(client: &str) -> &'static str {
+    if client == "vscode" {
+        "servers"
+    } else {
+        "mcpServers"
+    }
+}
+
+fn server_entry(client: &str, command: &Path, args: &[String]) -> Value {
+    let mut entry = json!({
+        "command": command.display().to_string(),
+        "args": args,
+    });
+    if client == "vscode" {
+        entry["type"] = json!("stdio");
+    }
+    entry
+}
+
+/// csm-mcp next to this executable, or else on the `PATH`
+fn find_server_binary() -> Result<PathBuf> {
+    let name = format!("csm-mcp{}", std::env::consts::EXE_SUFFIX);
+    if let Some(sibling) = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.canonicalize().ok())
+        .map(|exe| exe.with_file_name(&name))
+        .filter(|path| path.is_file())
+    {
+        return Ok(sibling);
+    }
+    std::env::var_os("PATH")
+        .and_then(|path| {
+            std::env::split_paths(&path)
+                .map(|dir| dir.join(&name))
+                .find(|path| path.is_file())
+        })
+        .context("csm-mcp was not found next to chasm or on the PATH; install it with `cargo install chasm-cli`")
+}
+
+/// Start csm-mcp, initialize, and list its tools, returning the server's
+/// name and version and the number of tools it offers
+fn verify_server(command: &Path, args: &[String]) -> Result<(String, usize)> {
+    let mut child = Command::new(command)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to start {}", command.display()))?;
+
+    // Closing stdin after the requests lets the server exit once it answers
+    let mut stdin = child.stdin.take().context("No stdin for csm-mcp")?;
+    let requests = [
+        json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": {"name": "csm mcp install", "version": env!("CARGO_PKG_VERSION")},
+        }}),
+        json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+        json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}),
+    ];
+    for request in &requests {
+        writeln!(stdin, "{}", request)?;
+    }
+    drop(stdin);
+
+    let stdout = child.stdout.take().context("No stdout for csm-mcp")?;
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(|l| l.ok()) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut server = None;
+    let mut tools = None;
+    while server.is_none() || tools.is_none() {
+        let Ok(line) = rx.recv_timeout(VERIFY_TIMEOUT) else {
+            let _ = child.kill();
+            bail!(
+                "csm-mcp did not answer within {}s",
+                VERIFY_TIMEOUT.as_secs()
+            );
+        };
+        let Ok(response) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        let id = response.get("id").and_then(Value::as_i64);
+        if let (Some(1 | 2), Some(error)) = (id, response.get("error")) {
+            let _ = child.kill();
+            bail!("csm-mcp answered with an error: {}", error);
+        }
+        match id {
+            Some(1) => {
+                let info = &response["result"]["serverInfo"];
+                server = Some(format!(
+                    "{} {}",
+                    info["name"].as_str().unwrap_or("csm-mcp"),
+                    info["version"].as_str().unwrap_or_default()
+                ));
+            }
+            Some(2) => {
+                tools = Some(
+                    response["result"]["tools"]
+                        .as_array()
+                        .map_or(0, |tools| tools.len()),
+                );
+            }
+            _ => {}
+        }
+    }
+    let _ = child.wait();
+    Ok((server.unwrap_or_default(), tools.unwrap_or_default()))
+}
+
+/// Add csm-mcp to an MCP client's configuration
+pub fn mcp_install(
+    client: &str,
+    project: bool,
+    name: &str,
+    allow_write: bool,
+    config: Option<&str>,
+    no_verify: bool,
+) -> Result<()> {
+    let path = match config {
+        Some(path) => PathBuf::from(path),
+        None => client_config_path(client, project)?,
+    };
+    let command = find_server_binary()?;
+    let args: Vec<String> = if allow_write {
+        vec!["--allow-write".to_string()]
+    } else {
+        Vec::new()
+    };
+
+    if !no_verify {
+        let (server, tools) = verify_server(&command, &args)?;
+        println!(
+            "{} {} answered with {} tools",
+            "[+]".green(),
+            server.cyan(),
+            tools
+        );
+    }
+
+    // Keep everything else in the file; refuse to rewrite what we cannot parse
+    let mut settings = match std::fs::read_to_string(&path) {
+        Ok(content) if !content.trim().is_empty() => {
+            serde_json::from_str::<Value>(&content).with_context(|| {
+                format!(
+                    "{} is not plain JSON (it may contain comments); add this under \"{}\" by hand:\n\"{}\": {}",
+                    path.display(),
+                    servers_key(client),
+                    name,
+                    server_entry(client, &command, &args)
+                )
+            })?
+        }
+        Ok(_) => json!({}),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => json!({}),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let Some(root) = settings.as_object_mut() else {
+        bail!("{} does not hold a JSON object", path.display());
+    };
+    let servers = root.entry(servers_key(client)).or_insert_with(|| json!({}));
+    let Some(servers) = servers.as_object_mut() else {
+        bail!(
+            "\"{}\" in {} is not an object",
+            servers_key(client),
+            path.display()
+        );
+    };
+
+    let entry = server_entry(client, &command, &args);
+    let action = match servers.get(name) {
+        Some(existing) if *existing == entry => {
+            println!(
+                "{} {} is already configured in {}",
+                "[i]".dimmed(),
+                name.cyan(),
+                path.display()
+            );
+            return Ok(());
+        }
+        Some(_) => "Updated",
+        None => "Added",
+    };
+    servers.insert(name.to_string(), entry);
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(&settings)? + "\n")
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    println!(
+        "{} {} {} in {}",
+        "[+]".green(),
+        action,
+        name.cyan(),
+        path.display()
+    );
+    println!(
+        "   Command: {}",
+        std::iter::once(command.display().to_string())
+            .chain(args.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    println!("   Restart the client to pick up the change.");
+    Ok(())
+}
@@ -7,6 +7,7 @@ use colored::*;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
+use crate::storage::copy_file_atomic;
 use crate::workspace::discover_workspaces;
 
 /// Migration package manifest
@@ -201,7 +202,7 @@ pub fn restore_migration(package: &str, mapping: Option<&str>, dry_run: bool) ->
                 std::fs::create_dir_all(&sessions_dst)?;
                 for entry in std::fs::read_dir(&sessions_src)? {
                     let entry = entry?;
-                    std::fs::copy(entry.path(), sessions_dst.join(entry.file_name()))?;
+                    copy_file_atomic(&entry.path(), &sessions_dst.join(entry.file_name()))?;
                 }
             }
         }
@@ -2,29 +2,74 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //! Command implementations
 
+mod actions;
 mod agency;
+mod automation;
+mod backup;
+mod config;
 mod detect;
+mod diff;
 mod export_import;
+mod extract;
 mod git;
+mod github;
+mod graph;
 mod harvest;
 mod history;
+mod intelligence;
+mod issues;
+mod mcp;
 mod migration;
+mod notify;
+mod obsidian;
+mod pack;
+mod plugin;
+mod project;
 mod providers;
 mod recover;
+mod recover_wizard;
 mod register;
+mod remote;
+mod report;
+mod routing;
+mod restore_browser;
 pub mod run;
+mod slack;
+mod summary;
 mod telemetry;
 mod workspace_cmds;
 
+pub use actions::*;
 pub use agency::*;
+pub use automation::*;
+pub use backup::*;
+pub use config::*;
 pub use detect::*;
+pub use diff::*;
 pub use export_import::*;
+pub use extract::*;
 pub use git::*;
+pub use github::*;
+pub use graph::*;
 pub use harvest::*;
 pub use history::*;
+pub use intelligence::*;
+pub use issues::*;
+pub use mcp::*;
 pub use migration::*;
+pub use notify::*;
+pub use obsidian::*;
+pub use pack::*;
+pub use plugin::*;
+pub use project::*;
 pub use providers::*;
 pub use recover::*;
+pub use recover_wizard::*;
 pub use register::*;
+pub use remote::*;
+pub use report::*;
+pub use routing::*;
+pub use restore_browser::*;
+pub use slack::*;
 pub use telemetry::*;
 pub use workspace_cmds::*;
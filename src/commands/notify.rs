@@ -0,0 +1,155 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Notification channel commands
+
+use anyhow::Result;
+use colored::*;
+use std::collections::BTreeMap;
+
+use crate::config::{ChannelConfig, Config};
+use crate::notifications::{self, Channel, Notification, NotificationLevel};
+
+/// List configured channels and available providers
+pub fn notify_list() -> Result<()> {
+    let config = Config::load()?;
+    let registry = notifications::registry();
+
+    println!("{}", "Channels".bold());
+    if config.notifications.channels.is_empty() {
+        println!(
+            "  None configured. Add one with {}",
+            "csm notify add <name> <provider>".cyan()
+        );
+    }
+    for (name, channel_config) in &config.notifications.channels {
+        let channel = Channel::new(name, channel_config.clone());
+        let status = match registry.validate(&channel) {
+            Err(e) => format!("{} {}", "[!]".yellow(), e),
+            Ok(()) if !channel.is_enabled() => "disabled".dimmed().to_string(),
+            Ok(()) => "ok".green().to_string(),
+        };
+        let events = channel_config
+            .events
+            .as_ref()
+            .map(|e| e.join(", "))
+            .unwrap_or_else(|| "*".to_string());
+        println!(
+            "  {:<16} {:<10} events: {:<24} {}",
+            name.cyan(),
+            channel_config.provider,
+            events,
+            status
+        );
+    }
+
+    println!();
+    println!("{}", "Providers".bold());
+    for provider in registry.providers() {
+        let required = provider.required_settings();
+        let settings = if required.is_empty() {
+            String::new()
+        } else {
+            format!("(requires {})", required.join(", "))
+                .dimmed()
+                .to_string()
+        };
+        println!(
+            "  {:<10} {:<22} {}",
+            provider.id(),
+            provider.name(),
+            settings
+        );
+    }
+    Ok(())
+}
+
+/// Send a test notification to one channel
+pub fn notify_test(channel: &str, message: Option<&str>) -> Result<()> {
+    let channel = notifications::find_channel(channel)?;
+    let notification = Notification::new(
+        "notify.test",
+        "csm test notification",
+        message.unwrap_or("Notifications from csm are working."),
+    )
+    .with_level(NotificationLevel::Success);
+
+    println!(
+        "{} Sending test notification to {} ({})...",
+        "[*]".blue(),
+        channel.name.cyan(),
+        channel.config.provider
+    );
+    notifications::registry().send(&channel, &notification)?;
+    println!("{} Delivered", "[OK]".green().bold());
+    Ok(())
+}
+
+/// Add or replace a channel in the config file
+pub fn notify_add(
+    name: &str,
+    provider: &str,
+    url: Option<String>,
+    to: Option<String>,
+    events: Option<Vec<String>>,
+    template: Option<String>,
+) -> Result<()> {
+    let mut settings = BTreeMap::new();
+    if let Some(url) = url {
+        settings.insert("url".to_string(), url);
+    }
+    if let Some(to) = to {
+        settings.insert("to".to_string(), to);
+    }
+    let channel = Channel::new(
+        name,
+        ChannelConfig {
+            provider: provider.to_lowercase(),
+            events,
+            template,
+            settings,
+            ..Default::default()
+        },
+    );
+
+    if let Err(e) = notifications::registry().validate(&channel) {
+        // Settings read from unset environment variables only fail at send time
+        let uses_env = channel.config.settings.values().any(|v| v.starts_with('$'));
+        if notifications::registry().get(provider).is_none() || !uses_env {
+            return Err(e.into());
+        }
+        println!("{} {}", "[!]".yellow(), e);
+    }
+
+    let mut config = Config::load()?;
+    let replaced = config
+        .notifications
+        .channels
+        .insert(name.to_string(), channel.config)
+        .is_some();
+    let path = config.save()?;
+
+    println!(
+        "{} {} channel {} ({})",
+        "[OK]".green().bold(),
+        if replaced { "Updated" } else { "Added" },
+        name.cyan(),
+        provider
+    );
+    println!("   Saved to {}", path.display().to_string().dimmed());
+    println!(
+        "   Try it with {}",
+        format!("csm notify test {}", name).cyan()
+    );
+    Ok(())
+}
+
+/// Remove a channel from the config file
+pub fn notify_remove(name: &str) -> Result<()> {
+    let mut config = Config::load()?;
+    if config.notifications.channels.remove(name).is_none() {
+        anyhow::bail!("No channel named '{}'", name);
+    }
+    config.save()?;
+    println!("{} Removed channel {}", "[OK]".green().bold(), name.cyan());
+    Ok(())
+}
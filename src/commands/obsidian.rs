@@ -0,0 +1,135 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Obsidian commands
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use colored::*;
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::harvest::{existing_db_path, WatchHarvester};
+use crate::config::{expand_home, Config};
+use crate::integrations::obsidian::{ObsidianSync, SyncStats};
+use crate::storage::watcher::{SessionEventKind, SessionWatcher};
+use crate::workspace::get_workspace_storage_path;
+
+/// Folder in the vault for the notes when none is configured
+const DEFAULT_FOLDER: &str = "Chat Sessions";
+
+fn describe(stats: &SyncStats) -> String {
+    format!(
+        "{} created, {} updated, {} deleted, {} index{} written ({} sessions)",
+        stats.created,
+        stats.updated,
+        stats.deleted,
+        stats.indexes,
+        if stats.indexes == 1 { "" } else { "es" },
+        stats.sessions
+    )
+}
+
+/// The vault from the argument or `[integrations.obsidian]`
+fn vault_dir(vault: Option<&str>) -> Result<PathBuf> {
+    let configured = &crate::config::current().integrations.obsidian;
+    let vault = vault
+        .map(String::from)
+        .or_else(|| configured.vault.clone())
+        .with_context(|| {
+            format!(
+                "Name a vault, or set vault under [integrations.obsidian] in {}",
+                Config::path()
+                    .map(|path| path.display().to_string())
+                    .unwrap_or_else(|_| "config.toml".to_string())
+            )
+        })?;
+    let vault = expand_home(Path::new(&vault));
+    if !vault.is_dir() {
+        bail!("Obsidian vault not found: {}", vault.display());
+    }
+    Ok(vault)
+}
+
+/// Write a note per harvested session and an index note per workspace into
+/// a vault folder, then, with `watch`, keep harvesting sessions as VS Code
+/// writes them and syncing the folder
+pub fn obsidian_sync(
+    db_path: Option<&str>,
+    vault: Option<&str>,
+    folder: Option<&str>,
+    watch: bool,
+    debounce_ms: u64,
+) -> Result<()> {
+    let db_path = existing_db_path(db_path)?;
+    let vault = vault_dir(vault)?;
+    let configured = &crate::config::current().integrations.obsidian;
+    let folder = folder
+        .map(String::from)
+        .or_else(|| configured.folder.clone())
+        .unwrap_or_else(|| DEFAULT_FOLDER.to_string());
+    let sync = ObsidianSync::new(vault.join(&folder));
+
+    println!("\n{} Obsidian Sync", "[*]".blue().bold());
+    println!("{}", "=".repeat(60));
+    println!("   Vault:    {}", vault.display());
+    println!("   Folder:   {}", folder);
+    println!("   Database: {}", db_path.display());
+    if !vault.join(".obsidian").is_dir() {
+        println!(
+            "{} {} has not been opened as a vault in Obsidian",
+            "[!]".yellow(),
+            vault.display()
+        );
+    }
+
+    if !watch {
+        let conn = Connection::open(&db_path)
+            .with_context(|| format!("Failed to open {}", db_path.display()))?;
+        let stats = sync.sync(&conn)?;
+        println!("\n{} {}", "[+]".green(), describe(&stats));
+        return Ok(());
+    }
+
+    let mut harvester = WatchHarvester::open(&db_path)?;
+    let stats = sync.sync(harvester.conn())?;
+    println!("\n{} {}", "[+]".green(), describe(&stats));
+
+    let storage_path = get_workspace_storage_path()?;
+    let watcher = SessionWatcher::watch(&storage_path, Duration::from_millis(debounce_ms))?;
+    println!("\n{} Watching for session changes", "[*]".blue().bold());
+    println!("   Storage:  {}", storage_path.display());
+    println!("   Press Ctrl+C to stop\n");
+
+    while let Some(event) = watcher.recv() {
+        // Sync once for the events that arrived together
+        for event in std::iter::once(event).chain(watcher.try_iter()) {
+            // Sessions deleted on disk stay harvested, and so keep their notes
+            if event.kind == SessionEventKind::Deleted {
+                continue;
+            }
+            let time = crate::datetime::format(Utc::now(), "%H:%M:%S");
+            match harvester.harvest(&event) {
+                Ok((session, _, updated)) => println!(
+                    "{} {} {}: {}",
+                    time.dimmed(),
+                    "[+]".green(),
+                    if updated { "Updated" } else { "Added" },
+                    session.title()
+                ),
+                Err(e) => println!("{} {} {}", time.dimmed(), "[!]".red(), e),
+            }
+        }
+
+        let time = crate::datetime::format(Utc::now(), "%H:%M:%S");
+        match sync.sync(harvester.conn()) {
+            Ok(stats) if stats.changed() => {
+                println!("{} {} {}", time.dimmed(), "[+]".green(), describe(&stats))
+            }
+            Ok(_) => {}
+            Err(e) => println!("{} {} Sync failed: {}", time.dimmed(), "[!]".red(), e),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,275 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Pack export and import commands
+
+use anyhow::Result;
+use colored::*;
+use std::path::Path;
+
+use crate::datetime::format_datetime_short;
+use crate::pack::{
+    key_from_env, Library, Pack, PackItem, PackItemKind, Verification, PACK_KEY_ENV,
+};
+
+/// Library items chosen for export, by kind
+#[derive(Debug, Default)]
+pub struct PackSelection {
+    pub workflows: Vec<String>,
+    pub agents: Vec<String>,
+    pub prompts: Vec<String>,
+    pub searches: Vec<String>,
+}
+
+impl PackSelection {
+    fn is_empty(&self) -> bool {
+        self.workflows.is_empty()
+            && self.agents.is_empty()
+            && self.prompts.is_empty()
+            && self.searches.is_empty()
+    }
+
+    fn names(&self) -> [(PackItemKind, &[String]); 4] {
+        [
+            (PackItemKind::Workflow, &self.workflows),
+            (PackItemKind::Agent, &self.agents),
+            (PackItemKind::Prompt, &self.prompts),
+            (PackItemKind::SavedSearch, &self.searches),
+        ]
+    }
+}
+
+fn print_items(items: &[PackItem]) {
+    for kind in PackItemKind::ALL {
+        let names: Vec<&str> = items
+            .iter()
+            .filter(|i| i.kind == kind)
+            .map(|i| i.name.as_str())
+            .collect();
+        if !names.is_empty() {
+            println!("  {:<16} {}", kind.dir_name(), names.join(", ").cyan());
+        }
+    }
+}
+
+/// List library items available for export
+pub fn pack_automation_list() -> Result<()> {
+    let library = Library::open()?;
+    let items = library.all_items()?;
+
+    println!("{} Library: {}", "[*]".blue(), library.root().display());
+    if items.is_empty() {
+        println!(
+            "  Empty. Add JSON files under {} or import a pack with {}",
+            "workflows/, agents/, prompts/, searches/".cyan(),
+            "csm pack import <file>".cyan()
+        );
+        return Ok(());
+    }
+    print_items(&items);
+    Ok(())
+}
+
+/// Export selected library items to a pack file
+pub fn pack_automation_export(
+    file: &str,
+    selection: PackSelection,
+    name: Option<&str>,
+    description: Option<&str>,
+) -> Result<()> {
+    let library = Library::open()?;
+
+    let items = if selection.is_empty() {
+        library.all_items()?
+    } else {
+        let mut items = Vec::new();
+        for (kind, names) in selection.names() {
+            for name in names {
+                match library.get(kind, name)? {
+                    Some(item) => items.push(item),
+                    None => anyhow::bail!(
+                        "No {} named '{}' in {}",
+                        kind,
+                        name,
+                        library.root().display()
+                    ),
+                }
+            }
+        }
+        items
+    };
+    if items.is_empty() {
+        anyhow::bail!(
+            "Nothing to export: the library at {} is empty",
+            library.root().display()
+        );
+    }
+
+    let path = Path::new(file);
+    let name = name.map(String::from).unwrap_or_else(|| {
+        path.file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "automation".to_string())
+    });
+    let key = key_from_env();
+    let pack = Pack::automation(&name, description, items, key.as_deref())?;
+    pack.write(path)?;
+
+    println!(
+        "{} Exported {} item(s) to {}",
+        "[OK]".green().bold(),
+        pack.items.len(),
+        path.display()
+    );
+    print_items(&pack.items);
+    if !pack.manifest.redacted.is_empty() {
+        println!(
+            "{} Stripped {} secret value(s); importers will need to fill them in:",
+            "[!]".yellow(),
+            pack.manifest.redacted.len()
+        );
+        for field in &pack.manifest.redacted {
+            println!("   {}", field.dimmed());
+        }
+    }
+    if pack.is_signed() {
+        println!("{} Signed with ${}", "[+]".green(), PACK_KEY_ENV);
+    } else {
+        println!(
+            "{} Unsigned. Set ${} to a key shared with your team to sign packs",
+            "[!]".yellow(),
+            PACK_KEY_ENV
+        );
+    }
+    Ok(())
+}
+
+fn verification_label(verification: Verification) -> String {
+    match verification {
+        Verification::Verified => "signed, verified".to_string(),
+        Verification::Unsigned => "unsigned (digest ok)".to_string(),
+        Verification::NoKey => format!("signed, not verified (${} not set)", PACK_KEY_ENV),
+        Verification::Invalid => "INVALID signature".to_string(),
+    }
+}
+
+fn describe_verification(verification: Verification) -> ColoredString {
+    let label = verification_label(verification);
+    match verification {
+        Verification::Verified => label.green(),
+        Verification::Unsigned | Verification::NoKey => label.yellow(),
+        Verification::Invalid => label.red().bold(),
+    }
+}
+
+/// Show a pack's manifest and contents
+pub fn pack_inspect(file: &str) -> Result<()> {
+    let pack = Pack::read(Path::new(file))?;
+    let verification = pack.verify(key_from_env().as_deref());
+    let manifest = &pack.manifest;
+
+    println!("{} {}", "Pack:".bold(), manifest.name.cyan().bold());
+    if let Some(description) = &manifest.description {
+        println!("  {}", description);
+    }
+    println!("  {:<16} {}", "type", manifest.kind);
+    println!(
+        "  {:<16} {}",
+        "created",
+        format_datetime_short(manifest.created_at)
+    );
+    println!("  {:<16} csm {}", "created with", manifest.created_with);
+    println!(
+        "  {:<16} {}",
+        "signature",
+        describe_verification(verification)
+    );
+    println!();
+    print_items(&pack.items);
+    if !manifest.redacted.is_empty() {
+        println!();
+        println!("{}", "Stripped secrets:".bold());
+        for field in &manifest.redacted {
+            println!("  {}", field.dimmed());
+        }
+    }
+    Ok(())
+}
+
+/// Import a pack into the local library
+pub fn pack_import(file: &str, force: bool, allow_unsigned: bool, dry_run: bool) -> Result<()> {
+    let pack = Pack::read(Path::new(file))?;
+    let verification = pack.verify(key_from_env().as_deref());
+
+    match verification {
+        Verification::Verified => {}
+        Verification::Invalid => anyhow::bail!(
+            "{} failed signature verification; it was modified or signed with a different key",
+            file
+        ),
+        Verification::Unsigned | Verification::NoKey if !allow_unsigned => anyhow::bail!(
+            "{} is {}. Set ${} to verify it, or pass --allow-unsigned",
+            file,
+            verification_label(verification),
+            PACK_KEY_ENV
+        ),
+        Verification::Unsigned | Verification::NoKey => println!(
+            "{} Importing {} pack",
+            "[!]".yellow(),
+            describe_verification(verification)
+        ),
+    }
+
+    let library = Library::open()?;
+    let (mut added, mut replaced, mut skipped) = (0, 0, 0);
+    for item in &pack.items {
+        let exists = library.contains(item.kind, &item.name);
+        let (marker, action) = match (exists, force) {
+            (true, false) => {
+                skipped += 1;
+                ("[=]".dimmed(), "exists, skipped")
+            }
+            (true, true) => {
+                replaced += 1;
+                ("[~]".yellow(), "replaced")
+            }
+            (false, _) => {
+                added += 1;
+                ("[+]".green(), "added")
+            }
+        };
+        if !dry_run && (!exists || force) {
+            library.save(item)?;
+        }
+        println!(
+            "   {} {} {} ({})",
+            marker,
+            item.kind,
+            item.name.cyan(),
+            action
+        );
+    }
+
+    println!();
+    println!(
+        "{} {}{} added, {} replaced, {} skipped",
+        "[OK]".green().bold(),
+        if dry_run { "Dry run: " } else { "" },
+        added,
+        replaced,
+        skipped
+    );
+    if skipped > 0 {
+        println!("   Use --force to replace existing items");
+    }
+    if !pack.manifest.redacted.is_empty() {
+        println!(
+            "{} Fill in stripped secrets in {}:",
+            "[!]".yellow(),
+            library.root().display()
+        );
+        for field in &pack.manifest.redacted {
+            println!("   {}", field.dimmed());
+        }
+    }
+    Ok(())
+}
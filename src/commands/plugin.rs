@@ -0,0 +1,703 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Plugin management commands
+
+use anyhow::{bail, Context, Result};
+use colored::*;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::plugins::host;
+use crate::plugins::install::{self, PluginSource};
+use crate::plugins::registry::{self, PluginRegistry};
+use crate::plugins::settings::SettingsFile;
+use crate::plugins::{
+    Permission, PluginCategory, PluginInstance, PluginKind, PluginManager, PluginManifest,
+    PluginState,
+};
+use crate::storage::watcher::{PathWatcher, DEFAULT_DEBOUNCE};
+
+/// A runtime and a manager with the installed plugins loaded
+fn open_manager() -> Result<(tokio::runtime::Runtime, PluginManager)> {
+    let manager =
+        PluginManager::new(PluginManager::default_dir()?).with_consent(host::ask_on_terminal());
+    let rt = tokio::runtime::Runtime::new()?;
+    rt.block_on(manager.discover_plugins())?;
+    Ok((rt, manager))
+}
+
+fn find_plugin(
+    rt: &tokio::runtime::Runtime,
+    manager: &PluginManager,
+    id: &str,
+) -> Result<PluginInstance> {
+    rt.block_on(manager.get_plugin(id)).with_context(|| {
+        format!(
+            "No plugin '{}' in {}; see `csm plugin list`",
+            id,
+            manager.dir().display()
+        )
+    })
+}
+
+fn print_permissions(permissions: &[Permission]) {
+    if permissions.is_empty() {
+        println!("    {}", "No permissions".dimmed());
+    }
+    for permission in permissions {
+        let name = format!("{:<16}", permission.name());
+        if permission.needs_confirmation() {
+            println!("    {} {}", name.yellow(), permission.description());
+        } else {
+            println!("    {} {}", name, permission.description());
+        }
+    }
+}
+
+/// Ask before granting permissions that let a plugin run commands or read
+/// secrets, unless `yes` is set
+fn confirm_permissions(
+    id: &str,
+    requested: &[Permission],
+    granted: &[Permission],
+    yes: bool,
+    verb: &str,
+) -> Result<()> {
+    let asking: Vec<&Permission> = requested
+        .iter()
+        .filter(|p| p.needs_confirmation() && !granted.contains(p))
+        .collect();
+    if asking.is_empty() || yes {
+        return Ok(());
+    }
+    let names: Vec<&str> = asking.iter().map(|p| p.name()).collect();
+    if !std::io::stdin().is_terminal() {
+        bail!(
+            "Plugin {} asks for the {} permission; pass --yes to grant it",
+            id,
+            names.join(" and ")
+        );
+    }
+    let can: Vec<&str> = asking.iter().map(|p| p.description()).collect();
+    print!(
+        "{} Let {} {}? [y/N] ",
+        "[!]".yellow(),
+        id.cyan(),
+        can.join(" and ")
+    );
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    if !matches!(input.trim().to_lowercase().as_str(), "y" | "yes") {
+        bail!("Plugin {} was not {}", id, verb);
+    }
+    Ok(())
+}
+
+/// Start the plugin to check it works, then stop it again
+fn check_starts(rt: &tokio::runtime::Runtime, manager: &PluginManager, id: &str) -> Result<()> {
+    rt.block_on(async {
+        manager.activate(id).await?;
+        manager.deactivate(id).await
+    })
+}
+
+/// List installed plugins
+pub fn plugin_list(json: bool) -> Result<()> {
+    let (rt, manager) = open_manager()?;
+    let mut plugins = rt.block_on(manager.list_plugins());
+    plugins.sort_by(|a, b| a.manifest.id.cmp(&b.manifest.id));
+    let settings = SettingsFile::load(manager.dir())?;
+
+    if json {
+        let list: Vec<serde_json::Value> = plugins
+            .iter()
+            .map(|p| {
+                let actions: Vec<&str> = p
+                    .manifest
+                    .capabilities
+                    .iter()
+                    .map(|c| c.action.as_str())
+                    .collect();
+                serde_json::json!({
+                    "id": p.manifest.id,
+                    "name": p.manifest.name,
+                    "version": p.manifest.version,
+                    "kind": p.manifest.kind,
+                    "enabled": p.state != PluginState::Disabled,
+                    "permissions": p.manifest.permissions,
+                    "granted": p.granted,
+                    "denied": settings.get(&p.manifest.id).denied,
+                    "actions": actions,
+                    "provider": p.manifest.provider.as_ref().map(|i| &i.name),
+                    "source": settings.get(&p.manifest.id).source,
+                    "path": p.path,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&list)?);
+        return Ok(());
+    }
+
+    if plugins.is_empty() {
+        println!(
+            "{} No plugins in {}. Install one with {}",
+            "[!]".yellow(),
+            manager.dir().display(),
+            "csm plugin install <dir|url>".cyan()
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{} Plugins in {}:",
+        "[*]".blue().bold(),
+        manager.dir().display()
+    );
+    for plugin in &plugins {
+        let manifest = &plugin.manifest;
+        let state = match plugin.state {
+            PluginState::Disabled => "disabled".dimmed(),
+            _ => "enabled".green(),
+        };
+        let kind = match manifest.kind {
+            PluginKind::Command => "",
+            PluginKind::Process => " (process)",
+        };
+        println!(
+            "  {} {} {}{}",
+            manifest.id.cyan(),
+            manifest.version,
+            state,
+            kind.dimmed()
+        );
+        if let Some(description) = &manifest.description {
+            println!("    {}", description.dimmed());
+        }
+        let granted: Vec<&str> = plugin.granted.iter().map(Permission::name).collect();
+        if !granted.is_empty() {
+            println!("    {} {}", "Permissions:".dimmed(), granted.join(", "));
+        }
+        let denied = &settings.get(&manifest.id).denied;
+        let (refused, missing): (Vec<&Permission>, Vec<&Permission>) = manifest
+            .permissions
+            .iter()
+            .filter(|p| !plugin.granted.contains(p))
+            .partition(|p| denied.contains(p));
+        for (label, permissions) in [("Refused:", refused), ("Not granted:", missing)] {
+            if permissions.is_empty() {
+                continue;
+            }
+            let names: Vec<&str> = permissions.iter().map(|p| p.name()).collect();
+            let when = match manifest.kind {
+                PluginKind::Process if label == "Not granted:" => "asked when first needed; ",
+                _ => "",
+            };
+            println!(
+                "    {} {} ({}grant with {})",
+                label.yellow(),
+                names.join(", "),
+                when,
+                format!("csm plugin enable {}", manifest.id).cyan()
+            );
+        }
+        let actions: Vec<&str> = manifest
+            .capabilities
+            .iter()
+            .map(|c| c.action.as_str())
+            .collect();
+        if !actions.is_empty() {
+            println!("    {} {}", "Actions:".dimmed(), actions.join(", "));
+        }
+        if let Some(provider) = &manifest.provider {
+            println!("    {} {}", "Chat provider:".dimmed(), provider.name);
+        }
+    }
+    Ok(())
+}
+
+/// Install a plugin from a directory, a plugin.json, a URL, or the registry
+pub fn plugin_install(source: &str, yes: bool, force: bool) -> Result<()> {
+    let is_path = crate::config::expand_home(std::path::Path::new(source)).exists();
+    if !source.contains("://") && !is_path && registry::is_plugin_id(source) {
+        return install_from_registry(source, yes, force);
+    }
+    let source = PluginSource::parse(source);
+    // Downloads block, so they happen before the runtime starts
+    let staged = source.fetch()?;
+    let manifest = &staged.manifest;
+    println!(
+        "{} {} {} ({}) from {}",
+        "[*]".blue(),
+        manifest.name,
+        manifest.version,
+        manifest.id.cyan(),
+        source
+    );
+    if let Some(description) = &manifest.description {
+        println!("    {}", description.dimmed());
+    }
+    println!("  Permissions:");
+    print_permissions(&manifest.permissions);
+    confirm_permissions(&manifest.id, &manifest.permissions, &[], yes, "installed")?;
+
+    let (rt, manager) = open_manager()?;
+    let id = rt.block_on(manager.install(
+        &staged,
+        &source.to_string(),
+        manifest.permissions.clone(),
+        force,
+    ))?;
+    println!(
+        "{} Installed {} in {}",
+        "[+]".green(),
+        id.cyan(),
+        manager.dir().join(&id).display()
+    );
+    if let Err(e) = check_starts(&rt, &manager, &id) {
+        println!("{} It failed to start: {}", "[!]".yellow(), e);
+    }
+    Ok(())
+}
+
+/// Show a plugin from the registry and decide its permissions, asking before
+/// granting sensitive ones it was not granted before
+fn grant_registry_plugin(
+    manifest: &PluginManifest,
+    previous: &[Permission],
+    yes: bool,
+    verb: &str,
+) -> Result<Vec<Permission>> {
+    println!(
+        "{} {} {} ({})",
+        "[*]".blue(),
+        manifest.name,
+        manifest.version,
+        manifest.id.cyan()
+    );
+    if let Some(description) = &manifest.description {
+        println!("    {}", description.dimmed());
+    }
+    println!("  Permissions:");
+    print_permissions(&manifest.permissions);
+    confirm_permissions(&manifest.id, &manifest.permissions, previous, yes, verb)?;
+    Ok(manifest.permissions.clone())
+}
+
+/// Install a plugin and the plugins it depends on from the registry
+fn install_from_registry(id: &str, yes: bool, force: bool) -> Result<()> {
+    let registry = PluginRegistry::from_config()?;
+    let (rt, manager) = open_manager()?;
+    if !force && rt.block_on(manager.get_plugin(id)).is_some() {
+        bail!(
+            "Plugin {} is already installed; use `csm plugin update {}` or --force",
+            id,
+            id
+        );
+    }
+    println!(
+        "{} Installing {} from {}",
+        "[*]".blue(),
+        id.cyan(),
+        registry.url()
+    );
+    let installed = rt.block_on(registry.install(id, &manager, |manifest| {
+        grant_registry_plugin(manifest, &[], yes, "installed")
+    }))?;
+    for id in &installed {
+        println!(
+            "{} Installed {} in {}",
+            "[+]".green(),
+            id.cyan(),
+            manager.dir().join(id).display()
+        );
+        if let Err(e) = check_starts(&rt, &manager, id) {
+            println!("{} It failed to start: {}", "[!]".yellow(), e);
+        }
+    }
+    Ok(())
+}
+
+/// Search the plugin registry
+pub fn plugin_search(query: &str, category: Option<&str>, json: bool) -> Result<()> {
+    let category: Option<PluginCategory> = category
+        .map(|c| serde_json::from_value(serde_json::Value::String(c.to_lowercase())))
+        .transpose()
+        .context("Unknown category; use provider, export, analysis, ui, automation, storage, auth, or other")?;
+    let registry = PluginRegistry::from_config()?;
+    let (rt, manager) = open_manager()?;
+    let results = rt.block_on(registry.search(query, category))?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        return Ok(());
+    }
+    if results.is_empty() {
+        println!("{} No plugins in {} match", "[!]".yellow(), registry.url());
+        return Ok(());
+    }
+    let installed = rt.block_on(registry::installed_versions(&manager));
+    println!(
+        "{} {} plugins in {}:",
+        "[*]".blue().bold(),
+        results.len(),
+        registry.url()
+    );
+    for entry in &results {
+        let state = match installed.get(&entry.id) {
+            Some(version) if entry.is_newer_than(version) => {
+                format!(" (installed {}, update available)", version).yellow()
+            }
+            Some(_) => " (installed)".green(),
+            None => "".normal(),
+        };
+        println!("  {} {}{}", entry.id.cyan(), entry.version, state);
+        if !entry.description.is_empty() {
+            println!("    {}", entry.description.dimmed());
+        }
+        let signed = if entry.signature.is_some() {
+            "signed"
+        } else {
+            "unsigned"
+        };
+        println!(
+            "    {} downloads, rated {:.1}, {}{}",
+            entry.downloads,
+            entry.rating,
+            signed,
+            if entry.author.is_empty() {
+                String::new()
+            } else {
+                format!(", by {}", entry.author)
+            }
+        );
+    }
+    Ok(())
+}
+
+/// Update plugins installed from the registry to its latest versions
+pub fn plugin_update(id: Option<&str>, yes: bool) -> Result<()> {
+    let registry = PluginRegistry::from_config()?;
+    let (rt, manager) = open_manager()?;
+    let settings = SettingsFile::load(manager.dir())?;
+    let from_registry = |p: &PluginInstance| {
+        settings
+            .get(&p.manifest.id)
+            .source
+            .is_some_and(|s| registry::is_registry_source(&s))
+    };
+
+    let plugins = match id {
+        Some(id) => {
+            let plugin = find_plugin(&rt, &manager, id)?;
+            if !from_registry(&plugin) {
+                bail!(
+                    "Plugin {} was not installed from the registry; reinstall it with `csm plugin install --force`",
+                    id
+                );
+            }
+            vec![plugin]
+        }
+        None => {
+            let mut plugins: Vec<PluginInstance> = rt
+                .block_on(manager.list_plugins())
+                .into_iter()
+                .filter(|p| from_registry(p))
+                .collect();
+            plugins.sort_by(|a, b| a.manifest.id.cmp(&b.manifest.id));
+            plugins
+        }
+    };
+
+    let mut updated = 0;
+    for plugin in plugins {
+        let id = plugin.manifest.id.as_str();
+        let current = &plugin.manifest.version;
+        let entry = rt.block_on(registry.get_plugin(id))?;
+        if !entry.is_newer_than(current) {
+            println!("{} {} {} is up to date", "[*]".blue(), id.cyan(), current);
+            continue;
+        }
+        println!(
+            "{} {} {} -> {}",
+            "[*]".blue(),
+            id.cyan(),
+            current,
+            entry.version.green()
+        );
+        for release in entry.changes_since(current) {
+            let date = release
+                .date
+                .map(|d| format!(" ({})", d.format("%Y-%m-%d")))
+                .unwrap_or_default();
+            println!("  {}{}", release.version.bold(), date.dimmed());
+            for line in release.notes.lines() {
+                println!("    {}", line);
+            }
+        }
+
+        // Plugins keep what they were granted; only new permissions are asked about
+        let mut granted_before = std::collections::HashMap::new();
+        for plugin in rt.block_on(manager.list_plugins()) {
+            granted_before.insert(plugin.manifest.id, plugin.granted);
+        }
+        let installed = rt.block_on(registry.install(id, &manager, |manifest| {
+            let previous = granted_before
+                .get(&manifest.id)
+                .cloned()
+                .unwrap_or_default();
+            grant_registry_plugin(manifest, &previous, yes, "updated")
+        }))?;
+        if plugin.state == PluginState::Disabled {
+            rt.block_on(manager.disable(id))?;
+        }
+        for other in installed.iter().filter(|i| i.as_str() != id) {
+            println!(
+                "{} Installed {}, which it needs",
+                "[+]".green(),
+                other.cyan()
+            );
+        }
+        println!(
+            "{} Updated {} to {}",
+            "[+]".green(),
+            id.cyan(),
+            entry.version
+        );
+        updated += 1;
+    }
+    if updated == 0 && id.is_none() {
+        println!("{} Nothing to update", "[*]".blue());
+    }
+    Ok(())
+}
+
+/// Enable a plugin, asking before granting sensitive permissions
+pub fn plugin_enable(id: &str, yes: bool) -> Result<()> {
+    let (rt, manager) = open_manager()?;
+    let plugin = find_plugin(&rt, &manager, id)?;
+    let requested = &plugin.manifest.permissions;
+    confirm_permissions(id, requested, &plugin.granted, yes, "enabled")?;
+
+    let result = rt.block_on(async {
+        manager.enable(id, requested.clone()).await?;
+        manager.deactivate(id).await
+    });
+    if let Err(e) = result {
+        bail!("Plugin {} is enabled but failed to start: {}", id, e);
+    }
+    println!("{} Enabled {}", "[+]".green(), id.cyan());
+    print_permissions(requested);
+    Ok(())
+}
+
+/// Disable a plugin without removing it
+pub fn plugin_disable(id: &str) -> Result<()> {
+    let (rt, manager) = open_manager()?;
+    find_plugin(&rt, &manager, id)?;
+    rt.block_on(manager.disable(id))?;
+    println!("{} Disabled {}", "[+]".green(), id.cyan());
+    Ok(())
+}
+
+/// Remove a plugin, its data, and its settings
+pub fn plugin_remove(id: &str) -> Result<()> {
+    let (rt, manager) = open_manager()?;
+    find_plugin(&rt, &manager, id)?;
+    rt.block_on(manager.uninstall(id))?;
+    println!("{} Removed {}", "[+]".green(), id.cyan());
+    Ok(())
+}
+
+/// Show a plugin's configuration, or change settings in it
+pub fn plugin_config(id: &str, set: &[String], unset: &[String]) -> Result<()> {
+    let (rt, manager) = open_manager()?;
+    let plugin = find_plugin(&rt, &manager, id)?;
+    let mut config = match plugin.config {
+        serde_json::Value::Object(config) => config,
+        _ => serde_json::Map::new(),
+    };
+
+    if !set.is_empty() || !unset.is_empty() {
+        for pair in set {
+            let (key, value) = pair
+                .split_once('=')
+                .with_context(|| format!("Expected KEY=VALUE, got '{}'", pair))?;
+            let value = serde_json::from_str(value)
+                .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+            config.insert(key.trim().to_string(), value);
+        }
+        for key in unset {
+            config.remove(key);
+        }
+        rt.block_on(manager.set_config(id, serde_json::Value::Object(config.clone())))?;
+        println!("{} Saved configuration of {}", "[+]".green(), id.cyan());
+    }
+    println!("{}", serde_json::to_string_pretty(&config)?);
+    Ok(())
+}
+
+/// Whether a change to `path` reloads the plugin in `dir`: changes to its
+/// data, hidden files such as `.git`, and editor backups do not
+fn is_plugin_source(dir: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(dir) else {
+        return false;
+    };
+    let name = relative.to_string_lossy();
+    !relative.starts_with("data")
+        && !relative
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+        && !name.ends_with('~')
+        && !name.ends_with(".swp")
+}
+
+/// Run a plugin from its source directory: reload it whenever a file in it
+/// changes, print what it writes to stderr, and invoke the actions typed as
+/// `ACTION [JSON]`
+pub fn plugin_dev(path: &str, yes: bool) -> Result<()> {
+    let dir = crate::config::expand_home(Path::new(path));
+    let dir = dir
+        .canonicalize()
+        .with_context(|| format!("No plugin directory at {}", dir.display()))?;
+    let manifest = install::read_manifest(&dir)?;
+    confirm_permissions(&manifest.id, &manifest.permissions, &[], yes, "started")?;
+
+    // Grants made for the session are kept apart from the installed plugins
+    let sandbox = std::env::temp_dir().join(format!("csm-plugin-dev-{}", uuid::Uuid::new_v4()));
+    let result = develop(&dir, &sandbox, manifest, yes);
+    let _ = std::fs::remove_dir_all(&sandbox);
+    result
+}
+
+/// Serve `csm plugin dev`, keeping the session's plugin settings in `sandbox`
+fn develop(dir: &Path, sandbox: &Path, manifest: PluginManifest, yes: bool) -> Result<()> {
+    let mut allowed = manifest.permissions.clone();
+    // An installed plugin's configuration is used
+    let installed = SettingsFile::load(&PluginManager::default_dir()?).unwrap_or_default();
+    let rt = tokio::runtime::Runtime::new()?;
+    let manager = PluginManager::new(sandbox.to_path_buf()).with_log(Arc::new(
+        |plugin_id: &str, line: &str| eprintln!("{} {}", format!("[{}]", plugin_id).dimmed(), line),
+    ));
+
+    let mut id = manifest.id.clone();
+    let mut reload = |id: &mut String| -> Result<()> {
+        let manifest = install::read_manifest(dir)?;
+        let asking: Vec<&str> = manifest
+            .permissions
+            .iter()
+            .filter(|p| p.needs_confirmation() && !allowed.contains(p))
+            .map(Permission::name)
+            .collect();
+        if !asking.is_empty() && !yes {
+            bail!(
+                "The plugin now asks for the {} permission; restart `csm plugin dev` to grant it",
+                asking.join(" and ")
+            );
+        }
+        allowed = manifest.permissions.clone();
+        SettingsFile::update(sandbox, &manifest.id, |settings| {
+            settings.granted = Some(manifest.permissions.clone());
+            settings.config = installed.get(&manifest.id).config;
+        })?;
+        *id = rt.block_on(manager.reload(id, dir))?;
+
+        println!(
+            "{} Loaded {} {}",
+            "[+]".green(),
+            id.cyan(),
+            manifest.version
+        );
+        if !manifest.hooks.is_empty() {
+            println!("    {} {}", "Hooks:".dimmed(), manifest.hooks.join(", "));
+        }
+        let actions: Vec<&str> = manifest
+            .capabilities
+            .iter()
+            .map(|c| c.action.as_str())
+            .collect();
+        if !actions.is_empty() {
+            println!("    {} {}", "Actions:".dimmed(), actions.join(", "));
+        }
+        Ok(())
+    };
+    if let Err(e) = reload(&mut id) {
+        println!("{} {:#}", "[x]".red(), e);
+    }
+
+    let root = dir.to_path_buf();
+    let watcher = PathWatcher::watch(
+        &[(root.clone(), true)],
+        move |path| is_plugin_source(&root, path),
+        DEFAULT_DEBOUNCE,
+    )?;
+    let (lines, input) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines().map_while(|l| l.ok()) {
+            if lines.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    let stop = Arc::new(AtomicBool::new(false));
+    {
+        let stop = stop.clone();
+        rt.spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                stop.store(true, Ordering::SeqCst);
+            }
+        });
+    }
+    println!(
+        "{} Watching {}. Type an action and its JSON arguments to run it; Ctrl-C stops",
+        "[*]".blue(),
+        dir.display()
+    );
+
+    while !stop.load(Ordering::SeqCst) {
+        if let Some(event) = watcher.recv_timeout(Duration::from_millis(100)) {
+            let mut changed = vec![event.path];
+            // The rest of a save that touched several files
+            while let Some(event) = watcher.recv_timeout(Duration::ZERO) {
+                changed.push(event.path);
+            }
+            let names: Vec<String> = changed
+                .iter()
+                .map(|p| p.strip_prefix(dir).unwrap_or(p).display().to_string())
+                .collect();
+            println!("{} Changed: {}", "[*]".blue(), names.join(", "));
+            if let Err(e) = reload(&mut id) {
+                println!("{} {:#}", "[x]".red(), e);
+            }
+        }
+        let Ok(line) = input.try_recv() else {
+            continue;
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (action, arguments) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let arguments = match arguments.trim() {
+            "" => serde_json::json!({}),
+            text => match serde_json::from_str(text) {
+                Ok(arguments) => arguments,
+                Err(e) => {
+                    println!("{} Arguments must be JSON: {}", "[x]".red(), e);
+                    continue;
+                }
+            },
+        };
+        match rt.block_on(manager.invoke(&id, action, arguments)) {
+            Ok(result) => println!("{}", serde_json::to_string_pretty(&result)?),
+            Err(e) => println!("{} {:#}", "[x]".red(), e),
+        }
+    }
+
+    rt.block_on(manager.deactivate(&id)).ok();
+    println!("\n{} Stopped", "[*]".blue());
+    Ok(())
+}
@@ -0,0 +1,343 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Project commands
+
+use anyhow::Result;
+use colored::*;
+use tabled::{settings::Style as TableStyle, Table, Tabled};
+
+use super::workspace_cmds::format_file_size;
+use crate::datetime::format_datetime_short;
+use crate::models::Workspace;
+use crate::projects::{git_remote, resolve_workspace, suggest_groupings, Project, ProjectStore};
+use crate::text::truncate_start;
+use crate::workspace::{discover_workspaces, get_chat_sessions_from_workspace};
+
+fn short_hash(hash: &str) -> &str {
+    &hash[..16.min(hash.len())]
+}
+
+/// Create a project, optionally adding workspaces to it
+pub fn project_create(name: &str, description: Option<&str>, workspaces: &[String]) -> Result<()> {
+    let store = ProjectStore::open()?;
+    let project = store.create(name, description)?;
+    println!(
+        "{} Created project {}",
+        "[OK]".green().bold(),
+        project.name.cyan()
+    );
+    if !workspaces.is_empty() {
+        add_workspaces(&store, &project.name, workspaces)?;
+    }
+    Ok(())
+}
+
+/// Add workspaces to a project by hash, hash prefix, or project folder
+pub fn project_add_workspace(project: &str, workspaces: &[String]) -> Result<()> {
+    let store = ProjectStore::open()?;
+    let project = store.require(project)?;
+    add_workspaces(&store, &project.name, workspaces)
+}
+
+fn add_workspaces(store: &ProjectStore, project: &str, queries: &[String]) -> Result<()> {
+    let discovered = discover_workspaces()?;
+    for query in queries {
+        let ws = resolve_workspace(query, &discovered)?;
+        let added = store.add_workspace(project, &ws.hash, ws.project_path.as_deref())?;
+        println!(
+            "   {} {}... {}{}",
+            if added { "[+]".green() } else { "[=]".dimmed() },
+            short_hash(&ws.hash),
+            ws.project_path.as_deref().unwrap_or("(unknown)"),
+            if added { "" } else { " (already in project)" }
+        );
+    }
+    Ok(())
+}
+
+/// Remove a workspace from a project
+pub fn project_remove_workspace(project: &str, workspace: &str) -> Result<()> {
+    let store = ProjectStore::open()?;
+    let project = store.require(project)?;
+
+    // Accept hashes of workspaces that no longer exist on disk
+    let hash = match project
+        .workspaces
+        .iter()
+        .find(|w| w.hash.starts_with(workspace))
+    {
+        Some(w) => w.hash.clone(),
+        None => resolve_workspace(workspace, &discover_workspaces()?)?
+            .hash
+            .clone(),
+    };
+
+    if store.remove_workspace(&project.name, &hash)? {
+        println!(
+            "{} Removed {}... from {}",
+            "[OK]".green().bold(),
+            short_hash(&hash),
+            project.name.cyan()
+        );
+    } else {
+        println!(
+            "{} {}... is not part of {}",
+            "[!]".yellow(),
+            short_hash(&hash),
+            project.name.cyan()
+        );
+    }
+    Ok(())
+}
+
+/// Delete a project (its workspaces and sessions are kept)
+pub fn project_delete(name: &str) -> Result<()> {
+    let store = ProjectStore::open()?;
+    let project = store.require(name)?;
+    store.delete(&project.name)?;
+    println!(
+        "{} Deleted project {}",
+        "[OK]".green().bold(),
+        project.name.cyan()
+    );
+    Ok(())
+}
+
+#[derive(Tabled)]
+struct ProjectRow {
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Workspaces")]
+    workspaces: usize,
+    #[tabled(rename = "Created")]
+    created: String,
+    #[tabled(rename = "Description")]
+    description: String,
+}
+
+/// List projects
+pub fn project_list(json: bool) -> Result<()> {
+    let store = ProjectStore::open()?;
+    let projects = store.list()?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&projects)?);
+        return Ok(());
+    }
+
+    if projects.is_empty() {
+        println!("No projects yet.");
+        println!(
+            "   Create one with {} or see {}",
+            "csm project create <name>".cyan(),
+            "csm project suggest".cyan()
+        );
+        return Ok(());
+    }
+
+    let rows: Vec<ProjectRow> = projects
+        .iter()
+        .map(|p| ProjectRow {
+            name: p.name.clone(),
+            workspaces: p.workspaces.len(),
+            created: format_datetime_short(p.created_at),
+            description: p.description.clone().unwrap_or_default(),
+        })
+        .collect();
+    println!("{}", Table::new(&rows).with(TableStyle::ascii_rounded()));
+    println!("\nTotal projects: {}", projects.len());
+    Ok(())
+}
+
+#[derive(Tabled)]
+struct WorkspaceStatsRow {
+    #[tabled(rename = "Workspace")]
+    hash: String,
+    #[tabled(rename = "Folder")]
+    folder: String,
+    #[tabled(rename = "Sessions")]
+    sessions: usize,
+    #[tabled(rename = "Messages")]
+    messages: usize,
+    #[tabled(rename = "Size")]
+    size: String,
+    #[tabled(rename = "Last Active")]
+    last_active: String,
+}
+
+fn workspace_stats(project: &Project, discovered: &[Workspace]) -> Vec<WorkspaceStatsRow> {
+    project
+        .workspaces
+        .iter()
+        .map(|member| {
+            let ws = discovered.iter().find(|w| w.hash == member.hash);
+            let sessions = ws
+                .and_then(|ws| get_chat_sessions_from_workspace(&ws.workspace_path).ok())
+                .unwrap_or_default();
+            let size: u64 = sessions
+                .iter()
+                .filter_map(|s| s.path.metadata().ok())
+                .map(|m| m.len())
+                .sum();
+            WorkspaceStatsRow {
+                hash: short_hash(&member.hash).to_string(),
+                folder: truncate_start(
+                    ws.and_then(|w| w.project_path.as_deref())
+                        .or(member.folder.as_deref())
+                        .unwrap_or("(unknown)"),
+                    50,
+                ),
+                sessions: sessions.len(),
+                messages: sessions.iter().map(|s| s.session.request_count()).sum(),
+                size: format_file_size(size),
+                last_active: match ws {
+                    Some(ws) => ws
+                        .last_modified
+                        .map(format_datetime_short)
+                        .unwrap_or_else(|| "-".to_string()),
+                    None => "missing".to_string(),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Show a project's workspaces with session counts
+pub fn project_show(name: &str) -> Result<()> {
+    let store = ProjectStore::open()?;
+    let project = store.require(name)?;
+
+    println!("{} {}", "Project:".bold(), project.name.cyan().bold());
+    if let Some(description) = &project.description {
+        println!("  {}", description);
+    }
+    println!("  Created {}", format_datetime_short(project.created_at));
+    println!();
+
+    if project.workspaces.is_empty() {
+        println!(
+            "No workspaces. Add one with {}",
+            format!("csm project add-workspace {} <hash|path>", project.name).cyan()
+        );
+        return Ok(());
+    }
+
+    let rows = workspace_stats(&project, &discover_workspaces()?);
+    println!("{}", Table::new(&rows).with(TableStyle::ascii_rounded()));
+    Ok(())
+}
+
+/// Show session and message totals for a project
+pub fn project_stats(name: &str) -> Result<()> {
+    let store = ProjectStore::open()?;
+    let project = store.require(name)?;
+    let discovered = discover_workspaces()?;
+    let rows = workspace_stats(&project, &discovered);
+
+    let sessions: usize = rows.iter().map(|r| r.sessions).sum();
+    let messages: usize = rows.iter().map(|r| r.messages).sum();
+    let members: Vec<&Workspace> = discovered
+        .iter()
+        .filter(|w| project.contains(&w.hash))
+        .collect();
+    let missing = project.workspaces.len() - members.len();
+
+    println!(
+        "\n{} Project Statistics: {}",
+        "[*]".blue().bold(),
+        project.name.cyan()
+    );
+    println!("{}", "=".repeat(60));
+    println!("  {:<16} {}", "Workspaces:", rows.len());
+    println!("  {:<16} {}", "Sessions:", sessions);
+    println!("  {:<16} {}", "Messages:", messages);
+    if let Some(last) = members.iter().filter_map(|w| w.last_modified).max() {
+        println!("  {:<16} {}", "Last active:", format_datetime_short(last));
+    }
+    if missing > 0 {
+        println!(
+            "\n{} {} workspace(s) no longer exist in workspace storage",
+            "[!]".yellow(),
+            missing
+        );
+    }
+    Ok(())
+}
+
+/// Suggest projects from workspaces that share a git remote
+pub fn project_suggest(create: bool) -> Result<()> {
+    let store = ProjectStore::open()?;
+    let discovered = discover_workspaces()?;
+    let suggestions = suggest_groupings(&discovered, git_remote);
+
+    let projects = store.list()?;
+    let mut shown = 0;
+    for suggestion in suggestions {
+        // Skip groups already fully covered by one project
+        if projects
+            .iter()
+            .any(|p| suggestion.workspaces.iter().all(|ws| p.contains(&ws.hash)))
+        {
+            continue;
+        }
+        shown += 1;
+
+        let existing = projects
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(&suggestion.name));
+        println!(
+            "\n{} {} ({})",
+            "[*]".blue(),
+            suggestion.name.cyan().bold(),
+            suggestion.remote.dimmed()
+        );
+        for ws in &suggestion.workspaces {
+            println!(
+                "   {}... {} ({} sessions)",
+                short_hash(&ws.hash),
+                ws.project_path.as_deref().unwrap_or("(unknown)"),
+                ws.chat_session_count
+            );
+        }
+
+        if create {
+            if existing.is_none() {
+                store.create(&suggestion.name, Some(&suggestion.remote))?;
+            }
+            let added = suggestion
+                .workspaces
+                .iter()
+                .map(|ws| {
+                    store.add_workspace(&suggestion.name, &ws.hash, ws.project_path.as_deref())
+                })
+                .collect::<crate::error::Result<Vec<bool>>>()?
+                .into_iter()
+                .filter(|added| *added)
+                .count();
+            println!(
+                "   {} {} project {}, added {} workspace(s)",
+                "[+]".green(),
+                if existing.is_some() {
+                    "Updated"
+                } else {
+                    "Created"
+                },
+                suggestion.name.cyan(),
+                added
+            );
+        }
+    }
+
+    if shown == 0 {
+        println!(
+            "{} No new groupings found: no ungrouped workspaces share a git remote",
+            "[OK]".green().bold()
+        );
+    } else if !create {
+        println!(
+            "\nRun {} to create these projects",
+            "csm project suggest --create".cyan()
+        );
+    }
+    Ok(())
+}
@@ -5,29 +5,43 @@
 use anyhow::Result;
 use colored::*;
 
+use super::harvest::get_db_path;
 use crate::providers::{
     config::{CsmConfig, ProviderConfig},
     discovery::print_provider_summary,
-    ProviderRegistry, ProviderType,
+    ChatProvider, ProviderRegistry, ProviderType,
 };
+use crate::storage::write_session_file;
+
+/// Provider registry that reuses the discovery result cached in the harvest
+/// database, when there is one
+pub(crate) fn provider_registry() -> ProviderRegistry {
+    match get_db_path(None) {
+        Ok(db_path) if db_path.exists() => ProviderRegistry::cached(db_path),
+        _ => ProviderRegistry::new(),
+    }
+}
 
 /// List all discovered providers
 pub fn list_providers() -> Result<()> {
-    let registry = ProviderRegistry::new();
+    let registry = provider_registry();
     print_provider_summary(&registry);
     Ok(())
 }
 
 /// Show detailed info about a specific provider
 pub fn provider_info(provider_name: &str) -> Result<()> {
-    let provider_type = parse_provider_name(provider_name)?;
-    let registry = ProviderRegistry::new();
+    let registry = provider_registry();
+    let (provider_type, provider) = find_provider(&registry, provider_name)?;
 
-    if let Some(provider) = registry.get_provider(provider_type) {
+    if let Some(provider) = provider {
         println!("{}", format!("Provider: {}", provider.name()).bold());
         println!();
 
         println!("  Type:      {}", provider_type.display_name());
+        if let Some(plugin_id) = provider.plugin_id() {
+            println!("  Plugin:    {}", plugin_id);
+        }
         println!(
             "  Available: {}",
             if provider.is_available() {
@@ -41,7 +55,7 @@ pub fn provider_info(provider_name: &str) -> Result<()> {
             println!("  Data Path: {}", path.display());
         }
 
-        if let Some(endpoint) = provider_type.default_endpoint() {
+        if let Some(endpoint) = provider.endpoint().or(provider_type.default_endpoint()) {
             println!("  Endpoint:  {}", endpoint);
         }
 
@@ -168,11 +182,9 @@ pub fn import_from_provider(
     target_path: Option<&str>,
     session_id: Option<&str>,
 ) -> Result<()> {
-    let provider_type = parse_provider_name(from_provider)?;
-    let registry = ProviderRegistry::new();
-
-    let provider = registry
-        .get_provider(provider_type)
+    let registry = provider_registry();
+    let provider = find_provider(&registry, from_provider)?
+        .1
         .ok_or_else(|| anyhow::anyhow!("Provider not found: {}", from_provider))?;
 
     if !provider.is_available() {
@@ -208,8 +220,7 @@ pub fn import_from_provider(
         std::fs::create_dir_all(&sessions_dir)?;
 
         let session_file = sessions_dir.join(format!("{}.json", session_id));
-        let content = serde_json::to_string_pretty(&session)?;
-        std::fs::write(&session_file, content)?;
+        write_session_file(&session_file, &session)?;
 
         println!("{} Imported session: {}", "+".green(), session.title());
     } else {
@@ -237,8 +248,7 @@ pub fn import_from_provider(
             let session_file = sessions_dir.join(format!("{}.json", id));
 
             if !session_file.exists() {
-                let content = serde_json::to_string_pretty(&session)?;
-                std::fs::write(&session_file, content)?;
+                write_session_file(&session_file, session)?;
                 imported += 1;
                 println!("  {} {}", "+".green(), session.title());
             }
@@ -257,12 +267,15 @@ pub fn import_from_provider(
 
 /// Test connection to a provider
 pub fn test_provider(provider_name: &str) -> Result<()> {
-    let provider_type = parse_provider_name(provider_name)?;
-    let registry = ProviderRegistry::new();
+    let registry = provider_registry();
+    let (provider_type, provider) = find_provider(&registry, provider_name)?;
 
-    print!("Testing {} connection... ", provider_type.display_name());
+    print!(
+        "Testing {} connection... ",
+        provider.map_or(provider_type.display_name(), |p| p.name())
+    );
 
-    if let Some(provider) = registry.get_provider(provider_type) {
+    if let Some(provider) = provider {
         if provider.is_available() {
             println!("{}", "OK".green());
 
@@ -294,28 +307,48 @@ pub fn test_provider(provider_name: &str) -> Result<()> {
     }
 }
 
+/// Find a provider by name: a built-in provider, or the ID of a plugin that
+/// adds one
+fn find_provider<'a>(
+    registry: &'a ProviderRegistry,
+    name: &str,
+) -> Result<(ProviderType, Option<&'a dyn ChatProvider>)> {
+    if builtin_provider_type(name).is_none() {
+        if let Some(provider) = registry.plugin_provider(name) {
+            return Ok((ProviderType::Plugin, Some(provider)));
+        }
+    }
+    let provider_type = parse_provider_name(name)?;
+    Ok((provider_type, registry.get_provider(provider_type)))
+}
+
 /// Parse a provider name string into ProviderType
 fn parse_provider_name(name: &str) -> Result<ProviderType> {
+    builtin_provider_type(name).ok_or_else(|| {
+        eprintln!("{} Unknown provider: {}", "Error:".red(), name);
+        eprintln!();
+        list_provider_types();
+        anyhow::anyhow!("Unknown provider")
+    })
+}
+
+/// The built-in provider called `name`
+fn builtin_provider_type(name: &str) -> Option<ProviderType> {
     match name.to_lowercase().as_str() {
-        "copilot" | "github-copilot" | "vscode" => Ok(ProviderType::Copilot),
-        "cursor" => Ok(ProviderType::Cursor),
-        "ollama" => Ok(ProviderType::Ollama),
-        "vllm" => Ok(ProviderType::Vllm),
-        "foundry" | "azure-foundry" | "foundry-local" | "ai-foundry" => Ok(ProviderType::Foundry),
-        "openai" => Ok(ProviderType::OpenAI),
-        "lm-studio" | "lmstudio" => Ok(ProviderType::LmStudio),
-        "localai" | "local-ai" => Ok(ProviderType::LocalAI),
-        "text-gen-webui" | "textgenwebui" | "oobabooga" => Ok(ProviderType::TextGenWebUI),
-        "jan" | "jan-ai" | "janai" => Ok(ProviderType::Jan),
-        "gpt4all" => Ok(ProviderType::Gpt4All),
-        "llamafile" => Ok(ProviderType::Llamafile),
-        "custom" => Ok(ProviderType::Custom),
-        _ => {
-            eprintln!("{} Unknown provider: {}", "Error:".red(), name);
-            eprintln!();
-            list_provider_types();
-            Err(anyhow::anyhow!("Unknown provider"))
-        }
+        "copilot" | "github-copilot" | "vscode" => Some(ProviderType::Copilot),
+        "cursor" => Some(ProviderType::Cursor),
+        "ollama" => Some(ProviderType::Ollama),
+        "vllm" => Some(ProviderType::Vllm),
+        "foundry" | "azure-foundry" | "foundry-local" | "ai-foundry" => Some(ProviderType::Foundry),
+        "openai" => Some(ProviderType::OpenAI),
+        "lm-studio" | "lmstudio" => Some(ProviderType::LmStudio),
+        "localai" | "local-ai" => Some(ProviderType::LocalAI),
+        "text-gen-webui" | "textgenwebui" | "oobabooga" => Some(ProviderType::TextGenWebUI),
+        "jan" | "jan-ai" | "janai" => Some(ProviderType::Jan),
+        "gpt4all" => Some(ProviderType::Gpt4All),
+        "llamafile" => Some(ProviderType::Llamafile),
+        "custom" => Some(ProviderType::Custom),
+        _ => None,
     }
 }
 
@@ -335,4 +368,5 @@ fn list_provider_types() {
     eprintln!("  gpt4all      - GPT4All");
     eprintln!("  llamafile    - Llamafile");
     eprintln!("  custom       - Custom OpenAI-compatible endpoint");
+    eprintln!("  <plugin-id>  - A provider added by a plugin");
 }
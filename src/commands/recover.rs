@@ -13,6 +13,11 @@ use anyhow::{Context, Result};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::backup::{backup_file, BackupKind};
+use crate::datetime::{format_datetime, from_epoch};
+use crate::storage::write_file_atomic;
+use crate::text::{pad_right, prefix_by_width, prefix_chars};
+
 /// Get workspace storage path for a provider
 fn get_provider_storage_path(provider: &str) -> Option<PathBuf> {
     let base = match std::env::consts::OS {
@@ -185,7 +190,12 @@ pub fn recover_from_recording(server: &str, session_id: Option<&str>, output: Op
         .timeout(std::time::Duration::from_secs(10))
         .build()?;
     
-    let response = client.get(&url)
+    let mut request = client.get(&url);
+    // Servers that deny anonymous access need an API key
+    if let Ok(key) = std::env::var("CSM_API_KEY") {
+        request = request.header("X-API-Key", key);
+    }
+    let response = request
         .send()
         .context("Failed to connect to recording server")?;
 
@@ -220,14 +230,13 @@ pub fn recover_from_recording(server: &str, session_id: Option<&str>, output: Op
                 let title = session.get("title").and_then(|v| v.as_str()).unwrap_or("Untitled");
                 
                 println!("║ {:36} {:10} {:>4} msgs  ║", 
-                    &id[..id.len().min(36)],
+                    prefix_chars(id, 36),
                     provider,
                     msgs
                 );
                 if title != "Untitled" {
-                    println!("║   └─ {}{}║", 
-                        &title[..title.len().min(55)],
-                        " ".repeat(55 - title.len().min(55))
+                    println!("║   └─ {}║", 
+                        pad_right(prefix_by_width(title, 55), 55)
                     );
                 }
             }
@@ -321,14 +330,13 @@ pub fn recover_from_database(backup_path: &str, session_id: Option<&str>, output
         for (id, title, provider, created, _) in &sessions {
             let title_display = if title.is_empty() { "Untitled" } else { title };
             println!("║ {:36} {:10} {:16}  ║",
-                &id[..id.len().min(36)],
-                &provider[..provider.len().min(10)],
-                &created[..created.len().min(16)]
+                prefix_chars(id, 36),
+                prefix_chars(provider, 10),
+                prefix_chars(created, 16)
             );
             if !title.is_empty() {
-                println!("║   └─ {}{}║",
-                    &title_display[..title_display.len().min(55)],
-                    " ".repeat(55 - title_display.len().min(55))
+                println!("║   └─ {}║",
+                    pad_right(prefix_by_width(title_display, 55), 55)
                 );
             }
         }
@@ -605,8 +613,7 @@ pub fn recover_repair(path: &str, create_backup: bool, dry_run: bool) -> Result<
 
 fn repair_file(path: &Path, create_backup: bool) -> Result<()> {
     if create_backup {
-        let backup_path = path.with_extension("backup");
-        fs::copy(path, &backup_path)?;
+        backup_file(BackupKind::SessionFile, path)?;
     }
 
     let content = fs::read_to_string(path)?;
@@ -634,7 +641,7 @@ fn repair_file(path: &Path, create_backup: bool) -> Result<()> {
         }
     }
 
-    fs::write(path, output)?;
+    write_file_atomic(path, output.as_bytes())?;
     Ok(())
 }
 
@@ -1148,22 +1155,12 @@ fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
 
 /// Format a Unix timestamp for display
 fn format_timestamp(ts: i64) -> String {
-    use std::time::{Duration, UNIX_EPOCH};
-    
     if ts <= 0 {
         return "Unknown".to_string();
     }
-    
-    // Handle both seconds and milliseconds
-    let ts_secs = if ts > 10_000_000_000 { ts / 1000 } else { ts };
-    
-    match UNIX_EPOCH.checked_add(Duration::from_secs(ts_secs as u64)) {
-        Some(time) => {
-            let datetime: chrono::DateTime<chrono::Utc> = time.into();
-            datetime.format("%Y-%m-%d %H:%M:%S UTC").to_string()
-        }
-        None => format!("{}", ts),
-    }
+    from_epoch(ts)
+        .map(format_datetime)
+        .unwrap_or_else(|| format!("{}", ts))
 }
 
 // ============================================================================
@@ -1274,7 +1271,7 @@ pub fn recover_detect(file: &str, verbose: bool, output_json: bool) -> Result<()
                             .and_then(|m| m.text.as_ref())
                             .map(|t| {
                                 let preview: String = t.chars().take(50).collect();
-                                if t.len() > 50 { format!("{}...", preview) } else { preview }
+                                if t.chars().count() > 50 { format!("{}...", preview) } else { preview }
                             })
                             .unwrap_or_else(|| "[no message]".to_string());
                         println!("    {}. {}", i + 1, msg_preview);
@@ -1293,7 +1290,7 @@ pub fn recover_detect(file: &str, verbose: bool, output_json: bool) -> Result<()
                     println!("[*] File Preview:");
                     for (i, line) in content.lines().take(5).enumerate() {
                         let preview: String = line.chars().take(100).collect();
-                        println!("    {}: {}{}", i + 1, preview, if line.len() > 100 { "..." } else { "" });
+                        println!("    {}: {}{}", i + 1, preview, if line.chars().count() > 100 { "..." } else { "" });
                     }
                 }
             }
@@ -0,0 +1,665 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Guided recovery of orphaned and deleted-workspace sessions
+//!
+//! Sessions go missing from VS Code in three ways:
+//!
+//! - the file is on disk but was never added to the workspace index
+//! - VS Code created a new workspace hash for the same folder and left the old one behind
+//! - the project folder was moved or deleted, so its workspace is never opened again
+//!
+//! The wizard finds all three, previews each session, suggests a live workspace
+//! to register it in (by folder path similarity and file references in the
+//! conversation), and registers or exports them in bulk.
+
+use anyhow::Result;
+use colored::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::config;
+use crate::datetime::{format_date, from_epoch};
+use crate::error::CsmError;
+use crate::models::{ChatSession, WorkspaceJson};
+use crate::storage::{
+    add_session_to_index, backup_workspace_sessions, check_session_file_size, copy_file_atomic,
+    is_session_file_extension, is_vscode_running, parse_session_auto, read_chat_session_index,
+};
+use crate::text::{prefix_chars, truncate};
+use crate::workspace::{decode_workspace_folder, get_workspace_storage_path, normalize_path};
+
+/// Why a session needs recovery
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrphanReason {
+    /// On disk in a live workspace but missing from its index
+    Unindexed,
+    /// In an older workspace for a folder that has a newer one
+    Superseded,
+    /// In a workspace whose project folder no longer exists
+    FolderDeleted,
+}
+
+impl OrphanReason {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Unindexed => "not indexed",
+            Self::Superseded => "old workspace",
+            Self::FolderDeleted => "folder deleted",
+        }
+    }
+}
+
+/// A workspace storage directory and the folder it belongs to
+#[derive(Debug, Clone)]
+pub struct StoredWorkspace {
+    pub hash: String,
+    pub dir: PathBuf,
+    pub project_path: Option<PathBuf>,
+    pub last_modified: SystemTime,
+}
+
+impl StoredWorkspace {
+    fn is_live(&self) -> bool {
+        self.project_path.as_ref().is_some_and(|p| p.exists())
+    }
+}
+
+/// Workspace the wizard proposes to register a session in
+#[derive(Debug, Clone)]
+pub struct WorkspaceSuggestion {
+    pub hash: String,
+    pub dir: PathBuf,
+    pub project_path: PathBuf,
+    pub score: u32,
+    pub reason: String,
+}
+
+/// A session that is not visible in VS Code
+#[derive(Debug, Clone)]
+pub struct OrphanCandidate {
+    pub path: PathBuf,
+    pub session_id: String,
+    pub title: String,
+    pub message_count: usize,
+    pub last_message_date: i64,
+    /// First user message, shortened for display
+    pub preview: String,
+    pub source_hash: String,
+    pub source_project: Option<PathBuf>,
+    pub reason: OrphanReason,
+    pub suggestion: Option<WorkspaceSuggestion>,
+    is_imported: bool,
+    initial_location: String,
+    is_empty: bool,
+}
+
+/// Similarity of two project paths, from 0 (unrelated) upward.
+///
+/// Matching trailing components (the folder name, then its parents) count the
+/// most, since moved projects usually keep their name.
+pub fn path_similarity(a: &Path, b: &Path) -> u32 {
+    let names = |p: &Path| -> Vec<String> {
+        p.components()
+            .filter_map(|c| match c {
+                Component::Normal(s) => Some(s.to_string_lossy().to_lowercase()),
+                _ => None,
+            })
+            .collect()
+    };
+    let (a, b) = (names(a), names(b));
+
+    let shared_tail = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count() as u32;
+    if shared_tail > 0 {
+        return 40 + 10 * (shared_tail - 1);
+    }
+
+    // Renamed with a suffix or prefix, e.g. `chasm` -> `chasm-cli`
+    match (a.last(), b.last()) {
+        (Some(x), Some(y)) if x.len().min(y.len()) >= 3 && (x.contains(y) || y.contains(x)) => 15,
+        _ => 0,
+    }
+}
+
+/// Number of times a session's content mentions files under `project`
+pub fn count_path_references(content: &str, project: &Path) -> usize {
+    let needle = project
+        .to_string_lossy()
+        .replace('\\', "/")
+        .trim_end_matches('/')
+        .to_lowercase();
+    if needle.len() < 4 {
+        return 0;
+    }
+    // JSON-escaped Windows paths and URI-encoded drive letters
+    let haystack = content
+        .to_lowercase()
+        .replace("\\\\", "/")
+        .replace('\\', "/")
+        .replace("%3a", ":");
+    haystack.matches(&needle).count()
+}
+
+/// Pick the live workspace a session most likely belongs to
+fn suggest_workspace(
+    source_project: Option<&Path>,
+    content: &str,
+    live: &[&StoredWorkspace],
+) -> Option<WorkspaceSuggestion> {
+    live.iter()
+        .filter_map(|ws| {
+            let project = ws.project_path.as_ref()?;
+            let path_score = source_project
+                .map(|src| path_similarity(src, project))
+                .unwrap_or(0);
+            let refs = count_path_references(content, project);
+            let score = path_score + refs.min(10) as u32 * 5;
+            if score == 0 {
+                return None;
+            }
+
+            let mut reasons = Vec::new();
+            if path_score >= 40 {
+                reasons.push("same folder name".to_string());
+            } else if path_score > 0 {
+                reasons.push("similar folder name".to_string());
+            }
+            if refs > 0 {
+                reasons.push(format!("{} file reference(s)", refs));
+            }
+            Some(WorkspaceSuggestion {
+                hash: ws.hash.clone(),
+                dir: ws.dir.clone(),
+                project_path: project.clone(),
+                score,
+                reason: reasons.join(", "),
+            })
+        })
+        .max_by_key(|s| s.score)
+}
+
+/// Decoded workspace folder as a local path, or `None` for remote folders
+fn local_folder_path(folder_uri: &str) -> Option<PathBuf> {
+    if folder_uri.contains("://") && !folder_uri.starts_with("file://") {
+        return None;
+    }
+    let decoded = decode_workspace_folder(folder_uri);
+    // `decode_workspace_folder` drops the leading `/` of Unix `file:///` URIs
+    if cfg!(not(windows)) && !decoded.starts_with('/') {
+        return Some(PathBuf::from(format!("/{}", decoded)));
+    }
+    Some(PathBuf::from(decoded))
+}
+
+/// All workspace directories under a workspaceStorage root
+fn read_stored_workspaces(storage_path: &Path) -> Result<Vec<StoredWorkspace>> {
+    let mut workspaces = Vec::new();
+    for entry in std::fs::read_dir(storage_path)?.filter_map(|e| e.ok()) {
+        let dir = entry.path();
+        if !dir.is_dir() {
+            continue;
+        }
+        let project_path = std::fs::read_to_string(dir.join("workspace.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str::<WorkspaceJson>(&content).ok())
+            .and_then(|ws| ws.folder)
+            .and_then(|folder| local_folder_path(&folder));
+        let last_modified = std::fs::read_dir(dir.join("chatSessions"))
+            .ok()
+            .and_then(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter_map(|e| e.metadata().ok()?.modified().ok())
+                    .max()
+            })
+            .unwrap_or(std::time::UNIX_EPOCH);
+
+        workspaces.push(StoredWorkspace {
+            hash: entry.file_name().to_string_lossy().to_string(),
+            dir,
+            project_path,
+            last_modified,
+        });
+    }
+    Ok(workspaces)
+}
+
+/// First user message of a session on one line
+fn session_preview(session: &ChatSession) -> String {
+    session
+        .requests
+        .iter()
+        .find_map(|r| r.message.as_ref()?.text.clone())
+        .map(|text| truncate(&text.split_whitespace().collect::<Vec<_>>().join(" "), 70))
+        .unwrap_or_default()
+}
+
+/// Find sessions under `storage_path` that VS Code will not show
+pub fn scan_orphan_candidates(storage_path: &Path) -> Result<Vec<OrphanCandidate>> {
+    let workspaces = read_stored_workspaces(storage_path)?;
+    let max_bytes = config::current().limits().max_session_bytes();
+
+    // The most recently used workspace for each live folder
+    let mut newest: HashMap<String, &StoredWorkspace> = HashMap::new();
+    for ws in workspaces.iter().filter(|ws| ws.is_live()) {
+        let key = normalize_path(&ws.project_path.as_ref().unwrap().to_string_lossy());
+        match newest.get(&key) {
+            Some(current) if current.last_modified >= ws.last_modified => {}
+            _ => {
+                newest.insert(key, ws);
+            }
+        }
+    }
+    let live: Vec<&StoredWorkspace> = newest.values().copied().collect();
+
+    let mut candidates = Vec::new();
+    for ws in &workspaces {
+        let Some(project) = &ws.project_path else {
+            continue;
+        };
+        let sessions_dir = ws.dir.join("chatSessions");
+        if !sessions_dir.is_dir() {
+            continue;
+        }
+
+        let newest_for_folder = if project.exists() {
+            newest
+                .get(&normalize_path(&project.to_string_lossy()))
+                .copied()
+        } else {
+            None
+        };
+        let workspace_reason = match newest_for_folder {
+            None => Some(OrphanReason::FolderDeleted),
+            Some(n) if n.hash != ws.hash => Some(OrphanReason::Superseded),
+            Some(_) => None,
+        };
+
+        // Only sessions in the active workspace for a folder can be merely unindexed
+        let indexed: HashSet<String> = if workspace_reason.is_none() {
+            let db_path = ws.dir.join("state.vscdb");
+            if db_path.exists() {
+                match read_chat_session_index(&db_path) {
+                    Ok(index) => index.entries.into_keys().collect(),
+                    Err(_) => continue,
+                }
+            } else {
+                HashSet::new()
+            }
+        } else {
+            HashSet::new()
+        };
+
+        for entry in std::fs::read_dir(&sessions_dir)?.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if hidden || !path.extension().is_some_and(is_session_file_extension) {
+                continue;
+            }
+            if check_session_file_size(&path, max_bytes).is_err() {
+                continue;
+            }
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let Ok((session, _)) = parse_session_auto(&content) else {
+                continue;
+            };
+            let session_id = session.session_id.clone().unwrap_or_else(|| {
+                path.file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            });
+
+            let (reason, suggestion) = match workspace_reason {
+                None if indexed.contains(&session_id) => continue,
+                None => (
+                    OrphanReason::Unindexed,
+                    Some(WorkspaceSuggestion {
+                        hash: ws.hash.clone(),
+                        dir: ws.dir.clone(),
+                        project_path: project.clone(),
+                        score: 100,
+                        reason: "register in place".to_string(),
+                    }),
+                ),
+                Some(OrphanReason::Superseded) => {
+                    let target = newest_for_folder.unwrap();
+                    (
+                        OrphanReason::Superseded,
+                        Some(WorkspaceSuggestion {
+                            hash: target.hash.clone(),
+                            dir: target.dir.clone(),
+                            project_path: project.clone(),
+                            score: 100,
+                            reason: "newer workspace for the same folder".to_string(),
+                        }),
+                    )
+                }
+                Some(reason) => (reason, suggest_workspace(Some(project), &content, &live)),
+            };
+
+            // Already copied into the suggested workspace by an earlier recovery
+            if let Some(s) = &suggestion {
+                if s.dir != ws.dir {
+                    if let Some(name) = path.file_name() {
+                        if s.dir.join("chatSessions").join(name).exists() {
+                            continue;
+                        }
+                    }
+                }
+            }
+
+            candidates.push(OrphanCandidate {
+                path: path.clone(),
+                title: session.title(),
+                message_count: session.request_count(),
+                last_message_date: session.last_message_date,
+                preview: session_preview(&session),
+                source_hash: ws.hash.clone(),
+                source_project: Some(project.clone()),
+                reason,
+                suggestion,
+                is_imported: session.is_imported,
+                initial_location: session.initial_location.clone(),
+                is_empty: session.is_empty(),
+                session_id,
+            });
+        }
+    }
+
+    candidates.sort_by_key(|c| std::cmp::Reverse(c.last_message_date));
+    Ok(candidates)
+}
+
+/// Copy a session into its suggested workspace (if needed) and add it to the index
+fn register_candidate(candidate: &OrphanCandidate, target: &WorkspaceSuggestion) -> Result<()> {
+    let dest_dir = target.dir.join("chatSessions");
+    std::fs::create_dir_all(&dest_dir)?;
+    let file_name = candidate
+        .path
+        .file_name()
+        .ok_or_else(|| CsmError::SessionNotFound(candidate.path.display().to_string()))?;
+    let dest = dest_dir.join(file_name);
+
+    let copied = dest != candidate.path;
+    if copied {
+        copy_file_atomic(&candidate.path, &dest)?;
+    }
+
+    let registered = add_session_to_index(
+        &target.dir.join("state.vscdb"),
+        &candidate.session_id,
+        &candidate.title,
+        candidate.last_message_date,
+        candidate.is_imported,
+        &candidate.initial_location,
+        candidate.is_empty,
+    );
+    if let Err(e) = registered {
+        if copied {
+            let _ = std::fs::remove_file(&dest);
+        }
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Copy sessions into a directory, prefixing the workspace hash on name clashes
+fn export_candidates(candidates: &[OrphanCandidate], dir: &Path) -> Result<usize> {
+    std::fs::create_dir_all(dir)?;
+    let mut exported = 0;
+    for candidate in candidates {
+        let Some(name) = candidate.path.file_name() else {
+            continue;
+        };
+        let mut dest = dir.join(name);
+        if dest.exists() {
+            dest = dir.join(format!(
+                "{}-{}",
+                prefix_chars(&candidate.source_hash, 8),
+                name.to_string_lossy()
+            ));
+        }
+        copy_file_atomic(&candidate.path, &dest)?;
+        exported += 1;
+    }
+    Ok(exported)
+}
+
+fn prompt(message: &str) -> Result<String> {
+    print!("{} ", message);
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_lowercase())
+}
+
+fn print_candidate(index: usize, candidate: &OrphanCandidate) {
+    let date = from_epoch(candidate.last_message_date)
+        .map(format_date)
+        .unwrap_or_else(|| "unknown".to_string());
+    println!(
+        "{:>4}. {} ({} msgs, {}) [{}]",
+        index + 1,
+        truncate(&candidate.title, 50).bold(),
+        candidate.message_count,
+        date,
+        candidate.reason.label().yellow()
+    );
+    println!(
+        "      {} {} in {}",
+        "id".dimmed(),
+        prefix_chars(&candidate.session_id, 12).cyan(),
+        candidate
+            .source_project
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| candidate.source_hash.clone())
+            .dimmed()
+    );
+    if !candidate.preview.is_empty() {
+        println!("      {} {}", ">".dimmed(), candidate.preview.dimmed());
+    }
+    match &candidate.suggestion {
+        Some(s) => println!(
+            "      {} {} ({})",
+            "->".green(),
+            s.project_path.display(),
+            s.reason
+        ),
+        None => println!("      {} no matching workspace", "->".red()),
+    }
+}
+
+/// Scan for orphaned and deleted-workspace sessions, then register or export them
+pub fn recover_wizard(
+    path: Option<&str>,
+    yes: bool,
+    export: Option<&str>,
+    dry_run: bool,
+    force: bool,
+) -> Result<()> {
+    println!("{} Session Recovery Wizard", "[CSM]".cyan().bold());
+    println!("{}", "=".repeat(60));
+
+    let storage_path = get_workspace_storage_path()?;
+    let mut candidates = scan_orphan_candidates(&storage_path)?;
+
+    if let Some(path) = path {
+        let target = normalize_path(path);
+        candidates.retain(|c| {
+            let matches = |p: &Path| normalize_path(&p.to_string_lossy()) == target;
+            c.source_project.as_deref().is_some_and(matches)
+                || c.suggestion
+                    .as_ref()
+                    .is_some_and(|s| matches(&s.project_path))
+        });
+    }
+
+    if candidates.is_empty() {
+        println!(
+            "{} No orphaned sessions found - everything is visible in VS Code",
+            "[OK]".green().bold()
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{} Found {} recoverable session(s):\n",
+        "[+]".green().bold(),
+        candidates.len()
+    );
+    for (i, candidate) in candidates.iter().enumerate() {
+        print_candidate(i, candidate);
+    }
+    println!();
+
+    if let Some(dir) = export {
+        let dir = config::resolve_export_path(dir);
+        if dry_run {
+            println!(
+                "{} Dry run: would export {} session(s) to {}",
+                "[*]".blue(),
+                candidates.len(),
+                dir.display()
+            );
+            return Ok(());
+        }
+        let exported = export_candidates(&candidates, &dir)?;
+        println!(
+            "{} Exported {} session(s) to {}",
+            "[OK]".green().bold(),
+            exported,
+            dir.display()
+        );
+        return Ok(());
+    }
+
+    let unmatched = candidates.iter().filter(|c| c.suggestion.is_none()).count();
+    let matched: Vec<&OrphanCandidate> = candidates
+        .iter()
+        .filter(|c| c.suggestion.is_some())
+        .collect();
+    if unmatched > 0 {
+        println!(
+            "{} {} session(s) have no matching workspace; use {} to save them",
+            "[!]".yellow(),
+            unmatched,
+            "--export <dir>".cyan()
+        );
+    }
+    if matched.is_empty() {
+        return Ok(());
+    }
+
+    if dry_run {
+        println!(
+            "{} Dry run: would register {} session(s)",
+            "[*]".blue(),
+            matched.len()
+        );
+        return Ok(());
+    }
+
+    if !force && is_vscode_running() {
+        println!(
+            "{} VS Code is running. Close it or use {} to recover anyway.",
+            "[!]".yellow(),
+            "--force".cyan()
+        );
+        return Err(CsmError::VSCodeRunning.into());
+    }
+
+    let selected: Vec<&OrphanCandidate> = if yes {
+        matched
+    } else {
+        let answer = prompt(&format!(
+            "Register {} session(s) as suggested? [a]ll / [r]eview each / [q]uit:",
+            matched.len()
+        ))?;
+        match answer.as_str() {
+            "a" | "all" | "y" | "yes" => matched,
+            "r" | "review" => {
+                let mut chosen = Vec::new();
+                for candidate in matched {
+                    let target = candidate.suggestion.as_ref().unwrap();
+                    let answer = prompt(&format!(
+                        "  Register \"{}\" in {}? [y/N/q]",
+                        truncate(&candidate.title, 40),
+                        target.project_path.display()
+                    ))?;
+                    match answer.as_str() {
+                        "y" | "yes" => chosen.push(candidate),
+                        "q" | "quit" => break,
+                        _ => {}
+                    }
+                }
+                chosen
+            }
+            _ => {
+                println!("Cancelled");
+                return Ok(());
+            }
+        }
+    };
+
+    // One backup per target workspace before anything is written
+    let mut by_target: BTreeMap<&str, Vec<&OrphanCandidate>> = BTreeMap::new();
+    for candidate in &selected {
+        let target = candidate.suggestion.as_ref().unwrap();
+        by_target.entry(&target.hash).or_default().push(candidate);
+    }
+
+    let mut registered = 0;
+    let mut failed = 0;
+    for group in by_target.values() {
+        let target = group[0].suggestion.as_ref().unwrap();
+        if let Some(backup) = backup_workspace_sessions(&target.dir)? {
+            println!(
+                "{} Backed up {} to {}",
+                "[*]".blue(),
+                target.project_path.display(),
+                backup.display().to_string().dimmed()
+            );
+        }
+        for candidate in group {
+            match register_candidate(candidate, target) {
+                Ok(()) => {
+                    registered += 1;
+                    println!(
+                        "   {} {} -> {}",
+                        "[+]".green(),
+                        truncate(&candidate.title, 50),
+                        target.project_path.display()
+                    );
+                }
+                Err(e) => {
+                    failed += 1;
+                    println!("   {} {}: {}", "[!]".red(), candidate.title, e);
+                }
+            }
+        }
+    }
+
+    println!(
+        "\n{} Registered {} session(s){}",
+        "[OK]".green().bold(),
+        registered,
+        if failed > 0 {
+            format!(", {} failed", failed).red().to_string()
+        } else {
+            String::new()
+        }
+    );
+    if registered > 0 {
+        println!("   Reload VS Code to see the recovered sessions.");
+    }
+    Ok(())
+}
@@ -17,6 +17,7 @@ use crate::storage::{
     add_session_to_index, get_workspace_storage_db, is_vscode_running, parse_session_json,
     read_chat_session_index, register_all_sessions_from_directory,
 };
+use crate::text::prefix_chars;
 use crate::workspace::{discover_workspaces, find_workspace_by_path, normalize_path};
 
 /// Resolve a path option to an absolute PathBuf, handling "." and relative paths
@@ -180,11 +181,7 @@ pub fn register_sessions(
                 session.is_empty(),
             )?;
 
-            let id_display = if session_id.len() > 12 {
-                &session_id[..12]
-            } else {
-                &session_id
-            };
+            let id_display = prefix_chars(&session_id, 12);
             println!(
                 "   {} {} (\"{}\")",
                 "[OK]".green(),
@@ -223,11 +220,7 @@ pub fn register_sessions(
                         session.is_empty(),
                     )?;
 
-                    let id_display = if actual_session_id.len() > 12 {
-                        &actual_session_id[..12]
-                    } else {
-                        &actual_session_id
-                    };
+                    let id_display = prefix_chars(&actual_session_id, 12);
                     println!(
                         "   {} {} (\"{}\")",
                         "[OK]".green(),
@@ -337,11 +330,7 @@ pub fn list_orphaned(project_path: Option<&str>) -> Result<()> {
     );
 
     for (session_id, title, msg_count, _path) in &orphaned_sessions {
-        let id_display = if session_id.len() > 12 {
-            &session_id[..12]
-        } else {
-            session_id
-        };
+        let id_display = prefix_chars(session_id, 12);
         println!(
             "   {} {} ({} messages)",
             id_display.cyan(),
@@ -354,6 +343,11 @@ pub fn list_orphaned(project_path: Option<&str>) -> Result<()> {
     println!("   csm register all --force");
     println!("\n{} To register specific sessions:", "->".cyan());
     println!("   csm register session <ID1> <ID2> ... --force");
+    println!(
+        "\n{} To preview and recover sessions from old or deleted workspaces too:",
+        "->".cyan()
+    );
+    println!("   csm recover wizard");
 
     Ok(())
 }
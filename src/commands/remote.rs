@@ -0,0 +1,295 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Remote agent command implementations
+//!
+//! `csm agency remote` hands agent runs to a csm API server (`csm api serve`
+//! on the machine with the GPUs) and follows them from here, or runs them
+//! over SSH on a host with csm installed.
+
+use crate::agency::{
+    generate_node_id, LogLevel, RemoteAgentClient, RemoteEvent, RemoteTask, TaskLogEntry,
+    TaskRequest,
+};
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use std::future::Future;
+use std::path::Path;
+use std::process::Command;
+
+/// A client for the server at `server`, sending `token` as its API key
+pub fn remote_client(server: Option<&str>, token: Option<&str>) -> Result<RemoteAgentClient> {
+    let Some(server) = server else {
+        bail!("Pass --server or set CSM_REMOTE_SERVER to the csm API server's URL");
+    };
+    let url = if server.contains("://") {
+        server.to_string()
+    } else {
+        format!("http://{}", server)
+    };
+    let client = RemoteAgentClient::new(generate_node_id(), url);
+    Ok(match token {
+        Some(token) => client.with_auth(token),
+        None => client,
+    })
+}
+
+fn block_on<F: Future>(future: F) -> Result<F::Output> {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    Ok(rt.block_on(future))
+}
+
+/// Start `agent` on the server and, unless `detach`, follow it to the end,
+/// then download what it wrote into `fetch`
+pub fn remote_submit(
+    client: &RemoteAgentClient,
+    agent: &str,
+    prompt: &str,
+    model: Option<String>,
+    detach: bool,
+    fetch: Option<&str>,
+) -> Result<()> {
+    let request = TaskRequest {
+        agent: agent.to_string(),
+        prompt: prompt.to_string(),
+        model,
+    };
+    let task = block_on(client.submit_task(&request))??;
+    let model = task.metadata.get("model").and_then(|m| m.as_str());
+    println!(
+        "{} Started task {} on {} ({}{})",
+        "[+]".green(),
+        task.id.bold(),
+        task.node_id,
+        task.agent_name,
+        model.map(|m| format!(", {}", m)).unwrap_or_default()
+    );
+    if detach {
+        println!(
+            "{}",
+            format!("[i] Follow it with 'csm agency remote logs {}'", task.id).dimmed()
+        );
+        return Ok(());
+    }
+
+    println!();
+    follow(client, &task.id)?;
+    if let Some(out) = fetch {
+        println!();
+        remote_fetch(client, &task.id, None, out)?;
+    }
+    Ok(())
+}
+
+/// Run `agent` over SSH on `host`, which has csm installed, showing its
+/// output as it comes
+pub fn remote_submit_ssh(host: &str, agent: &str, prompt: &str, model: Option<&str>) -> Result<()> {
+    let mut command = format!("chasm agency run --agent {}", shell_quote(agent));
+    if let Some(model) = model {
+        command.push_str(&format!(" --model {}", shell_quote(model)));
+    }
+    command.push_str(&format!(" {}", shell_quote(prompt)));
+
+    println!("{} Running on {} over SSH", "[*]".cyan(), host.bold());
+    let status = Command::new("ssh")
+        .arg(host)
+        .arg(&command)
+        .status()
+        .context("Cannot run ssh")?;
+    if !status.success() {
+        bail!("The run on {} failed ({})", host, status);
+    }
+    Ok(())
+}
+
+/// `value` as one word for a POSIX shell
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// List the server's tasks
+pub fn remote_list(client: &RemoteAgentClient) -> Result<()> {
+    let tasks = block_on(client.list_tasks())??;
+    if tasks.is_empty() {
+        println!("{}", "No tasks on the server".dimmed());
+        return Ok(());
+    }
+    for task in &tasks {
+        println!(
+            "{}  {:<10} {:<14} {}",
+            task.id,
+            status_name(task),
+            task.agent_name,
+            task.title.dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// Show a task's status, and its response and artifacts once it has them
+pub fn remote_status(client: &RemoteAgentClient, task_id: &str) -> Result<()> {
+    let task = block_on(client.get_task(task_id))??;
+    println!("{} {}", "Task:".dimmed(), task.id.bold());
+    println!("  {} {}", "Status:".dimmed(), status_name(&task));
+    println!("  {} {} on {}", "Agent:".dimmed(), task.agent_name, task.node_id);
+    if let Some(model) = task.metadata.get("model").and_then(|m| m.as_str()) {
+        println!("  {} {}", "Model:".dimmed(), model);
+    }
+    println!("  {} {}", "Prompt:".dimmed(), task.title);
+    println!(
+        "  {} {}",
+        "Started:".dimmed(),
+        task.started_at.format("%Y-%m-%d %H:%M:%S")
+    );
+    if let Some(error) = &task.error {
+        println!("  {} {}", "Error:".dimmed(), error.red());
+    }
+    if let Some(result) = &task.result {
+        print_artifacts(result);
+        if let Some(response) = result.output.as_ref().and_then(|o| o["response"].as_str()) {
+            println!();
+            println!("{}", response);
+        }
+    }
+    Ok(())
+}
+
+/// Follow a task's log until it finishes
+pub fn remote_logs(client: &RemoteAgentClient, task_id: &str) -> Result<()> {
+    follow(client, task_id)
+}
+
+/// Download a task's artifacts, or the one named `name`, into `out`
+pub fn remote_fetch(
+    client: &RemoteAgentClient,
+    task_id: &str,
+    name: Option<&str>,
+    out: &str,
+) -> Result<()> {
+    let task = block_on(client.get_task(task_id))??;
+    let Some(result) = task.result else {
+        bail!("Task {} has not finished; its files come with its result", task_id);
+    };
+    let artifacts: Vec<_> = result
+        .artifacts
+        .iter()
+        .filter(|artifact| name.is_none_or(|name| artifact.name == name))
+        .collect();
+    match (artifacts.is_empty(), name) {
+        (true, Some(name)) => bail!("Task {} has no artifact named {}", task_id, name),
+        (true, None) => {
+            println!("{}", "The task wrote no files".dimmed());
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let out = Path::new(out);
+    std::fs::create_dir_all(out)?;
+    for artifact in artifacts {
+        let bytes = block_on(client.download_artifact(task_id, &artifact.name))??;
+        let path = out.join(&artifact.name);
+        std::fs::write(&path, &bytes)
+            .with_context(|| format!("Cannot write {}", path.display()))?;
+        println!(
+            "{} {} ({} bytes)",
+            "[+]".green(),
+            path.display(),
+            bytes.len()
+        );
+    }
+    Ok(())
+}
+
+/// Stop a running task
+pub fn remote_cancel(client: &RemoteAgentClient, task_id: &str) -> Result<()> {
+    block_on(client.cancel_task(task_id))??;
+    println!("{} Cancelled task {}", "[+]".green(), task_id);
+    Ok(())
+}
+
+/// Print a task's events until it finishes, failing when it fails
+fn follow(client: &RemoteAgentClient, task_id: &str) -> Result<()> {
+    let mut outcome = None;
+    block_on(client.stream_events(task_id, |event| match event {
+        RemoteEvent::TaskLog { entry, .. } => print_log(&entry),
+        RemoteEvent::TaskProgress {
+            progress, message, ..
+        } => println!(
+            "{} {:.0}% {}",
+            "[~]".cyan(),
+            progress * 100.0,
+            message.unwrap_or_default()
+        ),
+        event if event.is_final() => outcome = Some(event),
+        _ => {}
+    }))??;
+
+    match outcome {
+        Some(RemoteEvent::TaskCompleted { result, .. }) => {
+            if let Some(response) = result.output.as_ref().and_then(|o| o["response"].as_str()) {
+                println!();
+                println!("{}", response);
+            }
+            println!();
+            println!(
+                "{}",
+                format!(
+                    "[+] Completed in {:.1}s, {} tokens",
+                    result.metrics.duration_ms as f64 / 1000.0,
+                    result.metrics.tokens_used.unwrap_or_default()
+                )
+                .dimmed()
+            );
+            print_artifacts(&result);
+            Ok(())
+        }
+        Some(RemoteEvent::TaskFailed { error, .. }) => bail!("Task {} failed: {}", task_id, error),
+        Some(RemoteEvent::TaskCancelled { .. }) => bail!("Task {} was cancelled", task_id),
+        _ => bail!("Lost the connection before task {} finished", task_id),
+    }
+}
+
+fn print_log(entry: &TaskLogEntry) {
+    let time = entry.timestamp.format("%H:%M:%S").to_string();
+    let message = match entry.level {
+        LogLevel::Error => entry.message.red(),
+        LogLevel::Warn => entry.message.yellow(),
+        LogLevel::Info => entry.message.normal(),
+        LogLevel::Debug | LogLevel::Trace => entry.message.dimmed(),
+    };
+    println!("{} {}", time.dimmed(), message);
+}
+
+fn print_artifacts(result: &crate::agency::TaskResult) {
+    if result.artifacts.is_empty() {
+        return;
+    }
+    println!("  {}", "Artifacts:".dimmed());
+    for artifact in &result.artifacts {
+        println!(
+            "    {} {}",
+            artifact.name,
+            format!("({} bytes)", artifact.size.unwrap_or_default()).dimmed()
+        );
+    }
+}
+
+fn status_name(task: &RemoteTask) -> String {
+    serde_json::to_value(task.status)
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("fix the build"), "'fix the build'");
+        assert_eq!(shell_quote("it's $HOME"), r"'it'\''s $HOME'");
+    }
+}
@@ -0,0 +1,425 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Report commands
+
+use anyhow::{Context, Result};
+use colored::*;
+use rusqlite::Connection;
+use tabled::{settings::Style as TableStyle, Table, Tabled};
+
+use super::harvest::open_harvest_db;
+use crate::agency::budget::{SpendReport, SpendTotal};
+use crate::analytics::{Digest, QualityReport, QualityTrend};
+use crate::datetime::{format_date, format_datetime_short, parse_since};
+use crate::integrations::email::{Email, SmtpMailer};
+use crate::reviews::{self, ReviewReport, Tally};
+use crate::text::truncate;
+
+#[derive(Tabled)]
+struct ReviewRow {
+    #[tabled(rename = "Date")]
+    date: String,
+    #[tabled(rename = "Session")]
+    title: String,
+    #[tabled(rename = "Workspace")]
+    workspace: String,
+    #[tabled(rename = "Files")]
+    files: usize,
+    #[tabled(rename = "Themes")]
+    themes: String,
+}
+
+/// Summarize code-review sessions updated since `since`
+pub fn report_reviews(
+    db_path: Option<&str>,
+    since: &str,
+    limit: usize,
+    rescan: bool,
+    json: bool,
+) -> Result<()> {
+    let since = parse_since(since)?;
+    let conn = open_harvest_db(db_path)?;
+    reviews::ensure_tags_table(&conn)?;
+    if rescan {
+        let tagged = reviews::retag_sessions(&conn, since)?;
+        if !json {
+            println!(
+                "{} Re-scanned sessions, {} tagged as {}",
+                "[*]".blue(),
+                tagged,
+                reviews::REVIEW_TAG.cyan()
+            );
+        }
+    }
+
+    let report = ReviewReport::load(&conn, since)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!(
+        "\n{} Code Reviews since {}",
+        "[*]".blue().bold(),
+        format_datetime_short(report.since)
+    );
+    println!("{}", "=".repeat(60));
+
+    if report.sessions.is_empty() {
+        println!("\n{} No review sessions found", "[!]".yellow());
+        if !rescan {
+            println!(
+                "   Sessions harvested before review detection can be tagged with {}",
+                "--rescan".cyan()
+            );
+        }
+        return Ok(());
+    }
+
+    let pull_requests: usize = report.sessions.iter().map(|s| s.pull_requests.len()).sum();
+    println!("  {:<16} {}", "Sessions:", report.sessions.len());
+    println!("  {:<16} {}", "Pull requests:", pull_requests);
+    println!("  {:<16} {}", "Files:", report.files.len());
+
+    println!("\n{} Reviewed", "[+]".green());
+    let rows: Vec<ReviewRow> = report
+        .sessions
+        .iter()
+        .take(limit)
+        .map(|s| {
+            let title = match s.pull_requests.first() {
+                Some(pr) => format!("{} ({})", s.title, pr),
+                None => s.title.clone(),
+            };
+            ReviewRow {
+                date: format_date(s.updated_at),
+                title: truncate(&title, 50),
+                workspace: truncate(s.workspace.as_deref().unwrap_or("-"), 24),
+                files: s.files.len(),
+                themes: truncate(&s.themes.join(", "), 40),
+            }
+        })
+        .collect();
+    println!("{}", Table::new(&rows).with(TableStyle::ascii_rounded()));
+    if report.sessions.len() > limit {
+        println!("   ... and {} more", report.sessions.len() - limit);
+    }
+
+    print_tallies("Feedback themes", &report.themes, limit);
+    print_tallies("Most discussed files", &report.files, limit);
+    Ok(())
+}
+
+#[derive(Tabled)]
+struct SpendRow {
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Runs")]
+    runs: u64,
+    #[tabled(rename = "Prompt")]
+    prompt_tokens: u64,
+    #[tabled(rename = "Completion")]
+    completion_tokens: u64,
+    #[tabled(rename = "Cost (USD)")]
+    cost: String,
+}
+
+impl From<&SpendTotal> for SpendRow {
+    fn from(spend: &SpendTotal) -> Self {
+        let mut cost = format!("{:.4}", spend.cost_usd);
+        if spend.unpriced_tokens > 0 {
+            cost.push_str(" *");
+        }
+        Self {
+            name: truncate(&spend.name, 40),
+            runs: spend.runs,
+            prompt_tokens: spend.prompt_tokens,
+            completion_tokens: spend.completion_tokens,
+            cost,
+        }
+    }
+}
+
+/// Summarize the tokens and cost of agent runs since `since`
+pub fn report_spend(db_path: Option<&str>, since: &str, json: bool) -> Result<()> {
+    let since = parse_since(since)?;
+    let conn = open_harvest_db(db_path)?;
+    let report = SpendReport::load(&conn, since)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!(
+        "\n{} Agent Spend since {}",
+        "[*]".blue().bold(),
+        format_datetime_short(report.since)
+    );
+    println!("{}", "=".repeat(60));
+
+    if report.total.runs == 0 {
+        println!("\n{} No agent runs recorded", "[!]".yellow());
+        return Ok(());
+    }
+
+    let total = &report.total;
+    println!("  {:<16} {}", "Runs:", total.runs);
+    println!(
+        "  {:<16} {} prompt + {} completion",
+        "Tokens:", total.prompt_tokens, total.completion_tokens
+    );
+    println!("  {:<16} ${:.4}", "Cost:", total.cost_usd);
+
+    for (heading, totals) in [
+        ("By day", &report.days),
+        ("By model", &report.models),
+        ("By agent", &report.agents),
+    ] {
+        println!("\n{} {}", "[+]".green(), heading);
+        let rows: Vec<SpendRow> = totals.iter().map(SpendRow::from).collect();
+        println!("{}", Table::new(&rows).with(TableStyle::ascii_rounded()));
+    }
+
+    if total.unpriced_tokens > 0 {
+        println!(
+            "\n   * {} tokens went to models without a known price; set one under {}",
+            total.unpriced_tokens,
+            "[agency.budget.prices]".cyan()
+        );
+    }
+    Ok(())
+}
+
+#[derive(Tabled)]
+struct QualityRow {
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Sessions")]
+    sessions: u64,
+    #[tabled(rename = "Quality")]
+    quality: String,
+    #[tabled(rename = "Sentiment")]
+    sentiment: String,
+    #[tabled(rename = "Negative")]
+    negative: String,
+}
+
+impl From<&QualityTrend> for QualityRow {
+    fn from(trend: &QualityTrend) -> Self {
+        Self {
+            name: truncate(&trend.name, 40),
+            sessions: trend.sessions,
+            quality: format!("{:.0}", trend.avg_quality),
+            sentiment: format!("{:+.2}", trend.avg_sentiment),
+            negative: format!("{:.0}%", trend.negative_share() * 100.0),
+        }
+    }
+}
+
+/// Compare the quality and sentiment of sessions updated since `since`
+pub fn report_quality(
+    db_path: Option<&str>,
+    since: &str,
+    provider: Option<&str>,
+    limit: usize,
+    json: bool,
+) -> Result<()> {
+    let since = parse_since(since)?;
+    let conn = open_harvest_db(db_path)?;
+    let report = QualityReport::load(&conn, since, provider)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!(
+        "\n{} Session Quality since {}",
+        "[*]".blue().bold(),
+        format_datetime_short(report.since)
+    );
+    println!("{}", "=".repeat(60));
+
+    if report.total.sessions == 0 {
+        println!("\n{} No sessions found", "[!]".yellow());
+        return Ok(());
+    }
+
+    let total = &report.total;
+    println!("  {:<16} {}", "Sessions:", total.sessions);
+    println!("  {:<16} {:.0}/100", "Avg quality:", total.avg_quality);
+    println!("  {:<16} {:+.2}", "Avg sentiment:", total.avg_sentiment);
+    println!(
+        "  {:<16} {} ({:.0}%)",
+        "Negative:",
+        total.negative_sessions,
+        total.negative_share() * 100.0
+    );
+
+    // Breakdowns are sorted most frustrating first
+    for (label, trends) in [("provider", &report.providers), ("model", &report.models)] {
+        if let Some(worst) = trends.first().filter(|t| t.avg_sentiment < 0.0) {
+            println!(
+                "\n{} Most frustrating {}: {} ({:+.2} over {} sessions)",
+                "[!]".yellow(),
+                label,
+                worst.name.cyan(),
+                worst.avg_sentiment,
+                worst.sessions
+            );
+        }
+    }
+
+    for (heading, trends) in [
+        ("By provider", &report.providers[..]),
+        ("By project", &report.projects[..]),
+        ("By model", &report.models[..]),
+        // The latest months
+        ("By month", &report.months[report.months.len().saturating_sub(limit)..]),
+    ] {
+        if trends.is_empty() {
+            continue;
+        }
+        println!("\n{} {}", "[+]".green(), heading);
+        let rows: Vec<QualityRow> = trends.iter().take(limit).map(QualityRow::from).collect();
+        println!("{}", Table::new(&rows).with(TableStyle::ascii_rounded()));
+        if trends.len() > limit {
+            println!("   ... and {} more", trends.len() - limit);
+        }
+    }
+    Ok(())
+}
+
+/// Recipients from the arguments, or `to` under `[integrations.smtp]`
+fn digest_recipients(to: &[String]) -> Result<Vec<String>> {
+    let recipients = if to.is_empty() {
+        crate::config::current().integrations.smtp.to.clone()
+    } else {
+        to.to_vec()
+    };
+    if recipients.is_empty() {
+        anyhow::bail!("Name a recipient with --to, or set to under [integrations.smtp]");
+    }
+    Ok(recipients)
+}
+
+/// Email a digest through the server under `[integrations.smtp]`
+pub(crate) fn send_digest(digest: &Digest, to: &[String]) -> Result<Vec<String>> {
+    let recipients = digest_recipients(to)?;
+    let email = Email {
+        to: recipients.clone(),
+        subject: digest.subject(),
+        html: digest.to_html(),
+        text: digest.to_text(),
+    };
+    SmtpMailer::from_config()?.send(&email)?;
+    Ok(recipients)
+}
+
+/// Sum up the sessions, messages, topics, and longest sessions since
+/// `since`, and print, save, or email it
+#[allow(clippy::too_many_arguments)]
+pub fn report_digest(
+    db_path: Option<&str>,
+    since: &str,
+    limit: usize,
+    send: bool,
+    to: &[String],
+    html: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    let since = parse_since(since)?;
+    let conn = open_harvest_db(db_path)?;
+    let digest = Digest::load(&conn, since, chrono::Utc::now(), limit)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&digest)?);
+    } else {
+        print_digest(&digest);
+    }
+    if let Some(path) = html {
+        std::fs::write(path, digest.to_html())
+            .with_context(|| format!("Failed to write {}", path))?;
+        if !json {
+            println!("\n{} Wrote {}", "[+]".green(), path);
+        }
+    }
+    if send {
+        let recipients = send_digest(&digest, to)?;
+        if !json {
+            println!(
+                "\n{} Sent \"{}\" to {}",
+                "[+]".green(),
+                digest.subject(),
+                recipients.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+fn print_digest(digest: &Digest) {
+    println!(
+        "\n{} Activity Digest since {}",
+        "[*]".blue().bold(),
+        format_datetime_short(digest.since)
+    );
+    println!("{}", "=".repeat(60));
+    if digest.is_empty() {
+        println!("\n{} No chat activity", "[!]".yellow());
+        return;
+    }
+    println!(
+        "  {:<16} {} ({:+} on the period before)",
+        "New sessions:",
+        digest.new_sessions,
+        digest.new_sessions as i64 - digest.previous_new_sessions as i64
+    );
+    println!("  {:<16} {}", "Active sessions:", digest.active_sessions);
+    println!("  {:<16} {}", "Messages:", digest.messages);
+
+    if !digest.providers.is_empty() {
+        println!("\n{} By provider", "[+]".green());
+        for provider in &digest.providers {
+            println!(
+                "   {:>3} new, {:>5} messages  {}",
+                provider.new_sessions.to_string().cyan(),
+                provider.messages,
+                provider.provider
+            );
+        }
+    }
+    if !digest.topics.is_empty() {
+        println!("\n{} Top topics", "[+]".green());
+        for topic in &digest.topics {
+            println!(
+                "   {:>3}  {}",
+                topic.sessions.to_string().cyan(),
+                topic.topic
+            );
+        }
+    }
+    if !digest.long_sessions.is_empty() {
+        println!("\n{} Longest sessions", "[+]".green());
+        for session in &digest.long_sessions {
+            println!(
+                "   {:>4}  {} {}",
+                session.messages.to_string().cyan(),
+                truncate(&session.title, 60),
+                format_date(session.updated_at).dimmed()
+            );
+        }
+    }
+}
+
+fn print_tallies(heading: &str, tallies: &[Tally], limit: usize) {
+    if tallies.is_empty() {
+        return;
+    }
+    println!("\n{} {}", "[+]".green(), heading);
+    for tally in tallies.iter().take(limit) {
+        println!(
+            "   {:>3}  {}",
+            tally.sessions.to_string().cyan(),
+            truncate(&tally.name, 70)
+        );
+    }
+}
@@ -0,0 +1,1141 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Interactive restore across backups, archives, git history, and checkpoints
+//!
+//! Restore points come from five places:
+//!
+//! - safety copies in the backup catalog
+//! - encrypted archives at the backup destination
+//! - commits of the git-tracked harvest database
+//! - chat snapshot tags in the current project
+//! - harvest session checkpoints
+//!
+//! The browser lists them together by time, restores the whole database, one
+//! workspace, or one session from the chosen point, and backs up whatever it
+//! is about to overwrite first.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use colored::*;
+use rusqlite::{params, Connection, OpenFlags};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+use super::harvest::{copy_sessions_from, get_db_path, restore_checkpoint};
+use super::workspace_cmds::format_file_size;
+use crate::backup::archive::{self, EntryKind, Snapshot};
+use crate::backup::{backup_file, destination, BackupEntry, BackupKind, BackupManager};
+use crate::config;
+use crate::datetime::{format_datetime_short, from_epoch};
+use crate::storage::{
+    backup_workspace_sessions, is_session_file_extension, is_vscode_running, parse_session_auto,
+    write_file_atomic,
+};
+use crate::text::{prefix_chars, truncate, truncate_start};
+use crate::workspace::{discover_workspaces, get_workspace_storage_path};
+
+/// Restore points listed per source
+const MAX_POINTS_PER_SOURCE: usize = 20;
+
+/// Sessions listed before asking for a filter
+const MAX_LISTED_SESSIONS: usize = 30;
+
+/// Where a restore point comes from
+enum PointSource {
+    /// Safety copy in the backup catalog
+    Catalog(BackupEntry),
+    /// Encrypted archive at the backup destination
+    Archive(String),
+    /// Commit of the git-tracked harvest database
+    HarvestCommit(String),
+    /// Chat snapshot tag in a project repository
+    ProjectSnapshot { project: PathBuf, tag: String },
+    /// Harvest session checkpoint
+    Checkpoint { session_id: String, number: i64 },
+}
+
+struct RestorePoint {
+    created_at: Option<DateTime<Utc>>,
+    source: PointSource,
+    description: String,
+}
+
+impl RestorePoint {
+    fn label(&self) -> &'static str {
+        match self.source {
+            PointSource::Catalog(_) => "backup",
+            PointSource::Archive(_) => "archive",
+            PointSource::HarvestCommit(_) => "git",
+            PointSource::ProjectSnapshot { .. } => "snapshot",
+            PointSource::Checkpoint { .. } => "checkpoint",
+        }
+    }
+}
+
+/// How much of a restore point to bring back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Database,
+    Workspace,
+    Session,
+}
+
+/// A harvest database to restore from
+struct DatabaseImage {
+    path: PathBuf,
+    /// Database the image replaces
+    target: PathBuf,
+    /// Extracted to a temp file, removed when dropped
+    temporary: bool,
+}
+
+impl Drop for DatabaseImage {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// A file a restore point can write back
+struct RestoreFile {
+    /// Workspace hash, or project folder name for project snapshots
+    workspace: String,
+    /// Session id; `None` for workspace metadata
+    session_id: Option<String>,
+    target: PathBuf,
+    data: Vec<u8>,
+}
+
+impl RestoreFile {
+    /// Whether the target already has these contents
+    fn is_current(&self) -> bool {
+        fs::read(&self.target).is_ok_and(|current| current == self.data)
+    }
+}
+
+/// A session in a restore point, from its file and/or the database image
+struct SessionChoice {
+    id: String,
+    title: String,
+    workspace: Option<String>,
+    updated_at: Option<DateTime<Utc>>,
+    in_database: bool,
+}
+
+/// Everything a restore point contains
+#[derive(Default)]
+struct RestoreSet {
+    database: Option<DatabaseImage>,
+    files: Vec<RestoreFile>,
+    sessions: Vec<SessionChoice>,
+}
+
+impl RestoreSet {
+    /// Merge session files into the session list, newest first
+    fn index_sessions(&mut self) -> Result<()> {
+        if let Some(image) = &self.database {
+            self.sessions = database_sessions(&image.path)?;
+        }
+        let positions: HashMap<String, usize> = self
+            .sessions
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.id.clone(), i))
+            .collect();
+
+        for file in &self.files {
+            let Some(id) = &file.session_id else {
+                continue;
+            };
+            if let Some(&i) = positions.get(id) {
+                self.sessions[i].workspace = Some(file.workspace.clone());
+                continue;
+            }
+            let parsed = std::str::from_utf8(&file.data)
+                .ok()
+                .and_then(|content| parse_session_auto(content).ok());
+            self.sessions.push(SessionChoice {
+                id: id.clone(),
+                title: parsed
+                    .as_ref()
+                    .map(|(s, _)| s.title())
+                    .unwrap_or_else(|| id.clone()),
+                workspace: Some(file.workspace.clone()),
+                updated_at: parsed.and_then(|(s, _)| from_epoch(s.last_message_date)),
+                in_database: false,
+            });
+        }
+
+        self.sessions
+            .sort_by(|a, b| b.updated_at.cmp(&a.updated_at).then(a.id.cmp(&b.id)));
+        Ok(())
+    }
+
+    /// Workspaces with their session counts
+    fn workspaces(&self) -> BTreeMap<&str, usize> {
+        let mut workspaces = BTreeMap::new();
+        for session in &self.sessions {
+            if let Some(ws) = &session.workspace {
+                *workspaces.entry(ws.as_str()).or_default() += 1;
+            }
+        }
+        workspaces
+    }
+}
+
+/// What a restore will write
+#[derive(Default)]
+struct Plan<'a> {
+    /// Replace the whole harvest database with the image
+    replace_database: bool,
+    /// Sessions to copy from the image into the harvest database
+    db_sessions: Vec<String>,
+    files: Vec<&'a RestoreFile>,
+}
+
+impl Plan<'_> {
+    fn touches_database(&self) -> bool {
+        self.replace_database || !self.db_sessions.is_empty()
+    }
+}
+
+/// Browse restore points across backups, archives, git history, and
+/// checkpoints, then restore a database, workspace, or session from one
+pub fn backup_restore_interactive(destination: Option<&str>, dry_run: bool) -> Result<()> {
+    println!("{} Restore Browser", "[CSM]".cyan().bold());
+    println!("{}", "=".repeat(60));
+
+    let db_path = get_db_path(None)?;
+    let points = gather_points(&db_path, destination);
+    if points.is_empty() {
+        println!(
+            "{} No backups, archives, snapshots, or checkpoints found",
+            "[!]".yellow()
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{} Found {} restore point(s):\n",
+        "[+]".green().bold(),
+        points.len()
+    );
+    for (i, point) in points.iter().enumerate() {
+        print_point(i, point);
+    }
+    println!();
+
+    let Some(index) = choose("Restore point", points.len())? else {
+        println!("Cancelled");
+        return Ok(());
+    };
+    let point = &points[index];
+
+    if let PointSource::Checkpoint { session_id, number } = &point.source {
+        return restore_checkpoint_point(&db_path, session_id, *number, dry_run);
+    }
+
+    println!("{} Loading {}...", "[*]".blue(), point.label());
+    let set = load_set(point, &db_path, destination)?;
+    print_contents(&set);
+
+    let Some(scope) = choose_scope(&set)? else {
+        println!("Cancelled");
+        return Ok(());
+    };
+    let Some(plan) = build_plan(&set, scope)? else {
+        println!("Cancelled");
+        return Ok(());
+    };
+
+    print_plan(&set, &plan);
+    if !plan.touches_database() && plan.files.iter().all(|f| f.is_current()) {
+        println!(
+            "{} Nothing to restore: everything already matches",
+            "[OK]".green().bold()
+        );
+        return Ok(());
+    }
+    if dry_run {
+        println!("\n{} Dry run: nothing was changed", "[*]".blue());
+        return Ok(());
+    }
+    if !plan.files.is_empty() && is_vscode_running() {
+        println!(
+            "{} VS Code is running; it may not pick up restored sessions until restarted",
+            "[!]".yellow()
+        );
+    }
+
+    let answer = prompt("Take a safety snapshot and restore? [y/N]")?;
+    if !matches!(answer.as_str(), "y" | "yes") {
+        println!("Cancelled");
+        return Ok(());
+    }
+
+    safety_snapshot(&set, &plan)?;
+    apply_plan(&set, &plan)
+}
+
+// ============================================================================
+// Restore points
+// ============================================================================
+
+/// Restore points from every source, newest first. A source that fails to
+/// load is reported and skipped.
+fn gather_points(db_path: &Path, destination: Option<&str>) -> Vec<RestorePoint> {
+    let mut points = Vec::new();
+    let mut add = |what: &str, result: Result<Vec<RestorePoint>>| match result {
+        Ok(found) => points.extend(found),
+        Err(e) => println!("{} Skipping {}: {}", "[!]".yellow(), what, e),
+    };
+
+    add("backup catalog", catalog_points());
+    if destination.is_some() || config::current().backups.destination.is_some() {
+        add("archives", archive_points(destination));
+    }
+    if let Some(dir) = db_path.parent().filter(|d| d.join(".git").exists()) {
+        add("harvest git history", harvest_commit_points(dir, db_path));
+    }
+    if let Ok(project) = std::env::current_dir() {
+        if project.join(".git").exists() && project.join(".vscode/chat-sessions").is_dir() {
+            add("project snapshots", project_snapshot_points(&project));
+        }
+    }
+    if db_path.is_file() {
+        add("checkpoints", checkpoint_points(db_path));
+    }
+
+    points.sort_by_key(|p| Reverse(p.created_at));
+    points
+}
+
+fn catalog_points() -> Result<Vec<RestorePoint>> {
+    let manager = BackupManager::open()?;
+    let mut entries: Vec<&BackupEntry> = manager.entries().iter().filter(|e| e.exists()).collect();
+    entries.sort_by_key(|e| Reverse(e.created_at));
+    Ok(entries
+        .into_iter()
+        .take(MAX_POINTS_PER_SOURCE)
+        .map(|entry| RestorePoint {
+            created_at: Some(entry.created_at),
+            description: format!(
+                "{} {} ({})",
+                entry.kind,
+                truncate_start(&entry.source.display().to_string(), 50),
+                entry.command
+            ),
+            source: PointSource::Catalog(entry.clone()),
+        })
+        .collect())
+}
+
+fn archive_points(destination: Option<&str>) -> Result<Vec<RestorePoint>> {
+    let dest = destination::open_or_configured(destination)?;
+    let mut archives = dest.list()?;
+    archives.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(archives
+        .into_iter()
+        .take(MAX_POINTS_PER_SOURCE)
+        .map(|archive| RestorePoint {
+            created_at: archive.modified,
+            description: format!("{} ({})", archive.name, format_file_size(archive.size)),
+            source: PointSource::Archive(archive.name),
+        })
+        .collect())
+}
+
+fn harvest_commit_points(dir: &Path, db_path: &Path) -> Result<Vec<RestorePoint>> {
+    let name = file_name(db_path);
+    let log = git(
+        dir,
+        &[
+            "log",
+            &format!("-{}", MAX_POINTS_PER_SOURCE),
+            "--format=%H%x09%ct%x09%s",
+            "--",
+            &name,
+        ],
+    )?;
+    Ok(String::from_utf8_lossy(&log)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let commit = parts.next()?.to_string();
+            let time = parts.next()?.parse::<i64>().ok();
+            let subject = parts.next().unwrap_or_default();
+            Some(RestorePoint {
+                created_at: time.and_then(|t| DateTime::from_timestamp(t, 0)),
+                description: format!("{} {} ({})", prefix_chars(&commit, 8), subject, name),
+                source: PointSource::HarvestCommit(commit),
+            })
+        })
+        .collect())
+}
+
+fn project_snapshot_points(project: &Path) -> Result<Vec<RestorePoint>> {
+    let refs = git(
+        project,
+        &[
+            "for-each-ref",
+            "--sort=-creatordate",
+            "--format=%(refname:short)%09%(creatordate:unix)%09%(subject)",
+            "refs/tags",
+        ],
+    )?;
+    Ok(String::from_utf8_lossy(&refs)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let tag = parts.next()?.to_string();
+            let time = parts.next()?.parse::<i64>().ok();
+            let subject = parts.next().unwrap_or_default();
+            // Only tags that include chat sessions
+            git(
+                project,
+                &["cat-file", "-e", &format!("{}:.vscode/chat-sessions", tag)],
+            )
+            .ok()?;
+            Some(RestorePoint {
+                created_at: time.and_then(|t| DateTime::from_timestamp(t, 0)),
+                description: format!("{} {} ({})", tag, subject, file_name(project)),
+                source: PointSource::ProjectSnapshot {
+                    project: project.to_path_buf(),
+                    tag,
+                },
+            })
+        })
+        .take(MAX_POINTS_PER_SOURCE)
+        .collect())
+}
+
+fn checkpoint_points(db_path: &Path) -> Result<Vec<RestorePoint>> {
+    let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let tables: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'checkpoints'",
+        [],
+        |row| row.get(0),
+    )?;
+    if tables == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT c.session_id, c.checkpoint_number, c.message, c.message_count, c.created_at, s.title
+         FROM checkpoints c LEFT JOIN sessions s ON s.id = c.session_id
+         ORDER BY c.created_at DESC LIMIT ?1",
+    )?;
+    let rows = stmt.query_map([MAX_POINTS_PER_SOURCE as i64], |row| {
+        let session_id: String = row.get(0)?;
+        let number: i64 = row.get(1)?;
+        let message: Option<String> = row.get(2)?;
+        let count: i64 = row.get(3)?;
+        let created_at: String = row.get(4)?;
+        let title: Option<String> = row.get(5)?;
+        Ok(RestorePoint {
+            created_at: NaiveDateTime::parse_from_str(&created_at, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .map(|dt| dt.and_utc()),
+            description: format!(
+                "#{} {} - {} ({} messages)",
+                number,
+                message.unwrap_or_default(),
+                truncate(title.as_deref().unwrap_or(&session_id), 40),
+                count
+            ),
+            source: PointSource::Checkpoint { session_id, number },
+        })
+    })?;
+    Ok(rows.collect::<std::result::Result<_, _>>()?)
+}
+
+fn print_point(index: usize, point: &RestorePoint) {
+    let date = point
+        .created_at
+        .map(format_datetime_short)
+        .unwrap_or_else(|| "unknown".to_string());
+    println!(
+        "{:>4}. {}  {:<10} {}",
+        index + 1,
+        date,
+        point.label().cyan(),
+        truncate(&point.description, 80)
+    );
+}
+
+// ============================================================================
+// Loading a restore point
+// ============================================================================
+
+fn load_set(point: &RestorePoint, db_path: &Path, destination: Option<&str>) -> Result<RestoreSet> {
+    let mut set = RestoreSet::default();
+    match &point.source {
+        PointSource::Catalog(entry) => match entry.kind {
+            BackupKind::HarvestDatabase => {
+                set.database = Some(DatabaseImage {
+                    path: entry.path.clone(),
+                    target: entry.source.clone(),
+                    temporary: false,
+                });
+            }
+            BackupKind::WorkspaceSessions | BackupKind::ProjectSessions => {
+                let workspace = workspace_key(&entry.source);
+                let mut paths: Vec<PathBuf> = fs::read_dir(&entry.path)?
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.path())
+                    .filter(|p| p.is_file() && p.extension().is_some_and(is_session_file_extension))
+                    .collect();
+                paths.sort();
+                for path in paths {
+                    set.files.push(RestoreFile {
+                        workspace: workspace.clone(),
+                        session_id: file_stem(&path),
+                        target: entry.source.join(path.file_name().unwrap_or_default()),
+                        data: fs::read(&path)?,
+                    });
+                }
+            }
+            BackupKind::SessionFile => {
+                set.files.push(RestoreFile {
+                    workspace: workspace_key(entry.source.parent().unwrap_or(Path::new(""))),
+                    session_id: file_stem(&entry.source),
+                    target: entry.source.clone(),
+                    data: fs::read(&entry.path)?,
+                });
+            }
+        },
+        PointSource::Archive(name) => {
+            let dest = destination::open_or_configured(destination)?;
+            let data = dest.get(name)?;
+            let snapshot = Snapshot::open(&data, &archive::passphrase()?)?;
+            load_archive(&mut set, &snapshot, db_path)?;
+        }
+        PointSource::HarvestCommit(commit) => {
+            let dir = db_path.parent().unwrap_or(Path::new("."));
+            let data = git(
+                dir,
+                &["show", &format!("{}:./{}", commit, file_name(db_path))],
+            )?;
+            set.database = Some(temp_database(&data, db_path)?);
+        }
+        PointSource::ProjectSnapshot { project, tag } => {
+            let listing = git(
+                project,
+                &[
+                    "ls-tree",
+                    "-r",
+                    "--name-only",
+                    "--full-tree",
+                    tag,
+                    ".vscode/chat-sessions",
+                ],
+            )?;
+            let workspace = file_name(project);
+            for path in String::from_utf8_lossy(&listing).lines() {
+                let relative = Path::new(path);
+                if !relative.extension().is_some_and(is_session_file_extension) {
+                    continue;
+                }
+                set.files.push(RestoreFile {
+                    workspace: workspace.clone(),
+                    session_id: file_stem(relative),
+                    target: project.join(relative),
+                    data: git(project, &["show", &format!("{}:{}", tag, path)])?,
+                });
+            }
+        }
+        PointSource::Checkpoint { .. } => {}
+    }
+    set.index_sessions()?;
+    Ok(set)
+}
+
+/// Map archive entries back to the current harvest database and workspace storage
+fn load_archive(set: &mut RestoreSet, snapshot: &Snapshot, db_path: &Path) -> Result<()> {
+    let storage = get_workspace_storage_path()
+        .ok()
+        .or_else(|| snapshot.manifest.workspace_storage.clone());
+
+    for (entry, data) in snapshot.files() {
+        let relative = Path::new(&entry.path);
+        if !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+        {
+            anyhow::bail!("refusing unsafe archive path {}", entry.path);
+        }
+        match entry.kind {
+            EntryKind::HarvestDatabase => {
+                set.database = Some(temp_database(data, db_path)?);
+            }
+            EntryKind::WorkspaceMetadata | EntryKind::SessionFile => {
+                let (Some(storage), Ok(rest)) = (&storage, relative.strip_prefix("workspaces"))
+                else {
+                    continue;
+                };
+                let workspace = rest
+                    .components()
+                    .next()
+                    .map(|c| c.as_os_str().to_string_lossy().to_string())
+                    .unwrap_or_default();
+                set.files.push(RestoreFile {
+                    workspace,
+                    session_id: match entry.kind {
+                        EntryKind::SessionFile => file_stem(relative),
+                        _ => None,
+                    },
+                    target: storage.join(rest),
+                    data: data.to_vec(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn database_sessions(path: &Path) -> Result<Vec<SessionChoice>> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut stmt = conn.prepare("SELECT id, title, workspace_id, updated_at FROM sessions")?;
+    let rows = stmt.query_map([], |row| {
+        Ok(SessionChoice {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            workspace: row.get(2)?,
+            updated_at: row.get::<_, Option<i64>>(3)?.and_then(from_epoch),
+            in_database: true,
+        })
+    })?;
+    Ok(rows.collect::<std::result::Result<_, _>>()?)
+}
+
+fn temp_database(data: &[u8], target: &Path) -> Result<DatabaseImage> {
+    let path =
+        std::env::temp_dir().join(format!("csm-restore-{}.db", uuid::Uuid::new_v4().simple()));
+    let image = DatabaseImage {
+        path,
+        target: target.to_path_buf(),
+        temporary: true,
+    };
+    fs::write(&image.path, data)?;
+    Ok(image)
+}
+
+fn print_contents(set: &RestoreSet) {
+    let mut parts = Vec::new();
+    if let Some(image) = &set.database {
+        parts.push(format!(
+            "harvest database ({})",
+            format_file_size(fs::metadata(&image.path).map(|m| m.len()).unwrap_or(0))
+        ));
+    }
+    let workspaces = set.workspaces().len();
+    if workspaces > 0 {
+        parts.push(format!("{} workspace(s)", workspaces));
+    }
+    parts.push(format!("{} session(s)", set.sessions.len()));
+    println!("   {} {}\n", "contains".dimmed(), parts.join(", "));
+}
+
+// ============================================================================
+// Choosing what to restore
+// ============================================================================
+
+fn choose_scope(set: &RestoreSet) -> Result<Option<Scope>> {
+    let mut options = Vec::new();
+    if set.database.is_some() {
+        options.push((Scope::Database, "d", "whole database"));
+    }
+    if !set.workspaces().is_empty() {
+        options.push((Scope::Workspace, "w", "one workspace"));
+    }
+    if !set.sessions.is_empty() {
+        options.push((Scope::Session, "s", "one session"));
+    }
+
+    match options.as_slice() {
+        [] => anyhow::bail!("This restore point is empty"),
+        [(scope, _, label)] => {
+            println!("{} Scope: {}", "[*]".blue(), label);
+            Ok(Some(*scope))
+        }
+        _ => {
+            let menu: Vec<String> = options
+                .iter()
+                .map(|(_, key, label)| format!("[{}] {}", key, label))
+                .collect();
+            loop {
+                let answer = prompt(&format!("Restore {} / [q]uit:", menu.join(" / ")))?;
+                if matches!(answer.as_str(), "" | "q" | "quit") {
+                    return Ok(None);
+                }
+                if let Some((scope, _, _)) = options.iter().find(|(_, key, _)| answer == *key) {
+                    return Ok(Some(*scope));
+                }
+            }
+        }
+    }
+}
+
+fn build_plan(set: &RestoreSet, scope: Scope) -> Result<Option<Plan<'_>>> {
+    let choice = match scope {
+        Scope::Database => String::new(),
+        Scope::Workspace => match choose_workspace(set)? {
+            Some(workspace) => workspace,
+            None => return Ok(None),
+        },
+        Scope::Session => match choose_session(&set.sessions)? {
+            Some(session) => session.id.clone(),
+            None => return Ok(None),
+        },
+    };
+    Ok(Some(plan_for(set, scope, &choice)))
+}
+
+/// The plan restoring `scope` from `set`, for the workspace or session id
+/// `choice` (unused for the whole database)
+fn plan_for<'a>(set: &'a RestoreSet, scope: Scope, choice: &str) -> Plan<'a> {
+    let mut plan = Plan::default();
+    match scope {
+        Scope::Database => plan.replace_database = true,
+        Scope::Workspace => {
+            plan.db_sessions = set
+                .sessions
+                .iter()
+                .filter(|s| s.in_database && s.workspace.as_deref() == Some(choice))
+                .map(|s| s.id.clone())
+                .collect();
+            plan.files = set.files.iter().filter(|f| f.workspace == choice).collect();
+        }
+        Scope::Session => {
+            if set.sessions.iter().any(|s| s.id == choice && s.in_database) {
+                plan.db_sessions.push(choice.to_string());
+            }
+            plan.files = set
+                .files
+                .iter()
+                .filter(|f| f.session_id.as_deref() == Some(choice))
+                .collect();
+        }
+    }
+    plan
+}
+
+fn choose_workspace(set: &RestoreSet) -> Result<Option<String>> {
+    let folders: HashMap<String, String> = discover_workspaces()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|ws| Some((ws.hash, ws.project_path?)))
+        .collect();
+    let workspaces: Vec<(&str, usize)> = set.workspaces().into_iter().collect();
+
+    println!();
+    for (i, (workspace, count)) in workspaces.iter().enumerate() {
+        println!(
+            "{:>4}. {} ({} session(s)) {}",
+            i + 1,
+            prefix_chars(workspace, 16),
+            count,
+            folders
+                .get(*workspace)
+                .map(String::as_str)
+                .unwrap_or_default()
+                .dimmed()
+        );
+    }
+    Ok(choose("Workspace", workspaces.len())?.map(|i| workspaces[i].0.to_string()))
+}
+
+fn choose_session(sessions: &[SessionChoice]) -> Result<Option<&SessionChoice>> {
+    let mut shown: Vec<&SessionChoice> = sessions.iter().collect();
+    if shown.len() > MAX_LISTED_SESSIONS {
+        let filter = prompt(&format!(
+            "{} sessions; filter by title or id (blank for the newest {}):",
+            shown.len(),
+            MAX_LISTED_SESSIONS
+        ))?;
+        if !filter.is_empty() {
+            shown.retain(|s| {
+                s.title.to_lowercase().contains(&filter) || s.id.to_lowercase().starts_with(&filter)
+            });
+        }
+        if shown.is_empty() {
+            println!("{} No sessions match '{}'", "[!]".yellow(), filter);
+            return Ok(None);
+        }
+        shown.truncate(MAX_LISTED_SESSIONS);
+    }
+
+    println!();
+    for (i, session) in shown.iter().enumerate() {
+        println!(
+            "{:>4}. {} ({}) {}",
+            i + 1,
+            truncate(&session.title, 50).bold(),
+            session
+                .updated_at
+                .map(format_datetime_short)
+                .unwrap_or_else(|| "unknown".to_string()),
+            prefix_chars(&session.id, 12).dimmed()
+        );
+    }
+    Ok(choose("Session", shown.len())?.map(|i| shown[i]))
+}
+
+/// Ask for a 1-based choice; `None` when the user quits
+fn choose(what: &str, count: usize) -> Result<Option<usize>> {
+    loop {
+        let answer = prompt(&format!("{} [1-{}, q to quit]:", what, count))?;
+        if matches!(answer.as_str(), "" | "q" | "quit") {
+            return Ok(None);
+        }
+        match answer.parse::<usize>() {
+            Ok(n) if (1..=count).contains(&n) => return Ok(Some(n - 1)),
+            _ => println!("{} Enter a number from 1 to {}", "[!]".yellow(), count),
+        }
+    }
+}
+
+fn prompt(message: &str) -> Result<String> {
+    print!("{} ", message);
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_lowercase())
+}
+
+// ============================================================================
+// Restoring
+// ============================================================================
+
+fn print_plan(set: &RestoreSet, plan: &Plan) {
+    println!("\n{} Plan:", "[*]".blue().bold());
+    if let Some(image) = &set.database {
+        if plan.replace_database {
+            println!("   replace harvest database {}", image.target.display());
+        } else if !plan.db_sessions.is_empty() {
+            println!(
+                "   copy {} session(s) into {}",
+                plan.db_sessions.len(),
+                image.target.display()
+            );
+        }
+    }
+    for file in &plan.files {
+        let status = match fs::read(&file.target) {
+            Ok(current) if current == file.data => "unchanged".dimmed(),
+            Ok(_) => "overwrite".yellow(),
+            Err(_) => "new".green(),
+        };
+        println!("   {:<9} {}", status, file.target.display());
+    }
+}
+
+/// Back up everything the plan is about to change
+fn safety_snapshot(set: &RestoreSet, plan: &Plan) -> Result<()> {
+    if let Some(image) = &set.database {
+        if plan.touches_database() && image.target.exists() {
+            let backup = backup_file(BackupKind::HarvestDatabase, &image.target)?;
+            println!(
+                "{} Safety snapshot of {} at {}",
+                "[*]".blue(),
+                image.target.display(),
+                backup.display().to_string().dimmed()
+            );
+        }
+    }
+
+    // One copy per VS Code chatSessions directory, single files elsewhere
+    let mut session_dirs = BTreeSet::new();
+    for file in &plan.files {
+        let Some(dir) = file.target.parent() else {
+            continue;
+        };
+        if dir.file_name().is_some_and(|n| n == "chatSessions") {
+            session_dirs.insert(dir);
+        } else if file.target.is_file() {
+            backup_file(BackupKind::SessionFile, &file.target)?;
+        }
+    }
+    for dir in session_dirs {
+        let Some(workspace_dir) = dir.parent() else {
+            continue;
+        };
+        if let Some(backup) = backup_workspace_sessions(workspace_dir)? {
+            println!(
+                "{} Safety snapshot of {} at {}",
+                "[*]".blue(),
+                dir.display(),
+                backup.display().to_string().dimmed()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn apply_plan(set: &RestoreSet, plan: &Plan) -> Result<()> {
+    if let Some(image) = &set.database {
+        if plan.replace_database {
+            write_file_atomic(&image.target, &fs::read(&image.path)?)?;
+            println!(
+                "   {} harvest database {}",
+                "[+]".green(),
+                image.target.display()
+            );
+        } else if !plan.db_sessions.is_empty() {
+            if !image.target.is_file() {
+                anyhow::bail!(
+                    "No harvest database at {}; restore the whole database instead",
+                    image.target.display()
+                );
+            }
+            let conn = Connection::open(&image.target)?;
+            let copied = copy_sessions_from(&conn, &image.path, &plan.db_sessions)?;
+            println!(
+                "   {} {} session(s) into {}",
+                "[+]".green(),
+                copied,
+                image.target.display()
+            );
+        }
+    }
+
+    let mut restored = 0;
+    let mut unchanged = 0;
+    for file in &plan.files {
+        if file.is_current() {
+            unchanged += 1;
+            continue;
+        }
+        if let Some(parent) = file.target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        write_file_atomic(&file.target, &file.data)
+            .with_context(|| format!("Failed to write {}", file.target.display()))?;
+        println!("   {} {}", "[+]".green(), file.target.display());
+        restored += 1;
+    }
+
+    if plan.files.is_empty() {
+        println!("\n{} Restore complete", "[OK]".green().bold());
+    } else {
+        println!(
+            "\n{} Restore complete: {} file(s) restored, {} unchanged",
+            "[OK]".green().bold(),
+            restored,
+            unchanged
+        );
+    }
+    if restored > 0
+        && plan.files.iter().any(|f| {
+            f.target
+                .components()
+                .any(|c| c.as_os_str() == "chatSessions")
+        })
+    {
+        println!(
+            "   Run {} in a project to add restored sessions to VS Code's index",
+            "csm register all".cyan()
+        );
+    }
+    Ok(())
+}
+
+fn restore_checkpoint_point(
+    db_path: &Path,
+    session_id: &str,
+    number: i64,
+    dry_run: bool,
+) -> Result<()> {
+    println!(
+        "\n{} Plan:\n   reset messages of session {} to checkpoint #{}",
+        "[*]".blue().bold(),
+        prefix_chars(session_id, 12),
+        number
+    );
+    if dry_run {
+        println!("\n{} Dry run: nothing was changed", "[*]".blue());
+        return Ok(());
+    }
+    let answer = prompt("Take a safety snapshot and restore? [y/N]")?;
+    if !matches!(answer.as_str(), "y" | "yes") {
+        println!("Cancelled");
+        return Ok(());
+    }
+
+    let backup = backup_file(BackupKind::HarvestDatabase, db_path)?;
+    println!(
+        "{} Safety snapshot of {} at {}",
+        "[*]".blue(),
+        db_path.display(),
+        backup.display().to_string().dimmed()
+    );
+
+    let conn = Connection::open(db_path)?;
+    let (_, restored) = restore_checkpoint(&conn, session_id, number)?;
+
+    println!(
+        "{} Restored {} message(s) from checkpoint #{}",
+        "[OK]".green().bold(),
+        restored,
+        number
+    );
+    Ok(())
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn git(dir: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// Workspace hash for `<hash>/chatSessions`, project name for
+/// `<project>/.vscode/chat-sessions`
+fn workspace_key(sessions_dir: &Path) -> String {
+    let owner = match sessions_dir.file_name().and_then(|n| n.to_str()) {
+        Some("chatSessions") => sessions_dir.parent(),
+        Some("chat-sessions") => sessions_dir.parent().and_then(Path::parent),
+        _ => Some(sessions_dir),
+    };
+    owner.map(file_name).unwrap_or_default()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn file_stem(path: &Path) -> Option<String> {
+    path.file_stem().map(|s| s.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(workspace: &str, session_id: Option<&str>) -> RestoreFile {
+        RestoreFile {
+            workspace: workspace.to_string(),
+            session_id: session_id.map(String::from),
+            target: PathBuf::from(workspace).join(session_id.unwrap_or("workspace")),
+            data: Vec::new(),
+        }
+    }
+
+    fn session(id: &str, workspace: &str, in_database: bool) -> SessionChoice {
+        SessionChoice {
+            id: id.to_string(),
+            title: id.to_string(),
+            workspace: Some(workspace.to_string()),
+            updated_at: None,
+            in_database,
+        }
+    }
+
+    #[test]
+    fn test_load_archive_rejects_unsafe_paths() {
+        let db_path = Path::new("harvest.db");
+        for path in [
+            "workspaces/../../.bashrc",
+            "/etc/cron.d/csm",
+            "./workspaces/abc/chatSessions/s1.json",
+        ] {
+            let mut snapshot = Snapshot::default();
+            snapshot.add(EntryKind::SessionFile, path.to_string(), b"{}".to_vec());
+            let mut set = RestoreSet::default();
+            let err = load_archive(&mut set, &snapshot, db_path).unwrap_err();
+            assert!(err.to_string().contains("unsafe archive path"), "{}", path);
+            assert!(set.files.is_empty());
+        }
+
+        let mut snapshot = Snapshot::default();
+        snapshot.manifest.workspace_storage = Some(PathBuf::from("storage"));
+        snapshot.add(
+            EntryKind::SessionFile,
+            "workspaces/abc/chatSessions/s1.json".to_string(),
+            b"{}".to_vec(),
+        );
+        snapshot.add(
+            EntryKind::WorkspaceMetadata,
+            "workspaces/abc/workspace.json".to_string(),
+            b"{}".to_vec(),
+        );
+        let mut set = RestoreSet::default();
+        load_archive(&mut set, &snapshot, db_path).unwrap();
+        assert_eq!(set.files.len(), 2);
+        assert_eq!(set.files[0].workspace, "abc");
+        assert_eq!(set.files[0].session_id.as_deref(), Some("s1"));
+        assert!(set.files[0].target.ends_with("abc/chatSessions/s1.json"));
+        assert_eq!(set.files[1].session_id, None);
+    }
+
+    #[test]
+    fn test_plan_for_each_scope() {
+        let set = RestoreSet {
+            database: None,
+            files: vec![
+                file("abc", Some("s1")),
+                file("abc", None),
+                file("def", Some("s3")),
+            ],
+            sessions: vec![
+                session("s1", "abc", true),
+                session("s2", "abc", true),
+                session("s3", "def", false),
+            ],
+        };
+
+        let plan = plan_for(&set, Scope::Database, "");
+        assert!(plan.replace_database);
+        assert!(plan.db_sessions.is_empty() && plan.files.is_empty());
+
+        let plan = plan_for(&set, Scope::Workspace, "abc");
+        assert!(!plan.replace_database);
+        assert_eq!(plan.db_sessions, ["s1", "s2"]);
+        assert_eq!(plan.files.len(), 2);
+        assert!(plan.files.iter().all(|f| f.workspace == "abc"));
+
+        let plan = plan_for(&set, Scope::Session, "s1");
+        assert_eq!(plan.db_sessions, ["s1"]);
+        assert_eq!(plan.files.len(), 1);
+
+        // A session only in a file is not copied from the database
+        let plan = plan_for(&set, Scope::Session, "s3");
+        assert!(plan.db_sessions.is_empty());
+        assert_eq!(plan.files[0].session_id.as_deref(), Some("s3"));
+        assert!(!plan.touches_database());
+    }
+
+    #[test]
+    fn test_workspace_key() {
+        assert_eq!(
+            workspace_key(Path::new("/storage/abc123/chatSessions")),
+            "abc123"
+        );
+        assert_eq!(
+            workspace_key(Path::new("/home/me/project/.vscode/chat-sessions")),
+            "project"
+        );
+        assert_eq!(workspace_key(Path::new("/backups/abc123")), "abc123");
+        assert_eq!(workspace_key(Path::new("")), "");
+    }
+}
@@ -0,0 +1,275 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Routing commands
+//!
+//! `csm routing status` asks a running API server what its gateway thinks of
+//! each provider: whether probes find it up, slow, or down, how fast it
+//! answers, the score the router gives it, and whether fallbacks skip it.
+//! `csm routing policy` shows the workspace policy covering a directory, and
+//! `csm routing audit` the providers policies allowed or refused.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+use serde::Deserialize;
+use tabled::{settings::Style as TableStyle, Table, Tabled};
+
+use super::open_harvest_db;
+use crate::datetime::{format_datetime_short, from_epoch};
+use crate::routing::{load_decisions, DecisionFilter, Policies};
+
+/// One provider as reported by `GET /api/system/routing`
+#[derive(Debug, Deserialize)]
+struct ProviderStatus {
+    provider: String,
+    state: String,
+    latency_ms: Option<f64>,
+    probes: u64,
+    failures: u64,
+    last_error: Option<String>,
+    score: f64,
+    configured: bool,
+    models: usize,
+    circuit: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RoutingStatus {
+    strategy: String,
+    min_quality: f64,
+    probe_interval_secs: Option<u64>,
+    fallback: Vec<String>,
+    max_attempts: usize,
+    providers: Vec<ProviderStatus>,
+}
+
+#[derive(Tabled)]
+struct ProviderRow {
+    #[tabled(rename = "Provider")]
+    provider: String,
+    #[tabled(rename = "State")]
+    state: String,
+    #[tabled(rename = "Latency")]
+    latency: String,
+    #[tabled(rename = "Score")]
+    score: String,
+    #[tabled(rename = "Failures")]
+    failures: String,
+    #[tabled(rename = "Circuit")]
+    circuit: String,
+    #[tabled(rename = "Models")]
+    models: usize,
+    #[tabled(rename = "Last error")]
+    last_error: String,
+}
+
+/// Show the gateway's routing policy and provider health on `server`
+pub fn routing_status(server: &str, json: bool) -> Result<()> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()?;
+    let mut request = client.get(format!(
+        "{}/api/system/routing",
+        server.trim_end_matches('/')
+    ));
+    // Servers that deny anonymous access need an API key
+    if let Ok(key) = std::env::var("CSM_API_KEY") {
+        request = request.header("X-API-Key", key);
+    }
+    let response = request
+        .send()
+        .with_context(|| format!("Failed to connect to {}", server))?;
+    if !response.status().is_success() {
+        anyhow::bail!("Server returned error: {}", response.status());
+    }
+    let body: serde_json::Value = response.json()?;
+    let data = body.get("data").cloned().unwrap_or_default();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&data)?);
+        return Ok(());
+    }
+    let status: RoutingStatus =
+        serde_json::from_value(data).context("Unexpected answer from the server")?;
+
+    println!("[*] Gateway routing on {}", server);
+    println!("{}", "=".repeat(60));
+    println!(
+        "   Strategy: {}, min quality {:.2}",
+        status.strategy, status.min_quality
+    );
+    match status.probe_interval_secs {
+        Some(secs) => println!("   Probing providers every {}s", secs),
+        None => println!("   [!] Probing is off; only failed requests mark providers down"),
+    }
+    if !status.fallback.is_empty() {
+        println!(
+            "   Fallback: {} (at most {} providers per request)",
+            status.fallback.join(" -> "),
+            status.max_attempts
+        );
+    }
+    println!();
+
+    let rows: Vec<ProviderRow> = status
+        .providers
+        .iter()
+        .map(|p| ProviderRow {
+            provider: p.provider.clone(),
+            state: if p.configured {
+                p.state.clone()
+            } else {
+                "not configured".to_string()
+            },
+            latency: p
+                .latency_ms
+                .map(|ms| format!("{:.0} ms", ms))
+                .unwrap_or_else(|| "-".to_string()),
+            score: if p.configured {
+                format!("{:.2}", p.score)
+            } else {
+                "-".to_string()
+            },
+            failures: format!("{}/{}", p.failures, p.probes),
+            circuit: p.circuit.replace('_', "-"),
+            models: p.models,
+            last_error: p.last_error.clone().unwrap_or_default(),
+        })
+        .collect();
+    println!("{}", Table::new(&rows).with(TableStyle::ascii_rounded()));
+
+    let down: Vec<&str> = status
+        .providers
+        .iter()
+        .filter(|p| p.configured && p.state == "down")
+        .map(|p| p.provider.as_str())
+        .collect();
+    if !down.is_empty() {
+        println!();
+        println!(
+            "[!] Not routing to {} until probes succeed again",
+            down.join(", ")
+        );
+    }
+    let open: Vec<&str> = status
+        .providers
+        .iter()
+        .filter(|p| p.circuit == "open")
+        .map(|p| p.provider.as_str())
+        .collect();
+    if !open.is_empty() {
+        println!(
+            "[!] Fallbacks skip {} until its cooldown ends",
+            open.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Show which policy covers `workspace`, or the current directory, and the
+/// providers it allows
+pub fn routing_policy(workspace: Option<&str>) -> Result<()> {
+    let workspace = match workspace {
+        Some(workspace) => workspace.to_string(),
+        None => std::env::current_dir()?.to_string_lossy().to_string(),
+    };
+    let policies = Policies::current()?;
+    println!("Workspace Policy");
+    println!("{}", "=".repeat(60));
+    println!("Workspace: {}", workspace);
+    let Some(policy) = policies.resolve(&workspace) else {
+        println!();
+        println!("[*] No policy covers it; every provider may be used");
+        if policies.is_empty() {
+            println!("    Add one under [policies.<name>] in the config file");
+        }
+        return Ok(());
+    };
+    println!("Policy: {}", policy.name);
+    for path in &policy.paths {
+        println!("  Path: {}", path.display());
+    }
+    if !policy.workspaces.is_empty() {
+        println!("  Workspaces: {}", policy.workspaces.join(", "));
+    }
+    if policy.local_only {
+        println!("  Local providers only");
+    }
+    if let Some(allowed) = &policy.allowed_providers {
+        println!("  Allowed: {}", allowed.join(", "));
+    }
+    if !policy.blocked_providers.is_empty() {
+        println!("  Blocked: {}", policy.blocked_providers.join(", "));
+    }
+    Ok(())
+}
+
+#[derive(Tabled)]
+struct DecisionRow {
+    #[tabled(rename = "Time")]
+    time: String,
+    #[tabled(rename = "Source")]
+    source: String,
+    #[tabled(rename = "Policy")]
+    policy: String,
+    #[tabled(rename = "Workspace")]
+    workspace: String,
+    #[tabled(rename = "Provider")]
+    provider: String,
+    #[tabled(rename = "Decision")]
+    decision: String,
+    #[tabled(rename = "Reason")]
+    reason: String,
+}
+
+/// List recorded policy decisions, newest first
+pub fn routing_audit(
+    db_path: Option<&str>,
+    workspace: Option<&str>,
+    denied: bool,
+    limit: usize,
+    json: bool,
+) -> Result<()> {
+    let conn = open_harvest_db(db_path)?;
+    let decisions = load_decisions(
+        &conn,
+        &DecisionFilter {
+            workspace,
+            denied_only: denied,
+            limit: Some(limit),
+        },
+    )?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&decisions)?);
+        return Ok(());
+    }
+    println!("Policy Decisions");
+    println!("{}", "=".repeat(60));
+    if decisions.is_empty() {
+        println!();
+        println!("[!] No decisions recorded; they are made where [policies] cover a workspace");
+        return Ok(());
+    }
+    let rows: Vec<DecisionRow> = decisions
+        .iter()
+        .map(|d| DecisionRow {
+            time: from_epoch(d.decided_at)
+                .map(format_datetime_short)
+                .unwrap_or_default(),
+            source: d.source.clone(),
+            policy: d.policy.clone(),
+            workspace: d.workspace.clone(),
+            provider: match &d.model {
+                Some(model) => format!("{}/{}", d.provider, model),
+                None => d.provider.clone(),
+            },
+            decision: if d.allowed { "allowed" } else { "denied" }.to_string(),
+            reason: d.reason.clone().unwrap_or_default(),
+        })
+        .collect();
+    println!("{}", Table::new(&rows).with(TableStyle::ascii_rounded()));
+    let refused = decisions.iter().filter(|d| !d.allowed).count();
+    println!();
+    println!("[*] {} decisions, {} refused", decisions.len(), refused);
+    Ok(())
+}
@@ -4,6 +4,8 @@
 //!
 //! Launches AI provider CLIs/APIs with a recording wrapper that captures
 //! all messages to Chasm's universal database, preventing data loss.
+//! Providers the workspace's `[policies]` do not allow are refused before
+//! anything starts.
 
 use anyhow::{Context, Result};
 use chrono::Utc;
@@ -13,15 +15,17 @@ use std::path::PathBuf;
 use std::process::Command;
 use uuid::Uuid;
 
+use crate::config::{self, StorageRoot};
 use crate::database::{ChatDatabase, Message, Session};
+use crate::routing::PolicyGuard;
 
 /// Get default database path for session persistence
 fn get_db_path() -> Result<PathBuf> {
     if let Ok(p) = std::env::var("CSM_DB_PATH") {
         return Ok(PathBuf::from(p));
     }
-    if let Ok(p) = std::env::var("CSM_HARVEST_DB") {
-        return Ok(PathBuf::from(p));
+    if let Some(p) = config::storage_root(StorageRoot::HarvestDb) {
+        return Ok(p);
     }
     let data_dir = dirs::data_dir()
         .unwrap_or_else(|| PathBuf::from("."))
@@ -30,6 +34,21 @@ fn get_db_path() -> Result<PathBuf> {
     Ok(data_dir.join("chat_sessions.db"))
 }
 
+/// Refuse to start when the policy covering the workspace (or the current
+/// directory) does not allow `provider`
+fn check_policy(provider: &str, model: Option<&str>, workspace: Option<&str>) -> Result<()> {
+    let guard = match workspace {
+        Some(workspace) => PolicyGuard::for_workspace("run", workspace)?,
+        None => PolicyGuard::here("run")?,
+    };
+    if let Some(guard) = guard {
+        if let Err(reason) = guard.with_audit_db(get_db_path().ok()).check(provider, model) {
+            anyhow::bail!("{} is not allowed in this workspace: {}", provider, reason);
+        }
+    }
+    Ok(())
+}
+
 /// Session recorder that wraps provider interactions and persists to the database
 struct SessionRecorder {
     session_id: String,
@@ -145,6 +164,7 @@ fn print_banner(provider: &str, model: Option<&str>, workspace: Option<&str>) {
 
 /// Run Ollama with automatic session recording
 pub fn run_ollama(model: &str, endpoint: Option<&str>, workspace: Option<&str>) -> Result<()> {
+    check_policy("ollama", Some(model), workspace)?;
     let endpoint = endpoint.unwrap_or("http://localhost:11434");
     print_banner("Ollama", Some(model), workspace);
 
@@ -223,6 +243,7 @@ pub fn run_ollama(model: &str, endpoint: Option<&str>, workspace: Option<&str>)
 
 /// Run Claude Code CLI with automatic session recording
 pub fn run_claude_code(workspace: Option<&str>) -> Result<()> {
+    check_policy("anthropic", None, workspace)?;
     print_banner("Claude Code", None, workspace);
 
     let mut cmd = Command::new("claude");
@@ -281,6 +302,7 @@ pub fn run_opencode(workspace: Option<&str>) -> Result<()> {
 
 /// Run Claude (Anthropic API) with automatic session recording
 pub fn run_claude(model: &str, workspace: Option<&str>) -> Result<()> {
+    check_policy("anthropic", Some(model), workspace)?;
     print_banner("Claude", Some(model), workspace);
 
     let api_key = std::env::var("ANTHROPIC_API_KEY")
@@ -354,6 +376,7 @@ pub fn run_claude(model: &str, workspace: Option<&str>) -> Result<()> {
 
 /// Run ChatGPT (OpenAI API) with automatic session recording
 pub fn run_chatgpt(model: &str, workspace: Option<&str>) -> Result<()> {
+    check_policy("openai", Some(model), workspace)?;
     print_banner("ChatGPT", Some(model), workspace);
 
     let api_key =
@@ -0,0 +1,234 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Slack commands
+
+use anyhow::{anyhow, bail, Context, Result};
+use colored::*;
+use rusqlite::Connection;
+use tabled::{settings::Style as TableStyle, Table, Tabled};
+
+use super::agency::find_harvested;
+use super::harvest::{insert_or_update_session, open_harvest_db};
+use super::summary::SessionSummary;
+use crate::config::Config;
+use crate::integrations::communication::{parse_thread, SlackBot, SlackProvider};
+use crate::text::truncate;
+
+/// Provider name of sessions imported from Slack
+const PROVIDER: &str = "slack";
+
+#[derive(Tabled)]
+struct ThreadRow {
+    #[tabled(rename = "Thread")]
+    thread: String,
+    #[tabled(rename = "Replies")]
+    replies: u64,
+    #[tabled(rename = "Started")]
+    started: String,
+    #[tabled(rename = "Message")]
+    text: String,
+}
+
+fn bot() -> Result<SlackBot> {
+    SlackBot::from_config().with_context(|| {
+        format!(
+            "Set bot_token under [integrations.slack] in {} to a Slack bot token (xoxb-...)",
+            Config::path()
+                .map(|path| path.display().to_string())
+                .unwrap_or_else(|_| "config.toml".to_string())
+        )
+    })
+}
+
+fn runtime() -> Result<tokio::runtime::Runtime> {
+    Ok(tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?)
+}
+
+/// List the recent threads of a channel, to pick some to import
+pub fn slack_threads(channel: &str, limit: u32, json: bool) -> Result<()> {
+    let bot = bot()?;
+    let rt = runtime()?;
+    let channel_id = rt
+        .block_on(bot.channel_id(channel))
+        .map_err(|e| anyhow!(e))?;
+    let result = rt.block_on(bot.list_messages(&channel_id, limit));
+    if !result.success {
+        bail!(
+            "Could not read {}: {}",
+            channel,
+            result.error.unwrap_or_default()
+        );
+    }
+    let threads: Vec<serde_json::Value> = result
+        .data
+        .as_ref()
+        .and_then(|data| data["messages"].as_array())
+        .into_iter()
+        .flatten()
+        .filter(|message| message["reply_count"].as_u64().unwrap_or(0) > 0)
+        .cloned()
+        .collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&threads)?);
+        return Ok(());
+    }
+    println!("\n{} Threads in {}", "[*]".blue().bold(), channel);
+    println!("{}", "=".repeat(60));
+    if threads.is_empty() {
+        println!(
+            "\n{} No threads in the last {} messages",
+            "[!]".yellow(),
+            limit
+        );
+        return Ok(());
+    }
+    let rows: Vec<ThreadRow> = threads
+        .iter()
+        .map(|thread| {
+            let ts = thread["ts"].as_str().unwrap_or_default();
+            ThreadRow {
+                thread: format!("{}:{}", channel_id, ts),
+                replies: thread["reply_count"].as_u64().unwrap_or(0),
+                started: ts
+                    .split('.')
+                    .next()
+                    .and_then(|secs| secs.parse().ok())
+                    .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+                    .map(crate::datetime::format_datetime_short)
+                    .unwrap_or_default(),
+                text: truncate(
+                    &thread["text"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    60,
+                ),
+            }
+        })
+        .collect();
+    println!("{}", Table::new(rows).with(TableStyle::ascii_rounded()));
+    println!(
+        "{}",
+        "[i] Import some with 'csm slack import <thread>...'".dimmed()
+    );
+    Ok(())
+}
+
+/// Import Slack threads, given as message links or `CHANNEL:TS`, as
+/// harvested sessions
+pub fn slack_import(db_path: Option<&str>, threads: &[String]) -> Result<()> {
+    let conn = open_harvest_db(db_path)?;
+    let targets = threads
+        .iter()
+        .map(|thread| {
+            parse_thread(thread).with_context(|| {
+                format!(
+                    "Not a Slack thread: {}; give a message link or CHANNEL:TS",
+                    thread
+                )
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let bot = bot()?;
+    let rt = runtime()?;
+    for (channel, ts) in targets {
+        let (session, channel_name) = rt
+            .block_on(bot.thread_session(&channel, &ts))
+            .map_err(|e| anyhow!("Could not read thread {} in {}: {}", ts, channel, e))?;
+        let session_id = session.session_id.clone().unwrap_or_default();
+        let updated =
+            insert_or_update_session(&conn, &session, PROVIDER, None, Some(&channel_name))?;
+        println!(
+            "{} {} {} as session {} ({} requests)",
+            "[+]".green(),
+            if updated { "Updated" } else { "Imported" },
+            session.title(),
+            session_id,
+            session.requests.len()
+        );
+    }
+    Ok(())
+}
+
+/// Post a summary of a harvested session to a Slack channel, or as a reply
+/// in a thread
+pub fn slack_post(
+    db_path: Option<&str>,
+    session: &str,
+    channel: Option<&str>,
+    thread: Option<&str>,
+    dry_run: bool,
+) -> Result<()> {
+    let conn = open_harvest_db(db_path)?;
+    let (session_id, provider, chat) = find_harvested(&conn, session)?;
+    let (channel, thread_ts) = match thread {
+        Some(thread) => {
+            let (channel, ts) = parse_thread(thread).with_context(|| {
+                format!(
+                    "Not a Slack thread: {}; give a message link or CHANNEL:TS",
+                    thread
+                )
+            })?;
+            (channel, Some(ts))
+        }
+        None => {
+            let channel = channel
+                .map(String::from)
+                .or_else(|| crate::config::current().integrations.slack.channel.clone())
+                .context(
+                    "Name a channel with --channel or set channel under [integrations.slack]",
+                )?;
+            (channel, None)
+        }
+    };
+    let text = SessionSummary::new(&session_id, &provider, &chat).to_slack();
+    if dry_run {
+        println!("{}", text);
+        println!(
+            "\n{} Dry run: nothing was posted to {}",
+            "[i]".blue(),
+            channel
+        );
+        return Ok(());
+    }
+
+    let bot = bot()?;
+    let rt = runtime()?;
+    let channel_id = rt
+        .block_on(bot.channel_id(&channel))
+        .map_err(|e| anyhow!(e))?;
+    let result = rt.block_on(bot.send_message(&channel_id, &text, thread_ts.as_deref()));
+    if !result.success {
+        bail!(
+            "Could not post to {}: {}",
+            channel,
+            result.error.unwrap_or_default()
+        );
+    }
+    let ts = result
+        .data
+        .as_ref()
+        .and_then(|posted| posted["ts"].as_str())
+        .unwrap_or_default()
+        .to_string();
+    let link = rt.block_on(bot.permalink(&channel_id, &ts));
+    let url = link
+        .data
+        .as_ref()
+        .and_then(|link| link["permalink"].as_str())
+        .unwrap_or_default()
+        .to_string();
+    println!(
+        "{} Posted a summary of {} to {} {}",
+        "[+]".green(),
+        session_id,
+        channel,
+        url.dimmed()
+    );
+    Ok(())
+}
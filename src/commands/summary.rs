@@ -0,0 +1,199 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Short summaries of sessions, for posting on GitHub or Slack and filing as
+//! issues in Jira or Linear
+
+use crate::datetime::{format_date, from_epoch};
+use crate::intelligence::{ActionExtractor, ActionKind};
+use crate::models::ChatSession;
+use crate::providers::GenericSession;
+use crate::text::truncate;
+
+/// Requests quoted in a summary
+const QUOTED_REQUESTS: usize = 10;
+
+/// What was asked in a session, and the decisions and TODOs found in it
+pub(crate) struct SessionSummary {
+    pub session_id: String,
+    pub title: String,
+    /// One sentence on how many requests, where and when, without its full stop
+    pub about: String,
+    /// The first requests, each on one line
+    pub asked: Vec<String>,
+    /// Requests not quoted
+    pub more: usize,
+    pub decisions: Vec<String>,
+    pub todos: Vec<String>,
+    /// Where to open the session, linked from its ID
+    pub link: Option<String>,
+}
+
+impl SessionSummary {
+    pub fn new(session_id: &str, provider: &str, chat: &ChatSession) -> Self {
+        let requests = chat.user_messages();
+        let dates = match (
+            from_epoch(chat.creation_date),
+            from_epoch(chat.last_message_date),
+        ) {
+            (Some(start), Some(end)) if format_date(start) != format_date(end) => {
+                format!(" from {} to {}", format_date(start), format_date(end))
+            }
+            (Some(start), _) => format!(" on {}", format_date(start)),
+            _ => String::new(),
+        };
+        let about = format!(
+            "{} request{} in {}{}",
+            requests.len(),
+            if requests.len() == 1 { "" } else { "s" },
+            provider,
+            dates
+        );
+        let asked = requests
+            .iter()
+            .take(QUOTED_REQUESTS)
+            .map(|request| {
+                let line = request.split_whitespace().collect::<Vec<_>>().join(" ");
+                truncate(&line, 100)
+            })
+            .collect();
+
+        let generic = GenericSession::from(chat.clone());
+        let items = ActionExtractor::new().extract(
+            generic
+                .messages
+                .iter()
+                .map(|message| (message.role.as_str(), message.content.as_str())),
+        );
+        let of_kind = |kind| {
+            items
+                .iter()
+                .filter(|item| item.kind == kind)
+                .map(|item| item.text.clone())
+                .collect()
+        };
+        Self {
+            session_id: session_id.to_string(),
+            title: chat.title(),
+            about,
+            asked,
+            more: requests.len().saturating_sub(QUOTED_REQUESTS),
+            decisions: of_kind(ActionKind::Decision),
+            todos: of_kind(ActionKind::Todo),
+            link: None,
+        }
+    }
+
+    /// GitHub-flavored Markdown
+    pub fn to_markdown(&self) -> String {
+        let mut body = format!("### Chat session: {}\n\n{}.\n", self.title, self.about);
+        if !self.asked.is_empty() {
+            body.push_str("\n**Asked**\n\n");
+            for (i, request) in self.asked.iter().enumerate() {
+                body.push_str(&format!("{}. {}\n", i + 1, request));
+            }
+            if self.more > 0 {
+                body.push_str(&format!("\n...and {} more\n", self.more));
+            }
+        }
+        for (heading, marker, items) in [
+            ("Decisions", "-", &self.decisions),
+            ("To do", "- [ ]", &self.todos),
+        ] {
+            if !items.is_empty() {
+                body.push_str(&format!("\n**{}**\n\n", heading));
+                for item in items {
+                    body.push_str(&format!("{} {}\n", marker, item));
+                }
+            }
+        }
+        let session = match &self.link {
+            Some(link) => format!("[`{}`]({})", self.session_id, link),
+            None => format!("`{}`", self.session_id),
+        };
+        body.push_str(&format!(
+            "\n<sub>Posted by csm from session {}</sub>\n",
+            session
+        ));
+        body
+    }
+
+    /// Slack's mrkdwn, with `&`, `<` and `>` escaped
+    pub fn to_slack(&self) -> String {
+        let mut body = format!(
+            "*Chat session: {}*\n{}.\n",
+            slack_escape(&self.title),
+            slack_escape(&self.about)
+        );
+        if !self.asked.is_empty() {
+            body.push_str("\n*Asked*\n");
+            for (i, request) in self.asked.iter().enumerate() {
+                body.push_str(&format!("{}. {}\n", i + 1, slack_escape(request)));
+            }
+            if self.more > 0 {
+                body.push_str(&format!("...and {} more\n", self.more));
+            }
+        }
+        for (heading, items) in [("Decisions", &self.decisions), ("To do", &self.todos)] {
+            if !items.is_empty() {
+                body.push_str(&format!("\n*{}*\n", heading));
+                for item in items {
+                    body.push_str(&format!("• {}\n", slack_escape(item)));
+                }
+            }
+        }
+        let session = match &self.link {
+            Some(link) => format!("<{}|{}>", link, self.session_id),
+            None => format!("`{}`", self.session_id),
+        };
+        body.push_str(&format!("\n_Posted by csm from session {}_", session));
+        body
+    }
+
+    /// Jira's wiki markup
+    pub fn to_jira(&self) -> String {
+        let mut body = format!(
+            "h3. Chat session: {}\n\n{}.\n",
+            jira_escape(&self.title),
+            jira_escape(&self.about)
+        );
+        if !self.asked.is_empty() {
+            body.push_str("\n*Asked*\n");
+            for request in &self.asked {
+                body.push_str(&format!("# {}\n", jira_escape(request)));
+            }
+            if self.more > 0 {
+                body.push_str(&format!("...and {} more\n", self.more));
+            }
+        }
+        for (heading, items) in [("Decisions", &self.decisions), ("To do", &self.todos)] {
+            if !items.is_empty() {
+                body.push_str(&format!("\n*{}*\n", heading));
+                for item in items {
+                    body.push_str(&format!("* {}\n", jira_escape(item)));
+                }
+            }
+        }
+        let session = match &self.link {
+            Some(link) => format!("[{}|{}]", self.session_id, link),
+            None => format!("{{{{{}}}}}", self.session_id),
+        };
+        body.push_str(&format!("\n_Filed by csm from session {}_", session));
+        body
+    }
+}
+
+/// Text without the brackets and braces Jira reads as links and macros
+pub(crate) fn jira_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace('{', "\\{")
+        .replace('}', "\\}")
+}
+
+/// Text as Slack shows it literally
+fn slack_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -7,6 +7,7 @@ use colored::*;
 use std::collections::HashMap;
 use std::io::{self, Write};
 
+use crate::datetime::{format_datetime, DateFilter};
 use crate::telemetry::{TelemetryConfig, TelemetryStore, TELEMETRY_INFO};
 
 /// Enable telemetry (opt-in)
@@ -85,7 +86,7 @@ pub fn telemetry_info() -> Result<()> {
     // Show preference change timestamp if available
     if let Some(changed_at) = config.preference_changed_at {
         let dt = chrono::DateTime::from_timestamp(changed_at, 0)
-            .map(|d| d.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .map(format_datetime)
             .unwrap_or_else(|| "Unknown".to_string());
         println!("Preference last changed: {}", dt.dimmed());
     }
@@ -190,9 +191,8 @@ pub fn telemetry_show(
 ) -> Result<()> {
     let store = TelemetryStore::new()?;
 
-    // Parse date filters
-    let after_ts = after.and_then(|d| parse_date_to_timestamp(d));
-    let before_ts = before.and_then(|d| parse_date_to_timestamp(d));
+    // Parse date filters in the display timezone
+    let (after_ts, before_ts) = DateFilter::parse(after, before)?.as_unix_secs();
 
     let records = store.read_records(category, event, tag, after_ts, before_ts, Some(limit))?;
 
@@ -221,7 +221,9 @@ pub fn telemetry_show(
             println!();
 
             for record in &records {
-                let time_short = &record.timestamp_iso[..19];
+                let time_short = chrono::DateTime::from_timestamp(record.timestamp, 0)
+                    .map(format_datetime)
+                    .unwrap_or_else(|| record.timestamp_iso.clone());
                 let tags_str = if record.tags.is_empty() {
                     String::new()
                 } else {
@@ -239,11 +241,7 @@ pub fn telemetry_show(
                 if !record.data.is_empty() {
                     let data_str = serde_json::to_string(&record.data).unwrap_or_default();
                     // Truncate long data
-                    let display = if data_str.len() > 80 {
-                        format!("{}...", &data_str[..77])
-                    } else {
-                        data_str
-                    };
+                    let display = crate::text::truncate(&data_str, 80);
                     println!("           {}", display.dimmed());
                 }
             }
@@ -556,10 +554,3 @@ pub fn telemetry_test() -> Result<()> {
 
     Ok(())
 }
-
-/// Parse a date string (YYYY-MM-DD) to a Unix timestamp
-fn parse_date_to_timestamp(date_str: &str) -> Option<i64> {
-    chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
-        .ok()
-        .map(|d| d.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp())
-}
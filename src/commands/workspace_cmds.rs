@@ -5,8 +5,10 @@
 use anyhow::Result;
 use tabled::{settings::Style as TableStyle, Table, Tabled};
 
+use crate::datetime::{format_datetime_short, local_date, DateFilter};
 use crate::models::Workspace;
 use crate::storage::{read_empty_window_sessions, VsCodeSessionFormat};
+use crate::text::{prefix_chars, truncate};
 use crate::workspace::discover_workspaces;
 
 #[derive(Tabled)]
@@ -91,7 +93,7 @@ pub fn list_workspaces() -> Result<()> {
 }
 
 /// Format file size in human-readable format
-fn format_file_size(bytes: u64) -> String {
+pub(crate) fn format_file_size(bytes: u64) -> String {
     if bytes < 1024 {
         format!("{} B", bytes)
     } else if bytes < 1024 * 1024 {
@@ -138,7 +140,7 @@ pub fn list_sessions(project_path: Option<&str>, show_size: bool, provider: Opti
                 for session in empty_sessions {
                     let modified =
                         chrono::DateTime::from_timestamp_millis(session.last_message_date)
-                            .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                            .map(format_datetime_short)
                             .unwrap_or_else(|| "unknown".to_string());
 
                     let session_id = session.session_id.as_deref().unwrap_or("unknown");
@@ -169,7 +171,7 @@ pub fn list_sessions(project_path: Option<&str>, show_size: bool, provider: Opti
                     .and_then(|m| m.modified().ok())
                     .map(|t| {
                         let datetime: chrono::DateTime<chrono::Utc> = t.into();
-                        datetime.format("%Y-%m-%d %H:%M").to_string()
+                        format_datetime_short(datetime)
                     })
                     .unwrap_or_else(|| "unknown".to_string());
 
@@ -213,7 +215,7 @@ pub fn list_sessions(project_path: Option<&str>, show_size: bool, provider: Opti
                 for session in empty_sessions {
                     let modified =
                         chrono::DateTime::from_timestamp_millis(session.last_message_date)
-                            .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                            .map(format_datetime_short)
                             .unwrap_or_else(|| "unknown".to_string());
 
                     let session_id = session.session_id.as_deref().unwrap_or("unknown");
@@ -242,7 +244,7 @@ pub fn list_sessions(project_path: Option<&str>, show_size: bool, provider: Opti
                     .and_then(|m| m.modified().ok())
                     .map(|t| {
                         let datetime: chrono::DateTime<chrono::Utc> = t.into();
-                        datetime.format("%Y-%m-%d %H:%M").to_string()
+                        format_datetime_short(datetime)
                     })
                     .unwrap_or_else(|| "unknown".to_string());
 
@@ -397,7 +399,7 @@ fn list_sessions_multi_provider(
                     .and_then(|m| m.modified().ok())
                     .map(|t| {
                         let datetime: chrono::DateTime<chrono::Utc> = t.into();
-                        datetime.format("%Y-%m-%d %H:%M").to_string()
+                        format_datetime_short(datetime)
                     })
                     .unwrap_or_else(|| "unknown".to_string());
 
@@ -415,8 +417,8 @@ fn list_sessions_multi_provider(
                 if show_size {
                     rows_with_size.push(SessionRowMultiWithSize {
                         provider: provider_name.clone(),
-                        project_path: truncate_string(&project_display, 30),
-                        session_file: truncate_string(&session_file, 20),
+                        project_path: truncate(&project_display, 30),
+                        session_file: truncate(&session_file, 20),
                         last_modified: modified,
                         messages,
                         size: format_file_size(file_size),
@@ -424,8 +426,8 @@ fn list_sessions_multi_provider(
                 } else {
                     rows.push(SessionRowMulti {
                         provider: provider_name.clone(),
-                        project_path: truncate_string(&project_display, 30),
-                        session_file: truncate_string(&session_file, 20),
+                        project_path: truncate(&project_display, 30),
+                        session_file: truncate(&session_file, 20),
                         last_modified: modified,
                         messages,
                     });
@@ -603,7 +605,7 @@ pub fn find_sessions(pattern: &str, project_path: Option<&str>) -> Result<()> {
                 .and_then(|m| m.modified().ok())
                 .map(|t| {
                     let datetime: chrono::DateTime<chrono::Utc> = t.into();
-                    datetime.format("%Y-%m-%d %H:%M").to_string()
+                    format_datetime_short(datetime)
                 })
                 .unwrap_or_else(|| "unknown".to_string());
 
@@ -646,6 +648,7 @@ pub fn find_sessions(pattern: &str, project_path: Option<&str>) -> Result<()> {
 /// 3. Only parsing JSON when needed
 /// 4. Content search is opt-in (expensive)
 /// 5. Parallel file scanning with rayon
+#[allow(clippy::too_many_arguments)]
 pub fn find_sessions_filtered(
     pattern: &str,
     workspace_filter: Option<&str>,
@@ -655,20 +658,19 @@ pub fn find_sessions_filtered(
     before: Option<&str>,
     date: Option<&str>,
     all_workspaces: bool,
+    project: Option<&str>,
     provider: Option<&str>,
     all_providers: bool,
     limit: usize,
 ) -> Result<()> {
-    use chrono::{NaiveDate, Utc};
     use rayon::prelude::*;
     use std::sync::atomic::{AtomicUsize, Ordering};
 
     let pattern_lower = pattern.to_lowercase();
 
-    // Parse date filters upfront
-    let after_date = after.and_then(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok());
-    let before_date = before.and_then(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok());
-    let target_date = date.and_then(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok());
+    // Parse date filters upfront, in the display timezone
+    let date_filter = DateFilter::parse(after, before)?;
+    let target_day = date.map(DateFilter::on_day).transpose()?;
 
     // Determine which storage paths to scan based on provider filter
     let storage_paths = if all_providers {
@@ -695,12 +697,13 @@ pub fn find_sessions_filtered(
     }
 
     // Collect workspace directories with minimal I/O
-    // If --all flag is set, don't filter by workspace
-    let ws_filter_lower = if all_workspaces {
+    // If --all or --project is set, don't filter by workspace name
+    let ws_filter_lower = if all_workspaces || project.is_some() {
         None
     } else {
         workspace_filter.map(|s| s.to_lowercase())
     };
+    let project_hashes = project.map(crate::projects::workspace_hashes).transpose()?;
 
     let workspace_dirs: Vec<_> = storage_paths
         .iter()
@@ -736,7 +739,12 @@ pub fn find_sessions_filtered(
                                     })
                             });
 
-                    // Apply workspace filter early
+                    // Apply project and workspace filters early
+                    if let Some(ref hashes) = project_hashes {
+                        if !hashes.contains(entry.file_name().to_string_lossy().as_ref()) {
+                            return None;
+                        }
+                    }
                     if let Some(ref filter) = ws_filter_lower {
                         let hash = entry.file_name().to_string_lossy().to_lowercase();
                         let path_matches = project_path
@@ -753,8 +761,7 @@ pub fn find_sessions_filtered(
                         .and_then(|p| std::path::Path::new(p).file_name())
                         .map(|n| n.to_string_lossy().to_string())
                         .unwrap_or_else(|| {
-                            entry.file_name().to_string_lossy()[..8.min(entry.file_name().len())]
-                                .to_string()
+                            prefix_chars(&entry.file_name().to_string_lossy(), 8).to_string()
                         });
 
                     Some((chat_sessions_dir, ws_name, provider_name.clone()))
@@ -764,7 +771,9 @@ pub fn find_sessions_filtered(
         .collect();
 
     if workspace_dirs.is_empty() {
-        if let Some(ws) = workspace_filter {
+        if let Some(name) = project {
+            println!("No workspaces with chat sessions in project '{}'", name);
+        } else if let Some(ws) = workspace_filter {
             println!("No workspaces found matching '{}'", ws);
         } else {
             println!("No workspaces with chat sessions found");
@@ -800,23 +809,12 @@ pub fn find_sessions_filtered(
         .par_iter()
         .filter_map(|(path, ws_name, provider_name)| {
             // Date filter using file metadata (very fast)
-            if after_date.is_some() || before_date.is_some() {
+            if !date_filter.is_empty() {
                 if let Ok(metadata) = path.metadata() {
                     if let Ok(modified) = metadata.modified() {
-                        let file_date: chrono::DateTime<Utc> = modified.into();
-                        let file_naive = file_date.date_naive();
-
-                        if let Some(after) = after_date {
-                            if file_naive < after {
-                                skipped_by_date.fetch_add(1, Ordering::Relaxed);
-                                return None;
-                            }
-                        }
-                        if let Some(before) = before_date {
-                            if file_naive > before {
-                                skipped_by_date.fetch_add(1, Ordering::Relaxed);
-                                return None;
-                            }
+                        if !date_filter.contains(modified.into()) {
+                            skipped_by_date.fetch_add(1, Ordering::Relaxed);
+                            return None;
                         }
                     }
                 }
@@ -831,7 +829,7 @@ pub fn find_sessions_filtered(
             };
 
             // Check for internal message timestamps if --date filter is used
-            if let Some(target) = target_date {
+            if let Some(target) = target_day {
                 // Look for timestamp fields in the JSON content
                 // Timestamps are in milliseconds since epoch
                 let has_matching_timestamp = content
@@ -846,7 +844,7 @@ pub fn find_sessions_filtered(
                             .collect();
                         if let Ok(ts_ms) = num_str.parse::<i64>() {
                             if let Some(dt) = chrono::DateTime::from_timestamp_millis(ts_ms) {
-                                return dt.date_naive() == target;
+                                return target.contains(dt);
                             }
                         }
                         false
@@ -913,7 +911,7 @@ pub fn find_sessions_filtered(
                 .and_then(|m| m.modified().ok())
                 .map(|t| {
                     let datetime: chrono::DateTime<chrono::Utc> = t.into();
-                    datetime.format("%Y-%m-%d %H:%M").to_string()
+                    format_datetime_short(datetime)
                 })
                 .unwrap_or_else(|| "unknown".to_string());
 
@@ -985,8 +983,8 @@ pub fn find_sessions_filtered(
                 |(title, workspace, provider, modified, messages, match_type)| {
                     SearchResultRowWithProvider {
                         provider,
-                        title: truncate_string(&title, 35),
-                        workspace: truncate_string(&workspace, 15),
+                        title: truncate(&title, 35),
+                        workspace: truncate(&workspace, 15),
                         modified,
                         messages,
                         match_type,
@@ -1019,8 +1017,8 @@ pub fn find_sessions_filtered(
             .into_iter()
             .map(
                 |(title, workspace, _provider, modified, messages, match_type)| SearchResultRow {
-                    title: truncate_string(&title, 40),
-                    workspace: truncate_string(&workspace, 20),
+                    title: truncate(&title, 40),
+                    workspace: truncate(&workspace, 20),
                     modified,
                     messages,
                     match_type,
@@ -1095,15 +1093,6 @@ fn extract_title_fast(header: &str) -> Option<String> {
     extract_title_from_content(header)
 }
 
-/// Truncate string to max length with ellipsis
-fn truncate_string(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
-        s.to_string()
-    } else {
-        format!("{}...", &s[..max_len.saturating_sub(3)])
-    }
-}
-
 /// Show workspace details
 pub fn show_workspace(workspace: &str) -> Result<()> {
     use colored::Colorize;
@@ -1741,7 +1730,7 @@ pub fn list_agents_sessions(
                 .as_ref()
                 .and_then(|p| std::path::Path::new(p).file_name())
                 .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_else(|| entry.file_name().to_string_lossy()[..8].to_string());
+                .unwrap_or_else(|| prefix_chars(&entry.file_name().to_string_lossy(), 8).to_string());
 
             // List agent session directories
             for session_entry in std::fs::read_dir(&agent_sessions_dir)?.filter_map(|e| e.ok()) {
@@ -1751,8 +1740,8 @@ pub fn list_agents_sessions(
                 }
 
                 let session_id = session_entry.file_name().to_string_lossy().to_string();
-                let short_id = if session_id.len() > 8 {
-                    format!("{}...", &session_id[..8])
+                let short_id = if session_id.chars().count() > 8 {
+                    format!("{}...", prefix_chars(&session_id, 8))
                 } else {
                     session_id.clone()
                 };
@@ -1780,7 +1769,7 @@ pub fn list_agents_sessions(
 
                 let modified = if last_mod != std::time::SystemTime::UNIX_EPOCH {
                     let datetime: chrono::DateTime<chrono::Utc> = last_mod.into();
-                    datetime.format("%Y-%m-%d %H:%M").to_string()
+                    format_datetime_short(datetime)
                 } else {
                     "unknown".to_string()
                 };
@@ -2022,7 +2011,7 @@ pub fn show_timeline(
                         if let Ok(meta) = file.metadata() {
                             if let Ok(modified) = meta.modified() {
                                 let datetime: chrono::DateTime<chrono::Utc> = modified.into();
-                                let date = datetime.date_naive();
+                                let date = local_date(datetime);
                                 let entry = date_activity.entry(date).or_insert((0, 0));
                                 entry.0 += 1;
                             }
@@ -2040,7 +2029,7 @@ pub fn show_timeline(
                             if let Ok(meta) = dir.metadata() {
                                 if let Ok(modified) = meta.modified() {
                                     let datetime: chrono::DateTime<chrono::Utc> = modified.into();
-                                    let date = datetime.date_naive();
+                                    let date = local_date(datetime);
                                     let entry = date_activity.entry(date).or_insert((0, 0));
                                     entry.1 += 1;
                                 }
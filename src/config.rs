@@ -0,0 +1,1363 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Central configuration for storage roots, profiles, and environment overrides
+//!
+//! Settings live in `~/.config/csm/config.toml` (or the path in `CSM_CONFIG`).
+//! Every storage root is resolved with the following precedence:
+//!
+//! 1. Environment variable (e.g. `CSM_HARVEST_DB`)
+//! 2. Active profile (`profile = "work"` or `CSM_PROFILE=work`)
+//! 3. Top-level `[storage]` section
+//! 4. Built-in platform default
+//!
+//! ```toml
+//! profile = "work"
+//!
+//! [storage]
+//! harvest_db = "~/chasm/chat_sessions.db"
+//!
+//! [profiles.work.storage]
+//! vscode_user_dir = "/mnt/work/Code/User"
+//!
+//! [profiles.work.defaults]
+//! harvest_providers = ["copilot", "cursor"]
+//!
+//! [limits]
+//! max_session_mb = 512
+//!
+//! [display]
+//! timezone = "utc"   # "local" (default), "utc", or a fixed offset like "+05:30"
+//!
+//! [backups]
+//! keep = 10          # newest backups kept per source by `csm backup prune`
+//! max_age_days = 90  # older backups are pruned even if within `keep`
+//! destination = "s3://my-bucket/chasm"  # or a directory, or "webdav+https://host/path"
+//! passphrase = "$CHASM_BACKUP_PASSPHRASE"
+//!
+//! [backups.s3]
+//! region = "us-east-1"
+//!
+//! [notifications.channels.team]
+//! provider = "slack"
+//! url = "$SLACK_WEBHOOK_URL"
+//! events = ["harvest.*"]
+//!
+//! # Reloaded by `csm api serve` on SIGHUP or `POST /api/admin/reload`
+//! [api]
+//! cors_origins = ["https://chat.example.com"]
+//!
+//! [api.providers.ollama]
+//! endpoint = "http://gpu-box:11434"
+//!
+//! [api.routing]
+//! strategy = "lowest_cost"   # best_quality, fastest_response, balanced (default)
+//! min_quality = 0.85         # task score lowest_cost needs
+//! blocked_providers = ["google"]
+//!
+//! [api.routing.prices]       # USD per million tokens, over the built-in ones
+//! "gpt-4o" = { input = 2.00, output = 8.00 }
+//!
+//! # Where requests go when their provider is unreachable or answers 429/5xx
+//! [api.routing.fallback]
+//! chain = ["vllm", "ollama/llama3.2", "openai/gpt-4o-mini"]
+//! max_attempts = 3           # providers one request may be sent to
+//! breaker_failures = 3       # failures in a row that skip a provider...
+//! breaker_cooldown_secs = 30 # ...for this long
+//!
+//! [mcp]
+//! allow_write = true
+//! deny_tools = ["csm_merge_sessions", "csm_db_*"]
+//! confirm_writes = true   # ask on the terminal before each write tool runs
+//!
+//! [mcp.databases]          # served as csm://db/{name}/ besides the default
+//! work = "~/work/chat_sessions.db"
+//! personal = "~/chat_sessions.db"
+//!
+//! # MCP servers whose tools agents can mount
+//! [agency.mcp_servers.github]
+//! command = "github-mcp-server"
+//! args = ["stdio"]
+//! env = { GITHUB_PERSONAL_ACCESS_TOKEN = "$GITHUB_TOKEN" }
+//! tools = ["get_*", "search_*"]
+//!
+//! # What agent runs may spend; runs stop once past a limit
+//! [agency.budget]
+//! max_run_usd = 0.50
+//! max_day_usd = 5.00
+//! max_run_tokens = 200000
+//!
+//! [agency.budget.prices."my-finetune"]   # USD per million tokens
+//! input = 3.0
+//! output = 12.0
+//!
+//! # Models agents fall back to; a bare provider keeps the agent's model name
+//! [agency.fallback]
+//! chain = ["ollama/llama3.1", "openai/gpt-4o-mini"]
+//!
+//! # What agents' file, shell, and HTTP tools may touch
+//! [agency.sandbox]
+//! paths = [".", "~/notes"]
+//! network = false
+//! timeout_secs = 30
+//!
+//! # Workflow runs kept in the harvest database for `csm automation runs`
+//! [automation]
+//! keep_runs = 500          # newest runs kept per workflow (default 100)
+//! max_run_age_days = 90    # older runs are dropped even if within `keep_runs`
+//!
+//! # Topics `csm intelligence topics` tags sessions with; `/` nests a topic
+//! [topics]
+//! builtin = false          # drop the built-in rust and python topics
+//!
+//! [topics.taxonomy."lang/rust"]
+//! keywords = ["rust", "cargo", "borrow checker"]
+//! patterns = ['\bimpl\s+\w+\s+for\b']
+//!
+//! # How harvest indexes messages; run `csm harvest rebuild` after a change
+//! [search]
+//! remove_diacritics = 0    # "cafe" no longer matches "café" (default 2)
+//! trigram = false          # no trigram index for Chinese, Japanese and Thai
+//!
+//! # Registry `csm plugin search`, `install <id>` and `update` use
+//! [plugins]
+//! registry = "https://plugins.example.com/v1"
+//! trusted_keys = ["x4Ip7mDkn9Q0YV1Gz8C1c1W0uOqHk3dJ2wq8b8mV0aA="]  # Ed25519, base64
+//! allow_unsigned = false   # refuse plugins the registry has not signed (default)
+//!
+//! # Where `csm issue create` and `csm actions export` file issues
+//! [integrations]
+//! issue_tracker = "jira"   # or "linear"; not needed when only one is set up
+//! session_url = "https://chasm.example.com/sessions/{id}"   # linked from issues
+//!
+//! # The Slack app `csm slack` imports threads with and posts summaries as
+//! [integrations.slack]
+//! bot_token = "$SLACK_BOT_TOKEN"   # channels:history, channels:read, chat:write, users:read
+//! channel = "#ai-debugging"        # where `csm slack post` posts by default
+//!
+//! # The vault `csm obsidian sync` keeps a note per harvested session in
+//! [integrations.obsidian]
+//! vault = "~/Documents/Notes"
+//! folder = "Chat Sessions"   # folder in the vault for the notes (default)
+//!
+//! [integrations.jira]
+//! url = "https://example.atlassian.net"
+//! email = "me@example.com"    # Jira Cloud; leave out for a Data Center token
+//! token = "$JIRA_API_TOKEN"
+//! project = "ENG"
+//! issue_type = "Task"         # (default)
+//!
+//! [integrations.linear]
+//! token = "$LINEAR_API_KEY"
+//! team = "ENG"                # team key or ID
+//!
+//! # The mail server `csm report digest` sends the weekly digest through
+//! [integrations.smtp]
+//! host = "smtp.example.com"
+//! security = "starttls"       # (default, port 587); "tls" for port 465, or "none"
+//! username = "csm@example.com"
+//! password = "$SMTP_PASSWORD"
+//! from = "Chasm <csm@example.com>"
+//! to = ["me@example.com"]     # where digests go by default
+//!
+//! # Which providers work in a workspace may use, enforced by the gateway,
+//! # agents, `csm run` and embeddings; decisions are kept for `csm routing audit`
+//! [policies.client-a]
+//! paths = ["~/work/client-a"]   # repositories under these directories
+//! workspaces = ["client-a"]     # workspace names or IDs, as sent to the gateway
+//! local_only = true             # only Ollama, LM Studio, vLLM and other local servers
+//! blocked_providers = ["vllm"]
+//! ```
+
+use crate::error::{CsmError, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Environment variable pointing at an alternate config file
+pub const CONFIG_ENV: &str = "CSM_CONFIG";
+
+/// Environment variable selecting the active profile
+pub const PROFILE_ENV: &str = "CSM_PROFILE";
+
+/// Environment variable overriding `limits.max_session_mb`
+pub const MAX_SESSION_MB_ENV: &str = "CSM_MAX_SESSION_MB";
+
+/// Environment variable overriding `display.timezone`
+pub const TIMEZONE_ENV: &str = "CSM_TIMEZONE";
+
+/// Config loaded once per process for path resolution
+static CURRENT: Lazy<Config> = Lazy::new(|| match Config::load() {
+    Ok(config) => config,
+    Err(e) => {
+        eprintln!("[!] Ignoring invalid configuration: {}", e);
+        Config::default()
+    }
+});
+
+/// A configurable storage location
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageRoot {
+    /// VS Code `User` directory (parent of workspaceStorage/globalStorage)
+    VsCodeUser,
+    /// VS Code workspaceStorage directory
+    WorkspaceStorage,
+    /// VS Code globalStorage directory
+    GlobalStorage,
+    /// Harvest database file
+    HarvestDb,
+    /// Directory for session backups
+    Backups,
+    /// Directory for relative export destinations
+    Exports,
+}
+
+impl StorageRoot {
+    /// All storage roots in display order
+    pub const ALL: [StorageRoot; 6] = [
+        Self::VsCodeUser,
+        Self::WorkspaceStorage,
+        Self::GlobalStorage,
+        Self::HarvestDb,
+        Self::Backups,
+        Self::Exports,
+    ];
+
+    /// Key used in the `[storage]` table
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::VsCodeUser => "vscode_user_dir",
+            Self::WorkspaceStorage => "workspace_storage",
+            Self::GlobalStorage => "global_storage",
+            Self::HarvestDb => "harvest_db",
+            Self::Backups => "backup_dir",
+            Self::Exports => "export_dir",
+        }
+    }
+
+    /// Environment variable that overrides this root
+    pub fn env_var(&self) -> &'static str {
+        match self {
+            Self::VsCodeUser => "CSM_VSCODE_USER_DIR",
+            Self::WorkspaceStorage => "CSM_WORKSPACE_STORAGE",
+            Self::GlobalStorage => "CSM_GLOBAL_STORAGE",
+            Self::HarvestDb => "CSM_HARVEST_DB",
+            Self::Backups => "CSM_BACKUP_DIR",
+            Self::Exports => "CSM_EXPORT_DIR",
+        }
+    }
+
+    /// Parse from a `[storage]` key
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.key() == key)
+    }
+}
+
+/// Overridable storage locations
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vscode_user_dir: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace_storage: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub global_storage: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub harvest_db: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup_dir: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub export_dir: Option<PathBuf>,
+}
+
+impl StorageConfig {
+    /// Get the configured value for a root
+    pub fn get(&self, root: StorageRoot) -> Option<&PathBuf> {
+        match root {
+            StorageRoot::VsCodeUser => self.vscode_user_dir.as_ref(),
+            StorageRoot::WorkspaceStorage => self.workspace_storage.as_ref(),
+            StorageRoot::GlobalStorage => self.global_storage.as_ref(),
+            StorageRoot::HarvestDb => self.harvest_db.as_ref(),
+            StorageRoot::Backups => self.backup_dir.as_ref(),
+            StorageRoot::Exports => self.export_dir.as_ref(),
+        }
+    }
+}
+
+/// Command defaults that can vary per profile
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DefaultsConfig {
+    /// Providers harvested when `--providers` is not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub harvest_providers: Option<Vec<String>>,
+    /// Providers excluded from harvest when `--exclude` is not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub harvest_exclude: Option<Vec<String>>,
+}
+
+/// Resource limits for parsing and storing large sessions
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// Largest session file that will be parsed, in MB
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_session_mb: Option<u64>,
+    /// Messages larger than this are stored in chunks in the harvest database, in KB
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_chunk_kb: Option<u64>,
+}
+
+impl LimitsConfig {
+    pub const DEFAULT_MAX_SESSION_MB: u64 = 256;
+    pub const DEFAULT_MESSAGE_CHUNK_KB: u64 = 256;
+
+    /// Maximum session file size in bytes
+    pub fn max_session_bytes(&self) -> u64 {
+        self.max_session_mb
+            .unwrap_or(Self::DEFAULT_MAX_SESSION_MB)
+            .saturating_mul(1024 * 1024)
+    }
+
+    /// Harvest message chunk size in bytes
+    pub fn message_chunk_bytes(&self) -> usize {
+        self.message_chunk_kb
+            .unwrap_or(Self::DEFAULT_MESSAGE_CHUNK_KB)
+            .max(1)
+            .saturating_mul(1024) as usize
+    }
+}
+
+/// Output formatting preferences
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplayConfig {
+    /// Timezone for printed timestamps and date filters: `local`, `utc`, or `+HH:MM`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+/// Retention policy applied by `csm backup prune`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupsConfig {
+    /// Newest backups to keep for each source
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep: Option<usize>,
+    /// Remove backups older than this many days
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u64>,
+    /// Where `csm backup run` writes archives: a directory, `s3://bucket/prefix`,
+    /// or `webdav+https://host/path`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub destination: Option<String>,
+    /// Archive passphrase; values starting with `$` are read from the environment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<String>,
+    #[serde(skip_serializing_if = "is_default")]
+    pub s3: S3DestinationConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub webdav: WebDavDestinationConfig,
+}
+
+/// Settings for `s3://` backup destinations. Credentials default to the
+/// `AWS_ACCESS_KEY_ID` / `AWS_SECRET_ACCESS_KEY` environment variables.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct S3DestinationConfig {
+    /// Bucket region (default `$AWS_REGION`, then `us-east-1`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Endpoint for S3-compatible services such as MinIO; uses path-style URLs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_key_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret_access_key: Option<String>,
+}
+
+/// Settings for `webdav+http(s)://` backup destinations
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebDavDestinationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+impl BackupsConfig {
+    pub const DEFAULT_KEEP: usize = 10;
+
+    /// Newest backups to keep for each source
+    pub fn keep(&self) -> usize {
+        self.keep.unwrap_or(Self::DEFAULT_KEEP)
+    }
+}
+
+/// How much workflow run history the harvest database keeps
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AutomationConfig {
+    /// Newest runs to keep for each workflow
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_runs: Option<usize>,
+    /// Remove runs older than this many days
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_run_age_days: Option<u64>,
+}
+
+impl AutomationConfig {
+    pub const DEFAULT_KEEP_RUNS: usize = 100;
+
+    /// Newest runs to keep for each workflow
+    pub fn keep_runs(&self) -> usize {
+        self.keep_runs.unwrap_or(Self::DEFAULT_KEEP_RUNS)
+    }
+}
+
+/// A named notification channel
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelConfig {
+    /// Provider that delivers messages (`system`, `slack`, `discord`, `email`, `webhook`)
+    pub provider: String,
+    /// Set to false to keep the channel configured but silent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// Event patterns to deliver (e.g. `["harvest.*"]`); all events when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub events: Option<Vec<String>>,
+    /// Message template, e.g. `"{{title}}: {{message}}"`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Provider settings such as `url` or `to`; values starting with `$` are read from the environment
+    #[serde(flatten)]
+    pub settings: BTreeMap<String, String>,
+}
+
+/// Notification channels
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationsConfig {
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub channels: BTreeMap<String, ChannelConfig>,
+}
+
+/// An upstream of the API server's OpenAI-compatible gateway. The provider's
+/// endpoint and API key environment variables win over these.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiProviderConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    /// Values starting with `$` are read from the environment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// Set to false to stop routing to the provider
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+}
+
+/// How the gateway picks a model for `model: "auto"`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiRoutingConfig {
+    /// `best_quality`, `lowest_cost`, `fastest_response`, or `balanced`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<String>,
+    /// Only route to these providers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_providers: Option<Vec<String>>,
+    /// Never route to these providers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocked_providers: Option<Vec<String>>,
+    /// Most a request may cost, in USD
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_cost: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_latency_ms: Option<u32>,
+    /// Task score (0-1) a model needs for `lowest_cost` to pick it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_quality: Option<f64>,
+    /// Prices by model ID, overriding the built-in ones
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub prices: BTreeMap<String, ModelPrice>,
+    /// Seconds between health probes of each provider (default 30, 0 stops
+    /// probing)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub probe_interval_secs: Option<u64>,
+    /// Probe latency above which a provider is avoided (default 2000)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slow_ms: Option<u64>,
+    #[serde(skip_serializing_if = "is_default")]
+    pub fallback: FallbackConfig,
+}
+
+/// Where requests go when their provider fails, and when to stop trying it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FallbackConfig {
+    /// `provider` or `provider/model`, tried in order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub chain: Vec<String>,
+    /// Most providers one request is sent to, the first included (default 3)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<usize>,
+    /// Failures in a row after which a provider is skipped (default 3)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub breaker_failures: Option<u32>,
+    /// Seconds a failing provider is skipped before it is tried again
+    /// (default 30)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub breaker_cooldown_secs: Option<u64>,
+}
+
+/// API server settings that can change while it runs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiConfig {
+    /// Browser origins allowed to call the API, besides localhost
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cors_origins: Option<Vec<String>>,
+    /// Gateway upstreams by provider name (`openai`, `anthropic`, `google`,
+    /// `ollama`, `vllm`)
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub providers: BTreeMap<String, ApiProviderConfig>,
+    #[serde(skip_serializing_if = "is_default")]
+    pub routing: ApiRoutingConfig,
+}
+
+/// Which tools `csm-mcp` offers. Tool names may end in `*` to match a prefix.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct McpConfig {
+    /// Offer the tools that change data, like `--allow-write`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_write: Option<bool>,
+    /// Offer only these tools
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_tools: Option<Vec<String>>,
+    /// Never offer these tools
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deny_tools: Option<Vec<String>>,
+    /// Ask on the server's terminal before each write tool runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirm_writes: Option<bool>,
+    /// More harvest databases to serve, by name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub databases: Option<BTreeMap<String, PathBuf>>,
+}
+
+/// A named set of overrides
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProfileConfig {
+    #[serde(skip_serializing_if = "is_default")]
+    pub storage: StorageConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub defaults: DefaultsConfig,
+}
+
+/// An external MCP server whose tools Agency agents can mount
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct McpServerConfig {
+    /// Program that serves MCP over stdio
+    pub command: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Environment for the server; values may name a variable as `$VAR`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Mount only these tools; names may end in `*` to match a prefix
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<String>>,
+}
+
+/// What a model costs, in USD per million tokens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelPrice {
+    /// Per million prompt tokens
+    pub input: f64,
+    /// Per million completion tokens
+    pub output: f64,
+}
+
+/// Limits on what agent runs spend. A run stops once it, or all of the
+/// day's runs together, pass a limit.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BudgetConfig {
+    /// Most one run may cost, in USD
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_run_usd: Option<f64>,
+    /// Most the day's runs may cost together, in USD
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_day_usd: Option<f64>,
+    /// Most tokens one run may use
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_run_tokens: Option<u64>,
+    /// Most tokens the day's runs may use together
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_day_tokens: Option<u64>,
+    /// Prices by model name, over the built-in ones
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub prices: BTreeMap<String, ModelPrice>,
+}
+
+/// What an agent's file, shell, and HTTP tools may touch. Agents that do
+/// not declare a `sandbox` of their own get the one in `[agency.sandbox]`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SandboxConfig {
+    /// Folders the tools may read and write, and commands run in the first
+    /// of. Relative paths start at the working directory.
+    pub paths: Vec<String>,
+    /// Refuse writes even inside `paths`
+    pub read_only: bool,
+    /// Run commands even where they cannot be confined to `paths` (no
+    /// bubblewrap). Never applies to a read-only sandbox.
+    pub allow_unconfined: bool,
+    /// Programs commands may not run; entries with spaces are phrases
+    /// commands may not contain
+    pub denied_commands: Vec<String>,
+    /// Allow HTTP requests, and network access from commands
+    pub network: bool,
+    /// Seconds a command or request may take
+    pub timeout_secs: u64,
+    /// Memory a command may use, in MB (Unix only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_memory_mb: Option<u64>,
+    /// Bytes of output or file content handed back to the model
+    pub max_output_bytes: usize,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            paths: vec![".".to_string()],
+            read_only: false,
+            allow_unconfined: false,
+            denied_commands: [
+                "sudo", "su", "doas", "shutdown", "reboot", "halt", "mkfs", "dd", "rm -rf /",
+            ]
+            .iter()
+            .map(|c| c.to_string())
+            .collect(),
+            network: false,
+            timeout_secs: 30,
+            max_memory_mb: None,
+            max_output_bytes: 64 * 1024,
+        }
+    }
+}
+
+/// Settings for Agency agents
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AgencyConfig {
+    /// MCP servers agents can mount, by name
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub mcp_servers: BTreeMap<String, McpServerConfig>,
+    #[serde(skip_serializing_if = "is_default")]
+    pub budget: BudgetConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub sandbox: SandboxConfig,
+    /// Models agents fall back to when theirs fails
+    #[serde(skip_serializing_if = "is_default")]
+    pub fallback: FallbackConfig,
+}
+
+/// Words and regexes that put a session under a topic
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TopicDefinition {
+    /// Words or phrases, matched whole and ignoring case
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    /// Regular expressions, matched ignoring case
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<String>,
+}
+
+/// The topic taxonomy sessions are sorted into
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TopicsConfig {
+    /// Keep the built-in topics next to these (default true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub builtin: Option<bool>,
+    /// Share (0-1) of a session's words that must match a topic
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_confidence: Option<f32>,
+    /// Topics by name; `parent/child` names a subtopic
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub taxonomy: BTreeMap<String, TopicDefinition>,
+}
+
+/// How the harvest database indexes messages for full-text search
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchConfig {
+    /// `remove_diacritics` option of the unicode61 tokenizer: 0 keeps
+    /// accents, 1 and 2 fold them (2 also folds letters with several)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remove_diacritics: Option<u8>,
+    /// Also index messages in languages written without spaces by trigram
+    /// (default true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trigram: Option<bool>,
+}
+
+impl SearchConfig {
+    pub const DEFAULT_REMOVE_DIACRITICS: u8 = 2;
+
+    /// Tokenizer of the `messages_fts` table
+    pub fn tokenizer(&self) -> String {
+        format!(
+            "unicode61 remove_diacritics {}",
+            self.remove_diacritics
+                .unwrap_or(Self::DEFAULT_REMOVE_DIACRITICS)
+                .min(2)
+        )
+    }
+
+    /// Whether to keep the trigram index
+    pub fn trigram(&self) -> bool {
+        self.trigram.unwrap_or(true)
+    }
+}
+
+/// Where plugins are installed from by ID, and whose signatures are trusted
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PluginsConfig {
+    /// URL of the plugin registry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+    /// Ed25519 public keys, base64, whose signatures are accepted
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub trusted_keys: Vec<String>,
+    /// Install plugins the registry has not signed (default false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_unsigned: Option<bool>,
+}
+
+/// The Slack app `csm slack` reads threads with and posts summaries as
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SlackIntegrationConfig {
+    /// Bot token (`xoxb-...`); a value starting with `$` is read from the environment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bot_token: Option<String>,
+    /// User token (`xoxp-...`), only for searching and setting a status
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_token: Option<String>,
+    /// Channel summaries are posted to when none is given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+}
+
+/// The Obsidian vault `csm obsidian sync` writes notes to
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ObsidianIntegrationConfig {
+    /// Vault directory; `~` is the home directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vault: Option<String>,
+    /// Folder in the vault for the notes (default "Chat Sessions")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+}
+
+/// The Jira site `csm issue create` files issues in
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JiraIntegrationConfig {
+    /// Site URL, such as `https://example.atlassian.net`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Account email, for Jira Cloud API tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// API token, or a Data Center personal access token; a value starting
+    /// with `$` is read from the environment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Key of the project issues are filed in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// Issue type (default "Task")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issue_type: Option<String>,
+}
+
+/// The Linear workspace `csm issue create` files issues in
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LinearIntegrationConfig {
+    /// Personal API key; a value starting with `$` is read from the environment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Key or ID of the team issues are filed for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team: Option<String>,
+}
+
+/// Accounts of the services csm reads from and posts to
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IntegrationsConfig {
+    /// Tracker issues are filed in when none is named: `jira` or `linear`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issue_tracker: Option<String>,
+    /// Link to a session from issues, with `{id}` for its ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_url: Option<String>,
+    #[serde(skip_serializing_if = "is_default")]
+    pub slack: SlackIntegrationConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub obsidian: ObsidianIntegrationConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub jira: JiraIntegrationConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub linear: LinearIntegrationConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub smtp: SmtpIntegrationConfig,
+}
+
+/// The mail server `csm report digest` sends through
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SmtpIntegrationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// Port (default 587, or 465 with `security = "tls"`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// `starttls` (default), `tls`, or `none` for a relay on the local network
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub security: Option<String>,
+    /// Account to sign in as; a value starting with `$` is read from the environment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// Password; a value starting with `$` is read from the environment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Sender, such as `Chasm <csm@example.com>`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    /// Recipients digests go to when none are given
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub to: Vec<String>,
+}
+
+/// Providers work in some workspaces is limited to
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PolicyConfig {
+    /// Directories the policy covers, with everything under them
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    /// Workspace names or IDs the policy covers
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub workspaces: Vec<String>,
+    /// Allow only providers that run on this machine or network
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_only: Option<bool>,
+    /// Allow only these providers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_providers: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub blocked_providers: Vec<String>,
+}
+
+/// Contents of `config.toml`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Active profile name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(skip_serializing_if = "is_default")]
+    pub storage: StorageConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub defaults: DefaultsConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub limits: LimitsConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub display: DisplayConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub backups: BackupsConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub notifications: NotificationsConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub api: ApiConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub mcp: McpConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub agency: AgencyConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub automation: AutomationConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub topics: TopicsConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub search: SearchConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub plugins: PluginsConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub integrations: IntegrationsConfig,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub policies: BTreeMap<String, PolicyConfig>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, ProfileConfig>,
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+/// Where a resolved value came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValueSource {
+    Env(&'static str),
+    Profile(String),
+    File,
+    Default,
+}
+
+impl std::fmt::Display for ValueSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Env(var) => write!(f, "env ${}", var),
+            Self::Profile(name) => write!(f, "profile '{}'", name),
+            Self::File => write!(f, "config file"),
+            Self::Default => write!(f, "default"),
+        }
+    }
+}
+
+impl Config {
+    /// Path of the config file (`CSM_CONFIG` or `~/.config/csm/config.toml`)
+    pub fn path() -> Result<PathBuf> {
+        if let Ok(p) = std::env::var(CONFIG_ENV) {
+            return Ok(PathBuf::from(p));
+        }
+
+        let config_dir = if cfg!(target_os = "windows") {
+            dirs::config_dir().map(|p| p.join("csm"))
+        } else {
+            dirs::home_dir().map(|p| p.join(".config").join("csm"))
+        };
+
+        config_dir
+            .map(|p| p.join("config.toml"))
+            .ok_or(CsmError::StorageNotFound)
+    }
+
+    /// Load the config file, returning defaults if it does not exist
+    pub fn load() -> Result<Self> {
+        Self::load_from(&Self::path()?)
+    }
+
+    /// Load a config file from an explicit path
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        Self::parse(&content)
+            .map_err(|e| CsmError::ConfigError(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Parse TOML config content
+    pub fn parse(content: &str) -> Result<Self> {
+        toml::from_str(content).map_err(|e| CsmError::ConfigError(e.message().to_string()))
+    }
+
+    /// Save to the default config path
+    pub fn save(&self) -> Result<PathBuf> {
+        let path = Self::path()?;
+        self.save_to(&path)?;
+        Ok(path)
+    }
+
+    /// Save to an explicit path
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content =
+            toml::to_string_pretty(self).map_err(|e| CsmError::ConfigError(e.to_string()))?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Name of the active profile (`CSM_PROFILE` wins over the file)
+    pub fn active_profile(&self) -> Option<String> {
+        self.active_profile_with(|k| std::env::var(k).ok())
+    }
+
+    fn active_profile_with(&self, env: impl Fn(&str) -> Option<String>) -> Option<String> {
+        env(PROFILE_ENV)
+            .filter(|p| !p.is_empty())
+            .or_else(|| self.profile.clone())
+    }
+
+    /// Resolve a storage root from env, profile, and file (no built-in default)
+    pub fn resolve(&self, root: StorageRoot) -> Option<(PathBuf, ValueSource)> {
+        self.resolve_with(root, |k| std::env::var(k).ok())
+    }
+
+    /// Resolve a storage root using a custom environment lookup
+    pub fn resolve_with(
+        &self,
+        root: StorageRoot,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Option<(PathBuf, ValueSource)> {
+        if let Some(value) = env(root.env_var()).filter(|v| !v.is_empty()) {
+            return Some((
+                expand_home(Path::new(&value)),
+                ValueSource::Env(root.env_var()),
+            ));
+        }
+
+        if let Some(name) = self.active_profile_with(&env) {
+            if let Some(path) = self.profiles.get(&name).and_then(|p| p.storage.get(root)) {
+                return Some((expand_home(path), ValueSource::Profile(name)));
+            }
+        }
+
+        self.storage
+            .get(root)
+            .map(|path| (expand_home(path), ValueSource::File))
+    }
+
+    /// Effective command defaults for the active profile
+    pub fn defaults(&self) -> DefaultsConfig {
+        let mut defaults = self.defaults.clone();
+        if let Some(profile) = self
+            .active_profile()
+            .and_then(|name| self.profiles.get(&name))
+        {
+            if profile.defaults.harvest_providers.is_some() {
+                defaults.harvest_providers = profile.defaults.harvest_providers.clone();
+            }
+            if profile.defaults.harvest_exclude.is_some() {
+                defaults.harvest_exclude = profile.defaults.harvest_exclude.clone();
+            }
+        }
+        defaults
+    }
+
+    /// Effective limits (`CSM_MAX_SESSION_MB` wins over the file)
+    pub fn limits(&self) -> LimitsConfig {
+        self.limits_with(|k| std::env::var(k).ok())
+    }
+
+    fn limits_with(&self, env: impl Fn(&str) -> Option<String>) -> LimitsConfig {
+        let mut limits = self.limits.clone();
+        if let Some(mb) = env(MAX_SESSION_MB_ENV).and_then(|v| v.trim().parse().ok()) {
+            limits.max_session_mb = Some(mb);
+        }
+        limits
+    }
+
+    /// Effective display settings (`CSM_TIMEZONE` wins over the file)
+    pub fn display(&self) -> DisplayConfig {
+        self.display_with(|k| std::env::var(k).ok())
+    }
+
+    fn display_with(&self, env: impl Fn(&str) -> Option<String>) -> DisplayConfig {
+        let mut display = self.display.clone();
+        if let Some(tz) = env(TIMEZONE_ENV).filter(|v| !v.trim().is_empty()) {
+            display.timezone = Some(tz);
+        }
+        display
+    }
+
+    /// Get a value by dotted key (e.g. `storage.harvest_db`), optionally within a profile
+    pub fn get_key(&self, key: &str, profile: Option<&str>) -> Result<Option<toml::Value>> {
+        let root = self.to_table(profile)?;
+        let mut current = &root;
+        let mut parts = key.split('.').peekable();
+        while let Some(part) = parts.next() {
+            match current.get(part) {
+                Some(toml::Value::Table(table)) if parts.peek().is_some() => {
+                    current = table;
+                }
+                Some(value) if parts.peek().is_none() => return Ok(Some(value.clone())),
+                _ => return Ok(None),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Set a value by dotted key, validating the result against the schema
+    pub fn set_key(&mut self, key: &str, raw: &str, profile: Option<&str>) -> Result<()> {
+        let value = parse_value(raw);
+        self.edit_table(key, profile, |table, leaf| {
+            table.insert(leaf.to_string(), value);
+        })
+    }
+
+    /// Remove a value by dotted key
+    pub fn unset_key(&mut self, key: &str, profile: Option<&str>) -> Result<()> {
+        self.edit_table(key, profile, |table, leaf| {
+            table.remove(leaf);
+        })
+    }
+
+    fn to_table(&self, profile: Option<&str>) -> Result<toml::Table> {
+        let table = match profile {
+            Some(name) => {
+                toml::Table::try_from(self.profiles.get(name).cloned().unwrap_or_default())
+            }
+            None => toml::Table::try_from(self),
+        };
+        table.map_err(|e| CsmError::ConfigError(e.to_string()))
+    }
+
+    fn edit_table(
+        &mut self,
+        key: &str,
+        profile: Option<&str>,
+        edit: impl FnOnce(&mut toml::Table, &str),
+    ) -> Result<()> {
+        let mut root = self.to_table(profile)?;
+        let parts: Vec<&str> = key.split('.').collect();
+        let (leaf, path) = parts
+            .split_last()
+            .filter(|(leaf, _)| !leaf.is_empty())
+            .ok_or_else(|| CsmError::ConfigError(format!("Invalid key: '{}'", key)))?;
+
+        let mut current = &mut root;
+        for part in path {
+            current = current
+                .entry(part.to_string())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                .as_table_mut()
+                .ok_or_else(|| CsmError::ConfigError(format!("'{}' is not a table", part)))?;
+        }
+        edit(current, leaf);
+
+        let invalid = |e: toml::de::Error| {
+            CsmError::ConfigError(format!("Invalid value for '{}': {}", key, e.message()))
+        };
+        match profile {
+            Some(name) => {
+                let updated: ProfileConfig =
+                    toml::Value::Table(root).try_into().map_err(invalid)?;
+                self.profiles.insert(name.to_string(), updated);
+            }
+            None => {
+                *self = toml::Value::Table(root).try_into().map_err(invalid)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse a CLI value as TOML, falling back to a plain string
+fn parse_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("v = {}", raw))
+        .ok()
+        .and_then(|mut t| t.remove("v"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+/// Expand a leading `~` to the home directory
+pub(crate) fn expand_home(path: &Path) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(rest) => dirs::home_dir()
+            .map(|home| home.join(rest))
+            .unwrap_or_else(|| path.to_path_buf()),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// Resolve a setting that may reference an environment variable (`$NAME`).
+/// Returns `None` for unset or empty variables.
+pub fn expand_env(value: &str) -> Option<String> {
+    match value.strip_prefix('$') {
+        Some(var) => std::env::var(var).ok().filter(|v| !v.is_empty()),
+        None => Some(value.to_string()),
+    }
+}
+
+/// The process-wide configuration
+pub fn current() -> &'static Config {
+    &CURRENT
+}
+
+/// Resolve a configured storage root, if any override is set
+pub fn storage_root(root: StorageRoot) -> Option<PathBuf> {
+    current().resolve(root).map(|(path, _)| path)
+}
+
+/// Resolve an export destination, placing relative paths under the exports root
+pub fn resolve_export_path(destination: &str) -> PathBuf {
+    let dest = Path::new(destination);
+    if dest.is_absolute() {
+        return dest.to_path_buf();
+    }
+    match storage_root(StorageRoot::Exports) {
+        Some(root) => root.join(dest),
+        None => dest.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+profile = "work"
+
+[storage]
+harvest_db = "/data/harvest.db"
+backup_dir = "/data/backups"
+
+[defaults]
+harvest_exclude = ["ollama"]
+
+[profiles.work.storage]
+harvest_db = "/work/harvest.db"
+
+[profiles.work.defaults]
+harvest_providers = ["copilot"]
+
+[topics.taxonomy."lang/rust"]
+keywords = ["cargo"]
+"#;
+
+    fn no_env(_: &str) -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn test_parse_sample() {
+        let config = Config::parse(SAMPLE).unwrap();
+        assert_eq!(config.profile.as_deref(), Some("work"));
+        assert_eq!(config.profiles.len(), 1);
+        assert_eq!(
+            config.storage.backup_dir,
+            Some(PathBuf::from("/data/backups"))
+        );
+    }
+
+    #[test]
+    fn test_unknown_key_rejected() {
+        assert!(Config::parse("[storage]\nbogus = 1\n").is_err());
+    }
+
+    #[test]
+    fn test_resolution_precedence() {
+        let config = Config::parse(SAMPLE).unwrap();
+
+        let (path, source) = config.resolve_with(StorageRoot::HarvestDb, no_env).unwrap();
+        assert_eq!(path, PathBuf::from("/work/harvest.db"));
+        assert_eq!(source, ValueSource::Profile("work".to_string()));
+
+        let (path, source) = config.resolve_with(StorageRoot::Backups, no_env).unwrap();
+        assert_eq!(path, PathBuf::from("/data/backups"));
+        assert_eq!(source, ValueSource::File);
+
+        let env = |k: &str| (k == "CSM_HARVEST_DB").then(|| "/env/h.db".to_string());
+        let (path, source) = config.resolve_with(StorageRoot::HarvestDb, env).unwrap();
+        assert_eq!(path, PathBuf::from("/env/h.db"));
+        assert_eq!(source, ValueSource::Env("CSM_HARVEST_DB"));
+
+        assert!(config.resolve_with(StorageRoot::Exports, no_env).is_none());
+    }
+
+    #[test]
+    fn test_profile_env_override() {
+        let config = Config::parse(SAMPLE).unwrap();
+        let env = |k: &str| (k == PROFILE_ENV).then(|| "other".to_string());
+        let (path, source) = config.resolve_with(StorageRoot::HarvestDb, env).unwrap();
+        assert_eq!(path, PathBuf::from("/data/harvest.db"));
+        assert_eq!(source, ValueSource::File);
+    }
+
+    #[test]
+    fn test_set_and_unset_keys() {
+        let mut config = Config::default();
+        config
+            .set_key("storage.export_dir", "/tmp/exports", None)
+            .unwrap();
+        config
+            .set_key(
+                "defaults.harvest_providers",
+                r#"["copilot", "cursor"]"#,
+                Some("home"),
+            )
+            .unwrap();
+
+        assert_eq!(
+            config.storage.export_dir,
+            Some(PathBuf::from("/tmp/exports"))
+        );
+        assert_eq!(
+            config.profiles["home"].defaults.harvest_providers,
+            Some(vec!["copilot".to_string(), "cursor".to_string()])
+        );
+        assert_eq!(
+            config.get_key("storage.export_dir", None).unwrap(),
+            Some(toml::Value::String("/tmp/exports".to_string()))
+        );
+
+        config.unset_key("storage.export_dir", None).unwrap();
+        assert!(config.storage.export_dir.is_none());
+    }
+
+    #[test]
+    fn test_set_invalid_key_rejected() {
+        let mut config = Config::default();
+        assert!(config.set_key("storage.nope", "x", None).is_err());
+        assert!(config
+            .set_key("defaults.harvest_providers", "copilot", None)
+            .is_err());
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_limits_defaults_and_env() {
+        let mut config = Config::default();
+        assert_eq!(
+            config.limits_with(no_env).max_session_bytes(),
+            LimitsConfig::DEFAULT_MAX_SESSION_MB * 1024 * 1024
+        );
+
+        config.set_key("limits.max_session_mb", "8", None).unwrap();
+        assert_eq!(config.limits_with(no_env).max_session_bytes(), 8 * 1024 * 1024);
+
+        let env = |k: &str| (k == MAX_SESSION_MB_ENV).then(|| "2".to_string());
+        assert_eq!(config.limits_with(env).max_session_bytes(), 2 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_display_timezone_env() {
+        let mut config = Config::default();
+        assert!(config.display_with(no_env).timezone.is_none());
+
+        config.set_key("display.timezone", "utc", None).unwrap();
+        assert_eq!(config.display_with(no_env).timezone.as_deref(), Some("utc"));
+
+        let env = |k: &str| (k == TIMEZONE_ENV).then(|| "+02:00".to_string());
+        assert_eq!(config.display_with(env).timezone.as_deref(), Some("+02:00"));
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let config = Config::parse(SAMPLE).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        config.save_to(&path).unwrap();
+        assert_eq!(Config::load_from(&path).unwrap(), config);
+    }
+}
@@ -0,0 +1,420 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Timezone-aware timestamp formatting and date filter parsing
+//!
+//! Timestamps are stored in UTC. For display they are converted to the
+//! configured timezone (`display.timezone` or `CSM_TIMEZONE`), which is the
+//! system local timezone by default. Date filters such as `--after 2024-05-01`
+//! are read in that same timezone unless they carry an explicit `Z` or
+//! `+HH:MM` offset.
+
+use crate::error::{CsmError, Result};
+use chrono::{
+    DateTime, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+};
+use once_cell::sync::Lazy;
+use std::fmt;
+use std::str::FromStr;
+
+/// `2024-05-01 09:30:00`
+pub const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+/// `2024-05-01 09:30`
+pub const DATETIME_SHORT_FORMAT: &str = "%Y-%m-%d %H:%M";
+/// `2024-05-01`
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Timezone used to print timestamps and interpret date filters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisplayTimezone {
+    /// System local timezone
+    #[default]
+    Local,
+    Utc,
+    /// Fixed offset from UTC
+    Fixed(FixedOffset),
+}
+
+impl DisplayTimezone {
+    /// Convert a UTC instant to wall-clock time in this timezone
+    pub fn convert(&self, dt: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self {
+            Self::Local => dt.with_timezone(&Local).fixed_offset(),
+            Self::Utc => dt.fixed_offset(),
+            Self::Fixed(offset) => dt.with_timezone(offset),
+        }
+    }
+
+    /// Resolve wall-clock time in this timezone to a UTC instant.
+    /// Local times skipped by a DST change resolve to the hour after.
+    pub fn resolve(&self, naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Local => Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .or_else(|| {
+                    Local
+                        .from_local_datetime(&(naive + Duration::hours(1)))
+                        .earliest()
+                })
+                .map(|dt| dt.with_timezone(&Utc)),
+            Self::Utc => Some(naive.and_utc()),
+            Self::Fixed(offset) => offset
+                .from_local_datetime(&naive)
+                .single()
+                .map(|dt| dt.with_timezone(&Utc)),
+        }
+    }
+
+    /// Calendar date of an instant in this timezone
+    pub fn date_of(&self, dt: DateTime<Utc>) -> NaiveDate {
+        self.convert(dt).date_naive()
+    }
+}
+
+impl FromStr for DisplayTimezone {
+    type Err = CsmError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        match s.to_ascii_lowercase().as_str() {
+            "" | "local" => return Ok(Self::Local),
+            "utc" | "z" | "gmt" => return Ok(Self::Utc),
+            _ => {}
+        }
+        let offset = s
+            .strip_prefix("UTC")
+            .or_else(|| s.strip_prefix("utc"))
+            .unwrap_or(s);
+        parse_offset(offset).map(Self::Fixed).ok_or_else(|| {
+            CsmError::ConfigError(format!(
+                "Invalid timezone '{}': use local, utc, or an offset like +05:30",
+                s
+            ))
+        })
+    }
+}
+
+impl fmt::Display for DisplayTimezone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local => write!(f, "local ({})", Local::now().offset()),
+            Self::Utc => write!(f, "utc"),
+            Self::Fixed(offset) => write!(f, "{}", offset),
+        }
+    }
+}
+
+/// Parse `+HH:MM`, `-HH:MM`, `+HHMM`, or `+HH`
+fn parse_offset(s: &str) -> Option<FixedOffset> {
+    let (sign, rest) = match s.as_bytes().first()? {
+        b'+' => (1, &s[1..]),
+        b'-' => (-1, &s[1..]),
+        _ => return None,
+    };
+    let digits: String = rest.chars().filter(|c| *c != ':').collect();
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        2 => (digits.parse::<i32>().ok()?, 0),
+        4 => (
+            digits[..2].parse::<i32>().ok()?,
+            digits[2..].parse::<i32>().ok()?,
+        ),
+        _ => return None,
+    };
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+static DISPLAY_TZ: Lazy<DisplayTimezone> = Lazy::new(|| {
+    let display = crate::config::current().display();
+    match display.timezone.as_deref().map(str::parse) {
+        None => DisplayTimezone::Local,
+        Some(Ok(tz)) => tz,
+        Some(Err(e)) => {
+            eprintln!("[!] {}; using local time", e);
+            DisplayTimezone::Local
+        }
+    }
+});
+
+/// The configured display timezone
+pub fn display_timezone() -> DisplayTimezone {
+    *DISPLAY_TZ
+}
+
+/// Format an instant in the display timezone
+pub fn format(dt: DateTime<Utc>, fmt: &str) -> String {
+    display_timezone().convert(dt).format(fmt).to_string()
+}
+
+/// `YYYY-MM-DD HH:MM:SS` in the display timezone
+pub fn format_datetime(dt: DateTime<Utc>) -> String {
+    format(dt, DATETIME_FORMAT)
+}
+
+/// `YYYY-MM-DD HH:MM` in the display timezone
+pub fn format_datetime_short(dt: DateTime<Utc>) -> String {
+    format(dt, DATETIME_SHORT_FORMAT)
+}
+
+/// `YYYY-MM-DD` in the display timezone
+pub fn format_date(dt: DateTime<Utc>) -> String {
+    format(dt, DATE_FORMAT)
+}
+
+/// Instant from a Unix timestamp in either seconds or milliseconds
+pub fn from_epoch(ts: i64) -> Option<DateTime<Utc>> {
+    if ts <= 0 {
+        None
+    } else if ts > 10_000_000_000 {
+        DateTime::from_timestamp_millis(ts)
+    } else {
+        DateTime::from_timestamp(ts, 0)
+    }
+}
+
+/// Calendar date of an instant in the display timezone
+pub fn local_date(dt: DateTime<Utc>) -> NaiveDate {
+    display_timezone().date_of(dt)
+}
+
+/// A half-open time range from `--after` / `--before` style arguments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DateFilter {
+    /// Inclusive lower bound
+    pub after: Option<DateTime<Utc>>,
+    /// Exclusive upper bound
+    pub before: Option<DateTime<Utc>>,
+}
+
+impl DateFilter {
+    /// Parse `--after` / `--before` in the display timezone.
+    /// A date-only `--before` includes that whole day.
+    pub fn parse(after: Option<&str>, before: Option<&str>) -> Result<Self> {
+        Self::parse_in(after, before, display_timezone())
+    }
+
+    /// Parse `--after` / `--before` in an explicit timezone
+    pub fn parse_in(
+        after: Option<&str>,
+        before: Option<&str>,
+        tz: DisplayTimezone,
+    ) -> Result<Self> {
+        Ok(Self {
+            after: after.map(|s| parse_bound(s, tz, false)).transpose()?,
+            before: before.map(|s| parse_bound(s, tz, true)).transpose()?,
+        })
+    }
+
+    /// The whole calendar day `date` in the display timezone
+    pub fn on_day(date: &str) -> Result<Self> {
+        Self::parse(Some(date), Some(date))
+    }
+
+    /// Whether neither bound is set
+    pub fn is_empty(&self) -> bool {
+        self.after.is_none() && self.before.is_none()
+    }
+
+    /// Whether `dt` falls within the range
+    pub fn contains(&self, dt: DateTime<Utc>) -> bool {
+        self.after.is_none_or(|after| dt >= after) && self.before.is_none_or(|before| dt < before)
+    }
+
+    /// Bounds as Unix seconds, with the upper bound made inclusive
+    pub fn as_unix_secs(&self) -> (Option<i64>, Option<i64>) {
+        (
+            self.after.map(|dt| dt.timestamp()),
+            self.before.map(|dt| dt.timestamp() - 1),
+        )
+    }
+}
+
+/// Parse a `--since` value: a relative age such as `12h`, `30d`, or `2w`,
+/// or a date/time accepted by `--after`.
+pub fn parse_since(s: &str) -> Result<DateTime<Utc>> {
+    parse_since_at(s, Utc::now(), display_timezone())
+}
+
+fn parse_since_at(s: &str, now: DateTime<Utc>, tz: DisplayTimezone) -> Result<DateTime<Utc>> {
+    let input = s.trim();
+    let relative = input
+        .char_indices()
+        .last()
+        .and_then(|(i, unit)| Some((input[..i].parse::<i64>().ok()?, unit)))
+        .and_then(|(n, unit)| match unit.to_ascii_lowercase() {
+            'h' => Duration::try_hours(n),
+            'd' => Duration::try_days(n),
+            'w' => Duration::try_weeks(n),
+            _ => None,
+        });
+
+    match relative {
+        Some(age) if age >= Duration::zero() => now
+            .checked_sub_signed(age)
+            .ok_or_else(|| CsmError::InvalidDate(s.to_string())),
+        Some(_) => Err(CsmError::InvalidDate(s.to_string())),
+        None => parse_bound(input, tz, false),
+    }
+}
+
+/// Parse one filter bound. Date-only upper bounds move to the following midnight.
+fn parse_bound(s: &str, tz: DisplayTimezone, upper: bool) -> Result<DateTime<Utc>> {
+    let invalid = || CsmError::InvalidDate(s.to_string());
+    let input = s.trim();
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(input) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+
+    // Split an explicit `Z` or `±HH:MM` suffix from the wall-clock part
+    let (wall, tz) = if let Some(rest) = input.strip_suffix(['Z', 'z']) {
+        (rest, DisplayTimezone::Utc)
+    } else {
+        match input
+            .rfind(['+', '-'])
+            // Only signs after the `YYYY-MM-DD` part can start an offset
+            .filter(|&i| i >= 10)
+            .and_then(|i| Some((&input[..i], parse_offset(&input[i..])?)))
+        {
+            Some((rest, offset)) => (rest, DisplayTimezone::Fixed(offset)),
+            None => (input, tz),
+        }
+    };
+
+    let naive = if let Ok(date) = NaiveDate::parse_from_str(wall, DATE_FORMAT) {
+        let date = if upper {
+            date.succ_opt().ok_or_else(invalid)?
+        } else {
+            date
+        };
+        date.and_time(NaiveTime::MIN)
+    } else {
+        [
+            "%Y-%m-%dT%H:%M:%S%.f",
+            "%Y-%m-%d %H:%M:%S%.f",
+            "%Y-%m-%dT%H:%M",
+            "%Y-%m-%d %H:%M",
+        ]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(wall, fmt).ok())
+        .ok_or_else(invalid)?
+    };
+
+    tz.resolve(naive).ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plus(hours: i32) -> DisplayTimezone {
+        DisplayTimezone::Fixed(FixedOffset::east_opt(hours * 3600).unwrap())
+    }
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_parse_timezone() {
+        assert_eq!(
+            "local".parse::<DisplayTimezone>().unwrap(),
+            DisplayTimezone::Local
+        );
+        assert_eq!(
+            "UTC".parse::<DisplayTimezone>().unwrap(),
+            DisplayTimezone::Utc
+        );
+        assert_eq!("+02:00".parse::<DisplayTimezone>().unwrap(), plus(2));
+        assert_eq!("UTC-0500".parse::<DisplayTimezone>().unwrap(), plus(-5));
+        assert!("Mars/Olympus".parse::<DisplayTimezone>().is_err());
+        assert!("+25:00".parse::<DisplayTimezone>().is_err());
+    }
+
+    #[test]
+    fn test_convert_for_display() {
+        let dt = utc("2024-05-01T22:30:00Z");
+        assert_eq!(
+            plus(2).convert(dt).format(DATETIME_FORMAT).to_string(),
+            "2024-05-02 00:30:00"
+        );
+        assert_eq!(
+            plus(2).date_of(dt),
+            NaiveDate::from_ymd_opt(2024, 5, 2).unwrap()
+        );
+        assert_eq!(
+            DisplayTimezone::Utc.date_of(dt),
+            NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_date_filter_uses_timezone() {
+        let filter = DateFilter::parse_in(Some("2024-05-01"), Some("2024-05-01"), plus(2)).unwrap();
+        assert_eq!(filter.after, Some(utc("2024-04-30T22:00:00Z")));
+        assert_eq!(filter.before, Some(utc("2024-05-01T22:00:00Z")));
+
+        assert!(filter.contains(utc("2024-04-30T22:00:00Z")));
+        assert!(filter.contains(utc("2024-05-01T21:59:59Z")));
+        assert!(!filter.contains(utc("2024-05-01T22:00:00Z")));
+        assert!(!filter.contains(utc("2024-04-30T21:59:59Z")));
+    }
+
+    #[test]
+    fn test_date_filter_explicit_offsets() {
+        let tz = plus(9);
+        let at = |s| {
+            DateFilter::parse_in(Some(s), None, tz)
+                .unwrap()
+                .after
+                .unwrap()
+        };
+
+        assert_eq!(at("2024-05-01T10:00:00Z"), utc("2024-05-01T10:00:00Z"));
+        assert_eq!(at("2024-05-01T10:00:00-05:00"), utc("2024-05-01T15:00:00Z"));
+        assert_eq!(at("2024-05-01 10:00Z"), utc("2024-05-01T10:00:00Z"));
+        assert_eq!(at("2024-05-01Z"), utc("2024-05-01T00:00:00Z"));
+        assert_eq!(at("2024-05-01+0130"), utc("2024-04-30T22:30:00Z"));
+        // No suffix: wall-clock time in the display timezone
+        assert_eq!(at("2024-05-01 10:00"), utc("2024-05-01T01:00:00Z"));
+    }
+
+    #[test]
+    fn test_date_filter_rejects_garbage() {
+        assert!(matches!(
+            DateFilter::parse_in(Some("yesterday"), None, DisplayTimezone::Utc),
+            Err(CsmError::InvalidDate(_))
+        ));
+        assert!(DateFilter::parse_in(None, Some("2024-13-01"), DisplayTimezone::Utc).is_err());
+        assert!(DateFilter::parse_in(None, None, DisplayTimezone::Utc)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let now = utc("2024-05-31T12:00:00Z");
+        let since = |s| parse_since_at(s, now, DisplayTimezone::Utc);
+
+        assert_eq!(since("30d").unwrap(), utc("2024-05-01T12:00:00Z"));
+        assert_eq!(since("2w").unwrap(), utc("2024-05-17T12:00:00Z"));
+        assert_eq!(since("36H").unwrap(), utc("2024-05-30T00:00:00Z"));
+        assert_eq!(since("2024-05-01").unwrap(), utc("2024-05-01T00:00:00Z"));
+        assert!(since("-3d").is_err());
+        assert!(since("30y").is_err());
+        assert!(since("d").is_err());
+    }
+
+    #[test]
+    fn test_from_epoch_seconds_or_millis() {
+        let expected = utc("2024-05-01T00:00:00Z");
+        assert_eq!(from_epoch(1_714_521_600), Some(expected));
+        assert_eq!(from_epoch(1_714_521_600_000), Some(expected));
+        assert_eq!(from_epoch(0), None);
+    }
+}
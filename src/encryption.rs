@@ -163,6 +163,40 @@ impl EncryptionManager {
         Ok(plaintext)
     }
 
+    /// Encrypt bytes into a binary `nonce || ciphertext` blob
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        if !self.enabled {
+            return Err(anyhow!("Encryption is not enabled"));
+        }
+
+        let cipher = Aes256Gcm::new(&self.key);
+        let nonce_bytes: [u8; NONCE_SIZE] = rand::random();
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce_bytes), plaintext)
+            .map_err(|e| anyhow!("Encryption failed: {}", e))?;
+
+        let mut sealed = Vec::with_capacity(NONCE_SIZE + ciphertext.len());
+        sealed.extend_from_slice(&nonce_bytes);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    /// Decrypt a blob produced by [`seal`](Self::seal)
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+        if !self.enabled {
+            return Err(anyhow!("Encryption is not enabled"));
+        }
+        if sealed.len() < NONCE_SIZE {
+            return Err(anyhow!("Invalid nonce size"));
+        }
+
+        let (nonce_bytes, ciphertext) = sealed.split_at(NONCE_SIZE);
+        let cipher = Aes256Gcm::new(&self.key);
+        cipher
+            .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+            .map_err(|e| anyhow!("Decryption failed: {}", e))
+    }
+
     /// Encrypt a string
     pub fn encrypt_string(&self, plaintext: &str) -> Result<String> {
         let encrypted = self.encrypt(plaintext.as_bytes())?;
@@ -327,6 +361,17 @@ mod tests {
         assert_eq!(decrypted, original);
     }
 
+    #[test]
+    fn test_seal_open() {
+        let manager = EncryptionManager::new("secret", b"salt_salt_salt_salt").unwrap();
+        let sealed = manager.seal(b"archive bytes").unwrap();
+        assert_eq!(manager.open(&sealed).unwrap(), b"archive bytes");
+
+        let other = EncryptionManager::new("other", b"salt_salt_salt_salt").unwrap();
+        assert!(other.open(&sealed).is_err());
+        assert!(manager.open(&sealed[..4]).is_err());
+    }
+
     #[test]
     fn test_password_verification() {
         let config = EncryptionConfig::new("my_password");
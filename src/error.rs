@@ -42,6 +42,42 @@ pub enum CsmError {
 
     #[error("Must specify either --hash or --path")]
     MissingTargetSpecifier,
+
+    #[error("Configuration error: {0}")]
+    ConfigError(String),
+
+    #[error("Watch error: {0}")]
+    WatchError(String),
+
+    #[error("Notification error: {0}")]
+    NotificationError(String),
+
+    #[error("Pack error: {0}")]
+    PackError(String),
+
+    #[error("Backup error: {0}")]
+    BackupError(String),
+
+    #[error("Email error: {0}")]
+    EmailError(String),
+
+    #[error("Project not found: {0}")]
+    ProjectNotFound(String),
+
+    #[error("Invalid date '{0}'. Use YYYY-MM-DD, YYYY-MM-DD HH:MM[:SS], or RFC 3339 with Z or an offset")]
+    InvalidDate(String),
+
+    #[error(
+        "Session file too large: {} is {:.1} MB (limit {:.1} MB). Raise limits.max_session_mb to allow it",
+        .path.display(),
+        *.size as f64 / 1048576.0,
+        *.limit as f64 / 1048576.0
+    )]
+    SessionTooLarge {
+        path: std::path::PathBuf,
+        size: u64,
+        limit: u64,
+    },
 }
 
 pub type Result<T> = std::result::Result<T, CsmError>;
@@ -0,0 +1,147 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Writing the graph for other tools
+
+use super::Graph;
+use std::fmt::Write;
+
+/// File formats the graph can be exported as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// GraphML, read by Gephi, yEd, Cytoscape and NetworkX
+    GraphMl,
+    /// Graphviz DOT
+    Dot,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format.to_lowercase().as_str() {
+            "graphml" | "xml" => Some(Self::GraphMl),
+            "dot" | "gv" | "graphviz" => Some(Self::Dot),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    /// Write `graph` in this format
+    pub fn render(&self, graph: &Graph) -> String {
+        match self {
+            Self::GraphMl => to_graphml(graph),
+            Self::Dot => to_dot(graph),
+            Self::Json => to_json(graph),
+        }
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The graph as GraphML, with the node kind and label and the edge relation
+/// and weight as attributes
+pub fn to_graphml(graph: &Graph) -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="kind" for="node" attr.name="kind" attr.type="string"/>
+  <key id="label" for="node" attr.name="label" attr.type="string"/>
+  <key id="relation" for="edge" attr.name="relation" attr.type="string"/>
+  <key id="weight" for="edge" attr.name="weight" attr.type="int"/>
+  <graph id="chasm" edgedefault="directed">
+"#,
+    );
+    for node in &graph.nodes {
+        let _ = writeln!(
+            xml,
+            "    <node id=\"n{}\"><data key=\"kind\">{}</data><data key=\"label\">{}</data></node>",
+            node.id,
+            xml_escape(&node.kind),
+            xml_escape(node.display_name())
+        );
+    }
+    for (i, edge) in graph.edges.iter().enumerate() {
+        let _ = writeln!(
+            xml,
+            "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\"><data key=\"relation\">{}</data><data key=\"weight\">{}</data></edge>",
+            i,
+            edge.source,
+            edge.target,
+            xml_escape(&edge.relation),
+            edge.weight
+        );
+    }
+    xml.push_str("  </graph>\n</graphml>\n");
+    xml
+}
+
+/// The graph in Graphviz DOT
+pub fn to_dot(graph: &Graph) -> String {
+    let quote = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut dot = String::from("digraph chasm {\n  node [shape=box];\n");
+    for node in &graph.nodes {
+        let _ = writeln!(
+            dot,
+            "  n{} [label=\"{}\", kind=\"{}\"];",
+            node.id,
+            quote(node.display_name()),
+            node.kind
+        );
+    }
+    for edge in &graph.edges {
+        let _ = writeln!(
+            dot,
+            "  n{} -> n{} [label=\"{}\", weight={}];",
+            edge.source, edge.target, edge.relation, edge.weight
+        );
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// The graph as JSON, `{"nodes": [...], "edges": [...]}`
+pub fn to_json(graph: &Graph) -> String {
+    serde_json::to_string_pretty(graph).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{GraphEdge, GraphNode};
+
+    #[test]
+    fn test_to_graphml() {
+        let graph = Graph {
+            nodes: vec![
+                GraphNode {
+                    id: 1,
+                    kind: "session".to_string(),
+                    name: "s1".to_string(),
+                    label: Some("Fix <T> & \"quotes\"".to_string()),
+                },
+                GraphNode {
+                    id: 2,
+                    kind: "file".to_string(),
+                    name: "src/main.rs".to_string(),
+                    label: None,
+                },
+            ],
+            edges: vec![GraphEdge {
+                source: 1,
+                target: 2,
+                relation: "mentions".to_string(),
+                weight: 3,
+            }],
+        };
+        let xml = to_graphml(&graph);
+        assert!(xml.contains("<data key=\"label\">Fix &lt;T&gt; &amp; &quot;quotes&quot;</data>"));
+        assert!(xml.contains("<data key=\"label\">src/main.rs</data>"));
+        assert!(xml.contains("source=\"n1\" target=\"n2\""));
+        assert!(xml.contains("<data key=\"weight\">3</data>"));
+        assert!(xml.ends_with("</graphml>\n"));
+    }
+}
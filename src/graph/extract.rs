@@ -0,0 +1,285 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Finding entities in session text
+
+use super::{Entity, EntityKind};
+use crate::reviews::FILE_MENTION;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Crates every Rust program has, which say nothing about a session
+const BUILTIN_CRATES: &[&str] = &[
+    "std",
+    "core",
+    "alloc",
+    "crate",
+    "self",
+    "super",
+    "proc_macro",
+];
+
+/// Chat participants addressed with `@` that are not people
+const CHAT_PARTICIPANTS: &[&str] = &[
+    "workspace",
+    "vscode",
+    "terminal",
+    "github",
+    "azure",
+    "copilot",
+    "cursor",
+    "codebase",
+    "web",
+    "docs",
+    "file",
+    "folder",
+    "git",
+    "code",
+    "symbols",
+];
+
+/// A message naming more entities than this is a dump (a log, a listing)
+/// whose entities are not related to each other in any useful way
+const MAX_RELATED_ENTITIES: usize = 12;
+
+/// Longest error message kept, in characters
+const MAX_ERROR_CHARS: usize = 100;
+
+/// `use serde::...` and `extern crate serde`
+static RUST_USE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^\s*(?:pub\s+)?(?:use|extern\s+crate)\s+([a-z_][a-z0-9_]*)").unwrap()
+});
+
+/// `cargo add serde tokio --features ...`
+static CARGO_ADD: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\bcargo add((?: +[A-Za-z0-9_@.-]+)+)").unwrap());
+
+/// `serde = "1.0"` and `tokio = { version = ... }` in a manifest
+static CARGO_DEPENDENCY: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?m)^\s*([a-z][a-z0-9_-]*)\s*=\s*(?:"\d|\{\s*(?:version|path|git)\s*=)"#).unwrap()
+});
+
+/// Inline code in prose
+static INLINE_CODE: Lazy<Regex> = Lazy::new(|| Regex::new(r"`([^`\n]+)`").unwrap());
+
+/// A path or method call such as `serde_json::from_str` or `fs.readFile()`
+static API_PATH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:[A-Za-z_][A-Za-z0-9_]*(?:::|\.))+[A-Za-z_][A-Za-z0-9_]*(?:\(\))?$").unwrap()
+});
+
+/// `GET /api/sessions/{id}`
+static HTTP_ENDPOINT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(GET|POST|PUT|PATCH|DELETE) (/[\w/{}:.-]*)").unwrap());
+
+/// `error[E0382]: borrow of moved value` from rustc
+static RUST_ERROR: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^\s*error(\[E\d{4}\])?: (.+)$").unwrap());
+
+/// `TypeError: x is undefined` and the like
+static EXCEPTION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)\b([A-Z]\w*(?:Error|Exception)): (.+)$").unwrap());
+
+/// `thread 'main' panicked at src/main.rs:3:5:\nmessage`, or the older
+/// `panicked at 'message'`
+static PANIC: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"panicked at '([^'\n]+)'|panicked at [^\n]*:\n([^\n]+)").unwrap());
+
+/// `@alice`, but not an email address
+static PERSON_MENTION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:^|[\s(])@([A-Za-z][\w-]{1,38})\b").unwrap());
+
+/// Quoted names in error messages, which differ from one occurrence of an
+/// error to the next
+static QUOTED: Lazy<Regex> = Lazy::new(|| Regex::new(r#"`[^`]*`|'[^']*'|"[^"]*""#).unwrap());
+
+/// The entities named in `text`
+pub fn extract_entities(text: &str) -> BTreeSet<Entity> {
+    let mut entities = BTreeSet::new();
+
+    for path in FILE_MENTION.find_iter(text) {
+        let path = path.as_str().replace('\\', "/");
+        let path = path.trim_start_matches("./");
+        entities.insert(Entity::new(EntityKind::File, path));
+    }
+
+    let crates = RUST_USE
+        .captures_iter(text)
+        .chain(CARGO_DEPENDENCY.captures_iter(text))
+        .map(|c| c[1].to_string())
+        .chain(CARGO_ADD.captures_iter(text).flat_map(|c| {
+            c[1].split_whitespace()
+                .take_while(|word| !word.starts_with('-'))
+                .map(|word| word.split('@').next().unwrap_or(word).to_string())
+                .collect::<Vec<_>>()
+        }));
+    for name in crates {
+        if let Some(name) = crate_name(&name) {
+            entities.insert(Entity::new(EntityKind::Crate, name));
+        }
+    }
+
+    for code in INLINE_CODE.captures_iter(text) {
+        let code = code[1].trim();
+        if API_PATH.is_match(code) && !FILE_MENTION.is_match(code) {
+            let api = code.trim_end_matches("()");
+            if !api.starts_with("self.") && !api.starts_with("this.") {
+                entities.insert(Entity::new(EntityKind::Api, api));
+            }
+        }
+    }
+    for endpoint in HTTP_ENDPOINT.captures_iter(text) {
+        let name = format!("{} {}", &endpoint[1], &endpoint[2]);
+        entities.insert(Entity::new(EntityKind::Api, name));
+    }
+
+    for error in RUST_ERROR.captures_iter(text) {
+        let code = error.get(1).map_or("", |c| c.as_str());
+        let name = format!("error{}: {}", code, normalize_error(&error[2]));
+        entities.insert(Entity::new(EntityKind::Error, name));
+    }
+    for error in EXCEPTION.captures_iter(text) {
+        let name = format!("{}: {}", &error[1], normalize_error(&error[2]));
+        entities.insert(Entity::new(EntityKind::Error, name));
+    }
+    for panic in PANIC.captures_iter(text) {
+        let message = panic.get(1).or(panic.get(2)).map_or("", |m| m.as_str());
+        let name = format!("panic: {}", normalize_error(message));
+        entities.insert(Entity::new(EntityKind::Error, name));
+    }
+
+    for person in PERSON_MENTION.captures_iter(text) {
+        let name = &person[1];
+        if !CHAT_PARTICIPANTS.contains(&name.to_lowercase().as_str()) {
+            entities.insert(Entity::new(EntityKind::Person, name));
+        }
+    }
+    entities
+}
+
+/// The crate an API such as `serde_json::from_str` belongs to
+pub fn api_crate(api: &str) -> Option<String> {
+    let (first, _) = api.split_once("::")?;
+    crate_name(first)
+}
+
+/// `name` as a crate, with the underscores Rust paths use, unless it is a
+/// crate every program has
+fn crate_name(name: &str) -> Option<String> {
+    let name = name.replace('-', "_");
+    let is_crate = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && !BUILTIN_CRATES.contains(&name.as_str());
+    is_crate.then_some(name)
+}
+
+/// An error message without the quoted names that change between
+/// occurrences, so the same error from two sessions is one node
+fn normalize_error(message: &str) -> String {
+    let message = QUOTED.replace_all(message.trim(), "_");
+    crate::text::truncate(message.trim_end_matches(['.', ':']), MAX_ERROR_CHARS)
+}
+
+/// What one session contributes to the graph
+#[derive(Debug, Default)]
+pub struct SessionGraph {
+    /// Entities and how many messages mention each
+    pub mentions: BTreeMap<Entity, u32>,
+    /// People who took part in the session
+    pub participants: BTreeSet<String>,
+    /// Entities named in the same message, and in how many messages
+    pub related: BTreeMap<(Entity, Entity), u32>,
+    /// APIs and the crates they belong to
+    pub part_of: BTreeSet<(Entity, Entity)>,
+}
+
+impl SessionGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the entities of one message
+    pub fn add_message(&mut self, text: &str) {
+        let entities = extract_entities(text);
+        for entity in &entities {
+            *self.mentions.entry(entity.clone()).or_default() += 1;
+            if entity.kind == EntityKind::Api {
+                if let Some(name) = api_crate(&entity.name) {
+                    let owner = Entity::new(EntityKind::Crate, name);
+                    self.part_of.insert((entity.clone(), owner));
+                }
+            }
+        }
+        if entities.len() > MAX_RELATED_ENTITIES {
+            return;
+        }
+        let entities: Vec<&Entity> = entities.iter().collect();
+        for (i, a) in entities.iter().enumerate() {
+            for b in &entities[i + 1..] {
+                *self
+                    .related
+                    .entry(((*a).clone(), (*b).clone()))
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    /// Add someone who took part in the session
+    pub fn add_participant(&mut self, name: &str) {
+        let name = name.trim();
+        if !name.is_empty() && !name.eq_ignore_ascii_case("user") {
+            self.participants.insert(name.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_entities() {
+        let text = "Running `cargo run` on src\\main.rs gives:\n\
+            error[E0382]: borrow of moved value: `config`\n\
+            Try `serde_json::from_str` instead, and `cargo add serde_json anyhow --features std`. \
+            The handler for POST /api/sessions calls `session.title()`. cc @alice, not @workspace.\n\
+            ```rust\nuse tokio::fs;\nuse std::io;\n```";
+        let entities = extract_entities(text);
+        let found: Vec<(EntityKind, &str)> = entities
+            .iter()
+            .map(|entity| (entity.kind, entity.name.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (EntityKind::File, "src/main.rs"),
+                (EntityKind::Crate, "anyhow"),
+                (EntityKind::Crate, "serde_json"),
+                (EntityKind::Crate, "tokio"),
+                (EntityKind::Api, "POST /api/sessions"),
+                (EntityKind::Api, "serde_json::from_str"),
+                (EntityKind::Api, "session.title"),
+                (EntityKind::Person, "alice"),
+                (EntityKind::Error, "error[E0382]: borrow of moved value: _"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_session_graph() {
+        let mut graph = SessionGraph::new();
+        graph.add_message("Why does src/lib.rs fail with `serde_json::from_str`?");
+        graph.add_message("Look at src/lib.rs again");
+        graph.add_participant("user");
+        graph.add_participant("octocat");
+
+        let lib = Entity::new(EntityKind::File, "src/lib.rs");
+        let api = Entity::new(EntityKind::Api, "serde_json::from_str");
+        let serde_json = Entity::new(EntityKind::Crate, "serde_json");
+        assert_eq!(graph.mentions[&lib], 2);
+        assert_eq!(graph.related[&(lib, api.clone())], 1);
+        assert!(graph.part_of.contains(&(api, serde_json)));
+        assert_eq!(graph.participants.len(), 1);
+    }
+}
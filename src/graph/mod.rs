@@ -0,0 +1,94 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Knowledge graph of harvested sessions
+//!
+//! `csm graph build` reads every harvested session for the things it talks
+//! about (files, crates, APIs, people and error messages) and stores them in
+//! the harvest database as nodes, linked to the sessions that mention them
+//! and to each other when they come up in the same message. `csm graph query`
+//! walks the links from one node; `csm graph export` writes the graph as
+//! GraphML, DOT or JSON for tools such as Gephi, yEd or Graphviz.
+
+pub mod export;
+pub mod extract;
+pub mod store;
+
+pub use export::{to_dot, to_graphml, to_json, ExportFormat};
+pub use extract::{extract_entities, SessionGraph};
+pub use store::{
+    ensure_graph_tables, find_nodes, index_session, load_graph, neighbors, prune_nodes, Graph,
+    GraphEdge, GraphNode, Neighbor,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// What a node stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntityKind {
+    Session,
+    File,
+    Crate,
+    Api,
+    Person,
+    Error,
+}
+
+impl EntityKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Session => "session",
+            Self::File => "file",
+            Self::Crate => "crate",
+            Self::Api => "api",
+            Self::Person => "person",
+            Self::Error => "error",
+        }
+    }
+
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind.to_lowercase().trim_end_matches('s') {
+            "session" => Some(Self::Session),
+            "file" => Some(Self::File),
+            "crate" | "package" => Some(Self::Crate),
+            "api" => Some(Self::Api),
+            "person" | "people" => Some(Self::Person),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for EntityKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Something a session talks about
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Entity {
+    pub kind: EntityKind,
+    pub name: String,
+}
+
+impl Entity {
+    pub fn new(kind: EntityKind, name: impl Into<String>) -> Self {
+        Self {
+            kind,
+            name: name.into(),
+        }
+    }
+}
+
+/// How two nodes are linked
+pub mod relation {
+    /// A session talks about an entity
+    pub const MENTIONS: &str = "mentions";
+    /// A person took part in a session
+    pub const PARTICIPATED_IN: &str = "participated_in";
+    /// Two entities came up in the same message
+    pub const CO_OCCURS: &str = "co_occurs";
+    /// An API belongs to a crate
+    pub const PART_OF: &str = "part_of";
+}
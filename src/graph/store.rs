@@ -0,0 +1,321 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Storing the graph in the harvest database
+
+use super::{relation, Entity, EntityKind, SessionGraph};
+use crate::error::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+/// Create the graph tables in the harvest database if they do not exist
+pub fn ensure_graph_tables(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS graph_nodes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            name TEXT NOT NULL,
+            label TEXT,
+            UNIQUE (kind, name)
+        );
+
+        CREATE TABLE IF NOT EXISTS graph_edges (
+            source INTEGER NOT NULL,
+            target INTEGER NOT NULL,
+            relation TEXT NOT NULL,
+            weight INTEGER NOT NULL DEFAULT 1,
+            session_id TEXT NOT NULL,
+            PRIMARY KEY (source, target, relation, session_id),
+            FOREIGN KEY (source) REFERENCES graph_nodes(id) ON DELETE CASCADE,
+            FOREIGN KEY (target) REFERENCES graph_nodes(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_graph_edges_target ON graph_edges(target);
+        CREATE INDEX IF NOT EXISTS idx_graph_edges_session ON graph_edges(session_id);
+        "#,
+    )?;
+    Ok(())
+}
+
+/// The ID of the node for `entity`, creating it if needed
+fn node_id(conn: &Connection, entity: &Entity, label: Option<&str>) -> Result<i64> {
+    let kind = entity.kind.as_str();
+    conn.prepare_cached(
+        "INSERT OR IGNORE INTO graph_nodes (kind, name, label) VALUES (?1, ?2, ?3)",
+    )?
+    .execute(params![kind, entity.name, label])?;
+    if label.is_some() {
+        conn.prepare_cached("UPDATE graph_nodes SET label = ?3 WHERE kind = ?1 AND name = ?2")?
+            .execute(params![kind, entity.name, label])?;
+    }
+    let id = conn
+        .prepare_cached("SELECT id FROM graph_nodes WHERE kind = ?1 AND name = ?2")?
+        .query_row(params![kind, entity.name], |row| row.get(0))?;
+    Ok(id)
+}
+
+/// Replace what a session contributes to the graph. Returns the number of
+/// edges stored.
+pub fn index_session(
+    conn: &Connection,
+    session_id: &str,
+    title: &str,
+    graph: &SessionGraph,
+) -> Result<usize> {
+    conn.execute(
+        "DELETE FROM graph_edges WHERE session_id = ?1",
+        [session_id],
+    )?;
+    let session = node_id(
+        conn,
+        &Entity::new(EntityKind::Session, session_id),
+        Some(title),
+    )?;
+
+    let mut insert = conn.prepare_cached(
+        "INSERT INTO graph_edges (source, target, relation, weight, session_id)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (source, target, relation, session_id)
+         DO UPDATE SET weight = weight + excluded.weight",
+    )?;
+    let mut edges = 0;
+    for (entity, count) in &graph.mentions {
+        let target = node_id(conn, entity, None)?;
+        edges += insert.execute(params![
+            session,
+            target,
+            relation::MENTIONS,
+            count,
+            session_id
+        ])?;
+    }
+    for name in &graph.participants {
+        let person = node_id(conn, &Entity::new(EntityKind::Person, name.as_str()), None)?;
+        edges += insert.execute(params![
+            person,
+            session,
+            relation::PARTICIPATED_IN,
+            1,
+            session_id
+        ])?;
+    }
+    for ((a, b), count) in &graph.related {
+        let (a, b) = (node_id(conn, a, None)?, node_id(conn, b, None)?);
+        edges += insert.execute(params![a, b, relation::CO_OCCURS, count, session_id])?;
+    }
+    for (api, owner) in &graph.part_of {
+        let (api, owner) = (node_id(conn, api, None)?, node_id(conn, owner, None)?);
+        edges += insert.execute(params![api, owner, relation::PART_OF, 1, session_id])?;
+    }
+    Ok(edges)
+}
+
+/// Delete the edges of sessions no longer in the database, then the nodes
+/// left without edges. Returns the number of nodes deleted.
+pub fn prune_nodes(conn: &Connection) -> Result<usize> {
+    conn.execute(
+        "DELETE FROM graph_edges WHERE session_id NOT IN (SELECT id FROM sessions)",
+        [],
+    )?;
+    let deleted = conn.execute(
+        "DELETE FROM graph_nodes WHERE id NOT IN (SELECT source FROM graph_edges)
+                                  AND id NOT IN (SELECT target FROM graph_edges)",
+        [],
+    )?;
+    Ok(deleted)
+}
+
+/// A node of the graph
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphNode {
+    pub id: i64,
+    pub kind: String,
+    pub name: String,
+    /// Display name, the title for sessions
+    pub label: Option<String>,
+}
+
+impl GraphNode {
+    /// The label if there is one, otherwise the name
+    pub fn display_name(&self) -> &str {
+        self.label
+            .as_deref()
+            .filter(|label| !label.is_empty())
+            .unwrap_or(&self.name)
+    }
+}
+
+/// An edge of the graph, with the weights of all sessions added up
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphEdge {
+    pub source: i64,
+    pub target: i64,
+    pub relation: String,
+    pub weight: i64,
+}
+
+/// The graph, or the part of it being exported
+#[derive(Debug, Default, Serialize)]
+pub struct Graph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// A node linked to another, and how
+#[derive(Debug, Clone, Serialize)]
+pub struct Neighbor {
+    pub node: GraphNode,
+    pub relation: String,
+    pub weight: i64,
+}
+
+fn read_node(row: &rusqlite::Row) -> rusqlite::Result<GraphNode> {
+    Ok(GraphNode {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        name: row.get(2)?,
+        label: row.get(3)?,
+    })
+}
+
+/// The nodes of the given kinds (all if empty) and the edges between them
+/// weighing at least `min_weight`
+pub fn load_graph(conn: &Connection, kinds: &[EntityKind], min_weight: i64) -> Result<Graph> {
+    let mut sql = String::from("SELECT id, kind, name, label FROM graph_nodes");
+    if !kinds.is_empty() {
+        let kinds: Vec<String> = kinds
+            .iter()
+            .map(|kind| format!("'{}'", kind.as_str()))
+            .collect();
+        sql.push_str(&format!(" WHERE kind IN ({})", kinds.join(", ")));
+    }
+    sql.push_str(" ORDER BY kind, name");
+    let mut stmt = conn.prepare(&sql)?;
+    let nodes = stmt
+        .query_map([], read_node)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let ids: std::collections::HashSet<i64> = nodes.iter().map(|node| node.id).collect();
+    let mut stmt = conn.prepare(
+        "SELECT source, target, relation, SUM(weight) AS total FROM graph_edges
+         GROUP BY source, target, relation
+         HAVING total >= ?1
+         ORDER BY source, target, relation",
+    )?;
+    let edges = stmt
+        .query_map([min_weight], |row| {
+            Ok(GraphEdge {
+                source: row.get(0)?,
+                target: row.get(1)?,
+                relation: row.get(2)?,
+                weight: row.get(3)?,
+            })
+        })?
+        .filter(|edge| {
+            edge.as_ref().map_or(true, |edge| {
+                ids.contains(&edge.source) && ids.contains(&edge.target)
+            })
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(Graph { nodes, edges })
+}
+
+/// Nodes named `name`, or containing it when none is named exactly that
+pub fn find_nodes(
+    conn: &Connection,
+    name: &str,
+    kind: Option<EntityKind>,
+) -> Result<Vec<GraphNode>> {
+    let kind = kind.map(|kind| kind.as_str());
+    let exact: Option<GraphNode> = conn
+        .query_row(
+            "SELECT id, kind, name, label FROM graph_nodes
+             WHERE (name = ?1 OR label = ?1) AND (?2 IS NULL OR kind = ?2)
+             ORDER BY kind LIMIT 1",
+            params![name, kind],
+            read_node,
+        )
+        .optional()?;
+    if let Some(node) = exact {
+        return Ok(vec![node]);
+    }
+    let mut stmt = conn.prepare(
+        "SELECT id, kind, name, label FROM graph_nodes
+         WHERE (name LIKE '%' || ?1 || '%' OR label LIKE '%' || ?1 || '%')
+           AND (?2 IS NULL OR kind = ?2)
+         ORDER BY kind, name LIMIT 50",
+    )?;
+    let nodes = stmt
+        .query_map(params![name, kind], read_node)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(nodes)
+}
+
+/// The nodes linked to `node_id` either way, heaviest links first
+pub fn neighbors(conn: &Connection, node_id: i64, limit: usize) -> Result<Vec<Neighbor>> {
+    let mut stmt = conn.prepare(
+        "SELECT n.id, n.kind, n.name, n.label, e.relation, SUM(e.weight) AS total
+         FROM graph_edges e
+         JOIN graph_nodes n ON n.id = CASE WHEN e.source = ?1 THEN e.target ELSE e.source END
+         WHERE e.source = ?1 OR e.target = ?1
+         GROUP BY n.id, e.relation
+         ORDER BY total DESC, n.kind, n.name
+         LIMIT ?2",
+    )?;
+    let neighbors = stmt
+        .query_map(params![node_id, limit as i64], |row| {
+            Ok(Neighbor {
+                node: read_node(row)?,
+                relation: row.get(4)?,
+                weight: row.get(5)?,
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(neighbors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_and_query() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE sessions (id TEXT PRIMARY KEY);")
+            .unwrap();
+        conn.execute("INSERT INTO sessions VALUES ('s1'), ('s2')", [])
+            .unwrap();
+        ensure_graph_tables(&conn).unwrap();
+
+        let mut first = SessionGraph::new();
+        first.add_message("src/db.rs calls `rusqlite::Connection::open`");
+        first.add_participant("octocat");
+        index_session(&conn, "s1", "Database setup", &first).unwrap();
+        let mut second = SessionGraph::new();
+        second.add_message("src/db.rs is slow");
+        index_session(&conn, "s2", "Slow queries", &second).unwrap();
+        // Indexing a session again replaces its edges
+        index_session(&conn, "s2", "Slow queries", &second).unwrap();
+
+        let file = &find_nodes(&conn, "db.rs", None).unwrap()[0];
+        assert_eq!(file.name, "src/db.rs");
+        let linked = neighbors(&conn, file.id, 10).unwrap();
+        let sessions: Vec<&str> = linked
+            .iter()
+            .filter(|n| n.relation == relation::MENTIONS)
+            .map(|n| n.node.display_name())
+            .collect();
+        assert_eq!(sessions, ["Database setup", "Slow queries"]);
+
+        let session = &find_nodes(&conn, "Database setup", None).unwrap()[0];
+        assert_eq!(session.kind, "session");
+
+        conn.execute("DELETE FROM sessions WHERE id = 's1'", [])
+            .unwrap();
+        assert!(prune_nodes(&conn).unwrap() > 0);
+        let graph = load_graph(&conn, &[], 1).unwrap();
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.edges.len(), 1);
+        assert_eq!(graph.edges[0].weight, 1);
+    }
+}
@@ -5,6 +5,7 @@
 //! Slack, Discord, Teams, Telegram, SMS, Email
 
 use super::IntegrationResult;
+use crate::models::{self, ChatRequest, ChatSession};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -94,6 +95,566 @@ pub trait SlackProvider: Send + Sync {
     async fn get_user(&self, user_id: &str) -> IntegrationResult;
 }
 
+/// A Slack app, through the Web API, authenticated with its bot token
+/// (`xoxb-...`). Searching and setting a status act as a person and need a
+/// user token (`xoxp-...`) as well.
+pub struct SlackBot {
+    bot_token: String,
+    user_token: Option<String>,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl SlackBot {
+    pub fn new(bot_token: impl Into<String>) -> Self {
+        Self {
+            bot_token: bot_token.into(),
+            user_token: None,
+            base_url: "https://slack.com/api".to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// A client for the tokens under `[integrations.slack]`
+    pub fn from_config() -> Option<Self> {
+        let slack = &crate::config::current().integrations.slack;
+        let bot = Self::new(
+            slack
+                .bot_token
+                .as_deref()
+                .and_then(crate::config::expand_env)?,
+        );
+        Some(
+            bot.with_user_token(
+                slack
+                    .user_token
+                    .as_deref()
+                    .and_then(crate::config::expand_env),
+            ),
+        )
+    }
+
+    pub fn with_user_token(mut self, user_token: Option<String>) -> Self {
+        self.user_token = user_token;
+        self
+    }
+
+    /// Send requests to another server, such as a test double
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    fn url(&self, method: &str) -> String {
+        format!("{}/{}", self.base_url.trim_end_matches('/'), method)
+    }
+
+    /// Slack answers 200 to most failures, with `ok` false and an `error` code
+    async fn send(&self, request: reqwest::RequestBuilder, token: &str) -> IntegrationResult {
+        let response = match request.bearer_auth(token).send().await {
+            Ok(response) => response,
+            Err(e) => return IntegrationResult::err(format!("Cannot reach Slack: {}", e)),
+        };
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        let value: serde_json::Value = match serde_json::from_str(&body) {
+            Ok(value) => value,
+            Err(_) => {
+                return IntegrationResult::err(format!(
+                    "Slack returned {}: {}",
+                    status,
+                    body.trim()
+                ))
+            }
+        };
+        if value["ok"].as_bool() != Some(true) {
+            let error = value["error"].as_str().unwrap_or("unknown error");
+            return IntegrationResult::err(format!("Slack returned {}", error));
+        }
+        IntegrationResult::ok(value)
+    }
+
+    async fn get(&self, method: &str, query: &[(&str, &str)]) -> IntegrationResult {
+        self.send(
+            self.client.get(self.url(method)).query(query),
+            &self.bot_token,
+        )
+        .await
+    }
+
+    async fn post(&self, method: &str, body: serde_json::Value) -> IntegrationResult {
+        self.send(
+            self.client.post(self.url(method)).json(&body),
+            &self.bot_token,
+        )
+        .await
+    }
+
+    fn user_token(&self, what: &str) -> Result<&str, IntegrationResult> {
+        self.user_token.as_deref().ok_or_else(|| {
+            IntegrationResult::err(format!("{} needs a Slack user token (xoxp-...)", what))
+        })
+    }
+
+    /// Every item under `field` of a cursor-paged list
+    async fn get_pages(
+        &self,
+        method: &str,
+        query: &[(&str, &str)],
+        field: &str,
+    ) -> IntegrationResult {
+        let mut items = Vec::new();
+        let mut cursor = String::new();
+        loop {
+            let mut page_query = query.to_vec();
+            page_query.push(("limit", "200"));
+            if !cursor.is_empty() {
+                page_query.push(("cursor", &cursor));
+            }
+            let result = self.get(method, &page_query).await;
+            let Some(page) = result.data.as_ref().filter(|_| result.success) else {
+                return result;
+            };
+            if let Some(page_items) = page[field].as_array() {
+                items.extend(page_items.iter().cloned());
+            }
+            match page["response_metadata"]["next_cursor"].as_str() {
+                Some(next) if !next.is_empty() => cursor = next.to_string(),
+                _ => break,
+            }
+        }
+        IntegrationResult::ok(serde_json::Value::Array(items))
+    }
+
+    /// The ID of a channel given by ID or by name, with or without `#`
+    pub async fn channel_id(&self, channel: &str) -> Result<String, String> {
+        let name = channel.trim_start_matches('#');
+        if is_conversation_id(name) {
+            return Ok(name.to_string());
+        }
+        let channels = slack_data(self.list_channels().await)?;
+        channels
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|c| c["name"].as_str() == Some(name))
+            .and_then(|c| c["id"].as_str())
+            .map(String::from)
+            .ok_or_else(|| format!("No channel #{} that the app can see", name))
+    }
+
+    /// The messages of a thread, its first message first
+    pub async fn thread_replies(&self, channel: &str, ts: &str) -> IntegrationResult {
+        self.get_pages(
+            "conversations.replies",
+            &[("channel", channel), ("ts", ts)],
+            "messages",
+        )
+        .await
+    }
+
+    /// A link to a message
+    pub async fn permalink(&self, channel: &str, ts: &str) -> IntegrationResult {
+        self.get(
+            "chat.getPermalink",
+            &[("channel", channel), ("message_ts", ts)],
+        )
+        .await
+    }
+
+    /// A thread as a session, with the name of its channel. People's
+    /// messages are requests and the messages of bots are the responses to
+    /// them, so a thread with an AI assistant reads like any other chat.
+    pub async fn thread_session(
+        &self,
+        channel: &str,
+        ts: &str,
+    ) -> Result<(ChatSession, String), String> {
+        let channel = self.channel_id(channel).await?;
+        let messages = slack_data(self.thread_replies(&channel, ts).await)?;
+        let messages = messages.as_array().cloned().unwrap_or_default();
+        if messages.is_empty() {
+            return Err(format!("Thread {} in {} has no messages", ts, channel));
+        }
+        let channel_name = match self
+            .get("conversations.info", &[("channel", &channel)])
+            .await
+        {
+            IntegrationResult {
+                success: true,
+                data: Some(info),
+                ..
+            } => info["channel"]["name"]
+                .as_str()
+                .map(|name| format!("#{}", name)),
+            _ => None,
+        }
+        .unwrap_or_else(|| channel.clone());
+
+        let mut names: HashMap<String, String> = HashMap::new();
+        for message in &messages {
+            let Some(user) = message["user"].as_str() else {
+                continue;
+            };
+            if names.contains_key(user) {
+                continue;
+            }
+            let name = match self.get_user(user).await {
+                IntegrationResult {
+                    success: true,
+                    data: Some(info),
+                    ..
+                } => ["display_name", "real_name"]
+                    .iter()
+                    .find_map(|field| {
+                        info["user"]["profile"][field]
+                            .as_str()
+                            .filter(|name| !name.is_empty())
+                    })
+                    .or(info["user"]["name"].as_str())
+                    .map(String::from),
+                _ => None,
+            };
+            names.insert(user.to_string(), name.unwrap_or_else(|| user.to_string()));
+        }
+
+        let mut requests: Vec<ChatRequest> = Vec::new();
+        let mut responder = None;
+        for message in &messages {
+            let text = with_mentions(message["text"].as_str().unwrap_or_default(), &names);
+            let timestamp = message["ts"]
+                .as_str()
+                .and_then(|ts| ts.parse::<f64>().ok())
+                .map(|secs| (secs * 1000.0) as i64);
+            let is_bot = message.get("bot_id").is_some_and(|id| !id.is_null());
+            if is_bot {
+                let bot = message["bot_profile"]["name"]
+                    .as_str()
+                    .or(message["username"].as_str())
+                    .unwrap_or("bot");
+                responder.get_or_insert_with(|| bot.to_string());
+                // A bot's message answers the requests before it; one
+                // that opens the thread is a request nobody asked
+                match requests.last_mut() {
+                    Some(request) => append_response(request, &text),
+                    None => {
+                        requests.push(thread_request(format!("{}:\n\n{}", bot, text), timestamp))
+                    }
+                }
+                continue;
+            }
+            let author = message["user"]
+                .as_str()
+                .and_then(|user| names.get(user))
+                .map(String::as_str)
+                .unwrap_or("someone");
+            let post = format!("@{}:\n\n{}", author, text);
+            match requests.last_mut() {
+                // Messages in a row from people are one request
+                Some(request) if request.response.is_none() => {
+                    if let Some(message) = request.message.as_mut() {
+                        let earlier = message.text.take().unwrap_or_default();
+                        message.text = Some(format!("{}\n\n{}", earlier, post));
+                    }
+                }
+                _ => requests.push(thread_request(post, timestamp)),
+            }
+        }
+
+        let first = messages[0]["text"].as_str().unwrap_or_default();
+        let first_line = with_mentions(first, &names);
+        let first_line = first_line
+            .lines()
+            .find(|l| !l.trim().is_empty())
+            .unwrap_or("");
+        let now = chrono::Utc::now().timestamp_millis();
+        let session = ChatSession {
+            version: 3,
+            session_id: Some(format!("slack-{}-{}", channel.to_lowercase(), ts)),
+            creation_date: requests.first().and_then(|r| r.timestamp).unwrap_or(now),
+            last_message_date: messages
+                .iter()
+                .filter_map(|m| m["ts"].as_str()?.parse::<f64>().ok())
+                .map(|secs| (secs * 1000.0) as i64)
+                .max()
+                .unwrap_or(now),
+            is_imported: true,
+            initial_location: "slack".to_string(),
+            custom_title: Some(format!(
+                "{}: {}",
+                channel_name,
+                crate::text::prefix_by_width(first_line.trim(), 60)
+            )),
+            requester_username: None,
+            requester_avatar_icon_uri: None,
+            responder_username: Some(responder.unwrap_or_else(|| "Slack".to_string())),
+            responder_avatar_icon_uri: None,
+            requests,
+        };
+        Ok((session, channel_name))
+    }
+}
+
+#[async_trait::async_trait]
+impl SlackProvider for SlackBot {
+    async fn send_message(
+        &self,
+        channel: &str,
+        text: &str,
+        thread_ts: Option<&str>,
+    ) -> IntegrationResult {
+        let mut body = serde_json::json!({
+            "channel": channel,
+            "text": text,
+            "unfurl_links": false,
+        });
+        if let Some(ts) = thread_ts {
+            body["thread_ts"] = ts.into();
+        }
+        self.post("chat.postMessage", body).await
+    }
+
+    async fn list_channels(&self) -> IntegrationResult {
+        self.get_pages(
+            "conversations.list",
+            &[
+                ("types", "public_channel,private_channel"),
+                ("exclude_archived", "true"),
+            ],
+            "channels",
+        )
+        .await
+    }
+
+    async fn list_messages(&self, channel: &str, limit: u32) -> IntegrationResult {
+        self.get(
+            "conversations.history",
+            &[("channel", channel), ("limit", &limit.to_string())],
+        )
+        .await
+    }
+
+    async fn upload_file(
+        &self,
+        channel: &str,
+        file_path: &str,
+        comment: Option<&str>,
+    ) -> IntegrationResult {
+        let content = match std::fs::read(file_path) {
+            Ok(content) => content,
+            Err(e) => return IntegrationResult::err(format!("Cannot read {}: {}", file_path, e)),
+        };
+        let filename = std::path::Path::new(file_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| file_path.to_string());
+        // Slack hands out an upload URL, takes the bytes there, then shares them
+        let upload = self
+            .get(
+                "files.getUploadURLExternal",
+                &[
+                    ("filename", &filename),
+                    ("length", &content.len().to_string()),
+                ],
+            )
+            .await;
+        let Some(upload) = upload.data.clone().filter(|_| upload.success) else {
+            return upload;
+        };
+        let url = upload["upload_url"].as_str().unwrap_or_default();
+        if let Err(e) = self
+            .client
+            .post(url)
+            .body(content)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+        {
+            return IntegrationResult::err(format!("Could not upload {}: {}", filename, e));
+        }
+        let mut body = serde_json::json!({
+            "files": [{ "id": upload["file_id"], "title": filename }],
+            "channel_id": channel,
+        });
+        if let Some(comment) = comment {
+            body["initial_comment"] = comment.into();
+        }
+        self.post("files.completeUploadExternal", body).await
+    }
+
+    async fn set_status(&self, status_text: &str, status_emoji: &str) -> IntegrationResult {
+        let token = match self.user_token("Setting a status") {
+            Ok(token) => token,
+            Err(result) => return result,
+        };
+        let body = serde_json::json!({
+            "profile": { "status_text": status_text, "status_emoji": status_emoji }
+        });
+        self.send(
+            self.client.post(self.url("users.profile.set")).json(&body),
+            token,
+        )
+        .await
+    }
+
+    async fn search_messages(&self, query: &str) -> IntegrationResult {
+        let token = match self.user_token("Searching") {
+            Ok(token) => token,
+            Err(result) => return result,
+        };
+        self.send(
+            self.client
+                .get(self.url("search.messages"))
+                .query(&[("query", query)]),
+            token,
+        )
+        .await
+    }
+
+    async fn add_reaction(&self, channel: &str, timestamp: &str, emoji: &str) -> IntegrationResult {
+        let body = serde_json::json!({
+            "channel": channel,
+            "timestamp": timestamp,
+            "name": emoji.trim_matches(':'),
+        });
+        self.post("reactions.add", body).await
+    }
+
+    async fn get_user(&self, user_id: &str) -> IntegrationResult {
+        self.get("users.info", &[("user", user_id)]).await
+    }
+}
+
+/// Whether `name` looks like a channel, group or DM ID rather than a name
+fn is_conversation_id(name: &str) -> bool {
+    name.len() >= 9
+        && name.starts_with(['C', 'G', 'D'])
+        && name
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+}
+
+/// The channel and thread timestamp of a Slack message link
+/// (`https://team.slack.com/archives/C0123/p1712345678123456`, which may
+/// point at a reply with `?thread_ts=`), or of `CHANNEL:TS`
+pub fn parse_thread(reference: &str) -> Option<(String, String)> {
+    let reference = reference.trim();
+    if let Some((_, path)) = reference.split_once("/archives/") {
+        let (path, query) = path.split_once('?').unwrap_or((path, ""));
+        let mut parts = path.trim_end_matches('/').split('/');
+        let channel = parts.next().filter(|c| !c.is_empty())?;
+        let thread_ts = query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("thread_ts="))
+            .map(String::from);
+        let ts = match thread_ts {
+            Some(ts) => ts,
+            // p1712345678123456 is 1712345678.123456
+            None => {
+                let digits = parts.next()?.strip_prefix('p')?;
+                if digits.len() <= 6 || !digits.chars().all(|c| c.is_ascii_digit()) {
+                    return None;
+                }
+                let (secs, micros) = digits.split_at(digits.len() - 6);
+                format!("{}.{}", secs, micros)
+            }
+        };
+        return Some((channel.to_string(), ts));
+    }
+    let (channel, ts) = reference.split_once(':')?;
+    let valid_ts = ts.split_once('.').is_some_and(|(secs, micros)| {
+        !secs.is_empty()
+            && secs.chars().all(|c| c.is_ascii_digit())
+            && micros.chars().all(|c| c.is_ascii_digit())
+    });
+    (!channel.is_empty() && valid_ts).then(|| (channel.to_string(), ts.to_string()))
+}
+
+fn slack_data(result: IntegrationResult) -> Result<serde_json::Value, String> {
+    if result.success {
+        Ok(result.data.unwrap_or_default())
+    } else {
+        Err(result.error.unwrap_or_default())
+    }
+}
+
+/// Slack markup made readable: `<@U1>` as `@name`, links as their URL or
+/// label, and `&amp;`, `&lt;` and `&gt;` unescaped
+fn with_mentions(text: &str, names: &HashMap<String, String>) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        let inner = &rest[start + 1..start + end];
+        let (target, label) = inner.split_once('|').unwrap_or((inner, ""));
+        if let Some(user) = target.strip_prefix('@') {
+            let name = names
+                .get(user)
+                .map(String::as_str)
+                .unwrap_or(if label.is_empty() { user } else { label });
+            out.push('@');
+            out.push_str(name);
+        } else if let Some(channel) = target.strip_prefix('#') {
+            out.push('#');
+            out.push_str(if label.is_empty() { channel } else { label });
+        } else if let Some(special) = target.strip_prefix('!') {
+            out.push('@');
+            out.push_str(special);
+        } else if label.is_empty() {
+            out.push_str(target);
+        } else {
+            out.push_str(&format!("{} ({})", label, target));
+        }
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    out.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+fn thread_request(text: String, timestamp: Option<i64>) -> ChatRequest {
+    ChatRequest {
+        timestamp,
+        message: Some(models::ChatMessage {
+            text: Some(text),
+            parts: None,
+        }),
+        response: None,
+        variable_data: None,
+        request_id: Some(uuid::Uuid::new_v4().to_string()),
+        response_id: None,
+        model_id: None,
+        agent: None,
+        result: None,
+        followups: None,
+        is_canceled: Some(false),
+        content_references: None,
+        code_citations: None,
+        response_markdown_info: None,
+        source_session: None,
+    }
+}
+
+/// Add a bot's message to a request's response
+fn append_response(request: &mut ChatRequest, text: &str) {
+    let answer = match request
+        .response
+        .as_ref()
+        .and_then(|r| r["value"][0]["value"].as_str())
+    {
+        Some(earlier) => format!("{}\n\n{}", earlier, text),
+        None => text.to_string(),
+    };
+    request.response = Some(serde_json::json!({ "value": [{ "value": answer }] }));
+    request
+        .response_id
+        .get_or_insert_with(|| uuid::Uuid::new_v4().to_string());
+}
+
 // =============================================================================
 // Discord
 // =============================================================================
@@ -305,3 +866,167 @@ pub trait NotificationsProvider: Send + Sync {
     async fn schedule(&self, notification: &Notification, at: &str) -> IntegrationResult;
     async fn cancel(&self, notification_id: &str) -> IntegrationResult;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_parse_thread() {
+        let thread = |channel: &str, ts: &str| Some((channel.to_string(), ts.to_string()));
+        assert_eq!(
+            parse_thread("https://team.slack.com/archives/C0123ABCD/p1712345678123456"),
+            thread("C0123ABCD", "1712345678.123456")
+        );
+        // A link to a reply names its thread
+        assert_eq!(
+            parse_thread(
+                "https://team.slack.com/archives/C0123ABCD/p1712345999000100?thread_ts=1712345678.123456&cid=C0123ABCD"
+            ),
+            thread("C0123ABCD", "1712345678.123456")
+        );
+        assert_eq!(
+            parse_thread("debugging:1712345678.123456"),
+            thread("debugging", "1712345678.123456")
+        );
+        assert_eq!(
+            parse_thread("https://team.slack.com/archives/C0123ABCD"),
+            None
+        );
+        assert_eq!(parse_thread("debugging:yesterday"), None);
+        assert_eq!(parse_thread("debugging"), None);
+    }
+
+    #[test]
+    fn test_with_mentions() {
+        let names = HashMap::from([("U1".to_string(), "ann".to_string())]);
+        assert_eq!(
+            with_mentions(
+                "<@U1> see <https://x.dev|the docs> in <#C1|dev>, <!here> &lt;T&gt; &amp; <@U2>",
+                &names
+            ),
+            "@ann see the docs (https://x.dev) in #dev, @here <T> & @U2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_slack_thread_session() {
+        let routes = HashMap::from([
+            (
+                "GET /api/conversations.replies?channel=C0123ABCD&ts=1700000000.000100&limit=200",
+                serde_json::json!({ "ok": true, "messages": [
+                    { "user": "U1", "text": "<@U9> why does the build fail?", "ts": "1700000000.000100" },
+                    { "user": "U1", "text": "It says E0382", "ts": "1700000010.000100" },
+                    { "bot_id": "B1", "bot_profile": { "name": "Helper" },
+                      "text": "A value is used after a move.", "ts": "1700000020.000100" },
+                    { "bot_id": "B1", "text": "Clone it first.", "ts": "1700000030.000100" },
+                    { "user": "U2", "text": "Thanks!", "ts": "1700000040.000100" }
+                ]}),
+            ),
+            (
+                "GET /api/conversations.info?channel=C0123ABCD",
+                serde_json::json!({ "ok": true, "channel": { "name": "debugging" } }),
+            ),
+            (
+                "GET /api/users.info?user=U1",
+                serde_json::json!({ "ok": true, "user": { "name": "ann", "profile": { "display_name": "Ann" } } }),
+            ),
+            (
+                "GET /api/users.info?user=U2",
+                serde_json::json!({ "ok": true, "user": { "name": "bob", "profile": {} } }),
+            ),
+            (
+                "POST /api/chat.postMessage",
+                serde_json::json!({ "ok": true, "ts": "1700000050.000100" }),
+            ),
+        ]);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api", listener.local_addr().unwrap());
+        let (sender, posted) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut reader = BufReader::new(stream.unwrap());
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" {
+                        break;
+                    }
+                    if let Some(v) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        length = v.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                let route = request.split(' ').take(2).collect::<Vec<_>>().join(" ");
+                if route.starts_with("POST") {
+                    sender.send(String::from_utf8(body).unwrap()).unwrap();
+                }
+                let response = routes
+                    .get(route.as_str())
+                    .cloned()
+                    .unwrap_or_else(
+                        || serde_json::json!({ "ok": false, "error": "user_not_found" }),
+                    )
+                    .to_string();
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    response.len(),
+                    response
+                )
+                .unwrap();
+            }
+        });
+
+        let bot = SlackBot::new("xoxb-test").with_base_url(url);
+        let (session, channel) = bot
+            .thread_session("C0123ABCD", "1700000000.000100")
+            .await
+            .unwrap();
+        assert_eq!(channel, "#debugging");
+        assert_eq!(
+            session.session_id.as_deref(),
+            Some("slack-c0123abcd-1700000000.000100")
+        );
+        assert_eq!(session.title(), "#debugging: @U9 why does the build fail?");
+        assert_eq!(session.responder_username.as_deref(), Some("Helper"));
+        assert_eq!(session.creation_date, 1_700_000_000_000);
+        assert_eq!(session.last_message_date, 1_700_000_040_000);
+        // Ann's two messages are one request, answered by both bot messages;
+        // Bob's thanks is a request with no answer
+        assert_eq!(session.requests.len(), 2);
+        assert_eq!(
+            session.user_messages(),
+            [
+                "@Ann:\n\n@U9 why does the build fail?\n\n@Ann:\n\nIt says E0382",
+                "@bob:\n\nThanks!"
+            ]
+        );
+        assert_eq!(
+            session.requests[0].response.as_ref().unwrap()["value"][0]["value"],
+            "A value is used after a move.\n\nClone it first."
+        );
+        assert!(session.requests[1].response.is_none());
+
+        let result = bot.send_message("C0123ABCD", "Summary", None).await;
+        assert!(result.success);
+        let body: serde_json::Value = serde_json::from_str(&posted.recv().unwrap()).unwrap();
+        assert_eq!(body["channel"], "C0123ABCD");
+        assert_eq!(body["text"], "Summary");
+
+        let missing = bot.thread_session("C0123ABCD", "1.2").await.unwrap_err();
+        assert_eq!(missing, "Slack returned user_not_found");
+        assert!(bot
+            .search_messages("build")
+            .await
+            .error
+            .unwrap()
+            .contains("user token"));
+    }
+}
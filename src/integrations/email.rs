@@ -0,0 +1,640 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Email over SMTP
+//!
+//! [`SmtpMailer`] sends an [`Email`] with an HTML body and a plain-text
+//! alternative through the server under `[integrations.smtp]`: over
+//! STARTTLS on port 587 by default, or TLS from the start on port 465, or
+//! unencrypted for a relay on the local network. It signs in with `AUTH
+//! PLAIN` when a username is configured. [`Sendmail`] hands the same
+//! message to a local sendmail-compatible command instead, and [`Mailer`]
+//! is either one.
+//!
+//! Every mailbox is checked before anything is sent, so a recipient or
+//! sender cannot add SMTP commands, headers, or recipients of its own.
+
+use crate::error::{CsmError, Result};
+use base64::Engine;
+use chrono::Utc;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long the server has to answer each command
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How the connection to the server is encrypted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Security {
+    /// Plain connection upgraded with `STARTTLS` (port 587)
+    StartTls,
+    /// TLS from the start (port 465)
+    Tls,
+    /// No encryption, for a relay on the local network (port 25)
+    None,
+}
+
+impl Security {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "starttls" => Some(Self::StartTls),
+            "tls" | "ssl" => Some(Self::Tls),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    fn default_port(self) -> u16 {
+        match self {
+            Self::StartTls => 587,
+            Self::Tls => 465,
+            Self::None => 25,
+        }
+    }
+}
+
+/// A message to send
+#[derive(Debug, Clone, PartialEq)]
+pub struct Email {
+    pub to: Vec<String>,
+    pub subject: String,
+    /// Left empty for a plain-text message
+    pub html: String,
+    /// Shown by clients that do not display HTML
+    pub text: String,
+}
+
+/// An SMTP server or a local command to send mail through
+#[derive(Debug, Clone)]
+pub enum Mailer {
+    Smtp(SmtpMailer),
+    Sendmail(Sendmail),
+}
+
+impl Mailer {
+    pub fn send(&self, email: &Email) -> Result<()> {
+        match self {
+            Self::Smtp(mailer) => mailer.send(email),
+            Self::Sendmail(mailer) => mailer.send(email),
+        }
+    }
+}
+
+/// A sendmail-compatible command to hand mail to
+#[derive(Debug, Clone)]
+pub struct Sendmail {
+    command: String,
+    from: Option<String>,
+}
+
+impl Sendmail {
+    /// Hand mail to `command`, from `from` if given or else the user
+    /// running it
+    pub fn new(command: impl Into<String>, from: Option<String>) -> Self {
+        Self {
+            command: command.into(),
+            from,
+        }
+    }
+
+    /// Send `email` to each of its recipients. They go on the command line
+    /// rather than being read from the message's headers.
+    pub fn send(&self, email: &Email) -> Result<()> {
+        let recipients = recipients(email)?;
+        let message = message(self.from.as_deref(), email)?;
+        let mut child = Command::new(&self.command)
+            .arg("-i")
+            .args(&recipients)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| CsmError::EmailError(format!("failed to run {}: {}", self.command, e)))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(message.as_bytes())?;
+        }
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(CsmError::EmailError(format!(
+                "{} exited with {}",
+                self.command, status
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// An SMTP server to send mail through
+#[derive(Debug, Clone)]
+pub struct SmtpMailer {
+    host: String,
+    port: u16,
+    security: Security,
+    credentials: Option<(String, String)>,
+    from: String,
+}
+
+impl SmtpMailer {
+    /// Send through `host` over STARTTLS on port 587, as `from`
+    /// (`csm@example.com` or `Chasm <csm@example.com>`)
+    pub fn new(host: impl Into<String>, from: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            port: Security::StartTls.default_port(),
+            security: Security::StartTls,
+            credentials: None,
+            from: from.into(),
+        }
+    }
+
+    /// The server under `[integrations.smtp]`
+    pub fn from_config() -> Result<Self> {
+        let smtp = &crate::config::current().integrations.smtp;
+        let missing = |key: &str| {
+            CsmError::ConfigError(format!(
+                "set {} under [integrations.smtp] to send email",
+                key
+            ))
+        };
+        let host = smtp.host.clone().ok_or_else(|| missing("host"))?;
+        let from = smtp.from.clone().ok_or_else(|| missing("from"))?;
+        let mut mailer = Self::new(host, from);
+        if let Some(security) = &smtp.security {
+            mailer = mailer.with_security(Security::parse(security).ok_or_else(|| {
+                CsmError::ConfigError(format!(
+                    "[integrations.smtp] security must be starttls, tls or none, not '{}'",
+                    security
+                ))
+            })?);
+        }
+        if let Some(port) = smtp.port {
+            mailer = mailer.with_port(port);
+        }
+        if let Some(username) = smtp.username.as_deref().and_then(crate::config::expand_env) {
+            let password = match &smtp.password {
+                Some(password) => crate::config::expand_env(password).ok_or_else(|| {
+                    CsmError::ConfigError(format!(
+                        "[integrations.smtp] password reads {}, which is not set",
+                        password
+                    ))
+                })?,
+                None => return Err(missing("password")),
+            };
+            mailer = mailer.with_credentials(username, password);
+        }
+        Ok(mailer)
+    }
+
+    /// Encrypt the connection this way, on its usual port
+    pub fn with_security(mut self, security: Security) -> Self {
+        self.port = security.default_port();
+        self.security = security;
+        self
+    }
+
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Sign in with `AUTH PLAIN`
+    pub fn with_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Send `email` to each of its recipients
+    pub fn send(&self, email: &Email) -> Result<()> {
+        let recipients = recipients(email)?;
+        let from = mailbox_address(&self.from)?;
+        let message = message(Some(&self.from), email)?;
+        let tcp = TcpStream::connect((self.host.as_str(), self.port)).map_err(|e| {
+            CsmError::EmailError(format!(
+                "could not connect to {}:{}: {}",
+                self.host, self.port, e
+            ))
+        })?;
+        tcp.set_read_timeout(Some(TIMEOUT))?;
+        tcp.set_write_timeout(Some(TIMEOUT))?;
+        let stream = match self.security {
+            Security::Tls => Stream::Tls(Box::new(self.tls(tcp)?)),
+            Security::StartTls | Security::None => Stream::Plain(tcp),
+        };
+
+        let mut session = SmtpSession::new(stream);
+        session.expect(220)?;
+        session.command(&format!("EHLO {}", hello_name()), 250)?;
+        if self.security == Security::StartTls {
+            session.command("STARTTLS", 220)?;
+            let Stream::Plain(tcp) = session.stream.into_inner() else {
+                unreachable!("STARTTLS on a TLS connection");
+            };
+            session = SmtpSession::new(Stream::Tls(Box::new(self.tls(tcp)?)));
+            session.command(&format!("EHLO {}", hello_name()), 250)?;
+        }
+        if let Some((username, password)) = &self.credentials {
+            let token = base64::engine::general_purpose::STANDARD
+                .encode(format!("\0{}\0{}", username, password));
+            session.command(&format!("AUTH PLAIN {}", token), 235)?;
+        }
+
+        session.command(&format!("MAIL FROM:<{}>", from), 250)?;
+        for to in recipients {
+            session.command(&format!("RCPT TO:<{}>", to), 250)?;
+        }
+        session.command("DATA", 354)?;
+        let mut data = String::new();
+        for line in message.lines() {
+            // A leading dot is doubled so the server does not read it as the end
+            if line.starts_with('.') {
+                data.push('.');
+            }
+            data.push_str(line);
+            data.push_str("\r\n");
+        }
+        data.push_str(".\r\n");
+        session.send(&data)?;
+        session.expect(250)?;
+        // The message is accepted; a failed goodbye does not change that
+        let _ = session.command("QUIT", 221);
+        Ok(())
+    }
+
+    fn tls(
+        &self,
+        tcp: TcpStream,
+    ) -> Result<rustls::StreamOwned<rustls::ClientConnection, TcpStream>> {
+        let roots = rustls::RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = rustls::ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|e| CsmError::EmailError(e.to_string()))?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let name = rustls::pki_types::ServerName::try_from(self.host.clone())
+            .map_err(|e| CsmError::EmailError(format!("{}: {}", self.host, e)))?;
+        let connection = rustls::ClientConnection::new(Arc::new(config), name)
+            .map_err(|e| CsmError::EmailError(e.to_string()))?;
+        Ok(rustls::StreamOwned::new(connection, tcp))
+    }
+}
+
+/// A connection to the server, encrypted or not
+enum Stream {
+    Plain(TcpStream),
+    Tls(Box<rustls::StreamOwned<rustls::ClientConnection, TcpStream>>),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Plain(stream) => stream.read(buf),
+            Self::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Plain(stream) => stream.write(buf),
+            Self::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Plain(stream) => stream.flush(),
+            Self::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+/// Commands sent and replies read on a connection
+struct SmtpSession {
+    stream: BufReader<Stream>,
+}
+
+impl SmtpSession {
+    fn new(stream: Stream) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+        }
+    }
+
+    fn send(&mut self, data: &str) -> Result<()> {
+        let stream = self.stream.get_mut();
+        stream.write_all(data.as_bytes())?;
+        stream.flush()?;
+        Ok(())
+    }
+
+    /// Send a command and read a reply with the expected code
+    fn command(&mut self, command: &str, code: u16) -> Result<String> {
+        self.send(&format!("{}\r\n", command))?;
+        self.expect(code)
+            .map_err(|e| match command.split(' ').next() {
+                // Keep credentials out of the error
+                Some("AUTH") => CsmError::EmailError(format!("sign-in failed ({})", e)),
+                _ => e,
+            })
+    }
+
+    /// Read a reply, which may span lines (`250-...` up to `250 ...`), and
+    /// fail unless it has the expected code
+    fn expect(&mut self, code: u16) -> Result<String> {
+        let mut reply = String::new();
+        loop {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line)? == 0 {
+                return Err(CsmError::EmailError(
+                    "the server closed the connection".to_string(),
+                ));
+            }
+            let line = line.trim_end();
+            reply.push_str(line);
+            reply.push('\n');
+            if line.as_bytes().get(3) != Some(&b'-') {
+                break;
+            }
+        }
+        match reply.get(..3).and_then(|c| c.parse::<u16>().ok()) {
+            Some(got) if got == code => Ok(reply),
+            _ => Err(CsmError::EmailError(format!(
+                "the server answered: {}",
+                reply.trim_end()
+            ))),
+        }
+    }
+}
+
+/// The name this machine greets the server with
+fn hello_name() -> String {
+    sysinfo::System::host_name()
+        .filter(|name| !name.is_empty() && name.is_ascii())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+/// The address of a mailbox written `Name <address>` or just `address`.
+/// Mailboxes with control characters, stray angle brackets, or an address
+/// that could be read as an option are refused.
+pub fn mailbox_address(mailbox: &str) -> Result<&str> {
+    let invalid = || {
+        CsmError::EmailError(format!(
+            "'{}' is not an email address",
+            mailbox.escape_default()
+        ))
+    };
+    if mailbox.chars().any(char::is_control) {
+        return Err(invalid());
+    }
+    let mailbox = mailbox.trim();
+    let address = match mailbox.strip_suffix('>').and_then(|m| m.rsplit_once('<')) {
+        Some((name, address)) if !name.contains(['<', '>']) => address,
+        Some(_) => return Err(invalid()),
+        None => mailbox,
+    };
+    let bad = |c: char| c.is_whitespace() || matches!(c, '<' | '>' | ',' | '"');
+    if address.starts_with('-') || !address.contains('@') || address.contains(bad) {
+        return Err(invalid());
+    }
+    Ok(address)
+}
+
+/// The addresses `email` goes to
+fn recipients(email: &Email) -> Result<Vec<&str>> {
+    if email.to.is_empty() {
+        return Err(CsmError::EmailError("no recipients".to_string()));
+    }
+    email.to.iter().map(|to| mailbox_address(to)).collect()
+}
+
+/// A header value on one line, encoded as RFC 2047 when it is not plain
+/// ASCII
+fn header_text(text: &str) -> String {
+    let text = text
+        .split(['\r', '\n'])
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if text.is_ascii() {
+        text
+    } else {
+        format!(
+            "=?UTF-8?B?{}?=",
+            base64::engine::general_purpose::STANDARD.encode(text)
+        )
+    }
+}
+
+/// Base64 in lines of 76 characters, as MIME bodies are written
+fn base64_lines(text: &str) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+    let mut lines = String::new();
+    for chunk in encoded.as_bytes().chunks(76) {
+        lines.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        lines.push('\n');
+    }
+    lines
+}
+
+/// The email as sent, with the text and HTML bodies as alternatives, or the
+/// text alone when there is no HTML
+fn message(from: Option<&str>, email: &Email) -> Result<String> {
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    let domain = match from {
+        Some(from) => mailbox_address(from)?
+            .rsplit('@')
+            .next()
+            .unwrap_or("localhost"),
+        None => "localhost",
+    };
+    for to in &email.to {
+        mailbox_address(to)?;
+    }
+    let mut message = String::new();
+    if let Some(from) = from {
+        message.push_str(&format!("From: {}\n", from.trim()));
+    }
+    message.push_str(&format!(
+        "To: {}\nSubject: {}\nDate: {}\nMessage-ID: <{}@{}>\nMIME-Version: 1.0\n",
+        email
+            .to
+            .iter()
+            .map(|to| to.trim())
+            .collect::<Vec<_>>()
+            .join(", "),
+        header_text(&email.subject),
+        Utc::now().to_rfc2822(),
+        id,
+        domain
+    ));
+    if email.html.is_empty() {
+        message.push_str(&format!(
+            "Content-Type: text/plain; charset=utf-8\nContent-Transfer-Encoding: base64\n\n{}",
+            base64_lines(&email.text)
+        ));
+        return Ok(message);
+    }
+
+    let boundary = format!("chasm-{}", id);
+    message.push_str(&format!(
+        "Content-Type: multipart/alternative; boundary=\"{}\"\n\n",
+        boundary
+    ));
+    for (content_type, body) in [("text/plain", &email.text), ("text/html", &email.html)] {
+        message.push_str(&format!(
+            "--{}\nContent-Type: {}; charset=utf-8\nContent-Transfer-Encoding: base64\n\n{}",
+            boundary,
+            content_type,
+            base64_lines(body)
+        ));
+    }
+    message.push_str(&format!("--{}--\n", boundary));
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_address() {
+        assert_eq!(
+            mailbox_address("Chasm <csm@example.com>").unwrap(),
+            "csm@example.com"
+        );
+        assert_eq!(
+            mailbox_address(" me@example.com ").unwrap(),
+            "me@example.com"
+        );
+        assert_eq!(header_text("Weekly digest"), "Weekly digest");
+        assert_eq!(
+            header_text("Digest\r\nBcc: them@example.com"),
+            "Digest Bcc: them@example.com"
+        );
+        assert!(header_text("Résumé").starts_with("=?UTF-8?B?"));
+    }
+
+    #[test]
+    fn test_mailbox_injection_refused() {
+        for mailbox in [
+            "me@example.com>\r\nRCPT TO:<them@example.com",
+            "me@example.com\nBcc: them@example.com",
+            "me@example.com> <them@example.com>",
+            "<me@example.com",
+            "me <you> <me@example.com>",
+            "-oQ/tmp@example.com",
+            "nobody",
+        ] {
+            assert!(
+                mailbox_address(mailbox).is_err(),
+                "{:?} was accepted",
+                mailbox
+            );
+        }
+
+        // Refused before connecting, so nothing reaches a server
+        let email = Email {
+            to: vec!["me@example.com>\r\nDATA".to_string()],
+            subject: "Weekly digest".to_string(),
+            html: String::new(),
+            text: "hello".to_string(),
+        };
+        let err = SmtpMailer::new("127.0.0.1", "csm@example.com")
+            .with_port(1)
+            .send(&email)
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("is not an email address"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_send() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            let mut commands = Vec::new();
+            let mut data = String::new();
+            writer.write_all(b"220 test ESMTP\r\n").unwrap();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0 {
+                    break;
+                }
+                let command = line.trim_end().to_string();
+                let reply: &[u8] = match command.split(' ').next().unwrap() {
+                    "EHLO" => b"250-test\r\n250 AUTH PLAIN\r\n",
+                    "AUTH" => b"235 OK\r\n",
+                    "DATA" => {
+                        writer.write_all(b"354 go on\r\n").unwrap();
+                        loop {
+                            let mut line = String::new();
+                            reader.read_line(&mut line).unwrap();
+                            if line == ".\r\n" {
+                                break;
+                            }
+                            data.push_str(&line);
+                        }
+                        b"250 queued\r\n"
+                    }
+                    "QUIT" => {
+                        writer.write_all(b"221 bye\r\n").unwrap();
+                        commands.push(command);
+                        break;
+                    }
+                    _ => b"250 OK\r\n",
+                };
+                commands.push(command);
+                writer.write_all(reply).unwrap();
+            }
+            (commands, data)
+        });
+
+        let mailer = SmtpMailer::new("127.0.0.1", "Chasm <csm@example.com>")
+            .with_security(Security::None)
+            .with_port(port)
+            .with_credentials("csm", "secret");
+        let email = Email {
+            to: vec!["me@example.com".to_string(), "you@example.com".to_string()],
+            subject: "Weekly digest".to_string(),
+            html: "<p>12 new sessions</p>".to_string(),
+            text: ".12 new sessions".to_string(),
+        };
+        mailer.send(&email).unwrap();
+
+        let (commands, data) = server.join().unwrap();
+        let token = base64::engine::general_purpose::STANDARD.encode("\0csm\0secret");
+        assert_eq!(commands[1], format!("AUTH PLAIN {}", token));
+        assert_eq!(
+            &commands[2..5],
+            [
+                "MAIL FROM:<csm@example.com>",
+                "RCPT TO:<me@example.com>",
+                "RCPT TO:<you@example.com>",
+            ]
+        );
+        assert_eq!(commands.last().unwrap(), "QUIT");
+        assert!(data.contains("Subject: Weekly digest\r\n"));
+        assert!(data.contains("To: me@example.com, you@example.com\r\n"));
+        assert!(data.contains("multipart/alternative"));
+        let encoded = |text: &str| base64::engine::general_purpose::STANDARD.encode(text);
+        assert!(data.contains(&encoded("<p>12 new sessions</p>")));
+        assert!(data.contains(&encoded(".12 new sessions")));
+    }
+}
@@ -0,0 +1,616 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! GitHub: sessions linked to repositories, pull requests and issues
+//!
+//! A session can be linked to the repository, branch and commit it was
+//! working on, and to a pull request or issue, in the `github_links` table
+//! of the harvest database. [`GitHubClient`] posts a summary of a session as
+//! a comment on its pull request, and reads an issue or pull request
+//! discussion as a session so that it can be searched and given as context.
+
+use super::IntegrationResult;
+use crate::error::Result;
+use crate::models::{ChatMessage, ChatRequest, ChatSession};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+
+/// Provider name of sessions read from GitHub discussions
+pub const PROVIDER: &str = "github";
+
+/// Comments asked for per page, the most GitHub gives
+const PER_PAGE: usize = 100;
+
+/// Where a session's work happened on GitHub
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SessionLink {
+    /// `owner/repo`
+    pub repo: String,
+    pub branch: Option<String>,
+    pub commit: Option<String>,
+    pub pull_request: Option<u64>,
+    pub issue: Option<u64>,
+}
+
+impl SessionLink {
+    /// The GitHub repository checked out in `dir`, with its current branch
+    /// and commit, if its `origin` remote is on GitHub
+    pub fn detect(dir: &Path) -> Option<Self> {
+        let repo = crate::projects::git_remote(dir).and_then(|url| github_repo(&url))?;
+        Some(Self {
+            repo,
+            // A detached HEAD has no branch
+            branch: git(dir, &["rev-parse", "--abbrev-ref", "HEAD"]).filter(|b| b != "HEAD"),
+            commit: git(dir, &["rev-parse", "HEAD"]),
+            ..Default::default()
+        })
+    }
+
+    /// `owner/repo#number` of the linked pull request, or else the issue
+    pub fn reference(&self) -> Option<String> {
+        self.pull_request
+            .or(self.issue)
+            .map(|number| format!("{}#{}", self.repo, number))
+    }
+}
+
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|out| !out.is_empty())
+}
+
+/// `owner/repo` of a GitHub remote URL, in any of the forms git takes
+pub fn github_repo(remote: &str) -> Option<String> {
+    let normalized = crate::projects::normalize_remote(remote);
+    let path = normalized.strip_prefix("github.com/")?;
+    match path.split('/').collect::<Vec<_>>().as_slice() {
+        [owner, repo] if !owner.is_empty() && !repo.is_empty() => {
+            Some(format!("{}/{}", owner, repo))
+        }
+        _ => None,
+    }
+}
+
+/// The repository and number of an issue or pull request, given as
+/// `owner/repo#12`, a github.com URL, or `#12` or `12` in `default_repo`
+pub fn parse_reference(reference: &str, default_repo: Option<&str>) -> Option<(String, u64)> {
+    let reference = reference.trim();
+    if let Some(path) = reference
+        .split_once("github.com/")
+        .map(|(_, path)| path.trim_end_matches('/'))
+    {
+        // owner/repo/pull/12, owner/repo/issues/12, maybe with more after
+        let parts: Vec<&str> = path.split(['/', '#', '?']).collect();
+        return match parts.as_slice() {
+            [owner, repo, "pull" | "issues", number, ..] => {
+                Some((format!("{}/{}", owner, repo), number.parse().ok()?))
+            }
+            _ => None,
+        };
+    }
+    let (repo, number) = match reference.rsplit_once('#') {
+        Some(("", number)) => (default_repo?.to_string(), number),
+        Some((repo, number)) if repo.split('/').count() == 2 => (repo.to_string(), number),
+        Some(_) => return None,
+        None => (default_repo?.to_string(), reference),
+    };
+    Some((repo, number.parse().ok()?))
+}
+
+/// GitHub, through its REST API. Reading public repositories works without
+/// a token; commenting needs one.
+pub struct GitHubClient {
+    token: Option<String>,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl GitHubClient {
+    pub fn new(token: Option<String>) -> Self {
+        Self {
+            token: token.filter(|token| !token.is_empty()),
+            base_url: "https://api.github.com".to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// A client for the token in `GITHUB_TOKEN` or `GH_TOKEN`, if either is
+    /// set, and the API in `GITHUB_API_URL` for GitHub Enterprise
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let client = Self::new(var("GITHUB_TOKEN").or_else(|| var("GH_TOKEN")));
+        match var("GITHUB_API_URL") {
+            Some(url) => client.with_base_url(url),
+            None => client,
+        }
+    }
+
+    /// Send requests to another server, such as GitHub Enterprise or a test double
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    pub fn has_token(&self) -> bool {
+        self.token.is_some()
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url.trim_end_matches('/'), path)
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> IntegrationResult {
+        let mut request = request
+            .header(reqwest::header::USER_AGENT, "csm")
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28");
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => return IntegrationResult::err(format!("Cannot reach GitHub: {}", e)),
+        };
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            let message = serde_json::from_str::<Value>(&body)
+                .ok()
+                .and_then(|error| error["message"].as_str().map(String::from))
+                .unwrap_or_else(|| body.trim().to_string());
+            return IntegrationResult::err(format!("GitHub returned {}: {}", status, message));
+        }
+        IntegrationResult::ok(serde_json::from_str(&body).unwrap_or(Value::Null))
+    }
+
+    /// Every item of a paged list
+    async fn send_pages(&self, path: &str) -> IntegrationResult {
+        let mut items = Vec::new();
+        for page in 1.. {
+            let request = self
+                .client
+                .get(self.url(path))
+                .query(&[("per_page", PER_PAGE), ("page", page)]);
+            let result = self.send(request).await;
+            if !result.success {
+                return result;
+            }
+            let page_items = array(result.data.unwrap_or(Value::Null));
+            let last = page_items.len() < PER_PAGE;
+            items.extend(page_items);
+            if last {
+                break;
+            }
+        }
+        IntegrationResult::ok(Value::Array(items))
+    }
+
+    /// An issue or pull request; pull requests have a `pull_request` field
+    pub async fn get_issue(&self, repo: &str, number: u64) -> IntegrationResult {
+        self.send(
+            self.client
+                .get(self.url(&format!("repos/{}/issues/{}", repo, number))),
+        )
+        .await
+    }
+
+    /// The conversation comments of an issue or pull request
+    pub async fn list_comments(&self, repo: &str, number: u64) -> IntegrationResult {
+        self.send_pages(&format!("repos/{}/issues/{}/comments", repo, number))
+            .await
+    }
+
+    /// The comments of a pull request's reviews, on lines of its diff
+    pub async fn list_review_comments(&self, repo: &str, number: u64) -> IntegrationResult {
+        self.send_pages(&format!("repos/{}/pulls/{}/comments", repo, number))
+            .await
+    }
+
+    /// Comment on an issue or pull request
+    pub async fn post_comment(&self, repo: &str, number: u64, body: &str) -> IntegrationResult {
+        let request = self
+            .client
+            .post(self.url(&format!("repos/{}/issues/{}/comments", repo, number)))
+            .json(&serde_json::json!({ "body": body }));
+        self.send(request).await
+    }
+
+    /// An issue or pull request discussion as a session, one request per
+    /// post in the order they were written, with its link
+    pub async fn thread_session(
+        &self,
+        repo: &str,
+        number: u64,
+    ) -> std::result::Result<(ChatSession, SessionLink), String> {
+        let issue = data(self.get_issue(repo, number).await)?;
+        let is_pull = issue.get("pull_request").is_some();
+        let mut posts = vec![issue.clone()];
+        posts.extend(array(data(self.list_comments(repo, number).await)?));
+        if is_pull {
+            posts.extend(array(data(self.list_review_comments(repo, number).await)?));
+        }
+        // RFC 3339 times in UTC sort as text
+        posts[1..].sort_by(|a, b| a["created_at"].as_str().cmp(&b["created_at"].as_str()));
+
+        let requests: Vec<ChatRequest> = posts.iter().map(post_request).collect();
+        let now = Utc::now().timestamp_millis();
+        let session = ChatSession {
+            version: 3,
+            session_id: Some(format!(
+                "github-{}-{}",
+                repo.replace('/', "-").to_lowercase(),
+                number
+            )),
+            creation_date: requests.first().and_then(|r| r.timestamp).unwrap_or(now),
+            last_message_date: requests
+                .iter()
+                .filter_map(|r| r.timestamp)
+                .max()
+                .unwrap_or(now),
+            is_imported: true,
+            initial_location: "github".to_string(),
+            custom_title: Some(format!(
+                "{}#{}: {}",
+                repo,
+                number,
+                issue["title"].as_str().unwrap_or_default()
+            )),
+            requester_username: issue["user"]["login"].as_str().map(String::from),
+            requester_avatar_icon_uri: None,
+            responder_username: Some("GitHub".to_string()),
+            responder_avatar_icon_uri: None,
+            requests,
+        };
+        let link = SessionLink {
+            repo: repo.to_string(),
+            pull_request: is_pull.then_some(number),
+            issue: (!is_pull).then_some(number),
+            ..Default::default()
+        };
+        Ok((session, link))
+    }
+}
+
+fn data(result: IntegrationResult) -> std::result::Result<Value, String> {
+    if result.success {
+        Ok(result.data.unwrap_or(Value::Null))
+    } else {
+        Err(result.error.unwrap_or_default())
+    }
+}
+
+fn array(value: Value) -> Vec<Value> {
+    match value {
+        Value::Array(items) => items,
+        _ => Vec::new(),
+    }
+}
+
+/// One post of a discussion as a request from its author
+fn post_request(post: &Value) -> ChatRequest {
+    let author = post["user"]["login"].as_str().unwrap_or("ghost");
+    let mut text = format!("@{}", author);
+    // Review comments are on a line of a file
+    if let Some(path) = post["path"].as_str() {
+        text.push_str(&format!(" on `{}`", path));
+    }
+    text.push_str(":\n\n");
+    if let Some(title) = post["title"].as_str() {
+        text.push_str(&format!("# {}\n\n", title));
+    }
+    text.push_str(post["body"].as_str().unwrap_or_default());
+    ChatRequest {
+        timestamp: post["created_at"]
+            .as_str()
+            .and_then(|time| chrono::DateTime::parse_from_rfc3339(time).ok())
+            .map(|time| time.timestamp_millis()),
+        message: Some(ChatMessage {
+            text: Some(text),
+            parts: None,
+        }),
+        response: None,
+        variable_data: None,
+        request_id: post["id"].as_u64().map(|id| id.to_string()),
+        response_id: None,
+        model_id: None,
+        agent: None,
+        result: None,
+        followups: None,
+        is_canceled: Some(false),
+        content_references: None,
+        code_citations: None,
+        response_markdown_info: None,
+        source_session: None,
+    }
+}
+
+/// Create the link table in the harvest database if it does not exist
+pub fn ensure_github_links_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS github_links (
+            session_id TEXT PRIMARY KEY,
+            repo TEXT NOT NULL,
+            branch TEXT,
+            commit_sha TEXT,
+            pull_request INTEGER,
+            issue INTEGER,
+            linked_at INTEGER NOT NULL,
+            FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_github_links_repo ON github_links(repo);
+        "#,
+    )?;
+    Ok(())
+}
+
+/// A link as stored, with the title of its session
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredLink {
+    pub session_id: String,
+    pub session_title: String,
+    #[serde(flatten)]
+    pub link: SessionLink,
+    pub linked_at: i64,
+}
+
+/// Link a session, replacing any link it had
+pub fn save_link(conn: &Connection, session_id: &str, link: &SessionLink) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO github_links
+         (session_id, repo, branch, commit_sha, pull_request, issue, linked_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            session_id,
+            link.repo,
+            link.branch,
+            link.commit,
+            link.pull_request.map(|n| n as i64),
+            link.issue.map(|n| n as i64),
+            Utc::now().timestamp_millis(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Remove a session's link. Returns whether it had one.
+pub fn remove_link(conn: &Connection, session_id: &str) -> Result<bool> {
+    let removed = conn.execute(
+        "DELETE FROM github_links WHERE session_id = ?1",
+        [session_id],
+    )?;
+    Ok(removed > 0)
+}
+
+/// The link of a session
+pub fn get_link(conn: &Connection, session_id: &str) -> Result<Option<SessionLink>> {
+    let link = conn
+        .query_row(
+            &format!("{} WHERE l.session_id = ?1", SELECT_LINKS),
+            [session_id],
+            read_link,
+        )
+        .optional()?;
+    Ok(link.map(|stored| stored.link))
+}
+
+/// Links, newest first, optionally only to `repo` and to issue or pull
+/// request `number`
+pub fn list_links(
+    conn: &Connection,
+    repo: Option<&str>,
+    number: Option<u64>,
+) -> Result<Vec<StoredLink>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE (?1 IS NULL OR LOWER(l.repo) = LOWER(?1))
+           AND (?2 IS NULL OR l.pull_request = ?2 OR l.issue = ?2)
+         ORDER BY l.linked_at DESC, l.session_id",
+        SELECT_LINKS
+    ))?;
+    let rows = stmt.query_map(params![repo, number.map(|n| n as i64)], read_link)?;
+    Ok(rows.collect::<std::result::Result<Vec<_>, _>>()?)
+}
+
+const SELECT_LINKS: &str = "SELECT l.session_id, COALESCE(s.title, ''), l.repo, l.branch,
+        l.commit_sha, l.pull_request, l.issue, l.linked_at
+     FROM github_links l LEFT JOIN sessions s ON s.id = l.session_id";
+
+fn read_link(row: &rusqlite::Row) -> rusqlite::Result<StoredLink> {
+    Ok(StoredLink {
+        session_id: row.get(0)?,
+        session_title: row.get(1)?,
+        link: SessionLink {
+            repo: row.get(2)?,
+            branch: row.get(3)?,
+            commit: row.get(4)?,
+            pull_request: row.get::<_, Option<i64>>(5)?.map(|n| n as u64),
+            issue: row.get::<_, Option<i64>>(6)?.map(|n| n as u64),
+        },
+        linked_at: row.get(7)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_parse_reference() {
+        assert_eq!(
+            github_repo("git@github.com:Nervosys/chasm-cli.git"),
+            Some("nervosys/chasm-cli".to_string())
+        );
+        assert_eq!(github_repo("https://gitlab.com/a/b"), None);
+
+        let repo = |r: &str| Some((r.to_string(), 12));
+        assert_eq!(parse_reference("a/b#12", None), repo("a/b"));
+        assert_eq!(parse_reference("#12", Some("a/b")), repo("a/b"));
+        assert_eq!(parse_reference("12", Some("a/b")), repo("a/b"));
+        assert_eq!(
+            parse_reference("https://github.com/a/b/pull/12/files", None),
+            repo("a/b")
+        );
+        assert_eq!(
+            parse_reference("https://github.com/a/b/issues/12#issuecomment-1", None),
+            repo("a/b")
+        );
+        assert_eq!(parse_reference("12", None), None);
+        assert_eq!(parse_reference("a#12", None), None);
+        assert_eq!(parse_reference("https://github.com/a/b", None), None);
+    }
+
+    #[test]
+    fn test_links() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE sessions (id TEXT PRIMARY KEY, title TEXT);")
+            .unwrap();
+        conn.execute("INSERT INTO sessions VALUES ('s1', 'Cache')", [])
+            .unwrap();
+        ensure_github_links_table(&conn).unwrap();
+
+        let link = SessionLink {
+            repo: "a/b".to_string(),
+            branch: Some("cache".to_string()),
+            commit: Some("abc123".to_string()),
+            pull_request: Some(7),
+            issue: None,
+        };
+        save_link(&conn, "s1", &link).unwrap();
+        assert_eq!(get_link(&conn, "s1").unwrap(), Some(link.clone()));
+        assert_eq!(link.reference().as_deref(), Some("a/b#7"));
+
+        let links = list_links(&conn, Some("A/B"), Some(7)).unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].session_title, "Cache");
+        assert!(list_links(&conn, Some("a/b"), Some(8)).unwrap().is_empty());
+        assert!(list_links(&conn, Some("a/c"), None).unwrap().is_empty());
+
+        // Linking again replaces the link
+        let issue = SessionLink {
+            repo: "a/b".to_string(),
+            issue: Some(3),
+            ..Default::default()
+        };
+        save_link(&conn, "s1", &issue).unwrap();
+        assert_eq!(list_links(&conn, None, None).unwrap().len(), 1);
+        assert_eq!(get_link(&conn, "s1").unwrap(), Some(issue));
+        assert!(remove_link(&conn, "s1").unwrap());
+        assert_eq!(get_link(&conn, "s1").unwrap(), None);
+    }
+
+    /// A server answering `routes` by method and path, sending each request
+    /// it gets with its body to the returned channel
+    fn serve(routes: HashMap<&'static str, Value>) -> (String, mpsc::Receiver<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut reader = BufReader::new(stream.unwrap());
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" {
+                        break;
+                    }
+                    if let Some(v) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        length = v.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                let route: Vec<&str> = request.split(' ').take(2).collect();
+                let route = route.join(" ");
+                let (status, response) = match routes.get(route.as_str()) {
+                    Some(response) => ("200 OK", response.to_string()),
+                    None => ("404 Not Found", r#"{"message":"Not Found"}"#.to_string()),
+                };
+                let _ = sender.send((route, String::from_utf8(body).unwrap()));
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    response.len(),
+                    response
+                )
+                .unwrap();
+            }
+        });
+        (url, receiver)
+    }
+
+    #[tokio::test]
+    async fn test_thread_session() {
+        let user = |login: &str| serde_json::json!({ "login": login });
+        let routes = HashMap::from([
+            (
+                "GET /repos/a/b/issues/7",
+                serde_json::json!({
+                    "id": 1, "title": "Cache sessions", "body": "Adds a cache.",
+                    "user": user("ann"), "created_at": "2026-01-01T10:00:00Z",
+                    "pull_request": {}
+                }),
+            ),
+            (
+                "GET /repos/a/b/issues/7/comments?per_page=100&page=1",
+                serde_json::json!([{
+                    "id": 3, "body": "Looks good.", "user": user("bob"),
+                    "created_at": "2026-01-01T12:00:00Z"
+                }]),
+            ),
+            (
+                "GET /repos/a/b/pulls/7/comments?per_page=100&page=1",
+                serde_json::json!([{
+                    "id": 2, "body": "Why 60 seconds?", "user": user("bob"),
+                    "path": "src/cache.rs", "created_at": "2026-01-01T11:00:00Z"
+                }]),
+            ),
+            (
+                "POST /repos/a/b/issues/7/comments",
+                serde_json::json!({ "html_url": "https://github.com/a/b/pull/7#c4" }),
+            ),
+        ]);
+        let (url, requests) = serve(routes);
+        let client = GitHubClient::new(Some("token".to_string())).with_base_url(url);
+
+        let (session, link) = client.thread_session("a/b", 7).await.unwrap();
+        assert_eq!(session.session_id.as_deref(), Some("github-a-b-7"));
+        assert_eq!(session.title(), "a/b#7: Cache sessions");
+        let posts = session.user_messages();
+        assert_eq!(posts.len(), 3);
+        assert_eq!(posts[0], "@ann:\n\n# Cache sessions\n\nAdds a cache.");
+        assert_eq!(posts[1], "@bob on `src/cache.rs`:\n\nWhy 60 seconds?");
+        assert_eq!(posts[2], "@bob:\n\nLooks good.");
+        assert!(session.creation_date < session.last_message_date);
+        assert_eq!(link.reference().as_deref(), Some("a/b#7"));
+        assert_eq!(link.pull_request, Some(7));
+
+        let posted = client.post_comment("a/b", 7, "Summary").await;
+        assert!(posted.success);
+        let post = requests
+            .try_iter()
+            .find(|(route, _)| route.starts_with("POST"))
+            .unwrap();
+        assert_eq!(post.1, r#"{"body":"Summary"}"#);
+
+        let missing = client.thread_session("a/b", 8).await.unwrap_err();
+        assert_eq!(missing, "GitHub returned 404 Not Found: Not Found");
+    }
+}
@@ -0,0 +1,575 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Jira and Linear: issues filed from sessions and action items
+//!
+//! An [`IssueTracker`] creates an issue from a title, a description and
+//! labels. [`JiraIssues`] and [`LinearIssues`] are read from
+//! `[integrations.jira]` and `[integrations.linear]`, and [`tracker`] picks
+//! one. Issues filed for a session are kept in the `session_issues` table of
+//! the harvest database, so that the session is not filed twice; those filed
+//! for an action item are kept as the item's `exported_to`.
+
+use crate::error::{CsmError, Result};
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Names of the trackers, as given to `--tracker`
+pub const TRACKERS: [&str; 2] = ["jira", "linear"];
+
+/// The Jira issue type used when none is configured
+const DEFAULT_ISSUE_TYPE: &str = "Task";
+
+/// How a tracker formats descriptions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Markup {
+    Markdown,
+    /// Jira's wiki markup
+    Jira,
+}
+
+/// An issue to file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NewIssue {
+    pub title: String,
+    /// In the tracker's [`Markup`]
+    pub description: String,
+    pub labels: Vec<String>,
+}
+
+/// An issue as filed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CreatedIssue {
+    /// `jira` or `linear`
+    pub tracker: String,
+    /// Key of the issue, such as `ENG-12`
+    pub key: String,
+    pub url: String,
+}
+
+impl CreatedIssue {
+    /// The issue as an action item's `exported_to`, `jira:ENG-12`
+    pub fn reference(&self) -> String {
+        format!("{}:{}", self.tracker, self.key)
+    }
+}
+
+/// A service issues are filed in
+#[async_trait::async_trait]
+pub trait IssueTracker: Send + Sync {
+    /// `jira` or `linear`
+    fn name(&self) -> &'static str;
+    fn markup(&self) -> Markup;
+    async fn create_issue(&self, issue: &NewIssue) -> std::result::Result<CreatedIssue, String>;
+}
+
+/// The tracker named, or `issue_tracker` under `[integrations]`, or the only
+/// one configured, filing in `project` (a Jira project or Linear team key)
+/// instead of the configured one
+pub fn tracker(name: Option<&str>, project: Option<&str>) -> Result<Box<dyn IssueTracker>> {
+    let integrations = &crate::config::current().integrations;
+    let name = match name.or(integrations.issue_tracker.as_deref()) {
+        Some(name) => name.to_lowercase(),
+        None => {
+            let configured: Vec<&str> = TRACKERS
+                .into_iter()
+                .filter(|tracker| match *tracker {
+                    "jira" => integrations.jira.token.is_some(),
+                    _ => integrations.linear.token.is_some(),
+                })
+                .collect();
+            match configured[..] {
+                [only] => only.to_string(),
+                [] => {
+                    return Err(CsmError::ConfigError(
+                        "No issue tracker is configured; set one up under [integrations.jira] \
+                         or [integrations.linear]"
+                            .to_string(),
+                    ))
+                }
+                _ => {
+                    return Err(CsmError::ConfigError(
+                        "Both Jira and Linear are configured; choose one with --tracker or \
+                         issue_tracker under [integrations]"
+                            .to_string(),
+                    ))
+                }
+            }
+        }
+    };
+    let tracker: Option<Box<dyn IssueTracker>> = match name.as_str() {
+        "jira" => JiraIssues::from_config(project).map(|jira| Box::new(jira) as _),
+        "linear" => LinearIssues::from_config(project).map(|linear| Box::new(linear) as _),
+        other => {
+            return Err(CsmError::ConfigError(format!(
+                "Unknown issue tracker '{}'; use {}",
+                other,
+                TRACKERS.join(" or ")
+            )))
+        }
+    };
+    tracker.ok_or_else(|| {
+        CsmError::ConfigError(match name.as_str() {
+            "jira" => "Set url, token and project under [integrations.jira]".to_string(),
+            _ => "Set token and team under [integrations.linear]".to_string(),
+        })
+    })
+}
+
+/// An issue title: one line, short enough for Jira's 255 characters
+pub fn issue_title(text: &str) -> String {
+    crate::text::truncate(&text.split_whitespace().collect::<Vec<_>>().join(" "), 200)
+}
+
+// =============================================================================
+// Jira
+// =============================================================================
+
+/// Jira, through its REST API. Jira Cloud takes an account email and an API
+/// token; Jira Data Center a personal access token alone.
+pub struct JiraIssues {
+    base_url: String,
+    email: Option<String>,
+    token: String,
+    project: String,
+    issue_type: String,
+    client: reqwest::Client,
+}
+
+impl JiraIssues {
+    /// File issues in `project` of the Jira site at `base_url`
+    pub fn new(
+        base_url: impl Into<String>,
+        email: Option<String>,
+        token: impl Into<String>,
+        project: impl Into<String>,
+    ) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            email,
+            token: token.into(),
+            project: project.into(),
+            issue_type: DEFAULT_ISSUE_TYPE.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// The site of `[integrations.jira]`, filing in `project` if given
+    pub fn from_config(project: Option<&str>) -> Option<Self> {
+        let jira = &crate::config::current().integrations.jira;
+        let jira_issues = Self::new(
+            jira.url.clone()?,
+            jira.email.as_deref().and_then(crate::config::expand_env),
+            jira.token.as_deref().and_then(crate::config::expand_env)?,
+            project.map(String::from).or_else(|| jira.project.clone())?,
+        );
+        Some(match &jira.issue_type {
+            Some(issue_type) => jira_issues.with_issue_type(issue_type),
+            None => jira_issues,
+        })
+    }
+
+    /// File issues of this type instead of tasks
+    pub fn with_issue_type(mut self, issue_type: impl Into<String>) -> Self {
+        self.issue_type = issue_type.into();
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl IssueTracker for JiraIssues {
+    fn name(&self) -> &'static str {
+        "jira"
+    }
+
+    fn markup(&self) -> Markup {
+        Markup::Jira
+    }
+
+    async fn create_issue(&self, issue: &NewIssue) -> std::result::Result<CreatedIssue, String> {
+        // Version 2 takes the description as wiki markup; 3 wants a document
+        let request = self
+            .client
+            .post(format!("{}/rest/api/2/issue", self.base_url))
+            .json(&json!({
+                "fields": {
+                    "project": { "key": self.project },
+                    "issuetype": { "name": self.issue_type },
+                    "summary": issue.title,
+                    "description": issue.description,
+                    "labels": issue.labels,
+                }
+            }));
+        let request = match &self.email {
+            Some(email) => request.basic_auth(email, Some(&self.token)),
+            None => request.bearer_auth(&self.token),
+        };
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Cannot reach Jira: {}", e))?;
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(format!("Jira returned {}: {}", status, body.trim()));
+        }
+        let created: Value = serde_json::from_str(&body).unwrap_or(Value::Null);
+        let key = created["key"]
+            .as_str()
+            .ok_or_else(|| format!("Jira did not return an issue key: {}", body.trim()))?;
+        Ok(CreatedIssue {
+            tracker: self.name().to_string(),
+            key: key.to_string(),
+            url: format!("{}/browse/{}", self.base_url, key),
+        })
+    }
+}
+
+// =============================================================================
+// Linear
+// =============================================================================
+
+/// Linear, through its GraphQL API, authenticated with a personal API key
+/// from Settings > Security & access
+pub struct LinearIssues {
+    token: String,
+    /// Team key, such as `ENG`, or ID
+    team: String,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl LinearIssues {
+    /// File issues for `team`, given by its key or ID
+    pub fn new(token: impl Into<String>, team: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            team: team.into(),
+            base_url: "https://api.linear.app/graphql".to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// The workspace of `[integrations.linear]`, filing for `team` if given
+    pub fn from_config(team: Option<&str>) -> Option<Self> {
+        let linear = &crate::config::current().integrations.linear;
+        Some(Self::new(
+            linear
+                .token
+                .as_deref()
+                .and_then(crate::config::expand_env)?,
+            team.map(String::from).or_else(|| linear.team.clone())?,
+        ))
+    }
+
+    /// Send requests to another server, such as a test double
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// The `data` of a query, or its first error
+    async fn graphql(&self, query: &str, variables: Value) -> std::result::Result<Value, String> {
+        // Personal API keys are sent as they are, OAuth tokens as bearer tokens
+        let authorization = if self.token.starts_with("lin_api_") {
+            self.token.clone()
+        } else {
+            format!("Bearer {}", self.token)
+        };
+        let response = self
+            .client
+            .post(&self.base_url)
+            .header(reqwest::header::AUTHORIZATION, authorization)
+            .json(&json!({ "query": query, "variables": variables }))
+            .send()
+            .await
+            .map_err(|e| format!("Cannot reach Linear: {}", e))?;
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        let result: Value = serde_json::from_str(&body).unwrap_or(Value::Null);
+        if let Some(error) = result["errors"][0]["message"].as_str() {
+            return Err(format!("Linear returned an error: {}", error));
+        }
+        if !status.is_success() {
+            return Err(format!("Linear returned {}: {}", status, body.trim()));
+        }
+        Ok(result["data"].clone())
+    }
+
+    /// The ID of the team, looked up by key unless it is one already
+    async fn team_id(&self) -> std::result::Result<String, String> {
+        if uuid::Uuid::parse_str(&self.team).is_ok() {
+            return Ok(self.team.clone());
+        }
+        let data = self
+            .graphql(
+                "query Team($key: String!) { teams(filter: { key: { eq: $key } }) { nodes { id } } }",
+                json!({ "key": self.team.to_uppercase() }),
+            )
+            .await?;
+        data["teams"]["nodes"][0]["id"]
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| format!("Linear has no team with the key {}", self.team))
+    }
+
+    /// IDs of the workspace's labels with these names; others are left out
+    async fn label_ids(&self, names: &[String]) -> std::result::Result<Vec<String>, String> {
+        if names.is_empty() {
+            return Ok(Vec::new());
+        }
+        let data = self
+            .graphql(
+                "query Labels($names: [String!]) { \
+                 issueLabels(filter: { name: { in: $names } }) { nodes { id name } } }",
+                json!({ "names": names }),
+            )
+            .await?;
+        let mut ids = Vec::new();
+        for name in names {
+            let id = data["issueLabels"]["nodes"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .find(|label| label["name"].as_str() == Some(name.as_str()))
+                .and_then(|label| label["id"].as_str());
+            ids.extend(id.map(String::from));
+        }
+        Ok(ids)
+    }
+}
+
+#[async_trait::async_trait]
+impl IssueTracker for LinearIssues {
+    fn name(&self) -> &'static str {
+        "linear"
+    }
+
+    fn markup(&self) -> Markup {
+        Markup::Markdown
+    }
+
+    async fn create_issue(&self, issue: &NewIssue) -> std::result::Result<CreatedIssue, String> {
+        let team_id = self.team_id().await?;
+        let label_ids = self.label_ids(&issue.labels).await?;
+        let data = self
+            .graphql(
+                "mutation Create($input: IssueCreateInput!) { \
+                 issueCreate(input: $input) { success issue { identifier url } } }",
+                json!({
+                    "input": {
+                        "teamId": team_id,
+                        "title": issue.title,
+                        "description": issue.description,
+                        "labelIds": label_ids,
+                    }
+                }),
+            )
+            .await?;
+        let created = &data["issueCreate"]["issue"];
+        match (created["identifier"].as_str(), created["url"].as_str()) {
+            (Some(key), Some(url)) => Ok(CreatedIssue {
+                tracker: self.name().to_string(),
+                key: key.to_string(),
+                url: url.to_string(),
+            }),
+            _ => Err("Linear did not create the issue".to_string()),
+        }
+    }
+}
+
+// =============================================================================
+// Issues filed for sessions
+// =============================================================================
+
+/// Create the session issue table in the harvest database if it does not
+/// exist
+pub fn ensure_session_issues_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS session_issues (
+            session_id TEXT NOT NULL,
+            tracker TEXT NOT NULL,
+            key TEXT NOT NULL,
+            url TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            PRIMARY KEY (session_id, tracker, key),
+            FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+/// An issue filed for a session, with the session's title
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredIssue {
+    pub session_id: String,
+    pub session_title: String,
+    #[serde(flatten)]
+    pub issue: CreatedIssue,
+    pub created_at: i64,
+}
+
+/// Record an issue filed for a session
+pub fn save_session_issue(conn: &Connection, session_id: &str, issue: &CreatedIssue) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO session_issues (session_id, tracker, key, url, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            session_id,
+            issue.tracker,
+            issue.key,
+            issue.url,
+            Utc::now().timestamp_millis()
+        ],
+    )?;
+    Ok(())
+}
+
+/// Issues filed, newest first, optionally only for sessions starting with
+/// `session` and only in `tracker`
+pub fn list_session_issues(
+    conn: &Connection,
+    session: Option<&str>,
+    tracker: Option<&str>,
+) -> Result<Vec<StoredIssue>> {
+    let mut stmt = conn.prepare(
+        "SELECT i.session_id, COALESCE(s.title, ''), i.tracker, i.key, i.url, i.created_at
+         FROM session_issues i LEFT JOIN sessions s ON s.id = i.session_id
+         WHERE (?1 IS NULL OR i.session_id LIKE ?1 || '%') AND (?2 IS NULL OR i.tracker = ?2)
+         ORDER BY i.created_at DESC, i.key",
+    )?;
+    let rows = stmt.query_map(params![session, tracker], |row| {
+        Ok(StoredIssue {
+            session_id: row.get(0)?,
+            session_title: row.get(1)?,
+            issue: CreatedIssue {
+                tracker: row.get(2)?,
+                key: row.get(3)?,
+                url: row.get(4)?,
+            },
+            created_at: row.get(5)?,
+        })
+    })?;
+    Ok(rows.collect::<std::result::Result<Vec<_>, _>>()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    /// A server giving `responses` in turn, sending each request body it
+    /// gets to the returned channel
+    fn serve(responses: Vec<Value>) -> (String, mpsc::Receiver<Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for (stream, response) in listener.incoming().zip(responses) {
+                let mut reader = BufReader::new(stream.unwrap());
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" {
+                        break;
+                    }
+                    if let Some(v) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        length = v.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                let _ = sender.send(serde_json::from_slice(&body).unwrap_or(Value::Null));
+                let response = response.to_string();
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    response.len(),
+                    response
+                )
+                .unwrap();
+            }
+        });
+        (url, receiver)
+    }
+
+    fn issue() -> NewIssue {
+        NewIssue {
+            title: "Retry failed uploads".to_string(),
+            description: "From a chat session".to_string(),
+            labels: vec!["csm".to_string(), "todo".to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_jira_issue() {
+        let (url, requests) = serve(vec![json!({ "id": "10001", "key": "ENG-12" })]);
+        let jira = JiraIssues::new(format!("{}/", url), None, "token", "ENG");
+        let created = jira.create_issue(&issue()).await.unwrap();
+        assert_eq!(created.key, "ENG-12");
+        assert_eq!(created.url, format!("{}/browse/ENG-12", url));
+        assert_eq!(created.reference(), "jira:ENG-12");
+
+        let fields = requests.recv().unwrap()["fields"].clone();
+        assert_eq!(fields["project"]["key"], "ENG");
+        assert_eq!(fields["issuetype"]["name"], "Task");
+        assert_eq!(fields["summary"], "Retry failed uploads");
+        assert_eq!(fields["labels"], json!(["csm", "todo"]));
+    }
+
+    #[tokio::test]
+    async fn test_linear_issue() {
+        let (url, requests) = serve(vec![
+            json!({ "data": { "teams": { "nodes": [{ "id": "team-1" }] } } }),
+            json!({ "data": { "issueLabels": { "nodes": [{ "id": "label-1", "name": "todo" }] } } }),
+            json!({ "data": { "issueCreate": { "success": true, "issue": {
+                "identifier": "ENG-7", "url": "https://linear.app/x/issue/ENG-7"
+            } } } }),
+            json!({ "data": null, "errors": [{ "message": "Authentication required" }] }),
+        ]);
+        let linear = LinearIssues::new("lin_api_key", "eng").with_base_url(url);
+        let created = linear.create_issue(&issue()).await.unwrap();
+        assert_eq!(created.reference(), "linear:ENG-7");
+        assert_eq!(created.url, "https://linear.app/x/issue/ENG-7");
+
+        assert_eq!(requests.recv().unwrap()["variables"]["key"], "ENG");
+        requests.recv().unwrap();
+        let input = requests.recv().unwrap()["variables"]["input"].clone();
+        assert_eq!(input["teamId"], "team-1");
+        assert_eq!(input["labelIds"], json!(["label-1"]));
+
+        let error = linear.create_issue(&issue()).await.unwrap_err();
+        assert_eq!(error, "Linear returned an error: Authentication required");
+    }
+
+    #[test]
+    fn test_session_issues() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE sessions (id TEXT PRIMARY KEY, title TEXT);")
+            .unwrap();
+        conn.execute("INSERT INTO sessions VALUES ('s1', 'Uploads')", [])
+            .unwrap();
+        ensure_session_issues_table(&conn).unwrap();
+
+        let filed = CreatedIssue {
+            tracker: "jira".to_string(),
+            key: "ENG-12".to_string(),
+            url: "https://x.atlassian.net/browse/ENG-12".to_string(),
+        };
+        save_session_issue(&conn, "s1", &filed).unwrap();
+        let issues = list_session_issues(&conn, Some("s"), None).unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].session_title, "Uploads");
+        assert_eq!(issues[0].issue, filed);
+        assert!(list_session_issues(&conn, None, Some("linear"))
+            .unwrap()
+            .is_empty());
+        assert_eq!(issue_title("A\nlong\n  title"), "A long title");
+    }
+}
@@ -22,7 +22,11 @@
 
 pub mod browser;
 pub mod communication;
+pub mod email;
+pub mod github;
 pub mod hooks;
+pub mod issues;
+pub mod obsidian;
 pub mod productivity;
 pub mod registry;
 pub mod smart_home;
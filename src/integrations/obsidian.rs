@@ -0,0 +1,470 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Obsidian: a vault folder kept in step with the harvest database
+//!
+//! [`ObsidianSync`] writes a note per harvested session, with its ID,
+//! provider, workspace and dates as properties, into a folder per workspace,
+//! and an index note in each folder linking that workspace's sessions. Run
+//! again, it rewrites the notes of sessions harvested since, moves notes of
+//! renamed sessions and deletes those of sessions no longer in the database.
+//! The notes it wrote are listed in a manifest in the folder; nothing else
+//! in the vault is touched.
+
+use crate::datetime::{format, format_date, from_epoch};
+use crate::error::Result;
+use crate::providers::session_format::session_to_markdown;
+use crate::storage::parse_session_json;
+use crate::text::prefix_chars;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Manifest of the notes written, in the synced folder
+pub const MANIFEST: &str = ".chasm-sync.json";
+
+/// Folder of sessions harvested without a workspace
+const NO_WORKSPACE: &str = "Other";
+
+/// Longest note or folder name, in characters, before the session ID
+const MAX_NAME: usize = 80;
+
+/// What a sync changed in the vault
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SyncStats {
+    pub created: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    /// Index notes written or deleted
+    pub indexes: usize,
+    /// Sessions in the database, and so notes in the folder
+    pub sessions: usize,
+}
+
+impl SyncStats {
+    pub fn changed(&self) -> bool {
+        self.created + self.updated + self.deleted + self.indexes > 0
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    /// Session ID -> its note
+    #[serde(default)]
+    sessions: BTreeMap<String, SyncedNote>,
+    /// Index notes, relative to the folder
+    #[serde(default)]
+    indexes: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SyncedNote {
+    /// Path relative to the folder, with `/` separators
+    note: String,
+    /// When the session was harvested, to skip notes that are up to date
+    harvested_at: i64,
+}
+
+/// A harvested session, without its messages
+struct SessionRow {
+    id: String,
+    provider: String,
+    workspace: Option<String>,
+    title: String,
+    messages: i64,
+    created_at: i64,
+    updated_at: i64,
+    harvested_at: i64,
+}
+
+impl SessionRow {
+    fn folder(&self) -> String {
+        workspace_folder(self.workspace.as_deref())
+    }
+
+    fn name(&self) -> String {
+        format!("{} ({})", note_name(&self.title), short_id(&self.id))
+    }
+
+    fn note(&self) -> String {
+        format!("{}/{}.md", self.folder(), self.name())
+    }
+}
+
+/// Keeps a folder of an Obsidian vault in step with a harvest database
+pub struct ObsidianSync {
+    folder: PathBuf,
+}
+
+impl ObsidianSync {
+    /// Sync into `folder`, usually a folder inside a vault
+    pub fn new(folder: impl Into<PathBuf>) -> Self {
+        Self {
+            folder: folder.into(),
+        }
+    }
+
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+
+    /// Create, update and delete notes and indexes to match the harvested
+    /// sessions in `conn`
+    pub fn sync(&self, conn: &Connection) -> Result<SyncStats> {
+        fs::create_dir_all(&self.folder)?;
+        let mut manifest = self.load_manifest()?;
+        let rows = session_rows(conn)?;
+        let mut stats = SyncStats {
+            sessions: rows.len(),
+            ..Default::default()
+        };
+
+        let mut synced = BTreeMap::new();
+        for row in &rows {
+            let note = row.note();
+            let previous = manifest.sessions.remove(&row.id);
+            let current = SyncedNote {
+                note: note.clone(),
+                harvested_at: row.harvested_at,
+            };
+            if previous.as_ref() == Some(&current) && self.path(&note).exists() {
+                synced.insert(row.id.clone(), current);
+                continue;
+            }
+            let session_json: String = conn.query_row(
+                "SELECT session_json FROM sessions WHERE id = ?1",
+                [&row.id],
+                |r| r.get(0),
+            )?;
+            let chat = parse_session_json(&session_json)?;
+            self.write(&note, &render_note(row, &session_to_markdown(&chat)))?;
+            match previous {
+                Some(previous) => {
+                    if previous.note != note {
+                        self.remove(&previous.note)?;
+                    }
+                    stats.updated += 1;
+                }
+                None => stats.created += 1,
+            }
+            synced.insert(row.id.clone(), current);
+        }
+        // Whatever is left is no longer harvested
+        for previous in manifest.sessions.values() {
+            self.remove(&previous.note)?;
+            stats.deleted += 1;
+        }
+
+        let mut workspaces: BTreeMap<String, Vec<&SessionRow>> = BTreeMap::new();
+        for row in &rows {
+            workspaces.entry(row.folder()).or_default().push(row);
+        }
+        let mut indexes = BTreeSet::new();
+        for (folder, rows) in &workspaces {
+            let index = format!("{}/{}.md", folder, folder);
+            let content = render_index(folder, rows);
+            if fs::read_to_string(self.path(&index)).ok().as_deref() != Some(content.as_str()) {
+                self.write(&index, &content)?;
+                stats.indexes += 1;
+            }
+            indexes.insert(index);
+        }
+        for index in manifest.indexes.difference(&indexes) {
+            self.remove(index)?;
+            stats.indexes += 1;
+        }
+
+        self.save_manifest(&Manifest {
+            sessions: synced,
+            indexes,
+        })?;
+        Ok(stats)
+    }
+
+    fn path(&self, relative: &str) -> PathBuf {
+        relative
+            .split('/')
+            .fold(self.folder.clone(), |path, part| path.join(part))
+    }
+
+    fn write(&self, relative: &str, content: &str) -> Result<()> {
+        let path = self.path(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Delete a note, and its folder once that is empty
+    fn remove(&self, relative: &str) -> Result<()> {
+        let path = self.path(relative);
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        if let Some(parent) = path.parent().filter(|parent| *parent != self.folder) {
+            // Fails, as it should, while anything is left in it
+            let _ = fs::remove_dir(parent);
+        }
+        Ok(())
+    }
+
+    fn load_manifest(&self) -> Result<Manifest> {
+        match fs::read_to_string(self.folder.join(MANIFEST)) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Manifest::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save_manifest(&self, manifest: &Manifest) -> Result<()> {
+        fs::write(
+            self.folder.join(MANIFEST),
+            serde_json::to_string_pretty(manifest)?,
+        )?;
+        Ok(())
+    }
+}
+
+fn session_rows(conn: &Connection) -> Result<Vec<SessionRow>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, provider, workspace_name, title, message_count, created_at, updated_at,
+                harvested_at
+         FROM sessions ORDER BY updated_at DESC, id",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(SessionRow {
+                id: row.get(0)?,
+                provider: row.get(1)?,
+                workspace: row.get(2)?,
+                title: row.get(3)?,
+                messages: row.get::<_, Option<i64>>(4)?.unwrap_or(0),
+                created_at: row.get(5)?,
+                updated_at: row.get(6)?,
+                harvested_at: row.get(7)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+/// `text` without the characters Obsidian does not allow in note names and
+/// links, on one line and not too long
+pub fn note_name(text: &str) -> String {
+    let cleaned: String = text
+        .chars()
+        .map(|c| {
+            if c.is_control() || "\\/:*?\"<>|#^[]".contains(c) {
+                ' '
+            } else {
+                c
+            }
+        })
+        .collect();
+    let cleaned = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+    let name = prefix_chars(&cleaned, MAX_NAME)
+        .trim_start_matches('.')
+        .trim_end_matches(['.', ' '])
+        .to_string();
+    if name.is_empty() {
+        "Untitled".to_string()
+    } else {
+        name
+    }
+}
+
+/// Folder of a workspace: the last part of its project path
+fn workspace_folder(workspace: Option<&str>) -> String {
+    workspace
+        .and_then(|ws| ws.rsplit(['/', '\\']).find(|part| !part.is_empty()))
+        .map(note_name)
+        .unwrap_or_else(|| NO_WORKSPACE.to_string())
+}
+
+/// The first part of a UUID, which is enough to tell sessions apart; other
+/// IDs whole
+fn short_id(id: &str) -> String {
+    match uuid::Uuid::parse_str(id) {
+        Ok(_) => prefix_chars(id, 8).to_string(),
+        Err(_) => note_name(id),
+    }
+}
+
+/// A value for YAML frontmatter; JSON strings are YAML strings
+fn yaml(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn iso(ts: i64) -> Option<String> {
+    from_epoch(ts).map(|dt| format(dt, "%Y-%m-%dT%H:%M:%S"))
+}
+
+fn render_note(row: &SessionRow, markdown: &str) -> String {
+    let mut note = String::from("---\n");
+    note.push_str(&format!("session_id: {}\n", yaml(&row.id)));
+    note.push_str(&format!("provider: {}\n", yaml(&row.provider)));
+    note.push_str(&format!(
+        "workspace: {}\n",
+        yaml(&format!("[[{}]]", row.folder()))
+    ));
+    if let Some(created) = iso(row.created_at) {
+        note.push_str(&format!("created: {}\n", created));
+    }
+    if let Some(updated) = iso(row.updated_at) {
+        note.push_str(&format!("updated: {}\n", updated));
+    }
+    note.push_str(&format!("messages: {}\n", row.messages));
+    note.push_str("tags: [chasm]\n---\n\n");
+    note.push_str(markdown);
+    note
+}
+
+fn render_index(folder: &str, rows: &[&SessionRow]) -> String {
+    let mut index = String::from("---\n");
+    if let Some(workspace) = rows.iter().find_map(|row| row.workspace.as_deref()) {
+        index.push_str(&format!("workspace: {}\n", yaml(workspace)));
+    }
+    index.push_str(&format!("sessions: {}\n", rows.len()));
+    index.push_str("tags: [chasm]\n---\n\n");
+    index.push_str(&format!("# {}\n\n", folder));
+    for row in rows {
+        index.push_str(&format!(
+            "- {}[[{}|{}]] ({} message{})\n",
+            from_epoch(row.updated_at)
+                .map(|dt| format!("{} ", format_date(dt)))
+                .unwrap_or_default(),
+            row.name(),
+            note_name(&row.title),
+            row.messages,
+            if row.messages == 1 { "" } else { "s" }
+        ));
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn harvest(conn: &Connection, id: &str, workspace: Option<&str>, title: &str, at: i64) {
+        let chat = serde_json::json!({
+            "sessionId": id,
+            "customTitle": title,
+            "creationDate": 1_700_000_000_000_i64,
+            "lastMessageDate": at,
+            "requests": [{ "message": { "text": format!("About {}", title) } }],
+        });
+        conn.execute(
+            "INSERT OR REPLACE INTO sessions VALUES (?1, 'copilot', ?2, ?3, 1, ?4, ?5, ?5, ?6)",
+            rusqlite::params![
+                id,
+                workspace,
+                title,
+                1_700_000_000_000_i64,
+                at,
+                chat.to_string()
+            ],
+        )
+        .unwrap();
+    }
+
+    fn files(folder: &Path) -> Vec<String> {
+        let mut files: Vec<String> = walkdir::WalkDir::new(folder)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| {
+                let relative = entry.path().strip_prefix(folder).unwrap();
+                relative.to_string_lossy().replace('\\', "/")
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_note_name() {
+        assert_eq!(note_name("Fix [bug] #12: a/b?"), "Fix bug 12 a b");
+        assert_eq!(note_name("..hidden."), "hidden");
+        assert_eq!(note_name(" \n "), "Untitled");
+        assert_eq!(workspace_folder(Some("/home/me/app/")), "app");
+        assert_eq!(workspace_folder(Some("C:\\src\\app")), "app");
+        assert_eq!(workspace_folder(None), "Other");
+        assert_eq!(short_id("0f8fad5b-d9cb-469f-a165-70867728950e"), "0f8fad5b");
+        assert_eq!(short_id("slack-C1-17.01"), "slack-C1-17.01");
+    }
+
+    #[test]
+    fn test_sync() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE sessions (id TEXT PRIMARY KEY, provider TEXT, workspace_name TEXT,
+                title TEXT, message_count INTEGER, created_at INTEGER, updated_at INTEGER,
+                harvested_at INTEGER, session_json TEXT);",
+        )
+        .unwrap();
+        let dir = TempDir::new().unwrap();
+        let sync = ObsidianSync::new(dir.path().join("Chat Sessions"));
+        let folder = sync.folder().to_path_buf();
+
+        harvest(
+            &conn,
+            "s1",
+            Some("/src/app"),
+            "Cache bug",
+            1_700_000_100_000,
+        );
+        harvest(&conn, "s2", None, "Setup", 1_700_000_200_000);
+        let stats = sync.sync(&conn).unwrap();
+        assert_eq!((stats.created, stats.indexes, stats.sessions), (2, 2, 2));
+        assert_eq!(
+            files(&folder),
+            [
+                ".chasm-sync.json",
+                "Other/Other.md",
+                "Other/Setup (s2).md",
+                "app/Cache bug (s1).md",
+                "app/app.md"
+            ]
+        );
+        let note = fs::read_to_string(folder.join("app/Cache bug (s1).md")).unwrap();
+        assert!(note.starts_with("---\nsession_id: \"s1\"\nprovider: \"copilot\"\n"));
+        assert!(note.contains("workspace: \"[[app]]\"\n"));
+        assert!(note.contains("About Cache bug"));
+        let index = fs::read_to_string(folder.join("app/app.md")).unwrap();
+        assert!(index.contains("workspace: \"/src/app\"\n"));
+        assert!(index.contains("[[Cache bug (s1)|Cache bug]] (1 message)"));
+
+        // Nothing harvested since
+        assert!(!sync.sync(&conn).unwrap().changed());
+
+        // A note the user keeps there is left alone
+        fs::write(folder.join("app/Mine.md"), "mine").unwrap();
+        harvest(
+            &conn,
+            "s1",
+            Some("/src/app"),
+            "Cache fix",
+            1_700_000_300_000,
+        );
+        conn.execute("DELETE FROM sessions WHERE id = 's2'", [])
+            .unwrap();
+        let stats = sync.sync(&conn).unwrap();
+        assert_eq!((stats.created, stats.updated, stats.deleted), (0, 1, 1));
+        assert_eq!(
+            files(&folder),
+            [
+                ".chasm-sync.json",
+                "app/Cache fix (s1).md",
+                "app/Mine.md",
+                "app/app.md"
+            ]
+        );
+        assert!(!folder.join("Other").exists());
+    }
+}
@@ -222,6 +222,135 @@ pub trait TasksProvider: Send + Sync {
     async fn list_projects(&self) -> IntegrationResult;
 }
 
+/// Todoist, through its REST API, authenticated with an API token from
+/// Settings > Integrations > Developer
+pub struct TodoistTasks {
+    token: String,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl TodoistTasks {
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            base_url: "https://api.todoist.com/api/v1".to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// A client for the token in `TODOIST_API_TOKEN`
+    pub fn from_env() -> Option<Self> {
+        std::env::var("TODOIST_API_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty())
+            .map(Self::new)
+    }
+
+    /// Send requests to another server, such as a test double
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url.trim_end_matches('/'), path)
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> IntegrationResult {
+        let response = match request.bearer_auth(&self.token).send().await {
+            Ok(response) => response,
+            Err(e) => return IntegrationResult::err(format!("Cannot reach Todoist: {}", e)),
+        };
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return IntegrationResult::err(format!("Todoist returned {}: {}", status, body.trim()));
+        }
+        IntegrationResult::ok(serde_json::from_str(&body).unwrap_or(serde_json::Value::Null))
+    }
+
+    /// The fields of a task as Todoist takes them
+    fn task_body(task: &Task) -> serde_json::Value {
+        // Todoist counts priority up from 1 (normal) to 4 (urgent)
+        let priority = match task.priority {
+            TaskPriority::None | TaskPriority::Low => 1,
+            TaskPriority::Medium => 2,
+            TaskPriority::High => 3,
+            TaskPriority::Urgent => 4,
+        };
+        let mut body = serde_json::json!({
+            "content": task.title,
+            "priority": priority,
+            "labels": task.labels,
+        });
+        if let Some(description) = &task.description {
+            body["description"] = description.clone().into();
+        }
+        if let Some(due) = &task.due_date {
+            body["due_string"] = due.clone().into();
+        }
+        if let Some(project) = &task.project {
+            body["project_id"] = project.clone().into();
+        }
+        body
+    }
+}
+
+#[async_trait::async_trait]
+impl TasksProvider for TodoistTasks {
+    async fn list_tasks(
+        &self,
+        project: Option<&str>,
+        _include_completed: bool,
+    ) -> IntegrationResult {
+        // Only active tasks; completed ones have their own endpoints
+        let mut request = self.client.get(self.url("tasks"));
+        if let Some(project) = project {
+            request = request.query(&[("project_id", project)]);
+        }
+        self.send(request).await
+    }
+
+    async fn get_task(&self, task_id: &str) -> IntegrationResult {
+        self.send(self.client.get(self.url(&format!("tasks/{}", task_id))))
+            .await
+    }
+
+    async fn create_task(&self, task: &Task) -> IntegrationResult {
+        let request = self
+            .client
+            .post(self.url("tasks"))
+            .json(&Self::task_body(task));
+        self.send(request).await
+    }
+
+    async fn update_task(&self, task_id: &str, task: &Task) -> IntegrationResult {
+        let request = self
+            .client
+            .post(self.url(&format!("tasks/{}", task_id)))
+            .json(&Self::task_body(task));
+        self.send(request).await
+    }
+
+    async fn complete_task(&self, task_id: &str) -> IntegrationResult {
+        self.send(
+            self.client
+                .post(self.url(&format!("tasks/{}/close", task_id))),
+        )
+        .await
+    }
+
+    async fn delete_task(&self, task_id: &str) -> IntegrationResult {
+        self.send(self.client.delete(self.url(&format!("tasks/{}", task_id))))
+            .await
+    }
+
+    async fn list_projects(&self) -> IntegrationResult {
+        self.send(self.client.get(self.url("projects"))).await
+    }
+}
+
 // =============================================================================
 // Documents
 // =============================================================================
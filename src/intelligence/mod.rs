@@ -2,8 +2,11 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //! AI Intelligence Module
 
+use crate::config::TopicsConfig;
+use crate::error::{CsmError, Result};
 use crate::models::ChatSession;
 use chrono::{DateTime, Utc};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
@@ -11,46 +14,140 @@ use std::collections::{HashMap, HashSet};
 pub struct Topic {
     pub name: String,
     pub confidence: f32,
+    /// Keywords and patterns that matched
     pub keywords: Vec<String>,
 }
 
+/// Share of a session's words that must match a topic, unless configured
+const MIN_TOPIC_CONFIDENCE: f32 = 0.005;
+
+/// Topics every extractor knows unless `[topics] builtin = false`
+const BUILTIN_TOPICS: &[(&str, &[&str])] = &[
+    ("rust", &["rust", "cargo", "crate"]),
+    ("python", &["python", "pip", "django"]),
+];
+
+/// A topic and what puts a session under it
+struct TopicPattern {
+    name: String,
+    /// Keywords and patterns as written, each with the regex that finds it
+    matchers: Vec<(String, Regex)>,
+}
+
+/// Sorts sessions into topics by the keywords and regexes they match.
+/// Topic names may nest with `/`, as in `lang/rust`; a subtopic is matched
+/// on its own keywords, not its parent's.
 pub struct TopicExtractor {
-    patterns: HashMap<String, Vec<String>>,
+    topics: Vec<TopicPattern>,
     min_confidence: f32,
 }
 
 impl TopicExtractor {
+    /// An extractor with the built-in topics
     pub fn new() -> Self {
-        let mut patterns = HashMap::new();
-        patterns.insert("rust".to_string(), vec!["rust", "cargo", "crate"].into_iter().map(String::from).collect());
-        patterns.insert("python".to_string(), vec!["python", "pip", "django"].into_iter().map(String::from).collect());
-        Self { patterns, min_confidence: 0.1 }
+        let mut extractor = Self {
+            topics: Vec::new(),
+            min_confidence: MIN_TOPIC_CONFIDENCE,
+        };
+        for (name, keywords) in BUILTIN_TOPICS {
+            let keywords: Vec<String> = keywords.iter().map(|k| k.to_string()).collect();
+            extractor
+                .add_topic(name, &keywords, &[])
+                .expect("built-in topics are valid");
+        }
+        extractor
+    }
+
+    /// An extractor with the topics of `[topics]` in the configuration
+    pub fn from_config(config: &TopicsConfig) -> Result<Self> {
+        let mut extractor = Self::new();
+        if config.builtin == Some(false) {
+            extractor.topics.clear();
+        }
+        if let Some(min_confidence) = config.min_confidence {
+            extractor.min_confidence = min_confidence;
+        }
+        for (name, topic) in &config.taxonomy {
+            extractor.add_topic(name, &topic.keywords, &topic.patterns)?;
+        }
+        Ok(extractor)
+    }
+
+    /// Add a topic, replacing any with the same name
+    pub fn add_topic(&mut self, name: &str, keywords: &[String], patterns: &[String]) -> Result<()> {
+        let name = name.trim().trim_matches('/').to_lowercase();
+        if name.is_empty() || name.split('/').any(|part| part.trim().is_empty()) {
+            return Err(CsmError::ConfigError(format!("Invalid topic name '{}'", name)));
+        }
+        let invalid = |e: regex::Error| CsmError::ConfigError(format!("Topic '{}': {}", name, e));
+        let mut matchers = Vec::new();
+        for keyword in keywords.iter().filter(|keyword| !keyword.trim().is_empty()) {
+            matchers.push((keyword.clone(), keyword_regex(keyword.trim()).map_err(invalid)?));
+        }
+        for pattern in patterns {
+            let regex = Regex::new(&format!("(?i){}", pattern)).map_err(invalid)?;
+            matchers.push((pattern.clone(), regex));
+        }
+
+        self.topics.retain(|topic| topic.name != name);
+        self.topics.push(TopicPattern { name, matchers });
+        Ok(())
+    }
+
+    /// Names of the known topics, sorted
+    pub fn topic_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.topics.iter().map(|topic| topic.name.as_str()).collect();
+        names.sort_unstable();
+        names
     }
 
     pub fn extract(&self, session: &ChatSession) -> Vec<Topic> {
-        let content = session.collect_all_text().to_lowercase();
-        let word_count = content.split_whitespace().count().max(1) as f32;
+        self.extract_text(&session.collect_all_text())
+    }
+
+    /// The topics of `text`, most confident first
+    pub fn extract_text(&self, text: &str) -> Vec<Topic> {
+        let word_count = text.split_whitespace().count().max(1) as f32;
         let mut topics = Vec::new();
-        for (name, keywords) in &self.patterns {
+        for topic in &self.topics {
             let mut matched = Vec::new();
             let mut count = 0;
-            for kw in keywords {
-                if content.contains(kw) {
-                    matched.push(kw.clone());
-                    count += content.matches(kw).count();
+            for (source, regex) in &topic.matchers {
+                let hits = regex.find_iter(text).count();
+                if hits > 0 {
+                    matched.push(source.clone());
+                    count += hits;
                 }
             }
             if !matched.is_empty() {
                 let confidence = (count as f32 / word_count).min(1.0);
                 if confidence >= self.min_confidence {
-                    topics.push(Topic { name: name.clone(), confidence, keywords: matched });
+                    topics.push(Topic {
+                        name: topic.name.clone(),
+                        confidence,
+                        keywords: matched,
+                    });
                 }
             }
         }
+        topics.sort_by(|a, b| {
+            b.confidence
+                .total_cmp(&a.confidence)
+                .then_with(|| a.name.cmp(&b.name))
+        });
         topics
     }
 }
 
+/// A regex finding `keyword` as a whole word, ignoring case. Word boundaries
+/// are only required next to word characters, so `c++` and `.net` work.
+fn keyword_regex(keyword: &str) -> std::result::Result<Regex, regex::Error> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let start = if keyword.starts_with(is_word) { r"\b" } else { "" };
+    let end = if keyword.ends_with(is_word) { r"\b" } else { "" };
+    Regex::new(&format!("(?i){}{}{}", start, regex::escape(keyword), end))
+}
+
 impl Default for TopicExtractor { fn default() -> Self { Self::new() } }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -105,6 +202,11 @@ impl Default for InsightsGenerator { fn default() -> Self { Self::new() } }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sentiment { pub score: f32, pub label: String, pub confidence: f32 }
 
+/// Sentiment score below which text is labeled negative
+pub const NEGATIVE_SENTIMENT: f32 = -0.3;
+
+/// Scores how pleased or frustrated text sounds by its positive and
+/// negative words
 pub struct SentimentAnalyzer {
     positive: HashSet<String>,
     negative: HashSet<String>,
@@ -113,19 +215,25 @@ pub struct SentimentAnalyzer {
 impl SentimentAnalyzer {
     pub fn new() -> Self {
         Self {
-            positive: vec!["good", "great", "thanks", "helpful", "works"].into_iter().map(String::from).collect(),
-            negative: vec!["bad", "error", "fail", "wrong", "bug"].into_iter().map(String::from).collect(),
+            positive: vec!["good", "great", "thanks", "thank", "helpful", "works", "worked", "perfect", "awesome", "excellent", "nice", "solved"].into_iter().map(String::from).collect(),
+            negative: vec!["bad", "error", "fail", "failed", "wrong", "bug", "broken", "useless", "frustrating", "annoying", "stuck", "ugh"].into_iter().map(String::from).collect(),
         }
     }
     
+    /// Sentiment of the user's side of a session, where satisfaction or
+    /// frustration with the answers shows
     pub fn analyze(&self, session: &ChatSession) -> Sentiment {
-        let text = session.collect_all_text().to_lowercase();
-        let words: Vec<&str> = text.split_whitespace().collect();
+        self.analyze_text(&session.user_messages().join("\n"))
+    }
+
+    pub fn analyze_text(&self, text: &str) -> Sentiment {
+        let text = text.to_lowercase();
+        let words: Vec<&str> = text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).collect();
         let pos = words.iter().filter(|w| self.positive.contains(**w)).count();
         let neg = words.iter().filter(|w| self.negative.contains(**w)).count();
         let total = pos + neg;
         let score = if total > 0 { (pos as f32 - neg as f32) / total as f32 } else { 0.0 };
-        let label = if score > 0.3 { "positive" } else if score < -0.3 { "negative" } else { "neutral" };
+        let label = if score > 0.3 { "positive" } else if score < NEGATIVE_SENTIMENT { "negative" } else { "neutral" };
         Sentiment { score, label: label.to_string(), confidence: if total > 0 { 0.8 } else { 0.5 } }
     }
 }
@@ -135,15 +243,42 @@ impl Default for SentimentAnalyzer { fn default() -> Self { Self::new() } }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QualityScore { pub overall: u8, pub clarity: u8, pub completeness: u8 }
 
+/// A prompt and what became of it
+#[derive(Debug, Clone, Copy)]
+pub struct Exchange<'a> {
+    pub prompt: &'a str,
+    pub answered: bool,
+    pub canceled: bool,
+}
+
+/// Scores sessions on how clearly they ask (prompt length) and how fully
+/// they are answered (prompts answered and not canceled)
 pub struct QualityScorer;
 
 impl QualityScorer {
     pub fn new() -> Self { Self }
     pub fn score(&self, session: &ChatSession) -> QualityScore {
-        let msgs = session.user_messages();
-        let clarity = if msgs.is_empty() { 50 } else { 70 };
-        let completeness = if session.assistant_responses().is_empty() { 0 } else { 80 };
-        QualityScore { overall: (clarity + completeness) / 2, clarity, completeness }
+        let exchanges: Vec<Exchange> = session.requests.iter().filter_map(|req| {
+            Some(Exchange {
+                prompt: req.message.as_ref()?.text.as_deref()?,
+                answered: req.response.as_ref().is_some_and(|r| !r.is_null()),
+                canceled: req.is_canceled.unwrap_or(false),
+            })
+        }).collect();
+        self.score_exchanges(&exchanges)
+    }
+
+    pub fn score_exchanges(&self, exchanges: &[Exchange]) -> QualityScore {
+        if exchanges.is_empty() {
+            return QualityScore { overall: 25, clarity: 50, completeness: 0 };
+        }
+        let words: usize = exchanges.iter().map(|e| e.prompt.split_whitespace().count()).sum();
+        let avg_words = words as f32 / exchanges.len() as f32;
+        // A one-word prompt scores 44, a full sentence (12+ words) 90
+        let clarity = (40.0 + avg_words * 50.0 / 12.0).min(90.0) as u8;
+        let completed = exchanges.iter().filter(|e| e.answered && !e.canceled).count();
+        let completeness = (completed * 100 / exchanges.len()) as u8;
+        QualityScore { overall: ((clarity as u16 + completeness as u16) / 2) as u8, clarity, completeness }
     }
 }
 
@@ -157,19 +292,582 @@ pub struct SimilarityDetector;
 impl SimilarityDetector {
     pub fn new() -> Self { Self }
     pub fn compare(&self, a: &ChatSession, b: &ChatSession) -> SimilarityResult {
-        let ta = a.collect_all_text().to_lowercase();
-        let tb = b.collect_all_text().to_lowercase();
-        let wa: HashSet<&str> = ta.split_whitespace().collect();
-        let wb: HashSet<&str> = tb.split_whitespace().collect();
-        let inter = wa.intersection(&wb).count();
-        let union = wa.union(&wb).count();
-        let score = if union > 0 { inter as f32 / union as f32 } else { 0.0 };
         SimilarityResult {
             session_a_id: a.session_id.clone().unwrap_or_default(),
             session_b_id: b.session_id.clone().unwrap_or_default(),
-            score,
+            score: jaccard(&words(&a.collect_all_text()), &words(&b.collect_all_text())),
+        }
+    }
+
+    /// Pairs of sessions, given as their IDs and text, that share at least
+    /// `threshold` of their words, most similar first
+    pub fn find_duplicates(
+        &self,
+        sessions: &[(String, String)],
+        threshold: f32,
+    ) -> Vec<SimilarityResult> {
+        let words: Vec<HashSet<String>> = sessions.iter().map(|(_, text)| words(text)).collect();
+        let mut pairs = Vec::new();
+        for i in 0..sessions.len() {
+            for j in i + 1..sessions.len() {
+                let (a, b) = (&words[i], &words[j]);
+                // Sets of very different sizes cannot reach the threshold
+                let (small, large) = (a.len().min(b.len()), a.len().max(b.len()));
+                if large == 0 || (small as f32 / large as f32) < threshold {
+                    continue;
+                }
+                let score = jaccard(a, b);
+                if score >= threshold {
+                    pairs.push(SimilarityResult {
+                        session_a_id: sessions[i].0.clone(),
+                        session_b_id: sessions[j].0.clone(),
+                        score,
+                    });
+                }
+            }
+        }
+        pairs.sort_by(|a, b| b.score.total_cmp(&a.score));
+        pairs
+    }
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.to_lowercase().split_whitespace().map(String::from).collect()
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f32 {
+    let inter = a.intersection(b).count();
+    let union = a.len() + b.len() - inter;
+    if union > 0 { inter as f32 / union as f32 } else { 0.0 }
+}
+
+/// Similarity at which two sessions are copies of one conversation rather
+/// than the same question asked twice
+pub const COPY_SIMILARITY: f32 = 0.95;
+
+/// What to do about a pair of near-duplicate sessions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateAction {
+    /// A copy: the other session already holds everything
+    Delete,
+    /// The same question asked again: the sessions belong together
+    Merge,
+}
+
+impl DuplicateAction {
+    pub fn for_score(score: f32) -> Self {
+        if score >= COPY_SIMILARITY { Self::Delete } else { Self::Merge }
+    }
+}
+
+impl std::fmt::Display for DuplicateAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Delete => write!(f, "delete"),
+            Self::Merge => write!(f, "merge"),
         }
     }
 }
 
 impl Default for SimilarityDetector { fn default() -> Self { Self::new() } }
+
+/// Openings that say how a request is asked rather than what it is about
+const POLITE_OPENINGS: &[&str] = &[
+    "hi", "hey", "hello", "please", "can you", "could you", "would you", "will you", "help me",
+    "i want to", "i need to", "i'd like to", "i would like to",
+];
+
+/// Titles for sessions that only have the default one
+pub struct TitleGenerator {
+    max_width: usize,
+}
+
+impl TitleGenerator {
+    pub fn new() -> Self {
+        Self { max_width: 60 }
+    }
+
+    /// Whether `session` has no title of its own: it is "Untitled", or named
+    /// after a first message that had to be cut short
+    pub fn needs_title(session: &ChatSession) -> bool {
+        if session.custom_title.as_deref().is_some_and(|t| !t.trim().is_empty()) {
+            return false;
+        }
+        let title = session.title();
+        title == "Untitled" || title.ends_with("...") || title.contains('\n')
+    }
+
+    /// A title from the first thing the user asked, if anything was
+    pub fn generate(&self, session: &ChatSession) -> Option<String> {
+        session.user_messages().into_iter().find_map(|message| {
+            // Code and pasted output come after the question
+            let prose = message.split("```").next().unwrap_or_default();
+            let line = prose.lines().map(str::trim).find(|line| !line.is_empty())?;
+            // `#file:src/main.rs` names main.rs; `@workspace` names nothing
+            let words: Vec<&str> = line
+                .split_whitespace()
+                .filter_map(|word| match word.strip_prefix('#') {
+                    Some(reference) => reference
+                        .split_once(':')
+                        .map(|(_, path)| path.rsplit(['/', '\\']).next().unwrap_or(path)),
+                    None if word.starts_with('@') => None,
+                    None => Some(word),
+                })
+                .collect();
+            self.tidy(&first_sentence(strip_openings(&words.join(" "))))
+        })
+    }
+
+    /// `text` as a title: one line, capitalized, cut at a word boundary,
+    /// without quotes or closing punctuation. None when too little is left.
+    pub fn tidy(&self, text: &str) -> Option<String> {
+        let line = text.lines().map(str::trim).find(|line| !line.is_empty())?;
+        let line = line.trim_matches(|c: char| c == '"' || c == '\'' || c == '`' || c == '*');
+        let mut title = String::new();
+        for word in line.split_whitespace() {
+            let candidate = if title.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", title, word)
+            };
+            if crate::text::display_width(&candidate) > self.max_width && !title.is_empty() {
+                break;
+            }
+            title = candidate;
+        }
+        let title = title.trim_end_matches(|c: char| ".,;:!-".contains(c)).trim();
+        if title.chars().filter(|c| c.is_alphanumeric()).count() < 3 {
+            return None;
+        }
+        let mut chars = title.chars();
+        let first = chars.next()?;
+        Some(first.to_uppercase().chain(chars).collect())
+    }
+}
+
+impl Default for TitleGenerator { fn default() -> Self { Self::new() } }
+
+/// Text up to the end of its first sentence, keeping a question mark
+fn first_sentence(text: &str) -> String {
+    for (i, c) in text.char_indices() {
+        let next = text[i + c.len_utf8()..].chars().next();
+        if matches!(c, '.' | '?' | '!') && next.is_none_or(char::is_whitespace) {
+            let end = if c == '?' { i + 1 } else { i };
+            return text[..end].to_string();
+        }
+    }
+    text.to_string()
+}
+
+fn strip_openings(text: &str) -> &str {
+    let mut text = text.trim();
+    loop {
+        let lower = text.to_lowercase();
+        let opening = POLITE_OPENINGS.iter().find(|opening| {
+            lower.starts_with(*opening)
+                && lower[opening.len()..]
+                    .chars()
+                    .next()
+                    .is_none_or(|c| !c.is_alphanumeric())
+        });
+        match opening {
+            Some(opening) => {
+                text = text[opening.len()..]
+                    .trim_start_matches(|c: char| c == ',' || c == '!' || c.is_whitespace());
+            }
+            None => return text,
+        }
+    }
+}
+
+/// What an action item is: something to do, something settled, or something
+/// the user promised
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionKind {
+    Todo,
+    Decision,
+    Commitment,
+}
+
+impl ActionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Todo => "todo",
+            Self::Decision => "decision",
+            Self::Commitment => "commitment",
+        }
+    }
+
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind.to_lowercase().trim_end_matches('s') {
+            "todo" => Some(Self::Todo),
+            "decision" => Some(Self::Decision),
+            "commitment" => Some(Self::Commitment),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ActionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A TODO, decision or commitment found in a conversation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ActionItem {
+    pub kind: ActionKind,
+    pub text: String,
+    /// Who said it: "user" or "assistant"
+    pub role: String,
+    pub message_index: usize,
+}
+
+/// Sentence openings that leave something to do
+const TODO_PHRASES: &[&str] = &[
+    "todo",
+    "fixme",
+    "next step is to",
+    "the next step is to",
+    "we need to",
+    "we still need to",
+    "we should",
+    "you need to",
+    "you'll need to",
+    "you will need to",
+    "you still need to",
+    "remember to",
+    "don't forget to",
+    "still need to",
+    "follow up on",
+];
+
+/// Sentence openings that settle something
+const DECISION_PHRASES: &[&str] = &[
+    "decision:",
+    "we decided to",
+    "we decided on",
+    "decided to",
+    "we'll go with",
+    "we will go with",
+    "let's go with",
+    "we're going with",
+    "we chose",
+    "we'll use",
+    "we will use",
+    "let's use",
+    "we agreed to",
+    "we agreed on",
+    "we settled on",
+];
+
+/// Sentence openings that promise something, when the user says them
+const COMMITMENT_PHRASES: &[&str] = &[
+    "i'll",
+    "i will",
+    "i'm going to",
+    "i am going to",
+    "i promise to",
+    "i plan to",
+];
+
+/// Filler a sentence can start with before its phrase
+const LEAD_INS: &[&str] = &[
+    "so", "ok", "okay", "then", "also", "and", "but", "now", "first", "finally",
+];
+
+/// Headings whose list items are all things to do
+const TODO_HEADINGS: &[&str] = &[
+    "next step",
+    "todo",
+    "to do",
+    "to-do",
+    "follow-up",
+    "follow up",
+    "action item",
+    "remaining work",
+];
+
+/// Finds TODOs, decisions and commitments in a conversation by their wording:
+/// unchecked `- [ ]` boxes, items under a "Next steps" heading, and sentences
+/// that open with phrases such as "we need to", "we decided to" or "I'll"
+pub struct ActionExtractor {
+    /// Shortest item kept, in words
+    pub min_words: usize,
+    /// Longest item kept, in characters
+    pub max_chars: usize,
+}
+
+impl ActionExtractor {
+    pub fn new() -> Self {
+        Self {
+            min_words: 3,
+            max_chars: 240,
+        }
+    }
+
+    /// The items in `messages`, given as (role, text) in conversation order
+    pub fn extract<'a>(
+        &self,
+        messages: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Vec<ActionItem> {
+        let mut items = Vec::new();
+        let mut seen = HashSet::new();
+        for (index, (role, text)) in messages.into_iter().enumerate() {
+            for (kind, text) in self.message_items(role, text) {
+                if seen.insert((kind, text.to_lowercase())) {
+                    items.push(ActionItem {
+                        kind,
+                        text,
+                        role: role.to_string(),
+                        message_index: index,
+                    });
+                }
+            }
+        }
+        items
+    }
+
+    fn message_items(&self, role: &str, text: &str) -> Vec<(ActionKind, String)> {
+        let mut items = Vec::new();
+        let mut under_todo_heading = false;
+        // Every other piece is inside a code block
+        for prose in text.split("```").step_by(2) {
+            for line in prose.lines().map(str::trim) {
+                let (item, is_list_item) = strip_list_marker(line);
+                if line.is_empty() {
+                    continue;
+                }
+                if let Some(rest) = item.strip_prefix("[ ]") {
+                    items.extend(self.clean(rest, 1).map(|text| (ActionKind::Todo, text)));
+                    continue;
+                }
+                if item.starts_with("[x]") || item.starts_with("[X]") {
+                    continue;
+                }
+                if is_list_item && under_todo_heading {
+                    items.extend(self.clean(item, 2).map(|text| (ActionKind::Todo, text)));
+                    continue;
+                }
+                let lower = item.to_lowercase();
+                if lower.trim_end_matches(['*', '_', ' ']).ends_with(':') || line.starts_with('#') {
+                    under_todo_heading =
+                        TODO_HEADINGS.iter().any(|heading| lower.contains(heading));
+                    if under_todo_heading {
+                        continue;
+                    }
+                } else if !is_list_item {
+                    under_todo_heading = false;
+                }
+                for sentence in sentences(item) {
+                    if let Some(kind) = classify(role, sentence) {
+                        items.extend(
+                            self.clean(sentence, self.min_words)
+                                .map(|text| (kind, text)),
+                        );
+                    }
+                }
+            }
+        }
+        items
+    }
+
+    /// `text` without markdown emphasis and closing punctuation, if it is
+    /// long enough to mean something and short enough to be one item
+    fn clean(&self, text: &str, min_words: usize) -> Option<String> {
+        let text = text.replace("**", "").replace("__", "");
+        let text = text.trim().trim_end_matches(['.', ',', ';', ':']).trim();
+        let words = text.split_whitespace().count();
+        (words >= min_words && text.chars().count() <= self.max_chars).then(|| text.to_string())
+    }
+}
+
+impl Default for ActionExtractor { fn default() -> Self { Self::new() } }
+
+/// The kind of item `sentence` is, from how it opens
+fn classify(role: &str, sentence: &str) -> Option<ActionKind> {
+    let lower = sentence
+        .to_lowercase()
+        .replace(['*', '_'], "")
+        .replace('\u{2019}', "'");
+    let mut opening = lower.trim_start();
+    while let Some(rest) = LEAD_INS.iter().find_map(|lead| {
+        opening
+            .strip_prefix(lead)
+            .filter(|rest| rest.starts_with([' ', ',']))
+            .map(|rest| rest.trim_start_matches([' ', ',']))
+    }) {
+        opening = rest;
+    }
+    let starts = |phrases: &[&str]| {
+        phrases.iter().any(|phrase| {
+            opening.starts_with(phrase)
+                && opening[phrase.len()..]
+                    .chars()
+                    .next()
+                    .is_none_or(|c| !c.is_alphanumeric())
+        })
+    };
+    if starts(DECISION_PHRASES) {
+        Some(ActionKind::Decision)
+    } else if starts(TODO_PHRASES) {
+        Some(ActionKind::Todo)
+    } else if role == "user" && starts(COMMITMENT_PHRASES) {
+        Some(ActionKind::Commitment)
+    } else {
+        None
+    }
+}
+
+/// `line` without a leading `-`, `*`, `+` or `1.` list marker, and whether
+/// it had one
+fn strip_list_marker(line: &str) -> (&str, bool) {
+    if let Some(rest) = line
+        .strip_prefix(['-', '*', '+'])
+        .filter(|rest| rest.starts_with(' '))
+    {
+        return (rest.trim_start(), true);
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    match line[digits..].strip_prefix(['.', ')']) {
+        Some(rest) if digits > 0 && rest.starts_with(' ') => (rest.trim_start(), true),
+        _ => (line, false),
+    }
+}
+
+/// The sentences of a line
+fn sentences(line: &str) -> impl Iterator<Item = &str> {
+    let mut start = 0;
+    let mut ends = Vec::new();
+    for (i, c) in line.char_indices() {
+        let next = line[i + c.len_utf8()..].chars().next();
+        if matches!(c, '.' | '?' | '!') && next.is_some_and(char::is_whitespace) {
+            ends.push((start, i + 1));
+            start = i + 1;
+        }
+    }
+    ends.push((start, line.len()));
+    ends.into_iter()
+        .map(move |(start, end)| line[start..end].trim())
+        .filter(|sentence| !sentence.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(first_message: &str) -> ChatSession {
+        serde_json::from_value(serde_json::json!({
+            "requests": [{ "message": { "text": first_message } }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_topic_taxonomy() {
+        let config: TopicsConfig = toml::from_str(
+            r#"
+            builtin = false
+            [taxonomy."lang/rust"]
+            keywords = ["cargo", "borrow checker"]
+            [taxonomy."lang/cpp"]
+            keywords = ["c++"]
+            patterns = ['#include\s*<\w+>']
+            "#,
+        )
+        .unwrap();
+        let extractor = TopicExtractor::from_config(&config).unwrap();
+        assert_eq!(extractor.topic_names(), ["lang/cpp", "lang/rust"]);
+
+        let topics = extractor.extract_text("Cargo fails: the borrow checker rejects my cargo.toml");
+        assert_eq!(topics.len(), 1);
+        assert_eq!(topics[0].name, "lang/rust");
+        assert_eq!(topics[0].keywords, ["cargo", "borrow checker"]);
+        // Whole words only
+        assert!(extractor.extract_text("cargoes of trust").is_empty());
+        let topics = extractor.extract_text("#include <vector> in C++");
+        assert_eq!(topics[0].keywords, ["c++", "#include\\s*<\\w+>"]);
+
+        let invalid: TopicsConfig = toml::from_str("[taxonomy.bad]\npatterns = ['(']").unwrap();
+        assert!(TopicExtractor::from_config(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_sentiment_and_quality() {
+        let sentiment = SentimentAnalyzer::new();
+        assert_eq!(sentiment.analyze_text("Thanks, that works!").label, "positive");
+        assert_eq!(sentiment.analyze_text("Still broken. Useless.").label, "negative");
+        assert_eq!(sentiment.analyze_text("Rename the module").label, "neutral");
+
+        let scorer = QualityScorer::new();
+        let exchange = |prompt, answered, canceled| Exchange { prompt, answered, canceled };
+        let score = scorer.score_exchanges(&[
+            exchange("Explain why the borrow checker rejects this closure in my parser", true, false),
+            exchange("and now?", true, true),
+        ]);
+        assert_eq!(score.completeness, 50);
+        assert_eq!(score.clarity, 67);
+        assert_eq!(score.overall, 58);
+        assert_eq!(scorer.score_exchanges(&[]).overall, 25);
+    }
+
+    #[test]
+    fn test_generate_title() {
+        let titles = TitleGenerator::new();
+        let long = session(
+            "Hi! Can you help me figure out why #file:main.rs panics on startup? It started after the last upgrade and I have no idea where to look.",
+        );
+        assert!(TitleGenerator::needs_title(&long));
+        assert_eq!(
+            titles.generate(&long).as_deref(),
+            Some("Figure out why main.rs panics on startup?")
+        );
+
+        let code = session("please fix this.\n```rust\nfn main() {}\n```");
+        assert_eq!(titles.generate(&code).as_deref(), Some("Fix this"));
+        assert!(!TitleGenerator::needs_title(&session("Fix the login bug")));
+        assert_eq!(titles.generate(&session("ok")), None);
+        assert_eq!(
+            titles.tidy("\"Debugging a startup panic.\"").as_deref(),
+            Some("Debugging a startup panic")
+        );
+    }
+
+    #[test]
+    fn test_extract_action_items() {
+        let messages = [
+            ("user", "We decided to use SQLite for the cache. I'll write the migration tomorrow."),
+            (
+                "assistant",
+                "Here it is:\n```rust\n// TODO: handle errors\n```\n\n**Next steps:**\n1. Add an index on `session_id`\n2. Run the benchmarks\n\n- [ ] Update the README\n- [x] Create the table\n\nI'll leave the schema alone. You need to restart the server afterwards.",
+            ),
+        ];
+        let items = ActionExtractor::new().extract(messages);
+        let found: Vec<(ActionKind, &str)> = items
+            .iter()
+            .map(|item| (item.kind, item.text.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    ActionKind::Decision,
+                    "We decided to use SQLite for the cache"
+                ),
+                (ActionKind::Commitment, "I'll write the migration tomorrow"),
+                (ActionKind::Todo, "Add an index on `session_id`"),
+                (ActionKind::Todo, "Run the benchmarks"),
+                (ActionKind::Todo, "Update the README"),
+                (
+                    ActionKind::Todo,
+                    "You need to restart the server afterwards"
+                ),
+            ]
+        );
+        assert_eq!(items[2].message_index, 1);
+        assert_eq!(ActionKind::parse("todos"), Some(ActionKind::Todo));
+    }
+}
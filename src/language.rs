@@ -0,0 +1,128 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Detecting the language messages are written in
+//!
+//! Harvest stores the ISO 639-3 code of each message's language in
+//! `messages_v2.language` and the language most of a session is written in
+//! in `sessions.language`, which `csm harvest search --lang` filters on.
+//!
+//! Chinese, Japanese, Thai, Khmer and Burmese are written without spaces
+//! between words, so the word tokenizer of `messages_fts` sees a sentence as
+//! one token. Messages in those languages are also indexed by trigram in
+//! `messages_trigram`, which search uses for queries in their scripts.
+
+use crate::error::Result;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rusqlite::Connection;
+use whatlang::Lang;
+
+/// Fewest letters in a message whose language is worth guessing
+const MIN_LETTERS: usize = 10;
+
+/// Languages written without spaces between words
+pub const UNSPACED_LANGUAGES: &[&str] = &["cmn", "jpn", "tha", "khm", "mya"];
+
+/// Code blocks and inline code, whose keywords say nothing about the
+/// language of the prose around them
+static CODE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)```.*?(?:```|$)|`[^`\n]*`").unwrap());
+
+/// Add the language columns to an existing harvest database
+pub fn ensure_language_columns(conn: &Connection) -> Result<()> {
+    crate::commands::add_column_if_missing(conn, "messages_v2", "language", "TEXT")?;
+    crate::commands::add_column_if_missing(conn, "sessions", "language", "TEXT")?;
+    conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_sessions_language ON sessions(language);")?;
+    Ok(())
+}
+
+/// The ISO 639-3 code of the language `text` is written in, if it can be
+/// told. Code is ignored; a guess between languages sharing a script that
+/// is not reliable, or made on too few letters, gives `None`.
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let prose = CODE.replace_all(text, " ");
+    let info = whatlang::detect(&prose)?;
+    // A script used by one language (Hangul, kana, Thai...) settles it
+    if info.script().langs().len() == 1 {
+        return Some(info.lang().code());
+    }
+    let letters = prose.chars().filter(|c| c.is_alphabetic()).count();
+    (letters >= MIN_LETTERS && info.is_reliable()).then(|| info.lang().code())
+}
+
+/// Whether `language` is written without spaces between words
+pub fn is_unspaced(language: &str) -> bool {
+    UNSPACED_LANGUAGES.contains(&language)
+}
+
+/// [`UNSPACED_LANGUAGES`] as SQL string literals, for `language IN (...)`
+pub fn unspaced_sql_list() -> String {
+    UNSPACED_LANGUAGES
+        .iter()
+        .map(|code| format!("'{}'", code))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Whether `text` has characters of a script written without spaces
+/// between words: Han, kana, Thai, Khmer or Myanmar
+pub fn has_unspaced_script(text: &str) -> bool {
+    text.chars().any(|c| {
+        matches!(c,
+            '\u{3040}'..='\u{30FF}'     // Hiragana, Katakana
+            | '\u{3400}'..='\u{4DBF}'   // CJK Extension A
+            | '\u{4E00}'..='\u{9FFF}'   // CJK Unified Ideographs
+            | '\u{0E00}'..='\u{0E7F}'   // Thai
+            | '\u{1780}'..='\u{17FF}'   // Khmer
+            | '\u{1000}'..='\u{109F}'   // Myanmar
+        )
+    })
+}
+
+/// The ISO 639-3 code for a language given by code (`spa`) or by name in
+/// English or in the language itself (`Spanish`, `Español`)
+pub fn parse_language(name: &str) -> Option<&'static str> {
+    let name = name.trim().to_lowercase();
+    if let Some(lang) = Lang::from_code(name.as_str()) {
+        return Some(lang.code());
+    }
+    Lang::all()
+        .iter()
+        .find(|lang| lang.eng_name().to_lowercase() == name || lang.name().to_lowercase() == name)
+        .map(|lang| lang.code())
+}
+
+/// The English name of the language with ISO 639-3 code `code`
+pub fn language_name(code: &str) -> Option<&'static str> {
+    Lang::from_code(code).map(|lang| lang.eng_name())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(
+            detect_language("¿Por qué el compilador rechaza esta referencia prestada?"),
+            Some("spa")
+        );
+        assert_eq!(
+            detect_language("このエラーはどういう意味ですか？借用チェッカーが拒否します"),
+            Some("jpn")
+        );
+        assert_eq!(detect_language("ありがとう"), Some("jpn"));
+        // Mostly code, with too little prose to judge
+        assert_eq!(
+            detect_language("Fix:\n```rust\nlet x = vec![1, 2, 3];\nprintln!(\"{:?}\", x);\n```"),
+            None
+        );
+
+        assert!(has_unspaced_script("rust 所有権"));
+        assert!(!has_unspaced_script("Überprüfung"));
+        assert_eq!(parse_language("Spanish"), Some("spa"));
+        assert_eq!(parse_language("deu"), Some("deu"));
+        assert_eq!(parse_language("日本語"), Some("jpn"));
+        assert_eq!(parse_language("klingon"), None);
+        assert_eq!(language_name("jpn"), Some("Japanese"));
+    }
+}
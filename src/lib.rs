@@ -42,28 +42,42 @@
 #![allow(clippy::upper_case_acronyms)]
 #![allow(clippy::type_complexity)]
 
+pub mod actions;
 pub mod agency;
 pub mod analytics;
 pub mod automation;
+pub mod backup;
 pub mod browser;
 pub mod cli;
 pub mod cloud_sync;
 pub mod commands;
+pub mod config;
 pub mod database;
+pub mod datetime;
 pub mod encryption;
 pub mod error;
+pub mod graph;
 pub mod integrations;
 pub mod intelligence;
+pub mod language;
 pub mod mcp;
 pub mod models;
+pub mod notifications;
+pub mod pack;
 pub mod plugins;
+pub mod projects;
 pub mod providers;
+pub mod reviews;
 pub mod routing;
 pub mod scaling;
+pub mod search;
+pub mod session_diff;
 pub mod storage;
 pub mod sync;
 pub mod teams;
 pub mod telemetry;
+pub mod text;
+pub mod topics;
 pub mod tui;
 pub mod workspace;
 
@@ -9,28 +9,54 @@
 #![allow(dead_code)]
 #![allow(unused_imports)]
 
+mod actions;
 mod agency;
+mod analytics;
+mod automation;
 mod api;
+mod backup;
 mod browser;
 mod cli;
+mod cloud_sync;
 mod commands;
+mod config;
 mod database;
+mod datetime;
+mod encryption;
 mod error;
+mod graph;
+mod integrations;
+mod intelligence;
+mod language;
 mod mcp;
 mod models;
+mod notifications;
+mod pack;
+mod plugins;
+mod projects;
 mod providers;
+mod reviews;
+mod routing;
+mod search;
+mod session_diff;
 mod storage;
 mod telemetry;
+mod text;
+mod topics;
 mod tui;
 mod workspace;
 
 use anyhow::Result;
 use clap::Parser;
 use cli::{
-    AgencyCommands, ApiCommands, Cli, Commands, DetectCommands, ExportCommands, FetchCommands,
-    FindCommands, GitCommands, HarvestCommands, HarvestGitCommands, ImportCommands, ListCommands,
-    MergeCommands, MigrationCommands, MoveCommands, ProviderCommands, RunCommands, ShowCommands,
-    TelemetryCommands,
+    ActionsCommands, AgencyCommands, AgencyRemoteCommands, ApiCommands, ApiKeyCommands,
+    ApiUserCommands, AuditCommands, AutomationCommands, AutomationPackCommands, BackupCommands, Cli,
+    Commands, ConfigCommands, DetectCommands, DiffCommands, ExportCommands, ExtractCommands,
+    FetchCommands, FindCommands, GitCommands, GithubCommands, GraphCommands, HarvestCommands,
+    HarvestGitCommands, ImportCommands, IntelligenceCommands, IssueCommands, ListCommands,
+    McpCommands, MergeCommands, MigrationCommands, MoveCommands, NotifyCommands, ObsidianCommands,
+    PackCommands, PluginCommands, ProjectCommands, ProviderCommands, ReportCommands,
+    RoutingCommands, RunCommands, ShowCommands, SlackCommands, TelemetryCommands,
 };
 
 /// Get the current directory name as a default pattern
@@ -43,6 +69,9 @@ fn get_current_dir_name() -> String {
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    if cli.refresh_providers {
+        providers::cache::force_refresh();
+    }
 
     match cli.command {
         // ====================================================================
@@ -92,6 +121,7 @@ fn main() -> Result<()> {
                 before,
                 date,
                 all,
+                project,
                 provider,
                 all_providers,
                 limit,
@@ -106,6 +136,7 @@ fn main() -> Result<()> {
                     before.as_deref(),
                     date.as_deref(),
                     all,
+                    project.as_deref(),
                     provider.as_deref(),
                     all_providers,
                     limit,
@@ -126,6 +157,7 @@ fn main() -> Result<()> {
                     None,
                     None,
                     false,
+                    None,  // project
                     None,  // provider
                     false, // all_providers
                     50,
@@ -297,8 +329,21 @@ fn main() -> Result<()> {
                 force,
                 no_backup,
             ),
+            Some(MergeCommands::Project {
+                project,
+                title,
+                target_path,
+                force,
+                no_backup,
+            }) => commands::merge_by_project(
+                &project,
+                title.as_deref(),
+                target_path.as_deref(),
+                force,
+                no_backup,
+            ),
             None => {
-                eprintln!("Usage: csm merge <workspace|workspaces|sessions|path|provider|providers|all> ...");
+                eprintln!("Usage: csm merge <workspace|workspaces|sessions|path|provider|providers|all|project> ...");
                 eprintln!("Run 'csm merge --help' for more information.");
                 Ok(())
             }
@@ -357,6 +402,29 @@ fn main() -> Result<()> {
             }
         },
 
+        // ====================================================================
+        // Extract Commands
+        // ====================================================================
+        Commands::Extract { command } => match command {
+            ExtractCommands::Code {
+                session,
+                out,
+                path,
+                lang,
+                dry_run,
+                force,
+                json,
+            } => commands::extract_code(
+                &session,
+                &out,
+                path.as_deref(),
+                lang.as_deref(),
+                dry_run,
+                force,
+                json,
+            ),
+        },
+
         // ====================================================================
         // Move Commands
         // ====================================================================
@@ -507,15 +575,17 @@ fn main() -> Result<()> {
             Some(DetectCommands::Session { session_id, path }) => {
                 commands::detect_session(&session_id, path.as_deref())
             }
-            Some(DetectCommands::All { path, verbose }) => {
-                commands::detect_all(path.as_deref(), verbose)
-            }
+            Some(DetectCommands::All {
+                path,
+                verbose,
+                json,
+            }) => commands::detect_all(path.as_deref(), verbose, json),
             Some(DetectCommands::Orphaned { path, recover }) => {
                 commands::detect_orphaned(path.as_deref(), recover)
             }
             None => {
                 // Default to detect all for current directory
-                commands::detect_all(None, false)
+                commands::detect_all(None, false, false)
             }
         },
 
@@ -545,17 +615,22 @@ fn main() -> Result<()> {
                 commit,
                 message.as_deref(),
             ),
+            HarvestCommands::Watch { path, debounce } => {
+                commands::harvest_watch(path.as_deref(), debounce)
+            }
             HarvestCommands::Status { path } => commands::harvest_status(path.as_deref()),
             HarvestCommands::List {
                 path,
                 provider,
                 limit,
                 search,
+                topic,
             } => commands::harvest_list(
                 path.as_deref(),
                 provider.as_deref(),
                 limit,
                 search.as_deref(),
+                topic.as_deref(),
             ),
             HarvestCommands::Export {
                 output,
@@ -604,8 +679,39 @@ fn main() -> Result<()> {
                 query,
                 path,
                 provider,
+                workspace,
+                after,
+                before,
+                tag,
+                topic,
+                lang,
+                semantic,
+                facets,
                 limit,
-            } => commands::harvest_search(path.as_deref(), &query, provider.as_deref(), limit),
+            } => {
+                let filters = search::SearchFilters {
+                    providers: provider,
+                    workspace,
+                    tags: tag,
+                    topics: topic,
+                    ..Default::default()
+                }
+                .with_dates(after.as_deref(), before.as_deref())?
+                .with_language(lang.as_deref())?;
+                let options = search::SearchOptions {
+                    query,
+                    mode: if semantic {
+                        search::SearchMode::Semantic
+                    } else {
+                        search::SearchMode::Text
+                    },
+                    filters,
+                    offset: 0,
+                    limit,
+                    facets,
+                };
+                commands::harvest_search(path.as_deref(), &options)
+            }
             HarvestCommands::Git { command: git_cmd } => match git_cmd {
                 HarvestGitCommands::Init { path } => commands::harvest_git_init(path.as_deref()),
                 HarvestGitCommands::Commit { path, message } => {
@@ -623,6 +729,15 @@ fn main() -> Result<()> {
             },
         },
 
+        Commands::Diff { command } => match command {
+            DiffCommands::Session {
+                session,
+                against,
+                path,
+                json,
+            } => commands::diff_session(path.as_deref(), &session, &against, json),
+        },
+
         // ====================================================================
         // Recover Commands
         // ====================================================================
@@ -683,6 +798,13 @@ fn main() -> Result<()> {
                 verbose,
                 json,
             } => commands::recover_detect(&file, verbose, json),
+            cli::RecoverCommands::Wizard {
+                path,
+                yes,
+                export,
+                dry_run,
+                force,
+            } => commands::recover_wizard(path.as_deref(), yes, export.as_deref(), dry_run, force),
         },
 
         // ====================================================================
@@ -715,24 +837,238 @@ fn main() -> Result<()> {
                 host,
                 port,
                 database,
+                anonymous,
+                rate_limit,
+                rate_burst,
+                max_body_mb,
+                tls_cert,
+                tls_key,
+                tls_self_signed,
+                daemon,
+                log_file,
+                log_max_mb,
+                shutdown_timeout,
+                grpc_port,
+                service_name,
             } => {
+                let defaults = api::ServerConfig::default();
+                let database_path = database.unwrap_or(defaults.database_path.clone());
+                if daemon {
+                    let log_path = log_file
+                        .as_deref()
+                        .map(std::path::PathBuf::from)
+                        .unwrap_or_else(|| {
+                            api::daemon::default_log_path(std::path::Path::new(&database_path))
+                        });
+                    // The background copy gets the same options, minus --daemon
+                    let mut args: Vec<std::ffi::OsString> = std::env::args_os()
+                        .skip(1)
+                        .filter(|arg| arg != "--daemon")
+                        .collect();
+                    if log_file.is_none() {
+                        args.push("--log-file".into());
+                        args.push(log_path.clone().into());
+                    }
+                    return api::daemon::spawn_daemon(
+                        args,
+                        std::path::Path::new(&database_path),
+                        &log_path,
+                    );
+                }
+                if let Some(path) = &log_file {
+                    api::daemon::redirect_output(
+                        std::path::Path::new(path),
+                        log_max_mb * 1024 * 1024,
+                    )?;
+                }
+                let tls = match (tls_cert, tls_key) {
+                    (Some(cert), Some(key)) => Some(api::TlsSource::Files {
+                        cert: cert.into(),
+                        key: key.into(),
+                    }),
+                    _ if tls_self_signed => Some(api::TlsSource::SelfSigned),
+                    _ => None,
+                };
                 let config = api::ServerConfig {
                     host,
                     port,
-                    database_path: database.unwrap_or_else(|| {
-                        dirs::data_local_dir()
-                            .map(|p| p.join("csm").join("csm.db").to_string_lossy().to_string())
-                            .unwrap_or_else(|| "csm.db".to_string())
-                    }),
-                    ..Default::default()
+                    database_path,
+                    anonymous: anonymous.parse()?,
+                    rate_limit: api::RateLimitConfig {
+                        requests_per_minute: rate_limit,
+                        burst: rate_burst,
+                        max_body_bytes: max_body_mb * 1024 * 1024,
+                    },
+                    tls,
+                    shutdown_timeout,
+                    grpc_port,
+                    ..defaults
                 };
 
+                if let Some(name) = service_name {
+                    #[cfg(windows)]
+                    return api::service::run_windows_service(&name, config);
+                    #[cfg(not(windows))]
+                    anyhow::bail!(
+                        "--service-name is only used by the Windows service manager ({})",
+                        name
+                    );
+                }
+
                 // Create tokio runtime and run the server
                 let rt = tokio::runtime::Builder::new_multi_thread()
                     .enable_all()
                     .build()?;
                 rt.block_on(api::start_server(config))
             }
+            ApiCommands::InstallService {
+                name,
+                system,
+                dry_run,
+                mut serve_args,
+            } => {
+                // Check the options now rather than when the service first starts
+                let serve = Cli::try_parse_from(
+                    ["chasm", "api", "serve"]
+                        .into_iter()
+                        .map(String::from)
+                        .chain(serve_args.iter().cloned()),
+                )?;
+                let Commands::Api {
+                    command:
+                        ApiCommands::Serve {
+                            database,
+                            daemon,
+                            log_file,
+                            ..
+                        },
+                } = serve.command
+                else {
+                    unreachable!("parsed as api serve");
+                };
+                if daemon {
+                    anyhow::bail!("Services run in the foreground; remove --daemon");
+                }
+                // Services run as another user or from another directory, so
+                // the database and log must not depend on either
+                let database = match database {
+                    Some(path) if !std::path::Path::new(&path).is_absolute() => {
+                        anyhow::bail!("Use an absolute --database path for a service")
+                    }
+                    Some(path) => path,
+                    None => {
+                        let path = api::ServerConfig::default().database_path;
+                        serve_args.extend(["--database".to_string(), path.clone()]);
+                        path
+                    }
+                };
+                // systemd keeps service output in the journal; elsewhere it would be lost
+                if log_file.is_none() && !cfg!(target_os = "linux") {
+                    let log = api::daemon::default_log_path(std::path::Path::new(&database));
+                    serve_args
+                        .extend(["--log-file".to_string(), log.to_string_lossy().to_string()]);
+                }
+                let spec = api::service::ServiceSpec {
+                    name,
+                    exe: std::env::current_exe()?,
+                    serve_args,
+                    system,
+                };
+                api::service::install(&spec, dry_run)
+            }
+            ApiCommands::UninstallService { name, system } => {
+                api::service::uninstall(&name, system)
+            }
+            ApiCommands::Keys { command } => {
+                let database = |path: Option<String>| {
+                    path.map(std::path::PathBuf::from)
+                        .unwrap_or_else(|| api::ServerConfig::default().database_path.into())
+                };
+                match command {
+                    ApiKeyCommands::Create {
+                        name,
+                        user,
+                        role,
+                        database: db,
+                    } => api::keys::keys_create(
+                        &database(db),
+                        &name,
+                        user.as_deref(),
+                        role.as_deref(),
+                    ),
+                    ApiKeyCommands::List { json, database: db } => {
+                        api::keys::keys_list(&database(db), json)
+                    }
+                    ApiKeyCommands::Revoke { key, database: db } => {
+                        api::keys::keys_revoke(&database(db), &key)
+                    }
+                }
+            }
+            ApiCommands::Users { command } => match command {
+                ApiUserCommands::SetRole {
+                    user,
+                    role,
+                    database: db,
+                } => {
+                    let database = db
+                        .map(std::path::PathBuf::from)
+                        .unwrap_or_else(|| api::ServerConfig::default().database_path.into());
+                    api::rbac::users_set_role(&database, &user, &role)
+                }
+            },
+        },
+
+        Commands::Routing { command } => match command {
+            RoutingCommands::Status { server, json } => commands::routing_status(&server, json),
+            RoutingCommands::Policy { workspace } => {
+                commands::routing_policy(workspace.as_deref())
+            }
+            RoutingCommands::Audit {
+                workspace,
+                denied,
+                limit,
+                json,
+                database,
+            } => commands::routing_audit(
+                database.as_deref(),
+                workspace.as_deref(),
+                denied,
+                limit,
+                json,
+            ),
+        },
+
+        Commands::Audit { command } => match command {
+            AuditCommands::List {
+                actor,
+                method,
+                resource,
+                after,
+                before,
+                limit,
+                json,
+                database,
+            } => {
+                let (resource_type, resource_id) = match resource {
+                    Some(resource) => match resource.split_once(':') {
+                        Some((kind, id)) => (Some(kind.to_string()), Some(id.to_string())),
+                        None => (Some(resource), None),
+                    },
+                    None => (None, None),
+                };
+                let filter = api::audit_log::AuditFilter {
+                    actor,
+                    method,
+                    resource_type,
+                    resource_id,
+                    ..Default::default()
+                }
+                .with_dates(after.as_deref(), before.as_deref())?;
+                let database = database
+                    .map(std::path::PathBuf::from)
+                    .unwrap_or_else(|| api::ServerConfig::default().database_path.into());
+                api::audit_log::audit_list(&database, &filter, limit, json)
+            }
         },
 
         // ====================================================================
@@ -747,18 +1083,153 @@ fn main() -> Result<()> {
                 prompt,
                 model,
                 orchestration,
+                images,
+                files,
                 verbose,
-            } => commands::run_agent(&agent, &prompt, model.as_deref(), &orchestration, verbose),
+            } => commands::run_agent(
+                &agent,
+                &prompt,
+                model.as_deref(),
+                orchestration.as_deref(),
+                &images,
+                &files,
+                verbose,
+            ),
+            AgencyCommands::Resume {
+                session_id,
+                prompt,
+                agent,
+                model,
+                no_save,
+                verbose,
+            } => commands::resume_session(
+                &session_id,
+                &prompt,
+                &agent,
+                model.as_deref(),
+                !no_save,
+                verbose,
+            ),
+            AgencyCommands::Remote {
+                server,
+                token,
+                command,
+            } => {
+                let client = || commands::remote_client(server.as_deref(), token.as_deref());
+                match command {
+                    AgencyRemoteCommands::Submit {
+                        prompt,
+                        agent,
+                        model,
+                        ssh: Some(host),
+                        ..
+                    } => commands::remote_submit_ssh(&host, &agent, &prompt, model.as_deref()),
+                    AgencyRemoteCommands::Submit {
+                        prompt,
+                        agent,
+                        model,
+                        detach,
+                        fetch,
+                        ..
+                    } => commands::remote_submit(
+                        &client()?,
+                        &agent,
+                        &prompt,
+                        model,
+                        detach,
+                        fetch.as_deref(),
+                    ),
+                    AgencyRemoteCommands::List => commands::remote_list(&client()?),
+                    AgencyRemoteCommands::Status { task_id } => {
+                        commands::remote_status(&client()?, &task_id)
+                    }
+                    AgencyRemoteCommands::Logs { task_id } => {
+                        commands::remote_logs(&client()?, &task_id)
+                    }
+                    AgencyRemoteCommands::Fetch { task_id, name, out } => {
+                        commands::remote_fetch(&client()?, &task_id, name.as_deref(), &out)
+                    }
+                    AgencyRemoteCommands::Cancel { task_id } => {
+                        commands::remote_cancel(&client()?, &task_id)
+                    }
+                }
+            }
+            AgencyCommands::Pipeline {
+                file,
+                prompt,
+                model,
+                no_save,
+                verbose,
+            } => commands::run_pipeline_file(
+                &file,
+                prompt.as_deref(),
+                model.as_deref(),
+                false,
+                !no_save,
+                verbose,
+            ),
+            AgencyCommands::Swarm {
+                file,
+                prompt,
+                model,
+                no_save,
+                verbose,
+            } => commands::run_pipeline_file(
+                &file,
+                prompt.as_deref(),
+                model.as_deref(),
+                true,
+                !no_save,
+                verbose,
+            ),
             AgencyCommands::Create {
                 name,
                 role,
                 instruction,
                 model,
-            } => commands::create_agent(&name, &role, instruction.as_deref(), model.as_deref()),
+                tools,
+                orchestration,
+                force,
+            } => commands::create_agent(
+                &name,
+                &role,
+                instruction.as_deref(),
+                model.as_deref(),
+                &tools,
+                orchestration.as_deref(),
+                force,
+            ),
             AgencyCommands::Tools => commands::list_tools(),
             AgencyCommands::Templates => commands::list_templates(),
         },
 
+        Commands::Continue {
+            session_id,
+            prompt,
+            with,
+            agent,
+            context_window,
+            no_save,
+            verbose,
+        } => commands::continue_session(
+            &session_id,
+            &prompt,
+            &with,
+            &agent,
+            context_window,
+            !no_save,
+            verbose,
+        ),
+
+        Commands::Ask {
+            question,
+            agent,
+            model,
+            limit,
+            no_index,
+            verbose,
+        } => commands::ask(&question, &agent, model.as_deref(), limit, !no_index, verbose),
+
         // ====================================================================
         // Telemetry (User Data Collection)
         // ====================================================================
@@ -827,6 +1298,531 @@ fn main() -> Result<()> {
             Some(TelemetryCommands::Test) => commands::telemetry_test(),
         },
 
+        // ====================================================================
+        // Backup
+        // ====================================================================
+        Commands::Backup { command } => match command {
+            BackupCommands::List { source, json } => commands::backup_list(source.as_deref(), json),
+            BackupCommands::Prune {
+                keep,
+                older_than,
+                dry_run,
+            } => commands::backup_prune(keep, older_than, dry_run),
+            BackupCommands::Run {
+                destination,
+                no_harvest,
+                no_sessions,
+            } => commands::backup_run(destination.as_deref(), no_harvest, no_sessions),
+            BackupCommands::Archives { destination } => {
+                commands::backup_archives(destination.as_deref())
+            }
+            BackupCommands::Verify {
+                archive,
+                destination,
+            } => commands::backup_verify(&archive, destination.as_deref()),
+            BackupCommands::Restore {
+                archive,
+                destination,
+                into,
+                force,
+                dry_run,
+                interactive: _,
+            } => match archive {
+                Some(archive) => commands::backup_restore(
+                    &archive,
+                    destination.as_deref(),
+                    into.as_deref(),
+                    force,
+                    dry_run,
+                ),
+                None => commands::backup_restore_interactive(destination.as_deref(), dry_run),
+            },
+        },
+
+        // ====================================================================
+        // Automation
+        // ====================================================================
+        Commands::Automation { command } => match command {
+            AutomationCommands::List => commands::automation_list(),
+            AutomationCommands::Run { name, json } => commands::automation_run(&name, json),
+            AutomationCommands::Start => commands::automation_start(),
+            AutomationCommands::Templates => commands::automation_templates(),
+            AutomationCommands::Init {
+                template,
+                name,
+                set,
+                yes,
+                force,
+            } => commands::automation_init(&template, name.as_deref(), &set, yes, force),
+            AutomationCommands::Runs {
+                workflow,
+                run,
+                limit,
+                json,
+            } => commands::automation_runs(workflow.as_deref(), run.as_deref(), limit, json),
+        },
+
+        // ====================================================================
+        // Plugins
+        // ====================================================================
+        Commands::Plugin { command } => match command {
+            PluginCommands::List { json } => commands::plugin_list(json),
+            PluginCommands::Install { source, yes, force } => {
+                commands::plugin_install(&source, yes, force)
+            }
+            PluginCommands::Search {
+                query,
+                category,
+                json,
+            } => commands::plugin_search(&query, category.as_deref(), json),
+            PluginCommands::Update { id, yes } => commands::plugin_update(id.as_deref(), yes),
+            PluginCommands::Enable { id, yes } => commands::plugin_enable(&id, yes),
+            PluginCommands::Disable { id } => commands::plugin_disable(&id),
+            PluginCommands::Remove { id } => commands::plugin_remove(&id),
+            PluginCommands::Dev { path, yes } => commands::plugin_dev(&path, yes),
+            PluginCommands::Config { id, set, unset } => commands::plugin_config(&id, &set, &unset),
+        },
+
+        // ====================================================================
+        // Pack
+        // ====================================================================
+        Commands::Pack { command } => match command {
+            PackCommands::Automation { command } => match command {
+                AutomationPackCommands::List => commands::pack_automation_list(),
+                AutomationPackCommands::Export {
+                    file,
+                    workflow,
+                    agent,
+                    prompt,
+                    search,
+                    name,
+                    description,
+                } => commands::pack_automation_export(
+                    &file,
+                    commands::PackSelection {
+                        workflows: workflow,
+                        agents: agent,
+                        prompts: prompt,
+                        searches: search,
+                    },
+                    name.as_deref(),
+                    description.as_deref(),
+                ),
+            },
+            PackCommands::Import {
+                file,
+                force,
+                allow_unsigned,
+                dry_run,
+            } => commands::pack_import(&file, force, allow_unsigned, dry_run),
+            PackCommands::Inspect { file } => commands::pack_inspect(&file),
+        },
+
+        // ====================================================================
+        // Config
+        // ====================================================================
+        Commands::Config { command } => match command {
+            Some(ConfigCommands::Show) | None => commands::config_show(),
+            Some(ConfigCommands::Path) => commands::config_path(),
+            Some(ConfigCommands::Get { key, profile }) => {
+                commands::config_get(&key, profile.as_deref())
+            }
+            Some(ConfigCommands::Set {
+                key,
+                value,
+                profile,
+            }) => commands::config_set(&key, &value, profile.as_deref()),
+            Some(ConfigCommands::Unset { key, profile }) => {
+                commands::config_unset(&key, profile.as_deref())
+            }
+            Some(ConfigCommands::Use { name }) => commands::config_use(name.as_deref()),
+            Some(ConfigCommands::Profiles) => commands::config_profiles(),
+        },
+
+        // ====================================================================
+        // Notify
+        // ====================================================================
+        Commands::Notify { command } => match command {
+            NotifyCommands::List => commands::notify_list(),
+            NotifyCommands::Test { channel, message } => {
+                commands::notify_test(&channel, message.as_deref())
+            }
+            NotifyCommands::Add {
+                name,
+                provider,
+                url,
+                to,
+                events,
+                template,
+            } => commands::notify_add(&name, &provider, url, to, events, template),
+            NotifyCommands::Remove { name } => commands::notify_remove(&name),
+        },
+
+        // ====================================================================
+        // Project
+        // ====================================================================
+        Commands::Project { command } => match command {
+            ProjectCommands::Create {
+                name,
+                description,
+                workspaces,
+            } => commands::project_create(&name, description.as_deref(), &workspaces),
+            ProjectCommands::AddWorkspace {
+                project,
+                workspaces,
+            } => commands::project_add_workspace(&project, &workspaces),
+            ProjectCommands::RemoveWorkspace { project, workspace } => {
+                commands::project_remove_workspace(&project, &workspace)
+            }
+            ProjectCommands::List { json } => commands::project_list(json),
+            ProjectCommands::Show { name } => commands::project_show(&name),
+            ProjectCommands::Stats { name } => commands::project_stats(&name),
+            ProjectCommands::Suggest { create } => commands::project_suggest(create),
+            ProjectCommands::Delete { name } => commands::project_delete(&name),
+        },
+
+        // ====================================================================
+        // Report Commands
+        // ====================================================================
+        Commands::Report { command } => match command {
+            ReportCommands::Reviews {
+                since,
+                path,
+                limit,
+                rescan,
+                json,
+            } => commands::report_reviews(path.as_deref(), &since, limit, rescan, json),
+            ReportCommands::Spend { since, path, json } => {
+                commands::report_spend(path.as_deref(), &since, json)
+            }
+            ReportCommands::Quality {
+                since,
+                path,
+                provider,
+                limit,
+                json,
+            } => commands::report_quality(
+                path.as_deref(),
+                &since,
+                provider.as_deref(),
+                limit,
+                json,
+            ),
+            ReportCommands::Digest {
+                since,
+                path,
+                limit,
+                send,
+                to,
+                html,
+                json,
+            } => commands::report_digest(
+                path.as_deref(),
+                &since,
+                limit,
+                send,
+                &to,
+                html.as_deref(),
+                json,
+            ),
+        },
+
+        // ====================================================================
+        // Intelligence
+        // ====================================================================
+        Commands::Intelligence { command } => match command {
+            IntelligenceCommands::Dupes {
+                path,
+                threshold,
+                semantic,
+                provider,
+                limit,
+                delete,
+                json,
+            } => commands::intelligence_dupes(
+                path.as_deref(),
+                threshold,
+                semantic,
+                provider.as_deref(),
+                limit,
+                delete,
+                json,
+            ),
+            IntelligenceCommands::Retitle {
+                path,
+                dry_run,
+                all,
+                model,
+                provider,
+                limit,
+                write_back,
+                json,
+            } => commands::intelligence_retitle(
+                path.as_deref(),
+                dry_run,
+                all,
+                model.as_deref(),
+                provider.as_deref(),
+                limit,
+                write_back,
+                json,
+            ),
+            IntelligenceCommands::Topics {
+                path,
+                provider,
+                session,
+                list,
+                json,
+            } => commands::intelligence_topics(
+                path.as_deref(),
+                provider.as_deref(),
+                session.as_deref(),
+                list,
+                json,
+            ),
+            IntelligenceCommands::Actions {
+                path,
+                provider,
+                session,
+                json,
+            } => commands::intelligence_actions(
+                path.as_deref(),
+                provider.as_deref(),
+                session.as_deref(),
+                json,
+            ),
+        },
+
+        // ====================================================================
+        // Action Items
+        // ====================================================================
+        Commands::Actions { command } => match command {
+            ActionsCommands::List {
+                path,
+                kind,
+                session,
+                all,
+                limit,
+                json,
+            } => commands::actions_list(
+                path.as_deref(),
+                kind.as_deref(),
+                session.as_deref(),
+                all,
+                limit,
+                json,
+            ),
+            ActionsCommands::Done { ids, path, reopen } => {
+                commands::actions_done(path.as_deref(), &ids, reopen)
+            }
+            ActionsCommands::Export {
+                to,
+                path,
+                kind,
+                session,
+                project,
+                output,
+                dry_run,
+            } => commands::actions_export(
+                path.as_deref(),
+                &to,
+                kind.as_deref(),
+                session.as_deref(),
+                project.as_deref(),
+                output.as_deref(),
+                dry_run,
+            ),
+        },
+
+        // ====================================================================
+        // GitHub
+        // ====================================================================
+        Commands::Github { command } => match command {
+            GithubCommands::Link {
+                session,
+                path,
+                dir,
+                repo,
+                branch,
+                commit,
+                pr,
+                issue,
+                remove,
+            } => commands::github_link(
+                path.as_deref(),
+                &session,
+                dir.as_deref(),
+                repo.as_deref(),
+                branch.as_deref(),
+                commit.as_deref(),
+                pr,
+                issue,
+                remove,
+            ),
+            GithubCommands::Links {
+                reference,
+                path,
+                repo,
+                json,
+            } => commands::github_links(
+                path.as_deref(),
+                repo.as_deref(),
+                reference.as_deref(),
+                json,
+            ),
+            GithubCommands::Comment {
+                session,
+                on,
+                path,
+                dry_run,
+            } => commands::github_comment(path.as_deref(), &session, on.as_deref(), dry_run),
+            GithubCommands::Import {
+                reference,
+                repo,
+                path,
+            } => commands::github_import(path.as_deref(), &reference, repo.as_deref()),
+        },
+
+        // ====================================================================
+        // Slack
+        // ====================================================================
+        Commands::Slack { command } => match command {
+            SlackCommands::Threads {
+                channel,
+                limit,
+                json,
+            } => commands::slack_threads(&channel, limit, json),
+            SlackCommands::Import { threads, path } => {
+                commands::slack_import(path.as_deref(), &threads)
+            }
+            SlackCommands::Post {
+                session,
+                channel,
+                thread,
+                path,
+                dry_run,
+            } => commands::slack_post(
+                path.as_deref(),
+                &session,
+                channel.as_deref(),
+                thread.as_deref(),
+                dry_run,
+            ),
+        },
+
+        // ====================================================================
+        // Obsidian
+        // ====================================================================
+        Commands::Obsidian { command } => match command {
+            ObsidianCommands::Sync {
+                vault,
+                folder,
+                watch,
+                debounce,
+                path,
+            } => commands::obsidian_sync(
+                path.as_deref(),
+                vault.as_deref(),
+                folder.as_deref(),
+                watch,
+                debounce,
+            ),
+        },
+
+        // ====================================================================
+        // Issues
+        // ====================================================================
+        Commands::Issue { command } => match command {
+            IssueCommands::Create {
+                session,
+                tracker,
+                project,
+                title,
+                path,
+                dry_run,
+                force,
+            } => commands::issue_create(
+                path.as_deref(),
+                &session,
+                tracker.as_deref(),
+                project.as_deref(),
+                title.as_deref(),
+                dry_run,
+                force,
+            ),
+            IssueCommands::List {
+                session,
+                tracker,
+                path,
+                json,
+            } => commands::issue_list(
+                path.as_deref(),
+                session.as_deref(),
+                tracker.as_deref(),
+                json,
+            ),
+        },
+
+        // ====================================================================
+        // Knowledge Graph
+        // ====================================================================
+        Commands::Graph { command } => match command {
+            GraphCommands::Build {
+                path,
+                provider,
+                session,
+                json,
+            } => commands::graph_build(
+                path.as_deref(),
+                provider.as_deref(),
+                session.as_deref(),
+                json,
+            ),
+            GraphCommands::Query {
+                name,
+                path,
+                kind,
+                limit,
+                json,
+            } => commands::graph_query(path.as_deref(), &name, kind.as_deref(), limit, json),
+            GraphCommands::Export {
+                format,
+                path,
+                output,
+                kind,
+                min_weight,
+                no_sessions,
+            } => commands::graph_export(
+                path.as_deref(),
+                &format,
+                output.as_deref(),
+                kind.as_deref(),
+                min_weight,
+                no_sessions,
+            ),
+        },
+
+        // ====================================================================
+        // MCP
+        // ====================================================================
+        Commands::Mcp { command } => match command {
+            McpCommands::Install {
+                client,
+                project,
+                name,
+                allow_write,
+                config,
+                no_verify,
+            } => commands::mcp_install(
+                &client,
+                project,
+                &name,
+                allow_write,
+                config.as_deref(),
+                no_verify,
+            ),
+        },
+
         // ====================================================================
         // Easter Egg
         // ====================================================================
@@ -0,0 +1,134 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! MCP Server - Named harvest databases
+//!
+//! `csm-mcp` can serve several harvest databases at once, such as one for work
+//! and one for personal projects. Each has a name, from `[mcp.databases]` in
+//! the config file or `--db NAME=PATH`, and its own resources under
+//! `csm://db/{name}/`. The harvest tools take a `database` argument naming
+//! one; without it they use `default`, the database `csm harvest` uses.
+
+use anyhow::{anyhow, bail};
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use super::logging;
+use crate::config;
+
+/// The database tools use when the client names none
+pub const DEFAULT: &str = "default";
+
+/// Names taken by the csm-web resources under `csm://db/`
+const RESERVED: &[&str] = &["workspaces", "sessions", "session", "stats"];
+
+static DATABASES: OnceLock<BTreeMap<String, PathBuf>> = OnceLock::new();
+
+/// Serve `extra` databases besides the configured ones. Call it before the
+/// server starts; later calls have no effect.
+pub fn configure(extra: Vec<(String, PathBuf)>) {
+    let _ = DATABASES.set(collect(extra));
+}
+
+/// Every database the server offers, by name
+pub fn all() -> &'static BTreeMap<String, PathBuf> {
+    DATABASES.get_or_init(|| collect(Vec::new()))
+}
+
+fn collect(extra: Vec<(String, PathBuf)>) -> BTreeMap<String, PathBuf> {
+    let mut databases = BTreeMap::new();
+    if let Ok(path) = crate::commands::get_db_path(None) {
+        databases.insert(DEFAULT.to_string(), path);
+    }
+    let configured = config::current().mcp.databases.clone().unwrap_or_default();
+    for (name, path) in configured {
+        match check_name(&name) {
+            Ok(()) => {
+                databases.insert(name, config::expand_home(&path));
+            }
+            Err(e) => eprintln!("[csm-mcp] Ignoring [mcp.databases] entry: {}", e),
+        }
+    }
+    databases.extend(extra);
+    databases
+}
+
+/// The path of the database called `name`, or of the default one
+pub fn resolve(name: Option<&str>) -> anyhow::Result<PathBuf> {
+    let name = name.unwrap_or(DEFAULT);
+    all().get(name).cloned().ok_or_else(|| {
+        let names: Vec<&str> = all().keys().map(String::as_str).collect();
+        anyhow!(
+            "Unknown database '{}'; this server has {}",
+            name,
+            names.join(", ")
+        )
+    })
+}
+
+/// Open a database, failing if nothing has been harvested into it yet
+pub fn open(name: Option<&str>) -> anyhow::Result<Connection> {
+    let path = resolve(name)?;
+    if !path.exists() {
+        bail!(
+            "Harvest database not found at {}. Run csm_harvest_run first.",
+            path.display()
+        );
+    }
+    let mut conn = Connection::open(path)?;
+    conn.profile(Some(logging::log_query));
+    Ok(conn)
+}
+
+/// Parse a `NAME=PATH` argument
+pub fn parse_database(arg: &str) -> Result<(String, PathBuf), String> {
+    let (name, path) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=PATH, got '{}'", arg))?;
+    check_name(name)?;
+    if path.is_empty() {
+        return Err(format!("no path given for database '{}'", name));
+    }
+    Ok((name.to_string(), config::expand_home(path.as_ref())))
+}
+
+/// Names become URI segments, so keep them to letters, digits, `-`, and `_`
+fn check_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "database name '{}' may only use letters, digits, '-', and '_'",
+            name
+        ));
+    }
+    if RESERVED.contains(&name) {
+        return Err(format!(
+            "database name '{}' is taken by the csm-web resources",
+            name
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_database() {
+        assert_eq!(
+            parse_database("work=/data/work.db").unwrap(),
+            ("work".to_string(), PathBuf::from("/data/work.db"))
+        );
+        assert!(parse_database("work").is_err());
+        assert!(parse_database("work=").is_err());
+        assert!(parse_database("my work=/data/work.db").is_err());
+        assert!(parse_database("sessions=/data/work.db")
+            .unwrap_err()
+            .contains("csm-web"));
+    }
+}
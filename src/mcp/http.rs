@@ -0,0 +1,357 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! MCP Server - Streamable HTTP transport
+//!
+//! Serves the same tools and resources as the stdio transport at a single
+//! `/mcp` endpoint, following the MCP streamable HTTP transport:
+//!
+//! - `POST /mcp` takes one JSON-RPC message or a batch. Requests are answered
+//!   with `application/json`, or with a `text/event-stream` of `message`
+//!   events when the client accepts only that; a POST holding only
+//!   notifications gets `202 Accepted`.
+//! - `initialize` starts a session whose ID comes back in `Mcp-Session-Id`.
+//!   Every later request must send it; unknown or expired sessions get
+//!   `404`, telling the client to initialize again.
+//! - `DELETE /mcp` ends the session.
+//! - `GET /mcp` answers `405`, since the server sends no messages of its
+//!   own.
+//!
+//! Browsers on other sites are refused by their `Origin`, and a bearer token
+//! can be required for remote clients.
+
+use actix_web::http::header::{self, HeaderMap};
+use actix_web::http::StatusCode;
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use super::logging;
+use super::policy::ToolPolicy;
+use super::server::McpServer;
+use super::types::{JsonRpcRequest, JsonRpcResponse};
+
+/// Header carrying the session ID
+pub const SESSION_HEADER: &str = "Mcp-Session-Id";
+
+/// Sessions unused for this long are dropped
+const SESSION_IDLE: Duration = Duration::from_secs(60 * 60);
+
+/// Settings for the HTTP transport
+#[derive(Debug, Clone, Default)]
+pub struct HttpOptions {
+    /// Browser origins allowed besides localhost
+    pub allowed_origins: Vec<String>,
+    /// Bearer token clients must send, if any
+    pub token: Option<String>,
+    /// Which tools are offered and may run
+    pub policy: ToolPolicy,
+}
+
+struct Transport {
+    options: HttpOptions,
+    /// Open sessions and when each was last used
+    sessions: Mutex<HashMap<String, Instant>>,
+}
+
+impl Transport {
+    /// Whether `id` names a live session, marking it used
+    fn touch(&self, id: &str) -> bool {
+        let mut sessions = self.sessions.lock().unwrap();
+        let now = Instant::now();
+        sessions.retain(|_, used| now.duration_since(*used) < SESSION_IDLE);
+        match sessions.get_mut(id) {
+            Some(used) => {
+                *used = now;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn open(&self) -> String {
+        let id = Uuid::new_v4().simple().to_string();
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(id.clone(), Instant::now());
+        id
+    }
+}
+
+/// Parse a `--http` address. A bare port or `:port` listens on localhost
+/// only; give a host, such as `0.0.0.0:8788`, to accept remote clients.
+pub fn parse_bind_address(addr: &str) -> Result<SocketAddr, String> {
+    let addr = addr.trim();
+    let full = if let Some(port) = addr.strip_prefix(':') {
+        format!("127.0.0.1:{}", port)
+    } else if addr.chars().all(|c| c.is_ascii_digit()) {
+        format!("127.0.0.1:{}", addr)
+    } else {
+        addr.to_string()
+    };
+    full.parse()
+        .map_err(|_| format!("Invalid address '{}' (expected [host]:port)", addr))
+}
+
+/// Whether a browser at `origin` may call the server. Requests without an
+/// `Origin` header come from non-browser clients and are always allowed.
+fn origin_allowed(origin: &str, allowed: &[String]) -> bool {
+    let host = origin
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or(origin);
+    let host = match host.rsplit_once(':') {
+        Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+        _ => host,
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "[::1]") || allowed.iter().any(|a| a == origin)
+}
+
+fn error(status: StatusCode, message: &str) -> HttpResponse {
+    HttpResponse::build(status).json(JsonRpcResponse::error(None, -32000, message.to_string()))
+}
+
+/// Refuse requests from foreign browser origins or without the token
+fn check_client(headers: &HeaderMap, options: &HttpOptions) -> Option<HttpResponse> {
+    if let Some(origin) = headers.get(header::ORIGIN).and_then(|v| v.to_str().ok()) {
+        if !origin_allowed(origin, &options.allowed_origins) {
+            return Some(error(StatusCode::FORBIDDEN, "Origin not allowed"));
+        }
+    }
+    if let Some(token) = &options.token {
+        let sent = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        if !sent.is_some_and(|sent| token_matches(sent, token)) {
+            return Some(error(StatusCode::UNAUTHORIZED, "Missing or invalid token"));
+        }
+    }
+    None
+}
+
+/// Whether `sent` is the token. Their SHA-256 digests are compared, so the
+/// time taken does not show how much of the token a client guessed.
+fn token_matches(sent: &str, token: &str) -> bool {
+    Sha256::digest(sent.as_bytes()) == Sha256::digest(token.as_bytes())
+}
+
+fn session_id(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(SESSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from)
+}
+
+/// Handle the messages of one POST, returning the responses to requests.
+/// Notifications and responses from the client get none.
+fn dispatch(server: &mut McpServer, messages: Vec<Value>) -> Vec<JsonRpcResponse> {
+    let mut responses = Vec::new();
+    for message in messages {
+        if message.get("method").is_none() {
+            // A response to a server request; we never send any
+            continue;
+        }
+        logging::traffic("<-", &message.to_string());
+        match serde_json::from_value::<JsonRpcRequest>(message) {
+            Ok(request) => {
+                let notification = request.id.is_none();
+                let response = server.handle_request(request);
+                if !notification {
+                    if let Ok(sent) = serde_json::to_string(&response) {
+                        logging::traffic("->", &sent);
+                    }
+                    responses.push(response);
+                }
+            }
+            Err(e) => responses.push(JsonRpcResponse::error(
+                None,
+                -32600,
+                format!("Invalid request: {}", e),
+            )),
+        }
+    }
+    responses
+}
+
+/// Whether the client wants responses as an event stream rather than JSON
+fn wants_event_stream(req: &HttpRequest) -> bool {
+    let accept = req
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    accept.contains("text/event-stream") && !accept.contains("application/json")
+}
+
+async fn post(req: HttpRequest, body: web::Bytes, transport: web::Data<Transport>) -> HttpResponse {
+    if let Some(refused) = check_client(req.headers(), &transport.options) {
+        return refused;
+    }
+    let (messages, batch) = match serde_json::from_slice::<Value>(&body) {
+        Ok(Value::Array(messages)) => (messages, true),
+        Ok(message) => (vec![message], false),
+        Err(e) => {
+            let response = JsonRpcResponse::error(None, -32700, format!("Parse error: {}", e));
+            return HttpResponse::BadRequest().json(response);
+        }
+    };
+
+    let initialize = messages
+        .iter()
+        .any(|m| m.get("method").and_then(Value::as_str) == Some("initialize"));
+    let session = if initialize {
+        if messages.len() > 1 {
+            return error(
+                StatusCode::BAD_REQUEST,
+                "initialize must be sent on its own",
+            );
+        }
+        transport.open()
+    } else {
+        match session_id(&req) {
+            Some(id) if transport.touch(&id) => id,
+            Some(_) => return error(StatusCode::NOT_FOUND, "Session not found"),
+            None => return error(StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header"),
+        }
+    };
+
+    // Tools read and write session files, so they run off the event loop
+    let policy = transport.options.policy.clone();
+    let responses =
+        match web::block(move || dispatch(&mut McpServer::new().with_policy(policy), messages))
+            .await
+        {
+            Ok(responses) => responses,
+            Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+        };
+    if responses.is_empty() {
+        return HttpResponse::Accepted().finish();
+    }
+
+    let mut builder = HttpResponse::Ok();
+    builder.insert_header((SESSION_HEADER, session));
+    if wants_event_stream(&req) {
+        let events: String = responses
+            .iter()
+            .map(|r| {
+                format!(
+                    "event: message\ndata: {}\n\n",
+                    serde_json::to_string(r).unwrap_or_default()
+                )
+            })
+            .collect();
+        return builder
+            .content_type("text/event-stream")
+            .insert_header((header::CACHE_CONTROL, "no-cache"))
+            .body(events);
+    }
+    if batch {
+        builder.json(responses)
+    } else {
+        builder.json(&responses[0])
+    }
+}
+
+async fn delete(req: HttpRequest, transport: web::Data<Transport>) -> HttpResponse {
+    if let Some(refused) = check_client(req.headers(), &transport.options) {
+        return refused;
+    }
+    let Some(id) = session_id(&req) else {
+        return error(StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header");
+    };
+    match transport.sessions.lock().unwrap().remove(&id) {
+        Some(_) => HttpResponse::Ok().finish(),
+        None => error(StatusCode::NOT_FOUND, "Session not found"),
+    }
+}
+
+async fn get(req: HttpRequest, transport: web::Data<Transport>) -> HttpResponse {
+    if let Some(refused) = check_client(req.headers(), &transport.options) {
+        return refused;
+    }
+    HttpResponse::MethodNotAllowed()
+        .insert_header((header::ALLOW, "POST, DELETE"))
+        .finish()
+}
+
+/// Serve MCP over HTTP at `http://{addr}/mcp` until the process is stopped
+pub fn serve(addr: SocketAddr, options: HttpOptions) -> io::Result<()> {
+    if !addr.ip().is_loopback() && options.token.is_none() {
+        eprintln!(
+            "[csm-mcp] Warning: listening on {} without a token; anyone who can reach it can run tools",
+            addr
+        );
+    }
+    let transport = web::Data::new(Transport {
+        options,
+        sessions: Mutex::new(HashMap::new()),
+    });
+    eprintln!("[csm-mcp] Listening on http://{}/mcp", addr);
+
+    actix_web::rt::System::new().block_on(
+        HttpServer::new(move || {
+            App::new().app_data(transport.clone()).service(
+                web::resource("/mcp")
+                    .route(web::post().to(post))
+                    .route(web::get().to(get))
+                    .route(web::delete().to(delete)),
+            )
+        })
+        .bind(addr)?
+        .run(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_http_transport_helpers() {
+        assert_eq!(
+            parse_bind_address(":8788").unwrap(),
+            "127.0.0.1:8788".parse().unwrap()
+        );
+        assert_eq!(
+            parse_bind_address("8788").unwrap(),
+            "127.0.0.1:8788".parse().unwrap()
+        );
+        assert!(!parse_bind_address("0.0.0.0:8788")
+            .unwrap()
+            .ip()
+            .is_loopback());
+        assert!(parse_bind_address("localhost").is_err());
+
+        let allowed = vec!["https://agents.example.com".to_string()];
+        assert!(origin_allowed("http://localhost:5173", &allowed));
+        assert!(origin_allowed("http://127.0.0.1", &allowed));
+        assert!(origin_allowed("http://[::1]:3000", &allowed));
+        assert!(origin_allowed("https://agents.example.com", &allowed));
+        assert!(!origin_allowed("https://evil.example", &allowed));
+        assert!(!origin_allowed("http://localhost.evil.example", &allowed));
+
+        assert!(token_matches("s3cret", "s3cret"));
+        assert!(!token_matches("s3cre", "s3cret"));
+        assert!(!token_matches("", "s3cret"));
+
+        // Notifications and client responses get no reply
+        let responses = dispatch(
+            &mut McpServer::new(),
+            vec![
+                json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+                json!({"jsonrpc": "2.0", "id": 7, "result": {}}),
+                json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}),
+            ],
+        );
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].id, Some(json!(1)));
+    }
+}
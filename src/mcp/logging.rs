@@ -0,0 +1,157 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! MCP Server - Logging
+//!
+//! Clients choose the least severe level they want with `logging/setLevel`
+//! and receive `notifications/message` for tool timings, harvest database
+//! queries, and errors at that level or above; until they choose, only
+//! warnings and errors are sent. The HTTP transport has no stream for
+//! notifications, so there only `--log-file` sees them. The log file gets
+//! every message at every level, along with the protocol traffic, to debug
+//! integrations that break before the client shows anything.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use super::subscriptions::Subscriptions;
+use super::types::JsonRpcNotification;
+
+/// Severity of a log message, least severe first (RFC 5424)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+/// Where log messages go
+struct Log {
+    /// Least severe level the client wants
+    level: Mutex<Level>,
+    /// The stdio connection, once the server runs on one
+    out: Mutex<Option<Arc<Subscriptions>>>,
+    file: Option<Mutex<File>>,
+}
+
+static LOG: OnceLock<Log> = OnceLock::new();
+
+fn log_sink() -> &'static Log {
+    LOG.get_or_init(|| Log::new(None))
+}
+
+impl Log {
+    fn new(file: Option<File>) -> Self {
+        Self {
+            level: Mutex::new(Level::Warning),
+            out: Mutex::new(None),
+            file: file.map(Mutex::new),
+        }
+    }
+}
+
+/// Append diagnostics to `path` as well. Call it before the server starts;
+/// later calls have no effect.
+pub fn configure(path: &Path) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let _ = LOG.set(Log::new(Some(file)));
+    Ok(())
+}
+
+/// Send log notifications over the stdio connection `out`
+pub fn connect(out: Arc<Subscriptions>) {
+    *log_sink().out.lock().unwrap() = Some(out);
+}
+
+/// Send the client messages at `level` and above
+pub fn set_level(level: Level) {
+    *log_sink().level.lock().unwrap() = level;
+}
+
+/// Log `data` from the part of the server called `logger`
+pub fn log(level: Level, logger: &str, data: Value) {
+    let sink = log_sink();
+    write_line(sink, &format_line(level, logger, &data.to_string()));
+
+    if level < *sink.level.lock().unwrap() {
+        return;
+    }
+    let out = sink.out.lock().unwrap().clone();
+    if let Some(out) = out {
+        let notification = JsonRpcNotification::new(
+            "notifications/message",
+            json!({ "level": level, "logger": logger, "data": data }),
+        );
+        if let Err(e) = out.send(&notification) {
+            eprintln!("[csm-mcp] Failed to send log message: {}", e);
+        }
+    }
+}
+
+/// Record a protocol message in the log file only
+pub fn traffic(direction: &str, message: &str) {
+    write_line(
+        log_sink(),
+        &format_line(
+            Level::Debug,
+            "traffic",
+            &format!("{} {}", direction, message),
+        ),
+    );
+}
+
+/// Log a harvest database statement and how long it took. Connections call
+/// this through [`rusqlite::Connection::profile`].
+pub fn log_query(sql: &str, duration: Duration) {
+    log(
+        Level::Debug,
+        "database",
+        json!({ "sql": sql, "duration_ms": duration.as_secs_f64() * 1000.0 }),
+    );
+}
+
+fn write_line(sink: &Log, line: &str) {
+    if let Some(file) = &sink.file {
+        let mut file = file.lock().unwrap();
+        let _ = writeln!(file, "{}", line).and_then(|_| file.flush());
+    }
+}
+
+fn format_line(level: Level, logger: &str, message: &str) -> String {
+    format!(
+        "{} {:<9} {}: {}",
+        chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+        format!("{:?}", level).to_uppercase(),
+        logger,
+        message
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_and_lines() {
+        assert!(Level::Debug < Level::Info);
+        assert!(Level::Warning < Level::Emergency);
+        assert_eq!(
+            serde_json::from_value::<Level>(json!("warning")).unwrap(),
+            Level::Warning
+        );
+        assert!(serde_json::from_value::<Level>(json!("verbose")).is_err());
+
+        let line = format_line(Level::Info, "tools", "{\"tool\":\"csm_detect\"}");
+        assert!(line.ends_with(" INFO      tools: {\"tool\":\"csm_detect\"}"));
+    }
+}
@@ -9,6 +9,14 @@
 //! # Usage
 //!
 //! The server communicates via stdio (stdin/stdout) using JSON-RPC 2.0.
+//! With `--http`, it serves the MCP streamable HTTP transport instead, so
+//! remote clients and web-based agents can connect without spawning a
+//! process:
+//!
+//! ```text
+//! csm-mcp --http :8788                          # http://127.0.0.1:8788/mcp
+//! csm-mcp --http 0.0.0.0:8788 --token $SECRET   # remote clients send a bearer token
+//! ```
 //!
 //! ## Available Tools
 //!
@@ -23,15 +31,53 @@
 //! - `csm_merge_sessions` - Merge multiple sessions
 //! - `csm_search` - Search sessions by content
 //! - `csm_detect` - Detect chat provider and sessions
+//! - `csm_harvest_databases` - List the harvest databases the server offers
+//! - `csm_harvest_run` - Harvest sessions from every provider
+//! - `csm_harvest_search` - Full-text search of harvested sessions
+//! - `csm_semantic_search` - Find the harvested sessions closest in meaning to a query
+//! - `csm_harvest_list` - List harvested sessions
+//! - `csm_harvest_export` - Export harvested sessions as JSON, JSON Lines, or Markdown
+//! - `csm_tag_session` - Add or remove tags on a harvested session
+//! - `csm_run_agent` - Run a local Agency agent on a prompt
+//!
+//! The tools that change data (`csm_register_all`, `csm_register_sessions`,
+//! `csm_merge_sessions`, `csm_harvest_run`, `csm_tag_session`,
+//! `csm_run_agent`, and `csm_harvest_export` with an `output` path) are
+//! offered only when the server runs with `--allow-write`. `--read-only`
+//! refuses them even if the config file allows writes, `--allow-tool` and
+//! `--deny-tool` narrow the tools offered, and `--confirm-writes` asks on the
+//! terminal before each write. The `[mcp]` section of `config.toml` sets the
+//! same policy.
+//!
+//! Plugins in the `plugins` folder next to `config.toml` add a
+//! `plugin_<id>_<action>` tool for each action in their `capabilities`.
 //!
 //! ## Available Resources
 //!
 //! - `csm://workspaces` - List of all registered workspaces
-//! - `csm://sessions` - List of all sessions across workspaces
+//! - `csm://sessions` - Sessions across workspaces, 100 per page
+//!   (`?page=N&workspace=HASH`)
 //! - `csm://orphaned` - List of orphaned session files
 //! - `csm://providers` - List of supported chat providers
 //! - `csm://workspace/{hash}` - Details of a specific workspace
 //! - `csm://session/{id}` - Details of a specific session
+//! - `csm://db/{name}/sessions` - Sessions in a harvest database, 100 per
+//!   page (`?page=N&provider=NAME`)
+//! - `csm://db/{name}/stats` - Session counts by provider in a harvest database
+//! - `csm://db/{name}/session/{id}` - A harvested session
+//!
+//! The harvest database `csm harvest` uses is named `default`. `--db
+//! work=~/work/chat_sessions.db` (repeatable) or `[mcp.databases]` in
+//! `config.toml` serves more, and the harvest tools take a `database`
+//! argument to pick one.
+//!
+//! Over stdio, clients can `resources/subscribe` to the workspace, session,
+//! and orphan resources and receive `notifications/resources/updated` when
+//! VS Code changes a session file. Tool calls sent with a `progressToken`
+//! receive `notifications/progress`, and `notifications/cancelled` stops a
+//! running harvest. Clients pick a level with `logging/setLevel` to receive
+//! `notifications/message` for tool timings, database queries, and errors;
+//! `--log-file PATH` writes all of them, with the protocol traffic, to a file.
 //!
 //! # Configuration
 //!
@@ -48,12 +94,118 @@
 //! }
 //! ```
 
+use chasm::mcp::databases;
+use chasm::mcp::http::{self, HttpOptions};
+use chasm::mcp::logging;
+use chasm::mcp::plugins;
+use chasm::mcp::policy::ToolPolicy;
 use chasm::mcp::server::McpServer;
+use clap::Parser;
+use std::path::PathBuf;
+
+/// MCP server for Chat System Manager
+#[derive(Parser)]
+#[command(name = "csm-mcp", version)]
+struct Args {
+    /// Serve the streamable HTTP transport at [host]:port instead of stdio;
+    /// a bare port or `:port` listens on localhost only
+    #[arg(long, value_name = "ADDR")]
+    http: Option<String>,
+
+    /// Bearer token HTTP clients must send
+    #[arg(long, env = "CSM_MCP_TOKEN")]
+    token: Option<String>,
+
+    /// Browser origin allowed to call the HTTP server besides localhost
+    /// (repeatable)
+    #[arg(long = "allow-origin", value_name = "ORIGIN", requires = "http")]
+    allowed_origins: Vec<String>,
+
+    /// Offer tools that register, merge, tag, or harvest sessions and write
+    /// exports to disk
+    #[arg(long)]
+    allow_write: bool,
+
+    /// Refuse every tool that changes data, even if the config allows writes
+    #[arg(long, conflicts_with = "allow_write")]
+    read_only: bool,
+
+    /// Offer only this tool; a trailing `*` matches a prefix (repeatable)
+    #[arg(long = "allow-tool", value_name = "TOOL")]
+    allow_tools: Vec<String>,
+
+    /// Never offer this tool; a trailing `*` matches a prefix (repeatable)
+    #[arg(long = "deny-tool", value_name = "TOOL")]
+    deny_tools: Vec<String>,
+
+    /// Ask on this terminal before each tool that changes data runs
+    #[arg(long)]
+    confirm_writes: bool,
+
+    /// Serve another harvest database as csm://db/NAME/ (repeatable)
+    #[arg(long = "db", value_name = "NAME=PATH", value_parser = databases::parse_database)]
+    databases: Vec<(String, PathBuf)>,
+
+    /// Append diagnostics (protocol traffic, tool timings, database queries,
+    /// errors) to this file at every level
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+}
+
+impl Args {
+    /// The config file's `[mcp]` policy with the flags applied
+    fn policy(&self) -> ToolPolicy {
+        let mut policy = ToolPolicy::from_config(&chasm::config::current().mcp);
+        policy.allow_write = (policy.allow_write || self.allow_write) && !self.read_only;
+        policy.allow_tools.extend(self.allow_tools.iter().cloned());
+        policy.deny_tools.extend(self.deny_tools.iter().cloned());
+        policy.confirm_writes |= self.confirm_writes;
+        policy
+    }
+}
 
 fn main() {
-    let mut server = McpServer::new();
+    let args = Args::parse();
+    let policy = args.policy();
+    databases::configure(args.databases);
+    match chasm::plugins::PluginManager::default_dir() {
+        Ok(dir) => {
+            if let Err(e) = plugins::load(&dir) {
+                eprintln!(
+                    "[csm-mcp] Failed to load plugins from {}: {}",
+                    dir.display(),
+                    e
+                );
+            }
+        }
+        Err(e) => eprintln!("[csm-mcp] No plugins directory: {}", e),
+    }
+    if let Some(path) = &args.log_file {
+        if let Err(e) = logging::configure(path) {
+            eprintln!("[csm-mcp] Cannot open log file {}: {}", path.display(), e);
+            std::process::exit(2);
+        }
+    }
+
+    let result = match args.http {
+        Some(addr) => match http::parse_bind_address(&addr) {
+            Ok(addr) => http::serve(
+                addr,
+                HttpOptions {
+                    allowed_origins: args.allowed_origins,
+                    token: args.token.filter(|t| !t.is_empty()),
+                    policy,
+                },
+            ),
+            Err(e) => {
+                eprintln!("[csm-mcp] {}", e);
+                std::process::exit(2);
+            }
+        },
+        None => McpServer::new().with_policy(policy).run(),
+    };
 
-    if let Err(e) = server.run() {
+    if let Err(e) = result {
         eprintln!("[csm-mcp] Server error: {}", e);
         std::process::exit(1);
     }
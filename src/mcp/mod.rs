@@ -10,12 +10,24 @@
 //! - Register sessions to make them visible
 //! - Merge sessions across workspaces
 //! - Search chat history with full-text search
+//! - Serve several named harvest databases
+//! - Notify subscribed clients when sessions change
+//! - Report the progress of long tools and let clients cancel them
+//! - Offer the actions of installed plugins as tools
 
 #![allow(dead_code, unused_imports)]
 
+pub mod databases;
 pub mod db;
+pub mod http;
+pub mod logging;
+pub mod plugins;
+pub mod policy;
+pub mod progress;
 pub mod resources;
+pub mod schemas;
 pub mod server;
+pub mod subscriptions;
 pub mod tools;
 pub mod types;
 
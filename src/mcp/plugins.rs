@@ -0,0 +1,307 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! MCP Server - Plugin tools
+//!
+//! Every action a plugin declares under `capabilities` in its `plugin.json`
+//! becomes a tool named `plugin_<id>_<action>`, so MCP clients can use
+//! installed plugins without glue code for each one. Plugins are loaded from
+//! the `plugins` folder next to `config.toml` when `csm-mcp` starts. Tools of
+//! plugins that may change sessions or config, or run shell commands, count
+//! as write tools. Process plugins are checked every minute and restarted if
+//! they have exited or stopped answering.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use super::types::{CallToolResult, Tool, ToolContent};
+use crate::plugins::{Permission, PluginInstance, PluginKind, PluginManager, PluginState};
+
+/// Longest tool name MCP clients reliably accept
+const MAX_TOOL_NAME: usize = 64;
+
+/// How often process plugins are checked
+const HEALTH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A plugin action offered as a tool
+struct PluginTool {
+    tool: Tool,
+    plugin_id: String,
+    action: String,
+    writes: bool,
+}
+
+struct Plugins {
+    manager: PluginManager,
+    tools: Vec<PluginTool>,
+}
+
+static PLUGINS: OnceLock<Plugins> = OnceLock::new();
+
+/// Load and activate the plugins in `dir` and offer their actions as tools.
+/// Call it before the server starts; later calls have no effect.
+pub fn load(dir: &Path) -> anyhow::Result<()> {
+    let manager = PluginManager::new(dir.to_path_buf());
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let plugins = rt.block_on(async {
+        for id in manager.discover_plugins().await? {
+            let disabled = manager
+                .get_plugin(&id)
+                .await
+                .is_some_and(|p| p.state == PluginState::Disabled);
+            if disabled {
+                continue;
+            }
+            if let Err(e) = manager.activate(&id).await {
+                eprintln!("[csm-mcp] Failed to start plugin {}: {}", id, e);
+            }
+        }
+        anyhow::Ok(manager.list_plugins().await)
+    })?;
+
+    let processes = plugins
+        .iter()
+        .any(|p| p.state == PluginState::Active && p.manifest.kind == PluginKind::Process);
+    if PLUGINS
+        .set(Plugins {
+            manager,
+            tools: plugin_tools(plugins),
+        })
+        .is_ok()
+        && processes
+    {
+        std::thread::spawn(check_health);
+    }
+    Ok(())
+}
+
+/// Check the process plugins every [`HEALTH_INTERVAL`] until the server exits
+fn check_health() {
+    let Some(plugins) = PLUGINS.get() else {
+        return;
+    };
+    let Ok(rt) = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    else {
+        return;
+    };
+    loop {
+        std::thread::sleep(HEALTH_INTERVAL);
+        for (plugin_id, health) in rt.block_on(plugins.manager.check_health()) {
+            if let Err(e) = health {
+                eprintln!("[csm-mcp] Plugin {} is down: {}", plugin_id, e);
+            }
+        }
+    }
+}
+
+fn plugin_tools(mut plugins: Vec<PluginInstance>) -> Vec<PluginTool> {
+    plugins.sort_by(|a, b| a.manifest.id.cmp(&b.manifest.id));
+
+    let mut tools: Vec<PluginTool> = Vec::new();
+    for plugin in plugins.iter().filter(|p| p.state == PluginState::Active) {
+        let manifest = &plugin.manifest;
+        let writes = manifest.permissions.iter().any(changes_data);
+        for capability in &manifest.capabilities {
+            let name = tool_name(&manifest.id, &capability.action);
+            if tools.iter().any(|t| t.tool.name == name) {
+                eprintln!(
+                    "[csm-mcp] Skipping {} action {}: another plugin already offers {}",
+                    manifest.id, capability.action, name
+                );
+                continue;
+            }
+            let description = capability
+                .description
+                .clone()
+                .unwrap_or_else(|| format!("Run the {} action", capability.action));
+            tools.push(PluginTool {
+                tool: Tool {
+                    name,
+                    description: Some(format!(
+                        "{} (plugin {} v{})",
+                        description, manifest.name, manifest.version
+                    )),
+                    input_schema: capability
+                        .input_schema
+                        .clone()
+                        .unwrap_or_else(|| json!({ "type": "object", "properties": {} })),
+                    output_schema: Some(output_schema()),
+                },
+                plugin_id: manifest.id.clone(),
+                action: capability.action.clone(),
+                writes,
+            });
+        }
+    }
+    tools
+}
+
+/// The tools of the loaded plugins
+pub fn tools() -> Vec<Tool> {
+    PLUGINS
+        .get()
+        .map(|plugins| plugins.tools.iter().map(|t| t.tool.clone()).collect())
+        .unwrap_or_default()
+}
+
+fn find(name: &str) -> Option<(&'static Plugins, &'static PluginTool)> {
+    let plugins = PLUGINS.get()?;
+    let tool = plugins.tools.iter().find(|t| t.tool.name == name)?;
+    Some((plugins, tool))
+}
+
+/// Whether `name` is a plugin tool
+pub fn is_plugin_tool(name: &str) -> bool {
+    find(name).is_some()
+}
+
+/// Whether `name` is the tool of a plugin that may change data
+pub fn is_write_tool(name: &str) -> bool {
+    find(name).is_some_and(|(_, tool)| tool.writes)
+}
+
+/// Run the plugin tool `name`, or return `None` if there is no such tool
+pub fn call(name: &str, arguments: &HashMap<String, Value>) -> Option<CallToolResult> {
+    let (plugins, tool) = find(name)?;
+    let arguments = serde_json::to_value(arguments).unwrap_or_else(|_| json!({}));
+
+    let result = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(anyhow::Error::from)
+        .and_then(|rt| {
+            rt.block_on(
+                plugins
+                    .manager
+                    .invoke(&tool.plugin_id, &tool.action, arguments),
+            )
+        });
+
+    Some(match result {
+        Ok(value) => {
+            let value = json!({
+                "plugin": tool.plugin_id,
+                "action": tool.action,
+                "result": value,
+            });
+            CallToolResult {
+                content: vec![ToolContent::Text {
+                    text: serde_json::to_string_pretty(&value).unwrap_or_default(),
+                }],
+                structured_content: Some(value),
+                is_error: None,
+            }
+        }
+        Err(e) => CallToolResult {
+            content: vec![ToolContent::Text {
+                text: format!("Error running plugin {}: {}", tool.plugin_id, e),
+            }],
+            structured_content: None,
+            is_error: Some(true),
+        },
+    })
+}
+
+/// The schema of what every plugin tool returns
+pub fn output_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "plugin": { "type": "string" },
+            "action": { "type": "string" },
+            "result": { "description": "What the plugin printed, as JSON or a string" }
+        },
+        "required": ["plugin", "action", "result"]
+    })
+}
+
+/// Permissions that let a plugin change data or run commands
+fn changes_data(permission: &Permission) -> bool {
+    matches!(
+        permission,
+        Permission::SessionWrite
+            | Permission::SessionDelete
+            | Permission::ConfigWrite
+            | Permission::Shell
+    )
+}
+
+/// `plugin_<id>_<action>`, limited to the characters and length clients allow
+fn tool_name(plugin_id: &str, action: &str) -> String {
+    format!("plugin_{}_{}", plugin_id, action)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(MAX_TOOL_NAME)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::{PluginCategory, PluginManifest, PluginState, PluginStats};
+
+    fn plugin(id: &str, permissions: Vec<Permission>, manifest: Value) -> PluginInstance {
+        let mut manifest: PluginManifest = serde_json::from_value(manifest).unwrap();
+        manifest.id = id.to_string();
+        manifest.permissions = permissions;
+        PluginInstance {
+            manifest,
+            state: PluginState::Active,
+            path: id.into(),
+            installed_at: chrono::Utc::now(),
+            last_activated: None,
+            config: json!({}),
+            granted: Vec::new(),
+            error: None,
+            stats: PluginStats::default(),
+        }
+    }
+
+    #[test]
+    fn test_capabilities_become_tools() {
+        let manifest = json!({
+            "id": "", "name": "Summaries", "version": "1.0.0", "csm_version": "*",
+            "main": "run.sh", "permissions": [], "hooks": [], "dependencies": [],
+            "category": PluginCategory::Analysis, "keywords": [],
+            "capabilities": [
+                { "action": "summarize", "description": "Summarize a session" },
+                { "action": "word.count" }
+            ]
+        });
+        let tools = plugin_tools(vec![
+            plugin("summaries", vec![Permission::SessionRead], manifest.clone()),
+            plugin("cleanup", vec![Permission::SessionDelete], manifest),
+        ]);
+
+        let names: Vec<&str> = tools.iter().map(|t| t.tool.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "plugin_cleanup_summarize",
+                "plugin_cleanup_word_count",
+                "plugin_summaries_summarize",
+                "plugin_summaries_word_count",
+            ]
+        );
+        assert!(tools[0].writes);
+        assert!(!tools[2].writes);
+        assert_eq!(
+            tools[2].tool.description.as_deref(),
+            Some("Summarize a session (plugin Summaries v1.0.0)")
+        );
+        assert_eq!(tools[3].action, "word.count");
+        assert_eq!(tools[3].tool.input_schema["type"], "object");
+    }
+}
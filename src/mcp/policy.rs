@@ -0,0 +1,177 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! MCP Server - Tool access control
+//!
+//! A [`ToolPolicy`] decides which tools an agent sees and may call. It starts
+//! from the `[mcp]` section of the config file and the `csm-mcp` flags:
+//!
+//! - Tools that change data need `allow_write`; `--read-only` turns it off
+//!   whatever the config says.
+//! - `allow_tools`, when set, limits the server to those tools, and
+//!   `deny_tools` removes tools. Names may end in `*` to match a prefix.
+//! - `confirm_writes` asks on the server's terminal before each write runs,
+//!   and refuses the call when there is no terminal to ask on.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::sync::Mutex;
+
+use super::tools;
+use crate::config::McpConfig;
+
+/// Only one approval prompt is shown at a time
+static PROMPT: Mutex<()> = Mutex::new(());
+
+/// Which tools the server offers and runs
+#[derive(Debug, Clone, Default)]
+pub struct ToolPolicy {
+    /// Offer tools that change data
+    pub allow_write: bool,
+    /// Offer only these tools, when not empty
+    pub allow_tools: Vec<String>,
+    /// Never offer these tools
+    pub deny_tools: Vec<String>,
+    /// Ask before each write tool runs
+    pub confirm_writes: bool,
+}
+
+impl ToolPolicy {
+    /// The policy in a config file's `[mcp]` section
+    pub fn from_config(config: &McpConfig) -> Self {
+        Self {
+            allow_write: config.allow_write.unwrap_or(false),
+            allow_tools: config.allow_tools.clone().unwrap_or_default(),
+            deny_tools: config.deny_tools.clone().unwrap_or_default(),
+            confirm_writes: config.confirm_writes.unwrap_or(false),
+        }
+    }
+
+    /// Whether `name` is listed to clients and may be called
+    pub fn offers(&self, name: &str) -> bool {
+        let allowed = self.allow_tools.is_empty()
+            || self.allow_tools.iter().any(|p| pattern_matches(p, name));
+        let denied = self.deny_tools.iter().any(|p| pattern_matches(p, name));
+        allowed && !denied && (self.allow_write || !tools::is_write_tool(name))
+    }
+
+    /// Check a call before it runs, asking for approval if the policy says
+    /// so. The error explains the refusal to the agent.
+    pub fn check(&self, name: &str, arguments: &HashMap<String, Value>) -> Result<(), String> {
+        if !self.offers(name) {
+            return Err(if tools::is_write_tool(name) && !self.allow_write {
+                format!(
+                    "{} changes your data; start csm-mcp with --allow-write to use it",
+                    name
+                )
+            } else {
+                format!("{} is disabled by the csm-mcp tool policy", name)
+            });
+        }
+        if !tools::writes(name, arguments) {
+            return Ok(());
+        }
+        if !self.allow_write {
+            return Err(format!(
+                "{} with these arguments changes your data; start csm-mcp with --allow-write to use it",
+                name
+            ));
+        }
+        if self.confirm_writes {
+            match ask_approval(name, arguments) {
+                Ok(true) => {}
+                Ok(false) => return Err(format!("The user declined to run {}", name)),
+                Err(e) => {
+                    return Err(format!(
+                        "{} needs approval, but there is no terminal to ask on: {}",
+                        name, e
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `pattern` names `tool`, either exactly or as a `prefix*`
+fn pattern_matches(pattern: &str, tool: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => tool.starts_with(prefix),
+        None => pattern == tool,
+    }
+}
+
+/// Ask on the controlling terminal whether to run a tool. Stdin and stdout
+/// carry the protocol, so the prompt cannot use them.
+fn ask_approval(name: &str, arguments: &HashMap<String, Value>) -> io::Result<bool> {
+    let _prompt = PROMPT.lock().unwrap_or_else(|e| e.into_inner());
+    let (input, mut output) = open_terminal()?;
+    write!(
+        output,
+        "\n[csm-mcp] An agent wants to run {} with {}\nAllow? [y/N] ",
+        name,
+        serde_json::to_string(arguments).unwrap_or_default()
+    )?;
+    output.flush()?;
+    let mut answer = String::new();
+    io::BufReader::new(input).read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+#[cfg(unix)]
+fn open_terminal() -> io::Result<(std::fs::File, std::fs::File)> {
+    let input = std::fs::File::open("/dev/tty")?;
+    let output = std::fs::OpenOptions::new().write(true).open("/dev/tty")?;
+    Ok((input, output))
+}
+
+#[cfg(windows)]
+fn open_terminal() -> io::Result<(std::fs::File, std::fs::File)> {
+    let input = std::fs::File::open("CONIN$")?;
+    let output = std::fs::OpenOptions::new().write(true).open("CONOUT$")?;
+    Ok((input, output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_filters_tools() {
+        let none = HashMap::new();
+        let read_only = ToolPolicy {
+            deny_tools: vec!["csm_db_*".to_string()],
+            ..Default::default()
+        };
+        assert!(read_only.offers("csm_search"));
+        assert!(!read_only.offers("csm_db_stats"));
+        assert!(!read_only.offers("csm_tag_session"));
+        assert!(read_only
+            .check("csm_tag_session", &none)
+            .unwrap_err()
+            .contains("--allow-write"));
+
+        // Agents run with a sandbox that can write files and execute code
+        assert!(!read_only.offers("csm_run_agent"));
+        assert!(read_only
+            .check("csm_run_agent", &none)
+            .unwrap_err()
+            .contains("--allow-write"));
+
+        // Exporting inline reads; exporting to a file writes
+        let output = HashMap::from([("output".to_string(), Value::from("out.md"))]);
+        assert!(read_only.check("csm_harvest_export", &none).is_ok());
+        assert!(read_only.check("csm_harvest_export", &output).is_err());
+
+        let harvest_only = ToolPolicy {
+            allow_write: true,
+            allow_tools: vec!["csm_harvest_*".to_string()],
+            ..Default::default()
+        };
+        assert!(harvest_only.check("csm_harvest_run", &none).is_ok());
+        assert!(harvest_only
+            .check("csm_search", &none)
+            .unwrap_err()
+            .contains("tool policy"));
+    }
+}
@@ -0,0 +1,132 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! MCP Server - Tool progress and cancellation
+//!
+//! A client that puts a `progressToken` in the `_meta` of a `tools/call`
+//! receives `notifications/progress` while a long tool runs, and can stop the
+//! call with `notifications/cancelled`. Over stdio, each tool call runs on its
+//! own thread so the server keeps reading cancellations while tools work.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use super::subscriptions::Subscriptions;
+use super::types::JsonRpcNotification;
+
+/// Where a running tool reports progress and learns it was cancelled
+pub struct ToolProgress {
+    token: Option<Value>,
+    out: Option<Arc<Subscriptions>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ToolProgress {
+    /// No one is listening and the call cannot be cancelled
+    pub fn none() -> Self {
+        Self {
+            token: None,
+            out: None,
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Report to `out` under the client's `token`, if it sent one
+    pub fn new(token: Option<Value>, out: Arc<Subscriptions>, cancelled: Arc<AtomicBool>) -> Self {
+        Self {
+            token,
+            out: Some(out),
+            cancelled,
+        }
+    }
+
+    /// Tell the client how far the tool has got
+    pub fn report(&self, progress: usize, total: Option<usize>, message: &str) {
+        let (Some(token), Some(out)) = (&self.token, &self.out) else {
+            return;
+        };
+        if let Err(e) = out.send(&progress_notification(token, progress, total, message)) {
+            eprintln!("[csm-mcp] Failed to send progress: {}", e);
+        }
+    }
+
+    /// Whether the client cancelled the call
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+fn progress_notification(
+    token: &Value,
+    progress: usize,
+    total: Option<usize>,
+    message: &str,
+) -> JsonRpcNotification {
+    let mut params = json!({
+        "progressToken": token,
+        "progress": progress,
+        "message": message,
+    });
+    if let Some(total) = total {
+        params["total"] = json!(total);
+    }
+    JsonRpcNotification::new("notifications/progress", params)
+}
+
+/// Tool calls still running, by request ID, so a client can cancel them
+#[derive(Default)]
+pub struct Cancellations {
+    calls: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl Cancellations {
+    /// Track the call `id`, returning the flag that cancels it
+    pub fn start(&self, id: &Value) -> Arc<AtomicBool> {
+        let flag = Arc::new(AtomicBool::new(false));
+        self.calls
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), Arc::clone(&flag));
+        flag
+    }
+
+    /// Stop tracking the call `id` once it has finished
+    pub fn finish(&self, id: &Value) {
+        self.calls.lock().unwrap().remove(&id.to_string());
+    }
+
+    /// Cancel the call `id`, returning whether it was still running
+    pub fn cancel(&self, id: &Value) -> bool {
+        match self.calls.lock().unwrap().get(&id.to_string()) {
+            Some(flag) => {
+                flag.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_running_calls_by_id() {
+        let calls = Cancellations::default();
+        let flag = calls.start(&json!(7));
+        assert!(!calls.cancel(&json!("7")));
+        assert!(!flag.load(Ordering::SeqCst));
+        assert!(calls.cancel(&json!(7)));
+        assert!(flag.load(Ordering::SeqCst));
+        calls.finish(&json!(7));
+        assert!(!calls.cancel(&json!(7)));
+
+        let notification = progress_notification(&json!("tok"), 2, Some(5), "Copilot");
+        let value = serde_json::to_value(notification).unwrap();
+        assert_eq!(value["method"], "notifications/progress");
+        assert_eq!(value["params"]["progressToken"], "tok");
+        assert_eq!(value["params"]["total"], 5);
+    }
+}
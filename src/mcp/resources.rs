@@ -4,12 +4,17 @@
 
 #![allow(dead_code, unused_imports)]
 
+use super::databases;
 use super::types::*;
 use serde_json::json;
+use std::collections::HashMap;
+
+/// Sessions per page of `csm://sessions` and `csm://db/{name}/sessions`
+const PAGE_SIZE: usize = 100;
 
 /// Get the list of available resources
 pub fn list_resources() -> Vec<Resource> {
-    vec![
+    let mut resources = vec![
         // VS Code workspace resources
         Resource {
             uri: "csm://workspaces".to_string(),
@@ -54,15 +59,102 @@ pub fn list_resources() -> Vec<Resource> {
             description: Some("Database statistics and session counts by provider".to_string()),
             mime_type: Some("application/json".to_string()),
         },
+    ];
+
+    // Harvest databases, one set of resources per name
+    for (name, path) in databases::all() {
+        resources.push(Resource {
+            uri: format!("csm://db/{}/sessions", name),
+            name: format!("Harvested Sessions ({})", name),
+            description: Some(format!("Sessions harvested into {}", path.display())),
+            mime_type: Some("application/json".to_string()),
+        });
+        resources.push(Resource {
+            uri: format!("csm://db/{}/stats", name),
+            name: format!("Harvest Statistics ({})", name),
+            description: Some(format!("Session counts by provider in {}", path.display())),
+            mime_type: Some("application/json".to_string()),
+        });
+    }
+    resources
+}
+
+/// Get the URI templates for resources that take parameters
+pub fn list_resource_templates() -> Vec<ResourceTemplate> {
+    vec![
+        ResourceTemplate {
+            uri_template: "csm://sessions{?page,workspace}".to_string(),
+            name: "VS Code Sessions (paged)".to_string(),
+            description: Some(format!(
+                "Chat sessions from VS Code workspaces, {} per page, optionally from one \
+                 workspace (hash or prefix)",
+                PAGE_SIZE
+            )),
+            mime_type: Some("application/json".to_string()),
+        },
+        ResourceTemplate {
+            uri_template: "csm://workspace/{hash}".to_string(),
+            name: "VS Code Workspace".to_string(),
+            description: Some("A workspace and its chat sessions".to_string()),
+            mime_type: Some("application/json".to_string()),
+        },
+        ResourceTemplate {
+            uri_template: "csm://session/{id}".to_string(),
+            name: "VS Code Session".to_string(),
+            description: Some("A chat session with all its messages".to_string()),
+            mime_type: Some("application/json".to_string()),
+        },
+        ResourceTemplate {
+            uri_template: "csm://db/session/{id}".to_string(),
+            name: "CSM-Web Session".to_string(),
+            description: Some("A session and its messages from the csm-web database".to_string()),
+            mime_type: Some("application/json".to_string()),
+        },
+        ResourceTemplate {
+            uri_template: "csm://db/{database}/sessions{?page,provider}".to_string(),
+            name: "Harvested Sessions (paged)".to_string(),
+            description: Some(format!(
+                "Sessions in a harvest database, newest first, {} per page, optionally from \
+                 one provider",
+                PAGE_SIZE
+            )),
+            mime_type: Some("application/json".to_string()),
+        },
+        ResourceTemplate {
+            uri_template: "csm://db/{database}/session/{id}".to_string(),
+            name: "Harvested Session".to_string(),
+            description: Some(
+                "A session with all its messages from a harvest database".to_string(),
+            ),
+            mime_type: Some("application/json".to_string()),
+        },
     ]
 }
 
 /// Read a resource by URI
 pub fn read_resource(uri: &str) -> ReadResourceResult {
-    match uri {
+    let (path, query) = split_query(uri);
+    let page = match query.get("page").map(|page| page.parse::<usize>()) {
+        None => 1,
+        Some(Ok(page)) if page > 0 => page,
+        Some(_) => {
+            return ReadResourceResult {
+                contents: vec![ResourceContent {
+                    uri: uri.to_string(),
+                    mime_type: Some("text/plain".to_string()),
+                    text: Some(format!("Invalid page in {}; pages start at 1", uri)),
+                    blob: None,
+                }],
+            }
+        }
+    };
+
+    match path {
         // VS Code workspace resources
         "csm://workspaces" => read_workspaces_resource(),
-        "csm://sessions" => read_sessions_resource(),
+        "csm://sessions" => {
+            read_sessions_resource(uri, page, query.get("workspace").map(String::as_str))
+        }
         "csm://orphaned" => read_orphaned_resource(),
         "csm://providers" => read_providers_resource(),
         // CSM Database resources (csm-web)
@@ -71,12 +163,19 @@ pub fn read_resource(uri: &str) -> ReadResourceResult {
         "csm://db/stats" => read_db_stats_resource(),
         _ => {
             // Try to parse dynamic URIs
-            if let Some(hash) = uri.strip_prefix("csm://workspace/") {
+            if let Some(hash) = path.strip_prefix("csm://workspace/") {
                 read_workspace_resource(hash)
-            } else if let Some(id) = uri.strip_prefix("csm://session/") {
+            } else if let Some(id) = path.strip_prefix("csm://session/") {
                 read_session_resource(id)
-            } else if let Some(id) = uri.strip_prefix("csm://db/session/") {
+            } else if let Some(id) = path.strip_prefix("csm://db/session/") {
                 read_db_session_resource(id)
+            } else if let Some((name, rest)) = path
+                .strip_prefix("csm://db/")
+                .and_then(|rest| rest.split_once('/'))
+                .filter(|(name, _)| databases::all().contains_key(*name))
+            {
+                let provider = query.get("provider").map(String::as_str);
+                read_harvest_resource(uri, name, rest, page, provider)
             } else {
                 ReadResourceResult {
                     contents: vec![ResourceContent {
@@ -135,47 +234,73 @@ fn read_workspaces_resource() -> ReadResourceResult {
     }
 }
 
-fn read_sessions_resource() -> ReadResourceResult {
-    use crate::workspace::{discover_workspaces, get_chat_sessions_from_workspace};
+fn read_sessions_resource(uri: &str, page: usize, workspace: Option<&str>) -> ReadResourceResult {
+    use crate::storage::{is_session_file_extension, parse_session_file};
+    use crate::workspace::discover_workspaces;
 
     match discover_workspaces() {
-        Ok(workspaces) => {
-            let mut all_sessions = Vec::new();
-
+        Ok(mut workspaces) => {
+            // List the files first so only this page's sessions are parsed
+            workspaces.sort_by(|a, b| a.hash.cmp(&b.hash));
+            let mut files = Vec::new();
             for ws in &workspaces {
-                if let Ok(sessions) = get_chat_sessions_from_workspace(&ws.workspace_path) {
-                    for s in sessions {
-                        all_sessions.push(json!({
-                            "id": s.session.session_id,
-                            "title": s.session.title(),
-                            "message_count": s.session.requests.len(),
-                            "last_message_date": s.session.last_message_date,
-                            "workspace_hash": ws.hash,
-                            "project_path": ws.project_path,
-                            "file_path": s.path.display().to_string()
-                        }));
-                    }
+                if workspace.is_some_and(|hash| !ws.hash.starts_with(hash)) {
+                    continue;
                 }
+                let Ok(entries) = std::fs::read_dir(&ws.chat_sessions_path) else {
+                    continue;
+                };
+                let mut paths: Vec<_> = entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.extension().is_some_and(is_session_file_extension))
+                    .collect();
+                paths.sort();
+                files.extend(paths.into_iter().map(|path| (ws, path)));
             }
 
+            let sessions: Vec<serde_json::Value> = files
+                .iter()
+                .skip((page - 1) * PAGE_SIZE)
+                .take(PAGE_SIZE)
+                .map(|(ws, path)| match parse_session_file(path) {
+                    Ok(session) => json!({
+                        "id": session.session_id,
+                        "title": session.title(),
+                        "message_count": session.requests.len(),
+                        "last_message_date": session.last_message_date,
+                        "workspace_hash": ws.hash,
+                        "project_path": ws.project_path,
+                        "file_path": path.display().to_string()
+                    }),
+                    Err(e) => json!({
+                        "workspace_hash": ws.hash,
+                        "project_path": ws.project_path,
+                        "file_path": path.display().to_string(),
+                        "error": e.to_string()
+                    }),
+                })
+                .collect();
+
+            let mut value = paging(
+                "csm://sessions",
+                page,
+                files.len(),
+                &[("workspace", workspace)],
+            );
+            value["sessions"] = json!(sessions);
+
             ReadResourceResult {
                 contents: vec![ResourceContent {
-                    uri: "csm://sessions".to_string(),
+                    uri: uri.to_string(),
                     mime_type: Some("application/json".to_string()),
-                    text: Some(
-                        serde_json::to_string_pretty(&json!({
-                            "sessions": all_sessions,
-                            "total": all_sessions.len()
-                        }))
-                        .unwrap_or_default(),
-                    ),
+                    text: Some(serde_json::to_string_pretty(&value).unwrap_or_default()),
                     blob: None,
                 }],
             }
         }
         Err(e) => ReadResourceResult {
             contents: vec![ResourceContent {
-                uri: "csm://sessions".to_string(),
+                uri: uri.to_string(),
                 mime_type: Some("text/plain".to_string()),
                 text: Some(format!("Error: {}", e)),
                 blob: None,
@@ -641,3 +766,166 @@ fn read_db_session_resource(session_id: &str) -> ReadResourceResult {
         },
     }
 }
+
+/// `sessions`, `stats`, or `session/{id}` in the harvest database `name`
+fn read_harvest_resource(
+    uri: &str,
+    name: &str,
+    path: &str,
+    page: usize,
+    provider: Option<&str>,
+) -> ReadResourceResult {
+    use crate::commands::{count_harvested_sessions, export_session_json, page_harvested_sessions};
+
+    let result = databases::open(Some(name)).and_then(|conn| match path {
+        "sessions" => {
+            let total = count_harvested_sessions(&conn, provider, None, None)?;
+            let offset = (page - 1) * PAGE_SIZE;
+            let sessions: Vec<serde_json::Value> =
+                page_harvested_sessions(&conn, provider, None, None, offset, PAGE_SIZE)?
+                    .into_iter()
+                    .map(
+                        |(id, provider, title, message_count, created, updated, workspace)| {
+                            json!({
+                                "id": id,
+                                "provider": provider,
+                                "title": title,
+                                "message_count": message_count,
+                                "created_at": created,
+                                "updated_at": updated,
+                                "workspace_name": workspace,
+                            })
+                        },
+                    )
+                    .collect();
+            let base = format!("csm://db/{}/sessions", name);
+            let mut value = paging(&base, page, total, &[("provider", provider)]);
+            value["database"] = json!(name);
+            value["sessions"] = json!(sessions);
+            Ok(value)
+        }
+        "stats" => {
+            let mut stmt = conn.prepare(
+                "SELECT provider, COUNT(*) FROM sessions GROUP BY provider ORDER BY provider",
+            )?;
+            let counts = stmt
+                .query_map([], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            let total: i64 = counts.iter().map(|(_, c)| c).sum();
+            Ok(json!({
+                "database": name,
+                "total_sessions": total,
+                "by_provider": counts.into_iter().collect::<serde_json::Value>(),
+                "db_path": databases::resolve(Some(name))?.display().to_string(),
+            }))
+        }
+        _ => {
+            let id = path
+                .strip_prefix("session/")
+                .ok_or_else(|| anyhow::anyhow!("Unknown resource: {}", uri))?;
+            let session = export_session_json(&conn, None, Some(&[id.to_string()]))?
+                .into_iter()
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("Session not found: {}", id))?;
+            Ok(serde_json::from_str(&session)?)
+        }
+    });
+
+    ReadResourceResult {
+        contents: vec![match result {
+            Ok(value) => ResourceContent {
+                uri: uri.to_string(),
+                mime_type: Some("application/json".to_string()),
+                text: Some(serde_json::to_string_pretty(&value).unwrap_or_default()),
+                blob: None,
+            },
+            Err(e) => ResourceContent {
+                uri: uri.to_string(),
+                mime_type: Some("text/plain".to_string()),
+                text: Some(format!("Error: {}", e)),
+                blob: None,
+            },
+        }],
+    }
+}
+
+/// Split `csm://sessions?page=2&workspace=ab` into its path and decoded
+/// query parameters. Empty parameters are left out.
+fn split_query(uri: &str) -> (&str, HashMap<String, String>) {
+    let Some((path, query)) = uri.split_once('?') else {
+        return (uri, HashMap::new());
+    };
+    let decode = |text: &str| {
+        urlencoding::decode(&text.replace('+', " "))
+            .map(|text| text.into_owned())
+            .unwrap_or_else(|_| text.to_string())
+    };
+    let params = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(key, value)| (decode(key), decode(value)))
+        .collect();
+    (path, params)
+}
+
+/// Paging fields for `page` of `total` items under `base`, with the URI of
+/// the next page, or null on the last one
+fn paging(
+    base: &str,
+    page: usize,
+    total: usize,
+    filters: &[(&str, Option<&str>)],
+) -> serde_json::Value {
+    let pages = total.div_ceil(PAGE_SIZE);
+    let next = (page < pages).then(|| {
+        let mut uri = format!("{}?page={}", base, page + 1);
+        for (key, value) in filters {
+            if let Some(value) = value {
+                uri.push_str(&format!("&{}={}", key, urlencoding::encode(value)));
+            }
+        }
+        uri
+    });
+    json!({
+        "page": page,
+        "page_size": PAGE_SIZE,
+        "pages": pages,
+        "total": total,
+        "next": next,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paged_uris() {
+        let (path, query) = split_query("csm://db/work/sessions?page=2&provider=copilot%20chat&x=");
+        assert_eq!(path, "csm://db/work/sessions");
+        assert_eq!(query.get("page").map(String::as_str), Some("2"));
+        assert_eq!(
+            query.get("provider").map(String::as_str),
+            Some("copilot chat")
+        );
+        assert!(!query.contains_key("x"));
+        assert_eq!(split_query("csm://sessions").0, "csm://sessions");
+
+        let value = paging(
+            "csm://db/work/sessions",
+            2,
+            250,
+            &[("provider", Some("copilot chat"))],
+        );
+        assert_eq!(value["pages"], 3);
+        assert_eq!(
+            value["next"],
+            "csm://db/work/sessions?page=3&provider=copilot%20chat"
+        );
+        let last = paging("csm://sessions", 3, 250, &[("workspace", None)]);
+        assert!(last["next"].is_null());
+    }
+}
@@ -0,0 +1,433 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! MCP Server - Tool output schemas
+//!
+//! Tools return their results as typed JSON in `structuredContent`, described
+//! by the `outputSchema` each tool lists, so clients can show session lists
+//! and search results as tables. The same JSON still goes in a text block for
+//! clients older than protocol version 2025-06-18.
+
+use serde_json::{json, Value};
+
+/// The schema of what the tool `name` returns when it succeeds
+pub fn output_schema(name: &str) -> Option<Value> {
+    let schema = match name {
+        "csm_list_workspaces" => object(
+            json!({
+                "workspaces": array(object(
+                    json!({
+                        "hash": string(),
+                        "project_path": string(),
+                        "session_count": integer(),
+                        "has_chats": boolean(),
+                    }),
+                    &["hash", "project_path", "session_count", "has_chats"],
+                )),
+                "total": integer(),
+            }),
+            &["workspaces", "total"],
+        ),
+        "csm_find_workspace" => object(
+            json!({
+                "pattern": string(),
+                "workspaces": array(object(
+                    json!({
+                        "hash": string(),
+                        "project_path": nullable("string"),
+                        "workspace_path": string(),
+                        "session_count": integer(),
+                        "sessions": array(object(
+                            json!({
+                                "id": nullable("string"),
+                                "title": string(),
+                                "path": string(),
+                            }),
+                            &["id", "title", "path"],
+                        )),
+                    }),
+                    &["hash", "workspace_path", "session_count", "sessions"],
+                )),
+                "total": integer(),
+            }),
+            &["pattern", "workspaces", "total"],
+        ),
+        "csm_list_sessions" => object(
+            json!({
+                "sessions": array(object(
+                    json!({
+                        "id": nullable("string"),
+                        "title": string(),
+                        "message_count": integer(),
+                        "workspace_hash": string(),
+                        "project_path": nullable("string"),
+                        "file_path": string(),
+                    }),
+                    &["id", "title", "message_count", "workspace_hash", "file_path"],
+                )),
+                "total": integer(),
+            }),
+            &["sessions", "total"],
+        ),
+        "csm_list_orphaned" | "csm_show_history" | "csm_detect" => status(json!({}), &[]),
+        "csm_register_all" => status(
+            json!({ "merge": boolean(), "force": boolean() }),
+            &["merge", "force"],
+        ),
+        "csm_register_sessions" => status(
+            json!({
+                "ids": array(string()),
+                "titles": nullable_array(string()),
+                "force": boolean(),
+            }),
+            &["ids", "force"],
+        ),
+        "csm_show_session" => object(
+            json!({
+                "id": string(),
+                "title": string(),
+                "message_count": integer(),
+                "last_message_date": integer(),
+                "is_imported": boolean(),
+                "workspace_hash": string(),
+                "project_path": nullable("string"),
+                "file_path": string(),
+                "messages": array(object(
+                    json!({ "message": string(), "response": string() }),
+                    &["message", "response"],
+                )),
+            }),
+            &[
+                "id",
+                "title",
+                "message_count",
+                "workspace_hash",
+                "file_path",
+                "messages",
+            ],
+        ),
+        "csm_merge_sessions" => status(
+            json!({ "title": nullable("string"), "force": boolean() }),
+            &["force"],
+        ),
+        "csm_search" => status(
+            json!({
+                "query": string(),
+                "total": integer(),
+                "results": array(search_hit()),
+            }),
+            &["query", "total", "results"],
+        ),
+        "csm_harvest_databases" => object(
+            json!({
+                "default": string(),
+                "databases": array(object(
+                    json!({
+                        "name": string(),
+                        "path": string(),
+                        "exists": boolean(),
+                        "sessions": nullable("integer"),
+                        "resources": string(),
+                    }),
+                    &["name", "path", "exists", "resources"],
+                )),
+            }),
+            &["default", "databases"],
+        ),
+        "csm_harvest_run" => status(
+            json!({
+                "stats": object(
+                    json!({
+                        "providers_scanned": integer(),
+                        "workspaces_scanned": integer(),
+                        "sessions_found": integer(),
+                        "sessions_added": integer(),
+                        "sessions_updated": integer(),
+                        "sessions_skipped": integer(),
+                        "errors": array(string()),
+                    }),
+                    &["sessions_found", "sessions_added", "sessions_updated", "errors"],
+                ),
+            }),
+            &["stats"],
+        ),
+        "csm_harvest_search" | "csm_semantic_search" => object(
+            json!({
+                "query": string(),
+                "mode": { "type": "string", "enum": ["text", "semantic"] },
+                "total": integer(),
+                "results": array(search_hit()),
+            }),
+            &["query", "mode", "total", "results"],
+        ),
+        "csm_harvest_list" => object(
+            json!({
+                "sessions": array(object(
+                    json!({
+                        "id": string(),
+                        "provider": string(),
+                        "title": string(),
+                        "message_count": integer(),
+                        "created_at": integer(),
+                        "updated_at": integer(),
+                        "workspace_name": nullable("string"),
+                    }),
+                    &["id", "provider", "title", "message_count", "created_at", "updated_at"],
+                )),
+                "total": integer(),
+            }),
+            &["sessions", "total"],
+        ),
+        "csm_harvest_export" => object(
+            json!({
+                "status": string(),
+                "sessions": integer(),
+                "format": string(),
+                "output": {
+                    "type": "string",
+                    "description": "File the export was written to"
+                },
+                "content": {
+                    "type": "string",
+                    "description": "The export, when no output file was given"
+                },
+            }),
+            &["sessions", "format"],
+        ),
+        "csm_tag_session" => status(
+            json!({ "session_id": string(), "tags": array(string()) }),
+            &["session_id", "tags"],
+        ),
+        "csm_run_agent" => object(
+            json!({
+                "agent": string(),
+                "response": string(),
+                "success": boolean(),
+                "error": nullable("string"),
+                "token_usage": object(
+                    json!({
+                        "prompt_tokens": integer(),
+                        "completion_tokens": integer(),
+                        "total_tokens": integer(),
+                    }),
+                    &["prompt_tokens", "completion_tokens", "total_tokens"],
+                ),
+                "duration_ms": integer(),
+            }),
+            &["agent", "response", "success", "token_usage", "duration_ms"],
+        ),
+        "csm_db_list_workspaces" => object(
+            json!({
+                "workspaces": array(object(
+                    json!({
+                        "id": string(),
+                        "name": string(),
+                        "path": nullable("string"),
+                        "provider": string(),
+                        "created_at": integer(),
+                        "updated_at": integer(),
+                    }),
+                    &["id", "name", "provider", "created_at", "updated_at"],
+                )),
+                "total": integer(),
+                "source": string(),
+            }),
+            &["workspaces", "total"],
+        ),
+        "csm_db_list_sessions" => object(
+            json!({
+                "sessions": array(db_session()),
+                "total": integer(),
+                "filters": object(
+                    json!({
+                        "workspace_id": nullable("string"),
+                        "provider": nullable("string"),
+                        "limit": integer(),
+                    }),
+                    &["limit"],
+                ),
+                "source": string(),
+            }),
+            &["sessions", "total"],
+        ),
+        "csm_db_get_session" => object(
+            json!({
+                "session": db_session(),
+                "messages": array(object(
+                    json!({
+                        "id": string(),
+                        "role": string(),
+                        "content": string(),
+                        "model": nullable("string"),
+                        "created_at": integer(),
+                    }),
+                    &["id", "role", "content", "created_at"],
+                )),
+                "source": string(),
+            }),
+            &["session", "messages"],
+        ),
+        "csm_db_search" => object(
+            json!({
+                "query": string(),
+                "results": array(object(
+                    json!({
+                        "id": string(),
+                        "title": string(),
+                        "provider": string(),
+                        "message_count": integer(),
+                        "updated_at": integer(),
+                    }),
+                    &["id", "title", "provider", "message_count", "updated_at"],
+                )),
+                "total": integer(),
+                "source": string(),
+            }),
+            &["query", "results", "total"],
+        ),
+        "csm_db_stats" => object(
+            json!({
+                "total_sessions": integer(),
+                "by_provider": {
+                    "type": "object",
+                    "additionalProperties": integer()
+                },
+                "db_path": string(),
+                "source": string(),
+            }),
+            &["total_sessions", "by_provider"],
+        ),
+        _ if super::plugins::is_plugin_tool(name) => super::plugins::output_schema(),
+        _ => return None,
+    };
+    Some(schema)
+}
+
+/// A harvest search result
+fn search_hit() -> Value {
+    object(
+        json!({
+            "id": string(),
+            "title": string(),
+            "provider": string(),
+            "workspace_id": nullable("string"),
+            "workspace_name": nullable("string"),
+            "message_count": integer(),
+            "updated_at": integer(),
+            "score": { "type": "number" },
+            "snippet": nullable("string"),
+        }),
+        &[
+            "id",
+            "title",
+            "provider",
+            "message_count",
+            "updated_at",
+            "score",
+        ],
+    )
+}
+
+/// A session in the csm-web database
+fn db_session() -> Value {
+    object(
+        json!({
+            "id": string(),
+            "workspace_id": nullable("string"),
+            "provider": string(),
+            "title": string(),
+            "model": nullable("string"),
+            "message_count": integer(),
+            "created_at": integer(),
+            "updated_at": integer(),
+            "archived": boolean(),
+        }),
+        &[
+            "id",
+            "provider",
+            "title",
+            "message_count",
+            "created_at",
+            "updated_at",
+        ],
+    )
+}
+
+/// The `status` and `message` of a tool that acts, plus its own `properties`
+fn status(mut properties: Value, required: &[&str]) -> Value {
+    properties["status"] = string();
+    properties["message"] = string();
+    let mut required = required.to_vec();
+    required.insert(0, "status");
+    object(properties, &required)
+}
+
+fn object(properties: Value, required: &[&str]) -> Value {
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+fn array(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+fn nullable_array(items: Value) -> Value {
+    json!({ "type": ["array", "null"], "items": items })
+}
+
+fn nullable(kind: &str) -> Value {
+    json!({ "type": [kind, "null"] })
+}
+
+fn string() -> Value {
+    json!({ "type": "string" })
+}
+
+fn integer() -> Value {
+    json!({ "type": "integer" })
+}
+
+fn boolean() -> Value {
+    json!({ "type": "boolean" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every required property is declared, in every nested object
+    fn check(schema: &Value, path: &str) {
+        if let Some(required) = schema["required"].as_array() {
+            for key in required {
+                let key = key.as_str().unwrap();
+                assert!(
+                    schema["properties"].get(key).is_some(),
+                    "{} requires undeclared '{}'",
+                    path,
+                    key
+                );
+            }
+        }
+        if let Some(properties) = schema["properties"].as_object() {
+            for (key, property) in properties {
+                check(property, &format!("{}.{}", path, key));
+            }
+        }
+        if schema.get("items").is_some() {
+            check(&schema["items"], &format!("{}[]", path));
+        }
+    }
+
+    #[test]
+    fn test_every_tool_has_an_output_schema() {
+        for tool in super::super::tools::list_tools() {
+            let schema = output_schema(&tool.name)
+                .unwrap_or_else(|| panic!("{} has no output schema", tool.name));
+            assert_eq!(schema["type"], "object");
+            check(&schema, &tool.name);
+        }
+        assert!(output_schema("csm_unknown").is_none());
+    }
+}
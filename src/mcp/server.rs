@@ -1,30 +1,92 @@
 // Copyright (c) 2024-2026 Nervosys LLC
 // SPDX-License-Identifier: AGPL-3.0-only
 //! MCP Server - Main server implementation using stdio transport
+//!
+//! The HTTP transport in [`super::http`] shares the request handling. Only
+//! stdio connections can subscribe to resources or follow tool progress,
+//! since the HTTP transport has no stream for messages the server starts.
 
 #![allow(dead_code, unused_imports)]
 
+use super::logging::{self, Level};
+use super::policy::ToolPolicy;
+use super::progress::{Cancellations, ToolProgress};
 use super::resources;
+use super::subscriptions::Subscriptions;
 use super::tools;
 use super::types::*;
 use serde_json::json;
 use std::io::{self, BufRead, Write};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Instant;
+
+/// Protocol revisions the server speaks, newest first. The streamable HTTP
+/// transport arrived in 2025-03-26, structured tool output in 2025-06-18.
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// Take stdout for protocol messages and point the process's stdout at
+/// stderr, so commands run by tools cannot print into the JSON-RPC stream
+#[cfg(unix)]
+fn protocol_output() -> io::Result<Box<dyn Write + Send>> {
+    use std::os::unix::io::FromRawFd;
+
+    // SAFETY: stdout is open; dup returns a new descriptor owned by no one else
+    let fd = unsafe { libc::dup(libc::STDOUT_FILENO) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: both descriptors are valid; dup2 replaces stdout atomically
+    if unsafe { libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: fd came from dup above and is closed only by this File
+    Ok(Box::new(io::BufWriter::new(unsafe {
+        std::fs::File::from_raw_fd(fd)
+    })))
+}
+
+#[cfg(not(unix))]
+fn protocol_output() -> io::Result<Box<dyn Write + Send>> {
+    Ok(Box::new(io::stdout()))
+}
 
 /// MCP Server for Chat System Manager
 pub struct McpServer {
     initialized: bool,
+    /// Which tools are offered and may run
+    policy: ToolPolicy,
+    /// Resource subscriptions, on transports that can deliver notifications
+    subscriptions: Option<Arc<Subscriptions>>,
 }
 
 impl McpServer {
     pub fn new() -> Self {
-        Self { initialized: false }
+        Self {
+            initialized: false,
+            policy: ToolPolicy::default(),
+            subscriptions: None,
+        }
+    }
+
+    /// Offer and run tools according to `policy`
+    pub fn with_policy(mut self, policy: ToolPolicy) -> Self {
+        self.policy = policy;
+        self
     }
 
     /// Run the MCP server using stdio transport
     pub fn run(&mut self) -> io::Result<()> {
         let stdin = io::stdin();
-        let stdout = io::stdout();
-        let mut stdout = stdout.lock();
+        // Responses and notifications share stdout
+        let output = Subscriptions::new(protocol_output()?);
+        self.subscriptions = Some(Arc::clone(&output));
+        logging::connect(Arc::clone(&output));
+
+        // Tool calls run on their own threads so cancellations get through
+        let cancellations = Arc::new(Cancellations::default());
+        let mut calls: Vec<JoinHandle<()>> = Vec::new();
 
         eprintln!("[csm-mcp] Server starting...");
 
@@ -35,33 +97,97 @@ impl McpServer {
                 continue;
             }
 
-            eprintln!("[csm-mcp] Received: {}", &line[..line.len().min(100)]);
+            eprintln!("[csm-mcp] Received: {}", crate::text::prefix_chars(&line, 100));
+            logging::traffic("<-", &line);
 
             match serde_json::from_str::<JsonRpcRequest>(&line) {
                 Ok(request) => {
+                    if request.method == "notifications/cancelled" {
+                        if let Some(id) = request.params.get("requestId") {
+                            cancellations.cancel(id);
+                        }
+                        continue;
+                    }
+                    if request.method == "tools/call" && request.id.is_some() {
+                        calls.retain(|call| !call.is_finished());
+                        calls.push(self.spawn_tool_call(request, &output, &cancellations)?);
+                        continue;
+                    }
+
+                    // Notifications are never answered
+                    let notification = request.id.is_none();
                     let response = self.handle_request(request);
+                    if notification {
+                        continue;
+                    }
                     let response_str = serde_json::to_string(&response)?;
                     eprintln!(
                         "[csm-mcp] Sending: {}",
-                        &response_str[..response_str.len().min(100)]
+                        crate::text::prefix_chars(&response_str, 100)
                     );
-                    writeln!(stdout, "{}", response_str)?;
-                    stdout.flush()?;
+                    logging::traffic("->", &response_str);
+                    output.send(&response)?;
                 }
                 Err(e) => {
                     eprintln!("[csm-mcp] Parse error: {}", e);
+                    logging::log(
+                        Level::Error,
+                        "server",
+                        json!({ "error": format!("Parse error: {}", e) }),
+                    );
                     let error_response =
                         JsonRpcResponse::error(None, -32700, format!("Parse error: {}", e));
-                    writeln!(stdout, "{}", serde_json::to_string(&error_response)?)?;
-                    stdout.flush()?;
+                    output.send(&error_response)?;
                 }
             }
         }
 
+        // Answer the calls still running before exiting
+        for call in calls {
+            let _ = call.join();
+        }
         Ok(())
     }
 
-    fn handle_request(&mut self, request: JsonRpcRequest) -> JsonRpcResponse {
+    /// Run a tool call on its own thread, reporting progress to the client
+    /// and sending the response unless the client cancelled the call
+    fn spawn_tool_call(
+        &self,
+        request: JsonRpcRequest,
+        output: &Arc<Subscriptions>,
+        cancellations: &Arc<Cancellations>,
+    ) -> io::Result<JoinHandle<()>> {
+        let id = request.id.clone().unwrap_or_default();
+        let cancelled = cancellations.start(&id);
+        let token = request
+            .params
+            .get("_meta")
+            .and_then(|meta| meta.get("progressToken"))
+            .cloned();
+        let progress = ToolProgress::new(token, Arc::clone(output), Arc::clone(&cancelled));
+        let policy = self.policy.clone();
+        let output = Arc::clone(output);
+        let cancellations = Arc::clone(cancellations);
+
+        std::thread::Builder::new()
+            .name("csm-mcp-tool".to_string())
+            .spawn(move || {
+                let response = call_tool(&policy, request, &progress);
+                cancellations.finish(&id);
+                if cancelled.load(Ordering::SeqCst) {
+                    eprintln!("[csm-mcp] Request {} was cancelled", id);
+                    return;
+                }
+                if let Ok(sent) = serde_json::to_string(&response) {
+                    logging::traffic("->", &sent);
+                }
+                if let Err(e) = output.send(&response) {
+                    eprintln!("[csm-mcp] Failed to send response: {}", e);
+                }
+            })
+    }
+
+    pub(crate) fn handle_request(&mut self, request: JsonRpcRequest) -> JsonRpcResponse {
         match request.method.as_str() {
             "initialize" => self.handle_initialize(request),
             "initialized" => {
@@ -71,7 +197,11 @@ impl McpServer {
             "tools/list" => self.handle_tools_list(request),
             "tools/call" => self.handle_tools_call(request),
             "resources/list" => self.handle_resources_list(request),
+            "resources/templates/list" => self.handle_resource_templates_list(request),
             "resources/read" => self.handle_resources_read(request),
+            "resources/subscribe" => self.handle_resources_subscribe(request),
+            "resources/unsubscribe" => self.handle_resources_unsubscribe(request),
+            "logging/setLevel" => self.handle_logging_set_level(request),
             "ping" => JsonRpcResponse::success(request.id, json!({})),
             _ => JsonRpcResponse::error(
                 request.id,
@@ -84,17 +214,29 @@ impl McpServer {
     fn handle_initialize(&mut self, request: JsonRpcRequest) -> JsonRpcResponse {
         self.initialized = true;
 
+        // Answer with the client's revision if we speak it, else our newest
+        let requested = request
+            .params
+            .get("protocolVersion")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        let protocol_version = PROTOCOL_VERSIONS
+            .iter()
+            .find(|v| **v == requested)
+            .unwrap_or(&PROTOCOL_VERSIONS[0]);
+
         let result = InitializeResult {
-            protocol_version: "2024-11-05".to_string(),
+            protocol_version: protocol_version.to_string(),
             capabilities: ServerCapabilities {
                 tools: Some(ToolsCapability {
                     list_changed: Some(false),
                 }),
                 resources: Some(ResourcesCapability {
                     list_changed: Some(false),
-                    subscribe: Some(false),
+                    subscribe: Some(self.subscriptions.is_some()),
                 }),
                 prompts: None,
+                logging: Some(json!({})),
             },
             server_info: ServerInfo {
                 name: "csm-mcp".to_string(),
@@ -106,19 +248,24 @@ impl McpServer {
     }
 
     fn handle_tools_list(&self, request: JsonRpcRequest) -> JsonRpcResponse {
-        let tools = tools::list_tools();
+        let tools: Vec<Tool> = tools::list_tools()
+            .into_iter()
+            .filter(|tool| self.policy.offers(&tool.name))
+            .collect();
         JsonRpcResponse::success(request.id, json!({ "tools": tools }))
     }
 
     fn handle_tools_call(&self, request: JsonRpcRequest) -> JsonRpcResponse {
-        let params: Result<CallToolParams, _> = serde_json::from_value(request.params.clone());
+        call_tool(&self.policy, request, &ToolProgress::none())
+    }
 
-        match params {
-            Ok(params) => {
-                let result = tools::call_tool(&params.name, &params.arguments);
-                JsonRpcResponse::success(request.id, serde_json::to_value(result).unwrap())
+    fn handle_logging_set_level(&self, request: JsonRpcRequest) -> JsonRpcResponse {
+        match serde_json::from_value::<Level>(request.params["level"].clone()) {
+            Ok(level) => {
+                logging::set_level(level);
+                JsonRpcResponse::success(request.id, json!({}))
             }
-            Err(e) => JsonRpcResponse::error(request.id, -32602, format!("Invalid params: {}", e)),
+            Err(e) => JsonRpcResponse::error(request.id, -32602, format!("Invalid level: {}", e)),
         }
     }
 
@@ -127,6 +274,11 @@ impl McpServer {
         JsonRpcResponse::success(request.id, json!({ "resources": resources }))
     }
 
+    fn handle_resource_templates_list(&self, request: JsonRpcRequest) -> JsonRpcResponse {
+        let templates = resources::list_resource_templates();
+        JsonRpcResponse::success(request.id, json!({ "resourceTemplates": templates }))
+    }
+
     fn handle_resources_read(&self, request: JsonRpcRequest) -> JsonRpcResponse {
         let params: Result<ReadResourceParams, _> = serde_json::from_value(request.params.clone());
 
@@ -138,6 +290,87 @@ impl McpServer {
             Err(e) => JsonRpcResponse::error(request.id, -32602, format!("Invalid params: {}", e)),
         }
     }
+
+    fn handle_resources_subscribe(&self, request: JsonRpcRequest) -> JsonRpcResponse {
+        let Some(subscriptions) = &self.subscriptions else {
+            return JsonRpcResponse::error(
+                request.id,
+                -32601,
+                "Resource subscriptions need the stdio transport".to_string(),
+            );
+        };
+        match serde_json::from_value::<SubscribeParams>(request.params.clone()) {
+            Ok(params) => match subscriptions.subscribe(&params.uri) {
+                Ok(()) => JsonRpcResponse::success(request.id, json!({})),
+                Err(e) => JsonRpcResponse::error(request.id, -32602, e),
+            },
+            Err(e) => JsonRpcResponse::error(request.id, -32602, format!("Invalid params: {}", e)),
+        }
+    }
+
+    fn handle_resources_unsubscribe(&self, request: JsonRpcRequest) -> JsonRpcResponse {
+        let Some(subscriptions) = &self.subscriptions else {
+            return JsonRpcResponse::error(
+                request.id,
+                -32601,
+                "Resource subscriptions need the stdio transport".to_string(),
+            );
+        };
+        match serde_json::from_value::<SubscribeParams>(request.params.clone()) {
+            Ok(params) => {
+                subscriptions.unsubscribe(&params.uri);
+                JsonRpcResponse::success(request.id, json!({}))
+            }
+            Err(e) => JsonRpcResponse::error(request.id, -32602, format!("Invalid params: {}", e)),
+        }
+    }
+}
+
+/// Check a `tools/call` against the policy and run it
+fn call_tool(
+    policy: &ToolPolicy,
+    request: JsonRpcRequest,
+    progress: &ToolProgress,
+) -> JsonRpcResponse {
+    let params: Result<CallToolParams, _> = serde_json::from_value(request.params.clone());
+
+    match params {
+        Ok(params) => {
+            if let Err(refusal) = policy.check(&params.name, &params.arguments) {
+                logging::log(
+                    Level::Warning,
+                    "tools",
+                    json!({ "tool": params.name, "refused": refusal }),
+                );
+                let refused = CallToolResult {
+                    content: vec![ToolContent::Text { text: refusal }],
+                    structured_content: None,
+                    is_error: Some(true),
+                };
+                return JsonRpcResponse::success(
+                    request.id,
+                    serde_json::to_value(refused).unwrap(),
+                );
+            }
+            let started = Instant::now();
+            let result = tools::call_tool_with_progress(&params.name, &params.arguments, progress);
+            let duration_ms = started.elapsed().as_millis() as u64;
+            match (&result.is_error, result.content.first()) {
+                (Some(true), Some(ToolContent::Text { text })) => logging::log(
+                    Level::Error,
+                    "tools",
+                    json!({ "tool": params.name, "duration_ms": duration_ms, "error": text }),
+                ),
+                _ => logging::log(
+                    Level::Info,
+                    "tools",
+                    json!({ "tool": params.name, "duration_ms": duration_ms }),
+                ),
+            }
+            JsonRpcResponse::success(request.id, serde_json::to_value(result).unwrap())
+        }
+        Err(e) => JsonRpcResponse::error(request.id, -32602, format!("Invalid params: {}", e)),
+    }
 }
 
 impl Default for McpServer {
@@ -0,0 +1,166 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! MCP Server - Resource subscriptions
+//!
+//! Clients call `resources/subscribe` for the workspace and session resources
+//! they show. The first subscription starts a [`SessionWatcher`] on VS Code's
+//! session files, and every change it reports sends
+//! `notifications/resources/updated` for each subscribed resource the change
+//! affects, so clients re-read `csm://sessions` only when it changed.
+
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use super::types::JsonRpcNotification;
+use crate::storage::watcher::{SessionEvent, SessionEventKind, SessionWatcher};
+
+/// Subscriptions of one stdio connection, and the stream their
+/// notifications share with responses
+pub struct Subscriptions {
+    uris: Mutex<HashSet<String>>,
+    out: Mutex<Box<dyn Write + Send>>,
+    watching: Mutex<bool>,
+}
+
+impl Subscriptions {
+    pub fn new(out: Box<dyn Write + Send>) -> Arc<Self> {
+        Arc::new(Self {
+            uris: Mutex::new(HashSet::new()),
+            out: Mutex::new(out),
+            watching: Mutex::new(false),
+        })
+    }
+
+    /// Write one JSON-RPC message as a line
+    pub fn send(&self, message: &impl Serialize) -> io::Result<()> {
+        let line = serde_json::to_string(message)?;
+        let mut out = self.out.lock().unwrap();
+        writeln!(out, "{}", line)?;
+        out.flush()
+    }
+
+    /// Subscribe to `uri`, starting the session watcher if needed
+    pub fn subscribe(self: &Arc<Self>, uri: &str) -> Result<(), String> {
+        if !is_watched(uri) {
+            return Err(format!("Resource {} does not support subscriptions", uri));
+        }
+        self.start_watcher()?;
+        self.uris.lock().unwrap().insert(uri.to_string());
+        Ok(())
+    }
+
+    /// Drop the subscription to `uri`, returning whether there was one
+    pub fn unsubscribe(&self, uri: &str) -> bool {
+        self.uris.lock().unwrap().remove(uri)
+    }
+
+    fn start_watcher(self: &Arc<Self>) -> Result<(), String> {
+        let mut watching = self.watching.lock().unwrap();
+        if *watching {
+            return Ok(());
+        }
+        let watcher = SessionWatcher::new().map_err(|e| format!("Cannot watch sessions: {}", e))?;
+        let subscriptions = Arc::clone(self);
+        std::thread::Builder::new()
+            .name("csm-mcp-subscriptions".to_string())
+            .spawn(move || subscriptions.notify_loop(watcher))
+            .map_err(|e| e.to_string())?;
+        *watching = true;
+        Ok(())
+    }
+
+    fn notify_loop(&self, watcher: SessionWatcher) {
+        for event in watcher {
+            let updated = updated_uris(&event, &self.uris.lock().unwrap());
+            for uri in updated {
+                let notification = JsonRpcNotification::new(
+                    "notifications/resources/updated",
+                    json!({ "uri": uri }),
+                );
+                if let Err(e) = self.send(&notification) {
+                    eprintln!("[csm-mcp] Failed to send notification: {}", e);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Whether the session watcher can tell when `uri` changes
+fn is_watched(uri: &str) -> bool {
+    matches!(
+        uri,
+        "csm://workspaces" | "csm://sessions" | "csm://orphaned"
+    ) || uri.starts_with("csm://workspace/")
+        || uri.starts_with("csm://session/")
+}
+
+/// Subscribed URIs whose content `event` changes. Session resources accept an
+/// ID prefix, like `resources/read`.
+fn updated_uris(event: &SessionEvent, subscribed: &HashSet<String>) -> Vec<String> {
+    // Session counts and orphans change only when files come or go
+    let added_or_removed = event.kind != SessionEventKind::Modified;
+    let mut uris: Vec<String> = subscribed
+        .iter()
+        .filter(|uri| match uri.as_str() {
+            "csm://sessions" => true,
+            "csm://workspaces" | "csm://orphaned" => added_or_removed,
+            uri => {
+                if let Some(hash) = uri.strip_prefix("csm://workspace/") {
+                    event.workspace_hash.as_deref() == Some(hash)
+                } else if let Some(id) = uri.strip_prefix("csm://session/") {
+                    event.session_id.starts_with(id)
+                } else {
+                    false
+                }
+            }
+        })
+        .cloned()
+        .collect();
+    uris.sort();
+    uris
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_updated_uris_follow_the_changed_session() {
+        let subscribed: HashSet<String> = [
+            "csm://sessions",
+            "csm://workspaces",
+            "csm://workspace/abc123",
+            "csm://workspace/other",
+            "csm://session/5f2e",
+            "csm://session/9999",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+        let event = |kind| SessionEvent {
+            kind,
+            path: PathBuf::from("/ws/abc123/chatSessions/5f2e9a.json"),
+            session_id: "5f2e9a".to_string(),
+            workspace_hash: Some("abc123".to_string()),
+        };
+
+        assert_eq!(
+            updated_uris(&event(SessionEventKind::Modified), &subscribed),
+            vec![
+                "csm://session/5f2e",
+                "csm://sessions",
+                "csm://workspace/abc123"
+            ]
+        );
+        assert!(updated_uris(&event(SessionEventKind::Created), &subscribed)
+            .contains(&"csm://workspaces".to_string()));
+
+        assert!(is_watched("csm://session/5f2e"));
+        assert!(!is_watched("csm://db/sessions"));
+    }
+}
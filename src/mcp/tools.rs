@@ -4,10 +4,16 @@
 
 #![allow(dead_code, unused_imports)]
 
+use super::databases;
+use super::plugins;
+use super::progress::ToolProgress;
+use super::schemas;
 use super::types::*;
+use crate::search::SearchMode;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::HashMap;
+use std::ops::ControlFlow;
 
 /// Workspace info for JSON serialization (local type to avoid conflict)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,9 +24,32 @@ struct McpWorkspaceInfo {
     has_chats: bool,
 }
 
-/// Get the list of available tools
+/// Tools that change session files or the harvest database, or run an agent
+/// that can write files and execute code. `csm-mcp` offers them only with
+/// `--allow-write`.
+const WRITE_TOOLS: &[&str] = &[
+    "csm_register_all",
+    "csm_register_sessions",
+    "csm_merge_sessions",
+    "csm_harvest_run",
+    "csm_tag_session",
+    "csm_run_agent",
+];
+
+/// Whether `name` is a tool that changes data
+pub fn is_write_tool(name: &str) -> bool {
+    WRITE_TOOLS.contains(&name) || plugins::is_write_tool(name)
+}
+
+/// Whether calling `name` with `arguments` changes data. Exports write only
+/// when given an `output` path.
+pub fn writes(name: &str, arguments: &HashMap<String, serde_json::Value>) -> bool {
+    is_write_tool(name) || (name == "csm_harvest_export" && arguments.contains_key("output"))
+}
+
+/// Get the list of available tools, followed by those of loaded plugins
 pub fn list_tools() -> Vec<Tool> {
-    vec![
+    let mut tools = vec![
         Tool {
             name: "csm_list_workspaces".to_string(),
             description: Some("List all VS Code workspaces with chat sessions".to_string()),
@@ -29,6 +58,7 @@ pub fn list_tools() -> Vec<Tool> {
                 "properties": {},
                 "required": []
             }),
+            output_schema: schemas::output_schema("csm_list_workspaces"),
         },
         Tool {
             name: "csm_find_workspace".to_string(),
@@ -45,6 +75,7 @@ pub fn list_tools() -> Vec<Tool> {
                 },
                 "required": ["pattern"]
             }),
+            output_schema: schemas::output_schema("csm_find_workspace"),
         },
         Tool {
             name: "csm_list_sessions".to_string(),
@@ -61,6 +92,7 @@ pub fn list_tools() -> Vec<Tool> {
                 },
                 "required": []
             }),
+            output_schema: schemas::output_schema("csm_list_sessions"),
         },
         Tool {
             name: "csm_list_orphaned".to_string(),
@@ -78,6 +110,7 @@ pub fn list_tools() -> Vec<Tool> {
                 },
                 "required": []
             }),
+            output_schema: schemas::output_schema("csm_list_orphaned"),
         },
         Tool {
             name: "csm_register_all".to_string(),
@@ -102,6 +135,7 @@ pub fn list_tools() -> Vec<Tool> {
                 },
                 "required": []
             }),
+            output_schema: schemas::output_schema("csm_register_all"),
         },
         Tool {
             name: "csm_register_sessions".to_string(),
@@ -130,6 +164,7 @@ pub fn list_tools() -> Vec<Tool> {
                 },
                 "required": []
             }),
+            output_schema: schemas::output_schema("csm_register_sessions"),
         },
         Tool {
             name: "csm_show_session".to_string(),
@@ -144,6 +179,7 @@ pub fn list_tools() -> Vec<Tool> {
                 },
                 "required": ["session_id"]
             }),
+            output_schema: schemas::output_schema("csm_show_session"),
         },
         Tool {
             name: "csm_show_history".to_string(),
@@ -158,6 +194,7 @@ pub fn list_tools() -> Vec<Tool> {
                 },
                 "required": []
             }),
+            output_schema: schemas::output_schema("csm_show_history"),
         },
         Tool {
             name: "csm_merge_sessions".to_string(),
@@ -180,6 +217,7 @@ pub fn list_tools() -> Vec<Tool> {
                 },
                 "required": []
             }),
+            output_schema: schemas::output_schema("csm_merge_sessions"),
         },
         Tool {
             name: "csm_search".to_string(),
@@ -198,6 +236,7 @@ pub fn list_tools() -> Vec<Tool> {
                 },
                 "required": ["query"]
             }),
+            output_schema: schemas::output_schema("csm_search"),
         },
         Tool {
             name: "csm_detect".to_string(),
@@ -214,6 +253,260 @@ pub fn list_tools() -> Vec<Tool> {
                 },
                 "required": []
             }),
+            output_schema: schemas::output_schema("csm_detect"),
+        },
+        // Harvest Database Tools (sessions gathered from every provider)
+        Tool {
+            name: "csm_harvest_databases".to_string(),
+            description: Some(
+                "List the harvest databases this server offers, with their paths and session counts"
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+            output_schema: schemas::output_schema("csm_harvest_databases"),
+        },
+        Tool {
+            name: "csm_harvest_run".to_string(),
+            description: Some(
+                "Harvest chat sessions from all providers into the harvest database".to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "providers": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Only harvest these providers (default: all detected)"
+                    },
+                    "incremental": {
+                        "type": "boolean",
+                        "description": "Skip sessions unchanged since the last harvest (default: true)"
+                    },
+                    "database": {
+                        "type": "string",
+                        "description": "Harvest database to use (default: default; see csm_harvest_databases)"
+                    }
+                },
+                "required": []
+            }),
+            output_schema: schemas::output_schema("csm_harvest_run"),
+        },
+        Tool {
+            name: "csm_harvest_search".to_string(),
+            description: Some(
+                "Full-text search of harvested sessions with provider, workspace, tag, language, and date filters"
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "Search query (FTS5 syntax)"
+                    },
+                    "provider": {
+                        "type": "string",
+                        "description": "Only sessions from this provider"
+                    },
+                    "workspace": {
+                        "type": "string",
+                        "description": "Only sessions from this workspace ID or name"
+                    },
+                    "tags": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Only sessions with all of these tags"
+                    },
+                    "language": {
+                        "type": "string",
+                        "description": "Only sessions mostly written in this language (ISO 639-3 code such as spa, or a name)"
+                    },
+                    "after": {
+                        "type": "string",
+                        "description": "Only sessions updated on or after this date (e.g. 2026-01-31 or 7d)"
+                    },
+                    "before": {
+                        "type": "string",
+                        "description": "Only sessions updated before this date"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of results (default: 20)"
+                    },
+                    "database": {
+                        "type": "string",
+                        "description": "Harvest database to use (default: default; see csm_harvest_databases)"
+                    }
+                },
+                "required": ["query"]
+            }),
+            output_schema: schemas::output_schema("csm_harvest_search"),
+        },
+        Tool {
+            name: "csm_semantic_search".to_string(),
+            description: Some(
+                "Find the past conversations most relevant to a natural-language query, ranked by embedding similarity, with snippets and scores. Needs an embeddings endpoint (CSM_EMBEDDING_URL, OPENAI_API_KEY, or OLLAMA_HOST)."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "What to look for, in plain language"
+                    },
+                    "provider": {
+                        "type": "string",
+                        "description": "Only sessions from this provider"
+                    },
+                    "workspace": {
+                        "type": "string",
+                        "description": "Only sessions from this workspace ID or name"
+                    },
+                    "after": {
+                        "type": "string",
+                        "description": "Only sessions updated on or after this date (e.g. 2026-01-31 or 7d)"
+                    },
+                    "before": {
+                        "type": "string",
+                        "description": "Only sessions updated before this date"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of results (default: 10)"
+                    },
+                    "database": {
+                        "type": "string",
+                        "description": "Harvest database to use (default: default; see csm_harvest_databases)"
+                    }
+                },
+                "required": ["query"]
+            }),
+            output_schema: schemas::output_schema("csm_semantic_search"),
+        },
+        Tool {
+            name: "csm_harvest_list".to_string(),
+            description: Some(
+                "List harvested sessions, most recently updated first".to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "provider": {
+                        "type": "string",
+                        "description": "Filter by provider (partial match)"
+                    },
+                    "search": {
+                        "type": "string",
+                        "description": "Filter by title or session ID (partial match)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum results (default: 20)"
+                    },
+                    "database": {
+                        "type": "string",
+                        "description": "Harvest database to use (default: default; see csm_harvest_databases)"
+                    }
+                },
+                "required": []
+            }),
+            output_schema: schemas::output_schema("csm_harvest_list"),
+        },
+        Tool {
+            name: "csm_harvest_export".to_string(),
+            description: Some(
+                "Export harvested sessions as JSON, JSON Lines, or Markdown".to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "session_ids": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Sessions to export (default: all matching provider)"
+                    },
+                    "provider": {
+                        "type": "string",
+                        "description": "Only export sessions from this provider (partial match)"
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["json", "jsonl", "md"],
+                        "description": "Export format (default: md)"
+                    },
+                    "output": {
+                        "type": "string",
+                        "description": "File to write (needs --allow-write); without it the export is returned inline"
+                    },
+                    "database": {
+                        "type": "string",
+                        "description": "Harvest database to use (default: default; see csm_harvest_databases)"
+                    }
+                },
+                "required": []
+            }),
+            output_schema: schemas::output_schema("csm_harvest_export"),
+        },
+        Tool {
+            name: "csm_tag_session".to_string(),
+            description: Some("Add or remove tags on a harvested session".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "session_id": {
+                        "type": "string",
+                        "description": "Harvested session ID"
+                    },
+                    "add": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Tags to add"
+                    },
+                    "remove": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Tags to remove"
+                    },
+                    "database": {
+                        "type": "string",
+                        "description": "Harvest database to use (default: default; see csm_harvest_databases)"
+                    }
+                },
+                "required": ["session_id"]
+            }),
+            output_schema: schemas::output_schema("csm_tag_session"),
+        },
+        // Agency Tools
+        Tool {
+            name: "csm_run_agent".to_string(),
+            description: Some(
+                "Delegate a task to a local csm agent and return its answer. Agents are defined in the csm library's agents/ folder; assistant, researcher, coder, and reviewer are always available. Progress notifications report the agent's steps."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "agent": {
+                        "type": "string",
+                        "description": "Agent name"
+                    },
+                    "prompt": {
+                        "type": "string",
+                        "description": "Task or question for the agent"
+                    },
+                    "model": {
+                        "type": "string",
+                        "description": "Model to use instead of the agent's own"
+                    }
+                },
+                "required": ["agent", "prompt"]
+            }),
+            output_schema: schemas::output_schema("csm_run_agent"),
         },
         // CSM Database Tools (for csm-web integration)
         Tool {
@@ -224,6 +517,7 @@ pub fn list_tools() -> Vec<Tool> {
                 "properties": {},
                 "required": []
             }),
+            output_schema: schemas::output_schema("csm_db_list_workspaces"),
         },
         Tool {
             name: "csm_db_list_sessions".to_string(),
@@ -246,6 +540,7 @@ pub fn list_tools() -> Vec<Tool> {
                 },
                 "required": []
             }),
+            output_schema: schemas::output_schema("csm_db_list_sessions"),
         },
         Tool {
             name: "csm_db_get_session".to_string(),
@@ -262,6 +557,7 @@ pub fn list_tools() -> Vec<Tool> {
                 },
                 "required": ["session_id"]
             }),
+            output_schema: schemas::output_schema("csm_db_get_session"),
         },
         Tool {
             name: "csm_db_search".to_string(),
@@ -280,6 +576,7 @@ pub fn list_tools() -> Vec<Tool> {
                 },
                 "required": ["query"]
             }),
+            output_schema: schemas::output_schema("csm_db_search"),
         },
         Tool {
             name: "csm_db_stats".to_string(),
@@ -291,12 +588,24 @@ pub fn list_tools() -> Vec<Tool> {
                 "properties": {},
                 "required": []
             }),
+            output_schema: schemas::output_schema("csm_db_stats"),
         },
-    ]
+    ];
+    tools.extend(plugins::tools());
+    tools
 }
 
 /// Execute a tool call
 pub fn call_tool(name: &str, arguments: &HashMap<String, serde_json::Value>) -> CallToolResult {
+    call_tool_with_progress(name, arguments, &ToolProgress::none())
+}
+
+/// Execute a tool call, reporting the progress of long tools to `progress`
+pub fn call_tool_with_progress(
+    name: &str,
+    arguments: &HashMap<String, serde_json::Value>,
+    progress: &ToolProgress,
+) -> CallToolResult {
     let result = match name {
         "csm_list_workspaces" => execute_list_workspaces(),
         "csm_find_workspace" => {
@@ -324,7 +633,7 @@ pub fn call_tool(name: &str, arguments: &HashMap<String, serde_json::Value>) ->
                 .get("force")
                 .and_then(|v| v.as_bool())
                 .unwrap_or(false);
-            execute_register_all(path, merge, force)
+            execute_register_all(path, merge, force, progress)
         }
         "csm_register_sessions" => {
             let ids: Vec<String> = arguments
@@ -369,7 +678,7 @@ pub fn call_tool(name: &str, arguments: &HashMap<String, serde_json::Value>) ->
                 .get("force")
                 .and_then(|v| v.as_bool())
                 .unwrap_or(false);
-            execute_merge_sessions(path, title, force)
+            execute_merge_sessions(path, title, force, progress)
         }
         "csm_search" => {
             let query = arguments
@@ -386,6 +695,86 @@ pub fn call_tool(name: &str, arguments: &HashMap<String, serde_json::Value>) ->
             let path = arguments.get("path").and_then(|v| v.as_str());
             execute_detect(path)
         }
+        // Harvest database tools
+        "csm_harvest_databases" => execute_harvest_databases(),
+        "csm_harvest_run" => {
+            let database = arguments.get("database").and_then(|v| v.as_str());
+            let providers = string_array(arguments, "providers");
+            let incremental = arguments
+                .get("incremental")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true);
+            execute_harvest_run(database, providers.as_deref(), incremental, progress)
+        }
+        "csm_harvest_search" => {
+            let query = arguments
+                .get("query")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let limit = arguments
+                .get("limit")
+                .and_then(|v| v.as_u64())
+                .map(|n| n as usize)
+                .unwrap_or(20);
+            execute_harvest_search(query, arguments, limit, SearchMode::Text)
+        }
+        "csm_semantic_search" => {
+            let query = arguments
+                .get("query")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let limit = arguments
+                .get("limit")
+                .and_then(|v| v.as_u64())
+                .map(|n| n as usize)
+                .unwrap_or(10);
+            execute_harvest_search(query, arguments, limit, SearchMode::Semantic)
+        }
+        "csm_harvest_list" => {
+            let database = arguments.get("database").and_then(|v| v.as_str());
+            let provider = arguments.get("provider").and_then(|v| v.as_str());
+            let search = arguments.get("search").and_then(|v| v.as_str());
+            let limit = arguments
+                .get("limit")
+                .and_then(|v| v.as_u64())
+                .map(|n| n as usize)
+                .unwrap_or(20);
+            execute_harvest_list(database, provider, search, limit)
+        }
+        "csm_harvest_export" => {
+            let database = arguments.get("database").and_then(|v| v.as_str());
+            let session_ids = string_array(arguments, "session_ids");
+            let provider = arguments.get("provider").and_then(|v| v.as_str());
+            let format = arguments
+                .get("format")
+                .and_then(|v| v.as_str())
+                .unwrap_or("md");
+            let output = arguments.get("output").and_then(|v| v.as_str());
+            execute_harvest_export(database, session_ids.as_deref(), provider, format, output)
+        }
+        "csm_tag_session" => {
+            let database = arguments.get("database").and_then(|v| v.as_str());
+            let session_id = arguments
+                .get("session_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let add = string_array(arguments, "add").unwrap_or_default();
+            let remove = string_array(arguments, "remove").unwrap_or_default();
+            execute_tag_session(database, session_id, &add, &remove)
+        }
+        // Agency tools
+        "csm_run_agent" => {
+            let agent = arguments
+                .get("agent")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let prompt = arguments
+                .get("prompt")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let model = arguments.get("model").and_then(|v| v.as_str());
+            execute_run_agent(agent, prompt, model, progress)
+        }
         // CSM Database tools (csm-web integration)
         "csm_db_list_workspaces" => execute_db_list_workspaces(),
         "csm_db_list_sessions" => {
@@ -418,12 +807,8 @@ pub fn call_tool(name: &str, arguments: &HashMap<String, serde_json::Value>) ->
             execute_db_search(query, limit)
         }
         "csm_db_stats" => execute_db_stats(),
-        _ => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: format!("Unknown tool: {}", name),
-            }],
-            is_error: Some(true),
-        },
+        _ => plugins::call(name, arguments)
+            .unwrap_or_else(|| tool_failure(format!("Unknown tool: {}", name))),
     };
 
     result
@@ -448,23 +833,12 @@ fn execute_list_workspaces() -> CallToolResult {
                 })
                 .collect();
 
-            CallToolResult {
-                content: vec![ToolContent::Text {
-                    text: serde_json::to_string_pretty(&json!({
-                        "workspaces": infos,
-                        "total": infos.len()
-                    }))
-                    .unwrap_or_default(),
-                }],
-                is_error: None,
-            }
+            tool_json(json!({
+                "workspaces": infos,
+                "total": infos.len()
+            }))
         }
-        Err(e) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: format!("Error listing workspaces: {}", e),
-            }],
-            is_error: Some(true),
-        },
+        Err(e) => tool_failure(format!("Error listing workspaces: {}", e)),
     }
 }
 
@@ -497,24 +871,13 @@ fn execute_find_workspace(pattern: &str) -> CallToolResult {
                 })
                 .collect();
 
-            CallToolResult {
-                content: vec![ToolContent::Text {
-                    text: serde_json::to_string_pretty(&json!({
-                        "pattern": pattern,
-                        "workspaces": infos,
-                        "total": infos.len()
-                    }))
-                    .unwrap_or_default(),
-                }],
-                is_error: None,
-            }
+            tool_json(json!({
+                "pattern": pattern,
+                "workspaces": infos,
+                "total": infos.len()
+            }))
         }
-        Err(e) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: format!("Error finding workspaces: {}", e),
-            }],
-            is_error: Some(true),
-        },
+        Err(e) => tool_failure(format!("Error finding workspaces: {}", e)),
     }
 }
 
@@ -551,23 +914,12 @@ fn execute_list_sessions(project_path: Option<&str>) -> CallToolResult {
                 }
             }
 
-            CallToolResult {
-                content: vec![ToolContent::Text {
-                    text: serde_json::to_string_pretty(&json!({
-                        "sessions": all_sessions,
-                        "total": all_sessions.len()
-                    }))
-                    .unwrap_or_default(),
-                }],
-                is_error: None,
-            }
+            tool_json(json!({
+                "sessions": all_sessions,
+                "total": all_sessions.len()
+            }))
         }
-        Err(e) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: format!("Error listing sessions: {}", e),
-            }],
-            is_error: Some(true),
-        },
+        Err(e) => tool_failure(format!("Error listing sessions: {}", e)),
     }
 }
 
@@ -577,47 +929,34 @@ fn execute_list_orphaned(path: Option<&str>) -> CallToolResult {
     // Capture stdout (list_orphaned prints to stdout)
     // For now, we'll call it and return a simplified response
     match list_orphaned(path) {
-        Ok(_) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: json!({
-                    "status": "success",
-                    "message": "Orphaned session check completed. See console output for details."
-                })
-                .to_string(),
-            }],
-            is_error: None,
-        },
-        Err(e) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: format!("Error listing orphaned sessions: {}", e),
-            }],
-            is_error: Some(true),
-        },
+        Ok(_) => tool_json(json!({
+            "status": "success",
+            "message": "Orphaned session check completed. See console output for details."
+        })),
+        Err(e) => tool_failure(format!("Error listing orphaned sessions: {}", e)),
     }
 }
 
-fn execute_register_all(path: Option<&str>, merge: bool, force: bool) -> CallToolResult {
+fn execute_register_all(
+    path: Option<&str>,
+    merge: bool,
+    force: bool,
+    progress: &ToolProgress,
+) -> CallToolResult {
     use crate::commands::register_all;
 
-    match register_all(path, merge, force) {
-        Ok(_) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: json!({
-                    "status": "success",
-                    "message": "Sessions registered successfully",
-                    "merge": merge,
-                    "force": force
-                })
-                .to_string(),
-            }],
-            is_error: None,
-        },
-        Err(e) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: format!("Error registering sessions: {}", e),
-            }],
-            is_error: Some(true),
-        },
+    // Registration cannot stop half way, so it only reports start and end
+    progress.report(0, Some(1), "Registering sessions");
+    let result = register_all(path, merge, force);
+    progress.report(1, Some(1), "Registered sessions");
+    match result {
+        Ok(_) => tool_json(json!({
+            "status": "success",
+            "message": "Sessions registered successfully",
+            "merge": merge,
+            "force": force
+        })),
+        Err(e) => tool_failure(format!("Error registering sessions: {}", e)),
     }
 }
 
@@ -630,25 +969,14 @@ fn execute_register_sessions(
     use crate::commands::register_sessions;
 
     match register_sessions(ids, titles, path, force) {
-        Ok(_) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: json!({
-                    "status": "success",
-                    "message": "Sessions registered successfully",
-                    "ids": ids,
-                    "titles": titles,
-                    "force": force
-                })
-                .to_string(),
-            }],
-            is_error: None,
-        },
-        Err(e) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: format!("Error registering sessions: {}", e),
-            }],
-            is_error: Some(true),
-        },
+        Ok(_) => tool_json(json!({
+            "status": "success",
+            "message": "Sessions registered successfully",
+            "ids": ids,
+            "titles": titles,
+            "force": force
+        })),
+        Err(e) => tool_failure(format!("Error registering sessions: {}", e)),
     }
 }
 
@@ -662,53 +990,37 @@ fn execute_show_session(session_id: &str) -> CallToolResult {
                     for s in sessions {
                         let sid = s.session.session_id.clone().unwrap_or_default();
                         if sid.starts_with(session_id) || sid == session_id {
-                            return CallToolResult {
-                                content: vec![ToolContent::Text {
-                                    text: serde_json::to_string_pretty(&json!({
-                                        "id": sid,
-                                        "title": s.session.title(),
-                                        "message_count": s.session.requests.len(),
-                                        "last_message_date": s.session.last_message_date,
-                                        "is_imported": s.session.is_imported,
-                                        "workspace_hash": ws.hash,
-                                        "project_path": ws.project_path,
-                                        "file_path": s.path.display().to_string(),
-                                        "messages": s.session.requests.iter().take(10).map(|r| {
-                                            let user_msg = r.message.as_ref()
-                                                .map(|m| m.get_text())
-                                                .unwrap_or_default();
-                                            let response_text = r.response.as_ref()
-                                                .and_then(|v| v.get("text"))
-                                                .and_then(|t| t.as_str())
-                                                .unwrap_or("");
-                                            json!({
-                                                "message": user_msg,
-                                                "response": response_text
-                                            })
-                                        }).collect::<Vec<_>>()
-                                    }))
-                                    .unwrap_or_default(),
-                                }],
-                                is_error: None,
-                            };
+                            return tool_json(json!({
+                                "id": sid,
+                                "title": s.session.title(),
+                                "message_count": s.session.requests.len(),
+                                "last_message_date": s.session.last_message_date,
+                                "is_imported": s.session.is_imported,
+                                "workspace_hash": ws.hash,
+                                "project_path": ws.project_path,
+                                "file_path": s.path.display().to_string(),
+                                "messages": s.session.requests.iter().take(10).map(|r| {
+                                    let user_msg = r.message.as_ref()
+                                        .map(|m| m.get_text())
+                                        .unwrap_or_default();
+                                    let response_text = r.response.as_ref()
+                                        .and_then(|v| v.get("text"))
+                                        .and_then(|t| t.as_str())
+                                        .unwrap_or("");
+                                    json!({
+                                        "message": user_msg,
+                                        "response": response_text
+                                    })
+                                }).collect::<Vec<_>>()
+                            }));
                         }
                     }
                 }
             }
 
-            CallToolResult {
-                content: vec![ToolContent::Text {
-                    text: format!("Session not found: {}", session_id),
-                }],
-                is_error: Some(true),
-            }
+            tool_failure(format!("Session not found: {}", session_id))
         }
-        Err(e) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: format!("Error finding session: {}", e),
-            }],
-            is_error: Some(true),
-        },
+        Err(e) => tool_failure(format!("Error finding session: {}", e)),
     }
 }
 
@@ -716,100 +1028,407 @@ fn execute_show_history(path: Option<&str>) -> CallToolResult {
     use crate::commands::history_show;
 
     match history_show(path) {
-        Ok(_) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: json!({
-                    "status": "success",
-                    "message": "History displayed. See console output for details."
-                })
-                .to_string(),
-            }],
-            is_error: None,
-        },
-        Err(e) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: format!("Error showing history: {}", e),
-            }],
-            is_error: Some(true),
-        },
+        Ok(_) => tool_json(json!({
+            "status": "success",
+            "message": "History displayed. See console output for details."
+        })),
+        Err(e) => tool_failure(format!("Error showing history: {}", e)),
     }
 }
 
-fn execute_merge_sessions(path: Option<&str>, title: Option<&str>, force: bool) -> CallToolResult {
+fn execute_merge_sessions(
+    path: Option<&str>,
+    title: Option<&str>,
+    force: bool,
+    progress: &ToolProgress,
+) -> CallToolResult {
     use crate::commands::history_merge;
 
-    match history_merge(path, title, force, false) {
-        Ok(_) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: json!({
-                    "status": "success",
-                    "message": "Sessions merged successfully",
-                    "title": title,
-                    "force": force
-                })
-                .to_string(),
-            }],
-            is_error: None,
-        },
-        Err(e) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: format!("Error merging sessions: {}", e),
-            }],
-            is_error: Some(true),
-        },
+    // A merge cannot stop half way, so it only reports start and end
+    progress.report(0, Some(1), "Merging sessions");
+    let result = history_merge(path, title, force, false);
+    progress.report(1, Some(1), "Merged sessions");
+    match result {
+        Ok(_) => tool_json(json!({
+            "status": "success",
+            "message": "Sessions merged successfully",
+            "title": title,
+            "force": force
+        })),
+        Err(e) => tool_failure(format!("Error merging sessions: {}", e)),
     }
 }
 
 fn execute_search(query: &str, limit: Option<usize>) -> CallToolResult {
-    use crate::commands::harvest_search;
+    use crate::search::{search, SearchOptions};
 
-    let limit = limit.unwrap_or(20);
+    let options = SearchOptions {
+        query: query.to_string(),
+        limit: limit.unwrap_or(20),
+        ..Default::default()
+    };
+    let result = crate::commands::get_db_path(None)
+        .and_then(|path| Ok(rusqlite::Connection::open(path)?))
+        .and_then(|conn| search(&conn, &options, None));
 
-    match harvest_search(None, query, None, limit) {
-        Ok(_) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: json!({
-                    "status": "success",
-                    "query": query,
-                    "limit": limit,
-                    "message": "Search completed. See console output for results."
-                })
-                .to_string(),
-            }],
-            is_error: None,
-        },
-        Err(e) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: format!("Error searching: {}", e),
-            }],
-            is_error: Some(true),
-        },
+    match result {
+        Ok(results) => tool_json(json!({
+            "status": "success",
+            "query": query,
+            "total": results.total,
+            "results": results.hits,
+        })),
+        Err(e) => tool_failure(format!("Error searching: {}", e)),
     }
 }
 
 fn execute_detect(path: Option<&str>) -> CallToolResult {
     use crate::commands::detect_all;
 
-    match detect_all(path, false) {
-        Ok(_) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: json!({
-                    "status": "success",
-                    "message": "Detection completed. See console output for details."
+    match detect_all(path, false, false) {
+        Ok(_) => tool_json(json!({
+            "status": "success",
+            "message": "Detection completed. See console output for details."
+        })),
+        Err(e) => tool_failure(format!("Error detecting: {}", e)),
+    }
+}
+
+// ============================================================================
+// Harvest Database Tool Implementations
+// ============================================================================
+
+/// Largest export returned inline; bigger exports need an `output` file
+const EXPORT_INLINE_LIMIT: usize = 1024 * 1024;
+
+/// String items of the array argument `key`
+fn string_array(arguments: &HashMap<String, serde_json::Value>, key: &str) -> Option<Vec<String>> {
+    arguments.get(key).and_then(|v| v.as_array()).map(|arr| {
+        arr.iter()
+            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .collect()
+    })
+}
+
+fn tool_failure(text: String) -> CallToolResult {
+    CallToolResult {
+        content: vec![ToolContent::Text { text }],
+        structured_content: None,
+        is_error: Some(true),
+    }
+}
+
+fn tool_error(context: &str, e: anyhow::Error) -> CallToolResult {
+    tool_failure(format!("{}: {}", context, e))
+}
+
+/// Return `value` as structured content, and as text for older clients
+fn tool_json(value: serde_json::Value) -> CallToolResult {
+    CallToolResult {
+        content: vec![ToolContent::Text {
+            text: serde_json::to_string_pretty(&value).unwrap_or_default(),
+        }],
+        structured_content: Some(value),
+        is_error: None,
+    }
+}
+
+fn execute_harvest_databases() -> CallToolResult {
+    let infos: Vec<serde_json::Value> = databases::all()
+        .iter()
+        .map(|(name, path)| {
+            let sessions = databases::open(Some(name)).ok().and_then(|conn| {
+                conn.query_row("SELECT COUNT(*) FROM sessions", [], |row| {
+                    row.get::<_, i64>(0)
                 })
-                .to_string(),
-            }],
-            is_error: None,
-        },
-        Err(e) => CallToolResult {
+                .ok()
+            });
+            json!({
+                "name": name,
+                "path": path.display().to_string(),
+                "exists": path.exists(),
+                "sessions": sessions,
+                "resources": format!("csm://db/{}/", name),
+            })
+        })
+        .collect();
+    tool_json(json!({
+        "default": databases::DEFAULT,
+        "databases": infos,
+    }))
+}
+
+fn execute_harvest_run(
+    database: Option<&str>,
+    providers: Option<&[String]>,
+    incremental: bool,
+    progress: &ToolProgress,
+) -> CallToolResult {
+    use crate::commands::harvest_run_with_progress;
+
+    let result = databases::resolve(database).and_then(|path| {
+        harvest_run_with_progress(
+            Some(&path.to_string_lossy()),
+            providers,
+            None,
+            incremental,
+            false,
+            None,
+            &mut |harvest| {
+                progress.report(
+                    harvest.sources_done,
+                    Some(harvest.sources_total),
+                    &format!(
+                        "Harvested {} ({} sessions added, {} updated)",
+                        harvest.source, harvest.sessions_added, harvest.sessions_updated
+                    ),
+                );
+                if progress.is_cancelled() {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            },
+        )
+    });
+    match result {
+        Ok(stats) => tool_json(json!({
+            "status": "success",
+            "stats": stats,
+        })),
+        Err(e) => tool_error("Error running harvest", e),
+    }
+}
+
+fn execute_harvest_search(
+    query: &str,
+    arguments: &HashMap<String, serde_json::Value>,
+    limit: usize,
+    mode: crate::search::SearchMode,
+) -> CallToolResult {
+    use crate::search::{search, Embedder, HttpEmbedder, SearchFilters, SearchOptions};
+
+    let embedder = match mode {
+        SearchMode::Text => None,
+        SearchMode::Semantic => HttpEmbedder::from_env(),
+    };
+    let text = |key: &str| arguments.get(key).and_then(|v| v.as_str());
+    let result = SearchFilters {
+        providers: text("provider")
+            .map(|p| vec![p.to_string()])
+            .unwrap_or_default(),
+        workspace: text("workspace").map(String::from),
+        tags: string_array(arguments, "tags").unwrap_or_default(),
+        ..Default::default()
+    }
+    .with_dates(text("after"), text("before"))
+    .and_then(|filters| filters.with_language(text("language")))
+    .and_then(|filters| {
+        let options = SearchOptions {
+            query: query.to_string(),
+            mode,
+            filters,
+            limit,
+            ..Default::default()
+        };
+        let embedder = embedder.as_ref().map(|e| e as &dyn Embedder);
+        search(&databases::open(text("database"))?, &options, embedder)
+    });
+
+    match result {
+        Ok(results) => tool_json(json!({
+            "query": query,
+            "mode": mode,
+            "total": results.total,
+            "results": results.hits,
+        })),
+        Err(e) => tool_error("Error searching harvest database", e),
+    }
+}
+
+fn execute_harvest_list(
+    database: Option<&str>,
+    provider: Option<&str>,
+    search: Option<&str>,
+    limit: usize,
+) -> CallToolResult {
+    use crate::commands::list_harvested_sessions;
+
+    let result = databases::open(database)
+        .and_then(|conn| list_harvested_sessions(&conn, provider, limit, search, None));
+
+    match result {
+        Ok(sessions) => {
+            let infos: Vec<serde_json::Value> = sessions
+                .iter()
+                .map(
+                    |(id, provider, title, message_count, created, updated, workspace)| {
+                        json!({
+                            "id": id,
+                            "provider": provider,
+                            "title": title,
+                            "message_count": message_count,
+                            "created_at": created,
+                            "updated_at": updated,
+                            "workspace_name": workspace,
+                        })
+                    },
+                )
+                .collect();
+            tool_json(json!({
+                "sessions": infos,
+                "total": infos.len(),
+            }))
+        }
+        Err(e) => tool_error("Error listing harvested sessions", e),
+    }
+}
+
+fn execute_harvest_export(
+    database: Option<&str>,
+    session_ids: Option<&[String]>,
+    provider: Option<&str>,
+    format: &str,
+    output: Option<&str>,
+) -> CallToolResult {
+    use crate::commands::{export_session_json, render_export};
+
+    let result = databases::open(database).and_then(|conn| {
+        let sessions = export_session_json(&conn, provider, session_ids)?;
+        let content = render_export(&sessions, format)?;
+        Ok((sessions.len(), content))
+    });
+    let (count, content) = match result {
+        Ok(export) => export,
+        Err(e) => return tool_error("Error exporting sessions", e),
+    };
+
+    match output {
+        Some(output) => {
+            let path = crate::config::resolve_export_path(output);
+            let written = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|_| std::fs::write(&path, &content));
+            match written {
+                Ok(()) => tool_json(json!({
+                    "status": "success",
+                    "sessions": count,
+                    "format": format,
+                    "output": path.display().to_string(),
+                })),
+                Err(e) => tool_error("Error writing export", e.into()),
+            }
+        }
+        None if content.len() > EXPORT_INLINE_LIMIT => tool_error(
+            "Export too large to return inline",
+            anyhow::anyhow!(
+                "{} sessions make {} bytes; pass output to write them to a file",
+                count,
+                content.len()
+            ),
+        ),
+        None => CallToolResult {
             content: vec![ToolContent::Text {
-                text: format!("Error detecting: {}", e),
+                text: content.clone(),
             }],
-            is_error: Some(true),
+            structured_content: Some(json!({
+                "sessions": count,
+                "format": format,
+                "content": content,
+            })),
+            is_error: None,
         },
     }
 }
 
+fn execute_tag_session(
+    database: Option<&str>,
+    session_id: &str,
+    add: &[String],
+    remove: &[String],
+) -> CallToolResult {
+    use crate::reviews::{set_user_tags, user_tags};
+    use rusqlite::OptionalExtension;
+
+    let result = databases::open(database).and_then(|conn| {
+        let exists = conn
+            .query_row("SELECT 1 FROM sessions WHERE id = ?1", [session_id], |_| {
+                Ok(())
+            })
+            .optional()?
+            .is_some();
+        if !exists {
+            anyhow::bail!("Session not found: {}", session_id);
+        }
+        let mut tags = user_tags(&conn, session_id)?;
+        tags.retain(|tag| !remove.contains(tag));
+        for tag in add {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+        Ok(set_user_tags(&conn, session_id, &tags)?)
+    });
+
+    match result {
+        Ok(tags) => tool_json(json!({
+            "status": "success",
+            "session_id": session_id,
+            "tags": tags,
+        })),
+        Err(e) => tool_error("Error tagging session", e),
+    }
+}
+
+fn execute_run_agent(
+    name: &str,
+    prompt: &str,
+    model: Option<&str>,
+    progress: &ToolProgress,
+) -> CallToolResult {
+    use crate::agency::models::EventType;
+    use crate::commands::{load_agent, run_agent_with_events};
+
+    let mut steps = 0;
+    let result = load_agent(name, model).and_then(|agent| {
+        run_agent_with_events(
+            agent,
+            prompt,
+            &mut |event| {
+                // Progress follows steps, not every token of the reply
+                if event.event_type == EventType::MessageDelta {
+                    return;
+                }
+                let kind = serde_json::to_value(event.event_type)
+                    .ok()
+                    .and_then(|v| v.as_str().map(String::from))
+                    .unwrap_or_default();
+                let message = match event.data.get("tool").and_then(|v| v.as_str()) {
+                    Some(tool) => format!("{}: {}", kind, tool),
+                    None => kind,
+                };
+                steps += 1;
+                progress.report(steps, None, &message);
+            },
+            &|| progress.is_cancelled(),
+        )
+    });
+
+    match result {
+        Ok(result) => tool_json(json!({
+            "agent": name,
+            "response": result.response,
+            "success": result.success,
+            "error": result.error,
+            "token_usage": result.token_usage,
+            "duration_ms": result.duration_ms,
+        })),
+        Err(e) => tool_error("Error running agent", e),
+    }
+}
+
 // ============================================================================
 // CSM Database Tool Implementations (csm-web integration)
 // ============================================================================
@@ -818,16 +1437,11 @@ fn execute_db_list_workspaces() -> CallToolResult {
     use super::db;
 
     if !db::csm_db_exists() {
-        return CallToolResult {
-            content: vec![ToolContent::Text {
-                text: json!({
+        return tool_failure(json!({
                     "error": "CSM database not found",
                     "message": "The csm-web database has not been initialized. Run 'csm api' to start the API server first.",
                     "db_path": db::get_csm_db_path().display().to_string()
-                }).to_string(),
-            }],
-            is_error: Some(true),
-        };
+                }).to_string());
     }
 
     match db::list_db_workspaces() {
@@ -846,24 +1460,13 @@ fn execute_db_list_workspaces() -> CallToolResult {
                 })
                 .collect();
 
-            CallToolResult {
-                content: vec![ToolContent::Text {
-                    text: serde_json::to_string_pretty(&json!({
-                        "workspaces": infos,
-                        "total": infos.len(),
-                        "source": "csm-web database"
-                    }))
-                    .unwrap_or_default(),
-                }],
-                is_error: None,
-            }
+            tool_json(json!({
+                "workspaces": infos,
+                "total": infos.len(),
+                "source": "csm-web database"
+            }))
         }
-        Err(e) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: format!("Error listing workspaces from CSM database: {}", e),
-            }],
-            is_error: Some(true),
-        },
+        Err(e) => tool_failure(format!("Error listing workspaces from CSM database: {}", e)),
     }
 }
 
@@ -875,16 +1478,13 @@ fn execute_db_list_sessions(
     use super::db;
 
     if !db::csm_db_exists() {
-        return CallToolResult {
-            content: vec![ToolContent::Text {
-                text: json!({
-                    "error": "CSM database not found",
-                    "message": "The csm-web database has not been initialized."
-                })
-                .to_string(),
-            }],
-            is_error: Some(true),
-        };
+        return tool_failure(
+            json!({
+                "error": "CSM database not found",
+                "message": "The csm-web database has not been initialized."
+            })
+            .to_string(),
+        );
     }
 
     match db::list_db_sessions(workspace_id, provider, limit) {
@@ -906,29 +1506,18 @@ fn execute_db_list_sessions(
                 })
                 .collect();
 
-            CallToolResult {
-                content: vec![ToolContent::Text {
-                    text: serde_json::to_string_pretty(&json!({
-                        "sessions": infos,
-                        "total": infos.len(),
-                        "filters": {
-                            "workspace_id": workspace_id,
-                            "provider": provider,
-                            "limit": limit
-                        },
-                        "source": "csm-web database"
-                    }))
-                    .unwrap_or_default(),
-                }],
-                is_error: None,
-            }
+            tool_json(json!({
+                "sessions": infos,
+                "total": infos.len(),
+                "filters": {
+                    "workspace_id": workspace_id,
+                    "provider": provider,
+                    "limit": limit
+                },
+                "source": "csm-web database"
+            }))
         }
-        Err(e) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: format!("Error listing sessions from CSM database: {}", e),
-            }],
-            is_error: Some(true),
-        },
+        Err(e) => tool_failure(format!("Error listing sessions from CSM database: {}", e)),
     }
 }
 
@@ -936,15 +1525,12 @@ fn execute_db_get_session(session_id: &str) -> CallToolResult {
     use super::db;
 
     if !db::csm_db_exists() {
-        return CallToolResult {
-            content: vec![ToolContent::Text {
-                text: json!({
-                    "error": "CSM database not found"
-                })
-                .to_string(),
-            }],
-            is_error: Some(true),
-        };
+        return tool_failure(
+            json!({
+                "error": "CSM database not found"
+            })
+            .to_string(),
+        );
     }
 
     match db::get_db_session(session_id) {
@@ -965,40 +1551,24 @@ fn execute_db_get_session(session_id: &str) -> CallToolResult {
                 })
                 .collect();
 
-            CallToolResult {
-                content: vec![ToolContent::Text {
-                    text: serde_json::to_string_pretty(&json!({
-                        "session": {
-                            "id": session.id,
-                            "workspace_id": session.workspace_id,
-                            "provider": session.provider,
-                            "title": session.title,
-                            "model": session.model,
-                            "message_count": session.message_count,
-                            "created_at": session.created_at,
-                            "updated_at": session.updated_at,
-                            "archived": session.archived
-                        },
-                        "messages": message_infos,
-                        "source": "csm-web database"
-                    }))
-                    .unwrap_or_default(),
-                }],
-                is_error: None,
-            }
+            tool_json(json!({
+                "session": {
+                    "id": session.id,
+                    "workspace_id": session.workspace_id,
+                    "provider": session.provider,
+                    "title": session.title,
+                    "model": session.model,
+                    "message_count": session.message_count,
+                    "created_at": session.created_at,
+                    "updated_at": session.updated_at,
+                    "archived": session.archived
+                },
+                "messages": message_infos,
+                "source": "csm-web database"
+            }))
         }
-        Ok(None) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: format!("Session not found: {}", session_id),
-            }],
-            is_error: Some(true),
-        },
-        Err(e) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: format!("Error getting session: {}", e),
-            }],
-            is_error: Some(true),
-        },
+        Ok(None) => tool_failure(format!("Session not found: {}", session_id)),
+        Err(e) => tool_failure(format!("Error getting session: {}", e)),
     }
 }
 
@@ -1006,15 +1576,12 @@ fn execute_db_search(query: &str, limit: usize) -> CallToolResult {
     use super::db;
 
     if !db::csm_db_exists() {
-        return CallToolResult {
-            content: vec![ToolContent::Text {
-                text: json!({
-                    "error": "CSM database not found"
-                })
-                .to_string(),
-            }],
-            is_error: Some(true),
-        };
+        return tool_failure(
+            json!({
+                "error": "CSM database not found"
+            })
+            .to_string(),
+        );
     }
 
     match db::search_db_sessions(query, limit) {
@@ -1032,25 +1599,14 @@ fn execute_db_search(query: &str, limit: usize) -> CallToolResult {
                 })
                 .collect();
 
-            CallToolResult {
-                content: vec![ToolContent::Text {
-                    text: serde_json::to_string_pretty(&json!({
-                        "query": query,
-                        "results": infos,
-                        "total": infos.len(),
-                        "source": "csm-web database"
-                    }))
-                    .unwrap_or_default(),
-                }],
-                is_error: None,
-            }
+            tool_json(json!({
+                "query": query,
+                "results": infos,
+                "total": infos.len(),
+                "source": "csm-web database"
+            }))
         }
-        Err(e) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: format!("Error searching: {}", e),
-            }],
-            is_error: Some(true),
-        },
+        Err(e) => tool_failure(format!("Error searching: {}", e)),
     }
 }
 
@@ -1058,16 +1614,13 @@ fn execute_db_stats() -> CallToolResult {
     use super::db;
 
     if !db::csm_db_exists() {
-        return CallToolResult {
-            content: vec![ToolContent::Text {
-                text: json!({
-                    "error": "CSM database not found",
-                    "db_path": db::get_csm_db_path().display().to_string()
-                })
-                .to_string(),
-            }],
-            is_error: Some(true),
-        };
+        return tool_failure(
+            json!({
+                "error": "CSM database not found",
+                "db_path": db::get_csm_db_path().display().to_string()
+            })
+            .to_string(),
+        );
     }
 
     match db::count_sessions_by_provider() {
@@ -1079,24 +1632,13 @@ fn execute_db_stats() -> CallToolResult {
 
             let total: i64 = counts.iter().map(|(_, c)| c).sum();
 
-            CallToolResult {
-                content: vec![ToolContent::Text {
-                    text: serde_json::to_string_pretty(&json!({
-                        "total_sessions": total,
-                        "by_provider": provider_counts,
-                        "db_path": db::get_csm_db_path().display().to_string(),
-                        "source": "csm-web database"
-                    }))
-                    .unwrap_or_default(),
-                }],
-                is_error: None,
-            }
+            tool_json(json!({
+                "total_sessions": total,
+                "by_provider": provider_counts,
+                "db_path": db::get_csm_db_path().display().to_string(),
+                "source": "csm-web database"
+            }))
         }
-        Err(e) => CallToolResult {
-            content: vec![ToolContent::Text {
-                text: format!("Error getting stats: {}", e),
-            }],
-            is_error: Some(true),
-        },
+        Err(e) => tool_failure(format!("Error getting stats: {}", e)),
     }
 }
@@ -61,6 +61,24 @@ impl JsonRpcResponse {
     }
 }
 
+/// JSON-RPC Notification, sent without an ID and never answered
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcNotification {
+    pub jsonrpc: String,
+    pub method: String,
+    pub params: serde_json::Value,
+}
+
+impl JsonRpcNotification {
+    pub fn new(method: &str, params: serde_json::Value) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params,
+        }
+    }
+}
+
 // ============================================================================
 // MCP Protocol Types
 // ============================================================================
@@ -74,6 +92,8 @@ pub struct ServerCapabilities {
     pub resources: Option<ResourcesCapability>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub prompts: Option<PromptsCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logging: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -150,6 +170,12 @@ pub struct Tool {
     pub description: Option<String>,
     #[serde(rename = "inputSchema")]
     pub input_schema: serde_json::Value,
+    #[serde(
+        rename = "outputSchema",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub output_schema: Option<serde_json::Value>,
 }
 
 /// Tool call request params
@@ -164,6 +190,13 @@ pub struct CallToolParams {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CallToolResult {
     pub content: Vec<ToolContent>,
+    /// The result as typed JSON matching the tool's `outputSchema`
+    #[serde(
+        rename = "structuredContent",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub structured_content: Option<serde_json::Value>,
     #[serde(rename = "isError", skip_serializing_if = "Option::is_none")]
     pub is_error: Option<bool>,
 }
@@ -194,6 +227,19 @@ pub struct Resource {
     pub mime_type: Option<String>,
 }
 
+/// Resource template, an RFC 6570 URI template clients fill in to read a
+/// family of resources
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceTemplate {
+    #[serde(rename = "uriTemplate")]
+    pub uri_template: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
 /// Resource content
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceContent {
@@ -212,6 +258,12 @@ pub struct ReadResourceParams {
     pub uri: String,
 }
 
+/// Subscribe / unsubscribe params
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscribeParams {
+    pub uri: String,
+}
+
 /// Read resource result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReadResourceResult {
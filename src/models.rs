@@ -335,13 +335,13 @@ impl ChatSession {
         if let Some(first_req) = self.requests.first() {
             if let Some(msg) = &first_req.message {
                 if let Some(text) = &msg.text {
-                    // Truncate to first 50 chars
-                    let title: String = text.chars().take(50).collect();
+                    // Truncate to the first 50 columns
+                    let title = crate::text::prefix_by_width(text, 50);
                     if !title.is_empty() {
                         if title.len() < text.len() {
                             return format!("{}...", title);
                         }
-                        return title;
+                        return title.to_string();
                     }
                 }
             }
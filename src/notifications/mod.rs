@@ -0,0 +1,440 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Notification providers and channels
+//!
+//! A *provider* knows how to deliver a message (desktop notification, Slack
+//! webhook, email, ...). A *channel* is a named provider configuration in
+//! `config.toml` under `[notifications.channels.<name>]`, with an optional
+//! message template and a list of event patterns it subscribes to.
+//!
+//! Features raise a [`Notification`] with a dotted event name
+//! (`harvest.session_added`, `agency.approval_requested`, ...) and call
+//! [`dispatch`], which renders and delivers it to every matching channel.
+//! Additional providers can be added with [`NotificationRegistry::register`].
+
+mod providers;
+
+pub use providers::{
+    DiscordProvider, EmailProvider, SlackProvider, SystemProvider, WebhookProvider,
+};
+
+use crate::config::{self, ChannelConfig};
+use crate::error::{CsmError, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Template used when a channel does not set one
+pub const DEFAULT_TEMPLATE: &str = "{{title}}: {{message}}";
+
+/// Severity of a notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationLevel {
+    #[default]
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for NotificationLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Info => "info",
+            Self::Success => "success",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A message to deliver
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Notification {
+    /// Dotted event name used for channel subscriptions
+    pub event: String,
+    pub title: String,
+    pub message: String,
+    pub level: NotificationLevel,
+    /// Extra values available to templates as `{{name}}`
+    pub fields: BTreeMap<String, String>,
+}
+
+impl Notification {
+    pub fn new(event: &str, title: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            event: event.to_string(),
+            title: title.into(),
+            message: message.into(),
+            level: NotificationLevel::Info,
+            fields: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_level(mut self, level: NotificationLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    pub fn with_field(mut self, key: &str, value: impl Into<String>) -> Self {
+        self.fields.insert(key.to_string(), value.into());
+        self
+    }
+
+    /// Fill `{{title}}`, `{{message}}`, `{{event}}`, `{{level}}`, and field placeholders.
+    /// Unknown placeholders are left as-is. Values are filled in one pass, so
+    /// placeholders inside them are not expanded again.
+    pub fn render(&self, template: &str) -> String {
+        let mut result = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            let end = start + len + 4;
+            result.push_str(&rest[..start]);
+            match self.value(&rest[start + 2..end - 2]) {
+                Some(value) => result.push_str(&value),
+                None => result.push_str(&rest[start..end]),
+            }
+            rest = &rest[end..];
+        }
+        result.push_str(rest);
+        result
+    }
+
+    /// The value of the placeholder `{{key}}`
+    fn value(&self, key: &str) -> Option<String> {
+        match key {
+            "title" => Some(self.title.clone()),
+            "message" => Some(self.message.clone()),
+            "event" => Some(self.event.clone()),
+            "level" => Some(self.level.to_string()),
+            _ => self.fields.get(key).cloned(),
+        }
+    }
+}
+
+/// Delivers notifications for one kind of channel
+pub trait NotificationProvider: Send + Sync {
+    /// Identifier used in `provider = "..."`
+    fn id(&self) -> &'static str;
+
+    /// Human-readable name
+    fn name(&self) -> &'static str;
+
+    /// Settings the channel must define
+    fn required_settings(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Deliver `text` (the rendered template) for `notification`
+    fn send(&self, channel: &Channel, notification: &Notification, text: &str) -> Result<()>;
+}
+
+/// A channel with its settings resolved
+#[derive(Debug, Clone)]
+pub struct Channel {
+    pub name: String,
+    pub config: ChannelConfig,
+}
+
+impl Channel {
+    pub fn new(name: &str, config: ChannelConfig) -> Self {
+        Self {
+            name: name.to_string(),
+            config,
+        }
+    }
+
+    /// A provider setting, reading `$VAR` values from the environment
+    pub fn setting(&self, key: &str) -> Option<String> {
+        config::expand_env(self.config.settings.get(key)?)
+    }
+
+    /// A required provider setting
+    pub fn require(&self, key: &str) -> Result<String> {
+        self.setting(key).ok_or_else(|| {
+            let detail = match self.config.settings.get(key) {
+                Some(value) if value.starts_with('$') => {
+                    format!("setting '{}' reads {}, which is not set", key, value)
+                }
+                _ => format!("missing setting '{}'", key),
+            };
+            CsmError::NotificationError(format!("channel '{}': {}", self.name, detail))
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled.unwrap_or(true)
+    }
+
+    /// Whether the channel subscribes to `event` (all events when no patterns are set)
+    pub fn accepts(&self, event: &str) -> bool {
+        match &self.config.events {
+            None => true,
+            Some(patterns) => patterns.iter().any(|p| {
+                glob::Pattern::new(p)
+                    .map(|pattern| pattern.matches(event))
+                    .unwrap_or(p == event)
+            }),
+        }
+    }
+
+    /// The channel's message for a notification
+    pub fn render(&self, notification: &Notification) -> String {
+        notification.render(self.config.template.as_deref().unwrap_or(DEFAULT_TEMPLATE))
+    }
+}
+
+/// Available notification providers
+pub struct NotificationRegistry {
+    providers: Vec<Box<dyn NotificationProvider>>,
+}
+
+impl Default for NotificationRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(SystemProvider));
+        registry.register(Box::new(SlackProvider));
+        registry.register(Box::new(DiscordProvider));
+        registry.register(Box::new(EmailProvider));
+        registry.register(Box::new(WebhookProvider));
+        registry
+    }
+}
+
+impl NotificationRegistry {
+    /// An empty registry (use `default()` for the built-in providers)
+    pub fn new() -> Self {
+        Self {
+            providers: Vec::new(),
+        }
+    }
+
+    /// Add a provider, replacing any with the same ID
+    pub fn register(&mut self, provider: Box<dyn NotificationProvider>) {
+        self.providers.retain(|p| p.id() != provider.id());
+        self.providers.push(provider);
+    }
+
+    pub fn get(&self, id: &str) -> Option<&dyn NotificationProvider> {
+        self.providers
+            .iter()
+            .find(|p| p.id().eq_ignore_ascii_case(id))
+            .map(|p| p.as_ref())
+    }
+
+    pub fn providers(&self) -> impl Iterator<Item = &dyn NotificationProvider> {
+        self.providers.iter().map(|p| p.as_ref())
+    }
+
+    /// Check that a channel names a known provider and has its required settings
+    pub fn validate(&self, channel: &Channel) -> Result<()> {
+        let provider = self.provider_for(channel)?;
+        for key in provider.required_settings() {
+            channel.require(key)?;
+        }
+        Ok(())
+    }
+
+    /// Send to one channel, ignoring its event filter
+    pub fn send(&self, channel: &Channel, notification: &Notification) -> Result<()> {
+        self.validate(channel)?;
+        let text = channel.render(notification);
+        self.provider_for(channel)?
+            .send(channel, notification, &text)
+    }
+
+    /// Send to every enabled channel that subscribes to the event.
+    /// Returns the channels that failed along with their errors.
+    pub fn dispatch_to(
+        &self,
+        channels: &[Channel],
+        notification: &Notification,
+    ) -> Vec<(String, CsmError)> {
+        channels
+            .iter()
+            .filter(|c| c.is_enabled() && c.accepts(&notification.event))
+            .filter_map(|c| {
+                self.send(c, notification)
+                    .err()
+                    .map(|e| (c.name.clone(), e))
+            })
+            .collect()
+    }
+
+    fn provider_for(&self, channel: &Channel) -> Result<&dyn NotificationProvider> {
+        self.get(&channel.config.provider).ok_or_else(|| {
+            CsmError::NotificationError(format!(
+                "channel '{}' uses unknown provider '{}'",
+                channel.name, channel.config.provider
+            ))
+        })
+    }
+}
+
+static REGISTRY: Lazy<NotificationRegistry> = Lazy::new(NotificationRegistry::default);
+
+/// Registry with the built-in providers
+pub fn registry() -> &'static NotificationRegistry {
+    &REGISTRY
+}
+
+/// Channels from the configuration file
+pub fn configured_channels() -> Vec<Channel> {
+    config::current()
+        .notifications
+        .channels
+        .iter()
+        .map(|(name, config)| Channel::new(name, config.clone()))
+        .collect()
+}
+
+/// Find a configured channel by name, or by provider when exactly one channel uses it
+pub fn find_channel(name: &str) -> Result<Channel> {
+    let channels = configured_channels();
+    if let Some(channel) = channels.iter().find(|c| c.name == name) {
+        return Ok(channel.clone());
+    }
+    let mut by_provider = channels
+        .into_iter()
+        .filter(|c| c.config.provider.eq_ignore_ascii_case(name));
+    match (by_provider.next(), by_provider.next()) {
+        (Some(channel), None) => Ok(channel),
+        (Some(_), Some(_)) => Err(CsmError::NotificationError(format!(
+            "several channels use provider '{}'; name one of them",
+            name
+        ))),
+        (None, _) => Err(CsmError::NotificationError(format!(
+            "no channel named '{}' (add one with `csm notify add {} <provider>`)",
+            name, name
+        ))),
+    }
+}
+
+/// Send a notification to all configured channels that subscribe to its event.
+/// Failures are returned, not raised, so callers can warn and carry on.
+pub fn dispatch(notification: &Notification) -> Vec<(String, CsmError)> {
+    registry().dispatch_to(&configured_channels(), notification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Records messages instead of sending them
+    struct RecordingProvider(Arc<Mutex<Vec<String>>>);
+
+    impl NotificationProvider for RecordingProvider {
+        fn id(&self) -> &'static str {
+            "recording"
+        }
+        fn name(&self) -> &'static str {
+            "Recording"
+        }
+        fn required_settings(&self) -> &'static [&'static str] {
+            &["target"]
+        }
+        fn send(&self, channel: &Channel, _n: &Notification, text: &str) -> Result<()> {
+            let target = channel.require("target")?;
+            self.0.lock().unwrap().push(format!("{} {}", target, text));
+            Ok(())
+        }
+    }
+
+    fn channel(name: &str, events: Option<Vec<&str>>, template: Option<&str>) -> Channel {
+        Channel::new(
+            name,
+            ChannelConfig {
+                provider: "recording".to_string(),
+                events: events.map(|e| e.into_iter().map(String::from).collect()),
+                template: template.map(String::from),
+                settings: BTreeMap::from([("target".to_string(), name.to_string())]),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_render_template() {
+        let n = Notification::new("harvest.session_added", "Harvest", "1 new session")
+            .with_level(NotificationLevel::Success)
+            .with_field("provider", "copilot");
+        assert_eq!(n.render(DEFAULT_TEMPLATE), "Harvest: 1 new session");
+        assert_eq!(
+            n.render("[{{level}}] {{event}} from {{provider}} {{missing}}"),
+            "[success] harvest.session_added from copilot {{missing}}"
+        );
+
+        // Values are not expanded again
+        let n = Notification::new("test", "{{message}}", "{{title}}").with_field("x", "{{level}}");
+        assert_eq!(
+            n.render("{{title}} {{message}} {{x}} {{"),
+            "{{message}} {{title}} {{level}} {{"
+        );
+    }
+
+    #[test]
+    fn test_event_patterns() {
+        let all = channel("all", None, None);
+        let harvest = channel("harvest", Some(vec!["harvest.*"]), None);
+        assert!(all.accepts("agency.approval_requested"));
+        assert!(harvest.accepts("harvest.session_added"));
+        assert!(!harvest.accepts("automation.notify"));
+    }
+
+    #[test]
+    fn test_dispatch_to_matching_channels() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let mut registry = NotificationRegistry::new();
+        registry.register(Box::new(RecordingProvider(sent.clone())));
+
+        let mut disabled = channel("off", None, None);
+        disabled.config.enabled = Some(false);
+        let mut broken = channel("broken", None, None);
+        broken.config.settings.clear();
+        let channels = vec![
+            channel("a", None, Some("{{message}}!")),
+            channel("b", Some(vec!["agency.*"]), None),
+            disabled,
+            broken,
+        ];
+
+        let failures = registry.dispatch_to(
+            &channels,
+            &Notification::new("harvest.session_added", "Harvest", "done"),
+        );
+        assert_eq!(*sent.lock().unwrap(), vec!["a done!".to_string()]);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, "broken");
+    }
+
+    #[test]
+    fn test_env_settings() {
+        std::env::set_var("CSM_TEST_NOTIFY_URL", "https://example.invalid/hook");
+        let mut c = channel("env", None, None);
+        c.config
+            .settings
+            .insert("url".to_string(), "$CSM_TEST_NOTIFY_URL".to_string());
+        assert_eq!(
+            c.setting("url").as_deref(),
+            Some("https://example.invalid/hook")
+        );
+        c.config
+            .settings
+            .insert("url".to_string(), "$CSM_TEST_NOTIFY_UNSET".to_string());
+        assert!(c.setting("url").is_none());
+    }
+
+    #[test]
+    fn test_unknown_provider_rejected() {
+        let mut c = channel("x", None, None);
+        c.config.provider = "pager".to_string();
+        assert!(registry().validate(&c).is_err());
+        assert!(registry().get("SLACK").is_some());
+    }
+}
@@ -0,0 +1,283 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Built-in notification providers
+
+use super::{Channel, Notification, NotificationProvider};
+use crate::config;
+use crate::error::{CsmError, Result};
+use crate::integrations::email::{Email, Mailer, Sendmail, SmtpMailer};
+use std::process::Command;
+use std::time::Duration;
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn post_json(channel: &Channel, body: &serde_json::Value) -> Result<()> {
+    let url = channel.require("url")?;
+    let client = reqwest::blocking::Client::builder()
+        .timeout(HTTP_TIMEOUT)
+        .build()
+        .map_err(|e| CsmError::NotificationError(e.to_string()))?;
+    let response = client
+        .post(&url)
+        .json(body)
+        .send()
+        .map_err(|e| CsmError::NotificationError(format!("{}: {}", channel.name, e)))?;
+    if !response.status().is_success() {
+        return Err(CsmError::NotificationError(format!(
+            "{}: server returned {}",
+            channel.name,
+            response.status()
+        )));
+    }
+    Ok(())
+}
+
+fn run(command: &mut Command, what: &str) -> Result<()> {
+    let status = command
+        .status()
+        .map_err(|e| CsmError::NotificationError(format!("failed to run {}: {}", what, e)))?;
+    if !status.success() {
+        return Err(CsmError::NotificationError(format!(
+            "{} exited with {}",
+            what, status
+        )));
+    }
+    Ok(())
+}
+
+/// Desktop notification (`notify-send` on Linux, `osascript` on macOS)
+pub struct SystemProvider;
+
+impl NotificationProvider for SystemProvider {
+    fn id(&self) -> &'static str {
+        "system"
+    }
+
+    fn name(&self) -> &'static str {
+        "Desktop notification"
+    }
+
+    fn send(&self, _channel: &Channel, notification: &Notification, text: &str) -> Result<()> {
+        if cfg!(target_os = "macos") {
+            let script = format!(
+                "display notification {:?} with title {:?}",
+                text, notification.title
+            );
+            run(Command::new("osascript").args(["-e", &script]), "osascript")
+        } else if cfg!(windows) {
+            Err(CsmError::NotificationError(
+                "desktop notifications are not supported on Windows yet".to_string(),
+            ))
+        } else {
+            run(
+                Command::new("notify-send").args(["--app-name=csm", &notification.title, text]),
+                "notify-send",
+            )
+        }
+    }
+}
+
+/// Slack incoming webhook (`url`)
+pub struct SlackProvider;
+
+impl NotificationProvider for SlackProvider {
+    fn id(&self) -> &'static str {
+        "slack"
+    }
+
+    fn name(&self) -> &'static str {
+        "Slack webhook"
+    }
+
+    fn required_settings(&self) -> &'static [&'static str] {
+        &["url"]
+    }
+
+    fn send(&self, channel: &Channel, _notification: &Notification, text: &str) -> Result<()> {
+        post_json(channel, &serde_json::json!({ "text": text }))
+    }
+}
+
+/// Discord webhook (`url`)
+pub struct DiscordProvider;
+
+impl NotificationProvider for DiscordProvider {
+    fn id(&self) -> &'static str {
+        "discord"
+    }
+
+    fn name(&self) -> &'static str {
+        "Discord webhook"
+    }
+
+    fn required_settings(&self) -> &'static [&'static str] {
+        &["url"]
+    }
+
+    fn send(&self, channel: &Channel, _notification: &Notification, text: &str) -> Result<()> {
+        post_json(channel, &serde_json::json!({ "content": text }))
+    }
+}
+
+/// Generic webhook (`url`); posts the notification as JSON with the rendered `text`
+pub struct WebhookProvider;
+
+impl NotificationProvider for WebhookProvider {
+    fn id(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn name(&self) -> &'static str {
+        "Webhook"
+    }
+
+    fn required_settings(&self) -> &'static [&'static str] {
+        &["url"]
+    }
+
+    fn send(&self, channel: &Channel, notification: &Notification, text: &str) -> Result<()> {
+        let mut body = serde_json::to_value(notification)?;
+        body["text"] = serde_json::Value::String(text.to_string());
+        post_json(channel, &body)
+    }
+}
+
+/// Email (`to`, optional `from` and `command`). Mail goes through the
+/// sendmail-compatible `command` if set, else the server under
+/// `[integrations.smtp]` if there is one, else `sendmail`.
+pub struct EmailProvider;
+
+impl NotificationProvider for EmailProvider {
+    fn id(&self) -> &'static str {
+        "email"
+    }
+
+    fn name(&self) -> &'static str {
+        "Email"
+    }
+
+    fn required_settings(&self) -> &'static [&'static str] {
+        &["to"]
+    }
+
+    fn send(&self, channel: &Channel, notification: &Notification, text: &str) -> Result<()> {
+        let email = Email {
+            to: channel
+                .require("to")?
+                .split(',')
+                .map(str::trim)
+                .filter(|to| !to.is_empty())
+                .map(String::from)
+                .collect(),
+            subject: notification.title.clone(),
+            html: String::new(),
+            text: text.to_string(),
+        };
+        let from = channel.setting("from");
+        let mailer = match channel.setting("command") {
+            Some(command) => Mailer::Sendmail(Sendmail::new(command, from)),
+            None if config::current().integrations.smtp.host.is_some() => {
+                Mailer::Smtp(SmtpMailer::from_config()?)
+            }
+            None => Mailer::Sendmail(Sendmail::new("sendmail", from)),
+        };
+        mailer
+            .send(&email)
+            .map_err(|e| CsmError::NotificationError(format!("{}: {}", channel.name, e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ChannelConfig;
+    use std::collections::BTreeMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_webhook_posts_rendered_json() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(v) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    length = v.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        });
+
+        let channel = Channel::new(
+            "hook",
+            ChannelConfig {
+                provider: "webhook".to_string(),
+                template: Some("{{level}}: {{message}}".to_string()),
+                settings: BTreeMap::from([("url".to_string(), url)]),
+                ..Default::default()
+            },
+        );
+        let notification = Notification::new("test", "Title", "hello");
+        super::super::registry()
+            .send(&channel, &notification)
+            .unwrap();
+
+        let body = server.join().unwrap();
+        assert_eq!(body["text"], "info: hello");
+        assert_eq!(body["event"], "test");
+        assert_eq!(body["title"], "Title");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_email_passes_recipients_as_arguments() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+        let script = dir.path().join("sendmail");
+        std::fs::write(
+            &script,
+            format!("#!/bin/sh\necho \"$@\" > {0}\ncat >> {0}\n", out.display()),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let channel = Channel::new(
+            "mail",
+            ChannelConfig {
+                provider: "email".to_string(),
+                settings: BTreeMap::from([
+                    ("to".to_string(), "me@example.com".to_string()),
+                    ("command".to_string(), script.display().to_string()),
+                ]),
+                ..Default::default()
+            },
+        );
+        let notification = Notification::new("test", "Done\nBcc: them@example.com", "hello");
+        EmailProvider
+            .send(&channel, &notification, "hello")
+            .unwrap();
+
+        let sent = std::fs::read_to_string(&out).unwrap();
+        assert!(sent.starts_with(
+            "-i me@example.com\nTo: me@example.com\nSubject: Done Bcc: them@example.com\n"
+        ));
+        assert!(!sent.contains("\nBcc:"));
+    }
+}
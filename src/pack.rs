@@ -0,0 +1,534 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Shareable packs of automation definitions
+//!
+//! Workflows, agents, prompt templates, and saved searches live as JSON files
+//! in a local library (`library/<kind>/<name>.json` next to `config.toml`).
+//! A pack bundles selected library items into a single gzip-compressed JSON
+//! file with a manifest. Values under secret-looking keys (`token`,
+//! `password`, `webhook_url`, ...) are replaced with a placeholder on export,
+//! and the pack is signed with HMAC-SHA256 when a team key is available
+//! (`CSM_PACK_KEY`), or carries a plain SHA-256 digest otherwise.
+
+use crate::config::Config;
+use crate::error::{CsmError, Result};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Current pack file format
+pub const PACK_FORMAT_VERSION: u32 = 1;
+
+/// Environment variable holding the shared signing key
+pub const PACK_KEY_ENV: &str = "CSM_PACK_KEY";
+
+/// Largest a pack may be once decompressed
+const MAX_PACK_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Placeholder written in place of stripped secrets
+pub const REDACTED: &str = "<redacted>";
+
+/// Key fragments whose values are treated as secrets
+const SECRET_KEYS: &[&str] = &[
+    "token",
+    "secret",
+    "password",
+    "passwd",
+    "api_key",
+    "apikey",
+    "authorization",
+    "credential",
+    "cookie",
+    "private_key",
+    "webhook_url",
+];
+
+/// Kind of library item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PackItemKind {
+    Workflow,
+    Agent,
+    Prompt,
+    SavedSearch,
+}
+
+impl PackItemKind {
+    pub const ALL: [PackItemKind; 4] =
+        [Self::Workflow, Self::Agent, Self::Prompt, Self::SavedSearch];
+
+    /// Library subdirectory
+    pub fn dir_name(&self) -> &'static str {
+        match self {
+            Self::Workflow => "workflows",
+            Self::Agent => "agents",
+            Self::Prompt => "prompts",
+            Self::SavedSearch => "searches",
+        }
+    }
+}
+
+impl std::fmt::Display for PackItemKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Workflow => "workflow",
+            Self::Agent => "agent",
+            Self::Prompt => "prompt",
+            Self::SavedSearch => "saved search",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A library item
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackItem {
+    pub kind: PackItemKind,
+    pub name: String,
+    pub data: serde_json::Value,
+}
+
+/// Describes a pack
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackManifest {
+    pub format_version: u32,
+    /// Pack type (`automation`)
+    pub kind: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// csm version that wrote the pack
+    pub created_with: String,
+    /// Item fields that were stripped on export (`workflow/nightly: actions.0.headers.Authorization`)
+    pub redacted: Vec<String>,
+}
+
+/// Integrity or authenticity check over the manifest and items
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackSignature {
+    /// `hmac-sha256` (signed with a team key) or `sha256` (digest only)
+    pub algorithm: String,
+    pub value: String,
+}
+
+/// Result of checking a pack's signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verification {
+    /// Signed, and the key matches
+    Verified,
+    /// Not signed; the digest matches
+    Unsigned,
+    /// Signed, but no key was given to check it
+    NoKey,
+    /// Signature or digest does not match
+    Invalid,
+}
+
+/// A pack file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pack {
+    pub manifest: PackManifest,
+    pub items: Vec<PackItem>,
+    pub signature: PackSignature,
+}
+
+impl Pack {
+    /// Build an automation pack, stripping secrets from the items
+    pub fn automation(
+        name: &str,
+        description: Option<&str>,
+        mut items: Vec<PackItem>,
+        key: Option<&str>,
+    ) -> Result<Self> {
+        let mut redacted = Vec::new();
+        for item in &mut items {
+            let mut fields = Vec::new();
+            strip_secrets(&mut item.data, "", &mut fields);
+            redacted.extend(
+                fields
+                    .into_iter()
+                    .map(|f| format!("{}/{}: {}", item.kind.dir_name(), item.name, f)),
+            );
+        }
+
+        let manifest = PackManifest {
+            format_version: PACK_FORMAT_VERSION,
+            kind: "automation".to_string(),
+            name: name.to_string(),
+            description: description.map(String::from),
+            created_at: Utc::now(),
+            created_with: env!("CARGO_PKG_VERSION").to_string(),
+            redacted,
+        };
+        let signature = sign(&payload(&manifest, &items)?, key);
+        Ok(Self {
+            manifest,
+            items,
+            signature,
+        })
+    }
+
+    /// Read a pack file
+    pub fn read(path: &Path) -> Result<Self> {
+        let mut json = String::new();
+        // One byte past the limit shows the pack is over it
+        GzDecoder::new(fs::File::open(path)?)
+            .take(MAX_PACK_BYTES + 1)
+            .read_to_string(&mut json)
+            .map_err(|e| CsmError::PackError(format!("{}: {}", path.display(), e)))?;
+        if json.len() as u64 > MAX_PACK_BYTES {
+            return Err(CsmError::PackError(format!(
+                "{} is over {} MB uncompressed",
+                path.display(),
+                MAX_PACK_BYTES / (1024 * 1024)
+            )));
+        }
+        let pack: Self = serde_json::from_str(&json)?;
+        if pack.manifest.format_version > PACK_FORMAT_VERSION {
+            return Err(CsmError::PackError(format!(
+                "{} uses pack format {}; this csm reads up to {}",
+                path.display(),
+                pack.manifest.format_version,
+                PACK_FORMAT_VERSION
+            )));
+        }
+        Ok(pack)
+    }
+
+    /// Write the pack as gzip-compressed JSON
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let mut encoder = GzEncoder::new(fs::File::create(path)?, Compression::default());
+        encoder.write_all(&serde_json::to_vec_pretty(self)?)?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    pub fn is_signed(&self) -> bool {
+        self.signature.algorithm == "hmac-sha256"
+    }
+
+    /// Check the signature (or digest, for unsigned packs)
+    pub fn verify(&self, key: Option<&str>) -> Verification {
+        let Ok(payload) = payload(&self.manifest, &self.items) else {
+            return Verification::Invalid;
+        };
+        if self.is_signed() {
+            match key {
+                None => Verification::NoKey,
+                Some(key) => {
+                    // Checked in constant time, so timing does not reveal the signature
+                    let valid = hex::decode(&self.signature.value)
+                        .is_ok_and(|value| mac(&payload, key).verify_slice(&value).is_ok());
+                    if valid {
+                        Verification::Verified
+                    } else {
+                        Verification::Invalid
+                    }
+                }
+            }
+        } else if sign(&payload, None) == self.signature {
+            Verification::Unsigned
+        } else {
+            Verification::Invalid
+        }
+    }
+}
+
+/// The signing key from `CSM_PACK_KEY`, if set
+pub fn key_from_env() -> Option<String> {
+    std::env::var(PACK_KEY_ENV).ok().filter(|k| !k.is_empty())
+}
+
+fn payload(manifest: &PackManifest, items: &[PackItem]) -> Result<Vec<u8>> {
+    Ok(serde_json::to_vec(&(manifest, items))?)
+}
+
+fn mac(payload: &[u8], key: &str) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(payload);
+    mac
+}
+
+fn sign(payload: &[u8], key: Option<&str>) -> PackSignature {
+    match key {
+        Some(key) => PackSignature {
+            algorithm: "hmac-sha256".to_string(),
+            value: hex::encode(mac(payload, key).finalize().into_bytes()),
+        },
+        None => PackSignature {
+            algorithm: "sha256".to_string(),
+            value: hex::encode(Sha256::digest(payload)),
+        },
+    }
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_lowercase().replace('-', "_");
+    SECRET_KEYS.iter().any(|s| key.contains(s))
+}
+
+/// Replace secret values in place, collecting the dotted paths that were stripped.
+/// `$VAR` references are kept since they name a secret rather than contain one.
+pub fn strip_secrets(value: &mut serde_json::Value, path: &str, stripped: &mut Vec<String>) {
+    let child_path = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                let is_reference = child.as_str().is_some_and(|s| s.starts_with('$'));
+                if is_secret_key(key) && !child.is_null() && !is_reference {
+                    if child.is_object() || child.is_array() {
+                        strip_all(child);
+                    } else {
+                        *child = serde_json::Value::String(REDACTED.to_string());
+                    }
+                    stripped.push(child_path(key));
+                } else {
+                    strip_secrets(child, &child_path(key), stripped);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for (i, child) in items.iter_mut().enumerate() {
+                strip_secrets(child, &child_path(&i.to_string()), stripped);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Redact every scalar under a secret key (e.g. a `credentials` object)
+fn strip_all(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => map.values_mut().for_each(strip_all),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(strip_all),
+        serde_json::Value::Null => {}
+        _ => *value = serde_json::Value::String(REDACTED.to_string()),
+    }
+}
+
+/// Reject names that would escape the library directory
+pub fn validate_item_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\', ':'])
+        && !name.starts_with('.');
+    if valid {
+        Ok(())
+    } else {
+        Err(CsmError::PackError(format!("invalid item name '{}'", name)))
+    }
+}
+
+/// Local store of workflows, agents, prompts, and saved searches
+pub struct Library {
+    root: PathBuf,
+}
+
+impl Library {
+    /// The library next to `config.toml`
+    pub fn open() -> Result<Self> {
+        let config_path = Config::path()?;
+        let dir = config_path.parent().unwrap_or(Path::new("."));
+        Ok(Self::at(&dir.join("library")))
+    }
+
+    pub fn at(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn item_path(&self, kind: PackItemKind, name: &str) -> PathBuf {
+        self.root
+            .join(kind.dir_name())
+            .join(format!("{}.json", name))
+    }
+
+    /// Items of one kind, sorted by name
+    pub fn items(&self, kind: PackItemKind) -> Result<Vec<PackItem>> {
+        let dir = self.root.join(kind.dir_name());
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut items = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                let name = path
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let data = serde_json::from_str(&fs::read_to_string(&path)?)
+                    .map_err(|e| CsmError::PackError(format!("{}: {}", path.display(), e)))?;
+                items.push(PackItem { kind, name, data });
+            }
+        }
+        items.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(items)
+    }
+
+    /// Every item in the library
+    pub fn all_items(&self) -> Result<Vec<PackItem>> {
+        let mut items = Vec::new();
+        for kind in PackItemKind::ALL {
+            items.extend(self.items(kind)?);
+        }
+        Ok(items)
+    }
+
+    pub fn get(&self, kind: PackItemKind, name: &str) -> Result<Option<PackItem>> {
+        validate_item_name(name)?;
+        Ok(self.items(kind)?.into_iter().find(|i| i.name == name))
+    }
+
+    pub fn contains(&self, kind: PackItemKind, name: &str) -> bool {
+        self.item_path(kind, name).exists()
+    }
+
+    /// Write an item, replacing any existing one with the same name
+    pub fn save(&self, item: &PackItem) -> Result<PathBuf> {
+        validate_item_name(&item.name)?;
+        let path = self.item_path(item.kind, &item.name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(&item.data)?)?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn workflow() -> PackItem {
+        PackItem {
+            kind: PackItemKind::Workflow,
+            name: "nightly".to_string(),
+            data: json!({
+                "id": "nightly",
+                "actions": [{
+                    "type": "notify",
+                    "channel": { "type": "slack", "webhook_url": "https://hooks.slack.com/T0/B0/xyz" },
+                    "message": "done"
+                }, {
+                    "type": "http",
+                    "headers": { "Authorization": "Bearer abc", "Accept": "json" },
+                    "api_key": "$OPENAI_API_KEY"
+                }],
+                "credentials": { "user": "me", "pass": "pw" }
+            }),
+        }
+    }
+
+    #[test]
+    fn test_strip_secrets() {
+        let mut data = workflow().data;
+        let mut stripped = Vec::new();
+        strip_secrets(&mut data, "", &mut stripped);
+
+        assert_eq!(data["actions"][0]["channel"]["webhook_url"], REDACTED);
+        assert_eq!(data["actions"][1]["headers"]["Authorization"], REDACTED);
+        assert_eq!(data["actions"][1]["headers"]["Accept"], "json");
+        assert_eq!(data["actions"][1]["api_key"], "$OPENAI_API_KEY");
+        assert_eq!(data["credentials"]["user"], REDACTED);
+        assert_eq!(data["actions"][0]["message"], "done");
+        assert!(stripped.contains(&"actions.1.headers.Authorization".to_string()));
+        assert_eq!(stripped.len(), 3);
+    }
+
+    #[test]
+    fn test_signed_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("team.csmpack");
+        let pack = Pack::automation("team", None, vec![workflow()], Some("k3y")).unwrap();
+        assert_eq!(pack.manifest.redacted.len(), 3);
+        pack.write(&path).unwrap();
+
+        let read = Pack::read(&path).unwrap();
+        assert_eq!(read, pack);
+        assert_eq!(read.verify(Some("k3y")), Verification::Verified);
+        assert_eq!(read.verify(Some("wrong")), Verification::Invalid);
+        assert_eq!(read.verify(None), Verification::NoKey);
+
+        let mut forged = read.clone();
+        forged.signature.value = "zz".to_string();
+        assert_eq!(forged.verify(Some("k3y")), Verification::Invalid);
+        forged.signature.value.clear();
+        assert_eq!(forged.verify(Some("k3y")), Verification::Invalid);
+    }
+
+    #[test]
+    fn test_read_refuses_oversized_packs() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bomb.csmpack");
+        let mut encoder = GzEncoder::new(fs::File::create(&path).unwrap(), Compression::best());
+        let chunk = vec![b' '; 1024 * 1024];
+        for _ in 0..=MAX_PACK_BYTES / chunk.len() as u64 {
+            encoder.write_all(&chunk).unwrap();
+        }
+        encoder.finish().unwrap();
+        assert!(fs::metadata(&path).unwrap().len() < 1024 * 1024);
+
+        let err = Pack::read(&path).unwrap_err();
+        assert!(err.to_string().contains("uncompressed"), "{}", err);
+    }
+
+    #[test]
+    fn test_unsigned_pack_detects_tampering() {
+        let mut pack = Pack::automation("team", None, vec![workflow()], None).unwrap();
+        assert_eq!(pack.verify(None), Verification::Unsigned);
+        pack.items[0].data["id"] = json!("changed");
+        assert_eq!(pack.verify(None), Verification::Invalid);
+    }
+
+    #[test]
+    fn test_library_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let library = Library::at(dir.path());
+        library.save(&workflow()).unwrap();
+        assert!(library.contains(PackItemKind::Workflow, "nightly"));
+        assert_eq!(library.all_items().unwrap(), vec![workflow()]);
+        assert!(library
+            .get(PackItemKind::Agent, "nightly")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_item_names_cannot_escape_library() {
+        let library = Library::at(Path::new("/tmp/unused"));
+        for name in ["../evil", "a/b", "..", ".hidden", ""] {
+            let item = PackItem {
+                name: name.to_string(),
+                ..workflow()
+            };
+            assert!(library.save(&item).is_err(), "{} accepted", name);
+        }
+    }
+}
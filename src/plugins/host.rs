@@ -0,0 +1,543 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Host API for process plugins
+//!
+//! A process plugin can ask csm for data and services by writing JSON-RPC
+//! requests to stdout between its responses; csm answers them on the
+//! plugin's stdin. Each method needs a permission the plugin declares in
+//! `plugin.json`:
+//!
+//! | Method            | Permission       | Params                                   |
+//! | ----------------- | ---------------- | ---------------------------------------- |
+//! | `sessions/list`   | `session_read`   | `provider`, `search`, `limit`            |
+//! | `sessions/get`    | `session_read`   | `session_id`                             |
+//! | `sessions/tag`    | `session_write`  | `session_id`, `add`, `remove`            |
+//! | `sessions/delete` | `session_delete` | `session_id` (moved to the trash)        |
+//! | `config/get`      | `config_read`    | (secrets in the file come back redacted) |
+//! | `http/fetch`      | `network`        | `url`, `method`, `headers`, `body`       |
+//! | `shell/run`       | `shell`          | `command`, `args`                        |
+//! | `data/read`       | `file_system`    | `file` in the plugin's data directory    |
+//! | `data/write`      | `file_system`    | `file`, `content`                        |
+//! | `notify`          | `notifications`  | `title`, `message`, `level`              |
+//!
+//! A declared permission the plugin has not been granted is asked about the
+//! first time it is needed, when csm runs in a terminal, and the answer is
+//! kept in `plugins.json`. Calls needing a permission that is undeclared,
+//! refused, or cannot be asked about fail with a JSON-RPC error.
+
+use super::settings::SettingsFile;
+use super::{Permission, PluginContext, PluginInstance, INVOKE_TIMEOUT};
+use anyhow::{anyhow, bail, Context, Result};
+use colored::Colorize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{IsTerminal, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+
+/// Decides whether a plugin may have a permission it has not been granted
+pub type Consent = Arc<dyn Fn(&str, &Permission) -> bool + Send + Sync>;
+
+/// Sessions `sessions/list` returns when no `limit` is given
+const DEFAULT_LIMIT: usize = 50;
+
+/// The permission a host API method needs, `None` for unknown methods
+pub fn required_permission(method: &str) -> Option<Permission> {
+    Some(match method {
+        "sessions/list" | "sessions/get" => Permission::SessionRead,
+        "sessions/tag" => Permission::SessionWrite,
+        "sessions/delete" => Permission::SessionDelete,
+        "config/get" => Permission::ConfigRead,
+        "http/fetch" => Permission::Network,
+        "shell/run" => Permission::Shell,
+        "data/read" | "data/write" => Permission::FileSystem,
+        "notify" => Permission::Notifications,
+        _ => return None,
+    })
+}
+
+/// Ask on the terminal, if csm runs in one
+pub fn ask_on_terminal() -> Option<Consent> {
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return None;
+    }
+    Some(Arc::new(|plugin_id: &str, permission: &Permission| {
+        eprint!(
+            "{} Plugin {} wants to {} ({}). Allow? [y/N] ",
+            "[!]".yellow(),
+            plugin_id.cyan(),
+            permission.description(),
+            permission.name()
+        );
+        let _ = std::io::stderr().flush();
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).is_ok()
+            && matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
+    }))
+}
+
+/// What one plugin's host API calls are checked against and served from
+#[derive(Clone)]
+pub(super) struct Host {
+    pub(super) plugin_id: String,
+    pub(super) plugins_dir: PathBuf,
+    pub(super) plugins: Arc<RwLock<HashMap<String, PluginInstance>>>,
+    pub(super) consent: Option<Consent>,
+    /// Held while the user is asked, so questions come one at a time
+    pub(super) asking: Arc<Mutex<()>>,
+}
+
+impl Host {
+    /// Fail unless the plugin may use `permission`, asking the user if it
+    /// has not been decided yet
+    pub(super) async fn authorize(&self, permission: &Permission) -> Result<()> {
+        let id = self.plugin_id.as_str();
+        if self.granted(permission).await? {
+            return Ok(());
+        }
+        let _asking = self.asking.lock().await;
+        // Granted while another call was asking
+        if self.granted(permission).await? {
+            return Ok(());
+        }
+        let settings = SettingsFile::load(&self.plugins_dir)?.get(id);
+        if settings.denied.contains(permission) {
+            bail!(
+                "The {} permission was refused to plugin {}; grant it with `csm plugin enable {}`",
+                permission.name(),
+                id,
+                id
+            );
+        }
+        let Some(consent) = self.consent.clone() else {
+            bail!(
+                "Plugin {} has not been granted the {} permission; grant it with `csm plugin enable {}`",
+                id,
+                permission.name(),
+                id
+            );
+        };
+
+        let (plugin_id, asked) = (id.to_string(), permission.clone());
+        let allowed = tokio::task::spawn_blocking(move || consent(&plugin_id, &asked)).await?;
+        let mut plugins = self.plugins.write().await;
+        let plugin = plugins
+            .get_mut(id)
+            .ok_or_else(|| anyhow!("Plugin not found: {}", id))?;
+        if allowed {
+            plugin.granted.push(permission.clone());
+        }
+        let granted = plugin.granted.clone();
+        SettingsFile::update(&self.plugins_dir, id, |settings| {
+            if allowed {
+                settings.granted = Some(granted);
+            } else {
+                settings.denied.push(permission.clone());
+            }
+        })?;
+        if !allowed {
+            bail!(
+                "The {} permission was refused to plugin {}",
+                permission.name(),
+                id
+            );
+        }
+        log::info!("Granted {} to plugin {}", permission.name(), id);
+        Ok(())
+    }
+
+    /// Whether the plugin has `permission`, failing if it did not declare it
+    async fn granted(&self, permission: &Permission) -> Result<bool> {
+        let plugins = self.plugins.read().await;
+        let plugin = plugins
+            .get(&self.plugin_id)
+            .ok_or_else(|| anyhow!("Plugin not found: {}", self.plugin_id))?;
+        if !plugin.manifest.permissions.contains(permission) {
+            bail!(
+                "Plugin {} did not declare the {} permission in plugin.json",
+                self.plugin_id,
+                permission.name()
+            );
+        }
+        Ok(plugin.granted.contains(permission))
+    }
+
+    /// Serve a host API call from the plugin
+    pub(super) async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let permission =
+            required_permission(method).ok_or_else(|| anyhow!("Unknown method {}", method))?;
+        self.authorize(&permission).await?;
+
+        match method {
+            "sessions/list" => {
+                let conn = crate::commands::open_harvest_db(None)?;
+                let limit = params["limit"]
+                    .as_u64()
+                    .map_or(DEFAULT_LIMIT, |l| l as usize);
+                let sessions = crate::commands::list_harvested_sessions(
+                    &conn,
+                    params["provider"].as_str(),
+                    limit,
+                    params["search"].as_str(),
+                    None,
+                )?;
+                Ok(sessions
+                    .into_iter()
+                    .map(
+                        |(id, provider, title, message_count, created, updated, workspace)| {
+                            json!({
+                                "id": id,
+                                "provider": provider,
+                                "title": title,
+                                "message_count": message_count,
+                                "created_at": created,
+                                "updated_at": updated,
+                                "workspace_name": workspace,
+                            })
+                        },
+                    )
+                    .collect())
+            }
+            "sessions/get" => {
+                let id = string(&params, "session_id")?;
+                let conn = crate::commands::open_harvest_db(None)?;
+                let ids = [id.to_string()];
+                let session = crate::commands::export_session_json(&conn, None, Some(&ids))?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| anyhow!("Session not found: {}", id))?;
+                Ok(serde_json::from_str(&session)?)
+            }
+            "sessions/tag" => {
+                let id = string(&params, "session_id")?;
+                let conn = crate::commands::open_harvest_db(None)?;
+                let list = |key: &str| -> Vec<String> {
+                    params[key]
+                        .as_array()
+                        .map(|tags| {
+                            tags.iter()
+                                .filter_map(|t| t.as_str().map(str::to_string))
+                                .collect()
+                        })
+                        .unwrap_or_default()
+                };
+                let (add, remove) = (list("add"), list("remove"));
+                let exists: i64 =
+                    conn.query_row("SELECT COUNT(*) FROM sessions WHERE id = ?1", [id], |row| {
+                        row.get(0)
+                    })?;
+                if exists == 0 {
+                    bail!("Session not found: {}", id);
+                }
+                let mut tags = crate::reviews::user_tags(&conn, id)?;
+                tags.retain(|tag| !remove.contains(tag));
+                for tag in add {
+                    if !tags.contains(&tag) {
+                        tags.push(tag);
+                    }
+                }
+                let tags = crate::reviews::set_user_tags(&conn, id, &tags)?;
+                Ok(json!({ "tags": tags }))
+            }
+            "sessions/delete" => {
+                let id = string(&params, "session_id")?;
+                let conn = crate::commands::open_harvest_db(None)?;
+                let deleted = crate::commands::delete_harvested_session(&conn, id, true)?;
+                Ok(json!({ "deleted": deleted }))
+            }
+            "config/get" => redacted_config(crate::config::current()),
+            "http/fetch" => fetch(&params).await,
+            "shell/run" => self.run(&params).await,
+            "data/read" | "data/write" => {
+                let context = self.context().await?;
+                let file = data_file(string(&params, "file")?)?;
+                if method == "data/read" {
+                    Ok(Value::String(context.read_data(file)?))
+                } else {
+                    context.write_data(file, string(&params, "content")?)?;
+                    Ok(Value::Null)
+                }
+            }
+            "notify" => {
+                let level = match params.get("level") {
+                    Some(level) => serde_json::from_value(level.clone())
+                        .context("level must be info, success, warning, or error")?,
+                    None => Default::default(),
+                };
+                let notification = crate::notifications::Notification::new(
+                    &format!("plugin.{}", self.plugin_id),
+                    string(&params, "title")?,
+                    string(&params, "message")?,
+                )
+                .with_level(level)
+                .with_field("plugin", self.plugin_id.as_str());
+                let failed: Vec<Value> = crate::notifications::dispatch(&notification)
+                    .into_iter()
+                    .map(|(channel, e)| json!({ "channel": channel, "error": e.to_string() }))
+                    .collect();
+                Ok(json!({ "failed": failed }))
+            }
+            _ => unreachable!("every method with a permission is served"),
+        }
+    }
+
+    /// Serve a call from a thread outside any runtime
+    pub(super) fn call_blocking(&self, method: &str, params: Value) -> Result<Value> {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?
+            .block_on(self.call(method, params))
+    }
+
+    async fn context(&self) -> Result<PluginContext> {
+        let plugins = self.plugins.read().await;
+        let plugin = plugins
+            .get(&self.plugin_id)
+            .ok_or_else(|| anyhow!("Plugin not found: {}", self.plugin_id))?;
+        Ok(PluginContext {
+            plugin_id: self.plugin_id.clone(),
+            permissions: plugin.granted.clone(),
+            data_dir: plugin.path.join("data"),
+            config: plugin.config.clone(),
+        })
+    }
+
+    /// Run a program in the plugin's directory
+    async fn run(&self, params: &Value) -> Result<Value> {
+        let command = string(params, "command")?;
+        let args: Vec<&str> = params["args"]
+            .as_array()
+            .map(|args| args.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let dir = self.context().await?.data_dir;
+        let dir = dir.parent().unwrap_or(&dir);
+        let output = tokio::process::Command::new(command)
+            .args(&args)
+            .current_dir(dir)
+            .env("CSM_PLUGIN_ID", &self.plugin_id)
+            .kill_on_drop(true)
+            .output();
+        let output = tokio::time::timeout(INVOKE_TIMEOUT, output)
+            .await
+            .map_err(|_| anyhow!("{} timed out after {}s", command, INVOKE_TIMEOUT.as_secs()))?
+            .with_context(|| format!("Failed to run {}", command))?;
+        Ok(json!({
+            "status": output.status.code(),
+            "stdout": String::from_utf8_lossy(&output.stdout),
+            "stderr": String::from_utf8_lossy(&output.stderr),
+        }))
+    }
+}
+
+/// Make an HTTP request for the plugin
+async fn fetch(params: &Value) -> Result<Value> {
+    let url = string(params, "url")?;
+    let method = params["method"].as_str().unwrap_or("GET");
+    let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+        .with_context(|| format!("Invalid HTTP method {}", method))?;
+    let mut request = reqwest::Client::new()
+        .request(method, url)
+        .timeout(INVOKE_TIMEOUT);
+    if let Some(headers) = params["headers"].as_object() {
+        for (name, value) in headers {
+            request = request.header(name, value.as_str().unwrap_or_default());
+        }
+    }
+    if let Some(body) = params.get("body").filter(|b| !b.is_null()) {
+        request = match body.as_str() {
+            Some(text) => request.body(text.to_string()),
+            None => request.json(body),
+        };
+    }
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("Request to {} failed", url))?;
+    let status = response.status().as_u16();
+    let headers: serde_json::Map<String, Value> = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            Some((
+                name.to_string(),
+                Value::String(value.to_str().ok()?.to_string()),
+            ))
+        })
+        .collect();
+    let body = response.text().await?;
+    Ok(json!({ "status": status, "headers": headers, "body": body }))
+}
+
+/// The configuration a plugin may read: literal secrets are redacted as in a
+/// pack, and `$VAR` references are kept
+fn redacted_config(config: &crate::config::Config) -> Result<Value> {
+    let mut value = serde_json::to_value(config)?;
+    crate::pack::strip_secrets(&mut value, "", &mut Vec::new());
+    Ok(value)
+}
+
+/// A string parameter that must be given
+fn string<'a>(params: &'a Value, key: &str) -> Result<&'a str> {
+    params[key]
+        .as_str()
+        .ok_or_else(|| anyhow!("Missing string parameter {}", key))
+}
+
+/// A file name that stays inside the data directory
+fn data_file(file: &str) -> Result<&str> {
+    let path = Path::new(file);
+    if file.is_empty() || !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        bail!("{} is not a file in the plugin's data directory", file);
+    }
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::PluginManager;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    async fn invoke(manager: &PluginManager, action: &str) -> Value {
+        manager.invoke("notes", action, json!({})).await.unwrap()
+    }
+
+    #[test]
+    fn test_config_secrets_redacted() {
+        let config = crate::config::Config::parse(
+            r#"
+[backups.s3]
+region = "eu-west-1"
+secret_access_key = "wJalrXUtnFEMI"
+
+[api.providers.openai]
+api_key = "$OPENAI_API_KEY"
+"#,
+        )
+        .unwrap();
+        let value = redacted_config(&config).unwrap();
+        assert_eq!(
+            value["backups"]["s3"]["secret_access_key"],
+            crate::pack::REDACTED
+        );
+        assert_eq!(value["backups"]["s3"]["region"], "eu-west-1");
+        assert_eq!(
+            value["api"]["providers"]["openai"]["api_key"],
+            "$OPENAI_API_KEY"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_host_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let plugins_dir = tempfile::tempdir().unwrap();
+        let dir = plugins_dir.path().join("notes");
+        std::fs::create_dir_all(&dir).unwrap();
+        // Each action makes one host call and answers with its response
+        let script = r#"#!/bin/sh
+call() {
+  printf '{"jsonrpc":"2.0","id":"host","method":"%s","params":%s}\n' "$1" "$2"
+  IFS= read -r answer
+  printf '{"jsonrpc":"2.0","id":%s,"result":%s}\n' "$id" "$answer"
+}
+while IFS= read -r line; do
+  id=$(printf '%s' "$line" | sed -n 's/.*"id":\([0-9]*\).*/\1/p')
+  case "$line" in
+    *'"action":"save"'*) call data/write '{"file":"note.txt","content":"hi"}' ;;
+    *'"action":"escape"'*) call data/write '{"file":"../plugin.json","content":""}' ;;
+    *'"action":"run"'*) call shell/run '{"command":"echo","args":["ok"]}' ;;
+    *'"action":"fetch"'*) call http/fetch '{"url":"http://127.0.0.1:9"}' ;;
+    *'"method":"shutdown"'*) exit 0 ;;
+    *) printf '{"jsonrpc":"2.0","id":%s,"result":{}}\n' "$id" ;;
+  esac
+done
+"#;
+        std::fs::write(dir.join("main.sh"), script).unwrap();
+        std::fs::set_permissions(dir.join("main.sh"), std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+        let manifest = json!({
+            "id": "notes", "name": "Notes", "version": "1.0.0", "csm_version": "*",
+            "main": "main.sh", "kind": "process", "permissions": ["file_system", "shell"],
+            "hooks": [], "dependencies": [], "category": "other", "keywords": [],
+            "capabilities": [
+                { "action": "save" }, { "action": "escape" }, { "action": "run" },
+                { "action": "fetch" }
+            ]
+        });
+        std::fs::write(dir.join("plugin.json"), manifest.to_string()).unwrap();
+
+        let asked = Arc::new(AtomicUsize::new(0));
+        let answering = |allow: bool| -> Consent {
+            let asked = asked.clone();
+            Arc::new(move |_: &str, permission: &Permission| {
+                assert_eq!(*permission, Permission::Shell);
+                asked.fetch_add(1, Ordering::SeqCst);
+                allow
+            })
+        };
+        let start = |consent: Option<Consent>| async {
+            let manager =
+                PluginManager::new(plugins_dir.path().to_path_buf()).with_consent(consent);
+            manager.discover_plugins().await.unwrap();
+            // Shell is asked for when it is needed, not to start
+            manager.activate("notes").await.unwrap();
+            manager
+        };
+        let error = |response: Value| response["error"]["message"].as_str().unwrap().to_string();
+
+        let manager = start(None).await;
+        assert!(invoke(&manager, "save").await["error"].is_null());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("data/note.txt")).unwrap(),
+            "hi"
+        );
+        let err = error(invoke(&manager, "escape").await);
+        assert!(
+            err.contains("not a file in the plugin's data directory"),
+            "{}",
+            err
+        );
+        let err = error(invoke(&manager, "fetch").await);
+        assert!(
+            err.contains("did not declare the network permission"),
+            "{}",
+            err
+        );
+        // Without a way to ask, undecided permissions are not granted
+        let err = error(invoke(&manager, "run").await);
+        assert!(
+            err.contains("has not been granted the shell permission"),
+            "{}",
+            err
+        );
+        manager.deactivate("notes").await.unwrap();
+
+        // A refusal is kept and not asked again
+        let manager = start(Some(answering(false))).await;
+        for _ in 0..2 {
+            let err = error(invoke(&manager, "run").await);
+            assert!(err.contains("shell permission was refused"), "{}", err);
+        }
+        assert_eq!(asked.load(Ordering::SeqCst), 1);
+        let settings = SettingsFile::load(plugins_dir.path()).unwrap().get("notes");
+        assert_eq!(settings.denied, [Permission::Shell]);
+        manager.deactivate("notes").await.unwrap();
+
+        SettingsFile::update(plugins_dir.path(), "notes", |s| s.denied.clear()).unwrap();
+        let manager = start(Some(answering(true))).await;
+        for _ in 0..2 {
+            let response = invoke(&manager, "run").await;
+            assert_eq!(response["result"]["stdout"], "ok\n");
+        }
+        assert_eq!(asked.load(Ordering::SeqCst), 2);
+        let settings = SettingsFile::load(plugins_dir.path()).unwrap().get("notes");
+        assert_eq!(
+            settings.granted,
+            Some(vec![Permission::FileSystem, Permission::Shell])
+        );
+        manager.deactivate("notes").await.unwrap();
+    }
+}
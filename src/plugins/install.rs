@@ -0,0 +1,214 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Installing plugins
+//!
+//! A plugin is installed from a directory holding its `plugin.json`, from the
+//! path of a `plugin.json`, or from the URL of one. From a URL, the manifest
+//! and its `main` program are downloaded; a plugin that needs more files than
+//! that is installed from a directory.
+
+use super::PluginManifest;
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::{Component, Path, PathBuf};
+
+/// Where a plugin is installed from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginSource {
+    Dir(PathBuf),
+    /// URL of a `plugin.json`
+    Url(String),
+}
+
+impl PluginSource {
+    /// A directory, `plugin.json` path, or URL. URLs that do not end in
+    /// `.json` are taken to be the directory the manifest is in.
+    pub fn parse(source: &str) -> Self {
+        if source.starts_with("http://") || source.starts_with("https://") {
+            if source.ends_with(".json") {
+                return Self::Url(source.to_string());
+            }
+            return Self::Url(format!("{}/plugin.json", source.trim_end_matches('/')));
+        }
+        let path = crate::config::expand_home(Path::new(source));
+        match path.parent() {
+            Some(dir) if path.is_file() => Self::Dir(dir.to_path_buf()),
+            _ => Self::Dir(path),
+        }
+    }
+
+    /// The plugin's files, downloaded to a temporary directory if needed
+    pub fn fetch(&self) -> Result<Staged> {
+        match self {
+            Self::Dir(dir) => {
+                let manifest = read_manifest(dir)?;
+                let main = dir.join(safe_path(&manifest.main)?);
+                if !main.is_file() {
+                    bail!("The plugin's main program {} is missing", main.display());
+                }
+                Ok(Staged {
+                    dir: dir.clone(),
+                    manifest,
+                    temporary: false,
+                })
+            }
+            Self::Url(url) => download(url),
+        }
+    }
+}
+
+impl std::fmt::Display for PluginSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Dir(dir) => write!(f, "{}", dir.display()),
+            Self::Url(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+/// A plugin's files, ready to be copied into the plugins directory
+pub struct Staged {
+    pub dir: PathBuf,
+    pub manifest: PluginManifest,
+    /// Whether `dir` was made for the download and is removed on drop
+    temporary: bool,
+}
+
+impl Staged {
+    /// Files unpacked into `dir` for the install, removed on drop
+    pub(super) fn temporary(dir: PathBuf, manifest: PluginManifest) -> Self {
+        Self {
+            dir,
+            manifest,
+            temporary: true,
+        }
+    }
+}
+
+impl Drop for Staged {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+/// The manifest in a plugin's directory
+pub fn read_manifest(dir: &Path) -> Result<PluginManifest> {
+    let path = dir.join("plugin.json");
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("No plugin.json in {}", dir.display()))?;
+    serde_json::from_str(&text).with_context(|| format!("Invalid {}", path.display()))
+}
+
+/// `path` if it stays inside the plugin's directory
+fn safe_path(path: &str) -> Result<&Path> {
+    let relative = Path::new(path);
+    if path.is_empty()
+        || !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        bail!(
+            "The plugin's main program must be inside its directory, not '{}'",
+            path
+        );
+    }
+    Ok(relative)
+}
+
+fn download(url: &str) -> Result<Staged> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(concat!("csm/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let get = |url: &reqwest::Url| -> Result<Vec<u8>> {
+        let response = client
+            .get(url.clone())
+            .send()
+            .with_context(|| format!("Failed to download {}", url))?;
+        if !response.status().is_success() {
+            bail!("Failed to download {}: {}", url, response.status());
+        }
+        Ok(response.bytes()?.to_vec())
+    };
+
+    let manifest_url = reqwest::Url::parse(url).with_context(|| format!("Invalid URL {}", url))?;
+    let text = get(&manifest_url)?;
+    let manifest: PluginManifest =
+        serde_json::from_slice(&text).with_context(|| format!("Invalid plugin.json at {}", url))?;
+    let main = safe_path(&manifest.main)?.to_path_buf();
+    let main_url = manifest_url
+        .join(&manifest.main)
+        .map_err(|e| anyhow!("Invalid main program '{}': {}", manifest.main, e))?;
+    let program = get(&main_url)?;
+
+    let staged = Staged::temporary(
+        std::env::temp_dir().join(format!("csm-plugin-{}", uuid::Uuid::new_v4())),
+        manifest,
+    );
+    let main = staged.dir.join(main);
+    std::fs::create_dir_all(main.parent().unwrap_or(&staged.dir))?;
+    std::fs::write(staged.dir.join("plugin.json"), &text)?;
+    std::fs::write(&main, program)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&main, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(staged)
+}
+
+/// Copy the files of `from` into `to`, leaving `to/data` alone
+pub fn copy_plugin(from: &Path, to: &Path) -> Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if name == "data" || name == ".git" {
+            continue;
+        }
+        copy_all(&entry.path(), &to.join(name))?;
+    }
+    Ok(())
+}
+
+fn copy_all(from: &Path, to: &Path) -> Result<()> {
+    if from.is_dir() {
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_all(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        std::fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_source() {
+        assert_eq!(
+            PluginSource::parse("https://example.com/plugins/summaries/"),
+            PluginSource::Url("https://example.com/plugins/summaries/plugin.json".to_string())
+        );
+        assert_eq!(
+            PluginSource::parse("https://example.com/summaries.json"),
+            PluginSource::Url("https://example.com/summaries.json".to_string())
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = dir.path().join("plugin.json");
+        std::fs::write(&manifest, "{}").unwrap();
+        let source = PluginSource::parse(manifest.to_str().unwrap());
+        assert_eq!(source, PluginSource::Dir(dir.path().to_path_buf()));
+        // Not a manifest
+        assert!(source.fetch().is_err());
+
+        assert!(safe_path("bin/run.py").is_ok());
+        assert!(safe_path("../run.py").is_err());
+        assert!(safe_path("/usr/bin/python3").is_err());
+    }
+}
@@ -9,14 +9,46 @@
 //! - Configuration management
 //! - Sandboxed execution
 
+pub mod host;
+pub mod install;
+pub mod process;
+pub mod registry;
+pub mod schema;
+pub mod settings;
+
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::process::Stdio;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
 use tokio::sync::RwLock;
 
+use crate::config::Config;
+use host::{Consent, Host};
+use install::Staged;
+use process::{LogSink, PluginProcess};
+pub use registry::{PluginRegistry, RegistryEntry};
+use settings::SettingsFile;
+
+/// How long a plugin may take to perform an action
+const INVOKE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How long a process plugin may take to answer `initialize`
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a process plugin may take to answer a health check
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a process plugin has to exit after `shutdown`
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+
+/// Restarts of a process plugin before it is left in the error state
+const MAX_RESTARTS: u32 = 5;
+
 // =============================================================================
 // Plugin Manifest and Metadata
 // =============================================================================
@@ -44,6 +76,9 @@ pub struct PluginManifest {
     pub csm_version: String,
     /// Plugin entry point
     pub main: String,
+    /// How `main` is run
+    #[serde(default)]
+    pub kind: PluginKind,
     /// Required permissions
     pub permissions: Vec<Permission>,
     /// Event hooks this plugin registers
@@ -56,6 +91,46 @@ pub struct PluginManifest {
     pub category: PluginCategory,
     /// Keywords for discovery
     pub keywords: Vec<String>,
+    /// Actions the plugin can be asked to perform
+    #[serde(default)]
+    pub capabilities: Vec<PluginCapability>,
+    /// Chat provider the plugin adds (see [`crate::providers::plugin`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<PluginProviderInfo>,
+}
+
+/// How a plugin's `main` program is run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginKind {
+    /// Run once for each action
+    #[default]
+    Command,
+    /// Started when the plugin is activated and kept running, answering
+    /// JSON-RPC requests on stdin (see [`process`])
+    Process,
+}
+
+/// An action a plugin performs on request, such as `summarize`. A command
+/// plugin's `main` program is run with `{"action", "arguments", "config"}` on
+/// stdin and answers with JSON on stdout; a process plugin gets the same as
+/// the parameters of an `invoke` request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginCapability {
+    /// Action name
+    pub action: String,
+    /// What the action does
+    pub description: Option<String>,
+    /// JSON Schema of the arguments
+    pub input_schema: Option<serde_json::Value>,
+}
+
+/// A chat provider added by a plugin, which answers the provider actions
+/// `list_sessions`, `import_session`, and optionally `discover`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginProviderInfo {
+    /// Name shown in provider lists
+    pub name: String,
 }
 
 /// Plugin author information
@@ -127,6 +202,47 @@ pub enum Permission {
     Notifications,
 }
 
+impl Permission {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SessionRead => "session_read",
+            Self::SessionWrite => "session_write",
+            Self::SessionDelete => "session_delete",
+            Self::ConfigRead => "config_read",
+            Self::ConfigWrite => "config_write",
+            Self::Network => "network",
+            Self::FileSystem => "file_system",
+            Self::Shell => "shell",
+            Self::Sensitive => "sensitive",
+            Self::Background => "background",
+            Self::Notifications => "notifications",
+        }
+    }
+
+    /// What the permission lets a plugin do
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::SessionRead => "read chat sessions",
+            Self::SessionWrite => "change chat sessions",
+            Self::SessionDelete => "delete chat sessions",
+            Self::ConfigRead => "read csm configuration",
+            Self::ConfigWrite => "change csm configuration",
+            Self::Network => "use the network",
+            Self::FileSystem => "read and write files in its data directory",
+            Self::Shell => "run shell commands",
+            Self::Sensitive => "read encryption keys and other secrets",
+            Self::Background => "run in the background",
+            Self::Notifications => "show notifications",
+        }
+    }
+
+    /// Whether the user is asked before the permission is granted, since it
+    /// lets a plugin run commands or read secrets
+    pub fn needs_confirmation(&self) -> bool {
+        matches!(self, Self::Shell | Self::Sensitive)
+    }
+}
+
 // =============================================================================
 // Plugin Instance and State
 // =============================================================================
@@ -162,6 +278,9 @@ pub struct PluginInstance {
     pub last_activated: Option<DateTime<Utc>>,
     /// Plugin configuration
     pub config: serde_json::Value,
+    /// Permissions the plugin runs with
+    #[serde(default)]
+    pub granted: Vec<Permission>,
     /// Error message if in error state
     pub error: Option<String>,
     /// Usage statistics
@@ -179,6 +298,9 @@ pub struct PluginStats {
     pub error_count: u64,
     /// Last error timestamp
     pub last_error: Option<DateTime<Utc>>,
+    /// Times a process plugin was restarted after exiting
+    #[serde(default)]
+    pub restart_count: u64,
 }
 
 // =============================================================================
@@ -293,6 +415,14 @@ impl PluginContext {
 // Plugin Manager
 // =============================================================================
 
+/// The process of an active process plugin
+struct ProcessSlot {
+    /// `None` after a restart failed
+    process: Option<Arc<PluginProcess>>,
+    /// Restarts since the plugin was activated
+    restarts: u32,
+}
+
 /// Plugin manager handles plugin lifecycle
 pub struct PluginManager {
     /// Plugins directory
@@ -303,6 +433,13 @@ pub struct PluginManager {
     hooks: Arc<RwLock<Vec<HookRegistration>>>,
     /// Plugin configurations
     configs: Arc<RwLock<HashMap<String, serde_json::Value>>>,
+    /// Processes of active process plugins
+    processes: Arc<RwLock<HashMap<String, ProcessSlot>>>,
+    /// Asked about permissions process plugins need but were not granted
+    consent: Option<Consent>,
+    asking: Arc<tokio::sync::Mutex<()>>,
+    /// Where what plugins write to stderr goes
+    log: LogSink,
 }
 
 impl PluginManager {
@@ -313,9 +450,38 @@ impl PluginManager {
             plugins: Arc::new(RwLock::new(HashMap::new())),
             hooks: Arc::new(RwLock::new(Vec::new())),
             configs: Arc::new(RwLock::new(HashMap::new())),
+            processes: Arc::new(RwLock::new(HashMap::new())),
+            consent: None,
+            asking: Arc::default(),
+            log: Arc::new(|plugin_id, line| log::info!("[plugin {}] {}", plugin_id, line)),
         }
     }
 
+    /// Pass what plugins write to stderr to `log` rather than the log
+    pub fn with_log(mut self, log: LogSink) -> Self {
+        self.log = log;
+        self
+    }
+
+    /// Ask `consent` when a process plugin first needs a permission it
+    /// declares but was not granted; without it such calls fail
+    pub fn with_consent(mut self, consent: Option<Consent>) -> Self {
+        self.consent = consent;
+        self
+    }
+
+    /// Directory the plugins are installed in
+    pub fn dir(&self) -> &std::path::Path {
+        &self.plugins_dir
+    }
+
+    /// The plugins directory next to `config.toml`
+    pub fn default_dir() -> Result<PathBuf> {
+        let config_path = Config::path()?;
+        let dir = config_path.parent().unwrap_or(std::path::Path::new("."));
+        Ok(dir.join("plugins"))
+    }
+
     /// Initialize the plugin manager
     pub async fn init(&self) -> Result<()> {
         std::fs::create_dir_all(&self.plugins_dir)?;
@@ -363,13 +529,22 @@ impl PluginManager {
         // Check dependencies
         self.check_dependencies(&manifest).await?;
         
+        let settings = SettingsFile::load(&self.plugins_dir)?.get(&manifest.id);
         let instance = PluginInstance {
             manifest: manifest.clone(),
-            state: PluginState::Loaded,
+            state: if settings.enabled {
+                PluginState::Loaded
+            } else {
+                PluginState::Disabled
+            },
             path: plugin_path.clone(),
-            installed_at: Utc::now(),
+            installed_at: settings.installed_at.unwrap_or_else(Utc::now),
             last_activated: None,
-            config: serde_json::Value::Object(serde_json::Map::new()),
+            config: match settings.config {
+                serde_json::Value::Null => serde_json::Value::Object(serde_json::Map::new()),
+                ref config => config.clone(),
+            },
+            granted: settings.permissions(&manifest.permissions),
             error: None,
             stats: PluginStats::default(),
         };
@@ -406,6 +581,14 @@ impl PluginManager {
                 current_version
             ));
         }
+
+        if manifest.provider.is_some() {
+            for action in crate::providers::plugin::REQUIRED_ACTIONS {
+                if !manifest.capabilities.iter().any(|c| c.action == *action) {
+                    return Err(anyhow!("A provider plugin must declare the {} action", action));
+                }
+            }
+        }
         
         Ok(())
     }
@@ -440,8 +623,65 @@ impl PluginManager {
         Ok(())
     }
 
-    /// Activate a plugin
+    /// Activate a plugin, starting its process if it is a process plugin
     pub async fn activate(&self, plugin_id: &str) -> Result<()> {
+        let kind = {
+            let plugins = self.plugins.read().await;
+            let plugin = plugins
+                .get(plugin_id)
+                .ok_or_else(|| anyhow!("Plugin not found: {}", plugin_id))?;
+            if plugin.state == PluginState::Active {
+                return Ok(());
+            }
+            // Process plugins are asked for them when they first need them
+            let missing = match plugin.manifest.kind {
+                PluginKind::Command => plugin
+                    .manifest
+                    .permissions
+                    .iter()
+                    .find(|p| !plugin.granted.contains(p)),
+                PluginKind::Process => None,
+            };
+            if let Some(missing) = missing {
+                return Err(anyhow!(
+                    "Plugin {} needs the {} permission; grant it with `csm plugin enable {}`",
+                    plugin_id,
+                    missing.name(),
+                    plugin_id
+                ));
+            }
+            if let Some(schema) = &plugin.manifest.config_schema {
+                self.validate_config(&plugin.config, schema, false)
+                    .map_err(|e| {
+                        anyhow!(
+                            "Plugin {} is not configured: {}; change it with `csm plugin config {} --set KEY=VALUE`",
+                            plugin_id,
+                            e,
+                            plugin_id
+                        )
+                    })?;
+            }
+            plugin.manifest.kind
+        };
+        if kind == PluginKind::Process {
+            match self.start_process(plugin_id).await {
+                Ok(process) => {
+                    let slot = ProcessSlot {
+                        process: Some(process),
+                        restarts: 0,
+                    };
+                    self.processes
+                        .write()
+                        .await
+                        .insert(plugin_id.to_string(), slot);
+                }
+                Err(e) => {
+                    self.set_error(plugin_id, &e).await;
+                    return Err(e);
+                }
+            }
+        }
+
         let mut plugins = self.plugins.write().await;
         let plugin = plugins.get_mut(plugin_id)
             .ok_or_else(|| anyhow!("Plugin not found: {}", plugin_id))?;
@@ -456,6 +696,7 @@ impl PluginManager {
         }
         
         plugin.state = PluginState::Active;
+        plugin.error = None;
         plugin.last_activated = Some(Utc::now());
         plugin.stats.activation_count += 1;
         
@@ -473,11 +714,247 @@ impl PluginManager {
         self.unregister_hooks(plugin_id).await?;
         
         plugin.state = PluginState::Disabled;
+        drop(plugins);
+
+        let slot = self.processes.write().await.remove(plugin_id);
+        if let Some(process) = slot.and_then(|s| s.process) {
+            process.shutdown(SHUTDOWN_GRACE).await;
+        }
         
         log::info!("Deactivated plugin: {}", plugin_id);
         Ok(())
     }
 
+    /// Start a process plugin and complete the `initialize` handshake
+    async fn start_process(&self, plugin_id: &str) -> Result<Arc<PluginProcess>> {
+        let (main, dir, config) = {
+            let plugins = self.plugins.read().await;
+            let plugin = plugins
+                .get(plugin_id)
+                .ok_or_else(|| anyhow!("Plugin not found: {}", plugin_id))?;
+            (
+                plugin.path.join(&plugin.manifest.main),
+                plugin.path.clone(),
+                plugin.config.clone(),
+            )
+        };
+
+        let host = self.host(plugin_id);
+        let process = PluginProcess::spawn(
+            plugin_id,
+            &main,
+            &dir,
+            Arc::new(move |method: &str, params| host.call_blocking(method, params)),
+            self.log.clone(),
+        )?;
+        let params = serde_json::json!({
+            "plugin_id": plugin_id,
+            "config": config,
+            "csm_version": env!("CARGO_PKG_VERSION"),
+        });
+        process
+            .request("initialize", params, HANDSHAKE_TIMEOUT)
+            .await
+            .map_err(|e| anyhow!("Handshake failed: {}", e))?;
+        Ok(Arc::new(process))
+    }
+
+    /// The process of an active process plugin, restarted if it has exited.
+    /// A plugin that has been restarted too often is put in the error state.
+    async fn running_process(&self, plugin_id: &str) -> Result<Arc<PluginProcess>> {
+        let mut processes = self.processes.write().await;
+        let slot = processes
+            .get_mut(plugin_id)
+            .ok_or_else(|| anyhow!("Plugin {} is not running", plugin_id))?;
+        if let Some(process) = slot.process.as_ref().filter(|p| p.is_running()) {
+            return Ok(process.clone());
+        }
+
+        if slot.restarts >= MAX_RESTARTS {
+            let e = anyhow!(
+                "Plugin {} stopped after {} restarts; activate it again to retry",
+                plugin_id,
+                slot.restarts
+            );
+            processes.remove(plugin_id);
+            drop(processes);
+            self.set_error(plugin_id, &e).await;
+            return Err(e);
+        }
+        slot.restarts += 1;
+        log::warn!(
+            "Plugin {} exited; restarting it ({} of {})",
+            plugin_id,
+            slot.restarts,
+            MAX_RESTARTS
+        );
+        if let Some(plugin) = self.plugins.write().await.get_mut(plugin_id) {
+            plugin.stats.restart_count += 1;
+        }
+        let started = self.start_process(plugin_id).await;
+        slot.process = started.as_ref().ok().cloned();
+        started
+    }
+
+    /// Ping every running process plugin, restarting the ones that have
+    /// exited or do not answer. Returns each plugin's ID and whether it is
+    /// up now.
+    pub async fn check_health(&self) -> Vec<(String, Result<()>)> {
+        let processes: Vec<(String, Option<Arc<PluginProcess>>)> = self
+            .processes
+            .read()
+            .await
+            .iter()
+            .map(|(id, slot)| (id.clone(), slot.process.clone()))
+            .collect();
+
+        let mut health = Vec::new();
+        for (plugin_id, process) in processes {
+            let answered = match &process {
+                Some(process) => process
+                    .request("ping", serde_json::Value::Null, HEALTH_TIMEOUT)
+                    .await
+                    .is_ok(),
+                None => false,
+            };
+            let outcome = if answered {
+                Ok(())
+            } else {
+                if let Some(process) = process {
+                    process.kill();
+                }
+                self.running_process(&plugin_id).await.map(|_| ())
+            };
+            health.push((plugin_id, outcome));
+        }
+        health
+    }
+
+    fn host(&self, plugin_id: &str) -> Host {
+        Host {
+            plugin_id: plugin_id.to_string(),
+            plugins_dir: self.plugins_dir.clone(),
+            plugins: self.plugins.clone(),
+            consent: self.consent.clone(),
+            asking: self.asking.clone(),
+        }
+    }
+
+    /// Fail unless a plugin may use `permission`: it must declare it, and a
+    /// process plugin not granted it yet is granted it if the user agrees
+    pub async fn authorize(&self, plugin_id: &str, permission: &Permission) -> Result<()> {
+        self.host(plugin_id).authorize(permission).await
+    }
+
+    /// Put a plugin in the error state
+    async fn set_error(&self, plugin_id: &str, error: &anyhow::Error) {
+        if let Some(plugin) = self.plugins.write().await.get_mut(plugin_id) {
+            plugin.state = PluginState::Error;
+            plugin.error = Some(error.to_string());
+            plugin.stats.error_count += 1;
+            plugin.stats.last_error = Some(Utc::now());
+        }
+    }
+
+    /// Copy a fetched plugin into the plugins directory, record where it came
+    /// from and the permissions granted to it, and load it. A plugin that is
+    /// already installed is only replaced with `force`, keeping its data and
+    /// configuration.
+    pub async fn install(
+        &self,
+        staged: &Staged,
+        source: &str,
+        granted: Vec<Permission>,
+        force: bool,
+    ) -> Result<String> {
+        let manifest = &staged.manifest;
+        self.validate_manifest(manifest)?;
+        if manifest.id.starts_with('.')
+            || !manifest
+                .id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        {
+            return Err(anyhow!(
+                "Plugin ID '{}' may only use letters, digits, '-', '_', and '.'",
+                manifest.id
+            ));
+        }
+
+        let dir = self.plugins_dir.join(&manifest.id);
+        if dir.exists() {
+            if !force {
+                return Err(anyhow!(
+                    "Plugin {} is already installed; use --force to replace it",
+                    manifest.id
+                ));
+            }
+            self.deactivate(&manifest.id).await.ok();
+            for entry in std::fs::read_dir(&dir)? {
+                let entry = entry?;
+                if entry.file_name() == "data" {
+                    continue;
+                }
+                if entry.path().is_dir() {
+                    std::fs::remove_dir_all(entry.path())?;
+                } else {
+                    std::fs::remove_file(entry.path())?;
+                }
+            }
+        }
+        install::copy_plugin(&staged.dir, &dir)?;
+
+        SettingsFile::update(&self.plugins_dir, &manifest.id, |settings| {
+            settings.enabled = true;
+            settings.granted = Some(granted);
+            settings.denied.clear();
+            settings.source = Some(source.to_string());
+            settings.installed_at = Some(Utc::now());
+        })?;
+        self.load_plugin(&dir).await
+    }
+
+    /// Grant a plugin permissions, mark it enabled, and activate it
+    pub async fn enable(&self, plugin_id: &str, granted: Vec<Permission>) -> Result<()> {
+        {
+            let mut plugins = self.plugins.write().await;
+            let plugin = plugins
+                .get_mut(plugin_id)
+                .ok_or_else(|| anyhow!("Plugin not found: {}", plugin_id))?;
+            plugin.granted = granted.clone();
+            if plugin.state != PluginState::Active {
+                plugin.state = PluginState::Loaded;
+            }
+        }
+        SettingsFile::update(&self.plugins_dir, plugin_id, |settings| {
+            settings.enabled = true;
+            settings.granted = Some(granted);
+            settings.denied.clear();
+        })?;
+        self.activate(plugin_id).await
+    }
+
+    /// Stop a plugin, load it again from `plugin_path`, and activate it,
+    /// registering its hooks again. Returns its ID, which may have changed.
+    pub async fn reload(&self, plugin_id: &str, plugin_path: &std::path::Path) -> Result<String> {
+        if self.plugins.read().await.contains_key(plugin_id) {
+            self.deactivate(plugin_id).await?;
+            self.plugins.write().await.remove(plugin_id);
+        }
+        let id = self.load_plugin(&plugin_path.to_path_buf()).await?;
+        self.activate(&id).await?;
+        Ok(id)
+    }
+
+    /// Deactivate a plugin and keep it disabled
+    pub async fn disable(&self, plugin_id: &str) -> Result<()> {
+        self.deactivate(plugin_id).await?;
+        SettingsFile::update(&self.plugins_dir, plugin_id, |settings| {
+            settings.enabled = false;
+        })?;
+        Ok(())
+    }
+
     /// Uninstall a plugin
     pub async fn uninstall(&self, plugin_id: &str) -> Result<()> {
         // Deactivate first
@@ -491,6 +968,10 @@ impl PluginManager {
         if plugin.path.exists() {
             std::fs::remove_dir_all(&plugin.path)?;
         }
+        let mut settings = SettingsFile::load(&self.plugins_dir)?;
+        if settings.plugins.remove(plugin_id).is_some() {
+            settings.save(&self.plugins_dir)?;
+        }
         
         log::info!("Uninstalled plugin: {}", plugin_id);
         Ok(())
@@ -601,7 +1082,7 @@ impl PluginManager {
             .map(|p| p.config.clone())
     }
 
-    /// Set plugin configuration
+    /// Set plugin configuration and save it
     pub async fn set_config(&self, plugin_id: &str, config: serde_json::Value) -> Result<()> {
         let mut plugins = self.plugins.write().await;
         let plugin = plugins.get_mut(plugin_id)
@@ -609,18 +1090,93 @@ impl PluginManager {
         
         // Validate against schema if present
         if let Some(schema) = &plugin.manifest.config_schema {
-            self.validate_config(&config, schema)?;
+            self.validate_config(&config, schema, true)
+                .map_err(|e| anyhow!("Invalid configuration of {}: {}", plugin_id, e))?;
         }
         
-        plugin.config = config;
+        plugin.config = config.clone();
+        SettingsFile::update(&self.plugins_dir, plugin_id, |settings| {
+            settings.config = config;
+        })?;
         Ok(())
     }
 
-    fn validate_config(&self, _config: &serde_json::Value, _schema: &serde_json::Value) -> Result<()> {
-        // In a real implementation, use JSON Schema validation
+    /// Check a configuration against the plugin's `config_schema`. With
+    /// `partial`, settings the schema requires may still be missing, so they
+    /// can be set one at a time.
+    fn validate_config(
+        &self,
+        config: &serde_json::Value,
+        schema: &serde_json::Value,
+        partial: bool,
+    ) -> Result<()> {
+        let errors: Vec<String> = schema::validate(schema, config)
+            .into_iter()
+            .filter(|e| !(partial && e.keyword == "required" && e.path.is_empty()))
+            .map(|e| e.to_string())
+            .collect();
+        if !errors.is_empty() {
+            return Err(anyhow!("{}", errors.join("; ")));
+        }
         Ok(())
     }
 
+    /// Ask an active plugin to perform one of its declared actions, returning
+    /// the JSON it prints (or its output as a string if it is not JSON)
+    pub async fn invoke(
+        &self,
+        plugin_id: &str,
+        action: &str,
+        arguments: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let (kind, main, dir, request) = {
+            let plugins = self.plugins.read().await;
+            let plugin = plugins
+                .get(plugin_id)
+                .ok_or_else(|| anyhow!("Plugin not found: {}", plugin_id))?;
+            if plugin.state != PluginState::Active {
+                return Err(anyhow!("Plugin {} is not active", plugin_id));
+            }
+            if !plugin
+                .manifest
+                .capabilities
+                .iter()
+                .any(|c| c.action == action)
+            {
+                return Err(anyhow!("Plugin {} has no action {}", plugin_id, action));
+            }
+            let request = serde_json::json!({
+                "action": action,
+                "arguments": arguments,
+                "config": plugin.config,
+            });
+            (
+                plugin.manifest.kind,
+                plugin.path.join(&plugin.manifest.main),
+                plugin.path.clone(),
+                request,
+            )
+        };
+
+        let start = std::time::Instant::now();
+        let result = match kind {
+            PluginKind::Command => run_plugin(plugin_id, &main, &dir, &request, &self.log).await,
+            PluginKind::Process => match self.running_process(plugin_id).await {
+                Ok(process) => process.request("invoke", request, INVOKE_TIMEOUT).await,
+                Err(e) => Err(e),
+            },
+        };
+
+        if let Some(plugin) = self.plugins.write().await.get_mut(plugin_id) {
+            plugin.stats.total_execution_ms += start.elapsed().as_millis() as u64;
+            if result.is_err() {
+                plugin.stats.error_count += 1;
+                plugin.stats.last_error = Some(Utc::now());
+            }
+        }
+        result
+    }
+
     /// Create plugin context
     pub async fn create_context(&self, plugin_id: &str) -> Result<PluginContext> {
         let plugins = self.plugins.read().await;
@@ -629,81 +1185,69 @@ impl PluginManager {
         
         Ok(PluginContext {
             plugin_id: plugin_id.to_string(),
-            permissions: plugin.manifest.permissions.clone(),
+            permissions: plugin.granted.clone(),
             data_dir: plugin.path.join("data"),
             config: plugin.config.clone(),
         })
     }
 }
 
-// =============================================================================
-// Plugin Registry (for discovery/installation)
-// =============================================================================
+/// Run a plugin's `main` program with `request` on stdin, passing what it
+/// writes to stderr to `log` when it succeeds
+async fn run_plugin(
+    plugin_id: &str,
+    main: &std::path::Path,
+    dir: &std::path::Path,
+    request: &serde_json::Value,
+    log: &LogSink,
+) -> Result<serde_json::Value> {
+    let mut child = tokio::process::Command::new(main)
+        .current_dir(dir)
+        .env("CSM_PLUGIN_ID", plugin_id)
+        .env("CSM_PLUGIN_DATA", dir.join("data"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| {
+            anyhow!(
+                "Failed to start plugin {} ({}): {}",
+                plugin_id,
+                main.display(),
+                e
+            )
+        })?;
 
-/// Plugin registry entry
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RegistryEntry {
-    /// Plugin ID
-    pub id: String,
-    /// Latest version
-    pub version: String,
-    /// Plugin name
-    pub name: String,
-    /// Description
-    pub description: String,
-    /// Author
-    pub author: String,
-    /// Download URL
-    pub download_url: String,
-    /// Download count
-    pub downloads: u64,
-    /// Rating (0-5)
-    pub rating: f32,
-    /// Category
-    pub category: PluginCategory,
-    /// Keywords
-    pub keywords: Vec<String>,
-    /// Updated at
-    pub updated_at: DateTime<Utc>,
-}
-
-/// Plugin registry client
-pub struct PluginRegistry {
-    /// Registry URL
-    registry_url: String,
-}
-
-impl PluginRegistry {
-    pub fn new(registry_url: String) -> Self {
-        Self { registry_url }
-    }
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    stdin.write_all(request.to_string().as_bytes()).await?;
+    drop(stdin);
 
-    /// Search for plugins
-    pub async fn search(&self, _query: &str, _category: Option<PluginCategory>) -> Result<Vec<RegistryEntry>> {
-        // In a real implementation, this would make an HTTP request
-        // For now, return empty results
-        Ok(Vec::new())
+    let output = tokio::time::timeout(INVOKE_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| {
+            anyhow!(
+                "Plugin {} timed out after {}s",
+                plugin_id,
+                INVOKE_TIMEOUT.as_secs()
+            )
+        })??;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!(
+            "Plugin {} failed ({}): {}",
+            plugin_id,
+            output.status,
+            stderr.trim()
+        ));
     }
 
-    /// Get plugin details
-    pub async fn get_plugin(&self, plugin_id: &str) -> Result<RegistryEntry> {
-        Err(anyhow!("Plugin not found in registry: {}", plugin_id))
-    }
-
-    /// Download and install plugin
-    pub async fn install(&self, plugin_id: &str, manager: &PluginManager) -> Result<()> {
-        let _entry = self.get_plugin(plugin_id).await?;
-        
-        // Download plugin
-        let plugin_dir = manager.plugins_dir.join(plugin_id);
-        std::fs::create_dir_all(&plugin_dir)?;
-        
-        // In a real implementation, download and extract the plugin
-        // For now, just create the directory
-        
-        manager.load_plugin(&plugin_dir).await?;
-        Ok(())
+    for line in String::from_utf8_lossy(&output.stderr).lines() {
+        log(plugin_id, line);
     }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(serde_json::from_str(&stdout)
+        .unwrap_or_else(|_| serde_json::Value::String(stdout.trim().to_string())))
 }
 
 #[cfg(test)]
@@ -719,6 +1263,174 @@ mod tests {
         assert!(manager.init().await.is_ok());
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_process_plugin() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path().join("counter");
+        std::fs::create_dir_all(&dir).unwrap();
+        // Answers with its PID, so restarts show
+        let script = r#"#!/bin/sh
+while IFS= read -r line; do
+  id=$(printf '%s' "$line" | sed -n 's/.*"id":\([0-9]*\).*/\1/p')
+  case "$line" in
+    *'"action":"crash"'*) exit 1 ;;
+    *'"action":"pid"'*) printf '{"jsonrpc":"2.0","id":%s,"result":%s}\n' "$id" "$$" ;;
+    *'"method":"invoke"'*)
+      printf '{"jsonrpc":"2.0","id":%s,"error":{"code":-32601,"message":"no such action"}}\n' "$id" ;;
+    *'"method":"shutdown"'*) exit 0 ;;
+    *) printf '{"jsonrpc":"2.0","id":%s,"result":{}}\n' "$id" ;;
+  esac
+done
+"#;
+        std::fs::write(dir.join("main.sh"), script).unwrap();
+        std::fs::set_permissions(dir.join("main.sh"), std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+        let manifest = serde_json::json!({
+            "id": "counter", "name": "Counter", "version": "1.0.0", "csm_version": "*",
+            "main": "main.sh", "kind": "process", "permissions": [], "hooks": [],
+            "dependencies": [], "category": "other", "keywords": [],
+            "capabilities": [{ "action": "pid" }, { "action": "crash" }, { "action": "other" }]
+        });
+        std::fs::write(dir.join("plugin.json"), manifest.to_string()).unwrap();
+
+        let manager = PluginManager::new(temp_dir.path().to_path_buf());
+        assert_eq!(manager.discover_plugins().await.unwrap(), ["counter"]);
+        manager.activate("counter").await.unwrap();
+
+        // One process serves every request
+        let invoke = |action| manager.invoke("counter", action, serde_json::json!({}));
+        let pid = invoke("pid").await.unwrap();
+        assert!(pid.is_u64());
+        assert_eq!(invoke("pid").await.unwrap(), pid);
+        let err = invoke("other").await.unwrap_err();
+        assert!(err.to_string().contains("no such action"), "{}", err);
+        assert!(manager.check_health().await[0].1.is_ok());
+
+        // A crashed process is started again
+        assert!(invoke("crash").await.is_err());
+        let restarted = invoke("pid").await.unwrap();
+        assert_ne!(restarted, pid);
+        let plugin = manager.get_plugin("counter").await.unwrap();
+        assert_eq!(plugin.stats.restart_count, 1);
+
+        manager.deactivate("counter").await.unwrap();
+        assert!(manager.check_health().await.is_empty());
+        assert!(invoke("pid").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reload() {
+        let source = tempdir().unwrap();
+        let write_manifest = |version: &str, hooks: serde_json::Value| {
+            let manifest = serde_json::json!({
+                "id": "dev", "name": "Dev", "version": version, "csm_version": "*",
+                "main": "run.sh", "permissions": [], "hooks": hooks,
+                "dependencies": [], "category": "other", "keywords": []
+            });
+            std::fs::write(source.path().join("plugin.json"), manifest.to_string()).unwrap();
+        };
+        write_manifest("1.0.0", serde_json::json!(["session.*"]));
+
+        let plugins_dir = tempdir().unwrap();
+        let manager = PluginManager::new(plugins_dir.path().to_path_buf());
+        assert_eq!(manager.reload("dev", source.path()).await.unwrap(), "dev");
+        assert_eq!(manager.hooks.read().await.len(), 1);
+
+        // Hooks are registered again rather than added to
+        write_manifest("1.1.0", serde_json::json!(["session.*", "harvest.completed"]));
+        manager.reload("dev", source.path()).await.unwrap();
+        let plugin = manager.get_plugin("dev").await.unwrap();
+        assert_eq!(plugin.manifest.version, "1.1.0");
+        assert_eq!(plugin.state, PluginState::Active);
+        let hooks: Vec<String> = manager
+            .hooks
+            .read()
+            .await
+            .iter()
+            .map(|h| h.event_pattern.clone())
+            .collect();
+        assert_eq!(hooks, ["session.*", "harvest.completed"]);
+
+        std::fs::write(source.path().join("plugin.json"), "{").unwrap();
+        assert!(manager.reload("dev", source.path()).await.is_err());
+        assert!(manager.get_plugin("dev").await.is_none());
+        assert!(manager.hooks.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_install_and_settings() {
+        let source = tempdir().unwrap();
+        let manifest = serde_json::json!({
+            "id": "cleanup", "name": "Cleanup", "version": "1.0.0", "csm_version": "*",
+            "main": "run.sh", "permissions": ["session_read", "shell"], "hooks": [],
+            "dependencies": [], "category": "other", "keywords": [],
+            "config_schema": {
+                "properties": { "limit": { "type": "integer" } },
+                "required": ["limit"],
+                "additionalProperties": false
+            }
+        });
+        std::fs::write(source.path().join("plugin.json"), manifest.to_string()).unwrap();
+        std::fs::write(source.path().join("run.sh"), "#!/bin/sh\n").unwrap();
+        let staged = install::PluginSource::Dir(source.path().to_path_buf())
+            .fetch()
+            .unwrap();
+
+        let plugins_dir = tempdir().unwrap();
+        let manager = PluginManager::new(plugins_dir.path().to_path_buf());
+        let read = vec![Permission::SessionRead];
+        let id = manager
+            .install(&staged, "local", read.clone(), false)
+            .await
+            .unwrap();
+        assert!(plugins_dir.path().join("cleanup/run.sh").exists());
+        assert!(manager.install(&staged, "local", read, false).await.is_err());
+
+        // Shell was not granted
+        let err = manager.activate(&id).await.unwrap_err();
+        assert!(err.to_string().contains("shell permission"), "{}", err);
+        let all = vec![Permission::SessionRead, Permission::Shell];
+        // Nor has the setting the schema requires
+        let err = manager.enable(&id, all.clone()).await.unwrap_err();
+        assert!(err.to_string().contains("missing required setting 'limit'"), "{}", err);
+
+        manager
+            .set_config(&id, serde_json::json!({ "limit": 5 }))
+            .await
+            .unwrap();
+        let err = manager
+            .set_config(&id, serde_json::json!({ "limit": "five" }))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().ends_with("/limit: must be of type integer"), "{}", err);
+        assert!(manager
+            .set_config(&id, serde_json::json!({ "other": 1 }))
+            .await
+            .is_err());
+        manager.enable(&id, all.clone()).await.unwrap();
+        assert_eq!(
+            manager.get_plugin(&id).await.unwrap().state,
+            PluginState::Active
+        );
+        manager.disable(&id).await.unwrap();
+
+        // What was decided survives a restart
+        let manager = PluginManager::new(plugins_dir.path().to_path_buf());
+        manager.discover_plugins().await.unwrap();
+        let plugin = manager.get_plugin(&id).await.unwrap();
+        assert_eq!(plugin.state, PluginState::Disabled);
+        assert_eq!(plugin.config["limit"], 5);
+        assert_eq!(plugin.granted, all);
+
+        manager.uninstall(&id).await.unwrap();
+        assert!(!plugins_dir.path().join("cleanup").exists());
+        let settings = settings::SettingsFile::load(plugins_dir.path()).unwrap();
+        assert!(settings.plugins.is_empty());
+    }
+
     #[test]
     fn test_event_name() {
         let manager = PluginManager::new(PathBuf::from("."));
@@ -0,0 +1,244 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Long-running plugin processes
+//!
+//! A plugin with `"kind": "process"` is started once and kept running. It
+//! reads JSON-RPC 2.0 requests from stdin and writes responses to stdout,
+//! one message per line, as MCP servers do over stdio:
+//!
+//! - `initialize` with `{"plugin_id", "config", "csm_version"}`, sent once
+//!   when the plugin starts
+//! - `invoke` with `{"action", "arguments", "config"}`, answered with the
+//!   action's result
+//! - `ping`, answered with anything, to check the plugin is responsive
+//! - `shutdown`, after which the plugin should exit
+//!
+//! Requests the plugin writes to stdout, rather than responses, are calls
+//! to the host API in [`super::host`]; their responses are written to the
+//! plugin's stdin.
+//!
+//! What the plugin writes to stderr goes to a [`LogSink`]. The pipes are served by
+//! threads rather than tasks, so a process outlives the runtime that
+//! started it.
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// Replies the plugin owes, by request ID
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value, String>>>>>;
+
+/// JSON-RPC error code of failed host API calls
+const HOST_ERROR: i64 = -32000;
+
+/// Answers the plugin's requests to the host, given their method and params
+pub type HostHandler = Arc<dyn Fn(&str, Value) -> anyhow::Result<Value> + Send + Sync>;
+
+/// Receives each line a plugin writes to stderr, after the plugin's ID
+pub type LogSink = Arc<dyn Fn(&str, &str) + Send + Sync>;
+
+/// A running plugin process
+pub struct PluginProcess {
+    plugin_id: String,
+    child: Mutex<Child>,
+    stdin: Arc<Mutex<ChildStdin>>,
+    pending: Pending,
+    /// Cleared when the plugin closes its stdout
+    alive: Arc<AtomicBool>,
+    next_id: AtomicU64,
+}
+
+impl PluginProcess {
+    /// Start `main` in `dir` and serve its pipes, answering its requests
+    /// with `host` and passing what it writes to stderr to `log`
+    pub fn spawn(
+        plugin_id: &str,
+        main: &Path,
+        dir: &Path,
+        host: HostHandler,
+        log: LogSink,
+    ) -> Result<Self> {
+        let mut child = Command::new(main)
+            .current_dir(dir)
+            .env("CSM_PLUGIN_ID", plugin_id)
+            .env("CSM_PLUGIN_DATA", dir.join("data"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                anyhow!(
+                    "Failed to start plugin {} ({}): {}",
+                    plugin_id,
+                    main.display(),
+                    e
+                )
+            })?;
+        let stdin = Arc::new(Mutex::new(child.stdin.take().expect("stdin is piped")));
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+
+        let pending: Pending = Arc::default();
+        let alive = Arc::new(AtomicBool::new(true));
+        {
+            let (plugin_id, pending, alive, stdin) = (
+                plugin_id.to_string(),
+                pending.clone(),
+                alive.clone(),
+                stdin.clone(),
+            );
+            std::thread::spawn(move || {
+                for line in BufReader::new(stdout).lines() {
+                    let Ok(line) = line else { break };
+                    if !line.trim().is_empty() {
+                        dispatch(&plugin_id, &line, &pending, &host, &stdin);
+                    }
+                }
+                alive.store(false, Ordering::SeqCst);
+                // Nothing more will answer what is still waiting
+                for (_, reply) in pending.lock().unwrap().drain() {
+                    let _ = reply.send(Err("the plugin exited".to_string()));
+                }
+            });
+        }
+        {
+            let plugin_id = plugin_id.to_string();
+            std::thread::spawn(move || {
+                for line in BufReader::new(stderr).lines().map_while(|l| l.ok()) {
+                    log(&plugin_id, &line);
+                }
+            });
+        }
+
+        Ok(Self {
+            plugin_id: plugin_id.to_string(),
+            child: Mutex::new(child),
+            stdin,
+            pending,
+            alive,
+            next_id: AtomicU64::new(1),
+        })
+    }
+
+    /// Whether the process is still running and reading requests
+    pub fn is_running(&self) -> bool {
+        self.alive.load(Ordering::SeqCst)
+            && matches!(self.child.lock().unwrap().try_wait(), Ok(None))
+    }
+
+    /// Send a request and wait up to `timeout` for its result
+    pub async fn request(&self, method: &str, params: Value, timeout: Duration) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (reply, answer) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, reply);
+
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let written = {
+            let mut stdin = self.stdin.lock().unwrap();
+            writeln!(stdin, "{}", message).and_then(|_| stdin.flush())
+        };
+        if let Err(e) = written {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(anyhow!("Plugin {} is not running: {}", self.plugin_id, e));
+        }
+
+        match tokio::time::timeout(timeout, answer).await {
+            Ok(Ok(Ok(result))) => Ok(result),
+            Ok(Ok(Err(e))) => Err(anyhow!("Plugin {}: {}", self.plugin_id, e)),
+            Ok(Err(_)) => Err(anyhow!("Plugin {} exited", self.plugin_id)),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id);
+                Err(anyhow!(
+                    "Plugin {} did not answer {} within {}s",
+                    self.plugin_id,
+                    method,
+                    timeout.as_secs()
+                ))
+            }
+        }
+    }
+
+    /// Ask the plugin to exit, and stop it if it has not within `grace`
+    pub async fn shutdown(&self, grace: Duration) {
+        if self.is_running() {
+            let _ = self.request("shutdown", Value::Null, grace).await;
+        }
+        let deadline = std::time::Instant::now() + grace;
+        while self.is_running() && std::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        self.kill();
+    }
+
+    /// Stop the process now
+    pub fn kill(&self) {
+        let mut child = self.child.lock().unwrap();
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+impl Drop for PluginProcess {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+/// Hand a response from the plugin to the request waiting for it, or
+/// answer a request from the plugin on a thread of its own
+fn dispatch(
+    plugin_id: &str,
+    line: &str,
+    pending: &Pending,
+    host: &HostHandler,
+    stdin: &Arc<Mutex<ChildStdin>>,
+) {
+    let message: Value = match serde_json::from_str(line) {
+        Ok(message) => message,
+        Err(_) => {
+            log::warn!("[plugin {}] Not JSON-RPC: {}", plugin_id, line);
+            return;
+        }
+    };
+    if let (Some(method), Some(id)) = (message["method"].as_str(), message.get("id")) {
+        let (method, id, params) = (method.to_string(), id.clone(), message["params"].clone());
+        let (plugin_id, host, stdin) = (plugin_id.to_string(), host.clone(), stdin.clone());
+        std::thread::spawn(move || {
+            let response = match host(&method, params) {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                Err(e) => {
+                    log::warn!("[plugin {}] {} failed: {}", plugin_id, method, e);
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": HOST_ERROR, "message": e.to_string() },
+                    })
+                }
+            };
+            let mut stdin = stdin.lock().unwrap();
+            let _ = writeln!(stdin, "{}", response).and_then(|_| stdin.flush());
+        });
+        return;
+    }
+    let Some(id) = message["id"].as_u64() else {
+        log::debug!("[plugin {}] Ignoring {}", plugin_id, line);
+        return;
+    };
+    let Some(reply) = pending.lock().unwrap().remove(&id) else {
+        return;
+    };
+    let result = match message.get("error") {
+        Some(error) => Err(error["message"]
+            .as_str()
+            .map_or_else(|| error.to_string(), str::to_string)),
+        None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+    };
+    let _ = reply.send(result);
+}
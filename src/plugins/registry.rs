@@ -0,0 +1,877 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Plugin registry client
+//!
+//! A registry is an HTTP service describing plugins as [`RegistryEntry`]s:
+//!
+//! - `GET <registry>/plugins?q=<query>&category=<category>` lists the
+//!   plugins matching a search
+//! - `GET <registry>/plugins/<id>` describes one plugin
+//!
+//! An entry points at a `.tar.gz` of the plugin's directory, with the SHA-256
+//! digest of the tarball and an Ed25519 signature of it. A download whose
+//! digest differs is rejected, and so is one not signed by a key in
+//! `[plugins] trusted_keys`; unsigned plugins are only installed with
+//! `allow_unsigned = true`. The plugins an entry depends on are installed
+//! with it, unless an installed version already satisfies them.
+
+use super::install::{self, Staged};
+use super::{PluginCategory, PluginDependency, PluginManager, PluginManifest};
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+/// Prefix of the install source recorded for plugins from the registry
+const SOURCE_PREFIX: &str = "registry:";
+
+/// Plugin registry entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryEntry {
+    /// Plugin ID
+    pub id: String,
+    /// Latest version
+    pub version: String,
+    /// Plugin name
+    pub name: String,
+    /// Description
+    #[serde(default)]
+    pub description: String,
+    /// Author
+    #[serde(default)]
+    pub author: String,
+    /// URL of the tarball, absolute or relative to the registry
+    pub download_url: String,
+    /// SHA-256 of the tarball, hex
+    pub sha256: String,
+    /// Ed25519 signature of the tarball, base64
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Plugins this version needs
+    #[serde(default)]
+    pub dependencies: Vec<PluginDependency>,
+    /// What changed in each version, newest first
+    #[serde(default)]
+    pub releases: Vec<RegistryRelease>,
+    /// Download count
+    #[serde(default)]
+    pub downloads: u64,
+    /// Rating (0-5)
+    #[serde(default)]
+    pub rating: f32,
+    /// Category
+    pub category: PluginCategory,
+    /// Keywords
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Updated at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// One version of a plugin in the registry's changelog
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryRelease {
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<DateTime<Utc>>,
+    /// What changed
+    #[serde(default)]
+    pub notes: String,
+}
+
+impl RegistryEntry {
+    /// Releases after `installed` up to this one, newest first
+    pub fn changes_since(&self, installed: &str) -> Vec<&RegistryRelease> {
+        let (Ok(installed), Ok(latest)) = (
+            semver::Version::parse(installed),
+            semver::Version::parse(&self.version),
+        ) else {
+            return Vec::new();
+        };
+        let mut releases: Vec<(semver::Version, &RegistryRelease)> = self
+            .releases
+            .iter()
+            .filter_map(|r| Some((semver::Version::parse(&r.version).ok()?, r)))
+            .filter(|(v, _)| *v > installed && *v <= latest)
+            .collect();
+        releases.sort_by(|a, b| b.0.cmp(&a.0));
+        releases.into_iter().map(|(_, r)| r).collect()
+    }
+
+    /// Whether this version is newer than `installed`
+    pub fn is_newer_than(&self, installed: &str) -> bool {
+        match (
+            semver::Version::parse(&self.version),
+            semver::Version::parse(installed),
+        ) {
+            (Ok(latest), Ok(installed)) => latest > installed,
+            _ => false,
+        }
+    }
+}
+
+/// The install source recorded for a plugin installed from the registry
+pub fn source(plugin_id: &str) -> String {
+    format!("{}{}", SOURCE_PREFIX, plugin_id)
+}
+
+/// Whether an install source is the registry
+pub fn is_registry_source(source: &str) -> bool {
+    source.starts_with(SOURCE_PREFIX)
+}
+
+/// Whether `name` can be a plugin ID rather than a path
+pub fn is_plugin_id(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Plugin registry client
+pub struct PluginRegistry {
+    /// Registry URL
+    registry_url: String,
+    /// Ed25519 public keys, base64, whose signatures are accepted
+    trusted_keys: Vec<String>,
+    allow_unsigned: bool,
+    client: reqwest::Client,
+}
+
+impl PluginRegistry {
+    pub fn new(registry_url: String) -> Self {
+        Self {
+            registry_url: registry_url.trim_end_matches('/').to_string(),
+            trusted_keys: Vec::new(),
+            allow_unsigned: false,
+            client: reqwest::Client::builder()
+                .user_agent(concat!("csm/", env!("CARGO_PKG_VERSION")))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The registry and keys set under `[plugins]` in config.toml
+    pub fn from_config() -> Result<Self> {
+        let config = &crate::config::current().plugins;
+        let url = config.registry.clone().context(
+            "No plugin registry is configured; set `registry` under [plugins] in config.toml",
+        )?;
+        Ok(Self::new(url).trust(
+            config.trusted_keys.clone(),
+            config.allow_unsigned.unwrap_or(false),
+        ))
+    }
+
+    /// Accept signatures by `keys`, and unsigned plugins if `allow_unsigned`
+    pub fn trust(mut self, keys: Vec<String>, allow_unsigned: bool) -> Self {
+        self.trusted_keys = keys;
+        self.allow_unsigned = allow_unsigned;
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.registry_url
+    }
+
+    async fn get(&self, url: &str) -> Result<reqwest::Response> {
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach the plugin registry at {}", url))?;
+        Ok(response)
+    }
+
+    /// Search for plugins
+    pub async fn search(
+        &self,
+        query: &str,
+        category: Option<PluginCategory>,
+    ) -> Result<Vec<RegistryEntry>> {
+        let mut url = reqwest::Url::parse(&format!("{}/plugins", self.registry_url))
+            .with_context(|| format!("Invalid registry URL {}", self.registry_url))?;
+        url.query_pairs_mut().append_pair("q", query);
+        if let Some(category) = category {
+            let category = serde_json::to_value(category)?;
+            url.query_pairs_mut()
+                .append_pair("category", category.as_str().unwrap_or_default());
+        }
+        let response = self.get(url.as_str()).await?;
+        if !response.status().is_success() {
+            bail!("Plugin registry search failed: {}", response.status());
+        }
+        response
+            .json()
+            .await
+            .context("The plugin registry sent an invalid search result")
+    }
+
+    /// Get plugin details
+    pub async fn get_plugin(&self, plugin_id: &str) -> Result<RegistryEntry> {
+        if !is_plugin_id(plugin_id) {
+            bail!("Invalid plugin ID '{}'", plugin_id);
+        }
+        let url = format!("{}/plugins/{}", self.registry_url, plugin_id);
+        let response = self.get(&url).await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            bail!("Plugin not found in registry: {}", plugin_id);
+        }
+        if !response.status().is_success() {
+            bail!(
+                "Plugin registry lookup of {} failed: {}",
+                plugin_id,
+                response.status()
+            );
+        }
+        response.json().await.with_context(|| {
+            format!(
+                "The plugin registry sent an invalid entry for {}",
+                plugin_id
+            )
+        })
+    }
+
+    /// Download a plugin's tarball, check it, and unpack it
+    pub async fn download(&self, entry: &RegistryEntry) -> Result<Staged> {
+        let base = reqwest::Url::parse(&format!("{}/", self.registry_url))
+            .with_context(|| format!("Invalid registry URL {}", self.registry_url))?;
+        let url = base
+            .join(&entry.download_url)
+            .with_context(|| format!("Invalid download URL {}", entry.download_url))?;
+        let response = self.get(url.as_str()).await?;
+        if !response.status().is_success() {
+            bail!("Failed to download {}: {}", url, response.status());
+        }
+        let tarball = response.bytes().await?;
+        verify(entry, &tarball, &self.trusted_keys, self.allow_unsigned)?;
+
+        let dir = unpack_plugin(&tarball)?;
+        let manifest = match install::read_manifest(&dir) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                let _ = std::fs::remove_dir_all(&dir);
+                return Err(e);
+            }
+        };
+        // Removed again when dropped
+        let staged = Staged::temporary(dir, manifest);
+        if staged.manifest.id != entry.id || staged.manifest.version != entry.version {
+            bail!(
+                "The registry's {} {} contains {} {}",
+                entry.id,
+                entry.version,
+                staged.manifest.id,
+                staged.manifest.version
+            );
+        }
+        Ok(staged)
+    }
+
+    /// The registry entries to install for `plugin_id`, dependencies before
+    /// the plugins needing them. `installed` maps installed plugin IDs to
+    /// their versions.
+    pub async fn resolve(
+        &self,
+        plugin_id: &str,
+        installed: &HashMap<String, String>,
+    ) -> Result<Vec<RegistryEntry>> {
+        let mut entries = HashMap::new();
+        let mut queue = vec![plugin_id.to_string()];
+        while let Some(id) = queue.pop() {
+            if entries.contains_key(&id) {
+                continue;
+            }
+            let entry = self.get_plugin(&id).await?;
+            for dep in needed_dependencies(&entry, installed)? {
+                queue.push(dep.id.clone());
+            }
+            entries.insert(id, entry);
+        }
+        let order = install_order(plugin_id, &entries, installed)?;
+        Ok(order
+            .into_iter()
+            .filter_map(|id| entries.remove(&id))
+            .collect())
+    }
+
+    /// Download and install plugin and the plugins it depends on. `grant`
+    /// decides the permissions of each; plugins that are already installed
+    /// are replaced. Returns the IDs installed, dependencies first.
+    pub async fn install(
+        &self,
+        plugin_id: &str,
+        manager: &PluginManager,
+        mut grant: impl FnMut(&PluginManifest) -> Result<Vec<super::Permission>>,
+    ) -> Result<Vec<String>> {
+        let installed = installed_versions(manager).await;
+        let mut ids = Vec::new();
+        for entry in self.resolve(plugin_id, &installed).await? {
+            let staged = self.download(&entry).await?;
+            let granted = grant(&staged.manifest)?;
+            ids.push(
+                manager
+                    .install(&staged, &source(&entry.id), granted, true)
+                    .await?,
+            );
+        }
+        Ok(ids)
+    }
+}
+
+/// Versions of the plugins `manager` has loaded, by ID
+pub async fn installed_versions(manager: &PluginManager) -> HashMap<String, String> {
+    manager
+        .list_plugins()
+        .await
+        .into_iter()
+        .map(|p| (p.manifest.id, p.manifest.version))
+        .collect()
+}
+
+/// The dependencies of `entry` that have to be installed: required ones no
+/// installed version satisfies
+fn needed_dependencies<'a>(
+    entry: &'a RegistryEntry,
+    installed: &HashMap<String, String>,
+) -> Result<Vec<&'a PluginDependency>> {
+    let mut needed = Vec::new();
+    for dep in entry.dependencies.iter().filter(|d| !d.optional) {
+        let req = semver::VersionReq::parse(&dep.version).with_context(|| {
+            format!(
+                "{} has an invalid requirement on {}: {}",
+                entry.id, dep.id, dep.version
+            )
+        })?;
+        let satisfied = installed
+            .get(&dep.id)
+            .and_then(|v| semver::Version::parse(v).ok())
+            .is_some_and(|v| req.matches(&v));
+        if !satisfied {
+            needed.push(dep);
+        }
+    }
+    Ok(needed)
+}
+
+/// The order to install `root` and the dependencies it needs in, dependencies
+/// first. Fails on dependency cycles and on requirements the registry's
+/// versions do not meet.
+fn install_order(
+    root: &str,
+    entries: &HashMap<String, RegistryEntry>,
+    installed: &HashMap<String, String>,
+) -> Result<Vec<String>> {
+    fn visit(
+        id: &str,
+        entries: &HashMap<String, RegistryEntry>,
+        installed: &HashMap<String, String>,
+        path: &mut Vec<String>,
+        order: &mut Vec<String>,
+    ) -> Result<()> {
+        if order.iter().any(|done| done == id) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|p| p == id) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(id.to_string());
+            bail!("Plugins depend on each other: {}", cycle.join(" -> "));
+        }
+        let entry = entries
+            .get(id)
+            .ok_or_else(|| anyhow!("Plugin not found in registry: {}", id))?;
+        path.push(id.to_string());
+        for dep in needed_dependencies(entry, installed)? {
+            let available = entries
+                .get(&dep.id)
+                .ok_or_else(|| anyhow!("Plugin not found in registry: {}", dep.id))?;
+            let req = semver::VersionReq::parse(&dep.version)?;
+            let version = semver::Version::parse(&available.version)?;
+            if !req.matches(&version) {
+                bail!(
+                    "{} needs {} {}, but the registry has {}",
+                    id,
+                    dep.id,
+                    dep.version,
+                    available.version
+                );
+            }
+            visit(&dep.id, entries, installed, path, order)?;
+        }
+        path.pop();
+        order.push(id.to_string());
+        Ok(())
+    }
+
+    let mut order = Vec::new();
+    visit(root, entries, installed, &mut Vec::new(), &mut order)?;
+    Ok(order)
+}
+
+/// Check a downloaded tarball against the digest and signature of its entry
+pub fn verify(
+    entry: &RegistryEntry,
+    tarball: &[u8],
+    trusted_keys: &[String],
+    allow_unsigned: bool,
+) -> Result<()> {
+    let digest = format!("{:x}", Sha256::digest(tarball));
+    if !digest.eq_ignore_ascii_case(entry.sha256.trim()) {
+        bail!(
+            "The download of {} {} is corrupt: its SHA-256 is {}, not {}",
+            entry.id,
+            entry.version,
+            digest,
+            entry.sha256
+        );
+    }
+
+    let Some(signature) = &entry.signature else {
+        if allow_unsigned {
+            return Ok(());
+        }
+        bail!(
+            "{} {} is not signed; set `allow_unsigned = true` under [plugins] to install it anyway",
+            entry.id,
+            entry.version
+        );
+    };
+    let signature = BASE64
+        .decode(signature.trim())
+        .with_context(|| format!("{} has an invalid signature", entry.id))?;
+    if trusted_keys.is_empty() {
+        bail!(
+            "{} is signed, but no key is trusted; add the registry's key to `trusted_keys` under [plugins]",
+            entry.id
+        );
+    }
+    for key in trusted_keys {
+        let key = BASE64
+            .decode(key.trim())
+            .with_context(|| format!("Invalid trusted key {}", key))?;
+        let key = ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, key);
+        if key.verify(tarball, &signature).is_ok() {
+            return Ok(());
+        }
+    }
+    bail!(
+        "The signature of {} {} was not made by a trusted key",
+        entry.id,
+        entry.version
+    )
+}
+
+/// Unpack a gzip-compressed tar archive into `dest`. Only files and
+/// directories are unpacked; links and devices are skipped.
+pub fn unpack(tarball: &[u8], dest: &Path) -> Result<()> {
+    let mut data = Vec::new();
+    GzDecoder::new(tarball)
+        .read_to_end(&mut data)
+        .context("The plugin download is not a .tar.gz")?;
+    std::fs::create_dir_all(dest)?;
+
+    let mut offset = 0;
+    let mut long_name: Option<String> = None;
+    while offset + 512 <= data.len() {
+        let header = &data[offset..offset + 512];
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        let size = octal(&header[124..136])? as usize;
+        let start = offset + 512;
+        let body = data
+            .get(start..start + size)
+            .context("The plugin archive is truncated")?;
+        offset = start + size.div_ceil(512) * 512;
+
+        let name = match long_name.take() {
+            Some(name) => name,
+            None => {
+                let name = text(&header[..100]);
+                let prefix = text(&header[345..500]);
+                if &header[257..262] == b"ustar" && !prefix.is_empty() {
+                    format!("{}/{}", prefix, name)
+                } else {
+                    name
+                }
+            }
+        };
+        match header[156] {
+            // GNU long name, or pax header, naming the next entry
+            b'L' => long_name = Some(text(body)),
+            b'x' => long_name = pax_path(body),
+            b'g' => {}
+            b'0' | b'7' | 0 | b'5' => {
+                let relative = Path::new(&name);
+                if !relative
+                    .components()
+                    .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+                {
+                    bail!(
+                        "The plugin archive has an entry outside its directory: {}",
+                        name
+                    );
+                }
+                let path = dest.join(relative);
+                if header[156] == b'5' {
+                    std::fs::create_dir_all(&path)?;
+                    continue;
+                }
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&path, body)?;
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    let mode = octal(&header[100..108]).unwrap_or(0o644) as u32;
+                    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode & 0o755))?;
+                }
+            }
+            kind => log::debug!("Skipping {} (entry type {}) in plugin archive", name, kind),
+        }
+    }
+    Ok(())
+}
+
+/// A NUL-terminated header field
+fn text(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// An octal number header field
+fn octal(field: &[u8]) -> Result<u64> {
+    let digits = text(field);
+    let digits = digits.trim_matches(|c: char| c == ' ' || c == '\0');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(digits, 8).map_err(|_| anyhow!("The plugin archive is corrupt"))
+}
+
+/// The `path` record of a pax extended header
+fn pax_path(body: &[u8]) -> Option<String> {
+    text(body).lines().find_map(|record| {
+        let (_, field) = record.split_once(' ')?;
+        field.strip_prefix("path=").map(str::to_string)
+    })
+}
+
+/// Unpack a plugin tarball into a new temporary directory with its
+/// `plugin.json` at the top, unwrapping the single directory tarballs usually
+/// put their files in
+fn unpack_plugin(tarball: &[u8]) -> Result<PathBuf> {
+    let temp_dir = || std::env::temp_dir().join(format!("csm-plugin-{}", uuid::Uuid::new_v4()));
+    let dir = temp_dir();
+    let unpacked = unpack(tarball, &dir).and_then(|_| {
+        if dir.join("plugin.json").is_file() {
+            return Ok(dir.clone());
+        }
+        let entries: Vec<PathBuf> = std::fs::read_dir(&dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .collect();
+        match entries.as_slice() {
+            [only] if only.join("plugin.json").is_file() => {
+                let unwrapped = temp_dir();
+                std::fs::rename(only, &unwrapped)?;
+                let _ = std::fs::remove_dir_all(&dir);
+                Ok(unwrapped)
+            }
+            _ => bail!("The plugin archive has no plugin.json"),
+        }
+    });
+    if unpacked.is_err() {
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    unpacked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// A .tar.gz of `files`, names and contents
+    fn tarball(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut tar = Vec::new();
+        for (name, content) in files {
+            let mut header = [0u8; 512];
+            header[..name.len()].copy_from_slice(name.as_bytes());
+            header[100..107].copy_from_slice(b"0000755");
+            header[124..135].copy_from_slice(format!("{:011o}", content.len()).as_bytes());
+            header[156] = b'0';
+            header[257..263].copy_from_slice(b"ustar\0");
+            tar.extend_from_slice(&header);
+            tar.extend_from_slice(content.as_bytes());
+            tar.resize(tar.len().div_ceil(512) * 512, 0);
+        }
+        tar.extend_from_slice(&[0; 1024]);
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&tar).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn manifest(id: &str, version: &str, dependencies: serde_json::Value) -> String {
+        serde_json::json!({
+            "id": id, "name": id, "version": version, "csm_version": "*",
+            "main": "run.sh", "permissions": [], "hooks": [],
+            "dependencies": dependencies, "category": "other", "keywords": []
+        })
+        .to_string()
+    }
+
+    fn entry(id: &str, version: &str, dependencies: &[(&str, &str)]) -> RegistryEntry {
+        serde_json::from_value(serde_json::json!({
+            "id": id, "version": version, "name": id,
+            "download_url": format!("files/{}.tar.gz", id), "sha256": "",
+            "category": "other",
+            "dependencies": dependencies.iter().map(|(id, version)| serde_json::json!({
+                "id": id, "version": version, "optional": false
+            })).collect::<Vec<_>>()
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_verify_and_unpack() {
+        let plugin = manifest("cleanup", "1.0.0", serde_json::json!([]));
+        let tarball = tarball(&[
+            ("cleanup/plugin.json", &plugin),
+            ("cleanup/run.sh", "#!/bin/sh\n"),
+        ]);
+        let mut entry = entry("cleanup", "1.0.0", &[]);
+        entry.sha256 = format!("{:X}", Sha256::digest(&tarball));
+
+        // Unsigned plugins need allow_unsigned
+        let err = verify(&entry, &tarball, &[], false).unwrap_err();
+        assert!(err.to_string().contains("not signed"), "{}", err);
+        verify(&entry, &tarball, &[], true).unwrap();
+        let err = verify(&entry, b"tampered", &[], true).unwrap_err();
+        assert!(err.to_string().contains("corrupt"), "{}", err);
+
+        let rng = ring::rand::SystemRandom::new();
+        let keys: Vec<Ed25519KeyPair> = (0..2)
+            .map(|_| {
+                let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+                Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+            })
+            .collect();
+        let public = |key: &Ed25519KeyPair| BASE64.encode(key.public_key().as_ref());
+        entry.signature = Some(BASE64.encode(keys[0].sign(&tarball).as_ref()));
+        verify(
+            &entry,
+            &tarball,
+            &[public(&keys[1]), public(&keys[0])],
+            false,
+        )
+        .unwrap();
+        let err = verify(&entry, &tarball, &[public(&keys[1])], true).unwrap_err();
+        assert!(
+            err.to_string().contains("not made by a trusted key"),
+            "{}",
+            err
+        );
+        assert!(verify(&entry, &tarball, &[], true).is_err());
+
+        // The top-level directory is unwrapped
+        let dir = unpack_plugin(&tarball).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("plugin.json")).unwrap(),
+            plugin
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.join("run.sh"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o755);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let dest = tempfile::tempdir().unwrap();
+        let evil = self::tarball(&[("plugin.json", &plugin), ("../evil.sh", "")]);
+        let err = unpack(&evil, dest.path()).unwrap_err();
+        assert!(err.to_string().contains("outside its directory"), "{}", err);
+        assert!(unpack_plugin(&self::tarball(&[("run.sh", "")])).is_err());
+    }
+
+    #[test]
+    fn test_install_order() {
+        let index = |entries: Vec<RegistryEntry>| -> HashMap<String, RegistryEntry> {
+            entries.into_iter().map(|e| (e.id.clone(), e)).collect()
+        };
+        let entries = index(vec![
+            entry("app", "2.0.0", &[("lib", "^1.2"), ("util", "*")]),
+            entry("lib", "1.3.0", &[("util", ">=0.5")]),
+            entry("util", "0.9.0", &[]),
+        ]);
+        let none = HashMap::new();
+        assert_eq!(
+            install_order("app", &entries, &none).unwrap(),
+            ["util", "lib", "app"]
+        );
+        // Installed versions that satisfy a requirement are kept
+        let installed = HashMap::from([("lib".to_string(), "1.2.5".to_string())]);
+        assert_eq!(
+            install_order("app", &entries, &installed).unwrap(),
+            ["util", "app"]
+        );
+
+        let stale = index(vec![
+            entry("app", "2.0.0", &[("lib", "^2")]),
+            entry("lib", "1.3.0", &[]),
+        ]);
+        let err = install_order("app", &stale, &none).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "app needs lib ^2, but the registry has 1.3.0"
+        );
+
+        let cycle = index(vec![
+            entry("app", "1.0.0", &[("lib", "*")]),
+            entry("lib", "1.0.0", &[("app", "*")]),
+        ]);
+        let err = install_order("app", &cycle, &none).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Plugins depend on each other: app -> lib -> app"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_registry_install() {
+        let app = tarball(&[
+            (
+                "plugin.json",
+                &manifest(
+                    "app",
+                    "1.1.0",
+                    serde_json::json!([{ "id": "lib", "version": "^1", "optional": false }]),
+                ),
+            ),
+            ("run.sh", "#!/bin/sh\n"),
+        ]);
+        let lib = tarball(&[
+            (
+                "lib/plugin.json",
+                &manifest("lib", "1.0.0", serde_json::json!([])),
+            ),
+            ("lib/run.sh", "#!/bin/sh\n"),
+        ]);
+        let mut app_entry = entry("app", "1.1.0", &[("lib", "^1")]);
+        app_entry.sha256 = format!("{:x}", Sha256::digest(&app));
+        app_entry.releases = vec![
+            RegistryRelease {
+                version: "1.0.0".into(),
+                date: None,
+                notes: "First".into(),
+            },
+            RegistryRelease {
+                version: "1.1.0".into(),
+                date: None,
+                notes: "Faster".into(),
+            },
+        ];
+        let mut lib_entry = entry("lib", "1.0.0", &[]);
+        lib_entry.sha256 = format!("{:x}", Sha256::digest(&lib));
+        let routes: HashMap<String, Vec<u8>> = HashMap::from([
+            (
+                "/plugins/app".to_string(),
+                serde_json::to_vec(&app_entry).unwrap(),
+            ),
+            (
+                "/plugins/lib".to_string(),
+                serde_json::to_vec(&lib_entry).unwrap(),
+            ),
+            (
+                "/plugins?q=ap&category=other".to_string(),
+                serde_json::to_vec(&[&app_entry]).unwrap(),
+            ),
+            ("/files/app.tar.gz".to_string(), app),
+            ("/files/lib.tar.gz".to_string(), lib),
+        ]);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut reader = BufReader::new(stream.unwrap());
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let path = request.split(' ').nth(1).unwrap_or_default();
+                let (status, body) =
+                    match path.strip_prefix("/registry").and_then(|p| routes.get(p)) {
+                        Some(body) => ("200 OK", body.clone()),
+                        None => ("404 Not Found", Vec::new()),
+                    };
+                let stream = reader.get_mut();
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                )
+                .unwrap();
+                stream.write_all(&body).unwrap();
+            }
+        });
+
+        let registry = PluginRegistry::new(format!("{}/registry/", url));
+        let found = registry
+            .search("ap", Some(PluginCategory::Other))
+            .await
+            .unwrap();
+        assert_eq!(found[0].id, "app");
+        let err = registry.get_plugin("missing").await.unwrap_err();
+        assert_eq!(err.to_string(), "Plugin not found in registry: missing");
+
+        let plugins_dir = tempfile::tempdir().unwrap();
+        let manager = PluginManager::new(plugins_dir.path().to_path_buf());
+        // Unsigned plugins are refused by default
+        assert!(registry
+            .install("app", &manager, |m| Ok(m.permissions.clone()))
+            .await
+            .is_err());
+
+        let registry = registry.trust(Vec::new(), true);
+        let mut granted = Vec::new();
+        let ids = registry
+            .install("app", &manager, |m| {
+                granted.push(m.id.clone());
+                Ok(m.permissions.clone())
+            })
+            .await
+            .unwrap();
+        assert_eq!(ids, ["lib", "app"]);
+        assert_eq!(granted, ids);
+        assert!(plugins_dir.path().join("lib/run.sh").exists());
+        let versions = installed_versions(&manager).await;
+        assert_eq!(versions["app"], "1.1.0");
+
+        let releases = app_entry.changes_since("1.0.0");
+        assert_eq!(releases.len(), 1);
+        assert_eq!(releases[0].notes, "Faster");
+        assert!(!app_entry.is_newer_than("1.1.0"));
+    }
+}
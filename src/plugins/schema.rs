@@ -0,0 +1,534 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Checking plugin configuration against its JSON Schema
+//!
+//! Covers the validation keywords of JSON Schema draft 2020-12 that config
+//! schemas use: `type`, `enum`, `const`, the number, string, array, and
+//! object keywords, `allOf` / `anyOf` / `oneOf` / `not`, `if` / `then` /
+//! `else`, and `$ref` to a definition in the same schema. Annotations such as
+//! `description`, `default`, and `format`, and keywords that are not known,
+//! are ignored, as the specification allows. Each error names the value it
+//! is about by its JSON Pointer, such as `/servers/0/port`.
+
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// How deep `$ref`s may nest before the schema is taken to be circular
+const MAX_DEPTH: usize = 64;
+
+/// A value that does not match the schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaError {
+    /// JSON Pointer to the value, empty for the whole configuration
+    pub path: String,
+    /// Keyword the value failed
+    pub keyword: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        write!(f, "{}: {}", path, self.message)
+    }
+}
+
+/// Every way `value` fails to match `schema`
+pub fn validate(schema: &Value, value: &Value) -> Vec<SchemaError> {
+    let mut validator = Validator {
+        root: schema,
+        errors: Vec::new(),
+        depth: 0,
+    };
+    validator.check(schema, value, "");
+    validator.errors
+}
+
+struct Validator<'a> {
+    root: &'a Value,
+    errors: Vec<SchemaError>,
+    depth: usize,
+}
+
+impl<'a> Validator<'a> {
+    fn fail(&mut self, path: &str, keyword: &'static str, message: impl Into<String>) {
+        self.errors.push(SchemaError {
+            path: path.to_string(),
+            keyword,
+            message: message.into(),
+        });
+    }
+
+    /// Whether `value` matches `schema`, without recording why not
+    fn matches(&mut self, schema: &'a Value, value: &Value, path: &str) -> bool {
+        let before = self.errors.len();
+        self.check(schema, value, path);
+        let matched = self.errors.len() == before;
+        self.errors.truncate(before);
+        matched
+    }
+
+    fn check(&mut self, schema: &'a Value, value: &Value, path: &str) {
+        let schema = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => return self.fail(path, "false", "is not allowed"),
+            Value::Object(schema) => schema,
+            _ => return,
+        };
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            match self.resolve(reference) {
+                Some(target) if self.depth < MAX_DEPTH => {
+                    self.depth += 1;
+                    self.check(target, value, path);
+                    self.depth -= 1;
+                }
+                Some(_) => self.fail(path, "$ref", format!("{} refers to itself", reference)),
+                None => self.fail(path, "$ref", format!("unknown definition {}", reference)),
+            }
+        }
+
+        if let Some(types) = schema.get("type") {
+            let names: Vec<&str> = match types {
+                Value::String(name) => vec![name.as_str()],
+                Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            if !names.is_empty() && !names.iter().any(|name| is_type(value, name)) {
+                self.fail(
+                    path,
+                    "type",
+                    format!("must be of type {}", names.join(" or ")),
+                );
+                // The other keywords would only repeat the mismatch
+                return;
+            }
+        }
+        if let Some(Value::Array(allowed)) = schema.get("enum") {
+            if !allowed.iter().any(|a| equal(a, value)) {
+                let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+                self.fail(
+                    path,
+                    "enum",
+                    format!("must be one of {}", allowed.join(", ")),
+                );
+            }
+        }
+        if let Some(constant) = schema.get("const") {
+            if !equal(constant, value) {
+                self.fail(path, "const", format!("must be {}", constant));
+            }
+        }
+
+        match value {
+            Value::Number(_) => self.check_number(schema, value, path),
+            Value::String(text) => self.check_string(schema, text, path),
+            Value::Array(items) => self.check_array(schema, items, path),
+            Value::Object(object) => self.check_object(schema, object, path),
+            _ => {}
+        }
+        self.check_combinators(schema, value, path);
+    }
+
+    /// The schema a `$ref` such as `#/$defs/server` points to
+    fn resolve(&self, reference: &str) -> Option<&'a Value> {
+        self.root.pointer(reference.strip_prefix('#')?)
+    }
+
+    fn check_number(&mut self, schema: &Map<String, Value>, value: &Value, path: &str) {
+        let Some(n) = value.as_f64() else { return };
+        let limit = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+        if let Some(min) = limit("minimum") {
+            if n < min {
+                self.fail(path, "minimum", format!("must be at least {}", min));
+            }
+        }
+        if let Some(max) = limit("maximum") {
+            if n > max {
+                self.fail(path, "maximum", format!("must be at most {}", max));
+            }
+        }
+        if let Some(min) = limit("exclusiveMinimum") {
+            if n <= min {
+                self.fail(
+                    path,
+                    "exclusiveMinimum",
+                    format!("must be greater than {}", min),
+                );
+            }
+        }
+        if let Some(max) = limit("exclusiveMaximum") {
+            if n >= max {
+                self.fail(
+                    path,
+                    "exclusiveMaximum",
+                    format!("must be less than {}", max),
+                );
+            }
+        }
+        if let Some(step) = limit("multipleOf").filter(|step| *step > 0.0) {
+            let quotient = n / step;
+            if (quotient - quotient.round()).abs() > 1e-9 {
+                self.fail(
+                    path,
+                    "multipleOf",
+                    format!("must be a multiple of {}", step),
+                );
+            }
+        }
+    }
+
+    fn check_string(&mut self, schema: &Map<String, Value>, text: &str, path: &str) {
+        let length = text.chars().count() as u64;
+        if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+            if length < min {
+                self.fail(
+                    path,
+                    "minLength",
+                    format!("must be at least {} characters long", min),
+                );
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+            if length > max {
+                self.fail(
+                    path,
+                    "maxLength",
+                    format!("must be at most {} characters long", max),
+                );
+            }
+        }
+        if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+            match Regex::new(pattern) {
+                Ok(re) if !re.is_match(text) => {
+                    self.fail(path, "pattern", format!("must match {}", pattern))
+                }
+                Ok(_) => {}
+                Err(_) => self.fail(
+                    path,
+                    "pattern",
+                    format!("the schema's pattern {} is invalid", pattern),
+                ),
+            }
+        }
+    }
+
+    fn check_array(&mut self, schema: &'a Map<String, Value>, items: &[Value], path: &str) {
+        let count = items.len() as u64;
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if count < min {
+                self.fail(
+                    path,
+                    "minItems",
+                    format!("must have at least {} items", min),
+                );
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if count > max {
+                self.fail(path, "maxItems", format!("must have at most {} items", max));
+            }
+        }
+        if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+            let duplicate = (0..items.len())
+                .find(|&i| items[..i].iter().any(|earlier| equal(earlier, &items[i])));
+            if let Some(i) = duplicate {
+                self.fail(
+                    &format!("{}/{}", path, i),
+                    "uniqueItems",
+                    "repeats an earlier item",
+                );
+            }
+        }
+
+        let prefix = match schema.get("prefixItems") {
+            Some(Value::Array(prefix)) => prefix.as_slice(),
+            _ => &[],
+        };
+        for (i, item) in items.iter().enumerate() {
+            let item_path = format!("{}/{}", path, i);
+            match prefix.get(i) {
+                Some(item_schema) => self.check(item_schema, item, &item_path),
+                None => {
+                    if let Some(item_schema) = schema.get("items") {
+                        self.check(item_schema, item, &item_path);
+                    }
+                }
+            }
+        }
+        if let Some(contains) = schema.get("contains") {
+            let found = items
+                .iter()
+                .enumerate()
+                .any(|(i, item)| self.matches(contains, item, &format!("{}/{}", path, i)));
+            if !found {
+                self.fail(path, "contains", "has no item matching `contains`");
+            }
+        }
+    }
+
+    fn check_object(
+        &mut self,
+        schema: &'a Map<String, Value>,
+        object: &Map<String, Value>,
+        path: &str,
+    ) {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(key) {
+                    self.fail(
+                        path,
+                        "required",
+                        format!("missing required setting '{}'", key),
+                    );
+                }
+            }
+        }
+        let count = object.len() as u64;
+        if let Some(min) = schema.get("minProperties").and_then(Value::as_u64) {
+            if count < min {
+                self.fail(
+                    path,
+                    "minProperties",
+                    format!("must have at least {} settings", min),
+                );
+            }
+        }
+        if let Some(max) = schema.get("maxProperties").and_then(Value::as_u64) {
+            if count > max {
+                self.fail(
+                    path,
+                    "maxProperties",
+                    format!("must have at most {} settings", max),
+                );
+            }
+        }
+        if let Some(Value::Object(dependent)) = schema.get("dependentRequired") {
+            for (key, needs) in dependent {
+                if !object.contains_key(key) {
+                    continue;
+                }
+                for needed in needs
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                {
+                    if !object.contains_key(needed) {
+                        self.fail(
+                            path,
+                            "dependentRequired",
+                            format!("'{}' needs '{}' to be set too", key, needed),
+                        );
+                    }
+                }
+            }
+        }
+
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let patterns: Vec<(Regex, &'a Value)> = schema
+            .get("patternProperties")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .filter_map(|(pattern, s)| Some((Regex::new(pattern).ok()?, s)))
+            .collect();
+        for (key, value) in object {
+            let value_path = format!("{}/{}", path, escape(key));
+            if let Some(names) = schema.get("propertyNames") {
+                if !self.matches(names, &Value::String(key.clone()), &value_path) {
+                    self.fail(
+                        &value_path,
+                        "propertyNames",
+                        format!("'{}' is not an allowed setting name", key),
+                    );
+                }
+            }
+            let mut known = false;
+            if let Some(property) = properties.and_then(|p| p.get(key)) {
+                known = true;
+                self.check(property, value, &value_path);
+            }
+            for (re, pattern_schema) in &patterns {
+                if re.is_match(key) {
+                    known = true;
+                    self.check(pattern_schema, value, &value_path);
+                }
+            }
+            if known {
+                continue;
+            }
+            match schema.get("additionalProperties") {
+                Some(Value::Bool(false)) => self.fail(
+                    &value_path,
+                    "additionalProperties",
+                    "is not a known setting",
+                ),
+                Some(additional) => self.check(additional, value, &value_path),
+                None => {}
+            }
+        }
+    }
+
+    fn check_combinators(&mut self, schema: &'a Map<String, Value>, value: &Value, path: &str) {
+        if let Some(Value::Array(all)) = schema.get("allOf") {
+            for sub in all {
+                self.check(sub, value, path);
+            }
+        }
+        if let Some(Value::Array(any)) = schema.get("anyOf") {
+            if !any.iter().any(|sub| self.matches(sub, value, path)) {
+                self.fail(path, "anyOf", "does not match any of the allowed schemas");
+            }
+        }
+        if let Some(Value::Array(one)) = schema.get("oneOf") {
+            let matched = one
+                .iter()
+                .filter(|sub| self.matches(sub, value, path))
+                .count();
+            if matched != 1 {
+                self.fail(
+                    path,
+                    "oneOf",
+                    format!("must match exactly one allowed schema, not {}", matched),
+                );
+            }
+        }
+        if let Some(not) = schema.get("not") {
+            if self.matches(not, value, path) {
+                self.fail(path, "not", "matches a schema it must not");
+            }
+        }
+        if let Some(condition) = schema.get("if") {
+            let branch = if self.matches(condition, value, path) {
+                schema.get("then")
+            } else {
+                schema.get("else")
+            };
+            if let Some(branch) = branch {
+                self.check(branch, value, path);
+            }
+        }
+    }
+}
+
+fn is_type(value: &Value, name: &str) -> bool {
+    match name {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        // 2.0 is an integer too
+        "integer" => value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/// JSON equality, under which 1 and 1.0 are the same number
+fn equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64() == y.as_f64(),
+        (Value::Array(x), Value::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(x, y)| equal(x, y))
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len() && x.iter().all(|(k, v)| y.get(k).is_some_and(|w| equal(v, w)))
+        }
+        _ => a == b,
+    }
+}
+
+/// A key as a JSON Pointer segment
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn errors(schema: &Value, value: Value) -> Vec<String> {
+        validate(schema, &value)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_config_schema() {
+        let schema = json!({
+            "type": "object",
+            "required": ["api_key"],
+            "properties": {
+                "api_key": { "type": "string", "minLength": 8 },
+                "limit": { "type": "integer", "minimum": 1, "maximum": 100 },
+                "mode": { "enum": ["fast", "full"] },
+                "servers": { "type": "array", "items": { "$ref": "#/$defs/server" } }
+            },
+            "additionalProperties": false,
+            "$defs": {
+                "server": {
+                    "type": "object",
+                    "required": ["host"],
+                    "properties": {
+                        "host": { "type": "string", "pattern": "^[a-z.]+$" },
+                        "port": { "type": "integer", "exclusiveMinimum": 0 }
+                    }
+                }
+            }
+        });
+
+        let valid = json!({
+            "api_key": "abcdefgh", "limit": 5.0, "mode": "fast",
+            "servers": [{ "host": "example.com", "port": 443 }]
+        });
+        assert!(validate(&schema, &valid).is_empty());
+
+        assert_eq!(
+            errors(
+                &schema,
+                json!({
+                    "api_key": "short", "limit": 500, "mode": "slow", "other": 1,
+                    "servers": [{ "host": "example.com" }, { "host": "BAD", "port": 0 }, {}]
+                })
+            ),
+            [
+                "/api_key: must be at least 8 characters long",
+                "/limit: must be at most 100",
+                "/mode: must be one of \"fast\", \"full\"",
+                "/other: is not a known setting",
+                "/servers/1/host: must match ^[a-z.]+$",
+                "/servers/1/port: must be greater than 0",
+                "/servers/2: missing required setting 'host'",
+            ]
+        );
+
+        let missing = validate(&schema, &json!({}));
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].keyword, "required");
+        assert_eq!(missing[0].path, "");
+        assert_eq!(errors(&schema, json!([])), ["/: must be of type object"]);
+
+        let choice = json!({
+            "oneOf": [{ "type": "string" }, { "type": "integer", "multipleOf": 5 }],
+            "not": { "const": "none" }
+        });
+        assert!(validate(&choice, &json!(10)).is_empty());
+        assert_eq!(
+            errors(&choice, json!(7)),
+            ["/: must match exactly one allowed schema, not 0"]
+        );
+        assert_eq!(
+            errors(&choice, json!("none")),
+            ["/: matches a schema it must not"]
+        );
+    }
+}
@@ -0,0 +1,157 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Installed plugin settings
+//!
+//! Whether each plugin is enabled, the permissions it was granted, its
+//! configuration, and where it was installed from are kept in `plugins.json`
+//! in the plugins directory, so they survive reinstalling the plugin.
+//! Plugins copied into the directory by hand have no entry; they are enabled
+//! with every permission they ask for except `shell` and `sensitive`, which
+//! must be granted with `csm plugin enable`. Permissions a process plugin is
+//! granted or refused when it first needs them are recorded here too.
+
+use super::Permission;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Settings file in the plugins directory
+pub const SETTINGS_FILE: &str = "plugins.json";
+
+/// What has been decided about one plugin
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Permissions the user granted; `None` until they have been asked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub granted: Option<Vec<Permission>>,
+    /// Permissions the user refused when the plugin first needed them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub denied: Vec<Permission>,
+    /// Plugin configuration
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub config: serde_json::Value,
+    /// Directory or URL the plugin was installed from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installed_at: Option<DateTime<Utc>>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for PluginSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            granted: None,
+            denied: Vec::new(),
+            config: serde_json::Value::Null,
+            source: None,
+            installed_at: None,
+        }
+    }
+}
+
+impl PluginSettings {
+    /// The permissions the plugin runs with: those granted, or if it has not
+    /// been asked, the ones it requests that need no confirmation
+    pub fn permissions(&self, requested: &[Permission]) -> Vec<Permission> {
+        match &self.granted {
+            Some(granted) => granted.clone(),
+            None => requested
+                .iter()
+                .filter(|p| !p.needs_confirmation())
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// Settings of every plugin, by ID
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SettingsFile {
+    #[serde(flatten)]
+    pub plugins: BTreeMap<String, PluginSettings>,
+}
+
+impl SettingsFile {
+    fn path(plugins_dir: &Path) -> PathBuf {
+        plugins_dir.join(SETTINGS_FILE)
+    }
+
+    /// Settings in `plugins_dir`, empty if there are none yet
+    pub fn load(plugins_dir: &Path) -> Result<Self> {
+        let path = Self::path(plugins_dir);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&text).with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    pub fn save(&self, plugins_dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(plugins_dir)?;
+        std::fs::write(
+            Self::path(plugins_dir),
+            serde_json::to_string_pretty(self)? + "\n",
+        )?;
+        Ok(())
+    }
+
+    /// Settings of one plugin, defaults if it has none
+    pub fn get(&self, plugin_id: &str) -> PluginSettings {
+        self.plugins.get(plugin_id).cloned().unwrap_or_default()
+    }
+
+    /// Change one plugin's settings and save them
+    pub fn update(
+        plugins_dir: &Path,
+        plugin_id: &str,
+        change: impl FnOnce(&mut PluginSettings),
+    ) -> Result<PluginSettings> {
+        let mut file = Self::load(plugins_dir)?;
+        let settings = file.plugins.entry(plugin_id.to_string()).or_default();
+        change(settings);
+        let settings = settings.clone();
+        file.save(plugins_dir)?;
+        Ok(settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            SettingsFile::load(dir.path()).unwrap(),
+            SettingsFile::default()
+        );
+
+        let requested = [Permission::SessionRead, Permission::Shell];
+        // Without an answer, permissions that need one are held back
+        let settings = SettingsFile::load(dir.path()).unwrap().get("tool");
+        assert!(settings.enabled);
+        assert_eq!(settings.permissions(&requested), [Permission::SessionRead]);
+
+        SettingsFile::update(dir.path(), "tool", |s| {
+            s.enabled = false;
+            s.granted = Some(requested.to_vec());
+        })
+        .unwrap();
+        let settings = SettingsFile::load(dir.path()).unwrap().get("tool");
+        assert!(!settings.enabled);
+        assert_eq!(settings.permissions(&requested), requested);
+
+        let text = std::fs::read_to_string(dir.path().join(SETTINGS_FILE)).unwrap();
+        assert!(text.contains("\"tool\""), "{}", text);
+    }
+}
@@ -0,0 +1,502 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Projects grouping several workspaces
+//!
+//! One real project often spans several workspace hashes: the folder was
+//! renamed or moved, it is checked out in more than one worktree, or parts of
+//! a monorepo are opened separately. A project names that group so search,
+//! merge and stats can treat it as one unit. Projects live in `projects.db`
+//! next to `config.toml`.
+
+use crate::config::Config;
+use crate::error::{CsmError, Result};
+use crate::models::Workspace;
+use crate::workspace::normalize_path;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Database file name, stored in the config directory
+pub const DATABASE_FILE: &str = "projects.db";
+
+/// A workspace that belongs to a project
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectWorkspace {
+    /// Workspace hash (folder name in workspaceStorage)
+    pub hash: String,
+    /// Project folder when the workspace was added
+    pub folder: Option<String>,
+    pub added_at: DateTime<Utc>,
+}
+
+/// A named group of workspaces
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Project {
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub workspaces: Vec<ProjectWorkspace>,
+}
+
+impl Project {
+    pub fn hashes(&self) -> HashSet<String> {
+        self.workspaces.iter().map(|w| w.hash.clone()).collect()
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.workspaces.iter().any(|w| w.hash == hash)
+    }
+}
+
+/// Create the project tables if they do not exist
+pub fn ensure_tables(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        PRAGMA foreign_keys = ON;
+
+        CREATE TABLE IF NOT EXISTS projects (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            description TEXT,
+            created_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS project_workspaces (
+            project_id INTEGER NOT NULL,
+            workspace_hash TEXT NOT NULL,
+            folder TEXT,
+            added_at INTEGER NOT NULL,
+            PRIMARY KEY (project_id, workspace_hash),
+            FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_project_workspaces_hash
+            ON project_workspaces(workspace_hash);
+        "#,
+    )?;
+    Ok(())
+}
+
+/// The project database
+pub struct ProjectStore {
+    conn: Connection,
+    path: PathBuf,
+}
+
+impl ProjectStore {
+    /// Open the database in the config directory
+    pub fn open() -> Result<Self> {
+        let config_path = Config::path()?;
+        let dir = config_path.parent().unwrap_or(Path::new("."));
+        Self::open_at(&dir.join(DATABASE_FILE))
+    }
+
+    /// Open a database file, creating it if needed
+    pub fn open_at(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)?;
+        ensure_tables(&conn)?;
+        Ok(Self {
+            conn,
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Create an empty project
+    pub fn create(&self, name: &str, description: Option<&str>) -> Result<Project> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(CsmError::ConfigError(
+                "project name must not be empty".to_string(),
+            ));
+        }
+        if self.id_of(name)?.is_some() {
+            return Err(CsmError::ConfigError(format!(
+                "project '{}' already exists",
+                name
+            )));
+        }
+        let now = Utc::now();
+        self.conn.execute(
+            "INSERT INTO projects (name, description, created_at) VALUES (?1, ?2, ?3)",
+            params![name, description, now.timestamp()],
+        )?;
+        Ok(Project {
+            name: name.to_string(),
+            description: description.map(String::from),
+            created_at: DateTime::from_timestamp(now.timestamp(), 0).unwrap_or(now),
+            workspaces: Vec::new(),
+        })
+    }
+
+    /// Delete a project. Its workspaces are untouched.
+    pub fn delete(&self, name: &str) -> Result<bool> {
+        let id = self.require_id(name)?;
+        let deleted = self
+            .conn
+            .execute("DELETE FROM projects WHERE id = ?1", params![id])?;
+        Ok(deleted > 0)
+    }
+
+    /// Add a workspace to a project. Returns false if it was already there.
+    pub fn add_workspace(&self, name: &str, hash: &str, folder: Option<&str>) -> Result<bool> {
+        let id = self.require_id(name)?;
+        let added = self.conn.execute(
+            "INSERT OR IGNORE INTO project_workspaces (project_id, workspace_hash, folder, added_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![id, hash, folder, Utc::now().timestamp()],
+        )?;
+        Ok(added > 0)
+    }
+
+    /// Remove a workspace from a project. Returns false if it was not there.
+    pub fn remove_workspace(&self, name: &str, hash: &str) -> Result<bool> {
+        let id = self.require_id(name)?;
+        let removed = self.conn.execute(
+            "DELETE FROM project_workspaces WHERE project_id = ?1 AND workspace_hash = ?2",
+            params![id, hash],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// All projects, by name
+    pub fn list(&self) -> Result<Vec<Project>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, name, description, created_at FROM projects ORDER BY name")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        rows.into_iter()
+            .map(|(id, name, description, created_at)| {
+                Ok(Project {
+                    name,
+                    description,
+                    created_at: DateTime::from_timestamp(created_at, 0).unwrap_or_default(),
+                    workspaces: self.workspaces_of(id)?,
+                })
+            })
+            .collect()
+    }
+
+    /// A project by name (case-insensitive)
+    pub fn get(&self, name: &str) -> Result<Option<Project>> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name.trim())))
+    }
+
+    /// A project by name, or `ProjectNotFound`
+    pub fn require(&self, name: &str) -> Result<Project> {
+        self.get(name)?
+            .ok_or_else(|| CsmError::ProjectNotFound(name.to_string()))
+    }
+
+    /// Names of the projects a workspace belongs to
+    pub fn projects_for(&self, hash: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT p.name FROM projects p
+             JOIN project_workspaces w ON w.project_id = p.id
+             WHERE w.workspace_hash = ?1 ORDER BY p.name",
+        )?;
+        let names = stmt
+            .query_map(params![hash], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<String>, _>>()?;
+        Ok(names)
+    }
+
+    fn id_of(&self, name: &str) -> Result<Option<i64>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT id FROM projects WHERE name = ?1",
+                params![name.trim()],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn require_id(&self, name: &str) -> Result<i64> {
+        self.id_of(name)?
+            .ok_or_else(|| CsmError::ProjectNotFound(name.to_string()))
+    }
+
+    fn workspaces_of(&self, id: i64) -> Result<Vec<ProjectWorkspace>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT workspace_hash, folder, added_at FROM project_workspaces
+             WHERE project_id = ?1 ORDER BY added_at, workspace_hash",
+        )?;
+        let workspaces = stmt
+            .query_map(params![id], |row| {
+                Ok(ProjectWorkspace {
+                    hash: row.get(0)?,
+                    folder: row.get(1)?,
+                    added_at: DateTime::from_timestamp(row.get(2)?, 0).unwrap_or_default(),
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(workspaces)
+    }
+}
+
+/// Workspace hashes of a project in the default database
+pub fn workspace_hashes(project: &str) -> Result<HashSet<String>> {
+    Ok(ProjectStore::open()?.require(project)?.hashes())
+}
+
+/// Find a discovered workspace by hash, unique hash prefix, or project folder
+pub fn resolve_workspace<'a>(query: &str, workspaces: &'a [Workspace]) -> Result<&'a Workspace> {
+    if let Some(ws) = workspaces.iter().find(|w| w.hash == query) {
+        return Ok(ws);
+    }
+
+    let by_prefix: Vec<&Workspace> = workspaces
+        .iter()
+        .filter(|w| w.hash.starts_with(query))
+        .collect();
+    if by_prefix.len() == 1 {
+        return Ok(by_prefix[0]);
+    }
+
+    let wanted = normalize_path(query);
+    let by_path: Vec<&Workspace> = workspaces
+        .iter()
+        .filter(|w| {
+            w.project_path
+                .as_deref()
+                .is_some_and(|p| normalize_path(p) == wanted)
+        })
+        .collect();
+    match (by_path.as_slice(), by_prefix.len()) {
+        ([ws], _) => Ok(ws),
+        ([], 0) => Err(CsmError::WorkspaceNotFound(query.to_string())),
+        _ => Err(CsmError::WorkspaceNotFound(format!(
+            "'{}' matches more than one workspace; use the full hash",
+            query
+        ))),
+    }
+}
+
+/// `origin` remote URL of the repository containing `path`
+///
+/// Uses `git` itself, so worktrees and subdirectories of a repository
+/// resolve to the repository's remote.
+pub fn git_remote(path: &Path) -> Option<String> {
+    if !path.is_dir() {
+        return None;
+    }
+    Command::new("git")
+        .args(["remote", "get-url", "origin"])
+        .current_dir(path)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|url| !url.is_empty())
+}
+
+/// Reduce a remote URL to `host/owner/repo` so SSH and HTTPS clones compare equal
+pub fn normalize_remote(url: &str) -> String {
+    let url = url.trim();
+    let without_scheme = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let without_user = without_scheme
+        .split_once('@')
+        .filter(|(user, _)| !user.contains('/'))
+        .map(|(_, rest)| rest)
+        .unwrap_or(without_scheme);
+    // scp-style `host:owner/repo`
+    let path_style = match without_user.split_once(':') {
+        Some((host, rest))
+            if !host.contains('/') && !rest.starts_with(|c: char| c.is_ascii_digit()) =>
+        {
+            format!("{}/{}", host, rest)
+        }
+        Some((host, rest)) if !host.contains('/') => {
+            // `host:port/owner/repo`
+            let path = rest.split_once('/').map(|(_, p)| p).unwrap_or("");
+            format!("{}/{}", host, path)
+        }
+        _ => without_user.to_string(),
+    };
+    path_style
+        .trim_end_matches('/')
+        .trim_end_matches(".git")
+        .to_lowercase()
+}
+
+/// Workspaces that share a git remote and could become one project
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    /// Proposed project name (the repository name)
+    pub name: String,
+    /// Normalized remote
+    pub remote: String,
+    pub workspaces: Vec<Workspace>,
+}
+
+/// Group workspaces by normalized git remote, keeping groups of two or more.
+/// `remote_of` maps a project folder to its remote URL (normally [`git_remote`]).
+pub fn suggest_groupings<F>(workspaces: &[Workspace], remote_of: F) -> Vec<Suggestion>
+where
+    F: Fn(&Path) -> Option<String>,
+{
+    let mut groups: BTreeMap<String, Vec<Workspace>> = BTreeMap::new();
+    for ws in workspaces {
+        let Some(folder) = ws.project_path.as_deref() else {
+            continue;
+        };
+        if let Some(url) = remote_of(Path::new(folder)) {
+            groups
+                .entry(normalize_remote(&url))
+                .or_default()
+                .push(ws.clone());
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(remote, workspaces)| Suggestion {
+            name: remote.rsplit('/').next().unwrap_or(&remote).to_string(),
+            remote,
+            workspaces,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn workspace(hash: &str, folder: Option<&str>) -> Workspace {
+        Workspace {
+            hash: hash.to_string(),
+            project_path: folder.map(String::from),
+            workspace_path: PathBuf::from(hash),
+            chat_sessions_path: PathBuf::from(hash).join("chatSessions"),
+            chat_session_count: 0,
+            has_chat_sessions: false,
+            last_modified: None,
+        }
+    }
+
+    #[test]
+    fn test_project_crud() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(DATABASE_FILE);
+        let store = ProjectStore::open_at(&path).unwrap();
+
+        store.create("chasm", Some("CLI")).unwrap();
+        assert!(store.create("Chasm", None).is_err());
+        assert!(store
+            .add_workspace("chasm", "aaa111", Some("/src/chasm"))
+            .unwrap());
+        assert!(store.add_workspace("chasm", "bbb222", None).unwrap());
+        assert!(!store.add_workspace("chasm", "aaa111", None).unwrap());
+        assert!(matches!(
+            store.add_workspace("missing", "aaa111", None),
+            Err(CsmError::ProjectNotFound(_))
+        ));
+
+        let reopened = ProjectStore::open_at(&path).unwrap();
+        let project = reopened.require("CHASM").unwrap();
+        assert_eq!(project.description.as_deref(), Some("CLI"));
+        assert_eq!(project.workspaces.len(), 2);
+        assert!(project.contains("bbb222"));
+        assert_eq!(reopened.projects_for("aaa111").unwrap(), vec!["chasm"]);
+
+        assert!(reopened.remove_workspace("chasm", "bbb222").unwrap());
+        assert_eq!(reopened.require("chasm").unwrap().workspaces.len(), 1);
+        assert!(reopened.delete("chasm").unwrap());
+        assert!(reopened.list().unwrap().is_empty());
+        assert!(reopened.projects_for("aaa111").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_normalize_remote() {
+        let expected = "github.com/nervosys/chasm-cli";
+        for url in [
+            "https://github.com/nervosys/chasm-cli.git",
+            "https://github.com/Nervosys/chasm-cli",
+            "git@github.com:nervosys/chasm-cli.git",
+            "ssh://git@github.com/nervosys/chasm-cli.git",
+            "https://user@github.com/nervosys/chasm-cli/",
+        ] {
+            assert_eq!(normalize_remote(url), expected, "{}", url);
+        }
+        assert_eq!(
+            normalize_remote("ssh://git@gitlab.example.com:2222/team/app.git"),
+            "gitlab.example.com/team/app"
+        );
+    }
+
+    #[test]
+    fn test_suggest_groupings_by_remote() {
+        let workspaces = vec![
+            workspace("a", Some("/src/chasm")),
+            workspace("b", Some("/src/chasm-old")),
+            workspace("c", Some("/src/worktrees/chasm-feature")),
+            workspace("d", Some("/src/other")),
+            workspace("e", None),
+        ];
+        let suggestions = suggest_groupings(&workspaces, |path| {
+            match path.to_str().unwrap() {
+                "/src/chasm" => Some("git@github.com:nervosys/chasm-cli.git"),
+                "/src/chasm-old" | "/src/worktrees/chasm-feature" => {
+                    Some("https://github.com/nervosys/chasm-cli")
+                }
+                "/src/other" => Some("https://github.com/nervosys/other"),
+                _ => None,
+            }
+            .map(String::from)
+        });
+
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].name, "chasm-cli");
+        let hashes: Vec<&str> = suggestions[0]
+            .workspaces
+            .iter()
+            .map(|w| w.hash.as_str())
+            .collect();
+        assert_eq!(hashes, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_resolve_workspace() {
+        let workspaces = vec![
+            workspace("abc123", Some("/src/one")),
+            workspace("abd456", Some("/src/two")),
+        ];
+        assert_eq!(
+            resolve_workspace("abc", &workspaces).unwrap().hash,
+            "abc123"
+        );
+        assert_eq!(
+            resolve_workspace("/src/two/", &workspaces).unwrap().hash,
+            "abd456"
+        );
+        assert!(resolve_workspace("ab", &workspaces).is_err());
+        assert!(resolve_workspace("zzz", &workspaces).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Cached provider discovery
+//!
+//! Discovery checks data directories and endpoints for every provider, which
+//! most commands never need. The result is cached in the harvest database for
+//! [`CACHE_TTL_SECS`] and reused until it expires, the endpoint environment
+//! variables change, or `--refresh-providers` is passed.
+
+use super::ProviderType;
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// How long a discovery result stays valid
+pub const CACHE_TTL_SECS: i64 = 60 * 60;
+
+/// Environment variables that change what discovery finds
+const ENDPOINT_VARS: &[&str] = &[
+    "OLLAMA_HOST",
+    "OLLAMA_MODELS",
+    "VLLM_ENDPOINT",
+    "LM_STUDIO_ENDPOINT",
+    "LOCALAI_ENDPOINT",
+    "TEXT_GEN_WEBUI_ENDPOINT",
+    "JAN_ENDPOINT",
+    "GPT4ALL_ENDPOINT",
+    "FOUNDRY_LOCAL_ENDPOINT",
+    "AI_FOUNDRY_ENDPOINT",
+];
+
+static REFRESH: AtomicBool = AtomicBool::new(false);
+
+/// Ignore cached results for the rest of this process (`--refresh-providers`)
+pub fn force_refresh() {
+    REFRESH.store(true, Ordering::Relaxed);
+}
+
+fn refresh_requested() -> bool {
+    REFRESH.load(Ordering::Relaxed)
+}
+
+/// What discovery found for one provider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscoveredProvider {
+    pub provider_type: ProviderType,
+    pub name: String,
+    pub endpoint: Option<String>,
+    pub path: Option<PathBuf>,
+    pub available: bool,
+    /// ID of the plugin that adds the provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin: Option<String>,
+}
+
+/// Create the cache table if it does not exist
+pub fn ensure_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS provider_discovery (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            environment TEXT NOT NULL,
+            providers TEXT NOT NULL,
+            discovered_at INTEGER NOT NULL
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Load a cached discovery result that is still valid
+pub fn load(db_path: &Path) -> Result<Option<Vec<DiscoveredProvider>>> {
+    if refresh_requested() {
+        return Ok(None);
+    }
+    let conn = Connection::open(db_path)?;
+    ensure_table(&conn)?;
+    load_at(&conn, &environment(), chrono::Utc::now().timestamp())
+}
+
+fn load_at(
+    conn: &Connection,
+    environment: &str,
+    now: i64,
+) -> Result<Option<Vec<DiscoveredProvider>>> {
+    let row: Option<(String, String, i64)> = conn
+        .query_row(
+            "SELECT environment, providers, discovered_at FROM provider_discovery WHERE id = 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+
+    match row {
+        Some((cached_env, providers, discovered_at))
+            if cached_env == environment && now - discovered_at < CACHE_TTL_SECS =>
+        {
+            Ok(serde_json::from_str(&providers).ok())
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Replace the cached discovery result
+pub fn store(db_path: &Path, providers: &[DiscoveredProvider]) -> Result<()> {
+    let conn = Connection::open(db_path)?;
+    ensure_table(&conn)?;
+    store_at(
+        &conn,
+        &environment(),
+        chrono::Utc::now().timestamp(),
+        providers,
+    )
+}
+
+fn store_at(
+    conn: &Connection,
+    environment: &str,
+    now: i64,
+    providers: &[DiscoveredProvider],
+) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO provider_discovery (id, environment, providers, discovered_at)
+         VALUES (1, ?1, ?2, ?3)",
+        params![environment, serde_json::to_string(providers)?, now],
+    )?;
+    Ok(())
+}
+
+/// Fingerprint of the endpoint variables and installed plugins, so changing
+/// either invalidates the cache
+fn environment() -> String {
+    let mut parts: Vec<String> = ENDPOINT_VARS
+        .iter()
+        .map(|var| format!("{}={}", var, std::env::var(var).unwrap_or_default()))
+        .collect();
+    parts.push(format!("plugins={}", plugins_stamp()));
+    parts.join(";")
+}
+
+/// When the plugins directory and its settings last changed, which installing,
+/// enabling, disabling, or removing a plugin does
+fn plugins_stamp() -> String {
+    let Ok(dir) = crate::plugins::PluginManager::default_dir() else {
+        return String::new();
+    };
+    let settings = dir.join(crate::plugins::settings::SETTINGS_FILE);
+    [&dir, &settings]
+        .iter()
+        .map(|path| {
+            std::fs::metadata(path)
+                .and_then(|m| m.modified())
+                .map(|t| format!("{:?}", t))
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<DiscoveredProvider> {
+        vec![DiscoveredProvider {
+            provider_type: ProviderType::Ollama,
+            name: "Ollama".to_string(),
+            endpoint: Some("http://localhost:11434".to_string()),
+            path: None,
+            available: true,
+            plugin: None,
+        }]
+    }
+
+    #[test]
+    fn test_cache_round_trip_and_expiry() {
+        let conn = Connection::open_in_memory().unwrap();
+        ensure_table(&conn).unwrap();
+        assert_eq!(load_at(&conn, "env", 1_000).unwrap(), None);
+
+        store_at(&conn, "env", 1_000, &sample()).unwrap();
+        assert_eq!(load_at(&conn, "env", 1_010).unwrap(), Some(sample()));
+        assert_eq!(load_at(&conn, "other", 1_010).unwrap(), None);
+        assert_eq!(load_at(&conn, "env", 1_000 + CACHE_TTL_SECS).unwrap(), None);
+    }
+}
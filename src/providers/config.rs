@@ -101,6 +101,9 @@ pub enum ProviderType {
 
     /// Custom OpenAI-compatible endpoint
     Custom,
+
+    /// Provided by a plugin
+    Plugin,
 }
 
 impl ProviderType {
@@ -139,6 +142,7 @@ impl ProviderType {
             Self::Replicate => "Replicate",
             Self::HuggingFace => "HuggingFace",
             Self::Custom => "Custom",
+            Self::Plugin => "Plugin",
         }
     }
 
@@ -177,6 +181,7 @@ impl ProviderType {
             Self::Replicate => Some("https://api.replicate.com/v1"),
             Self::HuggingFace => Some("https://api-inference.huggingface.co"),
             Self::Custom => None,
+            Self::Plugin => None,
         }
     }
 
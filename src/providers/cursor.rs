@@ -2,9 +2,9 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //! Cursor IDE chat provider
 
-use super::{ChatProvider, ProviderType};
+use super::{cache::DiscoveredProvider, ChatProvider, ProviderType, ScanFailure, SessionScan};
 use crate::models::ChatSession;
-use crate::storage::parse_session_json;
+use crate::storage::parse_session_file;
 use anyhow::Result;
 use std::path::PathBuf;
 
@@ -30,50 +30,60 @@ impl CursorProvider {
         })
     }
 
+    /// Recreate the provider from a cached discovery result
+    pub fn from_discovered(discovered: &DiscoveredProvider) -> Option<Self> {
+        Some(Self {
+            storage_path: discovered.path.clone()?,
+            available: discovered.available,
+        })
+    }
+
     /// Find Cursor's workspace storage directory
     fn find_cursor_storage() -> Option<PathBuf> {
+        Self::candidate_paths().into_iter().find(|p| p.exists())
+    }
+
+    /// Locations checked for Cursor's workspace storage, in search order
+    pub fn candidate_paths() -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+
         #[cfg(target_os = "windows")]
         {
-            let appdata = dirs::data_dir()?;
-            let cursor_path = appdata.join("Cursor").join("User").join("workspaceStorage");
-            if cursor_path.exists() {
-                return Some(cursor_path);
+            if let Some(appdata) = dirs::data_dir() {
+                paths.push(appdata.join("Cursor").join("User").join("workspaceStorage"));
             }
             // Also check Roaming
-            let roaming = std::env::var("APPDATA").ok()?;
-            let roaming_path = PathBuf::from(roaming)
-                .join("Cursor")
-                .join("User")
-                .join("workspaceStorage");
-            if roaming_path.exists() {
-                return Some(roaming_path);
+            if let Ok(roaming) = std::env::var("APPDATA") {
+                paths.push(
+                    PathBuf::from(roaming)
+                        .join("Cursor")
+                        .join("User")
+                        .join("workspaceStorage"),
+                );
             }
         }
 
         #[cfg(target_os = "macos")]
         {
-            let home = dirs::home_dir()?;
-            let cursor_path = home
-                .join("Library")
-                .join("Application Support")
-                .join("Cursor")
-                .join("User")
-                .join("workspaceStorage");
-            if cursor_path.exists() {
-                return Some(cursor_path);
+            if let Some(home) = dirs::home_dir() {
+                paths.push(
+                    home.join("Library")
+                        .join("Application Support")
+                        .join("Cursor")
+                        .join("User")
+                        .join("workspaceStorage"),
+                );
             }
         }
 
         #[cfg(target_os = "linux")]
         {
-            let config = dirs::config_dir()?;
-            let cursor_path = config.join("Cursor").join("User").join("workspaceStorage");
-            if cursor_path.exists() {
-                return Some(cursor_path);
+            if let Some(config) = dirs::config_dir() {
+                paths.push(config.join("Cursor").join("User").join("workspaceStorage"));
             }
         }
 
-        None
+        paths
     }
 
     /// List all workspace directories with chat sessions
@@ -117,28 +127,41 @@ impl ChatProvider for CursorProvider {
     }
 
     fn list_sessions(&self) -> Result<Vec<ChatSession>> {
-        let mut sessions = Vec::new();
+        Ok(self.scan_sessions()?.sessions)
+    }
+
+    fn scan_sessions(&self) -> Result<SessionScan> {
+        let mut scan = SessionScan::default();
 
         for workspace in self.list_workspaces()? {
             let chat_path = workspace.join("chatSessions");
+            let entries = match std::fs::read_dir(&chat_path) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    scan.failures.push(ScanFailure::new(&chat_path, &e.into()));
+                    continue;
+                }
+            };
+
+            for entry in entries {
+                let path = match entry {
+                    Ok(entry) => entry.path(),
+                    Err(e) => {
+                        scan.failures.push(ScanFailure::new(&chat_path, &e.into()));
+                        continue;
+                    }
+                };
 
-            if chat_path.exists() {
-                for entry in std::fs::read_dir(&chat_path)? {
-                    let entry = entry?;
-                    let path = entry.path();
-
-                    if path.extension().is_some_and(|e| e == "json") {
-                        if let Ok(content) = std::fs::read_to_string(&path) {
-                            if let Ok(session) = parse_session_json(&content) {
-                                sessions.push(session);
-                            }
-                        }
+                if path.extension().is_some_and(|e| e == "json") {
+                    match parse_session_file(&path) {
+                        Ok(session) => scan.sessions.push(session),
+                        Err(e) => scan.failures.push(ScanFailure::new(&path, &e.into())),
                     }
                 }
             }
         }
 
-        Ok(sessions)
+        Ok(scan)
     }
 
     fn import_session(&self, session_id: &str) -> Result<ChatSession> {
@@ -149,9 +172,7 @@ impl ChatProvider for CursorProvider {
                 .join(format!("{}.json", session_id));
 
             if session_path.exists() {
-                let content = std::fs::read_to_string(&session_path)?;
-                let session: ChatSession = serde_json::from_str(&content)?;
-                return Ok(session);
+                return Ok(parse_session_file(&session_path)?);
             }
         }
 
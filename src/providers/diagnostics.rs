@@ -0,0 +1,366 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Per-provider scan diagnostics
+//!
+//! A provider that cannot be read should not look the same as one with no
+//! sessions. [`diagnose`] records where each provider was looked for, what
+//! went wrong, and what the user can do about it.
+
+use super::{cursor::CursorProvider, io_error_kind, ProviderRegistry, ProviderType, ScanFailure};
+use crate::workspace::{discover_workspaces, get_workspace_storage_path};
+use colored::*;
+use serde::Serialize;
+use std::io;
+use std::path::PathBuf;
+
+/// Failures listed per provider unless verbose output is requested
+const MAX_LISTED_FAILURES: usize = 3;
+
+/// Outcome of checking one provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProviderStatus {
+    /// All sessions were read
+    Ready,
+    /// Some sessions were read, some sources failed
+    Partial,
+    /// The provider was found but its sessions could not be read
+    Failed,
+    /// The provider is configured but not reachable
+    Unavailable,
+    /// The provider is not installed
+    NotFound,
+}
+
+impl ProviderStatus {
+    /// Whether the user should look at this provider
+    pub fn is_problem(self) -> bool {
+        matches!(self, Self::Partial | Self::Failed)
+    }
+}
+
+/// An error met while reading a provider
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderError {
+    pub path: Option<PathBuf>,
+    pub message: String,
+}
+
+/// What was checked for a provider and what was found
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderDiagnostic {
+    pub provider: ProviderType,
+    pub name: String,
+    pub status: ProviderStatus,
+    pub paths_checked: Vec<PathBuf>,
+    pub endpoint: Option<String>,
+    pub sessions: usize,
+    pub errors: Vec<ProviderError>,
+    pub suggestion: Option<String>,
+}
+
+impl ProviderDiagnostic {
+    fn new(provider: ProviderType, name: &str, status: ProviderStatus) -> Self {
+        Self {
+            provider,
+            name: name.to_string(),
+            status,
+            paths_checked: Vec::new(),
+            endpoint: None,
+            sessions: 0,
+            errors: Vec::new(),
+            suggestion: None,
+        }
+    }
+}
+
+/// Check one provider, reading its sessions if it is available
+pub fn diagnose(registry: &ProviderRegistry, provider_type: ProviderType) -> ProviderDiagnostic {
+    if provider_type == ProviderType::Copilot {
+        return diagnose_copilot();
+    }
+
+    let Some(provider) = registry.get_provider(provider_type) else {
+        let mut diagnostic = ProviderDiagnostic::new(
+            provider_type,
+            provider_type.display_name(),
+            ProviderStatus::NotFound,
+        );
+        if provider_type == ProviderType::Cursor {
+            diagnostic.paths_checked = CursorProvider::candidate_paths();
+            diagnostic.suggestion =
+                Some("Install Cursor and open a folder in it to create its storage".to_string());
+        }
+        return diagnostic;
+    };
+
+    let mut diagnostic =
+        ProviderDiagnostic::new(provider_type, provider.name(), ProviderStatus::Unavailable);
+    diagnostic.paths_checked = provider.sessions_path().into_iter().collect();
+    diagnostic.endpoint = provider.endpoint().map(str::to_string);
+
+    if !provider.is_available() {
+        diagnostic.suggestion = endpoint_variable(provider_type)
+            .map(|var| format!("Start {} or point {} at its server", provider.name(), var));
+        return diagnostic;
+    }
+
+    match provider.scan_sessions() {
+        Ok(scan) => {
+            diagnostic.sessions = scan.sessions.len();
+            diagnostic.status = if scan.failures.is_empty() {
+                ProviderStatus::Ready
+            } else {
+                ProviderStatus::Partial
+            };
+            diagnostic.suggestion = suggest_for_failures(&scan.failures);
+            diagnostic.errors = scan
+                .failures
+                .into_iter()
+                .map(|f| ProviderError {
+                    path: Some(f.path),
+                    message: f.error,
+                })
+                .collect();
+        }
+        Err(e) => {
+            diagnostic.status = ProviderStatus::Failed;
+            diagnostic.suggestion = suggest(io_error_kind(&e), diagnostic.paths_checked.first());
+            diagnostic.errors.push(ProviderError {
+                path: diagnostic.paths_checked.first().cloned(),
+                message: format!("{:#}", e),
+            });
+        }
+    }
+    diagnostic
+}
+
+/// VS Code Copilot sessions live in workspace storage rather than behind a
+/// registered provider
+fn diagnose_copilot() -> ProviderDiagnostic {
+    let name = ProviderType::Copilot.display_name();
+    let mut diagnostic =
+        ProviderDiagnostic::new(ProviderType::Copilot, name, ProviderStatus::Ready);
+
+    let storage = match get_workspace_storage_path() {
+        Ok(path) => path,
+        Err(e) => {
+            diagnostic.status = ProviderStatus::NotFound;
+            diagnostic.errors.push(ProviderError {
+                path: None,
+                message: e.to_string(),
+            });
+            diagnostic.suggestion = Some(copilot_storage_hint());
+            return diagnostic;
+        }
+    };
+    diagnostic.paths_checked.push(storage.clone());
+
+    if !storage.exists() {
+        diagnostic.status = ProviderStatus::NotFound;
+        diagnostic.suggestion = Some(copilot_storage_hint());
+        return diagnostic;
+    }
+
+    match discover_workspaces() {
+        Ok(workspaces) => {
+            diagnostic.sessions = workspaces.iter().map(|w| w.chat_session_count).sum();
+        }
+        Err(e) => {
+            let e = anyhow::Error::from(e);
+            diagnostic.status = ProviderStatus::Failed;
+            diagnostic.suggestion = suggest(io_error_kind(&e), Some(&storage));
+            diagnostic.errors.push(ProviderError {
+                path: Some(storage),
+                message: format!("{:#}", e),
+            });
+        }
+    }
+    diagnostic
+}
+
+fn copilot_storage_hint() -> String {
+    "Open a folder in VS Code, or set CSM_WORKSPACE_STORAGE to its workspaceStorage directory"
+        .to_string()
+}
+
+/// Environment variable that overrides a server provider's endpoint
+fn endpoint_variable(provider_type: ProviderType) -> Option<&'static str> {
+    match provider_type {
+        ProviderType::Ollama => Some("OLLAMA_HOST"),
+        ProviderType::Vllm => Some("VLLM_ENDPOINT"),
+        ProviderType::LmStudio => Some("LM_STUDIO_ENDPOINT"),
+        ProviderType::LocalAI => Some("LOCALAI_ENDPOINT"),
+        ProviderType::TextGenWebUI => Some("TEXT_GEN_WEBUI_ENDPOINT"),
+        ProviderType::Jan => Some("JAN_ENDPOINT"),
+        ProviderType::Gpt4All => Some("GPT4ALL_ENDPOINT"),
+        ProviderType::Foundry => Some("FOUNDRY_LOCAL_ENDPOINT"),
+        _ => None,
+    }
+}
+
+fn suggest_for_failures(failures: &[ScanFailure]) -> Option<String> {
+    let first = failures.first()?;
+    if let Some(denied) = failures
+        .iter()
+        .find(|f| f.kind == Some(io::ErrorKind::PermissionDenied))
+    {
+        return suggest(denied.kind, Some(&denied.path));
+    }
+    match first.kind {
+        Some(kind) => suggest(Some(kind), Some(&first.path)),
+        None => Some(
+            "Some session files could not be parsed; run 'csm recover scan' to inspect them"
+                .to_string(),
+        ),
+    }
+}
+
+/// A suggested fix for an error reading `path`
+fn suggest(kind: Option<io::ErrorKind>, path: Option<&PathBuf>) -> Option<String> {
+    let path = path.map(|p| p.display().to_string());
+    let path = path.as_deref().unwrap_or("the provider's storage");
+    match kind? {
+        io::ErrorKind::PermissionDenied => Some(format!(
+            "Grant your user read access to {} (check ownership, or Full Disk Access on macOS)",
+            path
+        )),
+        io::ErrorKind::NotFound => Some(format!(
+            "{} disappeared during the scan; re-run once the editor has finished writing",
+            path
+        )),
+        _ => Some(format!("Check that {} is readable and not locked", path)),
+    }
+}
+
+/// Print one provider's diagnostic. Problems always print their errors and
+/// suggestion; `verbose` lists every failed source.
+pub fn print_diagnostic(diagnostic: &ProviderDiagnostic, verbose: bool) {
+    let name = diagnostic.name.bold();
+    match diagnostic.status {
+        ProviderStatus::Ready => {
+            let status = if diagnostic.sessions > 0 {
+                format!(
+                    "{} {} sessions",
+                    "+".green(),
+                    diagnostic.sessions.to_string().cyan()
+                )
+            } else {
+                format!("{} no sessions", "+".green())
+            };
+            println!("   {} {}: {}", "[+]".green(), name, status);
+        }
+        ProviderStatus::Partial => println!(
+            "   {} {}: {} sessions, {} unreadable",
+            "[!]".yellow(),
+            name,
+            diagnostic.sessions.to_string().cyan(),
+            diagnostic.errors.len().to_string().yellow()
+        ),
+        ProviderStatus::Failed => {
+            println!(
+                "   {} {}: {}",
+                "[X]".red(),
+                name,
+                "could not read sessions".red()
+            )
+        }
+        ProviderStatus::Unavailable => {
+            println!(
+                "   {} {}: {}",
+                "[-]".dimmed(),
+                name,
+                "not reachable".dimmed()
+            )
+        }
+        ProviderStatus::NotFound => {
+            println!(
+                "   {} {}: {}",
+                "[-]".dimmed(),
+                name,
+                "not installed".dimmed()
+            )
+        }
+    }
+
+    if diagnostic.status == ProviderStatus::NotFound {
+        for path in &diagnostic.paths_checked {
+            println!(
+                "      {} checked {}",
+                "`".dimmed(),
+                path.display().to_string().dimmed()
+            );
+        }
+    } else {
+        for path in &diagnostic.paths_checked {
+            println!(
+                "      {} {}",
+                "`".dimmed(),
+                path.display().to_string().dimmed()
+            );
+        }
+        if verbose {
+            if let Some(endpoint) = &diagnostic.endpoint {
+                println!("      {} {}", "`".dimmed(), endpoint.dimmed());
+            }
+        }
+    }
+
+    let limit = if verbose {
+        diagnostic.errors.len()
+    } else {
+        MAX_LISTED_FAILURES
+    };
+    for error in diagnostic.errors.iter().take(limit) {
+        match &error.path {
+            Some(path) if Some(path) != diagnostic.paths_checked.first() => println!(
+                "      {} {}: {}",
+                "!".yellow(),
+                path.display(),
+                error.message
+            ),
+            _ => println!("      {} {}", "!".yellow(), error.message),
+        }
+    }
+    if diagnostic.errors.len() > limit {
+        println!(
+            "      {} ... and {} more (use --verbose to list all)",
+            "!".yellow(),
+            diagnostic.errors.len() - limit
+        );
+    }
+
+    if let Some(suggestion) = &diagnostic.suggestion {
+        if diagnostic.status.is_problem() || verbose {
+            println!("      {} {}", "[->]".cyan(), suggestion);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permission_denied_takes_precedence() {
+        let parse = ScanFailure {
+            path: PathBuf::from("/tmp/a.json"),
+            error: "bad json".to_string(),
+            kind: None,
+        };
+        let denied = ScanFailure {
+            path: PathBuf::from("/tmp/ws/chatSessions"),
+            error: "Permission denied".to_string(),
+            kind: Some(io::ErrorKind::PermissionDenied),
+        };
+
+        let suggestion = suggest_for_failures(&[parse.clone(), denied]).unwrap();
+        assert!(suggestion.contains("read access to /tmp/ws/chatSessions"));
+
+        let suggestion = suggest_for_failures(&[parse]).unwrap();
+        assert!(suggestion.contains("csm recover scan"));
+        assert_eq!(suggest_for_failures(&[]), None);
+    }
+}
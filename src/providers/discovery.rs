@@ -43,6 +43,10 @@ pub fn print_provider_summary(registry: &ProviderRegistry) {
             print!(" {}", "(not available)".dimmed());
         }
 
+        if let Some(plugin_id) = provider.plugin_id() {
+            print!(" [plugin {}]", plugin_id.dimmed());
+        }
+
         // Show default endpoint for server-based providers
         if provider_type.is_openai_compatible() {
             if let Some(endpoint) = provider_type.default_endpoint() {
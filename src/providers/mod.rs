@@ -25,16 +25,22 @@
 //! - Cohere
 //! - Groq
 //! - Together AI
+//!
+//! ## Plugin Providers
+//! - Any tool with a provider plugin installed (see [`plugin`])
 
+pub mod cache;
 #[allow(dead_code)]
 pub mod cloud;
 pub mod config;
 pub mod continuedev;
 pub mod cursor;
+pub mod diagnostics;
 #[allow(dead_code)]
 pub mod discovery;
 pub mod ollama;
 pub mod openai_compat;
+pub mod plugin;
 #[allow(dead_code)]
 pub mod session_format;
 
@@ -50,8 +56,44 @@ pub use session_format::{GenericMessage, GenericSession};
 
 use crate::models::ChatSession;
 use anyhow::Result;
+use cache::DiscoveredProvider;
+use once_cell::sync::OnceCell;
+use std::io;
 use std::path::PathBuf;
 
+/// A session source that could not be read during a scan
+#[derive(Debug, Clone)]
+pub struct ScanFailure {
+    pub path: PathBuf,
+    pub error: String,
+    pub kind: Option<io::ErrorKind>,
+}
+
+impl ScanFailure {
+    pub fn new(path: impl Into<PathBuf>, error: &anyhow::Error) -> Self {
+        Self {
+            path: path.into(),
+            error: error.to_string(),
+            kind: io_error_kind(error),
+        }
+    }
+}
+
+/// Sessions found by a scan, plus the sources that were skipped
+#[derive(Debug, Default)]
+pub struct SessionScan {
+    pub sessions: Vec<ChatSession>,
+    pub failures: Vec<ScanFailure>,
+}
+
+/// The I/O error kind behind an error, if there is one
+pub fn io_error_kind(error: &anyhow::Error) -> Option<io::ErrorKind> {
+    error
+        .chain()
+        .find_map(|e| e.downcast_ref::<io::Error>())
+        .map(|e| e.kind())
+}
+
 /// Trait for LLM chat providers
 pub trait ChatProvider: Send + Sync {
     /// Get the provider type
@@ -66,9 +108,28 @@ pub trait ChatProvider: Send + Sync {
     /// Get the base path where sessions are stored
     fn sessions_path(&self) -> Option<PathBuf>;
 
+    /// Get the API endpoint for server-based providers
+    fn endpoint(&self) -> Option<&str> {
+        None
+    }
+
+    /// ID of the plugin that adds this provider
+    fn plugin_id(&self) -> Option<&str> {
+        None
+    }
+
     /// List all chat sessions from this provider
     fn list_sessions(&self) -> Result<Vec<ChatSession>>;
 
+    /// List sessions, reporting the sources that could not be read instead
+    /// of dropping them. Fails only when nothing could be read at all.
+    fn scan_sessions(&self) -> Result<SessionScan> {
+        Ok(SessionScan {
+            sessions: self.list_sessions()?,
+            failures: Vec::new(),
+        })
+    }
+
     /// Import a session from this provider into CSM format
     fn import_session(&self, session_id: &str) -> Result<ChatSession>;
 
@@ -78,46 +139,67 @@ pub trait ChatProvider: Send + Sync {
 }
 
 /// Registry of available providers
+///
+/// Providers are discovered on first use, so commands that never touch a
+/// provider pay nothing for discovery.
 pub struct ProviderRegistry {
-    providers: Vec<Box<dyn ChatProvider>>,
+    providers: OnceCell<Vec<Box<dyn ChatProvider>>>,
+    /// Harvest database holding the cached discovery result
+    cache_db: Option<PathBuf>,
 }
 
 impl ProviderRegistry {
-    /// Create a new provider registry with auto-discovered providers
+    /// Create a provider registry that discovers providers on first use
     pub fn new() -> Self {
-        let mut registry = Self {
-            providers: Vec::new(),
-        };
-        registry.discover_providers();
-        registry
+        Self {
+            providers: OnceCell::new(),
+            cache_db: None,
+        }
     }
 
-    /// Discover and register available providers
-    fn discover_providers(&mut self) {
-        // Add Cursor provider
-        if let Some(provider) = cursor::CursorProvider::discover() {
-            self.providers.push(Box::new(provider));
+    /// Create a provider registry that reuses the discovery result cached in
+    /// the harvest database at `db_path`
+    pub fn cached(db_path: impl Into<PathBuf>) -> Self {
+        Self {
+            providers: OnceCell::new(),
+            cache_db: Some(db_path.into()),
         }
+    }
 
-        // Add Ollama provider
-        if let Some(provider) = ollama::OllamaProvider::discover() {
-            self.providers.push(Box::new(provider));
-        }
+    fn load_providers(&self) -> Vec<Box<dyn ChatProvider>> {
+        let Some(db_path) = &self.cache_db else {
+            return discover_providers();
+        };
 
-        // Add OpenAI-compatible providers (vLLM, LM Studio, LocalAI, etc.)
-        for provider in openai_compat::discover_openai_compatible_providers() {
-            self.providers.push(Box::new(provider));
+        if let Ok(Some(cached)) = cache::load(db_path) {
+            return cached.iter().filter_map(from_discovered).collect();
         }
+
+        let providers = discover_providers();
+        let discovered: Vec<DiscoveredProvider> = providers
+            .iter()
+            .map(|p| DiscoveredProvider {
+                provider_type: p.provider_type(),
+                name: p.name().to_string(),
+                endpoint: p.endpoint().map(str::to_string),
+                path: p.sessions_path(),
+                available: p.is_available(),
+                plugin: p.plugin_id().map(str::to_string),
+            })
+            .collect();
+        // A cache that cannot be written only costs the next command a rediscovery
+        let _ = cache::store(db_path, &discovered);
+        providers
     }
 
     /// Get all registered providers
     pub fn providers(&self) -> &[Box<dyn ChatProvider>] {
-        &self.providers
+        self.providers.get_or_init(|| self.load_providers())
     }
 
     /// Get available (configured and working) providers
     pub fn available_providers(&self) -> Vec<&dyn ChatProvider> {
-        self.providers
+        self.providers()
             .iter()
             .filter(|p| p.is_available())
             .map(|p| p.as_ref())
@@ -126,18 +208,26 @@ impl ProviderRegistry {
 
     /// Get a provider by type
     pub fn get_provider(&self, provider_type: ProviderType) -> Option<&dyn ChatProvider> {
-        self.providers
+        self.providers()
             .iter()
             .find(|p| p.provider_type() == provider_type)
             .map(|p| p.as_ref())
     }
 
+    /// Get the provider added by a plugin, by the plugin's ID
+    pub fn plugin_provider(&self, plugin_id: &str) -> Option<&dyn ChatProvider> {
+        self.providers()
+            .iter()
+            .find(|p| p.plugin_id() == Some(plugin_id))
+            .map(|p| p.as_ref())
+    }
+
     /// List all sessions from all providers
     #[allow(dead_code)]
     pub fn list_all_sessions(&self) -> Result<Vec<(ProviderType, ChatSession)>> {
         let mut all_sessions = Vec::new();
 
-        for provider in &self.providers {
+        for provider in self.providers() {
             if provider.is_available() {
                 if let Ok(sessions) = provider.list_sessions() {
                     for session in sessions {
@@ -156,3 +246,50 @@ impl Default for ProviderRegistry {
         Self::new()
     }
 }
+
+/// Discover the installed and reachable providers
+fn discover_providers() -> Vec<Box<dyn ChatProvider>> {
+    let mut providers: Vec<Box<dyn ChatProvider>> = Vec::new();
+
+    // Add Cursor provider
+    if let Some(provider) = cursor::CursorProvider::discover() {
+        providers.push(Box::new(provider));
+    }
+
+    // Add Ollama provider
+    if let Some(provider) = ollama::OllamaProvider::discover() {
+        providers.push(Box::new(provider));
+    }
+
+    // Add OpenAI-compatible providers (vLLM, LM Studio, LocalAI, etc.)
+    for provider in openai_compat::discover_openai_compatible_providers() {
+        providers.push(Box::new(provider));
+    }
+
+    // Add providers from plugins
+    for provider in plugin::discover_plugin_providers() {
+        providers.push(Box::new(provider));
+    }
+
+    providers
+}
+
+/// Recreate a provider from a cached discovery result without probing it
+fn from_discovered(discovered: &DiscoveredProvider) -> Option<Box<dyn ChatProvider>> {
+    match discovered.provider_type {
+        ProviderType::Cursor => {
+            cursor::CursorProvider::from_discovered(discovered).map(|p| Box::new(p) as _)
+        }
+        ProviderType::Ollama => {
+            ollama::OllamaProvider::from_discovered(discovered).map(|p| Box::new(p) as _)
+        }
+        t if t.is_openai_compatible() => {
+            openai_compat::OpenAICompatProvider::from_discovered(discovered)
+                .map(|p| Box::new(p) as _)
+        }
+        ProviderType::Plugin => {
+            plugin::PluginChatProvider::from_discovered(discovered).map(|p| Box::new(p) as _)
+        }
+        _ => None,
+    }
+}
@@ -4,7 +4,7 @@
 
 #![allow(dead_code)]
 
-use super::{ChatProvider, ProviderType};
+use super::{cache::DiscoveredProvider, ChatProvider, ProviderType};
 use crate::models::{ChatMessage, ChatRequest, ChatSession};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
@@ -77,6 +77,15 @@ impl OllamaProvider {
         })
     }
 
+    /// Recreate the provider from a cached discovery result
+    pub fn from_discovered(discovered: &DiscoveredProvider) -> Option<Self> {
+        Some(Self {
+            endpoint: discovered.endpoint.clone()?,
+            available: discovered.available,
+            data_path: discovered.path.clone(),
+        })
+    }
+
     /// Find Ollama's data directory
     fn find_ollama_data() -> Option<PathBuf> {
         // Check OLLAMA_MODELS environment variable first
@@ -222,6 +231,10 @@ impl ChatProvider for OllamaProvider {
         self.data_path.clone()
     }
 
+    fn endpoint(&self) -> Option<&str> {
+        Some(&self.endpoint)
+    }
+
     fn list_sessions(&self) -> Result<Vec<ChatSession>> {
         // Ollama doesn't persist chat history by default
         // This would need integration with Ollama's history feature
@@ -15,7 +15,7 @@
 
 #![allow(dead_code)]
 
-use super::{ChatProvider, ProviderType};
+use super::{cache::DiscoveredProvider, ChatProvider, ProviderType};
 use crate::models::{ChatMessage, ChatRequest, ChatSession};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
@@ -95,6 +95,19 @@ impl OpenAICompatProvider {
         }
     }
 
+    /// Recreate a provider from a cached discovery result
+    pub fn from_discovered(discovered: &DiscoveredProvider) -> Option<Self> {
+        Some(Self {
+            provider_type: discovered.provider_type,
+            name: discovered.name.clone(),
+            endpoint: discovered.endpoint.clone()?,
+            api_key: None,
+            model: None,
+            available: discovered.available,
+            data_path: discovered.path.clone(),
+        })
+    }
+
     /// Set API key
     pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
         self.api_key = Some(api_key.into());
@@ -232,6 +245,10 @@ impl ChatProvider for OpenAICompatProvider {
         self.data_path.clone()
     }
 
+    fn endpoint(&self) -> Option<&str> {
+        Some(&self.endpoint)
+    }
+
     fn list_sessions(&self) -> Result<Vec<ChatSession>> {
         // OpenAI-compatible APIs don't persist sessions
         // This would need a local history storage layer
@@ -0,0 +1,293 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Chat providers added by plugins
+//!
+//! A plugin with a `provider` section in its manifest reads the sessions of a
+//! tool csm has no built-in provider for. It answers these actions:
+//!
+//! - `discover` (optional), answered with `{"available", "sessions_path",
+//!   "endpoint"}`; without it the provider is always available
+//! - `list_sessions`, answered with an array of sessions
+//! - `import_session` with `{"session_id"}`, answered with one session
+//!
+//! Sessions are in the generic format of [`GenericSession`]: an `id`, a
+//! `title`, and `messages` with a `role` and `content`. In `csm provider`
+//! commands a plugin provider is named by its plugin ID.
+
+use super::cache::DiscoveredProvider;
+use super::{ChatProvider, GenericSession, ProviderType};
+use crate::models::ChatSession;
+use crate::plugins::{host, PluginManager, PluginState};
+use anyhow::{anyhow, Context, Result};
+use once_cell::sync::OnceCell;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Action telling whether the tool is installed
+pub const DISCOVER: &str = "discover";
+/// Action listing the tool's sessions
+pub const LIST_SESSIONS: &str = "list_sessions";
+/// Action reading one session
+pub const IMPORT_SESSION: &str = "import_session";
+
+/// Actions a provider plugin must declare
+pub const REQUIRED_ACTIONS: &[&str] = &[LIST_SESSIONS, IMPORT_SESSION];
+
+/// A chat provider that asks a plugin for sessions
+pub struct PluginChatProvider {
+    plugin_id: String,
+    name: String,
+    available: bool,
+    sessions_path: Option<PathBuf>,
+    endpoint: Option<String>,
+    plugins_dir: PathBuf,
+    /// Loaded on first use when the provider comes from the cache
+    manager: OnceCell<Arc<PluginManager>>,
+}
+
+impl PluginChatProvider {
+    /// Recreate a provider from a cached discovery result
+    pub fn from_discovered(discovered: &DiscoveredProvider) -> Option<Self> {
+        Some(Self {
+            plugin_id: discovered.plugin.clone()?,
+            name: discovered.name.clone(),
+            available: discovered.available,
+            sessions_path: discovered.path.clone(),
+            endpoint: discovered.endpoint.clone(),
+            plugins_dir: PluginManager::default_dir().ok()?,
+            manager: OnceCell::new(),
+        })
+    }
+
+    fn manager(&self) -> Result<&Arc<PluginManager>> {
+        self.manager.get_or_try_init(|| {
+            let manager =
+                PluginManager::new(self.plugins_dir.clone()).with_consent(host::ask_on_terminal());
+            block_on(manager.discover_plugins())?;
+            Ok(Arc::new(manager))
+        })
+    }
+
+    /// Perform one of the plugin's actions, starting it if needed
+    fn call(&self, action: &str, arguments: Value) -> Result<Value> {
+        let manager = self.manager()?;
+        block_on(async {
+            manager.activate(&self.plugin_id).await?;
+            manager.invoke(&self.plugin_id, action, arguments).await
+        })
+    }
+
+    /// Ask the plugin whether its tool is installed and where
+    fn discover(&mut self) -> Result<()> {
+        let found = self.call(DISCOVER, json!({}))?;
+        self.available = found["available"].as_bool().unwrap_or(true);
+        self.sessions_path = found["sessions_path"]
+            .as_str()
+            .map(|path| crate::config::expand_home(Path::new(path)));
+        self.endpoint = found["endpoint"].as_str().map(str::to_string);
+        Ok(())
+    }
+
+    fn to_session(&self, value: Value) -> Result<ChatSession> {
+        let generic: GenericSession = serde_json::from_value(value)
+            .with_context(|| format!("Plugin {} returned an invalid session", self.plugin_id))?;
+        let mut session = ChatSession::from(generic);
+        if session.responder_username.is_none() {
+            session.responder_username = Some(self.name.clone());
+        }
+        Ok(session)
+    }
+}
+
+impl ChatProvider for PluginChatProvider {
+    fn provider_type(&self) -> ProviderType {
+        ProviderType::Plugin
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_available(&self) -> bool {
+        self.available
+    }
+
+    fn sessions_path(&self) -> Option<PathBuf> {
+        self.sessions_path.clone()
+    }
+
+    fn endpoint(&self) -> Option<&str> {
+        self.endpoint.as_deref()
+    }
+
+    fn plugin_id(&self) -> Option<&str> {
+        Some(&self.plugin_id)
+    }
+
+    fn list_sessions(&self) -> Result<Vec<ChatSession>> {
+        match self.call(LIST_SESSIONS, json!({}))? {
+            Value::Array(sessions) => sessions.into_iter().map(|s| self.to_session(s)).collect(),
+            other => Err(anyhow!(
+                "Plugin {} returned {} instead of a list of sessions",
+                self.plugin_id,
+                other
+            )),
+        }
+    }
+
+    fn import_session(&self, session_id: &str) -> Result<ChatSession> {
+        let session = self.call(IMPORT_SESSION, json!({ "session_id": session_id }))?;
+        self.to_session(session)
+    }
+
+    fn export_session(&self, _session: &ChatSession) -> Result<()> {
+        anyhow::bail!("Plugin providers do not support export")
+    }
+}
+
+/// Providers added by the enabled plugins in the plugins directory
+pub fn discover_plugin_providers() -> Vec<PluginChatProvider> {
+    match PluginManager::default_dir() {
+        Ok(dir) if dir.exists() => discover_in(&dir),
+        _ => Vec::new(),
+    }
+}
+
+/// Providers added by the enabled plugins in `plugins_dir`. A plugin that
+/// fails to start is listed as unavailable.
+pub fn discover_in(plugins_dir: &Path) -> Vec<PluginChatProvider> {
+    let manager =
+        PluginManager::new(plugins_dir.to_path_buf()).with_consent(host::ask_on_terminal());
+    let mut plugins = block_on(async {
+        if let Err(e) = manager.discover_plugins().await {
+            log::warn!("Failed to load plugins: {}", e);
+        }
+        manager.list_plugins().await
+    });
+    plugins.sort_by(|a, b| a.manifest.id.cmp(&b.manifest.id));
+    let manager = Arc::new(manager);
+
+    plugins
+        .into_iter()
+        .filter(|plugin| plugin.state != PluginState::Disabled)
+        .filter_map(|plugin| {
+            let info = plugin.manifest.provider.as_ref()?;
+            let mut provider = PluginChatProvider {
+                plugin_id: plugin.manifest.id.clone(),
+                name: info.name.clone(),
+                available: true,
+                sessions_path: None,
+                endpoint: None,
+                plugins_dir: plugins_dir.to_path_buf(),
+                manager: OnceCell::with_value(manager.clone()),
+            };
+            let discovers = plugin
+                .manifest
+                .capabilities
+                .iter()
+                .any(|c| c.action == DISCOVER);
+            let started = if discovers {
+                provider.discover()
+            } else {
+                block_on(manager.activate(&provider.plugin_id))
+            };
+            if let Err(e) = started {
+                log::warn!("Provider plugin {} failed: {}", provider.plugin_id, e);
+                provider.available = false;
+            }
+            Some(provider)
+        })
+        .collect()
+}
+
+/// Run `future` to completion from synchronous code, on a thread of its own
+/// so that callers already inside a runtime are not blocked by it
+fn block_on<F>(future: F) -> F::Output
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .expect("failed to start a runtime")
+                    .block_on(future)
+            })
+            .join()
+            .expect("plugin call panicked")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plugin_provider() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let plugins_dir = tempfile::tempdir().unwrap();
+        let dir = plugins_dir.path().join("notes");
+        std::fs::create_dir_all(&dir).unwrap();
+        let session = r#"{"id":"s1","title":"First","messages":[{"role":"user","content":"hi"},{"role":"assistant","content":"hello"}]}"#;
+        let script = format!(
+            r#"#!/bin/sh
+read -r request
+case "$request" in
+  *'"action":"discover"'*) echo '{{"available":true,"sessions_path":"/var/notes"}}' ;;
+  *'"action":"list_sessions"'*) echo '[{session}]' ;;
+  *'"session_id":"s1"'*) echo '{session}' ;;
+  *) echo 'no such session' >&2; exit 1 ;;
+esac
+"#
+        );
+        std::fs::write(dir.join("main.sh"), script).unwrap();
+        std::fs::set_permissions(dir.join("main.sh"), std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+        let manifest = json!({
+            "id": "notes", "name": "Notes", "version": "1.0.0", "csm_version": "*",
+            "main": "main.sh", "permissions": [], "hooks": [], "dependencies": [],
+            "category": "provider", "keywords": [],
+            "capabilities": [
+                { "action": "discover" }, { "action": "list_sessions" },
+                { "action": "import_session" }
+            ],
+            "provider": { "name": "Notes App" }
+        });
+        std::fs::write(dir.join("plugin.json"), manifest.to_string()).unwrap();
+
+        let providers = discover_in(plugins_dir.path());
+        assert_eq!(providers.len(), 1);
+        let provider = &providers[0];
+        assert_eq!(provider.name(), "Notes App");
+        assert_eq!(provider.plugin_id(), Some("notes"));
+        assert!(provider.is_available());
+        assert_eq!(provider.sessions_path(), Some(PathBuf::from("/var/notes")));
+
+        let sessions = provider.list_sessions().unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].title(), "First");
+        assert_eq!(sessions[0].requests.len(), 1);
+        assert_eq!(sessions[0].responder_username.as_deref(), Some("Notes App"));
+        let session = provider.import_session("s1").unwrap();
+        assert_eq!(session.session_id.as_deref(), Some("s1"));
+        assert!(provider.import_session("s2").is_err());
+
+        // A provider plugin must answer the provider actions
+        let manifest = json!({
+            "id": "notes", "name": "Notes", "version": "1.0.0", "csm_version": "*",
+            "main": "main.sh", "permissions": [], "hooks": [], "dependencies": [],
+            "category": "provider", "keywords": [],
+            "capabilities": [{ "action": "list_sessions" }],
+            "provider": { "name": "Notes App" }
+        });
+        std::fs::write(dir.join("plugin.json"), manifest.to_string()).unwrap();
+        assert!(discover_in(plugins_dir.path()).is_empty());
+    }
+}
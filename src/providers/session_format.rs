@@ -310,16 +310,20 @@ fn create_request(
 }
 
 /// Extract text from various response formats
-fn extract_response_text(response: &serde_json::Value) -> Option<String> {
+pub(crate) fn extract_response_text(response: &serde_json::Value) -> Option<String> {
     // Try direct text field
     if let Some(text) = response.get("text").and_then(|v| v.as_str()) {
         return Some(text.to_string());
     }
 
-    // Try value array format (VS Code Copilot format)
-    if let Some(value) = response.get("value").and_then(|v| v.as_array()) {
+    // Try value array format (VS Code Copilot format), bare or wrapped
+    let items = response
+        .as_array()
+        .or_else(|| response.get("value").and_then(|v| v.as_array()));
+    if let Some(value) = items {
         let parts: Vec<String> = value
             .iter()
+            .filter(|v| v.get("kind").and_then(|k| k.as_str()) != Some("thinking"))
             .filter_map(|v| v.get("value").and_then(|v| v.as_str()))
             .map(String::from)
             .collect();
@@ -346,7 +350,7 @@ fn format_timestamp(timestamp: i64) -> String {
 
     let dt = Utc.timestamp_millis_opt(timestamp);
     match dt {
-        chrono::LocalResult::Single(dt) => dt.format("%Y-%m-%d %H:%M:%S").to_string(),
+        chrono::LocalResult::Single(dt) => crate::datetime::format_datetime(dt),
         _ => "Invalid".to_string(),
     }
 }
@@ -0,0 +1,696 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Code-review session detection and reviewer reports
+//!
+//! A session is treated as a code review when the prompts ask for a review,
+//! the conversation carries unified diffs, and the assistant mostly inspects
+//! code rather than editing it. Harvest scores every session and tags the
+//! matches `code-review` in `session_tags`, so `csm report reviews` can
+//! summarize them without re-reading the whole database.
+
+use crate::error::Result;
+use crate::models::ChatSession;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Tag applied to detected code-review sessions
+pub const REVIEW_TAG: &str = "code-review";
+
+/// Tag source for tags written by detectors (re-evaluated on every harvest)
+pub const AUTO_SOURCE: &str = "auto";
+
+/// Tag source for tags set by users (never touched by detectors)
+pub const USER_SOURCE: &str = "user";
+
+/// Minimum score for a session to count as a review
+pub const REVIEW_THRESHOLD: u32 = 4;
+
+/// Changed lines before a session counts as diff-heavy
+const MIN_DIFF_LINES: usize = 10;
+const LARGE_DIFF_LINES: usize = 50;
+
+/// Prompts asking for a review of a change
+static REVIEW_PROMPT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)\b(?:review(?:s|ing|er)?|pull request|merge request|lgtm|nitpicks?|changeset)\b|(?-i:\bPRs?\b)",
+    )
+    .unwrap()
+});
+
+/// Links to pull requests or merge requests
+static PULL_REQUEST_URL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"https?://[^\s/]+/([\w.-]+/[\w.-]+)/(?:-/)?(?:pull|merge_requests)/(\d+)").unwrap()
+});
+
+/// Source file paths mentioned in prose
+pub(crate) static FILE_MENTION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?:[\w.@-]+[/\\])*[\w@-][\w.@-]*\.(?:rs|ts|tsx|js|jsx|mjs|py|go|java|kt|kts|c|h|cc|cpp|hpp|cs|rb|php|swift|scala|sql|toml|ya?ml|json|md|sh|vue|svelte|css|scss|html|proto|tf)\b",
+    )
+    .unwrap()
+});
+
+/// Recurring kinds of review feedback, matched against assistant replies
+static THEMES: Lazy<Vec<(&'static str, Regex)>> = Lazy::new(|| {
+    [
+        (
+            "Error handling",
+            r"(?i)\berror handling\b|\.unwrap\(\)|\.expect\(|\bpanics?\b|\bpropagat\w*|\bswallow\w*|\bexceptions?\b|\btry/catch\b",
+        ),
+        (
+            "Naming",
+            r"(?i)\b(?:naming|renam\w*|misleading name|clearer name|(?:variable|function|method) names?)\b",
+        ),
+        (
+            "Tests",
+            r"(?i)\b(?:tests?|testing|test coverage|assertions?|regressions?)\b",
+        ),
+        (
+            "Performance",
+            r"(?i)\b(?:performance|allocat\w*|unnecessary clon\w*|quadratic|inefficien\w*|hot path|n\+1)\b|O\(n\^?2\)",
+        ),
+        (
+            "Security",
+            r"(?i)\b(?:security|injection|sanitiz\w*|secrets?|credentials?|vulnerab\w*|xss|csrf|authori[sz]ation)\b",
+        ),
+        (
+            "Concurrency",
+            r"(?i)\b(?:race conditions?|deadlocks?|mutex\w*|thread[- ]safe\w*|locking|atomics?|concurren\w*)\b",
+        ),
+        (
+            "Edge cases",
+            r"(?i)\b(?:edge cases?|corner cases?|off[- ]by[- ]one|boundary|overflow\w*|empty (?:input|list|string|vec\w*))\b",
+        ),
+        (
+            "Documentation",
+            r"(?i)\b(?:documentation|doc[- ]comments?|docstrings?|readme|changelog|(?:add|missing|outdated|stale) (?:a )?comments?)\b",
+        ),
+        (
+            "Duplication",
+            r"(?i)\b(?:duplicat\w*|repeated code|copy[- ]past\w*|extract (?:a|this into a) (?:helper|function|method))\b",
+        ),
+        (
+            "Readability",
+            r"(?i)\b(?:readab\w*|formatting|code style|style guide|idiomatic|lint\w*|clippy|nit(?:pick)?s?|simplif\w*)\b",
+        ),
+        (
+            "API design",
+            r"(?i)\b(?:public api|api design|breaking changes?|backwards?[- ]compat\w*)\b",
+        ),
+    ]
+    .into_iter()
+    .map(|(name, pattern)| (name, Regex::new(pattern).unwrap()))
+    .collect()
+});
+
+/// Evidence that a session is a code review
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewSignals {
+    /// User prompts asking for a review
+    pub review_prompts: usize,
+    /// Added or removed lines inside diffs
+    pub diff_lines: usize,
+    /// Files named by diff headers
+    pub diff_files: usize,
+    /// Tool calls that inspect code (read, search, changed files)
+    pub read_tools: usize,
+    /// Tool calls that modify files
+    pub edit_tools: usize,
+}
+
+impl ReviewSignals {
+    /// Heuristic score: review prompts weigh most, diffs and read-only tool
+    /// use add to it, and sessions dominated by edits are implementation work.
+    pub fn score(&self) -> u32 {
+        let mut score: u32 = 0;
+        if self.review_prompts > 0 {
+            score += 3;
+        }
+        if self.diff_lines >= LARGE_DIFF_LINES {
+            score += 2;
+        } else if self.diff_lines >= MIN_DIFF_LINES {
+            score += 1;
+        }
+        if self.diff_files > 0 {
+            score += 1;
+        }
+        if self.read_tools > self.edit_tools {
+            score += 1;
+        }
+        if self.edit_tools > 3 && self.edit_tools > self.read_tools * 2 {
+            score = score.saturating_sub(2);
+        }
+        score
+    }
+
+    pub fn is_review(&self) -> bool {
+        self.score() >= REVIEW_THRESHOLD
+    }
+}
+
+/// What a session looked at and said
+#[derive(Debug, Clone, Default)]
+pub struct ReviewAnalysis {
+    pub signals: ReviewSignals,
+    /// Files from diff headers and prose mentions
+    pub files: BTreeSet<String>,
+    /// Feedback themes raised in assistant replies
+    pub themes: Vec<&'static str>,
+    /// Pull/merge request references (`owner/repo#123`)
+    pub pull_requests: BTreeSet<String>,
+}
+
+/// Score a session and collect the files, themes, and pull requests it covers
+pub fn analyze(session: &ChatSession) -> ReviewAnalysis {
+    let mut analysis = ReviewAnalysis::default();
+    let mut diff_files = BTreeSet::new();
+    let mut replies = String::new();
+
+    for request in &session.requests {
+        if let Some(message) = &request.message {
+            let text = message.get_text();
+            if REVIEW_PROMPT.is_match(&text) {
+                analysis.signals.review_prompts += 1;
+            }
+            scan_text(&text, &mut analysis, &mut diff_files);
+        }
+
+        if let Some(response) = &request.response {
+            let mut tools = Vec::new();
+            let text = response_text(response, &mut tools);
+            for tool in tools {
+                match classify_tool(&tool) {
+                    Some(ToolUse::Edit) => analysis.signals.edit_tools += 1,
+                    Some(ToolUse::Read) => analysis.signals.read_tools += 1,
+                    None => {}
+                }
+            }
+            scan_text(&text, &mut analysis, &mut diff_files);
+            replies.push_str(&text);
+            replies.push('\n');
+        }
+    }
+
+    analysis.signals.diff_files = diff_files.len();
+    analysis.files.extend(diff_files);
+    analysis.themes = feedback_themes(&replies);
+    analysis
+}
+
+fn scan_text(text: &str, analysis: &mut ReviewAnalysis, diff_files: &mut BTreeSet<String>) {
+    analysis.signals.diff_lines += scan_diff(text, diff_files);
+    analysis.files.extend(mentioned_files(text));
+    analysis.pull_requests.extend(
+        PULL_REQUEST_URL
+            .captures_iter(text)
+            .map(|c| format!("{}#{}", &c[1], &c[2])),
+    );
+}
+
+/// Count added/removed lines in unified diffs (raw or in ```diff fences)
+/// and collect the file names from their headers.
+pub fn scan_diff(text: &str, files: &mut BTreeSet<String>) -> usize {
+    let mut changed = 0;
+    let mut in_hunk = false;
+    let mut in_fence = false;
+
+    for line in text.lines() {
+        if let Some(lang) = line.trim_start().strip_prefix("```") {
+            in_fence = !in_fence && matches!(lang.trim(), "diff" | "patch");
+            in_hunk = false;
+        } else if let Some(rest) = line.strip_prefix("diff --git ") {
+            if let Some(path) = rest.split_whitespace().nth(1) {
+                files.insert(normalize_file(path));
+            }
+            in_hunk = false;
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            let path = path.split('\t').next().unwrap_or(path).trim();
+            if path != "/dev/null" {
+                files.insert(normalize_file(path));
+            }
+        } else if line.starts_with("--- ") && !in_hunk {
+            // Old-file header
+        } else if line.starts_with("@@") {
+            in_hunk = true;
+        } else if in_hunk || in_fence {
+            match line.as_bytes().first() {
+                Some(b'+') | Some(b'-') => changed += 1,
+                Some(b' ') | Some(b'\\') | None => {}
+                Some(_) => in_hunk = false,
+            }
+        }
+    }
+
+    changed
+}
+
+/// Source file paths mentioned in text
+pub fn mentioned_files(text: &str) -> BTreeSet<String> {
+    FILE_MENTION
+        .find_iter(text)
+        .map(|m| normalize_file(m.as_str()))
+        .collect()
+}
+
+/// Feedback themes raised in `text`, in a fixed order
+pub fn feedback_themes(text: &str) -> Vec<&'static str> {
+    THEMES
+        .iter()
+        .filter(|(_, pattern)| pattern.is_match(text))
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+fn normalize_file(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let path = path.trim_start_matches("./");
+    path.strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path)
+        .to_string()
+}
+
+enum ToolUse {
+    Read,
+    Edit,
+}
+
+fn classify_tool(tool_id: &str) -> Option<ToolUse> {
+    const EDIT: &[&str] = &[
+        "edit", "replace", "insert", "create", "write", "apply", "patch", "delete", "rename",
+    ];
+    const READ: &[&str] = &[
+        "read", "search", "find", "grep", "list", "changed", "diff", "pull", "usages", "errors",
+        "problems", "codebase",
+    ];
+
+    let id = tool_id.to_lowercase();
+    if EDIT.iter().any(|k| id.contains(k)) {
+        Some(ToolUse::Edit)
+    } else if READ.iter().any(|k| id.contains(k)) {
+        Some(ToolUse::Read)
+    } else {
+        None
+    }
+}
+
+/// Text of a response plus the ids of the tools it invoked
+fn response_text(response: &Value, tools: &mut Vec<String>) -> String {
+    match response {
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|item| {
+                let kind = item.get("kind").and_then(Value::as_str).unwrap_or("");
+                match kind {
+                    "toolInvocationSerialized" | "toolInvocation" => {
+                        if let Some(id) = item.get("toolId").and_then(Value::as_str) {
+                            tools.push(id.to_string());
+                        }
+                        None
+                    }
+                    "thinking" => None,
+                    _ => item
+                        .get("value")
+                        .and_then(Value::as_str)
+                        .map(str::to_string),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Value::Object(map) => match map.get("value") {
+            Some(value @ Value::Array(_)) => response_text(value, tools),
+            _ => ["value", "text", "result"]
+                .iter()
+                .find_map(|key| map.get(*key).and_then(Value::as_str))
+                .unwrap_or_default()
+                .to_string(),
+        },
+        Value::String(text) => text.clone(),
+        _ => String::new(),
+    }
+}
+
+// ============================================================================
+// Session tags
+// ============================================================================
+
+/// Create the session tag table in the harvest database if it does not exist
+pub fn ensure_tags_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS session_tags (
+            session_id TEXT NOT NULL,
+            tag TEXT NOT NULL,
+            source TEXT NOT NULL DEFAULT 'auto',
+            created_at INTEGER NOT NULL,
+            PRIMARY KEY (session_id, tag),
+            FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_session_tags_tag ON session_tags(tag);
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Add or remove the automatic `code-review` tag for a session.
+/// Returns whether the session is tagged as a review.
+pub fn sync_review_tag(conn: &Connection, session_id: &str, session: &ChatSession) -> Result<bool> {
+    let is_review = analyze(session).signals.is_review();
+    if is_review {
+        conn.execute(
+            "INSERT OR IGNORE INTO session_tags (session_id, tag, source, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                session_id,
+                REVIEW_TAG,
+                AUTO_SOURCE,
+                Utc::now().timestamp_millis()
+            ],
+        )?;
+    } else {
+        conn.execute(
+            "DELETE FROM session_tags WHERE session_id = ?1 AND tag = ?2 AND source = ?3",
+            params![session_id, REVIEW_TAG, AUTO_SOURCE],
+        )?;
+    }
+    Ok(is_review)
+}
+
+/// Re-run detection over sessions updated since `since`.
+/// Returns the number tagged as reviews.
+pub fn retag_sessions(conn: &Connection, since: DateTime<Utc>) -> Result<usize> {
+    let sessions = {
+        let mut stmt =
+            conn.prepare("SELECT id, session_json FROM sessions WHERE updated_at >= ?1")?;
+        let rows = stmt.query_map([since.timestamp_millis()], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()?
+    };
+
+    let mut tagged = 0;
+    for (id, json) in sessions {
+        let Ok(session) = serde_json::from_str::<ChatSession>(&json) else {
+            continue;
+        };
+        if sync_review_tag(conn, &id, &session)? {
+            tagged += 1;
+        }
+    }
+    Ok(tagged)
+}
+
+/// Replace a session's user tags, leaving detector tags alone.
+/// Returns all of the session's tags afterwards.
+pub fn set_user_tags(conn: &Connection, session_id: &str, tags: &[String]) -> Result<Vec<String>> {
+    ensure_tags_table(conn)?;
+    conn.execute(
+        "DELETE FROM session_tags WHERE session_id = ?1 AND source = ?2",
+        params![session_id, USER_SOURCE],
+    )?;
+    let now = Utc::now().timestamp_millis();
+    for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        conn.execute(
+            "INSERT OR IGNORE INTO session_tags (session_id, tag, source, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![session_id, tag, USER_SOURCE, now],
+        )?;
+    }
+    session_tags(conn, session_id)
+}
+
+/// Tags a user added to a session, in name order
+pub fn user_tags(conn: &Connection, session_id: &str) -> Result<Vec<String>> {
+    ensure_tags_table(conn)?;
+    let mut stmt = conn.prepare(
+        "SELECT tag FROM session_tags WHERE session_id = ?1 AND source = ?2 ORDER BY tag",
+    )?;
+    let tags = stmt.query_map(params![session_id, USER_SOURCE], |row| row.get(0))?;
+    Ok(tags.collect::<std::result::Result<Vec<String>, _>>()?)
+}
+
+/// All tags on a session, in name order
+pub fn session_tags(conn: &Connection, session_id: &str) -> Result<Vec<String>> {
+    ensure_tags_table(conn)?;
+    let mut stmt =
+        conn.prepare("SELECT tag FROM session_tags WHERE session_id = ?1 ORDER BY tag")?;
+    let tags = stmt.query_map([session_id], |row| row.get(0))?;
+    Ok(tags.collect::<std::result::Result<Vec<String>, _>>()?)
+}
+
+// ============================================================================
+// Reviewer report
+// ============================================================================
+
+/// A tagged review session
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewedSession {
+    pub id: String,
+    pub title: String,
+    pub workspace: Option<String>,
+    pub updated_at: DateTime<Utc>,
+    pub pull_requests: Vec<String>,
+    pub files: Vec<String>,
+    pub themes: Vec<String>,
+    pub signals: ReviewSignals,
+}
+
+/// Number of review sessions a theme or file appeared in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Tally {
+    pub name: String,
+    pub sessions: usize,
+}
+
+/// Summary of review sessions in a time window
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewReport {
+    pub since: DateTime<Utc>,
+    pub sessions: Vec<ReviewedSession>,
+    pub themes: Vec<Tally>,
+    pub files: Vec<Tally>,
+}
+
+impl ReviewReport {
+    /// Load tagged review sessions updated since `since`, newest first
+    pub fn load(conn: &Connection, since: DateTime<Utc>) -> Result<Self> {
+        let mut stmt = conn.prepare(
+            "SELECT s.id, s.title, s.workspace_name, s.updated_at, s.session_json
+             FROM sessions s
+             JOIN session_tags t ON t.session_id = s.id
+             WHERE t.tag = ?1 AND s.updated_at >= ?2
+             ORDER BY s.updated_at DESC",
+        )?;
+        let rows = stmt.query_map(params![REVIEW_TAG, since.timestamp_millis()], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })?;
+
+        let mut sessions = Vec::new();
+        for row in rows {
+            let (id, title, workspace, updated_at, json) = row?;
+            let Ok(session) = serde_json::from_str::<ChatSession>(&json) else {
+                continue;
+            };
+            let analysis = analyze(&session);
+            sessions.push(ReviewedSession {
+                id,
+                title,
+                workspace,
+                updated_at: DateTime::from_timestamp_millis(updated_at).unwrap_or_default(),
+                pull_requests: analysis.pull_requests.into_iter().collect(),
+                files: analysis.files.into_iter().collect(),
+                themes: analysis.themes.iter().map(|t| t.to_string()).collect(),
+                signals: analysis.signals,
+            });
+        }
+
+        Ok(Self::from_sessions(since, sessions))
+    }
+
+    /// Tally themes and files across sessions, most frequent first
+    pub fn from_sessions(since: DateTime<Utc>, sessions: Vec<ReviewedSession>) -> Self {
+        let themes = tally(sessions.iter().flat_map(|s| s.themes.iter()));
+        let files = tally(sessions.iter().flat_map(|s| s.files.iter()));
+        Self {
+            since,
+            sessions,
+            themes,
+            files,
+        }
+    }
+}
+
+fn tally<'a>(names: impl Iterator<Item = &'a String>) -> Vec<Tally> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for name in names {
+        *counts.entry(name.as_str()).or_default() += 1;
+    }
+    let mut tallies: Vec<Tally> = counts
+        .into_iter()
+        .map(|(name, sessions)| Tally {
+            name: name.to_string(),
+            sessions,
+        })
+        .collect();
+    tallies.sort_by(|a, b| {
+        b.sessions
+            .cmp(&a.sessions)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    tallies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const DIFF: &str = "diff --git a/src/parser.rs b/src/parser.rs
+--- a/src/parser.rs
++++ b/src/parser.rs
+@@ -10,7 +10,9 @@ fn parse(input: &str) -> Token {
+     let mut chars = input.chars();
+-    let first = chars.next().unwrap();
++    let first = match chars.next() {
++        Some(c) => c,
++        None => return Token::Eof,
++    };
+     match first {
+-        '0'..='9' => number(chars),
++        '0'..='9' => number(first, chars),
+         _ => ident(chars),
+     }
+ }
+-// TODO
+-// TODO
+-// TODO
+-// TODO
+";
+
+    fn session(requests: Vec<Value>) -> ChatSession {
+        serde_json::from_value(json!({ "requests": requests })).unwrap()
+    }
+
+    fn review_session() -> ChatSession {
+        session(vec![json!({
+            "message": { "text": format!("Can you review this PR? https://github.com/acme/lexer/pull/42\n{DIFF}") },
+            "response": [
+                { "kind": "toolInvocationSerialized", "toolId": "copilot_readFile" },
+                { "kind": "toolInvocationSerialized", "toolId": "copilot_getChangedFiles" },
+                { "value": "Looks good overall. The `.unwrap()` in src/parser.rs could panic on empty input, and there is no test for it." },
+            ],
+        })])
+    }
+
+    #[test]
+    fn test_scan_diff() {
+        let mut files = BTreeSet::new();
+        assert_eq!(scan_diff(DIFF, &mut files), 11);
+        assert_eq!(files.into_iter().collect::<Vec<_>>(), vec!["src/parser.rs"]);
+
+        let mut files = BTreeSet::new();
+        let fenced = "Here:\n```diff\n-old line\n+new line\n```\n- a markdown bullet";
+        assert_eq!(scan_diff(fenced, &mut files), 2);
+        assert!(files.is_empty());
+    }
+
+    #[test]
+    fn test_detects_review_session() {
+        let analysis = analyze(&review_session());
+        assert_eq!(analysis.signals.review_prompts, 1);
+        assert_eq!(analysis.signals.diff_files, 1);
+        assert_eq!(analysis.signals.read_tools, 2);
+        assert!(analysis.signals.is_review());
+        assert!(analysis.files.contains("src/parser.rs"));
+        assert_eq!(
+            analysis.pull_requests.into_iter().collect::<Vec<_>>(),
+            vec!["acme/lexer#42"]
+        );
+        assert_eq!(
+            analysis.themes,
+            vec!["Error handling", "Tests", "Edge cases"]
+        );
+    }
+
+    #[test]
+    fn test_ignores_implementation_session() {
+        let edits: Vec<Value> = (0..5)
+            .map(|_| json!({ "kind": "toolInvocationSerialized", "toolId": "copilot_replaceString" }))
+            .collect();
+        let analysis = analyze(&session(vec![json!({
+            "message": { "text": "Add a review queue to the dashboard" },
+            "response": edits,
+        })]));
+        assert_eq!(analysis.signals.edit_tools, 5);
+        assert!(!analysis.signals.is_review());
+
+        let question = analyze(&session(vec![json!({
+            "message": { "text": "How do I reverse a string in Rust?" },
+            "response": [{ "value": "Use `s.chars().rev().collect::<String>()`." }],
+        })]));
+        assert_eq!(question.signals.score(), 0);
+    }
+
+    #[test]
+    fn test_review_tag_and_report() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE sessions (id TEXT PRIMARY KEY, title TEXT NOT NULL,
+             workspace_name TEXT, updated_at INTEGER NOT NULL, session_json TEXT NOT NULL);",
+        )
+        .unwrap();
+        ensure_tags_table(&conn).unwrap();
+
+        let now = Utc::now();
+        let review = review_session();
+        let chat = session(vec![json!({ "message": { "text": "hello" } })]);
+        for (id, s) in [("r1", &review), ("r2", &review), ("c1", &chat)] {
+            conn.execute(
+                "INSERT INTO sessions VALUES (?1, ?1, NULL, ?2, ?3)",
+                params![
+                    id,
+                    now.timestamp_millis(),
+                    serde_json::to_string(s).unwrap()
+                ],
+            )
+            .unwrap();
+        }
+
+        assert_eq!(
+            retag_sessions(&conn, now - chrono::Duration::days(1)).unwrap(),
+            2
+        );
+        // Re-detection drops an automatic tag that no longer applies
+        assert!(!sync_review_tag(&conn, "r2", &chat).unwrap());
+
+        let report = ReviewReport::load(&conn, now - chrono::Duration::days(1)).unwrap();
+        assert_eq!(report.sessions.len(), 1);
+        assert_eq!(report.sessions[0].id, "r1");
+        assert_eq!(
+            report.files,
+            vec![Tally {
+                name: "src/parser.rs".to_string(),
+                sessions: 1
+            }]
+        );
+        assert_eq!(report.themes[0].name, "Edge cases");
+
+        let later = ReviewReport::load(&conn, now + chrono::Duration::days(1)).unwrap();
+        assert!(later.sessions.is_empty());
+    }
+}
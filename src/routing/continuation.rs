@@ -4,12 +4,26 @@
 //!
 //! Enables seamless continuation of conversations when switching between
 //! different AI providers or models.
+//!
+//! `csm continue` loads a harvested session into a [`ConversationContext`],
+//! folds its oldest messages into a summary until the rest fit the new
+//! model's context window ([`ConversationContext::compress`]), and sends
+//! what is left ([`ConversationContext::carried_messages`]) ahead of the next
+//! prompt. Each message keeps the provider and model that wrote it.
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
+use super::model_router::{estimate_tokens, ModelRouter};
+
+/// Context window assumed for models the router has no profile of
+pub const DEFAULT_CONTEXT_WINDOW: usize = 8192;
+
+/// Characters of each folded user message quoted in a summary
+const SUMMARY_EXCERPT_CHARS: usize = 200;
+
 // ============================================================================
 // Conversation State
 // ============================================================================
@@ -39,6 +53,29 @@ pub struct NormalizedMessage {
     pub metadata: HashMap<String, serde_json::Value>,
 }
 
+impl NormalizedMessage {
+    /// A text message written by `source_model` of `source_provider`
+    pub fn new(
+        role: MessageRole,
+        content: &str,
+        source_provider: &str,
+        source_model: Option<&str>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            role,
+            content: content.to_string(),
+            source_provider: source_provider.to_string(),
+            source_model: source_model.map(String::from),
+            attachments: vec![],
+            tool_calls: vec![],
+            token_count: None,
+            timestamp: Utc::now(),
+            metadata: HashMap::new(),
+        }
+    }
+}
+
 /// Message role
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -164,6 +201,188 @@ pub struct ProviderSwitch {
     pub switched_at: DateTime<Utc>,
 }
 
+impl ConversationContext {
+    /// An empty conversation
+    pub fn new(title: &str, system_prompt: Option<&str>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            title: title.to_string(),
+            system_prompt: system_prompt.map(String::from),
+            messages: vec![],
+            summary: None,
+            tools: vec![],
+            provider_history: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// Tokens the conversation takes up as [`Self::carried_messages`] sends it
+    pub fn estimate_tokens(&self) -> usize {
+        let system = self.system_prompt.as_deref().map_or(0, estimate_tokens);
+        let summary = self.summary.as_ref().map_or(0, |s| estimate_tokens(&s.text));
+        system
+            + summary
+            + self
+                .messages
+                .iter()
+                .map(|m| estimate_tokens(&m.content))
+                .sum::<usize>()
+    }
+
+    /// Fold the oldest messages into the summary until the conversation fits
+    /// in `budget` tokens. A quarter of the budget is left to the summary; the
+    /// latest message is always kept. Returns how many messages were folded.
+    pub fn compress(&mut self, budget: usize) -> usize {
+        if self.estimate_tokens() <= budget {
+            return 0;
+        }
+        let system = self.system_prompt.as_deref().map_or(0, estimate_tokens);
+        let room = (budget - budget / 4).saturating_sub(system);
+        let mut kept_tokens = 0;
+        let mut keep = 0;
+        for message in self.messages.iter().rev() {
+            kept_tokens += estimate_tokens(&message.content);
+            if keep > 0 && kept_tokens > room {
+                break;
+            }
+            keep += 1;
+        }
+        let folded: Vec<NormalizedMessage> = self
+            .messages
+            .drain(..self.messages.len() - keep)
+            .collect();
+        if folded.is_empty() {
+            return 0;
+        }
+        self.summary = Some(summarize(self.summary.take(), &folded, budget / 4));
+        self.updated_at = Utc::now();
+        folded.len()
+    }
+
+    /// What to send the next model: the summary of folded messages, as a
+    /// user message, followed by the messages that were kept
+    pub fn carried_messages(&self) -> Vec<NormalizedMessage> {
+        let summary = self.summary.as_ref().map(|summary| NormalizedMessage {
+            timestamp: summary.generated_at,
+            ..NormalizedMessage::new(MessageRole::User, &summary.text, "csm", None)
+        });
+        summary
+            .into_iter()
+            .chain(self.messages.iter().cloned())
+            .collect()
+    }
+
+    /// Record that the conversation moves on with `to_model` of `to_provider`
+    pub fn switch_to(&mut self, to_provider: &str, to_model: Option<&str>, reason: Option<&str>) {
+        // The first switch is away from whoever wrote the last message
+        let (from_provider, from_model) = match self.provider_history.last() {
+            Some(last) => (last.to_provider.clone(), last.to_model.clone()),
+            None => self
+                .messages
+                .iter()
+                .rev()
+                .find(|m| m.role == MessageRole::Assistant)
+                .map(|m| (m.source_provider.clone(), m.source_model.clone()))
+                .unwrap_or_default(),
+        };
+        self.provider_history.push(ProviderSwitch {
+            from_provider,
+            from_model,
+            to_provider: to_provider.to_string(),
+            to_model: to_model.map(String::from),
+            reason: reason.map(String::from),
+            switched_at: Utc::now(),
+        });
+        self.updated_at = Utc::now();
+    }
+}
+
+/// `previous` extended with what the user asked in `folded`, quoting the
+/// latest requests that fit in `budget` tokens
+fn summarize(
+    previous: Option<ConversationSummary>,
+    folded: &[NormalizedMessage],
+    budget: usize,
+) -> ConversationSummary {
+    let mut goals = previous.map(|s| s.goals).unwrap_or_default();
+    goals.extend(
+        folded
+            .iter()
+            .filter(|m| m.role == MessageRole::User)
+            .map(|m| excerpt(&m.content)),
+    );
+    let mut sources: Vec<String> = folded
+        .iter()
+        .filter(|m| m.role == MessageRole::Assistant)
+        .map(|m| match &m.source_model {
+            Some(model) => format!("{}/{}", m.source_provider, model),
+            None => m.source_provider.clone(),
+        })
+        .collect();
+    sources.sort();
+    sources.dedup();
+
+    let header = format!(
+        "Earlier in this conversation{}, the user asked, in order:",
+        if sources.is_empty() {
+            String::new()
+        } else {
+            format!(" (answered by {})", sources.join(", "))
+        }
+    );
+    let mut used = estimate_tokens(&header);
+    let mut quoted: Vec<&String> = Vec::new();
+    for goal in goals.iter().rev() {
+        used += estimate_tokens(goal) + 1;
+        if used > budget {
+            break;
+        }
+        quoted.push(goal);
+    }
+    let mut text = header;
+    if quoted.len() < goals.len() {
+        text.push_str(&format!("\n- ({} earlier requests)", goals.len() - quoted.len()));
+    }
+    for goal in quoted.iter().rev() {
+        text.push_str("\n- ");
+        text.push_str(goal);
+    }
+
+    ConversationSummary {
+        text,
+        topics: vec![],
+        entities: vec![],
+        goals,
+        up_to_message_id: folded.last().map(|m| m.id).unwrap_or(Uuid::nil()),
+        generated_at: Utc::now(),
+    }
+}
+
+/// The first line of `content`, cut to [`SUMMARY_EXCERPT_CHARS`]
+fn excerpt(content: &str) -> String {
+    let line = content.trim().lines().next().unwrap_or_default();
+    if line.chars().count() > SUMMARY_EXCERPT_CHARS {
+        let cut: String = line.chars().take(SUMMARY_EXCERPT_CHARS).collect();
+        format!("{}...", cut.trim_end())
+    } else {
+        line.to_string()
+    }
+}
+
+/// Context window of `model`, named by ID, optionally after `provider/`;
+/// [`DEFAULT_CONTEXT_WINDOW`] for models the router does not know
+pub fn context_window(model: &str) -> usize {
+    let router = ModelRouter::new();
+    let id = model.split_once('/').map_or(model, |(_, id)| id);
+    router
+        .models()
+        .iter()
+        .find(|m| m.model_id == id || m.model_id == model)
+        .map_or(DEFAULT_CONTEXT_WINDOW, |m| m.context_window)
+}
+
 // ============================================================================
 // Provider Adapters
 // ============================================================================
@@ -499,19 +718,8 @@ impl ContinuationManager {
 
     /// Create a new conversation context
     pub fn create_context(&mut self, title: &str, system_prompt: Option<&str>) -> Uuid {
-        let id = Uuid::new_v4();
-        let context = ConversationContext {
-            id,
-            title: title.to_string(),
-            system_prompt: system_prompt.map(String::from),
-            messages: vec![],
-            summary: None,
-            tools: vec![],
-            provider_history: vec![],
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-            metadata: HashMap::new(),
-        };
+        let context = ConversationContext::new(title, system_prompt);
+        let id = context.id;
         self.contexts.insert(id, context);
         id
     }
@@ -538,18 +746,7 @@ impl ContinuationManager {
         let context = self.contexts.get_mut(&context_id)?;
         let adapter = self.adapters.get(to_provider)?;
 
-        // Record the switch
-        let last_provider = context.provider_history.last();
-        let switch = ProviderSwitch {
-            from_provider: last_provider.map(|p| p.to_provider.clone()).unwrap_or_default(),
-            from_model: last_provider.and_then(|p| p.to_model.clone()),
-            to_provider: to_provider.to_string(),
-            to_model: to_model.map(String::from),
-            reason: reason.map(String::from),
-            switched_at: Utc::now(),
-        };
-        context.provider_history.push(switch);
-        context.updated_at = Utc::now();
+        context.switch_to(to_provider, to_model, reason);
 
         // Convert to new provider format
         Some(adapter.to_provider_format(context))
@@ -677,4 +874,40 @@ mod tests {
         assert_eq!(context.provider_history.len(), 1);
         assert_eq!(context.provider_history[0].to_provider, "anthropic");
     }
+
+    #[test]
+    fn test_compress_and_carry() {
+        let mut context = ConversationContext::new("Parser", None);
+        for i in 0..20 {
+            let question = format!("Question {} about the parser {}", i, "detail ".repeat(40));
+            context.messages.push(NormalizedMessage::new(MessageRole::User, &question, "copilot", None));
+            let answer = "answer ".repeat(60);
+            context.messages.push(NormalizedMessage::new(MessageRole::Assistant, &answer, "copilot", Some("gpt-4o")));
+        }
+        let before = context.estimate_tokens();
+        let folded = context.compress(1000);
+        assert!(folded > 0 && folded < 40);
+        assert!(context.estimate_tokens() <= 1000);
+        assert!(context.estimate_tokens() < before);
+
+        let summary = context.summary.as_ref().unwrap();
+        assert!(summary.text.contains("copilot/gpt-4o"));
+        assert_eq!(summary.goals.len(), folded.div_ceil(2));
+        // The latest requests are quoted; older ones are only counted
+        assert!(summary.text.contains("earlier requests"));
+
+        let carried = context.carried_messages();
+        assert_eq!(carried.len(), 40 - folded + 1);
+        assert_eq!(carried[0].source_provider, "csm");
+        assert_eq!(carried.last().unwrap().source_model.as_deref(), Some("gpt-4o"));
+
+        // Nothing to fold when it already fits
+        assert_eq!(context.compress(1000), 0);
+
+        context.switch_to("ollama", Some("llama3"), None);
+        let switch = &context.provider_history[0];
+        assert_eq!((switch.from_provider.as_str(), switch.from_model.as_deref()), ("copilot", Some("gpt-4o")));
+        assert_eq!(context_window("ollama/qwen2.5-coder:32b"), 32000);
+        assert_eq!(context_window("llama3"), DEFAULT_CONTEXT_WINDOW);
+    }
 }
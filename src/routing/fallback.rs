@@ -0,0 +1,341 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Fallback chains
+//!
+//! When the provider a request went to cannot be reached, or answers with a
+//! status worth retrying (408, 429, 5xx), the request is sent on along a
+//! configured chain, for example vLLM, then Ollama, then OpenAI, until one
+//! answers or `max_attempts` providers have been tried. A provider that
+//! keeps failing has its circuit opened and is skipped for a cooldown, after
+//! which one trial request decides whether it is back.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::config::FallbackConfig;
+use crate::error::{CsmError, Result};
+
+/// One step of a fallback chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FallbackTarget {
+    pub provider: String,
+    /// `None` lets the caller pick a model the provider serves
+    pub model: Option<String>,
+}
+
+impl FallbackTarget {
+    /// Parse `provider` or `provider/model`
+    pub fn parse(spec: &str) -> Option<Self> {
+        let spec = spec.trim();
+        let (provider, model) = match spec.split_once('/') {
+            Some((provider, model)) => (provider, Some(model.trim())),
+            None => (spec, None),
+        };
+        let provider = provider.trim().to_lowercase();
+        if provider.is_empty() || model == Some("") {
+            return None;
+        }
+        Some(Self {
+            provider,
+            model: model.map(str::to_string),
+        })
+    }
+}
+
+impl fmt::Display for FallbackTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.model {
+            Some(model) => write!(f, "{}/{}", self.provider, model),
+            None => write!(f, "{}", self.provider),
+        }
+    }
+}
+
+/// The chain, how far along it a request may go, and when circuits open
+#[derive(Debug, Clone, PartialEq)]
+pub struct FallbackPolicy {
+    /// Tried in order after the provider a request was routed to
+    pub chain: Vec<FallbackTarget>,
+    /// Most providers one request is sent to, the first included
+    pub max_attempts: usize,
+    /// Failures in a row that open a provider's circuit
+    pub breaker_failures: u32,
+    /// How long an open circuit skips its provider
+    pub breaker_cooldown: Duration,
+}
+
+impl Default for FallbackPolicy {
+    fn default() -> Self {
+        Self {
+            chain: Vec::new(),
+            max_attempts: 3,
+            breaker_failures: 3,
+            breaker_cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+impl FallbackPolicy {
+    /// The policy set in a `fallback` config section
+    pub fn from_config(config: &FallbackConfig) -> Result<Self> {
+        let defaults = Self::default();
+        let chain = config
+            .chain
+            .iter()
+            .map(|spec| {
+                FallbackTarget::parse(spec).ok_or_else(|| {
+                    CsmError::ConfigError(format!(
+                        "Invalid fallback '{}'; use provider or provider/model",
+                        spec
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if config.max_attempts == Some(0) {
+            return Err(CsmError::ConfigError(
+                "fallback max_attempts must be at least 1".to_string(),
+            ));
+        }
+        if config.breaker_failures == Some(0) {
+            return Err(CsmError::ConfigError(
+                "fallback breaker_failures must be at least 1".to_string(),
+            ));
+        }
+        Ok(Self {
+            chain,
+            max_attempts: config.max_attempts.unwrap_or(defaults.max_attempts),
+            breaker_failures: config.breaker_failures.unwrap_or(defaults.breaker_failures),
+            breaker_cooldown: config
+                .breaker_cooldown_secs
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.breaker_cooldown),
+        })
+    }
+
+    /// The chain steps to try after `primary`, leaving out ones that name
+    /// the primary again or repeat an earlier step. Callers drop the steps
+    /// they cannot serve, then keep `max_attempts` in all.
+    pub fn plan(&self, primary: &FallbackTarget) -> Vec<FallbackTarget> {
+        let mut tried = vec![primary.clone()];
+        let mut plan = Vec::new();
+        for target in &self.chain {
+            let repeated = tried.iter().any(|t| {
+                t.provider == target.provider && (target.model.is_none() || target.model == t.model)
+            });
+            if repeated {
+                continue;
+            }
+            tried.push(target.clone());
+            plan.push(target.clone());
+        }
+        plan
+    }
+}
+
+/// Whether a provider's answer with this status is worth trying elsewhere
+pub fn is_retryable_status(status: u16) -> bool {
+    status == 408 || status == 429 || (500..600).contains(&status)
+}
+
+/// Whether requests may go to a provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    /// Requests go through
+    Closed,
+    /// Failing; skipped until the cooldown ends
+    Open,
+    /// Cooldown over; one trial request is on its way
+    HalfOpen,
+}
+
+#[derive(Debug, Clone)]
+struct Breaker {
+    state: BreakerState,
+    consecutive_failures: u32,
+    /// When the circuit opened, or when its trial request went out
+    since: Instant,
+}
+
+/// The circuit of every provider requests have failed on
+#[derive(Debug, Clone)]
+pub struct CircuitBreakers {
+    failures_to_open: u32,
+    cooldown: Duration,
+    breakers: HashMap<String, Breaker>,
+}
+
+impl Default for CircuitBreakers {
+    fn default() -> Self {
+        let policy = FallbackPolicy::default();
+        Self::new(policy.breaker_failures, policy.breaker_cooldown)
+    }
+}
+
+impl CircuitBreakers {
+    pub fn new(failures_to_open: u32, cooldown: Duration) -> Self {
+        Self {
+            failures_to_open,
+            cooldown,
+            breakers: HashMap::new(),
+        }
+    }
+
+    /// Change the thresholds, keeping every circuit's state
+    pub fn set_limits(&mut self, failures_to_open: u32, cooldown: Duration) {
+        self.failures_to_open = failures_to_open;
+        self.cooldown = cooldown;
+    }
+
+    /// Whether a request may go to `provider` now
+    pub fn allow(&mut self, provider: &str) -> bool {
+        self.allow_at(provider, Instant::now())
+    }
+
+    /// Whether a request may go to `provider` at `now`. Once an open
+    /// circuit's cooldown is over, one request is let through as a trial;
+    /// a trial that never reports back is given up on after another
+    /// cooldown.
+    pub fn allow_at(&mut self, provider: &str, now: Instant) -> bool {
+        let cooldown = self.cooldown;
+        let Some(breaker) = self.breakers.get_mut(provider) else {
+            return true;
+        };
+        match breaker.state {
+            BreakerState::Closed => true,
+            BreakerState::Open | BreakerState::HalfOpen
+                if now.duration_since(breaker.since) >= cooldown =>
+            {
+                breaker.state = BreakerState::HalfOpen;
+                breaker.since = now;
+                true
+            }
+            BreakerState::Open | BreakerState::HalfOpen => false,
+        }
+    }
+
+    /// `provider` answered
+    pub fn record_success(&mut self, provider: &str) {
+        self.breakers.remove(provider);
+    }
+
+    /// A request to `provider` failed
+    pub fn record_failure(&mut self, provider: &str) {
+        self.record_failure_at(provider, Instant::now());
+    }
+
+    pub fn record_failure_at(&mut self, provider: &str, now: Instant) {
+        let failures_to_open = self.failures_to_open;
+        let breaker = self
+            .breakers
+            .entry(provider.to_string())
+            .or_insert_with(|| Breaker {
+                state: BreakerState::Closed,
+                consecutive_failures: 0,
+                since: now,
+            });
+        breaker.consecutive_failures += 1;
+        // A failed trial opens the circuit again at once
+        if breaker.state == BreakerState::HalfOpen
+            || breaker.consecutive_failures >= failures_to_open
+        {
+            breaker.state = BreakerState::Open;
+            breaker.since = now;
+        }
+    }
+
+    pub fn state(&self, provider: &str) -> BreakerState {
+        self.breakers
+            .get(provider)
+            .map_or(BreakerState::Closed, |b| b.state)
+    }
+}
+
+/// A provider a request was sent to, or skipped, before another served it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attempt {
+    /// `provider/model`
+    pub target: String,
+    pub error: String,
+}
+
+/// `target: error` of each attempt, for logs and headers
+pub fn describe_attempts(attempts: &[Attempt]) -> String {
+    attempts
+        .iter()
+        .map(|a| format!("{}: {}", a.target, a.error))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_and_breakers() {
+        let policy = FallbackPolicy::from_config(&FallbackConfig {
+            chain: vec![
+                "vllm".into(),
+                "ollama/llama3.2".into(),
+                "openai/gpt-4o-mini".into(),
+            ],
+            max_attempts: Some(2),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(policy.max_attempts, 2);
+        let primary = FallbackTarget::parse("vllm/meta-llama/Llama-3-8B").unwrap();
+        assert_eq!(primary.model.as_deref(), Some("meta-llama/Llama-3-8B"));
+        // The primary is not tried twice
+        let plan: Vec<String> = policy
+            .plan(&primary)
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        assert_eq!(plan, ["ollama/llama3.2", "openai/gpt-4o-mini"]);
+        // Another model of the same provider is a step of its own
+        let primary = FallbackTarget::parse("openai/gpt-4o").unwrap();
+        let plan: Vec<String> = policy
+            .plan(&primary)
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        assert_eq!(plan, ["vllm", "ollama/llama3.2", "openai/gpt-4o-mini"]);
+        let primary = FallbackTarget::parse("ollama/llama3.2").unwrap();
+        assert_eq!(policy.plan(&primary).len(), 2);
+        assert!(FallbackPolicy::from_config(&FallbackConfig {
+            max_attempts: Some(0),
+            ..Default::default()
+        })
+        .is_err());
+
+        assert!(is_retryable_status(503) && is_retryable_status(429));
+        assert!(!is_retryable_status(400) && !is_retryable_status(401));
+
+        let start = Instant::now();
+        let mut breakers = CircuitBreakers::new(2, Duration::from_secs(30));
+        breakers.record_failure_at("vllm", start);
+        assert!(breakers.allow_at("vllm", start));
+        breakers.record_failure_at("vllm", start);
+        assert_eq!(breakers.state("vllm"), BreakerState::Open);
+        assert!(!breakers.allow_at("vllm", start + Duration::from_secs(10)));
+
+        // One trial after the cooldown; failing it opens the circuit again
+        let later = start + Duration::from_secs(30);
+        assert!(breakers.allow_at("vllm", later));
+        assert_eq!(breakers.state("vllm"), BreakerState::HalfOpen);
+        assert!(!breakers.allow_at("vllm", later));
+        breakers.record_failure_at("vllm", later);
+        assert_eq!(breakers.state("vllm"), BreakerState::Open);
+        assert!(!breakers.allow_at("vllm", later + Duration::from_secs(1)));
+
+        let again = later + Duration::from_secs(30);
+        assert!(breakers.allow_at("vllm", again));
+        breakers.record_success("vllm");
+        assert_eq!(breakers.state("vllm"), BreakerState::Closed);
+        assert!(breakers.allow_at("vllm", again));
+    }
+}
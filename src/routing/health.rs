@@ -0,0 +1,254 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Provider health for routing
+//!
+//! Probes of each provider, and failed requests to it, feed a
+//! [`HealthTracker`], which tells the [`ModelRouter`] which providers are
+//! down and how slow the rest answer. States change with hysteresis: a
+//! provider goes down after several failures in a row and comes back after
+//! several successes, and is slow until its latency falls well under the
+//! limit that made it slow, so one bad probe does not flip routing back and
+//! forth.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::model_router::ModelRouter;
+
+/// Weight of the newest probe in the latency average
+const LATENCY_SMOOTHING: f64 = 0.3;
+
+/// Share of `slow_ms` the average has to fall under for a slow provider to
+/// count as up again
+const RECOVERY_FACTOR: f64 = 0.75;
+
+/// Whether requests should go to a provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderState {
+    /// Answering in time; also providers not probed yet
+    Up,
+    /// Answering, but slower than `slow_ms`; used when nothing else can serve
+    Slow,
+    /// Failing; never routed to
+    Down,
+}
+
+/// When providers change state
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HealthPolicy {
+    /// Failures in a row that take a provider down
+    pub failures_to_down: u32,
+    /// Successes in a row that bring it back
+    pub successes_to_up: u32,
+    /// Average probe latency above which a provider is slow
+    pub slow_ms: u64,
+}
+
+impl Default for HealthPolicy {
+    fn default() -> Self {
+        Self {
+            failures_to_down: 2,
+            successes_to_up: 2,
+            slow_ms: 2000,
+        }
+    }
+}
+
+/// What the probes found out about one provider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderHealth {
+    pub provider: String,
+    pub state: ProviderState,
+    /// Smoothed latency of successful probes
+    pub latency_ms: Option<f64>,
+    pub consecutive_failures: u32,
+    pub consecutive_successes: u32,
+    pub probes: u64,
+    pub failures: u64,
+    /// Why the last failure failed
+    pub last_error: Option<String>,
+    pub checked_at: Option<DateTime<Utc>>,
+}
+
+impl ProviderHealth {
+    /// A provider no probe has reached yet
+    pub fn new(provider: &str) -> Self {
+        Self {
+            provider: provider.to_string(),
+            state: ProviderState::Up,
+            latency_ms: None,
+            consecutive_failures: 0,
+            consecutive_successes: 0,
+            probes: 0,
+            failures: 0,
+            last_error: None,
+            checked_at: None,
+        }
+    }
+
+    /// 0 (down) to 1 (up and answering instantly); slow providers score at
+    /// most 0.5
+    pub fn score(&self, policy: &HealthPolicy) -> f64 {
+        let speed = match self.latency_ms {
+            Some(ms) => 1.0 - (ms / (2.0 * policy.slow_ms as f64)).min(1.0),
+            None => 1.0,
+        };
+        match self.state {
+            ProviderState::Up => speed,
+            ProviderState::Slow => speed.min(0.5),
+            ProviderState::Down => 0.0,
+        }
+    }
+
+    /// Share (0-1) of probes that failed
+    pub fn failure_rate(&self) -> f64 {
+        if self.probes == 0 {
+            0.0
+        } else {
+            self.failures as f64 / self.probes as f64
+        }
+    }
+}
+
+/// Health of every provider the router may use
+#[derive(Debug, Clone, Default)]
+pub struct HealthTracker {
+    policy: HealthPolicy,
+    providers: BTreeMap<String, ProviderHealth>,
+}
+
+impl HealthTracker {
+    pub fn new(policy: HealthPolicy) -> Self {
+        Self {
+            policy,
+            providers: BTreeMap::new(),
+        }
+    }
+
+    pub fn policy(&self) -> &HealthPolicy {
+        &self.policy
+    }
+
+    /// Change the thresholds, keeping what was measured
+    pub fn set_policy(&mut self, policy: HealthPolicy) {
+        self.policy = policy;
+    }
+
+    /// A probe of `provider` answered after `latency_ms`
+    pub fn record_success(&mut self, provider: &str, latency_ms: u64) {
+        let policy = self.policy;
+        let health = self.entry(provider);
+        health.consecutive_failures = 0;
+        health.consecutive_successes += 1;
+        let latency = match health.latency_ms {
+            // Start over after an outage, so old latencies do not linger
+            Some(avg) if health.state != ProviderState::Down => {
+                avg + LATENCY_SMOOTHING * (latency_ms as f64 - avg)
+            }
+            _ => latency_ms as f64,
+        };
+        health.latency_ms = Some(latency);
+
+        let slow = policy.slow_ms as f64;
+        health.state = match health.state {
+            ProviderState::Down if health.consecutive_successes < policy.successes_to_up => {
+                ProviderState::Down
+            }
+            ProviderState::Slow if latency >= slow * RECOVERY_FACTOR => ProviderState::Slow,
+            _ if latency > slow => ProviderState::Slow,
+            _ => ProviderState::Up,
+        };
+    }
+
+    /// A probe of, or request to, `provider` failed
+    pub fn record_failure(&mut self, provider: &str, error: &str) {
+        let policy = self.policy;
+        let health = self.entry(provider);
+        health.failures += 1;
+        health.consecutive_successes = 0;
+        health.consecutive_failures += 1;
+        health.last_error = Some(error.to_string());
+        if health.consecutive_failures >= policy.failures_to_down {
+            health.state = ProviderState::Down;
+        }
+    }
+
+    fn entry(&mut self, provider: &str) -> &mut ProviderHealth {
+        let health = self
+            .providers
+            .entry(provider.to_string())
+            .or_insert_with(|| ProviderHealth::new(provider));
+        health.probes += 1;
+        health.checked_at = Some(Utc::now());
+        health
+    }
+
+    pub fn get(&self, provider: &str) -> Option<&ProviderHealth> {
+        self.providers.get(provider)
+    }
+
+    /// Every provider probed so far, by name
+    pub fn providers(&self) -> impl Iterator<Item = &ProviderHealth> {
+        self.providers.values()
+    }
+
+    /// Tell `router` which providers are down or slow and how long they take
+    pub fn apply(&self, router: &mut ModelRouter) {
+        for health in self.providers.values() {
+            router.set_provider_health(
+                &health.provider,
+                health.state,
+                health.latency_ms.map(|ms| ms.round() as u32),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hysteresis() {
+        let mut tracker = HealthTracker::new(HealthPolicy {
+            failures_to_down: 2,
+            successes_to_up: 2,
+            slow_ms: 1000,
+        });
+        let state = |t: &HealthTracker| t.get("openai").unwrap().state;
+
+        // One failure is not an outage, two in a row are
+        tracker.record_success("openai", 200);
+        tracker.record_failure("openai", "timeout");
+        assert_eq!(state(&tracker), ProviderState::Up);
+        tracker.record_failure("openai", "timeout");
+        assert_eq!(state(&tracker), ProviderState::Down);
+        tracker.record_success("openai", 200);
+        assert_eq!(state(&tracker), ProviderState::Down);
+        tracker.record_success("openai", 200);
+        assert_eq!(state(&tracker), ProviderState::Up);
+        assert_eq!(tracker.get("openai").unwrap().failure_rate(), 2.0 / 5.0);
+
+        // Slow once the average passes slow_ms, up only well below it
+        for _ in 0..6 {
+            tracker.record_success("openai", 3000);
+        }
+        assert_eq!(state(&tracker), ProviderState::Slow);
+        tracker.record_success("openai", 100);
+        tracker.record_success("openai", 100);
+        assert_eq!(state(&tracker), ProviderState::Slow);
+        for _ in 0..6 {
+            tracker.record_success("openai", 100);
+        }
+        assert_eq!(state(&tracker), ProviderState::Up);
+
+        let policy = *tracker.policy();
+        let health = tracker.get("openai").unwrap();
+        assert!(health.score(&policy) > 0.5);
+        tracker.record_failure("openai", "503");
+        tracker.record_failure("openai", "503");
+        assert_eq!(tracker.get("openai").unwrap().score(&policy), 0.0);
+    }
+}
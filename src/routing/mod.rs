@@ -5,9 +5,15 @@
 //! Intelligent routing of conversations to optimal models and providers.
 
 pub mod continuation;
+pub mod fallback;
+pub mod health;
 pub mod model_router;
+pub mod policy;
 pub mod recommendations;
 
 pub use continuation::*;
+pub use fallback::*;
+pub use health::*;
 pub use model_router::*;
+pub use policy::*;
 pub use recommendations::*;
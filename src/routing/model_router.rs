@@ -4,12 +4,26 @@
 //!
 //! Routes conversations to optimal models based on task type, complexity,
 //! cost constraints, and performance requirements.
+//!
+//! Model prices come from the table [`builtin_price`] reads, which
+//! [`ModelRouter::set_price`] overrides per model. A request's cost is
+//! estimated from its prompt tokens and the reply length its task usually
+//! needs.
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
+use super::health::ProviderState;
+use crate::agency::budget::builtin_price;
+use crate::config::ModelPrice;
+
+/// Rough token count of `text`, at about four characters per token
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
 // ============================================================================
 // Task Classification
 // ============================================================================
@@ -72,6 +86,11 @@ impl TaskType {
         }
     }
 
+    /// Rough length in tokens of a typical reply to this kind of task
+    pub fn expected_output_tokens(&self) -> usize {
+        self.complexity_weight() as usize * 150
+    }
+
     /// Detect task type from message content
     pub fn detect(content: &str) -> Self {
         let lower = content.to_lowercase();
@@ -246,6 +265,12 @@ impl ModelCapabilities {
     pub fn score_for_task(&self, task: TaskType) -> f64 {
         self.task_scores.get(&task).copied().unwrap_or(0.5)
     }
+
+    /// Cost in USD of a request with these token counts
+    pub fn cost_for(&self, input_tokens: usize, output_tokens: usize) -> f64 {
+        (input_tokens as f64 * self.cost_per_1k_input + output_tokens as f64 * self.cost_per_1k_output)
+            / 1000.0
+    }
 }
 
 // ============================================================================
@@ -258,7 +283,7 @@ impl ModelCapabilities {
 pub enum RoutingStrategy {
     /// Best quality regardless of cost
     BestQuality,
-    /// Lowest cost that meets quality threshold
+    /// Lowest cost that meets the `min_quality` threshold
     LowestCost,
     /// Fastest response time
     FastestResponse,
@@ -314,6 +339,8 @@ pub struct RoutingConfig {
     pub cost_weight: f64,
     /// Latency weight (0.0 - 1.0)
     pub latency_weight: f64,
+    /// Task score (0.0 - 1.0) a model needs for `LowestCost` to pick it
+    pub min_quality: f64,
     /// Fallback model if routing fails
     pub fallback_model: Option<String>,
 }
@@ -326,6 +353,7 @@ impl Default for RoutingConfig {
             quality_weight: 0.5,
             cost_weight: 0.3,
             latency_weight: 0.2,
+            min_quality: 0.8,
             fallback_model: None,
         }
     }
@@ -344,8 +372,11 @@ pub struct RoutingRequest {
     pub content: String,
     /// Conversation context (previous messages)
     pub context: Vec<String>,
-    /// Estimated token count
+    /// Estimated prompt tokens; 0 estimates them from content and context
     pub estimated_tokens: usize,
+    /// Most tokens the reply may use, when the caller limits it
+    #[serde(default)]
+    pub max_output_tokens: Option<usize>,
     /// User preferences
     pub config: RoutingConfig,
     /// Timestamp
@@ -365,8 +396,12 @@ pub struct RoutingDecision {
     pub task_type: TaskType,
     /// Confidence score (0.0 - 1.0)
     pub confidence: f64,
-    /// Estimated cost
+    /// Estimated cost in USD
     pub estimated_cost: f64,
+    /// Estimated prompt tokens
+    pub estimated_input_tokens: usize,
+    /// Estimated reply tokens
+    pub estimated_output_tokens: usize,
     /// Estimated latency
     pub estimated_latency_ms: u32,
     /// Alternative models considered
@@ -392,6 +427,8 @@ pub struct ModelScore {
     pub latency_score: f64,
     /// Total weighted score
     pub total_score: f64,
+    /// Estimated cost of the request in USD
+    pub estimated_cost: f64,
     /// Why not selected (if applicable)
     pub rejection_reason: Option<String>,
 }
@@ -400,10 +437,17 @@ pub struct ModelScore {
 // Model Router
 // ============================================================================
 
+/// Routing decisions kept for [`ModelRouter::stats`]
+const MAX_HISTORY: usize = 10_000;
+
 /// Multi-model conversation router
 pub struct ModelRouter {
     /// Available models
     models: Vec<ModelCapabilities>,
+    /// Prices set with [`Self::set_price`], by model ID
+    prices: HashMap<String, ModelPrice>,
+    /// Probed state and latency by provider, from [`Self::set_provider_health`]
+    provider_health: HashMap<String, (ProviderState, Option<u32>)>,
     /// Default configuration
     default_config: RoutingConfig,
     /// Routing history for learning
@@ -413,43 +457,160 @@ pub struct ModelRouter {
 impl ModelRouter {
     /// Create a new model router
     pub fn new() -> Self {
-        Self {
-            models: Self::default_models(),
-            default_config: RoutingConfig::default(),
-            history: vec![],
-        }
+        Self::with_models(Self::default_models())
     }
 
     /// Create router with custom models
     pub fn with_models(models: Vec<ModelCapabilities>) -> Self {
         Self {
             models,
+            prices: HashMap::new(),
+            provider_health: HashMap::new(),
             default_config: RoutingConfig::default(),
             history: vec![],
         }
     }
 
+    /// Set the price of a model in USD per million prompt and completion
+    /// tokens, overriding the built-in one
+    pub fn set_price(&mut self, model_id: &str, input: f64, output: f64) {
+        for model in self.models.iter_mut().filter(|m| m.model_id == model_id) {
+            model.cost_per_1k_input = input / 1000.0;
+            model.cost_per_1k_output = output / 1000.0;
+        }
+        self.prices
+            .insert(model_id.to_string(), ModelPrice { input, output });
+    }
+
+    /// Cost in USD of a call to `model_id`, from its set price, the router's
+    /// profile of it, or the built-in table; `None` when it is unknown
+    pub fn cost(&self, model_id: &str, prompt_tokens: u64, completion_tokens: u64) -> Option<f64> {
+        let price = match self.prices.get(model_id) {
+            Some(price) => *price,
+            None => match self.models.iter().find(|m| m.model_id == model_id) {
+                Some(m) => ModelPrice {
+                    input: m.cost_per_1k_input * 1000.0,
+                    output: m.cost_per_1k_output * 1000.0,
+                },
+                None => builtin_price(model_id)?,
+            },
+        };
+        Some((prompt_tokens as f64 * price.input + completion_tokens as f64 * price.output) / 1_000_000.0)
+    }
+
     /// Add a model
     pub fn add_model(&mut self, model: ModelCapabilities) {
         self.models.push(model);
     }
 
+    /// Mark every model from a provider as available or not
+    pub fn set_provider_available(&mut self, provider: &str, available: bool) {
+        for model in self.models.iter_mut().filter(|m| m.provider == provider) {
+            model.available = available;
+        }
+    }
+
+    /// Set what probes found out about a provider: down providers are not
+    /// routed to, slow ones only when nothing else can serve a request, and
+    /// `latency_ms` adds to the latency of its models
+    pub fn set_provider_health(&mut self, provider: &str, state: ProviderState, latency_ms: Option<u32>) {
+        self.provider_health
+            .insert(provider.to_string(), (state, latency_ms));
+    }
+
+    /// The probed state of a provider; providers not probed are up
+    pub fn provider_state(&self, provider: &str) -> ProviderState {
+        self.provider_health
+            .get(provider)
+            .map_or(ProviderState::Up, |(state, _)| *state)
+    }
+
+    /// A model's profiled latency plus its provider's probed latency
+    pub fn latency_of(&self, model: &ModelCapabilities) -> u32 {
+        let probed = self
+            .provider_health
+            .get(&model.provider)
+            .and_then(|(_, latency)| *latency)
+            .unwrap_or(0);
+        model.avg_latency_ms.saturating_add(probed)
+    }
+
+    /// Models the router chooses from
+    pub fn models(&self) -> &[ModelCapabilities] {
+        &self.models
+    }
+
     /// Route a request to the optimal model
     pub fn route(&mut self, request: &RoutingRequest) -> RoutingDecision {
         // Detect task type
         let task_type = TaskType::detect(&request.content);
 
-        // Score all models
-        let mut scores: Vec<ModelScore> = self
+        // Estimate the tokens in and out
+        let input_tokens = match request.estimated_tokens {
+            0 => request
+                .context
+                .iter()
+                .chain(std::iter::once(&request.content))
+                .map(|text| estimate_tokens(text))
+                .sum(),
+            tokens => tokens,
+        };
+        let output_tokens = request
+            .max_output_tokens
+            .map_or(task_type.expected_output_tokens(), |max| {
+                max.min(task_type.expected_output_tokens())
+            });
+
+        // Leave out down providers and models that break the constraints
+        let mut candidates: Vec<&ModelCapabilities> = self
             .models
             .iter()
-            .filter(|m| m.available)
-            .filter(|m| self.meets_constraints(m, &request.config.constraints))
-            .map(|m| self.score_model(m, task_type, request))
+            .filter(|m| m.available && self.provider_state(&m.provider) != ProviderState::Down)
+            .filter(|m| {
+                let cost = m.cost_for(input_tokens, output_tokens);
+                self.meets_constraints(m, &request.config.constraints, cost)
+            })
             .collect();
 
-        // Sort by total score (descending)
-        scores.sort_by(|a, b| b.total_score.partial_cmp(&a.total_score).unwrap());
+        // Slow providers serve only when no other can
+        if candidates
+            .iter()
+            .any(|m| self.provider_state(&m.provider) == ProviderState::Up)
+        {
+            candidates.retain(|m| self.provider_state(&m.provider) == ProviderState::Up);
+        }
+
+        // Score all models
+        let mut scores: Vec<ModelScore> = candidates
+            .into_iter()
+            .map(|m| self.score_model(m, task_type, request, input_tokens, output_tokens))
+            .collect();
+
+        if request.config.strategy == RoutingStrategy::LowestCost {
+            // Cheapest capable model first; when none is capable, the best
+            let min_quality = request.config.min_quality;
+            for score in scores.iter_mut().filter(|s| s.quality_score < min_quality) {
+                score.rejection_reason = Some(format!(
+                    "Quality {:.2} below the {:.2} threshold",
+                    score.quality_score, min_quality
+                ));
+            }
+            scores.sort_by(|a, b| {
+                let capable = |s: &ModelScore| s.rejection_reason.is_none();
+                capable(b).cmp(&capable(a)).then_with(|| {
+                    let by_cost = a.estimated_cost.total_cmp(&b.estimated_cost);
+                    let by_quality = b.quality_score.total_cmp(&a.quality_score);
+                    if capable(a) {
+                        by_cost.then(by_quality)
+                    } else {
+                        by_quality.then(by_cost)
+                    }
+                })
+            });
+        } else {
+            // Sort by total score (descending)
+            scores.sort_by(|a, b| b.total_score.total_cmp(&a.total_score));
+        }
 
         // Select best model
         let selected = scores.first().cloned().unwrap_or_else(|| {
@@ -462,6 +623,7 @@ impl ModelRouter {
                 cost_score: 0.5,
                 latency_score: 0.5,
                 total_score: 0.5,
+                estimated_cost: 0.0,
                 rejection_reason: None,
             }
         });
@@ -472,31 +634,41 @@ impl ModelRouter {
             provider: selected.provider.clone(),
             task_type,
             confidence: selected.total_score,
-            estimated_cost: self.estimate_cost(&selected.model_id, request.estimated_tokens),
+            estimated_cost: selected.estimated_cost,
+            estimated_input_tokens: input_tokens,
+            estimated_output_tokens: output_tokens,
             estimated_latency_ms: self.estimate_latency(&selected.model_id),
             alternatives: scores.into_iter().skip(1).take(3).collect(),
             reasoning: self.generate_reasoning(&selected, task_type),
             decided_at: Utc::now(),
         };
 
-        // Store in history
+        // Store in history, dropping the oldest decisions in long-lived routers
+        if self.history.len() >= MAX_HISTORY {
+            self.history.drain(..MAX_HISTORY / 2);
+        }
         self.history.push(decision.clone());
 
         decision
     }
 
     /// Check if model meets constraints
-    fn meets_constraints(&self, model: &ModelCapabilities, constraints: &RoutingConstraints) -> bool {
+    fn meets_constraints(
+        &self,
+        model: &ModelCapabilities,
+        constraints: &RoutingConstraints,
+        cost: f64,
+    ) -> bool {
         // Check cost
         if let Some(max_cost) = constraints.max_cost {
-            if model.cost_per_1k_output > max_cost * 10.0 {
+            if cost > max_cost {
                 return false;
             }
         }
 
         // Check latency
         if let Some(max_latency) = constraints.max_latency_ms {
-            if model.avg_latency_ms > max_latency {
+            if self.latency_of(model) > max_latency {
                 return false;
             }
         }
@@ -534,7 +706,14 @@ impl ModelRouter {
     }
 
     /// Score a model for routing
-    fn score_model(&self, model: &ModelCapabilities, task: TaskType, request: &RoutingRequest) -> ModelScore {
+    fn score_model(
+        &self,
+        model: &ModelCapabilities,
+        task: TaskType,
+        request: &RoutingRequest,
+        input_tokens: usize,
+        output_tokens: usize,
+    ) -> ModelScore {
         let config = &request.config;
 
         // Quality score based on task
@@ -546,7 +725,7 @@ impl ModelRouter {
 
         // Latency score (inverse - lower latency = higher score)
         let max_latency = 5000.0; // 5 seconds as baseline
-        let latency_score = 1.0 - (model.avg_latency_ms as f64 / max_latency).min(1.0);
+        let latency_score = 1.0 - (self.latency_of(model) as f64 / max_latency).min(1.0);
 
         // Calculate total based on strategy
         let total_score = match config.strategy {
@@ -570,49 +749,41 @@ impl ModelRouter {
             cost_score,
             latency_score,
             total_score,
+            estimated_cost: model.cost_for(input_tokens, output_tokens),
             rejection_reason: None,
         }
     }
 
-    /// Estimate cost for a request
-    fn estimate_cost(&self, model_id: &str, tokens: usize) -> f64 {
-        self.models
-            .iter()
-            .find(|m| m.model_id == model_id)
-            .map(|m| (tokens as f64 / 1000.0) * (m.cost_per_1k_input + m.cost_per_1k_output))
-            .unwrap_or(0.0)
-    }
-
     /// Estimate latency for a model
     fn estimate_latency(&self, model_id: &str) -> u32 {
         self.models
             .iter()
             .find(|m| m.model_id == model_id)
-            .map(|m| m.avg_latency_ms)
+            .map(|m| self.latency_of(m))
             .unwrap_or(1000)
     }
 
     /// Generate reasoning for the selection
     fn generate_reasoning(&self, selected: &ModelScore, task: TaskType) -> String {
         format!(
-            "Selected {} for {:?} task. Quality: {:.0}%, Cost efficiency: {:.0}%, Speed: {:.0}%",
+            "Selected {} for {:?} task. Quality: {:.0}%, Cost efficiency: {:.0}%, Speed: {:.0}%, Est. cost: ${:.6}",
             selected.model_id,
             task,
             selected.quality_score * 100.0,
             selected.cost_score * 100.0,
-            selected.latency_score * 100.0
+            selected.latency_score * 100.0,
+            selected.estimated_cost
         )
     }
 
-    /// Get default model profiles
+    /// Get default model profiles, priced from the built-in table
     fn default_models() -> Vec<ModelCapabilities> {
-        vec![
+        let models = vec![
             // OpenAI models
             ModelCapabilities::new("gpt-4o", "openai", "GPT-4o")
                 .with_context_window(128000)
                 .with_vision(true)
                 .with_functions(true)
-                .with_cost(0.005, 0.015)
                 .with_latency(800)
                 .with_task_score(TaskType::Coding, 0.95)
                 .with_task_score(TaskType::Reasoning, 0.95)
@@ -624,7 +795,6 @@ impl ModelRouter {
                 .with_context_window(128000)
                 .with_vision(true)
                 .with_functions(true)
-                .with_cost(0.00015, 0.0006)
                 .with_latency(500)
                 .with_task_score(TaskType::Chat, 0.85)
                 .with_task_score(TaskType::Quick, 0.90)
@@ -635,7 +805,6 @@ impl ModelRouter {
                 .with_context_window(200000)
                 .with_vision(true)
                 .with_functions(false)
-                .with_cost(0.015, 0.06)
                 .with_latency(3000)
                 .with_task_score(TaskType::Reasoning, 0.99)
                 .with_task_score(TaskType::Math, 0.98)
@@ -647,7 +816,6 @@ impl ModelRouter {
                 .with_context_window(200000)
                 .with_vision(true)
                 .with_functions(true)
-                .with_cost(0.003, 0.015)
                 .with_latency(700)
                 .with_task_score(TaskType::Coding, 0.95)
                 .with_task_score(TaskType::Writing, 0.95)
@@ -658,7 +826,6 @@ impl ModelRouter {
                 .with_context_window(200000)
                 .with_vision(true)
                 .with_functions(true)
-                .with_cost(0.0008, 0.004)
                 .with_latency(400)
                 .with_task_score(TaskType::Chat, 0.85)
                 .with_task_score(TaskType::Quick, 0.90)
@@ -669,7 +836,6 @@ impl ModelRouter {
                 .with_context_window(1000000)
                 .with_vision(true)
                 .with_functions(true)
-                .with_cost(0.000075, 0.0003)
                 .with_latency(300)
                 .with_task_score(TaskType::Chat, 0.85)
                 .with_task_score(TaskType::Quick, 0.95)
@@ -680,7 +846,6 @@ impl ModelRouter {
                 .with_context_window(1000000)
                 .with_vision(true)
                 .with_functions(true)
-                .with_cost(0.00125, 0.005)
                 .with_latency(600)
                 .with_task_score(TaskType::Coding, 0.92)
                 .with_task_score(TaskType::Reasoning, 0.90)
@@ -707,7 +872,14 @@ impl ModelRouter {
                 .with_task_score(TaskType::Coding, 0.85)
                 .with_task_score(TaskType::CodeReview, 0.85)
                 .with_task_score(TaskType::Debugging, 0.80),
-        ]
+        ];
+        models
+            .into_iter()
+            .map(|model| match builtin_price(&model.model_id) {
+                Some(price) => model.with_cost(price.input / 1000.0, price.output / 1000.0),
+                None => model,
+            })
+            .collect()
     }
 
     /// Get routing statistics
@@ -776,6 +948,7 @@ mod tests {
             content: "Write a Python function to parse JSON".to_string(),
             context: vec![],
             estimated_tokens: 500,
+            max_output_tokens: None,
             config: RoutingConfig::default(),
             timestamp: Utc::now(),
         };
@@ -798,6 +971,7 @@ mod tests {
             content: "Quick question".to_string(),
             context: vec![],
             estimated_tokens: 100,
+            max_output_tokens: None,
             config,
             timestamp: Utc::now(),
         };
@@ -805,4 +979,81 @@ mod tests {
         let decision = router.route(&request);
         assert_eq!(decision.provider, "google");
     }
+
+    #[test]
+    fn test_cheapest_capable_model() {
+        let mut router = ModelRouter::new();
+        let mut config = RoutingConfig {
+            strategy: RoutingStrategy::LowestCost,
+            ..Default::default()
+        };
+        config.constraints.blocked_providers = vec!["ollama".to_string()];
+        let mut request = RoutingRequest {
+            id: Uuid::new_v4(),
+            content: "Write a function that parses this config file".to_string(),
+            context: vec![],
+            estimated_tokens: 0,
+            max_output_tokens: Some(400),
+            config,
+            timestamp: Utc::now(),
+        };
+
+        // gpt-4o-mini is the cheapest model scoring 0.8 on coding
+        let decision = router.route(&request);
+        assert_eq!(decision.model_id, "gpt-4o-mini");
+        assert_eq!(decision.estimated_input_tokens, 12);
+        assert_eq!(decision.estimated_output_tokens, 400);
+        assert!((decision.estimated_cost - (12.0 * 0.15 + 400.0 * 0.60) / 1e6).abs() < 1e-12);
+
+        // Above what the cheap models score, the cheapest of the strong ones
+        request.config.min_quality = 0.95;
+        assert_eq!(router.route(&request).model_id, "gpt-4o");
+
+        // A configured price overrides the table
+        let sonnet = "claude-sonnet-4-20250514";
+        router.set_price(sonnet, 0.1, 0.1);
+        assert_eq!(router.route(&request).model_id, sonnet);
+        assert_eq!(router.cost(sonnet, 1_000_000, 0), Some(0.1));
+        assert_eq!(router.cost("gpt-4.1-mini-2025-04-14", 0, 1_000_000), Some(1.6));
+        assert_eq!(router.cost("mystery", 1, 1), None);
+
+        // Nothing capable enough: the most capable
+        request.config.min_quality = 1.0;
+        let decision = router.route(&request);
+        assert_eq!(decision.model_id, "o1");
+        assert!(decision.alternatives[0].rejection_reason.is_some());
+    }
+
+    #[test]
+    fn test_provider_health() {
+        let mut router = ModelRouter::new();
+        let mut request = RoutingRequest {
+            id: Uuid::new_v4(),
+            content: "Quick question".to_string(),
+            context: vec![],
+            estimated_tokens: 100,
+            max_output_tokens: None,
+            config: RoutingConfig {
+                strategy: RoutingStrategy::FastestResponse,
+                ..Default::default()
+            },
+            timestamp: Utc::now(),
+        };
+        assert_eq!(router.route(&request).provider, "google");
+
+        // A down provider is skipped, and probed latency counts
+        router.set_provider_health("google", ProviderState::Down, None);
+        router.set_provider_health("anthropic", ProviderState::Up, Some(900));
+        assert_eq!(router.route(&request).model_id, "gpt-4o-mini");
+
+        // Slow providers serve only when nothing else can
+        router.set_provider_health("openai", ProviderState::Slow, Some(3000));
+        request.config.constraints.allowed_providers =
+            Some(vec!["openai".to_string(), "anthropic".to_string()]);
+        assert_eq!(router.route(&request).provider, "anthropic");
+        request.config.constraints.allowed_providers = Some(vec!["openai".to_string()]);
+        let decision = router.route(&request);
+        assert_eq!(decision.provider, "openai");
+        assert!(decision.confidence < 0.5);
+    }
 }
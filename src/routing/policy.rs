@@ -0,0 +1,429 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Workspace routing policies
+//!
+//! A `[policies.<name>]` section limits the providers work in some
+//! workspaces may use, for example "this client's repository only goes to
+//! local models". A policy covers directories (and everything under them)
+//! and workspace names; the gateway finds it from the `X-Chasm-Workspace`
+//! header, and agents, `csm run` and embeddings from the directory they run
+//! in. Every decision taken under a policy is kept in `policy_decisions` in
+//! the harvest database, where `csm routing audit` lists them.
+
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::agency::models::ModelProvider;
+use crate::config::{self, PolicyConfig};
+use crate::error::{CsmError, Result};
+
+/// Whether a provider runs on the user's own machine or network
+pub fn is_local_provider(provider: &str) -> bool {
+    ModelProvider::from_name(provider).is_some_and(|p| p.is_local())
+}
+
+/// The providers one policy allows
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WorkspacePolicy {
+    pub name: String,
+    pub paths: Vec<PathBuf>,
+    /// Lowercase workspace names or IDs
+    pub workspaces: Vec<String>,
+    pub local_only: bool,
+    pub allowed_providers: Option<Vec<String>>,
+    pub blocked_providers: Vec<String>,
+}
+
+impl WorkspacePolicy {
+    /// Whether `provider` may be used, and why not if it may not
+    pub fn decide(&self, provider: &str) -> std::result::Result<(), String> {
+        let provider = provider.to_lowercase();
+        if self.blocked_providers.contains(&provider) {
+            return Err(format!("policy '{}' blocks {}", self.name, provider));
+        }
+        if let Some(allowed) = &self.allowed_providers {
+            if !allowed.contains(&provider) {
+                return Err(format!(
+                    "policy '{}' only allows {}",
+                    self.name,
+                    allowed.join(", ")
+                ));
+            }
+        }
+        if self.local_only && !is_local_provider(&provider) {
+            return Err(format!(
+                "policy '{}' only allows local providers, and {} is not one",
+                self.name, provider
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn allows(&self, provider: &str) -> bool {
+        self.decide(provider).is_ok()
+    }
+
+    /// Whether `dir` is one of the policy's directories or under one, and
+    /// how deep that directory is
+    fn path_match(&self, dir: &Path) -> Option<usize> {
+        let dir = normalize(dir);
+        self.paths
+            .iter()
+            .filter(|path| dir.starts_with(path))
+            .map(|path| path.components().count())
+            .max()
+    }
+}
+
+/// Absolute and, where the path exists, without symlinks
+fn normalize(path: &Path) -> PathBuf {
+    let path = config::expand_home(path);
+    let path = if path.is_relative() {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(&path))
+            .unwrap_or(path)
+    } else {
+        path
+    };
+    std::fs::canonicalize(&path).unwrap_or(path)
+}
+
+/// Every configured policy
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Policies {
+    policies: Vec<WorkspacePolicy>,
+}
+
+impl Policies {
+    /// The policies set in `[policies]`
+    pub fn from_config(settings: &BTreeMap<String, PolicyConfig>) -> Result<Self> {
+        let lowercase = |names: &[String]| -> Vec<String> {
+            names.iter().map(|n| n.trim().to_lowercase()).collect()
+        };
+        let mut policies = Vec::new();
+        for (name, settings) in settings {
+            if settings.paths.is_empty() && settings.workspaces.is_empty() {
+                return Err(CsmError::ConfigError(format!(
+                    "Policy '{}' covers nothing; set paths or workspaces",
+                    name
+                )));
+            }
+            policies.push(WorkspacePolicy {
+                name: name.clone(),
+                paths: settings
+                    .paths
+                    .iter()
+                    .map(|path| normalize(Path::new(path)))
+                    .collect(),
+                workspaces: lowercase(&settings.workspaces),
+                local_only: settings.local_only.unwrap_or(false),
+                allowed_providers: settings.allowed_providers.as_deref().map(lowercase),
+                blocked_providers: lowercase(&settings.blocked_providers),
+            });
+        }
+        Ok(Self { policies })
+    }
+
+    /// The policies of the process-wide configuration
+    pub fn current() -> Result<Self> {
+        Self::from_config(&config::current().policies)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.policies.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &WorkspacePolicy> {
+        self.policies.iter()
+    }
+
+    /// The policy covering a directory; the one naming the deepest directory
+    /// wins
+    pub fn for_path(&self, dir: &Path) -> Option<&WorkspacePolicy> {
+        self.policies
+            .iter()
+            .filter_map(|policy| policy.path_match(dir).map(|depth| (depth, policy)))
+            .max_by_key(|(depth, _)| *depth)
+            .map(|(_, policy)| policy)
+    }
+
+    /// The policy naming a workspace
+    pub fn for_workspace(&self, workspace: &str) -> Option<&WorkspacePolicy> {
+        let workspace = workspace.trim().to_lowercase();
+        self.policies
+            .iter()
+            .find(|policy| policy.workspaces.contains(&workspace))
+    }
+
+    /// The policy for a workspace name or ID, or else a directory path
+    pub fn resolve(&self, workspace: &str) -> Option<&WorkspacePolicy> {
+        self.for_workspace(workspace)
+            .or_else(|| self.for_path(Path::new(workspace.trim())))
+    }
+}
+
+/// One provider a policy allowed or refused
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PolicyDecision {
+    /// Milliseconds since the epoch
+    pub decided_at: i64,
+    /// What asked: `gateway`, `agency`, `run` or `embeddings`
+    pub source: String,
+    /// The workspace name or directory the policy was found by
+    pub workspace: String,
+    pub policy: String,
+    pub provider: String,
+    pub model: Option<String>,
+    pub allowed: bool,
+    /// Why a provider was refused
+    pub reason: Option<String>,
+}
+
+impl PolicyDecision {
+    pub fn new(
+        source: &str,
+        workspace: &str,
+        policy: &WorkspacePolicy,
+        provider: &str,
+        model: Option<&str>,
+    ) -> Self {
+        let reason = policy.decide(provider).err();
+        Self {
+            decided_at: Utc::now().timestamp_millis(),
+            source: source.to_string(),
+            workspace: workspace.to_string(),
+            policy: policy.name.clone(),
+            provider: provider.to_lowercase(),
+            model: model.map(str::to_string),
+            allowed: reason.is_none(),
+            reason,
+        }
+    }
+}
+
+/// Create the policy decision table in the harvest database if it does not
+/// exist
+pub fn ensure_policy_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS policy_decisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            decided_at INTEGER NOT NULL,
+            source TEXT NOT NULL,
+            workspace TEXT NOT NULL,
+            policy TEXT NOT NULL,
+            provider TEXT NOT NULL,
+            model TEXT,
+            allowed INTEGER NOT NULL,
+            reason TEXT
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_policy_decisions_time ON policy_decisions(decided_at);
+        "#,
+    )?;
+    Ok(())
+}
+
+pub fn record_decision(conn: &Connection, decision: &PolicyDecision) -> Result<()> {
+    ensure_policy_table(conn)?;
+    conn.execute(
+        "INSERT INTO policy_decisions
+             (decided_at, source, workspace, policy, provider, model, allowed, reason)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            decision.decided_at,
+            decision.source,
+            decision.workspace,
+            decision.policy,
+            decision.provider,
+            decision.model,
+            decision.allowed,
+            decision.reason
+        ],
+    )?;
+    Ok(())
+}
+
+/// Which recorded decisions to list
+#[derive(Debug, Default)]
+pub struct DecisionFilter<'a> {
+    /// A policy name, or a workspace as it was recorded
+    pub workspace: Option<&'a str>,
+    pub denied_only: bool,
+    pub limit: Option<usize>,
+}
+
+/// Recorded decisions matching `filter`, newest first
+pub fn load_decisions(conn: &Connection, filter: &DecisionFilter) -> Result<Vec<PolicyDecision>> {
+    ensure_policy_table(conn)?;
+    let mut stmt = conn.prepare(
+        "SELECT decided_at, source, workspace, policy, provider, model, allowed, reason
+         FROM policy_decisions
+         WHERE (?1 IS NULL OR policy = ?1 OR workspace = ?1)
+           AND (?2 = 0 OR allowed = 0)
+         ORDER BY decided_at DESC, id DESC
+         LIMIT ?3",
+    )?;
+    let limit = filter.limit.map_or(-1, |limit| limit as i64);
+    let decisions = stmt
+        .query_map(
+            params![filter.workspace, filter.denied_only, limit],
+            |row| {
+                Ok(PolicyDecision {
+                    decided_at: row.get(0)?,
+                    source: row.get(1)?,
+                    workspace: row.get(2)?,
+                    policy: row.get(3)?,
+                    provider: row.get(4)?,
+                    model: row.get(5)?,
+                    allowed: row.get(6)?,
+                    reason: row.get(7)?,
+                })
+            },
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(decisions)
+}
+
+/// The policy one command or run works under, recording each distinct
+/// decision it takes once
+#[derive(Debug)]
+pub struct PolicyGuard {
+    source: String,
+    workspace: String,
+    policy: WorkspacePolicy,
+    /// Harvest database decisions are recorded in
+    audit_db: Option<PathBuf>,
+    recorded: Mutex<HashSet<(String, Option<String>)>>,
+}
+
+impl PolicyGuard {
+    pub fn new(source: &str, workspace: &str, policy: WorkspacePolicy) -> Self {
+        Self {
+            source: source.to_string(),
+            workspace: workspace.to_string(),
+            policy,
+            audit_db: crate::commands::get_db_path(None).ok(),
+            recorded: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// The guard for a workspace name or directory, if a policy covers it
+    pub fn for_workspace(source: &str, workspace: &str) -> Result<Option<Self>> {
+        let policies = Policies::current()?;
+        Ok(policies
+            .resolve(workspace)
+            .map(|policy| Self::new(source, workspace, policy.clone())))
+    }
+
+    /// The guard for the current directory, if a policy covers it
+    pub fn here(source: &str) -> Result<Option<Self>> {
+        let dir = std::env::current_dir()?;
+        Self::for_workspace(source, &dir.to_string_lossy())
+    }
+
+    /// Leave decisions unrecorded, or record them in another database
+    pub fn with_audit_db(mut self, db: Option<PathBuf>) -> Self {
+        self.audit_db = db;
+        self
+    }
+
+    pub fn policy(&self) -> &WorkspacePolicy {
+        &self.policy
+    }
+
+    /// Whether `provider` may be used for `model`, and why not if it may not
+    pub fn check(&self, provider: &str, model: Option<&str>) -> std::result::Result<(), String> {
+        let decision =
+            PolicyDecision::new(&self.source, &self.workspace, &self.policy, provider, model);
+        let key = (decision.provider.clone(), decision.model.clone());
+        if self.recorded.lock().unwrap().insert(key) {
+            if let Some(db) = &self.audit_db {
+                let recorded = Connection::open(db)
+                    .map_err(CsmError::from)
+                    .and_then(|conn| record_decision(&conn, &decision));
+                if let Err(e) = recorded {
+                    eprintln!("[WARN] Failed to record policy decision: {}", e);
+                }
+            }
+        }
+        match decision.reason {
+            Some(reason) => Err(reason),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policies_and_audit() {
+        let mut settings = BTreeMap::new();
+        settings.insert(
+            "client-a".to_string(),
+            PolicyConfig {
+                paths: vec!["/work/client-a".to_string()],
+                workspaces: vec!["Client-A".to_string()],
+                local_only: Some(true),
+                blocked_providers: vec!["vllm".to_string()],
+                ..Default::default()
+            },
+        );
+        settings.insert(
+            "secrets".to_string(),
+            PolicyConfig {
+                paths: vec!["/work/client-a/secrets".to_string()],
+                allowed_providers: Some(vec!["ollama".to_string()]),
+                ..Default::default()
+            },
+        );
+        let policies = Policies::from_config(&settings).unwrap();
+
+        let policy = policies.resolve("client-a").unwrap();
+        assert!(policy.allows("ollama") && policy.allows("LMStudio"));
+        assert!(!policy.allows("openai"));
+        assert!(policy.decide("vllm").unwrap_err().contains("blocks vllm"));
+        assert_eq!(
+            policies
+                .for_path(Path::new("/work/client-a/src"))
+                .unwrap()
+                .name,
+            "client-a"
+        );
+        // The deepest directory wins
+        let secrets = policies.resolve("/work/client-a/secrets/keys").unwrap();
+        assert_eq!(secrets.name, "secrets");
+        assert!(!secrets.allows("lmstudio"));
+        assert!(policies.resolve("/work/client-b").is_none());
+        assert!(policies.resolve("/work/client-ab").is_none());
+
+        settings.insert("empty".to_string(), PolicyConfig::default());
+        assert!(Policies::from_config(&settings).is_err());
+
+        let conn = Connection::open_in_memory().unwrap();
+        for provider in ["openai", "ollama"] {
+            let decision = PolicyDecision::new("gateway", "client-a", policy, provider, Some("m"));
+            record_decision(&conn, &decision).unwrap();
+        }
+        let all = load_decisions(&conn, &DecisionFilter::default()).unwrap();
+        assert_eq!(all.len(), 2);
+        let denied = load_decisions(
+            &conn,
+            &DecisionFilter {
+                workspace: Some("client-a"),
+                denied_only: true,
+                limit: Some(10),
+            },
+        )
+        .unwrap();
+        assert_eq!(denied.len(), 1);
+        assert_eq!(denied[0].provider, "openai");
+        assert!(denied[0].reason.as_deref().unwrap().contains("local"));
+    }
+}
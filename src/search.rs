@@ -0,0 +1,1011 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Session search shared by `csm harvest search` and the API
+//!
+//! Text mode matches message content through the `messages_fts` index, or
+//! with `LIKE` on databases without one, and session titles, ranking
+//! sessions by their summed BM25 scores. Queries in Chinese, Japanese, Thai,
+//! Khmer or Burmese use the `messages_trigram` index instead, since their
+//! words are not separated by spaces. Semantic mode ranks sessions by the
+//! cosine similarity of embeddings from an OpenAI-compatible `/embeddings`
+//! endpoint; session embeddings are cached in `session_embeddings` and
+//! recomputed when a session changes. Embeddings are only requested from a
+//! provider the `[policies]` covering the current directory allow.
+//!
+//! Both modes apply the same provider, workspace, date, tag, topic, and
+//! language filters and can count the matches per provider, workspace, tag,
+//! language, and month.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, OptionalExtension, ToSql};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::datetime::DateFilter;
+use crate::language;
+use crate::routing::PolicyGuard;
+use crate::reviews;
+use crate::topics;
+use crate::text::{prefix_chars, snippet};
+
+/// Sessions most recently updated that semantic mode ranks
+const SEMANTIC_CANDIDATES: usize = 1000;
+
+/// Characters of a session embedded for semantic mode
+const EMBED_CHARS: usize = 8000;
+
+/// Texts sent to the embeddings endpoint per request
+const EMBED_BATCH: usize = 32;
+
+/// Values listed per facet
+const FACET_LIMIT: usize = 20;
+
+/// Extra score for a session whose title contains the query
+const TITLE_BOOST: f64 = 5.0;
+
+/// How sessions are matched and ranked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    /// Full-text match on titles and messages
+    #[default]
+    Text,
+    /// Embedding similarity to the query
+    Semantic,
+}
+
+impl std::str::FromStr for SearchMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(Self::Text),
+            "semantic" => Ok(Self::Semantic),
+            _ => bail!("Unknown search mode '{}' (expected text or semantic)", s),
+        }
+    }
+}
+
+/// Conditions every result must meet
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilters {
+    /// Provider names, compared case-insensitively; any of them matches
+    pub providers: Vec<String>,
+    /// Workspace ID or name
+    pub workspace: Option<String>,
+    /// Sessions updated at or after this time (milliseconds)
+    pub after: Option<i64>,
+    /// Sessions updated before this time (milliseconds)
+    pub before: Option<i64>,
+    /// Tags a session must all have
+    pub tags: Vec<String>,
+    /// Topics a session must all be filed under, directly or by a subtopic
+    pub topics: Vec<String>,
+    /// ISO 639-3 code of the language most of a session is written in
+    pub language: Option<String>,
+    /// Extra SQL condition on `sessions` aliased as `s`, such as the API's
+    /// owner scope
+    pub condition: Option<String>,
+}
+
+impl SearchFilters {
+    /// Set the date range from `--after` / `--before` style values
+    pub fn with_dates(mut self, after: Option<&str>, before: Option<&str>) -> Result<Self> {
+        let range = DateFilter::parse(after, before)?;
+        self.after = range.after.map(|dt| dt.timestamp_millis());
+        self.before = range.before.map(|dt| dt.timestamp_millis());
+        Ok(self)
+    }
+
+    /// Set the language from a `--lang` style value: an ISO 639-3 code or a
+    /// language name
+    pub fn with_language(mut self, language: Option<&str>) -> Result<Self> {
+        self.language = match language.filter(|l| !l.trim().is_empty()) {
+            Some(name) => Some(
+                language::parse_language(name)
+                    .with_context(|| {
+                        format!(
+                            "Unknown language '{}' (use an ISO 639-3 code such as spa, or a name)",
+                            name
+                        )
+                    })?
+                    .to_string(),
+            ),
+            None => None,
+        };
+        Ok(self)
+    }
+}
+
+/// A search to run
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    pub query: String,
+    pub mode: SearchMode,
+    pub filters: SearchFilters,
+    /// Results to skip
+    pub offset: usize,
+    /// Results to return
+    pub limit: usize,
+    /// Count matches per provider, workspace, tag, language, and month
+    pub facets: bool,
+}
+
+/// A matching session
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub id: String,
+    pub title: String,
+    pub provider: String,
+    pub workspace_id: Option<String>,
+    pub workspace_name: Option<String>,
+    pub message_count: i64,
+    pub updated_at: i64,
+    /// Relevance; higher is better. BM25 in text mode, cosine similarity in
+    /// semantic mode.
+    pub score: f64,
+    /// Excerpt of the best matching message
+    pub snippet: Option<String>,
+    /// `message_index` of that message in `messages_v2`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_index: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: i64,
+}
+
+/// Matching sessions counted by a few of their properties
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Facets {
+    pub providers: Vec<FacetCount>,
+    pub workspaces: Vec<FacetCount>,
+    pub tags: Vec<FacetCount>,
+    /// ISO 639-3 codes
+    pub languages: Vec<FacetCount>,
+    /// `YYYY-MM` of the last update, newest first
+    pub months: Vec<FacetCount>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResults {
+    pub hits: Vec<SearchHit>,
+    /// Matching sessions across all pages
+    pub total: i64,
+    pub facets: Option<Facets>,
+}
+
+/// Turns text into vectors for semantic mode
+pub trait Embedder {
+    /// Model name, recorded with cached embeddings
+    fn model(&self) -> &str;
+
+    fn embed(&self, input: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// An OpenAI-compatible `/embeddings` endpoint
+pub struct HttpEmbedder {
+    client: reqwest::blocking::Client,
+    /// `openai`, `ollama`, or `custom` for `CSM_EMBEDDING_URL`
+    provider: &'static str,
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+    policy: Option<PolicyGuard>,
+}
+
+impl HttpEmbedder {
+    /// The endpoint named by `CSM_EMBEDDING_URL`, or else OpenAI when
+    /// `OPENAI_API_KEY` is set, or else Ollama at `OLLAMA_HOST`.
+    /// `CSM_EMBEDDING_MODEL` overrides the model. `None` as well when the
+    /// workspace policies cannot be read.
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let (provider, base_url, api_key, model) = if let Some(url) = var("CSM_EMBEDDING_URL") {
+            (
+                "custom",
+                url,
+                var("CSM_EMBEDDING_API_KEY"),
+                "text-embedding-3-small",
+            )
+        } else if let Some(key) = var("OPENAI_API_KEY") {
+            let url = var("OPENAI_BASE_URL").unwrap_or_else(|| "https://api.openai.com/v1".into());
+            ("openai", url, Some(key), "text-embedding-3-small")
+        } else if let Some(host) = var("OLLAMA_HOST") {
+            let host = if host.contains("://") {
+                host
+            } else {
+                format!("http://{}", host)
+            };
+            (
+                "ollama",
+                format!("{}/v1", host.trim_end_matches('/')),
+                None,
+                "nomic-embed-text",
+            )
+        } else {
+            return None;
+        };
+
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(120))
+            .build()
+            .ok()?;
+        let policy = match PolicyGuard::here("embeddings") {
+            Ok(policy) => policy,
+            Err(e) => {
+                eprintln!("[WARN] Embeddings disabled: {}", e);
+                return None;
+            }
+        };
+        Some(Self {
+            client,
+            provider,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            model: var("CSM_EMBEDDING_MODEL").unwrap_or_else(|| model.to_string()),
+            policy,
+        })
+    }
+}
+
+impl Embedder for HttpEmbedder {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn embed(&self, input: &[String]) -> Result<Vec<Vec<f32>>> {
+        #[derive(Deserialize)]
+        struct Response {
+            data: Vec<Embedding>,
+        }
+        #[derive(Deserialize)]
+        struct Embedding {
+            embedding: Vec<f32>,
+        }
+        if let Some(policy) = &self.policy {
+            if let Err(reason) = policy.check(self.provider, Some(&self.model)) {
+                bail!("Embeddings from {} are not allowed here: {}", self.provider, reason);
+            }
+        }
+
+        let mut request = self
+            .client
+            .post(format!("{}/embeddings", self.base_url))
+            .json(&serde_json::json!({ "model": self.model, "input": input }));
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+        let response: Response = request
+            .send()
+            .and_then(|r| r.error_for_status())
+            .with_context(|| format!("Embedding request to {} failed", self.base_url))?
+            .json()?;
+        if response.data.len() != input.len() {
+            bail!(
+                "Expected {} embeddings, got {}",
+                input.len(),
+                response.data.len()
+            );
+        }
+        Ok(response.data.into_iter().map(|d| d.embedding).collect())
+    }
+}
+
+/// SQL and named parameters for the sessions a search matches, as rows of
+/// `(id, score)`
+struct Matched {
+    sql: String,
+    params: Vec<(String, Value)>,
+}
+
+impl Matched {
+    fn named(&self) -> Vec<(&str, &dyn ToSql)> {
+        self.params
+            .iter()
+            .map(|(name, value)| (name.as_str(), value as &dyn ToSql))
+            .collect()
+    }
+}
+
+/// Quote each word so FTS5 treats the query as plain terms, all required
+fn fts_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn table_exists(conn: &Connection, name: &str) -> Result<bool> {
+    Ok(conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE name = ?1",
+            [name],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some())
+}
+
+/// The full-text index that can answer `query`, or `None` to match with
+/// `LIKE`. The trigram index finds no word shorter than three characters.
+fn fts_table(conn: &Connection, query: &str) -> Result<Option<&'static str>> {
+    if language::has_unspaced_script(query) {
+        let indexable = query
+            .split_whitespace()
+            .all(|term| term.chars().count() >= 3);
+        return Ok((indexable && table_exists(conn, "messages_trigram")?)
+            .then_some("messages_trigram"));
+    }
+    Ok(table_exists(conn, "messages_fts")?.then_some("messages_fts"))
+}
+
+/// `WHERE` conditions for `filters`, adding their parameters to `params`
+fn filter_sql(filters: &SearchFilters, params: &mut Vec<(String, Value)>) -> String {
+    let mut conditions = vec!["1".to_string()];
+    if !filters.providers.is_empty() {
+        let names: Vec<String> = filters
+            .providers
+            .iter()
+            .enumerate()
+            .map(|(i, provider)| {
+                params.push((format!(":provider{}", i), provider.to_lowercase().into()));
+                format!(":provider{}", i)
+            })
+            .collect();
+        conditions.push(format!("LOWER(s.provider) IN ({})", names.join(", ")));
+    }
+    if let Some(workspace) = &filters.workspace {
+        params.push((":workspace".into(), workspace.clone().into()));
+        conditions.push("(s.workspace_id = :workspace OR s.workspace_name = :workspace)".into());
+    }
+    if let Some(after) = filters.after {
+        params.push((":after".into(), after.into()));
+        conditions.push("s.updated_at >= :after".into());
+    }
+    if let Some(before) = filters.before {
+        params.push((":before".into(), before.into()));
+        conditions.push("s.updated_at < :before".into());
+    }
+    for (i, tag) in filters.tags.iter().enumerate() {
+        params.push((format!(":tag{}", i), tag.clone().into()));
+        conditions.push(format!(
+            "EXISTS (SELECT 1 FROM session_tags t WHERE t.session_id = s.id AND t.tag = :tag{})",
+            i
+        ));
+    }
+    for (i, topic) in filters.topics.iter().enumerate() {
+        let param = format!(":topic{}", i);
+        params.push((param.clone(), topic.to_lowercase().into()));
+        conditions.push(topics::topic_condition("s.id", &param));
+    }
+    if let Some(language) = &filters.language {
+        params.push((":language".into(), language.clone().into()));
+        conditions.push("s.language = :language".into());
+    }
+    if let Some(condition) = &filters.condition {
+        conditions.push(format!("({})", condition));
+    }
+    conditions.join(" AND ")
+}
+
+/// Sessions matching the query text and filters
+fn text_matches(conn: &Connection, query: &str, filters: &SearchFilters) -> Result<Matched> {
+    let mut params = Vec::new();
+    let filters = filter_sql(filters, &mut params);
+    if query.trim().is_empty() {
+        return Ok(Matched {
+            sql: format!(
+                "SELECT s.id AS id, 0.0 AS score FROM sessions s WHERE {}",
+                filters
+            ),
+            params,
+        });
+    }
+
+    params.push((":like".into(), format!("%{}%", query.trim()).into()));
+    let messages = match fts_table(conn, query)? {
+        Some(table) => {
+            params.push((":fts".into(), fts_query(query).into()));
+            // bm25() cannot be aggregated directly
+            format!(
+                "SELECT session_id, -SUM(rank) AS score FROM (
+                     SELECT m.session_id AS session_id, {0}.rank AS rank
+                     FROM {0} JOIN messages_v2 m ON m.id = {0}.rowid
+                     WHERE {0} MATCH :fts
+                 ) GROUP BY session_id",
+                table
+            )
+        }
+        None => "SELECT session_id, COUNT(*) AS score FROM messages_v2
+                 WHERE content_raw LIKE :like
+                 GROUP BY session_id"
+            .to_string(),
+    };
+    Ok(Matched {
+        sql: format!(
+            "SELECT s.id AS id,
+                    COALESCE(h.score, 0) + CASE WHEN s.title LIKE :like THEN {} ELSE 0 END AS score
+             FROM sessions s LEFT JOIN ({}) h ON h.session_id = s.id
+             WHERE (h.session_id IS NOT NULL OR s.title LIKE :like) AND {}",
+            TITLE_BOOST, messages, filters
+        ),
+        params,
+    })
+}
+
+fn hit_from_row(row: &rusqlite::Row) -> rusqlite::Result<SearchHit> {
+    Ok(SearchHit {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        provider: row.get(2)?,
+        workspace_id: row.get(3)?,
+        workspace_name: row.get(4)?,
+        message_count: row.get::<_, Option<i64>>(5)?.unwrap_or(0),
+        updated_at: row.get(6)?,
+        score: row.get(7)?,
+        snippet: None,
+        message_index: None,
+    })
+}
+
+const HIT_COLUMNS: &str = "s.id, s.title, s.provider, s.workspace_id, s.workspace_name, \
+     s.message_count, s.updated_at, r.score";
+
+fn count(conn: &Connection, matched: &Matched) -> Result<i64> {
+    Ok(conn.query_row(
+        &format!("SELECT COUNT(*) FROM ({})", matched.sql),
+        matched.named().as_slice(),
+        |row| row.get(0),
+    )?)
+}
+
+/// Count matching sessions grouped by `expr`, an expression over `s`
+fn facet(
+    conn: &Connection,
+    matched: &Matched,
+    expr: &str,
+    newest_first: bool,
+) -> Result<Vec<FacetCount>> {
+    let order = if newest_first { "1 DESC" } else { "2 DESC, 1" };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {expr} AS value, COUNT(*) FROM ({matched}) r JOIN sessions s ON s.id = r.id
+         WHERE value IS NOT NULL GROUP BY value ORDER BY {order} LIMIT {limit}",
+        matched = matched.sql,
+        limit = FACET_LIMIT,
+    ))?;
+    let rows = stmt.query_map(matched.named().as_slice(), |row| {
+        Ok(FacetCount {
+            value: row.get(0)?,
+            count: row.get(1)?,
+        })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+}
+
+fn facets(conn: &Connection, matched: &Matched) -> Result<Facets> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT t.tag, COUNT(*) FROM ({}) r JOIN session_tags t ON t.session_id = r.id
+         GROUP BY t.tag ORDER BY 2 DESC, 1 LIMIT {}",
+        matched.sql, FACET_LIMIT
+    ))?;
+    let tags = stmt
+        .query_map(matched.named().as_slice(), |row| {
+            Ok(FacetCount {
+                value: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(Facets {
+        providers: facet(conn, matched, "s.provider", false)?,
+        workspaces: facet(
+            conn,
+            matched,
+            "COALESCE(s.workspace_name, s.workspace_id)",
+            false,
+        )?,
+        tags,
+        languages: facet(conn, matched, "s.language", false)?,
+        months: facet(
+            conn,
+            matched,
+            "strftime('%Y-%m', s.updated_at / 1000, 'unixepoch')",
+            true,
+        )?,
+    })
+}
+
+/// Excerpt of the session's best matching message, found through the
+/// full-text index `fts` if there is one, and the message's index
+fn message_snippet(
+    conn: &Connection,
+    session_id: &str,
+    query: &str,
+    fts: Option<&str>,
+) -> Result<Option<(String, i64)>> {
+    let message: Option<(String, i64)> = if let Some(table) = fts {
+        conn.query_row(
+            &format!(
+                "SELECT m.content_raw, m.message_index FROM {0} JOIN messages_v2 m ON m.id = {0}.rowid
+                 WHERE {0} MATCH ?1 AND m.session_id = ?2
+                 ORDER BY bm25({0}) LIMIT 1",
+                table
+            ),
+            params![fts_query(query), session_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?
+    } else {
+        conn.query_row(
+            "SELECT content_raw, message_index FROM messages_v2
+             WHERE session_id = ?1 AND content_raw LIKE ?2
+             ORDER BY message_index LIMIT 1",
+            params![session_id, format!("%{}%", query.trim())],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?
+    };
+    // Point the excerpt at the first term FTS or LIKE would have matched
+    let term = if fts.is_some() {
+        query.split_whitespace().next().unwrap_or(query)
+    } else {
+        query.trim()
+    };
+    Ok(message.map(|(content, index)| (snippet(&content, term, 160), index)))
+}
+
+/// Excerpt of the message in a semantic hit closest to `query`, and its
+/// index: the best full-text match for any of its words, else the session's
+/// first message
+fn semantic_snippet(
+    conn: &Connection,
+    session_id: &str,
+    query: &str,
+    fts: bool,
+) -> Result<Option<(String, i64)>> {
+    // Short words such as "a" or "is" match nearly every message
+    let terms: Vec<&str> = query
+        .split_whitespace()
+        .filter(|term| term.chars().count() >= 3)
+        .collect();
+    let mut message: Option<(String, i64)> = None;
+    if fts && !terms.is_empty() {
+        let any = terms
+            .iter()
+            .map(|term| fts_query(term))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        message = conn
+            .query_row(
+                "SELECT m.content_raw, m.message_index
+                 FROM messages_fts JOIN messages_v2 m ON m.id = messages_fts.rowid
+                 WHERE messages_fts MATCH ?1 AND m.session_id = ?2
+                 ORDER BY bm25(messages_fts) LIMIT 1",
+                params![any, session_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+    }
+    if message.is_none() {
+        message = conn
+            .query_row(
+                "SELECT content_raw, message_index FROM messages_v2 WHERE session_id = ?1
+                 ORDER BY message_index, id LIMIT 1",
+                [session_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+    }
+    Ok(message.map(|(content, index)| {
+        let lower = content.to_lowercase();
+        let term = terms
+            .iter()
+            .find(|term| lower.contains(&term.to_lowercase()))
+            .copied()
+            .unwrap_or(query);
+        (snippet(&content, term, 160), index)
+    }))
+}
+
+fn text_search(conn: &Connection, options: &SearchOptions) -> Result<SearchResults> {
+    let matched = text_matches(conn, &options.query, &options.filters)?;
+    let total = count(conn, &matched)?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM ({}) r JOIN sessions s ON s.id = r.id
+         ORDER BY r.score DESC, s.updated_at DESC, s.id LIMIT {} OFFSET {}",
+        HIT_COLUMNS, matched.sql, options.limit, options.offset
+    ))?;
+    let mut hits = stmt
+        .query_map(matched.named().as_slice(), hit_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    if !options.query.trim().is_empty() {
+        let fts = fts_table(conn, &options.query)?;
+        for hit in &mut hits {
+            if let Some((snippet, index)) = message_snippet(conn, &hit.id, &options.query, fts)? {
+                hit.snippet = Some(snippet);
+                hit.message_index = Some(index);
+            }
+        }
+    }
+
+    Ok(SearchResults {
+        hits,
+        total,
+        facets: options.facets.then(|| facets(conn, &matched)).transpose()?,
+    })
+}
+
+fn ensure_embeddings_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS session_embeddings (
+            session_id TEXT PRIMARY KEY,
+            model TEXT NOT NULL,
+            updated_at INTEGER NOT NULL,
+            vector BLOB NOT NULL,
+            FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+fn to_blob(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn from_blob(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+pub(crate) fn cosine(a: &[f32], b: &[f32]) -> f64 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f64, 0.0f64, 0.0f64);
+    for (x, y) in a.iter().zip(b) {
+        let (x, y) = (f64::from(*x), f64::from(*y));
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// The text embedded for a session: its title and the start of its messages
+fn session_text(conn: &Connection, session_id: &str, title: &str) -> Result<String> {
+    let mut text = format!("{}\n", title);
+    let mut stmt = conn.prepare(
+        "SELECT content_raw FROM messages_v2 WHERE session_id = ?1 ORDER BY message_index, id",
+    )?;
+    let mut rows = stmt.query([session_id])?;
+    while let Some(row) = rows.next()? {
+        if text.len() >= EMBED_CHARS {
+            break;
+        }
+        text.push_str(&row.get::<_, String>(0)?);
+        text.push('\n');
+    }
+    Ok(prefix_chars(&text, EMBED_CHARS).to_string())
+}
+
+/// Embeddings for `hits`, computing and caching any that are missing or
+/// older than the session
+pub(crate) fn session_embeddings(
+    conn: &Connection,
+    hits: &[SearchHit],
+    embedder: &dyn Embedder,
+) -> Result<HashMap<String, Vec<f32>>> {
+    ensure_embeddings_table(conn)?;
+    let mut vectors = HashMap::new();
+    let mut stale = Vec::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT vector FROM session_embeddings
+             WHERE session_id = ?1 AND model = ?2 AND updated_at = ?3",
+        )?;
+        for hit in hits {
+            let cached: Option<Vec<u8>> = stmt
+                .query_row(params![hit.id, embedder.model(), hit.updated_at], |row| {
+                    row.get(0)
+                })
+                .optional()?;
+            match cached {
+                Some(blob) => {
+                    vectors.insert(hit.id.clone(), from_blob(&blob));
+                }
+                None => stale.push(hit),
+            }
+        }
+    }
+
+    for batch in stale.chunks(EMBED_BATCH) {
+        let texts = batch
+            .iter()
+            .map(|hit| session_text(conn, &hit.id, &hit.title))
+            .collect::<Result<Vec<_>>>()?;
+        let embedded = embedder.embed(&texts)?;
+        for (hit, vector) in batch.iter().zip(embedded) {
+            conn.execute(
+                "INSERT OR REPLACE INTO session_embeddings (session_id, model, updated_at, vector)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![hit.id, embedder.model(), hit.updated_at, to_blob(&vector)],
+            )?;
+            vectors.insert(hit.id.clone(), vector);
+        }
+    }
+    Ok(vectors)
+}
+
+fn semantic_search(
+    conn: &Connection,
+    options: &SearchOptions,
+    embedder: &dyn Embedder,
+) -> Result<SearchResults> {
+    if options.query.trim().is_empty() {
+        bail!("Semantic search needs a query");
+    }
+    // Candidates are every session the filters allow
+    let matched = text_matches(conn, "", &options.filters)?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM ({}) r JOIN sessions s ON s.id = r.id
+         ORDER BY s.updated_at DESC LIMIT {}",
+        HIT_COLUMNS, matched.sql, SEMANTIC_CANDIDATES
+    ))?;
+    let mut candidates = stmt
+        .query_map(matched.named().as_slice(), hit_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let vectors = session_embeddings(conn, &candidates, embedder)?;
+    let query = embedder
+        .embed(std::slice::from_ref(&options.query))?
+        .pop()
+        .context("No embedding returned for the query")?;
+    for hit in &mut candidates {
+        hit.score = vectors.get(&hit.id).map_or(0.0, |v| cosine(&query, v));
+    }
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    let total = candidates.len() as i64;
+    let mut hits: Vec<SearchHit> = candidates
+        .into_iter()
+        .skip(options.offset)
+        .take(options.limit)
+        .collect();
+    let fts = table_exists(conn, "messages_fts")?;
+    for hit in &mut hits {
+        if let Some((snippet, index)) = semantic_snippet(conn, &hit.id, &options.query, fts)? {
+            hit.snippet = Some(snippet);
+            hit.message_index = Some(index);
+        }
+    }
+
+    Ok(SearchResults {
+        total,
+        hits,
+        facets: options.facets.then(|| facets(conn, &matched)).transpose()?,
+    })
+}
+
+/// Run a search. Semantic mode needs an `embedder`.
+pub fn search(
+    conn: &Connection,
+    options: &SearchOptions,
+    embedder: Option<&dyn Embedder>,
+) -> Result<SearchResults> {
+    reviews::ensure_tags_table(conn)?;
+    topics::ensure_topics_table(conn)?;
+    language::ensure_language_columns(conn)?;
+    match options.mode {
+        SearchMode::Text => text_search(conn, options),
+        SearchMode::Semantic => {
+            let embedder = embedder.context(
+                "Semantic search needs an embeddings endpoint: set CSM_EMBEDDING_URL, OPENAI_API_KEY, or OLLAMA_HOST",
+            )?;
+            semantic_search(conn, options, embedder)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Embeds text as counts of a few words
+    struct WordCounts;
+
+    impl Embedder for WordCounts {
+        fn model(&self) -> &str {
+            "word-counts"
+        }
+
+        fn embed(&self, input: &[String]) -> Result<Vec<Vec<f32>>> {
+            Ok(input
+                .iter()
+                .map(|text| {
+                    ["rust", "python", "deploy"]
+                        .iter()
+                        .map(|word| text.matches(word).count() as f32)
+                        .collect()
+                })
+                .collect())
+        }
+    }
+
+    fn test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE sessions (
+                id TEXT PRIMARY KEY, provider TEXT NOT NULL, workspace_id TEXT,
+                workspace_name TEXT, title TEXT NOT NULL, message_count INTEGER,
+                updated_at INTEGER NOT NULL, user_id TEXT
+            );
+            CREATE TABLE messages_v2 (
+                id INTEGER PRIMARY KEY, session_id TEXT NOT NULL, message_index INTEGER NOT NULL,
+                content_raw TEXT NOT NULL
+            );
+            CREATE VIRTUAL TABLE messages_fts USING fts5(content_raw);
+            CREATE TRIGGER messages_v2_ai AFTER INSERT ON messages_v2 BEGIN
+                INSERT INTO messages_fts(rowid, content_raw) VALUES (new.id, new.content_raw);
+            END;
+
+            INSERT INTO sessions VALUES
+                ('a', 'copilot', 'w1', 'api', 'Borrow checker', 2, 1700000000000, NULL),
+                ('b', 'cursor', 'w2', 'web', 'Flask app', 1, 1710000000000, NULL),
+                ('c', 'copilot', 'w2', 'web', 'Deploy rust service', 1, 1720000000000, 'u1');
+            INSERT INTO messages_v2 (session_id, message_index, content_raw) VALUES
+                ('a', 0, 'Why does rust reject this borrow? rust lifetimes'),
+                ('a', 1, 'Because the reference outlives the value'),
+                ('b', 0, 'Write a python flask endpoint'),
+                ('c', 0, 'deploy the rust binary with systemd');
+            "#,
+        )
+        .unwrap();
+        reviews::ensure_tags_table(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO session_tags VALUES ('c', 'ops', 'user', 0)",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn ids(results: &SearchResults) -> Vec<&str> {
+        results.hits.iter().map(|h| h.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_search() {
+        let conn = test_db();
+        let mut options = SearchOptions {
+            query: "rust".to_string(),
+            limit: 10,
+            facets: true,
+            ..Default::default()
+        };
+
+        let results = search(&conn, &options, None).unwrap();
+        assert_eq!(results.total, 2);
+        // The title match outranks the session that mentions rust twice
+        assert_eq!(ids(&results), vec!["c", "a"]);
+        assert!(results.hits[1].snippet.as_deref().unwrap().contains("rust"));
+        assert_eq!(results.hits[1].message_index, Some(0));
+        let facets = results.facets.unwrap();
+        assert_eq!(facets.providers[0].value, "copilot");
+        assert_eq!(facets.tags[0].value, "ops");
+        assert_eq!(facets.months[0].value, "2024-07");
+
+        options.filters.tags = vec!["ops".to_string()];
+        options.filters.condition = Some("s.user_id IS NULL".to_string());
+        assert_eq!(search(&conn, &options, None).unwrap().total, 0);
+
+        options.filters = SearchFilters {
+            providers: vec!["Cursor".to_string()],
+            before: Some(1715000000000),
+            ..Default::default()
+        };
+        options.query = "\"flask".to_string();
+        assert_eq!(ids(&search(&conn, &options, None).unwrap()), vec!["b"]);
+
+        topics::ensure_topics_table(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO session_topics VALUES ('a', 'lang/rust', 0.5, 'rust')",
+            [],
+        )
+        .unwrap();
+        options.filters = SearchFilters {
+            topics: vec!["Lang".to_string()],
+            ..Default::default()
+        };
+        options.query = "rust".to_string();
+        assert_eq!(ids(&search(&conn, &options, None).unwrap()), vec!["a"]);
+
+        options.filters = SearchFilters::default();
+        options.query = "python code".to_string();
+        options.mode = SearchMode::Semantic;
+        assert!(search(&conn, &options, None).is_err());
+        let results = search(&conn, &options, Some(&WordCounts)).unwrap();
+        assert_eq!(results.total, 3);
+        assert_eq!(results.hits[0].id, "b");
+        assert_eq!(
+            results.hits[0].snippet.as_deref(),
+            Some("Write a python flask endpoint")
+        );
+        let cached: i64 = conn
+            .query_row("SELECT COUNT(*) FROM session_embeddings", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(cached, 3);
+    }
+    #[test]
+    fn test_search_languages() {
+        let conn = test_db();
+        language::ensure_language_columns(&conn).unwrap();
+        let text = "借用チェッカーがこの参照を拒否します";
+        conn.execute_batch(&format!(
+            r#"
+            CREATE VIRTUAL TABLE messages_trigram USING fts5(content_raw, tokenize = 'trigram');
+            INSERT INTO sessions (id, provider, title, message_count, updated_at, language)
+                VALUES ('d', 'copilot', 'Untitled', 1, 1725000000000, 'jpn');
+            UPDATE sessions SET language = 'eng' WHERE id != 'd';
+            INSERT INTO messages_v2 (id, session_id, message_index, content_raw)
+                VALUES (10, 'd', 0, '{0}');
+            INSERT INTO messages_trigram (rowid, content_raw) VALUES (10, '{0}');
+            "#,
+            text
+        ))
+        .unwrap();
+
+        let mut options = SearchOptions {
+            query: "チェッカー".to_string(),
+            limit: 10,
+            facets: true,
+            ..Default::default()
+        };
+        let results = search(&conn, &options, None).unwrap();
+        assert_eq!(ids(&results), vec!["d"]);
+        assert_eq!(results.hits[0].snippet.as_deref(), Some(text));
+        // Too short for the trigram index
+        options.query = "参照".to_string();
+        assert_eq!(ids(&search(&conn, &options, None).unwrap()), vec!["d"]);
+
+        options.query = String::new();
+        let facets = search(&conn, &options, None).unwrap().facets.unwrap();
+        assert_eq!(
+            facets.languages,
+            [
+                FacetCount {
+                    value: "eng".to_string(),
+                    count: 3
+                },
+                FacetCount {
+                    value: "jpn".to_string(),
+                    count: 1
+                },
+            ]
+        );
+        options.filters = SearchFilters::default()
+            .with_language(Some("Japanese"))
+            .unwrap();
+        assert_eq!(ids(&search(&conn, &options, None).unwrap()), vec!["d"]);
+        assert!(SearchFilters::default()
+            .with_language(Some("elvish"))
+            .is_err());
+    }
+}
@@ -0,0 +1,450 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Structured diffs between versions of a chat session
+//!
+//! A session in the harvest database can be compared with one of its
+//! checkpoints (`csm harvest checkpoint`), with itself as of a git commit of
+//! the harvest database (`csm harvest git`), or with another session. Turns
+//! are matched by role and content, and a removed turn followed by an added
+//! turn of the same role is reported as an edit. Used by `csm diff session`
+//! and `GET /api/sessions/{id}/diff`.
+
+use crate::commands::message_content;
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+
+/// Largest LCS table built before unmatched middles are treated as replaced
+const MAX_CELLS: usize = 16_000_000;
+
+/// One message of a session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Turn {
+    /// `user` or `assistant`
+    pub role: String,
+    pub content: String,
+}
+
+/// What a session is compared against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Against {
+    /// A checkpoint of the same session by number
+    Checkpoint(i64),
+    /// The same session in the harvest database as of a git commit
+    Commit(String),
+    /// Another session
+    Session(String),
+}
+
+impl Against {
+    /// Read `checkpoint:N`, `commit:REF`, or `session:ID`, or work out which
+    /// a bare value is: a checkpoint number of `session_id`, another
+    /// session's ID, or else a commit
+    pub fn resolve(conn: &Connection, session_id: &str, spec: &str) -> Result<Self> {
+        if let Some(number) = spec.strip_prefix("checkpoint:") {
+            let number = number
+                .parse()
+                .with_context(|| format!("Invalid checkpoint number '{}'", number))?;
+            return Ok(Against::Checkpoint(number));
+        }
+        if let Some(commit) = spec.strip_prefix("commit:") {
+            return Ok(Against::Commit(commit.to_string()));
+        }
+        if let Some(other) = spec.strip_prefix("session:") {
+            return Ok(Against::Session(other.to_string()));
+        }
+        if let Ok(number) = spec.parse::<i64>() {
+            if checkpoint_exists(conn, session_id, number)? {
+                return Ok(Against::Checkpoint(number));
+            }
+        }
+        if session_exists(conn, spec)? {
+            return Ok(Against::Session(spec.to_string()));
+        }
+        Ok(Against::Commit(spec.to_string()))
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Against::Checkpoint(number) => format!("checkpoint #{}", number),
+            Against::Commit(commit) => format!("commit {}", commit),
+            Against::Session(id) => format!("session {}", id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Edited,
+}
+
+/// A turn that differs between the two versions
+#[derive(Debug, Clone, Serialize)]
+pub struct TurnChange {
+    pub kind: ChangeKind,
+    pub role: String,
+    /// Position in the old version, for removed and edited turns
+    pub old_index: Option<usize>,
+    /// Position in the new version, for added and edited turns
+    pub new_index: Option<usize>,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionDiff {
+    pub session_id: String,
+    /// What the session was compared against
+    pub against: String,
+    pub added: usize,
+    pub removed: usize,
+    pub edited: usize,
+    pub unchanged: usize,
+    pub changes: Vec<TurnChange>,
+}
+
+fn checkpoint_exists(conn: &Connection, session_id: &str, number: i64) -> Result<bool> {
+    Ok(conn
+        .query_row(
+            "SELECT 1 FROM checkpoints WHERE session_id = ?1 AND checkpoint_number = ?2",
+            params![session_id, number],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some())
+}
+
+fn session_exists(conn: &Connection, session_id: &str) -> Result<bool> {
+    Ok(conn
+        .query_row("SELECT 1 FROM sessions WHERE id = ?1", [session_id], |_| {
+            Ok(())
+        })
+        .optional()?
+        .is_some())
+}
+
+/// The turns of a session as harvested
+pub fn session_turns(conn: &Connection, session_id: &str) -> Result<Vec<Turn>> {
+    if !session_exists(conn, session_id)? {
+        bail!("Session not found: {}", session_id);
+    }
+    let mut stmt = conn.prepare(
+        "SELECT id, role, content_raw FROM messages_v2 WHERE session_id = ?1
+         ORDER BY message_index, id",
+    )?;
+    let turns = stmt
+        .query_map([session_id], |row| {
+            Ok(Turn {
+                role: row.get(1)?,
+                content: message_content(conn, row.get(0)?, row.get(2)?)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(turns)
+}
+
+/// The turns saved in a checkpoint
+pub fn checkpoint_turns(conn: &Connection, session_id: &str, number: i64) -> Result<Vec<Turn>> {
+    let snapshot: Option<String> = conn
+        .query_row(
+            "SELECT snapshot FROM checkpoints WHERE session_id = ?1 AND checkpoint_number = ?2",
+            params![session_id, number],
+            |row| row.get(0),
+        )
+        .optional()?
+        .with_context(|| format!("Checkpoint #{} not found for {}", number, session_id))?;
+    let snapshot: serde_json::Value = serde_json::from_str(snapshot.as_deref().unwrap_or("{}"))
+        .context("Failed to parse checkpoint snapshot")?;
+    let messages = snapshot["messages"].as_array().cloned().unwrap_or_default();
+    Ok(messages
+        .iter()
+        .map(|message| match message.as_str() {
+            // Snapshots from before roles were recorded
+            Some(content) => Turn {
+                role: "unknown".to_string(),
+                content: content.to_string(),
+            },
+            None => Turn {
+                role: message["role"].as_str().unwrap_or("unknown").to_string(),
+                content: message["content"].as_str().unwrap_or_default().to_string(),
+            },
+        })
+        .collect())
+}
+
+/// The turns of a session in the harvest database as committed at `commit`
+pub fn commit_turns(db_path: &Path, commit: &str, session_id: &str) -> Result<Vec<Turn>> {
+    let db_dir = match db_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let db_name = db_path
+        .file_name()
+        .context("Invalid harvest database path")?
+        .to_string_lossy();
+    // A value starting with '-' would be read by git as an option
+    if commit.starts_with('-') {
+        bail!("'{}' is not a commit", commit);
+    }
+    let resolved = Command::new("git")
+        .current_dir(db_dir)
+        .args(["rev-parse", "--verify", "--quiet", "--end-of-options"])
+        .arg(format!("{}^{{commit}}", commit))
+        .output()
+        .context("Failed to run git rev-parse")?;
+    if !resolved.status.success() {
+        bail!(
+            "'{}' is not a checkpoint, a session, or a commit of the harvest database",
+            commit
+        );
+    }
+    let hash = String::from_utf8_lossy(&resolved.stdout).trim().to_string();
+
+    let output = Command::new("git")
+        .current_dir(db_dir)
+        .args(["show", &format!("{}:./{}", hash, db_name)])
+        .output()
+        .context("Failed to run git show")?;
+    if !output.status.success() {
+        bail!(
+            "'{}' is not a checkpoint, a session, or a commit of the harvest database: {}",
+            commit,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let copy = std::env::temp_dir().join(format!("chasm-diff-{}.db", uuid::Uuid::new_v4()));
+    std::fs::write(&copy, &output.stdout)?;
+    let turns = Connection::open(&copy)
+        .map_err(anyhow::Error::from)
+        .and_then(|conn| session_turns(&conn, session_id));
+    let _ = std::fs::remove_file(&copy);
+    turns.with_context(|| format!("At commit {}", commit))
+}
+
+/// Compare a session with an older version of itself or another session
+pub fn diff_session(
+    conn: &Connection,
+    db_path: &Path,
+    session_id: &str,
+    against: &Against,
+) -> Result<SessionDiff> {
+    let new = session_turns(conn, session_id)?;
+    let old = match against {
+        Against::Checkpoint(number) => checkpoint_turns(conn, session_id, *number)?,
+        Against::Commit(commit) => commit_turns(db_path, commit, session_id)?,
+        Against::Session(other) => session_turns(conn, other)?,
+    };
+    let (changes, unchanged) = diff_turns(&old, &new);
+    let count = |kind| changes.iter().filter(|c| c.kind == kind).count();
+    Ok(SessionDiff {
+        session_id: session_id.to_string(),
+        against: against.label(),
+        added: count(ChangeKind::Added),
+        removed: count(ChangeKind::Removed),
+        edited: count(ChangeKind::Edited),
+        unchanged,
+        changes,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Keep,
+    Remove(usize),
+    Add(usize),
+}
+
+/// Edit script turning `old` into `new` by longest common subsequence
+fn edit_script(old: &[Turn], new: &[Turn]) -> Vec<Op> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_mid, new_mid) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+    let (n, m) = (old_mid.len(), new_mid.len());
+
+    let mut ops = vec![Op::Keep; prefix];
+    if (n + 1) * (m + 1) > MAX_CELLS {
+        ops.extend((0..n).map(|i| Op::Remove(prefix + i)));
+        ops.extend((0..m).map(|j| Op::Add(prefix + j)));
+    } else {
+        // lcs[i][j] is the LCS length of old_mid[i..] and new_mid[j..]
+        let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+        let at = |i: usize, j: usize| i * (m + 1) + j;
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[at(i, j)] = if old_mid[i] == new_mid[j] {
+                    lcs[at(i + 1, j + 1)] + 1
+                } else {
+                    lcs[at(i + 1, j)].max(lcs[at(i, j + 1)])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && old_mid[i] == new_mid[j] {
+                ops.push(Op::Keep);
+                i += 1;
+                j += 1;
+            } else if j < m && (i == n || lcs[at(i, j + 1)] >= lcs[at(i + 1, j)]) {
+                ops.push(Op::Add(prefix + j));
+                j += 1;
+            } else {
+                ops.push(Op::Remove(prefix + i));
+                i += 1;
+            }
+        }
+    }
+    ops.extend(std::iter::repeat_n(Op::Keep, suffix));
+    ops
+}
+
+/// Changed turns between two versions, and the number of unchanged ones
+pub fn diff_turns(old: &[Turn], new: &[Turn]) -> (Vec<TurnChange>, usize) {
+    let ops = edit_script(old, new);
+    let unchanged = ops.iter().filter(|op| **op == Op::Keep).count();
+
+    let removed = |i: usize| TurnChange {
+        kind: ChangeKind::Removed,
+        role: old[i].role.clone(),
+        old_index: Some(i),
+        new_index: None,
+        old: Some(old[i].content.clone()),
+        new: None,
+    };
+    let added = |j: usize| TurnChange {
+        kind: ChangeKind::Added,
+        role: new[j].role.clone(),
+        old_index: None,
+        new_index: Some(j),
+        old: None,
+        new: Some(new[j].content.clone()),
+    };
+
+    // Within each run of changes between kept turns, pair removed and added
+    // turns of the same role, in order, as edits
+    let mut changes = Vec::new();
+    for run in ops.split(|op| *op == Op::Keep) {
+        let removes: Vec<usize> = run
+            .iter()
+            .filter_map(|op| match op {
+                Op::Remove(i) => Some(*i),
+                _ => None,
+            })
+            .collect();
+        let adds: Vec<usize> = run
+            .iter()
+            .filter_map(|op| match op {
+                Op::Add(j) => Some(*j),
+                _ => None,
+            })
+            .collect();
+        let mut next_add = 0;
+        for i in removes {
+            let pair = adds[next_add..]
+                .iter()
+                .position(|&j| new[j].role == old[i].role);
+            match pair {
+                Some(offset) => {
+                    changes.extend(adds[next_add..next_add + offset].iter().map(|&j| added(j)));
+                    let j = adds[next_add + offset];
+                    changes.push(TurnChange {
+                        kind: ChangeKind::Edited,
+                        role: new[j].role.clone(),
+                        old_index: Some(i),
+                        new_index: Some(j),
+                        old: Some(old[i].content.clone()),
+                        new: Some(new[j].content.clone()),
+                    });
+                    next_add += offset + 1;
+                }
+                None => changes.push(removed(i)),
+            }
+        }
+        changes.extend(adds[next_add..].iter().map(|&j| added(j)));
+    }
+    (changes, unchanged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turns(items: &[(&str, &str)]) -> Vec<Turn> {
+        items
+            .iter()
+            .map(|(role, content)| Turn {
+                role: role.to_string(),
+                content: content.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_turns() {
+        let old = turns(&[
+            ("user", "hi"),
+            ("assistant", "hello"),
+            ("user", "fix the bug"),
+            ("assistant", "done"),
+        ]);
+        let new = turns(&[
+            ("user", "hi"),
+            ("assistant", "hello"),
+            ("user", "fix the parser bug"),
+            ("assistant", "done"),
+            ("user", "thanks"),
+        ]);
+        let (changes, unchanged) = diff_turns(&old, &new);
+        assert_eq!(unchanged, 3);
+        let kinds: Vec<_> = changes.iter().map(|c| c.kind).collect();
+        assert_eq!(kinds, [ChangeKind::Edited, ChangeKind::Added]);
+        assert_eq!(changes[0].old.as_deref(), Some("fix the bug"));
+        assert_eq!(changes[0].new_index, Some(2));
+        assert_eq!(changes[1].new_index, Some(4));
+
+        let (changes, unchanged) = diff_turns(&old, &old[..2]);
+        assert_eq!(unchanged, 2);
+        assert!(changes.iter().all(|c| c.kind == ChangeKind::Removed));
+
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE sessions (id TEXT PRIMARY KEY);
+             CREATE TABLE checkpoints (session_id TEXT, checkpoint_number INTEGER, snapshot TEXT);
+             INSERT INTO sessions VALUES ('s1'), ('42');
+             INSERT INTO checkpoints VALUES
+                 ('s1', 2, '{\"messages\": [{\"role\": \"user\", \"content\": \"hi\"}]}');",
+        )
+        .unwrap();
+        let resolve = |spec| Against::resolve(&conn, "s1", spec).unwrap();
+        assert_eq!(resolve("2"), Against::Checkpoint(2));
+        assert_eq!(resolve("42"), Against::Session("42".to_string()));
+        assert_eq!(resolve("HEAD~1"), Against::Commit("HEAD~1".to_string()));
+        assert_eq!(resolve("commit:42"), Against::Commit("42".to_string()));
+        assert_eq!(
+            checkpoint_turns(&conn, "s1", 2).unwrap(),
+            turns(&[("user", "hi")])
+        );
+
+        // Never handed to git as an option
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+        let db = dir.path().join("harvest.db");
+        let spec = format!("--output={}", out.display());
+        let err = commit_turns(&db, &spec, "s1").unwrap_err();
+        assert!(err.to_string().contains("is not a commit"), "{}", err);
+        assert!(!out.exists());
+    }
+}
@@ -2,12 +2,16 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //! VS Code storage (SQLite database) operations
 
+pub mod watcher;
+
+use crate::backup::{self, BackupKind};
+use crate::config::{self, StorageRoot};
 use crate::error::{CsmError, Result};
 use crate::models::{ChatRequest, ChatSession, ChatSessionIndex, ChatSessionIndexEntry};
 use crate::workspace::{get_empty_window_sessions_path, get_workspace_storage_path};
 use once_cell::sync::Lazy;
 use regex::Regex;
-use rusqlite::Connection;
+use rusqlite::{Connection, TransactionBehavior};
 use std::path::{Path, PathBuf};
 use sysinfo::System;
 
@@ -266,13 +270,19 @@ fn sanitize_json_unicode(content: &str) -> String {
     result
 }
 
+/// Whether a parse error was caused by an invalid Unicode escape
+fn is_unicode_escape_error(e: &serde_json::Error) -> bool {
+    let msg = e.to_string();
+    msg.contains("surrogate") || msg.contains("escape")
+}
+
 /// Try to parse JSON, sanitizing invalid Unicode if needed
 pub fn parse_session_json(content: &str) -> std::result::Result<ChatSession, serde_json::Error> {
     match serde_json::from_str::<ChatSession>(content) {
         Ok(session) => Ok(session),
         Err(e) => {
             // If parsing fails due to Unicode issue, try sanitizing
-            if e.to_string().contains("surrogate") || e.to_string().contains("escape") {
+            if is_unicode_escape_error(&e) {
                 let sanitized = sanitize_json_unicode(content);
                 serde_json::from_str::<ChatSession>(&sanitized)
             } else {
@@ -293,35 +303,41 @@ enum JsonlKind {
     RequestsUpdate = 2,
 }
 
-/// Parse a JSONL (JSON Lines) session file (VS Code 1.109.0+ format)
-/// Each line is a JSON object with 'kind' field indicating the type:
-/// - kind 0: Initial session metadata with 'v' containing ChatSession-like structure
-/// - kind 1: Delta update with 'k' (keys path) and 'v' (value)
-/// - kind 2: Full requests array update with 'k' and 'v'
-pub fn parse_session_jsonl(content: &str) -> std::result::Result<ChatSession, serde_json::Error> {
-    let mut session = ChatSession {
-        version: 3,
-        session_id: None,
-        creation_date: 0,
-        last_message_date: 0,
-        is_imported: false,
-        initial_location: "panel".to_string(),
-        custom_title: None,
-        requester_username: None,
-        requester_avatar_icon_uri: None,
-        responder_username: None,
-        responder_avatar_icon_uri: None,
-        requests: Vec::new(),
-    };
+/// Incremental builder for JSONL sessions, fed one line at a time so large
+/// files never need to be held in memory as a whole
+struct JsonlSessionBuilder {
+    session: ChatSession,
+}
 
-    for line in content.lines() {
+impl JsonlSessionBuilder {
+    fn new() -> Self {
+        Self {
+            session: ChatSession {
+                version: 3,
+                session_id: None,
+                creation_date: 0,
+                last_message_date: 0,
+                is_imported: false,
+                initial_location: "panel".to_string(),
+                custom_title: None,
+                requester_username: None,
+                requester_avatar_icon_uri: None,
+                responder_username: None,
+                responder_avatar_icon_uri: None,
+                requests: Vec::new(),
+            },
+        }
+    }
+
+    /// Apply a single JSONL entry
+    fn apply_line(&mut self, line: &str) -> std::result::Result<(), serde_json::Error> {
         let line = line.trim();
         if line.is_empty() {
-            continue;
+            return Ok(());
         }
 
         // Parse each line as a JSON object
-        let entry: serde_json::Value = match serde_json::from_str(line) {
+        let mut entry: serde_json::Value = match serde_json::from_str(line) {
             Ok(v) => v,
             Err(_) => {
                 // Try sanitizing Unicode
@@ -331,11 +347,12 @@ pub fn parse_session_jsonl(content: &str) -> std::result::Result<ChatSession, se
         };
 
         let kind = entry.get("kind").and_then(|k| k.as_u64()).unwrap_or(0);
+        let session = &mut self.session;
 
         match kind {
             0 => {
                 // Initial state - 'v' contains the session metadata
-                if let Some(v) = entry.get("v") {
+                if let Some(v) = entry.get_mut("v") {
                     // Parse version
                     if let Some(version) = v.get("version").and_then(|x| x.as_u64()) {
                         session.version = version as u32;
@@ -356,10 +373,10 @@ pub fn parse_session_jsonl(content: &str) -> std::result::Result<ChatSession, se
                     if let Some(ru) = v.get("responderUsername").and_then(|x| x.as_str()) {
                         session.responder_username = Some(ru.to_string());
                     }
-                    // Parse requests array if present
-                    if let Some(requests) = v.get("requests") {
+                    // Parse requests array if present (taken, not cloned)
+                    if let Some(requests) = v.get_mut("requests") {
                         if let Ok(reqs) =
-                            serde_json::from_value::<Vec<ChatRequest>>(requests.clone())
+                            serde_json::from_value::<Vec<ChatRequest>>(requests.take())
                         {
                             session.requests = reqs;
                         }
@@ -393,8 +410,8 @@ pub fn parse_session_jsonl(content: &str) -> std::result::Result<ChatSession, se
             }
             2 => {
                 // Full requests array update - 'k' contains ["requests"], 'v' is the array
-                if let Some(value) = entry.get("v") {
-                    if let Ok(reqs) = serde_json::from_value::<Vec<ChatRequest>>(value.clone()) {
+                if let Some(value) = entry.get_mut("v") {
+                    if let Ok(reqs) = serde_json::from_value::<Vec<ChatRequest>>(value.take()) {
                         session.requests = reqs;
                         // Update last message date from last request
                         if let Some(last_req) = session.requests.last() {
@@ -407,9 +424,26 @@ pub fn parse_session_jsonl(content: &str) -> std::result::Result<ChatSession, se
             }
             _ => {} // Unknown kind, skip
         }
+
+        Ok(())
     }
 
-    Ok(session)
+    fn finish(self) -> ChatSession {
+        self.session
+    }
+}
+
+/// Parse a JSONL (JSON Lines) session file (VS Code 1.109.0+ format)
+/// Each line is a JSON object with 'kind' field indicating the type:
+/// - kind 0: Initial session metadata with 'v' containing ChatSession-like structure
+/// - kind 1: Delta update with 'k' (keys path) and 'v' (value)
+/// - kind 2: Full requests array update with 'k' and 'v'
+pub fn parse_session_jsonl(content: &str) -> std::result::Result<ChatSession, serde_json::Error> {
+    let mut builder = JsonlSessionBuilder::new();
+    for line in content.lines() {
+        builder.apply_line(line)?;
+    }
+    Ok(builder.finish())
 }
 
 /// Check if a file extension indicates a session file (.json or .jsonl)
@@ -496,18 +530,165 @@ pub fn parse_session_auto(content: &str) -> std::result::Result<(ChatSession, Se
     Ok((session, format_info))
 }
 
-/// Parse a session file, automatically detecting format from content (not just extension)
-pub fn parse_session_file(path: &Path) -> std::result::Result<ChatSession, serde_json::Error> {
-    let content = std::fs::read_to_string(path).map_err(|e| {
-        serde_json::Error::io(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            e.to_string(),
-        ))
-    })?;
+/// Bytes inspected at the start of a file to detect its format
+const FORMAT_PROBE_BYTES: usize = 64 * 1024;
+
+/// Fail with `SessionTooLarge` if a session file exceeds `max_bytes`
+pub fn check_session_file_size(path: &Path, max_bytes: u64) -> Result<u64> {
+    let size = std::fs::metadata(path)?.len();
+    if size > max_bytes {
+        return Err(CsmError::SessionTooLarge {
+            path: path.to_path_buf(),
+            size,
+            limit: max_bytes,
+        });
+    }
+    Ok(size)
+}
+
+/// Parse a session file, automatically detecting format from content (not just extension).
+/// Files larger than `limits.max_session_mb` are rejected.
+pub fn parse_session_file(path: &Path) -> Result<ChatSession> {
+    let max_bytes = config::current().limits().max_session_bytes();
+    parse_session_file_with_limit(path, max_bytes)
+}
+
+/// Parse a session file with an explicit size limit, streaming from disk.
+/// JSONL is applied line by line and legacy JSON is deserialized straight
+/// from the reader, so the raw file is never held in memory as a whole.
+pub fn parse_session_file_with_limit(path: &Path, max_bytes: u64) -> Result<ChatSession> {
+    use std::io::{BufRead, BufReader, Read};
+
+    check_session_file_size(path, max_bytes)?;
+
+    let file = std::fs::File::open(path)?;
+    let mut reader = BufReader::with_capacity(FORMAT_PROBE_BYTES, file);
+    let probe = String::from_utf8_lossy(reader.fill_buf()?).into_owned();
+
+    match VsCodeSessionFormat::from_content(&probe) {
+        VsCodeSessionFormat::JsonLines => {
+            let mut builder = JsonlSessionBuilder::new();
+            let mut line = String::new();
+            while reader.read_line(&mut line)? > 0 {
+                builder.apply_line(&line)?;
+                line.clear();
+            }
+            Ok(builder.finish())
+        }
+        VsCodeSessionFormat::LegacyJson => {
+            match serde_json::from_reader::<_, ChatSession>(&mut reader) {
+                Ok(session) => Ok(session),
+                Err(e) if is_unicode_escape_error(&e) => {
+                    // Lone surrogates need the text-level sanitizer
+                    let mut content = String::new();
+                    std::fs::File::open(path)?.read_to_string(&mut content)?;
+                    Ok(parse_session_json(&content)?)
+                }
+                Err(e) => Err(e.into()),
+            }
+        }
+    }
+}
+
+// =============================================================================
+// Crash-safe Writes
+// =============================================================================
+
+/// Write a file atomically: write to a temp file in the same directory, fsync it,
+/// then rename it over the target. Readers see either the old or the new contents,
+/// never a partial write.
+pub fn write_file_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    use std::io::Write;
+
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| CsmError::IoError(std::io::Error::other("Path has no file name")))?;
+    let tmp_path = parent.join(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        uuid::Uuid::new_v4().simple()
+    ));
+
+    let write_tmp = || -> std::io::Result<()> {
+        let mut file = std::fs::File::create(&tmp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, path)
+    };
+
+    if let Err(e) = write_tmp() {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(e.into());
+    }
+
+    // Persist the rename itself (directory entry) on platforms that support it
+    #[cfg(unix)]
+    if let Ok(dir) = std::fs::File::open(parent) {
+        let _ = dir.sync_all();
+    }
+
+    Ok(())
+}
+
+/// Copy a file into place atomically (see [`write_file_atomic`])
+pub fn copy_file_atomic(src: &Path, dst: &Path) -> Result<()> {
+    let contents = std::fs::read(src)?;
+    write_file_atomic(dst, &contents)
+}
 
-    // Use content-based auto-detection
-    let (session, _format_info) = parse_session_auto(&content)?;
-    Ok(session)
+/// Serialize a session and write it atomically
+pub fn write_session_file(path: &Path, session: &ChatSession) -> Result<()> {
+    let json = serde_json::to_string_pretty(session)?;
+    write_file_atomic(path, json.as_bytes())
+}
+
+/// Write a session file and register it in the workspace index as one unit.
+///
+/// If the index update fails, the session file is restored to its previous
+/// contents (or removed if it did not exist) so disk and index stay consistent.
+pub fn write_and_register_session(
+    db_path: &Path,
+    session_path: &Path,
+    session: &ChatSession,
+) -> Result<()> {
+    let previous = if session_path.exists() {
+        Some(std::fs::read(session_path)?)
+    } else {
+        None
+    };
+
+    write_session_file(session_path, session)?;
+
+    let session_id = session.session_id.clone().unwrap_or_else(|| {
+        session_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    });
+
+    let registered = add_session_to_index(
+        db_path,
+        &session_id,
+        &session.title(),
+        session.last_message_date,
+        session.is_imported,
+        &session.initial_location,
+        session.is_empty(),
+    );
+
+    if let Err(e) = registered {
+        let _ = match previous {
+            Some(bytes) => write_file_atomic(session_path, &bytes),
+            None => std::fs::remove_file(session_path).map_err(CsmError::from),
+        };
+        return Err(e);
+    }
+
+    Ok(())
 }
 
 /// Get the path to the workspace storage database
@@ -534,31 +715,63 @@ pub fn read_chat_session_index(db_path: &Path) -> Result<ChatSessionIndex> {
     }
 }
 
+/// Key under which VS Code stores the chat session index
+const CHAT_SESSION_INDEX_KEY: &str = "chat.ChatSessionStore.index";
+
 /// Write the chat session index to VS Code storage
 pub fn write_chat_session_index(db_path: &Path, index: &ChatSessionIndex) -> Result<()> {
-    let conn = Connection::open(db_path)?;
-    let json_str = serde_json::to_string(index)?;
+    update_chat_session_index(db_path, |current| {
+        *current = index.clone();
+        Ok(())
+    })
+}
 
-    // Check if the key exists
-    let exists: bool = conn.query_row(
-        "SELECT COUNT(*) > 0 FROM ItemTable WHERE key = ?",
-        ["chat.ChatSessionStore.index"],
+/// Read, modify, and write the chat session index in a single SQLite transaction.
+///
+/// The transaction takes the write lock up front, so concurrent writers cannot
+/// interleave. If `update` returns an error, or any step fails, the transaction
+/// is rolled back and the stored index is left untouched.
+pub fn update_chat_session_index<T>(
+    db_path: &Path,
+    update: impl FnOnce(&mut ChatSessionIndex) -> Result<T>,
+) -> Result<T> {
+    let mut conn = Connection::open(db_path)?;
+    conn.busy_timeout(std::time::Duration::from_secs(5))?;
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+    let existing: Option<String> = match tx.query_row(
+        "SELECT value FROM ItemTable WHERE key = ?",
+        [CHAT_SESSION_INDEX_KEY],
         |row| row.get(0),
-    )?;
+    ) {
+        Ok(json_str) => Some(json_str),
+        Err(rusqlite::Error::QueryReturnedNoRows) => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut index = match &existing {
+        Some(json_str) => serde_json::from_str(json_str)
+            .map_err(|e| CsmError::InvalidSessionFormat(e.to_string()))?,
+        None => ChatSessionIndex::default(),
+    };
+
+    let output = update(&mut index)?;
+    let json_str = serde_json::to_string(&index)?;
 
-    if exists {
-        conn.execute(
+    if existing.is_some() {
+        tx.execute(
             "UPDATE ItemTable SET value = ? WHERE key = ?",
-            [&json_str, "chat.ChatSessionStore.index"],
+            [&json_str, CHAT_SESSION_INDEX_KEY],
         )?;
     } else {
-        conn.execute(
+        tx.execute(
             "INSERT INTO ItemTable (key, value) VALUES (?, ?)",
-            ["chat.ChatSessionStore.index", &json_str],
+            [CHAT_SESSION_INDEX_KEY, &json_str],
         )?;
     }
 
-    Ok(())
+    tx.commit()?;
+    Ok(output)
 }
 
 /// Add a session to the VS Code index
@@ -571,32 +784,66 @@ pub fn add_session_to_index(
     initial_location: &str,
     is_empty: bool,
 ) -> Result<()> {
-    let mut index = read_chat_session_index(db_path)?;
-
-    index.entries.insert(
-        session_id.to_string(),
-        ChatSessionIndexEntry {
-            session_id: session_id.to_string(),
-            title: title.to_string(),
-            last_message_date: last_message_date_ms,
-            is_imported,
-            initial_location: initial_location.to_string(),
-            is_empty,
-        },
-    );
+    update_chat_session_index(db_path, |index| {
+        index.entries.insert(
+            session_id.to_string(),
+            ChatSessionIndexEntry {
+                session_id: session_id.to_string(),
+                title: title.to_string(),
+                last_message_date: last_message_date_ms,
+                is_imported,
+                initial_location: initial_location.to_string(),
+                is_empty,
+            },
+        );
+        Ok(())
+    })
+}
+
+/// Give a workspace's session `title` as its custom title: in its file,
+/// as a title patch for JSONL files, and in the workspace index. Returns
+/// false when the workspace has no file for the session.
+pub fn set_session_title(workspace_id: &str, session_id: &str, title: &str) -> Result<bool> {
+    let dir = get_workspace_storage_path()?
+        .join(workspace_id)
+        .join("chatSessions");
+    let jsonl = dir.join(format!("{}.jsonl", session_id));
+    let json = dir.join(format!("{}.json", session_id));
+    if jsonl.exists() {
+        let mut content = std::fs::read_to_string(&jsonl)?;
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        let patch = serde_json::json!({ "kind": 1, "k": ["customTitle"], "v": title });
+        content.push_str(&format!("{}\n", patch));
+        write_file_atomic(&jsonl, content.as_bytes())?;
+    } else if json.exists() {
+        // Edited as plain JSON so fields csm does not model are kept
+        let mut session: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&json)?)?;
+        session["customTitle"] = serde_json::json!(title);
+        write_file_atomic(&json, serde_json::to_string_pretty(&session)?.as_bytes())?;
+    } else {
+        return Ok(false);
+    }
 
-    write_chat_session_index(db_path, &index)
+    let db_path = get_workspace_storage_db(workspace_id)?;
+    if db_path.exists() {
+        update_chat_session_index(&db_path, |index| {
+            if let Some(entry) = index.entries.get_mut(session_id) {
+                entry.title = title.to_string();
+            }
+            Ok(())
+        })?;
+    }
+    Ok(true)
 }
 
 /// Remove a session from the VS Code index
 #[allow(dead_code)]
 pub fn remove_session_from_index(db_path: &Path, session_id: &str) -> Result<bool> {
-    let mut index = read_chat_session_index(db_path)?;
-    let removed = index.entries.remove(session_id).is_some();
-    if removed {
-        write_chat_session_index(db_path, &index)?;
-    }
-    Ok(removed)
+    update_chat_session_index(db_path, |index| {
+        Ok(index.entries.remove(session_id).is_some())
+    })
 }
 
 /// Sync the VS Code index with sessions on disk (remove stale entries, add missing ones)
@@ -619,9 +866,6 @@ pub fn sync_session_index(
         return Err(CsmError::VSCodeRunning);
     }
 
-    // Get current index
-    let mut index = read_chat_session_index(&db_path)?;
-
     // Get session files on disk
     let mut files_on_disk: std::collections::HashSet<String> = std::collections::HashSet::new();
     if chat_sessions_dir.exists() {
@@ -640,21 +884,8 @@ pub fn sync_session_index(
         }
     }
 
-    // Remove stale entries (in index but not on disk)
-    let stale_ids: Vec<String> = index
-        .entries
-        .keys()
-        .filter(|id| !files_on_disk.contains(*id))
-        .cloned()
-        .collect();
-
-    let removed = stale_ids.len();
-    for id in &stale_ids {
-        index.entries.remove(id);
-    }
-
-    // Add/update sessions from disk
-    let mut added = 0;
+    // Parse sessions from disk before touching the index
+    let mut disk_entries = Vec::new();
     for entry in std::fs::read_dir(chat_sessions_dir)? {
         let entry = entry?;
         let path = entry.path();
@@ -671,31 +902,33 @@ pub fn sync_session_index(
                         .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
                 });
 
-                let title = session.title();
-                let is_empty = session.is_empty();
-                let last_message_date = session.last_message_date;
-                let initial_location = session.initial_location.clone();
-
-                index.entries.insert(
-                    session_id.clone(),
-                    ChatSessionIndexEntry {
-                        session_id,
-                        title,
-                        last_message_date,
-                        is_imported: session.is_imported,
-                        initial_location,
-                        is_empty,
-                    },
-                );
-                added += 1;
+                disk_entries.push(ChatSessionIndexEntry {
+                    session_id,
+                    title: session.title(),
+                    last_message_date: session.last_message_date,
+                    is_imported: session.is_imported,
+                    initial_location: session.initial_location.clone(),
+                    is_empty: session.is_empty(),
+                });
             }
         }
     }
 
-    // Write the synced index
-    write_chat_session_index(&db_path, &index)?;
+    // Apply removals and additions in one transaction
+    update_chat_session_index(&db_path, |index| {
+        // Remove stale entries (in index but not on disk)
+        let before = index.entries.len();
+        index.entries.retain(|id, _| files_on_disk.contains(id));
+        let removed = before - index.entries.len();
+
+        // Add/update sessions from disk
+        let added = disk_entries.len();
+        for entry in disk_entries {
+            index.entries.insert(entry.session_id.clone(), entry);
+        }
 
-    Ok((added, removed))
+        Ok((added, removed))
+    })
 }
 
 /// Register all sessions from a directory into the VS Code index
@@ -743,7 +976,7 @@ pub fn register_all_sessions_from_directory(
                 println!(
                     "[OK] Registered: {} ({}...)",
                     title,
-                    &session_id[..12.min(session_id.len())]
+                    crate::text::prefix_chars(&session_id, 12)
                 );
             }
         }
@@ -772,6 +1005,10 @@ pub fn is_vscode_running() -> bool {
 }
 
 /// Backup workspace sessions to a timestamped directory
+///
+/// Backups are written next to `chatSessions` unless a backup root is configured,
+/// in which case they go to `<backup_dir>/<workspace hash>/`. Each backup is
+/// recorded in the backup catalog.
 pub fn backup_workspace_sessions(workspace_dir: &Path) -> Result<Option<PathBuf>> {
     let chat_sessions_dir = workspace_dir.join("chatSessions");
 
@@ -784,10 +1021,18 @@ pub fn backup_workspace_sessions(workspace_dir: &Path) -> Result<Option<PathBuf>
         .unwrap()
         .as_secs();
 
-    let backup_dir = workspace_dir.join(format!("chatSessions-backup-{}", timestamp));
+    let backup_parent = match config::storage_root(StorageRoot::Backups) {
+        Some(root) => match workspace_dir.file_name() {
+            Some(hash) => root.join(hash),
+            None => root,
+        },
+        None => workspace_dir.to_path_buf(),
+    };
+    let backup_dir = backup_parent.join(format!("chatSessions-backup-{}", timestamp));
 
     // Copy directory recursively
     copy_dir_all(&chat_sessions_dir, &backup_dir)?;
+    backup::record(BackupKind::WorkspaceSessions, &chat_sessions_dir, &backup_dir);
 
     Ok(Some(backup_dir))
 }
@@ -873,8 +1118,7 @@ pub fn write_empty_window_session(session: &ChatSession) -> Result<PathBuf> {
 
     let session_id = session.session_id.as_deref().unwrap_or("unknown");
     let session_path = sessions_path.join(format!("{}.json", session_id));
-    let content = serde_json::to_string_pretty(session)?;
-    std::fs::write(&session_path, content)?;
+    write_session_file(&session_path, session)?;
 
     Ok(session_path)
 }
@@ -0,0 +1,427 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Filesystem watcher for workspace chat session files
+//!
+//! Wraps `notify` to report Copilot session files (`<hash>/chatSessions/*.json[l]`)
+//! as VS Code creates, rewrites, or deletes them. Raw filesystem events are
+//! coalesced per path over a short debounce window, since VS Code writes a
+//! session in several bursts, and each path is re-checked on disk before an
+//! event is emitted so atomic rename-over writes report as `Modified`.
+//!
+//! [`PathWatcher`] does the same for any files and directories, such as the
+//! config file or a notes folder, reporting the paths a filter accepts.
+
+use crate::error::{CsmError, Result};
+use crate::storage::is_session_file_extension;
+use crate::workspace::get_workspace_storage_path;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+/// Default quiet period before a changed file is reported
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Kind of change to a session file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionEventKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+/// A change to a session file on disk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionEvent {
+    pub kind: SessionEventKind,
+    pub path: PathBuf,
+    /// Session ID (file stem)
+    pub session_id: String,
+    /// Workspace storage hash, when the file lives under `<hash>/chatSessions`
+    pub workspace_hash: Option<String>,
+}
+
+impl SessionEvent {
+    fn new(kind: SessionEventKind, path: PathBuf) -> Self {
+        let session_id = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let workspace_hash = path
+            .parent()
+            .and_then(|p| p.parent())
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string());
+        Self {
+            kind,
+            path,
+            session_id,
+            workspace_hash,
+        }
+    }
+}
+
+/// A change to a file under a path given to [`PathWatcher`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileEvent {
+    pub kind: SessionEventKind,
+    pub path: PathBuf,
+}
+
+impl FileEvent {
+    fn new(kind: SessionEventKind, path: PathBuf) -> Self {
+        Self { kind, path }
+    }
+}
+
+/// Whether a path is a session file inside a `chatSessions` directory
+pub fn is_session_path(path: &Path) -> bool {
+    let in_chat_sessions = path
+        .parent()
+        .and_then(|p| p.file_name())
+        .is_some_and(|n| n == "chatSessions");
+    let hidden = path
+        .file_name()
+        .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+    in_chat_sessions && !hidden && path.extension().is_some_and(is_session_file_extension)
+}
+
+/// Watches a directory tree for session file changes
+pub struct SessionWatcher {
+    root: PathBuf,
+    rx: mpsc::Receiver<SessionEvent>,
+    // Dropping the watcher closes the raw channel and stops the debounce thread
+    _watcher: RecommendedWatcher,
+}
+
+impl SessionWatcher {
+    /// Watch VS Code's workspaceStorage (honoring configured storage roots)
+    pub fn new() -> Result<Self> {
+        Self::watch(&get_workspace_storage_path()?, DEFAULT_DEBOUNCE)
+    }
+
+    /// Watch an explicit directory tree with the given debounce window
+    pub fn watch(root: &Path, debounce: Duration) -> Result<Self> {
+        if !root.exists() {
+            return Err(CsmError::WatchError(format!(
+                "{} does not exist",
+                root.display()
+            )));
+        }
+
+        let known = scan_session_files(root);
+        let (raw_tx, raw_rx) = mpsc::channel::<PathBuf>();
+        let (tx, rx) = mpsc::channel();
+
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            // Access events fire when we read the file ourselves; only writes matter
+            let Ok(event) = res else { return };
+            if !event.kind.is_access() {
+                for path in event.paths {
+                    if is_session_path(&path) {
+                        let _ = raw_tx.send(path);
+                    }
+                }
+            }
+        })
+        .map_err(|e| CsmError::WatchError(e.to_string()))?;
+        watcher
+            .watch(root, RecursiveMode::Recursive)
+            .map_err(|e| CsmError::WatchError(e.to_string()))?;
+
+        std::thread::Builder::new()
+            .name("csm-session-watcher".to_string())
+            .spawn(move || debounce_loop(raw_rx, tx, known, debounce, SessionEvent::new))?;
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            rx,
+            _watcher: watcher,
+        })
+    }
+
+    /// Directory being watched
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Block until the next event
+    pub fn recv(&self) -> Option<SessionEvent> {
+        self.rx.recv().ok()
+    }
+
+    /// Wait up to `timeout` for the next event
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<SessionEvent> {
+        self.rx.recv_timeout(timeout).ok()
+    }
+
+    /// Drain events that are already available
+    pub fn try_iter(&self) -> impl Iterator<Item = SessionEvent> + '_ {
+        self.rx.try_iter()
+    }
+}
+
+impl Iterator for SessionWatcher {
+    type Item = SessionEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+/// Watches files and directories for changes to the paths a filter accepts
+pub struct PathWatcher {
+    rx: mpsc::Receiver<FileEvent>,
+    _watcher: RecommendedWatcher,
+}
+
+impl PathWatcher {
+    /// Watch each `(path, recursive)` root, reporting changed files that
+    /// `filter` accepts once they have been quiet for `debounce`
+    pub fn watch(
+        roots: &[(PathBuf, bool)],
+        filter: impl Fn(&Path) -> bool + Send + Sync + 'static,
+        debounce: Duration,
+    ) -> Result<Self> {
+        if let Some((missing, _)) = roots.iter().find(|(root, _)| !root.exists()) {
+            return Err(CsmError::WatchError(format!(
+                "{} does not exist",
+                missing.display()
+            )));
+        }
+
+        let filter: Arc<dyn Fn(&Path) -> bool + Send + Sync> = Arc::new(filter);
+        let known: HashSet<PathBuf> = roots
+            .iter()
+            .flat_map(|(root, recursive)| {
+                walkdir::WalkDir::new(root)
+                    .max_depth(if *recursive { usize::MAX } else { 1 })
+                    .into_iter()
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.into_path())
+                    .filter(|p| p.is_file() && filter(p))
+            })
+            .collect();
+        let (raw_tx, raw_rx) = mpsc::channel::<PathBuf>();
+        let (tx, rx) = mpsc::channel();
+
+        let accepts = filter.clone();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            let Ok(event) = res else { return };
+            if !event.kind.is_access() {
+                for path in event.paths {
+                    if accepts(&path) {
+                        let _ = raw_tx.send(path);
+                    }
+                }
+            }
+        })
+        .map_err(|e| CsmError::WatchError(e.to_string()))?;
+        for (root, recursive) in roots {
+            let mode = if *recursive {
+                RecursiveMode::Recursive
+            } else {
+                RecursiveMode::NonRecursive
+            };
+            watcher
+                .watch(root, mode)
+                .map_err(|e| CsmError::WatchError(format!("{}: {}", root.display(), e)))?;
+        }
+
+        std::thread::Builder::new()
+            .name("csm-path-watcher".to_string())
+            .spawn(move || debounce_loop(raw_rx, tx, known, debounce, FileEvent::new))?;
+
+        Ok(Self {
+            rx,
+            _watcher: watcher,
+        })
+    }
+
+    /// Block until the next event
+    pub fn recv(&self) -> Option<FileEvent> {
+        self.rx.recv().ok()
+    }
+
+    /// Wait up to `timeout` for the next event
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<FileEvent> {
+        self.rx.recv_timeout(timeout).ok()
+    }
+}
+
+impl Iterator for PathWatcher {
+    type Item = FileEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+/// Existing session files under `root` (up to `<hash>/chatSessions/<file>`)
+fn scan_session_files(root: &Path) -> HashSet<PathBuf> {
+    walkdir::WalkDir::new(root)
+        .max_depth(3)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .map(|e| e.into_path())
+        .filter(|p| p.is_file() && is_session_path(p))
+        .collect()
+}
+
+/// Coalesce raw paths and emit one event per path once it has been quiet
+fn debounce_loop<E>(
+    raw_rx: mpsc::Receiver<PathBuf>,
+    tx: mpsc::Sender<E>,
+    mut known: HashSet<PathBuf>,
+    debounce: Duration,
+    event: fn(SessionEventKind, PathBuf) -> E,
+) {
+    let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+    let tick = (debounce / 4).max(Duration::from_millis(10));
+
+    loop {
+        let disconnected = match raw_rx.recv_timeout(tick) {
+            Ok(path) => {
+                pending.insert(path, Instant::now());
+                false
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => false,
+            Err(mpsc::RecvTimeoutError::Disconnected) => true,
+        };
+
+        let now = Instant::now();
+        let ready: Vec<PathBuf> = pending
+            .iter()
+            .filter(|(_, seen)| disconnected || now.duration_since(**seen) >= debounce)
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        for path in ready {
+            pending.remove(&path);
+            let kind = if path.is_file() {
+                if known.insert(path.clone()) {
+                    SessionEventKind::Created
+                } else {
+                    SessionEventKind::Modified
+                }
+            } else if known.remove(&path) {
+                SessionEventKind::Deleted
+            } else {
+                // Created and removed within the window
+                continue;
+            };
+
+            if tx.send(event(kind, path)).is_err() {
+                return;
+            }
+        }
+
+        if disconnected {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_session_path() {
+        assert!(is_session_path(Path::new("/ws/abc/chatSessions/s1.json")));
+        assert!(is_session_path(Path::new("/ws/abc/chatSessions/s1.jsonl")));
+        assert!(!is_session_path(Path::new(
+            "/ws/abc/chatSessions/.s1.json.tmp"
+        )));
+        assert!(!is_session_path(Path::new("/ws/abc/chatSessions/.s1.json")));
+        assert!(!is_session_path(Path::new("/ws/abc/state.vscdb")));
+        assert!(!is_session_path(Path::new("/ws/abc/other/s1.json")));
+    }
+
+    #[test]
+    fn test_event_fields_from_path() {
+        let event = SessionEvent::new(
+            SessionEventKind::Created,
+            PathBuf::from("/ws/abc123/chatSessions/s1.jsonl"),
+        );
+        assert_eq!(event.session_id, "s1");
+        assert_eq!(event.workspace_hash.as_deref(), Some("abc123"));
+    }
+
+    #[test]
+    fn test_debounce_classifies_changes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let sessions = dir.path().join("abc").join("chatSessions");
+        std::fs::create_dir_all(&sessions).unwrap();
+        let existing = sessions.join("old.json");
+        let created = sessions.join("new.json");
+        std::fs::write(&existing, "{}").unwrap();
+        std::fs::write(&created, "{}").unwrap();
+
+        let (raw_tx, raw_rx) = mpsc::channel();
+        let (tx, rx) = mpsc::channel();
+        let known = HashSet::from([existing.clone(), sessions.join("gone.json")]);
+
+        // Repeated raw events for one path collapse into a single event
+        raw_tx.send(existing.clone()).unwrap();
+        raw_tx.send(existing.clone()).unwrap();
+        raw_tx.send(created.clone()).unwrap();
+        raw_tx.send(sessions.join("gone.json")).unwrap();
+        raw_tx.send(sessions.join("transient.json")).unwrap();
+        drop(raw_tx);
+
+        debounce_loop(
+            raw_rx,
+            tx,
+            known,
+            Duration::from_millis(10),
+            SessionEvent::new,
+        );
+
+        let mut events: Vec<_> = rx.try_iter().map(|e| (e.session_id, e.kind)).collect();
+        events.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            events,
+            vec![
+                ("gone".to_string(), SessionEventKind::Deleted),
+                ("new".to_string(), SessionEventKind::Created),
+                ("old".to_string(), SessionEventKind::Modified),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_path_watcher_reports_matching_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = dir.path().join("config.toml");
+        std::fs::write(&config, "a = 1").unwrap();
+
+        let target = config.clone();
+        let watcher = PathWatcher::watch(
+            &[(dir.path().to_path_buf(), false)],
+            move |p| p == target,
+            Duration::from_millis(50),
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("other.txt"), "x").unwrap();
+        // Replaced in one step, as editors and `csm config set` do
+        let tmp = dir.path().join("config.toml.tmp");
+        std::fs::write(&tmp, "a = 2").unwrap();
+        std::fs::rename(&tmp, &config).unwrap();
+
+        let event = watcher.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(event, FileEvent::new(SessionEventKind::Modified, config));
+        assert!(watcher.recv_timeout(Duration::from_millis(200)).is_none());
+        assert!(PathWatcher::watch(
+            &[(dir.path().join("missing"), true)],
+            |_| true,
+            DEFAULT_DEBOUNCE
+        )
+        .is_err());
+    }
+}
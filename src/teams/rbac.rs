@@ -48,6 +48,7 @@ impl Role {
                 perms.insert(Permission::EditOwnSessions);
                 perms.insert(Permission::DeleteOwnSessions);
                 perms.insert(Permission::ShareSessions);
+                perms.insert(Permission::ManageAgents);
                 perms.insert(Permission::AddComments);
                 perms.insert(Permission::ViewAnalytics);
                 perms.insert(Permission::ViewActivityFeed);
@@ -74,6 +75,40 @@ impl Role {
     pub fn has_permission(&self, permission: Permission) -> bool {
         self.default_permissions().contains(&permission)
     }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Owner => "owner",
+            Role::Admin => "admin",
+            Role::Member => "member",
+            Role::Viewer => "viewer",
+            Role::Guest => "guest",
+        }
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "owner" => Ok(Role::Owner),
+            "admin" => Ok(Role::Admin),
+            "member" | "editor" => Ok(Role::Member),
+            "viewer" => Ok(Role::Viewer),
+            "guest" => Ok(Role::Guest),
+            _ => anyhow::bail!(
+                "Unknown role '{}' (expected owner, admin, member, viewer, or guest)",
+                s
+            ),
+        }
+    }
 }
 
 /// Granular permission
@@ -102,6 +137,9 @@ pub enum Permission {
     ShareSessions,
     ExportSessions,
 
+    // Agents
+    ManageAgents,
+
     // Collaboration
     AddComments,
     EditOwnComments,
@@ -148,6 +186,7 @@ impl Permission {
             DeleteAllSessions,
             ShareSessions,
             ExportSessions,
+            ManageAgents,
             AddComments,
             EditOwnComments,
             EditAllComments,
@@ -187,6 +226,7 @@ impl Permission {
             DeleteAllSessions => "Delete any session",
             ShareSessions => "Share sessions with team",
             ExportSessions => "Export sessions",
+            ManageAgents => "Create, change, and run agents and tools",
             AddComments => "Add comments",
             EditOwnComments => "Edit own comments",
             EditAllComments => "Edit any comment",
@@ -452,6 +492,12 @@ mod tests {
         assert!(!Role::Member.has_permission(Permission::EditAllSessions));
         assert!(Role::Viewer.has_permission(Permission::ViewSessions));
         assert!(!Role::Viewer.has_permission(Permission::CreateSession));
+        assert!(Role::Member.has_permission(Permission::ManageAgents));
+        assert!(!Role::Viewer.has_permission(Permission::ManageAgents));
+
+        assert_eq!("editor".parse::<Role>().unwrap(), Role::Member);
+        assert_eq!(Role::Viewer.to_string(), "viewer");
+        assert!("superuser".parse::<Role>().is_err());
     }
 
     #[test]
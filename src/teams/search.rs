@@ -11,6 +11,7 @@ use uuid::Uuid;
 
 use super::rbac::{AccessControl, Action, Permission, Resource};
 use super::workspace::{MemberId, SessionVisibility, TeamId};
+use crate::text::{find_case_insensitive, snippet};
 
 // ============================================================================
 // Search Types
@@ -407,12 +408,8 @@ impl TeamSearchEngine {
 
     /// Highlight matching text
     fn highlight_text(&self, text: &str, term: &str) -> String {
-        let lower = text.to_lowercase();
-        if let Some(pos) = lower.find(term) {
-            let before = &text[..pos];
-            let matched = &text[pos..pos + term.len()];
-            let after = &text[pos + term.len()..];
-            format!("{}**{}**{}", before, matched, after)
+        if let Some((start, end)) = find_case_insensitive(text, term) {
+            format!("{}**{}**{}", &text[..start], &text[start..end], &text[end..])
         } else {
             text.to_string()
         }
@@ -420,23 +417,7 @@ impl TeamSearchEngine {
 
     /// Extract snippet around matching term
     fn extract_snippet(&self, content: &str, term: &str) -> String {
-        let lower = content.to_lowercase();
-        if let Some(pos) = lower.find(term) {
-            let start = pos.saturating_sub(50);
-            let end = (pos + term.len() + 50).min(content.len());
-
-            let mut snippet = String::new();
-            if start > 0 {
-                snippet.push_str("...");
-            }
-            snippet.push_str(&content[start..end]);
-            if end < content.len() {
-                snippet.push_str("...");
-            }
-            snippet
-        } else {
-            content.chars().take(100).collect()
-        }
+        snippet(content, term, 100)
     }
 
     /// Sort search results
@@ -0,0 +1,227 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Unicode-aware truncation and display width helpers
+//!
+//! Byte slicing (`&s[..16]`) panics when the index lands inside a multi-byte
+//! character, and `str::len` overcounts the columns CJK text and emoji take
+//! in a terminal. These helpers always cut on character boundaries and
+//! measure in display columns.
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Marker appended (or prepended) when text is cut
+pub const ELLIPSIS: &str = "...";
+
+/// Number of terminal columns `s` occupies
+pub fn display_width(s: &str) -> usize {
+    UnicodeWidthStr::width(s)
+}
+
+fn char_width(c: char) -> usize {
+    UnicodeWidthChar::width(c).unwrap_or(0)
+}
+
+/// First `n` characters of `s`, for short IDs and hashes
+pub fn prefix_chars(s: &str, n: usize) -> &str {
+    match s.char_indices().nth(n) {
+        Some((idx, _)) => &s[..idx],
+        None => s,
+    }
+}
+
+/// Longest prefix of `s` that fits in `max_width` columns
+pub fn prefix_by_width(s: &str, max_width: usize) -> &str {
+    let mut width = 0;
+    for (idx, c) in s.char_indices() {
+        width += char_width(c);
+        if width > max_width {
+            return &s[..idx];
+        }
+    }
+    s
+}
+
+/// Longest suffix of `s` that fits in `max_width` columns
+pub fn suffix_by_width(s: &str, max_width: usize) -> &str {
+    let mut width = 0;
+    for (idx, c) in s.char_indices().rev() {
+        width += char_width(c);
+        if width > max_width {
+            return &s[idx + c.len_utf8()..];
+        }
+    }
+    s
+}
+
+/// Truncate to at most `max_width` columns, ending with `...` when cut
+pub fn truncate(s: &str, max_width: usize) -> String {
+    if display_width(s) <= max_width {
+        return s.to_string();
+    }
+    if max_width < ELLIPSIS.len() {
+        return prefix_by_width(s, max_width).to_string();
+    }
+    format!(
+        "{}{}",
+        prefix_by_width(s, max_width - ELLIPSIS.len()),
+        ELLIPSIS
+    )
+}
+
+/// Truncate to at most `max_width` columns, keeping the end and starting with `...` when cut
+pub fn truncate_start(s: &str, max_width: usize) -> String {
+    if display_width(s) <= max_width {
+        return s.to_string();
+    }
+    if max_width < ELLIPSIS.len() {
+        return suffix_by_width(s, max_width).to_string();
+    }
+    format!(
+        "{}{}",
+        ELLIPSIS,
+        suffix_by_width(s, max_width - ELLIPSIS.len())
+    )
+}
+
+/// Pad with spaces to `width` columns (`{:<N}` pads by chars, which misaligns wide text)
+pub fn pad_right(s: &str, width: usize) -> String {
+    let pad = width.saturating_sub(display_width(s));
+    format!("{}{}", s, " ".repeat(pad))
+}
+
+/// Byte range of the first case-insensitive match of `needle` in `haystack`.
+/// The range is in `haystack`'s own offsets, even when lowercasing changes
+/// byte lengths.
+pub fn find_case_insensitive(haystack: &str, needle: &str) -> Option<(usize, usize)> {
+    if needle.is_empty() {
+        return None;
+    }
+    let needle = needle.to_lowercase();
+
+    // (offset in lowered text, offset in original) for each original char
+    let mut lowered = String::with_capacity(haystack.len());
+    let mut offsets = Vec::with_capacity(haystack.len());
+    for (idx, c) in haystack.char_indices() {
+        offsets.push((lowered.len(), idx));
+        lowered.extend(c.to_lowercase());
+    }
+
+    let pos = lowered.find(&needle)?;
+    let end = pos + needle.len();
+
+    let start = match offsets.binary_search_by_key(&pos, |&(l, _)| l) {
+        Ok(i) => offsets[i].1,
+        Err(i) => offsets[i - 1].1,
+    };
+    let end = match offsets.binary_search_by_key(&end, |&(l, _)| l) {
+        Ok(i) => offsets[i].1,
+        Err(i) => offsets.get(i).map(|&(_, o)| o).unwrap_or(haystack.len()),
+    };
+    Some((start, end))
+}
+
+/// Single-line excerpt of `content` around the first match of `query`,
+/// with roughly `context_width` columns of context split around the match.
+/// Falls back to the start of `content` when there is no match.
+pub fn snippet(content: &str, query: &str, context_width: usize) -> String {
+    let Some((start, end)) = find_case_insensitive(content, query) else {
+        return truncate(content, context_width)
+            .replace('\n', " ")
+            .replace('\r', "");
+    };
+
+    let before = suffix_by_width(&content[..start], context_width / 2);
+    let after = prefix_by_width(&content[end..], context_width / 2);
+
+    let mut out = String::new();
+    if before.len() < start {
+        out.push_str(ELLIPSIS);
+    }
+    out.push_str(before);
+    out.push_str(&content[start..end]);
+    out.push_str(after);
+    if end + after.len() < content.len() {
+        out.push_str(ELLIPSIS);
+    }
+
+    // Replace newlines with spaces for display
+    out.replace('\n', " ").replace('\r', "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("日本語"), 6);
+        assert_eq!(display_width("é"), 1);
+    }
+
+    #[test]
+    fn test_prefix_chars_multibyte() {
+        assert_eq!(prefix_chars("日本語テキスト", 3), "日本語");
+        assert_eq!(prefix_chars("abc", 16), "abc");
+        assert_eq!(prefix_chars("", 4), "");
+    }
+
+    #[test]
+    fn test_truncate_ascii() {
+        assert_eq!(truncate("hello", 10), "hello");
+        assert_eq!(truncate("hello world", 8), "hello...");
+    }
+
+    #[test]
+    fn test_truncate_wide_chars_by_columns() {
+        // Each CJK char is two columns wide; never split one in half
+        assert_eq!(truncate("日本語テキスト", 9), "日本語...");
+        assert_eq!(truncate("日本語テキスト", 8), "日本...");
+        assert!(display_width(&truncate("🚀🚀🚀🚀🚀", 7)) <= 7);
+    }
+
+    #[test]
+    fn test_truncate_start_keeps_end() {
+        assert_eq!(truncate_start("/home/user/project", 11), ".../project");
+        assert_eq!(truncate_start("/home/用户/项目", 7), "...项目");
+    }
+
+    #[test]
+    fn test_pad_right_uses_columns() {
+        assert_eq!(pad_right("日本", 6), "日本  ");
+        assert_eq!(pad_right("toolong", 3), "toolong");
+    }
+
+    #[test]
+    fn test_find_case_insensitive_maps_offsets() {
+        let text = "Ünïcode ÉTÉ match";
+        let (start, end) = find_case_insensitive(text, "été").unwrap();
+        assert_eq!(&text[start..end], "ÉTÉ");
+
+        // 'İ' lowercases to two chars, shifting lowered offsets
+        let text = "İstanbul rocks";
+        let (start, end) = find_case_insensitive(text, "rocks").unwrap();
+        assert_eq!(&text[start..end], "rocks");
+
+        assert!(find_case_insensitive("abc", "").is_none());
+        assert!(find_case_insensitive("abc", "z").is_none());
+    }
+
+    #[test]
+    fn test_snippet_around_match() {
+        let content = "前置きの文章がここにあります。The needle is here。後ろの文章もあります。";
+        let s = snippet(content, "NEEDLE", 10);
+        assert!(s.contains("needle"));
+        assert!(s.starts_with(ELLIPSIS));
+        assert!(s.ends_with(ELLIPSIS));
+    }
+
+    #[test]
+    fn test_snippet_without_match() {
+        assert_eq!(
+            snippet("line one\nline two", "zzz", 100),
+            "line one line two"
+        );
+        assert_eq!(snippet("日本語テキスト", "zzz", 8), "日本...");
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright (c) 2024-2026 Nervosys LLC
+// SPDX-License-Identifier: AGPL-3.0-only
+//! Topics of harvested sessions
+//!
+//! `csm intelligence topics` sorts sessions into the taxonomy of
+//! [`TopicExtractor`] and keeps the result in `session_topics`, where
+//! `csm harvest list --topic` and `csm harvest search --topic` filter on it.
+//! Topics nest with `/`: filtering on `lang` also finds sessions filed under
+//! `lang/rust`.
+//!
+//! [`TopicExtractor`]: crate::intelligence::TopicExtractor
+
+use crate::error::Result;
+use crate::intelligence::Topic;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+/// Create the session topic table in the harvest database if it does not exist
+pub fn ensure_topics_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS session_topics (
+            session_id TEXT NOT NULL,
+            topic TEXT NOT NULL,
+            confidence REAL NOT NULL,
+            keywords TEXT NOT NULL DEFAULT '',
+            PRIMARY KEY (session_id, topic),
+            FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_session_topics_topic ON session_topics(topic);
+        "#,
+    )?;
+    Ok(())
+}
+
+/// SQL condition that the session whose ID is `session_column` is filed
+/// under the topic bound to `param`, or under one of its subtopics
+pub fn topic_condition(session_column: &str, param: &str) -> String {
+    format!(
+        "EXISTS (SELECT 1 FROM session_topics st WHERE st.session_id = {} \
+         AND (st.topic = {p} OR st.topic LIKE {p} || '/%'))",
+        session_column,
+        p = param
+    )
+}
+
+/// Replace the topics stored for a session
+pub fn save_session_topics(conn: &Connection, session_id: &str, topics: &[Topic]) -> Result<()> {
+    conn.execute(
+        "DELETE FROM session_topics WHERE session_id = ?1",
+        [session_id],
+    )?;
+    let mut stmt = conn.prepare_cached(
+        "INSERT INTO session_topics (session_id, topic, confidence, keywords)
+         VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for topic in topics {
+        stmt.execute(params![
+            session_id,
+            topic.name,
+            topic.confidence as f64,
+            topic.keywords.join(", ")
+        ])?;
+    }
+    Ok(())
+}
+
+/// A topic and how many sessions are filed under it or its subtopics
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TopicCount {
+    pub topic: String,
+    pub sessions: i64,
+}
+
+/// Every topic in use and each of its parents, with their session counts,
+/// in tree order (a parent before its subtopics)
+pub fn topic_counts(conn: &Connection) -> Result<Vec<TopicCount>> {
+    let mut stmt = conn.prepare("SELECT session_id, topic FROM session_topics")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+    let mut sessions: BTreeMap<String, HashSet<String>> = BTreeMap::new();
+    for row in rows {
+        let (session_id, topic) = row?;
+        let mut path = String::new();
+        for part in topic.split('/') {
+            if !path.is_empty() {
+                path.push('/');
+            }
+            path.push_str(part);
+            sessions
+                .entry(path.clone())
+                .or_default()
+                .insert(session_id.clone());
+        }
+    }
+    Ok(sessions
+        .into_iter()
+        .map(|(topic, ids)| TopicCount {
+            topic,
+            sessions: ids.len() as i64,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topics_nest() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE sessions (id TEXT PRIMARY KEY);")
+            .unwrap();
+        conn.execute("INSERT INTO sessions VALUES ('s1'), ('s2'), ('s3')", [])
+            .unwrap();
+        ensure_topics_table(&conn).unwrap();
+
+        let topic = |name: &str| Topic {
+            name: name.to_string(),
+            confidence: 0.1,
+            keywords: vec!["cargo".to_string()],
+        };
+        save_session_topics(&conn, "s1", &[topic("lang/rust"), topic("lang/python")]).unwrap();
+        save_session_topics(&conn, "s2", &[topic("lang/rust")]).unwrap();
+        save_session_topics(&conn, "s3", &[topic("language")]).unwrap();
+        // Saving again replaces what was there
+        save_session_topics(&conn, "s2", &[topic("lang/rust/async")]).unwrap();
+
+        let counts: Vec<(String, i64)> = topic_counts(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|count| (count.topic, count.sessions))
+            .collect();
+        assert_eq!(
+            counts,
+            [
+                ("lang".to_string(), 2),
+                ("lang/python".to_string(), 1),
+                ("lang/rust".to_string(), 2),
+                ("lang/rust/async".to_string(), 1),
+                ("language".to_string(), 1),
+            ]
+        );
+
+        let sql = format!(
+            "SELECT id FROM sessions s WHERE {} ORDER BY id",
+            topic_condition("s.id", "?1")
+        );
+        let mut stmt = conn.prepare(&sql).unwrap();
+        let ids: Vec<String> = stmt
+            .query_map(["lang"], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(ids, ["s1", "s2"]);
+    }
+}
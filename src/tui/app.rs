@@ -2,10 +2,24 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //! Application state for the TUI
 
+use super::ui;
+use crate::commands::{get_db_path, session_issue};
+use crate::datetime::{format_datetime_short, from_epoch};
+use crate::integrations::issues::{self, CreatedIssue};
 use crate::models::{ChatSession, Workspace};
+use crate::search::{self, Facets, SearchFilters, SearchHit, SearchOptions};
+use crate::storage::parse_session_json;
 use crate::workspace::{discover_workspaces, get_chat_sessions_from_workspace};
+use anyhow::Context;
+use rusqlite::{Connection, OptionalExtension};
 use std::path::PathBuf;
 
+/// Most sessions a search lists
+const SEARCH_LIMIT: usize = 50;
+
+/// Most harvested sessions listed at once
+const HARVEST_LIMIT: usize = 500;
+
 /// Current view mode in the TUI
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AppMode {
@@ -15,15 +29,35 @@ pub enum AppMode {
     Sessions,
     /// Viewing details of a session
     SessionDetail,
+    /// Searching the messages of harvested sessions
+    Search,
+    /// Viewing sessions in the harvest database
+    Harvest,
     /// Help overlay
     Help,
 }
 
+/// Where the TUI browses sessions from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataSource {
+    /// VS Code workspaceStorage files
+    Workspaces,
+    /// The multi-provider harvest database
+    Harvest,
+}
+
+/// A property harvested sessions can be filtered on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HarvestFilter {
+    Provider,
+    Workspace,
+    Tag,
+}
+
 /// Session info for display
 #[derive(Debug, Clone)]
 pub struct SessionInfo {
     pub filename: String,
-    #[allow(dead_code)]
     pub path: PathBuf,
     pub session: ChatSession,
     pub last_modified: String,
@@ -54,6 +88,41 @@ pub struct App {
     pub filtered_indices: Vec<usize>,
     /// Status message to display
     pub status_message: Option<String>,
+    /// Full-text search query
+    pub search_query: String,
+    /// Is the search query being typed
+    pub search_editing: bool,
+    /// Sessions matching the last search, best first
+    pub search_results: Vec<SearchHit>,
+    /// Sessions matching the last search in all, listed or not
+    pub search_total: i64,
+    /// Currently selected search result index
+    pub search_index: usize,
+    /// Session loaded from the harvest database, shown in the detail view
+    pub harvest_session: Option<SessionInfo>,
+    /// View to return to from the detail view of a harvested session
+    pub detail_return: AppMode,
+    /// Index of the message the search matched in the detail view, counting
+    /// each request's message and response
+    pub detail_match: Option<usize>,
+    /// View to return to when the search is closed
+    pub search_return: AppMode,
+    /// Where sessions are browsed from
+    pub source: DataSource,
+    /// Harvested sessions matching the filters, newest first
+    pub harvest_sessions: Vec<SearchHit>,
+    /// Harvested sessions matching the filters in all, listed or not
+    pub harvest_total: i64,
+    /// Currently selected harvested session index
+    pub harvest_index: usize,
+    /// Providers, workspaces and tags across the harvest database
+    pub harvest_choices: Facets,
+    /// Show only sessions from this provider
+    pub harvest_provider: Option<String>,
+    /// Show only sessions from this workspace
+    pub harvest_workspace: Option<String>,
+    /// Show only sessions with this tag
+    pub harvest_tag: Option<String>,
 }
 
 impl App {
@@ -74,6 +143,23 @@ impl App {
             filter_active: false,
             filtered_indices,
             status_message: None,
+            search_query: String::new(),
+            search_editing: false,
+            search_results: Vec::new(),
+            search_total: 0,
+            search_index: 0,
+            harvest_session: None,
+            detail_return: AppMode::Sessions,
+            detail_match: None,
+            search_return: AppMode::Workspaces,
+            source: DataSource::Workspaces,
+            harvest_sessions: Vec::new(),
+            harvest_total: 0,
+            harvest_index: 0,
+            harvest_choices: Facets::default(),
+            harvest_provider: None,
+            harvest_workspace: None,
+            harvest_tag: None,
         };
 
         Ok(app)
@@ -88,9 +174,12 @@ impl App {
         self.workspaces.get(*actual_index)
     }
 
-    /// Get the currently selected session (if any)
+    /// Get the currently selected session (if any), or the harvested one
+    /// being viewed
     pub fn current_session(&self) -> Option<&SessionInfo> {
-        self.sessions.get(self.session_index)
+        self.harvest_session
+            .as_ref()
+            .or_else(|| self.sessions.get(self.session_index))
     }
 
     /// Load sessions for the currently selected workspace
@@ -108,7 +197,7 @@ impl App {
                         .and_then(|m| m.modified().ok())
                         .map(|t| {
                             let datetime: chrono::DateTime<chrono::Utc> = t.into();
-                            datetime.format("%Y-%m-%d %H:%M").to_string()
+                            format_datetime_short(datetime)
                         })
                         .unwrap_or_else(|| "unknown".to_string());
 
@@ -179,6 +268,16 @@ impl App {
                     self.detail_scroll -= 1;
                 }
             }
+            AppMode::Search => {
+                if self.search_index > 0 {
+                    self.search_index -= 1;
+                }
+            }
+            AppMode::Harvest => {
+                if self.harvest_index > 0 {
+                    self.harvest_index -= 1;
+                }
+            }
             AppMode::Help => {}
         }
     }
@@ -199,6 +298,16 @@ impl App {
             AppMode::SessionDetail => {
                 self.detail_scroll += 1;
             }
+            AppMode::Search => {
+                if self.search_index + 1 < self.search_results.len() {
+                    self.search_index += 1;
+                }
+            }
+            AppMode::Harvest => {
+                if self.harvest_index + 1 < self.harvest_sessions.len() {
+                    self.harvest_index += 1;
+                }
+            }
             AppMode::Help => {}
         }
     }
@@ -215,6 +324,12 @@ impl App {
             AppMode::SessionDetail => {
                 self.detail_scroll = self.detail_scroll.saturating_sub(10);
             }
+            AppMode::Search => {
+                self.search_index = self.search_index.saturating_sub(10);
+            }
+            AppMode::Harvest => {
+                self.harvest_index = self.harvest_index.saturating_sub(10);
+            }
             AppMode::Help => {}
         }
     }
@@ -233,6 +348,14 @@ impl App {
             AppMode::SessionDetail => {
                 self.detail_scroll += 10;
             }
+            AppMode::Search => {
+                let max = self.search_results.len().saturating_sub(1);
+                self.search_index = (self.search_index + 10).min(max);
+            }
+            AppMode::Harvest => {
+                let max = self.harvest_sessions.len().saturating_sub(1);
+                self.harvest_index = (self.harvest_index + 10).min(max);
+            }
             AppMode::Help => {}
         }
     }
@@ -249,6 +372,12 @@ impl App {
             AppMode::SessionDetail => {
                 self.detail_scroll = 0;
             }
+            AppMode::Search => {
+                self.search_index = 0;
+            }
+            AppMode::Harvest => {
+                self.harvest_index = 0;
+            }
             AppMode::Help => {}
         }
     }
@@ -266,6 +395,12 @@ impl App {
                 // Will be clamped by scroll logic
                 self.detail_scroll = usize::MAX;
             }
+            AppMode::Search => {
+                self.search_index = self.search_results.len().saturating_sub(1);
+            }
+            AppMode::Harvest => {
+                self.harvest_index = self.harvest_sessions.len().saturating_sub(1);
+            }
             AppMode::Help => {}
         }
     }
@@ -289,7 +424,13 @@ impl App {
                     self.detail_scroll = 0;
                 }
             }
-            AppMode::SessionDetail | AppMode::Help => {}
+            AppMode::Harvest => {
+                if let Some(hit) = self.harvest_sessions.get(self.harvest_index).cloned() {
+                    self.show_harvested(&hit);
+                }
+            }
+            // Opening a search result needs the screen width (see `open_search_result`)
+            AppMode::SessionDetail | AppMode::Search | AppMode::Help => {}
         }
     }
 
@@ -303,7 +444,20 @@ impl App {
                 self.mode = AppMode::Workspaces;
             }
             AppMode::SessionDetail => {
-                self.mode = AppMode::Sessions;
+                // A harvested session goes back to the list it was opened from
+                if self.harvest_session.take().is_some() {
+                    self.detail_match = None;
+                    self.mode = self.detail_return;
+                } else {
+                    self.mode = AppMode::Sessions;
+                }
+            }
+            AppMode::Search => {
+                self.search_editing = false;
+                self.mode = self.search_return;
+            }
+            AppMode::Harvest => {
+                // Top level of the harvest source
             }
             AppMode::Help => {
                 self.mode = self.previous_mode;
@@ -355,8 +509,183 @@ impl App {
         self.apply_filter();
     }
 
+    /// Open the search view, or go back to typing its query
+    pub fn start_search(&mut self) {
+        match self.mode {
+            AppMode::Search => {}
+            AppMode::SessionDetail if self.harvest_session.is_some() => {
+                self.harvest_session = None;
+                self.detail_match = None;
+                if self.detail_return != AppMode::Search {
+                    self.search_return = self.detail_return;
+                }
+            }
+            mode => self.search_return = mode,
+        }
+        self.mode = AppMode::Search;
+        self.search_editing = true;
+    }
+
+    /// Handle search query character input
+    pub fn search_input(&mut self, c: char) {
+        self.search_query.push(c);
+    }
+
+    /// Handle backspace in the search query
+    pub fn search_backspace(&mut self) {
+        self.search_query.pop();
+    }
+
+    /// Stop typing the query, keeping the results, or close the search
+    /// when there are none
+    pub fn cancel_search(&mut self) {
+        if self.search_results.is_empty() {
+            self.back();
+        } else {
+            self.search_editing = false;
+        }
+    }
+
+    /// Search the harvest database for the query
+    pub fn run_search(&mut self) {
+        self.search_results.clear();
+        self.search_total = 0;
+        self.search_index = 0;
+        if self.search_query.trim().is_empty() {
+            return;
+        }
+        match search_harvest(&self.search_query) {
+            Ok(results) => {
+                self.search_results = results.hits;
+                self.search_total = results.total;
+                if self.search_results.is_empty() {
+                    self.status_message = Some("No harvested messages match".to_string());
+                } else {
+                    self.search_editing = false;
+                }
+            }
+            Err(e) => self.status_message = Some(format!("Search failed: {:#}", e)),
+        }
+    }
+
+    /// Show the selected result's session, scrolled to the message that
+    /// matched for a view `width` columns wide
+    pub fn open_search_result(&mut self, width: u16) {
+        let Some(hit) = self.search_results.get(self.search_index).cloned() else {
+            return;
+        };
+        if !self.show_harvested(&hit) {
+            return;
+        }
+        // Harvest stores each request's message and response at 2i and 2i + 1
+        self.detail_match = hit.message_index.map(|index| index.max(0) as usize);
+        if let (Some(index), Some(info)) = (self.detail_match, &self.harvest_session) {
+            self.detail_scroll = ui::message_offset(&info.session, index, width);
+        }
+    }
+
+    /// Show a harvested session in the detail view, returning whether it
+    /// could be loaded
+    fn show_harvested(&mut self, hit: &SearchHit) -> bool {
+        match load_harvested(hit) {
+            Ok(info) => {
+                self.harvest_session = Some(info);
+                self.detail_return = self.mode;
+                self.detail_match = None;
+                self.detail_scroll = 0;
+                self.mode = AppMode::SessionDetail;
+                true
+            }
+            Err(e) => {
+                self.status_message = Some(format!("Could not open {}: {:#}", hit.id, e));
+                false
+            }
+        }
+    }
+
+    /// Switch between browsing VS Code workspaces and the harvest database
+    pub fn toggle_source(&mut self) {
+        match self.source {
+            DataSource::Workspaces => {
+                self.source = DataSource::Harvest;
+                self.mode = AppMode::Harvest;
+                self.load_harvest();
+            }
+            DataSource::Harvest => {
+                self.source = DataSource::Workspaces;
+                self.mode = AppMode::Workspaces;
+            }
+        }
+    }
+
+    /// List the harvested sessions matching the filters, and what they can
+    /// be filtered on
+    pub fn load_harvest(&mut self) {
+        self.harvest_index = 0;
+        match list_harvest(self.harvest_filters()) {
+            Ok((results, choices)) => {
+                self.harvest_sessions = results.hits;
+                self.harvest_total = results.total;
+                self.harvest_choices = choices;
+            }
+            Err(e) => {
+                self.harvest_sessions.clear();
+                self.harvest_total = 0;
+                self.status_message = Some(format!("Could not read harvest: {:#}", e));
+            }
+        }
+    }
+
+    /// Filters for the harvested session list
+    fn harvest_filters(&self) -> SearchFilters {
+        SearchFilters {
+            providers: self.harvest_provider.iter().cloned().collect(),
+            workspace: self.harvest_workspace.clone(),
+            tags: self.harvest_tag.iter().cloned().collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Filter on the next value of `filter`, or stop filtering on it after
+    /// the last
+    pub fn cycle_harvest_filter(&mut self, filter: HarvestFilter) {
+        let (choices, current) = match filter {
+            HarvestFilter::Provider => {
+                (&self.harvest_choices.providers, &mut self.harvest_provider)
+            }
+            HarvestFilter::Workspace => (
+                &self.harvest_choices.workspaces,
+                &mut self.harvest_workspace,
+            ),
+            HarvestFilter::Tag => (&self.harvest_choices.tags, &mut self.harvest_tag),
+        };
+        let next = match current
+            .as_ref()
+            .and_then(|value| choices.iter().position(|choice| &choice.value == value))
+        {
+            Some(i) => choices.get(i + 1),
+            None if current.is_some() => None,
+            None => choices.first(),
+        };
+        *current = next.map(|choice| choice.value.clone());
+        self.load_harvest();
+    }
+
+    /// Show every harvested session
+    pub fn clear_harvest_filters(&mut self) {
+        self.harvest_provider = None;
+        self.harvest_workspace = None;
+        self.harvest_tag = None;
+        self.load_harvest();
+    }
+
     /// Refresh data
     pub fn refresh(&mut self) {
+        if self.source == DataSource::Harvest {
+            self.load_harvest();
+            self.status_message = Some("Refreshed harvest data".to_string());
+            return;
+        }
         if let Ok(workspaces) = discover_workspaces() {
             self.workspaces = workspaces;
             self.apply_filter();
@@ -364,6 +693,17 @@ impl App {
         }
     }
 
+    /// File the selected session as an issue in the configured tracker
+    pub fn create_issue(&mut self) {
+        let Some(info) = self.current_session() else {
+            return;
+        };
+        self.status_message = Some(match file_issue(info) {
+            Ok(issue) => format!("Filed {} {}", issue.key, issue.url),
+            Err(e) => format!("Could not file an issue: {}", e),
+        });
+    }
+
     /// Get count of workspaces with chats
     pub fn workspaces_with_chats(&self) -> usize {
         self.workspaces
@@ -377,3 +717,115 @@ impl App {
         self.workspaces.iter().map(|w| w.chat_session_count).sum()
     }
 }
+
+/// File a session in the configured tracker, recording the issue in the
+/// harvest database when the session has been harvested
+fn file_issue(info: &SessionInfo) -> anyhow::Result<CreatedIssue> {
+    let session_id = match &info.session.session_id {
+        Some(id) => id.clone(),
+        None => info
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| info.filename.clone()),
+    };
+    let tracker = issues::tracker(None, None)?;
+    let issue = session_issue(
+        tracker.markup(),
+        &session_id,
+        "GitHub Copilot",
+        &info.session,
+        None,
+    );
+    let created = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(tracker.create_issue(&issue))
+        .map_err(anyhow::Error::msg)?;
+
+    let db_path = get_db_path(None)?;
+    if db_path.exists() {
+        let conn = Connection::open(&db_path)?;
+        let harvested = conn
+            .query_row(
+                "SELECT 1 FROM sessions WHERE id = ?1",
+                [&session_id],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        if harvested {
+            issues::ensure_session_issues_table(&conn)?;
+            issues::save_session_issue(&conn, &session_id, &created)?;
+        }
+    }
+    Ok(created)
+}
+
+/// Open the harvest database, which must exist
+fn open_harvest() -> anyhow::Result<Connection> {
+    let db_path = get_db_path(None)?;
+    if !db_path.exists() {
+        anyhow::bail!(
+            "harvest database not found at {}; run 'csm harvest run' first",
+            db_path.display()
+        );
+    }
+    Ok(Connection::open(&db_path)?)
+}
+
+/// Run a full-text search of the harvest database
+fn search_harvest(query: &str) -> anyhow::Result<search::SearchResults> {
+    let conn = open_harvest()?;
+    let options = SearchOptions {
+        query: query.to_string(),
+        limit: SEARCH_LIMIT,
+        ..Default::default()
+    };
+    search::search(&conn, &options, None)
+}
+
+/// Harvested sessions matching `filters`, newest first, with the providers,
+/// workspaces and tags of all harvested sessions
+fn list_harvest(filters: SearchFilters) -> anyhow::Result<(search::SearchResults, Facets)> {
+    let conn = open_harvest()?;
+    let everything = search::search(
+        &conn,
+        &SearchOptions {
+            limit: 0,
+            facets: true,
+            ..Default::default()
+        },
+        None,
+    )?;
+    let options = SearchOptions {
+        filters,
+        limit: HARVEST_LIMIT,
+        ..Default::default()
+    };
+    let results = search::search(&conn, &options, None)?;
+    Ok((results, everything.facets.unwrap_or_default()))
+}
+
+/// A harvested session as the detail view shows it
+fn load_harvested(hit: &SearchHit) -> anyhow::Result<SessionInfo> {
+    let conn = open_harvest()?;
+    let json: String = conn
+        .query_row(
+            "SELECT session_json FROM sessions WHERE id = ?1",
+            [&hit.id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .context("no longer in the harvest database")?;
+    let session = parse_session_json(&json)?;
+    Ok(SessionInfo {
+        filename: hit.id.clone(),
+        path: PathBuf::from(&hit.id),
+        message_count: session.request_count(),
+        last_modified: from_epoch(hit.updated_at)
+            .map(format_datetime_short)
+            .unwrap_or_else(|| "unknown".to_string()),
+        session,
+    })
+}
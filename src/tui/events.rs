@@ -14,7 +14,7 @@ use crossterm::{
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 
-use super::app::{App, AppMode};
+use super::app::{App, AppMode, HarvestFilter};
 use super::ui;
 
 /// Run the TUI application
@@ -77,6 +77,22 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut A
                 continue;
             }
 
+            // Handle search query input the same way
+            if app.mode == AppMode::Search && app.search_editing {
+                match key.code {
+                    KeyCode::Enter => app.run_search(),
+                    KeyCode::Esc => app.cancel_search(),
+                    KeyCode::Backspace => app.search_backspace(),
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(());
+                    }
+                    KeyCode::Char(c) => app.search_input(c),
+                    _ => {}
+                }
+                terminal.draw(|f| ui::render(f, app))?;
+                continue;
+            }
+
             // Help mode - any key closes it
             if app.mode == AppMode::Help {
                 app.back();
@@ -113,18 +129,49 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut A
                 KeyCode::PageDown => {
                     app.page_down();
                 }
+                KeyCode::Enter if app.mode == AppMode::Search => {
+                    app.open_search_result(terminal.size()?.width);
+                }
                 KeyCode::Enter => {
                     app.enter();
                 }
                 KeyCode::Esc | KeyCode::Backspace => {
                     app.back();
                 }
-                KeyCode::Char('/') if app.mode == AppMode::Workspaces => {
+                KeyCode::Char('/') => {
+                    app.start_search();
+                }
+                KeyCode::Char('f') if app.mode == AppMode::Workspaces => {
                     app.start_filter();
                 }
                 KeyCode::Char('r') => {
                     app.refresh();
                 }
+                KeyCode::Tab
+                    if matches!(
+                        app.mode,
+                        AppMode::Workspaces | AppMode::Sessions | AppMode::Harvest
+                    ) =>
+                {
+                    app.toggle_source();
+                }
+                KeyCode::Char('p') if app.mode == AppMode::Harvest => {
+                    app.cycle_harvest_filter(HarvestFilter::Provider);
+                }
+                KeyCode::Char('w') if app.mode == AppMode::Harvest => {
+                    app.cycle_harvest_filter(HarvestFilter::Workspace);
+                }
+                KeyCode::Char('t') if app.mode == AppMode::Harvest => {
+                    app.cycle_harvest_filter(HarvestFilter::Tag);
+                }
+                KeyCode::Char('c') if app.mode == AppMode::Harvest => {
+                    app.clear_harvest_filters();
+                }
+                KeyCode::Char('i')
+                    if matches!(app.mode, AppMode::Sessions | AppMode::SessionDetail) =>
+                {
+                    app.create_issue();
+                }
                 _ => continue, // No redraw needed for unhandled keys
             }
 
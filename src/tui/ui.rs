@@ -7,13 +7,17 @@ use ratatui::{
     style::{Color, Style, Stylize},
     text::{Line, Span, Text},
     widgets::{
-        Block, Borders, Cell, Clear, List, ListItem, Paragraph, Row, Scrollbar,
+        Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Scrollbar,
         ScrollbarOrientation, ScrollbarState, Table, TableState, Wrap,
     },
     Frame,
 };
 
-use super::app::{App, AppMode};
+use super::app::{App, AppMode, DataSource};
+use crate::datetime::{format_datetime, format_datetime_short, from_epoch};
+use crate::models::ChatSession;
+use crate::providers::session_format::extract_response_text;
+use crate::text::{display_width, prefix_chars, truncate, truncate_start};
 
 /// Color scheme for the TUI (Ayu Monokai)
 #[allow(dead_code)]
@@ -71,15 +75,25 @@ fn render_header(frame: &mut Frame, app: &App, area: Rect) {
         AppMode::Workspaces => " CCSM - Workspaces ",
         AppMode::Sessions => " CCSM - Sessions ",
         AppMode::SessionDetail => " CCSM - Session Details ",
+        AppMode::Search => " CCSM - Search ",
+        AppMode::Harvest => " CCSM - Harvest ",
         AppMode::Help => " CCSM - Help ",
     };
 
-    let stats = format!(
-        " {} workspaces | {} with chats | {} total sessions ",
-        app.workspaces.len(),
-        app.workspaces_with_chats(),
-        app.total_sessions()
-    );
+    let stats = match app.source {
+        DataSource::Workspaces => format!(
+            " {} workspaces | {} with chats | {} total sessions ",
+            app.workspaces.len(),
+            app.workspaces_with_chats(),
+            app.total_sessions()
+        ),
+        DataSource::Harvest => format!(
+            " {} providers | {} workspaces | {} sessions shown ",
+            app.harvest_choices.providers.len(),
+            app.harvest_choices.workspaces.len(),
+            app.harvest_total
+        ),
+    };
 
     let header = Paragraph::new(Line::from(vec![
         Span::styled(title, Style::default().fg(Colors::ACCENT).bold()),
@@ -102,7 +116,13 @@ fn render_main_content(frame: &mut Frame, app: &App, area: Rect) {
         AppMode::Workspaces => render_workspaces_view(frame, app, area),
         AppMode::Sessions => render_sessions_view(frame, app, area),
         AppMode::SessionDetail => render_session_detail_view(frame, app, area),
-        AppMode::Help => render_workspaces_view(frame, app, area), // Show workspaces behind help
+        AppMode::Search => render_search_view(frame, app, area),
+        AppMode::Harvest => render_harvest_view(frame, app, area),
+        // Show the current source's list behind help
+        AppMode::Help => match app.source {
+            DataSource::Workspaces => render_workspaces_view(frame, app, area),
+            DataSource::Harvest => render_harvest_view(frame, app, area),
+        },
     }
 }
 
@@ -138,7 +158,7 @@ fn render_workspace_table(frame: &mut Frame, app: &App, area: Rect) {
             let ws = &app.workspaces[actual_idx];
             let is_selected = display_idx == app.workspace_index;
 
-            let hash = format!("{}...", &ws.hash[..8.min(ws.hash.len())]);
+            let hash = format!("{}...", prefix_chars(&ws.hash, 8));
             let path = ws
                 .project_path
                 .clone()
@@ -242,7 +262,7 @@ fn render_session_preview(frame: &mut Frame, app: &App, area: Rect) {
                     format!("{:2}. ", i + 1),
                     Style::default().fg(Colors::TEXT_DIM),
                 ),
-                Span::styled(truncate_string(&title, 30), style),
+                Span::styled(truncate(&title, 30), style),
                 Span::styled(
                     format!(" ({} msgs)", s.message_count),
                     Style::default().fg(Colors::INFO),
@@ -307,7 +327,7 @@ fn render_session_table(frame: &mut Frame, app: &App, area: Rect) {
 
             Row::new(vec![
                 Cell::from(format!("{}", i + 1)),
-                Cell::from(truncate_string(&title, 35)),
+                Cell::from(truncate(&title, 35)),
                 Cell::from(format!("{}", s.message_count)).style(Style::default().fg(Colors::INFO)),
                 Cell::from(s.last_modified.clone()).style(Style::default().fg(Colors::TEXT_DIM)),
             ])
@@ -344,6 +364,123 @@ fn render_session_table(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_stateful_widget(table, area, &mut state);
 }
 
+/// Render the harvested sessions view: the filters and the sessions they
+/// match
+fn render_harvest_view(frame: &mut Frame, app: &App, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(area);
+
+    let filter = |label: &'static str, value: &Option<String>| {
+        vec![
+            Span::styled(label, Style::default().fg(Colors::TEXT_DIM)),
+            match value {
+                Some(value) => Span::styled(value.clone(), Style::default().fg(Colors::WARNING)),
+                None => Span::styled("all", Style::default().fg(Colors::TEXT)),
+            },
+        ]
+    };
+    let mut spans = filter(" Provider: ", &app.harvest_provider);
+    spans.extend(filter("  |  Workspace: ", &app.harvest_workspace));
+    spans.extend(filter("  |  Tag: ", &app.harvest_tag));
+    let filters = Paragraph::new(Line::from(spans)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Colors::BORDER))
+            .title(Span::styled(
+                " Filters ",
+                Style::default().fg(Colors::ACCENT),
+            )),
+    );
+    frame.render_widget(filters, chunks[0]);
+
+    let header_cells = ["Title", "Provider", "Workspace", "Messages", "Modified"]
+        .iter()
+        .map(|h| Cell::from(*h).style(Style::default().fg(Colors::ACCENT).bold()));
+
+    let header = Row::new(header_cells)
+        .style(Style::default().bg(Colors::HEADER_BG))
+        .height(1);
+
+    let rows: Vec<Row> = app
+        .harvest_sessions
+        .iter()
+        .enumerate()
+        .map(|(i, hit)| {
+            let row_style = if i == app.harvest_index {
+                Style::default()
+                    .bg(Colors::SELECTED_BG)
+                    .fg(Colors::SELECTED_FG)
+            } else {
+                Style::default().fg(Colors::TEXT)
+            };
+            let workspace = hit
+                .workspace_name
+                .as_deref()
+                .or(hit.workspace_id.as_deref())
+                .unwrap_or("-");
+            let updated = from_epoch(hit.updated_at)
+                .map(format_datetime_short)
+                .unwrap_or_default();
+
+            Row::new(vec![
+                Cell::from(truncate(&hit.title, 50)),
+                Cell::from(hit.provider.clone()).style(Style::default().fg(Colors::PURPLE)),
+                Cell::from(truncate(workspace, 24)),
+                Cell::from(format!("{}", hit.message_count))
+                    .style(Style::default().fg(Colors::INFO)),
+                Cell::from(updated).style(Style::default().fg(Colors::TEXT_DIM)),
+            ])
+            .style(row_style)
+            .height(1)
+        })
+        .collect();
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Colors::BORDER_FOCUSED))
+        .title(Span::styled(
+            format!(
+                " Harvested Sessions ({} of {}) ",
+                app.harvest_sessions.len(),
+                app.harvest_total
+            ),
+            Style::default().fg(Colors::ACCENT),
+        ));
+
+    if app.harvest_sessions.is_empty() {
+        let empty = Paragraph::new(Span::styled(
+            "No harvested sessions match. Press [c] to clear the filters, or run 'csm harvest run'.",
+            Style::default().fg(Colors::TEXT_DIM),
+        ))
+        .block(block)
+        .wrap(Wrap { trim: true });
+        frame.render_widget(empty, chunks[1]);
+        return;
+    }
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(20),
+            Constraint::Length(16),
+            Constraint::Length(24),
+            Constraint::Length(10),
+            Constraint::Length(18),
+        ],
+    )
+    .header(header)
+    .block(block)
+    .row_highlight_style(Style::default().bg(Colors::SELECTED_BG))
+    .highlight_symbol(">> ");
+
+    let mut state = TableState::default();
+    state.select(Some(app.harvest_index));
+
+    frame.render_stateful_widget(table, chunks[1], &mut state);
+}
+
 /// Render message preview
 fn render_message_preview(frame: &mut Frame, app: &App, area: Rect) {
     let block = Block::default()
@@ -386,7 +523,7 @@ fn render_message_preview(frame: &mut Frame, app: &App, area: Rect) {
                 Style::default().fg(Colors::SUCCESS).bold(),
             )]));
             lines.push(Line::from(Span::styled(
-                truncate_string(&text, 60),
+                truncate(&text, 60),
                 Style::default().fg(Colors::TEXT),
             )));
             lines.push(Line::raw(""));
@@ -456,13 +593,60 @@ fn render_session_detail_view(frame: &mut Frame, app: &App, area: Rect) {
             Style::default().fg(Colors::ACCENT),
         ));
 
+    let (lines, _) = message_lines(&session.session, app.detail_match);
+    let total_lines = lines.len();
+
+    let paragraph = Paragraph::new(lines)
+        .block(msg_block)
+        .scroll((app.detail_scroll as u16, 0))
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(paragraph, chunks[1]);
+
+    // Scrollbar
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(Some("^"))
+        .end_symbol(Some("v"));
+
+    let mut scrollbar_state = ScrollbarState::new(total_lines).position(app.detail_scroll);
+
+    frame.render_stateful_widget(
+        scrollbar,
+        chunks[1].inner(ratatui::layout::Margin {
+            horizontal: 0,
+            vertical: 1,
+        }),
+        &mut scrollbar_state,
+    );
+}
+
+/// Lines of a session's messages and responses, with the matched message's
+/// header highlighted. Also returns the line each message starts on, indexed
+/// like harvested messages: 2i for request i's message and 2i + 1 for its
+/// response.
+fn message_lines(
+    session: &ChatSession,
+    matched: Option<usize>,
+) -> (Vec<Line<'static>>, Vec<usize>) {
     let mut lines: Vec<Line> = Vec::new();
+    let mut starts = Vec::with_capacity(session.requests.len() * 2);
+
+    let header_style = |index: usize, color: Color| {
+        if matched == Some(index) {
+            Style::default()
+                .fg(Colors::SELECTED_FG)
+                .bg(Colors::SELECTED_BG)
+                .bold()
+        } else {
+            Style::default().fg(color).bold()
+        }
+    };
 
-    for (i, req) in session.session.requests.iter().enumerate() {
+    for (i, req) in session.requests.iter().enumerate() {
         // Timestamp
         if let Some(ts) = req.timestamp {
             let dt = chrono::DateTime::from_timestamp_millis(ts)
-                .map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string())
+                .map(format_datetime)
                 .unwrap_or_else(|| "unknown".to_string());
             lines.push(Line::from(Span::styled(
                 format!("--- {} ---", dt),
@@ -471,11 +655,12 @@ fn render_session_detail_view(frame: &mut Frame, app: &App, area: Rect) {
         }
 
         // User message
+        starts.push(lines.len());
         if let Some(msg) = &req.message {
             let text = msg.get_text();
             lines.push(Line::from(vec![Span::styled(
                 format!("[{}] User: ", i + 1),
-                Style::default().fg(Colors::SUCCESS).bold(),
+                header_style(i * 2, Colors::SUCCESS),
             )]));
             for line in text.lines() {
                 lines.push(Line::from(Span::styled(
@@ -485,32 +670,150 @@ fn render_session_detail_view(frame: &mut Frame, app: &App, area: Rect) {
             }
         }
 
+        // Assistant response
+        starts.push(lines.len());
+        if let Some(text) = req.response.as_ref().and_then(extract_response_text) {
+            lines.push(Line::from(vec![Span::styled(
+                format!("[{}] Assistant: ", i + 1),
+                header_style(i * 2 + 1, Colors::ACCENT),
+            )]));
+            for line in text.lines() {
+                lines.push(Line::from(Span::styled(
+                    format!("    {}", line),
+                    Style::default().fg(Colors::TEXT_DIM),
+                )));
+            }
+        }
+
         lines.push(Line::raw(""));
     }
 
-    let paragraph = Paragraph::new(lines)
-        .block(msg_block)
-        .scroll((app.detail_scroll as u16, 0))
-        .wrap(Wrap { trim: false });
-
-    frame.render_widget(paragraph, chunks[1]);
+    (lines, starts)
+}
 
-    // Scrollbar
-    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
-        .begin_symbol(Some("^"))
-        .end_symbol(Some("v"));
+/// Scroll offset that brings message `index` (see `message_lines`) to the
+/// top of the detail view on a screen `width` columns wide
+pub(super) fn message_offset(session: &ChatSession, index: usize, width: u16) -> usize {
+    let (lines, starts) = message_lines(session, None);
+    let Some(&start) = starts.get(index) else {
+        return 0;
+    };
+    // Borders take a column on each side; wrapped lines take more than one row
+    let inner = usize::from(width.saturating_sub(2)).max(1);
+    lines[..start]
+        .iter()
+        .map(|line| line.width().div_ceil(inner).max(1))
+        .sum()
+}
 
-    let total_lines = session.session.requests.len() * 5; // Approximate
-    let mut scrollbar_state = ScrollbarState::new(total_lines).position(app.detail_scroll);
+/// Render the search view: the query and the sessions it matched
+fn render_search_view(frame: &mut Frame, app: &App, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(area);
 
-    frame.render_stateful_widget(
-        scrollbar,
-        chunks[1].inner(ratatui::layout::Margin {
-            horizontal: 0,
-            vertical: 1,
-        }),
-        &mut scrollbar_state,
+    let (border, cursor) = if app.search_editing {
+        (Colors::BORDER_FOCUSED, "_")
+    } else {
+        (Colors::BORDER, "")
+    };
+    let input = Paragraph::new(Line::from(vec![
+        Span::styled(&app.search_query, Style::default().fg(Colors::TEXT)),
+        Span::styled(cursor, Style::default().fg(Colors::ACCENT)),
+    ]))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(border))
+            .title(Span::styled(
+                " Search harvested messages ",
+                Style::default().fg(Colors::ACCENT),
+            )),
     );
+    frame.render_widget(input, chunks[0]);
+
+    let results_border = if app.search_editing {
+        Colors::BORDER
+    } else {
+        Colors::BORDER_FOCUSED
+    };
+    let title = if app.search_results.is_empty() {
+        " Results ".to_string()
+    } else {
+        format!(
+            " Results ({} of {}) ",
+            app.search_results.len(),
+            app.search_total
+        )
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(results_border))
+        .title(Span::styled(title, Style::default().fg(Colors::ACCENT)));
+
+    if app.search_results.is_empty() {
+        let hint = if app.search_query.trim().is_empty() {
+            "Type a query and press Enter. Quote phrases, use OR and NOT, or prefix* words."
+        } else {
+            "No results. Press Enter to search."
+        };
+        let empty = Paragraph::new(Span::styled(hint, Style::default().fg(Colors::TEXT_DIM)))
+            .block(block)
+            .wrap(Wrap { trim: true });
+        frame.render_widget(empty, chunks[1]);
+        return;
+    }
+
+    let width = usize::from(chunks[1].width.saturating_sub(6));
+    let items: Vec<ListItem> = app
+        .search_results
+        .iter()
+        .map(|hit| {
+            let updated = from_epoch(hit.updated_at)
+                .map(format_datetime_short)
+                .unwrap_or_default();
+            let workspace = hit.workspace_name.as_deref().unwrap_or("-");
+            let snippet = hit
+                .snippet
+                .as_deref()
+                .unwrap_or("")
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            ListItem::new(vec![
+                Line::from(vec![
+                    Span::styled(
+                        truncate(&hit.title, width.saturating_sub(40)),
+                        Style::default().fg(Colors::TEXT).bold(),
+                    ),
+                    Span::styled(
+                        format!("  {} | {} | {}", hit.provider, workspace, updated),
+                        Style::default().fg(Colors::TEXT_DIM),
+                    ),
+                ]),
+                Line::from(Span::styled(
+                    format!("  {}", truncate(&snippet, width.saturating_sub(2))),
+                    Style::default().fg(Colors::PURPLE),
+                )),
+            ])
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(
+            Style::default()
+                .bg(Colors::SELECTED_BG)
+                .fg(Colors::SELECTED_FG),
+        )
+        .highlight_symbol(">> ");
+
+    let mut state = ListState::default();
+    if !app.search_editing {
+        state.select(Some(app.search_index));
+    }
+    frame.render_stateful_widget(list, chunks[1], &mut state);
 }
 
 /// Render the footer/status bar
@@ -523,13 +826,28 @@ fn render_footer(frame: &mut Frame, app: &App, area: Rect) {
                     app.filter_query
                 )
             } else {
-                "[j/k] navigate | [Enter] view sessions | [/] filter | [r] refresh | [?] help | [q] quit".to_string()
+                "[j/k] navigate | [Enter] view sessions | [/] search | [f] filter | [Tab] harvest | [r] refresh | [?] help | [q] quit".to_string()
             }
         }
         AppMode::Sessions => {
-            "[j/k] navigate | [Enter] view details | [Esc] back | [?] help | [q] quit".to_string()
+            "[j/k] navigate | [Enter] view details | [/] search | [i] file issue | [Tab] harvest | [Esc] back | [?] help | [q] quit"
+                .to_string()
+        }
+        AppMode::Harvest => {
+            "[j/k] navigate | [Enter] view details | [p/w/t] provider/workspace/tag | [c] clear | [/] search | [Tab] workspaces | [q] quit"
+                .to_string()
+        }
+        AppMode::SessionDetail => {
+            "[j/k] scroll | [/] search | [i] file issue | [Esc] back | [?] help | [q] quit".to_string()
+        }
+        AppMode::Search => {
+            if app.search_editing {
+                "[Enter] search | [Esc] cancel".to_string()
+            } else {
+                "[j/k] navigate | [Enter] open at match | [/] new search | [Esc] back | [q] quit"
+                    .to_string()
+            }
         }
-        AppMode::SessionDetail => "[j/k] scroll | [Esc] back | [?] help | [q] quit".to_string(),
         AppMode::Help => "Press any key to close help".to_string(),
     };
 
@@ -603,14 +921,46 @@ fn render_help_overlay(frame: &mut Frame) {
         Line::from(vec![
             Span::styled("  /           ", Style::default().fg(Colors::PURPLE)),
             Span::styled(
-                "Start filter (workspaces view)",
+                "Search harvested messages",
                 Style::default().fg(Colors::TEXT),
             ),
         ]),
+        Line::from(vec![
+            Span::styled("  f           ", Style::default().fg(Colors::PURPLE)),
+            Span::styled(
+                "Filter workspaces (workspaces view)",
+                Style::default().fg(Colors::TEXT),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  Tab         ", Style::default().fg(Colors::PURPLE)),
+            Span::styled(
+                "Switch between workspaces and the harvest database",
+                Style::default().fg(Colors::TEXT),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  p / w / t   ", Style::default().fg(Colors::PURPLE)),
+            Span::styled(
+                "Cycle provider / workspace / tag filter (harvest view)",
+                Style::default().fg(Colors::TEXT),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  c           ", Style::default().fg(Colors::PURPLE)),
+            Span::styled("Clear harvest filters", Style::default().fg(Colors::TEXT)),
+        ]),
         Line::from(vec![
             Span::styled("  r           ", Style::default().fg(Colors::PURPLE)),
             Span::styled("Refresh data", Style::default().fg(Colors::TEXT)),
         ]),
+        Line::from(vec![
+            Span::styled("  i           ", Style::default().fg(Colors::PURPLE)),
+            Span::styled(
+                "File session as a Jira/Linear issue",
+                Style::default().fg(Colors::TEXT),
+            ),
+        ]),
         Line::raw(""),
         Line::from(vec![Span::styled(
             "General",
@@ -668,9 +1018,9 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         .split(popup_layout[1])[1]
 }
 
-/// Truncate a path for display
+/// Truncate a path for display, keeping trailing components
 fn truncate_path(path: &str, max_len: usize) -> String {
-    if path.len() <= max_len {
+    if display_width(path) <= max_len {
         return path.to_string();
     }
 
@@ -680,8 +1030,8 @@ fn truncate_path(path: &str, max_len: usize) -> String {
 
     for part in parts.iter().rev() {
         if result.is_empty() {
-            result = part.to_string();
-        } else if result.len() + part.len() + 4 < max_len {
+            result = truncate_start(part, max_len);
+        } else if display_width(&result) + display_width(part) + 4 < max_len {
             result = format!("{}/{}", part, result);
         } else {
             result = format!(".../{}", result);
@@ -692,11 +1042,3 @@ fn truncate_path(path: &str, max_len: usize) -> String {
     result
 }
 
-/// Truncate a string for display
-fn truncate_string(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
-        s.to_string()
-    } else {
-        format!("{}...", &s[..max_len.saturating_sub(3)])
-    }
-}
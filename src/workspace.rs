@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //! Workspace discovery and management
 
+use crate::config::{self, StorageRoot};
 use crate::error::{CsmError, Result};
 use crate::models::{SessionWithPath, Workspace, WorkspaceJson};
 use crate::storage::{is_session_file_extension, parse_session_file};
@@ -11,8 +12,33 @@ use urlencoding::decode;
 /// Type alias for workspace info tuple (hash, path, project_path, modified_time)
 pub type WorkspaceInfo = (String, PathBuf, Option<String>, std::time::SystemTime);
 
+/// Get the VS Code `User` directory, honoring configured overrides
+pub fn get_vscode_user_path() -> Result<PathBuf> {
+    if let Some(path) = config::storage_root(StorageRoot::VsCodeUser) {
+        return Ok(path);
+    }
+
+    let path = if cfg!(target_os = "windows") {
+        dirs::config_dir().map(|p| p.join("Code").join("User"))
+    } else if cfg!(target_os = "macos") {
+        dirs::home_dir().map(|p| p.join("Library/Application Support/Code/User"))
+    } else {
+        // Linux
+        dirs::home_dir().map(|p| p.join(".config/Code/User"))
+    };
+
+    path.ok_or(CsmError::StorageNotFound)
+}
+
 /// Get the VS Code workspaceStorage path based on the operating system
 pub fn get_workspace_storage_path() -> Result<PathBuf> {
+    if let Some(path) = config::storage_root(StorageRoot::WorkspaceStorage) {
+        return Ok(path);
+    }
+    if let Some(user) = config::storage_root(StorageRoot::VsCodeUser) {
+        return Ok(user.join("workspaceStorage"));
+    }
+
     let path = if cfg!(target_os = "windows") {
         dirs::config_dir().map(|p| p.join("Code").join("User").join("workspaceStorage"))
     } else if cfg!(target_os = "macos") {
@@ -27,6 +53,13 @@ pub fn get_workspace_storage_path() -> Result<PathBuf> {
 
 /// Get the VS Code globalStorage path based on the operating system
 pub fn get_global_storage_path() -> Result<PathBuf> {
+    if let Some(path) = config::storage_root(StorageRoot::GlobalStorage) {
+        return Ok(path);
+    }
+    if let Some(user) = config::storage_root(StorageRoot::VsCodeUser) {
+        return Ok(user.join("globalStorage"));
+    }
+
     let path = if cfg!(target_os = "windows") {
         dirs::config_dir().map(|p| p.join("Code").join("User").join("globalStorage"))
     } else if cfg!(target_os = "macos") {
@@ -339,13 +372,22 @@ pub fn find_all_workspaces_for_project(project_name: &str) -> Result<Vec<Workspa
 
 /// Get all chat sessions from a workspace directory
 pub fn get_chat_sessions_from_workspace(workspace_dir: &Path) -> Result<Vec<SessionWithPath>> {
+    Ok(scan_chat_sessions_from_workspace(workspace_dir)?.0)
+}
+
+/// Get all chat sessions from a workspace directory, along with the files
+/// that could not be parsed (e.g. oversized or corrupt sessions)
+pub fn scan_chat_sessions_from_workspace(
+    workspace_dir: &Path,
+) -> Result<(Vec<SessionWithPath>, Vec<(PathBuf, CsmError)>)> {
     let chat_sessions_dir = workspace_dir.join("chatSessions");
 
     if !chat_sessions_dir.exists() {
-        return Ok(Vec::new());
+        return Ok((Vec::new(), Vec::new()));
     }
 
     let mut sessions = Vec::new();
+    let mut failures = Vec::new();
 
     for entry in std::fs::read_dir(&chat_sessions_dir)? {
         let entry = entry?;
@@ -356,13 +398,14 @@ pub fn get_chat_sessions_from_workspace(workspace_dir: &Path) -> Result<Vec<Sess
             .map(|e| is_session_file_extension(e))
             .unwrap_or(false)
         {
-            if let Ok(session) = parse_session_file(&path) {
-                sessions.push(SessionWithPath { path, session });
+            match parse_session_file(&path) {
+                Ok(session) => sessions.push(SessionWithPath { path, session }),
+                Err(e) => failures.push((path, e)),
             }
         }
     }
 
-    Ok(sessions)
+    Ok((sessions, failures))
 }
 
 use chrono::Utc;
@@ -778,3 +778,104 @@ mod detection_integration_tests {
         }
     }
 }
+
+// ============================================================================
+// Recovery Wizard Tests
+// ============================================================================
+
+mod recover_wizard_tests {
+    use super::*;
+    use chasm::commands::{
+        count_path_references, path_similarity, scan_orphan_candidates, OrphanReason,
+    };
+    use std::path::Path;
+
+    #[test]
+    fn test_path_similarity_prefers_same_folder_name() {
+        let old = Path::new("/home/me/old/projects/chasm");
+        assert!(
+            path_similarity(old, Path::new("/mnt/work/projects/chasm"))
+                > path_similarity(old, Path::new("/mnt/work/chasm"))
+        );
+        assert!(path_similarity(old, Path::new("/mnt/work/chasm")) > 0);
+        assert!(path_similarity(old, Path::new("/mnt/work/chasm-cli")) > 0);
+        assert_eq!(path_similarity(old, Path::new("/mnt/work/other")), 0);
+    }
+
+    #[test]
+    fn test_count_path_references() {
+        let content =
+            r#"{"uri": "file:///home/me/app/src/main.rs", "path": "/home/me/app/Cargo.toml"}"#;
+        assert_eq!(count_path_references(content, Path::new("/home/me/app")), 2);
+        assert_eq!(
+            count_path_references(content, Path::new("/home/me/other")),
+            0
+        );
+
+        let windows = r#"{"fsPath": "C:\\Users\\me\\app\\main.rs", "uri": "file:///c%3A/Users/me/app/lib.rs"}"#;
+        assert_eq!(
+            count_path_references(windows, Path::new(r"C:\Users\me\app")),
+            2
+        );
+    }
+
+    #[test]
+    fn test_scan_finds_deleted_workspace_sessions() {
+        let temp = TempDir::new().unwrap();
+        let storage = temp.path().join("workspaceStorage");
+        let live_project = temp.path().join("projects").join("app");
+        fs::create_dir_all(&live_project).unwrap();
+        let live_project = live_project.canonicalize().unwrap();
+
+        let live_ws = storage.join("live");
+        fs::create_dir_all(live_ws.join("chatSessions")).unwrap();
+        fs::write(
+            live_ws.join("workspace.json"),
+            format!(r#"{{"folder": "file://{}"}}"#, live_project.display()),
+        )
+        .unwrap();
+
+        // Project moved from old/app to projects/app
+        let moved_ws = storage.join("moved");
+        fs::create_dir_all(moved_ws.join("chatSessions")).unwrap();
+        fs::write(
+            moved_ws.join("workspace.json"),
+            format!(
+                r#"{{"folder": "file://{}"}}"#,
+                temp.path().join("old").join("app").display()
+            ),
+        )
+        .unwrap();
+        create_mock_session(&moved_ws.join("chatSessions"), "moved-session", "Moved", 2);
+
+        // Unrelated deleted project
+        let gone_ws = storage.join("gone");
+        fs::create_dir_all(gone_ws.join("chatSessions")).unwrap();
+        fs::write(
+            gone_ws.join("workspace.json"),
+            r#"{"folder": "file:///nonexistent/zzz"}"#,
+        )
+        .unwrap();
+        create_mock_session(&gone_ws.join("chatSessions"), "gone-session", "Gone", 1);
+
+        let candidates = scan_orphan_candidates(&storage).unwrap();
+        assert_eq!(candidates.len(), 2);
+
+        let moved = candidates
+            .iter()
+            .find(|c| c.session_id == "moved-session")
+            .unwrap();
+        assert_eq!(moved.reason, OrphanReason::FolderDeleted);
+        assert_eq!(moved.preview, "Question 1");
+        let suggestion = moved.suggestion.as_ref().unwrap();
+        assert_eq!(suggestion.hash, "live");
+        assert_eq!(suggestion.project_path, live_project);
+
+        let gone = candidates
+            .iter()
+            .find(|c| c.session_id == "gone-session")
+            .unwrap();
+        assert_eq!(gone.reason, OrphanReason::FolderDeleted);
+        assert!(gone.suggestion.is_none());
+    }
+}
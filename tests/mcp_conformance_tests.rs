@@ -0,0 +1,494 @@
+//! MCP Conformance Tests
+//!
+//! End-to-end tests that spawn `csm-mcp`, drive it with JSON-RPC 2.0 over
+//! stdio, and check its answers against the Model Context Protocol.
+//! Set `CSM_MCP_BIN` to run them against another build of the server.
+
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// How long to wait for the server to answer before failing the test
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+// =============================================================================
+// Harness
+// =============================================================================
+
+/// A `csm-mcp` process with its own config, home, and harvest database
+struct McpProcess {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    messages: Receiver<Value>,
+    /// Notifications received while waiting for responses
+    notifications: Vec<Value>,
+    next_id: i64,
+    _dir: TempDir,
+}
+
+impl McpProcess {
+    fn spawn(args: &[&str]) -> Self {
+        let dir = TempDir::new().unwrap();
+        let db_path = create_harvest_db(dir.path());
+
+        let bin = std::env::var_os("CSM_MCP_BIN")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(env!("CARGO_BIN_EXE_csm-mcp")));
+        let mut child = Command::new(bin)
+            .args(args)
+            .current_dir(dir.path())
+            .env("HOME", dir.path())
+            .env("XDG_CONFIG_HOME", dir.path().join(".config"))
+            .env("CSM_CONFIG", dir.path().join("config.toml"))
+            .env("CSM_HARVEST_DB", &db_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Failed to start csm-mcp");
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (tx, messages) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                let message = serde_json::from_str(&line)
+                    .unwrap_or_else(|e| panic!("stdout is not JSON-RPC ({}): {}", e, line));
+                if tx.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            child,
+            stdin: Some(stdin),
+            messages,
+            notifications: Vec::new(),
+            next_id: 1,
+            _dir: dir,
+        }
+    }
+
+    /// Spawn a server and complete the handshake
+    fn initialized(args: &[&str]) -> Self {
+        let mut server = Self::spawn(args);
+        let result = server.result(
+            "initialize",
+            json!({
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": { "name": "conformance", "version": "1.0" }
+            }),
+        );
+        assert_eq!(result["protocolVersion"], "2025-06-18");
+        server.notify("notifications/initialized", json!({}));
+        server
+    }
+
+    fn send_line(&mut self, line: &str) {
+        let stdin = self.stdin.as_mut().unwrap();
+        writeln!(stdin, "{}", line).unwrap();
+        stdin.flush().unwrap();
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        self.send_line(&message.to_string());
+    }
+
+    /// Send a request and return the whole response
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        let id = self.next_id;
+        self.next_id += 1;
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        self.send_line(&message.to_string());
+        self.response(&json!(id))
+    }
+
+    /// Send a request that must succeed and return its result
+    fn result(&mut self, method: &str, params: Value) -> Value {
+        let response = self.request(method, params);
+        assert!(
+            response.get("error").is_none(),
+            "{} failed: {}",
+            method,
+            response
+        );
+        response["result"].clone()
+    }
+
+    fn call_tool(&mut self, name: &str, arguments: Value) -> Value {
+        self.result(
+            "tools/call",
+            json!({ "name": name, "arguments": arguments }),
+        )
+    }
+
+    fn read_resource(&mut self, uri: &str) -> Value {
+        let result = self.result("resources/read", json!({ "uri": uri }));
+        let contents = result["contents"].as_array().unwrap();
+        assert_eq!(contents.len(), 1);
+        assert_eq!(contents[0]["uri"], uri);
+        contents[0].clone()
+    }
+
+    /// Wait for the response with `id`, keeping notifications that come first
+    fn response(&mut self, id: &Value) -> Value {
+        loop {
+            let message = self.next_message();
+            assert_eq!(message["jsonrpc"], "2.0", "bad envelope: {}", message);
+            if message.get("id").is_none() {
+                assert!(
+                    message["method"].is_string(),
+                    "bad notification: {}",
+                    message
+                );
+                self.notifications.push(message);
+                continue;
+            }
+            assert_eq!(&message["id"], id, "unexpected response: {}", message);
+            assert!(
+                message.get("result").is_some() != message.get("error").is_some(),
+                "a response has exactly one of result and error: {}",
+                message
+            );
+            return message;
+        }
+    }
+
+    fn next_message(&mut self) -> Value {
+        self.messages
+            .recv_timeout(TIMEOUT)
+            .expect("csm-mcp did not answer in time")
+    }
+
+    /// Close stdin and wait for the server to exit
+    fn finish(mut self) -> bool {
+        drop(self.stdin.take());
+        self.child.wait().unwrap().success()
+    }
+}
+
+impl Drop for McpProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// A harvest database holding two sessions
+fn create_harvest_db(dir: &Path) -> PathBuf {
+    let db_path = dir.join("chat_sessions.db");
+    chasm::commands::harvest_init(Some(db_path.to_str().unwrap()), false).unwrap();
+
+    let conn = Connection::open(&db_path).unwrap();
+    for (id, provider, title, updated_at) in [
+        ("s1", "copilot", "Fix the parser", 1_700_000_000_000_i64),
+        ("s2", "cursor", "Write the docs", 1_700_000_100_000_i64),
+    ] {
+        let session_json = json!({ "sessionId": id, "title": title, "requests": [] });
+        conn.execute(
+            "INSERT INTO sessions
+             (id, provider, title, message_count, created_at, updated_at, harvested_at, session_json)
+             VALUES (?, ?, ?, 0, ?, ?, ?, ?)",
+            rusqlite::params![
+                id,
+                provider,
+                title,
+                updated_at,
+                updated_at,
+                updated_at,
+                session_json.to_string()
+            ],
+        )
+        .unwrap();
+    }
+    db_path
+}
+
+/// The JSON a tool returned as text, which must match its structured content
+fn tool_json(result: &Value) -> Value {
+    assert_ne!(result["isError"], true, "tool failed: {}", result);
+    let text = result["content"][0]["text"].as_str().unwrap();
+    let value: Value = serde_json::from_str(text).unwrap();
+    assert_eq!(value, result["structuredContent"]);
+    value
+}
+
+// =============================================================================
+// Lifecycle
+// =============================================================================
+
+mod lifecycle {
+    use super::*;
+
+    #[test]
+    fn test_initialize_negotiates_protocol_version() {
+        let mut server = McpProcess::spawn(&[]);
+        let result = server.result(
+            "initialize",
+            json!({ "protocolVersion": "2025-03-26", "capabilities": {} }),
+        );
+        assert_eq!(result["protocolVersion"], "2025-03-26");
+        assert_eq!(result["serverInfo"]["name"], "csm-mcp");
+        assert!(result["serverInfo"]["version"].is_string());
+        assert!(result["capabilities"]["tools"].is_object());
+        assert!(result["capabilities"]["resources"].is_object());
+        assert!(result["capabilities"]["logging"].is_object());
+
+        // An unknown revision gets the newest one the server speaks
+        let result = server.result(
+            "initialize",
+            json!({ "protocolVersion": "1999-01-01", "capabilities": {} }),
+        );
+        assert_eq!(result["protocolVersion"], "2025-06-18");
+    }
+
+    #[test]
+    fn test_notifications_are_not_answered() {
+        let mut server = McpProcess::initialized(&[]);
+        server.notify("notifications/roots/list_changed", json!({}));
+        // The next response is the ping's, not one for the notifications
+        assert_eq!(server.result("ping", json!({})), json!({}));
+        assert!(server.notifications.is_empty());
+    }
+
+    #[test]
+    fn test_string_ids_are_echoed() {
+        let mut server = McpProcess::initialized(&[]);
+        server.send_line(r#"{"jsonrpc":"2.0","id":"abc","method":"ping"}"#);
+        let response = server.response(&json!("abc"));
+        assert_eq!(response["result"], json!({}));
+    }
+
+    #[test]
+    fn test_exits_cleanly_when_stdin_closes() {
+        let server = McpProcess::initialized(&[]);
+        assert!(server.finish());
+    }
+}
+
+// =============================================================================
+// JSON-RPC Errors
+// =============================================================================
+
+mod errors {
+    use super::*;
+
+    #[test]
+    fn test_unknown_method() {
+        let mut server = McpProcess::initialized(&[]);
+        let response = server.request("prompts/get_everything", json!({}));
+        assert_eq!(response["error"]["code"], -32601);
+    }
+
+    #[test]
+    fn test_parse_error_has_null_id() {
+        let mut server = McpProcess::initialized(&[]);
+        server.send_line("{not json");
+        let response = server.response(&Value::Null);
+        assert_eq!(response["error"]["code"], -32700);
+
+        // The server keeps going
+        server.result("ping", json!({}));
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let mut server = McpProcess::initialized(&[]);
+        let response = server.request("tools/call", json!({ "arguments": {} }));
+        assert_eq!(response["error"]["code"], -32602);
+
+        let response = server.request("resources/read", json!({}));
+        assert_eq!(response["error"]["code"], -32602);
+
+        let response = server.request("logging/setLevel", json!({ "level": "loud" }));
+        assert_eq!(response["error"]["code"], -32602);
+    }
+}
+
+// =============================================================================
+// Tools
+// =============================================================================
+
+mod tools {
+    use super::*;
+
+    fn tool_names(server: &mut McpProcess) -> Vec<String> {
+        let result = server.result("tools/list", json!({}));
+        result["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_tools_are_well_formed() {
+        let mut server = McpProcess::initialized(&["--allow-write"]);
+        let result = server.result("tools/list", json!({}));
+        let tools = result["tools"].as_array().unwrap();
+        assert!(!tools.is_empty());
+
+        let mut names = Vec::new();
+        for tool in tools {
+            let name = tool["name"].as_str().unwrap();
+            assert!(
+                name.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'),
+                "bad tool name {}",
+                name
+            );
+            assert!(!tool["description"].as_str().unwrap().is_empty());
+            assert_eq!(tool["inputSchema"]["type"], "object", "{}", name);
+            assert_eq!(tool["outputSchema"]["type"], "object", "{}", name);
+            names.push(name);
+        }
+        let count = names.len();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), count, "duplicate tool names");
+    }
+
+    #[test]
+    fn test_write_tools_need_allow_write() {
+        let mut server = McpProcess::initialized(&[]);
+        let names = tool_names(&mut server);
+        assert!(names.contains(&"csm_harvest_list".to_string()));
+        assert!(!names.contains(&"csm_harvest_run".to_string()));
+
+        // Calling one anyway is a tool error, not a protocol error
+        let result = server.call_tool(
+            "csm_tag_session",
+            json!({ "session_id": "s1", "add": ["x"] }),
+        );
+        assert_eq!(result["isError"], true);
+
+        let mut server = McpProcess::initialized(&["--allow-write"]);
+        assert!(tool_names(&mut server).contains(&"csm_harvest_run".to_string()));
+    }
+
+    #[test]
+    fn test_structured_results() {
+        let mut server = McpProcess::initialized(&[]);
+        let list = tool_json(&server.call_tool("csm_harvest_list", json!({})));
+        assert_eq!(list["total"], 2);
+        // Newest first
+        assert_eq!(list["sessions"][0]["id"], "s2");
+        assert_eq!(list["sessions"][1]["id"], "s1");
+
+        let databases = tool_json(&server.call_tool("csm_harvest_databases", json!({})));
+        assert_eq!(databases["default"], "default");
+        assert_eq!(databases["databases"][0]["sessions"], 2);
+    }
+
+    #[test]
+    fn test_unknown_tool_is_a_tool_error() {
+        let mut server = McpProcess::initialized(&[]);
+        let result = server.call_tool("csm_does_not_exist", json!({}));
+        assert_eq!(result["isError"], true);
+        assert_eq!(result["content"][0]["type"], "text");
+    }
+}
+
+// =============================================================================
+// Resources
+// =============================================================================
+
+mod resources {
+    use super::*;
+
+    #[test]
+    fn test_list_resources_and_templates() {
+        let mut server = McpProcess::initialized(&[]);
+        let result = server.result("resources/list", json!({}));
+        let uris: Vec<&str> = result["resources"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|resource| {
+                assert!(resource["name"].is_string());
+                resource["uri"].as_str().unwrap()
+            })
+            .collect();
+        assert!(uris.contains(&"csm://db/default/sessions"));
+        assert!(uris.contains(&"csm://providers"));
+
+        let result = server.result("resources/templates/list", json!({}));
+        let templates = result["resourceTemplates"].as_array().unwrap();
+        assert!(templates
+            .iter()
+            .any(|t| t["uriTemplate"] == "csm://db/{database}/session/{id}"));
+    }
+
+    #[test]
+    fn test_read_paged_sessions() {
+        let mut server = McpProcess::initialized(&[]);
+        let content = server.read_resource("csm://db/default/sessions?provider=cursor");
+        assert_eq!(content["mimeType"], "application/json");
+        let sessions: Value = serde_json::from_str(content["text"].as_str().unwrap()).unwrap();
+        assert_eq!(sessions["total"], 1);
+        assert_eq!(sessions["sessions"][0]["id"], "s2");
+        assert_eq!(sessions["page"], 1);
+
+        let content = server.read_resource("csm://db/default/sessions?page=0");
+        assert!(content["text"]
+            .as_str()
+            .unwrap()
+            .contains("pages start at 1"));
+    }
+
+    #[test]
+    fn test_read_harvested_session() {
+        let mut server = McpProcess::initialized(&[]);
+        let content = server.read_resource("csm://db/default/session/s1");
+        assert!(content["text"].as_str().unwrap().contains("Fix the parser"));
+
+        let content = server.read_resource("csm://nowhere");
+        assert!(content["text"]
+            .as_str()
+            .unwrap()
+            .contains("Unknown resource"));
+    }
+}
+
+// =============================================================================
+// Logging
+// =============================================================================
+
+mod logging {
+    use super::*;
+
+    #[test]
+    fn test_set_level_sends_log_messages() {
+        let log_file = TempDir::new().unwrap();
+        let log_path = log_file.path().join("csm-mcp.log");
+        let mut server = McpProcess::initialized(&["--log-file", log_path.to_str().unwrap()]);
+
+        server.result("logging/setLevel", json!({ "level": "info" }));
+        tool_json(&server.call_tool("csm_harvest_list", json!({})));
+
+        let messages: Vec<&Value> = server
+            .notifications
+            .iter()
+            .filter(|n| n["method"] == "notifications/message")
+            .collect();
+        assert!(messages.iter().any(|m| m["params"]["logger"] == "tools"
+            && m["params"]["data"]["tool"] == "csm_harvest_list"));
+        // Database queries are debug messages, below the level asked for
+        assert!(messages.iter().all(|m| m["params"]["level"] != "debug"));
+
+        assert!(server.finish());
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        assert!(log.contains("logging/setLevel"));
+        assert!(log.contains("database:"));
+    }
+}
@@ -526,6 +526,12 @@ mod backup_workspace_sessions_tests {
     use super::*;
     use chasm::storage::backup_workspace_sessions;
 
+    /// Keep backup catalog writes out of the real config directory
+    fn isolate_catalog() {
+        let dir = std::env::temp_dir().join("csm-storage-tests");
+        std::env::set_var("CSM_CONFIG", dir.join("config.toml"));
+    }
+
     #[test]
     fn test_backup_nonexistent_workspace() {
         let temp_dir = TempDir::new().unwrap();
@@ -536,6 +542,7 @@ mod backup_workspace_sessions_tests {
 
     #[test]
     fn test_backup_empty_chat_sessions() {
+        isolate_catalog();
         let temp_dir = TempDir::new().unwrap();
         let chat_sessions = temp_dir.path().join("chatSessions");
         fs::create_dir(&chat_sessions).unwrap();
@@ -553,6 +560,7 @@ mod backup_workspace_sessions_tests {
 
     #[test]
     fn test_backup_with_sessions() {
+        isolate_catalog();
         let temp_dir = TempDir::new().unwrap();
         let chat_sessions = temp_dir.path().join("chatSessions");
         fs::create_dir(&chat_sessions).unwrap();
@@ -1118,3 +1126,261 @@ mod empty_window_sessions_tests {
         assert!(invalid_session.is_err());
     }
 }
+
+// ============================================================================
+// Crash-safe Write Tests
+// ============================================================================
+
+mod atomic_write_tests {
+    use super::*;
+    use chasm::error::CsmError;
+    use chasm::storage::{
+        add_session_to_index, read_chat_session_index, update_chat_session_index,
+        write_and_register_session, write_file_atomic,
+    };
+
+    fn test_session(session_id: &str) -> ChatSession {
+        serde_json::from_str(&format!(
+            r#"{{"version": 3, "sessionId": "{}", "creationDate": 1700000000000,
+                "lastMessageDate": 1700000000000, "customTitle": "Atomic",
+                "initialLocation": "panel", "requests": []}}"#,
+            session_id
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_write_file_atomic_creates_and_replaces() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("session.json");
+
+        write_file_atomic(&path, b"first").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+
+        write_file_atomic(&path, b"second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+    }
+
+    #[test]
+    fn test_write_file_atomic_leaves_no_temp_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("session.json");
+
+        for i in 0..5 {
+            write_file_atomic(&path, format!("v{}", i).as_bytes()).unwrap();
+        }
+
+        let names: Vec<_> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["session.json".to_string()]);
+    }
+
+    #[test]
+    fn test_write_file_atomic_missing_directory_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("missing").join("session.json");
+        assert!(write_file_atomic(&path, b"data").is_err());
+    }
+
+    #[test]
+    fn test_update_index_rolls_back_on_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("state.vscdb");
+        create_test_database(&db_path).unwrap();
+        add_session_to_index(&db_path, "keep", "Keep", 1, false, "panel", false).unwrap();
+
+        let result: Result<(), CsmError> = update_chat_session_index(&db_path, |index| {
+            index.entries.clear();
+            Err(CsmError::NoSessionsFound)
+        });
+        assert!(result.is_err());
+
+        let index = read_chat_session_index(&db_path).unwrap();
+        assert_eq!(index.entries.len(), 1);
+        assert!(index.entries.contains_key("keep"));
+    }
+
+    #[test]
+    fn test_update_index_returns_closure_value() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("state.vscdb");
+        create_test_database(&db_path).unwrap();
+
+        let count = update_chat_session_index(&db_path, |index| {
+            index.entries.insert(
+                "a".to_string(),
+                ChatSessionIndexEntry {
+                    session_id: "a".to_string(),
+                    title: "A".to_string(),
+                    last_message_date: 0,
+                    is_imported: false,
+                    initial_location: "panel".to_string(),
+                    is_empty: true,
+                },
+            );
+            Ok(index.entries.len())
+        })
+        .unwrap();
+
+        assert_eq!(count, 1);
+        assert!(read_from_db(&db_path, "chat.ChatSessionStore.index")
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_write_and_register_session() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("state.vscdb");
+        create_test_database(&db_path).unwrap();
+        let session_path = temp_dir.path().join("abc.json");
+
+        write_and_register_session(&db_path, &session_path, &test_session("abc")).unwrap();
+
+        assert!(session_path.exists());
+        let index = read_chat_session_index(&db_path).unwrap();
+        assert_eq!(index.entries["abc"].title, "Atomic");
+    }
+
+    #[test]
+    fn test_write_and_register_removes_new_file_on_index_failure() {
+        let temp_dir = TempDir::new().unwrap();
+        // No ItemTable: the index update fails
+        let db_path = temp_dir.path().join("state.vscdb");
+        rusqlite::Connection::open(&db_path).unwrap();
+        let session_path = temp_dir.path().join("abc.json");
+
+        let result = write_and_register_session(&db_path, &session_path, &test_session("abc"));
+
+        assert!(result.is_err());
+        assert!(!session_path.exists());
+    }
+
+    #[test]
+    fn test_write_and_register_restores_previous_file_on_index_failure() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("state.vscdb");
+        rusqlite::Connection::open(&db_path).unwrap();
+        let session_path = temp_dir.path().join("abc.json");
+        fs::write(&session_path, "original").unwrap();
+
+        let result = write_and_register_session(&db_path, &session_path, &test_session("abc"));
+
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&session_path).unwrap(), "original");
+    }
+}
+
+// ============================================================================
+// Streaming Parse and Size Guard Tests
+// ============================================================================
+
+mod streaming_parse_tests {
+    use super::*;
+    use chasm::error::CsmError;
+    use chasm::storage::{check_session_file_size, parse_session_file_with_limit};
+
+    const NO_LIMIT: u64 = u64::MAX;
+
+    #[test]
+    fn test_parse_legacy_json_from_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("legacy.json");
+        fs::write(
+            &path,
+            r#"{
+  "version": 3,
+  "sessionId": "legacy-1",
+  "creationDate": 1700000000000,
+  "lastMessageDate": 1700000000000,
+  "customTitle": "Legacy",
+  "initialLocation": "panel",
+  "requests": []
+}"#,
+        )
+        .unwrap();
+
+        let session = parse_session_file_with_limit(&path, NO_LIMIT).unwrap();
+        assert_eq!(session.session_id.as_deref(), Some("legacy-1"));
+        assert_eq!(session.custom_title.as_deref(), Some("Legacy"));
+    }
+
+    #[test]
+    fn test_parse_jsonl_from_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("session.jsonl");
+        fs::write(
+            &path,
+            concat!(
+                r#"{"kind":0,"v":{"version":3,"sessionId":"jsonl-1","creationDate":1700000000000,"requests":[]}}"#,
+                "\n",
+                r#"{"kind":1,"k":["customTitle"],"v":"Streamed"}"#,
+                "\n",
+                r#"{"kind":2,"k":["requests"],"v":[{"requestId":"r1","timestamp":1700000005000,"message":{"text":"hi"}}]}"#,
+                "\n"
+            ),
+        )
+        .unwrap();
+
+        let session = parse_session_file_with_limit(&path, NO_LIMIT).unwrap();
+        assert_eq!(session.session_id.as_deref(), Some("jsonl-1"));
+        assert_eq!(session.custom_title.as_deref(), Some("Streamed"));
+        assert_eq!(session.requests.len(), 1);
+        assert_eq!(session.last_message_date, 1700000005000);
+    }
+
+    #[test]
+    fn test_parse_legacy_json_with_lone_surrogate() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("surrogate.json");
+        fs::write(
+            &path,
+            r#"{"version": 3, "sessionId": "s-1", "creationDate": 0, "lastMessageDate": 0,
+                "customTitle": "bad \udde0 char", "initialLocation": "panel", "requests": []}"#,
+        )
+        .unwrap();
+
+        let session = parse_session_file_with_limit(&path, NO_LIMIT).unwrap();
+        assert_eq!(session.custom_title.as_deref(), Some("bad \u{FFFD} char"));
+    }
+
+    #[test]
+    fn test_oversized_file_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("big.json");
+        fs::write(&path, "x".repeat(2048)).unwrap();
+
+        match parse_session_file_with_limit(&path, 1024) {
+            Err(CsmError::SessionTooLarge { size, limit, .. }) => {
+                assert_eq!(size, 2048);
+                assert_eq!(limit, 1024);
+            }
+            other => panic!("expected SessionTooLarge, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_size_guard_error_message_is_actionable() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("big.json");
+        fs::write(&path, vec![b' '; 3 * 1024 * 1024]).unwrap();
+
+        let err = check_session_file_size(&path, 1024 * 1024).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("big.json"));
+        assert!(msg.contains("3.0 MB"));
+        assert!(msg.contains("limits.max_session_mb"));
+    }
+
+    #[test]
+    fn test_size_guard_allows_file_at_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("exact.json");
+        fs::write(&path, "x".repeat(1024)).unwrap();
+
+        assert_eq!(check_session_file_size(&path, 1024).unwrap(), 1024);
+    }
+}